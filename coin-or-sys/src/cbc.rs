@@ -486,6 +486,10 @@ extern "C" {
     #[doc = " @brief Queries vector of reduced costs\n\n @param model problem object\n @return reduced cost vector"]
     pub fn Cbc_getReducedCost(model: *mut Cbc_Model) -> *const f64;
 }
+extern "C" {
+    #[doc = " @brief Queries vector of row prices (values for dual variables)\n\n @param model problem object\n @return row price vector"]
+    pub fn Cbc_getRowPrice(model: *mut Cbc_Model) -> *const f64;
+}
 extern "C" {
     #[doc = " If optimization was abandoned due to numerical difficulties\n\n @param model problem object\n @return 1 if numerical difficulties interrupted the optimization, 0 otherwise"]
     pub fn Cbc_isAbandoned(model: *mut Cbc_Model) -> ::std::os::raw::c_int;
@@ -1,7 +1,20 @@
+mod ordering;
+mod symbolic;
+
 use nalgebra_sparse::csr::CsrMatrix;
+pub use ordering::{minimum_degree_ordering, permute_rows, PermutationError};
 use std::cmp::Ordering;
+use symbolic::{elimination_tree, factor_row_patterns, normal_lower_pattern};
 
 /// The indices for the LDL decomposition of A*AT
+///
+/// The L factor is built using the order of the rows of A. The rows should first be reordered
+/// with [`minimum_degree_ordering`] and [`permute_rows`] to reduce the fill-in of the factor.
+///
+/// The structure of L is found from the elimination tree of A*AT. The non-zero entries of each
+/// row of L are the rows visited when walking the tree from the non-zero entries of the same row
+/// of A*AT. This avoids comparing every pair of rows, and so scales with the number of non-zero
+/// entries in L rather than the square of the number of rows.
 #[derive(Debug)]
 pub struct SparseNormalCholeskyIndices {
     pub anorm_indptr: Vec<u32>,
@@ -21,29 +34,48 @@ pub struct SparseNormalCholeskyIndices {
 
 impl SparseNormalCholeskyIndices {
     pub fn from_matrix<T>(a: &CsrMatrix<T>) -> Self {
-        let mut anorm_indptr = vec![0u32];
+        // Symbolic analysis of the factor using the elimination tree of A*AT
+        let lower = normal_lower_pattern(a);
+        let parent = elimination_tree(&lower);
+        let l_rows = factor_row_patterns(&lower, &parent);
+        let l_nnz: usize = l_rows.iter().map(|r| r.len()).sum();
+
+        let mut anorm_indptr = Vec::with_capacity(l_nnz + 1);
+        anorm_indptr.push(0u32);
         let mut anorm_indptr_i = Vec::new();
         let mut anorm_indptr_j = Vec::new();
         let mut anorm_indices = Vec::new();
-        let mut ldecomp_indptr = vec![0u32];
+        let mut ldecomp_indptr = Vec::with_capacity(l_nnz + 1);
+        ldecomp_indptr.push(0u32);
         let mut ldecomp_indptr_i: Vec<u32> = Vec::new();
         let mut ldecomp_indptr_j: Vec<u32> = Vec::new();
         // Entries of the L matrix
-        let mut lindptr = vec![0u32];
-        let mut ldiag_indptr = Vec::new();
-        let mut lindices: Vec<u32> = Vec::new();
+        let mut lindptr = Vec::with_capacity(a.nrows() + 1);
+        lindptr.push(0u32);
+        let mut ldiag_indptr = Vec::with_capacity(a.nrows());
+        let mut lindices: Vec<u32> = Vec::with_capacity(l_nnz);
 
-        for i in 0..a.nrows() {
-            for j in 0..=i {
-                let i_offset = a.row_offsets()[i];
-                let i_row = a.get_row(i).unwrap();
-                let i_cols = i_row.col_indices();
-                let j_offset = a.row_offsets()[j];
-                let j_row = a.get_row(j).unwrap();
-                let j_cols = j_row.col_indices();
-                let mut non_zero = false;
-                {
+        // Position in `lindices` of each column of the current row of L
+        let mut l_position = vec![usize::MAX; a.nrows()];
+        // Marks the columns of the current row that are non-zero in A*AT
+        let mut a_marker = vec![usize::MAX; a.nrows()];
+
+        for (i, l_row) in l_rows.iter().enumerate() {
+            let i_offset = a.row_offsets()[i];
+            let i_row = a.get_row(i).unwrap();
+            let i_cols = i_row.col_indices();
+
+            for &j in &lower[i] {
+                a_marker[j] = i;
+            }
+            a_marker[i] = i;
+
+            for &j in l_row {
+                if a_marker[j] == i {
                     // Search for matching indices in the a matrix for element AAT[i, j]
+                    let j_offset = a.row_offsets()[j];
+                    let j_row = a.get_row(j).unwrap();
+                    let j_cols = j_row.col_indices();
 
                     let mut ii = 0usize;
                     let mut jj = 0usize;
@@ -57,7 +89,6 @@ impl SparseNormalCholeskyIndices {
                                 anorm_indptr_i.push((i_offset + ii) as u32);
                                 anorm_indptr_j.push((j_offset + jj) as u32);
                                 anorm_indices.push(ik as u32);
-                                non_zero = true;
                                 ii += 1;
                                 jj += 1;
                             }
@@ -67,21 +98,126 @@ impl SparseNormalCholeskyIndices {
                     }
                 }
 
-                // Now search for matching indices for the L[i, k]*L[j, k]
+                // Now find the matching entries for the L[i, k]*L[j, k] with k < j. Every entry of
+                // row `i` before column `j` has already been added to `lindices`.
+                let jj_start = lindptr[j] as usize;
+                let jj_end = if i == j {
+                    lindices.len()
+                } else {
+                    lindptr[j + 1] as usize - 1
+                };
+                for jj in jj_start..jj_end {
+                    let k = lindices[jj] as usize;
+                    if l_position[k] != usize::MAX {
+                        let ii = l_position[k];
+                        ldecomp_indptr_i.push(ii.try_into().expect("L decomposition index to overflow."));
+                        ldecomp_indptr_j.push(jj.try_into().expect("L decomposition index to overflow."));
+                    }
+                }
+
+                anorm_indptr.push(anorm_indptr_i.len() as u32);
+                ldecomp_indptr.push(ldecomp_indptr_i.len() as u32);
+                l_position[j] = lindices.len();
+                lindices.push(j as u32);
+            }
+            ldiag_indptr.push(lindices.len() as u32 - 1);
+            lindptr.push(lindices.len() as u32);
+
+            for &j in l_row {
+                l_position[j] = usize::MAX;
+            }
+        }
+
+        let lvalues = vec![1.0; lindices.len()];
+        let lower = CsrMatrix::try_from_csr_data(
+            a.nrows(),
+            a.nrows(),
+            lindptr.iter().map(|r| *r as usize).collect::<Vec<_>>(),
+            lindices.iter().map(|r| *r as usize).collect::<Vec<_>>(),
+            lvalues,
+        )
+        .expect("Failed to create CSR data for Cholesky decomposition.");
+        let lower_t = lower.transpose();
+
+        let mut ltmap: Vec<_> = (0..lower.col_indices().len()).map(|i| i as u32).collect();
+        ltmap.sort_by_key(|&i| lower.col_indices()[i as usize]);
+
+        Self {
+            anorm_indptr,
+            anorm_indptr_i,
+            anorm_indptr_j,
+            anorm_indices,
+            ldecomp_indptr,
+            ldecomp_indptr_i,
+            ldecomp_indptr_j,
+            lindptr,
+            ldiag_indptr,
+            lindices,
+            ltindptr: lower_t.row_offsets().iter().map(|r| *r as u32).collect::<Vec<_>>(),
+            ltindices: lower_t.col_indices().iter().map(|r| *r as u32).collect::<Vec<_>>(),
+            ltmap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseNormalCholeskyIndices;
+    use crate::ordering::permute_rows;
+    use crate::ordering::tests::arrow_matrix;
+    use nalgebra_sparse::{CooMatrix, CsrMatrix};
+    use std::cmp::Ordering;
+
+    /// The indices of L found by comparing every pair of rows of A and of L.
+    ///
+    /// This is the previous implementation of [`SparseNormalCholeskyIndices::from_matrix`], which
+    /// is kept as a reference for the elimination tree.
+    fn pairwise_indices<T>(a: &CsrMatrix<T>) -> SparseNormalCholeskyIndices {
+        let mut anorm_indptr = vec![0u32];
+        let mut anorm_indptr_i = Vec::new();
+        let mut anorm_indptr_j = Vec::new();
+        let mut anorm_indices = Vec::new();
+        let mut ldecomp_indptr = vec![0u32];
+        let mut ldecomp_indptr_i: Vec<u32> = Vec::new();
+        let mut ldecomp_indptr_j: Vec<u32> = Vec::new();
+        let mut lindptr = vec![0u32];
+        let mut ldiag_indptr = Vec::new();
+        let mut lindices: Vec<u32> = Vec::new();
+
+        for i in 0..a.nrows() {
+            for j in 0..=i {
+                let i_offset = a.row_offsets()[i];
+                let i_cols = a.get_row(i).unwrap().col_indices().to_vec();
+                let j_offset = a.row_offsets()[j];
+                let j_cols = a.get_row(j).unwrap().col_indices().to_vec();
+                let mut non_zero = false;
+
+                let mut ii = 0usize;
+                let mut jj = 0usize;
+                while (ii < i_cols.len()) && (jj < j_cols.len()) {
+                    match i_cols[ii].cmp(&j_cols[jj]) {
+                        Ordering::Equal => {
+                            anorm_indptr_i.push((i_offset + ii) as u32);
+                            anorm_indptr_j.push((j_offset + jj) as u32);
+                            anorm_indices.push(i_cols[ii] as u32);
+                            non_zero = true;
+                            ii += 1;
+                            jj += 1;
+                        }
+                        Ordering::Less => ii += 1,
+                        Ordering::Greater => jj += 1,
+                    }
+                }
+
                 let mut ii = lindptr[i] as usize;
                 let mut jj = lindptr[j] as usize;
                 let ii_max = lindices.len();
-
                 let jj_max = if i == j { ii_max } else { lindptr[j + 1] as usize };
-
                 while (ii < ii_max) && (jj < jj_max) {
-                    let ik = lindices[ii];
-                    let jk = lindices[jj];
-
-                    match ik.cmp(&jk) {
+                    match lindices[ii].cmp(&lindices[jj]) {
                         Ordering::Equal => {
-                            ldecomp_indptr_i.push(ii.try_into().expect("L decomposition index to overflow."));
-                            ldecomp_indptr_j.push(jj.try_into().expect("L decomposition index to overflow."));
+                            ldecomp_indptr_i.push(ii as u32);
+                            ldecomp_indptr_j.push(jj as u32);
                             non_zero = true;
                             ii += 1;
                             jj += 1;
@@ -103,21 +239,7 @@ impl SparseNormalCholeskyIndices {
             lindptr.push(lindices.len() as u32)
         }
 
-        let lvalues = vec![1.0; lindices.len()];
-        let lower = CsrMatrix::try_from_csr_data(
-            a.nrows(),
-            a.nrows(),
-            lindptr.iter().map(|r| *r as usize).collect::<Vec<_>>(),
-            lindices.iter().map(|r| *r as usize).collect::<Vec<_>>(),
-            lvalues,
-        )
-        .expect("Failed to create CSR data for Cholesky decomposition.");
-        let lower_t = lower.transpose();
-
-        let mut ltmap: Vec<_> = (0..lower.col_indices().len()).map(|i| i as u32).collect();
-        ltmap.sort_by_key(|&i| lower.col_indices()[i as usize]);
-
-        Self {
+        SparseNormalCholeskyIndices {
             anorm_indptr,
             anorm_indptr_i,
             anorm_indptr_j,
@@ -128,9 +250,89 @@ impl SparseNormalCholeskyIndices {
             lindptr,
             ldiag_indptr,
             lindices,
-            ltindptr: lower_t.row_offsets().iter().map(|r| *r as u32).collect::<Vec<_>>(),
-            ltindices: lower_t.col_indices().iter().map(|r| *r as u32).collect::<Vec<_>>(),
-            ltmap,
+            ltindptr: Vec::new(),
+            ltindices: Vec::new(),
+            ltmap: Vec::new(),
         }
     }
+
+    fn assert_matches_pairwise<T>(a: &CsrMatrix<T>) {
+        let indices = SparseNormalCholeskyIndices::from_matrix(a);
+        let expected = pairwise_indices(a);
+
+        assert_eq!(indices.lindptr, expected.lindptr);
+        assert_eq!(indices.lindices, expected.lindices);
+        assert_eq!(indices.ldiag_indptr, expected.ldiag_indptr);
+        assert_eq!(indices.anorm_indptr, expected.anorm_indptr);
+        assert_eq!(indices.anorm_indptr_i, expected.anorm_indptr_i);
+        assert_eq!(indices.anorm_indptr_j, expected.anorm_indptr_j);
+        assert_eq!(indices.anorm_indices, expected.anorm_indices);
+        assert_eq!(indices.ldecomp_indptr, expected.ldecomp_indptr);
+        assert_eq!(indices.ldecomp_indptr_i, expected.ldecomp_indptr_i);
+        assert_eq!(indices.ldecomp_indptr_j, expected.ldecomp_indptr_j);
+    }
+
+    #[test]
+    fn test_from_matrix_matches_pairwise() {
+        // The arrow matrix has a dense factor with fill-in in every row
+        assert_matches_pairwise(&arrow_matrix());
+        // Reordering the arrow matrix removes the fill-in
+        assert_matches_pairwise(&permute_rows(&arrow_matrix(), &[1, 2, 0, 3]).unwrap());
+    }
+
+    #[test]
+    fn test_from_matrix_matches_pairwise_sparse() {
+        // A fixed pseudo-random pattern with shared columns and fill-in. Every row has at least one
+        // entry so that the diagonal of A*AT is non-zero.
+        let (nrows, ncols) = (30, 24);
+        let mut rows = Vec::new();
+        let mut cols = Vec::new();
+        let mut seed = 12345u64;
+        for row in 0..nrows {
+            for col in 0..ncols {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                if col == row % ncols || (seed >> 33) % 8 == 0 {
+                    rows.push(row);
+                    cols.push(col);
+                }
+            }
+        }
+        let values = vec![1.0; rows.len()];
+        let a = CsrMatrix::from(&CooMatrix::try_from_triplets(nrows, ncols, rows, cols, values).unwrap());
+
+        assert_matches_pairwise(&a);
+    }
+
+    #[test]
+    fn test_from_matrix() {
+        let a = permute_rows(&arrow_matrix(), &[1, 2, 0, 3]).unwrap();
+        let indices = SparseNormalCholeskyIndices::from_matrix(&a);
+
+        // The rows of L
+        assert_eq!(indices.lindptr, vec![0, 1, 2, 5, 7]);
+        assert_eq!(indices.lindices, vec![0, 1, 0, 1, 2, 2, 3]);
+        assert_eq!(indices.ldiag_indptr, vec![0, 1, 4, 6]);
+
+        // The columns of A shared by the rows of each entry of L
+        assert_eq!(indices.anorm_indptr, vec![0, 1, 2, 3, 4, 7, 8, 9]);
+        assert_eq!(indices.anorm_indices, vec![0, 1, 0, 1, 0, 1, 2, 2, 2]);
+        assert_eq!(indices.anorm_indptr_i, vec![0, 1, 2, 3, 2, 3, 4, 5, 5]);
+        assert_eq!(indices.anorm_indptr_j, vec![0, 1, 0, 1, 2, 3, 4, 4, 5]);
+
+        // The transpose of L
+        assert_eq!(indices.ltindptr, vec![0, 2, 4, 6, 7]);
+        assert_eq!(indices.ltindices, vec![0, 2, 1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn test_from_matrix_decomposition() {
+        let a = permute_rows(&arrow_matrix(), &[1, 2, 0, 3]).unwrap();
+        let indices = SparseNormalCholeskyIndices::from_matrix(&a);
+
+        // Only the diagonals of the last two rows have products of earlier entries of L: L[2, 0]^2
+        // and L[2, 1]^2, and L[3, 2]^2
+        assert_eq!(indices.ldecomp_indptr, vec![0, 0, 0, 0, 0, 2, 2, 3]);
+        assert_eq!(indices.ldecomp_indptr_i, vec![2, 3, 5]);
+        assert_eq!(indices.ldecomp_indptr_j, vec![2, 3, 5]);
+    }
 }
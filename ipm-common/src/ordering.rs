@@ -0,0 +1,196 @@
+use nalgebra_sparse::csr::CsrMatrix;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Compute a fill-reducing ordering of the rows of `a` for the Cholesky decomposition of A*AT.
+///
+/// The ordering is found with a minimum degree heuristic on the graph of A*AT, in which two
+/// rows are connected if they share a column of `a`. The rows are eliminated one at a time,
+/// always choosing the row with the fewest connections, and the neighbours of each eliminated
+/// row are connected to each other to account for the fill-in of the L factor. Ties are broken
+/// by the row index so that the ordering is deterministic.
+///
+/// The first `num_inequality_constraints` rows are kept at the start of the ordering because the
+/// interior point methods expect the inequality constraints to be the first rows of the matrix.
+///
+/// The returned vector gives the original index of each row in the new ordering.
+pub fn minimum_degree_ordering<T>(a: &CsrMatrix<T>, num_inequality_constraints: usize) -> Vec<usize> {
+    let num_rows = a.nrows();
+
+    // Rows of `a` that have an entry in each column
+    let mut col_rows: Vec<Vec<usize>> = vec![Vec::new(); a.ncols()];
+    for (row, row_data) in a.row_iter().enumerate() {
+        for &col in row_data.col_indices() {
+            col_rows[col].push(row);
+        }
+    }
+
+    let mut adjacency: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); num_rows];
+    for rows in &col_rows {
+        for &i in rows {
+            for &j in rows {
+                if i != j {
+                    adjacency[i].insert(j);
+                }
+            }
+        }
+    }
+
+    let mut eliminated = vec![false; num_rows];
+    let mut order = Vec::with_capacity(num_rows);
+
+    // Order the inequality constraints and then the equality constraints
+    let split = num_inequality_constraints.min(num_rows);
+    for (start, end) in [(0, split), (split, num_rows)] {
+        let mut heap: BinaryHeap<Reverse<(usize, usize)>> =
+            (start..end).map(|row| Reverse((adjacency[row].len(), row))).collect();
+
+        while let Some(Reverse((degree, row))) = heap.pop() {
+            // Skip stale entries whose degree has changed since they were added
+            if eliminated[row] || degree != adjacency[row].len() {
+                continue;
+            }
+
+            eliminated[row] = true;
+            order.push(row);
+
+            let neighbours: Vec<usize> = std::mem::take(&mut adjacency[row]).into_iter().collect();
+            for &i in &neighbours {
+                adjacency[i].remove(&row);
+                for &j in &neighbours {
+                    if i != j {
+                        adjacency[i].insert(j);
+                    }
+                }
+            }
+
+            for &i in &neighbours {
+                if (start..end).contains(&i) {
+                    heap.push(Reverse((adjacency[i].len(), i)));
+                }
+            }
+        }
+    }
+
+    order
+}
+
+/// An error permuting the rows of a matrix with [`permute_rows`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermutationError {
+    /// The order does not have one entry for each row of the matrix.
+    InvalidLength { expected: usize, found: usize },
+    /// The order contains a row that is out of range or repeated.
+    InvalidRow(usize),
+    /// The permuted matrix could not be created.
+    InvalidMatrix(String),
+}
+
+impl Display for PermutationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLength { expected, found } => {
+                write!(f, "the row order has {found} rows, but the matrix has {expected} rows")
+            }
+            Self::InvalidRow(row) => write!(f, "row {row} is out of range or repeated in the row order"),
+            Self::InvalidMatrix(msg) => write!(f, "failed to create the permuted matrix: {msg}"),
+        }
+    }
+}
+
+impl Error for PermutationError {}
+
+/// Create a new matrix with the rows of `a` in the given `order`.
+///
+/// `order` gives the original index of each row in the new matrix (see [`minimum_degree_ordering`]).
+/// It must contain each row of `a` exactly once.
+pub fn permute_rows<T: Clone>(a: &CsrMatrix<T>, order: &[usize]) -> Result<CsrMatrix<T>, PermutationError> {
+    if order.len() != a.nrows() {
+        return Err(PermutationError::InvalidLength {
+            expected: a.nrows(),
+            found: order.len(),
+        });
+    }
+
+    let mut seen = vec![false; a.nrows()];
+    for &row in order {
+        match seen.get_mut(row) {
+            Some(s) if !*s => *s = true,
+            _ => return Err(PermutationError::InvalidRow(row)),
+        }
+    }
+
+    let mut row_offsets = vec![0usize];
+    let mut col_indices = Vec::with_capacity(a.nnz());
+    let mut values = Vec::with_capacity(a.nnz());
+
+    for &row in order {
+        let row_data = a.row(row);
+        col_indices.extend_from_slice(row_data.col_indices());
+        values.extend_from_slice(row_data.values());
+        row_offsets.push(col_indices.len());
+    }
+
+    CsrMatrix::try_from_csr_data(a.nrows(), a.ncols(), row_offsets, col_indices, values)
+        .map_err(|err| PermutationError::InvalidMatrix(err.to_string()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{minimum_degree_ordering, permute_rows, PermutationError};
+    use nalgebra_sparse::csr::CsrMatrix;
+    use nalgebra_sparse::CooMatrix;
+
+    /// A matrix whose first row shares a column with each of the other rows. Its Cholesky factor
+    /// is dense unless the first row is moved to the end.
+    pub(crate) fn arrow_matrix() -> CsrMatrix<f64> {
+        let coo = CooMatrix::try_from_triplets(
+            4,
+            3,
+            vec![0, 0, 0, 1, 2, 3],
+            vec![0, 1, 2, 0, 1, 2],
+            vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        )
+        .unwrap();
+
+        CsrMatrix::from(&coo)
+    }
+
+    #[test]
+    fn test_minimum_degree_ordering() {
+        let a = arrow_matrix();
+
+        // The rows with the fewest connections are eliminated first, with ties broken by index
+        assert_eq!(minimum_degree_ordering(&a, 0), vec![1, 2, 0, 3]);
+        assert_eq!(minimum_degree_ordering(&a, 4), vec![1, 2, 0, 3]);
+        // ... but the inequality constraints are kept at the start
+        assert_eq!(minimum_degree_ordering(&a, 1), vec![0, 1, 2, 3]);
+        assert_eq!(minimum_degree_ordering(&a, 2), vec![1, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_permute_rows() {
+        let a = arrow_matrix();
+        let b = permute_rows(&a, &[1, 2, 0, 3]).unwrap();
+
+        assert_eq!(b.nrows(), 4);
+        assert_eq!(b.ncols(), 3);
+        assert_eq!(b.row_offsets(), &[0, 1, 2, 5, 6]);
+        assert_eq!(b.col_indices(), &[0, 1, 0, 1, 2, 2]);
+        assert_eq!(b.values(), &[4.0, 5.0, 1.0, 2.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn test_permute_rows_invalid_order() {
+        let a = arrow_matrix();
+
+        assert_eq!(
+            permute_rows(&a, &[0, 1, 2]),
+            Err(PermutationError::InvalidLength { expected: 4, found: 3 })
+        );
+        assert_eq!(permute_rows(&a, &[0, 1, 2, 4]), Err(PermutationError::InvalidRow(4)));
+        assert_eq!(permute_rows(&a, &[0, 1, 1, 3]), Err(PermutationError::InvalidRow(1)));
+    }
+}
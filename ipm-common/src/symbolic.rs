@@ -0,0 +1,131 @@
+use nalgebra_sparse::csr::CsrMatrix;
+
+/// Sentinel for a row of the elimination tree without a parent (i.e. a root).
+const NO_PARENT: usize = usize::MAX;
+
+/// The lower triangular structure of A*AT, excluding the diagonal.
+///
+/// Row `i` contains the (unsorted) rows `j < i` that share at least one column with row `i`.
+pub(crate) fn normal_lower_pattern<T>(a: &CsrMatrix<T>) -> Vec<Vec<usize>> {
+    let num_rows = a.nrows();
+
+    // Rows of `a` that have an entry in each column
+    let mut col_rows: Vec<Vec<usize>> = vec![Vec::new(); a.ncols()];
+    for (row, row_data) in a.row_iter().enumerate() {
+        for &col in row_data.col_indices() {
+            col_rows[col].push(row);
+        }
+    }
+
+    let mut marker = vec![NO_PARENT; num_rows];
+    let mut pattern = Vec::with_capacity(num_rows);
+
+    for (i, row_data) in a.row_iter().enumerate() {
+        let mut row_pattern = Vec::new();
+        for &col in row_data.col_indices() {
+            // The rows in each column are in ascending order
+            for &j in col_rows[col].iter().take_while(|&&j| j < i) {
+                if marker[j] != i {
+                    marker[j] = i;
+                    row_pattern.push(j);
+                }
+            }
+        }
+        pattern.push(row_pattern);
+    }
+
+    pattern
+}
+
+/// Compute the elimination tree of the Cholesky factor of a symmetric matrix.
+///
+/// `lower` is the structure of the strictly lower triangle of the matrix (see
+/// [`normal_lower_pattern`]). The tree is found with Liu's algorithm using path compression
+/// of the ancestors; the parent of a root is [`NO_PARENT`].
+pub(crate) fn elimination_tree(lower: &[Vec<usize>]) -> Vec<usize> {
+    let n = lower.len();
+    let mut parent = vec![NO_PARENT; n];
+    let mut ancestor = vec![NO_PARENT; n];
+
+    for (i, row) in lower.iter().enumerate() {
+        for &k in row {
+            // Traverse from `k` to the root of its current subtree, compressing the path to `i`
+            let mut j = k;
+            while j != NO_PARENT && j < i {
+                let next = ancestor[j];
+                ancestor[j] = i;
+                if next == NO_PARENT {
+                    parent[j] = i;
+                }
+                j = next;
+            }
+        }
+    }
+
+    parent
+}
+
+/// Compute the structure of each row of the Cholesky factor L, including the diagonal.
+///
+/// The structure of row `i` of L is the union of the paths in the elimination tree from each
+/// entry in row `i` of the lower triangle up to `i` itself (the "row subtree" of `i`). The
+/// returned rows are sorted in ascending column order.
+pub(crate) fn factor_row_patterns(lower: &[Vec<usize>], parent: &[usize]) -> Vec<Vec<usize>> {
+    let n = lower.len();
+    let mut marker = vec![NO_PARENT; n];
+
+    lower
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut row_pattern = Vec::new();
+            marker[i] = i;
+            for &k in row {
+                let mut j = k;
+                while marker[j] != i {
+                    marker[j] = i;
+                    row_pattern.push(j);
+                    j = parent[j];
+                }
+            }
+            row_pattern.sort_unstable();
+            row_pattern.push(i);
+            row_pattern
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{elimination_tree, factor_row_patterns, normal_lower_pattern, NO_PARENT};
+    use crate::ordering::permute_rows;
+    use crate::ordering::tests::arrow_matrix;
+
+    #[test]
+    fn test_symbolic_factor() {
+        // The first row shares a column with every other row, so the factor is dense
+        let a = arrow_matrix();
+        let lower = normal_lower_pattern(&a);
+        assert_eq!(lower, vec![vec![], vec![0], vec![0], vec![0]]);
+
+        let parent = elimination_tree(&lower);
+        assert_eq!(parent, vec![1, 2, 3, NO_PARENT]);
+
+        let l_rows = factor_row_patterns(&lower, &parent);
+        assert_eq!(l_rows, vec![vec![0], vec![0, 1], vec![0, 1, 2], vec![0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_symbolic_factor_reordered() {
+        // Moving the first row to the end avoids the fill-in
+        let a = permute_rows(&arrow_matrix(), &[1, 2, 0, 3]).unwrap();
+        let lower = normal_lower_pattern(&a);
+        assert_eq!(lower, vec![vec![], vec![], vec![0, 1], vec![2]]);
+
+        let parent = elimination_tree(&lower);
+        assert_eq!(parent, vec![2, 2, 3, NO_PARENT]);
+
+        let l_rows = factor_row_patterns(&lower, &parent);
+        assert_eq!(l_rows, vec![vec![0], vec![1], vec![0, 1, 2], vec![2, 3]]);
+    }
+}
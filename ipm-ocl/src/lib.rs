@@ -1,4 +1,4 @@
-use ipm_common::SparseNormalCholeskyIndices;
+use ipm_common::{minimum_degree_ordering, permute_rows, SparseNormalCholeskyIndices};
 use log::debug;
 use nalgebra_sparse::csr::CsrMatrix;
 use std::num::NonZeroUsize;
@@ -20,6 +20,107 @@ impl Default for Tolerances {
     }
 }
 
+/// The type of an OpenCL device.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DeviceType {
+    Cpu,
+    Gpu,
+    Accelerator,
+}
+
+impl DeviceType {
+    fn flags(&self) -> ocl::flags::DeviceType {
+        match self {
+            DeviceType::Cpu => ocl::flags::DEVICE_TYPE_CPU,
+            DeviceType::Gpu => ocl::flags::DEVICE_TYPE_GPU,
+            DeviceType::Accelerator => ocl::flags::DEVICE_TYPE_ACCELERATOR,
+        }
+    }
+
+    fn from_flags(flags: ocl::flags::DeviceType) -> Option<Self> {
+        [DeviceType::Gpu, DeviceType::Accelerator, DeviceType::Cpu]
+            .into_iter()
+            .find(|ty| flags.contains(ty.flags()))
+    }
+}
+
+/// A description of an OpenCL device that is available on this machine.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DeviceDescription {
+    /// The index of the device's platform in the list of platforms.
+    pub platform_index: usize,
+    pub platform_name: String,
+    /// The index of the device in the list of the platform's devices.
+    pub device_index: usize,
+    pub name: String,
+    pub device_type: Option<DeviceType>,
+}
+
+/// List the devices of all the OpenCL platforms that are available.
+///
+/// The platform and device indices of the returned descriptions can be used with a
+/// [`DeviceSelector`] to choose the device that is used by the solver.
+pub fn list_devices() -> ocl::Result<Vec<DeviceDescription>> {
+    let mut descriptions = Vec::new();
+
+    for (platform_index, platform) in ocl::Platform::list().into_iter().enumerate() {
+        let platform_name = platform.name()?;
+
+        for (device_index, device) in ocl::Device::list_all(platform)?.into_iter().enumerate() {
+            let device_type = match device.info(ocl::enums::DeviceInfo::Type)? {
+                ocl::enums::DeviceInfoResult::Type(flags) => DeviceType::from_flags(flags),
+                _ => None,
+            };
+
+            descriptions.push(DeviceDescription {
+                platform_index,
+                platform_name: platform_name.clone(),
+                device_index,
+                name: device.name()?,
+                device_type,
+            });
+        }
+    }
+
+    Ok(descriptions)
+}
+
+/// Selection of the OpenCL platform and device used by the solver.
+///
+/// By default the first device of the default platform is selected. If `device_type` is given
+/// only the devices of that type are considered, and `device` is then the index within those
+/// devices.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct DeviceSelector {
+    pub platform: Option<usize>,
+    pub device: Option<usize>,
+    pub device_type: Option<DeviceType>,
+}
+
+impl DeviceSelector {
+    /// Find the platform and device that match this selection.
+    pub fn select(&self) -> ocl::Result<(ocl::Platform, ocl::Device)> {
+        let platform = match self.platform {
+            Some(idx) => *ocl::Platform::list()
+                .get(idx)
+                .ok_or_else(|| ocl::Error::from(format!("OpenCL platform with index {idx} not found.")))?,
+            None => ocl::Platform::default(),
+        };
+
+        let devices = ocl::Device::list(platform, self.device_type.map(|ty| ty.flags()))?;
+        let idx = self.device.unwrap_or(0);
+        let device = *devices.get(idx).ok_or_else(|| {
+            ocl::Error::from(format!(
+                "OpenCL device with index {idx} (type: {:?}) not found on platform `{}`.",
+                self.device_type,
+                platform.name().unwrap_or_default()
+            ))
+        })?;
+
+        Ok((platform, device))
+    }
+}
+
 pub trait GetClProgram {
     fn get_cl_program(
         context: &ocl::Context,
@@ -34,7 +135,12 @@ impl GetClProgram for f64 {
         device: &ocl::Device,
         tolerances: &Tolerances,
     ) -> ocl::Result<ocl::Program> {
-        let src = [include_str!("common.cl"), include_str!("path_following_direct.cl")].join("\n");
+        let src = [
+            include_str!("common.cl"),
+            include_str!("path_following_direct.cl"),
+            include_str!("reduce.cl"),
+        ]
+        .join("\n");
 
         // TODO this was done with build argument before "-DREAL=double". Need to do a proper search
         // on the ocl docs about whether this is possible.
@@ -62,7 +168,12 @@ impl GetClProgram for f32 {
         device: &ocl::Device,
         tolerances: &Tolerances,
     ) -> ocl::Result<ocl::Program> {
-        let src = [include_str!("common.cl"), include_str!("path_following_direct.cl")].join("\n");
+        let src = [
+            include_str!("common.cl"),
+            include_str!("path_following_direct.cl"),
+            include_str!("reduce.cl"),
+        ]
+        .join("\n");
 
         // TODO this was done with build argument before "-DREAL=float". Need to do a proper search
         // on the ocl docs about whether this is possible.
@@ -321,6 +432,7 @@ where
     tmp_buffer: ocl::Buffer<T>,
     rhs_buffer: ocl::Buffer<T>,
     status_buffer: ocl::Buffer<u8>,
+    reduction_buffer: ocl::Buffer<T>,
 }
 
 impl<T> PathFollowingDirectClBuffers<T>
@@ -395,6 +507,13 @@ where
             .len(num_lps)
             .build()?;
 
+        // The sum, minimum and maximum of each column across all the LPs.
+        let reduction_buffer = ocl::Buffer::<T>::builder()
+            .queue(queue.clone())
+            .flags(ocl::flags::MEM_READ_WRITE)
+            .len(num_cols * 3)
+            .build()?;
+
         Ok(Self {
             a_buffers,
             at_buffers,
@@ -407,10 +526,39 @@ where
             tmp_buffer,
             rhs_buffer,
             status_buffer,
+            reduction_buffer,
         })
     }
 }
 
+/// The sum, minimum and maximum of each column of the solution across a set of LPs.
+#[derive(Clone, Debug)]
+pub struct LpReduction<T> {
+    values: Vec<T>,
+}
+
+impl<T> LpReduction<T>
+where
+    T: Copy,
+{
+    /// The number of columns of the LPs.
+    pub fn num_cols(&self) -> usize {
+        self.values.len() / 3
+    }
+
+    pub fn sum(&self, col: usize) -> T {
+        self.values[3 * col]
+    }
+
+    pub fn min(&self, col: usize) -> T {
+        self.values[3 * col + 1]
+    }
+
+    pub fn max(&self, col: usize) -> T {
+        self.values[3 * col + 2]
+    }
+}
+
 pub struct PathFollowingDirectClSolver<T>
 where
     T: ocl::OclPrm,
@@ -419,8 +567,14 @@ where
     kernel_normal_init: ocl::Kernel,
     kernel_normal_eq_step: ocl::Kernel,
     // kernel_normal_eq_solve: ocl::Kernel,
+    kernel_reduce_columns: ocl::Kernel,
     solution: Vec<T>,
     status: Vec<u8>,
+    reduction: LpReduction<T>,
+    /// The original index of each row of the matrix on the device.
+    row_order: Vec<usize>,
+    /// The row bounds in the order of the rows of the matrix on the device.
+    b: Vec<T>,
 }
 
 impl<T> PathFollowingDirectClSolver<T>
@@ -442,6 +596,10 @@ where
         let a = CsrMatrix::try_from_csr_data(num_rows, num_cols, row_offsets, col_indices, values)
             .expect("Failed to create matrix from given data");
 
+        // Reorder the rows to reduce the fill-in of the Cholesky decomposition
+        let row_order = minimum_degree_ordering(&a, num_inequality_constraints as usize);
+        let a = permute_rows(&a, &row_order).map_err(|err| ocl::Error::from(err.to_string()))?;
+
         let buffers = PathFollowingDirectClBuffers::from_data(&a, num_lps, queue)?;
 
         let kernel_normal_init = ocl::Kernel::builder()
@@ -506,21 +664,82 @@ where
             .arg(&buffers.status_buffer)
             .build()?;
 
+        let kernel_reduce_columns = ocl::Kernel::builder()
+            .program(program)
+            .name("reduce_columns")
+            .queue(queue.clone())
+            .global_work_size(num_cols)
+            .arg(num_lps)
+            .arg(&buffers.path_buffers.x)
+            .arg(&buffers.reduction_buffer)
+            .build()?;
+
         let solution: Vec<T> = vec![T::default(); num_cols * num_lps as usize];
         let status = vec![0u8; num_lps as usize];
+        let reduction = LpReduction {
+            values: vec![T::default(); num_cols * 3],
+        };
+        let b = vec![T::default(); num_rows * num_lps as usize];
 
         Ok(Self {
             buffers,
             kernel_normal_init,
             kernel_normal_eq_step,
+            kernel_reduce_columns,
             solution,
             status,
+            reduction,
+            row_order,
+            b,
         })
     }
 
+    /// Solve the LPs and copy the solution of every LP back to the host.
+    ///
+    /// The solution is ordered by column, with the values of each column for all the LPs
+    /// stored contiguously.
     pub fn solve(&mut self, queue: &ocl::Queue, b: &[T], c: &[T], max_iterations: NonZeroUsize) -> ocl::Result<&[T]> {
-        // Copy b & c to the device
-        self.buffers.b_buffer.write(b).enq()?;
+        self.solve_on_device(b, c, max_iterations)?;
+
+        self.buffers.path_buffers.x.read(&mut self.solution).enq()?;
+        queue.finish()?;
+
+        Ok(self.solution.as_slice())
+    }
+
+    /// Solve the LPs and copy only the sum, minimum and maximum of each column across all the
+    /// LPs back to the host.
+    ///
+    /// This avoids copying the full solution of every LP from the device when only the
+    /// aggregated values of the columns are required.
+    pub fn solve_reduced(
+        &mut self,
+        queue: &ocl::Queue,
+        b: &[T],
+        c: &[T],
+        max_iterations: NonZeroUsize,
+    ) -> ocl::Result<&LpReduction<T>> {
+        self.solve_on_device(b, c, max_iterations)?;
+
+        unsafe {
+            self.kernel_reduce_columns.enq()?;
+        }
+
+        self.buffers.reduction_buffer.read(&mut self.reduction.values).enq()?;
+        queue.finish()?;
+
+        Ok(&self.reduction)
+    }
+
+    /// Run the interior point method until all the LPs have converged or `max_iterations` is
+    /// reached. The solution is left in the device's buffers.
+    fn solve_on_device(&mut self, b: &[T], c: &[T], max_iterations: NonZeroUsize) -> ocl::Result<()> {
+        // Copy b & c to the device; b is reordered to match the rows of the matrix
+        let num_lps = self.status.len();
+        for (new_row, &row) in self.row_order.iter().enumerate() {
+            self.b[new_row * num_lps..(new_row + 1) * num_lps].copy_from_slice(&b[row * num_lps..(row + 1) * num_lps]);
+        }
+        self.buffers.b_buffer.write(&self.b).enq()?;
         self.buffers.c_buffer.write(c).enq()?;
 
         unsafe {
@@ -552,14 +771,26 @@ where
         };
 
         if last_iteration.is_none() {
-            panic!("Interior point method failed to converged all scenarios.")
+            debug!(
+                "Interior point method failed to converge {} of {} LPs.",
+                self.non_converged().len(),
+                self.status.len()
+            );
         }
 
         // println!("Finished after iterations: {}", last_iteration);
-        self.buffers.path_buffers.x.read(&mut self.solution).enq()?;
-        queue.finish()?;
+        Ok(())
+    }
 
-        Ok(self.solution.as_slice())
+    /// The indices of the LPs that did not converge in the last call to [`Self::solve`].
+    ///
+    /// The solution of these LPs is not optimal and should not be used.
+    pub fn non_converged(&self) -> Vec<usize> {
+        self.status
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &s)| if s != 0 { Some(i) } else { None })
+            .collect()
     }
 }
 
@@ -568,6 +799,18 @@ mod tests {
     use super::*;
     use nalgebra_sparse::CooMatrix;
 
+    #[test]
+    fn select_default_device() {
+        let (_, device) = DeviceSelector::default().select().unwrap();
+        assert_eq!(device, ocl::Device::first(ocl::Platform::default()).unwrap());
+
+        let selector = DeviceSelector {
+            device: Some(usize::MAX),
+            ..Default::default()
+        };
+        assert!(selector.select().is_err());
+    }
+
     #[test]
     fn build_program() {
         let platform = ocl::Platform::default();
@@ -610,4 +853,53 @@ mod tests {
         let a = test_matrx();
         let _pf = PathFollowingDirectClBuffers::from_data(&a, 10, &queue).unwrap();
     }
+
+    #[test]
+    fn solve_reduced() {
+        let platform = ocl::Platform::default();
+        let device = ocl::Device::first(platform).unwrap();
+        let context = ocl::Context::builder()
+            .platform(platform)
+            .devices(device)
+            .build()
+            .unwrap();
+        let queue = ocl::Queue::new(&context, device, None).unwrap();
+        let program = f64::get_cl_program(&context, &device, &Tolerances::default()).unwrap();
+
+        let a = test_matrx();
+        let num_lps = 8;
+        let (row_offsets, col_indices, values) = a.disassemble();
+        let mut solver = PathFollowingDirectClSolver::from_data(
+            &queue,
+            &program,
+            4,
+            4,
+            row_offsets,
+            col_indices,
+            values,
+            4,
+            num_lps,
+        )
+        .unwrap();
+
+        // Maximise the sum of the columns with different upper bounds for each LP
+        let b: Vec<f64> = (0..4 * num_lps).map(|i| 1.0 + (i % num_lps) as f64).collect();
+        let c = vec![-1.0; 4 * num_lps as usize];
+        let max_iterations = NonZeroUsize::new(200).unwrap();
+
+        let solution = solver.solve(&queue, &b, &c, max_iterations).unwrap().to_vec();
+        let reduction = solver.solve_reduced(&queue, &b, &c, max_iterations).unwrap();
+
+        assert_eq!(reduction.num_cols(), 4);
+        for col in 0..4 {
+            let values = &solution[col * num_lps as usize..(col + 1) * num_lps as usize];
+            let sum: f64 = values.iter().sum();
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+            assert!((reduction.sum(col) - sum).abs() < 1e-6);
+            assert!((reduction.min(col) - min).abs() < 1e-6);
+            assert!((reduction.max(col) - max).abs() < 1e-6);
+        }
+    }
 }
@@ -0,0 +1,33 @@
+/* Reductions of the solutions of a set of LPs.
+ *
+ *
+ */
+
+__kernel void reduce_columns(
+    uint num_lps,
+    __global REAL* restrict x,
+    __global REAL* restrict out
+) {
+    /* Compute the sum, minimum and maximum of each column of x across all the LPs.
+     *
+     * The work size is the number of columns. The results for column `col` are written to
+     * out[3*col], out[3*col + 1] and out[3*col + 2] respectively.
+     */
+    uint col = get_global_id(0);
+    uint offset = col*num_lps;
+
+    REAL sum = 0.0;
+    REAL min_value = INFINITY;
+    REAL max_value = -INFINITY;
+
+    for (uint lp=0; lp<num_lps; lp++) {
+        REAL val = x[offset + lp];
+        sum += val;
+        min_value = fmin(min_value, val);
+        max_value = fmax(max_value, val);
+    }
+
+    out[3*col] = sum;
+    out[3*col + 1] = min_value;
+    out[3*col + 2] = max_value;
+}
@@ -3,9 +3,9 @@
 mod common;
 mod path_following_direct;
 
-use crate::path_following_direct::{normal_eqn_init, normal_eqn_step};
+use crate::path_following_direct::{normal_eqn_init, normal_eqn_reset_lanes, normal_eqn_step};
 use common::{dual_feasibility, primal_feasibility, Matrix};
-use ipm_common::SparseNormalCholeskyIndices;
+use ipm_common::{minimum_degree_ordering, permute_rows, PermutationError, SparseNormalCholeskyIndices};
 use nalgebra_sparse::CsrMatrix;
 use path_following_direct::ANormIndices;
 use path_following_direct::LDecompositionIndices;
@@ -74,6 +74,11 @@ where
 
     tmp: Vec<Simd<T, N>>,
     rhs: Vec<Simd<T, N>>,
+
+    /// The original index of each row of `a`.
+    row_order: Vec<usize>,
+    /// The row bounds in the order of the rows of `a`.
+    b: Vec<Simd<T, N>>,
 }
 
 impl<T, const N: usize> PathFollowingDirectSimdData<T, N>
@@ -81,10 +86,14 @@ where
     LaneCount<N>: SupportedLaneCount,
     T: SimdElement + From<f64>,
 {
-    pub fn from_data(a: &CsrMatrix<f64>, num_inequality_constraints: usize) -> Self {
+    pub fn from_data(a: &CsrMatrix<f64>, num_inequality_constraints: usize) -> Result<Self, PermutationError> {
         let num_rows = a.nrows();
         let num_cols = a.ncols();
 
+        // Reorder the rows to reduce the fill-in of the Cholesky decomposition
+        let row_order = minimum_degree_ordering(a, num_inequality_constraints);
+        let a = &permute_rows(a, &row_order)?;
+
         let a_buffers = Matrix::from_sparse_matrix(a);
         let at = a.transpose();
         let at_buffers = Matrix::from_sparse_matrix(&at);
@@ -128,8 +137,12 @@ where
             .into_iter()
             .map(|_| Simd::<T, N>::splat(0.0.into()))
             .collect();
+        let b = (0..num_rows)
+            .into_iter()
+            .map(|_| Simd::<T, N>::splat(0.0.into()))
+            .collect();
 
-        Self {
+        Ok(Self {
             a: a_buffers,
             at: at_buffers,
             a_norm_ptr,
@@ -141,7 +154,9 @@ where
             delta_path_buffers,
             tmp,
             rhs,
-        }
+            row_order,
+            b,
+        })
     }
 }
 
@@ -170,6 +185,52 @@ where
     }
 }
 
+/// The result of solving the LPs of all the SIMD lanes.
+pub struct SimdSolution<'a, T, const N: usize>
+where
+    LaneCount<N>: SupportedLaneCount,
+    T: SimdElement,
+{
+    x: &'a [Simd<T, N>],
+    converged: [bool; N],
+    restarts: [usize; N],
+}
+
+impl<T, const N: usize> SimdSolution<'_, T, N>
+where
+    LaneCount<N>: SupportedLaneCount,
+    T: SimdElement,
+{
+    /// The values of the primal variables.
+    ///
+    /// The values of the lanes that did not converge are not optimal and should not be used.
+    pub fn x(&self) -> &[Simd<T, N>] {
+        self.x
+    }
+
+    /// Whether the LP of each lane converged.
+    ///
+    /// A lane has converged if it is feasible and optimal within the tolerances and all of its
+    /// primal variables are finite.
+    pub fn converged(&self) -> &[bool; N] {
+        &self.converged
+    }
+
+    /// The number of times that each lane was restarted after diverging.
+    pub fn restarts(&self) -> &[usize; N] {
+        &self.restarts
+    }
+
+    /// The indices of the lanes that did not converge.
+    pub fn failed_lanes(&self) -> Vec<usize> {
+        self.converged
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &c)| if c { None } else { Some(i) })
+            .collect()
+    }
+}
+
 pub struct PathFollowingDirectSimdSolver<T, const N: usize>
 where
     LaneCount<N>: SupportedLaneCount,
@@ -190,22 +251,29 @@ where
         col_indices: Vec<usize>,
         values: Vec<f64>,
         num_inequality_constraints: usize,
-    ) -> Self {
+    ) -> Result<Self, PermutationError> {
         let a = CsrMatrix::try_from_csr_data(num_rows, num_cols, row_offsets, col_indices, values)
             .expect("Failed to create matrix from given data");
 
-        let buffers = PathFollowingDirectSimdData::from_data(&a, num_inequality_constraints);
+        let buffers = PathFollowingDirectSimdData::from_data(&a, num_inequality_constraints)?;
 
-        Self { buffers }
+        Ok(Self { buffers })
     }
 
+    /// Solve the LPs of all the SIMD lanes.
+    ///
+    /// The lanes are solved independently. If a lane diverges it is restarted from the initial
+    /// point with half of its previous step size, up to `max_restarts` times. The iterations of
+    /// the restarted lanes count towards `max_iterations`. The returned solution reports which
+    /// lanes did not converge so that they can be solved by other means.
     pub fn solve(
         &mut self,
         b: &[Simd<T, N>],
         c: &[Simd<T, N>],
         tolerances: &Tolerances<T, N>,
         max_iterations: NonZeroUsize,
-    ) -> &[Simd<T, N>]
+        max_restarts: usize,
+    ) -> SimdSolution<T, N>
     where
         LaneCount<N>: SupportedLaneCount,
         T: SimdElement<Mask = i64> + From<f64> + Debug,
@@ -221,6 +289,10 @@ where
             + Div<Output = Simd<T, N>>
             + Neg<Output = Simd<T, N>>,
     {
+        for (b_row, &row) in self.buffers.b.iter_mut().zip(&self.buffers.row_order) {
+            *b_row = b[row];
+        }
+
         normal_eqn_init(
             &mut self.buffers.path_buffers.x,
             &mut self.buffers.path_buffers.z,
@@ -229,13 +301,13 @@ where
         );
 
         let delta = Simd::<T, N>::splat(0.1.into());
+        let mut step_fraction = Simd::<T, N>::splat(0.9995.into());
+        let mut restarts = [0usize; N];
+        let mut failed = Mask::<i64, N>::splat(false);
         let mut iter = 0;
 
-        let last_iteration = loop {
-            if iter >= max_iterations.get() {
-                break None;
-            }
-            let status = normal_eqn_step(
+        let converged = loop {
+            let (status, diverged) = normal_eqn_step(
                 &self.buffers.a,
                 &self.buffers.at,
                 &self.buffers.a_norm_ptr,
@@ -247,7 +319,7 @@ where
                 &mut self.buffers.path_buffers.z,
                 &mut self.buffers.path_buffers.y,
                 &mut self.buffers.path_buffers.w,
-                &b,
+                &self.buffers.b,
                 &c,
                 delta,
                 &mut self.buffers.delta_path_buffers.x,
@@ -257,21 +329,117 @@ where
                 &mut self.buffers.tmp,
                 &mut self.buffers.rhs,
                 tolerances,
+                step_fraction,
             );
 
-            if status.all() {
-                break Some(iter);
+            // Restart the diverged lanes with a smaller step, or give up on them.
+            let can_restart = Mask::from_array(restarts.map(|r| r < max_restarts));
+            let restart = diverged & !failed & can_restart;
+            failed |= diverged & !restart;
+
+            if restart.any() {
+                normal_eqn_reset_lanes(
+                    &mut self.buffers.path_buffers.x,
+                    &mut self.buffers.path_buffers.z,
+                    &mut self.buffers.path_buffers.y,
+                    &mut self.buffers.path_buffers.w,
+                    restart,
+                );
+                step_fraction = restart.select(step_fraction * Simd::<T, N>::splat(0.5.into()), step_fraction);
+                for (r, lane_restarted) in restarts.iter_mut().zip(restart.to_array()) {
+                    if lane_restarted {
+                        *r += 1;
+                    }
+                }
             }
 
-            iter += 1
+            iter += 1;
+            if (status | failed).all() || iter >= max_iterations.get() {
+                break status & !failed;
+            }
         };
 
-        if last_iteration.is_none() {
-            panic!("Interior point method failed to converged all SIMD lanes.")
+        // A lane with non-finite values has not converged, whatever its status.
+        let finite = self
+            .buffers
+            .path_buffers
+            .x
+            .iter()
+            .fold(Mask::<i64, N>::splat(true), |finite, x| finite & x.is_finite());
+
+        SimdSolution {
+            x: self.buffers.path_buffers.x.as_slice(),
+            converged: (converged & finite).to_array(),
+            restarts,
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathFollowingDirectSimdSolver, Tolerances};
+    use std::num::NonZeroUsize;
+    use std::simd::Simd;
+
+    /// A solver for the LPs `max x0 + x1` subject to `x0 <= b0` and `x1 <= b1`.
+    fn bounded_solver() -> PathFollowingDirectSimdSolver<f64, 4> {
+        PathFollowingDirectSimdSolver::from_data(2, 2, vec![0, 1, 2], vec![0, 1], vec![1.0, 1.0], 2).unwrap()
+    }
+
+    fn solve(
+        b0: [f64; 4],
+        max_iterations: usize,
+        max_restarts: usize,
+    ) -> (Vec<[f64; 4]>, [bool; 4], Vec<usize>, [usize; 4]) {
+        let mut solver = bounded_solver();
+        let b = [Simd::from_array(b0), Simd::splat(2.0)];
+        let c = [Simd::splat(-1.0); 2];
+        let solution = solver.solve(
+            &b,
+            &c,
+            &Tolerances::default(),
+            NonZeroUsize::new(max_iterations).unwrap(),
+            max_restarts,
+        );
+
+        let x = solution.x().iter().map(|x| x.to_array()).collect();
+        (x, *solution.converged(), solution.failed_lanes(), *solution.restarts())
+    }
+
+    #[test]
+    fn test_solve() {
+        let (x, converged, failed, restarts) = solve([1.0, 2.0, 3.0, 4.0], 200, 3);
+
+        assert_eq!(converged, [true; 4]);
+        assert!(failed.is_empty());
+        assert_eq!(restarts, [0; 4]);
+        for (lane, b0) in [1.0, 2.0, 3.0, 4.0].into_iter().enumerate() {
+            assert!((x[0][lane] - b0).abs() < 1e-4);
+            assert!((x[1][lane] - 2.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_diverged_lane_is_restarted_and_fails() {
+        // The LP of the third lane has a non-finite bound and diverges on every attempt
+        let (x, converged, failed, restarts) = solve([1.0, 2.0, f64::NAN, 4.0], 200, 2);
+
+        assert_eq!(converged, [true, true, false, true]);
+        assert_eq!(failed, vec![2]);
+        assert_eq!(restarts, [0, 0, 2, 0]);
+        // ... and the other lanes are unaffected
+        assert!((x[0][3] - 4.0).abs() < 1e-4);
+
+        let (_, _, failed, restarts) = solve([1.0, 2.0, f64::NAN, 4.0], 200, 0);
+        assert_eq!(failed, vec![2]);
+        assert_eq!(restarts, [0; 4]);
+    }
+
+    #[test]
+    fn test_max_iterations() {
+        let (_, converged, failed, _) = solve([1.0, 2.0, 3.0, 4.0], 1, 3);
 
-        // println!("Finished after iterations: {}", last_iteration);
-        // println!("x: {:#?}", self.buffers.path_buffers.x);
-        self.buffers.path_buffers.x.as_slice()
+        assert_eq!(converged, [false; 4]);
+        assert_eq!(failed, vec![0, 1, 2, 3]);
     }
 }
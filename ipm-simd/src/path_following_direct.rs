@@ -199,6 +199,11 @@ fn cholesky_solve<T, const N: usize>(
 }
 
 /// Perform a single step of the path-following algorithm.
+///
+/// The step of each lane is the fraction `step_fraction` of the maximum step that keeps the
+/// path variables positive. Returns the masks of the lanes that have converged and of the lanes
+/// that have diverged (i.e. their feasibility or optimality is no longer finite). The path
+/// variables of the lanes that have converged or diverged are not updated.
 pub fn normal_eqn_step<T, const N: usize>(
     a: &Matrix<T, N>,  // Sparse A matrix
     at: &Matrix<T, N>, // Sparse transpose of A matrix
@@ -221,7 +226,8 @@ pub fn normal_eqn_step<T, const N: usize>(
     tmp: &mut [Simd<T, N>],
     tmp2: &mut [Simd<T, N>],
     tolerances: &Tolerances<T, N>,
-) -> Mask<i64, N>
+    step_fraction: Simd<T, N>,
+) -> (Mask<i64, N>, Mask<i64, N>)
 where
     LaneCount<N>: SupportedLaneCount,
     T: SimdElement<Mask = i64> + From<f64>,
@@ -250,10 +256,7 @@ where
     // update relative tolerance
     gamma = gamma / (Simd::<T, N>::splat(1.0.into()) + vector_norm(x) + vector_norm(y));
 
-    let is_nan: Mask<i64, N> = gamma.is_nan();
-    if is_nan.any() {
-        panic!("NaN encountered during IPM solve!")
-    }
+    let diverged: Mask<i64, N> = !(normr.is_finite() & norms.is_finite() & gamma.is_finite());
 
     // #ifdef DEBUG_GID
     // if (gid == DEBUG_GID) {
@@ -265,9 +268,9 @@ where
         & norms.simd_lt(tolerances.dual_feasibility)
         & gamma.simd_lt(tolerances.optimality);
 
-    if status.all() {
-        // Feasible and optimal; no further work!
-        return status;
+    if (status | diverged).all() {
+        // Feasible and optimal, or diverged; no further work!
+        return (status, diverged);
     }
 
     // Solve normal equations
@@ -290,21 +293,21 @@ where
     // println!("dx: {:?}, dz: {:?}, dy: {:?}, dw: {:?}", dx, dz, dy, dw);
     // println!("Theta: {:?}", theta);
 
-    theta = (Simd::<T, N>::splat(0.9995.into()) / theta).simd_min(Simd::<T, N>::splat(1.0.into()));
+    theta = (step_fraction / theta).simd_min(Simd::<T, N>::splat(1.0.into()));
     // if (gid == 0) {
     //     printf("%d theta: %g", gid, theta);
     // }
 
     // println!("Theta: {:?}", theta);
-    // Set theta to zero for lanes that have completed (status == True)
-    theta = status.select(Simd::<T, N>::splat(0.0.into()), theta);
+    // Set theta to zero for lanes that have completed (status == True) or diverged
+    theta = (status | diverged).select(Simd::<T, N>::splat(0.0.into()), theta);
 
     vector_update(x, dx, Simd::<T, N>::splat(1.0.into()), theta);
     vector_update(z, dz, Simd::<T, N>::splat(1.0.into()), theta);
     vector_update(y, dy, Simd::<T, N>::splat(1.0.into()), theta);
     vector_update(w, dw, Simd::<T, N>::splat(1.0.into()), theta);
 
-    return status;
+    return (status, diverged);
 }
 
 pub fn normal_eqn_init<T, const N: usize>(
@@ -321,3 +324,42 @@ pub fn normal_eqn_init<T, const N: usize>(
     vector_set(y, Simd::<T, N>::splat(1000.0.into()));
     vector_set(w, Simd::<T, N>::splat(1000.0.into()));
 }
+
+/// Reset the path variables of the lanes in `lanes` to their initial values.
+pub fn normal_eqn_reset_lanes<T, const N: usize>(
+    x: &mut [Simd<T, N>],
+    z: &mut [Simd<T, N>],
+    y: &mut [Simd<T, N>],
+    w: &mut [Simd<T, N>],
+    lanes: Mask<i64, N>,
+) where
+    LaneCount<N>: SupportedLaneCount,
+    T: SimdElement<Mask = i64> + From<f64>,
+{
+    let initial = Simd::<T, N>::splat(1000.0.into());
+    for v in x.iter_mut().chain(z.iter_mut()).chain(y.iter_mut()).chain(w.iter_mut()) {
+        *v = lanes.select(initial, *v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normal_eqn_reset_lanes;
+    use std::simd::{Mask, Simd};
+
+    #[test]
+    fn test_reset_lanes() {
+        let mut x = vec![Simd::<f64, 4>::splat(1.0); 2];
+        let mut z = vec![Simd::<f64, 4>::splat(2.0); 2];
+        let mut y = vec![Simd::<f64, 4>::splat(3.0); 1];
+        let mut w = vec![Simd::<f64, 4>::splat(4.0); 1];
+
+        let lanes = Mask::from_array([false, true, false, true]);
+        normal_eqn_reset_lanes(&mut x, &mut z, &mut y, &mut w, lanes);
+
+        assert_eq!(x[1].to_array(), [1.0, 1000.0, 1.0, 1000.0]);
+        assert_eq!(z[0].to_array(), [2.0, 1000.0, 2.0, 1000.0]);
+        assert_eq!(y[0].to_array(), [3.0, 1000.0, 3.0, 1000.0]);
+        assert_eq!(w[0].to_array(), [4.0, 1000.0, 4.0, 1000.0]);
+    }
+}
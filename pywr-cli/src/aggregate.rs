@@ -0,0 +1,212 @@
+use ::tracing::{info, warn};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use pywr_core::recorders::{read_csv_long_metrics, read_hdf5_metrics, AggregationFunction};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How the values of a metric are reduced to a single value for each run.
+#[derive(Copy, Clone, ValueEnum)]
+pub enum RunAggregation {
+    Mean,
+    Sum,
+    Min,
+    Max,
+}
+
+impl From<RunAggregation> for AggregationFunction {
+    fn from(value: RunAggregation) -> Self {
+        match value {
+            RunAggregation::Mean => AggregationFunction::Mean,
+            RunAggregation::Sum => AggregationFunction::Sum,
+            RunAggregation::Min => AggregationFunction::Min,
+            RunAggregation::Max => AggregationFunction::Max,
+        }
+    }
+}
+
+/// The percentiles of the distribution across runs included in the summary.
+const PERCENTILES: [f64; 5] = [5.0, 25.0, 50.0, 75.0, 95.0];
+
+/// Statistics of the distribution of a metric's values across runs.
+struct MetricSummary {
+    count: usize,
+    mean: f64,
+    std: f64,
+    min: f64,
+    percentiles: [f64; PERCENTILES.len()],
+    max: f64,
+}
+
+impl MetricSummary {
+    fn from_values(values: &mut [f64]) -> Self {
+        values.sort_by(f64::total_cmp);
+
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64).sqrt();
+
+        Self {
+            count,
+            mean,
+            std,
+            min: values[0],
+            percentiles: PERCENTILES.map(|p| percentile(values, p)),
+            max: values[count - 1],
+        }
+    }
+}
+
+/// Calculate the `p`th percentile of sorted values using linear interpolation.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Find the result files matching any of the given glob patterns.
+fn find_result_files(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        for entry in glob::glob(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))? {
+            let path = entry.with_context(|| format!("Failed to read path matching: {}", pattern))?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files.dedup();
+
+    if files.is_empty() {
+        bail!("No result files match the given patterns.");
+    }
+
+    Ok(files)
+}
+
+/// Read the values of every metric in a result file.
+///
+/// HDF5 files and long format CSV files (optionally compressed with zstd) are supported.
+fn read_result_file(path: &Path) -> Result<Vec<(String, Vec<f64>)>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("h5") | Some("hdf5") => {
+            let metrics =
+                read_hdf5_metrics(path).with_context(|| format!("Failed to read HDF5 results: {:?}", path))?;
+            Ok(metrics
+                .into_iter()
+                .map(|(name, values)| (name, values.iter().copied().collect()))
+                .collect())
+        }
+        Some("csv") | Some("zst") => {
+            read_csv_long_metrics(path).with_context(|| format!("Failed to read CSV results: {:?}", path))
+        }
+        _ => bail!("Unsupported result file format: {:?}", path),
+    }
+}
+
+/// Combine the results of multiple runs in to a summary of each metric's distribution across
+/// the runs.
+///
+/// The values of each metric (over all time-steps and scenarios) are first reduced to a single
+/// value for each run using `aggregation`. The summary contains the number of runs, and the mean,
+/// standard deviation, minimum, percentiles and maximum of these values. It is written as CSV
+/// with a row for each metric.
+pub fn aggregate_results(patterns: &[String], out_path: &Path, aggregation: RunAggregation) -> Result<()> {
+    let files = find_result_files(patterns)?;
+    info!("Aggregating results of {} runs.", files.len());
+
+    let function: AggregationFunction = aggregation.into();
+    let mut run_values: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+
+    for path in &files {
+        for (name, values) in read_result_file(path)? {
+            match function.calc_f64(&values) {
+                Some(value) => run_values.entry(name).or_default().push(value),
+                None => warn!("Metric `{}` has no values in: {:?}", name, path),
+            }
+        }
+    }
+
+    if run_values.is_empty() {
+        bail!("No metrics were found in the result files.");
+    }
+
+    let mut writer =
+        csv::Writer::from_path(out_path).with_context(|| format!("Failed to create file: {:?}", out_path))?;
+
+    let mut header = vec![
+        "metric".to_string(),
+        "runs".to_string(),
+        "mean".to_string(),
+        "std".to_string(),
+        "min".to_string(),
+    ];
+    header.extend(PERCENTILES.iter().map(|p| format!("p{}", p)));
+    header.push("max".to_string());
+    writer.write_record(&header)?;
+
+    for (name, mut values) in run_values {
+        if values.len() != files.len() {
+            warn!(
+                "Metric `{}` is only found in {} of {} runs.",
+                name,
+                values.len(),
+                files.len()
+            );
+        }
+
+        let s = MetricSummary::from_values(&mut values);
+        let mut record = vec![
+            name,
+            s.count.to_string(),
+            s.mean.to_string(),
+            s.std.to_string(),
+            s.min.to_string(),
+        ];
+        record.extend(s.percentiles.iter().map(|p| p.to_string()));
+        record.push(s.max.to_string());
+        writer.write_record(&record)?;
+    }
+
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write file: {:?}", out_path))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{percentile, MetricSummary};
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_percentile() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_approx_eq!(f64, percentile(&values, 0.0), 1.0);
+        assert_approx_eq!(f64, percentile(&values, 50.0), 3.0);
+        assert_approx_eq!(f64, percentile(&values, 100.0), 5.0);
+        // Percentiles between the values are linearly interpolated
+        assert_approx_eq!(f64, percentile(&values, 5.0), 1.2);
+        assert_approx_eq!(f64, percentile(&values, 62.5), 3.5);
+
+        // A single value is every percentile
+        assert_approx_eq!(f64, percentile(&[7.0], 95.0), 7.0);
+    }
+
+    #[test]
+    fn test_metric_summary() {
+        let mut values = [4.0, 1.0, 3.0, 2.0];
+        let s = MetricSummary::from_values(&mut values);
+
+        assert_eq!(s.count, 4);
+        assert_approx_eq!(f64, s.mean, 2.5);
+        assert_approx_eq!(f64, s.std, 1.25_f64.sqrt());
+        assert_approx_eq!(f64, s.min, 1.0);
+        assert_approx_eq!(f64, s.max, 4.0);
+        assert_approx_eq!(&[f64], &s.percentiles, &[1.15, 1.75, 2.5, 3.25, 3.85]);
+    }
+}
@@ -0,0 +1,291 @@
+use ::tracing::info;
+use anyhow::{Context, Result};
+use pywr_schema::json_schema::{node_schema, parameter_schema};
+use pywr_schema::model::{DateType, Timestep};
+use pywr_schema::outputs::Output;
+use pywr_schema::{PywrModel, VisitPaths};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Write a Markdown report documenting the model at `path`.
+///
+/// The report is generated from the model file itself, so that the documentation does not
+/// become out of date as the model changes. The descriptions of the node and parameter types
+/// are taken from the schema.
+pub fn document(path: &Path, out_path: &Path) -> Result<()> {
+    let schema = PywrModel::from_path(path).with_context(|| format!("Failed to load model: {:?}", path))?;
+
+    let report = render_report(&schema)?;
+
+    info!("Writing model documentation to: {}", out_path.display());
+    std::fs::write(out_path, report).with_context(|| format!("Failed to write file: {:?}", out_path))?;
+
+    Ok(())
+}
+
+/// Escape text for use in the cell of a Markdown table.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Format a date of the time-stepper.
+fn date(date: &DateType) -> String {
+    match date {
+        DateType::Date(date) => date.to_string(),
+        DateType::DateTime(date_time) => date_time.to_string(),
+    }
+}
+
+/// Format the time-steps of the time-stepper.
+fn timestep(timestep: &Timestep) -> String {
+    match timestep {
+        Timestep::Days(1) => "1 day".to_string(),
+        Timestep::Days(days) => format!("{days} days"),
+        Timestep::Frequency(frequency) => frequency.clone(),
+        Timestep::Weekly { weekday } => format!("Weekly from {weekday}"),
+        Timestep::Dates(dates) => format!("{} dates", dates.len()),
+    }
+}
+
+fn render_report(schema: &PywrModel) -> Result<String> {
+    let network = &schema.network;
+    let mut report = String::new();
+
+    writeln!(report, "# {}\n", schema.metadata.title)?;
+    if let Some(description) = &schema.metadata.description {
+        writeln!(report, "{description}\n")?;
+    }
+
+    writeln!(report, "## Time-stepping\n")?;
+    writeln!(report, "| Start | End | Time-step |")?;
+    writeln!(report, "|---|---|---|")?;
+    writeln!(
+        report,
+        "| {} | {} | {} |\n",
+        date(&schema.timestepper.start),
+        date(&schema.timestepper.end),
+        timestep(&schema.timestepper.timestep)
+    )?;
+
+    if let Some(scenarios) = schema.scenarios.as_deref().filter(|s| !s.is_empty()) {
+        writeln!(report, "## Scenarios\n")?;
+        writeln!(report, "| Group | Size | Members |")?;
+        writeln!(report, "|---|---|---|")?;
+        for scenario in scenarios {
+            let members = scenario
+                .ensemble_names
+                .as_ref()
+                .map(|names| names.join(", "))
+                .unwrap_or_default();
+            writeln!(
+                report,
+                "| {} | {} | {} |",
+                cell(&scenario.name),
+                scenario.size,
+                cell(&members)
+            )?;
+        }
+        writeln!(report)?;
+    }
+
+    if let Some(constants) = schema.constants.as_ref().filter(|c| !c.is_empty()) {
+        writeln!(report, "## Constants\n")?;
+        writeln!(report, "| Name | Value |")?;
+        writeln!(report, "|---|---|")?;
+        for (name, value) in constants {
+            writeln!(report, "| {} | {value} |", cell(name))?;
+        }
+        writeln!(report)?;
+    }
+
+    writeln!(report, "## Nodes\n")?;
+    writeln!(report, "| Name | Type | Comment |")?;
+    writeln!(report, "|---|---|---|")?;
+    for node in &network.nodes {
+        let comment = node.meta().comment.as_deref().unwrap_or_default();
+        writeln!(
+            report,
+            "| {} | {} | {} |",
+            cell(node.name()),
+            node.node_type(),
+            cell(comment)
+        )?;
+    }
+    writeln!(report)?;
+
+    let node_types: BTreeSet<String> = network.nodes.iter().map(|n| n.node_type().to_string()).collect();
+    render_type_descriptions(
+        &mut report,
+        "Node types",
+        &node_types,
+        &node_schema().variant_descriptions(),
+    )?;
+
+    writeln!(report, "## Edges\n")?;
+    writeln!(report, "| From | To |")?;
+    writeln!(report, "|---|---|")?;
+    for edge in &network.edges {
+        let from = match &edge.from_slot {
+            Some(slot) => format!("{}[{slot}]", edge.from_node),
+            None => edge.from_node.clone(),
+        };
+        let to = match &edge.to_slot {
+            Some(slot) => format!("{}[{slot}]", edge.to_node),
+            None => edge.to_node.clone(),
+        };
+        writeln!(report, "| {} | {} |", cell(&from), cell(&to))?;
+    }
+    writeln!(report)?;
+
+    // Include the parameters defined locally on the nodes as well as the global parameters
+    let mut parameters: Vec<(Option<&str>, _)> = Vec::new();
+    for node in &network.nodes {
+        if let Some(local_parameters) = node.local_parameters() {
+            parameters.extend(local_parameters.iter().map(|p| (Some(node.name()), p)));
+        }
+    }
+    if let Some(global_parameters) = network.parameters.as_deref() {
+        parameters.extend(global_parameters.iter().map(|p| (None, p)));
+    }
+
+    if !parameters.is_empty() {
+        writeln!(report, "## Parameters\n")?;
+        writeln!(report, "| Name | Node | Type | Comment |")?;
+        writeln!(report, "|---|---|---|---|")?;
+        for (parent, parameter) in &parameters {
+            let comment = parameter.meta().comment.as_deref().unwrap_or_default();
+            writeln!(
+                report,
+                "| {} | {} | {} | {} |",
+                cell(parameter.name()),
+                cell(parent.unwrap_or_default()),
+                parameter.parameter_type(),
+                cell(comment)
+            )?;
+        }
+        writeln!(report)?;
+
+        let parameter_types: BTreeSet<String> =
+            parameters.iter().map(|(_, p)| p.parameter_type().to_string()).collect();
+        render_type_descriptions(
+            &mut report,
+            "Parameter types",
+            &parameter_types,
+            &parameter_schema().variant_descriptions(),
+        )?;
+    }
+
+    if let Some(outputs) = network.outputs.as_deref().filter(|o| !o.is_empty()) {
+        writeln!(report, "## Outputs\n")?;
+        writeln!(report, "| Name | Type | File |")?;
+        writeln!(report, "|---|---|---|")?;
+        for output in outputs {
+            let (name, filename) = match output {
+                Output::CSV(o) => (o.name.as_str(), Some(&o.filename)),
+                Output::HDF5(o) => (o.name.as_str(), Some(&o.filename)),
+                Output::Memory(o) => (o.name.as_str(), None),
+                Output::Events(o) => (o.name.as_str(), o.filename.as_ref()),
+                Output::Manifest(o) => (o.name.as_str(), Some(&o.filename)),
+                Output::Trace(o) => (o.name.as_str(), Some(&o.filename)),
+            };
+            let filename = filename.map(|f| f.display().to_string()).unwrap_or_default();
+            writeln!(report, "| {} | {output} | {} |", cell(name), cell(&filename))?;
+        }
+        writeln!(report)?;
+    }
+
+    // The data files referenced anywhere in the model
+    let mut paths = BTreeSet::new();
+    schema.visit_paths(&mut |path: &Path| {
+        paths.insert(path.display().to_string());
+    });
+
+    if !paths.is_empty() {
+        writeln!(report, "## Data sources\n")?;
+        for path in paths {
+            writeln!(report, "- `{path}`")?;
+        }
+        writeln!(report)?;
+    }
+
+    Ok(report)
+}
+
+/// Write the descriptions from the schema of the component types used in the model.
+fn render_type_descriptions(
+    report: &mut String,
+    heading: &str,
+    types: &BTreeSet<String>,
+    descriptions: &BTreeMap<String, String>,
+) -> Result<()> {
+    let described: Vec<_> = types
+        .iter()
+        .filter_map(|ty| descriptions.get(ty).map(|d| (ty, d)))
+        .collect();
+
+    if described.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(report, "### {heading}\n")?;
+    for (ty, description) in described {
+        writeln!(report, "- **{ty}**: {}", description.replace('\n', " "))?;
+    }
+    writeln!(report)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_report;
+    use pywr_schema::PywrModel;
+
+    #[test]
+    fn test_render_report() {
+        let data = r#"
+            {
+              "metadata": {
+                "title": "Report test",
+                "description": "A model to test the report."
+              },
+              "timestepper": {
+                "start": "2015-01-01",
+                "end": "2015-12-31",
+                "timestep": 1
+              },
+              "network": {
+                "nodes": [
+                  {"meta": {"name": "supply1"}, "type": "Input", "max_flow": {"type": "Constant", "value": 15.0}},
+                  {"meta": {"name": "demand1", "comment": "Demand | with a pipe"}, "type": "Output",
+                   "max_flow": {"type": "Parameter", "name": "demand"}, "cost": {"type": "Constant", "value": -10}}
+                ],
+                "edges": [{"from_node": "supply1", "to_node": "demand1"}],
+                "parameters": [
+                  {"meta": {"name": "demand", "comment": "The demand"}, "type": "Constant", "value": 10.0}
+                ],
+                "metric_sets": [{"name": "nodes", "metrics": [{"type": "Node", "name": "demand1"}]}],
+                "outputs": [
+                  {"name": "flows", "type": "CSV", "format": "long", "filename": "flows.csv", "metric_set": "nodes"}
+                ]
+              }
+            }
+        "#;
+        let schema: PywrModel = data.parse().unwrap();
+
+        let report = render_report(&schema).unwrap();
+
+        assert!(report.starts_with("# Report test\n\nA model to test the report.\n"));
+        assert!(report.contains("| 2015-01-01 | 2015-12-31 | 1 day |\n"));
+        assert!(report.contains("| supply1 | Input |  |\n"));
+        // Pipes are escaped so that they do not split the cells of the table
+        assert!(report.contains("| demand1 | Output | Demand \\| with a pipe |\n"));
+        assert!(report.contains("| supply1 | demand1 |\n"));
+        assert!(report.contains("| demand |  | Constant | The demand |\n"));
+        assert!(report.contains("| flows | CSV | flows.csv |\n"));
+        // The model has no scenarios or constants
+        assert!(!report.contains("## Scenarios"));
+        assert!(!report.contains("## Constants"));
+    }
+}
@@ -1,26 +1,46 @@
+mod aggregate;
+mod document;
+mod progress;
 mod tracing;
 
+use crate::aggregate::{aggregate_results, RunAggregation};
+use crate::document::document;
+use crate::progress::ProgressBar;
 use crate::tracing::setup_tracing;
-use ::tracing::info;
+use ::tracing::{error, info, warn};
 use anyhow::{bail, Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
+use pywr_core::models::{
+    CancellationToken, CouplingTransport, JsonLinesTransport, Model, ModelResult, ProgressFrequency,
+    RandomModelBuilder, RunDispatcher, RunOptions,
+};
+use pywr_core::recorders::{FileManifest, OutputMetric};
+#[cfg(any(feature = "ipm-ocl", feature = "ipm-simd"))]
+use pywr_core::solvers::MultiStateSolver;
 #[cfg(feature = "cbc")]
 use pywr_core::solvers::{CbcSolver, CbcSolverSettings, CbcSolverSettingsBuilder};
 #[cfg(feature = "ipm-ocl")]
 use pywr_core::solvers::{ClIpmF32Solver, ClIpmF64Solver, ClIpmSolverSettings};
-use pywr_core::solvers::{ClpSolver, ClpSolverSettings, ClpSolverSettingsBuilder};
+use pywr_core::solvers::{
+    ClpSolver, ClpSolverSettings, ClpSolverSettingsBuilder, LpExport, LpFileFormat, SolverSettings,
+};
+#[cfg(feature = "gurobi")]
+use pywr_core::solvers::{GurobiMethod, GurobiSolver, GurobiSolverSettings, GurobiSolverSettingsBuilder};
 #[cfg(feature = "highs")]
 use pywr_core::solvers::{HighsSolver, HighsSolverSettings, HighsSolverSettingsBuilder};
+#[cfg(feature = "osqp")]
+use pywr_core::solvers::{OsqpFallback, OsqpSolver, OsqpSolverSettings, OsqpSolverSettingsBuilder};
 #[cfg(feature = "ipm-simd")]
 use pywr_core::solvers::{SimdIpmF64Solver, SimdIpmSolverSettings};
-use pywr_core::test_utils::make_random_model;
-use pywr_schema::model::{PywrModel, PywrMultiNetworkModel, PywrNetwork};
-use pywr_schema::ComponentConversionError;
-use rand::SeedableRng;
-use rand_chacha::ChaCha8Rng;
-use schemars::schema_for;
-use std::fmt::{Display, Formatter};
+use pywr_core::PywrError;
+use pywr_schema::json_schema::{all_schemas, model_schema, SchemaDocument};
+use pywr_schema::model::{PywrModel, PywrMultiNetworkModel, PywrNetwork, ScenarioSubset};
+use pywr_schema::{ComponentConversionError, SchemaError, ValidationSeverity};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Write};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Copy, Clone, ValueEnum)]
 enum Solver {
@@ -29,6 +49,10 @@ enum Solver {
     Highs,
     #[cfg(feature = "cbc")]
     Cbc,
+    #[cfg(feature = "gurobi")]
+    Gurobi,
+    #[cfg(feature = "osqp")]
+    Osqp,
     #[cfg(feature = "ipm-ocl")]
     CLIPMF32,
     #[cfg(feature = "ipm-ocl")]
@@ -45,6 +69,10 @@ impl Display for Solver {
             Solver::Highs => write!(f, "highs"),
             #[cfg(feature = "cbc")]
             Solver::Cbc => write!(f, "cbc"),
+            #[cfg(feature = "gurobi")]
+            Solver::Gurobi => write!(f, "gurobi"),
+            #[cfg(feature = "osqp")]
+            Solver::Osqp => write!(f, "osqp"),
             #[cfg(feature = "ipm-ocl")]
             Solver::CLIPMF32 => write!(f, "clipmf32"),
             #[cfg(feature = "ipm-ocl")]
@@ -55,6 +83,203 @@ impl Display for Solver {
     }
 }
 
+/// The options used to load a model file.
+#[derive(clap::Args)]
+struct ModelLoadArgs {
+    /// Override the value of one of the model's constants (e.g. `--constant peak_demand=12.5`).
+    /// May be given more than once.
+    #[arg(long = "constant", value_parser = parse_constant)]
+    constants: Vec<(String, f64)>,
+    /// Define a variable referenced by the model (e.g. `--define DATA_DIR=/data` for
+    /// `"${DATA_DIR}/inflows.csv"`). Variables that are not defined are read from the
+    /// environment. May be given more than once.
+    #[arg(long = "define", value_parser = parse_define)]
+    definitions: Vec<(String, String)>,
+}
+
+impl ModelLoadArgs {
+    fn definitions(&self) -> BTreeMap<String, String> {
+        self.definitions.iter().cloned().collect()
+    }
+
+    /// Load the model at `path`, resolving its constants and definitions.
+    fn load_model(&self, path: &Path) -> Result<PywrModel> {
+        let constants = self.constants.iter().cloned().collect();
+        PywrModel::from_path_with_definitions(path, &constants, &self.definitions())
+            .with_context(|| format!("Failed to load Pywr v2 model file: {:?}", path))
+    }
+}
+
+/// The options of the OSQP solver.
+#[derive(clap::Args)]
+struct OsqpArgs {
+    /// The absolute and relative tolerance of the residuals at which an OSQP solve stops.
+    #[arg(long)]
+    osqp_tolerance: Option<f64>,
+    /// The maximum number of iterations of each OSQP solve.
+    #[arg(long)]
+    osqp_max_iterations: Option<u32>,
+    /// Polish each OSQP solution by solving the equations of its active constraints.
+    #[arg(long, default_value_t = false)]
+    osqp_polish: bool,
+    /// Solve any time-step that OSQP fails to solve with Clp, rather than stopping the run.
+    #[arg(long, default_value_t = false)]
+    osqp_fallback_clp: bool,
+}
+
+impl OsqpArgs {
+    /// Whether any of the options are given.
+    fn is_set(&self) -> bool {
+        self.osqp_tolerance.is_some()
+            || self.osqp_max_iterations.is_some()
+            || self.osqp_polish
+            || self.osqp_fallback_clp
+    }
+
+    #[cfg(feature = "osqp")]
+    fn settings_builder(&self) -> OsqpSolverSettingsBuilder {
+        let mut builder = OsqpSolverSettingsBuilder::default();
+        if let Some(tolerance) = self.osqp_tolerance {
+            builder = builder.tolerance(tolerance);
+        }
+        if let Some(max_iterations) = self.osqp_max_iterations {
+            builder = builder.max_iterations(max_iterations);
+        }
+        if self.osqp_polish {
+            builder = builder.polish();
+        }
+        if self.osqp_fallback_clp {
+            builder = builder.fallback(OsqpFallback::Clp);
+        }
+        builder
+    }
+}
+
+/// The algorithm used by Gurobi to solve each LP.
+#[derive(Copy, Clone, ValueEnum)]
+enum GurobiMethodArg {
+    Automatic,
+    PrimalSimplex,
+    DualSimplex,
+    Barrier,
+    Concurrent,
+    DeterministicConcurrent,
+}
+
+#[cfg(feature = "gurobi")]
+impl From<GurobiMethodArg> for GurobiMethod {
+    fn from(value: GurobiMethodArg) -> Self {
+        match value {
+            GurobiMethodArg::Automatic => GurobiMethod::Automatic,
+            GurobiMethodArg::PrimalSimplex => GurobiMethod::PrimalSimplex,
+            GurobiMethodArg::DualSimplex => GurobiMethod::DualSimplex,
+            GurobiMethodArg::Barrier => GurobiMethod::Barrier,
+            GurobiMethodArg::Concurrent => GurobiMethod::Concurrent,
+            GurobiMethodArg::DeterministicConcurrent => GurobiMethod::DeterministicConcurrent,
+        }
+    }
+}
+
+/// The options of the Gurobi solver.
+#[derive(clap::Args)]
+struct GurobiArgs {
+    /// The algorithm used by Gurobi to solve each LP.
+    #[arg(long)]
+    gurobi_method: Option<GurobiMethodArg>,
+    /// The primal feasibility tolerance of Gurobi.
+    #[arg(long)]
+    gurobi_feasibility_tolerance: Option<f64>,
+    /// The dual feasibility (optimality) tolerance of Gurobi.
+    #[arg(long)]
+    gurobi_optimality_tolerance: Option<f64>,
+    /// The relative gap at which Gurobi stops solving an LP with integer columns.
+    #[arg(long)]
+    gurobi_mip_gap: Option<f64>,
+    /// The number of threads used by Gurobi to solve each LP. This defaults to one when the
+    /// scenarios are run in parallel, and otherwise to all of the cores of the machine.
+    #[arg(long)]
+    gurobi_threads: Option<usize>,
+}
+
+impl GurobiArgs {
+    /// Whether any of the options are given.
+    fn is_set(&self) -> bool {
+        self.gurobi_method.is_some()
+            || self.gurobi_feasibility_tolerance.is_some()
+            || self.gurobi_optimality_tolerance.is_some()
+            || self.gurobi_mip_gap.is_some()
+            || self.gurobi_threads.is_some()
+    }
+
+    #[cfg(feature = "gurobi")]
+    fn settings_builder(&self) -> GurobiSolverSettingsBuilder {
+        let mut builder = GurobiSolverSettingsBuilder::default();
+        if let Some(method) = self.gurobi_method {
+            builder = builder.method(method.into());
+        }
+        if let Some(tolerance) = self.gurobi_feasibility_tolerance {
+            builder = builder.feasibility_tolerance(tolerance);
+        }
+        if let Some(tolerance) = self.gurobi_optimality_tolerance {
+            builder = builder.optimality_tolerance(tolerance);
+        }
+        if let Some(gap) = self.gurobi_mip_gap {
+            builder = builder.mip_gap(gap);
+        }
+        if let Some(threads) = self.gurobi_threads {
+            builder = builder.solver_threads(threads);
+        }
+        builder
+    }
+}
+
+impl Solver {
+    /// Whether the settings of the solver support writing the LP to a file.
+    fn supports_lp_export(&self) -> bool {
+        match self {
+            Solver::Clp => true,
+            #[cfg(feature = "highs")]
+            Solver::Highs => true,
+            #[cfg(feature = "cbc")]
+            Solver::Cbc => true,
+            #[cfg(feature = "gurobi")]
+            Solver::Gurobi => true,
+            #[cfg(feature = "osqp")]
+            Solver::Osqp => true,
+            #[cfg(feature = "ipm-ocl")]
+            Solver::CLIPMF32 | Solver::CLIPMF64 => false,
+            #[cfg(feature = "ipm-simd")]
+            Solver::IpmSimd => false,
+        }
+    }
+
+    /// Whether the settings of the solver support tie-breaking.
+    ///
+    /// Tie-breaking is applied by the LP builder shared by the solvers, like the LP export.
+    fn supports_tie_breaking(&self) -> bool {
+        self.supports_lp_export()
+    }
+
+    /// Whether the solver can diagnose an infeasible time-step.
+    fn supports_infeasibility_diagnosis(&self) -> bool {
+        match self {
+            Solver::Clp => true,
+            #[cfg(feature = "highs")]
+            Solver::Highs => true,
+            #[cfg(feature = "cbc")]
+            Solver::Cbc => true,
+            #[cfg(feature = "gurobi")]
+            Solver::Gurobi => true,
+            #[cfg(feature = "osqp")]
+            Solver::Osqp => false,
+            #[cfg(feature = "ipm-ocl")]
+            Solver::CLIPMF32 | Solver::CLIPMF64 => false,
+            #[cfg(feature = "ipm-simd")]
+            Solver::IpmSimd => false,
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -94,6 +319,103 @@ enum Commands {
         /// The number of threads to use in parallel simulation.
         #[arg(short, long, default_value_t = 1)]
         threads: usize,
+        /// Report statistics of the LP constraint matrix before running the model.
+        #[arg(long, default_value_t = false)]
+        lp_stats: bool,
+        #[command(flatten)]
+        load: ModelLoadArgs,
+        /// Run only a subset of the members of a scenario group, either as a range of indices
+        /// (e.g. `--scenarios ensemble=100..200`) or a list of indices (e.g. `--scenarios ensemble=1,5,7`).
+        /// May be given more than once.
+        #[arg(long = "scenarios", value_parser = parse_scenario_subset)]
+        scenarios: Vec<(String, ScenarioSubset)>,
+        /// Show a progress bar while the model is running.
+        #[arg(long, default_value_t = false)]
+        progress: bool,
+        /// Solve all the time-steps as a single linear program with perfect foresight, rather
+        /// than one time-step at a time. The linear program is solved with Clp, so only the clp
+        /// solver may be given.
+        #[arg(long, default_value_t = false)]
+        whole_horizon: bool,
+        /// Remove the components of the model that can not affect its results (e.g. unreferenced
+        /// parameters and unconstrained link nodes) before it is built.
+        #[arg(long, default_value_t = false)]
+        prune: bool,
+        /// Write the LP of a time-step to a file in the MPS (`.mps`) or CPLEX LP (`.lp`) format,
+        /// as it is given to the solver, e.g. to find the cause of an infeasibility.
+        #[arg(long, value_parser = parse_lp_export_path)]
+        export_lp: Option<(PathBuf, LpFileFormat)>,
+        /// The index of the time-step whose LP is written with `--export-lp`.
+        #[arg(long, default_value_t = 0, requires = "export_lp")]
+        export_lp_timestep: usize,
+        /// The index of the scenario whose LP is written with `--export-lp`.
+        #[arg(long, default_value_t = 0, requires = "export_lp")]
+        export_lp_scenario: usize,
+        /// If a time-step is infeasible, report the constraints of the network that had to be
+        /// violated to solve it before the run fails.
+        #[arg(long, default_value_t = false)]
+        diagnose_infeasibility: bool,
+        /// Break ties between routes of equal cost by perturbing the edge costs by up to this
+        /// value (e.g. 1e-6), so that the allocation does not depend on the solver.
+        #[arg(long)]
+        tie_breaking: Option<f64>,
+        #[command(flatten)]
+        osqp: OsqpArgs,
+        #[command(flatten)]
+        gurobi: GurobiArgs,
+    },
+    /// Run each member of a scenario group of a model as a separate run.
+    ///
+    /// The outputs of each member are written to a sub-directory of the output path named after
+    /// the group and member (e.g. `ensemble-3`). A run that fails, or that exceeds the time
+    /// limit, is reported and the remaining members are still run.
+    RunBatch {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        /// Solver to use.
+        #[arg(short, long, default_value_t=Solver::Clp)]
+        solver: Solver,
+        /// The scenario group whose members are run separately.
+        #[arg(short, long)]
+        group: String,
+        #[arg(short, long)]
+        data_path: Option<PathBuf>,
+        #[arg(short, long)]
+        output_path: PathBuf,
+        /// The number of members to run at the same time.
+        #[arg(short, long, default_value = "1")]
+        threads: NonZeroUsize,
+        /// Stop any run that takes longer than this number of seconds. The limit is also passed
+        /// to the solvers that can stop a solve part way through a time-step (e.g. Clp).
+        #[arg(long)]
+        time_limit: Option<f64>,
+        #[command(flatten)]
+        load: ModelLoadArgs,
+    },
+    /// Run a model coupled to an external process.
+    ///
+    /// Before each time-step the external process must send a single line of JSON containing the
+    /// values of the model's external inputs. After each time-step the values of the metrics in
+    /// the given metric set are sent back as a single line of JSON.
+    RunCoupled {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        /// Solver to use.
+        #[arg(short, long, default_value_t=Solver::Clp)]
+        solver: Solver,
+        #[arg(short, long)]
+        data_path: Option<PathBuf>,
+        #[arg(short, long)]
+        output_path: Option<PathBuf>,
+        /// Name of the metric set whose values are sent to the external process.
+        #[arg(short, long)]
+        metric_set: String,
+        /// Address of a TCP socket to exchange values over. If not given the values are
+        /// exchanged over stdin and stdout, and logs are written to stderr.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        load: ModelLoadArgs,
     },
     RunMulti {
         /// Path to Pywr model JSON.
@@ -108,6 +430,8 @@ enum Commands {
         /// The number of threads to use in parallel simulation.
         #[arg(short, long, default_value_t = 1)]
         threads: usize,
+        #[command(flatten)]
+        load: ModelLoadArgs,
     },
     RunRandom {
         num_systems: usize,
@@ -116,16 +440,156 @@ enum Commands {
         /// Solver to use.
         #[arg(short, long, default_value_t=Solver::Clp)]
         solver: Solver,
+        /// Seed of the random number generator.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Proportion of systems that include a reservoir.
+        #[arg(long, default_value_t = 0.0)]
+        storage_fraction: f64,
+        /// Proportion of systems that have a licence.
+        #[arg(long, default_value_t = 0.0)]
+        licence_fraction: f64,
+        /// Coefficient of variation of the mean inflow between scenarios.
+        #[arg(long, default_value_t = 0.0)]
+        inflow_variation: f64,
+    },
+    /// Benchmark the setup and run time of a model.
+    ///
+    /// The model is setup and run the given number of times, and statistics of the timings
+    /// are reported. This can be used to quantify the performance of a model on different
+    /// platforms, or between versions of Pywr.
+    Benchmark {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        /// Solver to use.
+        #[arg(short, long, default_value_t=Solver::Clp)]
+        solver: Solver,
+        #[arg(short, long)]
+        data_path: Option<PathBuf>,
+        /// The number of times to run the model.
+        #[arg(short, long, default_value_t = 5)]
+        num_samples: usize,
+        #[command(flatten)]
+        load: ModelLoadArgs,
+    },
+    /// Estimate the run time and output size of a model.
+    ///
+    /// The model is setup and run for a small sample of its time-steps and scenarios, and the
+    /// timings are extrapolated to the full run. This can be used to plan batch submissions or
+    /// to compare solvers before committing to a long run.
+    Estimate {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        /// Solver to use.
+        #[arg(short, long, default_value_t=Solver::Clp)]
+        solver: Solver,
+        #[arg(short, long)]
+        data_path: Option<PathBuf>,
+        /// The number of time-steps to run in the sample.
+        #[arg(long, default_value_t = NonZeroUsize::new(30).unwrap())]
+        num_timesteps: NonZeroUsize,
+        /// The number of scenarios to run in the sample.
+        #[arg(long, default_value_t = NonZeroUsize::new(1).unwrap())]
+        num_scenarios: NonZeroUsize,
+        #[command(flatten)]
+        load: ModelLoadArgs,
+    },
+    /// Inspect a model without running it.
+    ///
+    /// The requested information is printed to stdout as JSON so that it can be used to
+    /// generate downstream processing of the model's results.
+    Inspect {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        #[arg(short, long)]
+        data_path: Option<PathBuf>,
+        #[arg(short, long)]
+        output_path: Option<PathBuf>,
+        /// Describe the metrics, dimensions, units and size of the values written by each output.
+        #[arg(long, default_value_t = false)]
+        outputs: bool,
+        #[command(flatten)]
+        load: ModelLoadArgs,
+    },
+    /// Check a model for problems without building or running it.
+    ///
+    /// Every reference between the components of the model (e.g. edges to nodes, and metrics of
+    /// nodes and parameters) is checked, and all the problems found are reported with the JSON
+    /// path of the part of the file that caused them. No data is loaded and no solver is used.
+    Check {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        /// Fail if there are any warnings as well as errors.
+        #[arg(long, default_value_t = false)]
+        deny_warnings: bool,
+        /// Check a multi-network model. The networks given by a path are loaded relative to
+        /// `data_path`, or the directory of the model if not given.
+        #[arg(long, default_value_t = false)]
+        multi: bool,
+        #[arg(short, long)]
+        data_path: Option<PathBuf>,
+    },
+    /// Aggregate the results of multiple model runs.
+    ///
+    /// The HDF5 or long format CSV result files of a batch of runs (e.g. from a sensitivity or
+    /// optimisation study) are read and the distribution of each metric across the runs is
+    /// summarised in a single CSV file.
+    AggregateResults {
+        /// Glob pattern(s) of the result files to aggregate (e.g. `"outputs/*/outputs.h5"`).
+        #[arg(required = true)]
+        patterns: Vec<String>,
+        /// Path to save the summary CSV.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// How each metric's values are reduced to a single value for each run.
+        #[arg(short, long, value_enum, default_value_t = RunAggregation::Mean)]
+        aggregation: RunAggregation,
+    },
+    /// Optimise the variable parameters of a model.
+    ///
+    /// The model must define an `optimisation` with the objectives to optimise. The model is
+    /// run repeatedly by the NSGA-II algorithm, and the non-dominated solutions are saved as
+    /// CSV with a column for each variable value and objective.
+    Optimise {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        /// Solver to use.
+        #[arg(short, long, default_value_t=Solver::Clp)]
+        solver: Solver,
+        #[arg(short, long)]
+        data_path: Option<PathBuf>,
+        /// Path to save the solutions CSV.
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        load: ModelLoadArgs,
+    },
+    /// Write a Markdown report documenting a model.
+    ///
+    /// The report includes the nodes, edges, parameters, scenarios, outputs and data sources
+    /// of the model, along with descriptions of the node and parameter types used. It is
+    /// generated from the model file so that the documentation stays in sync with the model.
+    Document {
+        /// Path to Pywr model JSON.
+        model: PathBuf,
+        /// Path to save the Markdown report.
+        #[arg(short, long)]
+        out: PathBuf,
     },
     ExportSchema {
-        /// Path to save the JSON schema.
+        /// Path to save the JSON schema. If `--all` is given this must be an existing directory.
         out: PathBuf,
+        /// Export the schemas of all model file types and their components as separate files.
+        #[arg(short, long, default_value_t = false)]
+        all: bool,
     },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    setup_tracing(cli.debug)?;
+    // Stdout is reserved for the coupling protocol if no socket is given.
+    let log_to_stderr = matches!(cli.command, Commands::RunCoupled { connect: None, .. });
+    setup_tracing(cli.debug, log_to_stderr)?;
 
     match &cli.command {
         Commands::Convert {
@@ -140,26 +604,203 @@ fn main() -> Result<()> {
             data_path,
             output_path,
             threads,
-        } => run(model, solver, data_path.as_deref(), output_path.as_deref(), *threads),
+            lp_stats,
+            load,
+            scenarios,
+            progress,
+            whole_horizon,
+            prune,
+            export_lp,
+            export_lp_timestep,
+            export_lp_scenario,
+            diagnose_infeasibility,
+            tie_breaking,
+            osqp,
+            gurobi,
+        } => run(
+            model,
+            solver,
+            data_path.as_deref(),
+            output_path.as_deref(),
+            *threads,
+            *lp_stats,
+            load,
+            scenarios,
+            *progress,
+            *whole_horizon,
+            *prune,
+            export_lp.as_ref().map(|(path, format)| {
+                LpExport::new(path, *format, *export_lp_timestep).with_scenario(*export_lp_scenario)
+            }),
+            *diagnose_infeasibility,
+            *tie_breaking,
+            osqp,
+            gurobi,
+        )?,
+        Commands::RunBatch {
+            model,
+            solver,
+            group,
+            data_path,
+            output_path,
+            threads,
+            time_limit,
+            load,
+        } => run_batch(
+            model,
+            *solver,
+            group,
+            data_path.as_deref(),
+            output_path,
+            *threads,
+            *time_limit,
+            load,
+        )?,
+        Commands::RunCoupled {
+            model,
+            solver,
+            data_path,
+            output_path,
+            metric_set,
+            connect,
+            load,
+        } => run_coupled(
+            model,
+            solver,
+            data_path.as_deref(),
+            output_path.as_deref(),
+            metric_set,
+            connect.as_deref(),
+            load,
+        )?,
         Commands::RunMulti {
             model,
             solver,
             data_path,
             output_path,
             threads: _,
-        } => run_multi(model, solver, data_path.as_deref(), output_path.as_deref()),
+            load,
+        } => run_multi(model, solver, data_path.as_deref(), output_path.as_deref(), load)?,
         Commands::RunRandom {
             num_systems,
             density,
             num_scenarios,
             solver,
-        } => run_random(*num_systems, *density, *num_scenarios, solver),
-        Commands::ExportSchema { out } => export_schema(out)?,
+            seed,
+            storage_fraction,
+            licence_fraction,
+            inflow_variation,
+        } => {
+            let builder = RandomModelBuilder::new(*num_systems, *density, *num_scenarios)
+                .seed(*seed)
+                .storage_fraction(*storage_fraction)
+                .licence_fraction(*licence_fraction)
+                .inflow_scenario_variation(*inflow_variation);
+            run_random(&builder, solver)?
+        }
+        Commands::Benchmark {
+            model,
+            solver,
+            data_path,
+            num_samples,
+            load,
+        } => benchmark(model, solver, data_path.as_deref(), *num_samples, load)?,
+        Commands::Estimate {
+            model,
+            solver,
+            data_path,
+            num_timesteps,
+            num_scenarios,
+            load,
+        } => estimate(
+            model,
+            solver,
+            data_path.as_deref(),
+            *num_timesteps,
+            *num_scenarios,
+            load,
+        )?,
+        Commands::Inspect {
+            model,
+            data_path,
+            output_path,
+            outputs,
+            load,
+        } => inspect(model, data_path.as_deref(), output_path.as_deref(), *outputs, load)?,
+        Commands::Check {
+            model,
+            deny_warnings,
+            multi,
+            data_path,
+        } => check(model, *deny_warnings, *multi, data_path.as_deref())?,
+        Commands::AggregateResults {
+            patterns,
+            output,
+            aggregation,
+        } => aggregate_results(patterns, output, *aggregation)?,
+        Commands::Optimise {
+            model,
+            solver,
+            data_path,
+            output,
+            load,
+        } => optimise(model, solver, data_path.as_deref(), output, load)?,
+        Commands::Document { model, out } => document(model, out)?,
+        Commands::ExportSchema { out, all } => export_schema(out, *all)?,
     }
 
     Ok(())
 }
 
+/// Parse a `NAME=VALUE` pair given to the `--constant` argument.
+fn parse_constant(s: &str) -> Result<(String, f64), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid constant `{s}`; expected `NAME=VALUE`"))?;
+    let value = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("invalid value for constant `{name}`: {e}"))?;
+    Ok((name.trim().to_string(), value))
+}
+
+fn parse_lp_export_path(s: &str) -> Result<(PathBuf, LpFileFormat), String> {
+    let path = PathBuf::from(s);
+    let format = LpFileFormat::from_path(&path)
+        .ok_or_else(|| format!("unknown LP file format of `{s}`; expected a `.mps` or `.lp` file"))?;
+    Ok((path, format))
+}
+
+fn parse_define(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid definition `{s}`; expected `NAME=VALUE`"))?;
+    Ok((name.trim().to_string(), value.to_string()))
+}
+
+fn parse_scenario_subset(s: &str) -> Result<(String, ScenarioSubset), String> {
+    let (name, subset) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid scenarios `{s}`; expected `GROUP=START..END` or `GROUP=I,J,...`"))?;
+    let parse_index = |v: &str| {
+        v.trim()
+            .parse::<usize>()
+            .map_err(|e| format!("invalid scenario index `{v}` for group `{name}`: {e}"))
+    };
+
+    let subset = match subset.split_once("..") {
+        Some((start, end)) => ScenarioSubset::Slice {
+            start: parse_index(start)?,
+            end: parse_index(end)?,
+        },
+        None => ScenarioSubset::Indices {
+            indices: subset.split(',').map(parse_index).collect::<Result<_, _>>()?,
+        },
+    };
+
+    Ok((name.trim().to_string(), subset))
+}
+
 fn convert(in_path: &Path, out_path: &Path, stop_on_error: bool, network_only: bool) -> Result<()> {
     if in_path.is_dir() {
         if !out_path.is_dir() {
@@ -250,22 +891,109 @@ fn handle_conversion_errors(errors: &[ComponentConversionError], stop_on_error:
     Ok(())
 }
 
-fn run(path: &Path, solver: &Solver, data_path: Option<&Path>, output_path: Option<&Path>, threads: usize) {
-    let data = std::fs::read_to_string(path).unwrap();
+#[allow(clippy::too_many_arguments)]
+fn run(
+    path: &Path,
+    solver: &Solver,
+    data_path: Option<&Path>,
+    output_path: Option<&Path>,
+    threads: usize,
+    lp_stats: bool,
+    load: &ModelLoadArgs,
+    scenarios: &[(String, ScenarioSubset)],
+    progress: bool,
+    whole_horizon: bool,
+    prune: bool,
+    lp_export: Option<LpExport>,
+    diagnose_infeasibility: bool,
+    tie_breaking: Option<f64>,
+    osqp: &OsqpArgs,
+    gurobi: &GurobiArgs,
+) -> Result<()> {
+    if whole_horizon && !matches!(solver, Solver::Clp) {
+        bail!(
+            "The whole-horizon run mode only supports the clp solver, not `{}`.",
+            solver
+        );
+    }
+
     let data_path = data_path.or_else(|| path.parent());
-    let schema_v2: PywrModel = serde_json::from_str(data.as_str()).unwrap();
+    let mut schema_v2 = load.load_model(path)?;
+    for (name, subset) in scenarios {
+        schema_v2.set_scenario_subset(name, subset.clone()).unwrap();
+    }
+
+    if prune {
+        let report = schema_v2.network.prune().unwrap();
+        info!("Pruned {} components from the model.", report.len());
+        if !report.is_empty() {
+            info!("{report}");
+        }
+    }
 
     let model = schema_v2.build_model(data_path, output_path).unwrap();
 
-    match *solver {
+    if lp_stats {
+        report_lp_statistics(&model);
+    }
+
+    if lp_export.is_some() && (whole_horizon || !solver.supports_lp_export()) {
+        warn!(
+            "The LP is only exported by the Clp, HiGHS, CBC, Gurobi and OSQP solvers when run one time-step at a time."
+        );
+    }
+
+    if diagnose_infeasibility && (whole_horizon || !solver.supports_infeasibility_diagnosis()) {
+        warn!("Infeasibility is only diagnosed by the Clp, HiGHS, CBC and Gurobi solvers when run one time-step at a time.");
+    }
+
+    if tie_breaking.is_some() && (whole_horizon || !solver.supports_tie_breaking()) {
+        warn!("Tie-breaking is only applied by the Clp, HiGHS, CBC, Gurobi and OSQP solvers when run one time-step at a time.");
+    }
+
+    if osqp.is_set() && (whole_horizon || solver.to_string() != "osqp") {
+        warn!("The OSQP options are only used by the osqp solver.");
+    }
+
+    if gurobi.is_set() && (whole_horizon || solver.to_string() != "gurobi") {
+        warn!("The Gurobi options are only used by the gurobi solver.");
+    }
+
+    if whole_horizon {
+        let result = model.run_whole_horizon().unwrap();
+        report_manifest(result.manifest());
+        return Ok(());
+    }
+
+    let mut progress_bar = progress.then(ProgressBar::default);
+    let progress_bar = progress_bar.as_mut();
+
+    // Stop the run at the end of the current time-step when it is interrupted (e.g. with
+    // Ctrl-C), so that the outputs of the completed time-steps are still written.
+    let token = CancellationToken::new();
+    let handler_token = token.clone();
+    if let Err(error) = ctrlc::set_handler(move || handler_token.cancel()) {
+        warn!("The run can not be cancelled with Ctrl-C: {error}");
+    }
+
+    let result = match *solver {
         Solver::Clp => {
             let mut settings_builder = ClpSolverSettingsBuilder::default();
             if threads > 1 {
                 settings_builder = settings_builder.parallel();
                 settings_builder = settings_builder.threads(threads);
             }
+            if let Some(export) = &lp_export {
+                settings_builder = settings_builder.export_lp(export.clone());
+            }
+            if let Some(epsilon) = tie_breaking {
+                settings_builder = settings_builder.tie_breaking(epsilon);
+            }
+            if diagnose_infeasibility {
+                settings_builder = settings_builder.diagnose_infeasibility();
+            }
             let settings = settings_builder.build();
-            model.run::<ClpSolver>(&settings)
+            run_with_progress::<ClpSolver>(&model, &settings, progress_bar, &token)
         }
         #[cfg(feature = "cbc")]
         Solver::Cbc => {
@@ -274,8 +1002,17 @@ fn run(path: &Path, solver: &Solver, data_path: Option<&Path>, output_path: Opti
                 settings_builder = settings_builder.parallel();
                 settings_builder = settings_builder.threads(threads);
             }
+            if let Some(export) = &lp_export {
+                settings_builder = settings_builder.export_lp(export.clone());
+            }
+            if let Some(epsilon) = tie_breaking {
+                settings_builder = settings_builder.tie_breaking(epsilon);
+            }
+            if diagnose_infeasibility {
+                settings_builder = settings_builder.diagnose_infeasibility();
+            }
             let settings = settings_builder.build();
-            model.run::<CbcSolver>(&settings)
+            run_with_progress::<CbcSolver>(&model, &settings, progress_bar, &token)
         }
         #[cfg(feature = "highs")]
         Solver::Highs => {
@@ -284,31 +1021,349 @@ fn run(path: &Path, solver: &Solver, data_path: Option<&Path>, output_path: Opti
                 settings_builder = settings_builder.parallel();
                 settings_builder = settings_builder.threads(threads);
             }
+            if let Some(export) = &lp_export {
+                settings_builder = settings_builder.export_lp(export.clone());
+            }
+            if let Some(epsilon) = tie_breaking {
+                settings_builder = settings_builder.tie_breaking(epsilon);
+            }
+            if diagnose_infeasibility {
+                settings_builder = settings_builder.diagnose_infeasibility();
+            }
             let settings = settings_builder.build();
-            model.run::<HighsSolver>(&settings)
+            run_with_progress::<HighsSolver>(&model, &settings, progress_bar, &token)
+        }
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => {
+            let mut settings_builder = gurobi.settings_builder();
+            if threads > 1 {
+                settings_builder = settings_builder.parallel();
+                settings_builder = settings_builder.threads(threads);
+            }
+            if let Some(export) = &lp_export {
+                settings_builder = settings_builder.export_lp(export.clone());
+            }
+            if let Some(epsilon) = tie_breaking {
+                settings_builder = settings_builder.tie_breaking(epsilon);
+            }
+            if diagnose_infeasibility {
+                settings_builder = settings_builder.diagnose_infeasibility();
+            }
+            let settings = settings_builder.build();
+            run_with_progress::<GurobiSolver>(&model, &settings, progress_bar, &token)
+        }
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => {
+            let mut settings_builder = osqp.settings_builder();
+            if threads > 1 {
+                settings_builder = settings_builder.parallel();
+                settings_builder = settings_builder.threads(threads);
+            }
+            if let Some(export) = &lp_export {
+                settings_builder = settings_builder.export_lp(export.clone());
+            }
+            if let Some(epsilon) = tie_breaking {
+                settings_builder = settings_builder.tie_breaking(epsilon);
+            }
+            let settings = settings_builder.build();
+            run_with_progress::<OsqpSolver>(&model, &settings, progress_bar, &token)
         }
         #[cfg(feature = "ipm-ocl")]
-        Solver::CLIPMF32 => model.run_multi_scenario::<ClIpmF32Solver>(&ClIpmSolverSettings::default()),
+        Solver::CLIPMF32 => run_multi_scenario_with_progress::<ClIpmF32Solver>(
+            &model,
+            &ClIpmSolverSettings::default(),
+            progress_bar,
+            &token,
+        ),
         #[cfg(feature = "ipm-ocl")]
-        Solver::CLIPMF64 => model.run_multi_scenario::<ClIpmF64Solver>(&ClIpmSolverSettings::default()),
+        Solver::CLIPMF64 => run_multi_scenario_with_progress::<ClIpmF64Solver>(
+            &model,
+            &ClIpmSolverSettings::default(),
+            progress_bar,
+            &token,
+        ),
         #[cfg(feature = "ipm-simd")]
-        Solver::IpmSimd => model.run_multi_scenario::<SimdIpmF64Solver<4>>(&SimdIpmSolverSettings::default()),
+        Solver::IpmSimd => run_multi_scenario_with_progress::<SimdIpmF64Solver<4>>(
+            &model,
+            &SimdIpmSolverSettings::default(),
+            progress_bar,
+            &token,
+        ),
+    };
+
+    let result = match result {
+        Err(PywrError::RunCancelled {
+            completed_timesteps,
+            num_timesteps,
+            manifest,
+        }) => {
+            report_manifest(&manifest);
+            bail!("The run was cancelled after {completed_timesteps} of {num_timesteps} time-steps.");
+        }
+        result => result?,
+    };
+
+    report_manifest(result.manifest());
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_batch(
+    path: &Path,
+    solver: Solver,
+    group: &str,
+    data_path: Option<&Path>,
+    output_path: &Path,
+    threads: NonZeroUsize,
+    time_limit: Option<f64>,
+    load: &ModelLoadArgs,
+) -> Result<()> {
+    let data_path = data_path.or_else(|| path.parent());
+    let schema_v2 = load.load_model(path)?;
+
+    let size = schema_v2
+        .scenarios
+        .iter()
+        .flatten()
+        .find(|s| s.name == group)
+        .map(|s| s.size)
+        .with_context(|| format!("Scenario group `{group}` not found in the model."))?;
+
+    let mut dispatcher = RunDispatcher::new(threads);
+    if let Some(time_limit) = time_limit {
+        dispatcher = dispatcher.with_time_limit(Duration::from_secs_f64(time_limit));
+    }
+
+    let schema_v2 = &schema_v2;
+    let jobs = (0..size).map(move |member| {
+        let label = format!("{group}-{member}");
+        let member_output_path = output_path.join(&label);
+
+        let job = move |options: RunOptions<'static>| -> Result<FileManifest, SchemaError> {
+            let mut schema = schema_v2.clone();
+            schema.set_scenario_subset(group, ScenarioSubset::Indices { indices: vec![member] })?;
+
+            std::fs::create_dir_all(&member_output_path).map_err(|error| SchemaError::IO {
+                path: member_output_path.clone(),
+                error,
+            })?;
+            let model = schema.build_model(data_path, Some(&member_output_path))?;
+            let result = run_with_options(&model, solver, options)?;
+
+            Ok(result.manifest().clone())
+        };
+
+        (label, job)
+    });
+
+    // The outcome of each run is logged by the dispatcher
+    let runs = dispatcher.run(jobs);
+    for manifest in runs.iter().filter_map(|run| run.result.as_ref().ok()) {
+        report_manifest(manifest);
+    }
+
+    let failed: Vec<&str> = runs
+        .iter()
+        .filter(|run| run.result.is_err())
+        .map(|run| run.label.as_str())
+        .collect();
+    if !failed.is_empty() {
+        bail!("{} of {} runs failed: {}", failed.len(), runs.len(), failed.join(", "));
+    }
+
+    Ok(())
+}
+
+/// The progress bar is updated every 1% of the time-steps.
+const PROGRESS_FREQUENCY: ProgressFrequency = ProgressFrequency::Percent(NonZeroUsize::MIN);
+
+/// Run the model until it is cancelled with `token`, showing the progress of the run if a
+/// progress bar is given.
+fn run_with_progress<S>(
+    model: &Model,
+    settings: &S::Settings,
+    progress_bar: Option<&mut ProgressBar>,
+    token: &CancellationToken,
+) -> Result<ModelResult, PywrError>
+where
+    S: pywr_core::solvers::Solver,
+    <S as pywr_core::solvers::Solver>::Settings: SolverSettings,
+{
+    model.run_with_options::<S>(settings, run_options(progress_bar, token))
+}
+
+/// The options of a run that is cancelled with `token` and reports its progress to the
+/// progress bar, if one is given.
+fn run_options<'a>(progress_bar: Option<&'a mut ProgressBar>, token: &CancellationToken) -> RunOptions<'a> {
+    let options = RunOptions::default().cancellation_token(token.clone());
+    match progress_bar {
+        Some(progress_bar) => options.progress(progress_bar, PROGRESS_FREQUENCY),
+        None => options,
+    }
+}
+
+/// Run the model with the default settings of `solver` and the given [`RunOptions`].
+fn run_with_options(model: &Model, solver: Solver, options: RunOptions) -> Result<ModelResult, PywrError> {
+    match solver {
+        Solver::Clp => model.run_with_options::<ClpSolver>(&ClpSolverSettings::default(), options),
+        #[cfg(feature = "cbc")]
+        Solver::Cbc => model.run_with_options::<CbcSolver>(&CbcSolverSettings::default(), options),
+        #[cfg(feature = "highs")]
+        Solver::Highs => model.run_with_options::<HighsSolver>(&HighsSolverSettings::default(), options),
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => model.run_with_options::<GurobiSolver>(&GurobiSolverSettings::default(), options),
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => model.run_with_options::<OsqpSolver>(&OsqpSolverSettings::default(), options),
+        #[cfg(feature = "ipm-ocl")]
+        Solver::CLIPMF32 => {
+            model.run_multi_scenario_with_options::<ClIpmF32Solver>(&ClIpmSolverSettings::default(), options)
+        }
+        #[cfg(feature = "ipm-ocl")]
+        Solver::CLIPMF64 => {
+            model.run_multi_scenario_with_options::<ClIpmF64Solver>(&ClIpmSolverSettings::default(), options)
+        }
+        #[cfg(feature = "ipm-simd")]
+        Solver::IpmSimd => {
+            model.run_multi_scenario_with_options::<SimdIpmF64Solver<4>>(&SimdIpmSolverSettings::default(), options)
+        }
+    }
+}
+
+/// Run the model with a [`MultiStateSolver`] until it is cancelled with `token`, showing the
+/// progress of the run if a progress bar is given.
+#[cfg(any(feature = "ipm-ocl", feature = "ipm-simd"))]
+fn run_multi_scenario_with_progress<S>(
+    model: &Model,
+    settings: &S::Settings,
+    progress_bar: Option<&mut ProgressBar>,
+    token: &CancellationToken,
+) -> Result<ModelResult, PywrError>
+where
+    S: MultiStateSolver,
+    <S as MultiStateSolver>::Settings: SolverSettings,
+{
+    model.run_multi_scenario_with_options::<S>(settings, run_options(progress_bar, token))
+}
+
+/// Log the files written by the recorders along with their sizes and checksums.
+fn report_manifest(manifest: &FileManifest) {
+    if manifest.is_empty() {
+        return;
+    }
+
+    info!(
+        "Output files written: {} ({} bytes)",
+        manifest.len(),
+        manifest.total_size()
+    );
+    for line in manifest.to_string().lines() {
+        info!("{}", line);
+    }
+}
+
+fn report_lp_statistics(model: &Model) {
+    let stats = model.lp_statistics().unwrap();
+
+    info!("LP constraint matrix statistics:");
+    for line in stats.to_string().lines() {
+        info!("{}", line);
+    }
+
+    for warning in stats.warnings() {
+        warn!("{}", warning);
     }
-    .unwrap();
 }
 
-fn run_multi(path: &Path, solver: &Solver, data_path: Option<&Path>, output_path: Option<&Path>) {
-    let data = std::fs::read_to_string(path).unwrap();
+#[allow(clippy::too_many_arguments)]
+fn run_coupled(
+    path: &Path,
+    solver: &Solver,
+    data_path: Option<&Path>,
+    output_path: Option<&Path>,
+    metric_set: &str,
+    connect: Option<&str>,
+    load: &ModelLoadArgs,
+) -> Result<()> {
     let data_path = data_path.or_else(|| path.parent());
+    let schema_v2 = load.load_model(path)?;
 
-    let schema_v2: PywrMultiNetworkModel = serde_json::from_str(data.as_str()).unwrap();
+    let model = schema_v2
+        .build_model(data_path, output_path)
+        .with_context(|| format!("Failed to build Pywr v2 model: {:?}", path))?;
 
-    let model = schema_v2.build_model(data_path, output_path).unwrap();
+    let outputs: Vec<_> = model
+        .network()
+        .get_metric_set_by_name(metric_set)
+        .with_context(|| format!("Metric set `{metric_set}` not found in the model."))?
+        .iter_metrics()
+        .cloned()
+        .collect();
+
+    match connect {
+        Some(addr) => {
+            info!("Connecting to coupled model at: {}", addr);
+            let mut transport = JsonLinesTransport::connect(addr)
+                .with_context(|| format!("Failed to connect to coupled model at: {addr}"))?;
+            run_coupled_with_transport(&model, solver, &mut transport, &outputs)
+        }
+        None => {
+            let mut transport = JsonLinesTransport::stdio();
+            run_coupled_with_transport(&model, solver, &mut transport, &outputs)
+        }
+    }
+}
 
+fn run_coupled_with_transport<T: CouplingTransport>(
+    model: &Model,
+    solver: &Solver,
+    transport: &mut T,
+    outputs: &[OutputMetric],
+) -> Result<()> {
     match *solver {
+        Solver::Clp => model.run_coupled::<ClpSolver, _>(&ClpSolverSettings::default(), transport, outputs),
+        #[cfg(feature = "highs")]
+        Solver::Highs => model.run_coupled::<HighsSolver, _>(&HighsSolverSettings::default(), transport, outputs),
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => model.run_coupled::<GurobiSolver, _>(&GurobiSolverSettings::default(), transport, outputs),
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => model.run_coupled::<OsqpSolver, _>(&OsqpSolverSettings::default(), transport, outputs),
+        #[cfg(feature = "cbc")]
+        Solver::Cbc => model.run_coupled::<CbcSolver, _>(&CbcSolverSettings::default(), transport, outputs),
+        #[allow(unreachable_patterns)]
+        _ => bail!("Solver `{}` does not support coupled simulation.", solver),
+    }
+    .with_context(|| "Coupled simulation failed.".to_string())?;
+
+    Ok(())
+}
+
+fn run_multi(
+    path: &Path,
+    solver: &Solver,
+    data_path: Option<&Path>,
+    output_path: Option<&Path>,
+    load: &ModelLoadArgs,
+) -> Result<()> {
+    if !load.constants.is_empty() {
+        bail!("The constants of a multi-network model can not be overridden.");
+    }
+
+    let data_path = data_path.or_else(|| path.parent());
+
+    let schema_v2 = PywrMultiNetworkModel::from_path_with_definitions(path, &load.definitions())
+        .with_context(|| format!("Failed to load Pywr v2 multi-network model file: {:?}", path))?;
+
+    let model = schema_v2.build_model(data_path, output_path).unwrap();
+
+    let manifest = match *solver {
         Solver::Clp => model.run::<ClpSolver>(&ClpSolverSettings::default()),
         #[cfg(feature = "highs")]
         Solver::Highs => model.run::<HighsSolver>(&HighsSolverSettings::default()),
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => model.run::<GurobiSolver>(&GurobiSolverSettings::default()),
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => model.run::<OsqpSolver>(&OsqpSolverSettings::default()),
         #[cfg(feature = "cbc")]
         Solver::Cbc => model.run::<CbcSolver>(&CbcSolverSettings::default()),
         #[cfg(feature = "ipm-ocl")]
@@ -319,16 +1374,23 @@ fn run_multi(path: &Path, solver: &Solver, data_path: Option<&Path>, output_path
         Solver::IpmSimd => model.run_multi_scenario::<SimdIpmF64Solver<4>>(&SimdIpmSolverSettings::default()),
     }
     .unwrap();
+
+    report_manifest(&manifest);
+
+    Ok(())
 }
 
-fn run_random(num_systems: usize, density: usize, num_scenarios: usize, solver: &Solver) {
-    let mut rng = ChaCha8Rng::seed_from_u64(0);
-    let model = make_random_model(num_systems, density, num_scenarios, &mut rng).unwrap();
+fn run_random(builder: &RandomModelBuilder, solver: &Solver) -> Result<()> {
+    let model = builder.build().context("Failed to generate the random model.")?;
 
     match *solver {
         Solver::Clp => model.run::<ClpSolver>(&ClpSolverSettings::default()),
         #[cfg(feature = "highs")]
         Solver::Highs => model.run::<HighsSolver>(&HighsSolverSettings::default()),
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => model.run::<GurobiSolver>(&GurobiSolverSettings::default()),
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => model.run::<OsqpSolver>(&OsqpSolverSettings::default()),
         #[cfg(feature = "cbc")]
         Solver::Cbc => model.run::<CbcSolver>(&CbcSolverSettings::default()),
         #[cfg(feature = "ipm-ocl")]
@@ -338,14 +1400,286 @@ fn run_random(num_systems: usize, density: usize, num_scenarios: usize, solver:
         #[cfg(feature = "ipm-simd")]
         Solver::IpmSimd => model.run_multi_scenario::<SimdIpmF64Solver<4>>(&SimdIpmSolverSettings::default()),
     }
-    .unwrap();
+    .context("Failed to run the random model.")?;
+
+    Ok(())
 }
 
-fn export_schema(out_path: &Path) -> Result<()> {
-    let schema = schema_for!(PywrModel);
+fn benchmark(
+    path: &Path,
+    solver: &Solver,
+    data_path: Option<&Path>,
+    num_samples: usize,
+    load: &ModelLoadArgs,
+) -> Result<()> {
+    if num_samples == 0 {
+        bail!("The number of samples must be greater than zero.");
+    }
+
+    let data_path = data_path.or_else(|| path.parent());
+    let schema_v2 = load.load_model(path)?;
+
+    // Results are not written when benchmarking
+    let model = schema_v2
+        .build_model(data_path, None)
+        .with_context(|| format!("Failed to build model: {:?}", path))?;
+
+    match *solver {
+        Solver::Clp => benchmark_model::<ClpSolver>(&model, &ClpSolverSettings::default(), num_samples),
+        #[cfg(feature = "highs")]
+        Solver::Highs => benchmark_model::<HighsSolver>(&model, &HighsSolverSettings::default(), num_samples),
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => benchmark_model::<GurobiSolver>(&model, &GurobiSolverSettings::default(), num_samples),
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => benchmark_model::<OsqpSolver>(&model, &OsqpSolverSettings::default(), num_samples),
+        #[cfg(feature = "cbc")]
+        Solver::Cbc => benchmark_model::<CbcSolver>(&model, &CbcSolverSettings::default(), num_samples),
+        #[allow(unreachable_patterns)]
+        _ => bail!("Solver `{}` is not supported by the benchmark command.", solver),
+    }
+}
+
+fn benchmark_model<S>(model: &Model, settings: &S::Settings, num_samples: usize) -> Result<()>
+where
+    S: pywr_core::solvers::Solver,
+    <S as pywr_core::solvers::Solver>::Settings: SolverSettings,
+{
+    let mut setup_times = Vec::with_capacity(num_samples);
+    let mut run_times = Vec::with_capacity(num_samples);
+
+    for sample in 0..num_samples {
+        info!("Running benchmark sample {} of {}", sample + 1, num_samples);
+
+        let start = Instant::now();
+        let mut state = model
+            .setup::<S>(settings)
+            .with_context(|| format!("Failed to setup benchmark sample {}.", sample + 1))?;
+        setup_times.push(start.elapsed());
+
+        let start = Instant::now();
+        model
+            .run_with_state::<S>(&mut state, settings)
+            .with_context(|| format!("Failed to run benchmark sample {}.", sample + 1))?;
+        run_times.push(start.elapsed());
+    }
+
+    let (num_timesteps, num_scenarios) = model.domain().shape();
+    let mean_run_time = mean_duration(&run_times);
+
+    info!("Benchmark results ({} samples):", num_samples);
+    report_durations("Setup", &setup_times);
+    report_durations("Run", &run_times);
+    info!(
+        "{: <24} | {: <10.5} ts/s",
+        "Mean speed",
+        (num_timesteps * num_scenarios) as f64 / mean_run_time
+    );
+
+    Ok(())
+}
+
+fn mean_duration(durations: &[Duration]) -> f64 {
+    durations.iter().map(|d| d.as_secs_f64()).sum::<f64>() / durations.len() as f64
+}
+
+fn report_durations(name: &str, durations: &[Duration]) {
+    let min = durations.iter().min().copied().unwrap_or_default();
+    let max = durations.iter().max().copied().unwrap_or_default();
+
+    info!(
+        "{: <24} | min {:.5}s | mean {:.5}s | max {:.5}s",
+        name,
+        min.as_secs_f64(),
+        mean_duration(durations),
+        max.as_secs_f64()
+    );
+}
+
+fn estimate(
+    path: &Path,
+    solver: &Solver,
+    data_path: Option<&Path>,
+    num_timesteps: NonZeroUsize,
+    num_scenarios: NonZeroUsize,
+    load: &ModelLoadArgs,
+) -> Result<()> {
+    let data_path = data_path.or_else(|| path.parent());
+    let schema_v2 = load.load_model(path)?;
+
+    // Results are not written when estimating
+    let model = schema_v2
+        .build_model(data_path, None)
+        .with_context(|| format!("Failed to build model: {:?}", path))?;
+
+    let estimate = match *solver {
+        Solver::Clp => model.estimate::<ClpSolver>(&ClpSolverSettings::default(), num_timesteps, num_scenarios),
+        #[cfg(feature = "highs")]
+        Solver::Highs => model.estimate::<HighsSolver>(&HighsSolverSettings::default(), num_timesteps, num_scenarios),
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => {
+            model.estimate::<GurobiSolver>(&GurobiSolverSettings::default(), num_timesteps, num_scenarios)
+        }
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => model.estimate::<OsqpSolver>(&OsqpSolverSettings::default(), num_timesteps, num_scenarios),
+        #[cfg(feature = "cbc")]
+        Solver::Cbc => model.estimate::<CbcSolver>(&CbcSolverSettings::default(), num_timesteps, num_scenarios),
+        #[allow(unreachable_patterns)]
+        _ => bail!("Solver `{}` is not supported by the estimate command.", solver),
+    }
+    .context("Failed to run the model sample.")?;
+
+    info!("Run estimate using solver `{}`:", solver);
+    for line in estimate.to_string().lines() {
+        info!("{}", line);
+    }
+
+    Ok(())
+}
+
+fn inspect(
+    path: &Path,
+    data_path: Option<&Path>,
+    output_path: Option<&Path>,
+    outputs: bool,
+    load: &ModelLoadArgs,
+) -> Result<()> {
+    if !outputs {
+        bail!("Nothing to inspect; use `--outputs` to describe the model's outputs.");
+    }
+
+    let data_path = data_path.or_else(|| path.parent());
+    let schema_v2 = load.load_model(path)?;
+
+    let model = schema_v2
+        .build_model(data_path, output_path)
+        .with_context(|| format!("Failed to build model: {:?}", path))?;
+
+    let descriptions = model
+        .describe_outputs()
+        .context("Failed to describe the model's outputs.")?;
+
+    let inspection = serde_json::json!({ "outputs": descriptions });
+    println!("{}", serde_json::to_string_pretty(&inspection)?);
+
+    Ok(())
+}
+
+fn check(path: &Path, deny_warnings: bool, multi: bool, data_path: Option<&Path>) -> Result<()> {
+    let issues = if multi {
+        let data_path = data_path.or_else(|| path.parent());
+        // Components of an unknown type are reported by the check rather than failing to load
+        let schema = PywrMultiNetworkModel::from_path_preserving_unknown(path)
+            .with_context(|| format!("Failed to load Pywr v2 multi-network model file: {:?}", path))?;
+        schema.validate(data_path)
+    } else {
+        let schema = PywrModel::from_path_preserving_unknown(path)
+            .with_context(|| format!("Failed to load Pywr v2 model file: {:?}", path))?;
+        schema.validate()
+    };
+
+    for issue in &issues {
+        match issue.severity {
+            ValidationSeverity::Error => error!("{}", issue),
+            ValidationSeverity::Warning => warn!("{}", issue),
+        }
+    }
+
+    let num_errors = issues
+        .iter()
+        .filter(|i| i.severity == ValidationSeverity::Error)
+        .count();
+    let num_warnings = issues.len() - num_errors;
+
+    if num_errors > 0 || (deny_warnings && num_warnings > 0) {
+        bail!(
+            "Found {} error(s) and {} warning(s) in model: {:?}",
+            num_errors,
+            num_warnings,
+            path
+        );
+    }
+
+    info!("No errors and {} warning(s) found in model: {:?}", num_warnings, path);
+    Ok(())
+}
+
+fn optimise(
+    path: &Path,
+    solver: &Solver,
+    data_path: Option<&Path>,
+    out_path: &Path,
+    load: &ModelLoadArgs,
+) -> Result<()> {
+    let data_path = data_path.or_else(|| path.parent());
+    let schema_v2 = load.load_model(path)?;
+
+    let Some(optimisation) = &schema_v2.optimisation else {
+        bail!("The model does not define an optimisation: {:?}", path);
+    };
+
+    let solutions = match *solver {
+        Solver::Clp => schema_v2.optimise::<ClpSolver>(data_path, &ClpSolverSettings::default()),
+        #[cfg(feature = "highs")]
+        Solver::Highs => schema_v2.optimise::<HighsSolver>(data_path, &HighsSolverSettings::default()),
+        #[cfg(feature = "gurobi")]
+        Solver::Gurobi => schema_v2.optimise::<GurobiSolver>(data_path, &GurobiSolverSettings::default()),
+        #[cfg(feature = "osqp")]
+        Solver::Osqp => schema_v2.optimise::<OsqpSolver>(data_path, &OsqpSolverSettings::default()),
+        #[cfg(feature = "cbc")]
+        Solver::Cbc => schema_v2.optimise::<CbcSolver>(data_path, &CbcSolverSettings::default()),
+        #[allow(unreachable_patterns)]
+        _ => bail!("Solver `{}` is not supported by the optimise command.", solver),
+    }
+    .context("Failed to optimise the model.")?;
+
+    info!("Optimisation found {} non-dominated solutions.", solutions.len());
+
+    let num_variables = solutions.first().map(|s| s.variables.len()).unwrap_or_default();
+    let mut csv = (0..num_variables)
+        .map(|i| format!("variable_{}", i))
+        .chain(optimisation.objectives.iter().map(|o| o.output.clone()))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+
+    for solution in &solutions {
+        let row = solution
+            .variables
+            .iter()
+            .chain(&solution.objectives)
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(csv, "{}", row)?;
+    }
+
+    std::fs::write(out_path, csv).with_context(|| format!("Failed to write file: {:?}", out_path))?;
+
+    Ok(())
+}
+
+fn export_schema(out_path: &Path, all: bool) -> Result<()> {
+    if all {
+        if !out_path.is_dir() {
+            bail!("Output path must be an existing directory when exporting all schemas");
+        }
+
+        for doc in all_schemas() {
+            write_schema(&doc, &out_path.join(doc.file_name()))?;
+        }
+    } else {
+        write_schema(&model_schema(), out_path)?;
+    }
+
+    Ok(())
+}
+
+fn write_schema(doc: &SchemaDocument, out_path: &Path) -> Result<()> {
+    info!("Writing schema `{}` to: {}", doc.id(), out_path.display());
+
     std::fs::write(
         out_path,
-        serde_json::to_string_pretty(&schema).with_context(|| "Failed serialise Pywr schema".to_string())?,
+        serde_json::to_string_pretty(doc.schema()).with_context(|| "Failed serialise Pywr schema".to_string())?,
     )
     .with_context(|| format!("Failed to write file: {:?}", out_path))?;
 
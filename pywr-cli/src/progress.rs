@@ -0,0 +1,40 @@
+use pywr_core::models::{Progress, ProgressCallback, ProgressControl};
+use std::io::Write;
+
+/// The number of characters in the bar.
+const WIDTH: usize = 40;
+
+/// A progress bar written to stderr.
+///
+/// The bar is redrawn in place on each update, and is finished with a new line once all of the
+/// time-steps have been completed.
+#[derive(Default)]
+pub struct ProgressBar;
+
+impl ProgressCallback for ProgressBar {
+    fn on_progress(&mut self, progress: &Progress) -> ProgressControl {
+        let filled = (progress.fraction() * WIDTH as f64).round() as usize;
+        let remaining = match progress.remaining() {
+            Some(remaining) => format!("{:.0}s remaining", remaining.as_secs_f64()),
+            None => String::new(),
+        };
+
+        let mut stderr = std::io::stderr().lock();
+        // Failing to draw the progress bar should not stop the run
+        let _ = write!(
+            stderr,
+            "\r[{}{}] {:>3.0}% {} {:<16}",
+            "#".repeat(filled),
+            " ".repeat(WIDTH - filled),
+            progress.fraction() * 100.0,
+            progress.date.format("%Y-%m-%d"),
+            remaining
+        );
+        if progress.completed_timesteps >= progress.num_timesteps {
+            let _ = writeln!(stderr);
+        }
+        let _ = stderr.flush();
+
+        ProgressControl::Continue
+    }
+}
@@ -1,10 +1,21 @@
 use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 use tracing_subscriber::{EnvFilter, Registry};
 
-pub fn setup_tracing(debug: bool) -> Result<(), ParseError> {
-    // Layer to output to stdout
-    let stdout_layer = tracing_subscriber::fmt::layer();
+/// Setup the global tracing subscriber.
+///
+/// Logs are written to stdout unless `stderr` is true. The latter is required when stdout is
+/// used for other purposes (e.g. exchanging data with a coupled model).
+pub fn setup_tracing(debug: bool, stderr: bool) -> Result<(), ParseError> {
+    let writer = if stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
+    // Layer to output to stdout (or stderr)
+    let stdout_layer = tracing_subscriber::fmt::layer().with_writer(writer);
 
     let filter_level = if debug { "pywr=debug" } else { "pywr=info" };
 
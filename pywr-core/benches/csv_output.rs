@@ -0,0 +1,119 @@
+/// Benchmarks of writing the results of a model to CSV files.
+///
+/// These benchmarks run the same random models as the `random_models` benchmarks with the
+/// inflow of every node written to a CSV file in each of the supported formats. A run without
+/// any output is included as a baseline, so that the time spent writing the output can be
+/// compared across formats and compression settings.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pywr_core::metric::MetricF64;
+use pywr_core::models::Model;
+use pywr_core::recorders::{CsvCompression, CsvLongFmtOutput, CsvWideFmtOutput, MetricSet, OutputMetric, Recorder};
+use pywr_core::solvers::{ClpSolver, ClpSolverSettings};
+use pywr_core::test_utils::make_random_model;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::path::Path;
+
+/// Model sizes as (number of systems, number of scenarios).
+const MODEL_SIZES: [(usize, usize); 2] = [(10, 10), (100, 10)];
+const DENSITY: usize = 5;
+
+#[derive(Clone, Copy)]
+enum CsvSetup {
+    None,
+    Wide,
+    Long,
+    LongZstd,
+}
+
+impl CsvSetup {
+    const ALL: [CsvSetup; 4] = [Self::None, Self::Wide, Self::Long, Self::LongZstd];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Wide => "wide",
+            Self::Long => "long",
+            Self::LongZstd => "long-zstd",
+        }
+    }
+}
+
+/// Make a random model that writes the inflow of every node using `setup`.
+///
+/// Returns the model and the number of values written each run.
+fn make_model(num_systems: usize, num_scenarios: usize, setup: CsvSetup, dir: &Path) -> (Model, usize) {
+    // ChaCha8 should be consistent across builds and platforms
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut model =
+        make_random_model(num_systems, DENSITY, num_scenarios, &mut rng).expect("Failed to create the model.");
+
+    let metrics: Vec<_> = model
+        .network()
+        .nodes()
+        .iter()
+        .map(|n| OutputMetric::new(n.name(), "inflow", "Node", None, MetricF64::NodeInFlow(n.index())))
+        .collect();
+    let num_values = metrics.len() * num_scenarios * model.domain().time().timesteps().len();
+
+    let metric_set_idx = model
+        .network_mut()
+        .add_metric_set(MetricSet::new("nodes", None, metrics))
+        .expect("Failed to add the metric set.");
+
+    let recorder: Option<Box<dyn Recorder>> = match setup {
+        CsvSetup::None => None,
+        CsvSetup::Wide => Some(Box::new(CsvWideFmtOutput::new(
+            "outputs",
+            dir.join("wide.csv"),
+            metric_set_idx,
+        ))),
+        CsvSetup::Long => Some(Box::new(CsvLongFmtOutput::new(
+            "outputs",
+            dir.join("long.csv"),
+            &[metric_set_idx],
+            None,
+        ))),
+        CsvSetup::LongZstd => Some(Box::new(
+            CsvLongFmtOutput::new("outputs", dir.join("long.csv.zst"), &[metric_set_idx], None)
+                .with_compression(CsvCompression::Zstd(0)),
+        )),
+    };
+
+    if let Some(recorder) = recorder {
+        model
+            .network_mut()
+            .add_recorder(recorder)
+            .expect("Failed to add the recorder.");
+    }
+
+    (model, num_values)
+}
+
+fn bench_csv_output(c: &mut Criterion) {
+    let mut group = c.benchmark_group("csv-output");
+    group.sample_size(10);
+
+    let dir = std::env::temp_dir().join(format!("pywr-bench-csv-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Failed to create the output directory.");
+    let settings = ClpSolverSettings::default();
+
+    for (n_sys, n_sc) in MODEL_SIZES {
+        for setup in CsvSetup::ALL {
+            let (model, num_values) = make_model(n_sys, n_sc, setup, &dir);
+            group.throughput(Throughput::Elements(num_values as u64));
+
+            group.bench_with_input(
+                BenchmarkId::new(setup.name(), format!("{n_sys} * {n_sc}")),
+                &model,
+                |b, model| b.iter(|| model.run::<ClpSolver>(&settings).expect("Failed to run the model.")),
+            );
+        }
+    }
+
+    group.finish();
+    std::fs::remove_dir_all(&dir).expect("Failed to remove the output directory.");
+}
+
+criterion_group!(benches, bench_csv_output);
+criterion_main!(benches);
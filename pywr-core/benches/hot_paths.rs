@@ -0,0 +1,273 @@
+/// Micro-benchmarks of the hot paths in the state, parameter and solver layers.
+///
+/// These benchmarks use the same random models as the `random_models` benchmarks, but
+/// measure the individual operations that are repeated every time-step rather than complete
+/// model runs. This makes it easier to identify which layer is responsible for a change in
+/// overall performance, and to compare the performance of these layers across platforms.
+///
+/// The model sizes are representative of small, medium and large networks. Each benchmark
+/// reports its throughput in terms of the number of items (e.g. nodes or scenarios) processed.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use pywr_core::metric::MetricF64;
+use pywr_core::models::{Model, MultiNetworkTransferIndex};
+use pywr_core::network::RunTimings;
+use pywr_core::node::Node;
+use pywr_core::parameters::SimpleParameterIndex;
+use pywr_core::solvers::{ClpSolver, ClpSolverSettings};
+#[cfg(feature = "ipm-simd")]
+use pywr_core::solvers::{SimdIpmF64Solver, SimdIpmSolverSettings};
+use pywr_core::state::StateBuilder;
+use pywr_core::test_utils::make_random_model;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::time::Duration;
+
+/// Model sizes as (number of systems, density of transfers).
+const MODEL_SIZES: [(usize, usize); 3] = [(10, 2), (100, 5), (1000, 5)];
+const NUM_SCENARIOS: usize = 4;
+
+fn make_model(num_systems: usize, density: usize, num_scenarios: usize) -> Model {
+    // ChaCha8 should be consistent across builds and platforms
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    make_random_model(num_systems, density, num_scenarios, &mut rng).expect("Failed to create the model.")
+}
+
+/// Benchmark reading and writing values in the [`pywr_core::state::State`].
+fn bench_state(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state");
+
+    for (n_sys, density) in MODEL_SIZES {
+        let model = make_model(n_sys, density, 1);
+        let network = model.network();
+        let timesteps = model.domain().time().timesteps();
+        let scenario_indices = model.domain().scenarios().indices();
+
+        let mut network_state = network
+            .setup_network(timesteps, scenario_indices, 0)
+            .expect("Failed to setup the network.");
+        let state = network_state.state(&scenario_indices[0]);
+
+        let metrics: Vec<_> = network
+            .nodes()
+            .iter()
+            .map(|n| MetricF64::NodeInFlow(n.index()))
+            .collect();
+        group.throughput(Throughput::Elements(metrics.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("get-node-flow-metrics", n_sys),
+            &metrics,
+            |b, metrics| {
+                b.iter(|| {
+                    metrics
+                        .iter()
+                        .map(|m| m.get_value(network, state).unwrap())
+                        .sum::<f64>()
+                })
+            },
+        );
+
+        let flow_nodes: Vec<_> = network
+            .nodes()
+            .iter()
+            .filter(|n| !matches!(n, Node::Storage(_)))
+            .map(|n| n.index())
+            .collect();
+        let state = network_state.state_mut(&scenario_indices[0]);
+
+        group.throughput(Throughput::Elements(flow_nodes.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("set-get-node-flows", n_sys),
+            &flow_nodes,
+            |b, flow_nodes| {
+                b.iter(|| {
+                    for (i, idx) in flow_nodes.iter().enumerate() {
+                        let network_state = state.get_mut_network_state();
+                        network_state.set_node_flow(*idx, i as f64).unwrap();
+                        network_state.get_node_in_flow(idx).unwrap();
+                    }
+                })
+            },
+        );
+
+        // The random models use a simple parameter for the inflow of each system
+        let num_parameters = (0..)
+            .take_while(|&i| {
+                state
+                    .get_simple_parameter_values()
+                    .get_simple_parameter_f64(SimpleParameterIndex::new(i))
+                    .is_ok()
+            })
+            .count();
+
+        group.throughput(Throughput::Elements(num_parameters as u64));
+        group.bench_with_input(
+            BenchmarkId::new("set-get-parameter-values", n_sys),
+            &num_parameters,
+            |b, &n| {
+                b.iter(|| {
+                    for i in 0..n {
+                        let idx = SimpleParameterIndex::new(i);
+                        state.set_simple_parameter_value(idx, i as f64).unwrap();
+                        state
+                            .get_simple_parameter_values()
+                            .get_simple_parameter_f64(idx)
+                            .unwrap();
+                    }
+                })
+            },
+        );
+
+        let num_values = network.nodes().len();
+        let mut state = StateBuilder::new(Vec::new(), 0)
+            .with_inter_network_transfers(num_values)
+            .build();
+
+        group.throughput(Throughput::Elements(num_values as u64));
+        group.bench_with_input(
+            BenchmarkId::new("set-get-transfer-values", n_sys),
+            &num_values,
+            |b, &n| {
+                b.iter(|| {
+                    for i in 0..n {
+                        let idx = MultiNetworkTransferIndex(i);
+                        state.set_inter_network_transfer_value(idx, i as f64).unwrap();
+                        state.get_inter_network_transfer_value(idx).unwrap();
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Benchmark the setup of the network state, including the computation of the constant
+/// parameters for every scenario.
+fn bench_parameters(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parameters");
+
+    for (n_sys, density) in MODEL_SIZES {
+        let model = make_model(n_sys, density, NUM_SCENARIOS);
+        let network = model.network();
+        let timesteps = model.domain().time().timesteps();
+        let scenario_indices = model.domain().scenarios().indices();
+
+        group.throughput(Throughput::Elements(scenario_indices.len() as u64));
+        group.bench_function(BenchmarkId::new("setup-network", n_sys), |b| {
+            b.iter(|| network.setup_network(timesteps, scenario_indices, 0).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark updating the solver's arrays (i.e. the edge costs and the row bounds) from the state
+/// of the first time-step. The LP is also solved, but only the time spent updating the arrays is
+/// measured.
+fn bench_solver_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solver-update");
+    let settings = ClpSolverSettings::default();
+
+    for (n_sys, density) in MODEL_SIZES {
+        let model = make_model(n_sys, density, NUM_SCENARIOS);
+
+        group.throughput(Throughput::Elements(NUM_SCENARIOS as u64));
+        group.bench_function(BenchmarkId::new("clp", n_sys), |b| {
+            b.iter_custom(|iters| {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    let mut state = model.setup::<ClpSolver>(&settings).expect("Failed to setup the model.");
+                    let mut timings = RunTimings::default();
+                    model.step(&mut state, None, &mut timings).unwrap();
+                    elapsed += timings.solve.update_objective + timings.solve.update_constraints;
+                }
+                elapsed
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark a single time-step of the model. This includes the computation of the
+/// parameters, updating the solver's arrays, solving the LP and saving the results to the state.
+fn bench_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    let settings = ClpSolverSettings::default();
+
+    for (n_sys, density) in MODEL_SIZES {
+        let model = make_model(n_sys, density, NUM_SCENARIOS);
+
+        group.throughput(Throughput::Elements(NUM_SCENARIOS as u64));
+        group.bench_function(BenchmarkId::new("clp", n_sys), |b| {
+            b.iter_batched(
+                || model.setup::<ClpSolver>(&settings).expect("Failed to setup the model."),
+                |mut state| {
+                    let mut timings = RunTimings::default();
+                    model.step(&mut state, None, &mut timings).unwrap();
+                    state
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark all the time-steps of a small model with many scenarios using the SIMD IPM
+/// solver.
+///
+/// The scenarios are solved in many small chunks, so this mostly measures the cost of updating
+/// the LPs of each chunk, which reuses the work buffers of each thread between chunks and
+/// time-steps.
+#[cfg(feature = "ipm-simd")]
+fn bench_multi_scenario_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multi-scenario-step");
+    let settings = SimdIpmSolverSettings::default();
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .build()
+        .expect("Failed to create the thread pool.");
+
+    for num_scenarios in [64, 256, 1024] {
+        let model = make_model(10, 2, num_scenarios);
+        let num_timesteps = model.domain().time().timesteps().len();
+
+        group.throughput(Throughput::Elements((num_scenarios * num_timesteps) as u64));
+        group.bench_function(BenchmarkId::new("ipm-simd-f64x4", num_scenarios), |b| {
+            b.iter_batched(
+                || {
+                    model
+                        .setup_multi_scenario::<SimdIpmF64Solver<4>>(&settings)
+                        .expect("Failed to setup the model.")
+                },
+                |mut state| {
+                    let mut timings = RunTimings::default();
+                    for _ in 0..num_timesteps {
+                        model
+                            .step_multi_scenario(&mut state, &thread_pool, &mut timings)
+                            .unwrap();
+                    }
+                    state
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+#[cfg(feature = "ipm-simd")]
+criterion_group!(
+    benches,
+    bench_state,
+    bench_parameters,
+    bench_solver_update,
+    bench_step,
+    bench_multi_scenario_step
+);
+#[cfg(not(feature = "ipm-simd"))]
+criterion_group!(benches, bench_state, bench_parameters, bench_solver_update, bench_step);
+criterion_main!(benches);
@@ -286,33 +286,44 @@ impl AggregatedNode {
     }
 
     /// Return constant normalised factor pairs
-    pub fn get_const_norm_factor_pairs(&self, values: &ConstParameterValues) -> Option<Vec<NodeConstFactorPair>> {
+    pub fn get_const_norm_factor_pairs(
+        &self,
+        values: &ConstParameterValues,
+    ) -> Result<Option<Vec<NodeConstFactorPair>>, PywrError> {
         if let Some(factors) = self.get_factors() {
             let pairs = match factors {
                 Factors::Proportion(prop_factors) => {
-                    get_const_norm_proportional_factor_pairs(prop_factors, &self.nodes, values)
+                    get_const_norm_proportional_factor_pairs(self.name(), prop_factors, &self.nodes, values)?
+                }
+                Factors::Ratio(ratio_factors) => {
+                    get_const_norm_ratio_factor_pairs(self.name(), ratio_factors, &self.nodes, values)?
                 }
-                Factors::Ratio(ratio_factors) => get_const_norm_ratio_factor_pairs(ratio_factors, &self.nodes, values),
             };
-            Some(pairs)
+            Ok(Some(pairs))
         } else {
-            None
+            Ok(None)
         }
     }
 
     /// Return normalised factor pairs
     ///
-    pub fn get_norm_factor_pairs(&self, model: &Network, state: &State) -> Option<Vec<NodeFactorPair>> {
+    pub fn get_norm_factor_pairs(
+        &self,
+        model: &Network,
+        state: &State,
+    ) -> Result<Option<Vec<NodeFactorPair>>, PywrError> {
         if let Some(factors) = self.get_factors() {
             let pairs = match factors {
                 Factors::Proportion(prop_factors) => {
-                    get_norm_proportional_factor_pairs(prop_factors, &self.nodes, model, state)
+                    get_norm_proportional_factor_pairs(self.name(), prop_factors, &self.nodes, model, state)?
+                }
+                Factors::Ratio(ratio_factors) => {
+                    get_norm_ratio_factor_pairs(self.name(), ratio_factors, &self.nodes, model, state)?
                 }
-                Factors::Ratio(ratio_factors) => get_norm_ratio_factor_pairs(ratio_factors, &self.nodes, model, state),
             };
-            Some(pairs)
+            Ok(Some(pairs))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -367,52 +378,61 @@ impl AggregatedNode {
     }
 }
 
+/// Check the number of factors is valid for the number of nodes in aggregated node `name`.
+fn check_factor_count(name: &str, num_factors: usize, num_nodes: usize, expected: usize) -> Result<(), PywrError> {
+    if num_factors != expected {
+        return Err(PywrError::AggregatedNodeFactorCountMismatch {
+            name: name.to_string(),
+            factors: num_factors,
+            nodes: num_nodes,
+        });
+    }
+    Ok(())
+}
+
+/// Check a factor of aggregated node `name` is not negative.
+fn check_non_negative_factor(name: &str, value: f64) -> Result<f64, PywrError> {
+    if value < 0.0 {
+        Err(PywrError::AggregatedNodeNegativeFactor(name.to_string()))
+    } else {
+        Ok(value)
+    }
+}
+
 /// Calculate factor pairs for proportional factors.
 ///
 /// There should be one less factor than node indices. The factors correspond to each of the node
 /// indices after the first. Factor pairs relating the first index to each of the other indices are
 /// calculated. This requires the sum of the factors to be greater than 0.0 and less than 1.0.
 fn get_norm_proportional_factor_pairs<'a>(
+    name: &str,
     factors: &[MetricF64],
     nodes: &'a [Vec<NodeIndex>],
     model: &Network,
     state: &State,
-) -> Vec<NodeFactorPair<'a>> {
-    if factors.len() != nodes.len() - 1 {
-        panic!("Found {} proportional factors and {} nodes in aggregated node. The number of proportional factors should equal one less than the number of nodes.", factors.len(), nodes.len());
-    }
+) -> Result<Vec<NodeFactorPair<'a>>, PywrError> {
+    check_factor_count(name, factors.len(), nodes.len(), nodes.len() - 1)?;
 
     // First get the current factor values
     let values: Vec<f64> = factors
         .iter()
-        .map(|f| {
-            let v = f.get_value(model, state)?;
-            if v < 0.0 {
-                Err(PywrError::NegativeFactor)
-            } else {
-                Ok(v)
-            }
-        })
-        .collect::<Result<Vec<_>, PywrError>>()
-        .expect("Failed to get current factor values. Ensure that all factors are not negative.");
+        .map(|f| check_non_negative_factor(name, f.get_value(model, state)?))
+        .collect::<Result<Vec<_>, PywrError>>()?;
 
     let total: f64 = values.iter().sum();
-    if total < 0.0 {
-        panic!("Proportional factors are too small or negative.");
-    }
     if total >= 1.0 {
-        panic!("Proportional factors are too large.")
+        return Err(PywrError::AggregatedNodeProportionalFactorsTooLarge(name.to_string()));
     }
 
     let f0 = 1.0 - total;
     let n0 = nodes[0].as_slice();
 
-    nodes
+    Ok(nodes
         .iter()
         .skip(1)
         .zip(values)
         .map(move |(n1, f1)| NodeFactorPair::new(NodeFactor::new(n0, f0), NodeFactor::new(n1.as_slice(), f1)))
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>())
 }
 
 /// Calculate constant factor pairs for proportional factors.
@@ -422,69 +442,45 @@ fn get_norm_proportional_factor_pairs<'a>(
 /// calculated. This requires the sum of the factors to be greater than 0.0 and less than 1.0. If
 /// any of the factors are not constant, the factor pairs will contain `None` values.
 fn get_const_norm_proportional_factor_pairs<'a>(
+    name: &str,
     factors: &[MetricF64],
     nodes: &'a [Vec<NodeIndex>],
     values: &ConstParameterValues,
-) -> Vec<NodeConstFactorPair<'a>> {
-    if factors.len() != nodes.len() - 1 {
-        panic!("Found {} proportional factors and {} nodes in aggregated node. The number of proportional factors should equal one less than the number of nodes.", factors.len(), nodes.len());
-    }
+) -> Result<Vec<NodeConstFactorPair<'a>>, PywrError> {
+    check_factor_count(name, factors.len(), nodes.len(), nodes.len() - 1)?;
 
     // First get the current factor values, ensuring they are all non-negative
     let values: Vec<Option<f64>> = factors
         .iter()
         .map(|f| {
-            let v = f.try_get_constant_value(values)?;
-            if let Some(v) = v {
-                if v < 0.0 {
-                    Err(PywrError::NegativeFactor)
-                } else {
-                    Ok(Some(v))
-                }
-            } else {
-                Ok(None)
-            }
+            f.try_get_constant_value(values)?
+                .map(|v| check_non_negative_factor(name, v))
+                .transpose()
         })
-        .collect::<Result<Vec<_>, PywrError>>()
-        .expect("Failed to get current factor values. Ensure that all factors are not negative.");
+        .collect::<Result<Vec<_>, PywrError>>()?;
 
     let n0 = nodes[0].as_slice();
 
     // To calculate the factors we require that every factor is available.
-    if values.iter().any(|v| v.is_none()) {
-        // At least one factor is not available; therefore we can not calculate "f0"
-        nodes
-            .iter()
-            .skip(1)
-            .zip(values)
-            .map(move |(n1, f1)| {
-                NodeConstFactorPair::new(NodeConstFactor::new(n0, None), NodeConstFactor::new(n1.as_slice(), f1))
-            })
-            .collect::<Vec<_>>()
-    } else {
-        // All factors are available; therefore we can calculate "f0"
-        let total: f64 = values
-            .iter()
-            .map(|v| v.expect("Factor is `None`; this should be impossible."))
-            .sum();
-        if total < 0.0 {
-            panic!("Proportional factors are too small or negative.");
-        }
-        if total >= 1.0 {
-            panic!("Proportional factors are too large.")
+    let f0 = match values.iter().copied().sum::<Option<f64>>() {
+        Some(total) => {
+            if total >= 1.0 {
+                return Err(PywrError::AggregatedNodeProportionalFactorsTooLarge(name.to_string()));
+            }
+            Some(1.0 - total)
         }
+        // At least one factor is not available; therefore we can not calculate "f0"
+        None => None,
+    };
 
-        let f0 = Some(1.0 - total);
-
-        nodes
-            .iter()
-            .skip(1)
-            .zip(values)
-            .map(move |(n1, f1)| {
-                NodeConstFactorPair::new(NodeConstFactor::new(n0, f0), NodeConstFactor::new(n1.as_slice(), f1))
-            })
-            .collect::<Vec<_>>()
-    }
+    Ok(nodes
+        .iter()
+        .skip(1)
+        .zip(values)
+        .map(move |(n1, f1)| {
+            NodeConstFactorPair::new(NodeConstFactor::new(n0, f0), NodeConstFactor::new(n1.as_slice(), f1))
+        })
+        .collect::<Vec<_>>())
 }
 
 /// Calculate factor pairs for ratio factors.
@@ -493,63 +489,47 @@ fn get_const_norm_proportional_factor_pairs<'a>(
 /// node indices. Factor pairs relating the first index to each of the other indices are calculated.
 /// This requires that the factors are all non-zero.
 fn get_norm_ratio_factor_pairs<'a>(
+    name: &str,
     factors: &[MetricF64],
     nodes: &'a [Vec<NodeIndex>],
     model: &Network,
     state: &State,
-) -> Vec<NodeFactorPair<'a>> {
-    if factors.len() != nodes.len() {
-        panic!("Found {} ratio factors and {} nodes in aggregated node. The number of ratio factors should equal the number of nodes.", factors.len(), nodes.len());
-    }
+) -> Result<Vec<NodeFactorPair<'a>>, PywrError> {
+    check_factor_count(name, factors.len(), nodes.len(), nodes.len())?;
 
     let n0 = nodes[0].as_slice();
-    let f0 = factors[0].get_value(model, state).unwrap();
-    if f0 < 0.0 {
-        panic!("Negative factor is not allowed");
-    }
+    let f0 = check_non_negative_factor(name, factors[0].get_value(model, state)?)?;
 
     nodes
         .iter()
         .zip(factors)
         .skip(1)
         .map(move |(n1, f1)| {
-            let v1 = f1.get_value(model, state)?;
-            if v1 < 0.0 {
-                Err(PywrError::NegativeFactor)
-            } else {
-                Ok(NodeFactorPair::new(
-                    NodeFactor::new(n0, f0),
-                    NodeFactor::new(n1.as_slice(), v1),
-                ))
-            }
+            let v1 = check_non_negative_factor(name, f1.get_value(model, state)?)?;
+            Ok(NodeFactorPair::new(
+                NodeFactor::new(n0, f0),
+                NodeFactor::new(n1.as_slice(), v1),
+            ))
         })
         .collect::<Result<Vec<_>, PywrError>>()
-        .expect("Failed to get current factor values. Ensure that all factors are not negative.")
 }
 
 /// Constant ratio factors using constant values if they are available. If they are not available,
 /// the factors are `None`.
 fn get_const_norm_ratio_factor_pairs<'a>(
+    name: &str,
     factors: &[MetricF64],
     nodes: &'a [Vec<NodeIndex>],
     values: &ConstParameterValues,
-) -> Vec<NodeConstFactorPair<'a>> {
-    if factors.len() != nodes.len() {
-        panic!("Found {} ratio factors and {} nodes in aggregated node. The number of ratio factors should equal the number of nodes.", factors.len(), nodes.len());
-    }
+) -> Result<Vec<NodeConstFactorPair<'a>>, PywrError> {
+    check_factor_count(name, factors.len(), nodes.len(), nodes.len())?;
 
     let n0 = nodes[0].as_slice();
     // Try to convert the factor into a constant
-
     let f0 = factors[0]
-        .try_get_constant_value(values)
-        .unwrap_or_else(|e| panic!("Failed to get constant value for factor: {}", e));
-
-    if let Some(v0) = f0 {
-        if v0 < 0.0 {
-            panic!("Negative factor is not allowed");
-        }
-    }
+        .try_get_constant_value(values)?
+        .map(|v| check_non_negative_factor(name, v))
+        .transpose()?;
 
     nodes
         .iter()
@@ -557,14 +537,9 @@ fn get_const_norm_ratio_factor_pairs<'a>(
         .skip(1)
         .map(move |(n1, f1)| {
             let v1 = f1
-                .try_get_constant_value(values)
-                .unwrap_or_else(|e| panic!("Failed to get constant value for factor: {}", e));
-
-            if let Some(v) = v1 {
-                if v < 0.0 {
-                    return Err(PywrError::NegativeFactor);
-                }
-            }
+                .try_get_constant_value(values)?
+                .map(|v| check_non_negative_factor(name, v))
+                .transpose()?;
 
             Ok(NodeConstFactorPair::new(
                 NodeConstFactor::new(n0, f0),
@@ -572,7 +547,6 @@ fn get_const_norm_ratio_factor_pairs<'a>(
             ))
         })
         .collect::<Result<Vec<_>, PywrError>>()
-        .expect("Failed to get current factor values. Ensure that all factors are not negative.")
 }
 
 #[cfg(test)]
@@ -584,6 +558,8 @@ mod tests {
     use crate::parameters::MonthlyProfileParameter;
     use crate::recorders::AssertionRecorder;
     use crate::test_utils::{default_time_domain, run_all_solvers};
+    use crate::timestep::{TimeDomain, TimestepDuration, Timestepper};
+    use chrono::{Datelike, Days, NaiveDate};
     use ndarray::Array2;
 
     /// Test the factors forcing a simple ratio of flow
@@ -688,6 +664,75 @@ mod tests {
         run_all_solvers(&model, &["cbc", "ipm-simd", "ipm-ocl"], &[], &[]);
     }
 
+    /// Test a maximum flow, given by a parameter, on the combined flow of two links
+    ///
+    /// The model has a single input that diverges to two links and respective output nodes. The
+    /// maximum flow of the aggregated node varies by month, and is greater than the combined
+    /// demand of the output nodes in some months.
+    #[test]
+    fn test_max_flow_profile() {
+        let mut network = Network::default();
+
+        let input_node = network.add_input_node("input", None).unwrap();
+        let mut link_nodes = Vec::new();
+
+        for sub_name in ["0", "1"] {
+            let link_node = network.add_link_node("link", Some(sub_name)).unwrap();
+            let output_node = network.add_output_node("output", Some(sub_name)).unwrap();
+
+            network.connect_nodes(input_node, link_node).unwrap();
+            network.connect_nodes(link_node, output_node).unwrap();
+            link_nodes.push(vec![link_node]);
+
+            let output_node = network.get_mut_node_by_name("output", Some(sub_name)).unwrap();
+            output_node.set_max_flow_constraint(Some(50.0.into())).unwrap();
+            output_node.set_cost(Some((-10.0).into()));
+        }
+
+        let profile = [
+            30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 100.0, 110.0, 120.0, 90.0, 60.0,
+        ];
+        let max_flow_profile = MonthlyProfileParameter::new("max-flow-profile".into(), profile, None);
+        let max_flow_profile_idx = network.add_simple_parameter(Box::new(max_flow_profile)).unwrap();
+
+        let agg_node_idx = network
+            .add_aggregated_node("agg-node", None, &link_nodes, None)
+            .unwrap();
+        network
+            .set_aggregated_node_max_flow("agg-node", None, Some(max_flow_profile_idx.into()))
+            .unwrap();
+
+        // Set-up assertion for the combined flow of the links; this is limited to the combined
+        // demand of 100.0 when the profile is greater than the demand.
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let expected = Array2::from_shape_fn((366, 10), |(i, _)| {
+            let month = (start + Days::new(i as u64)).month0() as usize;
+            profile[month].min(100.0)
+        });
+        let recorder = AssertionRecorder::new(
+            "agg-node-flow",
+            MetricF64::AggregatedNodeOutFlow(agg_node_idx),
+            expected,
+            None,
+            None,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // Run for a whole year so that every month of the profile is used
+        let timestepper = Timestepper::new(
+            start.and_hms_opt(0, 0, 0).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 12, 31)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            TimestepDuration::Days(1),
+        );
+        let time_domain: TimeDomain = timestepper.try_into().unwrap();
+        let model = Model::new(time_domain.into(), network);
+
+        run_all_solvers(&model, &["ipm-simd", "ipm-ocl"], &[], &[]);
+    }
+
     /// Test mutual exclusive flows
     ///
     /// The model has a single input that diverges to two links, only one of which can be active at a time.
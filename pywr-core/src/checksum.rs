@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// A checksum of some data, typically the contents of a file.
+///
+/// The checksum is stored as a lowercase hexadecimal string and displayed with the name of the
+/// algorithm as a prefix (e.g. `sha256:9f86d0...`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "algorithm", content = "value", rename_all = "lowercase")]
+pub enum Checksum {
+    Sha256(String),
+}
+
+impl Checksum {
+    /// Calculate the SHA-256 checksum of `data`.
+    pub fn sha256(data: &[u8]) -> Self {
+        Self::Sha256(format!("{:x}", Sha256::digest(data)))
+    }
+
+    /// Calculate the SHA-256 checksum of the file at `path`.
+    ///
+    /// The file is read in chunks so that large files are not loaded in to memory.
+    pub fn sha256_file(path: &Path) -> Result<Self, std::io::Error> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 8192];
+
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+        }
+
+        Ok(Self::Sha256(format!("{:x}", hasher.finalize())))
+    }
+
+    /// The name of the algorithm used to calculate the checksum.
+    pub fn algorithm(&self) -> &str {
+        match self {
+            Self::Sha256(_) => "sha256",
+        }
+    }
+
+    /// The checksum as a lowercase hexadecimal string.
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Sha256(value) => value,
+        }
+    }
+
+    /// Returns `true` if the file at `path` matches this checksum.
+    pub fn verify_file(&self, path: &Path) -> Result<bool, std::io::Error> {
+        let actual = match self {
+            Self::Sha256(_) => Self::sha256_file(path)?,
+        };
+
+        Ok(&actual == self)
+    }
+}
+
+impl Display for Checksum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm(), self.value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checksum;
+
+    #[test]
+    fn test_sha256() {
+        let checksum = Checksum::sha256(b"abc");
+        assert_eq!(
+            checksum.value(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(checksum.to_string(), format!("sha256:{}", checksum.value()));
+    }
+}
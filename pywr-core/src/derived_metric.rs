@@ -1,14 +1,19 @@
+use crate::aggregated_node::AggregatedNodeIndex;
 use crate::aggregated_storage_node::AggregatedStorageNodeIndex;
 use crate::metric::MetricF64;
 use crate::network::Network;
-use crate::node::NodeIndex;
+use crate::node::{Node, NodeIndex};
 use crate::state::State;
 use crate::timestep::Timestep;
 use crate::utils::hydropower_calculation;
 use crate::virtual_storage::VirtualStorageIndex;
 use crate::PywrError;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::num::NonZeroUsize;
 use std::ops::Deref;
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
@@ -43,6 +48,11 @@ pub struct TurbineData {
     pub efficiency: f64,
     // The water elevation above the turbine
     pub water_elevation: Option<MetricF64>,
+    /// The elevation of the water downstream of the turbine. If this is above the turbine
+    /// elevation then it is used instead of the turbine elevation to calculate the head.
+    pub tailwater_elevation: Option<MetricF64>,
+    /// The head at or below which no power is produced.
+    pub min_head: f64,
     // The water density
     pub water_density: f64,
     /// A factor used to transform the units of flow to be compatible with the hydropower equation
@@ -51,6 +61,197 @@ pub struct TurbineData {
     pub energy_unit_conversion: f64,
 }
 
+/// A constraint, other than a node's flow constraint, with a dual value.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DualConstraint {
+    /// The flow constraint of an aggregated node.
+    AggregatedNode(AggregatedNodeIndex),
+    /// The volume constraint of a virtual storage node.
+    VirtualStorage(VirtualStorageIndex),
+}
+
+/// A statistic of the values of a metric over a period of time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WindowStatistic {
+    /// The sum of the values multiplied by the duration of their time-step in days (e.g. the
+    /// volume from a flow).
+    Total,
+    /// The mean of the values weighted by the duration of their time-step.
+    Mean,
+    Min,
+    Max,
+}
+
+/// A statistic of the values of a metric in the last `window` time-steps, including the
+/// current time-step. Before `window` time-steps have been simulated the statistic is of the
+/// time-steps so far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RollingWindow {
+    name: String,
+    metric: MetricF64,
+    window: NonZeroUsize,
+    statistic: WindowStatistic,
+}
+
+impl RollingWindow {
+    pub fn new(name: &str, metric: MetricF64, window: NonZeroUsize, statistic: WindowStatistic) -> Self {
+        Self {
+            name: name.to_string(),
+            metric,
+            window,
+            statistic,
+        }
+    }
+
+    fn update(
+        &self,
+        idx: DerivedMetricIndex,
+        timestep: &Timestep,
+        network: &Network,
+        state: &mut State,
+    ) -> Result<f64, PywrError> {
+        let value = self.metric.get_value(network, state)?;
+        let history = state.get_mut_derived_metric_history(idx)?;
+        history.push(value, timestep.days(), Some(self.window));
+        Ok(history.statistic(self.statistic))
+    }
+}
+
+/// A statistic of the values of a metric since the start of the current season, including the
+/// current time-step.
+///
+/// The season starts each year on the given month and day (e.g. the 1st April for a water
+/// year). A season starting on the 29th February starts on the 28th February in other years.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeasonToDate {
+    name: String,
+    metric: MetricF64,
+    month: u32,
+    day: u32,
+    statistic: WindowStatistic,
+}
+
+impl SeasonToDate {
+    pub fn new(
+        name: &str,
+        metric: MetricF64,
+        month: u32,
+        day: u32,
+        statistic: WindowStatistic,
+    ) -> Result<Self, PywrError> {
+        // 2000 is a leap year, so this accepts every day that can start a season
+        if NaiveDate::from_ymd_opt(2000, month, day).is_none() {
+            return Err(PywrError::InvalidSeasonStart { month, day });
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            metric,
+            month,
+            day,
+            statistic,
+        })
+    }
+
+    /// The start of the season that `date` is in.
+    fn season_start(&self, date: NaiveDate) -> NaiveDate {
+        let start_in = |year: i32| {
+            NaiveDate::from_ymd_opt(year, self.month, self.day)
+                .or_else(|| NaiveDate::from_ymd_opt(year, self.month, self.day - 1))
+                .expect("Season start is validated on construction.")
+        };
+
+        let start = start_in(date.year());
+        if start <= date {
+            start
+        } else {
+            start_in(date.year() - 1)
+        }
+    }
+
+    fn update(
+        &self,
+        idx: DerivedMetricIndex,
+        timestep: &Timestep,
+        network: &Network,
+        state: &mut State,
+    ) -> Result<f64, PywrError> {
+        let value = self.metric.get_value(network, state)?;
+        let season_start = self.season_start(timestep.date.date());
+
+        let history = state.get_mut_derived_metric_history(idx)?;
+        if history.season_start != Some(season_start) {
+            *history = DerivedMetricHistory {
+                season_start: Some(season_start),
+                ..Default::default()
+            };
+        }
+        history.push(value, timestep.days(), None);
+        Ok(history.statistic(self.statistic))
+    }
+}
+
+/// The values used to update the rolling and season-to-date derived metrics incrementally.
+///
+/// Rolling windows keep only the values in the window. Season-to-date metrics keep only running
+/// totals, so their memory use does not grow with the length of the simulation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DerivedMetricHistory {
+    /// The values and the durations of their time-steps, in days, in a rolling window. The oldest
+    /// value is at the front of the queue.
+    values: VecDeque<(f64, f64)>,
+    /// The sum of the values multiplied by the durations of their time-steps.
+    total: f64,
+    /// The sum of the durations of the time-steps.
+    days: f64,
+    /// The smallest and largest values since the start of the season.
+    min: Option<f64>,
+    max: Option<f64>,
+    /// The start of the season being accumulated.
+    season_start: Option<NaiveDate>,
+}
+
+impl DerivedMetricHistory {
+    /// Add a value, removing the oldest value if there are more than `window` values.
+    fn push(&mut self, value: f64, days: f64, window: Option<NonZeroUsize>) {
+        self.total += value * days;
+        self.days += days;
+
+        match window {
+            Some(window) => {
+                self.values.push_back((value, days));
+                if self.values.len() > window.get() {
+                    if let Some((value, days)) = self.values.pop_front() {
+                        self.total -= value * days;
+                        self.days -= days;
+                    }
+                }
+            }
+            None => {
+                self.min = Some(self.min.map_or(value, |m| m.min(value)));
+                self.max = Some(self.max.map_or(value, |m| m.max(value)));
+            }
+        }
+    }
+
+    fn statistic(&self, statistic: WindowStatistic) -> f64 {
+        let values = || self.values.iter().map(|(value, _)| *value);
+
+        match statistic {
+            WindowStatistic::Total => self.total,
+            WindowStatistic::Mean => {
+                if self.days > 0.0 {
+                    self.total / self.days
+                } else {
+                    0.0
+                }
+            }
+            WindowStatistic::Min => self.min.unwrap_or_else(|| values().fold(f64::INFINITY, f64::min)),
+            WindowStatistic::Max => self.max.unwrap_or_else(|| values().fold(f64::NEG_INFINITY, f64::max)),
+        }
+    }
+}
+
 /// Derived metrics are updated after the model is solved.
 ///
 /// These metrics are "derived" from node states (e.g. volume, flow) and must be updated
@@ -59,23 +260,65 @@ pub struct TurbineData {
 #[derive(Clone, Debug, PartialEq)]
 pub enum DerivedMetric {
     NodeInFlowDeficit(NodeIndex),
+    /// The flow through a node divided by its maximum flow.
+    NodeUtilisation(NodeIndex),
     NodeProportionalVolume(NodeIndex),
     AggregatedNodeProportionalVolume(AggregatedStorageNodeIndex),
     VirtualStorageProportionalVolume(VirtualStorageIndex),
+    /// The smallest volume of several virtual storage nodes (e.g. the remaining volume of a
+    /// licence with more than one limit).
+    VirtualStorageMinVolume(Vec<VirtualStorageIndex>),
+    /// The smallest proportional volume of several virtual storage nodes.
+    VirtualStorageMinProportionalVolume(Vec<VirtualStorageIndex>),
     PowerFromNodeFlow(NodeIndex, TurbineData),
+    /// The total power produced by several turbines (e.g. a cascade of hydropower stations).
+    PowerFromNodeFlows(Vec<(NodeIndex, TurbineData)>),
+    /// The dual value (shadow price) of a node's flow constraint.
+    ///
+    /// This requires the solver to be configured to save dual values (see
+    /// [`crate::solvers::SolverSettings::duals`]).
+    NodeDual(NodeIndex),
+    /// The dual value (shadow price) of an aggregated node or virtual storage constraint.
+    ConstraintDual(DualConstraint),
+    /// A statistic of a metric over a rolling window of time-steps.
+    ///
+    /// This, and [`DerivedMetric::SeasonToDate`], depend on the values of previous time-steps
+    /// and are zero until the end of the first time-step.
+    Rolling(RollingWindow),
+    /// A statistic of a metric since the start of the current season.
+    SeasonToDate(SeasonToDate),
 }
 
 impl DerivedMetric {
     pub fn before(&self, timestep: &Timestep, network: &Network, state: &State) -> Result<Option<f64>, PywrError> {
+        // Dual values are only available once the model has been solved, and rolling and
+        // seasonal statistics include the current time-step's value.
+        if matches!(
+            self,
+            Self::NodeDual(_) | Self::ConstraintDual(_) | Self::Rolling(_) | Self::SeasonToDate(_)
+        ) {
+            return Ok(None);
+        }
+
         // Virtual storage nodes can reset their volume. If this has happened then the
         // proportional volume should also be recalculated.
-        let has_reset = if let Self::VirtualStorageProportionalVolume(idx) = self {
+        let has_reset = |idx: &VirtualStorageIndex| -> Result<bool, PywrError> {
             match state.get_network_state().get_virtual_storage_last_reset(*idx)? {
-                Some(last_reset) => last_reset == timestep,
-                None => false,
+                Some(last_reset) => Ok(last_reset == timestep),
+                None => Ok(false),
             }
-        } else {
-            false
+        };
+
+        let has_reset = match self {
+            Self::VirtualStorageProportionalVolume(idx) => has_reset(idx)?,
+            Self::VirtualStorageMinVolume(indices) | Self::VirtualStorageMinProportionalVolume(indices) => {
+                let mut any_reset = false;
+                for idx in indices {
+                    any_reset |= has_reset(idx)?;
+                }
+                any_reset
+            }
+            _ => false,
         };
 
         // On the first time-step set the initial value
@@ -86,6 +329,25 @@ impl DerivedMetric {
         }
     }
 
+    /// Update the value of the metric after the model has been solved.
+    pub fn after(
+        &self,
+        idx: DerivedMetricIndex,
+        timestep: &Timestep,
+        network: &Network,
+        state: &mut State,
+    ) -> Result<f64, PywrError> {
+        match self {
+            Self::Rolling(rolling) => rolling.update(idx, timestep, network, state),
+            Self::SeasonToDate(season) => season.update(idx, timestep, network, state),
+            _ => self.compute(network, state),
+        }
+    }
+
+    /// Compute the value of the metric from the current state.
+    ///
+    /// Rolling and season-to-date metrics can not be computed from the current state alone and
+    /// return an error; they are updated by [`DerivedMetric::after`].
     pub fn compute(&self, network: &Network, state: &State) -> Result<f64, PywrError> {
         match self {
             Self::NodeProportionalVolume(idx) => {
@@ -100,6 +362,16 @@ impl DerivedMetric {
                     .get_network_state()
                     .get_virtual_storage_proportional_volume(*idx, max_volume)?)
             }
+            Self::VirtualStorageMinVolume(indices) => indices.iter().try_fold(f64::INFINITY, |min, idx| {
+                Ok(min.min(state.get_network_state().get_virtual_storage_volume(idx)?))
+            }),
+            Self::VirtualStorageMinProportionalVolume(indices) => indices.iter().try_fold(f64::INFINITY, |min, idx| {
+                let max_volume = network.get_virtual_storage_node(idx)?.get_max_volume(state)?;
+                let volume = state
+                    .get_network_state()
+                    .get_virtual_storage_proportional_volume(*idx, max_volume)?;
+                Ok(min.min(volume))
+            }),
             Self::AggregatedNodeProportionalVolume(idx) => {
                 let node = network.get_aggregated_storage_node(idx)?;
                 let volume: f64 = node
@@ -122,58 +394,318 @@ impl DerivedMetric {
                 let max_flow = node.get_max_flow(network, state)?;
                 Ok(max_flow - flow)
             }
-            Self::PowerFromNodeFlow(idx, turbine_data) => {
-                let flow = state.get_network_state().get_node_in_flow(idx)?;
-
-                // Calculate the head (the head may be negative)
-                let head = if let Some(water_elevation) = &turbine_data.water_elevation {
-                    water_elevation.get_value(network, state)? - turbine_data.elevation
+            Self::NodeUtilisation(idx) => {
+                let node = network.get_node(idx)?;
+                // Input nodes only have an outflow
+                let flow = match node {
+                    Node::Input(_) => state.get_network_state().get_node_out_flow(idx)?,
+                    _ => state.get_network_state().get_node_in_flow(idx)?,
+                };
+                let max_flow = node.get_max_flow(network, state)?;
+                // A node without any capacity is not utilised
+                if max_flow > 0.0 {
+                    Ok(flow / max_flow)
                 } else {
-                    turbine_data.elevation
+                    Ok(0.0)
                 }
-                .max(0.0);
-
-                Ok(hydropower_calculation(
-                    flow,
-                    head,
-                    turbine_data.efficiency,
-                    turbine_data.flow_unit_conversion,
-                    turbine_data.energy_unit_conversion,
-                    turbine_data.water_density,
-                ))
+            }
+            Self::PowerFromNodeFlow(idx, turbine_data) => power_from_node_flow(idx, turbine_data, network, state),
+            Self::PowerFromNodeFlows(turbines) => turbines.iter().try_fold(0.0, |total, (idx, turbine_data)| {
+                Ok(total + power_from_node_flow(idx, turbine_data, network, state)?)
+            }),
+            Self::NodeDual(idx) => state.get_network_state().get_node_dual(idx),
+            Self::ConstraintDual(DualConstraint::AggregatedNode(idx)) => {
+                state.get_network_state().get_aggregated_node_dual(idx)
+            }
+            Self::ConstraintDual(DualConstraint::VirtualStorage(idx)) => {
+                state.get_network_state().get_virtual_storage_dual(idx)
+            }
+            Self::Rolling(RollingWindow { name, .. }) | Self::SeasonToDate(SeasonToDate { name, .. }) => {
+                Err(PywrError::DerivedMetricRequiresHistory(name.clone()))
             }
         }
     }
 
-    pub fn name<'a>(&self, network: &'a Network) -> Result<&'a str, PywrError> {
+    pub fn name<'a>(&'a self, network: &'a Network) -> Result<&'a str, PywrError> {
         match self {
-            Self::NodeInFlowDeficit(idx) | Self::NodeProportionalVolume(idx) | Self::PowerFromNodeFlow(idx, _) => {
+            Self::NodeInFlowDeficit(idx)
+            | Self::NodeUtilisation(idx)
+            | Self::NodeProportionalVolume(idx)
+            | Self::PowerFromNodeFlow(idx, _)
+            | Self::NodeDual(idx) => network.get_node(idx).map(|n| n.name()),
+            // A group of turbines is named after its first node
+            Self::PowerFromNodeFlows(turbines) => {
+                let (idx, _) = turbines.first().ok_or(PywrError::NoTurbineNodes)?;
                 network.get_node(idx).map(|n| n.name())
             }
             Self::AggregatedNodeProportionalVolume(idx) => network.get_aggregated_storage_node(idx).map(|n| n.name()),
-            Self::VirtualStorageProportionalVolume(idx) => network.get_virtual_storage_node(idx).map(|v| v.name()),
+            Self::VirtualStorageProportionalVolume(idx) | Self::ConstraintDual(DualConstraint::VirtualStorage(idx)) => {
+                network.get_virtual_storage_node(idx).map(|v| v.name())
+            }
+            // A group of virtual storage nodes is named after its first node
+            Self::VirtualStorageMinVolume(indices) | Self::VirtualStorageMinProportionalVolume(indices) => {
+                let idx = indices.first().ok_or(PywrError::NoVirtualStorageNodes)?;
+                network.get_virtual_storage_node(idx).map(|v| v.name())
+            }
+            Self::ConstraintDual(DualConstraint::AggregatedNode(idx)) => {
+                network.get_aggregated_node(idx).map(|n| n.name())
+            }
+            Self::Rolling(RollingWindow { name, .. }) | Self::SeasonToDate(SeasonToDate { name, .. }) => Ok(name),
         }
     }
 
     pub fn sub_name<'a>(&self, network: &'a Network) -> Result<Option<&'a str>, PywrError> {
         match self {
-            Self::NodeInFlowDeficit(idx) | Self::NodeProportionalVolume(idx) | Self::PowerFromNodeFlow(idx, _) => {
-                network.get_node(idx).map(|n| n.sub_name())
-            }
+            Self::NodeInFlowDeficit(idx)
+            | Self::NodeUtilisation(idx)
+            | Self::NodeProportionalVolume(idx)
+            | Self::PowerFromNodeFlow(idx, _)
+            | Self::NodeDual(idx) => network.get_node(idx).map(|n| n.sub_name()),
             Self::AggregatedNodeProportionalVolume(idx) => {
                 network.get_aggregated_storage_node(idx).map(|n| n.sub_name())
             }
-            Self::VirtualStorageProportionalVolume(idx) => network.get_virtual_storage_node(idx).map(|v| v.sub_name()),
+            Self::VirtualStorageProportionalVolume(idx) | Self::ConstraintDual(DualConstraint::VirtualStorage(idx)) => {
+                network.get_virtual_storage_node(idx).map(|v| v.sub_name())
+            }
+            // The sub-names of the nodes in a group differ, so the group does not have a sub-name
+            Self::VirtualStorageMinVolume(_)
+            | Self::VirtualStorageMinProportionalVolume(_)
+            | Self::PowerFromNodeFlows(_) => Ok(None),
+            Self::ConstraintDual(DualConstraint::AggregatedNode(idx)) => {
+                network.get_aggregated_node(idx).map(|n| n.sub_name())
+            }
+            Self::Rolling(_) | Self::SeasonToDate(_) => Ok(None),
         }
     }
 
     pub fn attribute(&self) -> &str {
         match self {
+            Self::Rolling(rolling) => match rolling.statistic {
+                WindowStatistic::Total => "rolling_total",
+                WindowStatistic::Mean => "rolling_mean",
+                WindowStatistic::Min => "rolling_min",
+                WindowStatistic::Max => "rolling_max",
+            },
+            Self::SeasonToDate(season) => match season.statistic {
+                WindowStatistic::Total => "season_to_date_total",
+                WindowStatistic::Mean => "season_to_date_mean",
+                WindowStatistic::Min => "season_to_date_min",
+                WindowStatistic::Max => "season_to_date_max",
+            },
             Self::NodeInFlowDeficit(_) => "in_flow_deficit",
+            Self::NodeUtilisation(_) => "utilisation",
             Self::NodeProportionalVolume(_) => "proportional_volume",
             Self::AggregatedNodeProportionalVolume(_) => "proportional_volume",
             Self::VirtualStorageProportionalVolume(_) => "proportional_volume",
+            Self::VirtualStorageMinVolume(_) => "volume",
+            Self::VirtualStorageMinProportionalVolume(_) => "proportional_volume",
             Self::PowerFromNodeFlow(_, _) => "power_from_flow",
+            Self::PowerFromNodeFlows(_) => "total_power_from_flow",
+            Self::NodeDual(_) | Self::ConstraintDual(_) => "dual",
+        }
+    }
+}
+
+/// The power produced by the flow through a turbine node.
+fn power_from_node_flow(
+    idx: &NodeIndex,
+    turbine_data: &TurbineData,
+    network: &Network,
+    state: &State,
+) -> Result<f64, PywrError> {
+    let flow = state.get_network_state().get_node_in_flow(idx)?;
+
+    // The head is measured from the turbine or the tailwater, whichever is higher
+    let base_elevation = match &turbine_data.tailwater_elevation {
+        Some(tailwater_elevation) => tailwater_elevation
+            .get_value(network, state)?
+            .max(turbine_data.elevation),
+        None => turbine_data.elevation,
+    };
+
+    // Calculate the head (the head may be negative)
+    let head = if let Some(water_elevation) = &turbine_data.water_elevation {
+        water_elevation.get_value(network, state)? - base_elevation
+    } else {
+        base_elevation
+    }
+    .max(0.0);
+
+    if head <= turbine_data.min_head {
+        return Ok(0.0);
+    }
+
+    Ok(hydropower_calculation(
+        flow,
+        head,
+        turbine_data.efficiency,
+        turbine_data.flow_unit_conversion,
+        turbine_data.energy_unit_conversion,
+        turbine_data.water_density,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DerivedMetric, RollingWindow, SeasonToDate, WindowStatistic};
+    use crate::metric::MetricF64;
+    use crate::recorders::AssertionFnRecorder;
+    use crate::scenario::ScenarioIndex;
+    #[cfg(feature = "cbc")]
+    use crate::solvers::{CbcSolver, CbcSolverSettingsBuilder};
+    use crate::solvers::{ClpSolver, ClpSolverSettingsBuilder};
+    #[cfg(feature = "highs")]
+    use crate::solvers::{HighsSolver, HighsSolverSettingsBuilder};
+    use crate::test_utils::{run_all_solvers, simple_model};
+    use crate::timestep::{Timestep, TimestepDuration, Timestepper};
+    use chrono::NaiveDate;
+    use std::num::NonZeroUsize;
+
+    /// The inflow of the input node in the simple model.
+    fn inflow(index: usize) -> f64 {
+        (1.0 + index as f64).min(12.0)
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let mut model = simple_model(1, None);
+        let network = model.network_mut();
+        let input_idx = network.get_node_index_by_name("input", None).unwrap();
+        let window = NonZeroUsize::new(3).unwrap();
+
+        let mean = RollingWindow::new("mean", MetricF64::NodeOutFlow(input_idx), window, WindowStatistic::Mean);
+        let dm_idx = network.add_derived_metric(DerivedMetric::Rolling(mean));
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| {
+            let start = ts.index.saturating_sub(2);
+            (start..=ts.index).map(inflow).sum::<f64>() / (ts.index - start + 1) as f64
+        };
+        let recorder = AssertionFnRecorder::new("rolling-mean", MetricF64::DerivedMetric(dm_idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let min = RollingWindow::new("min", MetricF64::NodeOutFlow(input_idx), window, WindowStatistic::Min);
+        let dm_idx = network.add_derived_metric(DerivedMetric::Rolling(min));
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| inflow(ts.index.saturating_sub(2));
+        let recorder = AssertionFnRecorder::new("rolling-min", MetricF64::DerivedMetric(dm_idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        run_all_solvers(&model, &[], &[], &[]);
+    }
+
+    #[test]
+    fn test_season_to_date() {
+        let mut model = simple_model(1, None);
+        let network = model.network_mut();
+        let input_idx = network.get_node_index_by_name("input", None).unwrap();
+
+        // The season starts on the 10th January; the 10th time-step of the model
+        let total = SeasonToDate::new(
+            "total",
+            MetricF64::NodeOutFlow(input_idx),
+            1,
+            10,
+            WindowStatistic::Total,
+        )
+        .unwrap();
+        let dm_idx = network.add_derived_metric(DerivedMetric::SeasonToDate(total));
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| {
+            let start = if ts.index < 9 { 0 } else { 9 };
+            (start..=ts.index).map(inflow).sum::<f64>()
+        };
+        let recorder = AssertionFnRecorder::new("season-total", MetricF64::DerivedMetric(dm_idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        run_all_solvers(&model, &[], &[], &[]);
+    }
+
+    #[test]
+    fn test_season_start() {
+        let season = SeasonToDate::new("total", 0.0.into(), 2, 29, WindowStatistic::Total).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(season.season_start(date(2020, 3, 1)), date(2020, 2, 29));
+        assert_eq!(season.season_start(date(2021, 2, 28)), date(2021, 2, 28));
+        assert_eq!(season.season_start(date(2021, 2, 27)), date(2020, 2, 29));
+
+        assert!(SeasonToDate::new("total", 0.0.into(), 2, 30, WindowStatistic::Total).is_err());
+        assert!(SeasonToDate::new("total", 0.0.into(), 13, 1, WindowStatistic::Total).is_err());
+    }
+
+    #[test]
+    fn test_node_utilisation() {
+        let mut model = simple_model(1, None);
+        let network = model.network_mut();
+
+        // The inflow is `1 + ts.index` and the demand is 12.0
+        let input_idx = network.get_node_index_by_name("input", None).unwrap();
+        let dm_idx = network.add_derived_metric(DerivedMetric::NodeUtilisation(input_idx));
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| (1.0 + ts.index as f64).min(12.0) / (1.0 + ts.index as f64);
+        let recorder = AssertionFnRecorder::new(
+            "input-utilisation",
+            MetricF64::DerivedMetric(dm_idx),
+            expected,
+            None,
+            None,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let output_idx = network.get_node_index_by_name("output", None).unwrap();
+        let dm_idx = network.add_derived_metric(DerivedMetric::NodeUtilisation(output_idx));
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| (1.0 + ts.index as f64).min(12.0) / 12.0;
+        let recorder = AssertionFnRecorder::new(
+            "output-utilisation",
+            MetricF64::DerivedMetric(dm_idx),
+            expected,
+            None,
+            None,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        run_all_solvers(&model, &[], &[], &[]);
+    }
+
+    #[test]
+    fn test_node_dual() {
+        // Only simulate the first 10 days where the inflow (`1 + ts.index`) is less than the demand
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2020, 1, 10)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let mut model = simple_model(1, Some(Timestepper::new(start, end, TimestepDuration::Days(1))));
+        let network = model.network_mut();
+
+        // The input's constraint is binding; an extra unit of inflow reduces the cost by the
+        // demand's cost of -10.0
+        let input_idx = network.get_node_index_by_name("input", None).unwrap();
+        let dm_idx = network.add_derived_metric(DerivedMetric::NodeDual(input_idx));
+        let expected = |_ts: &Timestep, _si: &ScenarioIndex| -10.0;
+        let recorder = AssertionFnRecorder::new("input-dual", MetricF64::DerivedMetric(dm_idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // The demand is not met, so there is no value in increasing it
+        let output_idx = network.get_node_index_by_name("output", None).unwrap();
+        let dm_idx = network.add_derived_metric(DerivedMetric::NodeDual(output_idx));
+        let expected = |_ts: &Timestep, _si: &ScenarioIndex| 0.0;
+        let recorder = AssertionFnRecorder::new("output-dual", MetricF64::DerivedMetric(dm_idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // The dual values are saved by each of the LP solvers that support them
+        let settings = ClpSolverSettingsBuilder::default().duals().build();
+        model.run::<ClpSolver>(&settings).unwrap();
+
+        #[cfg(feature = "highs")]
+        {
+            let settings = HighsSolverSettingsBuilder::default().duals().build();
+            model.run::<HighsSolver>(&settings).unwrap();
+        }
+
+        #[cfg(feature = "cbc")]
+        {
+            let settings = CbcSolverSettingsBuilder::default().duals().build();
+            model.run::<CbcSolver>(&settings).unwrap();
         }
     }
 }
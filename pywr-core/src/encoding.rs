@@ -0,0 +1,69 @@
+//! The binary encoding of the files written by Pywr.
+//!
+//! Binary files (e.g. [`crate::snapshot::StateSnapshot`] and [`crate::solvers::WarmStart`]) are
+//! encoded with [`bincode`] using a fixed configuration: values are little-endian and integers,
+//! including `usize`, are encoded with a fixed width (i.e. a `usize` is always written as a
+//! 64-bit integer). The encoding is therefore independent of the endianness and pointer width of
+//! the target, and files written on one platform can be read on any other. Decoding a `usize`
+//! that does not fit in the target's pointer width is an error.
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_little_endian()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+}
+
+/// Encode `value` in to bytes.
+pub fn serialize<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, bincode::Error> {
+    options().serialize(value)
+}
+
+/// Decode a value from bytes.
+pub fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, bincode::Error> {
+    options().deserialize(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deserialize, serialize};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Example {
+        index: usize,
+        values: Vec<f64>,
+    }
+
+    /// The encoding of `Example { index: 3, values: vec![1.5] }`.
+    const EXPECTED: [u8; 24] = [
+        3, 0, 0, 0, 0, 0, 0, 0, // index as a little-endian u64
+        1, 0, 0, 0, 0, 0, 0, 0, // length of values as a little-endian u64
+        0, 0, 0, 0, 0, 0, 0xf8, 0x3f, // 1.5 as a little-endian f64
+    ];
+
+    #[test]
+    fn test_encoding_is_little_endian_and_64_bit() {
+        let value = Example {
+            index: 3,
+            values: vec![1.5],
+        };
+
+        assert_eq!(serialize(&value).unwrap(), EXPECTED);
+        assert_eq!(deserialize::<Example>(&EXPECTED).unwrap(), value);
+    }
+
+    #[test]
+    fn test_matches_legacy_encoding() {
+        // Files written before the encoding was made explicit used bincode's default functions.
+        let value = Example {
+            index: usize::MAX >> 1,
+            values: vec![f64::MIN, 0.0, f64::MAX],
+        };
+
+        assert_eq!(serialize(&value).unwrap(), bincode::serialize(&value).unwrap());
+    }
+}
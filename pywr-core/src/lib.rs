@@ -6,35 +6,45 @@
 extern crate core;
 
 use crate::derived_metric::DerivedMetricIndex;
-use crate::models::MultiNetworkTransferIndex;
+use crate::models::{ExternalInputIndex, MultiNetworkTransferIndex};
 use crate::node::NodeIndex;
 use crate::parameters::{
     ConstParameterIndex, GeneralParameterIndex, InterpolationError, ParameterIndex, SimpleParameterIndex,
 };
-use crate::recorders::{AggregationError, MetricSetIndex, RecorderIndex};
+use crate::recorders::{AggregationError, BufferError, FileManifest, MetricSetIndex, RecorderIndex};
+use crate::snapshot::SnapshotError;
 use crate::state::MultiValue;
+use crate::state_override::StateOverrideIndex;
 use crate::virtual_storage::VirtualStorageIndex;
+use chrono::NaiveDateTime;
 #[cfg(feature = "pyo3")]
 use pyo3::{
     create_exception,
     exceptions::{PyException, PyRuntimeError},
     PyErr,
 };
+use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 pub mod aggregated_node;
 mod aggregated_storage_node;
+pub mod checksum;
 pub mod derived_metric;
 pub mod edge;
+pub mod encoding;
 pub mod metric;
 pub mod models;
 pub mod network;
 pub mod node;
+pub mod optimise;
 pub mod parameters;
 pub mod recorders;
 pub mod scenario;
+pub mod snapshot;
 pub mod solvers;
 pub mod state;
+pub mod state_override;
 pub mod test_utils;
 pub mod timestep;
 pub mod utils;
@@ -48,6 +58,8 @@ pub enum PywrError {
     NodeConnectionAlreadyExists,
     #[error("node index not found")]
     NodeIndexNotFound,
+    #[error("dual value not available; dual values must be enabled in the solver settings")]
+    DualValueNotAvailable,
     #[error("node with name {0} not found")]
     NodeNotFound(String),
     #[error("edge index not found")]
@@ -84,6 +96,37 @@ pub enum PywrError {
     InterNetworkParameterStateNotInitialised,
     #[error("inter-network parameter index {0} not found")]
     MultiNetworkTransferIndexNotFound(MultiNetworkTransferIndex),
+    #[error("invalid loss for inter-network transfer: {0}. It must be between 0 and 1.")]
+    InvalidMultiNetworkTransferLoss(f64),
+    #[error("invalid damping for iterating inter-network transfers: {0}. It must be greater than 0 and at most 1.")]
+    InvalidMultiNetworkIterationDamping(f64),
+    #[error("invalid domain for network `{network}`: {reason}")]
+    InvalidNetworkDomain { network: String, reason: String },
+    #[error("external input index {0} not found")]
+    ExternalInputIndexNotFound(ExternalInputIndex),
+    #[error("external input `{0}` not found")]
+    ExternalInputNotFound(String),
+    #[error("external input name `{0}` already exists")]
+    ExternalInputNameAlreadyExists(String),
+    #[error("state override index {0} not found")]
+    StateOverrideIndexNotFound(StateOverrideIndex),
+    #[error("state override `{0}` not found")]
+    StateOverrideNotFound(String),
+    #[error("state override name `{0}` already exists")]
+    StateOverrideNameAlreadyExists(String),
+    #[error("state override `{0}` must target the volume of a storage node or the flow of a link node")]
+    InvalidStateOverrideTarget(String),
+    #[error("state override `{name}` flow ({value}) must not be negative")]
+    StateOverrideNegativeFlow { name: String, value: f64 },
+    #[error("state override `{name}` value ({value}) is outside the volume range ({min_volume} to {max_volume})")]
+    StateOverrideOutOfBounds {
+        name: String,
+        value: f64,
+        min_volume: f64,
+        max_volume: f64,
+    },
+    #[error("co-simulation error: {0}")]
+    CoSimulation(String),
     #[error("parameter {0} not found")]
     ParameterNotFound(String),
     #[error("metric set index {0} not found")]
@@ -96,10 +139,16 @@ pub enum PywrError {
     RecorderNotFound,
     #[error("derived metric not found")]
     DerivedMetricNotFound,
+    #[error("no virtual storage nodes are defined")]
+    NoVirtualStorageNodes,
+    #[error("no turbine nodes are defined")]
+    NoTurbineNodes,
     #[error("derived metric index {0} not found")]
     DerivedMetricIndexNotFound(DerivedMetricIndex),
     #[error("node name `{0}` already exists")]
     NodeNameAlreadyExists(String),
+    #[error("parameter `{0}` can not be replaced by a parameter with a different name or type")]
+    ParameterReplacementMismatch(String),
     #[error("parameter name `{0}` already exists")]
     ParameterNameAlreadyExists(String),
     #[error("index parameter name `{0}` already exists at index {1}")]
@@ -122,20 +171,60 @@ pub enum PywrError {
     EndOfTimesteps,
     #[error("can not add virtual storage node to a storage node")]
     NoVirtualStorageOnStorageNode,
+    #[error("storage node `{node}` is not supported on virtual storage node `{virtual_storage}`")]
+    StorageNodeOnVirtualStorage { virtual_storage: String, node: String },
+    #[error(
+        "mass-balance error detected at `{name}`; volume ({volume}) is smaller than the minimum volume ({min_volume})"
+    )]
+    VolumeBelowMinimum { name: String, volume: f64, min_volume: f64 },
+    #[error(
+        "mass-balance error detected at `{name}`; volume ({volume}) is greater than the maximum volume ({max_volume})"
+    )]
+    VolumeAboveMaximum { name: String, volume: f64, max_volume: f64 },
     #[error("timestep index out of range")]
     TimestepIndexOutOfRange,
     #[error("solver not initialised")]
     SolverNotSetup,
     #[error("no edges defined")]
     NoEdgesDefined,
+    #[error("node `{0}` has no incoming edges")]
+    NodeHasNoIncomingEdges(String),
+    #[error("node `{0}` has no outgoing edges")]
+    NodeHasNoOutgoingEdges(String),
+    #[error("an edge from node `{0}` is not one of its outgoing edges")]
+    EdgeNotOutgoingFromNode(String),
+    #[error("the mass-balance constraint of node `{0}` contains no columns")]
+    MassBalanceRowHasNoColumns(String),
+    #[error("a non-finite factor was added to a row of the LP")]
+    NonFiniteRowFactor,
+    #[error("binary constraints on node `{0}` with a non-zero lower bound are not supported")]
+    BinaryNodeNonZeroLowerBound(String),
+    #[error("aggregated node `{0}` has no factors defined")]
+    AggregatedNodeFactorsNotDefined(String),
+    #[error("virtual storage node `{0}` contains a non-finite factor")]
+    VirtualStorageNonFiniteFactor(String),
     #[error("Python error: {0}")]
     PythonError(String),
     #[error("Unrecognised metric")]
     UnrecognisedMetric,
     #[error("Unrecognised solver")]
     UnrecognisedSolver,
+    #[error("failed to select the OpenCL device: {0}")]
+    OpenClDeviceNotFound(String),
+    #[error("OpenCL error: {0}")]
+    OpenCl(String),
+    #[error("failed to set up the interior point method: {0}")]
+    IpmSetup(String),
+    #[error("the interior point method failed to converge {count} of {total} LPs")]
+    IpmNotConverged { count: usize, total: usize },
+    #[error("edge flows can not be reduced on the device: {0}")]
+    DeviceReductionUnsupported(String),
+    #[error("the state is of time-step {snapshot}, which is after time-step {timestep}")]
+    SnapshotAfterTimestep { snapshot: usize, timestep: usize },
     #[error("Solve failed")]
     SolveFailed,
+    #[error("the solve was stopped because the scenario reached its time limit")]
+    SolveTimeLimitReached,
     #[error("atleast one parameter is required")]
     AtleastOneParameterRequired,
     #[error("scenario state not found")]
@@ -144,8 +233,19 @@ pub enum PywrError {
     ScenarioNotFound(String),
     #[error("scenario group index not found: {0}")]
     ScenarioGroupIndexNotFound(usize),
+    #[error("invalid subset of scenario group `{group}`: {reason}")]
+    InvalidScenarioSubset { group: String, reason: String },
     #[error("clp error")]
     ClpError(#[from] solvers::ClpError),
+    #[cfg(feature = "highs")]
+    #[error("highs error: {0}")]
+    HighsError(#[from] solvers::HighsError),
+    #[cfg(feature = "gurobi")]
+    #[error("gurobi error: {0}")]
+    GurobiError(#[from] solvers::GurobiError),
+    #[cfg(feature = "osqp")]
+    #[error("osqp error: {0}")]
+    OsqpError(#[from] solvers::OsqpError),
     #[error("metric not defined")]
     MetricNotDefinedForNode,
     #[error("invalid metric type: {0}")]
@@ -154,10 +254,47 @@ pub enum PywrError {
     InvalidMetricValue(String),
     #[error("recorder not initialised")]
     RecorderNotInitialised,
+    #[error("recorder `{0}` has no internal state when one was expected")]
+    RecorderInternalStateNotFound(String),
+    #[error("internal state of recorder `{0}` is not of the expected type")]
+    RecorderInternalStateDowncastFailed(String),
     #[error("recorder does not supported aggregation")]
     RecorderDoesNotSupportAggregation,
+    #[error("recorder does not support data frames")]
+    RecorderDoesNotSupportDataFrame,
+    #[error("recorder does not support results frames")]
+    RecorderDoesNotSupportResultsFrame,
+    #[error("the LP is infeasible in {0}")]
+    Infeasible(Box<solvers::InfeasibilityReport>),
+    #[error("the diagnosis of an infeasible time-step failed with Clp status {0}")]
+    InfeasibilityDiagnosisFailed(i32),
+    #[error("the scenario statistics of metric set `{metric_set}` can not be aggregated by recorder `{recorder}`")]
+    ScenarioStatisticsNotAggregated { metric_set: String, recorder: String },
+    #[error("the scenario statistics of metric set `{metric_set}` are not supported by recorder `{recorder}`")]
+    ScenarioStatisticsNotSupported { metric_set: String, recorder: String },
+    #[error("the metric sets of recorder `{0}` must either all have scenario statistics or none of them")]
+    MixedScenarioStatistics(String),
+    #[error("data frame error: {0}")]
+    DataFrameError(String),
+    #[error("arrow error: {0}")]
+    ArrowError(String),
+    #[error("scenario {scenario_index} of recorder `{recorder}` saved {found} periods but the first scenario saved {expected}")]
+    ResultsPeriodsMismatch {
+        recorder: String,
+        scenario_index: usize,
+        expected: usize,
+        found: usize,
+    },
     #[error("hdf5 error: {0}")]
     HDF5Error(#[from] hdf5_metno::Error),
+    #[error("baseline dataset not found: {0}")]
+    BaselineDatasetNotFound(String),
+    #[error("baseline dataset {path} has shape {found:?} but {expected:?} was expected")]
+    BaselineShapeMismatch {
+        path: String,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
     #[error("could not create unicode variable name from: {0}")]
     HDF5VarLenUnicode(String),
     #[error("csv error: {0}")]
@@ -174,8 +311,23 @@ pub enum PywrError {
     DataOutOfRange,
     #[error("internal parameter error: {0}")]
     InternalParameterError(String),
+    #[error("NaN value computed in parameter `{name}`{backtrace}")]
+    ParameterNaNValue {
+        name: String,
+        backtrace: metric::MetricBacktrace,
+    },
+    #[error("parameter `{0}` has no internal state when one was expected")]
+    ParameterInternalStateNotFound(String),
+    #[error("internal state of parameter `{0}` is not of the expected type")]
+    ParameterInternalStateDowncastFailed(String),
+    #[error("variable config of parameter `{0}` is not of the expected type")]
+    ParameterVariableConfigDowncastFailed(String),
+    #[error("failed to build the thread pool: {0}")]
+    ThreadPoolBuildError(String),
     #[error("parameter type does is not a valid variable")]
     ParameterTypeNotVariable,
+    #[error("variable value of parameter `{0}` is NaN")]
+    ParameterVariableValueNaN(String),
     #[error("parameter variable is not active")]
     ParameterVariableNotActive,
     #[error("incorrect number of values for parameter variable")]
@@ -200,14 +352,92 @@ pub enum PywrError {
     TimestepRangeGenerationError(String),
     #[error("Could not create timesteps for frequency '{0}'")]
     TimestepGenerationError(String),
-    #[error("Pywr does not currently support timesteps of varying duration")]
-    TimestepDurationMismatch,
+    #[error("the dates of the time-steps must be strictly increasing and contain at least two dates")]
+    InvalidTimestepDates,
+    #[error(
+        "a diurnal profile can not have {0} values; the number of values must divide a day into whole milliseconds"
+    )]
+    InvalidDiurnalProfile(usize),
+    #[error("the time-step starting {date} does not align with the intervals of diurnal profile `{name}`")]
+    DiurnalProfileResolutionMismatch { name: String, date: NaiveDateTime },
+    #[error("excluded period from {0} to {1} ends before it starts")]
+    InvalidExcludedPeriod(NaiveDateTime, NaiveDateTime),
+    #[error("no time-steps remain in the time domain")]
+    NoTimesteps,
+    #[error("derived metric `{0}` depends on previous time-steps and can not be computed from the current state")]
+    DerivedMetricRequiresHistory(String),
+    #[error("invalid season start; month {month}, day {day}")]
+    InvalidSeasonStart { month: u32, day: u32 },
+    #[error("invalid random distribution: {0}")]
+    InvalidRandomDistribution(String),
+    #[error("scenario group `{0}` already exists")]
+    ScenarioGroupAlreadyExists(String),
     #[error("aggregation error: {0}")]
     Aggregation(#[from] AggregationError),
+    #[error("a value spans multiple aggregation periods, which is not supported")]
+    ValueSpansMultipleAggregationPeriods,
+    #[error("the aggregator state does not match the structure of the aggregator")]
+    AggregatorStateMismatch,
+    #[error("aggregation state not found for metric set `{0}`")]
+    MetricSetAggregationStateNotFound(String),
+    #[error("result buffer error: {0}")]
+    ResultBuffer(#[from] BufferError),
+    #[error("failed to add file {0} to the manifest: {1}")]
+    ManifestError(PathBuf, String),
+    #[error("failed to write the run manifest {0}: {1}")]
+    RunManifestError(PathBuf, String),
+    #[error("failed to write the solve trace {0}: {1}")]
+    SolveTraceError(PathBuf, String),
+    #[error("failed to export the LP to {0}: {1}")]
+    LpExportError(PathBuf, String),
+    #[error("warm start error: {0}")]
+    WarmStart(#[from] solvers::WarmStartError),
+    #[error("solver `{0}` does not support warm starting")]
+    WarmStartNotSupported(String),
+    #[error("snapshot error: {0}")]
+    Snapshot(#[from] SnapshotError),
     #[error("cannot simplify metric")]
     CannotSimplifyMetric,
-    #[error("Negative factor is not allowed")]
-    NegativeFactor,
+    #[error("aggregated node `{0}` has a negative factor; negative factors are not allowed")]
+    AggregatedNodeNegativeFactor(String),
+    #[error("proportional factors of aggregated node `{0}` must sum to less than 1.0")]
+    AggregatedNodeProportionalFactorsTooLarge(String),
+    #[error("aggregated node `{name}` has {factors} factors which is not valid for its {nodes} nodes")]
+    AggregatedNodeFactorCountMismatch { name: String, factors: usize, nodes: usize },
+    #[error("invalid parameter bounds: minimum ({min}) is greater than maximum ({max})")]
+    InvalidParameterBounds { min: f64, max: f64 },
+    #[error("invalid variable bounds ({lower}, {upper}); the bounds must be finite and the lower bound not greater than the upper bound")]
+    InvalidVariableBounds { lower: f64, upper: f64 },
+    #[error("failed to solve the radial basis function system for the interpolation weights; the points may have duplicate days")]
+    RbfSystemNotSolvable,
+    #[error("invalid solver weight ({0}); the weight must be finite and greater than zero")]
+    InvalidSolverWeight(f64),
+    #[error("invalid minimum on flow ({0}); the flow must be finite and not negative")]
+    InvalidMinOnFlow(f64),
+    #[error("invalid model generator settings: {0}")]
+    InvalidModelGeneratorSettings(String),
+    #[error("error reading chunk of timeseries data: {0}")]
+    TimeseriesChunkError(String),
+    #[error("the whole-horizon run mode does not support {0}")]
+    WholeHorizonNotSupported(String),
+    #[error("the whole-horizon linear program could not be solved (status {0})")]
+    WholeHorizonSolveFailed(i32),
+    #[error("run cancelled after {completed_timesteps} of {num_timesteps} time-steps")]
+    RunCancelled {
+        completed_timesteps: usize,
+        num_timesteps: usize,
+        /// The files written by the recorders for the completed time-steps.
+        manifest: FileManifest,
+    },
+    #[error("scenario {scenario_index} exceeded its time limit of {time_limit:?} after {completed_timesteps} of {num_timesteps} time-steps")]
+    RunTimedOut {
+        time_limit: Duration,
+        scenario_index: usize,
+        completed_timesteps: usize,
+        num_timesteps: usize,
+        /// The files written by the recorders for the completed time-steps.
+        manifest: FileManifest,
+    },
 }
 
 // Python errors
@@ -2,13 +2,16 @@ use crate::aggregated_node::AggregatedNodeIndex;
 use crate::aggregated_storage_node::AggregatedStorageNodeIndex;
 use crate::derived_metric::DerivedMetricIndex;
 use crate::edge::EdgeIndex;
-use crate::models::MultiNetworkTransferIndex;
+use crate::models::{ExternalInputIndex, MultiNetworkTransferIndex};
 use crate::network::Network;
 use crate::node::NodeIndex;
 use crate::parameters::{ConstParameterIndex, GeneralParameterIndex, ParameterIndex, SimpleParameterIndex};
 use crate::state::{ConstParameterValues, MultiValue, SimpleParameterValues, State};
+use crate::state_override::StateOverrideIndex;
 use crate::virtual_storage::VirtualStorageIndex;
 use crate::PywrError;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ConstantMetricF64 {
@@ -35,6 +38,26 @@ impl ConstantMetricF64 {
             _ => false,
         }
     }
+
+    fn describe(&self, network: &Network) -> Result<String, PywrError> {
+        let description = match self {
+            ConstantMetricF64::ParameterValue(idx) => {
+                format!("value of parameter `{}`", network.get_parameter((*idx).into())?.name())
+            }
+            ConstantMetricF64::IndexParameterValue(idx) => {
+                format!(
+                    "value of index parameter `{}`",
+                    network.get_index_parameter((*idx).into())?.name()
+                )
+            }
+            ConstantMetricF64::MultiParameterValue((idx, key)) => format!(
+                "value `{key}` of parameter `{}`",
+                network.get_multi_valued_parameter(&(*idx).into())?.name()
+            ),
+            ConstantMetricF64::Constant(_) => "constant".to_string(),
+        };
+        Ok(description)
+    }
 }
 #[derive(Clone, Debug, PartialEq)]
 pub enum SimpleMetricF64 {
@@ -74,6 +97,26 @@ impl SimpleMetricF64 {
             _ => false,
         }
     }
+
+    fn describe(&self, network: &Network) -> Result<String, PywrError> {
+        let description = match self {
+            SimpleMetricF64::ParameterValue(idx) => {
+                format!("value of parameter `{}`", network.get_parameter((*idx).into())?.name())
+            }
+            SimpleMetricF64::IndexParameterValue(idx) => {
+                format!(
+                    "value of index parameter `{}`",
+                    network.get_index_parameter((*idx).into())?.name()
+                )
+            }
+            SimpleMetricF64::MultiParameterValue((idx, key)) => format!(
+                "value `{key}` of parameter `{}`",
+                network.get_multi_valued_parameter(&(*idx).into())?.name()
+            ),
+            SimpleMetricF64::Constant(c) => c.describe(network)?,
+        };
+        Ok(description)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -95,6 +138,8 @@ pub enum MetricF64 {
     // TODO implement other MultiNodeXXX variants
     DerivedMetric(DerivedMetricIndex),
     InterNetworkTransfer(MultiNetworkTransferIndex),
+    ExternalInput(ExternalInputIndex),
+    StateOverrideResidual(StateOverrideIndex),
     Simple(SimpleMetricF64),
 }
 
@@ -156,6 +201,8 @@ impl MetricF64 {
                 Ok(flow)
             }
             MetricF64::InterNetworkTransfer(idx) => state.get_inter_network_transfer_value(*idx),
+            MetricF64::ExternalInput(idx) => state.get_external_input_value(*idx),
+            MetricF64::StateOverrideResidual(idx) => state.get_state_override_residual(*idx),
             MetricF64::Simple(s) => s.get_value(&state.get_simple_parameter_values()),
         }
     }
@@ -182,6 +229,133 @@ impl MetricF64 {
             _ => false,
         }
     }
+
+    /// A description of the metric using the names of the components of the network it refers to.
+    pub fn describe(&self, network: &Network) -> String {
+        self.try_describe(network).unwrap_or_else(|_| format!("{self:?}"))
+    }
+
+    fn try_describe(&self, network: &Network) -> Result<String, PywrError> {
+        let description = match self {
+            MetricF64::NodeInFlow(idx) => format!("inflow of node {}", full_name(network.get_node(idx)?.full_name())),
+            MetricF64::NodeOutFlow(idx) => {
+                format!("outflow of node {}", full_name(network.get_node(idx)?.full_name()))
+            }
+            MetricF64::NodeVolume(idx) => format!("volume of node {}", full_name(network.get_node(idx)?.full_name())),
+            MetricF64::AggregatedNodeInFlow(idx) => format!(
+                "inflow of aggregated node {}",
+                full_name(network.get_aggregated_node(idx)?.full_name())
+            ),
+            MetricF64::AggregatedNodeOutFlow(idx) => format!(
+                "outflow of aggregated node {}",
+                full_name(network.get_aggregated_node(idx)?.full_name())
+            ),
+            MetricF64::AggregatedNodeVolume(idx) => format!(
+                "volume of aggregated storage node {}",
+                full_name(network.get_aggregated_storage_node(idx)?.full_name())
+            ),
+            MetricF64::EdgeFlow(idx) => {
+                let edge = network.get_edge(idx)?;
+                format!(
+                    "flow of edge from node {} to node {}",
+                    full_name(network.get_node(&edge.from_node_index)?.full_name()),
+                    full_name(network.get_node(&edge.to_node_index)?.full_name())
+                )
+            }
+            MetricF64::MultiEdgeFlow { name, .. } => format!("flow of edges `{name}`"),
+            MetricF64::ParameterValue(idx) => {
+                format!("value of parameter `{}`", network.get_parameter((*idx).into())?.name())
+            }
+            MetricF64::IndexParameterValue(idx) => {
+                format!(
+                    "value of index parameter `{}`",
+                    network.get_index_parameter((*idx).into())?.name()
+                )
+            }
+            MetricF64::MultiParameterValue((idx, key)) => format!(
+                "value `{key}` of parameter `{}`",
+                network.get_multi_valued_parameter(&(*idx).into())?.name()
+            ),
+            MetricF64::VirtualStorageVolume(idx) => format!(
+                "volume of virtual storage node {}",
+                full_name(network.get_virtual_storage_node(idx)?.full_name())
+            ),
+            MetricF64::MultiNodeInFlow { name, .. } => format!("inflow of nodes `{name}`"),
+            MetricF64::MultiNodeOutFlow { name, .. } => format!("outflow of nodes `{name}`"),
+            MetricF64::DerivedMetric(idx) => {
+                let metric = network.get_derived_metric(idx)?;
+                format!("derived metric of `{}`", metric.name(network)?)
+            }
+            MetricF64::InterNetworkTransfer(idx) => format!("inter-network transfer {idx}"),
+            MetricF64::ExternalInput(idx) => {
+                let name = network
+                    .external_inputs()
+                    .get(**idx)
+                    .ok_or(PywrError::ExternalInputIndexNotFound(*idx))?;
+                format!("external input `{name}`")
+            }
+            MetricF64::StateOverrideResidual(idx) => {
+                format!(
+                    "residual of state override `{}`",
+                    network.get_state_override(idx)?.name()
+                )
+            }
+            MetricF64::Simple(s) => s.describe(network)?,
+        };
+        Ok(description)
+    }
+}
+
+/// Format the full name of a component of the network.
+fn full_name((name, sub_name): (&str, Option<&str>)) -> String {
+    match sub_name {
+        Some(sub_name) => format!("`{name}` (`{sub_name}`)"),
+        None => format!("`{name}`"),
+    }
+}
+
+/// The value of a metric when an error occurred, and the traces of the metrics from which
+/// that value was computed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricTrace {
+    /// A description of the metric (see [`MetricF64::describe`]).
+    pub description: String,
+    /// The value of the metric in the state, if it could be read.
+    pub value: Option<f64>,
+    pub dependencies: Vec<MetricTrace>,
+}
+
+impl MetricTrace {
+    fn fmt_indented(&self, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "\n{:indent$}- {} = ", "", self.description, indent = 2 * depth)?;
+        match self.value {
+            Some(value) => write!(f, "{value}")?,
+            None => write!(f, "<unavailable>")?,
+        }
+        for dependency in &self.dependencies {
+            dependency.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The traces of the metrics from which an invalid value was computed.
+///
+/// This is displayed as an indented tree of the metrics and their values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricBacktrace(pub Vec<MetricTrace>);
+
+impl Display for MetricBacktrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        write!(f, "; computed from:")?;
+        for trace in &self.0 {
+            trace.fmt_indented(f, 1)?;
+        }
+        Ok(())
+    }
 }
 
 impl TryFrom<MetricF64> for SimpleMetricF64 {
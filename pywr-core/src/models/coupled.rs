@@ -0,0 +1,309 @@
+use crate::network::{Network, NetworkState};
+use crate::recorders::OutputMetric;
+use crate::scenario::ScenarioIndex;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, BufReader, Stdin, Stdout, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::ops::Deref;
+
+/// An index to a value supplied to the network by an external process.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub struct ExternalInputIndex(usize);
+
+impl ExternalInputIndex {
+    pub fn new(idx: usize) -> Self {
+        Self(idx)
+    }
+}
+
+impl Deref for ExternalInputIndex {
+    type Target = usize;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for ExternalInputIndex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A value for an external input.
+///
+/// A single value is applied to every scenario, otherwise one value must be given per scenario.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExternalValue {
+    Scalar(f64),
+    PerScenario(Vec<f64>),
+}
+
+/// A frame of values received from the external process before a time-step is simulated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+    /// The index of the time-step these values are for.
+    pub timestep: usize,
+    /// Values keyed by the name of the external input. Inputs that are omitted retain their
+    /// value from the previous time-step.
+    #[serde(default)]
+    pub values: BTreeMap<String, ExternalValue>,
+}
+
+impl InputFrame {
+    /// Apply the values in this frame to the external inputs of each scenario's state.
+    pub(crate) fn apply(
+        &self,
+        timestep: &Timestep,
+        network: &Network,
+        scenario_indices: &[ScenarioIndex],
+        state: &mut NetworkState,
+    ) -> Result<(), PywrError> {
+        if self.timestep != timestep.index {
+            return Err(PywrError::CoSimulation(format!(
+                "expected values for time-step {} but received time-step {}",
+                timestep.index, self.timestep
+            )));
+        }
+
+        for (name, value) in &self.values {
+            let idx = network.get_external_input_index_by_name(name)?;
+
+            for scenario_index in scenario_indices {
+                let v = match value {
+                    ExternalValue::Scalar(v) => *v,
+                    ExternalValue::PerScenario(values) => {
+                        if values.len() != scenario_indices.len() {
+                            return Err(PywrError::CoSimulation(format!(
+                                "external input `{}` has {} values but there are {} scenarios",
+                                name,
+                                values.len(),
+                                scenario_indices.len()
+                            )));
+                        }
+                        values[scenario_index.index]
+                    }
+                };
+
+                state.state_mut(scenario_index).set_external_input_value(idx, v)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The value of a single output metric for each scenario.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputValue {
+    pub name: String,
+    pub attribute: String,
+    pub values: Vec<f64>,
+}
+
+/// A frame of values sent to the external process after a time-step has been simulated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputFrame {
+    /// The index of the time-step that was simulated.
+    pub timestep: usize,
+    /// The date of the time-step that was simulated.
+    pub date: String,
+    pub values: Vec<OutputValue>,
+}
+
+impl OutputFrame {
+    /// Create a new frame from the current value of each metric in `outputs`.
+    pub(crate) fn from_state(
+        timestep: &Timestep,
+        network: &Network,
+        scenario_indices: &[ScenarioIndex],
+        state: &NetworkState,
+        outputs: &[OutputMetric],
+    ) -> Result<Self, PywrError> {
+        let values = outputs
+            .iter()
+            .map(|metric| {
+                let values = scenario_indices
+                    .iter()
+                    .map(|scenario_index| metric.get_value(network, state.state(scenario_index)))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(OutputValue {
+                    name: metric.name().to_string(),
+                    attribute: metric.attribute().to_string(),
+                    values,
+                })
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        Ok(Self {
+            timestep: timestep.index,
+            date: timestep.date.to_string(),
+            values,
+        })
+    }
+}
+
+/// A channel for exchanging frames with an external process during a coupled simulation.
+pub trait CouplingTransport {
+    /// Receive the input values for the next time-step.
+    fn receive(&mut self) -> Result<InputFrame, PywrError>;
+    /// Send the output values of the last time-step.
+    fn send(&mut self, frame: &OutputFrame) -> Result<(), PywrError>;
+}
+
+/// A [`CouplingTransport`] that exchanges frames as newline delimited JSON.
+///
+/// Each frame is serialised as a single line of JSON. Input frames are read from `reader` and
+/// output frames are written, and flushed, to `writer`.
+pub struct JsonLinesTransport<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: BufRead, W: Write> JsonLinesTransport<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+}
+
+impl JsonLinesTransport<BufReader<Stdin>, Stdout> {
+    /// Exchange frames over the standard input and output of this process.
+    pub fn stdio() -> Self {
+        Self::new(BufReader::new(std::io::stdin()), std::io::stdout())
+    }
+}
+
+impl JsonLinesTransport<BufReader<TcpStream>, TcpStream> {
+    /// Exchange frames over a TCP socket connected to `addr`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, PywrError> {
+        let stream = TcpStream::connect(addr).map_err(|e| PywrError::CoSimulation(e.to_string()))?;
+        let reader = stream.try_clone().map_err(|e| PywrError::CoSimulation(e.to_string()))?;
+
+        Ok(Self::new(BufReader::new(reader), stream))
+    }
+}
+
+impl<R: BufRead, W: Write> CouplingTransport for JsonLinesTransport<R, W> {
+    fn receive(&mut self) -> Result<InputFrame, PywrError> {
+        let mut line = String::new();
+        let n = self
+            .reader
+            .read_line(&mut line)
+            .map_err(|e| PywrError::CoSimulation(e.to_string()))?;
+
+        if n == 0 {
+            return Err(PywrError::CoSimulation(
+                "input closed before the end of the simulation".to_string(),
+            ));
+        }
+
+        serde_json::from_str(&line).map_err(|e| PywrError::CoSimulation(format!("invalid input frame: {e}")))
+    }
+
+    fn send(&mut self, frame: &OutputFrame) -> Result<(), PywrError> {
+        serde_json::to_writer(&mut self.writer, frame).map_err(|e| PywrError::CoSimulation(e.to_string()))?;
+        writeln!(self.writer).map_err(|e| PywrError::CoSimulation(e.to_string()))?;
+        self.writer.flush().map_err(|e| PywrError::CoSimulation(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CouplingTransport, ExternalValue, InputFrame, JsonLinesTransport, OutputFrame};
+    use crate::metric::MetricF64;
+    use crate::recorders::OutputMetric;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+    use std::io::{BufRead, Cursor};
+
+    #[test]
+    fn test_json_lines_transport() {
+        let input = "{\"timestep\": 0, \"values\": {\"a\": 1.5, \"b\": [1.0, 2.0]}}\n{\"timestep\": 1}\n";
+        let mut output = Vec::new();
+
+        let mut transport = JsonLinesTransport::new(Cursor::new(input), &mut output);
+
+        let frame = transport.receive().unwrap();
+        assert_eq!(frame.timestep, 0);
+        assert_eq!(frame.values["a"], ExternalValue::Scalar(1.5));
+        assert_eq!(frame.values["b"], ExternalValue::PerScenario(vec![1.0, 2.0]));
+
+        let frame: InputFrame = transport.receive().unwrap();
+        assert_eq!(frame.timestep, 1);
+        assert!(frame.values.is_empty());
+
+        // No more frames
+        assert!(transport.receive().is_err());
+
+        let frame = OutputFrame {
+            timestep: 0,
+            date: "2020-01-01 00:00:00".to_string(),
+            values: vec![],
+        };
+        transport.send(&frame).unwrap();
+
+        let written: OutputFrame = serde_json::from_slice(&output).unwrap();
+        assert_eq!(written, frame);
+    }
+
+    #[test]
+    fn test_run_coupled() {
+        let mut model = simple_model(2, None);
+        let idx = model.network_mut().add_external_input("recharge").unwrap();
+
+        let num_timesteps = model.domain().time().timesteps().len();
+        let input: String = (0..num_timesteps)
+            .map(|i| {
+                format!(
+                    "{{\"timestep\": {i}, \"values\": {{\"recharge\": [{i}.0, {}.0]}}}}\n",
+                    2 * i
+                )
+            })
+            .collect();
+
+        let outputs = vec![OutputMetric::new(
+            "recharge",
+            "value",
+            "external",
+            None,
+            MetricF64::ExternalInput(idx),
+        )];
+
+        let mut output = Vec::new();
+        let mut transport = JsonLinesTransport::new(Cursor::new(input), &mut output);
+
+        model
+            .run_coupled::<ClpSolver, _>(&ClpSolverSettings::default(), &mut transport, &outputs)
+            .unwrap();
+
+        let frames = Cursor::new(output)
+            .lines()
+            .map(|l| serde_json::from_str::<OutputFrame>(&l.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(frames.len(), num_timesteps);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.timestep, i);
+            assert_eq!(frame.values[0].values, vec![i as f64, 2.0 * i as f64]);
+        }
+    }
+
+    #[test]
+    fn test_run_coupled_wrong_timestep() {
+        let mut model = simple_model(1, None);
+        model.network_mut().add_external_input("recharge").unwrap();
+
+        let input = "{\"timestep\": 1, \"values\": {\"recharge\": 1.0}}\n";
+        let mut transport = JsonLinesTransport::new(Cursor::new(input), Vec::new());
+
+        let result = model.run_coupled::<ClpSolver, _>(&ClpSolverSettings::default(), &mut transport, &[]);
+        assert!(result.is_err());
+    }
+}
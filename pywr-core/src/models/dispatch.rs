@@ -0,0 +1,215 @@
+use crate::models::RunOptions;
+use crate::recorders::FileManifest;
+use crate::PywrError;
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// The reason a run started by a [`RunDispatcher`] failed.
+#[derive(Error, Debug)]
+pub enum DispatchError<E> {
+    /// The job of the run returned an error.
+    #[error("{0}")]
+    Failed(E),
+    /// The job of the run panicked.
+    #[error("the run panicked: {0}")]
+    Panicked(String),
+}
+
+/// The result of a run started by a [`RunDispatcher`].
+#[derive(Debug)]
+pub struct DispatchedRun<E = PywrError> {
+    /// The label of the run (e.g. the member of the ensemble that it runs).
+    pub label: String,
+    /// The wall-clock time of the run.
+    pub elapsed: Duration,
+    /// The files written by the run, or the reason it failed.
+    pub result: Result<FileManifest, DispatchError<E>>,
+}
+
+impl DispatchedRun<PywrError> {
+    /// Returns true if the run was stopped because it exceeded its time limit.
+    pub fn timed_out(&self) -> bool {
+        matches!(self.result, Err(DispatchError::Failed(PywrError::RunTimedOut { .. })))
+    }
+}
+
+/// The message of a panic caught by [`catch_unwind`].
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
+
+/// Runs a batch of independent model runs (e.g. a run of each member of an ensemble) on a fixed
+/// number of threads.
+///
+/// The job of each run is only started once a thread is free to run it, so no more than
+/// `threads` models are built and held in memory at once however many runs are in the batch.
+/// A run that fails or panics, or that takes longer than the time limit and is stopped, is
+/// recorded and the remaining runs continue. This prevents a single degenerate run (e.g. of one
+/// member of an ensemble) from holding up the rest of the batch.
+///
+/// # Examples
+///
+/// ```
+/// use pywr_core::models::{RunDispatcher, RunOptions};
+/// use pywr_core::recorders::FileManifest;
+/// use pywr_core::PywrError;
+/// use std::num::NonZeroUsize;
+/// use std::time::Duration;
+///
+/// let dispatcher = RunDispatcher::new(NonZeroUsize::new(4).unwrap()).with_time_limit(Duration::from_secs(600));
+///
+/// let jobs = (0..10).map(|member| {
+///     let job = move |_options: RunOptions<'static>| -> Result<FileManifest, PywrError> {
+///         // Build the model of `member` here and run it with `Model::run_with_options`,
+///         // passing the options on to the run.
+///         Ok(FileManifest::default())
+///     };
+///     (format!("member-{member}"), job)
+/// });
+///
+/// let runs = dispatcher.run(jobs);
+/// assert!(runs.iter().all(|r| r.result.is_ok()));
+/// ```
+pub struct RunDispatcher {
+    threads: NonZeroUsize,
+    time_limit: Option<Duration>,
+}
+
+impl RunDispatcher {
+    pub fn new(threads: NonZeroUsize) -> Self {
+        Self {
+            threads,
+            time_limit: None,
+        }
+    }
+
+    /// Stop any run in which a scenario takes longer than `time_limit` (see
+    /// [`RunOptions::time_limit`]).
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Run each of the `jobs` and return their results in the same order.
+    ///
+    /// Each job is a label and a function that builds and runs a model with the given
+    /// [`RunOptions`] (e.g. with [`Model::run_with_options`](crate::models::Model::run_with_options)).
+    /// The options must be passed to the run for the time limit to be applied. The error of a
+    /// job can be any error, so that a failure to build its model is also recorded.
+    pub fn run<I, F, E>(&self, jobs: I) -> Vec<DispatchedRun<E>>
+    where
+        I: IntoIterator<Item = (String, F)>,
+        I::IntoIter: Send,
+        F: FnOnce(RunOptions<'static>) -> Result<FileManifest, E> + Send,
+        E: Display + Send,
+    {
+        let jobs = Mutex::new(jobs.into_iter().enumerate());
+        let runs = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads.get() {
+                scope.spawn(|| loop {
+                    // The next job is only taken once this thread is free to run it
+                    let next = jobs.lock().unwrap().next();
+                    let Some((idx, (label, job))) = next else {
+                        break;
+                    };
+
+                    let mut options = RunOptions::default();
+                    if let Some(time_limit) = self.time_limit {
+                        options = options.time_limit(time_limit);
+                    }
+
+                    let started = Instant::now();
+                    let result = match catch_unwind(AssertUnwindSafe(|| job(options))) {
+                        Ok(result) => result.map_err(DispatchError::Failed),
+                        Err(payload) => Err(DispatchError::Panicked(panic_message(payload))),
+                    };
+                    let elapsed = started.elapsed();
+
+                    match &result {
+                        Ok(_) => info!("Run `{label}` completed in {elapsed:?}."),
+                        Err(e) => warn!("Run `{label}` failed after {elapsed:?}: {e}"),
+                    }
+
+                    runs.lock()
+                        .unwrap()
+                        .push((idx, DispatchedRun { label, elapsed, result }));
+                });
+            }
+        });
+
+        let mut runs = runs.into_inner().unwrap();
+        runs.sort_by_key(|(idx, _)| *idx);
+        runs.into_iter().map(|(_, run)| run).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DispatchError, RunDispatcher};
+    use crate::models::RunOptions;
+    use crate::recorders::FileManifest;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+    use crate::PywrError;
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    fn run_simple_model(options: RunOptions<'static>) -> Result<FileManifest, PywrError> {
+        let model = simple_model(1, None);
+        model
+            .run_with_options::<ClpSolver>(&ClpSolverSettings::default(), options)
+            .map(|result| result.manifest().clone())
+    }
+
+    #[test]
+    fn test_dispatcher() {
+        let dispatcher = RunDispatcher::new(NonZeroUsize::new(2).unwrap());
+
+        // The second job fails and the third panics; the remaining jobs are still run
+        let jobs = (0..4).map(|i| {
+            let job = move |options: RunOptions<'static>| match i {
+                1 => Err(PywrError::EndOfTimesteps),
+                2 => panic!("degenerate run"),
+                _ => run_simple_model(options),
+            };
+            (format!("run-{i}"), job)
+        });
+
+        let runs = dispatcher.run(jobs);
+        assert_eq!(runs.len(), 4);
+        for (i, run) in runs.iter().enumerate() {
+            assert_eq!(run.label, format!("run-{i}"));
+            assert_eq!(run.result.is_ok(), i != 1 && i != 2);
+            assert!(!run.timed_out());
+        }
+        assert!(matches!(
+            &runs[1].result,
+            Err(DispatchError::Failed(PywrError::EndOfTimesteps))
+        ));
+        assert!(matches!(&runs[2].result, Err(DispatchError::Panicked(message)) if message == "degenerate run"));
+    }
+
+    #[test]
+    fn test_dispatcher_time_limit() {
+        let dispatcher = RunDispatcher::new(NonZeroUsize::new(2).unwrap()).with_time_limit(Duration::ZERO);
+
+        let jobs = (0..3).map(|i| (format!("run-{i}"), run_simple_model));
+
+        let runs = dispatcher.run(jobs);
+        assert_eq!(runs.len(), 3);
+        assert!(runs.iter().all(|r| r.timed_out()));
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+/// An estimate of the run time and output size of a model.
+///
+/// The estimate is extrapolated from a sample run of a subset of the model's time-steps and
+/// scenarios. The setup time is assumed to scale with the number of scenarios, and the run
+/// time with the number of time-steps multiplied by the number of scenarios. The output size
+/// is an upper bound that assumes every metric of every metric set is saved as a 64-bit value
+/// for every time-step and scenario (i.e. no aggregation or compression).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunEstimate {
+    sample_timesteps: usize,
+    sample_scenarios: usize,
+    total_timesteps: usize,
+    total_scenarios: usize,
+    sample_setup_time: Duration,
+    sample_run_time: Duration,
+    values_per_step: usize,
+}
+
+impl RunEstimate {
+    pub(crate) fn new(
+        sample_shape: (usize, usize),
+        total_shape: (usize, usize),
+        sample_setup_time: Duration,
+        sample_run_time: Duration,
+        values_per_step: usize,
+    ) -> Self {
+        Self {
+            sample_timesteps: sample_shape.0,
+            sample_scenarios: sample_shape.1,
+            total_timesteps: total_shape.0,
+            total_scenarios: total_shape.1,
+            sample_setup_time,
+            sample_run_time,
+            values_per_step,
+        }
+    }
+
+    /// The number of time-steps and scenarios that were run in the sample.
+    pub fn sample_shape(&self) -> (usize, usize) {
+        (self.sample_timesteps, self.sample_scenarios)
+    }
+
+    /// The number of time-steps and scenarios in the full run.
+    pub fn total_shape(&self) -> (usize, usize) {
+        (self.total_timesteps, self.total_scenarios)
+    }
+
+    /// The time taken to setup and run the sample.
+    pub fn sample_time(&self) -> Duration {
+        self.sample_setup_time + self.sample_run_time
+    }
+
+    /// The estimated time to setup the full run.
+    pub fn setup_time(&self) -> Duration {
+        let ratio = self.total_scenarios as f64 / self.sample_scenarios as f64;
+        self.sample_setup_time.mul_f64(ratio)
+    }
+
+    /// The estimated time to run all of the time-steps and scenarios of the full run.
+    pub fn run_time(&self) -> Duration {
+        let ratio = (self.total_timesteps * self.total_scenarios) as f64
+            / (self.sample_timesteps * self.sample_scenarios) as f64;
+        self.sample_run_time.mul_f64(ratio)
+    }
+
+    /// The estimated total time of the full run.
+    pub fn total_time(&self) -> Duration {
+        self.setup_time() + self.run_time()
+    }
+
+    /// The speed of the sample run in time-steps per second.
+    ///
+    /// Returns `None` if the sample run was too quick to be timed.
+    pub fn speed(&self) -> Option<f64> {
+        if self.sample_run_time.is_zero() {
+            return None;
+        }
+        Some((self.sample_timesteps * self.sample_scenarios) as f64 / self.sample_run_time.as_secs_f64())
+    }
+
+    /// The upper bound of the number of values output by the full run.
+    pub fn output_values(&self) -> usize {
+        self.values_per_step * self.total_timesteps * self.total_scenarios
+    }
+
+    /// The upper bound of the size of the output of the full run in bytes.
+    pub fn output_bytes(&self) -> usize {
+        self.output_values() * std::mem::size_of::<f64>()
+    }
+}
+
+impl Display for RunEstimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{: <24} | {: <10}", "Estimate", "Value")?;
+        writeln!(
+            f,
+            "{: <24} | {} of {} time-steps, {} of {} scenarios",
+            "Sample", self.sample_timesteps, self.total_timesteps, self.sample_scenarios, self.total_scenarios
+        )?;
+        writeln!(
+            f,
+            "{: <24} | {: <10.5}s",
+            "Sample time",
+            self.sample_time().as_secs_f64()
+        )?;
+        writeln!(f, "{: <24} | {: <10.5}s", "Setup time", self.setup_time().as_secs_f64())?;
+        writeln!(f, "{: <24} | {: <10.5}s", "Run time", self.run_time().as_secs_f64())?;
+        writeln!(f, "{: <24} | {: <10.5}s", "Total time", self.total_time().as_secs_f64())?;
+        match self.speed() {
+            None => writeln!(f, "{: <24} | Unknown", "Speed")?,
+            Some(speed) => writeln!(f, "{: <24} | {: <10.5} ts/s", "Speed", speed)?,
+        }
+        writeln!(f, "{: <24} | {: <10}", "Output values", self.output_values())?;
+        write!(
+            f,
+            "{: <24} | {: <10.3} MB",
+            "Output size",
+            self.output_bytes() as f64 / 1024.0 / 1024.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RunEstimate;
+    use std::time::Duration;
+
+    #[test]
+    fn test_extrapolation() {
+        let estimate = RunEstimate::new((10, 2), (100, 8), Duration::from_secs(1), Duration::from_secs(2), 3);
+
+        assert_eq!(estimate.setup_time(), Duration::from_secs(4));
+        assert_eq!(estimate.run_time(), Duration::from_secs(80));
+        assert_eq!(estimate.total_time(), Duration::from_secs(84));
+        assert_eq!(estimate.speed(), Some(10.0));
+        assert_eq!(estimate.output_values(), 2400);
+        assert_eq!(estimate.output_bytes(), 2400 * 8);
+    }
+
+    #[test]
+    fn test_zero_run_time() {
+        let estimate = RunEstimate::new((10, 2), (100, 8), Duration::from_secs(1), Duration::ZERO, 3);
+
+        assert_eq!(estimate.speed(), None);
+        assert_eq!(estimate.run_time(), Duration::ZERO);
+        assert!(estimate.to_string().contains("Unknown"));
+    }
+}
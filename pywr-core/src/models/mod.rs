@@ -1,11 +1,25 @@
+mod coupled;
+mod dispatch;
+mod estimate;
 mod multi;
+mod options;
+mod progress;
+mod random;
 mod simple;
 
 use crate::scenario::{ScenarioDomain, ScenarioGroupCollection};
 use crate::timestep::{TimeDomain, Timestepper};
 use crate::PywrError;
-pub use multi::{MultiNetworkModel, MultiNetworkTransferIndex};
-pub use simple::{Model, ModelState};
+pub use coupled::{
+    CouplingTransport, ExternalInputIndex, ExternalValue, InputFrame, JsonLinesTransport, OutputFrame, OutputValue,
+};
+pub use dispatch::{DispatchError, DispatchedRun, RunDispatcher};
+pub use estimate::RunEstimate;
+pub use multi::{MultiNetworkIteration, MultiNetworkIterationStats, MultiNetworkModel, MultiNetworkTransferIndex};
+pub use options::{CancellationToken, RunOptions};
+pub use progress::{ChannelProgress, Progress, ProgressCallback, ProgressControl, ProgressFrequency};
+pub use random::RandomModelBuilder;
+pub use simple::{Model, ModelResult, ModelState};
 
 #[derive(Debug)]
 pub struct ModelDomain {
@@ -1,10 +1,15 @@
 use crate::metric::MetricF64;
 use crate::models::ModelDomain;
 use crate::network::{Network, NetworkState, RunTimings};
+use crate::parameters::ParameterStates;
+use crate::recorders::{FileManifest, RunInfo};
 use crate::scenario::ScenarioIndex;
 use crate::solvers::{MultiStateSolver, Solver, SolverSettings};
+use crate::state::State;
 use crate::timestep::Timestep;
 use crate::PywrError;
+use rayon::prelude::*;
+use rayon::ThreadPool;
 use std::any::Any;
 use std::cmp::Ordering;
 use std::fmt;
@@ -12,6 +17,7 @@ use std::fmt::{Display, Formatter};
 use std::num::NonZeroUsize;
 use std::ops::Deref;
 use std::time::Instant;
+use tracing::{info, warn};
 
 /// An index to another model
 ///
@@ -29,6 +35,14 @@ impl OtherNetworkIndex {
             Ordering::Greater => Self::After(NonZeroUsize::new(from_idx - to_idx).unwrap()),
         }
     }
+
+    /// The index of the other network relative to the network `to_idx`.
+    fn network_idx(&self, to_idx: usize) -> usize {
+        match self {
+            Self::Before(i) => to_idx - i.get(),
+            Self::After(i) => to_idx + i.get(),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
@@ -54,14 +68,120 @@ struct MultiNetworkTransfer {
     from_model_idx: OtherNetworkIndex,
     /// The metric to get the value from.
     from_metric: MetricF64,
-    /// Optional initial value to use on the first time-step
+    /// Optional initial value to use on the first time-step, or the first `lag` time-steps of a
+    /// lagged transfer.
     initial_value: Option<f64>,
+    /// The number of time-steps between a value being sent and received.
+    lag: usize,
+    /// The fraction of the value that is lost in transfer.
+    loss: f64,
+    /// Whether the other network has more time-steps than the receiving network, in which case
+    /// the mean of the values sent since the transfer was last received is used.
+    aggregated: bool,
+}
+
+/// The duration weighted mean of the values sent by an aggregated transfer since it was last
+/// received.
+#[derive(Copy, Clone, Debug, Default)]
+struct TransferMean {
+    total: f64,
+    days: f64,
+}
+
+impl TransferMean {
+    fn add(&mut self, value: f64, days: f64) {
+        self.total += value * days;
+        self.days += days;
+    }
+
+    /// Take the mean of the values added since it was last taken, if any.
+    fn take(&mut self) -> Option<f64> {
+        let mean = (self.days > 0.0).then(|| self.total / self.days);
+        *self = Self::default();
+        mean
+    }
+}
+
+/// The time domain of a network that is stepped less often than the model.
+struct NetworkTime {
+    domain: ModelDomain,
+    /// The index of the network's time-step that starts at each of the model's time-steps, if any.
+    starts: Vec<Option<usize>>,
 }
 
 struct MultiNetworkEntry {
     name: String,
     network: Network,
     parameters: Vec<MultiNetworkTransfer>,
+    /// The time domain of the network if it differs from that of the model.
+    time: Option<NetworkTime>,
+}
+
+/// Settings for iterating the inter-network transfers to convergence within each time-step.
+///
+/// By default the networks of a [`MultiNetworkModel`] are solved once per time-step in the order
+/// they were added. A network can only use the values of the current time-step from networks
+/// solved before it; values from the networks solved after it are from the previous time-step.
+/// With these settings the networks are instead solved repeatedly, starting from the same state
+/// each time, until the values of all the transfers change by less than `tolerance` between
+/// iterations or `max_iterations` is reached.
+///
+/// The network state and the internal states of the parameters are restored at the start of
+/// each iteration, so every iteration computes the time-step from the same starting point. The
+/// "after" methods, metric sets and recorders are only run once the iteration has finished.
+///
+/// Transfers that oscillate between iterations can be damped with [`Self::with_damping`]. After
+/// the first iteration each un-lagged transfer then only moves by a fraction of the change from
+/// the value it took in the previous iteration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MultiNetworkIteration {
+    tolerance: f64,
+    max_iterations: NonZeroUsize,
+    damping: f64,
+}
+
+impl MultiNetworkIteration {
+    pub fn new(tolerance: f64, max_iterations: NonZeroUsize) -> Self {
+        Self {
+            tolerance,
+            max_iterations,
+            damping: 1.0,
+        }
+    }
+
+    /// Damp the changes of the transfers between iterations by `damping`.
+    ///
+    /// The damping must be greater than zero and at most one (the default, no damping).
+    pub fn with_damping(mut self, damping: f64) -> Result<Self, PywrError> {
+        if !(damping > 0.0 && damping <= 1.0) {
+            return Err(PywrError::InvalidMultiNetworkIterationDamping(damping));
+        }
+        self.damping = damping;
+        Ok(self)
+    }
+
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    pub fn max_iterations(&self) -> NonZeroUsize {
+        self.max_iterations
+    }
+
+    pub fn damping(&self) -> f64 {
+        self.damping
+    }
+}
+
+/// The outcome of iterating the inter-network transfers in a single time-step.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MultiNetworkIterationStats {
+    /// The number of times the networks were solved.
+    pub iterations: usize,
+    /// The largest absolute change of a transfer's value in the final iteration.
+    pub residual: f64,
+    /// Whether the residual is within the tolerance.
+    pub converged: bool,
 }
 
 pub struct MultiNetworkModelState<S> {
@@ -69,12 +189,37 @@ pub struct MultiNetworkModelState<S> {
     states: Vec<NetworkState>,
     recorder_states: Vec<Vec<Option<Box<dyn Any>>>>,
     solvers: Vec<S>,
+    iteration_stats: Vec<MultiNetworkIterationStats>,
+    /// The mean of each aggregated transfer of each network for each scenario.
+    transfer_means: Vec<Vec<Vec<TransferMean>>>,
+}
+
+impl<S> MultiNetworkModelState<S> {
+    /// The outcome of iterating the inter-network transfers in each completed time-step.
+    ///
+    /// This is empty unless the model is configured with a [`MultiNetworkIteration`].
+    pub fn iteration_stats(&self) -> &[MultiNetworkIterationStats] {
+        &self.iteration_stats
+    }
 }
 
 /// A MultiNetwork is a collection of models that can be run together.
+///
+/// By default every network is stepped with the time-steps of the model's domain. A network can
+/// instead be added with its own, coarser, time domain (see
+/// [`MultiNetworkModel::add_network_with_domain`]); for example, a weekly resource network
+/// linked to a daily supply network. The time-steps of the model are then the frequency at which
+/// the networks exchange values, and each network is only stepped at the start of each of its
+/// own time-steps. A network receives the value of a transfer from a network with fewer
+/// time-steps as it is in the current state of that network. The value of a transfer from a
+/// network with more time-steps is the mean of the values sent since it was last received,
+/// weighted by the duration of the sending network's time-steps.
 pub struct MultiNetworkModel {
     domain: ModelDomain,
     networks: Vec<MultiNetworkEntry>,
+    iteration: Option<MultiNetworkIteration>,
+    /// The indices of the networks in each stage of a time-step (see [`MultiNetworkModel::stages`]).
+    stages: Vec<Vec<usize>>,
 }
 
 impl MultiNetworkModel {
@@ -82,14 +227,77 @@ impl MultiNetworkModel {
         Self {
             domain,
             networks: Vec::new(),
+            iteration: None,
+            stages: Vec::new(),
         }
     }
 
+    /// Iterate the inter-network transfers to convergence within each time-step.
+    ///
+    /// See [`MultiNetworkIteration`] for details. If `None` the networks are solved once per time-step.
+    pub fn set_iteration(&mut self, iteration: Option<MultiNetworkIteration>) {
+        self.iteration = iteration;
+    }
+
+    pub fn iteration(&self) -> Option<&MultiNetworkIteration> {
+        self.iteration.as_ref()
+    }
+
     /// Get a reference to the [`ModelDomain`]
     pub fn domain(&self) -> &ModelDomain {
         &self.domain
     }
 
+    /// The indices of the networks in each stage of a time-step.
+    ///
+    /// The networks of a stage do not depend on each other within a time-step, and are stepped
+    /// concurrently when the solver settings are parallel. The inter-network transfers of all the
+    /// networks in a stage are computed before any of them are stepped. The results are the same
+    /// as stepping the networks one at a time in the order they were added.
+    pub fn stages(&self) -> &[Vec<usize>] {
+        &self.stages
+    }
+
+    /// Group the networks into stages from the dependencies created by their transfers.
+    ///
+    /// A network that receives a transfer from an earlier network must be in a later stage than
+    /// that network, so that it receives the value from the current time-step. A network that
+    /// sends a transfer to an earlier network must not be in an earlier stage than that network,
+    /// so that the earlier network receives the value from the previous time-step.
+    fn update_stages(&mut self) {
+        let mut levels: Vec<usize> = Vec::with_capacity(self.networks.len());
+
+        for (idx, entry) in self.networks.iter().enumerate() {
+            let mut level = 0;
+
+            for parameter in &entry.parameters {
+                if let OtherNetworkIndex::Before(i) = parameter.from_model_idx {
+                    level = level.max(levels[idx - i.get()] + 1);
+                }
+            }
+
+            for (other_idx, other) in self.networks[..idx].iter().enumerate() {
+                let sends_to_other = other
+                    .parameters
+                    .iter()
+                    .any(|p| matches!(p.from_model_idx, OtherNetworkIndex::After(i) if other_idx + i.get() == idx));
+                if sends_to_other {
+                    level = level.max(levels[other_idx]);
+                }
+            }
+
+            levels.push(level);
+        }
+
+        let num_stages = levels.iter().max().map_or(0, |l| l + 1);
+        let mut stages = vec![Vec::new(); num_stages];
+        for (idx, level) in levels.into_iter().enumerate() {
+            stages[level].push(idx);
+        }
+
+        self.stages = stages;
+    }
+
     /// Get a reference to a network by index.
     pub fn network(&self, idx: usize) -> Result<&Network, PywrError> {
         self.networks
@@ -125,44 +333,134 @@ impl MultiNetworkModel {
             name: name.to_string(),
             network,
             parameters: Vec::new(),
+            time: None,
         });
+        self.update_stages();
+
+        Ok(idx)
+    }
+
+    /// Add a [`Network`] to the model that is stepped with the time-steps of its own `domain`.
+    /// The name must be unique.
+    ///
+    /// The domain must have the same scenarios as the model. Each of its time-steps must start
+    /// and end at the start or end of one of the model's time-steps, and together they must
+    /// cover all of the model's time-steps. See [`MultiNetworkModel`] for how the values of the
+    /// transfers between networks with different time-steps are calculated.
+    pub fn add_network_with_domain(
+        &mut self,
+        name: &str,
+        network: Network,
+        domain: ModelDomain,
+    ) -> Result<usize, PywrError> {
+        let invalid = |reason: String| PywrError::InvalidNetworkDomain {
+            network: name.to_string(),
+            reason,
+        };
+
+        if domain.scenarios.len() != self.domain.scenarios.len() {
+            return Err(invalid(format!(
+                "it has {} scenarios but the model has {}",
+                domain.scenarios.len(),
+                self.domain.scenarios.len()
+            )));
+        }
+
+        let starts = map_timesteps(self.domain.time.timesteps(), domain.time.timesteps()).map_err(invalid)?;
+
+        let idx = self.add_network(name, network)?;
+        self.networks[idx].time = Some(NetworkTime { domain, starts });
 
         Ok(idx)
     }
 
+    /// The domain of the network `idx`.
+    fn network_domain(&self, idx: usize) -> &ModelDomain {
+        match &self.networks[idx].time {
+            Some(time) => &time.domain,
+            None => &self.domain,
+        }
+    }
+
+    /// The time-step of the network `idx` that starts at the model's `timestep`, if any.
+    fn network_timestep<'a>(&'a self, idx: usize, timestep: &'a Timestep) -> Option<&'a Timestep> {
+        match &self.networks[idx].time {
+            Some(time) => {
+                let network_idx = (*time.starts.get(timestep.index)?)?;
+                time.domain.time.timesteps().get(network_idx)
+            }
+            None => Some(timestep),
+        }
+    }
+
     /// Add a transfer of data from one network to another.
+    ///
+    /// The value received is the value of `from_metric` in the other network `lag` time-steps
+    /// earlier, reduced by the fraction `loss`. The `initial_value` (or zero) is received before
+    /// the first value sent arrives. An error is returned if either of the networks does not
+    /// exist.
     pub fn add_inter_network_transfer(
         &mut self,
         from_network_idx: usize,
         from_metric: MetricF64,
         to_network_idx: usize,
         initial_value: Option<f64>,
-    ) {
+        lag: usize,
+        loss: f64,
+    ) -> Result<(), PywrError> {
+        if !(0.0..=1.0).contains(&loss) {
+            return Err(PywrError::InvalidMultiNetworkTransferLoss(loss));
+        }
+
+        for idx in [from_network_idx, to_network_idx] {
+            if idx >= self.networks.len() {
+                return Err(PywrError::NetworkIndexNotFound(idx));
+            }
+        }
+
+        let num_timesteps = |idx: usize| self.network_domain(idx).time.timesteps().len();
+        let aggregated = num_timesteps(from_network_idx) > num_timesteps(to_network_idx);
+
         let parameter = MultiNetworkTransfer {
             from_model_idx: OtherNetworkIndex::new(from_network_idx, to_network_idx),
             from_metric,
             initial_value,
+            lag,
+            loss,
+            aggregated,
         };
 
         self.networks[to_network_idx].parameters.push(parameter);
+        self.update_stages();
+
+        Ok(())
     }
 
     pub fn setup<S>(&self, settings: &S::Settings) -> Result<MultiNetworkModelState<Vec<Box<S>>>, PywrError>
     where
         S: Solver,
+        <S as Solver>::Settings: SolverSettings,
     {
-        let timesteps = self.domain.time.timesteps();
+        self.validate_network_domains()?;
         let scenario_indices = self.domain.scenarios.indices();
 
         let mut states = Vec::with_capacity(self.networks.len());
         let mut recorder_states = Vec::with_capacity(self.networks.len());
         let mut solvers = Vec::with_capacity(self.networks.len());
+        let run = RunInfo::new(S::name()).with_solver_settings(settings);
 
-        for entry in &self.networks {
-            let state = entry
-                .network
-                .setup_network(timesteps, scenario_indices, entry.parameters.len())?;
-            let recorder_state = entry.network.setup_recorders(&self.domain)?;
+        for (idx, entry) in self.networks.iter().enumerate() {
+            let domain = self.network_domain(idx);
+            let mut state =
+                entry
+                    .network
+                    .setup_network(domain.time.timesteps(), scenario_indices, entry.parameters.len())?;
+            if let Some(export) = settings.lp_export() {
+                export.check(domain.time.timesteps().len(), scenario_indices.len())?;
+                state.set_lp_export(export.for_network(&entry.name), settings.tie_breaking());
+            }
+            let mut recorder_state = entry.network.setup_recorders(domain)?;
+            entry.network.start_recorders(&run, &mut recorder_state)?;
             let solver = entry.network.setup_solver::<S>(scenario_indices, &state, settings)?;
 
             states.push(state);
@@ -175,25 +473,36 @@ impl MultiNetworkModel {
             states,
             recorder_states,
             solvers,
+            iteration_stats: Vec::new(),
+            transfer_means: self.setup_transfer_means(),
         })
     }
 
     pub fn setup_multi_scenario<S>(&self, settings: &S::Settings) -> Result<MultiNetworkModelState<Box<S>>, PywrError>
     where
         S: MultiStateSolver,
+        <S as MultiStateSolver>::Settings: SolverSettings,
     {
-        let timesteps = self.domain.time.timesteps();
+        self.validate_network_domains()?;
         let scenario_indices = self.domain.scenarios.indices();
 
         let mut states = Vec::with_capacity(self.networks.len());
         let mut recorder_states = Vec::with_capacity(self.networks.len());
         let mut solvers = Vec::with_capacity(self.networks.len());
+        let run = RunInfo::new(S::name()).with_solver_settings(settings);
 
-        for entry in &self.networks {
-            let state = entry
-                .network
-                .setup_network(timesteps, scenario_indices, entry.parameters.len())?;
-            let recorder_state = entry.network.setup_recorders(&self.domain)?;
+        for (idx, entry) in self.networks.iter().enumerate() {
+            let domain = self.network_domain(idx);
+            let mut state =
+                entry
+                    .network
+                    .setup_network(domain.time.timesteps(), scenario_indices, entry.parameters.len())?;
+            if let Some(export) = settings.lp_export() {
+                export.check(domain.time.timesteps().len(), scenario_indices.len())?;
+                state.set_lp_export(export.for_network(&entry.name), settings.tie_breaking());
+            }
+            let mut recorder_state = entry.network.setup_recorders(domain)?;
+            entry.network.start_recorders(&run, &mut recorder_state)?;
             let solver = entry
                 .network
                 .setup_multi_scenario_solver::<S>(scenario_indices, settings)?;
@@ -208,9 +517,64 @@ impl MultiNetworkModel {
             states,
             recorder_states,
             solvers,
+            iteration_stats: Vec::new(),
+            transfer_means: self.setup_transfer_means(),
         })
     }
 
+    /// Check the networks with their own time domains can be run with the settings of the model.
+    fn validate_network_domains(&self) -> Result<(), PywrError> {
+        if self.iteration.is_none() {
+            return Ok(());
+        }
+
+        match self.networks.iter().find(|entry| entry.time.is_some()) {
+            Some(entry) => Err(PywrError::InvalidNetworkDomain {
+                network: entry.name.clone(),
+                reason: "the transfers can not be iterated when the networks have different time-steps".to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn setup_transfer_means(&self) -> Vec<Vec<Vec<TransferMean>>> {
+        let num_scenarios = self.domain.scenarios.len();
+        self.networks
+            .iter()
+            .map(|entry| vec![vec![TransferMean::default(); num_scenarios]; entry.parameters.len()])
+            .collect()
+    }
+
+    /// Add the values sent by the network `from_idx` in its `timestep` to the means of the
+    /// aggregated transfers it sends.
+    fn update_transfer_means(
+        &self,
+        from_idx: usize,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        states: &[NetworkState],
+        transfer_means: &mut [Vec<Vec<TransferMean>>],
+    ) -> Result<(), PywrError> {
+        let from = &self.networks[from_idx];
+
+        for (to_idx, (entry, means)) in self.networks.iter().zip(transfer_means.iter_mut()).enumerate() {
+            for (parameter, means) in entry.parameters.iter().zip(means.iter_mut()) {
+                if !parameter.aggregated || parameter.from_model_idx.network_idx(to_idx) != from_idx {
+                    continue;
+                }
+
+                for scenario_index in scenario_indices {
+                    let value = parameter
+                        .from_metric
+                        .get_value(&from.network, states[from_idx].state(scenario_index))?;
+                    means[scenario_index.index].add(value * (1.0 - parameter.loss), timestep.days());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Compute inter model transfers
     fn compute_inter_network_transfers(
         &self,
@@ -218,6 +582,7 @@ impl MultiNetworkModel {
         timestep: &Timestep,
         scenario_indices: &[ScenarioIndex],
         states: &mut [NetworkState],
+        transfer_means: &mut [Vec<TransferMean>],
     ) -> Result<(), PywrError> {
         // Get references to the models before and after this model
         let (before_models, after_models) = self.networks.split_at(model_idx);
@@ -233,6 +598,7 @@ impl MultiNetworkModel {
                 scenario_index,
                 &this_model.parameters,
                 this_models_state,
+                transfer_means,
                 before_models,
                 before,
                 after_models,
@@ -243,13 +609,111 @@ impl MultiNetworkModel {
         Ok(())
     }
 
-    /// Perform a single time-step of the multi1-model.
-    pub fn step<S>(&self, state: &mut MultiNetworkModelState<Vec<Box<S>>>) -> Result<(), PywrError>
+    /// The largest absolute difference between the values of the inter-network transfers of a
+    /// network and the values they would take from the current states of the other networks.
+    fn inter_network_transfer_residual(
+        &self,
+        model_idx: usize,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        states: &[NetworkState],
+    ) -> Result<f64, PywrError> {
+        let (before_models, after_models) = self.networks.split_at(model_idx);
+        let (this_model, after_models) = after_models.split_first().unwrap();
+        let (before, after) = states.split_at(model_idx);
+        let (this_models_state, after) = after.split_first().unwrap();
+
+        let mut residual: f64 = 0.0;
+        for scenario_index in scenario_indices.iter() {
+            for (idx, parameter) in this_model.parameters.iter().enumerate() {
+                // A lagged transfer does not depend on the current time-step of the other network
+                if parameter.lag > 0 {
+                    continue;
+                }
+
+                let value = inter_network_transfer_value(
+                    timestep,
+                    scenario_index,
+                    parameter,
+                    before_models,
+                    before,
+                    after_models,
+                    after,
+                )?;
+                let current = this_models_state
+                    .state(scenario_index)
+                    .get_inter_network_transfer_value(MultiNetworkTransferIndex(idx))?;
+                residual = residual.max((value - current).abs());
+            }
+        }
+
+        Ok(residual)
+    }
+
+    /// Damp the changes of the un-lagged inter-network transfers of a network from the values
+    /// they took in the `previous` iteration, if any.
+    ///
+    /// Returns the values of the transfers of each scenario after damping.
+    fn damp_inter_network_transfers(
+        &self,
+        model_idx: usize,
+        scenario_indices: &[ScenarioIndex],
+        state: &mut NetworkState,
+        previous: Option<&[Vec<f64>]>,
+        damping: f64,
+    ) -> Result<Vec<Vec<f64>>, PywrError> {
+        let parameters = &self.networks[model_idx].parameters;
+
+        scenario_indices
+            .iter()
+            .map(|scenario_index| {
+                let scenario_state = state.state_mut(scenario_index);
+
+                parameters
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, parameter)| {
+                        let idx = MultiNetworkTransferIndex(idx);
+                        let value = scenario_state.get_inter_network_transfer_value(idx)?;
+
+                        let previous = previous.map(|p| p[scenario_index.index][*idx]);
+                        match previous {
+                            Some(previous) if parameter.lag == 0 && damping < 1.0 => {
+                                let damped = previous + damping * (value - previous);
+                                scenario_state.set_inter_network_transfer_value(idx, damped)?;
+                                Ok(damped)
+                            }
+                            _ => Ok(value),
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Solve all the networks for a single time-step, iterating until the inter-network
+    /// transfers converge.
+    ///
+    /// `solve` and `after` perform the two halves of a network's time-step with the solver type `T`.
+    fn step_iterative<T, F, G>(
+        &self,
+        state: &mut MultiNetworkModelState<T>,
+        iteration: &MultiNetworkIteration,
+        timings: &mut RunTimings,
+        solve: F,
+        after: G,
+    ) -> Result<(), PywrError>
     where
-        S: Solver,
+        F: Fn(
+            &Network,
+            &Timestep,
+            &[ScenarioIndex],
+            &mut T,
+            &mut NetworkState,
+            &mut RunTimings,
+        ) -> Result<(), PywrError>,
+        G: Fn(&Network, &Timestep, &[ScenarioIndex], &mut NetworkState, &mut RunTimings) -> Result<(), PywrError>,
     {
-        let mut timings = RunTimings::default();
-
         let timestep = self
             .domain
             .time
@@ -259,47 +723,121 @@ impl MultiNetworkModel {
 
         let scenario_indices = self.domain.scenarios.indices();
 
-        for (idx, entry) in self.networks.iter().enumerate() {
-            // Perform inter-model state updates
-            self.compute_inter_network_transfers(idx, timestep, scenario_indices, &mut state.states)?;
+        // Each iteration starts from the state at the start of the time-step
+        let initial_states: Vec<Vec<State>> = state.states.iter().map(|s| s.states().to_vec()).collect();
+        let initial_parameter_states: Vec<Vec<ParameterStates>> = state
+            .states
+            .iter()
+            .map(|s| s.iter_parameter_states().cloned().collect())
+            .collect();
 
-            let sub_model_solvers = state.solvers.get_mut(idx).unwrap();
-            let sub_model_states = state.states.get_mut(idx).unwrap();
+        // The values of the transfers of each network used in the previous iteration
+        let mut previous_transfers: Vec<Vec<Vec<f64>>> = Vec::with_capacity(self.networks.len());
 
-            // Perform sub-model step
-            entry
-                .network
-                .step(
+        let mut iterations = 0;
+        let stats = loop {
+            iterations += 1;
+
+            for (idx, entry) in self.networks.iter().enumerate() {
+                state.states[idx].set_states(&initial_states[idx]);
+                state.states[idx].set_parameter_states(&initial_parameter_states[idx]);
+                self.compute_inter_network_transfers(
+                    idx,
                     timestep,
                     scenario_indices,
-                    sub_model_solvers,
-                    sub_model_states,
-                    &mut timings,
-                )
-                .unwrap();
+                    &mut state.states,
+                    &mut state.transfer_means[idx],
+                )?;
 
-            let start_r_save = Instant::now();
+                let transfers = self.damp_inter_network_transfers(
+                    idx,
+                    scenario_indices,
+                    &mut state.states[idx],
+                    previous_transfers
+                        .get(idx)
+                        .filter(|_| iterations > 1)
+                        .map(|p| p.as_slice()),
+                    iteration.damping,
+                )?;
+                match previous_transfers.get_mut(idx) {
+                    Some(previous) => *previous = transfers,
+                    None => previous_transfers.push(transfers),
+                }
+
+                solve(
+                    &entry.network,
+                    timestep,
+                    scenario_indices,
+                    &mut state.solvers[idx],
+                    &mut state.states[idx],
+                    timings,
+                )?;
+            }
 
-            let sub_model_recorder_states = state.recorder_states.get_mut(idx).unwrap();
+            let mut residual: f64 = 0.0;
+            for idx in 0..self.networks.len() {
+                residual = residual.max(self.inter_network_transfer_residual(
+                    idx,
+                    timestep,
+                    scenario_indices,
+                    &state.states,
+                )?);
+            }
+
+            let converged = residual <= iteration.tolerance;
+            if converged || iterations >= iteration.max_iterations.get() {
+                break MultiNetworkIterationStats {
+                    iterations,
+                    residual,
+                    converged,
+                };
+            }
+        };
+
+        for (idx, entry) in self.networks.iter().enumerate() {
+            let sub_model_states = state.states.get_mut(idx).unwrap();
+            after(&entry.network, timestep, scenario_indices, sub_model_states, timings)?;
 
+            let start_r_save = Instant::now();
+            let sub_model_recorder_states = state.recorder_states.get_mut(idx).unwrap();
             entry
                 .network
                 .save_recorders(timestep, scenario_indices, sub_model_states, sub_model_recorder_states)?;
             timings.recorder_saving += start_r_save.elapsed();
         }
 
+        state.iteration_stats.push(stats);
+
         // Finally increment the time-step index
         state.current_time_step_idx += 1;
 
         Ok(())
     }
 
-    pub fn step_multi_scenario<S>(&self, state: &mut MultiNetworkModelState<Box<S>>) -> Result<(), PywrError>
+    /// Step the networks for the current time-step one stage at a time (see
+    /// [`MultiNetworkModel::stages`]).
+    ///
+    /// The networks of each stage are stepped concurrently on `thread_pool` if one is given, and
+    /// their recorders are saved once the whole stage is complete. `step` performs the time-step
+    /// of a single network with the solver type `T`.
+    fn step_stages<T, F>(
+        &self,
+        state: &mut MultiNetworkModelState<T>,
+        thread_pool: Option<&ThreadPool>,
+        step: F,
+    ) -> Result<(), PywrError>
     where
-        S: MultiStateSolver,
+        T: Send,
+        F: Fn(
+                &Network,
+                &Timestep,
+                &[ScenarioIndex],
+                &mut T,
+                &mut NetworkState,
+                &mut RunTimings,
+            ) -> Result<(), PywrError>
+            + Sync,
     {
-        let mut timings = RunTimings::default();
-
         let timestep = self
             .domain
             .time
@@ -308,34 +846,87 @@ impl MultiNetworkModel {
             .ok_or(PywrError::EndOfTimesteps)?;
 
         let scenario_indices = self.domain.scenarios.indices();
+        let mut timings = RunTimings::default();
 
-        for (idx, entry) in self.networks.iter().enumerate() {
-            // Perform inter-model state updates
-            self.compute_inter_network_transfers(idx, timestep, scenario_indices, &mut state.states)?;
-
-            let sub_model_solvers = state.solvers.get_mut(idx).unwrap();
-            let sub_model_states = state.states.get_mut(idx).unwrap();
-
-            // Perform sub-model step
-            entry
-                .network
-                .step_multi_scenario(
-                    timestep,
+        for stage in &self.stages {
+            // Only the networks with a time-step starting in this time-step of the model are stepped
+            let active: Vec<(usize, &Timestep)> = stage
+                .iter()
+                .filter_map(|idx| Some((*idx, self.network_timestep(*idx, timestep)?)))
+                .collect();
+
+            // Perform the inter-model state updates of the whole stage before any of its networks are stepped
+            for (idx, network_timestep) in &active {
+                self.compute_inter_network_transfers(
+                    *idx,
+                    network_timestep,
                     scenario_indices,
-                    sub_model_solvers,
-                    sub_model_states,
-                    &mut timings,
-                )
-                .unwrap();
+                    &mut state.states,
+                    &mut state.transfer_means[*idx],
+                )?;
+            }
 
-            let start_r_save = Instant::now();
+            match thread_pool {
+                Some(pool) if active.len() > 1 => {
+                    let sub_models: Vec<_> = state
+                        .solvers
+                        .iter_mut()
+                        .zip(state.states.iter_mut())
+                        .enumerate()
+                        .filter_map(|(idx, sub_model)| {
+                            let (_, network_timestep) = active.iter().find(|(i, _)| *i == idx)?;
+                            Some((idx, *network_timestep, sub_model))
+                        })
+                        .collect();
+
+                    pool.install(|| {
+                        sub_models.into_par_iter().try_for_each(
+                            |(idx, network_timestep, (sub_model_solvers, sub_model_states))| {
+                                let mut timings = RunTimings::default();
+                                step(
+                                    &self.networks[idx].network,
+                                    network_timestep,
+                                    scenario_indices,
+                                    sub_model_solvers,
+                                    sub_model_states,
+                                    &mut timings,
+                                )
+                            },
+                        )
+                    })?;
+                }
+                _ => {
+                    for (idx, network_timestep) in &active {
+                        step(
+                            &self.networks[*idx].network,
+                            network_timestep,
+                            scenario_indices,
+                            &mut state.solvers[*idx],
+                            &mut state.states[*idx],
+                            &mut timings,
+                        )?;
+                    }
+                }
+            }
 
-            let sub_model_recorder_states = state.recorder_states.get_mut(idx).unwrap();
+            for (idx, network_timestep) in &active {
+                self.update_transfer_means(
+                    *idx,
+                    network_timestep,
+                    scenario_indices,
+                    &state.states,
+                    &mut state.transfer_means,
+                )?;
 
-            entry
-                .network
-                .save_recorders(timestep, scenario_indices, sub_model_states, sub_model_recorder_states)?;
-            timings.recorder_saving += start_r_save.elapsed();
+                let start_r_save = Instant::now();
+                self.networks[*idx].network.save_recorders(
+                    network_timestep,
+                    scenario_indices,
+                    &state.states[*idx],
+                    &mut state.recorder_states[*idx],
+                )?;
+                timings.recorder_saving += start_r_save.elapsed();
+            }
         }
 
         // Finally increment the time-step index
@@ -344,27 +935,128 @@ impl MultiNetworkModel {
         Ok(())
     }
 
+    /// Log a summary of the iteration of the inter-network transfers.
+    fn report_iterations(&self, iteration_stats: &[MultiNetworkIterationStats]) {
+        if iteration_stats.is_empty() {
+            return;
+        }
+
+        let total: usize = iteration_stats.iter().map(|s| s.iterations).sum();
+        let max = iteration_stats.iter().map(|s| s.iterations).max().unwrap_or_default();
+        info!(
+            "Inter-network transfers iterated a mean of {:.2} times per time-step (maximum {})",
+            total as f64 / iteration_stats.len() as f64,
+            max
+        );
+
+        let unconverged = iteration_stats.iter().filter(|s| !s.converged).count();
+        if unconverged > 0 {
+            warn!(
+                "Inter-network transfers did not converge in {} of {} time-steps",
+                unconverged,
+                iteration_stats.len()
+            );
+        }
+    }
+
+    /// Perform a single time-step of the multi1-model.
+    pub fn step<S>(&self, state: &mut MultiNetworkModelState<Vec<Box<S>>>) -> Result<(), PywrError>
+    where
+        S: Solver,
+    {
+        self.step_with_thread_pool(state, None)
+    }
+
+    /// Perform a single time-step of the multi1-model, stepping the networks of each stage (see
+    /// [`MultiNetworkModel::stages`]) concurrently on `thread_pool` if one is given.
+    ///
+    /// When the transfers are iterated to convergence the networks are always stepped in order.
+    fn step_with_thread_pool<S>(
+        &self,
+        state: &mut MultiNetworkModelState<Vec<Box<S>>>,
+        thread_pool: Option<&ThreadPool>,
+    ) -> Result<(), PywrError>
+    where
+        S: Solver,
+    {
+        let mut timings = RunTimings::default();
+
+        if let Some(iteration) = &self.iteration {
+            return self.step_iterative(
+                state,
+                iteration,
+                &mut timings,
+                |network, timestep, scenario_indices, solvers, network_state, timings| {
+                    network.solve_step(timestep, scenario_indices, solvers, network_state, timings)
+                },
+                |network, timestep, scenario_indices, network_state, timings| {
+                    network.after_step(timestep, scenario_indices, network_state, timings)
+                },
+            );
+        }
+
+        self.step_stages(
+            state,
+            thread_pool,
+            |network, timestep, scenario_indices, solvers, network_state, timings| {
+                network.step(timestep, scenario_indices, solvers, network_state, timings)
+            },
+        )
+    }
+
+    pub fn step_multi_scenario<S>(&self, state: &mut MultiNetworkModelState<Box<S>>) -> Result<(), PywrError>
+    where
+        S: MultiStateSolver,
+    {
+        let mut timings = RunTimings::default();
+
+        if let Some(iteration) = &self.iteration {
+            return self.step_iterative(
+                state,
+                iteration,
+                &mut timings,
+                |network, timestep, scenario_indices, solver, network_state, timings| {
+                    network.solve_step_multi_scenario(timestep, scenario_indices, solver, network_state, timings)
+                },
+                |network, timestep, scenario_indices, network_state, timings| {
+                    network.after_step_multi_scenario(timestep, scenario_indices, network_state, timings)
+                },
+            );
+        }
+
+        self.step_stages(
+            state,
+            None,
+            |network, timestep, scenario_indices, solver, network_state, timings| {
+                network.step_multi_scenario(timestep, scenario_indices, solver, network_state, timings)
+            },
+        )
+    }
+
     /// Run the model through the given time-steps.
     ///
     /// This method will setup state and solvers, and then run the model through the time-steps.
-    pub fn run<S>(&self, settings: &S::Settings) -> Result<(), PywrError>
+    ///
+    /// Returns a [`FileManifest`] of the files written by the recorders of all the networks.
+    pub fn run<S>(&self, settings: &S::Settings) -> Result<FileManifest, PywrError>
     where
         S: Solver,
         <S as Solver>::Settings: SolverSettings,
     {
         let mut state = self.setup::<S>(settings)?;
 
-        self.run_with_state::<S>(&mut state, settings)?;
-
-        Ok(())
+        self.run_with_state::<S>(&mut state, settings)
     }
 
     /// Run the model with the provided states and solvers.
+    ///
+    /// If the solver settings are parallel the independent networks of each time-step are
+    /// stepped concurrently (see [`MultiNetworkModel::stages`]).
     pub fn run_with_state<S>(
         &self,
         state: &mut MultiNetworkModelState<Vec<Box<S>>>,
-        _settings: &S::Settings,
-    ) -> Result<(), PywrError>
+        settings: &S::Settings,
+    ) -> Result<FileManifest, PywrError>
     where
         S: Solver,
         <S as Solver>::Settings: SolverSettings,
@@ -372,10 +1064,20 @@ impl MultiNetworkModel {
         let mut timings = RunTimings::default();
         let mut count = 0;
 
-        // TODO: Setup thread pool if running in parallel
+        // Setup thread pool if running in parallel
+        let pool = if settings.parallel() {
+            Some(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(settings.threads())
+                    .build()
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
 
         loop {
-            match self.step::<S>(state) {
+            match self.step_with_thread_pool::<S>(state, pool.as_ref()) {
                 Ok(_) => {}
                 Err(PywrError::EndOfTimesteps) => break,
                 Err(e) => return Err(e),
@@ -384,31 +1086,37 @@ impl MultiNetworkModel {
             count += self.domain.scenarios.indices().len();
         }
 
+        let mut manifest = FileManifest::default();
         for (idx, entry) in self.networks.iter().enumerate() {
             let sub_model_ms_states = state.states.get_mut(idx).unwrap().all_metric_set_internal_states_mut();
             let sub_model_recorder_states = state.recorder_states.get_mut(idx).unwrap();
-            entry.network.finalise(sub_model_ms_states, sub_model_recorder_states)?;
+            manifest.extend(entry.network.finalise(sub_model_ms_states, sub_model_recorder_states)?);
         }
         // End the global timer and print the run statistics
         timings.finish(count);
         timings.print_table();
 
-        Ok(())
+        for (idx, entry) in self.networks.iter().enumerate() {
+            entry.network.report_parameter_clamping(&state.states[idx])?;
+        }
+        self.report_iterations(&state.iteration_stats);
+
+        Ok(manifest)
     }
 
     /// Run the model through the given time-steps.
     ///
     /// This method will setup state and solvers, and then run the model through the time-steps.
-    pub fn run_multi_scenario<S>(&self, settings: &S::Settings) -> Result<(), PywrError>
+    ///
+    /// Returns a [`FileManifest`] of the files written by the recorders of all the networks.
+    pub fn run_multi_scenario<S>(&self, settings: &S::Settings) -> Result<FileManifest, PywrError>
     where
         S: MultiStateSolver,
         <S as MultiStateSolver>::Settings: SolverSettings,
     {
         let mut state = self.setup_multi_scenario::<S>(settings)?;
 
-        self.run_multi_scenario_with_state::<S>(&mut state, settings)?;
-
-        Ok(())
+        self.run_multi_scenario_with_state::<S>(&mut state, settings)
     }
 
     /// Run the model with the provided states and solvers.
@@ -416,7 +1124,7 @@ impl MultiNetworkModel {
         &self,
         state: &mut MultiNetworkModelState<Box<S>>,
         _settings: &S::Settings,
-    ) -> Result<(), PywrError>
+    ) -> Result<FileManifest, PywrError>
     where
         S: MultiStateSolver,
         <S as MultiStateSolver>::Settings: SolverSettings,
@@ -436,69 +1144,207 @@ impl MultiNetworkModel {
             count += self.domain.scenarios.indices().len();
         }
 
+        let mut manifest = FileManifest::default();
         for (idx, entry) in self.networks.iter().enumerate() {
             let sub_model_ms_states = state.states.get_mut(idx).unwrap().all_metric_set_internal_states_mut();
             let sub_model_recorder_states = state.recorder_states.get_mut(idx).unwrap();
-            entry.network.finalise(sub_model_ms_states, sub_model_recorder_states)?;
+            manifest.extend(entry.network.finalise(sub_model_ms_states, sub_model_recorder_states)?);
         }
         // End the global timer and print the run statistics
         timings.finish(count);
         timings.print_table();
 
-        Ok(())
+        for (idx, entry) in self.networks.iter().enumerate() {
+            entry.network.report_parameter_clamping(&state.states[idx])?;
+        }
+        self.report_iterations(&state.iteration_stats);
+
+        Ok(manifest)
     }
 }
 
 /// Calculate inter-model parameters for the given scenario index.
 ///
 ///
-#[allow(clippy::too_many_arguments)] // This function is not too unreadable with 8 arguments.
+#[allow(clippy::too_many_arguments)] // This function is not too unreadable with 9 arguments.
 fn compute_inter_network_transfers(
     timestep: &Timestep,
     scenario_index: &ScenarioIndex,
     inter_network_transfers: &[MultiNetworkTransfer],
     state: &mut NetworkState,
+    transfer_means: &mut [Vec<TransferMean>],
     before_models: &[MultiNetworkEntry],
     before_states: &[NetworkState],
     after_models: &[MultiNetworkEntry],
     after_states: &[NetworkState],
 ) -> Result<(), PywrError> {
     // Iterate through all of the inter-model transfers
-    for (idx, parameter) in inter_network_transfers.iter().enumerate() {
-        // Determine which model and state we are getting the value from
-        let (other_model, other_model_state) = match parameter.from_model_idx {
-            OtherNetworkIndex::Before(i) => {
-                let rev_i = before_states.len() - i.get();
-                (&before_models[rev_i], &before_states[rev_i])
+    for (idx, (parameter, means)) in inter_network_transfers
+        .iter()
+        .zip(transfer_means.iter_mut())
+        .enumerate()
+    {
+        let idx = MultiNetworkTransferIndex(idx);
+
+        let value = if parameter.aggregated {
+            // The mean of the values sent since the transfer was last received
+            let sent = means[scenario_index.index].take();
+
+            if parameter.lag == 0 {
+                match timestep.is_first().then_some(parameter.initial_value).flatten() {
+                    Some(initial_value) => initial_value,
+                    None => sent.or(parameter.initial_value).unwrap_or_default(),
+                }
+            } else {
+                state
+                    .state_mut(scenario_index)
+                    .delay_inter_network_transfer_value(idx, sent.unwrap_or_default(), parameter.lag)?
+                    .or(parameter.initial_value)
+                    .unwrap_or_default()
             }
-            OtherNetworkIndex::After(i) => (&after_models[i.get() - 1], &after_states[i.get() - 1]),
-        };
+        } else if parameter.lag == 0 {
+            inter_network_transfer_value(
+                timestep,
+                scenario_index,
+                parameter,
+                before_models,
+                before_states,
+                after_models,
+                after_states,
+            )?
+        } else {
+            let sent = inter_network_transfer_sent_value(
+                scenario_index,
+                parameter,
+                before_models,
+                before_states,
+                after_models,
+                after_states,
+            )?;
 
-        let value = match timestep.is_first().then_some(parameter.initial_value).flatten() {
-            // Use the initial value if it is given and it is the first time-step.
-            Some(initial_value) => initial_value,
-            // Otherwise, get the value from the other model's state/metric
-            None => parameter
-                .from_metric
-                .get_value(&other_model.network, other_model_state.state(scenario_index))?,
+            state
+                .state_mut(scenario_index)
+                .delay_inter_network_transfer_value(idx, sent, parameter.lag)?
+                .or(parameter.initial_value)
+                .unwrap_or_default()
         };
 
         state
             .state_mut(scenario_index)
-            .set_inter_network_transfer_value(MultiNetworkTransferIndex(idx), value)?;
+            .set_inter_network_transfer_value(idx, value)?;
     }
 
     Ok(())
 }
 
+/// Calculate the value of an un-lagged inter-model transfer for the given scenario index.
+fn inter_network_transfer_value(
+    timestep: &Timestep,
+    scenario_index: &ScenarioIndex,
+    parameter: &MultiNetworkTransfer,
+    before_models: &[MultiNetworkEntry],
+    before_states: &[NetworkState],
+    after_models: &[MultiNetworkEntry],
+    after_states: &[NetworkState],
+) -> Result<f64, PywrError> {
+    let value = match timestep.is_first().then_some(parameter.initial_value).flatten() {
+        // Use the initial value if it is given and it is the first time-step.
+        Some(initial_value) => initial_value,
+        // Otherwise, get the value from the other model's state/metric
+        None => inter_network_transfer_sent_value(
+            scenario_index,
+            parameter,
+            before_models,
+            before_states,
+            after_models,
+            after_states,
+        )?,
+    };
+
+    Ok(value)
+}
+
+/// Calculate the value sent by an inter-model transfer in the current state of the other model,
+/// after any loss in transfer.
+fn inter_network_transfer_sent_value(
+    scenario_index: &ScenarioIndex,
+    parameter: &MultiNetworkTransfer,
+    before_models: &[MultiNetworkEntry],
+    before_states: &[NetworkState],
+    after_models: &[MultiNetworkEntry],
+    after_states: &[NetworkState],
+) -> Result<f64, PywrError> {
+    // Determine which model and state we are getting the value from
+    let (other_model, other_model_state) = match parameter.from_model_idx {
+        OtherNetworkIndex::Before(i) => {
+            let rev_i = before_states.len() - i.get();
+            (&before_models[rev_i], &before_states[rev_i])
+        }
+        OtherNetworkIndex::After(i) => (&after_models[i.get() - 1], &after_states[i.get() - 1]),
+    };
+
+    let value = parameter
+        .from_metric
+        .get_value(&other_model.network, other_model_state.state(scenario_index))?;
+
+    Ok(value * (1.0 - parameter.loss))
+}
+
+/// Map the time-steps of a network to the time-steps of the model.
+///
+/// Returns the index of the network's time-step that starts at each of the model's time-steps,
+/// or the reason the time-steps do not align.
+fn map_timesteps(model: &[Timestep], network: &[Timestep]) -> Result<Vec<Option<usize>>, String> {
+    let mut starts = vec![None; model.len()];
+    let model_end = model.last().map(|t| t.duration + t.date);
+    let mut model_idx = 0;
+
+    for (idx, timestep) in network.iter().enumerate() {
+        if model.get(model_idx).map(|t| t.date) != Some(timestep.date) {
+            return Err(format!(
+                "the time-step starting {} does not start at the start of a time-step of the model",
+                timestep.date
+            ));
+        }
+        starts[model_idx] = Some(idx);
+
+        let end = timestep.duration + timestep.date;
+        while model.get(model_idx).is_some_and(|t| t.date < end) {
+            model_idx += 1;
+        }
+
+        let boundary = model.get(model_idx).map(|t| t.date).or(model_end);
+        if boundary != Some(end) {
+            return Err(format!(
+                "the time-step starting {} does not end at the end of a time-step of the model",
+                timestep.date
+            ));
+        }
+    }
+
+    if model_idx != model.len() {
+        return Err("its time-steps do not cover all the time-steps of the model".to_string());
+    }
+
+    Ok(starts)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::MultiNetworkModel;
+    use super::{MultiNetworkIteration, MultiNetworkModel, MultiNetworkTransferIndex};
+    use crate::metric::MetricF64;
     use crate::models::ModelDomain;
     use crate::network::Network;
+    use crate::parameters::{Array1Parameter, ConsecutiveCountParameter, Predicate};
+    use crate::recorders::AssertionRecorder;
     use crate::scenario::ScenarioGroupCollection;
-    use crate::solvers::ClpSolver;
+    use crate::solvers::{ClpSolver, ClpSolverSettingsBuilder, LpExport, LpFileFormat};
     use crate::test_utils::{default_timestepper, simple_network};
+    use crate::timestep::{TimestepDuration, Timestepper};
+    use crate::PywrError;
+    use chrono::NaiveDate;
+    use ndarray::{Array1, Array2};
+    use std::num::NonZeroUsize;
 
     /// Test basic [`MultiNetworkModel`] functionality by running two independent models.
     #[test]
@@ -532,6 +1378,350 @@ mod tests {
         multi_model.step(&mut state).expect("Failed to step multi1-model.")
     }
 
+    /// Test the LP of each network is exported to its own file.
+    #[test]
+    fn test_multi_model_lp_export() {
+        let timestepper = default_timestepper();
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("test-scenario", 2);
+
+        let mut multi_model = MultiNetworkModel::new(ModelDomain::from(timestepper, scenario_collection).unwrap());
+
+        for name in ["network1", "network2"] {
+            let mut network = Network::default();
+            simple_network(&mut network, 0, 2);
+            multi_model.add_network(name, network).unwrap();
+        }
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let export = LpExport::new(dir.path().join("model.lp"), LpFileFormat::Lp, 2).with_scenario(1);
+        let settings = ClpSolverSettingsBuilder::default().export_lp(export).build();
+        multi_model.run::<ClpSolver>(&settings).unwrap();
+
+        for name in ["network1", "network2"] {
+            let data = std::fs::read_to_string(dir.path().join(format!("model-{name}.lp"))).unwrap();
+            assert!(data.contains("C0: input -> link, link -> output\n"));
+        }
+        assert!(!dir.path().join("model.lp").exists());
+    }
+
+    /// Test the inter-network transfers are iterated to convergence within each time-step.
+    #[test]
+    fn test_multi_model_iteration() {
+        let timestepper = default_timestepper();
+        let scenario_collection = ScenarioGroupCollection::default();
+
+        let mut multi_model = MultiNetworkModel::new(ModelDomain::from(timestepper, scenario_collection).unwrap());
+
+        // The supply of the first network is limited by the flow to the demand of the second
+        let mut network1 = Network::default();
+        let supply1 = network1.add_input_node("supply1", None).unwrap();
+        let demand1 = network1.add_output_node("demand1", None).unwrap();
+        network1.connect_nodes(supply1, demand1).unwrap();
+        network1
+            .set_node_max_flow(
+                "supply1",
+                None,
+                Some(MetricF64::InterNetworkTransfer(MultiNetworkTransferIndex(0))),
+            )
+            .unwrap();
+        network1.set_node_max_flow("demand1", None, Some(10.0.into())).unwrap();
+        network1.set_node_cost("demand1", None, Some((-10.0).into())).unwrap();
+
+        // Without iteration the first network would only see the previous time-step's flow
+        let expected = Array2::from_elem((15, 1), 5.0);
+        let recorder = AssertionRecorder::new("assert-demand1", MetricF64::NodeInFlow(demand1), expected, None, None);
+        network1.add_recorder(Box::new(recorder)).unwrap();
+
+        // The count is incremented once per time-step, however many iterations there are, because
+        // the internal states of the parameters are restored at the start of each iteration
+        let count = ConsecutiveCountParameter::new("count".into(), 1.0.into(), 0.0.into(), Predicate::GreaterThan);
+        let count_idx = network1.add_index_parameter(Box::new(count)).unwrap();
+        let expected = Array2::from_shape_fn((15, 1), |(i, _)| (i + 1) as f64);
+        let recorder = AssertionRecorder::new("assert-count", count_idx.into(), expected, None, None);
+        network1.add_recorder(Box::new(recorder)).unwrap();
+
+        let mut network2 = Network::default();
+        let supply2 = network2.add_input_node("supply2", None).unwrap();
+        let demand2 = network2.add_output_node("demand2", None).unwrap();
+        network2.connect_nodes(supply2, demand2).unwrap();
+        network2.set_node_max_flow("supply2", None, Some(5.0.into())).unwrap();
+        network2.set_node_max_flow("demand2", None, Some(100.0.into())).unwrap();
+        network2.set_node_cost("demand2", None, Some((-10.0).into())).unwrap();
+
+        let network1_idx = multi_model.add_network("network1", network1).unwrap();
+        let network2_idx = multi_model.add_network("network2", network2).unwrap();
+        multi_model
+            .add_inter_network_transfer(network2_idx, MetricF64::NodeInFlow(demand2), network1_idx, None, 0, 0.0)
+            .unwrap();
+
+        multi_model.set_iteration(Some(MultiNetworkIteration::new(1e-6, NonZeroUsize::new(10).unwrap())));
+
+        let mut state = multi_model.setup::<ClpSolver>(&Default::default()).unwrap();
+        multi_model
+            .run_with_state::<ClpSolver>(&mut state, &Default::default())
+            .unwrap();
+
+        // The first time-step needs a second iteration to use the flow of the second network
+        let iterations: Vec<_> = state.iteration_stats().iter().map(|s| s.iterations).collect();
+        assert_eq!(iterations[0], 2);
+        assert!(iterations[1..].iter().all(|&i| i == 1));
+        assert!(state.iteration_stats().iter().all(|s| s.converged));
+    }
+
+    /// Test the damping of the inter-network transfers between iterations.
+    #[test]
+    fn test_multi_model_iteration_damping() {
+        let iteration = MultiNetworkIteration::new(1e-6, NonZeroUsize::new(100).unwrap());
+        assert!(iteration.with_damping(0.0).is_err());
+        assert!(iteration.with_damping(1.5).is_err());
+        assert!(iteration.with_damping(f64::NAN).is_err());
+
+        let timestepper = default_timestepper();
+        let scenario_collection = ScenarioGroupCollection::default();
+
+        let mut multi_model = MultiNetworkModel::new(ModelDomain::from(timestepper, scenario_collection).unwrap());
+
+        // The supply of the first network is limited by the flow to the demand of the second
+        let mut network1 = Network::default();
+        let supply1 = network1.add_input_node("supply1", None).unwrap();
+        let demand1 = network1.add_output_node("demand1", None).unwrap();
+        network1.connect_nodes(supply1, demand1).unwrap();
+        network1
+            .set_node_max_flow(
+                "supply1",
+                None,
+                Some(MetricF64::InterNetworkTransfer(MultiNetworkTransferIndex(0))),
+            )
+            .unwrap();
+        network1.set_node_max_flow("demand1", None, Some(10.0.into())).unwrap();
+        network1.set_node_cost("demand1", None, Some((-10.0).into())).unwrap();
+
+        // The damped transfer converges to the same flow (within the tolerance), just more slowly
+        let expected = Array2::from_elem((15, 1), 5.0);
+        let recorder = AssertionRecorder::new(
+            "assert-demand1",
+            MetricF64::NodeInFlow(demand1),
+            expected,
+            None,
+            Some(1e-5),
+        );
+        network1.add_recorder(Box::new(recorder)).unwrap();
+
+        let mut network2 = Network::default();
+        let supply2 = network2.add_input_node("supply2", None).unwrap();
+        let demand2 = network2.add_output_node("demand2", None).unwrap();
+        network2.connect_nodes(supply2, demand2).unwrap();
+        network2.set_node_max_flow("supply2", None, Some(5.0.into())).unwrap();
+        network2.set_node_max_flow("demand2", None, Some(100.0.into())).unwrap();
+        network2.set_node_cost("demand2", None, Some((-10.0).into())).unwrap();
+
+        let network1_idx = multi_model.add_network("network1", network1).unwrap();
+        let network2_idx = multi_model.add_network("network2", network2).unwrap();
+        multi_model
+            .add_inter_network_transfer(network2_idx, MetricF64::NodeInFlow(demand2), network1_idx, None, 0, 0.0)
+            .unwrap();
+
+        multi_model.set_iteration(Some(iteration.with_damping(0.5).unwrap()));
+
+        let mut state = multi_model.setup::<ClpSolver>(&Default::default()).unwrap();
+        multi_model
+            .run_with_state::<ClpSolver>(&mut state, &Default::default())
+            .unwrap();
+
+        // Each iteration of the first time-step halves the distance of the transfer from five
+        let iterations: Vec<_> = state.iteration_stats().iter().map(|s| s.iterations).collect();
+        assert!(iterations[0] > 2);
+        assert!(iterations[1..].iter().all(|&i| i == 1));
+        assert!(state.iteration_stats().iter().all(|s| s.converged));
+    }
+
+    /// Test an inter-network transfer with a lag and a loss.
+    #[test]
+    fn test_multi_model_transfer_lag_and_loss() {
+        let timestepper = default_timestepper();
+        let scenario_collection = ScenarioGroupCollection::default();
+
+        let mut multi_model = MultiNetworkModel::new(ModelDomain::from(timestepper, scenario_collection).unwrap());
+
+        let mut network1 = Network::default();
+        let supply1 = network1.add_input_node("supply1", None).unwrap();
+        let demand1 = network1.add_output_node("demand1", None).unwrap();
+        network1.connect_nodes(supply1, demand1).unwrap();
+        network1.set_node_max_flow("supply1", None, Some(5.0.into())).unwrap();
+        network1.set_node_max_flow("demand1", None, Some(100.0.into())).unwrap();
+        network1.set_node_cost("demand1", None, Some((-10.0).into())).unwrap();
+
+        // The supply of the second network is the flow to the first network's demand two
+        // time-steps earlier, less 20% lost in transfer.
+        let mut network2 = Network::default();
+        let supply2 = network2.add_input_node("supply2", None).unwrap();
+        let demand2 = network2.add_output_node("demand2", None).unwrap();
+        network2.connect_nodes(supply2, demand2).unwrap();
+        network2
+            .set_node_max_flow(
+                "supply2",
+                None,
+                Some(MetricF64::InterNetworkTransfer(MultiNetworkTransferIndex(0))),
+            )
+            .unwrap();
+        network2.set_node_max_flow("demand2", None, Some(100.0.into())).unwrap();
+        network2.set_node_cost("demand2", None, Some((-10.0).into())).unwrap();
+
+        let mut expected = Array2::from_elem((15, 1), 4.0);
+        expected[[0, 0]] = 1.0;
+        expected[[1, 0]] = 1.0;
+        let recorder = AssertionRecorder::new("assert-demand2", MetricF64::NodeInFlow(demand2), expected, None, None);
+        network2.add_recorder(Box::new(recorder)).unwrap();
+
+        let network1_idx = multi_model.add_network("network1", network1).unwrap();
+        let network2_idx = multi_model.add_network("network2", network2).unwrap();
+
+        assert!(multi_model
+            .add_inter_network_transfer(network1_idx, MetricF64::NodeInFlow(demand1), network2_idx, None, 2, 1.5)
+            .is_err());
+        multi_model
+            .add_inter_network_transfer(
+                network1_idx,
+                MetricF64::NodeInFlow(demand1),
+                network2_idx,
+                Some(1.0),
+                2,
+                0.2,
+            )
+            .unwrap();
+
+        let mut state = multi_model.setup::<ClpSolver>(&Default::default()).unwrap();
+        multi_model
+            .run_with_state::<ClpSolver>(&mut state, &Default::default())
+            .unwrap();
+    }
+
+    /// Test the networks are grouped into stages by their transfers and stepped in parallel.
+    #[test]
+    fn test_multi_model_parallel_stages() {
+        let timestepper = default_timestepper();
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("test-scenario", 2);
+
+        let mut multi_model = MultiNetworkModel::new(ModelDomain::from(timestepper, scenario_collection).unwrap());
+
+        let mut networks = Vec::new();
+        for name in ["network1", "network2", "network3"] {
+            let mut network = Network::default();
+            simple_network(&mut network, 0, 2);
+            let output = network.get_node_index_by_name("output", None).unwrap();
+            networks.push((multi_model.add_network(name, network).unwrap(), output));
+        }
+        assert_eq!(multi_model.stages(), &[vec![0, 1, 2]]);
+
+        // The third network must be stepped after the first to receive its value for the time-step
+        multi_model
+            .add_inter_network_transfer(
+                networks[0].0,
+                MetricF64::NodeInFlow(networks[0].1),
+                networks[2].0,
+                None,
+                0,
+                0.0,
+            )
+            .unwrap();
+        assert_eq!(multi_model.stages(), &[vec![0, 1], vec![2]]);
+
+        // A transfer to an earlier network uses the value of the previous time-step
+        multi_model
+            .add_inter_network_transfer(
+                networks[1].0,
+                MetricF64::NodeInFlow(networks[1].1),
+                networks[0].0,
+                None,
+                0,
+                0.0,
+            )
+            .unwrap();
+        assert_eq!(multi_model.stages(), &[vec![0, 1], vec![2]]);
+
+        let settings = ClpSolverSettingsBuilder::default().parallel().threads(2).build();
+        let mut state = multi_model.setup::<ClpSolver>(&settings).unwrap();
+        multi_model.run_with_state::<ClpSolver>(&mut state, &settings).unwrap();
+    }
+
+    /// Test a weekly network receiving the mean of the values of a daily network.
+    #[test]
+    fn test_multi_model_network_domains() {
+        let scenario_collection = ScenarioGroupCollection::default();
+        let mut multi_model =
+            MultiNetworkModel::new(ModelDomain::from(default_timestepper(), scenario_collection).unwrap());
+
+        // The supply of the first network increases by one each day
+        let mut network1 = Network::default();
+        let supply1 = network1.add_input_node("supply1", None).unwrap();
+        let demand1 = network1.add_output_node("demand1", None).unwrap();
+        network1.connect_nodes(supply1, demand1).unwrap();
+        let inflow = Array1Parameter::new("inflow".into(), Array1::from_iter((1..=15).map(f64::from)), None);
+        let inflow = network1.add_simple_parameter(Box::new(inflow)).unwrap();
+        network1
+            .set_node_max_flow("supply1", None, Some(inflow.into()))
+            .unwrap();
+        network1.set_node_max_flow("demand1", None, Some(100.0.into())).unwrap();
+        network1.set_node_cost("demand1", None, Some((-10.0).into())).unwrap();
+
+        // The second network is stepped every five days
+        let mut network2 = Network::default();
+        let supply2 = network2.add_input_node("supply2", None).unwrap();
+        let demand2 = network2.add_output_node("demand2", None).unwrap();
+        network2.connect_nodes(supply2, demand2).unwrap();
+        network2
+            .set_node_max_flow(
+                "supply2",
+                None,
+                Some(MetricF64::InterNetworkTransfer(MultiNetworkTransferIndex(0))),
+            )
+            .unwrap();
+        network2.set_node_max_flow("demand2", None, Some(100.0.into())).unwrap();
+        network2.set_node_cost("demand2", None, Some((-10.0).into())).unwrap();
+
+        // The mean of the first day, and then of the five days since the previous time-step
+        let expected = Array2::from_shape_vec((3, 1), vec![1.0, 4.0, 9.0]).unwrap();
+        let recorder = AssertionRecorder::new("assert-demand2", MetricF64::NodeInFlow(demand2), expected, None, None);
+        network2.add_recorder(Box::new(recorder)).unwrap();
+
+        let domain = |days| {
+            let start = NaiveDate::from_ymd_opt(2020, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            let end = NaiveDate::from_ymd_opt(2020, 1, 15)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            let timestepper = Timestepper::new(start, end, TimestepDuration::Days(days));
+            ModelDomain::from(timestepper, ScenarioGroupCollection::default()).unwrap()
+        };
+
+        // Four day time-steps do not end at the end of the model's time-steps
+        assert!(multi_model
+            .add_network_with_domain("network2", Network::default(), domain(4))
+            .is_err());
+
+        let network1_idx = multi_model.add_network("network1", network1).unwrap();
+        let network2_idx = multi_model
+            .add_network_with_domain("network2", network2, domain(5))
+            .unwrap();
+        multi_model
+            .add_inter_network_transfer(network1_idx, MetricF64::NodeInFlow(demand1), network2_idx, None, 0, 0.0)
+            .unwrap();
+
+        let mut state = multi_model.setup::<ClpSolver>(&Default::default()).unwrap();
+        multi_model
+            .run_with_state::<ClpSolver>(&mut state, &Default::default())
+            .unwrap();
+
+        // The transfers can not be iterated with different time-steps
+        multi_model.set_iteration(Some(MultiNetworkIteration::new(1e-6, NonZeroUsize::new(10).unwrap())));
+        assert!(multi_model.setup::<ClpSolver>(&Default::default()).is_err());
+    }
+
     #[test]
     fn test_duplicate_network_names() {
         let timestepper = default_timestepper();
@@ -546,4 +1736,22 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_inter_network_transfer_network_not_found() {
+        let timestepper = default_timestepper();
+        let scenario_collection = ScenarioGroupCollection::default();
+
+        let mut multi_model = MultiNetworkModel::new(ModelDomain::from(timestepper, scenario_collection).unwrap());
+
+        let mut network = Network::default();
+        let supply = network.add_input_node("supply", None).unwrap();
+        let network_idx = multi_model.add_network("network1", network).unwrap();
+
+        for (from_idx, to_idx) in [(network_idx, 1), (1, network_idx)] {
+            let result =
+                multi_model.add_inter_network_transfer(from_idx, MetricF64::NodeOutFlow(supply), to_idx, None, 0, 0.0);
+            assert!(matches!(result, Err(PywrError::NetworkIndexNotFound(1))));
+        }
+    }
 }
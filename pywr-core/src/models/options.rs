@@ -0,0 +1,295 @@
+use crate::models::progress::ProgressReporter;
+use crate::models::{ProgressCallback, ProgressControl, ProgressFrequency};
+use crate::recorders::FileManifest;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A token used to cancel a model run from another thread.
+///
+/// The token is cheap to clone and all clones share the same state. A run checks the token at
+/// the end of every time-step; once [`CancellationToken::cancel`] has been called the run stops,
+/// finalises its recorders with the completed time-steps and returns
+/// [`PywrError::RunCancelled`](crate::PywrError::RunCancelled).
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request that any run using this token is stopped.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Optional settings for a model run.
+///
+/// # Examples
+///
+/// ```
+/// use pywr_core::models::{CancellationToken, RunOptions};
+///
+/// let token = CancellationToken::new();
+/// let options = RunOptions::default().cancellation_token(token.clone());
+///
+/// // The token can be moved to another thread and used to stop the run.
+/// std::thread::spawn(move || token.cancel());
+/// ```
+#[derive(Default)]
+pub struct RunOptions<'a> {
+    progress: Option<(&'a mut dyn ProgressCallback, ProgressFrequency)>,
+    cancellation_token: Option<CancellationToken>,
+    time_limit: Option<Duration>,
+}
+
+impl<'a> RunOptions<'a> {
+    /// Report the progress of the run to `callback` at the given `frequency`.
+    pub fn progress(mut self, callback: &'a mut dyn ProgressCallback, frequency: ProgressFrequency) -> Self {
+        self.progress = Some((callback, frequency));
+        self
+    }
+
+    /// Stop the run once `token` has been cancelled.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Stop the run once any of its scenarios has taken longer than `time_limit`.
+    ///
+    /// The limit applies to the wall-clock time spent computing and solving each scenario (see
+    /// [`NetworkState::scenario_elapsed`](crate::network::NetworkState::scenario_elapsed)). The
+    /// remaining time of a scenario is passed to its solver before each solve, so that a solve
+    /// that does not terminate is also stopped by solvers that support it (see
+    /// [`Solver::set_time_limit`](crate::solvers::Solver::set_time_limit)); otherwise the limit is
+    /// checked at the end of every time-step. A run that is stopped finalises its recorders with
+    /// the completed time-steps and returns [`PywrError::RunTimedOut`].
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+}
+
+/// The reason a run was stopped before its last time-step.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RunStop {
+    Cancelled,
+    TimedOut {
+        time_limit: Duration,
+        scenario_index: usize,
+    },
+}
+
+impl RunStop {
+    pub(crate) fn into_error(
+        self,
+        completed_timesteps: usize,
+        num_timesteps: usize,
+        manifest: FileManifest,
+    ) -> PywrError {
+        match self {
+            Self::Cancelled => PywrError::RunCancelled {
+                completed_timesteps,
+                num_timesteps,
+                manifest,
+            },
+            Self::TimedOut {
+                time_limit,
+                scenario_index,
+            } => PywrError::RunTimedOut {
+                time_limit,
+                scenario_index,
+                completed_timesteps,
+                num_timesteps,
+                manifest,
+            },
+        }
+    }
+}
+
+/// Applies the [`RunOptions`] at the end of each time-step of a run.
+pub(crate) struct RunMonitor<'a> {
+    progress: Option<ProgressReporter<'a>>,
+    cancellation_token: Option<CancellationToken>,
+    time_limit: Option<Duration>,
+}
+
+impl<'a> RunMonitor<'a> {
+    pub(crate) fn new(options: RunOptions<'a>, num_timesteps: usize) -> Self {
+        Self {
+            progress: options
+                .progress
+                .map(|(callback, frequency)| ProgressReporter::new(callback, frequency, num_timesteps)),
+            cancellation_token: options.cancellation_token,
+            time_limit: options.time_limit,
+        }
+    }
+
+    /// The time limit of each scenario of the run, if any.
+    pub(crate) fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    /// The reason to stop the run if any scenario has reached the time limit, given the time
+    /// spent on each scenario so far.
+    pub(crate) fn timed_out(&self, scenario_elapsed: &[Duration]) -> Option<RunStop> {
+        let time_limit = self.time_limit?;
+        scenario_elapsed
+            .iter()
+            .position(|elapsed| *elapsed >= time_limit)
+            .map(|scenario_index| RunStop::TimedOut {
+                time_limit,
+                scenario_index,
+            })
+    }
+
+    /// The reason to stop the run after a solve was stopped on reaching its time limit (see
+    /// [`PywrError::SolveTimeLimitReached`]).
+    ///
+    /// The scenario that has taken the longest is reported as the one that timed out.
+    pub(crate) fn solve_timed_out(&self, scenario_elapsed: &[Duration]) -> Result<RunStop, PywrError> {
+        let time_limit = self.time_limit.ok_or(PywrError::SolveTimeLimitReached)?;
+        let scenario_index = scenario_elapsed
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, elapsed)| **elapsed)
+            .map(|(idx, _)| idx)
+            .unwrap_or_default();
+
+        Ok(RunStop::TimedOut {
+            time_limit,
+            scenario_index,
+        })
+    }
+
+    /// Update the monitor after `timestep` has been completed.
+    ///
+    /// Returns the reason the run should be stopped, if it should be stopped.
+    pub(crate) fn update(&mut self, timestep: &Timestep, scenario_elapsed: &[Duration]) -> Option<RunStop> {
+        if let Some(progress) = &mut self.progress {
+            if progress.update(timestep) == ProgressControl::Cancel {
+                return Some(RunStop::Cancelled);
+            }
+        }
+
+        if let Some(token) = &self.cancellation_token {
+            if token.is_cancelled() {
+                return Some(RunStop::Cancelled);
+            }
+        }
+
+        self.timed_out(scenario_elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CancellationToken, RunMonitor, RunOptions, RunStop};
+    use crate::models::{Progress, ProgressControl, ProgressFrequency};
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+    use crate::PywrError;
+    use std::time::Duration;
+
+    #[test]
+    fn test_cancel_run_from_progress() {
+        let model = simple_model(1, None);
+
+        let mut callback = |p: &Progress| {
+            if p.completed_timesteps >= 3 {
+                ProgressControl::Cancel
+            } else {
+                ProgressControl::Continue
+            }
+        };
+
+        let result = model.run_with_progress::<ClpSolver>(
+            &ClpSolverSettings::default(),
+            &mut callback,
+            ProgressFrequency::Timestep,
+        );
+
+        assert!(matches!(
+            result,
+            Err(PywrError::RunCancelled {
+                completed_timesteps: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cancel_run_with_token() {
+        let model = simple_model(1, None);
+
+        // A token that is already cancelled stops the run after the first time-step
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let options = RunOptions::default().cancellation_token(token);
+        let result = model.run_with_options::<ClpSolver>(&ClpSolverSettings::default(), options);
+
+        match result {
+            Err(PywrError::RunCancelled {
+                completed_timesteps,
+                num_timesteps,
+                ..
+            }) => {
+                assert_eq!(completed_timesteps, 1);
+                assert_eq!(num_timesteps, model.domain().time().timesteps().len());
+            }
+            _ => panic!("Expected the run to be cancelled."),
+        }
+    }
+
+    #[test]
+    fn test_run_time_limit() {
+        let model = simple_model(1, None);
+
+        // A run that exceeds its time limit stops during or at the end of the first time-step
+        let options = RunOptions::default().time_limit(Duration::ZERO);
+        let result = model.run_with_options::<ClpSolver>(&ClpSolverSettings::default(), options);
+
+        match result {
+            Err(PywrError::RunTimedOut {
+                scenario_index,
+                completed_timesteps,
+                ..
+            }) => {
+                assert_eq!(scenario_index, 0);
+                assert!(completed_timesteps <= 1);
+            }
+            _ => panic!("Expected the run to time out."),
+        }
+
+        let options = RunOptions::default().time_limit(Duration::from_secs(3600));
+        assert!(model
+            .run_with_options::<ClpSolver>(&ClpSolverSettings::default(), options)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_scenario_time_limit() {
+        let monitor = RunMonitor::new(RunOptions::default().time_limit(Duration::from_secs(10)), 5);
+
+        // Only the second scenario has exceeded the limit
+        let elapsed = [Duration::from_secs(2), Duration::from_secs(12), Duration::from_secs(1)];
+        assert_eq!(
+            monitor.timed_out(&elapsed),
+            Some(RunStop::TimedOut {
+                time_limit: Duration::from_secs(10),
+                scenario_index: 1
+            })
+        );
+        assert_eq!(monitor.timed_out(&elapsed[..1]), None);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::timestep::Timestep;
+use chrono::NaiveDateTime;
+use std::num::NonZeroUsize;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// The progress of a model run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// The number of time-steps that have been completed.
+    pub completed_timesteps: usize,
+    /// The total number of time-steps in the run.
+    pub num_timesteps: usize,
+    /// The date of the last completed time-step.
+    pub date: NaiveDateTime,
+    /// The time elapsed since the start of the run.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// The fraction of the time-steps that have been completed.
+    pub fn fraction(&self) -> f64 {
+        if self.num_timesteps == 0 {
+            1.0
+        } else {
+            self.completed_timesteps as f64 / self.num_timesteps as f64
+        }
+    }
+
+    /// Estimate the time remaining assuming the remaining time-steps take as long as the
+    /// completed time-steps.
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.completed_timesteps == 0 {
+            return None;
+        }
+        let remaining = self.num_timesteps.saturating_sub(self.completed_timesteps) as u32;
+        Some(self.elapsed / self.completed_timesteps as u32 * remaining)
+    }
+}
+
+/// How often the progress of a run is reported.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ProgressFrequency {
+    /// Report the progress after every time-step.
+    #[default]
+    Timestep,
+    /// Report the progress every time the given percentage of the time-steps has been completed.
+    Percent(NonZeroUsize),
+}
+
+/// The response of a [`ProgressCallback`] to a progress report.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProgressControl {
+    /// Continue the run.
+    Continue,
+    /// Stop the run at the end of the current time-step.
+    Cancel,
+}
+
+/// A callback that receives the progress of a model run.
+///
+/// The callback is called by the thread running the model. A run can be stopped by returning
+/// [`ProgressControl::Cancel`], in which case the run returns [`PywrError::RunCancelled`](crate::PywrError::RunCancelled).
+pub trait ProgressCallback: Send {
+    fn on_progress(&mut self, progress: &Progress) -> ProgressControl;
+}
+
+impl<F> ProgressCallback for F
+where
+    F: FnMut(&Progress) -> ProgressControl + Send,
+{
+    fn on_progress(&mut self, progress: &Progress) -> ProgressControl {
+        self(progress)
+    }
+}
+
+/// A [`ProgressCallback`] that sends each report through a channel.
+///
+/// This allows the progress to be monitored from a different thread to the one running the
+/// model. The run is not affected if the receiver is dropped.
+pub struct ChannelProgress {
+    sender: Sender<Progress>,
+}
+
+impl ChannelProgress {
+    /// Create a new callback and the [`Receiver`] of its reports.
+    pub fn new() -> (Self, Receiver<Progress>) {
+        let (sender, receiver) = channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl ProgressCallback for ChannelProgress {
+    fn on_progress(&mut self, progress: &Progress) -> ProgressControl {
+        // Ignore the error if the receiver has been dropped
+        let _ = self.sender.send(progress.clone());
+        ProgressControl::Continue
+    }
+}
+
+/// Tracks the progress of a run and calls a [`ProgressCallback`] at the requested frequency.
+pub(crate) struct ProgressReporter<'a> {
+    callback: &'a mut dyn ProgressCallback,
+    frequency: ProgressFrequency,
+    num_timesteps: usize,
+    start: Instant,
+    next_percent: usize,
+}
+
+impl<'a> ProgressReporter<'a> {
+    pub(crate) fn new(
+        callback: &'a mut dyn ProgressCallback,
+        frequency: ProgressFrequency,
+        num_timesteps: usize,
+    ) -> Self {
+        let next_percent = match frequency {
+            ProgressFrequency::Timestep => 0,
+            ProgressFrequency::Percent(p) => p.get(),
+        };
+
+        Self {
+            callback,
+            frequency,
+            num_timesteps,
+            start: Instant::now(),
+            next_percent,
+        }
+    }
+
+    /// Update the progress after `timestep` has been completed.
+    ///
+    /// Returns the response of the callback, or [`ProgressControl::Continue`] if no report was due.
+    pub(crate) fn update(&mut self, timestep: &Timestep) -> ProgressControl {
+        let completed_timesteps = timestep.index + 1;
+
+        let report = match self.frequency {
+            ProgressFrequency::Timestep => true,
+            ProgressFrequency::Percent(p) => {
+                let percent = completed_timesteps * 100 / self.num_timesteps.max(1);
+                let due = percent >= self.next_percent || completed_timesteps >= self.num_timesteps;
+                if due {
+                    // Skip any thresholds already passed (e.g. when there are few time-steps)
+                    while self.next_percent <= percent {
+                        self.next_percent += p.get();
+                    }
+                }
+                due
+            }
+        };
+
+        if !report {
+            return ProgressControl::Continue;
+        }
+
+        let progress = Progress {
+            completed_timesteps,
+            num_timesteps: self.num_timesteps,
+            date: timestep.date,
+            elapsed: self.start.elapsed(),
+        };
+
+        self.callback.on_progress(&progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Progress, ProgressCallback, ProgressControl, ProgressFrequency, ProgressReporter};
+    use crate::timestep::{Timestep, TimestepIndex};
+    use chrono::{NaiveDate, TimeDelta};
+    use std::num::NonZeroUsize;
+
+    fn timestep(index: TimestepIndex) -> Timestep {
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            + TimeDelta::days(index as i64);
+        Timestep::new(date, index, TimeDelta::days(1).into())
+    }
+
+    #[test]
+    fn test_progress_percent() {
+        let mut reported = Vec::new();
+        let mut callback = |p: &Progress| {
+            reported.push(p.completed_timesteps);
+            ProgressControl::Continue
+        };
+
+        {
+            let callback: &mut dyn ProgressCallback = &mut callback;
+            let mut reporter =
+                ProgressReporter::new(callback, ProgressFrequency::Percent(NonZeroUsize::new(25).unwrap()), 10);
+            for i in 0..10 {
+                assert_eq!(reporter.update(&timestep(i)), ProgressControl::Continue);
+            }
+        }
+
+        assert_eq!(reported, vec![3, 5, 8, 10]);
+    }
+}
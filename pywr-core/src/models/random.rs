@@ -0,0 +1,364 @@
+use crate::models::{Model, ModelDomain};
+use crate::network::Network;
+use crate::node::StorageInitialVolume;
+use crate::parameters::Array2Parameter;
+use crate::scenario::ScenarioGroupCollection;
+use crate::timestep::{TimestepDuration, Timestepper};
+use crate::virtual_storage::{VirtualStorageBuilder, VirtualStorageReset};
+use crate::PywrError;
+use chrono::NaiveDate;
+use ndarray::Array2;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Normal};
+use rayon::prelude::*;
+
+/// The random number stream used to generate the connections between systems.
+const CONNECTION_STREAM: u64 = u64::MAX;
+/// The random number stream used to generate the scenario inflow factors.
+const SCENARIO_STREAM: u64 = u64::MAX - 1;
+
+/// A builder for generating random models of a configurable size.
+///
+/// The generated model contains a number of independent systems (an input, a link and an output
+/// node) connected by randomly chosen transfers. Optionally, a proportion of the systems can
+/// include a reservoir (storage node) upstream of the link, and a proportion can have a licence
+/// (virtual storage) limiting the total abstraction by the output over the simulation.
+///
+/// The model is fully determined by the seed. Each system is generated from its own stream of
+/// the random number generator, which means the systems can be generated in parallel and the
+/// same model is produced regardless of the number of threads. This makes the generator suitable
+/// for producing standard models for comparing the scaling of the solvers.
+///
+/// ```
+/// use pywr_core::models::RandomModelBuilder;
+///
+/// let model = RandomModelBuilder::new(10, 5, 4)
+///     .seed(42)
+///     .storage_fraction(0.5)
+///     .licence_fraction(0.25)
+///     .inflow_scenario_variation(0.2)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(model.domain().scenarios().len(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RandomModelBuilder {
+    num_systems: usize,
+    density: usize,
+    num_scenarios: usize,
+    seed: u64,
+    storage_fraction: f64,
+    licence_fraction: f64,
+    inflow_scenario_variation: f64,
+}
+
+/// The randomly generated data for a single system.
+struct SystemData {
+    inflow: Array2<f64>,
+    input_cost: f64,
+    demand: f64,
+    reservoir_volume: Option<f64>,
+    licence_volume: Option<f64>,
+}
+
+impl RandomModelBuilder {
+    /// Create a new builder for a model with `num_systems` systems and `num_scenarios` scenarios.
+    ///
+    /// The `density` is the percentage of all possible pairs of systems that are connected
+    /// by a transfer.
+    pub fn new(num_systems: usize, density: usize, num_scenarios: usize) -> Self {
+        Self {
+            num_systems,
+            density,
+            num_scenarios,
+            seed: 0,
+            storage_fraction: 0.0,
+            licence_fraction: 0.0,
+            inflow_scenario_variation: 0.0,
+        }
+    }
+
+    /// The seed of the random number generator.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The proportion (0.0 to 1.0) of systems that include a reservoir.
+    pub fn storage_fraction(mut self, fraction: f64) -> Self {
+        self.storage_fraction = fraction;
+        self
+    }
+
+    /// The proportion (0.0 to 1.0) of systems that have a licence on their output.
+    pub fn licence_fraction(mut self, fraction: f64) -> Self {
+        self.licence_fraction = fraction;
+        self
+    }
+
+    /// The coefficient of variation of the mean inflow between scenarios.
+    ///
+    /// Each scenario's inflows are scaled by a random factor with a mean of one. If this is
+    /// zero (the default) the inflows of each scenario have the same mean.
+    pub fn inflow_scenario_variation(mut self, variation: f64) -> Self {
+        self.inflow_scenario_variation = variation;
+        self
+    }
+
+    fn rng(&self, stream: u64) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(stream);
+        rng
+    }
+
+    fn validate(&self) -> Result<(), PywrError> {
+        if !(0.0..=1.0).contains(&self.storage_fraction) {
+            return Err(PywrError::InvalidModelGeneratorSettings(
+                "storage fraction must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.licence_fraction) {
+            return Err(PywrError::InvalidModelGeneratorSettings(
+                "licence fraction must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        if self.inflow_scenario_variation < 0.0 {
+            return Err(PywrError::InvalidModelGeneratorSettings(
+                "inflow scenario variation must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Generate the scaling factor applied to the inflows of each scenario.
+    fn scenario_factors(&self) -> Vec<f64> {
+        if self.inflow_scenario_variation == 0.0 {
+            return vec![1.0; self.num_scenarios];
+        }
+
+        let mut rng = self.rng(SCENARIO_STREAM);
+        let distr = Normal::new(1.0, self.inflow_scenario_variation).unwrap();
+        (0..self.num_scenarios)
+            .map(|_| distr.sample(&mut rng).max(0.0))
+            .collect()
+    }
+
+    /// Generate the data for the system with index `i`.
+    fn system_data(&self, i: usize, num_timesteps: usize, scenario_factors: &[f64]) -> SystemData {
+        let mut rng = self.rng(i as u64);
+
+        let inflow_distr: Normal<f64> = Normal::new(9.0, 1.0).unwrap();
+        let inflow = Array2::from_shape_fn((num_timesteps, scenario_factors.len()), |(_, s)| {
+            (inflow_distr.sample(&mut rng) * scenario_factors[s]).max(0.0)
+        });
+
+        let input_cost = rng.gen_range(-20.0..-5.00);
+
+        let outflow_distr = Normal::new(8.0, 3.0).unwrap();
+        let demand: f64 = outflow_distr.sample(&mut rng).max(0.0);
+
+        let reservoir_volume = rng.gen_bool(self.storage_fraction).then(|| rng.gen_range(50.0..500.0));
+
+        let licence_volume = rng
+            .gen_bool(self.licence_fraction)
+            .then(|| demand * num_timesteps as f64 * rng.gen_range(0.5..1.0));
+
+        SystemData {
+            inflow,
+            input_cost,
+            demand,
+            reservoir_volume,
+            licence_volume,
+        }
+    }
+
+    fn add_system(
+        network: &mut Network,
+        suffix: &str,
+        data: SystemData,
+        inflow_scenario_group_index: usize,
+    ) -> Result<(), PywrError> {
+        let input_idx = network.add_input_node("input", Some(suffix))?;
+        let link_idx = network.add_link_node("link", Some(suffix))?;
+        let output_idx = network.add_output_node("output", Some(suffix))?;
+
+        match data.reservoir_volume {
+            Some(max_volume) => {
+                let reservoir_idx = network.add_storage_node(
+                    "reservoir",
+                    Some(suffix),
+                    StorageInitialVolume::Proportional(0.5),
+                    None,
+                    Some(max_volume.into()),
+                )?;
+                network.set_node_cost("reservoir", Some(suffix), Some((-1.0).into()))?;
+
+                network.connect_nodes(input_idx, reservoir_idx)?;
+                network.connect_nodes(reservoir_idx, link_idx)?;
+            }
+            None => {
+                network.connect_nodes(input_idx, link_idx)?;
+            }
+        }
+        network.connect_nodes(link_idx, output_idx)?;
+
+        let inflow = Array2Parameter::new(
+            format!("inflow-{suffix}").as_str().into(),
+            data.inflow,
+            inflow_scenario_group_index,
+            None,
+        );
+        let idx = network.add_simple_parameter(Box::new(inflow))?;
+
+        network.set_node_max_flow("input", Some(suffix), Some(idx.into()))?;
+        network.set_node_cost("input", Some(suffix), Some(data.input_cost.into()))?;
+
+        network.set_node_max_flow("output", Some(suffix), Some(data.demand.into()))?;
+        network.set_node_cost("output", Some(suffix), Some((-500.0).into()))?;
+
+        if let Some(licence_volume) = data.licence_volume {
+            let builder = VirtualStorageBuilder::new("licence", &[output_idx])
+                .sub_name(suffix)
+                .initial_volume(StorageInitialVolume::Proportional(1.0))
+                .min_volume(Some(0.0.into()))
+                .max_volume(Some(licence_volume.into()))
+                .reset(VirtualStorageReset::Never);
+
+            network.add_virtual_storage_node(builder)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add random transfers between the systems.
+    fn add_connections(&self, network: &mut Network) -> Result<(), PywrError> {
+        if self.num_systems < 2 {
+            return Ok(());
+        }
+
+        let mut rng = self.rng(CONNECTION_STREAM);
+        let num_connections = (self.num_systems.pow(2) * self.density / 100 / 2).max(1);
+
+        let mut connections_added: usize = 0;
+
+        while connections_added < num_connections {
+            let i = rng.gen_range(0..self.num_systems);
+            let j = rng.gen_range(0..self.num_systems);
+
+            if i == j {
+                continue;
+            }
+
+            let name = format!("{i:04}->{j:04}");
+
+            if let Ok(idx) = network.add_link_node("transfer", Some(&name)) {
+                let transfer_cost = rng.gen_range(0.0..1.0);
+                network.set_node_cost("transfer", Some(&name), Some(transfer_cost.into()))?;
+
+                let from_suffix = format!("sys-{i:04}");
+                let from_idx = network.get_node_index_by_name("link", Some(&from_suffix))?;
+                let to_suffix = format!("sys-{j:04}");
+                let to_idx = network.get_node_index_by_name("link", Some(&to_suffix))?;
+
+                network.connect_nodes(from_idx, idx)?;
+                network.connect_nodes(idx, to_idx)?;
+
+                connections_added += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate the model.
+    pub fn build(&self) -> Result<Model, PywrError> {
+        self.validate()?;
+
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2020, 4, 9)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let duration = TimestepDuration::Days(1);
+        let timestepper = Timestepper::new(start, end, duration);
+
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("test-scenario", self.num_scenarios);
+
+        let domain = ModelDomain::from(timestepper, scenario_collection)?;
+
+        let inflow_scenario_group_index = domain
+            .scenarios()
+            .group_index("test-scenario")
+            .expect("Could not find scenario group.");
+
+        let (num_timesteps, _) = domain.shape();
+        let scenario_factors = self.scenario_factors();
+
+        // Generating the data is independent for each system; the order of the results is
+        // retained so the network is the same regardless of the number of threads.
+        let systems: Vec<SystemData> = (0..self.num_systems)
+            .into_par_iter()
+            .map(|i| self.system_data(i, num_timesteps, &scenario_factors))
+            .collect();
+
+        let mut network = Network::default();
+        for (i, data) in systems.into_iter().enumerate() {
+            let suffix = format!("sys-{i:04}");
+            Self::add_system(&mut network, &suffix, data, inflow_scenario_group_index)?;
+        }
+
+        self.add_connections(&mut network)?;
+
+        Ok(Model::new(domain, network))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RandomModelBuilder;
+    use crate::models::Model;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+
+    #[test]
+    fn test_deterministic() {
+        let builder = RandomModelBuilder::new(20, 5, 3)
+            .seed(7)
+            .storage_fraction(0.5)
+            .licence_fraction(0.5)
+            .inflow_scenario_variation(0.2);
+
+        let model1 = builder.build().unwrap();
+
+        // Generating with a single thread must produce the same model
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let model2 = pool.install(|| builder.build()).unwrap();
+
+        assert_eq!(model1.network().nodes().len(), model2.network().nodes().len());
+        for (n1, n2) in model1.network().nodes().iter().zip(model2.network().nodes().iter()) {
+            assert_eq!(n1.full_name(), n2.full_name());
+        }
+        assert_eq!(model1.network().structure_digest(), model2.network().structure_digest());
+
+        // The generated data must also be the same; the flows, volumes and parameter values at
+        // the end of a run are compared as these depend on the data of every time-step.
+        let settings = ClpSolverSettings::default();
+        let final_state = |model: &Model| {
+            let mut state = model.setup::<ClpSolver>(&settings).unwrap();
+            model.run_with_state::<ClpSolver>(&mut state, &settings).unwrap();
+            state.snapshot().to_bytes().unwrap()
+        };
+        assert_eq!(final_state(&model1), final_state(&model2));
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(RandomModelBuilder::new(2, 5, 1).storage_fraction(1.5).build().is_err());
+        assert!(RandomModelBuilder::new(2, 5, 1).licence_fraction(-0.1).build().is_err());
+    }
+}
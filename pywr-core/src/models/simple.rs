@@ -1,9 +1,19 @@
-use crate::models::ModelDomain;
+use crate::models::options::RunMonitor;
+use crate::models::{
+    CouplingTransport, InputFrame, ModelDomain, OutputFrame, ProgressCallback, ProgressFrequency, RunEstimate,
+    RunOptions,
+};
 use crate::network::{Network, NetworkState, RunTimings};
-use crate::solvers::{MultiStateSolver, Solver, SolverSettings};
+use crate::recorders::{FileManifest, OutputDescription, OutputMetric, ResultsFrame, RunInfo};
+use crate::snapshot::StateSnapshot;
+use crate::solvers::{
+    solve_horizon_lp, ClpSolver, HorizonLpBuilder, LpStatistics, MultiStateSolver, Solver, SolverSettings, WarmStart,
+};
+use crate::timestep::Timestep;
 use crate::PywrError;
 use rayon::ThreadPool;
 use std::any::Any;
+use std::num::NonZeroUsize;
 use std::time::Instant;
 use tracing::debug;
 
@@ -15,6 +25,11 @@ pub struct ModelState<S> {
 }
 
 impl<S> ModelState<S> {
+    /// The index of the next time-step to be run.
+    pub fn current_timestep_index(&self) -> usize {
+        self.current_time_step_idx
+    }
+
     pub fn network_state(&self) -> &NetworkState {
         &self.state
     }
@@ -26,6 +41,76 @@ impl<S> ModelState<S> {
     pub fn recorder_state(&self) -> &Vec<Option<Box<dyn Any>>> {
         &self.recorder_state
     }
+
+    /// The solver(s) used to solve the network's LPs.
+    pub fn solvers(&self) -> &S {
+        &self.solvers
+    }
+
+    /// Create a [`StateSnapshot`] of the current state of each scenario.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot::new(self.current_time_step_idx, self.state.states().to_vec())
+    }
+
+    /// Restore the state of each scenario from a [`StateSnapshot`].
+    ///
+    /// The model will continue from the time-step at which the snapshot was taken. The snapshot
+    /// must have been taken from a model with the same network and number of scenarios.
+    pub fn restore(&mut self, snapshot: StateSnapshot) -> Result<(), PywrError> {
+        let timestep_index = snapshot.timestep_index();
+        self.state.restore_snapshot(snapshot)?;
+        self.current_time_step_idx = timestep_index;
+        Ok(())
+    }
+}
+
+/// The result of a model run.
+///
+/// This contains the internal states of the recorders, which can be used to retrieve aggregated
+/// values, the [`ResultsFrame`] of each recorder that keeps its values in memory, and a
+/// [`FileManifest`] of all the files written by the recorders.
+pub struct ModelResult {
+    recorder_states: Vec<Option<Box<dyn Any>>>,
+    results: Vec<(String, ResultsFrame)>,
+    manifest: FileManifest,
+}
+
+impl ModelResult {
+    pub(crate) fn new(
+        network: &Network,
+        recorder_states: Vec<Option<Box<dyn Any>>>,
+        manifest: FileManifest,
+    ) -> Result<Self, PywrError> {
+        let results = network.get_recorder_results_frames(&recorder_states)?;
+        Ok(Self {
+            recorder_states,
+            results,
+            manifest,
+        })
+    }
+
+    pub fn recorder_states(&self) -> &Vec<Option<Box<dyn Any>>> {
+        &self.recorder_states
+    }
+
+    pub fn into_recorder_states(self) -> Vec<Option<Box<dyn Any>>> {
+        self.recorder_states
+    }
+
+    /// The files written by the recorders during the run.
+    pub fn manifest(&self) -> &FileManifest {
+        &self.manifest
+    }
+
+    /// The values saved by the recorder with `name`, if it keeps its values in memory.
+    pub fn results(&self, name: &str) -> Option<&ResultsFrame> {
+        self.results.iter().find(|(n, _)| n == name).map(|(_, frame)| frame)
+    }
+
+    /// The name and values of each recorder that keeps its values in memory.
+    pub fn results_frames(&self) -> &[(String, ResultsFrame)] {
+        &self.results
+    }
 }
 
 /// A standard Pywr model containing a single network.
@@ -53,6 +138,13 @@ impl Model {
         &mut self.network
     }
 
+    /// Describe the values that will be written by each of the model's outputs.
+    ///
+    /// This does not run the model; see [`Network::describe_outputs`].
+    pub fn describe_outputs(&self) -> Result<Vec<OutputDescription>, PywrError> {
+        self.network.describe_outputs(&self.domain)
+    }
+
     /// Check whether a solver [`S`] has the required features to run this model.
     pub fn check_solver_features<S>(&self) -> bool
     where
@@ -69,15 +161,33 @@ impl Model {
         self.network.check_multi_scenario_solver_features::<S>()
     }
 
+    /// Build the linear program for the first scenario and return statistics of its constraint matrix.
+    ///
+    /// This can be used to diagnose numerical problems before running the model.
+    pub fn lp_statistics(&self) -> Result<LpStatistics, PywrError> {
+        let timesteps = self.domain.time.timesteps();
+        let scenario_indices = &self.domain.scenarios.indices()[..1];
+
+        let state = self.network.setup_network(timesteps, scenario_indices, 0)?;
+        self.network.lp_statistics(&scenario_indices[0], &state)
+    }
+
     pub fn setup<S>(&self, settings: &S::Settings) -> Result<ModelState<Vec<Box<S>>>, PywrError>
     where
         S: Solver,
+        <S as Solver>::Settings: SolverSettings,
     {
         let timesteps = self.domain.time.timesteps();
         let scenario_indices = self.domain.scenarios.indices();
 
-        let state = self.network.setup_network(timesteps, scenario_indices, 0)?;
-        let recorder_state = self.network.setup_recorders(&self.domain)?;
+        let mut state = self.network.setup_network(timesteps, scenario_indices, 0)?;
+        if let Some(export) = settings.lp_export() {
+            export.check(timesteps.len(), scenario_indices.len())?;
+            state.set_lp_export(export.clone(), settings.tie_breaking());
+        }
+        let mut recorder_state = self.network.setup_recorders(&self.domain)?;
+        let run = RunInfo::new(S::name()).with_solver_settings(settings);
+        self.network.start_recorders(&run, &mut recorder_state)?;
         let solvers = self.network.setup_solver::<S>(scenario_indices, &state, settings)?;
 
         Ok(ModelState {
@@ -91,12 +201,19 @@ impl Model {
     pub fn setup_multi_scenario<S>(&self, settings: &S::Settings) -> Result<ModelState<Box<S>>, PywrError>
     where
         S: MultiStateSolver,
+        <S as MultiStateSolver>::Settings: SolverSettings,
     {
         let timesteps = self.domain.time.timesteps();
         let scenario_indices = self.domain.scenarios.indices();
 
-        let state = self.network.setup_network(timesteps, scenario_indices, 0)?;
-        let recorder_state = self.network.setup_recorders(&self.domain)?;
+        let mut state = self.network.setup_network(timesteps, scenario_indices, 0)?;
+        if let Some(export) = settings.lp_export() {
+            export.check(timesteps.len(), scenario_indices.len())?;
+            state.set_lp_export(export.clone(), settings.tie_breaking());
+        }
+        let mut recorder_state = self.network.setup_recorders(&self.domain)?;
+        let run = RunInfo::new(S::name()).with_solver_settings(settings);
+        self.network.start_recorders(&run, &mut recorder_state)?;
         let solvers = self
             .network
             .setup_multi_scenario_solver::<S>(scenario_indices, settings)?;
@@ -157,6 +274,59 @@ impl Model {
         Ok(())
     }
 
+    /// Compute the components of the network for the next time-step of `state` without solving it.
+    ///
+    /// This updates the values of the parameters in `state` so that the effective bounds and
+    /// costs of the nodes can be inspected (see [`Network::get_node_flow_bounds`] and
+    /// [`Network::get_node_cost`]). The time-step is returned. The state should not be used to
+    /// continue the run afterwards.
+    pub fn compute_step<S>(&self, state: &mut ModelState<S>) -> Result<Timestep, PywrError> {
+        let timestep = *self
+            .domain
+            .time
+            .timesteps()
+            .get(state.current_time_step_idx)
+            .ok_or(PywrError::EndOfTimesteps)?;
+
+        self.network
+            .compute_step(&timestep, self.domain.scenarios.indices(), &mut state.state)?;
+
+        Ok(timestep)
+    }
+
+    /// Compute the parameters of the network for the time-step `timestep_index` without running
+    /// the model.
+    ///
+    /// Unlike [`Model::compute_step`] no solvers or recorders are set up, and the preceding
+    /// time-steps are not run. The parameters are computed from the initial state of the
+    /// network, or from `snapshot` if one is given; the snapshot must not be after the
+    /// time-step. The time-step and the state of the network, from which the values of the
+    /// parameters and the effective bounds of the nodes can be read, are returned.
+    pub fn compute_parameters(
+        &self,
+        timestep_index: usize,
+        snapshot: Option<&StateSnapshot>,
+    ) -> Result<(Timestep, NetworkState), PywrError> {
+        let timesteps = self.domain.time.timesteps();
+        let timestep = *timesteps.get(timestep_index).ok_or(PywrError::EndOfTimesteps)?;
+        let scenario_indices = self.domain.scenarios.indices();
+
+        let mut state = self.network.setup_network(timesteps, scenario_indices, 0)?;
+        if let Some(snapshot) = snapshot {
+            if snapshot.timestep_index() > timestep_index {
+                return Err(PywrError::SnapshotAfterTimestep {
+                    snapshot: snapshot.timestep_index(),
+                    timestep: timestep_index,
+                });
+            }
+            state.restore_snapshot(snapshot.clone())?;
+        }
+
+        self.network.compute_step(&timestep, scenario_indices, &mut state)?;
+
+        Ok((timestep, state))
+    }
+
     pub fn step_multi_scenario<S>(
         &self,
         state: &mut ModelState<Box<S>>,
@@ -200,28 +370,196 @@ impl Model {
     /// Run a model through the given time-steps.
     ///
     /// This method will setup state and solvers, and then run the model through the time-steps.
-    pub fn run<S>(&self, settings: &S::Settings) -> Result<Vec<Option<Box<dyn Any>>>, PywrError>
+    pub fn run<S>(&self, settings: &S::Settings) -> Result<ModelResult, PywrError>
     where
         S: Solver,
         <S as Solver>::Settings: SolverSettings,
     {
         let mut state = self.setup::<S>(settings)?;
 
-        self.run_with_state::<S>(&mut state, settings)?;
+        let manifest = self.run_with_state::<S>(&mut state, settings)?;
 
-        Ok(state.recorder_state)
+        ModelResult::new(&self.network, state.recorder_state, manifest)
+    }
+
+    /// Run a model through the given time-steps with the given [`RunOptions`].
+    ///
+    /// See [`Model::run_with_state_and_options`].
+    pub fn run_with_options<S>(&self, settings: &S::Settings, options: RunOptions) -> Result<ModelResult, PywrError>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings,
+    {
+        let mut state = self.setup::<S>(settings)?;
+
+        let manifest = self.run_with_state_and_options::<S>(&mut state, settings, options)?;
+
+        ModelResult::new(&self.network, state.recorder_state, manifest)
+    }
+
+    /// Run a model through the given time-steps while reporting its progress.
+    ///
+    /// The `callback` is called with the [`Progress`](crate::models::Progress) of the run at the
+    /// given `frequency`. This is a shorthand for [`Model::run_with_options`] with
+    /// [`RunOptions::progress`].
+    pub fn run_with_progress<S>(
+        &self,
+        settings: &S::Settings,
+        callback: &mut dyn ProgressCallback,
+        frequency: ProgressFrequency,
+    ) -> Result<ModelResult, PywrError>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings,
+    {
+        self.run_with_options::<S>(settings, RunOptions::default().progress(callback, frequency))
+    }
+
+    /// Run the model with the provided states and solvers while reporting its progress.
+    ///
+    /// See [`Model::run_with_progress`].
+    pub fn run_with_state_and_progress<S>(
+        &self,
+        state: &mut ModelState<Vec<Box<S>>>,
+        settings: &S::Settings,
+        callback: &mut dyn ProgressCallback,
+        frequency: ProgressFrequency,
+    ) -> Result<FileManifest, PywrError>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings,
+    {
+        self.run_with_state_and_options::<S>(state, settings, RunOptions::default().progress(callback, frequency))
+    }
+
+    /// Run the model as a single linear program over all of its time-steps.
+    ///
+    /// Rather than solving each time-step in sequence, the time-steps of each scenario are linked
+    /// together by the continuity of the storage nodes and solved at once with Clp. This gives the
+    /// perfect-foresight allocation of a deterministic planning study. The flows of the solution
+    /// are then applied to the network one time-step at a time, and saved by the recorders as in
+    /// a sequential run.
+    ///
+    /// The parameters of every time-step are computed before the linear program is solved, from
+    /// the initial state of the network. Parameters that depend on the state of the network (e.g.
+    /// storage control curves) therefore do not respond to the solution. The same parameter values
+    /// are used when the solution is applied, so that the recorded values are those of the linear
+    /// program that was solved. Virtual storage nodes and binary variables are not supported.
+    pub fn run_whole_horizon(&self) -> Result<ModelResult, PywrError> {
+        let timesteps = self.domain.time.timesteps();
+        let scenario_indices = self.domain.scenarios.indices();
+        let mut timings = RunTimings::default();
+
+        // Compute the parameters of every time-step and build the linear program of each scenario
+        let mut state = self.network.setup_network(timesteps, scenario_indices, 0)?;
+        let mut builders = scenario_indices
+            .iter()
+            .map(|scenario_index| {
+                let values = state.state(scenario_index).get_const_parameter_values();
+                HorizonLpBuilder::<i32>::new(&self.network, &values)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The parameter values of each time-step (by scenario) used in the linear programs
+        let mut parameter_values = Vec::with_capacity(timesteps.len());
+        for timestep in timesteps {
+            self.network.compute_step(timestep, scenario_indices, &mut state)?;
+            for (scenario_index, builder) in scenario_indices.iter().zip(builders.iter_mut()) {
+                builder.add_timestep(&self.network, timestep, state.state(scenario_index))?;
+            }
+            parameter_values.push(
+                scenario_indices
+                    .iter()
+                    .map(|scenario_index| state.state(scenario_index).parameter_values().clone())
+                    .collect::<Vec<_>>(),
+            );
+            self.network
+                .after_step(timestep, scenario_indices, &mut state, &mut timings)?;
+        }
+
+        let solutions = builders
+            .into_iter()
+            .map(|builder| {
+                let (lp, solution) = builder.build();
+                let values = solve_horizon_lp(&lp)?;
+                Ok((solution, values))
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        // Replay the run with the flows of the solutions to update the state and save the recorders
+        let mut state = self.network.setup_network(timesteps, scenario_indices, 0)?;
+        let mut recorder_states = self.network.setup_recorders(&self.domain)?;
+        let run = RunInfo::new(&format!("{} (whole horizon)", ClpSolver::name()));
+        self.network.start_recorders(&run, &mut recorder_states)?;
+
+        for ((step, timestep), step_parameter_values) in timesteps.iter().enumerate().zip(parameter_values) {
+            // The parameters are computed to update their internal state and the derived metrics,
+            // but those that depend on the state of the network would differ from the values used
+            // in the linear program, so the values of the first pass are restored.
+            self.network.compute_step(timestep, scenario_indices, &mut state)?;
+
+            for ((scenario_index, (solution, values)), step_parameter_values) in
+                scenario_indices.iter().zip(solutions.iter()).zip(step_parameter_values)
+            {
+                let scenario_state = state.state_mut(scenario_index);
+                scenario_state.set_parameter_values(step_parameter_values);
+                let network_state = scenario_state.get_mut_network_state();
+                network_state.reset();
+                for edge in self.network.edges().iter() {
+                    let flow = solution.edge_flow(values, step, &edge.index());
+                    network_state.add_flow(edge, timestep, flow)?;
+                }
+                scenario_state.complete(&self.network, timestep)?;
+            }
+
+            self.network
+                .after_step(timestep, scenario_indices, &mut state, &mut timings)?;
+            self.network
+                .save_recorders(timestep, scenario_indices, &state, &mut recorder_states)?;
+        }
+
+        let manifest = self
+            .network
+            .finalise(state.all_metric_set_internal_states_mut(), &mut recorder_states)?;
+
+        ModelResult::new(&self.network, recorder_states, manifest)
     }
 
     /// Run the model with the provided states and solvers.
+    ///
+    /// Returns a [`FileManifest`] of the files written by the recorders.
     pub fn run_with_state<S>(
         &self,
         state: &mut ModelState<Vec<Box<S>>>,
         settings: &S::Settings,
-    ) -> Result<(), PywrError>
+    ) -> Result<FileManifest, PywrError>
     where
         S: Solver,
         <S as Solver>::Settings: SolverSettings,
     {
+        self.run_with_state_and_options::<S>(state, settings, RunOptions::default())
+    }
+
+    /// Run the model with the provided states and solvers and the given [`RunOptions`].
+    ///
+    /// The progress of the run is reported, and the cancellation token and time limit are checked,
+    /// at the end of each time-step. If the run is cancelled the recorders are finalised with the
+    /// time-steps completed so far and [`PywrError::RunCancelled`] is returned, or
+    /// [`PywrError::RunTimedOut`] if a scenario exceeded its time limit (see
+    /// [`RunOptions::time_limit`]). The time limit is also passed to the solvers, so that a
+    /// solve can be stopped part way through a time-step.
+    pub fn run_with_state_and_options<S>(
+        &self,
+        state: &mut ModelState<Vec<Box<S>>>,
+        settings: &S::Settings,
+        options: RunOptions,
+    ) -> Result<FileManifest, PywrError>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings,
+    {
+        let timesteps = self.domain.time.timesteps();
+        let mut monitor = RunMonitor::new(options, timesteps.len());
         let mut timings = RunTimings::default();
         let mut count = 0;
 
@@ -237,31 +575,202 @@ impl Model {
             None
         };
 
+        let mut stopped = None;
+        state.state.set_scenario_time_limit(monitor.time_limit());
+
         loop {
             match self.step::<S>(state, pool.as_ref(), &mut timings) {
                 Ok(_) => {}
                 Err(PywrError::EndOfTimesteps) => break,
+                Err(PywrError::SolveTimeLimitReached) => {
+                    // A scenario reached its time limit part way through the time-step
+                    let stop = monitor.solve_timed_out(state.state.scenario_elapsed())?;
+                    stopped = Some((stop, state.current_time_step_idx));
+                    break;
+                }
                 Err(e) => return Err(e),
             }
 
             count += self.domain.scenarios.indices().len();
+
+            let timestep = &timesteps[state.current_time_step_idx - 1];
+            if let Some(stop) = monitor.update(timestep, state.state.scenario_elapsed()) {
+                stopped = Some((stop, state.current_time_step_idx));
+                break;
+            }
         }
 
-        self.network.finalise(
+        let manifest = self.network.finalise(
             state.state.all_metric_set_internal_states_mut(),
             &mut state.recorder_state,
         )?;
         // End the global timer and print the run statistics
         timings.finish(count);
         timings.print_table();
+        self.network.report_parameter_clamping(&state.state)?;
+
+        match stopped {
+            Some((stop, completed_timesteps)) => Err(stop.into_error(completed_timesteps, timesteps.len(), manifest)),
+            None => Ok(manifest),
+        }
+    }
+
+    /// Export the current state of the solver of each scenario as a [`WarmStart`].
+    ///
+    /// This is typically called at the end of a run (see [`Model::run_with_state`]) so that the
+    /// final states can be imported in to a subsequent run of the same model with
+    /// [`Model::import_warm_start`]. An error is returned if the solver does not support
+    /// warm-starting.
+    pub fn export_warm_start<S>(&self, state: &ModelState<Vec<Box<S>>>) -> Result<WarmStart, PywrError>
+    where
+        S: Solver,
+    {
+        let states = state
+            .solvers
+            .iter()
+            .map(|solver| {
+                solver
+                    .export_warm_state()
+                    .ok_or_else(|| PywrError::WarmStartNotSupported(S::name().to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(WarmStart::new(S::name(), self.network.structure_digest(), states))
+    }
+
+    /// Import a [`WarmStart`] in to the solver of each scenario.
+    ///
+    /// The warm start must have been exported using the same solver from a model with the
+    /// same network structure and number of scenarios.
+    pub fn import_warm_start<S>(
+        &self,
+        state: &mut ModelState<Vec<Box<S>>>,
+        warm_start: &WarmStart,
+    ) -> Result<(), PywrError>
+    where
+        S: Solver,
+    {
+        warm_start.check_compatible(S::name(), &self.network.structure_digest(), state.solvers.len())?;
+
+        for (solver, warm_state) in state.solvers.iter_mut().zip(warm_start.states()) {
+            solver.import_warm_state(warm_state)?;
+        }
 
         Ok(())
     }
 
+    /// Estimate the run time and output size of the model.
+    ///
+    /// The model is setup and run for the first `num_timesteps` time-steps of the first
+    /// `num_scenarios` scenarios only, and the timings are extrapolated to the full run. Recorders
+    /// are not setup or saved during the sample. See [`RunEstimate`] for the assumptions made.
+    pub fn estimate<S>(
+        &self,
+        settings: &S::Settings,
+        num_timesteps: NonZeroUsize,
+        num_scenarios: NonZeroUsize,
+    ) -> Result<RunEstimate, PywrError>
+    where
+        S: Solver,
+    {
+        let timesteps = self.domain.time.timesteps();
+        let scenario_indices = self.domain.scenarios.indices();
+
+        let sample_timesteps = &timesteps[..num_timesteps.get().min(timesteps.len())];
+        let sample_scenarios = &scenario_indices[..num_scenarios.get().min(scenario_indices.len())];
+
+        let start = Instant::now();
+        let mut state = self.network.setup_network(timesteps, sample_scenarios, 0)?;
+        let mut solvers = self.network.setup_solver::<S>(sample_scenarios, &state, settings)?;
+        let sample_setup_time = start.elapsed();
+
+        let mut timings = RunTimings::default();
+        let start = Instant::now();
+        for timestep in sample_timesteps {
+            self.network
+                .step(timestep, sample_scenarios, &mut solvers, &mut state, &mut timings)?;
+        }
+        let sample_run_time = start.elapsed();
+
+        let values_per_step = self
+            .network
+            .metric_sets()
+            .iter()
+            .map(|metric_set| metric_set.iter_metrics().count())
+            .sum();
+
+        Ok(RunEstimate::new(
+            (sample_timesteps.len(), sample_scenarios.len()),
+            self.domain.shape(),
+            sample_setup_time,
+            sample_run_time,
+            values_per_step,
+        ))
+    }
+
+    /// Run the model while exchanging values with an external process every time-step.
+    ///
+    /// Before each time-step an [`InputFrame`] is received from the `transport` and its values are
+    /// assigned to the network's external inputs. Once the time-step has been solved the values of
+    /// `outputs` for every scenario are sent back as an [`OutputFrame`]. This allows the model to be
+    /// coupled with another simulation (e.g. a groundwater or energy model) that must exchange
+    /// state with it every time-step.
+    pub fn run_coupled<S, T>(
+        &self,
+        settings: &S::Settings,
+        transport: &mut T,
+        outputs: &[OutputMetric],
+    ) -> Result<ModelResult, PywrError>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings,
+        T: CouplingTransport,
+    {
+        let mut state = self.setup::<S>(settings)?;
+        let mut timings = RunTimings::default();
+        let mut count = 0;
+
+        // Setup thread pool if running in parallel
+        let pool = if settings.parallel() {
+            Some(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(settings.threads())
+                    .build()
+                    .map_err(|e| PywrError::ThreadPoolBuildError(e.to_string()))?,
+            )
+        } else {
+            None
+        };
+
+        let scenario_indices = self.domain.scenarios.indices();
+
+        for timestep in self.domain.time.timesteps() {
+            let frame: InputFrame = transport.receive()?;
+            frame.apply(timestep, &self.network, scenario_indices, &mut state.state)?;
+
+            self.step::<S>(&mut state, pool.as_ref(), &mut timings)?;
+            count += scenario_indices.len();
+
+            let frame = OutputFrame::from_state(timestep, &self.network, scenario_indices, &state.state, outputs)?;
+            transport.send(&frame)?;
+        }
+
+        let manifest = self.network.finalise(
+            state.state.all_metric_set_internal_states_mut(),
+            &mut state.recorder_state,
+        )?;
+        // End the global timer and print the run statistics
+        timings.finish(count);
+        timings.print_table();
+        self.network.report_parameter_clamping(&state.state)?;
+
+        ModelResult::new(&self.network, state.recorder_state, manifest)
+    }
+
     /// Run a network through the given time-steps with [`MultiStateSolver`].
     ///
     /// This method will setup state and the solver, and then run the network through the time-steps.
-    pub fn run_multi_scenario<S>(&self, settings: &S::Settings) -> Result<Vec<Option<Box<dyn Any>>>, PywrError>
+    pub fn run_multi_scenario<S>(&self, settings: &S::Settings) -> Result<ModelResult, PywrError>
     where
         S: MultiStateSolver,
         <S as MultiStateSolver>::Settings: SolverSettings,
@@ -269,21 +778,100 @@ impl Model {
         // Setup the network and create the initial state
         let mut state = self.setup_multi_scenario(settings)?;
 
-        self.run_multi_scenario_with_state::<S>(&mut state, settings)?;
+        let manifest = self.run_multi_scenario_with_state::<S>(&mut state, settings)?;
+
+        ModelResult::new(&self.network, state.recorder_state, manifest)
+    }
+
+    /// Run a network through the given time-steps with [`MultiStateSolver`] and the given [`RunOptions`].
+    ///
+    /// See [`Model::run_multi_scenario_with_state_and_options`].
+    pub fn run_multi_scenario_with_options<S>(
+        &self,
+        settings: &S::Settings,
+        options: RunOptions,
+    ) -> Result<ModelResult, PywrError>
+    where
+        S: MultiStateSolver,
+        <S as MultiStateSolver>::Settings: SolverSettings,
+    {
+        let mut state = self.setup_multi_scenario(settings)?;
+
+        let manifest = self.run_multi_scenario_with_state_and_options::<S>(&mut state, settings, options)?;
 
-        Ok(state.recorder_state)
+        ModelResult::new(&self.network, state.recorder_state, manifest)
+    }
+
+    /// Run a network through the given time-steps with [`MultiStateSolver`] while reporting its
+    /// progress.
+    ///
+    /// See [`Model::run_with_progress`].
+    pub fn run_multi_scenario_with_progress<S>(
+        &self,
+        settings: &S::Settings,
+        callback: &mut dyn ProgressCallback,
+        frequency: ProgressFrequency,
+    ) -> Result<ModelResult, PywrError>
+    where
+        S: MultiStateSolver,
+        <S as MultiStateSolver>::Settings: SolverSettings,
+    {
+        self.run_multi_scenario_with_options::<S>(settings, RunOptions::default().progress(callback, frequency))
+    }
+
+    /// Run the network with the provided states and [`MultiStateSolver`] solver while reporting
+    /// its progress.
+    ///
+    /// See [`Model::run_with_progress`].
+    pub fn run_multi_scenario_with_state_and_progress<S>(
+        &self,
+        state: &mut ModelState<Box<S>>,
+        settings: &S::Settings,
+        callback: &mut dyn ProgressCallback,
+        frequency: ProgressFrequency,
+    ) -> Result<FileManifest, PywrError>
+    where
+        S: MultiStateSolver,
+        <S as MultiStateSolver>::Settings: SolverSettings,
+    {
+        self.run_multi_scenario_with_state_and_options::<S>(
+            state,
+            settings,
+            RunOptions::default().progress(callback, frequency),
+        )
     }
 
     /// Run the network with the provided states and [`MultiStateSolver`] solver.
+    ///
+    /// Returns a [`FileManifest`] of the files written by the recorders.
     pub fn run_multi_scenario_with_state<S>(
         &self,
         state: &mut ModelState<Box<S>>,
         settings: &S::Settings,
-    ) -> Result<(), PywrError>
+    ) -> Result<FileManifest, PywrError>
     where
         S: MultiStateSolver,
         <S as MultiStateSolver>::Settings: SolverSettings,
     {
+        self.run_multi_scenario_with_state_and_options::<S>(state, settings, RunOptions::default())
+    }
+
+    /// Run the network with the provided states and [`MultiStateSolver`] solver and the given
+    /// [`RunOptions`].
+    ///
+    /// See [`Model::run_with_state_and_options`] for how the options are applied.
+    pub fn run_multi_scenario_with_state_and_options<S>(
+        &self,
+        state: &mut ModelState<Box<S>>,
+        settings: &S::Settings,
+        options: RunOptions,
+    ) -> Result<FileManifest, PywrError>
+    where
+        S: MultiStateSolver,
+        <S as MultiStateSolver>::Settings: SolverSettings,
+    {
+        let timesteps = self.domain.time.timesteps();
+        let mut monitor = RunMonitor::new(options, timesteps.len());
         let mut timings = RunTimings::default();
         let mut count = 0;
 
@@ -295,17 +883,32 @@ impl Model {
             .build()
             .unwrap();
 
+        let mut stopped = None;
+        state.state.set_scenario_time_limit(monitor.time_limit());
+
         loop {
             match self.step_multi_scenario::<S>(state, &pool, &mut timings) {
                 Ok(_) => {}
                 Err(PywrError::EndOfTimesteps) => break,
+                Err(PywrError::SolveTimeLimitReached) => {
+                    // A scenario reached its time limit part way through the time-step
+                    let stop = monitor.solve_timed_out(state.state.scenario_elapsed())?;
+                    stopped = Some((stop, state.current_time_step_idx));
+                    break;
+                }
                 Err(e) => return Err(e),
             }
 
             count += self.domain.scenarios.indices().len();
+
+            let timestep = &timesteps[state.current_time_step_idx - 1];
+            if let Some(stop) = monitor.update(timestep, state.state.scenario_elapsed()) {
+                stopped = Some((stop, state.current_time_step_idx));
+                break;
+            }
         }
 
-        self.network.finalise(
+        let manifest = self.network.finalise(
             state.state.all_metric_set_internal_states_mut(),
             &mut state.recorder_state,
         )?;
@@ -313,7 +916,11 @@ impl Model {
         // End the global timer and print the run statistics
         timings.finish(count);
         timings.print_table();
+        self.network.report_parameter_clamping(&state.state)?;
 
-        Ok(())
+        match stopped {
+            Some((stop, completed_timesteps)) => Err(stop.into_error(completed_timesteps, timesteps.len(), manifest)),
+            None => Ok(manifest),
+        }
     }
 }
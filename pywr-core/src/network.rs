@@ -1,20 +1,31 @@
 use crate::aggregated_node::{AggregatedNode, AggregatedNodeIndex, AggregatedNodeVec, Relationship};
 use crate::aggregated_storage_node::{AggregatedStorageNode, AggregatedStorageNodeIndex, AggregatedStorageNodeVec};
+use crate::checksum::Checksum;
 use crate::derived_metric::{DerivedMetric, DerivedMetricIndex};
 use crate::edge::{Edge, EdgeIndex, EdgeVec};
-use crate::metric::{MetricF64, SimpleMetricF64};
-use crate::models::ModelDomain;
-use crate::node::{Node, NodeVec, StorageInitialVolume};
+use crate::metric::{MetricBacktrace, MetricF64, MetricTrace, SimpleMetricF64};
+use crate::models::{ExternalInputIndex, ModelDomain};
+use crate::node::{
+    FlowBounds, Node, NodeBounds, NodeCost, NodeType, NodeVec, StorageInitialVolume, SwitchingConstraints,
+};
 use crate::parameters::{
     GeneralParameterType, ParameterCollection, ParameterIndex, ParameterName, ParameterStates, VariableConfig,
 };
-use crate::recorders::{MetricSet, MetricSetIndex, MetricSetState};
+use crate::recorders::{
+    FileManifest, FileManifestEntry, MetricSet, MetricSetIndex, MetricSetState, OutputDescription, PreSolveTrace,
+    ResultsFrame, RunInfo, ScenarioGroupValues,
+};
 use crate::scenario::ScenarioIndex;
-use crate::solvers::{MultiStateSolver, Solver, SolverFeatures, SolverTimings};
+use crate::snapshot::{SnapshotError, StateSnapshot};
+use crate::solvers::{
+    export_lp, with_scenario_index, LpExport, LpStatistics, MultiStateSolver, Solver, SolverFeatures, SolverTimings,
+};
 use crate::state::{MultiValue, State, StateBuilder};
+use crate::state_override::{StateOverride, StateOverrideIndex, StateOverrideTarget};
 use crate::timestep::Timestep;
 use crate::virtual_storage::{VirtualStorage, VirtualStorageBuilder, VirtualStorageIndex, VirtualStorageVec};
 use crate::{parameters, recorders, NodeIndex, PywrError, RecorderIndex};
+use polars::frame::DataFrame;
 use rayon::prelude::*;
 use std::any::Any;
 use std::collections::HashSet;
@@ -22,7 +33,10 @@ use std::ops::Deref;
 use std::slice::{Iter, IterMut};
 use std::time::Duration;
 use std::time::Instant;
-use tracing::info;
+use tracing::{info, warn};
+
+/// The maximum depth of the dependencies traced when a parameter computes an invalid value.
+const MAX_TRACE_DEPTH: usize = 8;
 
 pub enum RunDuration {
     Running(Instant),
@@ -154,13 +168,68 @@ pub struct NetworkState {
     parameter_internal_states: Vec<ParameterStates>,
     // Metric set states by scenario
     metric_set_internal_states: Vec<Vec<MetricSetState>>,
+    // The LP to write to a file and the tie-breaking of the solver that solves it
+    lp_export: Option<(LpExport, Option<f64>)>,
+    // The maximum wall-clock time of each scenario, if limited
+    scenario_time_limit: Option<Duration>,
+    // The wall-clock time spent computing and solving each scenario
+    scenario_elapsed: Vec<Duration>,
 }
 
 impl NetworkState {
+    /// Returns the state of each scenario.
+    pub fn states(&self) -> &[State] {
+        &self.states
+    }
+
+    /// Replace the state of each scenario with a copy of `states`.
+    ///
+    /// This is used to repeat a time-step from the same starting state. The internal states of
+    /// the parameters and metric sets are not modified (see [`Self::set_parameter_states`]).
+    pub(crate) fn set_states(&mut self, states: &[State]) {
+        self.states.clone_from_slice(states);
+    }
+
+    /// Replace the internal states of the parameters of each scenario with a copy of `states`.
+    ///
+    /// This is used together with [`Self::set_states`] to repeat a time-step from the same
+    /// starting state.
+    pub(crate) fn set_parameter_states(&mut self, states: &[ParameterStates]) {
+        self.parameter_internal_states.clone_from_slice(states);
+    }
+
+    /// Replace the state of each scenario with the states from a snapshot.
+    ///
+    /// The snapshot must have been created from a network with the same structure. The internal
+    /// states of the parameters and metric sets are not modified.
+    pub fn restore_snapshot(&mut self, snapshot: StateSnapshot) -> Result<(), SnapshotError> {
+        snapshot.check_compatible(&self.states)?;
+        self.states = snapshot.into_states();
+        Ok(())
+    }
+
     pub fn state(&self, scenario_index: &ScenarioIndex) -> &State {
         &self.states[scenario_index.index]
     }
 
+    /// Write the LP of a time-step of a scenario to a file when it is solved (see [`LpExport`]).
+    pub(crate) fn set_lp_export(&mut self, export: LpExport, tie_breaking: Option<f64>) {
+        self.lp_export = Some((export, tie_breaking));
+    }
+
+    /// Limit the wall-clock time spent computing and solving each scenario to `time_limit`.
+    ///
+    /// The remaining time of a scenario is passed to its solver before each solve (see
+    /// [`Solver::set_time_limit`]).
+    pub(crate) fn set_scenario_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.scenario_time_limit = time_limit;
+    }
+
+    /// The wall-clock time spent computing and solving each scenario so far.
+    pub fn scenario_elapsed(&self) -> &[Duration] {
+        &self.scenario_elapsed
+    }
+
     pub fn state_mut(&mut self, scenario_index: &ScenarioIndex) -> &mut State {
         &mut self.states[scenario_index.index]
     }
@@ -206,9 +275,54 @@ pub struct Network {
     metric_sets: Vec<MetricSet>,
     resolve_order: Vec<ComponentType>,
     recorders: Vec<Box<dyn recorders::Recorder>>,
+    external_inputs: Vec<String>,
+    state_overrides: Vec<StateOverride>,
 }
 
 impl Network {
+    /// A digest of the structure of the network.
+    ///
+    /// The digest covers the nodes, edges, aggregated nodes and virtual storages (their names,
+    /// types and connectivity) that determine the structure of the linear program. It does not
+    /// include the parameters or any other values that only change the coefficients and bounds
+    /// of the linear program. Two networks with the same digest therefore produce linear programs
+    /// with the same rows and columns.
+    pub fn structure_digest(&self) -> Checksum {
+        let mut structure = String::new();
+
+        for node in self.nodes.iter() {
+            let ty = match node.node_type() {
+                NodeType::Input => "input",
+                NodeType::Output => "output",
+                NodeType::Link => "link",
+                NodeType::Storage => "storage",
+            };
+            structure.push_str(&format!("node:{:?}:{}\n", node.full_name(), ty));
+        }
+
+        for edge in self.edges.iter() {
+            structure.push_str(&format!("edge:{}:{}\n", *edge.from_node_index(), *edge.to_node_index()));
+        }
+
+        for node in self.aggregated_nodes.iter() {
+            let nodes: Vec<Vec<usize>> = node.iter_nodes().map(|n| n.iter().map(|i| **i).collect()).collect();
+            structure.push_str(&format!(
+                "aggregated:{:?}:{:?}:{}:{}\n",
+                node.full_name(),
+                nodes,
+                node.has_exclusivity(),
+                node.has_factors()
+            ));
+        }
+
+        for node in self.virtual_storage_nodes.iter() {
+            let nodes: Vec<usize> = node.nodes().iter().map(|i| **i).collect();
+            structure.push_str(&format!("virtual-storage:{:?}:{:?}\n", node.full_name(), nodes));
+        }
+
+        Checksum::sha256(structure.as_bytes())
+    }
+
     pub fn nodes(&self) -> &NodeVec {
         &self.nodes
     }
@@ -249,7 +363,9 @@ impl Network {
                 .with_virtual_storage_states(initial_virtual_storage_states)
                 .with_parameters(&self.parameters)
                 .with_derived_metrics(self.derived_metrics.len())
-                .with_inter_network_transfers(num_inter_network_transfers);
+                .with_inter_network_transfers(num_inter_network_transfers)
+                .with_external_inputs(self.external_inputs.len())
+                .with_state_overrides(self.state_overrides.len());
 
             let mut state = state_builder.build();
 
@@ -267,9 +383,12 @@ impl Network {
         }
 
         Ok(NetworkState {
+            scenario_elapsed: vec![Duration::ZERO; states.len()],
             states,
             parameter_internal_states,
             metric_set_internal_states,
+            lp_export: None,
+            scenario_time_limit: None,
         })
     }
 
@@ -284,6 +403,19 @@ impl Network {
         Ok(recorder_internal_states)
     }
 
+    /// Pass the details of a run to the recorders before the first time-step.
+    pub fn start_recorders(
+        &self,
+        run: &RunInfo,
+        recorder_internal_states: &mut [Option<Box<dyn Any>>],
+    ) -> Result<(), PywrError> {
+        for (recorder, internal_state) in self.recorders.iter().zip(recorder_internal_states) {
+            recorder.start_run(run, internal_state)?;
+        }
+
+        Ok(())
+    }
+
     /// Check whether a solver [`S`] has the required features to run this network.
     pub fn check_solver_features<S>(&self) -> bool
     where
@@ -329,6 +461,18 @@ impl Network {
         Ok(solvers)
     }
 
+    /// Build the linear program for the given scenario and return statistics of its constraint matrix.
+    ///
+    /// The constraint matrix is the same for all solvers that share the common LP builder.
+    pub fn lp_statistics(
+        &self,
+        scenario_index: &ScenarioIndex,
+        state: &NetworkState,
+    ) -> Result<LpStatistics, PywrError> {
+        let const_values = state.state(scenario_index).get_const_parameter_values();
+        crate::solvers::lp_statistics(self, &const_values)
+    }
+
     pub fn setup_multi_scenario_solver<S>(
         &self,
         scenario_indices: &[ScenarioIndex],
@@ -343,25 +487,47 @@ impl Network {
         S::setup(self, scenario_indices.len(), settings)
     }
 
+    /// Finalise the metric sets and recorders at the end of a run.
+    ///
+    /// Returns a [`FileManifest`] of the files written by the recorders.
     pub fn finalise(
         &self,
         metric_set_states: &mut [Vec<MetricSetState>],
         recorder_internal_states: &mut [Option<Box<dyn Any>>],
-    ) -> Result<(), PywrError> {
+    ) -> Result<FileManifest, PywrError> {
         // Finally, save new data to the metric set
 
         for ms_states in metric_set_states.iter_mut() {
             for (metric_set, ms_state) in self.metric_sets.iter().zip(ms_states.iter_mut()) {
-                metric_set.finalise(ms_state);
+                metric_set.finalise(ms_state)?;
             }
         }
 
-        // Setup recorders
+        let mut manifest = FileManifest::default();
+
+        // Finalise recorders
         for (recorder, internal_state) in self.recorders.iter().zip(recorder_internal_states) {
             recorder.finalise(self, metric_set_states, internal_state)?;
+
+            for path in recorder.output_paths() {
+                manifest.push(FileManifestEntry::from_path(recorder.name(), path)?);
+            }
         }
 
-        Ok(())
+        Ok(manifest)
+    }
+
+    /// Describe the values that will be written by each of the recorders in a run of `domain`.
+    ///
+    /// Recorders that do not write any values are not included.
+    pub fn describe_outputs(&self, domain: &ModelDomain) -> Result<Vec<OutputDescription>, PywrError> {
+        let mut descriptions = Vec::new();
+        for recorder in &self.recorders {
+            if let Some(description) = recorder.describe(domain, self)? {
+                descriptions.push(description);
+            }
+        }
+        Ok(descriptions)
     }
 
     /// Perform a single timestep mutating the current state.
@@ -376,42 +542,109 @@ impl Network {
     where
         S: Solver,
     {
-        scenario_indices
+        self.solve_step(timestep, scenario_indices, solvers, state, timings)?;
+        self.after_step(timestep, scenario_indices, state, timings)
+    }
+
+    /// Compute the components of the network for a single timestep without solving it.
+    ///
+    /// The values of the parameters in `state` are updated for the timestep, so that the bounds
+    /// and costs of the nodes can be inspected as the solver would see them (see
+    /// [`Network::get_node_flow_bounds`] and [`Network::get_node_cost`]). The internal states of
+    /// the parameters are also updated, so the timestep should not then be solved from `state`.
+    pub fn compute_step(
+        &self,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        state: &mut NetworkState,
+    ) -> Result<(), PywrError> {
+        for ((scenario_index, current_state), p_internal_states) in scenario_indices
+            .iter()
+            .zip(state.states.iter_mut())
+            .zip(state.parameter_internal_states.iter_mut())
+        {
+            self.compute_components(timestep, scenario_index, current_state, p_internal_states)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compute the components and solve the network for a single timestep.
+    ///
+    /// This is the first half of [`Network::step`]. The "after" method of the components is not
+    /// run, and no values are saved to the metric sets, until [`Network::after_step`] is called.
+    /// This allows the timestep to be solved more than once (e.g. to iterate inter-network
+    /// transfers to convergence) by restoring the state before each solve.
+    pub(crate) fn solve_step<S>(
+        &self,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        solvers: &mut [Box<S>],
+        state: &mut NetworkState,
+        timings: &mut RunTimings,
+    ) -> Result<(), PywrError>
+    where
+        S: Solver,
+    {
+        for ((((scenario_index, current_state), p_internal_states), solver), elapsed) in scenario_indices
             .iter()
             .zip(state.states.iter_mut())
             .zip(state.parameter_internal_states.iter_mut())
-            .zip(state.metric_set_internal_states.iter_mut())
             .zip(solvers)
-            .for_each(
-                |((((scenario_index, current_state), p_internal_states), ms_internal_states), solver)| {
-                    // TODO clear the current parameter values state (i.e. set them all to zero).
+            .zip(state.scenario_elapsed.iter_mut())
+        {
+            // TODO clear the current parameter values state (i.e. set them all to zero).
 
-                    let start_p_calc = Instant::now();
-                    self.compute_components(timestep, scenario_index, current_state, p_internal_states)
-                        .unwrap();
+            let start_p_calc = Instant::now();
+            self.compute_components(timestep, scenario_index, current_state, p_internal_states)?;
 
-                    // State now contains updated parameter values BUT original network state
-                    timings.parameter_calculation += start_p_calc.elapsed();
+            // State now contains updated parameter values BUT original network state
+            timings.parameter_calculation += start_p_calc.elapsed();
 
-                    // Solve determines the new network state
-                    let solve_timings = solver.solve(self, timestep, current_state).unwrap();
-                    // State now contains updated parameter values AND updated network state
-                    timings.solve += solve_timings;
+            self.export_lp_if_requested(state.lp_export.as_ref(), timestep, scenario_index, current_state)?;
 
-                    // Now run the "after" method on all components
-                    let start_p_after = Instant::now();
-                    self.after(
-                        timestep,
-                        scenario_index,
-                        current_state,
-                        p_internal_states,
-                        ms_internal_states,
-                    )
-                    .unwrap();
+            // Solve determines the new network state
+            if let Some(time_limit) = state.scenario_time_limit {
+                solver.set_time_limit(time_limit.saturating_sub(*elapsed + start_p_calc.elapsed()));
+            }
+            let result = solver.solve(self, timestep, current_state);
+            *elapsed += start_p_calc.elapsed();
+            let solve_timings = result.map_err(|e| with_scenario_index(e, scenario_index))?;
+            // State now contains updated parameter values AND updated network state
+            timings.solve += solve_timings;
+        }
 
-                    timings.parameter_calculation += start_p_after.elapsed();
-                },
-            );
+        Ok(())
+    }
+
+    /// Run the "after" method on all components for a single timestep.
+    ///
+    /// This is the second half of [`Network::step`] and must be called once the network has been
+    /// solved with [`Network::solve_step`].
+    pub(crate) fn after_step(
+        &self,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        state: &mut NetworkState,
+        timings: &mut RunTimings,
+    ) -> Result<(), PywrError> {
+        for (((scenario_index, current_state), p_internal_states), ms_internal_states) in scenario_indices
+            .iter()
+            .zip(state.states.iter_mut())
+            .zip(state.parameter_internal_states.iter_mut())
+            .zip(state.metric_set_internal_states.iter_mut())
+        {
+            let start_p_after = Instant::now();
+            self.after(
+                timestep,
+                scenario_index,
+                current_state,
+                p_internal_states,
+                ms_internal_states,
+            )?;
+
+            timings.parameter_calculation += start_p_after.elapsed();
+        }
 
         Ok(())
     }
@@ -432,6 +665,9 @@ impl Network {
     where
         S: Solver,
     {
+        let lp_export = state.lp_export.as_ref();
+        let time_limit = state.scenario_time_limit;
+
         // Collect all the timings from each parallel solve
         let step_times: Vec<_> = scenario_indices
             .par_iter()
@@ -439,19 +675,26 @@ impl Network {
             .zip(&mut state.parameter_internal_states)
             .zip(&mut state.metric_set_internal_states)
             .zip(solvers)
+            .zip(&mut state.scenario_elapsed)
             .map(
-                |((((scenario_index, current_state), p_internal_state), ms_internal_state), solver)| {
+                |(((((scenario_index, current_state), p_internal_state), ms_internal_state), solver), elapsed)| {
                     // TODO clear the current parameter values state (i.e. set them all to zero).
 
                     let start_p_calc = Instant::now();
-                    self.compute_components(timestep, scenario_index, current_state, p_internal_state)
-                        .unwrap();
+                    self.compute_components(timestep, scenario_index, current_state, p_internal_state)?;
 
                     // State now contains updated parameter values BUT original network state
                     let mut parameter_calculation = start_p_calc.elapsed();
 
+                    self.export_lp_if_requested(lp_export, timestep, scenario_index, current_state)?;
+
                     // Solve determines the new network state
-                    let solve_timings = solver.solve(self, timestep, current_state).unwrap();
+                    if let Some(time_limit) = time_limit {
+                        solver.set_time_limit(time_limit.saturating_sub(*elapsed + start_p_calc.elapsed()));
+                    }
+                    let result = solver.solve(self, timestep, current_state);
+                    *elapsed += start_p_calc.elapsed();
+                    let solve_timings = result.map_err(|e| with_scenario_index(e, scenario_index))?;
                     // State now contains updated parameter values AND updated network state
 
                     // Now run the "after" method on all components
@@ -462,15 +705,14 @@ impl Network {
                         current_state,
                         p_internal_state,
                         ms_internal_state,
-                    )
-                    .unwrap();
+                    )?;
 
                     parameter_calculation += start_p_after.elapsed();
 
-                    (parameter_calculation, solve_timings)
+                    Ok((parameter_calculation, solve_timings))
                 },
             )
-            .collect();
+            .collect::<Result<_, PywrError>>()?;
 
         // Add them all together
         for (parameter_calculation, solve_timings) in step_times.into_iter() {
@@ -490,11 +732,30 @@ impl Network {
         state: &mut NetworkState,
         timings: &mut RunTimings,
     ) -> Result<(), PywrError>
+    where
+        S: MultiStateSolver,
+    {
+        self.solve_step_multi_scenario(timestep, scenario_indices, solver, state, timings)?;
+        self.after_step_multi_scenario(timestep, scenario_indices, state, timings)
+    }
+
+    /// Compute the components and solve the network for a single timestep with a [`MultiStateSolver`].
+    ///
+    /// See [`Network::solve_step`].
+    pub(crate) fn solve_step_multi_scenario<S>(
+        &self,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        solver: &mut Box<S>,
+        state: &mut NetworkState,
+        timings: &mut RunTimings,
+    ) -> Result<(), PywrError>
     where
         S: MultiStateSolver,
     {
         // First compute all the updated state
 
+        let lp_export = state.lp_export.as_ref();
         let p_calc_timings: Vec<_> = scenario_indices
             .par_iter()
             .zip(&mut state.states)
@@ -503,24 +764,70 @@ impl Network {
                 // TODO clear the current parameter values state (i.e. set them all to zero).
 
                 let start_p_calc = Instant::now();
-                self.compute_components(timestep, scenario_index, current_state, p_internal_states)
-                    .unwrap();
+                self.compute_components(timestep, scenario_index, current_state, p_internal_states)?;
 
                 // State now contains updated parameter values BUT original network state
-                start_p_calc.elapsed()
+                let elapsed = start_p_calc.elapsed();
+
+                self.export_lp_if_requested(lp_export, timestep, scenario_index, current_state)?;
+
+                Ok(elapsed)
             })
-            .collect();
+            .collect::<Result<_, PywrError>>()?;
 
-        for t in p_calc_timings.into_iter() {
-            timings.parameter_calculation += t;
+        for t in p_calc_timings.iter() {
+            timings.parameter_calculation += *t;
         }
 
         // Now solve all the LPs simultaneously
 
-        let solve_timings = solver.solve(self, timestep, &mut state.states).unwrap();
+        let start_solve = Instant::now();
+        let result = solver.solve(self, timestep, &mut state.states);
+        // The LPs are solved together, so the time of the solve is added to every scenario
+        let solve_elapsed = start_solve.elapsed();
+        for (elapsed, p_calc) in state.scenario_elapsed.iter_mut().zip(p_calc_timings.iter()) {
+            *elapsed += *p_calc + solve_elapsed;
+        }
+        let solve_timings = result?;
         // State now contains updated parameter values AND updated network state
         timings.solve += solve_timings;
 
+        Ok(())
+    }
+
+    /// Write the LP of `scenario_index` in `timestep` to a file if it was requested.
+    ///
+    /// This must be called once the components have been computed and before the solve.
+    fn export_lp_if_requested(
+        &self,
+        lp_export: Option<&(LpExport, Option<f64>)>,
+        timestep: &Timestep,
+        scenario_index: &ScenarioIndex,
+        state: &State,
+    ) -> Result<(), PywrError> {
+        if let Some((export, tie_breaking)) = lp_export {
+            if export.is_requested(timestep, scenario_index) {
+                export_lp(self, timestep, state, export, *tie_breaking)?;
+                info!(
+                    "Exported the LP of time-step {} to {}.",
+                    timestep.index,
+                    export.path().display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the "after" method on all components for a single timestep in parallel.
+    ///
+    /// See [`Network::after_step`].
+    pub(crate) fn after_step_multi_scenario(
+        &self,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        state: &mut NetworkState,
+        timings: &mut RunTimings,
+    ) -> Result<(), PywrError> {
         // Now run the "after" method on all components
         let p_after_timings: Vec<_> = scenario_indices
             .par_iter()
@@ -536,12 +843,11 @@ impl Network {
                         current_state,
                         p_internal_states,
                         ms_internal_states,
-                    )
-                    .unwrap();
-                    start_p_after.elapsed()
+                    )?;
+                    Ok(start_p_after.elapsed())
                 },
             )
-            .collect();
+            .collect::<Result<_, PywrError>>()?;
 
         for t in p_after_timings.into_iter() {
             timings.parameter_calculation += t;
@@ -550,6 +856,37 @@ impl Network {
         Ok(())
     }
 
+    /// Describe the first component, if any, that reads the flows or volumes of the solved
+    /// state of each scenario.
+    ///
+    /// Solvers that do not save the flows of each scenario to its state can only be used with
+    /// networks without any such components.
+    pub(crate) fn flow_dependent_component(&self) -> Option<String> {
+        if let Some(node) = self.nodes.iter().find(|n| n.node_type() == NodeType::Storage) {
+            return Some(format!("the storage node `{}`", node.name()));
+        }
+        if let Some(node) = self.virtual_storage_nodes.iter().next() {
+            return Some(format!("the virtual storage node `{}`", node.name()));
+        }
+        let size = self.parameters.size();
+        if size.general_f64 + size.general_usize + size.general_multi > 0 {
+            return Some("parameters that depend on the state of the network".to_string());
+        }
+        if !self.derived_metrics.is_empty() {
+            return Some("derived metrics".to_string());
+        }
+        if let Some(metric_set) = self.metric_sets.first() {
+            return Some(format!("the metric set `{}`", metric_set.name()));
+        }
+        if let Some(recorder) = self.recorders.first() {
+            return Some(format!("the recorder `{}`", recorder.name()));
+        }
+        if let Some(state_override) = self.state_overrides.first() {
+            return Some(format!("the state override `{}`", state_override.name()));
+        }
+        None
+    }
+
     /// Calculate the set of [`SolverFeatures`] required to correctly run this network.
     fn required_features(&self) -> HashSet<SolverFeatures> {
         let mut features = HashSet::new();
@@ -583,6 +920,11 @@ impl Network {
             features.insert(SolverFeatures::VirtualStorage);
         }
 
+        // Nodes with switching constraints require the SwitchingConstraints feature.
+        if self.nodes.iter().any(|n| n.get_switching_constraints().is_some()) {
+            features.insert(SolverFeatures::SwitchingConstraints);
+        }
+
         features
     }
 
@@ -633,8 +975,12 @@ impl Network {
 
                             // TODO move this check into the method below
                             if value.is_nan() {
-                                panic!("NaN value computed in parameter: {}", p.name());
+                                return Err(PywrError::ParameterNaNValue {
+                                    name: p.name().to_string(),
+                                    backtrace: MetricBacktrace(self.trace_metrics(&p.dependencies(), state, 1)),
+                                });
                             }
+                            let value = self.parameters.apply_f64_bounds((*idx).into(), value, internal_states);
                             state.set_parameter_value(*idx, value)?;
                         }
                         GeneralParameterType::Index(idx) => {
@@ -682,9 +1028,43 @@ impl Network {
             }
         }
 
+        // Capture the values the solver will see if any recorder requires a trace of the solve
+        let trace = if self.recorders.iter().any(|r| r.traces(timestep, scenario_index)) {
+            Some(PreSolveTrace::capture(self, timestep, state)?)
+        } else {
+            None
+        };
+        state.set_pre_solve_trace(trace);
+
         Ok(())
     }
 
+    /// Trace the current values of `metrics` and, recursively, of the metrics from which any
+    /// general parameters among them are computed.
+    ///
+    /// The recursion is limited to [`MAX_TRACE_DEPTH`] levels of dependencies.
+    fn trace_metrics(&self, metrics: &[&MetricF64], state: &State, depth: usize) -> Vec<MetricTrace> {
+        metrics
+            .iter()
+            .map(|metric| {
+                let dependencies = match metric {
+                    MetricF64::ParameterValue(idx) if depth < MAX_TRACE_DEPTH => self
+                        .parameters
+                        .get_general_f64(*idx)
+                        .map(|p| self.trace_metrics(&p.dependencies(), state, depth + 1))
+                        .unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+
+                MetricTrace {
+                    description: metric.describe(self),
+                    value: metric.get_value(self, state).ok(),
+                    dependencies,
+                }
+            })
+            .collect()
+    }
+
     /// Undertake "after" for network components after solve.
     ///
     /// This method iterates through the network components (nodes, parameters, etc) to perform
@@ -700,6 +1080,12 @@ impl Network {
         internal_states: &mut ParameterStates,
         metric_set_states: &mut [MetricSetState],
     ) -> Result<(), PywrError> {
+        // Apply any overrides to the solved state before it is used by the other components
+        for (idx, state_override) in self.state_overrides.iter().enumerate() {
+            let residual = state_override.apply(self, state)?;
+            state.set_state_override_residual(StateOverrideIndex::new(idx), residual)?;
+        }
+
         // TODO reset parameter state to zero
 
         self.parameters
@@ -762,7 +1148,7 @@ impl Network {
                         .derived_metrics
                         .get(*idx.deref())
                         .ok_or(PywrError::DerivedMetricIndexNotFound(*idx))?;
-                    let value = m.compute(self, state)?;
+                    let value = m.after(*idx, timestep, self, state)?;
                     state.set_derived_metric_value(*idx, value)?;
                 }
             }
@@ -822,6 +1208,44 @@ impl Network {
         self.nodes.get(index)
     }
 
+    /// Get the effective flow bounds of a node in the current state, as the solver sees them.
+    ///
+    /// The volume bounds of storage nodes are converted to the flow into (positive) or out of
+    /// (negative) the node over the duration of `timestep`. The constraints of any aggregated or
+    /// virtual storage nodes that the node is part of are not included.
+    pub fn get_node_flow_bounds(
+        &self,
+        index: &NodeIndex,
+        timestep: &Timestep,
+        state: &State,
+    ) -> Result<FlowBounds, PywrError> {
+        let bounds = match self.get_node(index)?.get_bounds(self, state)? {
+            NodeBounds::Flow(bounds) => bounds,
+            NodeBounds::Volume(bounds) => {
+                let dt = timestep.days();
+                FlowBounds {
+                    min_flow: -bounds.available / dt,
+                    max_flow: bounds.missing / dt,
+                }
+            }
+        };
+
+        Ok(bounds)
+    }
+
+    /// Get the cost of flow into and out of a node in the current state, as the solver sees them.
+    ///
+    /// The cost of each edge in the solver is the outgoing cost of the node it leaves plus the
+    /// incoming cost of the node it enters.
+    pub fn get_node_cost(&self, index: &NodeIndex, state: &State) -> Result<NodeCost, PywrError> {
+        let node = self.get_node(index)?;
+
+        Ok(NodeCost {
+            incoming: node.get_incoming_cost(self, state)?,
+            outgoing: node.get_outgoing_cost(self, state)?,
+        })
+    }
+
     /// Get a Node from a node's name
     pub fn get_node_by_name(&self, name: &str, sub_name: Option<&str>) -> Result<&Node, PywrError> {
         match self.nodes.iter().find(|&n| n.full_name() == (name, sub_name)) {
@@ -869,6 +1293,16 @@ impl Network {
         node.set_min_flow_constraint(value)
     }
 
+    pub fn set_node_switching_constraints(
+        &mut self,
+        name: &str,
+        sub_name: Option<&str>,
+        value: Option<SwitchingConstraints>,
+    ) -> Result<(), PywrError> {
+        let node = self.get_mut_node_by_name(name, sub_name)?;
+        node.set_switching_constraints(value)
+    }
+
     pub fn set_node_max_volume(
         &mut self,
         name: &str,
@@ -889,6 +1323,19 @@ impl Network {
         node.set_min_volume_constraint(value)
     }
 
+    /// Set the weight applied to a node's constraints by solvers that support it.
+    ///
+    /// See [`Node::set_solver_weight`] for details.
+    pub fn set_node_solver_weight(
+        &mut self,
+        name: &str,
+        sub_name: Option<&str>,
+        weight: Option<f64>,
+    ) -> Result<(), PywrError> {
+        let node = self.get_mut_node_by_name(name, sub_name)?;
+        node.set_solver_weight(weight)
+    }
+
     /// Get a `AggregatedNodeIndex` from a node's name
     pub fn get_aggregated_node(&self, index: &AggregatedNodeIndex) -> Result<&AggregatedNode, PywrError> {
         self.aggregated_nodes.get(index)
@@ -1142,6 +1589,108 @@ impl Network {
         }
     }
 
+    /// Add a named input whose value is supplied by an external process each time-step.
+    pub fn add_external_input(&mut self, name: &str) -> Result<ExternalInputIndex, PywrError> {
+        if self.get_external_input_index_by_name(name).is_ok() {
+            return Err(PywrError::ExternalInputNameAlreadyExists(name.to_string()));
+        }
+
+        self.external_inputs.push(name.to_string());
+        Ok(ExternalInputIndex::new(self.external_inputs.len() - 1))
+    }
+
+    /// Get the [`ExternalInputIndex`] of an external input from its name.
+    pub fn get_external_input_index_by_name(&self, name: &str) -> Result<ExternalInputIndex, PywrError> {
+        match self.external_inputs.iter().position(|n| n == name) {
+            Some(idx) => Ok(ExternalInputIndex::new(idx)),
+            None => Err(PywrError::ExternalInputNotFound(name.to_string())),
+        }
+    }
+
+    /// The names of the external inputs in the network.
+    pub fn external_inputs(&self) -> &[String] {
+        &self.external_inputs
+    }
+
+    /// Add an override that replaces part of the network state after each solve.
+    ///
+    /// See [`StateOverride`] for more information.
+    pub fn add_state_override(&mut self, state_override: StateOverride) -> Result<StateOverrideIndex, PywrError> {
+        if self.get_state_override_index_by_name(state_override.name()).is_ok() {
+            return Err(PywrError::StateOverrideNameAlreadyExists(
+                state_override.name().to_string(),
+            ));
+        }
+
+        let valid = match state_override.target() {
+            StateOverrideTarget::NodeVolume(idx) => matches!(self.get_node(idx)?, Node::Storage(_)),
+            StateOverrideTarget::NodeFlow(idx) => matches!(self.get_node(idx)?, Node::Link(_)),
+            StateOverrideTarget::VirtualStorageVolume(_) => true,
+        };
+        if !valid {
+            return Err(PywrError::InvalidStateOverrideTarget(state_override.name().to_string()));
+        }
+
+        self.state_overrides.push(state_override);
+        Ok(StateOverrideIndex::new(self.state_overrides.len() - 1))
+    }
+
+    /// Get the [`StateOverrideIndex`] of a state override from its name.
+    pub fn get_state_override_index_by_name(&self, name: &str) -> Result<StateOverrideIndex, PywrError> {
+        match self.state_overrides.iter().position(|o| o.name() == name) {
+            Some(idx) => Ok(StateOverrideIndex::new(idx)),
+            None => Err(PywrError::StateOverrideNotFound(name.to_string())),
+        }
+    }
+
+    pub fn get_state_override(&self, idx: &StateOverrideIndex) -> Result<&StateOverride, PywrError> {
+        self.state_overrides
+            .get(*idx.deref())
+            .ok_or(PywrError::StateOverrideIndexNotFound(*idx))
+    }
+
+    /// Set the bounds that the computed value of the parameter [`index`] is clamped to.
+    pub fn set_parameter_bounds(
+        &mut self,
+        index: ParameterIndex<f64>,
+        bounds: parameters::ParameterBounds,
+    ) -> Result<(), PywrError> {
+        self.parameters.set_f64_bounds(index, bounds)
+    }
+
+    /// Return the total number of times, across all scenarios, that the value of each
+    /// bounded parameter has been clamped.
+    pub fn parameter_clamp_counts(&self, state: &NetworkState) -> Result<Vec<(ParameterName, u64)>, PywrError> {
+        self.parameters
+            .iter_f64_bounded()
+            .map(|idx| {
+                let parameter = self
+                    .parameters
+                    .get_f64(idx)
+                    .ok_or(PywrError::ParameterIndexNotFound(idx))?;
+                let count = state.iter_parameter_states().map(|s| s.clamp_counts().get(idx)).sum();
+                Ok((parameter.name().clone(), count))
+            })
+            .collect()
+    }
+
+    /// Log a warning for each parameter whose value has been clamped to its bounds.
+    pub fn report_parameter_clamping(&self, state: &NetworkState) -> Result<(), PywrError> {
+        for (name, count) in self.parameter_clamp_counts(state)? {
+            if count > 0 {
+                warn!(
+                    "Value of parameter `{}` was clamped to its bounds {} times.",
+                    name, count
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parameters(&self) -> &ParameterCollection {
+        &self.parameters
+    }
+
     /// Get a `Parameter` from a parameter's name
     pub fn get_parameter(&self, index: ParameterIndex<f64>) -> Result<&dyn parameters::Parameter, PywrError> {
         match self.parameters.get_f64(index) {
@@ -1234,6 +1783,84 @@ impl Network {
         }
     }
 
+    /// Get the values of a recorder aggregated over a single scenario group.
+    pub fn get_aggregated_values_by_scenario_group(
+        &self,
+        name: &str,
+        recorder_states: &[Option<Box<dyn Any>>],
+    ) -> Result<ScenarioGroupValues, PywrError> {
+        match self.recorders.iter().enumerate().find(|(_, r)| r.name() == name) {
+            Some((idx, recorder)) => recorder.aggregated_values_by_scenario_group(&recorder_states[idx]),
+            None => Err(PywrError::RecorderNotFound),
+        }
+    }
+
+    /// Get the values saved by a recorder as a [`DataFrame`].
+    ///
+    /// Only recorders that keep their values in memory (e.g. [`MemoryRecorder`](recorders::MemoryRecorder))
+    /// support data frames.
+    pub fn get_recorder_dataframe(
+        &self,
+        name: &str,
+        recorder_states: &[Option<Box<dyn Any>>],
+    ) -> Result<DataFrame, PywrError> {
+        match self.recorders.iter().enumerate().find(|(_, r)| r.name() == name) {
+            Some((idx, recorder)) => recorder
+                .to_dataframe(self, &recorder_states[idx])?
+                .ok_or(PywrError::RecorderDoesNotSupportDataFrame),
+            None => Err(PywrError::RecorderNotFound),
+        }
+    }
+
+    /// Get the values saved by a recorder as a [`ResultsFrame`].
+    ///
+    /// Only recorders that keep their values in memory (e.g. [`MemoryRecorder`](recorders::MemoryRecorder))
+    /// support results frames.
+    pub fn get_recorder_results(
+        &self,
+        name: &str,
+        recorder_states: &[Option<Box<dyn Any>>],
+    ) -> Result<ResultsFrame, PywrError> {
+        match self.recorders.iter().enumerate().find(|(_, r)| r.name() == name) {
+            Some((idx, recorder)) => recorder
+                .to_results_frame(self, &recorder_states[idx])?
+                .ok_or(PywrError::RecorderDoesNotSupportResultsFrame),
+            None => Err(PywrError::RecorderNotFound),
+        }
+    }
+
+    /// Get the values saved by all of the recorders that support data frames.
+    ///
+    /// Returns the name of each recorder and its values.
+    pub fn get_recorder_dataframes(
+        &self,
+        recorder_states: &[Option<Box<dyn Any>>],
+    ) -> Result<Vec<(String, DataFrame)>, PywrError> {
+        let mut dataframes = Vec::new();
+        for (recorder, internal_state) in self.recorders.iter().zip(recorder_states) {
+            if let Some(df) = recorder.to_dataframe(self, internal_state)? {
+                dataframes.push((recorder.name().to_string(), df));
+            }
+        }
+        Ok(dataframes)
+    }
+
+    /// Get the values saved by all of the recorders that support results frames.
+    ///
+    /// Returns the name of each recorder and its values.
+    pub fn get_recorder_results_frames(
+        &self,
+        recorder_states: &[Option<Box<dyn Any>>],
+    ) -> Result<Vec<(String, ResultsFrame)>, PywrError> {
+        let mut frames = Vec::new();
+        for (recorder, internal_state) in self.recorders.iter().zip(recorder_states) {
+            if let Some(frame) = recorder.to_results_frame(self, internal_state)? {
+                frames.push((recorder.name().to_string(), frame));
+            }
+        }
+        Ok(frames)
+    }
+
     /// Add a new Node::Input to the network.
     pub fn add_input_node(&mut self, name: &str, sub_name: Option<&str>) -> Result<NodeIndex, PywrError> {
         // Check for name.
@@ -1405,6 +2032,24 @@ impl Network {
         self.parameters.add_const_f64(parameter)
     }
 
+    /// Replace the [`parameters::ConstParameter`] at `index` with a new parameter of the same name.
+    pub fn replace_const_parameter(
+        &mut self,
+        index: ParameterIndex<f64>,
+        parameter: Box<dyn parameters::ConstParameter<f64>>,
+    ) -> Result<(), PywrError> {
+        self.parameters.replace_const_f64(index, parameter)
+    }
+
+    /// Replace the [`parameters::SimpleParameter`] at `index` with a new parameter of the same name.
+    pub fn replace_simple_parameter(
+        &mut self,
+        index: ParameterIndex<f64>,
+        parameter: Box<dyn parameters::SimpleParameter<f64>>,
+    ) -> Result<(), PywrError> {
+        self.parameters.replace_simple_f64(index, parameter)
+    }
+
     /// Add a `parameters::IndexParameter` to the network
     pub fn add_index_parameter(
         &mut self,
@@ -1451,6 +2096,11 @@ impl Network {
             .ok_or(PywrError::MetricSetIndexNotFound(index))
     }
 
+    /// Get all of the [`MetricSet`]s in the network.
+    pub fn metric_sets(&self) -> &[MetricSet] {
+        &self.metric_sets
+    }
+
     /// Get a ['MetricSet'] by its name.
     pub fn get_metric_set_by_name(&self, name: &str) -> Result<&MetricSet, PywrError> {
         self.metric_sets
@@ -1586,6 +2236,32 @@ impl Network {
         }
     }
 
+    /// Return the cost associated with the current variable values of the parameter
+    /// [`parameter_index`] for scenario [`scenario_index`].
+    ///
+    /// Returns `None` if the parameter's variable values do not have an associated cost.
+    pub fn get_f64_parameter_variable_cost_for_scenario(
+        &self,
+        parameter_index: ParameterIndex<f64>,
+        scenario_index: ScenarioIndex,
+        state: &NetworkState,
+    ) -> Result<Option<f64>, PywrError> {
+        match self.parameters.get_f64(parameter_index) {
+            Some(parameter) => match parameter.as_f64_variable() {
+                Some(variable) => {
+                    let internal_state = state
+                        .parameter_states(&scenario_index)
+                        .get_f64_state(parameter_index)
+                        .ok_or(PywrError::ParameterStateNotFound(parameter_index))?;
+
+                    Ok(variable.get_variable_cost(internal_state))
+                }
+                None => Err(PywrError::ParameterTypeNotVariable),
+            },
+            None => Err(PywrError::ParameterIndexNotFound(parameter_index)),
+        }
+    }
+
     pub fn get_f64_parameter_variable_values(
         &self,
         parameter_index: ParameterIndex<f64>,
@@ -1613,6 +2289,24 @@ impl Network {
         }
     }
 
+    /// Return the lower and upper bounds of the variable values of the parameter [`parameter_index`].
+    pub fn get_f64_parameter_variable_bounds(
+        &self,
+        parameter_index: ParameterIndex<f64>,
+        variable_config: &dyn VariableConfig,
+    ) -> Result<(Vec<f64>, Vec<f64>), PywrError> {
+        match self.parameters.get_f64(parameter_index) {
+            Some(parameter) => match parameter.as_f64_variable() {
+                Some(variable) => Ok((
+                    variable.get_lower_bounds(variable_config)?,
+                    variable.get_upper_bounds(variable_config)?,
+                )),
+                None => Err(PywrError::ParameterTypeNotVariable),
+            },
+            None => Err(PywrError::ParameterIndexNotFound(parameter_index)),
+        }
+    }
+
     /// Set the variable values on the parameter [`parameter_index`].
     ///
     /// This will update the internal state of the parameter with the new values for scenarios.
@@ -1696,14 +2390,17 @@ impl Network {
 mod tests {
     use super::*;
     use crate::metric::MetricF64;
+    use crate::models::Model;
     use crate::network::Network;
-    use crate::parameters::{ActivationFunction, ControlCurveInterpolatedParameter, Parameter};
+    use crate::parameters::{
+        ActivationFunction, AggFunc, AggregatedParameter, Array1Parameter, ControlCurveInterpolatedParameter, Parameter,
+    };
     use crate::recorders::AssertionRecorder;
     use crate::scenario::{ScenarioDomain, ScenarioGroupCollection, ScenarioIndex};
     use crate::solvers::{ClpSolver, ClpSolverSettings};
-    use crate::test_utils::{run_all_solvers, simple_model, simple_storage_model};
+    use crate::test_utils::{default_time_domain, run_all_solvers, simple_model, simple_storage_model};
     use float_cmp::assert_approx_eq;
-    use ndarray::{Array, Array2};
+    use ndarray::{Array, Array1, Array2};
     use std::default::Default;
     use std::ops::Deref;
 
@@ -1770,6 +2467,45 @@ mod tests {
             Err(PywrError::NodeNameAlreadyExists(n)) if n == "my-node"));
     }
 
+    #[test]
+    /// Test setting a node's solver weight.
+    fn test_node_solver_weight() {
+        let mut network = Network::default();
+        network.add_link_node("my-node", None).unwrap();
+
+        let node = network.get_node_by_name("my-node", None).unwrap();
+        assert_eq!(node.get_solver_weight(), 1.0);
+
+        network.set_node_solver_weight("my-node", None, Some(10.0)).unwrap();
+        let node = network.get_node_by_name("my-node", None).unwrap();
+        assert_eq!(node.get_solver_weight(), 10.0);
+
+        assert!(matches!(
+            network.set_node_solver_weight("my-node", None, Some(0.0)),
+            Err(PywrError::InvalidSolverWeight(_))
+        ));
+        assert!(matches!(
+            network.set_node_solver_weight("my-node", None, Some(f64::NAN)),
+            Err(PywrError::InvalidSolverWeight(_))
+        ));
+    }
+
+    #[test]
+    /// Test a link node without any outgoing edges is an error rather than a panic.
+    fn test_link_without_outgoing_edges() {
+        let mut network = Network::default();
+        let input_node = network.add_input_node("input", None).unwrap();
+        let link_node = network.add_link_node("link", None).unwrap();
+        network.connect_nodes(input_node, link_node).unwrap();
+
+        let model = Model::new(default_time_domain().into(), network);
+
+        assert!(matches!(
+            model.setup::<ClpSolver>(&ClpSolverSettings::default()),
+            Err(PywrError::NodeHasNoOutgoingEdges(n)) if n == "link"
+        ));
+    }
+
     #[test]
     /// Test adding a constant parameter to a network.
     fn test_constant_parameter() {
@@ -1815,6 +2551,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_node_flow_bounds_and_cost() {
+        const NUM_SCENARIOS: usize = 2;
+        let model = simple_model(NUM_SCENARIOS, None);
+        let mut timings = RunTimings::default();
+        let mut state = model.setup::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        model.step(&mut state, None, &mut timings).unwrap();
+        let timestep = model.compute_step(&mut state).unwrap();
+        assert_eq!(timestep.index, 1);
+
+        let network = model.network();
+        let input_idx = network.get_node_index_by_name("input", None).unwrap();
+        let output_idx = network.get_node_index_by_name("output", None).unwrap();
+
+        for (j, state_j) in state.network_state().states().iter().enumerate() {
+            let bounds = network.get_node_flow_bounds(&input_idx, &timestep, state_j).unwrap();
+            assert_approx_eq!(f64, bounds.min_flow, 0.0);
+            assert_approx_eq!(f64, bounds.max_flow, 2.0 + j as f64);
+
+            let bounds = network.get_node_flow_bounds(&output_idx, &timestep, state_j).unwrap();
+            assert_approx_eq!(f64, bounds.max_flow, 12.0);
+
+            let cost = network.get_node_cost(&output_idx, state_j).unwrap();
+            assert_approx_eq!(f64, cost.incoming, -10.0);
+        }
+
+        // Storage bounds are converted to flows over the time-step
+        let model = simple_storage_model();
+        let mut state = model.setup::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+        model.step(&mut state, None, &mut timings).unwrap();
+        let timestep = model.compute_step(&mut state).unwrap();
+
+        let network = model.network();
+        let idx = network.get_node_index_by_name("reservoir", None).unwrap();
+        let bounds = network
+            .get_node_flow_bounds(&idx, &timestep, &state.network_state().states()[0])
+            .unwrap();
+        assert_approx_eq!(f64, bounds.min_flow, -90.0);
+        assert_approx_eq!(f64, bounds.max_flow, 10.0);
+    }
+
     #[test]
     /// Test running a simple model
     fn test_run() {
@@ -1847,6 +2625,65 @@ mod tests {
         run_all_solvers(&model, &[], &[], &[]);
     }
 
+    #[test]
+    fn test_lp_statistics() {
+        let model = simple_model(2, None);
+
+        let stats = model.lp_statistics().unwrap();
+
+        assert!(stats.num_rows > 0);
+        assert!(stats.num_cols > 0);
+        assert!(stats.num_non_zero > 0);
+        assert!(stats.rank_estimate <= stats.num_rows.min(stats.num_cols));
+        assert_eq!(stats.max_abs_coefficient, Some(1.0));
+        assert_eq!(stats.min_abs_coefficient, Some(1.0));
+    }
+
+    #[test]
+    /// Test a node with minimum up and down times.
+    fn test_switching_constraints() {
+        let mut network = Network::default();
+
+        let plant = network.add_input_node("plant", None).unwrap();
+        let demand = network.add_output_node("demand", None).unwrap();
+        let spill = network.add_output_node("spill", None).unwrap();
+        network.connect_nodes(plant, demand).unwrap();
+        network.connect_nodes(plant, spill).unwrap();
+
+        let demands = Array1::from_vec(vec![
+            5.0, 0.0, 0.0, 0.0, 5.0, 5.0, 0.0, 0.0, 5.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        ]);
+        let demand_flow = Array1Parameter::new("demand-flow".into(), demands, None);
+        let demand_flow = network.add_simple_parameter(Box::new(demand_flow)).unwrap();
+
+        let node = network.get_mut_node_by_name("plant", None).unwrap();
+        node.set_max_flow_constraint(Some(10.0.into())).unwrap();
+        node.set_cost(Some(1.0.into()));
+        node.set_switching_constraints(Some(SwitchingConstraints::new(3, 2, 4.0)))
+            .unwrap();
+
+        let node = network.get_mut_node_by_name("demand", None).unwrap();
+        node.set_max_flow_constraint(Some(demand_flow.into())).unwrap();
+        node.set_cost(Some((-10.0).into()));
+
+        // Once switched on the plant runs for at least three days, spilling any excess; once
+        // switched off it remains off for at least two days even if there is a demand.
+        let expected = Array2::from_shape_vec(
+            (15, 1),
+            vec![
+                5.0, 4.0, 4.0, 0.0, 0.0, 5.0, 4.0, 4.0, 5.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            ],
+        )
+        .unwrap();
+        let recorder = AssertionRecorder::new("plant-flow", MetricF64::NodeOutFlow(plant), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(default_time_domain().into(), network);
+
+        // Test all solvers
+        run_all_solvers(&model, &["ipm-ocl", "ipm-simd"], &[], &[]);
+    }
+
     #[test]
     fn test_run_storage() {
         let mut model = simple_storage_model();
@@ -1911,6 +2748,98 @@ mod tests {
         run_all_solvers(&model, &[], &[], &[]);
     }
 
+    /// Test that a state override replaces the solved volume and records the residual.
+    #[test]
+    fn test_state_override() {
+        let mut model = simple_storage_model();
+        let network = model.network_mut();
+        let idx = network.get_node_by_name("reservoir", None).unwrap().index();
+
+        // The observed volume is only applied on the third time-step
+        let mut active = Array1::zeros(15);
+        active[2] = 1.0;
+        let active = Array1Parameter::new("observed".into(), active, None);
+        let active = network.add_simple_parameter(Box::new(active)).unwrap();
+
+        let state_override = StateOverride::new(
+            "observed-volume",
+            StateOverrideTarget::NodeVolume(idx),
+            50.0.into(),
+            Some(active.into()),
+        );
+        let override_idx = network.add_state_override(state_override).unwrap();
+
+        let expected = Array2::from_shape_fn((15, 10), |(i, _j)| match i {
+            0 | 1 => 90.0 - 10.0 * i as f64,
+            _ => (70.0 - 10.0 * i as f64).max(0.0),
+        });
+        let recorder = AssertionRecorder::new("reservoir-volume", MetricF64::NodeVolume(idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let expected = Array2::from_shape_fn((15, 10), |(i, _j)| if i == 2 { -20.0 } else { 0.0 });
+        let recorder = AssertionRecorder::new(
+            "override-residual",
+            MetricF64::StateOverrideResidual(override_idx),
+            expected,
+            None,
+            None,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // Test all solvers
+        run_all_solvers(&model, &[], &[], &[]);
+    }
+
+    #[test]
+    fn test_state_override_requires_storage() {
+        let mut model = simple_model(1, None);
+        let network = model.network_mut();
+        let idx = network.get_node_index_by_name("input", None).unwrap();
+
+        let state_override = StateOverride::new("input", StateOverrideTarget::NodeVolume(idx), 0.0.into(), None);
+        let result = network.add_state_override(state_override);
+        assert!(matches!(result, Err(PywrError::InvalidStateOverrideTarget(_))));
+
+        // Flows can only be overridden on link nodes
+        let state_override = StateOverride::new("input", StateOverrideTarget::NodeFlow(idx), 0.0.into(), None);
+        let result = network.add_state_override(state_override);
+        assert!(matches!(result, Err(PywrError::InvalidStateOverrideTarget(_))));
+    }
+
+    /// Test that a state override replaces the solved flow of a link and records the residual.
+    #[test]
+    fn test_state_override_flow() {
+        let mut model = simple_model(1, None);
+        let network = model.network_mut();
+        let idx = network.get_node_index_by_name("link", None).unwrap();
+
+        let state_override = StateOverride::new(
+            "observed-transfer",
+            StateOverrideTarget::NodeFlow(idx),
+            5.0.into(),
+            None,
+        );
+        let override_idx = network.add_state_override(state_override).unwrap();
+
+        let expected = Array2::from_elem((15, 1), 5.0);
+        let recorder = AssertionRecorder::new("link-flow", MetricF64::NodeInFlow(idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // The solved flow is limited by the inflow (1.0 + i) and the demand (12.0)
+        let expected = Array2::from_shape_fn((15, 1), |(i, _j)| 5.0 - (1.0 + i as f64).min(12.0));
+        let recorder = AssertionRecorder::new(
+            "override-residual",
+            MetricF64::StateOverrideResidual(override_idx),
+            expected,
+            None,
+            None,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // Test all solvers
+        run_all_solvers(&model, &[], &[], &[]);
+    }
+
     #[test]
     /// Test `ScenarioGroupCollection` iteration
     fn test_scenario_iteration() {
@@ -1923,92 +2852,64 @@ mod tests {
         let mut iter = domain.indices().iter();
 
         // Test generation of scenario indices
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 0,
-                indices: vec![0, 0, 0]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 1,
-                indices: vec![0, 0, 1]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 2,
-                indices: vec![0, 0, 2]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 3,
-                indices: vec![0, 0, 3]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 4,
-                indices: vec![0, 0, 4]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 5,
-                indices: vec![0, 1, 0]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 6,
-                indices: vec![0, 1, 1]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 7,
-                indices: vec![0, 1, 2]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 8,
-                indices: vec![0, 1, 3]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 9,
-                indices: vec![0, 1, 4]
-            })
-        );
-        assert_eq!(
-            iter.next(),
-            Some(&ScenarioIndex {
-                index: 10,
-                indices: vec![1, 0, 0]
-            })
-        );
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(0, vec![0, 0, 0])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(1, vec![0, 0, 1])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(2, vec![0, 0, 2])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(3, vec![0, 0, 3])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(4, vec![0, 0, 4])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(5, vec![0, 1, 0])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(6, vec![0, 1, 1])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(7, vec![0, 1, 2])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(8, vec![0, 1, 3])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(9, vec![0, 1, 4])));
+        assert_eq!(iter.next(), Some(&ScenarioIndex::new(10, vec![1, 0, 0])));
 
         // Test final index
-        assert_eq!(
-            iter.last(),
-            Some(&ScenarioIndex {
-                index: 99,
-                indices: vec![9, 1, 4]
-            })
+        assert_eq!(iter.last(), Some(&ScenarioIndex::new(99, vec![9, 1, 4])));
+    }
+
+    /// Test that a NaN value computed by a parameter reports the metrics it was computed from.
+    #[test]
+    fn test_parameter_nan_backtrace() {
+        let mut model = simple_model(1, None);
+        let network = model.network_mut();
+
+        let input_idx = network.get_node_index_by_name("input", None).unwrap();
+        let scaled = AggregatedParameter::new(
+            "scaled".into(),
+            &[MetricF64::NodeInFlow(input_idx), 0.0.into()],
+            AggFunc::Sum,
+        );
+        let scaled_idx = network.add_parameter(Box::new(scaled)).unwrap();
+
+        // The inflow is zero before the first time-step is solved, so this is `0.0 * inf`
+        let nan = AggregatedParameter::new(
+            "nan".into(),
+            &[scaled_idx.into(), f64::INFINITY.into()],
+            AggFunc::Product,
         );
+        network.add_parameter(Box::new(nan)).unwrap();
+
+        let error = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap_err();
+        let PywrError::ParameterNaNValue { name, backtrace } = &error else {
+            panic!("Unexpected error: {error}");
+        };
+
+        assert_eq!(name, "nan");
+        assert_eq!(backtrace.0.len(), 2);
+
+        let scaled_trace = &backtrace.0[0];
+        assert_eq!(scaled_trace.description, "value of parameter `scaled`");
+        assert_eq!(scaled_trace.value, Some(0.0));
+        assert_eq!(scaled_trace.dependencies.len(), 2);
+        assert_eq!(scaled_trace.dependencies[0].description, "inflow of node `input`");
+        assert_eq!(scaled_trace.dependencies[0].value, Some(0.0));
+
+        assert_eq!(backtrace.0[1].value, Some(f64::INFINITY));
+
+        let message = error.to_string();
+        assert!(message.contains("value of parameter `scaled` = 0"));
+        assert!(message.contains("\n    - inflow of node `input` = 0"));
     }
 
     #[test]
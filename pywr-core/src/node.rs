@@ -1,7 +1,7 @@
 use crate::edge::EdgeIndex;
 use crate::metric::{MetricF64, SimpleMetricF64};
 use crate::network::Network;
-use crate::state::{ConstParameterValues, NodeState, SimpleParameterValues, State};
+use crate::state::{ConstParameterValues, NodeState, SimpleParameterValues, State, SwitchState};
 use crate::timestep::Timestep;
 use crate::virtual_storage::VirtualStorageIndex;
 use crate::PywrError;
@@ -118,6 +118,15 @@ pub enum NodeBounds {
     Volume(VolumeBounds),
 }
 
+/// The costs of a node as seen by the solver.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeCost {
+    /// Cost of flow into the node
+    pub incoming: f64,
+    /// Cost of flow out of the node
+    pub outgoing: f64,
+}
+
 #[derive(Debug, Clone)]
 pub enum Constraint {
     MinFlow,
@@ -406,6 +415,70 @@ impl Node {
         }
     }
 
+    /// Set the weight applied to this node's constraints by solvers that support it.
+    ///
+    /// The weight scales the node's constraint rows, and therefore the residuals used to test
+    /// convergence. A weight greater than one tightens the effective tolerance of this node's
+    /// constraints relative to the rest of the network. The weight must be finite and positive.
+    pub fn set_solver_weight(&mut self, weight: Option<f64>) -> Result<(), PywrError> {
+        if let Some(w) = weight {
+            if !w.is_finite() || w <= 0.0 {
+                return Err(PywrError::InvalidSolverWeight(w));
+            }
+        }
+
+        match self {
+            Self::Input(n) => n.solver_weight = weight,
+            Self::Link(n) => n.solver_weight = weight,
+            Self::Output(n) => n.solver_weight = weight,
+            Self::Storage(n) => n.solver_weight = weight,
+        };
+
+        Ok(())
+    }
+
+    /// Get the weight applied to this node's constraints by solvers that support it.
+    ///
+    /// Defaults to one if no weight has been set.
+    pub fn get_solver_weight(&self) -> f64 {
+        let weight = match self {
+            Self::Input(n) => n.solver_weight,
+            Self::Link(n) => n.solver_weight,
+            Self::Output(n) => n.solver_weight,
+            Self::Storage(n) => n.solver_weight,
+        };
+        weight.unwrap_or(1.0)
+    }
+
+    /// Set the constraints on how often this node can be switched on and off.
+    ///
+    /// See [`SwitchingConstraints`] for more information.
+    pub fn set_switching_constraints(&mut self, value: Option<SwitchingConstraints>) -> Result<(), PywrError> {
+        if let Some(switching) = &value {
+            if !switching.min_on_flow.is_finite() || switching.min_on_flow < 0.0 {
+                return Err(PywrError::InvalidMinOnFlow(switching.min_on_flow));
+            }
+        }
+
+        match self {
+            Self::Input(n) => n.flow_constraints.switching = value,
+            Self::Link(n) => n.flow_constraints.switching = value,
+            Self::Output(n) => n.flow_constraints.switching = value,
+            Self::Storage(_) => return Err(PywrError::FlowConstraintsUndefined),
+        };
+
+        Ok(())
+    }
+
+    pub fn get_switching_constraints(&self) -> Option<&SwitchingConstraints> {
+        match self {
+            Self::Input(n) => n.flow_constraints.switching.as_ref(),
+            Self::Link(n) => n.flow_constraints.switching.as_ref(),
+            Self::Output(n) => n.flow_constraints.switching.as_ref(),
+            Self::Storage(_) => None,
+        }
+    }
+
     pub fn set_min_volume_constraint(&mut self, value: Option<SimpleMetricF64>) -> Result<(), PywrError> {
         match self {
             Self::Input(_) => Err(PywrError::StorageConstraintsUndefined),
@@ -593,10 +666,69 @@ where
     }
 }
 
+/// Whether a node with [`SwitchingConstraints`] may change its on/off status in a time-step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchStatus {
+    /// The node may be switched on or off.
+    Free,
+    /// The node has not been on for its minimum up time and must remain on.
+    MustBeOn,
+    /// The node has not been off for its minimum down time and must remain off.
+    MustBeOff,
+}
+
+/// Constraints on how often a node can be switched on and off.
+///
+/// These are intended for assets, such as desalination plants, that cannot be started and
+/// stopped freely. A node is on in a time-step if its flow is greater than zero. Once switched
+/// on the node must remain on for at least `min_up_time` time-steps, during which its flow is
+/// at least `min_on_flow`. Once switched off it must remain off for at least `min_down_time`
+/// time-steps, during which its flow is zero. The node is free to switch at the start of a run.
+///
+/// Solvers that support integer variables (e.g. CBC and HiGHS) use a binary variable for the
+/// node's status; when the node is free to switch its flow is either zero or at least
+/// `min_on_flow`. Other solvers (e.g. CLP) solve the relaxation of this formulation. This is a
+/// heuristic that enforces the minimum up and down times exactly, but allows a flow between zero
+/// and `min_on_flow` when the node is free to switch. Such a flow switches the node on.
+///
+/// Nodes with switching constraints must not have a non-zero minimum flow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwitchingConstraints {
+    /// The minimum number of time-steps the node must remain on once switched on.
+    pub min_up_time: usize,
+    /// The minimum number of time-steps the node must remain off once switched off.
+    pub min_down_time: usize,
+    /// The minimum flow through the node while it is on.
+    pub min_on_flow: f64,
+}
+
+impl SwitchingConstraints {
+    pub fn new(min_up_time: usize, min_down_time: usize, min_on_flow: f64) -> Self {
+        Self {
+            min_up_time,
+            min_down_time,
+            min_on_flow,
+        }
+    }
+
+    /// Determine whether the node may change its status given its status in the previous
+    /// time-steps.
+    pub fn status(&self, state: &SwitchState) -> SwitchStatus {
+        if state.is_on && state.duration < self.min_up_time {
+            SwitchStatus::MustBeOn
+        } else if !state.is_on && state.duration < self.min_down_time {
+            SwitchStatus::MustBeOff
+        } else {
+            SwitchStatus::Free
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Default)]
 pub struct FlowConstraints {
     pub min_flow: Option<MetricF64>,
     pub max_flow: Option<MetricF64>,
+    pub switching: Option<SwitchingConstraints>,
 }
 
 impl FlowConstraints {
@@ -733,6 +865,7 @@ pub struct InputNode {
     cost: NodeCost,
     pub flow_constraints: FlowConstraints,
     pub outgoing_edges: Vec<EdgeIndex>,
+    pub solver_weight: Option<f64>,
 }
 
 impl InputNode {
@@ -742,6 +875,7 @@ impl InputNode {
             cost: NodeCost::default(),
             flow_constraints: FlowConstraints::default(),
             outgoing_edges: Vec::new(),
+            solver_weight: None,
         }
     }
     fn set_cost(&mut self, value: Option<MetricF64>) {
@@ -785,6 +919,7 @@ pub struct OutputNode {
     cost: NodeCost,
     pub flow_constraints: FlowConstraints,
     pub incoming_edges: Vec<EdgeIndex>,
+    pub solver_weight: Option<f64>,
 }
 
 impl OutputNode {
@@ -794,6 +929,7 @@ impl OutputNode {
             cost: NodeCost::default(),
             flow_constraints: FlowConstraints::default(),
             incoming_edges: Vec::new(),
+            solver_weight: None,
         }
     }
     fn set_cost(&mut self, value: Option<MetricF64>) {
@@ -838,6 +974,7 @@ pub struct LinkNode {
     pub flow_constraints: FlowConstraints,
     pub incoming_edges: Vec<EdgeIndex>,
     pub outgoing_edges: Vec<EdgeIndex>,
+    pub solver_weight: Option<f64>,
 }
 
 impl LinkNode {
@@ -848,6 +985,7 @@ impl LinkNode {
             flow_constraints: FlowConstraints::default(),
             incoming_edges: Vec::new(),
             outgoing_edges: Vec::new(),
+            solver_weight: None,
         }
     }
     fn set_cost(&mut self, value: Option<MetricF64>) {
@@ -902,6 +1040,7 @@ pub struct StorageNode {
     pub storage_constraints: StorageConstraints,
     pub incoming_edges: Vec<EdgeIndex>,
     pub outgoing_edges: Vec<EdgeIndex>,
+    pub solver_weight: Option<f64>,
 }
 
 impl StorageNode {
@@ -920,6 +1059,7 @@ impl StorageNode {
             storage_constraints: StorageConstraints::new(min_volume, max_volume),
             incoming_edges: Vec::new(),
             outgoing_edges: Vec::new(),
+            solver_weight: None,
         }
     }
 
@@ -0,0 +1,285 @@
+//! Optimisation of the variable parameters of a model.
+//!
+//! A [`Problem`] defines the variables of a model, which are parameters that implement
+//! [`crate::parameters::VariableParameter`], and the objectives, which are the aggregated values
+//! of recorders at the end of a run. The problem can then be optimised with the NSGA-II
+//! multi-objective evolutionary algorithm (see [`Problem::optimise`]) to find a set of
+//! Pareto-optimal solutions.
+mod nsga2;
+
+use crate::models::Model;
+use crate::parameters::{ParameterIndex, VariableConfig};
+use crate::solvers::{Solver, SolverSettings};
+use crate::PywrError;
+pub use nsga2::Nsga2Settings;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OptimisationError {
+    #[error("the problem has no variables")]
+    NoVariables,
+    #[error("the problem has no objectives")]
+    NoObjectives,
+    #[error("the population size must be at least 2; found {0}")]
+    InvalidPopulationSize(usize),
+    #[error("expected {expected} variable values but found {found}")]
+    VariableCountMismatch { expected: usize, found: usize },
+    #[error("model error: {0}")]
+    Model(#[from] PywrError),
+}
+
+/// Whether an objective is to be minimised or maximised.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ObjectiveSense {
+    Minimise,
+    Maximise,
+}
+
+/// An objective of an optimisation problem.
+///
+/// The value of the objective is the aggregated value of the named recorder at the end of
+/// each run of the model (see [`crate::recorders::Recorder::aggregated_value`]).
+#[derive(Debug, Clone)]
+pub struct Objective {
+    recorder: String,
+    sense: ObjectiveSense,
+}
+
+impl Objective {
+    pub fn new(recorder: &str, sense: ObjectiveSense) -> Self {
+        Self {
+            recorder: recorder.to_string(),
+            sense,
+        }
+    }
+
+    pub fn recorder(&self) -> &str {
+        &self.recorder
+    }
+
+    pub fn sense(&self) -> ObjectiveSense {
+        self.sense
+    }
+
+    /// The value of the objective to be minimised.
+    fn fitness(&self, value: f64) -> f64 {
+        match self.sense {
+            ObjectiveSense::Minimise => value,
+            ObjectiveSense::Maximise => -value,
+        }
+    }
+}
+
+/// A variable parameter of an optimisation problem.
+struct Variable {
+    parameter_index: ParameterIndex<f64>,
+    config: Box<dyn VariableConfig>,
+    lower_bounds: Vec<f64>,
+    upper_bounds: Vec<f64>,
+}
+
+/// A solution to an optimisation problem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// The values of the variables of each parameter, in the order they were added to the problem.
+    pub variables: Vec<f64>,
+    /// The value of each objective.
+    pub objectives: Vec<f64>,
+}
+
+/// An optimisation problem of the variable parameters of a [`Model`].
+pub struct Problem<'a> {
+    model: &'a Model,
+    variables: Vec<Variable>,
+    objectives: Vec<Objective>,
+}
+
+impl<'a> Problem<'a> {
+    pub fn new(model: &'a Model) -> Self {
+        Self {
+            model,
+            variables: Vec::new(),
+            objectives: Vec::new(),
+        }
+    }
+
+    /// Add the parameter [`parameter_index`] as a variable of the problem.
+    ///
+    /// The number of variable values and their bounds are defined by the parameter using the
+    /// given `config` (see [`crate::parameters::VariableParameter`]). An error is returned if
+    /// any of the bounds are not finite or a lower bound is greater than its upper bound.
+    pub fn add_variable(
+        &mut self,
+        parameter_index: ParameterIndex<f64>,
+        config: Box<dyn VariableConfig>,
+    ) -> Result<(), PywrError> {
+        let (lower_bounds, upper_bounds) = self
+            .model
+            .network()
+            .get_f64_parameter_variable_bounds(parameter_index, config.as_ref())?;
+
+        for (&lower, &upper) in lower_bounds.iter().zip(upper_bounds.iter()) {
+            if !lower.is_finite() || !upper.is_finite() || lower > upper {
+                return Err(PywrError::InvalidVariableBounds { lower, upper });
+            }
+        }
+
+        self.variables.push(Variable {
+            parameter_index,
+            config,
+            lower_bounds,
+            upper_bounds,
+        });
+
+        Ok(())
+    }
+
+    /// Add an objective to the problem.
+    ///
+    /// An error is returned if the model does not have a recorder with the objective's name.
+    pub fn add_objective(&mut self, objective: Objective) -> Result<(), PywrError> {
+        self.model.network().get_recorder_by_name(&objective.recorder)?;
+        self.objectives.push(objective);
+        Ok(())
+    }
+
+    pub fn objectives(&self) -> &[Objective] {
+        &self.objectives
+    }
+
+    /// The total number of variable values of all the variable parameters.
+    pub fn num_variables(&self) -> usize {
+        self.variables.iter().map(|v| v.lower_bounds.len()).sum()
+    }
+
+    /// The lower bounds of all the variable values.
+    pub fn lower_bounds(&self) -> Vec<f64> {
+        self.variables
+            .iter()
+            .flat_map(|v| v.lower_bounds.iter().copied())
+            .collect()
+    }
+
+    /// The upper bounds of all the variable values.
+    pub fn upper_bounds(&self) -> Vec<f64> {
+        self.variables
+            .iter()
+            .flat_map(|v| v.upper_bounds.iter().copied())
+            .collect()
+    }
+
+    /// Run the model with the given variable values and return the value of each objective.
+    pub fn evaluate<S>(&self, values: &[f64], settings: &S::Settings) -> Result<Vec<f64>, OptimisationError>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings,
+    {
+        let expected = self.num_variables();
+        if values.len() != expected {
+            return Err(OptimisationError::VariableCountMismatch {
+                expected,
+                found: values.len(),
+            });
+        }
+
+        let network = self.model.network();
+        let mut state = self.model.setup::<S>(settings)?;
+
+        let mut offset = 0;
+        for variable in &self.variables {
+            let size = variable.lower_bounds.len();
+            network.set_f64_parameter_variable_values(
+                variable.parameter_index,
+                &values[offset..offset + size],
+                variable.config.as_ref(),
+                state.network_state_mut(),
+            )?;
+            offset += size;
+        }
+
+        self.model.run_with_state::<S>(&mut state, settings)?;
+
+        let objectives = self
+            .objectives
+            .iter()
+            .map(|o| network.get_aggregated_value(&o.recorder, state.recorder_state()))
+            .collect::<Result<_, _>>()?;
+
+        Ok(objectives)
+    }
+
+    /// Optimise the problem using the NSGA-II algorithm.
+    ///
+    /// Each evaluation of the algorithm is a run of the model with the solver [`S`]. The
+    /// non-dominated solutions of the final population (i.e. the approximate Pareto front)
+    /// are returned.
+    pub fn optimise<S>(
+        &self,
+        solver_settings: &S::Settings,
+        settings: &Nsga2Settings,
+    ) -> Result<Vec<Solution>, OptimisationError>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings,
+    {
+        if self.variables.is_empty() {
+            return Err(OptimisationError::NoVariables);
+        }
+        if self.objectives.is_empty() {
+            return Err(OptimisationError::NoObjectives);
+        }
+
+        nsga2::optimise(
+            &self.lower_bounds(),
+            &self.upper_bounds(),
+            settings,
+            |values: &[f64]| {
+                let objectives = self.evaluate::<S>(values, solver_settings)?;
+                let fitness = self
+                    .objectives
+                    .iter()
+                    .zip(&objectives)
+                    .map(|(o, v)| o.fitness(*v))
+                    .collect();
+                Ok((objectives, fitness))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Problem;
+    use crate::parameters::{ActivationFunction, ConstantParameter};
+    use crate::test_utils::simple_model;
+    use crate::PywrError;
+
+    #[test]
+    fn test_invalid_variable_bounds() {
+        let mut model = simple_model(1, None);
+        let idx = model
+            .network_mut()
+            .add_const_parameter(Box::new(ConstantParameter::new("my-constant".into(), 10.0)))
+            .unwrap();
+
+        let mut problem = Problem::new(&model);
+
+        let result = problem.add_variable(
+            idx,
+            Box::new(ActivationFunction::Unit {
+                min: 0.0,
+                max: f64::INFINITY,
+            }),
+        );
+        assert!(matches!(result, Err(PywrError::InvalidVariableBounds { .. })));
+
+        let result = problem.add_variable(idx, Box::new(ActivationFunction::Unit { min: 10.0, max: 0.0 }));
+        assert!(matches!(result, Err(PywrError::InvalidVariableBounds { .. })));
+        assert_eq!(problem.num_variables(), 0);
+
+        problem
+            .add_variable(idx, Box::new(ActivationFunction::Unit { min: 0.0, max: 10.0 }))
+            .unwrap();
+        assert_eq!(problem.num_variables(), 1);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::optimise::{OptimisationError, Solution};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cmp::Ordering;
+use tracing::info;
+
+/// Settings for the NSGA-II algorithm.
+///
+/// The variables are recombined with simulated binary crossover (SBX) and mutated with
+/// polynomial mutation. Each variable is mutated with a probability of one over the number
+/// of variables.
+#[derive(Debug, Clone)]
+pub struct Nsga2Settings {
+    population_size: usize,
+    generations: usize,
+    crossover_probability: f64,
+    crossover_eta: f64,
+    mutation_eta: f64,
+    seed: u64,
+}
+
+impl Default for Nsga2Settings {
+    fn default() -> Self {
+        Self {
+            population_size: 40,
+            generations: 25,
+            crossover_probability: 0.9,
+            crossover_eta: 15.0,
+            mutation_eta: 20.0,
+            seed: 0,
+        }
+    }
+}
+
+impl Nsga2Settings {
+    /// The number of solutions in each generation.
+    pub fn with_population_size(mut self, population_size: usize) -> Self {
+        self.population_size = population_size;
+        self
+    }
+
+    /// The number of generations to evolve after the initial population.
+    pub fn with_generations(mut self, generations: usize) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// The probability that a pair of parents are recombined.
+    pub fn with_crossover_probability(mut self, probability: f64) -> Self {
+        self.crossover_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The distribution index of the crossover; larger values create children closer to their parents.
+    pub fn with_crossover_eta(mut self, eta: f64) -> Self {
+        self.crossover_eta = eta;
+        self
+    }
+
+    /// The distribution index of the mutation; larger values create smaller mutations.
+    pub fn with_mutation_eta(mut self, eta: f64) -> Self {
+        self.mutation_eta = eta;
+        self
+    }
+
+    /// The seed of the random number generator.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+struct Individual {
+    variables: Vec<f64>,
+    objectives: Vec<f64>,
+    /// The objective values to be minimised.
+    fitness: Vec<f64>,
+    rank: usize,
+    crowding: f64,
+}
+
+impl Individual {
+    fn new(variables: Vec<f64>, objectives: Vec<f64>, fitness: Vec<f64>) -> Self {
+        // Solutions with undefined objectives are dominated by all other solutions.
+        let fitness = fitness
+            .into_iter()
+            .map(|f| if f.is_nan() { f64::INFINITY } else { f })
+            .collect();
+
+        Self {
+            variables,
+            objectives,
+            fitness,
+            rank: 0,
+            crowding: 0.0,
+        }
+    }
+
+    /// Compare by rank and then crowding distance; the better individual is `Ordering::Less`.
+    fn crowded_cmp(&self, other: &Self) -> Ordering {
+        self.rank
+            .cmp(&other.rank)
+            .then_with(|| other.crowding.total_cmp(&self.crowding))
+    }
+}
+
+/// Returns `true` if `a` dominates `b` when minimising.
+fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x <= y) && a.iter().zip(b).any(|(x, y)| x < y)
+}
+
+/// Sort the fitness values in to fronts of non-dominated solutions.
+///
+/// Returns the indices of the solutions in each front, starting with the non-dominated front.
+fn non_dominated_sort(fitness: &[&[f64]]) -> Vec<Vec<usize>> {
+    let n = fitness.len();
+    let mut dominated_by: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut domination_count = vec![0usize; n];
+
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if dominates(fitness[i], fitness[j]) {
+                dominated_by[i].push(j);
+                domination_count[j] += 1;
+            } else if dominates(fitness[j], fitness[i]) {
+                dominated_by[j].push(i);
+                domination_count[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut current: Vec<usize> = (0..n).filter(|i| domination_count[*i] == 0).collect();
+
+    while !current.is_empty() {
+        let mut next = Vec::new();
+        for i in &current {
+            for j in &dominated_by[*i] {
+                domination_count[*j] -= 1;
+                if domination_count[*j] == 0 {
+                    next.push(*j);
+                }
+            }
+        }
+        fronts.push(current);
+        current = next;
+    }
+
+    fronts
+}
+
+/// Calculate the crowding distance of each solution in a front.
+fn crowding_distance(front: &[usize], fitness: &[&[f64]]) -> Vec<f64> {
+    let mut distance = vec![0.0; front.len()];
+    if front.len() <= 2 {
+        distance.fill(f64::INFINITY);
+        return distance;
+    }
+
+    let num_objectives = fitness[front[0]].len();
+    let mut order: Vec<usize> = (0..front.len()).collect();
+
+    for m in 0..num_objectives {
+        order.sort_by(|a, b| fitness[front[*a]][m].total_cmp(&fitness[front[*b]][m]));
+
+        let min = fitness[front[order[0]]][m];
+        let max = fitness[front[order[front.len() - 1]]][m];
+
+        distance[order[0]] = f64::INFINITY;
+        distance[order[front.len() - 1]] = f64::INFINITY;
+
+        let range = max - min;
+        if range <= 0.0 || !range.is_finite() {
+            continue;
+        }
+
+        for k in 1..(front.len() - 1) {
+            distance[order[k]] += (fitness[front[order[k + 1]]][m] - fitness[front[order[k - 1]]][m]) / range;
+        }
+    }
+
+    distance
+}
+
+/// Assign the rank and crowding distance of each individual.
+fn rank_population(population: &mut [Individual]) -> Vec<Vec<usize>> {
+    let fitness: Vec<&[f64]> = population.iter().map(|i| i.fitness.as_slice()).collect();
+    let fronts = non_dominated_sort(&fitness);
+    let distances: Vec<Vec<f64>> = fronts.iter().map(|f| crowding_distance(f, &fitness)).collect();
+
+    for (rank, (front, distance)) in fronts.iter().zip(distances).enumerate() {
+        for (i, d) in front.iter().zip(distance) {
+            population[*i].rank = rank;
+            population[*i].crowding = d;
+        }
+    }
+
+    fronts
+}
+
+/// Select an individual by binary tournament.
+fn tournament<'a>(population: &'a [Individual], rng: &mut ChaCha8Rng) -> &'a Individual {
+    let a = &population[rng.gen_range(0..population.len())];
+    let b = &population[rng.gen_range(0..population.len())];
+    match a.crowded_cmp(b) {
+        Ordering::Greater => b,
+        _ => a,
+    }
+}
+
+/// Recombine two parents using simulated binary crossover (SBX).
+fn crossover(
+    p1: &[f64],
+    p2: &[f64],
+    lower: &[f64],
+    upper: &[f64],
+    settings: &Nsga2Settings,
+    rng: &mut ChaCha8Rng,
+) -> (Vec<f64>, Vec<f64>) {
+    let mut c1 = p1.to_vec();
+    let mut c2 = p2.to_vec();
+
+    if rng.gen::<f64>() > settings.crossover_probability {
+        return (c1, c2);
+    }
+
+    let eta = settings.crossover_eta;
+    let spread = |beta: f64, u: f64| {
+        let alpha = 2.0 - beta.powf(-(eta + 1.0));
+        if u <= 1.0 / alpha {
+            (u * alpha).powf(1.0 / (eta + 1.0))
+        } else {
+            (1.0 / (2.0 - u * alpha)).powf(1.0 / (eta + 1.0))
+        }
+    };
+
+    for i in 0..c1.len() {
+        if rng.gen::<f64>() > 0.5 || (p1[i] - p2[i]).abs() <= 1e-14 {
+            continue;
+        }
+
+        let (y1, y2) = if p1[i] < p2[i] { (p1[i], p2[i]) } else { (p2[i], p1[i]) };
+        let (yl, yu) = (lower[i], upper[i]);
+        let u = rng.gen::<f64>();
+
+        let betaq = spread(1.0 + 2.0 * (y1 - yl) / (y2 - y1), u);
+        let v1 = (0.5 * ((y1 + y2) - betaq * (y2 - y1))).clamp(yl, yu);
+
+        let betaq = spread(1.0 + 2.0 * (yu - y2) / (y2 - y1), u);
+        let v2 = (0.5 * ((y1 + y2) + betaq * (y2 - y1))).clamp(yl, yu);
+
+        if rng.gen_bool(0.5) {
+            c1[i] = v2;
+            c2[i] = v1;
+        } else {
+            c1[i] = v1;
+            c2[i] = v2;
+        }
+    }
+
+    (c1, c2)
+}
+
+/// Apply polynomial mutation to the variables.
+fn mutate(x: &mut [f64], lower: &[f64], upper: &[f64], settings: &Nsga2Settings, rng: &mut ChaCha8Rng) {
+    let probability = 1.0 / x.len() as f64;
+    let eta = settings.mutation_eta;
+
+    for i in 0..x.len() {
+        let (yl, yu) = (lower[i], upper[i]);
+        if rng.gen::<f64>() >= probability || yu <= yl {
+            continue;
+        }
+
+        let y = x[i];
+        let u = rng.gen::<f64>();
+        let deltaq = if u < 0.5 {
+            let xy = 1.0 - (y - yl) / (yu - yl);
+            let val = 2.0 * u + (1.0 - 2.0 * u) * xy.powf(eta + 1.0);
+            val.powf(1.0 / (eta + 1.0)) - 1.0
+        } else {
+            let xy = 1.0 - (yu - y) / (yu - yl);
+            let val = 2.0 * (1.0 - u) + 2.0 * (u - 0.5) * xy.powf(eta + 1.0);
+            1.0 - val.powf(1.0 / (eta + 1.0))
+        };
+
+        x[i] = (y + deltaq * (yu - yl)).clamp(yl, yu);
+    }
+}
+
+/// Optimise using the NSGA-II algorithm.
+///
+/// `evaluate` returns the objective values of the given variables, and the corresponding
+/// values to be minimised. The non-dominated solutions of the final population are returned.
+pub(crate) fn optimise<F>(
+    lower: &[f64],
+    upper: &[f64],
+    settings: &Nsga2Settings,
+    mut evaluate: F,
+) -> Result<Vec<Solution>, OptimisationError>
+where
+    F: FnMut(&[f64]) -> Result<(Vec<f64>, Vec<f64>), OptimisationError>,
+{
+    let n = settings.population_size;
+    if n < 2 {
+        return Err(OptimisationError::InvalidPopulationSize(n));
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(settings.seed);
+    let mut create = |variables: Vec<f64>| -> Result<Individual, OptimisationError> {
+        let (objectives, fitness) = evaluate(&variables)?;
+        Ok(Individual::new(variables, objectives, fitness))
+    };
+
+    let mut population = Vec::with_capacity(2 * n);
+    for _ in 0..n {
+        let variables = lower
+            .iter()
+            .zip(upper)
+            .map(|(l, u)| if u > l { rng.gen_range(*l..=*u) } else { *l })
+            .collect();
+        population.push(create(variables)?);
+    }
+    rank_population(&mut population);
+
+    for generation in 0..settings.generations {
+        let mut offspring = Vec::with_capacity(n);
+        while offspring.len() < n {
+            let p1 = tournament(&population, &mut rng);
+            let p2 = tournament(&population, &mut rng);
+            let (mut c1, mut c2) = crossover(&p1.variables, &p2.variables, lower, upper, settings, &mut rng);
+
+            mutate(&mut c1, lower, upper, settings, &mut rng);
+            offspring.push(c1);
+            if offspring.len() < n {
+                mutate(&mut c2, lower, upper, settings, &mut rng);
+                offspring.push(c2);
+            }
+        }
+
+        for variables in offspring {
+            population.push(create(variables)?);
+        }
+
+        // Select the next generation from the best fronts of the parents and offspring.
+        let fronts = rank_population(&mut population);
+        let mut selected = vec![false; population.len()];
+        let mut count = 0;
+        for front in fronts {
+            if count + front.len() <= n {
+                for i in front {
+                    selected[i] = true;
+                    count += 1;
+                }
+            } else {
+                let mut front = front;
+                front.sort_by(|a, b| population[*a].crowded_cmp(&population[*b]));
+                for i in front.into_iter().take(n - count) {
+                    selected[i] = true;
+                    count += 1;
+                }
+            }
+            if count == n {
+                break;
+            }
+        }
+
+        let mut selected = selected.into_iter();
+        population.retain(|_| selected.next().unwrap_or(false));
+        let fronts = rank_population(&mut population);
+
+        info!(
+            "NSGA-II generation {} of {}: {} non-dominated solutions.",
+            generation + 1,
+            settings.generations,
+            fronts.first().map(|f| f.len()).unwrap_or_default()
+        );
+    }
+
+    Ok(population
+        .into_iter()
+        .filter(|i| i.rank == 0)
+        .map(|i| Solution {
+            variables: i.variables,
+            objectives: i.objectives,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{non_dominated_sort, optimise, Nsga2Settings};
+
+    #[test]
+    fn test_non_dominated_sort() {
+        let fitness: Vec<&[f64]> = vec![&[1.0, 4.0], &[2.0, 2.0], &[3.0, 3.0], &[4.0, 1.0], &[4.0, 4.0]];
+        let fronts = non_dominated_sort(&fitness);
+
+        assert_eq!(fronts, vec![vec![0, 1, 3], vec![2], vec![4]]);
+    }
+
+    #[test]
+    fn test_schaffer() {
+        // Schaffer's problem N1; the Pareto optimal solutions are 0 <= x <= 2.
+        let settings = Nsga2Settings::default()
+            .with_population_size(20)
+            .with_generations(20)
+            .with_seed(42);
+
+        let solutions = optimise(&[-10.0], &[10.0], &settings, |x: &[f64]| {
+            let objectives = vec![x[0].powi(2), (x[0] - 2.0).powi(2)];
+            Ok((objectives.clone(), objectives))
+        })
+        .unwrap();
+
+        assert!(!solutions.is_empty());
+        for s in solutions {
+            assert!(s.variables[0] >= -0.1 && s.variables[0] <= 2.1, "{:?}", s);
+        }
+    }
+}
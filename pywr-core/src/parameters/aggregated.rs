@@ -116,6 +116,10 @@ impl GeneralParameter<f64> for AggregatedParameter<MetricF64> {
         self
     }
 
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        self.metrics.iter().collect()
+    }
+
     fn try_into_simple(&self) -> Option<Box<dyn SimpleParameter<f64>>> {
         // We can make a simple version if all metrics can be simplified
         let metrics: Vec<SimpleMetricF64> = self
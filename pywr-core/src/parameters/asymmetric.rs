@@ -49,7 +49,7 @@ impl GeneralParameter<u64> for AsymmetricSwitchIndexParameter {
         let on_value = self.on_parameter.get_value(network, state)?;
 
         // Downcast the internal state to the correct type
-        let current_state = downcast_internal_state_mut::<u64>(internal_state);
+        let current_state = downcast_internal_state_mut::<u64>(&self.meta.name, internal_state)?;
 
         if *current_state > 0 {
             if on_value > 0 {
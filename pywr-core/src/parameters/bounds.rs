@@ -0,0 +1,179 @@
+use crate::parameters::{
+    ConstParameterIndex, GeneralParameterIndex, ParameterCollectionSize, ParameterIndex, SimpleParameterIndex,
+};
+use crate::PywrError;
+use std::ops::Deref;
+
+/// Optional lower and upper bounds applied to the value computed by a parameter.
+///
+/// If the computed value is outside the bounds it is clamped to the nearest bound. Each time
+/// this occurs a counter is incremented for the parameter so that clamping can be reported
+/// to the user, rather than silently hiding out-of-range values.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ParameterBounds {
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl ParameterBounds {
+    pub fn new(min: Option<f64>, max: Option<f64>) -> Result<Self, PywrError> {
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                return Err(PywrError::InvalidParameterBounds { min, max });
+            }
+        }
+
+        Ok(Self { min, max })
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    /// Clamp `value` to the bounds.
+    ///
+    /// Returns `None` if the value is within the bounds, otherwise the clamped value.
+    pub fn clamp(&self, value: f64) -> Option<f64> {
+        match (self.min, self.max) {
+            (Some(min), _) if value < min => Some(min),
+            (_, Some(max)) if value > max => Some(max),
+            _ => None,
+        }
+    }
+}
+
+/// The bounds of the f64 parameters in a [`crate::parameters::ParameterCollection`].
+///
+/// The bounds are stored by parameter type so that they can be looked up by index when the
+/// parameters are computed.
+#[derive(Default)]
+pub(crate) struct F64ParameterBounds {
+    constant: Vec<Option<ParameterBounds>>,
+    simple: Vec<Option<ParameterBounds>>,
+    general: Vec<Option<ParameterBounds>>,
+}
+
+impl F64ParameterBounds {
+    pub(crate) fn set(&mut self, index: ParameterIndex<f64>, bounds: ParameterBounds) {
+        let (values, idx) = match index {
+            ParameterIndex::Const(idx) => (&mut self.constant, *idx.deref()),
+            ParameterIndex::Simple(idx) => (&mut self.simple, *idx.deref()),
+            ParameterIndex::General(idx) => (&mut self.general, *idx.deref()),
+        };
+
+        if values.len() <= idx {
+            values.resize(idx + 1, None);
+        }
+        values[idx] = Some(bounds);
+    }
+
+    pub(crate) fn clear(&mut self, index: ParameterIndex<f64>) {
+        let (values, idx) = match index {
+            ParameterIndex::Const(idx) => (&mut self.constant, *idx.deref()),
+            ParameterIndex::Simple(idx) => (&mut self.simple, *idx.deref()),
+            ParameterIndex::General(idx) => (&mut self.general, *idx.deref()),
+        };
+
+        if let Some(bounds) = values.get_mut(idx) {
+            *bounds = None;
+        }
+    }
+
+    pub(crate) fn get(&self, index: ParameterIndex<f64>) -> Option<&ParameterBounds> {
+        let bounds = match index {
+            ParameterIndex::Const(idx) => self.constant.get(*idx.deref()),
+            ParameterIndex::Simple(idx) => self.simple.get(*idx.deref()),
+            ParameterIndex::General(idx) => self.general.get(*idx.deref()),
+        };
+
+        bounds.and_then(|b| b.as_ref())
+    }
+
+    /// Iterate over the indices of the parameters that have bounds.
+    pub(crate) fn iter_indices(&self) -> impl Iterator<Item = ParameterIndex<f64>> + '_ {
+        let constant = self
+            .constant
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.is_some())
+            .map(|(i, _)| ParameterIndex::Const(ConstParameterIndex::new(i)));
+        let simple = self
+            .simple
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.is_some())
+            .map(|(i, _)| ParameterIndex::Simple(SimpleParameterIndex::new(i)));
+        let general = self
+            .general
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.is_some())
+            .map(|(i, _)| ParameterIndex::General(GeneralParameterIndex::new(i)));
+
+        constant.chain(simple).chain(general)
+    }
+}
+
+/// The number of times the value of each f64 parameter has been clamped to its bounds.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterClampCounts {
+    constant: Vec<u64>,
+    simple: Vec<u64>,
+    general: Vec<u64>,
+}
+
+impl ParameterClampCounts {
+    pub(crate) fn new(size: &ParameterCollectionSize) -> Self {
+        Self {
+            constant: vec![0; size.const_f64],
+            simple: vec![0; size.simple_f64],
+            general: vec![0; size.general_f64],
+        }
+    }
+
+    pub(crate) fn increment(&mut self, index: ParameterIndex<f64>) {
+        let count = match index {
+            ParameterIndex::Const(idx) => self.constant.get_mut(*idx.deref()),
+            ParameterIndex::Simple(idx) => self.simple.get_mut(*idx.deref()),
+            ParameterIndex::General(idx) => self.general.get_mut(*idx.deref()),
+        };
+
+        if let Some(count) = count {
+            *count += 1;
+        }
+    }
+
+    /// The number of times the parameter's value has been clamped.
+    pub fn get(&self, index: ParameterIndex<f64>) -> u64 {
+        let count = match index {
+            ParameterIndex::Const(idx) => self.constant.get(*idx.deref()),
+            ParameterIndex::Simple(idx) => self.simple.get(*idx.deref()),
+            ParameterIndex::General(idx) => self.general.get(*idx.deref()),
+        };
+
+        count.copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParameterBounds;
+
+    #[test]
+    fn test_clamp() {
+        let bounds = ParameterBounds::new(Some(0.0), Some(10.0)).unwrap();
+        assert_eq!(bounds.clamp(5.0), None);
+        assert_eq!(bounds.clamp(-1.0), Some(0.0));
+        assert_eq!(bounds.clamp(11.0), Some(10.0));
+
+        let bounds = ParameterBounds::new(None, Some(10.0)).unwrap();
+        assert_eq!(bounds.clamp(-100.0), None);
+        assert_eq!(bounds.clamp(11.0), Some(10.0));
+
+        assert!(ParameterBounds::new(Some(10.0), Some(0.0)).is_err());
+    }
+}
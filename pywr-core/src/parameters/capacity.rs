@@ -0,0 +1,197 @@
+use crate::parameters::{
+    downcast_internal_state_mut, downcast_internal_state_ref, ConstParameter, Parameter, ParameterMeta, ParameterName,
+    ParameterState, VariableConfig, VariableParameter,
+};
+use crate::scenario::ScenarioIndex;
+use crate::state::ConstParameterValues;
+use crate::timestep::Timestep;
+use crate::PywrError;
+
+/// A constant parameter that represents the capacity of an asset (e.g. a node's maximum flow or
+/// volume).
+///
+/// The capacity can be optimised using the variable API. Unlike [`crate::parameters::ConstantParameter`]
+/// the bounds of the variable are a property of the parameter, and the variable value is used
+/// directly as the capacity. Each unit of capacity has an associated cost which is reported
+/// via [`VariableParameter::get_variable_cost`]. This allows capacity expansion studies to
+/// include the cost of the infrastructure in their objectives.
+pub struct CapacityParameter {
+    meta: ParameterMeta,
+    value: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+    unit_cost: f64,
+}
+
+// We store this internal value as an Option<f64> so that it can be updated by the variable API
+type InternalValue = Option<f64>;
+
+impl CapacityParameter {
+    /// Create a new capacity parameter.
+    ///
+    /// An error is returned if either bound is not finite or the lower bound is greater than the
+    /// upper bound.
+    pub fn new(
+        name: ParameterName,
+        value: f64,
+        lower_bound: f64,
+        upper_bound: f64,
+        unit_cost: f64,
+    ) -> Result<Self, PywrError> {
+        if !lower_bound.is_finite() || !upper_bound.is_finite() || lower_bound > upper_bound {
+            return Err(PywrError::InvalidParameterBounds {
+                min: lower_bound,
+                max: upper_bound,
+            });
+        }
+
+        Ok(Self {
+            meta: ParameterMeta::new(name),
+            value,
+            lower_bound,
+            upper_bound,
+            unit_cost,
+        })
+    }
+
+    /// Return the current capacity.
+    ///
+    /// If the internal state is None the initial value is returned, otherwise the value must
+    /// have been set via the variable API.
+    fn value(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Result<f64, PywrError> {
+        match downcast_internal_state_ref::<InternalValue>(&self.meta.name, internal_state)? {
+            Some(value) => Ok(*value),
+            None => Ok(self.value),
+        }
+    }
+}
+
+impl Parameter for CapacityParameter {
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+
+    fn setup(
+        &self,
+        _timesteps: &[Timestep],
+        _scenario_index: &ScenarioIndex,
+    ) -> Result<Option<Box<dyn ParameterState>>, PywrError> {
+        let value: Option<f64> = None;
+        Ok(Some(Box::new(value)))
+    }
+
+    fn as_f64_variable(&self) -> Option<&dyn VariableParameter<f64>> {
+        Some(self)
+    }
+
+    fn as_f64_variable_mut(&mut self) -> Option<&mut dyn VariableParameter<f64>> {
+        Some(self)
+    }
+}
+
+impl ConstParameter<f64> for CapacityParameter {
+    fn compute(
+        &self,
+        _scenario_index: &ScenarioIndex,
+        _values: &ConstParameterValues,
+        internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<f64, PywrError> {
+        self.value(internal_state)
+    }
+
+    fn as_parameter(&self) -> &dyn Parameter
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+impl VariableParameter<f64> for CapacityParameter {
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+
+    fn size(&self, _variable_config: &dyn VariableConfig) -> Result<usize, PywrError> {
+        Ok(1)
+    }
+
+    fn set_variables(
+        &self,
+        values: &[f64],
+        _variable_config: &dyn VariableConfig,
+        internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<(), PywrError> {
+        if values.len() != 1 {
+            return Err(PywrError::ParameterVariableValuesIncorrectLength);
+        }
+        if values[0].is_nan() {
+            return Err(PywrError::ParameterVariableValueNaN(self.meta.name.to_string()));
+        }
+
+        let value = downcast_internal_state_mut::<InternalValue>(&self.meta.name, internal_state)?;
+        *value = Some(values[0].clamp(self.lower_bound, self.upper_bound));
+        Ok(())
+    }
+
+    fn get_variables(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Option<Vec<f64>> {
+        downcast_internal_state_ref::<InternalValue>(&self.meta.name, internal_state)
+            .ok()?
+            .map(|value| vec![value])
+    }
+
+    fn get_lower_bounds(&self, _variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
+        Ok(vec![self.lower_bound])
+    }
+
+    fn get_upper_bounds(&self, _variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
+        Ok(vec![self.upper_bound])
+    }
+
+    fn get_variable_cost(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Option<f64> {
+        Some(self.value(internal_state).ok()? * self.unit_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::{CapacityParameter, Parameter, VariableParameter};
+    use crate::test_utils::default_domain;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_variable_api() {
+        let domain = default_domain();
+
+        let p = CapacityParameter::new("test".into(), 10.0, 5.0, 20.0, 2.5).unwrap();
+        let mut state = p
+            .setup(domain.time().timesteps(), domain.scenarios().indices().first().unwrap())
+            .unwrap();
+
+        // Initial capacity is used until the variable is set
+        assert_eq!(p.get_variables(&state), None);
+        assert_approx_eq!(f64, p.get_variable_cost(&state).unwrap(), 25.0);
+
+        assert_approx_eq!(&[f64], &p.get_lower_bounds(&()).unwrap(), &[5.0]);
+        assert_approx_eq!(&[f64], &p.get_upper_bounds(&()).unwrap(), &[20.0]);
+
+        p.set_variables(&[15.0], &(), &mut state).unwrap();
+        assert_approx_eq!(f64, p.value(&state).unwrap(), 15.0);
+        assert_approx_eq!(f64, p.get_variable_cost(&state).unwrap(), 37.5);
+
+        // Values outside the bounds are clamped
+        p.set_variables(&[100.0], &(), &mut state).unwrap();
+        assert_approx_eq!(&[f64], &p.get_variables(&state).unwrap(), &[20.0]);
+
+        // NaN values are rejected rather than clamped
+        assert!(p.set_variables(&[f64::NAN], &(), &mut state).is_err());
+        assert_approx_eq!(&[f64], &p.get_variables(&state).unwrap(), &[20.0]);
+    }
+
+    #[test]
+    fn test_invalid_bounds() {
+        assert!(CapacityParameter::new("test".into(), 10.0, 20.0, 5.0, 2.5).is_err());
+        assert!(CapacityParameter::new("test".into(), 10.0, f64::NAN, 20.0, 2.5).is_err());
+        assert!(CapacityParameter::new("test".into(), 10.0, 5.0, f64::INFINITY, 2.5).is_err());
+    }
+}
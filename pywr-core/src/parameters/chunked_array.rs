@@ -0,0 +1,314 @@
+use crate::parameters::{Parameter, ParameterMeta, ParameterName, ParameterState, SimpleParameter};
+use crate::scenario::ScenarioIndex;
+use crate::state::SimpleParameterValues;
+use crate::timestep::{Timestep, TimestepIndex};
+use crate::PywrError;
+use ndarray::Array2;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+/// A source of array data that is read a block (or chunk) of time-steps at a time.
+pub trait ChunkSource<T>: Send + Sync {
+    /// Read the values of the `len` time-steps starting at time-step `start`.
+    ///
+    /// The returned array must have a row for each time-step, and either a column for each
+    /// scenario of the parameter's scenario group or a single column.
+    fn read(&self, start: TimestepIndex, len: usize) -> Result<Array2<T>, PywrError>;
+}
+
+/// A block of consecutive time-steps of data.
+struct Chunk<T> {
+    start: TimestepIndex,
+    values: Array2<T>,
+}
+
+impl<T: Copy> Chunk<T> {
+    fn end(&self) -> TimestepIndex {
+        self.start + self.values.nrows()
+    }
+
+    /// Return the value for the given time-step and column if the time-step is in this chunk.
+    fn get(&self, timestep_index: TimestepIndex, col: usize) -> Option<T> {
+        if timestep_index < self.start {
+            return None;
+        }
+        self.values.get([timestep_index - self.start, col]).copied()
+    }
+}
+
+/// Reads chunks on a background thread ahead of them being required.
+///
+/// The channel to the thread has no buffer so that only the next chunk is held in memory while
+/// waiting to be received.
+struct Prefetcher<T> {
+    /// The first time-step of the next chunk that will be received.
+    next_start: TimestepIndex,
+    receiver: Receiver<Result<Chunk<T>, PywrError>>,
+}
+
+impl<T> Prefetcher<T>
+where
+    T: Send + 'static,
+{
+    fn spawn(source: Arc<dyn ChunkSource<T>>, start: TimestepIndex, num_timesteps: usize, chunk_size: usize) -> Self {
+        let (sender, receiver) = sync_channel(0);
+
+        thread::spawn(move || {
+            let mut chunk_start = start;
+            while chunk_start < num_timesteps {
+                let len = chunk_size.min(num_timesteps - chunk_start);
+                let chunk = source.read(chunk_start, len).and_then(|values| {
+                    if values.nrows() != len {
+                        return Err(PywrError::TimeseriesChunkError(format!(
+                            "expected {} rows for the time-steps starting at {} but found {}",
+                            len,
+                            chunk_start,
+                            values.nrows()
+                        )));
+                    }
+                    Ok(Chunk {
+                        start: chunk_start,
+                        values,
+                    })
+                });
+
+                let failed = chunk.is_err();
+                // Stop if the parameter has been dropped or restarted the prefetching
+                if sender.send(chunk).is_err() || failed {
+                    break;
+                }
+                chunk_start += len;
+            }
+        });
+
+        Self {
+            next_start: start,
+            receiver,
+        }
+    }
+}
+
+/// A parameter that returns values from a 2D array that is read from its source in chunks of
+/// time-steps during the simulation.
+///
+/// This allows the use of datasets that are too large to be loaded in to memory (e.g. with
+/// a large number of scenarios). Only the current chunk is held in memory while the next
+/// chunk is read on a background thread. The chunks are expected to be required in order;
+/// if a time-step before the current chunk is required (e.g. because the model is run again)
+/// reading restarts from the chunk containing that time-step.
+///
+/// If `scenario_group_index` is `None` the first column of the data is used for all scenarios.
+pub struct ChunkedArray2Parameter<T> {
+    meta: ParameterMeta,
+    source: Arc<dyn ChunkSource<T>>,
+    num_timesteps: usize,
+    chunk_size: usize,
+    scenario_group_index: Option<usize>,
+    chunk: RwLock<Option<Chunk<T>>>,
+    prefetcher: Mutex<Option<Prefetcher<T>>>,
+}
+
+impl<T> ChunkedArray2Parameter<T>
+where
+    T: Copy + Send + Sync + 'static,
+{
+    pub fn new(
+        name: ParameterName,
+        source: Arc<dyn ChunkSource<T>>,
+        num_timesteps: usize,
+        chunk_size: usize,
+        scenario_group_index: Option<usize>,
+    ) -> Result<Self, PywrError> {
+        if chunk_size == 0 {
+            return Err(PywrError::TimeseriesChunkError(
+                "the chunk size must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            meta: ParameterMeta::new(name),
+            source,
+            num_timesteps,
+            chunk_size,
+            scenario_group_index,
+            chunk: RwLock::new(None),
+            prefetcher: Mutex::new(None),
+        })
+    }
+
+    fn value(&self, timestep: &Timestep, scenario_index: &ScenarioIndex) -> Result<T, PywrError> {
+        let col = match self.scenario_group_index {
+            Some(idx) => scenario_index.indices[idx],
+            None => 0,
+        };
+
+        if let Some(value) = self
+            .chunk
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|c| c.get(timestep.index, col))
+        {
+            return Ok(value);
+        }
+
+        let mut chunk = self.chunk.write().unwrap();
+        // Another scenario may have already fetched the chunk while waiting for the lock.
+        if let Some(value) = chunk.as_ref().and_then(|c| c.get(timestep.index, col)) {
+            return Ok(value);
+        }
+
+        let next = self.fetch(timestep.index)?;
+        let value = next.get(timestep.index, col).ok_or_else(|| {
+            PywrError::TimeseriesChunkError(format!(
+                "column {} not found in the chunk of time-steps {} to {}",
+                col,
+                next.start,
+                next.end()
+            ))
+        })?;
+        // Replace the current chunk; this frees the memory of the previous chunk.
+        *chunk = Some(next);
+
+        Ok(value)
+    }
+
+    /// Receive the chunk containing the given time-step from the prefetching thread.
+    fn fetch(&self, timestep_index: TimestepIndex) -> Result<Chunk<T>, PywrError> {
+        if timestep_index >= self.num_timesteps {
+            return Err(PywrError::TimestepIndexOutOfRange);
+        }
+        let start = timestep_index / self.chunk_size * self.chunk_size;
+
+        let mut guard = self.prefetcher.lock().unwrap();
+        // Restart reading from the required chunk if it is not the next one.
+        if guard.as_ref().map(|p| p.next_start) != Some(start) {
+            *guard = None;
+        }
+        let prefetcher = guard
+            .get_or_insert_with(|| Prefetcher::spawn(self.source.clone(), start, self.num_timesteps, self.chunk_size));
+
+        let chunk = match prefetcher.receiver.recv() {
+            Ok(chunk) => chunk,
+            Err(_) => Err(PywrError::TimeseriesChunkError(format!(
+                "reading of the chunk of time-steps starting at {} stopped unexpectedly",
+                start
+            ))),
+        };
+
+        match chunk {
+            Ok(chunk) => {
+                prefetcher.next_start = chunk.end();
+                Ok(chunk)
+            }
+            Err(e) => {
+                *guard = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<T> Parameter for ChunkedArray2Parameter<T>
+where
+    T: Send + Sync,
+{
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+}
+
+impl SimpleParameter<f64> for ChunkedArray2Parameter<f64> {
+    fn compute(
+        &self,
+        timestep: &Timestep,
+        scenario_index: &ScenarioIndex,
+        _values: &SimpleParameterValues,
+        _internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<f64, PywrError> {
+        self.value(timestep, scenario_index)
+    }
+
+    fn as_parameter(&self) -> &dyn Parameter
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+impl SimpleParameter<u64> for ChunkedArray2Parameter<u64> {
+    fn compute(
+        &self,
+        timestep: &Timestep,
+        scenario_index: &ScenarioIndex,
+        _values: &SimpleParameterValues,
+        _internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<u64, PywrError> {
+        self.value(timestep, scenario_index)
+    }
+
+    fn as_parameter(&self) -> &dyn Parameter
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkSource, ChunkedArray2Parameter};
+    use crate::recorders::AssertionRecorder;
+    use crate::test_utils::{run_all_solvers, simple_model};
+    use crate::timestep::TimestepIndex;
+    use crate::PywrError;
+    use ndarray::Array2;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// A source that generates its values and records the largest chunk read.
+    struct TestSource {
+        num_scenarios: usize,
+        max_len: AtomicUsize,
+    }
+
+    impl ChunkSource<f64> for TestSource {
+        fn read(&self, start: TimestepIndex, len: usize) -> Result<Array2<f64>, PywrError> {
+            self.max_len.fetch_max(len, Ordering::Relaxed);
+            Ok(Array2::from_shape_fn((len, self.num_scenarios), |(t, s)| {
+                ((start + t) * 10 + s) as f64
+            }))
+        }
+    }
+
+    #[test]
+    fn test_chunked_values() {
+        let mut model = simple_model(3, None);
+        let num_timesteps = model.domain().time().timesteps().len();
+        let scenario_group_index = model.domain().scenarios().group_index("test-scenario").unwrap();
+
+        let source = Arc::new(TestSource {
+            num_scenarios: 3,
+            max_len: AtomicUsize::new(0),
+        });
+        let parameter = ChunkedArray2Parameter::new(
+            "chunked".into(),
+            source.clone(),
+            num_timesteps,
+            4,
+            Some(scenario_group_index),
+        )
+        .unwrap();
+        let p_idx = model.network_mut().add_simple_parameter(Box::new(parameter)).unwrap();
+
+        let expected = Array2::from_shape_fn((num_timesteps, 3), |(t, s)| (t * 10 + s) as f64);
+        let rec = AssertionRecorder::new("assert", p_idx.into(), expected, None, None);
+        model.network_mut().add_recorder(Box::new(rec)).unwrap();
+
+        // The model is run with each solver which also checks the chunks are re-read for each run.
+        run_all_solvers(&model, &[], &[], &[]);
+        assert_eq!(source.max_len.load(Ordering::Relaxed), 4);
+    }
+}
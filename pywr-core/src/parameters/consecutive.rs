@@ -0,0 +1,79 @@
+use crate::metric::MetricF64;
+use crate::network::Network;
+use crate::parameters::{
+    downcast_internal_state_mut, GeneralParameter, Parameter, ParameterMeta, ParameterName, ParameterState, Predicate,
+};
+use crate::scenario::ScenarioIndex;
+use crate::state::State;
+use crate::timestep::Timestep;
+use crate::PywrError;
+
+/// A parameter that counts the number of consecutive time-steps a predicate has been true.
+///
+/// Each time-step `metric` is compared against `threshold` using `predicate`. If the comparison
+/// is true the count is incremented and returned, otherwise the count is reset to zero. This
+/// makes it straightforward to define rules such as "trigger after N consecutive days below X".
+pub struct ConsecutiveCountParameter {
+    meta: ParameterMeta,
+    metric: MetricF64,
+    threshold: MetricF64,
+    predicate: Predicate,
+}
+
+impl ConsecutiveCountParameter {
+    pub fn new(name: ParameterName, metric: MetricF64, threshold: MetricF64, predicate: Predicate) -> Self {
+        Self {
+            meta: ParameterMeta::new(name),
+            metric,
+            threshold,
+            predicate,
+        }
+    }
+}
+
+impl Parameter for ConsecutiveCountParameter {
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+
+    fn setup(
+        &self,
+        _timesteps: &[Timestep],
+        _scenario_index: &ScenarioIndex,
+    ) -> Result<Option<Box<dyn ParameterState>>, PywrError> {
+        // Internal state is the current count of consecutive time-steps. Initially this is zero.
+        Ok(Some(Box::new(0u64)))
+    }
+}
+
+impl GeneralParameter<u64> for ConsecutiveCountParameter {
+    fn compute(
+        &self,
+        _timestep: &Timestep,
+        _scenario_index: &ScenarioIndex,
+        model: &Network,
+        state: &State,
+        internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<u64, PywrError> {
+        // Downcast the internal state to the correct type
+        let count = downcast_internal_state_mut::<u64>(&self.meta.name, internal_state)?;
+
+        let threshold = self.threshold.get_value(model, state)?;
+        let value = self.metric.get_value(model, state)?;
+
+        if self.predicate.apply(value, threshold) {
+            *count += 1;
+        } else {
+            *count = 0;
+        }
+
+        Ok(*count)
+    }
+
+    fn as_parameter(&self) -> &dyn Parameter
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
@@ -27,10 +27,10 @@ impl ConstantParameter {
     ///
     /// If the internal state is None, the value is returned directly. Otherwise, the internal value must
     /// have come from the variable API and is passed through the activation function.
-    fn value(&self, internal_state: &Option<Box<dyn ParameterState>>) -> f64 {
-        match downcast_internal_state_ref::<InternalValue>(internal_state) {
-            Some(value) => *value,
-            None => self.value,
+    fn value(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Result<f64, PywrError> {
+        match downcast_internal_state_ref::<InternalValue>(&self.meta.name, internal_state)? {
+            Some(value) => Ok(*value),
+            None => Ok(self.value),
         }
     }
 }
@@ -64,7 +64,7 @@ impl ConstParameter<f64> for ConstantParameter {
         _values: &ConstParameterValues,
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
-        Ok(self.value(internal_state))
+        self.value(internal_state)
     }
 
     fn as_parameter(&self) -> &dyn Parameter
@@ -80,8 +80,8 @@ impl VariableParameter<f64> for ConstantParameter {
         &self.meta
     }
 
-    fn size(&self, _variable_config: &dyn VariableConfig) -> usize {
-        1
+    fn size(&self, _variable_config: &dyn VariableConfig) -> Result<usize, PywrError> {
+        Ok(1)
     }
 
     fn set_variables(
@@ -90,10 +90,10 @@ impl VariableParameter<f64> for ConstantParameter {
         variable_config: &dyn VariableConfig,
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<(), PywrError> {
-        let activation_function = downcast_variable_config_ref::<ActivationFunction>(variable_config);
+        let activation_function = downcast_variable_config_ref::<ActivationFunction>(&self.meta.name, variable_config)?;
 
         if values.len() == 1 {
-            let value = downcast_internal_state_mut::<InternalValue>(internal_state);
+            let value = downcast_internal_state_mut::<InternalValue>(&self.meta.name, internal_state)?;
             *value = Some(activation_function.apply(values[0]));
             Ok(())
         } else {
@@ -102,18 +102,18 @@ impl VariableParameter<f64> for ConstantParameter {
     }
 
     fn get_variables(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Option<Vec<f64>> {
-        downcast_internal_state_ref::<InternalValue>(internal_state)
-            .as_ref()
-            .map(|value| vec![*value])
+        downcast_internal_state_ref::<InternalValue>(&self.meta.name, internal_state)
+            .ok()?
+            .map(|value| vec![value])
     }
 
     fn get_lower_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
-        let activation_function = downcast_variable_config_ref::<ActivationFunction>(variable_config);
+        let activation_function = downcast_variable_config_ref::<ActivationFunction>(&self.meta.name, variable_config)?;
         Ok(vec![activation_function.lower_bound()])
     }
 
     fn get_upper_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
-        let activation_function = downcast_variable_config_ref::<ActivationFunction>(variable_config);
+        let activation_function = downcast_variable_config_ref::<ActivationFunction>(&self.meta.name, variable_config)?;
         Ok(vec![activation_function.upper_bound()])
     }
 }
@@ -72,4 +72,11 @@ impl GeneralParameter<f64> for ControlCurveInterpolatedParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        std::iter::once(&self.metric)
+            .chain(&self.control_curves)
+            .chain(&self.values)
+            .collect()
+    }
 }
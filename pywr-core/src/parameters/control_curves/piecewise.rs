@@ -71,6 +71,10 @@ impl GeneralParameter<f64> for PiecewiseInterpolatedParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        std::iter::once(&self.metric).chain(&self.control_curves).collect()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,108 @@
+use crate::parameters::{Parameter, ParameterMeta, ParameterName, ParameterState, SimpleParameter};
+use crate::scenario::ScenarioIndex;
+use crate::state::SimpleParameterValues;
+use crate::timestep::{DayOfYearWindow, Timestep};
+use crate::PywrError;
+use chrono::NaiveDateTime;
+
+/// A window of dates in which a [`DateRangeIndexParameter`] is active.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateWindow {
+    /// From `start` to `end` (inclusive).
+    Dates { start: NaiveDateTime, end: NaiveDateTime },
+    /// Within the same days of every year.
+    Annual(DayOfYearWindow),
+}
+
+impl DateWindow {
+    fn contains(&self, date: &NaiveDateTime) -> bool {
+        match self {
+            Self::Dates { start, end } => start <= date && date <= end,
+            Self::Annual(window) => window.contains(date),
+        }
+    }
+}
+
+/// A parameter that returns 1 if the time-step starts within any of its windows, and 0 otherwise.
+///
+/// This is intended to switch other parameters or constraints on and off for parts of a run
+/// or for seasons of each year (e.g. with an `IndexedArrayParameter`).
+pub struct DateRangeIndexParameter {
+    meta: ParameterMeta,
+    windows: Vec<DateWindow>,
+}
+
+impl DateRangeIndexParameter {
+    pub fn new(name: ParameterName, windows: Vec<DateWindow>) -> Self {
+        Self {
+            meta: ParameterMeta::new(name),
+            windows,
+        }
+    }
+
+    fn is_active(&self, date: &NaiveDateTime) -> bool {
+        self.windows.iter().any(|w| w.contains(date))
+    }
+}
+
+impl Parameter for DateRangeIndexParameter {
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+}
+
+impl SimpleParameter<u64> for DateRangeIndexParameter {
+    fn compute(
+        &self,
+        timestep: &Timestep,
+        _scenario_index: &ScenarioIndex,
+        _values: &SimpleParameterValues,
+        _internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<u64, PywrError> {
+        Ok(self.is_active(&timestep.date) as u64)
+    }
+
+    fn as_parameter(&self) -> &dyn Parameter
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DateRangeIndexParameter, DateWindow};
+    use crate::timestep::DayOfYearWindow;
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_date_range() {
+        let p = DateRangeIndexParameter::new(
+            "date-range".into(),
+            vec![
+                DateWindow::Dates {
+                    start: date(2021, 3, 1),
+                    end: date(2021, 3, 31),
+                },
+                // A winter window that wraps around the end of the year
+                DateWindow::Annual(DayOfYearWindow::new((11, 1), (1, 31))),
+            ],
+        );
+
+        assert!(p.is_active(&date(2021, 3, 1)));
+        assert!(p.is_active(&date(2021, 3, 31)));
+        assert!(!p.is_active(&date(2022, 3, 15)));
+        assert!(p.is_active(&date(2021, 1, 15)));
+        assert!(p.is_active(&date(2030, 12, 25)));
+        assert!(!p.is_active(&date(2021, 6, 1)));
+        assert!(!p.is_active(&date(2021, 10, 31)));
+    }
+}
@@ -70,7 +70,7 @@ impl GeneralParameter<f64> for DelayParameter<MetricF64> {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
         // Downcast the internal state to the correct type
-        let memory = downcast_internal_state_mut::<VecDeque<f64>>(internal_state);
+        let memory = downcast_internal_state_mut::<VecDeque<f64>>(&self.meta.name, internal_state)?;
 
         // Take the oldest value from the queue
         // It should be guaranteed that the internal memory/queue has self.delay number of values
@@ -90,7 +90,7 @@ impl GeneralParameter<f64> for DelayParameter<MetricF64> {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<(), PywrError> {
         // Downcast the internal state to the correct type
-        let memory = downcast_internal_state_mut::<VecDeque<f64>>(internal_state);
+        let memory = downcast_internal_state_mut::<VecDeque<f64>>(&self.meta.name, internal_state)?;
 
         // Get today's value from the metric
         let value = self.metric.get_value(model, state)?;
@@ -114,6 +114,10 @@ impl GeneralParameter<f64> for DelayParameter<MetricF64> {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.metric]
+    }
 }
 
 impl SimpleParameter<f64> for DelayParameter<SimpleMetricF64> {
@@ -125,7 +129,7 @@ impl SimpleParameter<f64> for DelayParameter<SimpleMetricF64> {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
         // Downcast the internal state to the correct type
-        let memory = downcast_internal_state_mut::<VecDeque<f64>>(internal_state);
+        let memory = downcast_internal_state_mut::<VecDeque<f64>>(&self.meta.name, internal_state)?;
 
         // Take the oldest value from the queue
         // It should be guaranteed that the internal memory/queue has self.delay number of values
@@ -144,7 +148,7 @@ impl SimpleParameter<f64> for DelayParameter<SimpleMetricF64> {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<(), PywrError> {
         // Downcast the internal state to the correct type
-        let memory = downcast_internal_state_mut::<VecDeque<f64>>(internal_state);
+        let memory = downcast_internal_state_mut::<VecDeque<f64>>(&self.meta.name, internal_state)?;
 
         // Get today's value from the metric
         let value = self.metric.get_value(values)?;
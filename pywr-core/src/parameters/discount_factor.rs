@@ -50,6 +50,10 @@ impl GeneralParameter<f64> for DiscountFactorParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.discount_rate]
+    }
 }
 
 #[cfg(test)]
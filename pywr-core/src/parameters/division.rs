@@ -55,4 +55,8 @@ impl GeneralParameter<f64> for DivisionParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.numerator, &self.denominator]
+    }
 }
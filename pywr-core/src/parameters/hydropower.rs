@@ -15,6 +15,9 @@ pub struct HydropowerTargetData {
     pub min_flow: Option<MetricF64>,
     pub efficiency: Option<f64>,
     pub water_elevation: Option<MetricF64>,
+    /// The elevation of the water downstream of the turbine. If this is above the turbine
+    /// elevation then it is used instead of the turbine elevation to calculate the head.
+    pub tailwater_elevation: Option<MetricF64>,
     pub water_density: Option<f64>,
     pub flow_unit_conversion: Option<f64>,
     pub energy_unit_conversion: Option<f64>,
@@ -29,6 +32,7 @@ pub struct HydropowerTargetParameter {
     pub turbine_elevation: f64,
     pub turbine_efficiency: f64,
     pub water_elevation: Option<MetricF64>,
+    pub tailwater_elevation: Option<MetricF64>,
     pub water_density: f64,
     pub flow_unit_conversion: f64,
     pub energy_unit_conversion: f64,
@@ -40,6 +44,7 @@ impl HydropowerTargetParameter {
             meta: ParameterMeta::new(name),
             target: turbine_data.target,
             water_elevation: turbine_data.water_elevation,
+            tailwater_elevation: turbine_data.tailwater_elevation,
             turbine_elevation: turbine_data.elevation.unwrap_or(0.0),
             turbine_min_head: turbine_data.min_head.unwrap_or(0.0),
             turbine_efficiency: turbine_data.efficiency.unwrap_or(1.0),
@@ -67,11 +72,17 @@ impl GeneralParameter<f64> for HydropowerTargetParameter {
         state: &State,
         _internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
+        // The head is measured from the turbine or the tailwater, whichever is higher
+        let base_elevation = match &self.tailwater_elevation {
+            Some(tailwater_elevation) => tailwater_elevation.get_value(model, state)?.max(self.turbine_elevation),
+            None => self.turbine_elevation,
+        };
+
         // Calculate the head
         let mut head = if let Some(water_elevation) = &self.water_elevation {
-            water_elevation.get_value(model, state)? - self.turbine_elevation
+            water_elevation.get_value(model, state)? - base_elevation
         } else {
-            self.turbine_elevation
+            base_elevation
         };
 
         // the head may be negative
@@ -116,4 +127,13 @@ impl GeneralParameter<f64> for HydropowerTargetParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        std::iter::once(&self.target)
+            .chain(self.max_flow.as_ref())
+            .chain(self.min_flow.as_ref())
+            .chain(self.water_elevation.as_ref())
+            .chain(self.tailwater_elevation.as_ref())
+            .collect()
+    }
 }
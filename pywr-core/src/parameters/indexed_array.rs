@@ -50,4 +50,8 @@ impl GeneralParameter<f64> for IndexedArrayParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        self.metrics.iter().collect()
+    }
 }
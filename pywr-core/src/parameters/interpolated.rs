@@ -64,4 +64,10 @@ impl GeneralParameter<f64> for InterpolatedParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        std::iter::once(&self.x)
+            .chain(self.points.iter().flat_map(|(x, y)| [x, y]))
+            .collect()
+    }
 }
@@ -48,4 +48,8 @@ impl GeneralParameter<f64> for MaxParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.metric]
+    }
 }
@@ -3,8 +3,13 @@ mod aggregated;
 mod aggregated_index;
 mod array;
 mod asymmetric;
+mod bounds;
+mod capacity;
+mod chunked_array;
+mod consecutive;
 mod constant;
 mod control_curves;
+mod date_range;
 mod delay;
 mod discount_factor;
 mod division;
@@ -23,6 +28,7 @@ mod profiles;
 
 #[cfg(feature = "pyo3")]
 mod py;
+mod random;
 mod rhai;
 mod threshold;
 mod vector;
@@ -31,6 +37,7 @@ use std::any::Any;
 // Re-imports
 pub use self::rhai::RhaiParameter;
 use super::PywrError;
+use crate::metric::MetricF64;
 use crate::network::Network;
 use crate::scenario::ScenarioIndex;
 use crate::state::{ConstParameterValues, MultiValue, SimpleParameterValues, State};
@@ -40,11 +47,17 @@ pub use aggregated::{AggFunc, AggregatedParameter};
 pub use aggregated_index::{AggIndexFunc, AggregatedIndexParameter};
 pub use array::{Array1Parameter, Array2Parameter};
 pub use asymmetric::AsymmetricSwitchIndexParameter;
+use bounds::F64ParameterBounds;
+pub use bounds::{ParameterBounds, ParameterClampCounts};
+pub use capacity::CapacityParameter;
+pub use chunked_array::{ChunkSource, ChunkedArray2Parameter};
+pub use consecutive::ConsecutiveCountParameter;
 pub use constant::ConstantParameter;
 pub use control_curves::{
     ApportionParameter, ControlCurveIndexParameter, ControlCurveInterpolatedParameter, ControlCurveParameter,
     PiecewiseInterpolatedParameter, VolumeBetweenControlCurvesParameter,
 };
+pub use date_range::{DateRangeIndexParameter, DateWindow};
 pub use delay::DelayParameter;
 pub use discount_factor::DiscountFactorParameter;
 pub use division::DivisionParameter;
@@ -60,12 +73,13 @@ pub use negativemin::NegativeMinParameter;
 pub use offset::OffsetParameter;
 pub use polynomial::Polynomial1DParameter;
 pub use profiles::{
-    DailyProfileParameter, MonthlyInterpDay, MonthlyProfileParameter, RadialBasisFunction, RbfProfileParameter,
-    RbfProfileVariableConfig, UniformDrawdownProfileParameter, WeeklyInterpDay, WeeklyProfileError,
-    WeeklyProfileParameter, WeeklyProfileValues,
+    DailyProfileParameter, DiurnalProfileParameter, LeapDayPolicy, LeapDayPolicyError, MonthlyInterpDay,
+    MonthlyProfileParameter, RadialBasisFunction, RbfProfileParameter, RbfProfileVariableConfig,
+    UniformDrawdownProfileParameter, WeeklyInterpDay, WeeklyProfileError, WeeklyProfileParameter, WeeklyProfileValues,
 };
 #[cfg(feature = "pyo3")]
 pub use py::PyParameter;
+pub use random::{RandomDistribution, RandomParameter};
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
@@ -316,14 +330,19 @@ impl ParameterMeta {
     }
 }
 
+/// The internal state of a parameter.
+///
+/// Internal states must be cloneable so that the states of a network can be restored, for
+/// example to repeat a time-step when iterating the transfers of a multi-network model.
 pub trait ParameterState: Any + Send {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn clone_box(&self) -> Box<dyn ParameterState>;
 }
 
 impl<T> ParameterState for T
 where
-    T: Any + Send,
+    T: Any + Send + Clone,
 {
     fn as_any(&self) -> &dyn Any {
         self
@@ -331,18 +350,30 @@ where
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+    fn clone_box(&self) -> Box<dyn ParameterState> {
+        Box::new(self.clone())
+    }
 }
 
+impl Clone for Box<dyn ParameterState> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+#[derive(Clone)]
 struct ParameterStatesByType {
     f64: Vec<Option<Box<dyn ParameterState>>>,
     u64: Vec<Option<Box<dyn ParameterState>>>,
     multi: Vec<Option<Box<dyn ParameterState>>>,
 }
 
+#[derive(Clone)]
 pub struct ParameterStates {
     constant: ParameterStatesByType,
     simple: ParameterStatesByType,
     general: ParameterStatesByType,
+    clamp_counts: ParameterClampCounts,
 }
 
 impl ParameterStates {
@@ -355,14 +386,21 @@ impl ParameterStates {
         let constant = collection.const_initial_states(timesteps, scenario_index)?;
         let simple = collection.simple_initial_states(timesteps, scenario_index)?;
         let general = collection.general_initial_states(timesteps, scenario_index)?;
+        let clamp_counts = ParameterClampCounts::new(&collection.size());
 
         Ok(Self {
             constant,
             simple,
             general,
+            clamp_counts,
         })
     }
 
+    /// The number of times the value of each parameter has been clamped to its bounds.
+    pub fn clamp_counts(&self) -> &ParameterClampCounts {
+        &self.clamp_counts
+    }
+
     pub fn get_f64_state(&self, index: ParameterIndex<f64>) -> Option<&Option<Box<dyn ParameterState>>> {
         match index {
             ParameterIndex::Const(idx) => self.constant.f64.get(*idx.deref()),
@@ -450,29 +488,41 @@ impl ParameterStates {
     }
 }
 
-/// Helper function to downcast to internal parameter state and print a helpful panic
-/// message if this fails.
-pub fn downcast_internal_state_mut<T: 'static>(internal_state: &mut Option<Box<dyn ParameterState>>) -> &mut T {
+/// Helper function to downcast to internal parameter state.
+///
+/// An error naming the parameter is returned if there is no internal state or it is not of
+/// the expected type.
+pub fn downcast_internal_state_mut<'a, T: 'static>(
+    name: &ParameterName,
+    internal_state: &'a mut Option<Box<dyn ParameterState>>,
+) -> Result<&'a mut T, PywrError> {
     // Downcast the internal state to the correct type
     match internal_state {
-        Some(internal) => match internal.as_mut().as_any_mut().downcast_mut::<T>() {
-            Some(pa) => pa,
-            None => panic!("Internal state did not downcast to the correct type! :("),
-        },
-        None => panic!("No internal state defined when one was expected! :("),
+        Some(internal) => internal
+            .as_mut()
+            .as_any_mut()
+            .downcast_mut::<T>()
+            .ok_or_else(|| PywrError::ParameterInternalStateDowncastFailed(name.to_string())),
+        None => Err(PywrError::ParameterInternalStateNotFound(name.to_string())),
     }
 }
 
-/// Helper function to downcast to internal parameter state and print a helpful panic
-/// message if this fails.
-pub fn downcast_internal_state_ref<T: 'static>(internal_state: &Option<Box<dyn ParameterState>>) -> &T {
+/// Helper function to downcast to internal parameter state.
+///
+/// An error naming the parameter is returned if there is no internal state or it is not of
+/// the expected type.
+pub fn downcast_internal_state_ref<'a, T: 'static>(
+    name: &ParameterName,
+    internal_state: &'a Option<Box<dyn ParameterState>>,
+) -> Result<&'a T, PywrError> {
     // Downcast the internal state to the correct type
     match internal_state {
-        Some(internal) => match internal.as_ref().as_any().downcast_ref::<T>() {
-            Some(pa) => pa,
-            None => panic!("Internal state did not downcast to the correct type! :("),
-        },
-        None => panic!("No internal state defined when one was expected! :("),
+        Some(internal) => internal
+            .as_ref()
+            .as_any()
+            .downcast_ref::<T>()
+            .ok_or_else(|| PywrError::ParameterInternalStateDowncastFailed(name.to_string())),
+        None => Err(PywrError::ParameterInternalStateNotFound(name.to_string())),
     }
 }
 
@@ -493,13 +543,16 @@ where
     }
 }
 
-/// Helper function to downcast to variable config and print a helpful panic message if this fails.
-pub fn downcast_variable_config_ref<T: 'static>(variable_config: &dyn VariableConfig) -> &T {
-    // Downcast the internal state to the correct type
-    match variable_config.as_any().downcast_ref::<T>() {
-        Some(pa) => pa,
-        None => panic!("Variable config did not downcast to the correct type! :("),
-    }
+/// Helper function to downcast to variable config and return an error if this fails.
+pub fn downcast_variable_config_ref<'a, T: 'static>(
+    name: &ParameterName,
+    variable_config: &'a dyn VariableConfig,
+) -> Result<&'a T, PywrError> {
+    // Downcast the variable config to the correct type
+    variable_config
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| PywrError::ParameterVariableConfigDowncastFailed(name.to_string()))
 }
 
 /// A trait that defines a component that produces a value each time-step.
@@ -579,6 +632,14 @@ pub trait GeneralParameter<T>: Parameter {
     }
 
     fn as_parameter(&self) -> &dyn Parameter;
+
+    /// The metrics from which the parameter's value is computed.
+    ///
+    /// These are used to trace the origin of an invalid value computed by the parameter
+    /// (see [`PywrError::ParameterNaNValue`]).
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        Vec::new()
+    }
 }
 
 /// A trait that defines a component that produces a value each time-step.
@@ -733,7 +794,7 @@ pub trait VariableParameter<T> {
     }
 
     /// Return the number of variables required
-    fn size(&self, variable_config: &dyn VariableConfig) -> usize;
+    fn size(&self, variable_config: &dyn VariableConfig) -> Result<usize, PywrError>;
     /// Apply new variable values to the parameter's state
     fn set_variables(
         &self,
@@ -747,6 +808,16 @@ pub trait VariableParameter<T> {
     fn get_lower_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<T>, PywrError>;
     /// Get variable upper bounds
     fn get_upper_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<T>, PywrError>;
+    /// Get the cost associated with the current variable values, if any.
+    ///
+    /// This is used by planning studies where the variable represents the size of an asset
+    /// (e.g. the capacity of a node) that has an associated cost.
+    fn get_variable_cost(
+        &self,
+        #[allow(unused_variables)] internal_state: &Option<Box<dyn ParameterState>>,
+    ) -> Option<f64> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -780,6 +851,9 @@ pub struct ParameterCollection {
     general_f64: Vec<Box<dyn GeneralParameter<f64>>>,
     general_u64: Vec<Box<dyn GeneralParameter<u64>>>,
     general_multi: Vec<Box<dyn GeneralParameter<MultiValue>>>,
+
+    // Optional bounds applied to the computed values of f64 parameters.
+    f64_bounds: F64ParameterBounds,
 }
 
 impl ParameterCollection {
@@ -796,6 +870,59 @@ impl ParameterCollection {
             general_multi: self.general_multi.len(),
         }
     }
+    /// Set the bounds that are applied to the computed value of an f64 parameter.
+    pub fn set_f64_bounds(&mut self, index: ParameterIndex<f64>, bounds: ParameterBounds) -> Result<(), PywrError> {
+        if self.get_f64(index).is_none() {
+            return Err(PywrError::ParameterIndexNotFound(index));
+        }
+        self.f64_bounds.set(index, bounds);
+        Ok(())
+    }
+
+    /// Get the bounds of an f64 parameter, if any.
+    pub fn get_f64_bounds(&self, index: ParameterIndex<f64>) -> Option<&ParameterBounds> {
+        self.f64_bounds.get(index)
+    }
+
+    /// Iterate over the indices of all the f64 parameters.
+    pub fn iter_f64_indices(&self) -> impl Iterator<Item = ParameterIndex<f64>> {
+        let constant = (0..self.constant_f64.len()).map(|i| ParameterIndex::Const(ConstParameterIndex::new(i)));
+        let simple = (0..self.simple_f64.len()).map(|i| ParameterIndex::Simple(SimpleParameterIndex::new(i)));
+        let general = (0..self.general_f64.len()).map(|i| ParameterIndex::General(GeneralParameterIndex::new(i)));
+        constant.chain(simple).chain(general)
+    }
+
+    /// Iterate over the indices of all the u64 (index) parameters.
+    pub fn iter_u64_indices(&self) -> impl Iterator<Item = ParameterIndex<u64>> {
+        let constant = (0..self.constant_u64.len()).map(|i| ParameterIndex::Const(ConstParameterIndex::new(i)));
+        let simple = (0..self.simple_u64.len()).map(|i| ParameterIndex::Simple(SimpleParameterIndex::new(i)));
+        let general = (0..self.general_u64.len()).map(|i| ParameterIndex::General(GeneralParameterIndex::new(i)));
+        constant.chain(simple).chain(general)
+    }
+
+    /// Iterate over the indices of the f64 parameters that have bounds.
+    pub fn iter_f64_bounded(&self) -> impl Iterator<Item = ParameterIndex<f64>> + '_ {
+        self.f64_bounds.iter_indices()
+    }
+
+    /// Apply any bounds of an f64 parameter to its computed `value`.
+    ///
+    /// If the value is clamped the parameter's clamp counter in `internal_states` is incremented.
+    pub(crate) fn apply_f64_bounds(
+        &self,
+        index: ParameterIndex<f64>,
+        value: f64,
+        internal_states: &mut ParameterStates,
+    ) -> f64 {
+        match self.f64_bounds.get(index).and_then(|b| b.clamp(value)) {
+            Some(clamped) => {
+                internal_states.clamp_counts.increment(index);
+                clamped
+            }
+            None => value,
+        }
+    }
+
     fn general_initial_states(
         &self,
         timesteps: &[Timestep],
@@ -953,6 +1080,57 @@ impl ParameterCollection {
         Ok(index.into())
     }
 
+    /// Replace the constant parameter at `index` with `parameter`.
+    ///
+    /// The new parameter must have the same name as the parameter it replaces. Any bounds of
+    /// the replaced parameter are removed.
+    pub fn replace_const_f64(
+        &mut self,
+        index: ParameterIndex<f64>,
+        parameter: Box<dyn ConstParameter<f64>>,
+    ) -> Result<(), PywrError> {
+        self.check_replacement(index, parameter.name())?;
+
+        match index {
+            ParameterIndex::Const(idx) => self.constant_f64[*idx.deref()] = parameter,
+            _ => return Err(PywrError::ParameterReplacementMismatch(parameter.name().to_string())),
+        }
+
+        self.f64_bounds.clear(index);
+        Ok(())
+    }
+
+    /// Replace the simple parameter at `index` with `parameter`.
+    ///
+    /// The new parameter must have the same name as the parameter it replaces, and must be
+    /// of the same type (i.e. a simple parameter that is not converted to a constant parameter
+    /// can not replace a constant parameter). Any bounds of the replaced parameter are removed.
+    pub fn replace_simple_f64(
+        &mut self,
+        index: ParameterIndex<f64>,
+        parameter: Box<dyn SimpleParameter<f64>>,
+    ) -> Result<(), PywrError> {
+        self.check_replacement(index, parameter.name())?;
+
+        match (index, parameter.try_into_const()) {
+            (ParameterIndex::Const(idx), Some(constant)) => self.constant_f64[*idx.deref()] = constant,
+            (ParameterIndex::Simple(idx), None) => self.simple_f64[*idx.deref()] = parameter,
+            _ => return Err(PywrError::ParameterReplacementMismatch(parameter.name().to_string())),
+        }
+
+        self.f64_bounds.clear(index);
+        Ok(())
+    }
+
+    /// Check that the parameter at `index` exists and has the name `name`.
+    fn check_replacement(&self, index: ParameterIndex<f64>, name: &ParameterName) -> Result<(), PywrError> {
+        let existing = self.get_f64(index).ok_or(PywrError::ParameterIndexNotFound(index))?;
+        if existing.name() != name {
+            return Err(PywrError::ParameterReplacementMismatch(name.to_string()));
+        }
+        Ok(())
+    }
+
     pub fn get_f64(&self, index: ParameterIndex<f64>) -> Option<&dyn Parameter> {
         match index {
             ParameterIndex::Const(idx) => self.constant_f64.get(*idx.deref()).map(|p| p.as_parameter()),
@@ -1212,6 +1390,7 @@ impl ParameterCollection {
                         &state.get_simple_parameter_values(),
                         internal_state,
                     )?;
+                    let value = self.apply_f64_bounds((*idx).into(), value, internal_states);
                     state.set_simple_parameter_value(*idx, value)?;
                 }
                 SimpleParameterType::Index(idx) => {
@@ -1349,6 +1528,7 @@ impl ParameterCollection {
                         .ok_or(PywrError::ConstParameterIndexNotFound(*idx))?;
 
                     let value = p.compute(scenario_index, &state.get_const_parameter_values(), internal_state)?;
+                    let value = self.apply_f64_bounds((*idx).into(), value, internal_states);
                     state.set_const_parameter_value(*idx, value)?;
                 }
                 ConstParameterType::Index(idx) => {
@@ -1567,6 +1747,28 @@ mod tests {
         assert!(ret.is_err());
     }
 
+    /// Test replacing parameters in a parameter collection.
+    #[test]
+    fn test_parameter_collection_replace() {
+        let mut collection = ParameterCollection::default();
+
+        let const_idx = collection.add_const_f64(Box::new(TestParameter::default())).unwrap();
+        assert!(collection
+            .replace_const_f64(const_idx, Box::new(TestParameter::default()))
+            .is_ok());
+
+        // The replacement must have the same name
+        let other = TestParameter {
+            meta: ParameterMeta::new("other-parameter".into()),
+        };
+        assert!(collection.replace_const_f64(const_idx, Box::new(other)).is_err());
+
+        // A simple parameter can not replace a constant parameter
+        assert!(collection
+            .replace_simple_f64(const_idx, Box::new(TestParameter::default()))
+            .is_err());
+    }
+
     // #[test]
     // /// Test `ConstantParameter` returns the correct value.
     // fn test_constant_parameter() {
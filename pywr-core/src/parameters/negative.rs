@@ -46,4 +46,8 @@ impl GeneralParameter<f64> for NegativeParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.metric]
+    }
 }
@@ -46,4 +46,8 @@ impl GeneralParameter<f64> for NegativeMaxParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.metric]
+    }
 }
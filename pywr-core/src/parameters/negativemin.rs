@@ -46,4 +46,8 @@ impl GeneralParameter<f64> for NegativeMinParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.metric]
+    }
 }
@@ -31,10 +31,10 @@ impl OffsetParameter {
     ///
     /// If the internal state is None, the value is returned directly. Otherwise, the internal value must
     /// have come from the variable API and is passed through the activation function.
-    fn offset(&self, internal_state: &Option<Box<dyn ParameterState>>) -> f64 {
-        match downcast_internal_state_ref::<InternalValue>(internal_state) {
-            Some(value) => *value,
-            None => self.offset,
+    fn offset(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Result<f64, PywrError> {
+        match downcast_internal_state_ref::<InternalValue>(&self.meta.name, internal_state)? {
+            Some(value) => Ok(*value),
+            None => Ok(self.offset),
         }
     }
 }
@@ -60,7 +60,7 @@ impl GeneralParameter<f64> for OffsetParameter {
         state: &State,
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
-        let offset = self.offset(internal_state);
+        let offset = self.offset(internal_state)?;
         // Current value
         let x = self.metric.get_value(model, state)?;
         Ok(x + offset)
@@ -72,6 +72,10 @@ impl GeneralParameter<f64> for OffsetParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.metric]
+    }
 }
 
 impl VariableParameter<f64> for OffsetParameter {
@@ -79,8 +83,8 @@ impl VariableParameter<f64> for OffsetParameter {
         &self.meta
     }
 
-    fn size(&self, _variable_config: &dyn VariableConfig) -> usize {
-        1
+    fn size(&self, _variable_config: &dyn VariableConfig) -> Result<usize, PywrError> {
+        Ok(1)
     }
 
     fn set_variables(
@@ -89,10 +93,10 @@ impl VariableParameter<f64> for OffsetParameter {
         variable_config: &dyn VariableConfig,
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<(), PywrError> {
-        let activation_function = downcast_variable_config_ref::<ActivationFunction>(variable_config);
+        let activation_function = downcast_variable_config_ref::<ActivationFunction>(&self.meta.name, variable_config)?;
 
         if values.len() == 1 {
-            let value = downcast_internal_state_mut::<InternalValue>(internal_state);
+            let value = downcast_internal_state_mut::<InternalValue>(&self.meta.name, internal_state)?;
             *value = Some(activation_function.apply(values[0]));
             Ok(())
         } else {
@@ -101,18 +105,18 @@ impl VariableParameter<f64> for OffsetParameter {
     }
 
     fn get_variables(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Option<Vec<f64>> {
-        downcast_internal_state_ref::<InternalValue>(internal_state)
-            .as_ref()
-            .map(|value| vec![*value])
+        downcast_internal_state_ref::<InternalValue>(&self.meta.name, internal_state)
+            .ok()?
+            .map(|value| vec![value])
     }
 
     fn get_lower_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
-        let activation_function = downcast_variable_config_ref::<ActivationFunction>(variable_config);
+        let activation_function = downcast_variable_config_ref::<ActivationFunction>(&self.meta.name, variable_config)?;
         Ok(vec![activation_function.lower_bound()])
     }
 
     fn get_upper_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
-        let activation_function = downcast_variable_config_ref::<ActivationFunction>(variable_config);
+        let activation_function = downcast_variable_config_ref::<ActivationFunction>(&self.meta.name, variable_config)?;
         Ok(vec![activation_function.upper_bound()])
     }
 }
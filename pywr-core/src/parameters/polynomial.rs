@@ -59,4 +59,8 @@ impl GeneralParameter<f64> for Polynomial1DParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        vec![&self.metric]
+    }
 }
@@ -1,3 +1,4 @@
+use crate::parameters::profiles::{LeapDayPolicy, LeapDayPolicyError};
 use crate::parameters::{Parameter, ParameterMeta, ParameterName, ParameterState, SimpleParameter};
 use crate::scenario::ScenarioIndex;
 use crate::state::SimpleParameterValues;
@@ -16,6 +17,16 @@ impl DailyProfileParameter {
             values,
         }
     }
+
+    /// Create a profile from values whose number and treatment of the 29th February are given by
+    /// `leap_day_policy` (see [`LeapDayPolicy`]).
+    pub fn from_values(
+        name: ParameterName,
+        values: &[f64],
+        leap_day_policy: LeapDayPolicy,
+    ) -> Result<Self, LeapDayPolicyError> {
+        Ok(Self::new(name, leap_day_policy.daily_profile(values)?))
+    }
 }
 
 impl Parameter for DailyProfileParameter {
@@ -0,0 +1,135 @@
+use crate::parameters::{Parameter, ParameterMeta, ParameterName, ParameterState, SimpleParameter};
+use crate::scenario::ScenarioIndex;
+use crate::state::SimpleParameterValues;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use chrono::NaiveTime;
+
+const MILLISECS_IN_DAY: i64 = 1000 * 60 * 60 * 24;
+
+/// A profile of values over the course of a day.
+///
+/// The day is divided into intervals of equal length, one for each value (e.g. 24 values give an
+/// hourly profile), and the value of the interval containing the start of the time-step is
+/// returned. Each time-step of the model must lie within a single interval, so the time-steps can
+/// not be longer than the intervals or cross their boundaries.
+pub struct DiurnalProfileParameter {
+    meta: ParameterMeta,
+    values: Vec<f64>,
+    /// The length of each interval in milliseconds.
+    interval: i64,
+}
+
+impl DiurnalProfileParameter {
+    pub fn new(name: ParameterName, values: Vec<f64>) -> Result<Self, PywrError> {
+        let n = values.len() as i64;
+        if n == 0 || MILLISECS_IN_DAY % n != 0 {
+            return Err(PywrError::InvalidDiurnalProfile(values.len()));
+        }
+
+        Ok(Self {
+            meta: ParameterMeta::new(name),
+            values,
+            interval: MILLISECS_IN_DAY / n,
+        })
+    }
+
+    /// The number of milliseconds between midnight and the start of the time-step.
+    fn since_midnight(timestep: &Timestep) -> i64 {
+        timestep
+            .date
+            .time()
+            .signed_duration_since(NaiveTime::MIN)
+            .num_milliseconds()
+    }
+
+    /// The index of the interval containing the start of the time-step.
+    fn index(&self, timestep: &Timestep) -> usize {
+        (Self::since_midnight(timestep) / self.interval) as usize
+    }
+}
+
+impl Parameter for DiurnalProfileParameter {
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+
+    fn setup(
+        &self,
+        timesteps: &[Timestep],
+        _scenario_index: &ScenarioIndex,
+    ) -> Result<Option<Box<dyn ParameterState>>, PywrError> {
+        // Check the time-steps align with the intervals of the profile
+        for timestep in timesteps {
+            let offset = Self::since_midnight(timestep) % self.interval;
+            if offset + timestep.duration.milliseconds() > self.interval {
+                return Err(PywrError::DiurnalProfileResolutionMismatch {
+                    name: self.meta.name.to_string(),
+                    date: timestep.date,
+                });
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl SimpleParameter<f64> for DiurnalProfileParameter {
+    fn compute(
+        &self,
+        timestep: &Timestep,
+        _scenario_index: &ScenarioIndex,
+        _values: &SimpleParameterValues,
+        _internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<f64, PywrError> {
+        Ok(self.values[self.index(timestep)])
+    }
+
+    fn as_parameter(&self) -> &dyn Parameter
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiurnalProfileParameter;
+    use crate::parameters::Parameter;
+    use crate::scenario::ScenarioIndex;
+    use crate::timestep::{TimeDomain, TimestepDuration, Timestepper};
+    use chrono::NaiveDateTime;
+
+    fn time_domain(start: &str, end: &str, frequency: &str) -> TimeDomain {
+        let start = NaiveDateTime::parse_from_str(start, "%Y-%m-%d %H:%M:%S").unwrap();
+        let end = NaiveDateTime::parse_from_str(end, "%Y-%m-%d %H:%M:%S").unwrap();
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Frequency(frequency.to_string()));
+        TimeDomain::try_from(timestepper).unwrap()
+    }
+
+    #[test]
+    fn test_resolution() {
+        let scenario_index = ScenarioIndex::new(0, vec![0]);
+        // A profile with a value for every six hours
+        let p = DiurnalProfileParameter::new("diurnal".into(), vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+
+        let domain = time_domain("2021-01-01 00:00:00", "2021-01-02 23:00:00", "1h");
+        assert!(p.setup(domain.timesteps(), &scenario_index).is_ok());
+        assert_eq!(p.index(&domain.timesteps()[7]), 1);
+        assert_eq!(p.index(&domain.timesteps()[30]), 1);
+
+        let domain = time_domain("2021-01-01 00:00:00", "2021-01-02 18:00:00", "6h");
+        assert!(p.setup(domain.timesteps(), &scenario_index).is_ok());
+
+        // Time-steps that are longer than the intervals, or that cross them, are an error
+        let domain = time_domain("2021-01-01 00:00:00", "2021-01-03 00:00:00", "1d");
+        assert!(p.setup(domain.timesteps(), &scenario_index).is_err());
+        let domain = time_domain("2021-01-01 04:00:00", "2021-01-02 04:00:00", "4h");
+        assert!(p.setup(domain.timesteps(), &scenario_index).is_err());
+
+        // The number of values must divide the day into whole milliseconds
+        assert!(DiurnalProfileParameter::new("diurnal".into(), vec![]).is_err());
+        assert!(DiurnalProfileParameter::new("diurnal".into(), vec![1.0; 7]).is_err());
+    }
+}
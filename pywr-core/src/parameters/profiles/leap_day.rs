@@ -0,0 +1,144 @@
+use chrono::{Datelike, NaiveDate};
+use thiserror::Error;
+
+/// The index of the 28th February in a daily profile.
+const FEB_28_INDEX: usize = 58;
+
+/// How a daily profile gives a value for the 29th February.
+///
+/// A daily profile is looked up by [`Timestep::day_of_year_index`](crate::timestep::Timestep::day_of_year_index),
+/// which has 366 days in every year; the 29th February is skipped in years that are not leap years.
+/// Other profiles use [`LeapDayPolicy::day_of_year`] to place a date in a year of
+/// [`LeapDayPolicy::days_in_year`] days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeapDayPolicy {
+    /// The profile has 366 values, including one for the 29th February.
+    #[default]
+    Profile366,
+    /// The profile has 365 values and the value of the 28th February is repeated on the 29th.
+    RepeatFeb28,
+    /// The profile has 365 values and the value of the 29th February is the mean of the values of
+    /// the 28th February and the 1st March.
+    Interpolate,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LeapDayPolicyError {
+    #[error("{expected} values must be given for a daily profile with the {policy:?} leap day policy, but {found} were given")]
+    InvalidLength {
+        policy: LeapDayPolicy,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl LeapDayPolicy {
+    /// The number of values of a profile with this policy.
+    pub fn num_values(&self) -> usize {
+        match self {
+            Self::Profile366 => 366,
+            Self::RepeatFeb28 | Self::Interpolate => 365,
+        }
+    }
+
+    /// The number of days in every year with this policy.
+    pub fn days_in_year(&self) -> u32 {
+        self.num_values() as u32
+    }
+
+    /// The zero-based day of the year of `date` in a year of [`Self::days_in_year`] days.
+    ///
+    /// With [`Self::Profile366`] the 29th February is skipped in years that are not leap years.
+    /// Otherwise the 29th February is given the day of the 28th February ([`Self::RepeatFeb28`])
+    /// or the day halfway between the 28th February and the 1st March ([`Self::Interpolate`]).
+    pub fn day_of_year(&self, date: &NaiveDate) -> f64 {
+        let day = date.ordinal0() as usize;
+        if day <= FEB_28_INDEX {
+            return day as f64;
+        }
+
+        match self {
+            Self::Profile366 if date.leap_year() => day as f64,
+            Self::Profile366 => (day + 1) as f64,
+            _ if !date.leap_year() => day as f64,
+            _ if day > FEB_28_INDEX + 1 => (day - 1) as f64,
+            Self::RepeatFeb28 => FEB_28_INDEX as f64,
+            Self::Interpolate => FEB_28_INDEX as f64 + 0.5,
+        }
+    }
+
+    /// Create a profile of 366 values, indexed by the day of the year, from the given values.
+    pub fn daily_profile(&self, values: &[f64]) -> Result<[f64; 366], LeapDayPolicyError> {
+        if values.len() != self.num_values() {
+            return Err(LeapDayPolicyError::InvalidLength {
+                policy: *self,
+                expected: self.num_values(),
+                found: values.len(),
+            });
+        }
+
+        let mut profile = [0.0; 366];
+        match self {
+            Self::Profile366 => profile.copy_from_slice(values),
+            Self::RepeatFeb28 | Self::Interpolate => {
+                let (before, after) = values.split_at(FEB_28_INDEX + 1);
+                let feb_28 = before[FEB_28_INDEX];
+                let leap_day = match self {
+                    Self::Interpolate => (feb_28 + after[0]) / 2.0,
+                    _ => feb_28,
+                };
+
+                profile[..=FEB_28_INDEX].copy_from_slice(before);
+                profile[FEB_28_INDEX + 1] = leap_day;
+                profile[FEB_28_INDEX + 2..].copy_from_slice(after);
+            }
+        }
+
+        Ok(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LeapDayPolicy, LeapDayPolicyError};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_daily_profile() {
+        let values: Vec<f64> = (0..365).map(|i| i as f64).collect();
+
+        let profile = LeapDayPolicy::RepeatFeb28.daily_profile(&values).unwrap();
+        assert_eq!(profile[58..61], [58.0, 58.0, 59.0]);
+        assert_eq!(profile[365], 364.0);
+
+        let profile = LeapDayPolicy::Interpolate.daily_profile(&values).unwrap();
+        assert_eq!(profile[58..61], [58.0, 58.5, 59.0]);
+        assert_eq!(profile[0], 0.0);
+
+        assert_eq!(
+            LeapDayPolicy::Profile366.daily_profile(&values),
+            Err(LeapDayPolicyError::InvalidLength {
+                policy: LeapDayPolicy::Profile366,
+                expected: 366,
+                found: 365
+            })
+        );
+    }
+
+    #[test]
+    fn test_day_of_year() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(LeapDayPolicy::Profile366.day_of_year(&date(2020, 2, 29)), 59.0);
+        assert_eq!(LeapDayPolicy::Profile366.day_of_year(&date(2021, 3, 1)), 60.0);
+        assert_eq!(LeapDayPolicy::Profile366.day_of_year(&date(2020, 3, 1)), 60.0);
+
+        assert_eq!(LeapDayPolicy::RepeatFeb28.day_of_year(&date(2020, 2, 29)), 58.0);
+        assert_eq!(LeapDayPolicy::RepeatFeb28.day_of_year(&date(2020, 3, 1)), 59.0);
+        assert_eq!(LeapDayPolicy::RepeatFeb28.day_of_year(&date(2021, 3, 1)), 59.0);
+        assert_eq!(LeapDayPolicy::RepeatFeb28.day_of_year(&date(2020, 12, 31)), 364.0);
+
+        assert_eq!(LeapDayPolicy::Interpolate.day_of_year(&date(2020, 2, 29)), 58.5);
+        assert_eq!(LeapDayPolicy::Interpolate.day_of_year(&date(2020, 2, 28)), 58.0);
+    }
+}
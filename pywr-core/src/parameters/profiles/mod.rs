@@ -1,10 +1,14 @@
 mod daily;
+mod diurnal;
+mod leap_day;
 mod monthly;
 mod rbf;
 mod uniform_drawdown;
 mod weekly;
 
 pub use daily::DailyProfileParameter;
+pub use diurnal::DiurnalProfileParameter;
+pub use leap_day::{LeapDayPolicy, LeapDayPolicyError};
 pub use monthly::{MonthlyInterpDay, MonthlyProfileParameter};
 pub use rbf::{RadialBasisFunction, RbfProfileParameter, RbfProfileVariableConfig};
 pub use uniform_drawdown::UniformDrawdownProfileParameter;
@@ -1,3 +1,4 @@
+use crate::parameters::profiles::LeapDayPolicy;
 use crate::parameters::{Parameter, ParameterMeta, ParameterName, ParameterState, SimpleParameter};
 use crate::scenario::ScenarioIndex;
 use crate::state::SimpleParameterValues;
@@ -11,10 +12,15 @@ pub enum MonthlyInterpDay {
     Last,
 }
 
+/// Monthly profile parameter, with or without interpolation between the months.
+///
+/// By default an interpolated February has 29 days in leap years. An optional [`LeapDayPolicy`]
+/// gives February the same number of days in every year instead.
 pub struct MonthlyProfileParameter {
     meta: ParameterMeta,
     values: [f64; 12],
     interp_day: Option<MonthlyInterpDay>,
+    leap_day_policy: Option<LeapDayPolicy>,
 }
 
 impl MonthlyProfileParameter {
@@ -23,48 +29,80 @@ impl MonthlyProfileParameter {
             meta: ParameterMeta::new(name),
             values,
             interp_day,
+            leap_day_policy: None,
         }
     }
+
+    /// Set the number of days in February used for interpolation. With [`LeapDayPolicy::Profile366`]
+    /// February always has 29 days. Otherwise it has 28 days and the 29th February is at the
+    /// position of the 28th February ([`LeapDayPolicy::RepeatFeb28`]) or halfway between the 28th
+    /// February and the 1st March ([`LeapDayPolicy::Interpolate`]).
+    pub fn with_leap_day_policy(mut self, leap_day_policy: LeapDayPolicy) -> Self {
+        self.leap_day_policy = Some(leap_day_policy);
+        self
+    }
 }
 
 fn days_in_year_month(datetime: &NaiveDateTime) -> u32 {
+    // `NaiveDateTime::month` is always in the range 1-12; every other month has 31 days.
     match datetime.month() {
-        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
         4 | 6 | 9 | 11 => 30,
         2 if datetime.date().leap_year() => 29,
         2 => 28,
-        _ => panic!("Invalid month"),
+        _ => 31,
+    }
+}
+
+/// Get the day of the month of the date and the number of days in its month, with February
+/// given by the leap day policy.
+fn day_of_month(datetime: &NaiveDateTime, leap_day_policy: Option<LeapDayPolicy>) -> (f64, u32) {
+    let day = datetime.day() as f64;
+    match (datetime.month(), leap_day_policy) {
+        (2, Some(LeapDayPolicy::Profile366)) => (day, 29),
+        (2, Some(LeapDayPolicy::RepeatFeb28)) => (day.min(28.0), 28),
+        (2, Some(LeapDayPolicy::Interpolate)) => (day.min(28.5), 28),
+        _ => (day, days_in_year_month(datetime)),
     }
 }
 
 /// Interpolate between first_value and last value based on the day of the month. The last
 /// value is assumed to correspond to the first day of the next month.
-fn interpolate_first(date: &NaiveDateTime, first_value: f64, last_value: f64) -> f64 {
-    let days_in_month = days_in_year_month(date);
+fn interpolate_first(
+    date: &NaiveDateTime,
+    first_value: f64,
+    last_value: f64,
+    leap_day_policy: Option<LeapDayPolicy>,
+) -> f64 {
+    let (day, days_in_month) = day_of_month(date, leap_day_policy);
 
-    if date.day() <= 1 {
+    if day <= 1.0 {
         first_value
-    } else if date.day() > days_in_month {
+    } else if day >= days_in_month as f64 + 1.0 {
         last_value
     } else {
         first_value
-            + (last_value - first_value) * (date.day() as f64 + date.num_seconds_from_midnight() as f64 / 86400.0 - 1.0)
+            + (last_value - first_value) * (day + date.num_seconds_from_midnight() as f64 / 86400.0 - 1.0)
                 / days_in_month as f64
     }
 }
 
 /// Interpolate between first_value and last value based on the day of the month. The first
 /// value is assumed to correspond to the last day of the previous month.
-fn interpolate_last(date: &NaiveDateTime, first_value: f64, last_value: f64) -> f64 {
-    let days_in_month = days_in_year_month(date);
+fn interpolate_last(
+    date: &NaiveDateTime,
+    first_value: f64,
+    last_value: f64,
+    leap_day_policy: Option<LeapDayPolicy>,
+) -> f64 {
+    let (day, days_in_month) = day_of_month(date, leap_day_policy);
 
-    if date.day() < 1 {
+    if day < 1.0 {
         first_value
-    } else if date.day() >= days_in_month {
+    } else if day >= days_in_month as f64 {
         last_value
     } else {
         first_value
-            + (last_value - first_value) * (date.day() as f64 + date.num_seconds_from_midnight() as f64 / 86400.0)
+            + (last_value - first_value) * (day + date.num_seconds_from_midnight() as f64 / 86400.0)
                 / days_in_month as f64
     }
 }
@@ -89,7 +127,7 @@ impl SimpleParameter<f64> for MonthlyProfileParameter {
                     let first_value = self.values[timestep.date.month0() as usize];
                     let last_value = self.values[next_month0 as usize];
 
-                    interpolate_first(&timestep.date, first_value, last_value)
+                    interpolate_first(&timestep.date, first_value, last_value, self.leap_day_policy)
                 }
                 MonthlyInterpDay::Last => {
                     let current_month = timestep.date.month();
@@ -97,7 +135,7 @@ impl SimpleParameter<f64> for MonthlyProfileParameter {
                     let first_value = self.values[last_month as usize - 1];
                     let last_value = self.values[timestep.date.month() as usize - 1];
 
-                    interpolate_last(&timestep.date, first_value, last_value)
+                    interpolate_last(&timestep.date, first_value, last_value, self.leap_day_policy)
                 }
             },
             None => self.values[timestep.date.month() as usize - 1],
@@ -112,3 +150,32 @@ impl SimpleParameter<f64> for MonthlyProfileParameter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{interpolate_first, interpolate_last};
+    use crate::parameters::profiles::LeapDayPolicy;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_interpolate_leap_day() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        // By the calendar February has 29 days in a leap year
+        assert_eq!(interpolate_first(&date(2020, 2, 29), 0.0, 29.0, None), 28.0);
+        assert_eq!(interpolate_first(&date(2021, 2, 28), 0.0, 28.0, None), 27.0);
+
+        // February always has 29 days
+        let policy = Some(LeapDayPolicy::Profile366);
+        assert_eq!(interpolate_first(&date(2021, 2, 28), 0.0, 29.0, policy), 27.0);
+
+        // February always has 28 days
+        let policy = Some(LeapDayPolicy::RepeatFeb28);
+        assert_eq!(interpolate_first(&date(2020, 2, 28), 0.0, 28.0, policy), 27.0);
+        assert_eq!(interpolate_first(&date(2020, 2, 29), 0.0, 28.0, policy), 27.0);
+        assert_eq!(interpolate_last(&date(2020, 2, 29), 0.0, 28.0, policy), 28.0);
+
+        let policy = Some(LeapDayPolicy::Interpolate);
+        assert_eq!(interpolate_first(&date(2020, 2, 29), 0.0, 28.0, policy), 27.5);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::parameters::profiles::LeapDayPolicy;
 use crate::parameters::{
     downcast_internal_state_mut, downcast_internal_state_ref, downcast_variable_config_ref, Parameter, ParameterMeta,
     ParameterName, ParameterState, SimpleParameter, VariableConfig, VariableParameter,
@@ -30,6 +31,7 @@ pub struct RbfProfileParameter {
     meta: ParameterMeta,
     points: Vec<(u32, f64)>,
     function: RadialBasisFunction,
+    leap_day_policy: LeapDayPolicy,
 }
 
 /// The internal state of the RbfProfileParameter.
@@ -46,14 +48,18 @@ struct RbfProfileInternalState {
 }
 
 impl RbfProfileInternalState {
-    fn new(points: &[(u32, f64)], function: &RadialBasisFunction) -> Self {
-        let profile = interpolate_rbf_profile(points, function);
-
-        Self {
+    fn new(
+        points: &[(u32, f64)],
+        function: &RadialBasisFunction,
+        leap_day_policy: LeapDayPolicy,
+    ) -> Result<Self, PywrError> {
+        let profile = interpolate_rbf_profile(points, function, leap_day_policy)?;
+
+        Ok(Self {
             profile,
             points_x: None,
             points_y: None,
-        }
+        })
     }
 
     /// Update the x values of the points.
@@ -72,7 +78,15 @@ impl RbfProfileInternalState {
 
     /// Update the profile with the given points used as default. Any locally stored x and y values are
     /// used in preference to the default points when interpolating the profile.
-    fn update_profile(&mut self, points: &[(u32, f64)], function: &RadialBasisFunction) {
+    ///
+    /// An error is returned, and the profile is not changed, if the profile can not be
+    /// interpolated from the points (e.g. because updated x values duplicate a day).
+    fn update_profile(
+        &mut self,
+        points: &[(u32, f64)],
+        function: &RadialBasisFunction,
+        leap_day_policy: LeapDayPolicy,
+    ) -> Result<(), PywrError> {
         let points: Vec<_> = match (&self.points_x, &self.points_y) {
             (Some(x), Some(y)) => x.iter().zip(y.iter()).map(|(x, y)| (*x, *y)).collect(),
             (Some(x), None) => x
@@ -84,7 +98,8 @@ impl RbfProfileInternalState {
             (None, None) => points.to_vec(),
         };
 
-        self.profile = interpolate_rbf_profile(&points, function);
+        self.profile = interpolate_rbf_profile(&points, function, leap_day_policy)?;
+        Ok(())
     }
 }
 
@@ -94,8 +109,18 @@ impl RbfProfileParameter {
             meta: ParameterMeta::new(name),
             points,
             function,
+            leap_day_policy: LeapDayPolicy::RepeatFeb28,
         }
     }
+
+    /// Set how the value of the 29th February is found (the default is [`LeapDayPolicy::RepeatFeb28`]).
+    ///
+    /// With [`LeapDayPolicy::Profile366`] the radial basis function is evaluated half way between
+    /// the 28th February and the 1st March.
+    pub fn with_leap_day_policy(mut self, leap_day_policy: LeapDayPolicy) -> Self {
+        self.leap_day_policy = leap_day_policy;
+        self
+    }
 }
 
 impl Parameter for RbfProfileParameter {
@@ -108,7 +133,7 @@ impl Parameter for RbfProfileParameter {
         _timesteps: &[Timestep],
         _scenario_index: &ScenarioIndex,
     ) -> Result<Option<Box<dyn ParameterState>>, PywrError> {
-        let internal_state = RbfProfileInternalState::new(&self.points, &self.function);
+        let internal_state = RbfProfileInternalState::new(&self.points, &self.function, self.leap_day_policy)?;
         Ok(Some(Box::new(internal_state)))
     }
     fn as_f64_variable(&self) -> Option<&dyn VariableParameter<f64>> {
@@ -137,7 +162,7 @@ impl SimpleParameter<f64> for RbfProfileParameter {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
         // Get the profile from the internal state
-        let internal_state = downcast_internal_state_ref::<RbfProfileInternalState>(internal_state);
+        let internal_state = downcast_internal_state_ref::<RbfProfileInternalState>(&self.meta.name, internal_state)?;
         // Return today's value from the profile
         Ok(internal_state.profile[timestep.day_of_year_index()])
     }
@@ -156,8 +181,8 @@ impl VariableParameter<f64> for RbfProfileParameter {
     }
 
     /// The size is the number of points that define the profile.
-    fn size(&self, _variable_config: &dyn VariableConfig) -> usize {
-        self.points.len()
+    fn size(&self, _variable_config: &dyn VariableConfig) -> Result<usize, PywrError> {
+        Ok(self.points.len())
     }
 
     /// The f64 values update the profile value of each point.
@@ -168,12 +193,10 @@ impl VariableParameter<f64> for RbfProfileParameter {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<(), PywrError> {
         if values.len() == self.points.len() {
-            let value = downcast_internal_state_mut::<RbfProfileInternalState>(internal_state);
+            let value = downcast_internal_state_mut::<RbfProfileInternalState>(&self.meta.name, internal_state)?;
 
             value.update_y(values.to_vec());
-            value.update_profile(&self.points, &self.function);
-
-            Ok(())
+            value.update_profile(&self.points, &self.function, self.leap_day_policy)
         } else {
             Err(PywrError::ParameterVariableValuesIncorrectLength)
         }
@@ -181,18 +204,18 @@ impl VariableParameter<f64> for RbfProfileParameter {
 
     /// The f64 values are the profile values of each point.
     fn get_variables(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Option<Vec<f64>> {
-        let value = downcast_internal_state_ref::<RbfProfileInternalState>(internal_state);
+        let value = downcast_internal_state_ref::<RbfProfileInternalState>(&self.meta.name, internal_state).ok()?;
         value.points_y.clone()
     }
 
     fn get_lower_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
-        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(variable_config);
+        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(&self.meta.name, variable_config)?;
         let lb = (0..self.points.len()).map(|_| config.value_lower_bounds).collect();
         Ok(lb)
     }
 
     fn get_upper_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<f64>, PywrError> {
-        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(variable_config);
+        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(&self.meta.name, variable_config)?;
         let lb = (0..self.points.len()).map(|_| config.value_upper_bounds).collect();
         Ok(lb)
     }
@@ -203,11 +226,11 @@ impl VariableParameter<u32> for RbfProfileParameter {
         &self.meta
     }
     /// The size is the number of points that define the profile.
-    fn size(&self, variable_config: &dyn VariableConfig) -> usize {
-        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(variable_config);
+    fn size(&self, variable_config: &dyn VariableConfig) -> Result<usize, PywrError> {
+        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(&self.meta.name, variable_config)?;
         match config.days_of_year_range {
-            Some(_) => self.points.len(),
-            None => 0,
+            Some(_) => Ok(self.points.len()),
+            None => Ok(0),
         }
     }
 
@@ -219,12 +242,10 @@ impl VariableParameter<u32> for RbfProfileParameter {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<(), PywrError> {
         if values.len() == self.points.len() {
-            let value = downcast_internal_state_mut::<RbfProfileInternalState>(internal_state);
+            let value = downcast_internal_state_mut::<RbfProfileInternalState>(&self.meta.name, internal_state)?;
 
             value.update_x(values.to_vec());
-            value.update_profile(&self.points, &self.function);
-
-            Ok(())
+            value.update_profile(&self.points, &self.function, self.leap_day_policy)
         } else {
             Err(PywrError::ParameterVariableValuesIncorrectLength)
         }
@@ -232,12 +253,12 @@ impl VariableParameter<u32> for RbfProfileParameter {
 
     /// Returns the day of year for each point.
     fn get_variables(&self, internal_state: &Option<Box<dyn ParameterState>>) -> Option<Vec<u32>> {
-        let value = downcast_internal_state_ref::<RbfProfileInternalState>(internal_state);
+        let value = downcast_internal_state_ref::<RbfProfileInternalState>(&self.meta.name, internal_state).ok()?;
         value.points_x.clone()
     }
 
     fn get_lower_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<u32>, PywrError> {
-        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(variable_config);
+        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(&self.meta.name, variable_config)?;
 
         if let Some(days_of_year_range) = &config.days_of_year_range {
             // Make sure the lower bound is not less than 1 and handle integer underflow
@@ -254,7 +275,7 @@ impl VariableParameter<u32> for RbfProfileParameter {
     }
 
     fn get_upper_bounds(&self, variable_config: &dyn VariableConfig) -> Result<Vec<u32>, PywrError> {
-        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(variable_config);
+        let config = downcast_variable_config_ref::<RbfProfileVariableConfig>(&self.meta.name, variable_config)?;
 
         if let Some(days_of_year_range) = &config.days_of_year_range {
             // Make sure the upper bound is not greater than 365 and handle integer overflow
@@ -298,8 +319,13 @@ impl RadialBasisFunction {
 
 /// Perform radial-basis function interpolation from the given points.
 ///
-/// The provided points are a tuple of observed (x, y) values.
-fn interpolate_rbf<const N: usize>(points: &[(f64, f64)], function: &RadialBasisFunction, x: &[f64; N]) -> [f64; N] {
+/// The provided points are a tuple of observed (x, y) values. An error is returned if the
+/// interpolation weights can not be found (e.g. because two points have the same x value).
+fn interpolate_rbf<const N: usize>(
+    points: &[(f64, f64)],
+    function: &RadialBasisFunction,
+    x: &[f64; N],
+) -> Result<[f64; N], PywrError> {
     let n = points.len();
 
     let matrix = DMatrix::from_fn(n, n, |r, c| {
@@ -312,7 +338,8 @@ fn interpolate_rbf<const N: usize>(points: &[(f64, f64)], function: &RadialBasis
     let weights = matrix
         .lu()
         .solve(&b)
-        .expect("Failed to solve RBF system for interpolation weights.");
+        .filter(|w| w.iter().all(|v| v.is_finite()))
+        .ok_or(PywrError::RbfSystemNotSolvable)?;
 
     let mut profile = [f64::default(); N];
 
@@ -328,15 +355,19 @@ fn interpolate_rbf<const N: usize>(points: &[(f64, f64)], function: &RadialBasis
             .sum();
     }
 
-    profile
+    Ok(profile)
 }
 
 /// Calculate the interpolation weights for the given points.
 ///
 /// This method repeats the point 365 days before and after the user provided points. This
-/// helps create a cyclic interpolation suitable for a annual profile. It then repeats the
-/// value for the 58th day to create a daily profile 366 days long.
-fn interpolate_rbf_profile(points: &[(u32, f64)], function: &RadialBasisFunction) -> [f64; 366] {
+/// helps create a cyclic interpolation suitable for a annual profile. The value of the 29th
+/// February is then given by `leap_day_policy` to create a daily profile 366 days long.
+fn interpolate_rbf_profile(
+    points: &[(u32, f64)],
+    function: &RadialBasisFunction,
+    leap_day_policy: LeapDayPolicy,
+) -> Result<[f64; 366], PywrError> {
     // Replicate the points in the year before and after.
     let year_before = points.iter().map(|p| (p.0 as f64 - 365.0, p.1));
     let year_after = points.iter().map(|p| (p.0 as f64 + 365.0, p.1));
@@ -345,22 +376,39 @@ fn interpolate_rbf_profile(points: &[(u32, f64)], function: &RadialBasisFunction
         .chain(year_after)
         .collect();
 
+    if leap_day_policy == LeapDayPolicy::Profile366 {
+        // The 29th February is half way between the 28th February and the 1st March
+        let mut x_out = [f64::default(); 366];
+        for (i, v) in x_out.iter_mut().enumerate() {
+            *v = match i {
+                0..=58 => i as f64,
+                59 => 58.5,
+                _ => i as f64 - 1.0,
+            };
+        }
+        return interpolate_rbf(&points, function, &x_out);
+    }
+
     let mut x_out = [f64::default(); 365];
     for (i, v) in x_out.iter_mut().enumerate() {
         *v = i as f64;
     }
-    let short_profile = interpolate_rbf(&points, function, &x_out);
+    let short_profile = interpolate_rbf(&points, function, &x_out)?;
 
-    let (start, end) = short_profile.split_at(58);
-
-    let profile = [start, &[end[0]], end].concat();
-
-    profile.try_into().unwrap()
+    Ok(leap_day_policy
+        .daily_profile(&short_profile)
+        .expect("A profile of 365 values is valid for this leap day policy."))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parameters::profiles::rbf::{interpolate_rbf, interpolate_rbf_profile, RadialBasisFunction};
+    use crate::parameters::profiles::rbf::{
+        interpolate_rbf, interpolate_rbf_profile, RadialBasisFunction, RbfProfileParameter, RbfProfileVariableConfig,
+    };
+    use crate::parameters::profiles::LeapDayPolicy;
+    use crate::parameters::{Parameter, VariableParameter};
+    use crate::scenario::ScenarioIndex;
+    use crate::PywrError;
     use float_cmp::{assert_approx_eq, F64Margin};
     use std::f64::consts::PI;
 
@@ -397,7 +445,7 @@ mod tests {
         }
 
         let rbf = RadialBasisFunction::Gaussian { epsilon: 3.0 };
-        let f_interp = interpolate_rbf(&points, &rbf, &x_out);
+        let f_interp = interpolate_rbf(&points, &rbf, &x_out).unwrap();
 
         // Values computed from the Scipy RBF interpolation function for the same problem.
         let f_expected = [
@@ -449,7 +497,7 @@ mod tests {
         let points: Vec<(u32, f64)> = vec![(90, 0.5), (180, 0.3), (270, 0.7)];
 
         let rbf = RadialBasisFunction::MultiQuadric { epsilon: 1.0 / 50.0 };
-        let f_interp = interpolate_rbf_profile(&points, &rbf);
+        let f_interp = interpolate_rbf_profile(&points, &rbf, LeapDayPolicy::RepeatFeb28).unwrap();
 
         let f_expected = [
             0.69464463, 0.69308183, 0.69150736, 0.68992139, 0.68832406, 0.68671551, 0.68509589, 0.68346531, 0.68182389,
@@ -499,4 +547,56 @@ mod tests {
             assert_approx_eq!(f64, *i, e, F64Margin { ulps: 2, epsilon: 1e-6 });
         }
     }
+
+    /// Test the value of the 29th February with each leap day policy.
+    #[test]
+    fn test_rbf_interpolation_profile_leap_day() {
+        let points: Vec<(u32, f64)> = vec![(90, 0.5), (180, 0.3), (270, 0.7)];
+        let rbf = RadialBasisFunction::MultiQuadric { epsilon: 1.0 / 50.0 };
+
+        let repeat = interpolate_rbf_profile(&points, &rbf, LeapDayPolicy::RepeatFeb28).unwrap();
+        let interpolate = interpolate_rbf_profile(&points, &rbf, LeapDayPolicy::Interpolate).unwrap();
+        let full = interpolate_rbf_profile(&points, &rbf, LeapDayPolicy::Profile366).unwrap();
+
+        // Every other day is the same
+        for i in (0..366).filter(|i| *i != 59) {
+            assert_approx_eq!(f64, repeat[i], interpolate[i]);
+            assert_approx_eq!(f64, repeat[i], full[i]);
+        }
+
+        assert_approx_eq!(f64, repeat[59], repeat[58]);
+        assert_approx_eq!(f64, interpolate[59], (repeat[58] + repeat[60]) / 2.0);
+        // The profile is decreasing at the end of February
+        assert!(full[59] < full[58] && full[59] > full[60]);
+    }
+
+    /// Test that points with the same day return an error rather than panicking.
+    #[test]
+    fn test_rbf_duplicate_days() {
+        let points: Vec<(u32, f64)> = vec![(90, 0.5), (90, 0.3), (270, 0.7)];
+        let rbf = RadialBasisFunction::MultiQuadric { epsilon: 1.0 / 50.0 };
+
+        let result = interpolate_rbf_profile(&points, &rbf, LeapDayPolicy::RepeatFeb28);
+        assert!(matches!(result, Err(PywrError::RbfSystemNotSolvable)));
+
+        let parameter = RbfProfileParameter::new("rbf".into(), points, rbf);
+        let result = Parameter::setup(&parameter, &[], &ScenarioIndex::new(0, vec![0]));
+        assert!(matches!(result, Err(PywrError::RbfSystemNotSolvable)));
+    }
+
+    /// Test that updating the days of the points to the same day returns an error.
+    #[test]
+    fn test_rbf_update_duplicate_days() {
+        let points: Vec<(u32, f64)> = vec![(90, 0.5), (180, 0.3), (270, 0.7)];
+        let rbf = RadialBasisFunction::MultiQuadric { epsilon: 1.0 / 50.0 };
+        let parameter = RbfProfileParameter::new("rbf".into(), points, rbf);
+        let config = RbfProfileVariableConfig::new(Some(90), 1.0, 0.0);
+
+        let mut state = Parameter::setup(&parameter, &[], &ScenarioIndex::new(0, vec![0])).unwrap();
+
+        VariableParameter::<u32>::set_variables(&parameter, &[100, 170, 280], &config, &mut state).unwrap();
+
+        let result = VariableParameter::<u32>::set_variables(&parameter, &[120, 120, 280], &config, &mut state);
+        assert!(matches!(result, Err(PywrError::RbfSystemNotSolvable)));
+    }
 }
@@ -1,53 +1,69 @@
+use crate::parameters::profiles::LeapDayPolicy;
 use crate::parameters::{Parameter, ParameterMeta, ParameterName, ParameterState, SimpleParameter};
 use crate::scenario::ScenarioIndex;
 use crate::state::SimpleParameterValues;
 use crate::timestep::Timestep;
 use crate::PywrError;
-use chrono::{Datelike, NaiveDate};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
 
 fn is_leap_year(year: i32) -> bool {
     (year % 4 == 0) & ((year % 100 != 0) | (year % 400 == 0))
 }
 
+/// A profile that decreases uniformly from one on the reset day to the residual proportion on the
+/// day before the next reset day.
+///
+/// By default the length of the period is 366 days if it contains the 29th February. An optional
+/// [`LeapDayPolicy`] gives every period the same length instead.
 pub struct UniformDrawdownProfileParameter {
     meta: ParameterMeta,
     residual_days: u8,
+    reset_date: NaiveDate,
     reset_doy: u16,
+    leap_day_policy: Option<LeapDayPolicy>,
 }
 
 impl UniformDrawdownProfileParameter {
     pub fn new(name: ParameterName, reset_day: u32, reset_month: u32, residual_days: u8) -> Self {
         // Calculate the reset day of year in a known leap year.
-        let reset_doy = NaiveDate::from_ymd_opt(2016, reset_month, reset_day)
-            .expect("Invalid reset day")
-            .ordinal() as u16;
+        let reset_date = NaiveDate::from_ymd_opt(2016, reset_month, reset_day).expect("Invalid reset day");
+        let reset_doy = reset_date.ordinal() as u16;
 
         Self {
             meta: ParameterMeta::new(name),
             residual_days,
+            reset_date,
             reset_doy,
+            leap_day_policy: None,
         }
     }
-}
 
-impl Parameter for UniformDrawdownProfileParameter {
-    fn meta(&self) -> &ParameterMeta {
-        &self.meta
+    /// Set how the 29th February is placed in the period (see [`LeapDayPolicy::day_of_year`]).
+    pub fn with_leap_day_policy(mut self, leap_day_policy: LeapDayPolicy) -> Self {
+        self.leap_day_policy = Some(leap_day_policy);
+        self
     }
-}
-impl SimpleParameter<f64> for UniformDrawdownProfileParameter {
-    fn compute(
-        &self,
-        timestep: &Timestep,
-        _scenario_index: &ScenarioIndex,
-        _values: &SimpleParameterValues,
-        _internal_state: &mut Option<Box<dyn ParameterState>>,
-    ) -> Result<f64, PywrError> {
+
+    /// The value of the profile on the given date.
+    fn value(&self, date: &NaiveDateTime) -> f64 {
+        if let Some(policy) = self.leap_day_policy {
+            let total_days_in_period = policy.days_in_year() as f64;
+            let mut days_into_period = policy.day_of_year(&date.date()) - policy.day_of_year(&self.reset_date);
+            if days_into_period < 0.0 {
+                days_into_period += total_days_in_period;
+            }
+
+            let residual_proportion = self.residual_days as f64 / total_days_in_period;
+            let slope = (residual_proportion - 1.0) / total_days_in_period;
+
+            return 1.0 + (slope * days_into_period);
+        }
+
         // Current calendar year (might be adjusted depending on position of reset day)
-        let mut year = timestep.date.year();
+        let mut year = date.year();
 
         // Current day of the year.
-        let current_doy = timestep.date.ordinal();
+        let current_doy = date.ordinal();
         let mut days_into_period: i32 = current_doy as i32 - self.reset_doy as i32;
         if days_into_period < 0 {
             // We're not past the reset day yet; use the previous year
@@ -67,7 +83,7 @@ impl SimpleParameter<f64> for UniformDrawdownProfileParameter {
             days_into_period += 366;
             // Need to adjust for post 29th Feb in non-leap years.
             // Recall `current_doy` was incremented by 1 if it is a non-leap already (hence comparison to 60)
-            if !is_leap_year(timestep.date.year()) && current_doy > 60 {
+            if !is_leap_year(date.year()) && current_doy > 60 {
                 days_into_period -= 1;
             }
         }
@@ -75,7 +91,24 @@ impl SimpleParameter<f64> for UniformDrawdownProfileParameter {
         let residual_proportion = self.residual_days as f64 / total_days_in_period as f64;
         let slope = (residual_proportion - 1.0) / total_days_in_period as f64;
 
-        Ok(1.0 + (slope * days_into_period as f64))
+        1.0 + (slope * days_into_period as f64)
+    }
+}
+
+impl Parameter for UniformDrawdownProfileParameter {
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+}
+impl SimpleParameter<f64> for UniformDrawdownProfileParameter {
+    fn compute(
+        &self,
+        timestep: &Timestep,
+        _scenario_index: &ScenarioIndex,
+        _values: &SimpleParameterValues,
+        _internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<f64, PywrError> {
+        Ok(self.value(&timestep.date))
     }
 
     fn as_parameter(&self) -> &dyn Parameter
@@ -85,3 +118,31 @@ impl SimpleParameter<f64> for UniformDrawdownProfileParameter {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::UniformDrawdownProfileParameter;
+    use crate::parameters::profiles::LeapDayPolicy;
+    use chrono::NaiveDate;
+    use float_cmp::assert_approx_eq;
+
+    fn value(p: &UniformDrawdownProfileParameter, y: i32, m: u32, d: u32) -> f64 {
+        p.value(&NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn test_leap_day_policy() {
+        // By the calendar the period from 1st January 2020 has 366 days
+        let p = UniformDrawdownProfileParameter::new("p".into(), 1, 1, 0);
+        assert_eq!(value(&p, 2020, 1, 1), 1.0);
+        assert_approx_eq!(f64, value(&p, 2020, 12, 31), 1.0 / 366.0);
+        assert_approx_eq!(f64, value(&p, 2021, 12, 31), 1.0 / 365.0);
+
+        // Every period has 365 days and the 29th February is the same as the 28th February
+        let p =
+            UniformDrawdownProfileParameter::new("p".into(), 1, 1, 0).with_leap_day_policy(LeapDayPolicy::RepeatFeb28);
+        assert_approx_eq!(f64, value(&p, 2020, 12, 31), 1.0 / 365.0);
+        assert_eq!(value(&p, 2020, 2, 29), value(&p, 2020, 2, 28));
+        assert_eq!(value(&p, 2020, 3, 1), value(&p, 2021, 3, 1));
+    }
+}
@@ -1,9 +1,10 @@
+use crate::parameters::profiles::LeapDayPolicy;
 use crate::parameters::{Parameter, ParameterMeta, ParameterName, ParameterState, SimpleParameter};
 use crate::scenario::ScenarioIndex;
 use crate::state::SimpleParameterValues;
 use crate::timestep::Timestep;
 use crate::PywrError;
-use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use chrono::{Datelike, NaiveDateTime, Timelike};
 use thiserror::Error;
 
 pub enum WeeklyInterpDay {
@@ -17,27 +18,38 @@ pub enum WeeklyProfileValues {
     FiftyThree([f64; 53]),
 }
 
+/// Get the zero-based day of the year of date and the number of days in its year. Without a
+/// leap day policy these are given by the calendar.
+fn day_of_year(date: &NaiveDateTime, leap_day_policy: Option<LeapDayPolicy>) -> (f64, u32) {
+    match leap_day_policy {
+        Some(policy) => (policy.day_of_year(&date.date()), policy.days_in_year()),
+        None => {
+            let days_in_year = if date.date().leap_year() { 366 } else { 365 };
+            (date.ordinal0() as f64, days_in_year)
+        }
+    }
+}
+
 impl WeeklyProfileValues {
     /// Get the week position in a calendar year from date. In the first year week, the position
     /// starts from 0 on the first week day and ends with 1 on the last day. Seconds may be
     /// included in the position by setting with_seconds to true.
-    fn current_pos(&self, date: &NaiveDateTime, with_seconds: bool) -> f64 {
-        let mut current_day = date.ordinal() as f64;
+    fn current_pos(&self, date: &NaiveDateTime, with_seconds: bool, leap_day_policy: Option<LeapDayPolicy>) -> f64 {
+        let (mut current_day, _) = day_of_year(date, leap_day_policy);
         if with_seconds {
             let seconds_in_day = date.num_seconds_from_midnight() as f64 / 86400.0;
             current_day += seconds_in_day;
         }
-        (current_day - 1.0) / 7.0
+        current_day / 7.0
     }
 
     /// Get the week index from the provided date
-    fn current_index(&self, date: &NaiveDateTime) -> usize {
-        let current_day = date.ordinal();
-        let current_pos = self.current_pos(date, false) as usize;
+    fn current_index(&self, date: &NaiveDateTime, leap_day_policy: Option<LeapDayPolicy>) -> usize {
+        let (current_day, days_in_year) = day_of_year(date, leap_day_policy);
+        let current_pos = self.current_pos(date, false, leap_day_policy) as usize;
 
-        // if year is leap the last week starts on the 365th day
-        let is_leap_year = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap().leap_year();
-        let last_week_day_start = if is_leap_year { 365 } else { 364 };
+        // the last week starts on the second to last day of the year (e.g. the 365th day of a leap year)
+        let last_week_day_start = (days_in_year - 2) as f64;
 
         match self {
             Self::FiftyTwo(_) => {
@@ -58,11 +70,11 @@ impl WeeklyProfileValues {
     }
 
     /// Get the value corresponding to the week index for the provided date
-    fn current(&self, date: &NaiveDateTime) -> f64 {
+    fn current(&self, date: &NaiveDateTime, leap_day_policy: Option<LeapDayPolicy>) -> f64 {
         // The current_index function always returns and index between 0 and
         // 52 (for Self::FiftyTwo) or 53 (Self::FiftyThree). This ensures
         // that the index is always in range in the value array below
-        let current_index = self.current_index(date);
+        let current_index = self.current_index(date, leap_day_policy);
 
         match self {
             Self::FiftyTwo(values) => values[current_index],
@@ -73,8 +85,8 @@ impl WeeklyProfileValues {
     /// Get the next week's value based on the week index of the provided date. If the current
     /// week is larger than the array length, the value corresponding to the first week is
     /// returned.
-    fn next(&self, date: &NaiveDateTime) -> f64 {
-        let current_week_index = self.current_index(date);
+    fn next(&self, date: &NaiveDateTime, leap_day_policy: Option<LeapDayPolicy>) -> f64 {
+        let current_week_index = self.current_index(date, leap_day_policy);
 
         match self {
             Self::FiftyTwo(values) => {
@@ -96,8 +108,8 @@ impl WeeklyProfileValues {
 
     /// Get the previous week's value based on the week index of the provided date. If the
     /// current week index is 0 than the last array value is returned.
-    fn prev(&self, date: &NaiveDateTime) -> f64 {
-        let current_week_index = self.current_index(date);
+    fn prev(&self, date: &NaiveDateTime, leap_day_policy: Option<LeapDayPolicy>) -> f64 {
+        let current_week_index = self.current_index(date, leap_day_policy);
 
         match self {
             Self::FiftyTwo(values) => {
@@ -119,8 +131,14 @@ impl WeeklyProfileValues {
 
     /// Find the value corresponding to the given date by linearly interpolating between two
     /// consecutive week's values.
-    fn interpolate(&self, date: &NaiveDateTime, first_value: f64, last_value: f64) -> f64 {
-        let current_pos = self.current_pos(date, true);
+    fn interpolate(
+        &self,
+        date: &NaiveDateTime,
+        first_value: f64,
+        last_value: f64,
+        leap_day_policy: Option<LeapDayPolicy>,
+    ) -> f64 {
+        let current_pos = self.current_pos(date, true, leap_day_policy);
         let week_delta = current_pos - current_pos.floor();
         first_value + (last_value - first_value) * week_delta
     }
@@ -129,19 +147,24 @@ impl WeeklyProfileValues {
     /// interpolated profile, the upper boundary in the 52nd and 53rd week is the same when
     /// WeeklyInterpDay is First (i.e. the value on 1st January). When WeeklyInterpDay is Last the
     /// 1st and last week will share the same lower bound (i.e. the value on the last week).
-    fn value(&self, date: &NaiveDateTime, interp_day: &Option<WeeklyInterpDay>) -> f64 {
+    fn value(
+        &self,
+        date: &NaiveDateTime,
+        interp_day: &Option<WeeklyInterpDay>,
+        leap_day_policy: Option<LeapDayPolicy>,
+    ) -> f64 {
         match interp_day {
-            None => self.current(date),
+            None => self.current(date, leap_day_policy),
             Some(interp_day) => match interp_day {
                 WeeklyInterpDay::First => {
-                    let first_value = self.current(date);
-                    let last_value = self.next(date);
-                    self.interpolate(date, first_value, last_value)
+                    let first_value = self.current(date, leap_day_policy);
+                    let last_value = self.next(date, leap_day_policy);
+                    self.interpolate(date, first_value, last_value, leap_day_policy)
                 }
                 WeeklyInterpDay::Last => {
-                    let first_value = self.prev(date);
-                    let last_value = self.current(date);
-                    self.interpolate(date, first_value, last_value)
+                    let first_value = self.prev(date, leap_day_policy);
+                    let last_value = self.current(date, leap_day_policy);
+                    self.interpolate(date, first_value, last_value, leap_day_policy)
                 }
             },
         }
@@ -167,10 +190,14 @@ impl TryFrom<&[f64]> for WeeklyProfileValues {
 }
 
 /// Weekly profile parameter. This supports a profile with either 52 or 53 weeks, with or without interpolation.
+///
+/// By default the weeks follow the calendar, so the 29th February shifts the rest of a leap year
+/// by one day. An optional [`LeapDayPolicy`] places every date in a year of a fixed length instead.
 pub struct WeeklyProfileParameter {
     meta: ParameterMeta,
     values: WeeklyProfileValues,
     interp_day: Option<WeeklyInterpDay>,
+    leap_day_policy: Option<LeapDayPolicy>,
 }
 
 impl WeeklyProfileParameter {
@@ -179,8 +206,15 @@ impl WeeklyProfileParameter {
             meta: ParameterMeta::new(name),
             values,
             interp_day,
+            leap_day_policy: None,
         }
     }
+
+    /// Set how the 29th February is placed in the year (see [`LeapDayPolicy::day_of_year`]).
+    pub fn with_leap_day_policy(mut self, leap_day_policy: LeapDayPolicy) -> Self {
+        self.leap_day_policy = Some(leap_day_policy);
+        self
+    }
 }
 
 impl Parameter for WeeklyProfileParameter {
@@ -197,7 +231,9 @@ impl SimpleParameter<f64> for WeeklyProfileParameter {
         _values: &SimpleParameterValues,
         _internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
-        Ok(self.values.value(&timestep.date, &self.interp_day))
+        Ok(self
+            .values
+            .value(&timestep.date, &self.interp_day, self.leap_day_policy))
     }
 
     fn as_parameter(&self) -> &dyn Parameter
@@ -211,6 +247,7 @@ impl SimpleParameter<f64> for WeeklyProfileParameter {
 #[cfg(test)]
 mod tests {
     use crate::parameters::profiles::weekly::{WeeklyInterpDay, WeeklyProfileValues};
+    use crate::parameters::profiles::LeapDayPolicy;
     use crate::test_utils::assert_approx_array_eq;
     use chrono::{Datelike, NaiveDate, TimeDelta};
     use float_cmp::{assert_approx_eq, F64Margin};
@@ -233,7 +270,7 @@ mod tests {
                 .unwrap()
                 .and_hms_opt(0, 0, 0)
                 .unwrap();
-            let value = week_size.value(&date, &interp_day);
+            let value = week_size.value(&date, &interp_day, None);
             data.push(value);
 
             dt += TimeDelta::days(1);
@@ -485,7 +522,7 @@ mod tests {
             .unwrap()
             .and_hms_opt(0, 0, 0)
             .unwrap();
-        assert_eq!(week_size.interpolate(&t0, 0.0, 1.0), 0.0);
+        assert_eq!(week_size.interpolate(&t0, 0.0, 1.0, None), 0.0);
 
         let t0 = NaiveDate::from_ymd_opt(2016, 1, 7)
             .unwrap()
@@ -495,6 +532,27 @@ mod tests {
             epsilon: 2.0,
             ulps: (f64::EPSILON * 2.0) as i64,
         };
-        assert_approx_eq!(f64, week_size.interpolate(&t0, 0.0, 1.0), 1.928571429, margins);
+        assert_approx_eq!(f64, week_size.interpolate(&t0, 0.0, 1.0, None), 1.928571429, margins);
+    }
+
+    /// Test that a leap day policy gives a leap year the weeks of a year of fixed length
+    #[test]
+    fn test_leap_day_policy() {
+        let profile: Vec<f64> = (1..=52).map(|v| v as f64).collect();
+        let week_size = WeeklyProfileValues::try_from(profile.as_slice()).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        // The 4th March is in the 9th week of 2021 and in the 10th week of 2020 by the calendar
+        assert_eq!(week_size.value(&date(2021, 3, 4), &None, None), 9.0);
+        assert_eq!(week_size.value(&date(2020, 3, 4), &None, None), 10.0);
+
+        let policy = Some(LeapDayPolicy::RepeatFeb28);
+        assert_eq!(week_size.value(&date(2020, 3, 4), &None, policy), 9.0);
+        assert_eq!(week_size.value(&date(2020, 2, 29), &None, policy), 9.0);
+        assert_eq!(week_size.value(&date(2020, 12, 30), &None, policy), 52.0);
+
+        // A 366 day year in every year
+        let policy = Some(LeapDayPolicy::Profile366);
+        assert_eq!(week_size.value(&date(2021, 3, 4), &None, policy), 10.0);
     }
 }
@@ -21,6 +21,22 @@ struct Internal {
     user_obj: PyObject,
 }
 
+/// The user object is copied with `copy.deepcopy` so that a restored state does not share any
+/// mutable state with the original. If the object can not be deep copied the copy refers to the
+/// same object.
+impl Clone for Internal {
+    fn clone(&self) -> Self {
+        let user_obj = Python::with_gil(|py| {
+            py.import_bound("copy")
+                .and_then(|copy| copy.call_method1("deepcopy", (self.user_obj.bind(py),)))
+                .map(|obj| obj.unbind())
+                .unwrap_or_else(|_| self.user_obj.clone_ref(py))
+        });
+
+        Self { user_obj }
+    }
+}
+
 impl Internal {
     fn into_boxed_any(self) -> Box<dyn ParameterState> {
         Box::new(self)
@@ -102,12 +118,12 @@ impl PyParameter {
     where
         T: for<'a> FromPyObject<'a>,
     {
-        let internal = downcast_internal_state_mut::<Internal>(internal_state);
+        let internal = downcast_internal_state_mut::<Internal>(&self.meta.name, internal_state)?;
 
         let value: T = Python::with_gil(|py| {
             let date = timestep.date.into_py(py);
 
-            let si = scenario_index.index.into_py(py);
+            let si = scenario_index.global_index.into_py(py);
 
             let metric_dict = self.get_metrics_dict(network, state, py)?;
             let index_dict = self.get_indices_dict(network, state, py)?;
@@ -132,14 +148,14 @@ impl PyParameter {
         state: &State,
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<(), PywrError> {
-        let internal = downcast_internal_state_mut::<Internal>(internal_state);
+        let internal = downcast_internal_state_mut::<Internal>(&self.meta.name, internal_state)?;
 
         Python::with_gil(|py| {
             // Only do this if the object has an "after" method defined.
             if internal.user_obj.getattr(py, "after").is_ok() {
                 let date = timestep.date.into_py(py);
 
-                let si = scenario_index.index.into_py(py);
+                let si = scenario_index.global_index.into_py(py);
 
                 let metric_dict = self.get_metrics_dict(network, state, py)?;
                 let index_dict = self.get_indices_dict(network, state, py)?;
@@ -202,6 +218,10 @@ impl GeneralParameter<f64> for PyParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        self.metrics.values().collect()
+    }
 }
 
 impl GeneralParameter<u64> for PyParameter {
@@ -244,12 +264,12 @@ impl GeneralParameter<MultiValue> for PyParameter {
         state: &State,
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<MultiValue, PywrError> {
-        let internal = downcast_internal_state_mut::<Internal>(internal_state);
+        let internal = downcast_internal_state_mut::<Internal>(&self.meta.name, internal_state)?;
 
         let value: MultiValue = Python::with_gil(|py| {
             let date = timestep.date.into_py(py);
 
-            let si = scenario_index.index.into_py(py);
+            let si = scenario_index.global_index.into_py(py);
 
             let metric_dict = self.get_metrics_dict(network, state, py)?;
             let index_dict = self.get_indices_dict(network, state, py)?;
@@ -364,16 +384,7 @@ class MyParameter:
         let time: TimeDomain = TimeDomain::try_from(timestepper).unwrap();
         let timesteps = time.timesteps();
 
-        let scenario_indices = [
-            ScenarioIndex {
-                index: 0,
-                indices: vec![0],
-            },
-            ScenarioIndex {
-                index: 1,
-                indices: vec![1],
-            },
-        ];
+        let scenario_indices = [ScenarioIndex::new(0, vec![0]), ScenarioIndex::new(1, vec![1])];
 
         let state = StateBuilder::new(vec![], 0).build();
 
@@ -440,16 +451,7 @@ class MyParameter:
         let time: TimeDomain = TimeDomain::try_from(timestepper).unwrap();
         let timesteps = time.timesteps();
 
-        let scenario_indices = [
-            ScenarioIndex {
-                index: 0,
-                indices: vec![0],
-            },
-            ScenarioIndex {
-                index: 1,
-                indices: vec![1],
-            },
-        ];
+        let scenario_indices = [ScenarioIndex::new(0, vec![0]), ScenarioIndex::new(1, vec![1])];
 
         let state = StateBuilder::new(vec![], 0).build();
 
@@ -0,0 +1,178 @@
+use crate::network::Network;
+use crate::parameters::{
+    downcast_internal_state_mut, GeneralParameter, Parameter, ParameterMeta, ParameterName, ParameterState,
+};
+use crate::scenario::ScenarioIndex;
+use crate::state::State;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::StandardNormal;
+
+/// The distribution that a [`RandomParameter`] samples from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RandomDistribution {
+    /// A uniform distribution between `lower` (inclusive) and `upper` (exclusive).
+    Uniform { lower: f64, upper: f64 },
+    /// A normal distribution.
+    Normal { mean: f64, std_dev: f64 },
+}
+
+impl RandomDistribution {
+    fn validate(&self) -> Result<(), PywrError> {
+        match self {
+            Self::Uniform { lower, upper } => {
+                if !(lower.is_finite() && upper.is_finite() && lower < upper) {
+                    return Err(PywrError::InvalidRandomDistribution(format!(
+                        "the lower bound ({lower}) must be less than the upper bound ({upper})"
+                    )));
+                }
+            }
+            Self::Normal { mean, std_dev } => {
+                if !(mean.is_finite() && std_dev.is_finite() && *std_dev >= 0.0) {
+                    return Err(PywrError::InvalidRandomDistribution(format!(
+                        "the standard deviation ({std_dev}) must be non-negative"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sample(&self, rng: &mut ChaCha8Rng) -> f64 {
+        match self {
+            Self::Uniform { lower, upper } => rng.gen_range(*lower..*upper),
+            Self::Normal { mean, std_dev } => mean + std_dev * rng.sample::<f64, _>(StandardNormal),
+        }
+    }
+}
+
+/// A parameter that samples a new value from a random distribution each time-step.
+///
+/// The random numbers are determined by the `seed`. If the model is repeated (see
+/// [`crate::scenario::ScenarioGroupCollection::add_repeats`]) the index of the repeat selects a
+/// different stream of random numbers, so each repeat is an independent sample. The other
+/// scenarios of the same repeat share the same random numbers, which allows them to be compared
+/// without the noise of different samples.
+pub struct RandomParameter {
+    meta: ParameterMeta,
+    distribution: RandomDistribution,
+    seed: u64,
+    repeat_group: Option<usize>,
+}
+
+impl RandomParameter {
+    pub fn new(name: ParameterName, distribution: RandomDistribution, seed: u64) -> Result<Self, PywrError> {
+        distribution.validate()?;
+
+        Ok(Self {
+            meta: ParameterMeta::new(name),
+            distribution,
+            seed,
+            repeat_group: None,
+        })
+    }
+
+    /// Use a different stream of random numbers for each member of the scenario group with the
+    /// given index.
+    pub fn with_repeat_group(mut self, repeat_group: usize) -> Self {
+        self.repeat_group = Some(repeat_group);
+        self
+    }
+
+    /// The random number generator of a scenario.
+    fn rng(&self, scenario_index: &ScenarioIndex) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        if let Some(repeat) = self.repeat_group.and_then(|g| scenario_index.indices.get(g)) {
+            rng.set_stream(*repeat as u64);
+        }
+        rng
+    }
+}
+
+impl Parameter for RandomParameter {
+    fn meta(&self) -> &ParameterMeta {
+        &self.meta
+    }
+
+    fn setup(
+        &self,
+        _timesteps: &[Timestep],
+        scenario_index: &ScenarioIndex,
+    ) -> Result<Option<Box<dyn ParameterState>>, PywrError> {
+        Ok(Some(Box::new(self.rng(scenario_index))))
+    }
+}
+
+impl GeneralParameter<f64> for RandomParameter {
+    fn compute(
+        &self,
+        _timestep: &Timestep,
+        _scenario_index: &ScenarioIndex,
+        _model: &Network,
+        _state: &State,
+        internal_state: &mut Option<Box<dyn ParameterState>>,
+    ) -> Result<f64, PywrError> {
+        let rng = downcast_internal_state_mut::<ChaCha8Rng>(&self.meta.name, internal_state)?;
+        Ok(self.distribution.sample(rng))
+    }
+
+    fn as_parameter(&self) -> &dyn Parameter
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RandomDistribution, RandomParameter};
+    use crate::scenario::ScenarioIndex;
+
+    fn samples(parameter: &RandomParameter, scenario_index: &ScenarioIndex) -> Vec<f64> {
+        let mut rng = parameter.rng(scenario_index);
+        (0..100).map(|_| parameter.distribution.sample(&mut rng)).collect()
+    }
+
+    #[test]
+    fn test_repeats() {
+        let distribution = RandomDistribution::Uniform { lower: 2.0, upper: 3.0 };
+        // The repeat group is the first group
+        let parameter = RandomParameter::new("random".into(), distribution, 42)
+            .unwrap()
+            .with_repeat_group(0);
+
+        let first = samples(&parameter, &ScenarioIndex::new(0, vec![0, 0]));
+        assert!(first.iter().all(|v| (2.0..3.0).contains(v)));
+
+        // Scenarios of the same repeat share their random numbers ...
+        assert_eq!(first, samples(&parameter, &ScenarioIndex::new(1, vec![0, 1])));
+        // ... but each repeat is different
+        assert_ne!(first, samples(&parameter, &ScenarioIndex::new(2, vec![1, 0])));
+        // ... as is another seed
+        let other = RandomParameter::new("random".into(), distribution, 43).unwrap();
+        assert_ne!(first, samples(&other, &ScenarioIndex::new(0, vec![0, 0])));
+    }
+
+    #[test]
+    fn test_normal() {
+        let distribution = RandomDistribution::Normal {
+            mean: 10.0,
+            std_dev: 0.0,
+        };
+        let parameter = RandomParameter::new("random".into(), distribution, 0).unwrap();
+        assert!(samples(&parameter, &ScenarioIndex::new(0, vec![0]))
+            .iter()
+            .all(|v| *v == 10.0));
+
+        let invalid = RandomDistribution::Normal {
+            mean: 10.0,
+            std_dev: -1.0,
+        };
+        assert!(RandomParameter::new("random".into(), invalid, 0).is_err());
+        let invalid = RandomDistribution::Uniform { lower: 1.0, upper: 1.0 };
+        assert!(RandomParameter::new("random".into(), invalid, 0).is_err());
+    }
+}
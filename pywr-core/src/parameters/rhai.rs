@@ -88,7 +88,7 @@ impl GeneralParameter<f64> for RhaiParameter {
         state: &State,
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<f64, PywrError> {
-        let internal = downcast_internal_state_mut::<Internal>(internal_state);
+        let internal = downcast_internal_state_mut::<Internal>(&self.meta.name, internal_state)?;
 
         let metric_values = self
             .metrics
@@ -102,7 +102,12 @@ impl GeneralParameter<f64> for RhaiParameter {
             .map(|(k, value)| Ok((k.into(), (value.get_value(network, state)? as i64).into())))
             .collect::<Result<rhai::Map, PywrError>>()?;
 
-        let args = (*timestep, scenario_index.index as i64, metric_values, index_values);
+        let args = (
+            *timestep,
+            scenario_index.global_index as i64,
+            metric_values,
+            index_values,
+        );
 
         let options = rhai::CallFnOptions::new().bind_this_ptr(&mut internal.state);
         let mut scope = Scope::new();
@@ -120,6 +125,10 @@ impl GeneralParameter<f64> for RhaiParameter {
     {
         self
     }
+
+    fn dependencies(&self) -> Vec<&MetricF64> {
+        self.metrics.values().collect()
+    }
 }
 
 #[cfg(test)]
@@ -160,16 +169,7 @@ mod tests {
         let time: TimeDomain = TimeDomain::try_from(timestepper).unwrap();
         let timesteps = time.timesteps();
 
-        let scenario_indices = [
-            ScenarioIndex {
-                index: 0,
-                indices: vec![0],
-            },
-            ScenarioIndex {
-                index: 1,
-                indices: vec![1],
-            },
-        ];
+        let scenario_indices = [ScenarioIndex::new(0, vec![0]), ScenarioIndex::new(1, vec![1])];
 
         let state = StateBuilder::new(vec![], 0).build();
 
@@ -9,6 +9,7 @@ use crate::timestep::Timestep;
 use crate::PywrError;
 use std::str::FromStr;
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Predicate {
     LessThan,
     GreaterThan,
@@ -32,6 +33,19 @@ impl FromStr for Predicate {
     }
 }
 
+impl Predicate {
+    /// Evaluate the predicate comparing `value` against `threshold`.
+    pub fn apply(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Predicate::LessThan => value < threshold,
+            Predicate::GreaterThan => value > threshold,
+            Predicate::EqualTo => (value - threshold).abs() < 1E-6, // TODO make this a global constant
+            Predicate::LessThanOrEqualTo => value <= threshold,
+            Predicate::GreaterThanOrEqualTo => value >= threshold,
+        }
+    }
+}
+
 pub struct ThresholdParameter {
     meta: ParameterMeta,
     metric: MetricF64,
@@ -84,7 +98,7 @@ impl GeneralParameter<u64> for ThresholdParameter {
         internal_state: &mut Option<Box<dyn ParameterState>>,
     ) -> Result<u64, PywrError> {
         // Downcast the internal state to the correct type
-        let previously_activated = downcast_internal_state_mut::<bool>(internal_state);
+        let previously_activated = downcast_internal_state_mut::<bool>(&self.meta.name, internal_state)?;
 
         // Return early if ratchet has been hit
         if self.ratchet & *previously_activated {
@@ -94,15 +108,7 @@ impl GeneralParameter<u64> for ThresholdParameter {
         let threshold = self.threshold.get_value(model, state)?;
         let value = self.metric.get_value(model, state)?;
 
-        let active = match self.predicate {
-            Predicate::LessThan => value < threshold,
-            Predicate::GreaterThan => value > threshold,
-            Predicate::EqualTo => (value - threshold).abs() < 1E-6, // TODO make this a global constant
-            Predicate::LessThanOrEqualTo => value <= threshold,
-            Predicate::GreaterThanOrEqualTo => value >= threshold,
-        };
-
-        if active {
+        if self.predicate.apply(value, threshold) {
             // Update the internal state to remember we've been triggered!
             *previously_activated = true;
             Ok(1)
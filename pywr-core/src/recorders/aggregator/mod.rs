@@ -1,12 +1,22 @@
 use crate::timestep::PywrDuration;
-use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use crate::PywrError;
+use chrono::{Datelike, Duration, Month, NaiveDate, NaiveDateTime, NaiveTime};
 use std::num::NonZeroUsize;
 
 #[derive(Clone, Debug)]
 pub enum AggregationFrequency {
     Monthly,
     Annual,
+    /// Annual periods that start on the first day of the given month (e.g. water years that
+    /// start in October).
+    WaterYear {
+        start_month: Month,
+    },
     Days(NonZeroUsize),
+    /// Periods bounded by the given dates, which must be in ascending order. Each date is the
+    /// start of a new period; any dates before the first boundary are in a single period, and
+    /// any dates after the last boundary are in a single period.
+    Periods(Vec<NaiveDateTime>),
 }
 
 impl AggregationFrequency {
@@ -14,10 +24,14 @@ impl AggregationFrequency {
         match self {
             Self::Monthly => (period_start.year() == date.year()) && (period_start.month() == date.month()),
             Self::Annual => period_start.year() == date.year(),
+            Self::WaterYear { start_month } => water_year(period_start, *start_month) == water_year(date, *start_month),
             Self::Days(days) => {
                 let period_end = *period_start + Duration::days(days.get() as i64);
                 (period_start <= date) && (date < &period_end)
             }
+            Self::Periods(boundaries) => {
+                boundaries.partition_point(|b| b <= period_start) == boundaries.partition_point(|b| b <= date)
+            }
         }
     }
 
@@ -45,10 +59,34 @@ impl AggregationFrequency {
                 let date = NaiveDate::from_ymd_opt(current_date.year() + 1, 1, 1).unwrap();
                 NaiveDateTime::new(date, NaiveTime::default())
             }
+            Self::WaterYear { start_month } => {
+                // 1st of the start month in the next water year
+                // SAFETY: This should be safe to unwrap as it will always create a valid date unless
+                // we are at the limit of dates that are representable.
+                let year = water_year(current_date, *start_month) + 1;
+                let date = NaiveDate::from_ymd_opt(year, start_month.number_from_month(), 1).unwrap();
+                NaiveDateTime::new(date, NaiveTime::default())
+            }
             Self::Days(days) => *current_date + Duration::days(days.get() as i64),
+            Self::Periods(boundaries) => {
+                // The first boundary after the current date; the last period never ends.
+                let idx = boundaries.partition_point(|b| b <= current_date);
+                boundaries.get(idx).copied().unwrap_or(NaiveDateTime::MAX)
+            }
         }
     }
 
+    /// The number of periods that overlap the time from `start` (inclusive) to `end` (exclusive).
+    fn num_periods(&self, start: &NaiveDateTime, end: &NaiveDateTime) -> usize {
+        let mut count = 0;
+        let mut current_date = *start;
+        while current_date < *end {
+            count += 1;
+            current_date = self.start_of_next_period(&current_date);
+        }
+        count
+    }
+
     /// Split the value representing a period into multiple ['PeriodValue'] that do not cross the
     /// boundary of the given period.
     fn split_value_into_periods(&self, value: PeriodValue<f64>) -> Vec<PeriodValue<f64>> {
@@ -79,6 +117,15 @@ impl AggregationFrequency {
     }
 }
 
+/// The calendar year in which the water year containing `date` starts.
+fn water_year(date: &NaiveDateTime, start_month: Month) -> i32 {
+    if date.month() >= start_month.number_from_month() {
+        date.year()
+    } else {
+        date.year() - 1
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum AggregationFunction {
     Sum,
@@ -86,7 +133,35 @@ pub enum AggregationFunction {
     Min,
     Max,
     CountNonZero,
-    CountFunc { func: fn(f64) -> bool },
+    CountFunc {
+        func: fn(f64) -> bool,
+    },
+    /// The quantile (between 0 and 1) of the values, linearly interpolating between the two
+    /// nearest values. The durations of any periods are ignored.
+    Quantile(f64),
+    /// The proportion of the values that are greater than the threshold. This is weighted by the
+    /// duration of any periods.
+    ExceedanceFrequency {
+        threshold: f64,
+    },
+}
+
+/// The `q` quantile of `values`, linearly interpolating between the two nearest values.
+///
+/// Returns `None` if there are no values or `q` is not between 0 and 1.
+fn quantile(values: &[f64], q: f64) -> Option<f64> {
+    if values.is_empty() || !(0.0..=1.0).contains(&q) {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64))
 }
 
 impl AggregationFunction {
@@ -120,6 +195,23 @@ impl AggregationFunction {
                 let count = values.iter().filter(|v| func(v.value)).count();
                 Some(count as f64)
             }
+            AggregationFunction::Quantile(q) => {
+                let values: Vec<f64> = values.iter().map(|v| v.value).collect();
+                quantile(&values, *q)
+            }
+            AggregationFunction::ExceedanceFrequency { threshold } => {
+                let ndays: f64 = values.iter().map(|v| v.duration.fractional_days()).sum();
+                if ndays == 0.0 {
+                    None
+                } else {
+                    let exceeded: f64 = values
+                        .iter()
+                        .filter(|v| v.value > *threshold)
+                        .map(|v| v.duration.fractional_days())
+                        .sum();
+                    Some(exceeded / ndays)
+                }
+            }
         }
     }
 
@@ -157,6 +249,15 @@ impl AggregationFunction {
                 let count = values.iter().filter(|v| func(**v)).count();
                 Some(count as f64)
             }
+            AggregationFunction::Quantile(q) => quantile(values, *q),
+            AggregationFunction::ExceedanceFrequency { threshold } => {
+                if values.is_empty() {
+                    None
+                } else {
+                    let count = values.iter().filter(|v| **v > *threshold).count();
+                    Some(count as f64 / values.len() as f64)
+                }
+            }
         }
     }
 }
@@ -310,12 +411,12 @@ impl PeriodicAggregator {
     ///
     /// The new value should sequentially follow from the previously processed values. If the
     /// value completes a new aggregation period then a value representing that aggregation is
-    /// returned.
+    /// returned. An error is returned if the value completes more than one aggregation period.
     fn process_value(
         &self,
         current_state: &mut PeriodicAggregatorState,
         value: PeriodValue<f64>,
-    ) -> Option<PeriodValue<f64>> {
+    ) -> Result<Option<PeriodValue<f64>>, PywrError> {
         // Split the given period into separate periods that align with the aggregation period.
         let mut agg_value = None;
 
@@ -324,7 +425,7 @@ impl PeriodicAggregator {
                 let av = current_state.process_value(v, period, &self.function);
                 if av.is_some() {
                     if agg_value.is_some() {
-                        panic!("Multiple aggregated values yielded from aggregator. This indicates that the given value spans multiple aggregation periods which is not supported.")
+                        return Err(PywrError::ValueSpansMultipleAggregationPeriods);
                     }
                     agg_value = av;
                 }
@@ -332,7 +433,7 @@ impl PeriodicAggregator {
         } else {
             current_state.process_value_no_period(value);
         }
-        agg_value
+        Ok(agg_value)
     }
 
     fn calc_aggregation(&self, state: &PeriodicAggregatorState) -> Option<PeriodValue<f64>> {
@@ -371,18 +472,20 @@ impl Aggregator {
     }
 
     /// Append a new value to the aggregator.
-    pub fn append_value(&self, state: &mut AggregatorState, value: PeriodValue<f64>) -> Option<PeriodValue<f64>> {
+    pub fn append_value(
+        &self,
+        state: &mut AggregatorState,
+        value: PeriodValue<f64>,
+    ) -> Result<Option<PeriodValue<f64>>, PywrError> {
         let agg_value = match (&self.child, state.child.as_mut()) {
-            (Some(child), Some(child_state)) => child.append_value(child_state, value),
+            (Some(child), Some(child_state)) => child.append_value(child_state, value)?,
             (None, None) => Some(value),
-            (None, Some(_)) => panic!("Aggregator state contains a child state when none is expected."),
-            (Some(_), None) => panic!("Aggregator state does not contain a child state when one is expected."),
+            _ => return Err(PywrError::AggregatorStateMismatch),
         };
 
-        if let Some(agg_value) = agg_value {
-            self.agg.process_value(&mut state.state, agg_value)
-        } else {
-            None
+        match agg_value {
+            Some(agg_value) => self.agg.process_value(&mut state.state, agg_value),
+            None => Ok(None),
         }
     }
 
@@ -390,21 +493,30 @@ impl Aggregator {
     ///
     /// This will also compute the final aggregation value from the child aggregators if any exists.
     /// This includes aggregation calculations over partial or unfinished periods.
-    pub fn finalise(&self, state: &mut AggregatorState) -> Option<PeriodValue<f64>> {
+    pub fn finalise(&self, state: &mut AggregatorState) -> Result<Option<PeriodValue<f64>>, PywrError> {
         let final_child_value = match (&self.child, state.child.as_mut()) {
-            (Some(child), Some(child_state)) => child.finalise(child_state),
+            (Some(child), Some(child_state)) => child.finalise(child_state)?,
             (None, None) => None,
-            (None, Some(_)) => panic!("Aggregator state contains a child state when none is expected."),
-            (Some(_), None) => panic!("Aggregator state does not contain a child state when one is expected."),
+            _ => return Err(PywrError::AggregatorStateMismatch),
         };
 
         // If there is a final value from the child aggregator then process it
         if let Some(final_child_value) = final_child_value {
-            let _ = self.agg.process_value(&mut state.state, final_child_value);
+            let _ = self.agg.process_value(&mut state.state, final_child_value)?;
         }
 
         // Finally, compute the aggregation of the current state
-        self.agg.calc_aggregation(&state.state)
+        Ok(self.agg.calc_aggregation(&state.state))
+    }
+
+    /// The number of values yielded by the aggregator over a run from `start` to `end`.
+    ///
+    /// An aggregator without a frequency yields a single value at the end of the run.
+    pub fn num_periods(&self, start: &NaiveDateTime, end: &NaiveDateTime) -> usize {
+        match &self.agg.frequency {
+            Some(frequency) => frequency.num_periods(start, end),
+            None => 1,
+        }
     }
 
     /// Create the initial default state for the aggregator.
@@ -419,7 +531,8 @@ impl Aggregator {
 mod tests {
     use super::{AggregationFrequency, AggregationFunction, Aggregator, PeriodicAggregator, PeriodicAggregatorState};
     use crate::recorders::aggregator::PeriodValue;
-    use chrono::{Datelike, NaiveDate, TimeDelta};
+    use crate::PywrError;
+    use chrono::{Datelike, Month, NaiveDate, TimeDelta};
     use float_cmp::assert_approx_eq;
 
     #[test]
@@ -435,28 +548,36 @@ mod tests {
             .unwrap()
             .and_hms_opt(0, 0, 0)
             .unwrap();
-        let agg_value = agg.process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0));
+        let agg_value = agg
+            .process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0))
+            .unwrap();
         assert!(agg_value.is_none());
 
         let start = NaiveDate::from_ymd_opt(2023, 1, 31)
             .unwrap()
             .and_hms_opt(0, 0, 0)
             .unwrap();
-        let agg_value = agg.process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0));
+        let agg_value = agg
+            .process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0))
+            .unwrap();
         assert!(agg_value.is_none());
 
         let start = NaiveDate::from_ymd_opt(2023, 2, 1)
             .unwrap()
             .and_hms_opt(0, 0, 0)
             .unwrap();
-        let agg_value = agg.process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0));
+        let agg_value = agg
+            .process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0))
+            .unwrap();
         assert!(agg_value.is_some());
 
         let start = NaiveDate::from_ymd_opt(2023, 2, 2)
             .unwrap()
             .and_hms_opt(0, 0, 0)
             .unwrap();
-        let agg_value = agg.process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0));
+        let agg_value = agg
+            .process_value(&mut state, PeriodValue::new(start, TimeDelta::days(1).into(), 1.0))
+            .unwrap();
         assert!(agg_value.is_none());
     }
 
@@ -489,11 +610,11 @@ mod tests {
             .unwrap();
         for _i in 0..365 * 3 {
             let value = PeriodValue::new(date, TimeDelta::days(1).into(), date.year() as f64);
-            let _agg_value = max_annual_min.append_value(&mut state, value);
+            let _agg_value = max_annual_min.append_value(&mut state, value).unwrap();
             date += TimeDelta::days(1);
         }
 
-        let final_value = max_annual_min.finalise(&mut state);
+        let final_value = max_annual_min.finalise(&mut state).unwrap();
 
         if let Some(final_value) = final_value {
             assert_approx_eq!(f64, final_value.value, 2025.0);
@@ -502,6 +623,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_value_spanning_multiple_periods() {
+        let agg = PeriodicAggregator {
+            frequency: Some(AggregationFrequency::Monthly),
+            function: AggregationFunction::Sum,
+        };
+
+        let mut state = PeriodicAggregatorState::default();
+
+        // A value from the end of January to the start of March completes two monthly periods
+        let start = NaiveDate::from_ymd_opt(2023, 1, 31)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let result = agg.process_value(&mut state, PeriodValue::new(start, TimeDelta::days(31).into(), 1.0));
+        assert!(matches!(result, Err(PywrError::ValueSpansMultipleAggregationPeriods)));
+    }
+
+    #[test]
+    fn test_water_year_aggregator() {
+        let agg = Aggregator::new(
+            Some(AggregationFrequency::WaterYear {
+                start_month: Month::October,
+            }),
+            AggregationFunction::Sum,
+            None,
+        );
+        let mut state = agg.default_state();
+
+        // Daily values from 1st August 2022 for 2 years
+        let start = NaiveDate::from_ymd_opt(2022, 8, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let mut agg_values = Vec::new();
+        for i in 0..731 {
+            let date = start + TimeDelta::days(i);
+            let value = PeriodValue::new(date, TimeDelta::days(1).into(), 1.0);
+            agg_values.extend(agg.append_value(&mut state, value).unwrap());
+        }
+
+        // The partial water year of Aug-Sep 2022, and the full water year of 2022/23
+        assert_eq!(agg_values.len(), 2);
+        assert_eq!(agg_values[0].start, start);
+        assert_approx_eq!(f64, agg_values[0].value, 61.0);
+        assert_eq!(
+            agg_values[1].start,
+            NaiveDate::from_ymd_opt(2022, 10, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+        assert_approx_eq!(f64, agg_values[1].value, 365.0);
+
+        // The remaining values of the 2023/24 water year
+        let final_value = agg.finalise(&mut state).unwrap().unwrap();
+        assert_approx_eq!(f64, final_value.value, 305.0);
+    }
+
+    #[test]
+    fn test_custom_periods_aggregator() {
+        let date = |m: u32, d: u32| {
+            NaiveDate::from_ymd_opt(2023, m, d)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        };
+
+        let agg = Aggregator::new(
+            Some(AggregationFrequency::Periods(vec![date(1, 10), date(3, 1)])),
+            AggregationFunction::Sum,
+            None,
+        );
+        let mut state = agg.default_state();
+
+        // Weekly values that straddle the boundaries of the periods
+        let mut agg_values = Vec::new();
+        for i in 0..12 {
+            let value = PeriodValue::new(date(1, 1) + TimeDelta::weeks(i), TimeDelta::weeks(1).into(), 1.0);
+            agg_values.extend(agg.append_value(&mut state, value).unwrap());
+        }
+
+        assert_eq!(agg_values.len(), 2);
+        assert_eq!(agg_values[0].start, date(1, 1));
+        assert_approx_eq!(f64, agg_values[0].value, 9.0);
+        assert_eq!(agg_values[1].start, date(1, 10));
+        assert_approx_eq!(f64, agg_values[1].value, 50.0);
+
+        // The last period continues until the end of the values
+        let final_value = agg.finalise(&mut state).unwrap().unwrap();
+        assert_eq!(final_value.start, date(3, 1));
+        assert_approx_eq!(f64, final_value.value, 25.0);
+    }
+
     #[test]
     fn test_sub_daily_aggregation() {
         let values = vec![
@@ -538,4 +753,47 @@ mod tests {
         let expected = 2.0 * (1.0 / 24.0) + 1.0 * (2.0 / 24.0) + 3.0 * (1.0 / 24.0);
         assert_approx_eq!(f64, agg_value, expected);
     }
+
+    #[test]
+    fn test_num_periods() {
+        let start = NaiveDate::from_ymd_opt(2023, 1, 15)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        let monthly = Aggregator::new(Some(AggregationFrequency::Monthly), AggregationFunction::Sum, None);
+        assert_eq!(monthly.num_periods(&start, &end), 14);
+
+        let annual = Aggregator::new(Some(AggregationFrequency::Annual), AggregationFunction::Sum, None);
+        assert_eq!(annual.num_periods(&start, &end), 2);
+
+        let total = Aggregator::new(None, AggregationFunction::Sum, None);
+        assert_eq!(total.num_periods(&start, &end), 1);
+    }
+
+    #[test]
+    fn test_quantile_and_exceedance() {
+        let values = [4.0, 1.0, 3.0, 2.0];
+
+        let median = AggregationFunction::Quantile(0.5).calc_f64(&values).unwrap();
+        assert_approx_eq!(f64, median, 2.5);
+
+        let p90 = AggregationFunction::Quantile(0.9).calc_f64(&values).unwrap();
+        assert_approx_eq!(f64, p90, 3.7);
+
+        let min = AggregationFunction::Quantile(0.0).calc_f64(&values).unwrap();
+        assert_approx_eq!(f64, min, 1.0);
+
+        assert!(AggregationFunction::Quantile(1.5).calc_f64(&values).is_none());
+        assert!(AggregationFunction::Quantile(0.5).calc_f64(&[]).is_none());
+
+        let exceedance = AggregationFunction::ExceedanceFrequency { threshold: 2.0 }
+            .calc_f64(&values)
+            .unwrap();
+        assert_approx_eq!(f64, exceedance, 0.5);
+    }
 }
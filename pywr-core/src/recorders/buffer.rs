@@ -0,0 +1,333 @@
+use crate::recorders::aggregator::PeriodValue;
+use crate::timestep::PywrDuration;
+use chrono::{DateTime, NaiveDateTime, TimeDelta};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BufferError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to decompress block: {0}")]
+    Decompress(#[from] lz4_flex::block::DecompressError),
+    #[error("block of {0} periods is corrupt")]
+    CorruptBlock(usize),
+    #[error("scenario index {0} is out of range")]
+    ScenarioIndexOutOfRange(usize),
+    #[error("expected {expected} metric values but found {found}")]
+    MetricCountMismatch { expected: usize, found: usize },
+}
+
+/// Settings for a [`ResultBuffer`].
+#[derive(Debug, Clone)]
+pub struct BufferSettings {
+    block_len: usize,
+    memory_budget: Option<usize>,
+    spill_dir: Option<PathBuf>,
+}
+
+impl Default for BufferSettings {
+    fn default() -> Self {
+        Self {
+            block_len: 128,
+            memory_budget: None,
+            spill_dir: None,
+        }
+    }
+}
+
+impl BufferSettings {
+    /// The number of periods of each scenario that are compressed together in a block.
+    ///
+    /// The values of the current block of every scenario are held uncompressed, so larger
+    /// blocks compress better but require more memory.
+    pub fn with_block_len(mut self, block_len: usize) -> Self {
+        self.block_len = block_len.max(1);
+        self
+    }
+
+    /// The maximum number of bytes of compressed blocks to hold in memory. Any further blocks
+    /// are written to a temporary file.
+    pub fn with_memory_budget(mut self, memory_budget: usize) -> Self {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
+    /// The directory in which to create the temporary file for blocks that exceed the memory
+    /// budget. The system's temporary directory is used by default.
+    pub fn with_spill_dir(mut self, spill_dir: &Path) -> Self {
+        self.spill_dir = Some(spill_dir.to_path_buf());
+        self
+    }
+}
+
+/// Where the compressed data of a block is stored.
+enum BlockData {
+    Memory(Vec<u8>),
+    Disk { offset: u64, size: usize },
+}
+
+/// A compressed block of consecutive periods of a single scenario.
+struct Block {
+    num_periods: usize,
+    data: BlockData,
+}
+
+#[derive(Default)]
+struct ScenarioBuffer {
+    blocks: Vec<Block>,
+    /// The periods that have not yet been compressed in to a block.
+    open: Vec<PeriodValue<Vec<f64>>>,
+}
+
+/// A compressed, in-memory store of the metric values of each scenario over time.
+///
+/// The values of each scenario are collected in to blocks of [`BufferSettings::with_block_len`]
+/// periods. Each block is stored in a columnar layout (the start and duration of the periods,
+/// followed by the values of each metric) and compressed with LZ4. If a memory budget is set,
+/// blocks that would exceed it are written to a temporary file that is deleted when the buffer
+/// is dropped.
+pub struct ResultBuffer {
+    settings: BufferSettings,
+    num_metrics: Option<usize>,
+    scenarios: Vec<ScenarioBuffer>,
+    memory_used: usize,
+    spill_file: Option<File>,
+    spilled: u64,
+}
+
+impl ResultBuffer {
+    pub fn new(num_scenarios: usize, settings: BufferSettings) -> Self {
+        Self {
+            settings,
+            num_metrics: None,
+            scenarios: (0..num_scenarios).map(|_| ScenarioBuffer::default()).collect(),
+            memory_used: 0,
+            spill_file: None,
+            spilled: 0,
+        }
+    }
+
+    /// The number of scenarios in the buffer.
+    pub fn num_scenarios(&self) -> usize {
+        self.scenarios.len()
+    }
+
+    /// The number of bytes of compressed blocks held in memory.
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+
+    /// The number of bytes of compressed blocks written to disk.
+    pub fn spilled(&self) -> u64 {
+        self.spilled
+    }
+
+    /// Append the metric values of a period to the given scenario.
+    pub fn push(&mut self, scenario_index: usize, value: PeriodValue<Vec<f64>>) -> Result<(), BufferError> {
+        let expected = *self.num_metrics.get_or_insert(value.len());
+        if value.len() != expected {
+            return Err(BufferError::MetricCountMismatch {
+                expected,
+                found: value.len(),
+            });
+        }
+
+        let scenario = self
+            .scenarios
+            .get_mut(scenario_index)
+            .ok_or(BufferError::ScenarioIndexOutOfRange(scenario_index))?;
+
+        scenario.open.push(value);
+
+        if scenario.open.len() >= self.settings.block_len {
+            let values = std::mem::take(&mut scenario.open);
+            let block = self.store(&values)?;
+            self.scenarios[scenario_index].blocks.push(block);
+        }
+
+        Ok(())
+    }
+
+    /// Return all the values of the given scenario.
+    pub fn values(&self, scenario_index: usize) -> Result<Vec<PeriodValue<Vec<f64>>>, BufferError> {
+        let scenario = self
+            .scenarios
+            .get(scenario_index)
+            .ok_or(BufferError::ScenarioIndexOutOfRange(scenario_index))?;
+
+        let mut values = Vec::new();
+        for block in &scenario.blocks {
+            let bytes = match &block.data {
+                BlockData::Memory(compressed) => lz4_flex::decompress_size_prepended(compressed)?,
+                BlockData::Disk { offset, size } => {
+                    let mut file = self
+                        .spill_file
+                        .as_ref()
+                        .ok_or(BufferError::CorruptBlock(block.num_periods))?;
+                    let mut compressed = vec![0u8; *size];
+                    file.seek(SeekFrom::Start(*offset))?;
+                    file.read_exact(&mut compressed)?;
+                    lz4_flex::decompress_size_prepended(&compressed)?
+                }
+            };
+            values.extend(self.decode(block.num_periods, &bytes)?);
+        }
+        values.extend(scenario.open.iter().cloned());
+
+        Ok(values)
+    }
+
+    /// Compress the values in to a block, writing it to disk if it exceeds the memory budget.
+    fn store(&mut self, values: &[PeriodValue<Vec<f64>>]) -> Result<Block, BufferError> {
+        let bytes = lz4_flex::compress_prepend_size(&encode(values));
+
+        let exceeds_budget = self
+            .settings
+            .memory_budget
+            .is_some_and(|budget| self.memory_used + bytes.len() > budget);
+
+        let data = if exceeds_budget {
+            let mut file = match self.spill_file.take() {
+                Some(file) => file,
+                None => match &self.settings.spill_dir {
+                    Some(dir) => tempfile::tempfile_in(dir)?,
+                    None => tempfile::tempfile()?,
+                },
+            };
+            // Reading blocks moves the file's cursor, so always seek to the end of the data.
+            let offset = self.spilled;
+            let result = file.seek(SeekFrom::Start(offset)).and_then(|_| file.write_all(&bytes));
+            self.spill_file = Some(file);
+            result?;
+
+            self.spilled += bytes.len() as u64;
+            BlockData::Disk {
+                offset,
+                size: bytes.len(),
+            }
+        } else {
+            self.memory_used += bytes.len();
+            BlockData::Memory(bytes)
+        };
+
+        Ok(Block {
+            num_periods: values.len(),
+            data,
+        })
+    }
+
+    /// Decode the values of an uncompressed block.
+    fn decode(&self, num_periods: usize, bytes: &[u8]) -> Result<Vec<PeriodValue<Vec<f64>>>, BufferError> {
+        let num_metrics = self.num_metrics.unwrap_or_default();
+        if bytes.len() != num_periods * (num_metrics + 2) * 8 {
+            return Err(BufferError::CorruptBlock(num_periods));
+        }
+
+        let mut columns = bytes
+            .chunks_exact(8)
+            .map(|b| <[u8; 8]>::try_from(b).expect("Chunks are always 8 bytes."));
+
+        let starts: Vec<NaiveDateTime> = columns
+            .by_ref()
+            .take(num_periods)
+            .map(|b| DateTime::from_timestamp_millis(i64::from_le_bytes(b)).map(|d| d.naive_utc()))
+            .collect::<Option<_>>()
+            .ok_or(BufferError::CorruptBlock(num_periods))?;
+
+        let durations: Vec<PywrDuration> = columns
+            .by_ref()
+            .take(num_periods)
+            .map(|b| TimeDelta::milliseconds(i64::from_le_bytes(b)).into())
+            .collect();
+
+        let mut values: Vec<PeriodValue<Vec<f64>>> = starts
+            .into_iter()
+            .zip(durations)
+            .map(|(start, duration)| PeriodValue::new(start, duration, Vec::with_capacity(num_metrics)))
+            .collect();
+
+        for _ in 0..num_metrics {
+            for (value, b) in values.iter_mut().zip(columns.by_ref().take(num_periods)) {
+                value.value.push(f64::from_le_bytes(b));
+            }
+        }
+
+        Ok(values)
+    }
+}
+
+/// Encode the values in a columnar layout; the start and duration of every period are followed
+/// by the values of each metric in turn.
+fn encode(values: &[PeriodValue<Vec<f64>>]) -> Vec<u8> {
+    let num_metrics = values.first().map(|v| v.len()).unwrap_or_default();
+    let mut bytes = Vec::with_capacity(values.len() * (num_metrics + 2) * 8);
+
+    for value in values {
+        bytes.extend_from_slice(&value.start.and_utc().timestamp_millis().to_le_bytes());
+    }
+    for value in values {
+        bytes.extend_from_slice(&value.duration.milliseconds().to_le_bytes());
+    }
+    for metric_idx in 0..num_metrics {
+        for value in values {
+            bytes.extend_from_slice(&value.value[metric_idx].to_le_bytes());
+        }
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BufferSettings, ResultBuffer};
+    use crate::recorders::aggregator::PeriodValue;
+    use crate::test_utils::default_time_domain;
+
+    #[test]
+    fn test_round_trip() {
+        let time_domain = default_time_domain();
+        let settings = BufferSettings::default().with_block_len(4).with_memory_budget(300);
+        let mut buffer = ResultBuffer::new(2, settings);
+
+        let value = |s: usize, t: usize| vec![(s * 100 + t) as f64, -0.5 * t as f64];
+
+        for (t, timestep) in time_domain.timesteps().iter().enumerate() {
+            for s in 0..2 {
+                let v = PeriodValue::new(timestep.date, timestep.duration, value(s, t));
+                buffer.push(s, v).unwrap();
+            }
+        }
+
+        // Some blocks are held in memory and the remainder are written to disk.
+        assert!(buffer.memory_used() > 0 && buffer.memory_used() <= 300);
+        assert!(buffer.spilled() > 0);
+
+        for s in 0..2 {
+            let values = buffer.values(s).unwrap();
+            assert_eq!(values.len(), time_domain.timesteps().len());
+
+            for (t, (v, timestep)) in values.iter().zip(time_domain.timesteps()).enumerate() {
+                assert_eq!(v.start, timestep.date);
+                assert_eq!(v.duration, timestep.duration);
+                assert_eq!(v.value, value(s, t));
+            }
+        }
+    }
+
+    #[test]
+    fn test_metric_count_mismatch() {
+        let time_domain = default_time_domain();
+        let timestep = &time_domain.timesteps()[0];
+        let mut buffer = ResultBuffer::new(1, BufferSettings::default());
+
+        buffer
+            .push(0, PeriodValue::new(timestep.date, timestep.duration, vec![1.0, 2.0]))
+            .unwrap();
+        assert!(buffer
+            .push(0, PeriodValue::new(timestep.date, timestep.duration, vec![1.0]))
+            .is_err());
+    }
+}
@@ -1,27 +1,173 @@
-use super::{MetricSetState, PywrError, Recorder, RecorderMeta, Timestep};
+use super::{
+    downcast_internal_state, downcast_internal_state_mut, MetricSetState, PywrError, Recorder, RecorderMeta, Timestep,
+};
 use crate::models::ModelDomain;
 use crate::network::Network;
-use crate::recorders::metric_set::MetricSetIndex;
+use crate::recorders::aggregator::{Aggregator, AggregatorState, PeriodValue};
+use crate::recorders::description::{num_periods, MetricSetDescription, OutputDescription};
+use crate::recorders::metric_set::{MetricFilter, MetricSetIndex, ScenarioStatisticGroup, TimeFilter};
+use crate::recorders::{MetricSet, OutputMetric};
 use crate::scenario::ScenarioIndex;
 use crate::state::State;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs::File;
-use std::num::NonZeroU32;
+use std::io::Write;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// The capacity of the buffer of the CSV writers in bytes.
+const WRITER_CAPACITY: usize = 1 << 20;
+
+/// The default number of rows of the wide format that are buffered before they are written.
+const DEFAULT_BLOCK_ROWS: usize = 1024;
+
+/// Compression of the files written by the CSV recorders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CsvCompression {
+    #[default]
+    None,
+    /// Compress the file with zstd at the given level; zero uses the default level of zstd.
+    Zstd(i32),
+}
+
+/// The file written by a CSV recorder, with optional compression.
+enum CsvFile {
+    Plain(File),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl CsvFile {
+    fn create(filename: &Path, compression: CsvCompression) -> Result<Self, PywrError> {
+        let file = File::create(filename).map_err(|e| PywrError::CSVError(e.to_string()))?;
+
+        match compression {
+            CsvCompression::None => Ok(Self::Plain(file)),
+            CsvCompression::Zstd(level) => {
+                let encoder = zstd::Encoder::new(file, level).map_err(|e| PywrError::CSVError(e.to_string()))?;
+                Ok(Self::Zstd(encoder))
+            }
+        }
+    }
+
+    /// Flush the file, writing the end of the compressed stream if any.
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(mut file) => file.flush(),
+            Self::Zstd(encoder) => encoder.finish().map(|_| ()),
+        }
+    }
+}
+
+impl Write for CsvFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+fn create_writer(filename: &Path, compression: CsvCompression) -> Result<csv::Writer<CsvFile>, PywrError> {
+    let file = CsvFile::create(filename, compression)?;
+    Ok(csv::WriterBuilder::new()
+        .buffer_capacity(WRITER_CAPACITY)
+        .from_writer(file))
+}
+
+/// Write any buffered data and close the file of `writer`.
+fn finish_writer(writer: csv::Writer<CsvFile>) -> Result<(), PywrError> {
+    let file = writer
+        .into_inner()
+        .map_err(|e| PywrError::CSVError(e.error().to_string()))?;
+    file.finish().map_err(|e| PywrError::CSVError(e.to_string()))
+}
 
 /// Output the values from a [`MetricSet`] to a CSV file.
+///
+/// The rows are buffered as blocks of columns and written once a block is full (see
+/// [`CsvWideFmtOutput::with_block_rows`]), which avoids formatting and writing the file one
+/// row at a time. The file can optionally be compressed (see [`CsvWideFmtOutput::with_compression`]).
+///
+/// Only the rows of the periods that match the [`TimeFilter`], if any, are written.
 #[derive(Clone, Debug)]
 pub struct CsvWideFmtOutput {
     meta: RecorderMeta,
     filename: PathBuf,
     metric_set_idx: MetricSetIndex,
+    filter: MetricFilter,
+    time_filter: TimeFilter,
+    compression: CsvCompression,
+    block_rows: NonZeroUsize,
+}
+
+/// The rows of the wide format that have not yet been written, stored by column.
+struct ColumnBlock {
+    starts: Vec<NaiveDateTime>,
+    columns: Vec<Vec<f64>>,
+}
+
+impl ColumnBlock {
+    fn new(num_columns: usize, capacity: usize) -> Self {
+        Self {
+            starts: Vec::with_capacity(capacity),
+            columns: (0..num_columns).map(|_| Vec::with_capacity(capacity)).collect(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Write the rows of the block to `writer` and clear the block.
+    fn write(&mut self, writer: &mut csv::Writer<CsvFile>) -> Result<(), PywrError> {
+        let mut field = String::new();
+
+        for (row, start) in self.starts.iter().enumerate() {
+            field.clear();
+            write!(field, "{start}").unwrap();
+            writer
+                .write_field(&field)
+                .map_err(|e| PywrError::CSVError(e.to_string()))?;
+
+            for column in &self.columns {
+                field.clear();
+                write!(field, "{:.2}", column[row]).unwrap();
+                writer
+                    .write_field(&field)
+                    .map_err(|e| PywrError::CSVError(e.to_string()))?;
+            }
+
+            writer
+                .write_record(None::<&[u8]>)
+                .map_err(|e| PywrError::CSVError(e.to_string()))?;
+        }
+
+        self.starts.clear();
+        for column in self.columns.iter_mut() {
+            column.clear();
+        }
+
+        Ok(())
+    }
 }
 
 struct Internal {
-    writer: csv::Writer<File>,
+    writer: csv::Writer<CsvFile>,
+    block: ColumnBlock,
+    /// The groups of scenarios over which the scenario statistics, if any, are computed.
+    statistic_groups: Vec<ScenarioStatisticGroup>,
 }
 
 impl CsvWideFmtOutput {
@@ -30,43 +176,90 @@ impl CsvWideFmtOutput {
             meta: RecorderMeta::new(name),
             filename: filename.into(),
             metric_set_idx,
+            filter: MetricFilter::default(),
+            time_filter: TimeFilter::default(),
+            compression: CsvCompression::default(),
+            block_rows: NonZeroUsize::new(DEFAULT_BLOCK_ROWS).unwrap(),
         }
     }
 
+    /// Only write the metrics that match `filter`.
+    pub fn with_metric_filter(mut self, filter: MetricFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Only write the periods that match `time_filter`.
+    pub fn with_time_filter(mut self, time_filter: TimeFilter) -> Self {
+        self.time_filter = time_filter;
+        self
+    }
+
+    /// Compress the file with `compression`.
+    pub fn with_compression(mut self, compression: CsvCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Buffer `block_rows` rows before they are written to the file (the default is 1024).
+    pub fn with_block_rows(mut self, block_rows: NonZeroUsize) -> Self {
+        self.block_rows = block_rows;
+        self
+    }
+
     fn write_values(
         &self,
+        network: &Network,
         metric_set_states: &[Vec<MetricSetState>],
         internal: &mut Internal,
     ) -> Result<(), PywrError> {
-        let mut row = Vec::new();
+        let metric_set = network.get_metric_set(self.metric_set_idx)?;
 
-        // Iterate through all scenario's state
-        for ms_scenario_states in metric_set_states.iter() {
-            let metric_set_state = ms_scenario_states
-                .get(*self.metric_set_idx.deref())
-                .ok_or(PywrError::MetricSetIndexNotFound(self.metric_set_idx))?;
+        let states = metric_set_states
+            .iter()
+            .map(|ms_scenario_states| {
+                ms_scenario_states
+                    .get(*self.metric_set_idx.deref())
+                    .ok_or(PywrError::MetricSetIndexNotFound(self.metric_set_idx))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Write the scenario statistics instead of the values of each scenario, if any
+        let statistics;
+        let columns: Vec<&[PeriodValue<f64>]> = if metric_set.scenario_statistics().is_empty() {
+            states.iter().filter_map(|s| s.current_values()).collect()
+        } else {
+            statistics = metric_set
+                .calc_scenario_statistics(&states, &internal.statistic_groups)?
+                .unwrap_or_default();
+            statistics.iter().map(|v| v.as_slice()).collect()
+        };
 
-            if let Some(current_values) = metric_set_state.current_values() {
-                let values = current_values
-                    .iter()
-                    .map(|v| format!("{:.2}", v.value))
-                    .collect::<Vec<_>>();
+        // Only add a row if there are values for the current period
+        let Some(start) = columns.iter().find_map(|values| values.first()).map(|v| v.start) else {
+            return Ok(());
+        };
 
-                // If the row is empty, add the start time
-                if row.is_empty() {
-                    row.push(current_values.first().unwrap().start.to_string())
-                }
+        if !self.time_filter.is_match(&start) {
+            return Ok(());
+        }
 
-                row.extend(values);
-            }
+        let values = columns.iter().flat_map(|current_values| {
+            metric_set
+                .iter_metrics()
+                .zip(current_values.iter())
+                .filter(|(metric, _)| self.filter.is_match(metric))
+                .map(|(_, v)| v.value)
+        });
+
+        let block = &mut internal.block;
+        block.starts.push(start);
+        for (column, value) in block.columns.iter_mut().zip(values) {
+            column.push(value);
         }
 
-        // Only write
-        if row.len() > 1 {
-            internal
-                .writer
-                .write_record(row)
-                .map_err(|e| PywrError::CSVError(e.to_string()))?;
+        if block.len() >= self.block_rows.get() {
+            block.write(&mut internal.writer)?;
         }
 
         Ok(())
@@ -78,14 +271,15 @@ impl Recorder for CsvWideFmtOutput {
         &self.meta
     }
     fn setup(&self, domain: &ModelDomain, network: &Network) -> Result<Option<Box<(dyn Any)>>, PywrError> {
-        let mut writer = csv::Writer::from_path(&self.filename).map_err(|e| PywrError::CSVError(e.to_string()))?;
+        let mut writer = create_writer(&self.filename, self.compression)?;
 
         let mut names = vec![];
         let mut attributes = vec![];
 
         let metric_set = network.get_metric_set(self.metric_set_idx)?;
+        let statistic_groups = metric_set.scenario_statistic_groups(domain)?;
 
-        for metric in metric_set.iter_metrics() {
+        for metric in metric_set.iter_metrics().filter(|m| self.filter.is_match(m)) {
             let name = metric.name().to_string();
             let attribute = metric.attribute().to_string();
 
@@ -105,14 +299,27 @@ impl Recorder for CsvWideFmtOutput {
             header_scenario_groups.push(vec![format!("scenario-group: {}", group.name())]);
         }
 
-        for scenario_index in domain.scenarios().indices().iter() {
-            // Repeat the names, sub-names and attributes for every scenario
-            header_name.extend(names.clone());
-            header_attribute.extend(attributes.clone());
-            header_scenario.extend(vec![format!("{}", scenario_index.index); names.len()]);
+        if metric_set.scenario_statistics().is_empty() {
+            for scenario_index in domain.scenarios().indices().iter() {
+                // Repeat the names, sub-names and attributes for every scenario
+                header_name.extend(names.clone());
+                header_attribute.extend(attributes.clone());
+                header_scenario.extend(vec![format!("{}", scenario_index.global_index); names.len()]);
 
-            for (group_idx, idx) in scenario_index.indices.iter().enumerate() {
-                header_scenario_groups[group_idx].extend(vec![format!("{}", idx); names.len()]);
+                for (group_idx, idx) in scenario_index.indices.iter().enumerate() {
+                    header_scenario_groups[group_idx].extend(vec![format!("{}", idx); names.len()]);
+                }
+            }
+        } else {
+            for label in metric_set.scenario_statistic_labels(&statistic_groups) {
+                // Repeat the names, sub-names and attributes for every statistic
+                header_name.extend(names.clone());
+                header_attribute.extend(attributes.clone());
+                header_scenario.extend(vec![label.clone(); names.len()]);
+
+                for group in header_scenario_groups.iter_mut() {
+                    group.extend(vec![label.clone(); names.len()]);
+                }
             }
         }
 
@@ -136,7 +343,13 @@ impl Recorder for CsvWideFmtOutput {
             }
         }
 
-        let internal = Internal { writer };
+        // The first column of the header is the time
+        let block = ColumnBlock::new(header_name.len() - 1, self.block_rows.get());
+        let internal = Internal {
+            writer,
+            block,
+            statistic_groups,
+        };
 
         Ok(Some(Box::new(internal)))
     }
@@ -145,46 +358,67 @@ impl Recorder for CsvWideFmtOutput {
         &self,
         _timestep: &Timestep,
         _scenario_indices: &[ScenarioIndex],
-        _network: &Network,
+        network: &Network,
         _state: &[State],
         metric_set_states: &[Vec<MetricSetState>],
         internal_state: &mut Option<Box<dyn Any>>,
     ) -> Result<(), PywrError> {
-        let internal = match internal_state {
-            Some(internal) => match internal.downcast_mut::<Internal>() {
-                Some(pa) => pa,
-                None => panic!("Internal state did not downcast to the correct type! :("),
-            },
-            None => panic!("No internal state defined when one was expected! :("),
-        };
+        let internal = downcast_internal_state_mut::<Internal>(self.name(), internal_state)?;
 
-        self.write_values(metric_set_states, internal)?;
+        self.write_values(network, metric_set_states, internal)?;
 
         Ok(())
     }
 
     fn finalise(
         &self,
-        _network: &Network,
+        network: &Network,
         metric_set_states: &[Vec<MetricSetState>],
         internal_state: &mut Option<Box<dyn Any>>,
     ) -> Result<(), PywrError> {
         // This will leave the internal state with a `None` because we need to take
         // ownership of the file handle in order to close it.
-        match internal_state.take() {
-            Some(mut internal) => {
-                if let Some(internal) = internal.downcast_mut::<Internal>() {
-                    self.write_values(metric_set_states, internal)?;
-                    Ok(())
-                } else {
-                    panic!("Internal state did not downcast to the correct type! :(");
-                }
-            }
-            None => panic!("No internal state defined when one was expected! :("),
-        }
+        let mut internal = downcast_internal_state::<Internal>(self.name(), internal_state.take())?;
+
+        self.write_values(network, metric_set_states, &mut internal)?;
+        internal.block.write(&mut internal.writer)?;
+
+        finish_writer(internal.writer)
+    }
+
+    fn output_paths(&self) -> Vec<&Path> {
+        vec![self.filename.as_path()]
+    }
+
+    fn describe(&self, domain: &ModelDomain, network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        let metric_set = network.get_metric_set(self.metric_set_idx)?;
+        let description = describe_metric_set(metric_set, domain, &self.filter, metric_set.num_periods(domain))?;
+
+        Ok(Some(OutputDescription::new(
+            self.name(),
+            "csv",
+            Some(self.filename.clone()),
+            vec![description],
+        )))
+    }
+}
+
+/// Describe the values of `metric_set` written to a CSV file; these are the scenario statistics
+/// of the set, if any, instead of the values of each scenario.
+fn describe_metric_set(
+    metric_set: &MetricSet,
+    domain: &ModelDomain,
+    filter: &MetricFilter,
+    num_periods: usize,
+) -> Result<MetricSetDescription, PywrError> {
+    if metric_set.scenario_statistics().is_empty() {
+        Ok(metric_set.describe(domain, filter, num_periods))
+    } else {
+        metric_set.describe_scenario_statistics(domain, filter, num_periods)
     }
 }
 
+/// A row of the CSV file written by [`CsvLongFmtOutput`].
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CsvLongFmtRecord {
     time_start: NaiveDateTime,
@@ -196,10 +430,74 @@ pub struct CsvLongFmtRecord {
     value: f64,
 }
 
+/// Read the values of every metric saved in a long format CSV file written by a
+/// [`CsvLongFmtOutput`].
+///
+/// The file is decompressed if its extension is `zst`. The metrics are returned in the order
+/// they are found with the metric set, name and attribute as their path (e.g.
+/// `nodes/Inflow/inflow`). The values of each metric are in the order they are found in the file.
+/// Files containing scenario statistics (see [`CsvLongFmtStatisticRecord`]) are not supported.
+pub fn read_csv_long_metrics<P: AsRef<Path>>(filename: P) -> Result<Vec<(String, Vec<f64>)>, PywrError> {
+    let filename = filename.as_ref();
+    let file = File::open(filename).map_err(|e| PywrError::CSVError(e.to_string()))?;
+    let file: Box<dyn std::io::Read> = if filename.extension().is_some_and(|ext| ext == "zst") {
+        Box::new(zstd::Decoder::new(file).map_err(|e| PywrError::CSVError(e.to_string()))?)
+    } else {
+        Box::new(file)
+    };
+
+    let mut reader = csv::Reader::from_reader(file);
+    let mut metrics: Vec<(String, Vec<f64>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for record in reader.deserialize::<CsvLongFmtRecord>() {
+        let record = record.map_err(|e| PywrError::CSVError(e.to_string()))?;
+        let path = format!("{}/{}/{}", record.metric_set, record.name, record.attribute);
+
+        let position = *positions.entry(path).or_insert_with_key(|path| {
+            metrics.push((path.clone(), Vec::new()));
+            metrics.len() - 1
+        });
+        metrics[position].1.push(record.value);
+    }
+
+    Ok(metrics)
+}
+
+/// A row of the CSV file written by [`CsvLongFmtOutput`] for metric sets with scenario
+/// statistics.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CsvLongFmtStatisticRecord {
+    time_start: NaiveDateTime,
+    time_end: NaiveDateTime,
+    /// The label of the scenario statistic.
+    statistic: String,
+    metric_set: String,
+    name: String,
+    attribute: String,
+    value: f64,
+}
+
 /// Output the values from a several [`MetricSet`]s to a CSV file in long format.
 ///
 /// The long format contains a row for each value produced by the metric set. This is useful
-/// for analysis in tools like R or Python which can easily read long format data.
+/// for analysis in tools like R or Python which can easily read long format data. Each row
+/// includes the name of the metric set that produced the value.
+///
+/// An optional [`Aggregator`] can be used to aggregate the values of each metric over time
+/// (e.g. from daily to monthly values) before they are written. This is applied after any
+/// aggregation defined by the metric sets themselves.
+///
+/// The scenario statistics of the metric sets, if any, are written instead of the values of each
+/// scenario, with the label of the statistic in a `statistic` column in place of the
+/// `scenario_index` column (see [`CsvLongFmtStatisticRecord`]). Either all or none of the metric
+/// sets must have scenario statistics, and these can not be combined with the aggregator of the
+/// output.
+///
+/// The file can optionally be compressed (see [`CsvLongFmtOutput::with_compression`]).
+///
+/// Only the values of the periods that match the [`TimeFilter`], if any, are written. The filter
+/// is applied to the start of each period after any aggregation by the output.
 ///
 #[derive(Clone, Debug)]
 pub struct CsvLongFmtOutput {
@@ -207,6 +505,44 @@ pub struct CsvLongFmtOutput {
     filename: PathBuf,
     metric_set_indices: Vec<MetricSetIndex>,
     decimal_places: Option<NonZeroU32>,
+    filter: MetricFilter,
+    time_filter: TimeFilter,
+    aggregator: Option<Aggregator>,
+    compression: CsvCompression,
+}
+
+/// The header of the long format; these are the fields of [`CsvLongFmtRecord`].
+const LONG_FMT_HEADER: [&str; 7] = [
+    "time_start",
+    "time_end",
+    "scenario_index",
+    "metric_set",
+    "name",
+    "attribute",
+    "value",
+];
+
+/// The header of the long format for metric sets with scenario statistics; these are the fields
+/// of [`CsvLongFmtStatisticRecord`].
+const LONG_FMT_STATISTIC_HEADER: [&str; 7] = [
+    "time_start",
+    "time_end",
+    "statistic",
+    "metric_set",
+    "name",
+    "attribute",
+    "value",
+];
+
+struct LongInternal {
+    writer: csv::Writer<CsvFile>,
+    /// A buffer for formatting the fields of a record.
+    field: String,
+    /// The state of the aggregator for each scenario, metric set and metric.
+    aggregation_states: Option<Vec<Vec<Vec<AggregatorState>>>>,
+    /// The groups of scenarios over which the scenario statistics, if any, of each metric set
+    /// are computed.
+    statistic_groups: Vec<Vec<ScenarioStatisticGroup>>,
 }
 
 impl CsvLongFmtOutput {
@@ -221,50 +557,201 @@ impl CsvLongFmtOutput {
             filename: filename.into(),
             metric_set_indices: metric_set_indices.to_vec(),
             decimal_places,
+            filter: MetricFilter::default(),
+            time_filter: TimeFilter::default(),
+            aggregator: None,
+            compression: CsvCompression::default(),
         }
     }
 
+    /// Only write the metrics that match `filter`.
+    pub fn with_metric_filter(mut self, filter: MetricFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Only write the periods that match `time_filter`.
+    pub fn with_time_filter(mut self, time_filter: TimeFilter) -> Self {
+        self.time_filter = time_filter;
+        self
+    }
+
+    /// Aggregate the values of each metric over time using `aggregator` before writing them.
+    pub fn with_aggregator(mut self, aggregator: Aggregator) -> Self {
+        self.aggregator = Some(aggregator);
+        self
+    }
+
+    /// Compress the file with `compression`.
+    pub fn with_compression(mut self, compression: CsvCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Write a [`CsvLongFmtRecord`] field by field, reusing the buffer `field` to avoid
+    /// allocating the record. Nothing is written if the period does not match the time filter.
+    fn write_record(
+        &self,
+        writer: &mut csv::Writer<CsvFile>,
+        field: &mut String,
+        scenario: &str,
+        metric_set_name: &str,
+        metric: &OutputMetric,
+        value: &PeriodValue<f64>,
+    ) -> Result<(), PywrError> {
+        if !self.time_filter.is_match(&value.start) {
+            return Ok(());
+        }
+
+        let value_scaled = if let Some(decimal_places) = self.decimal_places {
+            let scale = 10.0_f64.powi(decimal_places.get() as i32);
+            (value.value * scale).round() / scale
+        } else {
+            value.value
+        };
+
+        // The times are formatted in the same way as they are serialised
+        for time in [value.start, value.end()] {
+            field.clear();
+            write!(field, "{time:?}").unwrap();
+            writer
+                .write_field(field.as_str())
+                .map_err(|e| PywrError::CSVError(e.to_string()))?;
+        }
+
+        for text in [scenario, metric_set_name, metric.name(), metric.attribute()] {
+            writer
+                .write_field(text)
+                .map_err(|e| PywrError::CSVError(e.to_string()))?;
+        }
+
+        let mut buffer = ryu::Buffer::new();
+        writer
+            .write_field(buffer.format(value_scaled))
+            .map_err(|e| PywrError::CSVError(e.to_string()))?;
+
+        writer
+            .write_record(None::<&[u8]>)
+            .map_err(|e| PywrError::CSVError(e.to_string()))
+    }
+
     fn write_values(
         &self,
         network: &Network,
         metric_set_states: &[Vec<MetricSetState>],
-        internal: &mut Internal,
+        internal: &mut LongInternal,
     ) -> Result<(), PywrError> {
         // Iterate through all the scenario's state
         for (scenario_idx, ms_scenario_states) in metric_set_states.iter().enumerate() {
-            for metric_set_idx in self.metric_set_indices.iter() {
+            let scenario = scenario_idx.to_string();
+
+            for (i, metric_set_idx) in self.metric_set_indices.iter().enumerate() {
                 let metric_set_state = ms_scenario_states
                     .get(*metric_set_idx.deref())
                     .ok_or(PywrError::MetricSetIndexNotFound(*metric_set_idx))?;
 
+                let metric_set = network.get_metric_set(*metric_set_idx)?;
+                if !metric_set.scenario_statistics().is_empty() {
+                    // These are written once for all the scenarios below
+                    continue;
+                }
+
                 if let Some(current_values) = metric_set_state.current_values() {
-                    let metric_set = network.get_metric_set(*metric_set_idx)?;
+                    for (j, (metric, value)) in metric_set
+                        .iter_metrics()
+                        .zip(current_values.iter())
+                        .enumerate()
+                        .filter(|(_, (metric, _))| self.filter.is_match(metric))
+                    {
+                        // Write the aggregated value, if any, instead of the value itself
+                        let value = match (&self.aggregator, internal.aggregation_states.as_mut()) {
+                            (Some(aggregator), Some(states)) => {
+                                aggregator.append_value(&mut states[scenario_idx][i][j], *value)?
+                            }
+                            _ => Some(*value),
+                        };
 
-                    for (metric, value) in metric_set.iter_metrics().zip(current_values.iter()) {
-                        let name = metric.name().to_string();
-                        let attribute = metric.attribute().to_string();
+                        if let Some(value) = value {
+                            self.write_record(
+                                &mut internal.writer,
+                                &mut internal.field,
+                                &scenario,
+                                metric_set.name(),
+                                metric,
+                                &value,
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
 
-                        let value_scaled = if let Some(decimal_places) = self.decimal_places {
-                            let scale = 10.0_f64.powi(decimal_places.get() as i32);
-                            (value.value * scale).round() / scale
-                        } else {
-                            value.value
-                        };
+        for (metric_set_idx, groups) in self.metric_set_indices.iter().zip(internal.statistic_groups.iter()) {
+            let metric_set = network.get_metric_set(*metric_set_idx)?;
+            if metric_set.scenario_statistics().is_empty() {
+                continue;
+            }
 
-                        let record = CsvLongFmtRecord {
-                            time_start: value.start,
-                            time_end: value.end(),
-                            scenario_index: scenario_idx,
-                            metric_set: metric_set.name().to_string(),
-                            name,
-                            attribute,
-                            value: value_scaled,
-                        };
+            let states = metric_set_states
+                .iter()
+                .map(|ms_scenario_states| {
+                    ms_scenario_states
+                        .get(*metric_set_idx.deref())
+                        .ok_or(PywrError::MetricSetIndexNotFound(*metric_set_idx))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if let Some(statistics) = metric_set.calc_scenario_statistics(&states, groups)? {
+                let labels = metric_set.scenario_statistic_labels(groups);
+                for (label, values) in labels.iter().zip(statistics.iter()) {
+                    for (metric, value) in metric_set
+                        .iter_metrics()
+                        .zip(values.iter())
+                        .filter(|(metric, _)| self.filter.is_match(metric))
+                    {
+                        self.write_record(
+                            &mut internal.writer,
+                            &mut internal.field,
+                            label,
+                            metric_set.name(),
+                            metric,
+                            value,
+                        )?;
+                    }
+                }
+            }
+        }
 
-                        internal
-                            .writer
-                            .serialize(record)
-                            .map_err(|e| PywrError::CSVError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Write the final aggregated values, including those of any partial periods.
+    fn write_final_aggregated_values(&self, network: &Network, internal: &mut LongInternal) -> Result<(), PywrError> {
+        let (aggregator, states) = match (&self.aggregator, internal.aggregation_states.as_mut()) {
+            (Some(aggregator), Some(states)) => (aggregator, states),
+            _ => return Ok(()),
+        };
+
+        for (scenario_idx, scenario_states) in states.iter_mut().enumerate() {
+            let scenario = scenario_idx.to_string();
+
+            for (metric_set_idx, metric_set_states) in self.metric_set_indices.iter().zip(scenario_states.iter_mut()) {
+                let metric_set = network.get_metric_set(*metric_set_idx)?;
+
+                for (metric, state) in metric_set
+                    .iter_metrics()
+                    .zip(metric_set_states.iter_mut())
+                    .filter(|(metric, _)| self.filter.is_match(metric))
+                {
+                    if let Some(value) = aggregator.finalise(state)? {
+                        self.write_record(
+                            &mut internal.writer,
+                            &mut internal.field,
+                            &scenario,
+                            metric_set.name(),
+                            metric,
+                            &value,
+                        )?;
                     }
                 }
             }
@@ -278,10 +765,61 @@ impl Recorder for CsvLongFmtOutput {
     fn meta(&self) -> &RecorderMeta {
         &self.meta
     }
-    fn setup(&self, _domain: &ModelDomain, _network: &Network) -> Result<Option<Box<(dyn Any)>>, PywrError> {
-        let writer = csv::Writer::from_path(&self.filename).map_err(|e| PywrError::CSVError(e.to_string()))?;
+    fn setup(&self, domain: &ModelDomain, network: &Network) -> Result<Option<Box<(dyn Any)>>, PywrError> {
+        let with_statistics = self
+            .metric_set_indices
+            .iter()
+            .map(|idx| Ok(!network.get_metric_set(*idx)?.scenario_statistics().is_empty()))
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        let header = if with_statistics.iter().all(|s| !*s) {
+            LONG_FMT_HEADER
+        } else if with_statistics.iter().all(|s| *s) {
+            LONG_FMT_STATISTIC_HEADER
+        } else {
+            return Err(PywrError::MixedScenarioStatistics(self.name().to_string()));
+        };
 
-        let internal = Internal { writer };
+        let mut writer = create_writer(&self.filename, self.compression)?;
+        writer
+            .write_record(header)
+            .map_err(|e| PywrError::CSVError(e.to_string()))?;
+
+        let aggregation_states = match &self.aggregator {
+            Some(aggregator) => {
+                // One aggregator state for every metric of every metric set
+                let metric_set_states = self
+                    .metric_set_indices
+                    .iter()
+                    .map(|idx| {
+                        let metric_set = network.get_metric_set(*idx)?;
+                        if !metric_set.scenario_statistics().is_empty() {
+                            return Err(PywrError::ScenarioStatisticsNotAggregated {
+                                metric_set: metric_set.name().to_string(),
+                                recorder: self.name().to_string(),
+                            });
+                        }
+                        Ok(metric_set.iter_metrics().map(|_| aggregator.setup()).collect())
+                    })
+                    .collect::<Result<Vec<Vec<_>>, PywrError>>()?;
+
+                Some(vec![metric_set_states; domain.scenarios().len()])
+            }
+            None => None,
+        };
+
+        let statistic_groups = self
+            .metric_set_indices
+            .iter()
+            .map(|idx| network.get_metric_set(*idx)?.scenario_statistic_groups(domain))
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        let internal = LongInternal {
+            writer,
+            field: String::new(),
+            aggregation_states,
+            statistic_groups,
+        };
 
         Ok(Some(Box::new(internal)))
     }
@@ -295,13 +833,7 @@ impl Recorder for CsvLongFmtOutput {
         metric_set_states: &[Vec<MetricSetState>],
         internal_state: &mut Option<Box<dyn Any>>,
     ) -> Result<(), PywrError> {
-        let internal = match internal_state {
-            Some(internal) => match internal.downcast_mut::<Internal>() {
-                Some(pa) => pa,
-                None => panic!("Internal state did not downcast to the correct type! :("),
-            },
-            None => panic!("No internal state defined when one was expected! :("),
-        };
+        let internal = downcast_internal_state_mut::<LongInternal>(self.name(), internal_state)?;
 
         self.write_values(network, metric_set_states, internal)?;
 
@@ -316,16 +848,101 @@ impl Recorder for CsvLongFmtOutput {
     ) -> Result<(), PywrError> {
         // This will leave the internal state with a `None` because we need to take
         // ownership of the file handle in order to close it.
-        match internal_state.take() {
-            Some(mut internal) => {
-                if let Some(internal) = internal.downcast_mut::<Internal>() {
-                    self.write_values(network, metric_set_states, internal)?;
-                    Ok(())
-                } else {
-                    panic!("Internal state did not downcast to the correct type! :(");
-                }
-            }
-            None => panic!("No internal state defined when one was expected! :("),
+        let mut internal = downcast_internal_state::<LongInternal>(self.name(), internal_state.take())?;
+
+        self.write_values(network, metric_set_states, &mut internal)?;
+        self.write_final_aggregated_values(network, &mut internal)?;
+
+        finish_writer(internal.writer)
+    }
+
+    fn output_paths(&self) -> Vec<&Path> {
+        vec![self.filename.as_path()]
+    }
+
+    fn describe(&self, domain: &ModelDomain, network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        let metric_sets = self
+            .metric_set_indices
+            .iter()
+            .map(|idx| {
+                let metric_set = network.get_metric_set(*idx)?;
+                let num_periods = match &self.aggregator {
+                    Some(aggregator) => num_periods(aggregator, domain),
+                    None => metric_set.num_periods(domain),
+                };
+                describe_metric_set(metric_set, domain, &self.filter, num_periods)
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        Ok(Some(OutputDescription::new(
+            self.name(),
+            "csv-long",
+            Some(self.filename.clone()),
+            metric_sets,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_csv_long_metrics, CsvCompression, CsvLongFmtOutput, CsvWideFmtOutput};
+    use crate::metric::MetricF64;
+    use crate::recorders::{MetricSet, OutputMetric};
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+    use std::num::NonZeroUsize;
+
+    /// Test the buffered and compressed files contain the same rows as the unbuffered files.
+    #[test]
+    fn test_csv_blocks_and_compression() {
+        let mut model = simple_model(2, None);
+
+        let node_idx = model.network().get_node_index_by_name("output", None).unwrap();
+        let metric = OutputMetric::new("output", "inflow", "Output", None, MetricF64::NodeInFlow(node_idx));
+        let metric_set = MetricSet::new("nodes", None, vec![metric]);
+        let metric_set_idx = model.network_mut().add_metric_set(metric_set).unwrap();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+
+        let recorders = [
+            CsvWideFmtOutput::new("wide-1", dir.join("wide-1.csv"), metric_set_idx)
+                .with_block_rows(NonZeroUsize::new(1).unwrap()),
+            CsvWideFmtOutput::new("wide-4", dir.join("wide-4.csv"), metric_set_idx)
+                .with_block_rows(NonZeroUsize::new(4).unwrap()),
+            CsvWideFmtOutput::new("wide-zstd", dir.join("wide.csv.zst"), metric_set_idx)
+                .with_compression(CsvCompression::Zstd(0)),
+        ];
+        for recorder in recorders {
+            model.network_mut().add_recorder(Box::new(recorder)).unwrap();
         }
+
+        let long = CsvLongFmtOutput::new("long", dir.join("long.csv"), &[metric_set_idx], None);
+        model.network_mut().add_recorder(Box::new(long)).unwrap();
+        let long = CsvLongFmtOutput::new("long-zstd", dir.join("long.csv.zst"), &[metric_set_idx], None)
+            .with_compression(CsvCompression::Zstd(3));
+        model.network_mut().add_recorder(Box::new(long)).unwrap();
+
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let read = |name: &str| std::fs::read(dir.join(name)).unwrap();
+        let decode = |name: &str| zstd::decode_all(read(name).as_slice()).unwrap();
+
+        let wide = read("wide-1.csv");
+        // Three header rows and a row for each time-step
+        assert_eq!(wide.iter().filter(|&&b| b == b'\n').count(), 3 + 15);
+        assert_eq!(read("wide-4.csv"), wide);
+        assert_eq!(decode("wide.csv.zst"), wide);
+
+        let long = read("long.csv");
+        assert!(long.starts_with(b"time_start,time_end,scenario_index,metric_set,name,attribute,value\n"));
+        assert_eq!(decode("long.csv.zst"), long);
+
+        // The long format can be read back with a value for each time-step and scenario
+        let metrics = read_csv_long_metrics(dir.join("long.csv")).unwrap();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].0, "nodes/output/inflow");
+        assert_eq!(metrics[0].1.len(), 15 * 2);
+        assert_eq!(read_csv_long_metrics(dir.join("long.csv.zst")).unwrap(), metrics);
     }
 }
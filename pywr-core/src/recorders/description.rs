@@ -0,0 +1,185 @@
+use crate::models::ModelDomain;
+use crate::recorders::aggregator::Aggregator;
+use crate::recorders::OutputMetric;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// A description of the values that will be written by an output.
+///
+/// Descriptions are created from the configuration of a model before it is run (see
+/// [`Model::describe_outputs`](crate::models::Model::describe_outputs)), so that downstream
+/// tools can prepare for the results without parsing the model file themselves.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OutputDescription {
+    /// The name of the output.
+    pub name: String,
+    /// The format of the output (e.g. `csv` or `hdf5`).
+    pub format: String,
+    /// The path of the file written by the output, if any.
+    pub path: Option<PathBuf>,
+    /// The values written for each metric set.
+    pub metric_sets: Vec<MetricSetDescription>,
+}
+
+impl OutputDescription {
+    pub fn new(name: &str, format: &str, path: Option<PathBuf>, metric_sets: Vec<MetricSetDescription>) -> Self {
+        Self {
+            name: name.to_string(),
+            format: format.to_string(),
+            path,
+            metric_sets,
+        }
+    }
+
+    /// The total number of values written by the output.
+    pub fn num_values(&self) -> usize {
+        self.metric_sets.iter().map(|ms| ms.num_values()).sum()
+    }
+}
+
+/// A description of the values written for a metric set.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MetricSetDescription {
+    /// The name of the metric set.
+    pub name: String,
+    /// The units of the metrics, if given.
+    pub units: Option<String>,
+    /// The metrics that are written.
+    pub metrics: Vec<OutputMetricDescription>,
+    /// The dimensions of the values, in order.
+    pub dimensions: Vec<OutputDimension>,
+}
+
+impl MetricSetDescription {
+    /// The shape of the values.
+    pub fn shape(&self) -> Vec<usize> {
+        self.dimensions.iter().map(|d| d.size).collect()
+    }
+
+    /// The number of values written for the metric set.
+    pub fn num_values(&self) -> usize {
+        self.dimensions.iter().map(|d| d.size).product()
+    }
+}
+
+/// A description of a single metric in an output.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OutputMetricDescription {
+    pub name: String,
+    pub attribute: String,
+    /// The originating type of the metric (e.g. node, parameter, etc.)
+    pub ty: String,
+    /// The originating subtype of the metric (e.g. node type, parameter type, etc.)
+    pub sub_type: Option<String>,
+}
+
+impl From<&OutputMetric> for OutputMetricDescription {
+    fn from(metric: &OutputMetric) -> Self {
+        Self {
+            name: metric.name().to_string(),
+            attribute: metric.attribute().to_string(),
+            ty: metric.ty().to_string(),
+            sub_type: metric.sub_type().map(|s| s.to_string()),
+        }
+    }
+}
+
+/// A dimension of the values written by an output.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OutputDimension {
+    /// The name of the dimension (e.g. `time`, `metric` or the name of a scenario group).
+    pub name: String,
+    /// The number of entries in the dimension.
+    pub size: usize,
+    /// The labels of the entries, if the dimension has labels.
+    ///
+    /// The entries of a scenario group are labelled with the index of each member that is run.
+    pub labels: Option<Vec<String>>,
+}
+
+impl OutputDimension {
+    pub fn new(name: &str, size: usize, labels: Option<Vec<String>>) -> Self {
+        Self {
+            name: name.to_string(),
+            size,
+            labels,
+        }
+    }
+}
+
+/// The number of values yielded by `aggregator` over a run of `domain`.
+pub(crate) fn num_periods(aggregator: &Aggregator, domain: &ModelDomain) -> usize {
+    let timesteps = domain.time().timesteps();
+    match (timesteps.first(), timesteps.last()) {
+        (Some(first), Some(last)) => aggregator.num_periods(&first.date, &(last.duration + last.date)),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputDimension;
+    use crate::metric::MetricF64;
+    use crate::models::{Model, ModelDomain};
+    use crate::network::Network;
+    use crate::recorders::{Aggregation, AggregationOrder, AssertionRecorder, MemoryRecorder, MetricSet, OutputMetric};
+    use crate::scenario::{ScenarioGroupCollection, ScenarioSubset};
+    use crate::test_utils::{default_timestepper, simple_network};
+    use ndarray::Array2;
+
+    #[test]
+    fn test_describe_outputs() {
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("climate", 2);
+        scenario_collection.add_group("hydrology", 3);
+        scenario_collection
+            .subset("hydrology", &ScenarioSubset::Indices(vec![0, 2]))
+            .unwrap();
+        let domain = ModelDomain::from(default_timestepper(), scenario_collection).unwrap();
+
+        let mut network = Network::default();
+        simple_network(&mut network, 1, 3);
+
+        let idx = network.get_node_index_by_name("input", None).unwrap();
+        let metric = OutputMetric::new("input", "outflow", "Input", None, MetricF64::NodeOutFlow(idx));
+        let metric_set = MetricSet::new("flows", None, vec![metric]).with_units("Ml/d");
+        let metric_set_idx = network.add_metric_set(metric_set).unwrap();
+
+        let recorder = MemoryRecorder::new(
+            "outputs",
+            metric_set_idx,
+            Aggregation::new(None, None, None),
+            AggregationOrder::default(),
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // Assertion recorders do not write any values and are not described
+        let expected = Array2::zeros((domain.time().len(), domain.scenarios().len()));
+        let recorder = AssertionRecorder::new("assert", MetricF64::NodeOutFlow(idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(domain, network);
+        let descriptions = model.describe_outputs().unwrap();
+
+        assert_eq!(descriptions.len(), 1);
+        let description = &descriptions[0];
+        assert_eq!(description.name, "outputs");
+        assert_eq!(description.format, "memory");
+        assert_eq!(description.path, None);
+
+        let metric_set = &description.metric_sets[0];
+        assert_eq!(metric_set.name, "flows");
+        assert_eq!(metric_set.units.as_deref(), Some("Ml/d"));
+        assert_eq!(
+            metric_set.dimensions,
+            vec![
+                OutputDimension::new("time", 15, None),
+                OutputDimension::new("climate", 2, Some(vec!["0".to_string(), "1".to_string()])),
+                OutputDimension::new("hydrology", 2, Some(vec!["0".to_string(), "2".to_string()])),
+                OutputDimension::new("metric", 1, Some(vec!["input/outflow".to_string()])),
+            ]
+        );
+        assert_eq!(metric_set.shape(), vec![15, 2, 2, 1]);
+        assert_eq!(description.num_values(), 60);
+    }
+}
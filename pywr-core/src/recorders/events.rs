@@ -0,0 +1,350 @@
+use super::{downcast_internal_state_mut, downcast_internal_state_ref, MetricSetState, Recorder, RecorderMeta};
+use crate::metric::MetricF64;
+use crate::models::ModelDomain;
+use crate::network::Network;
+use crate::parameters::Predicate;
+use crate::recorders::OutputDescription;
+use crate::scenario::ScenarioIndex;
+use crate::state::State;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use chrono::NaiveDateTime;
+use polars::prelude::{Column, DataFrame, ParquetWriter};
+use serde::Serialize;
+use std::any::Any;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// The format of the file of events written by an [`EventRecorder`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EventFileFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+/// A period of consecutive time-steps in which the predicate of an [`EventRecorder`] was true.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Event {
+    scenario_index: usize,
+    /// The start of the first time-step of the event.
+    start: NaiveDateTime,
+    /// The end of the last time-step of the event.
+    end: NaiveDateTime,
+    /// The number of time-steps in the event.
+    duration: usize,
+    /// The largest absolute difference between the metric and the threshold during the event.
+    peak: f64,
+}
+
+struct InternalState {
+    /// The event in progress in each scenario, if any.
+    current: Vec<Option<Event>>,
+    /// The completed events of all the scenarios.
+    events: Vec<Event>,
+    /// Whether each time-step follows an excluded period of the time domain.
+    follows_gap: Vec<bool>,
+}
+
+impl InternalState {
+    /// The completed events ordered by scenario and then start.
+    fn sorted_events(&self) -> Vec<&Event> {
+        let mut events: Vec<&Event> = self.events.iter().collect();
+        events.sort_by_key(|e| (e.scenario_index, e.start));
+        events
+    }
+
+    /// The events as a table with a row for each event, ordered by scenario and then start.
+    fn to_dataframe(&self) -> Result<DataFrame, PywrError> {
+        let events = self.sorted_events();
+
+        let columns = vec![
+            Column::new(
+                "scenario_index".into(),
+                events.iter().map(|e| e.scenario_index as u64).collect::<Vec<_>>(),
+            ),
+            Column::new("start".into(), events.iter().map(|e| e.start).collect::<Vec<_>>()),
+            Column::new("end".into(), events.iter().map(|e| e.end).collect::<Vec<_>>()),
+            Column::new(
+                "duration".into(),
+                events.iter().map(|e| e.duration as u64).collect::<Vec<_>>(),
+            ),
+            Column::new("peak".into(), events.iter().map(|e| e.peak).collect::<Vec<_>>()),
+        ];
+
+        DataFrame::new(columns).map_err(|e| PywrError::DataFrameError(e.to_string()))
+    }
+}
+
+/// A recorder of the events in which a metric crosses a threshold.
+///
+/// An event starts in the first time-step that `predicate` comparing the metric against the
+/// threshold is true, and ends when it is next false (or at the end of the run or an excluded
+/// period of the time domain). The start, end,
+/// duration and peak magnitude (the largest absolute difference between the metric and the
+/// threshold) of each event are recorded for each scenario. For example, the events in which a
+/// reservoir is below its emergency storage can be used to calculate the reliability,
+/// resilience and vulnerability of a system.
+///
+/// The events can be written to a file with [`EventRecorder::with_file`], and are also available
+/// as a data frame after the run (see [`Recorder::to_dataframe`]).
+pub struct EventRecorder {
+    meta: RecorderMeta,
+    metric: MetricF64,
+    threshold: MetricF64,
+    predicate: Predicate,
+    file: Option<(PathBuf, EventFileFormat)>,
+}
+
+impl EventRecorder {
+    pub fn new(name: &str, metric: MetricF64, threshold: MetricF64, predicate: Predicate) -> Self {
+        Self {
+            meta: RecorderMeta::new(name),
+            metric,
+            threshold,
+            predicate,
+            file: None,
+        }
+    }
+
+    /// Write the events to `filename` in the given format at the end of the run.
+    pub fn with_file<P: Into<PathBuf>>(mut self, filename: P, format: EventFileFormat) -> Self {
+        self.file = Some((filename.into(), format));
+        self
+    }
+
+    fn write_file(&self, filename: &Path, format: EventFileFormat, state: &InternalState) -> Result<(), PywrError> {
+        match format {
+            EventFileFormat::Csv => {
+                let mut writer = csv::Writer::from_path(filename).map_err(|e| PywrError::CSVError(e.to_string()))?;
+                for event in state.sorted_events() {
+                    writer
+                        .serialize(event)
+                        .map_err(|e| PywrError::CSVError(e.to_string()))?;
+                }
+                writer.flush().map_err(|e| PywrError::CSVError(e.to_string()))?;
+            }
+            EventFileFormat::Parquet => {
+                let mut df = state.to_dataframe()?;
+                let file = File::create(filename).map_err(|e| PywrError::DataFrameError(e.to_string()))?;
+                ParquetWriter::new(file)
+                    .finish(&mut df)
+                    .map_err(|e| PywrError::DataFrameError(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Recorder for EventRecorder {
+    fn meta(&self) -> &RecorderMeta {
+        &self.meta
+    }
+
+    fn setup(&self, domain: &ModelDomain, _network: &Network) -> Result<Option<Box<dyn Any>>, PywrError> {
+        let state = InternalState {
+            current: vec![None; domain.scenarios().len()],
+            events: Vec::new(),
+            follows_gap: domain
+                .time()
+                .timesteps()
+                .iter()
+                .map(|t| domain.time().follows_gap(t))
+                .collect(),
+        };
+
+        Ok(Some(Box::new(state)))
+    }
+
+    fn save(
+        &self,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        model: &Network,
+        state: &[State],
+        _metric_set_states: &[Vec<MetricSetState>],
+        internal_state: &mut Option<Box<dyn Any>>,
+    ) -> Result<(), PywrError> {
+        let internal_state = downcast_internal_state_mut::<InternalState>(self.name(), internal_state)?;
+        let follows_gap = internal_state.follows_gap.get(timestep.index).copied().unwrap_or(false);
+
+        for scenario_index in scenario_indices {
+            let value = self.metric.get_value(model, &state[scenario_index.index])?;
+            let threshold = self.threshold.get_value(model, &state[scenario_index.index])?;
+            let current = &mut internal_state.current[scenario_index.index];
+
+            if self.predicate.apply(value, threshold) {
+                let magnitude = (value - threshold).abs();
+                let end = timestep.duration + timestep.date;
+
+                // An event does not continue across an excluded period of the time domain
+                if follows_gap {
+                    internal_state.events.extend(current.take());
+                }
+
+                match current {
+                    Some(event) => {
+                        event.end = end;
+                        event.duration += 1;
+                        event.peak = event.peak.max(magnitude);
+                    }
+                    None => {
+                        *current = Some(Event {
+                            scenario_index: scenario_index.index,
+                            start: timestep.date,
+                            end,
+                            duration: 1,
+                            peak: magnitude,
+                        })
+                    }
+                }
+            } else if let Some(event) = current.take() {
+                internal_state.events.push(event);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finalise(
+        &self,
+        _network: &Network,
+        _metric_set_states: &[Vec<MetricSetState>],
+        internal_state: &mut Option<Box<dyn Any>>,
+    ) -> Result<(), PywrError> {
+        let internal_state = downcast_internal_state_mut::<InternalState>(self.name(), internal_state)?;
+
+        // Any events still in progress end with the run
+        let InternalState { current, events, .. } = internal_state;
+        events.extend(current.iter_mut().filter_map(|e| e.take()));
+
+        if let Some((filename, format)) = &self.file {
+            self.write_file(filename, *format, internal_state)?;
+        }
+
+        Ok(())
+    }
+
+    fn output_paths(&self) -> Vec<&Path> {
+        self.file.iter().map(|(filename, _)| filename.as_path()).collect()
+    }
+
+    fn describe(&self, _domain: &ModelDomain, _network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        // The number of events is not known before the run; only the file is described.
+        Ok(self.file.as_ref().map(|(filename, format)| {
+            let format = match format {
+                EventFileFormat::Csv => "events-csv",
+                EventFileFormat::Parquet => "events-parquet",
+            };
+            OutputDescription::new(self.name(), format, Some(filename.clone()), Vec::new())
+        }))
+    }
+
+    /// The events with the columns `scenario_index`, `start`, `end`, `duration` and `peak`.
+    fn to_dataframe(
+        &self,
+        _network: &Network,
+        internal_state: &Option<Box<dyn Any>>,
+    ) -> Result<Option<DataFrame>, PywrError> {
+        let internal_state = downcast_internal_state_ref::<InternalState>(self.name(), internal_state)?;
+
+        Ok(Some(internal_state.to_dataframe()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventRecorder;
+    use crate::metric::MetricF64;
+    use crate::models::{Model, ModelDomain};
+    use crate::network::Network;
+    use crate::parameters::Predicate;
+    use crate::scenario::ScenarioGroupCollection;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::{default_timestepper, simple_network};
+    use crate::timestep::ExcludedPeriod;
+    use chrono::NaiveDate;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_events() {
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("hydrology", 2);
+        let domain = ModelDomain::from(default_timestepper(), scenario_collection).unwrap();
+
+        let mut network = Network::default();
+        simple_network(&mut network, 0, 2);
+
+        // The demand of 12.0 is not met while the inflow, `1 + ts.index + hydrology index`, is
+        // less than 12.0.
+        let idx = network.get_node_index_by_name("input", None).unwrap();
+        let recorder = EventRecorder::new(
+            "deficits",
+            MetricF64::NodeOutFlow(idx),
+            12.0.into(),
+            Predicate::LessThan,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(domain, network);
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let df = model
+            .network()
+            .get_recorder_dataframe("deficits", result.recorder_states())
+            .unwrap();
+
+        // A single event in each scenario from the first time-step
+        assert_eq!(df.height(), 2);
+        let durations = df.column("duration").unwrap().u64().unwrap();
+        assert_eq!(durations.get(0), Some(11));
+        assert_eq!(durations.get(1), Some(10));
+
+        let peaks = df.column("peak").unwrap().f64().unwrap();
+        assert_approx_eq!(f64, peaks.get(0).unwrap(), 11.0);
+        assert_approx_eq!(f64, peaks.get(1).unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_events_excluded_period() {
+        let start = NaiveDate::from_ymd_opt(2020, 1, 5)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2020, 1, 6)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let timestepper = default_timestepper().with_excluded_periods(vec![ExcludedPeriod::new(start, end)]);
+
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("hydrology", 2);
+        let domain = ModelDomain::from(timestepper, scenario_collection).unwrap();
+
+        let mut network = Network::default();
+        simple_network(&mut network, 0, 2);
+
+        let idx = network.get_node_index_by_name("input", None).unwrap();
+        let recorder = EventRecorder::new(
+            "deficits",
+            MetricF64::NodeOutFlow(idx),
+            12.0.into(),
+            Predicate::LessThan,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(domain, network);
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let df = model
+            .network()
+            .get_recorder_dataframe("deficits", result.recorder_states())
+            .unwrap();
+
+        // The event in each scenario ends at the excluded period, and a new one starts after it
+        assert_eq!(df.height(), 4);
+        let durations = df.column("duration").unwrap().u64().unwrap();
+        assert_eq!(durations.into_no_null_iter().collect::<Vec<_>>(), vec![4, 7, 4, 6]);
+    }
+}
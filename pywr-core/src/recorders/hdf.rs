@@ -1,15 +1,15 @@
-use super::{MetricSetState, OutputMetric, PywrError, Recorder, RecorderMeta, Timestep};
+use super::{downcast_internal_state_mut, MetricSetState, OutputMetric, PywrError, Recorder, RecorderMeta, Timestep};
 use crate::models::ModelDomain;
 use crate::network::Network;
-use crate::recorders::MetricSetIndex;
+use crate::recorders::{MetricFilter, MetricSetIndex, OutputDescription, TimeFilter};
 use crate::scenario::{ScenarioDomain, ScenarioIndex};
 use crate::state::State;
 use chrono::{Datelike, Timelike};
-use hdf5_metno::{Extents, Group};
-use ndarray::{s, Array1};
+use hdf5_metno::{Group, H5Type};
+use ndarray::{s, Array1, Array2};
 use std::any::Any;
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 /// A recorder that saves model outputs to an HDF5 file.
@@ -18,23 +18,129 @@ use std::str::FromStr;
 /// and datasets that correspond to the metrics in the metric set. Additionally, the file will
 /// contain metadata about the time steps and scenarios that were used in the model simulation.
 ///
+/// If a single metric set is given its metrics are saved in the root of the file. Otherwise,
+/// the metrics of each metric set are saved in a group with the name of the metric set.
+///
+/// If a [`Baseline`] is given the differences between the model's values and those of the
+/// baseline are saved instead of the values themselves.
+///
+/// If a [`TimeFilter`] is given only the time-steps that match it are saved, and the `/time`
+/// table only contains those time-steps.
+///
+/// The datasets of the metrics are uncompressed, stored as `f64` and contiguous by default. For
+/// large ensembles they can be compressed (see [`Hdf5Compression`]), stored with a given chunk
+/// shape, and stored with single precision (see [`Hdf5Precision`]) to reduce the size of the file.
+///
 #[derive(Clone, Debug)]
 pub struct HDF5Recorder {
     meta: RecorderMeta,
     filename: PathBuf,
-    // TODO this could support saving multiple metric sets in different groups
-    metric_set_idx: MetricSetIndex,
+    metric_set_indices: Vec<MetricSetIndex>,
+    filter: MetricFilter,
+    time_filter: TimeFilter,
+    baseline: Option<Baseline>,
+    compression: Hdf5Compression,
+    chunk_shape: Option<(usize, usize)>,
+    precision: Hdf5Precision,
+}
+
+/// Compression of the metric datasets written by the [`HDF5Recorder`].
+///
+/// Compressed datasets are always chunked. If a chunk shape is not given one is chosen by HDF5.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Hdf5Compression {
+    #[default]
+    None,
+    /// Compress the datasets with gzip at the given level (0-9).
+    Gzip(u8),
+    /// Compress the datasets with LZF, which is faster than gzip but compresses less.
+    Lzf,
+}
+
+/// The precision of the values of the metric datasets written by the [`HDF5Recorder`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Hdf5Precision {
+    /// Store the values as `f64`.
+    #[default]
+    Double,
+    /// Store the values as `f32`, which halves the size of the datasets.
+    Single,
 }
 
 struct Internal {
     file: hdf5_metno::File,
-    datasets: Vec<hdf5_metno::Dataset>,
+    // The row of the datasets for each time-step, if it is saved
+    rows: Vec<Option<usize>>,
+    // The datasets for each metric set that match the filter
+    datasets: Vec<Vec<hdf5_metno::Dataset>>,
+    // The baseline values for each of the datasets
+    baselines: Option<Vec<Vec<Array2<f64>>>>,
 }
 
+/// How the difference between a value and its baseline value is calculated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DifferenceKind {
+    /// The value minus the baseline value.
+    #[default]
+    Absolute,
+    /// The difference as a percentage of the baseline value. If the baseline value is zero the
+    /// result is zero when the value is also zero, and NaN otherwise.
+    Percentage,
+}
+
+impl DifferenceKind {
+    fn apply(&self, value: f64, baseline: f64) -> f64 {
+        match self {
+            Self::Absolute => value - baseline,
+            Self::Percentage => {
+                if baseline == 0.0 {
+                    if value == 0.0 {
+                        0.0
+                    } else {
+                        f64::NAN
+                    }
+                } else {
+                    100.0 * (value - baseline) / baseline
+                }
+            }
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Absolute => "absolute",
+            Self::Percentage => "percentage",
+        }
+    }
+}
+
+/// An HDF5 file, written by a previous run, to compare a model's values against.
+///
+/// The baseline must contain a dataset for every metric saved by the recorder with the same
+/// layout (i.e. the same metric sets) and shape (i.e. the same time-steps and scenarios).
+#[derive(Clone, Debug)]
+pub struct Baseline {
+    filename: PathBuf,
+    kind: DifferenceKind,
+}
+
+impl Baseline {
+    pub fn new<P: Into<PathBuf>>(filename: P, kind: DifferenceKind) -> Self {
+        Self {
+            filename: filename.into(),
+            kind,
+        }
+    }
+}
+
+/// A row of the `/time` table.
+///
+/// Indices and sizes written to the file are always 64-bit, regardless of the pointer width of
+/// the target, and HDF5 records the byte order of each value so that files are portable.
 #[derive(hdf5_metno::H5Type, Copy, Clone, Debug)]
 #[repr(C)]
 pub struct DateTime {
-    index: usize,
+    index: u64,
     year: i32,
     month: u8,
     day: u8,
@@ -46,7 +152,7 @@ pub struct DateTime {
 impl DateTime {
     fn from_timestamp(ts: &Timestep) -> Self {
         Self {
-            index: ts.index,
+            index: ts.index as u64,
             year: ts.date.year(),
             month: ts.date.month() as u8,
             day: ts.date.day() as u8,
@@ -58,13 +164,97 @@ impl DateTime {
 }
 
 impl HDF5Recorder {
-    pub fn new<P: Into<PathBuf>>(name: &str, filename: P, metric_set_idx: MetricSetIndex) -> Self {
+    pub fn new<P: Into<PathBuf>>(name: &str, filename: P, metric_set_indices: &[MetricSetIndex]) -> Self {
         Self {
             meta: RecorderMeta::new(name),
             filename: filename.into(),
-            metric_set_idx,
+            metric_set_indices: metric_set_indices.to_vec(),
+            filter: MetricFilter::default(),
+            time_filter: TimeFilter::default(),
+            baseline: None,
+            compression: Hdf5Compression::default(),
+            chunk_shape: None,
+            precision: Hdf5Precision::default(),
         }
     }
+
+    /// Only save the metrics that match `filter`.
+    pub fn with_metric_filter(mut self, filter: MetricFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Only save the time-steps that match `time_filter`.
+    pub fn with_time_filter(mut self, time_filter: TimeFilter) -> Self {
+        self.time_filter = time_filter;
+        self
+    }
+
+    /// Save the differences from `baseline` instead of the values.
+    pub fn with_baseline(mut self, baseline: Baseline) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
+    /// Compress the metric datasets with `compression`.
+    pub fn with_compression(mut self, compression: Hdf5Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Store the metric datasets in chunks of (up to) `time_steps` rows and `scenarios` columns.
+    pub fn with_chunk_shape(mut self, time_steps: usize, scenarios: usize) -> Self {
+        self.chunk_shape = Some((time_steps, scenarios));
+        self
+    }
+
+    /// Store the values of the metric datasets with `precision`.
+    pub fn with_precision(mut self, precision: Hdf5Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Create an empty metric dataset with the storage options of the recorder.
+    fn create_dataset<T: H5Type>(
+        &self,
+        parent: &Group,
+        shape: (usize, usize),
+        name: &str,
+    ) -> Result<hdf5_metno::Dataset, PywrError> {
+        let mut builder = parent.new_dataset::<T>().shape(shape);
+
+        // The chunks can not be larger than, or have a zero size in, a fixed size dataset
+        if let Some((rows, cols)) = self.chunk_shape {
+            if shape.0 > 0 && shape.1 > 0 {
+                builder = builder.chunk((rows.clamp(1, shape.0), cols.clamp(1, shape.1)));
+            }
+        }
+
+        builder = match self.compression {
+            Hdf5Compression::None => builder,
+            Hdf5Compression::Gzip(level) => builder.deflate(level),
+            Hdf5Compression::Lzf => builder.lzf(),
+        };
+
+        Ok(builder.create(name)?)
+    }
+
+    /// Create a metric dataset in /parent/name/attribute
+    fn require_metric_dataset(
+        &self,
+        parent: &Group,
+        shape: (usize, usize),
+        metric: &OutputMetric,
+    ) -> Result<hdf5_metno::Dataset, PywrError> {
+        let grp = require_group(parent, metric.name())?;
+        let ds = match self.precision {
+            Hdf5Precision::Double => self.create_dataset::<f64>(&grp, shape, metric.attribute())?,
+            Hdf5Precision::Single => self.create_dataset::<f32>(&grp, shape, metric.attribute())?,
+        };
+
+        write_metric_attributes(&ds, metric)?;
+        Ok(ds)
+    }
 }
 
 impl Recorder for HDF5Recorder {
@@ -77,24 +267,85 @@ impl Recorder for HDF5Recorder {
         write_pywr_metadata(&file)?;
         write_scenarios_metadata(&file, domain.scenarios())?;
 
-        // Create the time table
-        let dates: Array1<_> = domain.time().timesteps().iter().map(DateTime::from_timestamp).collect();
+        // Create the time table of the saved time-steps, and the row of each in the datasets
+        let timesteps = domain.time().timesteps();
+        let mut rows = Vec::with_capacity(timesteps.len());
+        let mut dates = Vec::with_capacity(timesteps.len());
+        for ts in timesteps {
+            if self.time_filter.is_match(&ts.date) {
+                rows.push(Some(dates.len()));
+                dates.push(DateTime::from_timestamp(ts));
+            } else {
+                rows.push(None);
+            }
+        }
+        let dates = Array1::from(dates);
         file.deref().new_dataset_builder().with_data(&dates).create("time")?;
 
-        let shape = (domain.time().len(), domain.scenarios().len());
+        let shape = (dates.len(), domain.scenarios().len());
 
         let root_grp = file.deref();
 
-        let metric_set = network.get_metric_set(self.metric_set_idx)?;
+        let baseline_file = match &self.baseline {
+            Some(baseline) => {
+                write_baseline_metadata(&file, baseline)?;
+                Some(hdf5_metno::File::open(&baseline.filename)?)
+            }
+            None => None,
+        };
+
+        let mut datasets = Vec::with_capacity(self.metric_set_indices.len());
+        let mut baselines = Vec::with_capacity(self.metric_set_indices.len());
+
+        for metric_set_idx in &self.metric_set_indices {
+            let metric_set = network.get_metric_set(*metric_set_idx)?;
+            if !metric_set.scenario_statistics().is_empty() {
+                return Err(PywrError::ScenarioStatisticsNotSupported {
+                    metric_set: metric_set.name().to_string(),
+                    recorder: self.name().to_string(),
+                });
+            }
+
+            let grouped = self.metric_set_indices.len() > 1;
+            let grp = if grouped {
+                require_group(root_grp, metric_set.name())?
+            } else {
+                root_grp.clone()
+            };
 
-        let mut datasets = Vec::new();
+            let metrics: Vec<_> = metric_set.iter_metrics().filter(|m| self.filter.is_match(m)).collect();
 
-        for metric in metric_set.iter_metrics() {
-            let ds = require_metric_dataset(root_grp, shape, metric)?;
-            datasets.push(ds);
+            let ms_datasets = metrics
+                .iter()
+                .map(|metric| self.require_metric_dataset(&grp, shape, metric))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            datasets.push(ms_datasets);
+
+            if let Some(baseline_file) = &baseline_file {
+                let ms_baselines = metrics
+                    .iter()
+                    .map(|metric| {
+                        let path = if grouped {
+                            format!("{}/{}/{}", metric_set.name(), metric.name(), metric.attribute())
+                        } else {
+                            format!("{}/{}", metric.name(), metric.attribute())
+                        };
+                        read_baseline_dataset(baseline_file, &path, shape)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                baselines.push(ms_baselines);
+            }
         }
 
-        let internal = Internal { datasets, file };
+        let baselines = baseline_file.map(|_| baselines);
+        let internal = Internal {
+            rows,
+            datasets,
+            baselines,
+            file,
+        };
 
         Ok(Some(Box::new(internal)))
     }
@@ -107,25 +358,46 @@ impl Recorder for HDF5Recorder {
         _metric_set_states: &[Vec<MetricSetState>],
         internal_state: &mut Option<Box<dyn Any>>,
     ) -> Result<(), PywrError> {
-        let internal = match internal_state {
-            Some(internal) => match internal.downcast_mut::<Internal>() {
-                Some(pa) => pa,
-                None => panic!("Internal state did not downcast to the correct type! :("),
-            },
-            None => panic!("No internal state defined when one was expected! :("),
-        };
+        let internal = downcast_internal_state_mut::<Internal>(self.name(), internal_state)?;
 
-        let metric_set = model.get_metric_set(self.metric_set_idx)?;
+        let Some(row) = internal.rows.get(timestep.index).copied().flatten() else {
+            return Ok(());
+        };
 
-        for (dataset, metric) in internal.datasets.iter_mut().zip(metric_set.iter_metrics()) {
-            // Combine all the values for metric across all of the scenarios
-            let values = scenario_indices
-                .iter()
-                .zip(state)
-                .map(|(_, s)| metric.get_value(model, s))
-                .collect::<Result<Vec<_>, _>>()?;
+        for (i, (metric_set_idx, datasets)) in self
+            .metric_set_indices
+            .iter()
+            .zip(internal.datasets.iter_mut())
+            .enumerate()
+        {
+            let metric_set = model.get_metric_set(*metric_set_idx)?;
+            let metrics = metric_set.iter_metrics().filter(|m| self.filter.is_match(m));
+
+            for (j, (dataset, metric)) in datasets.iter_mut().zip(metrics).enumerate() {
+                // Combine all the values for metric across all of the scenarios
+                let mut values = scenario_indices
+                    .iter()
+                    .zip(state)
+                    .map(|(_, s)| metric.get_value(model, s))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if let (Some(baselines), Some(baseline)) = (&internal.baselines, &self.baseline) {
+                    let baseline_values = &baselines[i][j];
+                    for (value, scenario_index) in values.iter_mut().zip(scenario_indices) {
+                        *value = baseline
+                            .kind
+                            .apply(*value, baseline_values[[row, scenario_index.index]]);
+                    }
+                }
 
-            dataset.write_slice(&values, s![timestep.index, ..])?;
+                match self.precision {
+                    Hdf5Precision::Double => dataset.write_slice(&values, s![row, ..])?,
+                    Hdf5Precision::Single => {
+                        let values: Vec<f32> = values.iter().map(|v| *v as f32).collect();
+                        dataset.write_slice(&values, s![row, ..])?
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -140,32 +412,46 @@ impl Recorder for HDF5Recorder {
         // This will leave the internal state with a `None` because we need to take
         // ownership of the file handle in order to close it.
         match internal_state.take() {
-            Some(internal) => {
-                if let Ok(internal) = internal.downcast::<Internal>() {
-                    Ok(internal.file.close()?)
-                } else {
-                    panic!("Internal state did not downcast to the correct type! :(");
-                }
-            }
-            None => panic!("No internal state defined when one was expected! :("),
+            Some(internal) => match internal.downcast::<Internal>() {
+                Ok(internal) => Ok(internal.file.close()?),
+                Err(_) => Err(PywrError::RecorderInternalStateDowncastFailed(self.name().to_string())),
+            },
+            None => Err(PywrError::RecorderInternalStateNotFound(self.name().to_string())),
         }
     }
-}
 
-fn require_dataset<S: Into<Extents>>(parent: &Group, shape: S, name: &str) -> Result<hdf5_metno::Dataset, PywrError> {
-    Ok(parent.new_dataset::<f64>().shape(shape).create(name)?)
-}
+    fn output_paths(&self) -> Vec<&Path> {
+        vec![self.filename.as_path()]
+    }
 
-/// Create a node dataset in /parent/name/sub_name/attribute
-fn require_metric_dataset<S: Into<Extents>>(
-    parent: &Group,
-    shape: S,
-    metric: &OutputMetric,
-) -> Result<hdf5_metno::Dataset, PywrError> {
-    let grp = require_group(parent, metric.name())?;
-    let ds = require_dataset(&grp, shape, metric.attribute())?;
+    fn describe(&self, domain: &ModelDomain, network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        // The metrics are saved every time-step regardless of any aggregation of the metric set
+        let num_rows = domain
+            .time()
+            .timesteps()
+            .iter()
+            .filter(|ts| self.time_filter.is_match(&ts.date))
+            .count();
+        let metric_sets = self
+            .metric_set_indices
+            .iter()
+            .map(|idx| {
+                let metric_set = network.get_metric_set(*idx)?;
+                Ok(metric_set.describe(domain, &self.filter, num_rows))
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        Ok(Some(OutputDescription::new(
+            self.name(),
+            "hdf5",
+            Some(self.filename.clone()),
+            metric_sets,
+        )))
+    }
+}
 
-    // Write the type and subtype as attributes
+/// Write the type and subtype of a metric as attributes of its dataset.
+fn write_metric_attributes(ds: &hdf5_metno::Dataset, metric: &OutputMetric) -> Result<(), PywrError> {
     let ty = hdf5_metno::types::VarLenUnicode::from_str(metric.ty())
         .map_err(|e| PywrError::HDF5VarLenUnicode(e.to_string()))?;
     let attr = ds
@@ -183,7 +469,57 @@ fn require_metric_dataset<S: Into<Extents>>(
             .create("pywr-subtype")?;
         attr.as_writer().write_scalar(&sub_type)?;
     }
-    Ok(ds)
+    Ok(())
+}
+
+/// Read the values of a baseline dataset checking that it has the expected shape.
+fn read_baseline_dataset(file: &hdf5_metno::File, path: &str, shape: (usize, usize)) -> Result<Array2<f64>, PywrError> {
+    let ds = file
+        .dataset(path)
+        .map_err(|_| PywrError::BaselineDatasetNotFound(path.to_string()))?;
+    let values = ds.read_2d::<f64>()?;
+
+    if values.dim() != shape {
+        return Err(PywrError::BaselineShapeMismatch {
+            path: path.to_string(),
+            expected: shape,
+            found: values.dim(),
+        });
+    }
+
+    Ok(values)
+}
+
+/// Read the values of every metric saved in an HDF5 file written by a [`HDF5Recorder`].
+///
+/// The metrics are returned in the order they are found with their path in the file (e.g.
+/// `node/Inflow`, or `metric-set/node/Inflow` if the file contains multiple metric sets). The
+/// values have a row for each time-step and a column for each scenario.
+pub fn read_hdf5_metrics<P: AsRef<Path>>(filename: P) -> Result<Vec<(String, Array2<f64>)>, PywrError> {
+    let file = hdf5_metno::File::open(filename)?;
+    let mut metrics = Vec::new();
+    read_metric_datasets(file.deref(), &mut metrics)?;
+    Ok(metrics)
+}
+
+/// Recursively read the metric datasets of `group` in to `metrics`.
+///
+/// Metric datasets are identified by the `pywr-type` attribute written when they are created.
+fn read_metric_datasets(group: &Group, metrics: &mut Vec<(String, Array2<f64>)>) -> Result<(), PywrError> {
+    for ds in group.datasets()? {
+        if ds.attr("pywr-type").is_ok() && ds.ndim() == 2 {
+            let path = ds.name().trim_start_matches('/').to_string();
+            metrics.push((path, ds.read_2d::<f64>()?));
+        }
+    }
+
+    for grp in group.groups()? {
+        if grp.name() != "/scenarios" {
+            read_metric_datasets(&grp, metrics)?;
+        }
+    }
+
+    Ok(())
 }
 
 fn require_group(parent: &Group, name: &str) -> Result<Group, PywrError> {
@@ -212,18 +548,41 @@ fn write_pywr_metadata(file: &hdf5_metno::File) -> Result<(), PywrError> {
     Ok(())
 }
 
+/// Write the baseline file name and how the differences are calculated as attributes of the
+/// root group.
+fn write_baseline_metadata(file: &hdf5_metno::File, baseline: &Baseline) -> Result<(), PywrError> {
+    let root = file.deref();
+
+    let attributes = [
+        ("pywr-baseline", baseline.filename.to_string_lossy().to_string()),
+        ("pywr-difference", baseline.kind.as_str().to_string()),
+    ];
+
+    for (name, value) in attributes {
+        let value = hdf5_metno::types::VarLenUnicode::from_str(&value)
+            .map_err(|e| PywrError::HDF5VarLenUnicode(e.to_string()))?;
+        let attr = root
+            .new_attr::<hdf5_metno::types::VarLenUnicode>()
+            .shape(())
+            .create(name)?;
+        attr.as_writer().write_scalar(&value)?;
+    }
+
+    Ok(())
+}
+
 #[derive(hdf5_metno::H5Type, Clone, PartialEq, Debug)]
 #[repr(C)]
 pub struct ScenarioGroupEntry {
     pub name: hdf5_metno::types::VarLenUnicode,
-    pub size: usize,
+    pub size: u64,
 }
 
 #[derive(hdf5_metno::H5Type, Clone, PartialEq, Debug)]
 #[repr(C)]
 pub struct H5ScenarioIndex {
-    index: usize,
-    indices: hdf5_metno::types::VarLenArray<usize>,
+    index: u64,
+    indices: hdf5_metno::types::VarLenArray<u64>,
 }
 
 /// Write scenario metadata to the HDF5 file.
@@ -241,7 +600,10 @@ fn write_scenarios_metadata(file: &hdf5_metno::File, domain: &ScenarioDomain) ->
             let name = hdf5_metno::types::VarLenUnicode::from_str(s.name())
                 .map_err(|e| PywrError::HDF5VarLenUnicode(e.to_string()))?;
 
-            Ok(ScenarioGroupEntry { name, size: s.size() })
+            Ok(ScenarioGroupEntry {
+                name,
+                size: s.size() as u64,
+            })
         })
         .collect::<Result<_, PywrError>>()?;
 
@@ -251,11 +613,11 @@ fn write_scenarios_metadata(file: &hdf5_metno::File, domain: &ScenarioDomain) ->
         .indices()
         .iter()
         .map(|s| {
-            let indices = hdf5_metno::types::VarLenArray::from_slice(&s.indices);
+            let indices: Vec<u64> = s.indices.iter().map(|i| *i as u64).collect();
 
             Ok(H5ScenarioIndex {
-                index: s.index,
-                indices,
+                index: s.index as u64,
+                indices: hdf5_metno::types::VarLenArray::from_slice(&indices),
             })
         })
         .collect::<Result<_, PywrError>>()?;
@@ -264,3 +626,113 @@ fn write_scenarios_metadata(file: &hdf5_metno::File, domain: &ScenarioDomain) ->
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{read_hdf5_metrics, HDF5Recorder, Hdf5Compression, Hdf5Precision};
+    use crate::metric::MetricF64;
+    use crate::recorders::{MetricSet, OutputMetric};
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+    use hdf5_metno::filters::Filter;
+    use hdf5_metno::types::{FloatSize, IntSize, TypeDescriptor};
+
+    #[test]
+    fn test_read_hdf5_metrics() {
+        let mut model = simple_model(2, None);
+
+        let node_idx = model.network().get_node_index_by_name("output", None).unwrap();
+        let metric = OutputMetric::new("output", "inflow", "Output", None, MetricF64::NodeInFlow(node_idx));
+        let metric_set = MetricSet::new("nodes", None, vec![metric]);
+        let metric_set_idx = model.network_mut().add_metric_set(metric_set).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("read-hdf5.h5");
+        let recorder = HDF5Recorder::new("outputs", &path, &[metric_set_idx]);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let metrics = read_hdf5_metrics(&path).unwrap();
+
+        assert_eq!(metrics.len(), 1);
+        let (name, values) = &metrics[0];
+        assert_eq!(name, "output/inflow");
+        assert_eq!(values.dim(), (model.domain().time().len(), 2));
+    }
+
+    #[test]
+    fn test_hdf5_storage_options() {
+        let mut model = simple_model(2, None);
+
+        let node_idx = model.network().get_node_index_by_name("output", None).unwrap();
+        let metric = OutputMetric::new("output", "inflow", "Output", None, MetricF64::NodeInFlow(node_idx));
+        let metric_set = MetricSet::new("nodes", None, vec![metric]);
+        let metric_set_idx = model.network_mut().add_metric_set(metric_set).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("storage.h5");
+        let recorder = HDF5Recorder::new("outputs", &path, &[metric_set_idx])
+            .with_compression(Hdf5Compression::Gzip(4))
+            .with_chunk_shape(100, 10)
+            .with_precision(Hdf5Precision::Single);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let file = hdf5_metno::File::open(&path).unwrap();
+        let ds = file.dataset("output/inflow").unwrap();
+        assert_eq!(
+            ds.dtype().unwrap().to_descriptor().unwrap(),
+            TypeDescriptor::Float(FloatSize::U4)
+        );
+        // The chunks are limited to the shape of the dataset
+        assert_eq!(ds.chunk(), Some(vec![model.domain().time().len().min(100), 2]));
+        assert!(ds.filters().contains(&Filter::Deflate(4)));
+        drop(file);
+
+        // The values can still be read as `f64`
+        let metrics = read_hdf5_metrics(&path).unwrap();
+        assert_eq!(metrics[0].1.dim(), (model.domain().time().len(), 2));
+    }
+
+    #[test]
+    fn test_hdf5_indices_are_64_bit() {
+        let mut model = simple_model(2, None);
+
+        let node_idx = model.network().get_node_index_by_name("output", None).unwrap();
+        let metric = OutputMetric::new("output", "inflow", "Output", None, MetricF64::NodeInFlow(node_idx));
+        let metric_set = MetricSet::new("nodes", None, vec![metric]);
+        let metric_set_idx = model.network_mut().add_metric_set(metric_set).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("indices.h5");
+        let recorder = HDF5Recorder::new("outputs", &path, &[metric_set_idx]);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let file = hdf5_metno::File::open(&path).unwrap();
+        let field_type =
+            |dataset: &str, field: &str| match file.dataset(dataset).unwrap().dtype().unwrap().to_descriptor() {
+                Ok(TypeDescriptor::Compound(compound)) => {
+                    compound.fields.into_iter().find(|f| f.name == field).unwrap().ty
+                }
+                other => panic!("Expected a compound type; found {other:?}"),
+            };
+
+        assert_eq!(field_type("time", "index"), TypeDescriptor::Unsigned(IntSize::U8));
+        assert_eq!(
+            field_type("scenarios/groups", "size"),
+            TypeDescriptor::Unsigned(IntSize::U8)
+        );
+        assert_eq!(
+            field_type("scenarios/indices", "index"),
+            TypeDescriptor::Unsigned(IntSize::U8)
+        );
+        assert_eq!(
+            field_type("scenarios/indices", "indices"),
+            TypeDescriptor::VarLenArray(Box::new(TypeDescriptor::Unsigned(IntSize::U8)))
+        );
+    }
+}
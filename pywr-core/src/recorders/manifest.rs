@@ -0,0 +1,146 @@
+use crate::checksum::Checksum;
+use crate::PywrError;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// A file written by a recorder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileManifestEntry {
+    /// The name of the recorder that wrote the file.
+    pub recorder: String,
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub size: u64,
+    pub checksum: Checksum,
+}
+
+impl FileManifestEntry {
+    /// Create an entry for the file at `path` by reading its size and calculating its checksum.
+    ///
+    /// This should only be called once the file has been completely written and closed.
+    pub fn from_path(recorder: &str, path: &Path) -> Result<Self, PywrError> {
+        let map_err = |e: std::io::Error| PywrError::ManifestError(path.to_path_buf(), e.to_string());
+
+        let size = std::fs::metadata(path).map_err(map_err)?.len();
+        let checksum = Checksum::sha256_file(path).map_err(map_err)?;
+
+        Ok(Self {
+            recorder: recorder.to_string(),
+            path: path.to_path_buf(),
+            size,
+            checksum,
+        })
+    }
+}
+
+/// A manifest of all the files written by the recorders during a model run.
+///
+/// The manifest can be used to verify that the results have been completely, and correctly,
+/// transferred elsewhere (e.g. to long-term storage).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileManifest {
+    files: Vec<FileManifestEntry>,
+}
+
+impl FileManifest {
+    pub fn push(&mut self, entry: FileManifestEntry) {
+        self.files.push(entry);
+    }
+
+    /// Add all the entries of `other` to this manifest.
+    pub fn extend(&mut self, other: FileManifest) {
+        self.files.extend(other.files);
+    }
+
+    pub fn files(&self) -> &[FileManifestEntry] {
+        &self.files
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// The total size of all the files in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Check every file in the manifest still exists with the same size and checksum.
+    ///
+    /// Returns the entries of any files that are missing or do not match.
+    pub fn verify(&self) -> Vec<&FileManifestEntry> {
+        self.files
+            .iter()
+            .filter(|entry| {
+                let size_matches = std::fs::metadata(&entry.path)
+                    .map(|m| m.len() == entry.size)
+                    .unwrap_or(false);
+                !size_matches || !entry.checksum.verify_file(&entry.path).unwrap_or(false)
+            })
+            .collect()
+    }
+}
+
+impl Display for FileManifest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{: <24} | {: <12} | {: <72} | Path",
+            "Recorder", "Size (bytes)", "Checksum"
+        )?;
+        for entry in &self.files {
+            write!(
+                f,
+                "\n{: <24} | {: <12} | {: <72} | {}",
+                entry.recorder,
+                entry.size,
+                entry.checksum,
+                entry.path.display()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metric::MetricF64;
+    use crate::recorders::{CsvWideFmtOutput, MetricSet, OutputMetric};
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+
+    #[test]
+    fn test_run_manifest() {
+        let mut model = simple_model(1, None);
+
+        let node_idx = model.network().get_node_index_by_name("output", None).unwrap();
+        let metric = OutputMetric::new("output", "inflow", "Output", None, MetricF64::NodeInFlow(node_idx));
+        let metric_set = MetricSet::new("nodes", None, vec![metric]);
+        let metric_set_idx = model.network_mut().add_metric_set(metric_set).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("outputs.csv");
+        let recorder = CsvWideFmtOutput::new("outputs", &path, metric_set_idx);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let manifest = result.manifest();
+        assert_eq!(manifest.len(), 1);
+
+        let entry = &manifest.files()[0];
+        assert_eq!(entry.recorder, "outputs");
+        assert_eq!(entry.path, path);
+        assert_eq!(entry.size, std::fs::metadata(&path).unwrap().len());
+        assert!(manifest.verify().is_empty());
+
+        // Modifying the file is detected by the manifest.
+        std::fs::write(&path, "corrupted").unwrap();
+        assert_eq!(manifest.verify().len(), 1);
+    }
+}
@@ -1,11 +1,17 @@
 use crate::models::ModelDomain;
 use crate::network::Network;
 use crate::recorders::aggregator::PeriodValue;
-use crate::recorders::{AggregationFunction, MetricSetIndex, MetricSetState, Recorder, RecorderMeta};
+use crate::recorders::buffer::{BufferError, BufferSettings, ResultBuffer};
+use crate::recorders::{
+    downcast_internal_state_mut, downcast_internal_state_ref, AggregationFunction, MetricFilter, MetricSet,
+    MetricSetIndex, MetricSetState, OutputDescription, Recorder, RecorderMeta, ResultsFrame, ResultsMetric, TimeFilter,
+};
 use crate::scenario::ScenarioIndex;
 use crate::state::State;
 use crate::timestep::Timestep;
 use crate::PywrError;
+use ndarray::Array3;
+use polars::prelude::DataFrame;
 use std::any::Any;
 use std::ops::Deref;
 use thiserror::Error;
@@ -17,6 +23,16 @@ pub enum AggregationError {
     AggregationFunctionNotDefined,
     #[error("Aggregation function failed.")]
     AggregationFunctionFailed,
+    #[error("Scenario group to aggregate over not defined.")]
+    ScenarioGroupNotDefined,
+    #[error("Failed to read the saved data: {0}")]
+    BufferReadFailed(String),
+}
+
+impl From<BufferError> for AggregationError {
+    fn from(error: BufferError) -> Self {
+        Self::BufferReadFailed(error.to_string())
+    }
 }
 
 pub struct Aggregation {
@@ -122,35 +138,105 @@ impl Aggregation {
     }
 }
 
+/// Values aggregated over a single scenario group.
+///
+/// There is a value for each combination of the remaining scenario groups. For example,
+/// aggregating over a group of hydrology members gives a value for each of the climate
+/// scenarios of another group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioGroupValues {
+    groups: Vec<String>,
+    values: Vec<(Vec<usize>, f64)>,
+}
+
+impl ScenarioGroupValues {
+    /// The names of the remaining scenario groups that index the values.
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    /// The aggregated values and the index of each of the remaining scenario groups, sorted by
+    /// the indices.
+    pub fn values(&self) -> &[(Vec<usize>, f64)] {
+        &self.values
+    }
+
+    /// Get the aggregated value for the given indices of the remaining scenario groups.
+    pub fn get(&self, indices: &[usize]) -> Option<f64> {
+        // The values are sorted by their indices
+        self.values
+            .binary_search_by(|(idx, _)| idx.as_slice().cmp(indices))
+            .ok()
+            .map(|i| self.values[i].1)
+    }
+}
+
 /// Internal state for the memory recorder.
 ///
-/// This is a 3D array, where the first dimension is the scenario, the second dimension is the time,
-/// and the third dimension is the metric.
+/// The saved data is a compressed buffer of the metric values of each scenario over time.
 struct InternalState {
-    data: Vec<Vec<PeriodValue<Vec<f64>>>>,
+    data: ResultBuffer,
+    /// The names of the scenario groups.
+    group_names: Vec<String>,
+    /// The index in each scenario group of each scenario.
+    group_indices: Vec<Vec<usize>>,
+    /// The index of the scenario group to aggregate over, if any.
+    scenario_group: Option<usize>,
+    /// The scenarios of each combination of the members of the remaining groups, if a scenario
+    /// group to aggregate over is defined (see [`crate::scenario::ScenarioDomain::partition_by_group`]).
+    partitions: Vec<(Vec<usize>, Vec<usize>)>,
 }
 
 impl InternalState {
-    fn new(num_scenarios: usize) -> Self {
-        let mut data: Vec<Vec<PeriodValue<Vec<f64>>>> = Vec::with_capacity(num_scenarios);
+    fn new(num_scenarios: usize, settings: BufferSettings) -> Self {
+        Self {
+            data: ResultBuffer::new(num_scenarios, settings),
+            group_names: vec!["default".to_string()],
+            group_indices: (0..num_scenarios).map(|i| vec![i]).collect(),
+            scenario_group: None,
+            partitions: Vec::new(),
+        }
+    }
 
-        for _ in 0..num_scenarios {
-            // We can't use `Vec::with_capacity` here because we don't know the number of
-            // periods that will be recorded.
-            data.push(Vec::new())
+    /// Create the internal state for the scenarios of `domain`, optionally aggregating over
+    /// the scenario group named `scenario_group`.
+    fn from_domain(
+        domain: &ModelDomain,
+        scenario_group: Option<&str>,
+        settings: BufferSettings,
+    ) -> Result<Self, PywrError> {
+        let scenarios = domain.scenarios();
+        let mut state = Self::new(scenarios.len(), settings);
+
+        state.group_names = scenarios.groups().iter().map(|g| g.name().to_string()).collect();
+        state.group_indices = scenarios.indices().iter().map(|si| si.indices.clone()).collect();
+        state.scenario_group = scenario_group
+            .map(|name| {
+                scenarios
+                    .group_index(name)
+                    .ok_or_else(|| PywrError::ScenarioNotFound(name.to_string()))
+            })
+            .transpose()?;
+        if let Some(group) = state.scenario_group {
+            state.partitions = scenarios.partition_by_group(group);
         }
 
-        Self { data }
+        Ok(state)
     }
 
     /// Aggregate over the saved data to a single value using the provided aggregation functions.
     ///
     /// This method will first aggregation over the metrics, then over time, and finally over the scenarios.
     fn aggregate_metric_time_scenario(&self, aggregation: &Aggregation) -> Result<f64, AggregationError> {
-        let scenario_data: Vec<f64> = self
-            .data
-            .iter()
-            .map(|time_data| {
+        let scenario_data = self.aggregate_metric_time(aggregation)?;
+        aggregation.apply_scenario_func(&scenario_data)
+    }
+
+    /// Aggregate over the metrics and then time for each scenario.
+    fn aggregate_metric_time(&self, aggregation: &Aggregation) -> Result<Vec<f64>, AggregationError> {
+        (0..self.data.num_scenarios())
+            .map(|scenario_index| {
+                let time_data = self.data.values(scenario_index)?;
                 // Aggregate each metric at each time step;
                 // this results in a time series iterator of aggregated values
                 let ts: Vec<PeriodValue<f64>> = time_data
@@ -160,19 +246,22 @@ impl InternalState {
 
                 aggregation.apply_time_func(&ts)
             })
-            .collect::<Result<_, _>>()?;
-
-        aggregation.apply_scenario_func(&scenario_data)
+            .collect()
     }
 
     /// Aggregate over the saved data to a single value using the provided aggregation functions.
     ///
     /// This method will first aggregation over time, then over the metrics, and finally over the scenarios.
     fn aggregate_time_metric_scenario(&self, aggregation: &Aggregation) -> Result<f64, AggregationError> {
-        let scenario_data: Vec<f64> = self
-            .data
-            .iter()
-            .map(|time_data| {
+        let scenario_data = self.aggregate_time_metric(aggregation)?;
+        aggregation.apply_scenario_func(&scenario_data)
+    }
+
+    /// Aggregate over time and then the metrics for each scenario.
+    fn aggregate_time_metric(&self, aggregation: &Aggregation) -> Result<Vec<f64>, AggregationError> {
+        (0..self.data.num_scenarios())
+            .map(|scenario_index| {
+                let time_data = self.data.values(scenario_index)?;
                 // We expect the same number of metrics in all the entries
                 let num_metrics = time_data.first().expect("No metrics found in time data").len();
 
@@ -186,9 +275,82 @@ impl InternalState {
                 // Now aggregate over the metrics
                 aggregation.apply_metric_func_f64(&metric_ts)
             })
-            .collect::<Result<_, _>>()?;
+            .collect()
+    }
 
-        aggregation.apply_scenario_func(&scenario_data)
+    /// The saved values of each scenario, period and metric of `metric_set`.
+    ///
+    /// Returns an error if the scenarios did not save the same number of periods.
+    fn to_results_frame(&self, recorder: &str, metric_set: &MetricSet) -> Result<ResultsFrame, PywrError> {
+        let metrics: Vec<ResultsMetric> = metric_set.iter_metrics().map(ResultsMetric::from).collect();
+
+        let scenario_values = (0..self.data.num_scenarios())
+            .map(|idx| self.data.values(idx))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let periods: Vec<_> = scenario_values
+            .first()
+            .map(|values| values.iter().map(|p| (p.start, p.end())).collect())
+            .unwrap_or_default();
+
+        // The frame is only rectangular if every scenario saved the same periods
+        if let Some((scenario_index, values)) = scenario_values
+            .iter()
+            .enumerate()
+            .find(|(_, values)| values.len() != periods.len())
+        {
+            return Err(PywrError::ResultsPeriodsMismatch {
+                recorder: recorder.to_string(),
+                scenario_index,
+                expected: periods.len(),
+                found: values.len(),
+            });
+        }
+
+        let values = Array3::from_shape_fn(
+            (scenario_values.len(), periods.len(), metrics.len()),
+            |(scenario, period, metric)| scenario_values[scenario][period].value[metric],
+        );
+
+        Ok(ResultsFrame::new(
+            metric_set.name(),
+            metrics,
+            periods,
+            self.group_names.clone(),
+            self.group_indices.clone(),
+            values,
+        ))
+    }
+
+    /// Aggregate the values of each scenario over the scenario group only.
+    ///
+    /// The scenarios are partitioned by their indices in the remaining scenario groups, and the
+    /// scenario aggregation function is applied to each of those partitions.
+    fn aggregate_scenario_group(
+        &self,
+        scenario_data: &[f64],
+        aggregation: &Aggregation,
+    ) -> Result<ScenarioGroupValues, AggregationError> {
+        let group = self.scenario_group.ok_or(AggregationError::ScenarioGroupNotDefined)?;
+
+        let values = self
+            .partitions
+            .iter()
+            .map(|(key, scenarios)| {
+                let values: Vec<f64> = scenarios.iter().map(|&s| scenario_data[s]).collect();
+                Ok((key.clone(), aggregation.apply_scenario_func(&values)?))
+            })
+            .collect::<Result<_, AggregationError>>()?;
+
+        let groups = self
+            .group_names
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != group)
+            .map(|(_, name)| name.clone())
+            .collect();
+
+        Ok(ScenarioGroupValues { groups, values })
     }
 }
 
@@ -202,16 +364,21 @@ pub enum AggregationOrder {
 /// A recorder that saves the metric values to memory.
 ///
 /// This recorder saves data into memory and can be used to provide aggregated data for external
-/// analysis. The data is saved in a 3D array, where the first dimension is the scenario, the second
-/// dimension is the time, and the third dimension is the metric.
+/// analysis. The values of each scenario are saved in compressed blocks of time (see
+/// [`ResultBuffer`]), and are only decompressed one scenario at a time when they are aggregated.
 ///
-/// Users should be aware that this recorder can consume a large amount of memory if the number of
-/// scenarios, time steps, and metrics is large.
+/// The memory used by the recorder can be limited with [`MemoryRecorder::with_buffer_settings`];
+/// data in excess of the memory budget is written to a temporary file.
+///
+/// Only the periods that match the [`TimeFilter`], if any, are saved and therefore aggregated.
 pub struct MemoryRecorder {
     meta: RecorderMeta,
     metric_set_idx: MetricSetIndex,
     aggregation: Aggregation,
     order: AggregationOrder,
+    scenario_group: Option<String>,
+    buffer_settings: BufferSettings,
+    time_filter: TimeFilter,
 }
 
 impl MemoryRecorder {
@@ -221,6 +388,42 @@ impl MemoryRecorder {
             metric_set_idx,
             aggregation,
             order,
+            scenario_group: None,
+            buffer_settings: BufferSettings::default(),
+            time_filter: TimeFilter::default(),
+        }
+    }
+
+    /// Use the given settings for the buffer in which the data is saved.
+    pub fn with_buffer_settings(mut self, buffer_settings: BufferSettings) -> Self {
+        self.buffer_settings = buffer_settings;
+        self
+    }
+
+    /// Only save the periods that match `time_filter`.
+    pub fn with_time_filter(mut self, time_filter: TimeFilter) -> Self {
+        self.time_filter = time_filter;
+        self
+    }
+
+    /// Also aggregate over the named scenario group only, keeping the other groups separate.
+    ///
+    /// See [`Recorder::aggregated_values_by_scenario_group`].
+    pub fn with_scenario_group(mut self, scenario_group: &str) -> Self {
+        self.scenario_group = Some(scenario_group.to_string());
+        self
+    }
+
+    /// Returns true if the values of a period match the time filter.
+    fn is_saved(&self, values: &[PeriodValue<f64>]) -> bool {
+        values.first().map_or(true, |v| self.time_filter.is_match(&v.start))
+    }
+
+    /// Aggregate the saved data over the metrics and time, in the configured order.
+    fn aggregate_scenarios(&self, internal_state: &InternalState) -> Result<Vec<f64>, AggregationError> {
+        match self.order {
+            AggregationOrder::MetricTimeScenario => internal_state.aggregate_metric_time(&self.aggregation),
+            AggregationOrder::TimeMetricScenario => internal_state.aggregate_time_metric(&self.aggregation),
         }
     }
 }
@@ -230,8 +433,28 @@ impl Recorder for MemoryRecorder {
         &self.meta
     }
 
-    fn setup(&self, domain: &ModelDomain, _network: &Network) -> Result<Option<Box<(dyn Any)>>, PywrError> {
-        let data = InternalState::new(domain.scenarios().len());
+    fn describe(&self, domain: &ModelDomain, network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        let metric_set = network.get_metric_set(self.metric_set_idx)?;
+        let description = metric_set.describe(domain, &MetricFilter::default(), metric_set.num_periods(domain));
+
+        Ok(Some(OutputDescription::new(
+            self.name(),
+            "memory",
+            None,
+            vec![description],
+        )))
+    }
+
+    fn setup(&self, domain: &ModelDomain, network: &Network) -> Result<Option<Box<(dyn Any)>>, PywrError> {
+        let metric_set = network.get_metric_set(self.metric_set_idx)?;
+        if !metric_set.scenario_statistics().is_empty() {
+            return Err(PywrError::ScenarioStatisticsNotSupported {
+                metric_set: metric_set.name().to_string(),
+                recorder: self.name().to_string(),
+            });
+        }
+
+        let data = InternalState::from_domain(domain, self.scenario_group.as_deref(), self.buffer_settings.clone())?;
 
         Ok(Some(Box::new(data)))
     }
@@ -245,22 +468,18 @@ impl Recorder for MemoryRecorder {
         metric_set_states: &[Vec<MetricSetState>],
         internal_state: &mut Option<Box<dyn Any>>,
     ) -> Result<(), PywrError> {
-        let internal_state = match internal_state {
-            Some(internal) => match internal.downcast_mut::<InternalState>() {
-                Some(pa) => pa,
-                None => panic!("Internal state did not downcast to the correct type! :("),
-            },
-            None => panic!("No internal state defined when one was expected! :("),
-        };
+        let internal_state = downcast_internal_state_mut::<InternalState>(self.name(), internal_state)?;
 
         // Iterate through all of the scenario's state
-        for (ms_scenario_states, scenario_data) in metric_set_states.iter().zip(internal_state.data.iter_mut()) {
+        for (scenario_index, ms_scenario_states) in metric_set_states.iter().enumerate() {
             let metric_set_state = ms_scenario_states
                 .get(*self.metric_set_idx.deref())
                 .ok_or(PywrError::MetricSetIndexNotFound(self.metric_set_idx))?;
 
             if let Some(current_values) = metric_set_state.current_values() {
-                scenario_data.push(current_values.into());
+                if self.is_saved(current_values) {
+                    internal_state.data.push(scenario_index, current_values.into())?;
+                }
             }
         }
 
@@ -273,22 +492,18 @@ impl Recorder for MemoryRecorder {
         metric_set_states: &[Vec<MetricSetState>],
         internal_state: &mut Option<Box<dyn Any>>,
     ) -> Result<(), PywrError> {
-        let internal_state = match internal_state {
-            Some(internal) => match internal.downcast_mut::<InternalState>() {
-                Some(pa) => pa,
-                None => panic!("Internal state did not downcast to the correct type! :("),
-            },
-            None => panic!("No internal state defined when one was expected! :("),
-        };
+        let internal_state = downcast_internal_state_mut::<InternalState>(self.name(), internal_state)?;
 
         // Iterate through all of the scenario's state
-        for (ms_scenario_states, scenario_data) in metric_set_states.iter().zip(internal_state.data.iter_mut()) {
+        for (scenario_index, ms_scenario_states) in metric_set_states.iter().enumerate() {
             let metric_set_state = ms_scenario_states
                 .get(*self.metric_set_idx.deref())
                 .ok_or(PywrError::MetricSetIndexNotFound(self.metric_set_idx))?;
 
             if let Some(current_values) = metric_set_state.current_values() {
-                scenario_data.push(current_values.into());
+                if self.is_saved(current_values) {
+                    internal_state.data.push(scenario_index, current_values.into())?;
+                }
             }
         }
 
@@ -299,13 +514,7 @@ impl Recorder for MemoryRecorder {
     ///
     /// This method will first aggregation over the metrics, then over time, and finally over the scenarios.
     fn aggregated_value(&self, internal_state: &Option<Box<dyn Any>>) -> Result<f64, PywrError> {
-        let internal_state = match internal_state {
-            Some(internal) => match internal.downcast_ref::<InternalState>() {
-                Some(pa) => pa,
-                None => panic!("Internal state did not downcast to the correct type! :("),
-            },
-            None => panic!("No internal state defined when one was expected! :("),
-        };
+        let internal_state = downcast_internal_state_ref::<InternalState>(self.name(), internal_state)?;
 
         let agg_value = match self.order {
             AggregationOrder::MetricTimeScenario => internal_state.aggregate_metric_time_scenario(&self.aggregation)?,
@@ -314,15 +523,66 @@ impl Recorder for MemoryRecorder {
 
         Ok(agg_value)
     }
+
+    /// The saved values in long format, with a row for each scenario, time period and metric.
+    ///
+    /// The columns are `time_start`, `time_end`, `scenario_index`, the index of the scenario in
+    /// each scenario group (named after the group), `metric_set`, `name`, `attribute` and `value`.
+    fn to_dataframe(
+        &self,
+        network: &Network,
+        internal_state: &Option<Box<dyn Any>>,
+    ) -> Result<Option<DataFrame>, PywrError> {
+        let internal_state = downcast_internal_state_ref::<InternalState>(self.name(), internal_state)?;
+        let metric_set = network.get_metric_set(self.metric_set_idx)?;
+
+        Ok(Some(
+            internal_state
+                .to_results_frame(self.name(), metric_set)?
+                .to_dataframe()?,
+        ))
+    }
+
+    fn to_results_frame(
+        &self,
+        network: &Network,
+        internal_state: &Option<Box<dyn Any>>,
+    ) -> Result<Option<ResultsFrame>, PywrError> {
+        let internal_state = downcast_internal_state_ref::<InternalState>(self.name(), internal_state)?;
+        let metric_set = network.get_metric_set(self.metric_set_idx)?;
+
+        Ok(Some(internal_state.to_results_frame(self.name(), metric_set)?))
+    }
+
+    /// Aggregate the saved data over the metrics and time, and then over the scenario group
+    /// given by [`MemoryRecorder::with_scenario_group`] only.
+    fn aggregated_values_by_scenario_group(
+        &self,
+        internal_state: &Option<Box<dyn Any>>,
+    ) -> Result<ScenarioGroupValues, PywrError> {
+        let internal_state = downcast_internal_state_ref::<InternalState>(self.name(), internal_state)?;
+
+        let scenario_data = self.aggregate_scenarios(internal_state)?;
+        Ok(internal_state.aggregate_scenario_group(&scenario_data, &self.aggregation)?)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Aggregation, InternalState};
+    use super::{Aggregation, AggregationOrder, InternalState, MemoryRecorder};
+    use crate::metric::MetricF64;
+    use crate::models::{Model, ModelDomain};
+    use crate::network::Network;
     use crate::recorders::aggregator::PeriodValue;
+    use crate::recorders::buffer::BufferSettings;
     use crate::recorders::AggregationFunction;
+    use crate::recorders::{MetricSet, OutputMetric, ScenarioStatistic};
+    use crate::scenario::ScenarioGroupCollection;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
     use crate::test_utils::default_timestepper;
+    use crate::test_utils::simple_network;
     use crate::timestep::TimeDomain;
+    use crate::PywrError;
     use float_cmp::assert_approx_eq;
     use rand::{Rng, SeedableRng};
     use rand_chacha::ChaCha8Rng;
@@ -332,7 +592,7 @@ mod tests {
     fn test_aggregation_orders() {
         let num_scenarios = 2;
         let num_metrics = 3;
-        let mut state = InternalState::new(num_scenarios);
+        let mut state = InternalState::new(num_scenarios, BufferSettings::default().with_block_len(4));
 
         let mut rng = ChaCha8Rng::seed_from_u64(0);
         let dist: Normal<f64> = Normal::new(0.0, 1.0).unwrap();
@@ -343,7 +603,7 @@ mod tests {
         let mut count_non_zero_by_metric = vec![0.0; num_metrics];
 
         time_domain.timesteps().iter().for_each(|timestep| {
-            state.data.iter_mut().for_each(|scenario_data| {
+            (0..num_scenarios).for_each(|scenario_index| {
                 let metric_data = (&mut rng).sample_iter(&dist).take(num_metrics).collect::<Vec<f64>>();
 
                 // Compute the expected values
@@ -359,7 +619,7 @@ mod tests {
 
                 let metric_data = PeriodValue::new(timestep.date, timestep.duration, metric_data);
 
-                scenario_data.push(metric_data);
+                state.data.push(scenario_index, metric_data).unwrap();
             });
         });
 
@@ -374,4 +634,186 @@ mod tests {
         let agg_value = state.aggregate_time_metric_scenario(&agg).expect("Aggregation failed");
         assert_approx_eq!(f64, agg_value, count_non_zero_by_metric.iter().sum());
     }
+
+    #[test]
+    fn test_scenario_group_aggregation() {
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("climate", 2);
+        scenario_collection.add_group("hydrology", 3);
+        let domain = ModelDomain::from(default_timestepper(), scenario_collection).unwrap();
+
+        // The inflow varies with the hydrology member only
+        let mut network = Network::default();
+        simple_network(&mut network, 1, 3);
+
+        let idx = network.get_node_index_by_name("input", None).unwrap();
+        let metric = OutputMetric::new("input", "outflow", "Input", None, MetricF64::NodeOutFlow(idx));
+        let metric_set_idx = network
+            .add_metric_set(MetricSet::new("nodes", None, vec![metric]))
+            .unwrap();
+
+        let recorder = MemoryRecorder::new(
+            "outputs",
+            metric_set_idx,
+            Aggregation::new(Some(AggregationFunction::Mean), Some(AggregationFunction::Sum), None),
+            AggregationOrder::default(),
+        )
+        .with_scenario_group("hydrology");
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(domain, network);
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let values = model
+            .network()
+            .get_aggregated_values_by_scenario_group("outputs", result.recorder_states())
+            .unwrap();
+
+        assert_eq!(values.groups(), &["climate".to_string()]);
+        assert_eq!(values.values().len(), 2);
+
+        // The inflow in the first 12 days is `1 + ts.index + hydrology index` and is limited to
+        // 12.0 thereafter; the sum over the 15 days is averaged over the hydrology members.
+        let total = |j: usize| (0..15).map(|i| (1.0 + i as f64 + j as f64).min(12.0)).sum::<f64>();
+        let expected = (0..3).map(total).sum::<f64>() / 3.0;
+        for climate in 0..2 {
+            assert_approx_eq!(f64, values.get(&[climate]).unwrap(), expected);
+        }
+
+        // The flat aggregation over all the scenarios is unchanged
+        let flat = model
+            .network()
+            .get_aggregated_value("outputs", result.recorder_states())
+            .unwrap();
+        assert_approx_eq!(f64, flat, expected);
+    }
+
+    #[test]
+    fn test_scenario_statistics_not_supported() {
+        let domain = ModelDomain::from(default_timestepper(), ScenarioGroupCollection::default()).unwrap();
+
+        let mut network = Network::default();
+        simple_network(&mut network, 0, 1);
+
+        let idx = network.get_node_index_by_name("input", None).unwrap();
+        let metric = OutputMetric::new("input", "outflow", "Input", None, MetricF64::NodeOutFlow(idx));
+        let metric_set = MetricSet::new("nodes", None, vec![metric])
+            .with_scenario_statistics(vec![ScenarioStatistic::new("max", AggregationFunction::Max)]);
+        let metric_set_idx = network.add_metric_set(metric_set).unwrap();
+
+        let recorder = MemoryRecorder::new(
+            "outputs",
+            metric_set_idx,
+            Aggregation::new(None, None, None),
+            AggregationOrder::default(),
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(domain, network);
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default());
+        assert!(matches!(result, Err(PywrError::ScenarioStatisticsNotSupported { .. })));
+    }
+
+    #[test]
+    fn test_results_frame_periods_mismatch() {
+        let time_domain: TimeDomain = default_timestepper().try_into().unwrap();
+        let mut state = InternalState::new(2, BufferSettings::default());
+
+        // The second scenario saves one period fewer than the first
+        for (t, timestep) in time_domain.timesteps().iter().enumerate() {
+            for s in 0..2 {
+                if s == 1 && t == 0 {
+                    continue;
+                }
+                let value = PeriodValue::new(timestep.date, timestep.duration, vec![t as f64]);
+                state.data.push(s, value).unwrap();
+            }
+        }
+
+        let metric_set = MetricSet::new("nodes", None, vec![]);
+        let result = state.to_results_frame("outputs", &metric_set);
+        assert!(matches!(
+            result,
+            Err(PywrError::ResultsPeriodsMismatch {
+                scenario_index: 1,
+                expected: 15,
+                found: 14,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_to_dataframe() {
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("climate", 2);
+        scenario_collection.add_group("hydrology", 3);
+        let domain = ModelDomain::from(default_timestepper(), scenario_collection).unwrap();
+
+        let mut network = Network::default();
+        simple_network(&mut network, 1, 3);
+
+        let idx = network.get_node_index_by_name("input", None).unwrap();
+        let metric = OutputMetric::new("input", "outflow", "Input", None, MetricF64::NodeOutFlow(idx));
+        let metric_set_idx = network
+            .add_metric_set(MetricSet::new("nodes", None, vec![metric]))
+            .unwrap();
+
+        // No aggregation is required to retrieve the values
+        let recorder = MemoryRecorder::new(
+            "outputs",
+            metric_set_idx,
+            Aggregation::new(None, None, None),
+            AggregationOrder::default(),
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(domain, network);
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let df = model
+            .network()
+            .get_recorder_dataframe("outputs", result.recorder_states())
+            .unwrap();
+
+        // A row for each of the 15 time-steps of the 6 scenarios
+        assert_eq!(df.height(), 90);
+        let names: Vec<&str> = df.get_column_names().iter().map(|n| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "time_start",
+                "time_end",
+                "scenario_index",
+                "climate",
+                "hydrology",
+                "metric_set",
+                "name",
+                "attribute",
+                "value"
+            ]
+        );
+
+        // The inflow in the first time-step is `1 + hydrology index`
+        let hydrology = df.column("hydrology").unwrap().u64().unwrap();
+        let values = df.column("value").unwrap().f64().unwrap();
+        for scenario in 0..6 {
+            let row = scenario * 15;
+            let expected = 1.0 + hydrology.get(row).unwrap() as f64;
+            assert_approx_eq!(f64, values.get(row).unwrap(), expected);
+        }
+
+        // The same values are returned from the run as typed results
+        let results = result.results("outputs").unwrap();
+        assert_eq!(results.metric_set(), "nodes");
+        assert_eq!(results.num_scenarios(), 6);
+        assert_eq!(results.periods().len(), 15);
+
+        let input = results.metric_index("input", Some("outflow")).unwrap();
+        for scenario in 0..6 {
+            let hydrology = results.scenario_group_indices(scenario).unwrap()[1];
+            let series = results.series(input, scenario).unwrap();
+            assert_approx_eq!(f64, series[0], 1.0 + hydrology as f64);
+        }
+    }
 }
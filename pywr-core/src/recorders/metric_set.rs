@@ -1,10 +1,14 @@
 use crate::metric::MetricF64;
+use crate::models::ModelDomain;
 use crate::network::Network;
-use crate::recorders::aggregator::{Aggregator, AggregatorState, PeriodValue};
+use crate::recorders::aggregator::{AggregationFunction, Aggregator, AggregatorState, PeriodValue};
+use crate::recorders::description::{num_periods, MetricSetDescription, OutputDimension, OutputMetricDescription};
+use crate::recorders::AggregationError;
 use crate::scenario::ScenarioIndex;
 use crate::state::State;
-use crate::timestep::Timestep;
+use crate::timestep::{DayOfYearWindow, Timestep};
 use crate::PywrError;
+use chrono::NaiveDateTime;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
@@ -21,7 +25,46 @@ pub struct OutputMetric {
     ty: String,
     // The originating subtype of the metric (e.g. node type, parameter type, etc.)
     sub_type: Option<String>,
-    metric: MetricF64,
+    source: OutputMetricSource,
+}
+
+/// The calculation of a derived [`OutputMetric`] from the values of two other metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DerivedOutputOperation {
+    /// The first value divided by the second.
+    Ratio,
+    /// The first value minus the second.
+    Difference,
+    /// The sum of the two values.
+    Sum,
+}
+
+impl DerivedOutputOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ratio => "ratio",
+            Self::Difference => "difference",
+            Self::Sum => "sum",
+        }
+    }
+
+    fn apply(&self, lhs: f64, rhs: f64) -> f64 {
+        match self {
+            Self::Ratio => lhs / rhs,
+            Self::Difference => lhs - rhs,
+            Self::Sum => lhs + rhs,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum OutputMetricSource {
+    Metric(MetricF64),
+    Derived {
+        operation: DerivedOutputOperation,
+        lhs: Box<OutputMetric>,
+        rhs: Box<OutputMetric>,
+    },
 }
 
 impl OutputMetric {
@@ -31,12 +74,43 @@ impl OutputMetric {
             attribute: attribute.to_string(),
             ty: ty.to_string(),
             sub_type: sub_type.map(|s| s.to_string()),
-            metric,
+            source: OutputMetricSource::Metric(metric),
+        }
+    }
+
+    /// Create a metric whose value is calculated from the values of two other metrics.
+    ///
+    /// The value is calculated when it is saved by a [`MetricSet`] (i.e. before any aggregation
+    /// by the set), so that common indicators (e.g. the ratio of supply to demand) can be output
+    /// without adding parameters to the model. A ratio with a denominator of zero is infinite,
+    /// or NaN if the numerator is also zero.
+    pub fn derived(
+        name: &str,
+        attribute: &str,
+        operation: DerivedOutputOperation,
+        lhs: OutputMetric,
+        rhs: OutputMetric,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            attribute: attribute.to_string(),
+            ty: "Derived".to_string(),
+            sub_type: Some(operation.name().to_string()),
+            source: OutputMetricSource::Derived {
+                operation,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            },
         }
     }
 
     pub fn get_value(&self, model: &Network, state: &State) -> Result<f64, PywrError> {
-        self.metric.get_value(model, state)
+        match &self.source {
+            OutputMetricSource::Metric(metric) => metric.get_value(model, state),
+            OutputMetricSource::Derived { operation, lhs, rhs } => {
+                Ok(operation.apply(lhs.get_value(model, state)?, rhs.get_value(model, state)?))
+            }
+        }
     }
 
     pub fn name(&self) -> &str {
@@ -55,6 +129,66 @@ impl OutputMetric {
     }
 }
 
+/// A filter on the names of the metrics written by an output.
+///
+/// If `include` is given only the metrics with those names are retained. Any metrics with a
+/// name in `exclude` are then removed. The default filter retains all metrics.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricFilter {
+    include: Option<Vec<String>>,
+    exclude: Vec<String>,
+}
+
+impl MetricFilter {
+    pub fn new(include: Option<Vec<String>>, exclude: Vec<String>) -> Self {
+        Self { include, exclude }
+    }
+
+    /// Returns true if the metric should be retained by this filter.
+    pub fn is_match(&self, metric: &OutputMetric) -> bool {
+        let included = match &self.include {
+            Some(include) => include.iter().any(|n| n == metric.name()),
+            None => true,
+        };
+
+        included && !self.exclude.iter().any(|n| n == metric.name())
+    }
+}
+
+/// A filter on the periods written by an output.
+///
+/// A period is retained if it starts between `start` and `end` (inclusive), and within the
+/// window of days of each year if one is given. The default filter retains all periods.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimeFilter {
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    window: Option<DayOfYearWindow>,
+}
+
+impl TimeFilter {
+    pub fn new(start: Option<NaiveDateTime>, end: Option<NaiveDateTime>) -> Self {
+        Self {
+            start,
+            end,
+            window: None,
+        }
+    }
+
+    /// Only retain the periods that start within `window` of each year.
+    pub fn with_window(mut self, window: DayOfYearWindow) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Returns true if the period starting at `start` should be retained by this filter.
+    pub fn is_match(&self, start: &NaiveDateTime) -> bool {
+        self.start.map_or(true, |s| *start >= s)
+            && self.end.map_or(true, |e| *start <= e)
+            && self.window.map_or(true, |w| w.contains(start))
+    }
+}
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub struct MetricSetIndex(usize);
 
@@ -92,12 +226,62 @@ impl MetricSetState {
     }
 }
 
+/// A statistic of the values of each metric computed across all of the scenarios.
+///
+/// For example, the 90th percentile of the flows in an ensemble of hydrological scenarios, or
+/// the proportion of the scenarios in which a demand is not met.
+#[derive(Clone, Debug)]
+pub struct ScenarioStatistic {
+    label: String,
+    function: AggregationFunction,
+}
+
+impl ScenarioStatistic {
+    pub fn new(label: &str, function: AggregationFunction) -> Self {
+        Self {
+            label: label.to_string(),
+            function,
+        }
+    }
+
+    /// The label of the statistic used in place of the scenario in the outputs.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// The scenarios over which the scenario statistics of a [`MetricSet`] are computed.
+///
+/// There is a single group of all the scenarios unless the statistics are computed over a
+/// scenario group (see [`MetricSet::with_scenario_group`]). In that case there is a group for
+/// each combination of the members of the remaining scenario groups.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioStatisticGroup {
+    /// The members of the remaining scenario groups (e.g. `climate=1`), if any.
+    label: Option<String>,
+    /// The position of each scenario of the group.
+    scenarios: Vec<usize>,
+}
+
+impl ScenarioStatisticGroup {
+    /// The label of `statistic` computed over the scenarios of this group.
+    fn statistic_label(&self, statistic: &ScenarioStatistic) -> String {
+        match &self.label {
+            Some(label) => format!("{}[{}]", statistic.label, label),
+            None => statistic.label.clone(),
+        }
+    }
+}
+
 /// A set of metrics with an optional aggregator
 #[derive(Clone, Debug)]
 pub struct MetricSet {
     name: String,
     aggregator: Option<Aggregator>,
     metrics: Vec<OutputMetric>,
+    units: Option<String>,
+    scenario_statistics: Vec<ScenarioStatistic>,
+    scenario_group: Option<String>,
 }
 
 impl MetricSet {
@@ -106,13 +290,179 @@ impl MetricSet {
             name: name.to_string(),
             aggregator,
             metrics,
+            units: None,
+            scenario_statistics: Vec::new(),
+            scenario_group: None,
         }
     }
 
+    /// Set the units of the metrics in the set.
+    ///
+    /// The units are not used by the model; they are only reported in the [`MetricSetDescription`].
+    pub fn with_units(mut self, units: &str) -> Self {
+        self.units = Some(units.to_string());
+        self
+    }
+
+    /// Compute the given statistics of the values of each metric across all of the scenarios.
+    ///
+    /// The values of each metric are first aggregated over time by the aggregator of the set, if
+    /// any. The CSV outputs write the value of each statistic instead of the values of each
+    /// scenario; the other outputs do not support metric sets with scenario statistics.
+    pub fn with_scenario_statistics(mut self, scenario_statistics: Vec<ScenarioStatistic>) -> Self {
+        self.scenario_statistics = scenario_statistics;
+        self
+    }
+
+    /// Compute the scenario statistics over the members of the named scenario group only.
+    ///
+    /// The statistics are computed separately for each combination of the members of the
+    /// remaining scenario groups. For example, the mean over a group of hydrology members gives a
+    /// value for each of the climate scenarios of another group. The label of each statistic is
+    /// then followed by the members of the remaining groups (e.g. `mean[climate=1]`).
+    pub fn with_scenario_group(mut self, scenario_group: &str) -> Self {
+        self.scenario_group = Some(scenario_group.to_string());
+        self
+    }
+
     /// The name of the [`MetricSet`].
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn units(&self) -> Option<&str> {
+        self.units.as_deref()
+    }
+
+    pub fn aggregator(&self) -> Option<&Aggregator> {
+        self.aggregator.as_ref()
+    }
+
+    pub fn scenario_statistics(&self) -> &[ScenarioStatistic] {
+        &self.scenario_statistics
+    }
+
+    pub fn scenario_group(&self) -> Option<&str> {
+        self.scenario_group.as_deref()
+    }
+
+    /// The groups of the scenarios of `domain` over which the scenario statistics are computed.
+    ///
+    /// Returns an error if the scenario group of the set is not found in `domain`.
+    pub fn scenario_statistic_groups(&self, domain: &ModelDomain) -> Result<Vec<ScenarioStatisticGroup>, PywrError> {
+        let scenarios = domain.scenarios();
+
+        let Some(name) = &self.scenario_group else {
+            return Ok(vec![ScenarioStatisticGroup {
+                label: None,
+                scenarios: (0..scenarios.len()).collect(),
+            }]);
+        };
+
+        let group_idx = scenarios
+            .group_index(name)
+            .ok_or_else(|| PywrError::ScenarioNotFound(name.clone()))?;
+
+        let groups = scenarios
+            .partition_by_group(group_idx)
+            .into_iter()
+            .map(|(remaining, positions)| {
+                let label = scenarios
+                    .groups()
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != group_idx)
+                    .zip(remaining)
+                    .map(|((_, group), member)| format!("{}={}", group.name(), member))
+                    .collect::<Vec<_>>()
+                    .join(",");
+
+                ScenarioStatisticGroup {
+                    label: Some(label),
+                    scenarios: positions,
+                }
+            })
+            .collect();
+
+        Ok(groups)
+    }
+
+    /// The label of each scenario statistic computed over each of `groups`, in the order of
+    /// the values returned by [`MetricSet::calc_scenario_statistics`].
+    pub fn scenario_statistic_labels(&self, groups: &[ScenarioStatisticGroup]) -> Vec<String> {
+        self.scenario_statistics
+            .iter()
+            .flat_map(|statistic| groups.iter().map(move |group| group.statistic_label(statistic)))
+            .collect()
+    }
+
+    /// The number of values of each metric yielded by the set over a run of `domain`.
+    pub fn num_periods(&self, domain: &ModelDomain) -> usize {
+        match &self.aggregator {
+            Some(aggregator) => num_periods(aggregator, domain),
+            None => domain.time().len(),
+        }
+    }
+
+    /// Describe the values of the metrics in this set that match `filter`.
+    ///
+    /// The values have a dimension for each time period, scenario group and metric. `num_periods`
+    /// is the number of values of each metric in each scenario (see [`MetricSet::num_periods`]).
+    pub fn describe(&self, domain: &ModelDomain, filter: &MetricFilter, num_periods: usize) -> MetricSetDescription {
+        let scenario_dimensions = domain
+            .scenarios()
+            .groups()
+            .iter()
+            .map(|group| {
+                let labels = group.members().map(|m| m.to_string()).collect();
+                OutputDimension::new(group.name(), group.len(), Some(labels))
+            })
+            .collect();
+
+        self.describe_dimensions(filter, num_periods, scenario_dimensions)
+    }
+
+    /// Describe the scenario statistics of the metrics in this set that match `filter`.
+    ///
+    /// This is the same as [`MetricSet::describe`] except that the scenario groups are replaced
+    /// by a single dimension for the statistics.
+    pub fn describe_scenario_statistics(
+        &self,
+        domain: &ModelDomain,
+        filter: &MetricFilter,
+        num_periods: usize,
+    ) -> Result<MetricSetDescription, PywrError> {
+        let labels = self.scenario_statistic_labels(&self.scenario_statistic_groups(domain)?);
+        let dimension = OutputDimension::new("statistic", labels.len(), Some(labels));
+
+        Ok(self.describe_dimensions(filter, num_periods, vec![dimension]))
+    }
+
+    fn describe_dimensions(
+        &self,
+        filter: &MetricFilter,
+        num_periods: usize,
+        scenario_dimensions: Vec<OutputDimension>,
+    ) -> MetricSetDescription {
+        let metrics: Vec<_> = self
+            .metrics
+            .iter()
+            .filter(|m| filter.is_match(m))
+            .map(OutputMetricDescription::from)
+            .collect();
+
+        let mut dimensions = vec![OutputDimension::new("time", num_periods, None)];
+        dimensions.extend(scenario_dimensions);
+        let labels = metrics.iter().map(|m| format!("{}/{}", m.name, m.attribute)).collect();
+        dimensions.push(OutputDimension::new("metric", metrics.len(), Some(labels)));
+
+        MetricSetDescription {
+            name: self.name.clone(),
+            units: self.units.clone(),
+            metrics,
+            dimensions,
+        }
+    }
     pub fn iter_metrics(&self) -> impl Iterator<Item = &OutputMetric> + '_ {
         self.metrics.iter()
     }
@@ -149,11 +499,10 @@ impl MetricSet {
         if let Some(aggregator) = &self.aggregator {
             // Apply aggregation if required
 
-            // TODO: make this a `PywrError`.
             let aggregation_states = internal_state
                 .aggregation_states
                 .as_mut()
-                .expect("Aggregation state expected for metric set with aggregator!");
+                .ok_or_else(|| PywrError::MetricSetAggregationStateNotFound(self.name.clone()))?;
 
             // Collect any aggregated values. This will remain empty if the aggregator yields
             // no values. However, if there are values we will expect the same number of aggregated
@@ -162,7 +511,7 @@ impl MetricSet {
             // Use a for loop instead of using an iterator because we need to execute the
             // `append_value` method on all aggregators.
             for (value, current_state) in values.iter().zip(aggregation_states.iter_mut()) {
-                if let Some(agg_value) = aggregator.append_value(current_state, *value) {
+                if let Some(agg_value) = aggregator.append_value(current_state, *value)? {
                     agg_values.push(agg_value);
                 }
             }
@@ -185,21 +534,257 @@ impl MetricSet {
         Ok(())
     }
 
-    pub fn finalise(&self, internal_state: &mut MetricSetState) {
+    /// Calculate the scenario statistics of the current values of each metric.
+    ///
+    /// `states` is the state of this set in each scenario, and `groups` are the groups of those
+    /// scenarios over which the statistics are computed (see
+    /// [`MetricSet::scenario_statistic_groups`]). The values are returned for each statistic,
+    /// then each group and then each metric, or `None` if the set yielded no values in the last
+    /// time-step.
+    pub fn calc_scenario_statistics(
+        &self,
+        states: &[&MetricSetState],
+        groups: &[ScenarioStatisticGroup],
+    ) -> Result<Option<Vec<Vec<PeriodValue<f64>>>>, PywrError> {
+        let scenario_values: Vec<Option<&[PeriodValue<f64>]>> = states.iter().map(|s| s.current_values()).collect();
+
+        let first = match scenario_values.iter().flatten().next() {
+            Some(first) => *first,
+            None => return Ok(None),
+        };
+
+        let statistics = self
+            .scenario_statistics
+            .iter()
+            .flat_map(|statistic| groups.iter().map(move |group| (statistic, group)))
+            .map(|(statistic, group)| {
+                first
+                    .iter()
+                    .enumerate()
+                    .map(|(metric_idx, period)| {
+                        let values: Vec<f64> = group
+                            .scenarios
+                            .iter()
+                            .filter_map(|&s| scenario_values.get(s).copied().flatten())
+                            .map(|v| v[metric_idx].value)
+                            .collect();
+                        let value = statistic
+                            .function
+                            .calc_f64(&values)
+                            .ok_or(AggregationError::AggregationFunctionFailed)?;
+                        Ok(PeriodValue::new(period.start, period.duration, value))
+                    })
+                    .collect::<Result<Vec<_>, PywrError>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(statistics))
+    }
+
+    pub fn finalise(&self, internal_state: &mut MetricSetState) -> Result<(), PywrError> {
         if let Some(aggregator) = &self.aggregator {
             let aggregation_states = internal_state
                 .aggregation_states
                 .as_mut()
-                .expect("Aggregation state expected for metric set with aggregator!");
+                .ok_or_else(|| PywrError::MetricSetAggregationStateNotFound(self.name.clone()))?;
 
             let final_values = aggregation_states
                 .iter_mut()
                 .map(|current_state| aggregator.finalise(current_state))
-                .collect::<Option<Vec<_>>>();
+                .collect::<Result<Option<Vec<_>>, _>>()?;
 
             internal_state.current_values = final_values;
         } else {
             internal_state.current_values = None;
         }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DerivedOutputOperation, MetricFilter, MetricSet, MetricSetState, OutputMetric, ScenarioStatistic, TimeFilter,
+    };
+    use crate::metric::{ConstantMetricF64, MetricF64, SimpleMetricF64};
+    use crate::models::ModelDomain;
+    use crate::recorders::aggregator::PeriodValue;
+    use crate::recorders::{Aggregation, AggregationFunction, AggregationOrder, MemoryRecorder};
+    use crate::scenario::ScenarioGroupCollection;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::{default_timestepper, simple_model};
+    use crate::timestep::DayOfYearWindow;
+    use chrono::{NaiveDate, TimeDelta};
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn test_metric_filter() {
+        let metric = |name: &str| {
+            let constant = MetricF64::Simple(SimpleMetricF64::Constant(ConstantMetricF64::Constant(0.0)));
+            OutputMetric::new(name, "value", "constant", None, constant)
+        };
+
+        let filter = MetricFilter::default();
+        assert!(filter.is_match(&metric("a")));
+
+        let filter = MetricFilter::new(Some(vec!["a".to_string(), "b".to_string()]), vec!["b".to_string()]);
+        assert!(filter.is_match(&metric("a")));
+        assert!(!filter.is_match(&metric("b")));
+        assert!(!filter.is_match(&metric("c")));
+
+        let filter = MetricFilter::new(None, vec!["b".to_string()]);
+        assert!(filter.is_match(&metric("a")));
+        assert!(!filter.is_match(&metric("b")));
+    }
+
+    #[test]
+    fn test_time_filter() {
+        let date = |y: i32, m: u32, d: u32| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        let filter = TimeFilter::default();
+        assert!(filter.is_match(&date(2020, 1, 1)));
+
+        let filter = TimeFilter::new(Some(date(2020, 1, 1)), Some(date(2020, 12, 31)));
+        assert!(filter.is_match(&date(2020, 1, 1)));
+        assert!(filter.is_match(&date(2020, 12, 31)));
+        assert!(!filter.is_match(&date(2019, 12, 31)));
+        assert!(!filter.is_match(&date(2021, 1, 1)));
+
+        let filter = TimeFilter::new(None, None).with_window(DayOfYearWindow::new((6, 1), (8, 31)));
+        assert!(filter.is_match(&date(2020, 6, 1)));
+        assert!(filter.is_match(&date(2021, 8, 31)));
+        assert!(!filter.is_match(&date(2020, 5, 31)));
+        assert!(!filter.is_match(&date(2020, 9, 1)));
+
+        // The window wraps around the end of the year
+        let filter = TimeFilter::new(Some(date(2020, 1, 1)), None).with_window(DayOfYearWindow::new((11, 1), (2, 29)));
+        assert!(filter.is_match(&date(2020, 2, 29)));
+        assert!(filter.is_match(&date(2020, 12, 25)));
+        assert!(!filter.is_match(&date(2020, 3, 1)));
+        assert!(!filter.is_match(&date(2019, 12, 25)));
+    }
+
+    #[test]
+    fn test_scenario_statistics() {
+        let constant = MetricF64::Simple(SimpleMetricF64::Constant(ConstantMetricF64::Constant(0.0)));
+        let metrics = vec![
+            OutputMetric::new("a", "value", "constant", None, constant.clone()),
+            OutputMetric::new("b", "value", "constant", None, constant),
+        ];
+        let metric_set = MetricSet::new("test", None, metrics).with_scenario_statistics(vec![
+            ScenarioStatistic::new("p50", AggregationFunction::Quantile(0.5)),
+            ScenarioStatistic::new("exceed-2", AggregationFunction::ExceedanceFrequency { threshold: 2.0 }),
+        ]);
+
+        let start = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        // The values of the two metrics in each of four scenarios
+        let states: Vec<MetricSetState> = [(1.0, 10.0), (2.0, 20.0), (3.0, 30.0), (4.0, 40.0)]
+            .iter()
+            .map(|(a, b)| {
+                let mut state = metric_set.setup();
+                state.current_values = Some(vec![
+                    PeriodValue::new(start, TimeDelta::days(1).into(), *a),
+                    PeriodValue::new(start, TimeDelta::days(1).into(), *b),
+                ]);
+                state
+            })
+            .collect();
+        let states: Vec<&MetricSetState> = states.iter().collect();
+
+        let mut scenario_collection = ScenarioGroupCollection::default();
+        scenario_collection.add_group("climate", 2);
+        scenario_collection.add_group("hydrology", 2);
+        let domain = ModelDomain::from(default_timestepper(), scenario_collection).unwrap();
+
+        let groups = metric_set.scenario_statistic_groups(&domain).unwrap();
+        assert_eq!(metric_set.scenario_statistic_labels(&groups), vec!["p50", "exceed-2"]);
+
+        let statistics = metric_set.calc_scenario_statistics(&states, &groups).unwrap().unwrap();
+        assert_eq!(statistics.len(), 2);
+
+        assert_eq!(statistics[0][0].start, start);
+        assert_approx_eq!(f64, statistics[0][0].value, 2.5);
+        assert_approx_eq!(f64, statistics[0][1].value, 25.0);
+        assert_approx_eq!(f64, statistics[1][0].value, 0.5);
+        assert_approx_eq!(f64, statistics[1][1].value, 1.0);
+
+        // No values are yielded if the scenarios have no current values
+        let empty = metric_set.setup();
+        assert!(metric_set
+            .calc_scenario_statistics(&[&empty], &groups)
+            .unwrap()
+            .is_none());
+
+        // Statistics over the hydrology members only; there are values for each climate scenario
+        let metric_set = metric_set.with_scenario_group("hydrology");
+        let groups = metric_set.scenario_statistic_groups(&domain).unwrap();
+        assert_eq!(
+            metric_set.scenario_statistic_labels(&groups),
+            vec![
+                "p50[climate=0]",
+                "p50[climate=1]",
+                "exceed-2[climate=0]",
+                "exceed-2[climate=1]"
+            ]
+        );
+
+        let statistics = metric_set.calc_scenario_statistics(&states, &groups).unwrap().unwrap();
+        assert_eq!(statistics.len(), 4);
+        assert_approx_eq!(f64, statistics[0][0].value, 1.5);
+        assert_approx_eq!(f64, statistics[0][1].value, 15.0);
+        assert_approx_eq!(f64, statistics[1][0].value, 3.5);
+        assert_approx_eq!(f64, statistics[1][1].value, 35.0);
+        assert_approx_eq!(f64, statistics[2][0].value, 0.0);
+        assert_approx_eq!(f64, statistics[3][0].value, 1.0);
+
+        // The scenario group must be in the domain
+        let metric_set = metric_set.with_scenario_group("missing");
+        assert!(metric_set.scenario_statistic_groups(&domain).is_err());
+    }
+
+    #[test]
+    fn test_derived_metrics() {
+        let mut model = simple_model(2, None);
+
+        let constant = |v: f64| MetricF64::Simple(SimpleMetricF64::Constant(ConstantMetricF64::Constant(v)));
+        let lhs = OutputMetric::new("a", "value", "constant", None, constant(6.0));
+        let rhs = OutputMetric::new("b", "value", "constant", None, constant(4.0));
+
+        let expected = [
+            (DerivedOutputOperation::Ratio, 1.5),
+            (DerivedOutputOperation::Difference, 2.0),
+            (DerivedOutputOperation::Sum, 10.0),
+        ];
+
+        for (operation, _) in expected {
+            let metric = OutputMetric::derived("a-b", operation.name(), operation, lhs.clone(), rhs.clone());
+            assert_eq!(metric.ty(), "Derived");
+            assert_eq!(metric.sub_type(), Some(operation.name()));
+
+            let metric_set = MetricSet::new(operation.name(), None, vec![metric]);
+            let metric_set_idx = model.network_mut().add_metric_set(metric_set).unwrap();
+            let recorder = MemoryRecorder::new(
+                operation.name(),
+                metric_set_idx,
+                Aggregation::new(Some(AggregationFunction::Mean), Some(AggregationFunction::Mean), None),
+                AggregationOrder::default(),
+            );
+            model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+        }
+
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        for (operation, value) in expected {
+            let aggregated = model
+                .network()
+                .get_aggregated_value(operation.name(), result.recorder_states())
+                .unwrap();
+            assert_approx_eq!(f64, aggregated, value);
+        }
     }
 }
@@ -1,9 +1,16 @@
 mod aggregator;
+mod buffer;
 mod csv;
+mod description;
+mod events;
 mod hdf;
+mod manifest;
 mod memory;
 mod metric_set;
 mod py;
+mod results;
+mod run_manifest;
+mod trace;
 
 use crate::metric::{MetricF64, MetricU64};
 use crate::models::ModelDomain;
@@ -13,17 +20,37 @@ use crate::state::State;
 use crate::timestep::Timestep;
 use crate::PywrError;
 pub use aggregator::{AggregationFrequency, AggregationFunction, Aggregator};
-pub use csv::{CsvLongFmtOutput, CsvLongFmtRecord, CsvWideFmtOutput};
+pub use buffer::{BufferError, BufferSettings, ResultBuffer};
+pub use csv::{
+    read_csv_long_metrics, CsvCompression, CsvLongFmtOutput, CsvLongFmtRecord, CsvLongFmtStatisticRecord,
+    CsvWideFmtOutput,
+};
+pub use description::{MetricSetDescription, OutputDescription, OutputDimension, OutputMetricDescription};
+pub use events::{EventFileFormat, EventRecorder};
 use float_cmp::{approx_eq, ApproxEq, F64Margin};
-pub use hdf::HDF5Recorder;
-pub use memory::{Aggregation, AggregationError, AggregationOrder, MemoryRecorder};
-pub use metric_set::{MetricSet, MetricSetIndex, MetricSetState, OutputMetric};
+pub use hdf::{read_hdf5_metrics, Baseline, DifferenceKind, HDF5Recorder, Hdf5Compression, Hdf5Precision};
+pub use manifest::{FileManifest, FileManifestEntry};
+pub use memory::{Aggregation, AggregationError, AggregationOrder, MemoryRecorder, ScenarioGroupValues};
+pub use metric_set::{
+    DerivedOutputOperation, MetricFilter, MetricSet, MetricSetIndex, MetricSetState, OutputMetric, ScenarioStatistic,
+    ScenarioStatisticGroup, TimeFilter,
+};
 use ndarray::prelude::*;
 use ndarray::Array2;
+use polars::frame::DataFrame;
+pub use results::{ResultsFrame, ResultsMetric, ResultsValue};
+pub use run_manifest::{
+    RunInfo, RunManifest, RunManifestOutput, RunManifestRecorder, RunManifestScenarioGroup, RunManifestTimings,
+};
 use std::any::Any;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
+use std::path::Path;
+pub use trace::{
+    PreSolveTrace, SolveTrace, SolveTraceEdge, SolveTraceEntry, SolveTraceIndexParameter, SolveTraceNode,
+    SolveTraceParameter, TraceRecorder,
+};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct RecorderIndex(usize);
@@ -64,6 +91,51 @@ impl RecorderMeta {
     }
 }
 
+/// Helper function to downcast to the internal state of a recorder.
+///
+/// An error naming the recorder is returned if there is no internal state or it is not of the
+/// expected type.
+fn downcast_internal_state_mut<'a, T: 'static>(
+    name: &str,
+    internal_state: &'a mut Option<Box<dyn Any>>,
+) -> Result<&'a mut T, PywrError> {
+    match internal_state {
+        Some(internal) => internal
+            .downcast_mut::<T>()
+            .ok_or_else(|| PywrError::RecorderInternalStateDowncastFailed(name.to_string())),
+        None => Err(PywrError::RecorderInternalStateNotFound(name.to_string())),
+    }
+}
+
+/// Helper function to downcast to the internal state of a recorder.
+///
+/// An error naming the recorder is returned if there is no internal state or it is not of the
+/// expected type.
+fn downcast_internal_state_ref<'a, T: 'static>(
+    name: &str,
+    internal_state: &'a Option<Box<dyn Any>>,
+) -> Result<&'a T, PywrError> {
+    match internal_state {
+        Some(internal) => internal
+            .downcast_ref::<T>()
+            .ok_or_else(|| PywrError::RecorderInternalStateDowncastFailed(name.to_string())),
+        None => Err(PywrError::RecorderInternalStateNotFound(name.to_string())),
+    }
+}
+
+/// Helper function to take ownership of the internal state of a recorder.
+///
+/// An error naming the recorder is returned if there is no internal state or it is not of the
+/// expected type.
+fn downcast_internal_state<T: 'static>(name: &str, internal_state: Option<Box<dyn Any>>) -> Result<Box<T>, PywrError> {
+    match internal_state {
+        Some(internal) => internal
+            .downcast::<T>()
+            .map_err(|_| PywrError::RecorderInternalStateDowncastFailed(name.to_string())),
+        None => Err(PywrError::RecorderInternalStateNotFound(name.to_string())),
+    }
+}
+
 pub trait Recorder: Send + Sync {
     fn meta(&self) -> &RecorderMeta;
     fn name(&self) -> &str {
@@ -74,6 +146,21 @@ pub trait Recorder: Send + Sync {
     }
     fn before(&self) {}
 
+    /// Called once at the start of a run, after [`Recorder::setup`], with the details of the run.
+    fn start_run(&self, _run: &RunInfo, _internal_state: &mut Option<Box<dyn Any>>) -> Result<(), PywrError> {
+        Ok(())
+    }
+
+    /// Returns true if the recorder requires the values that the solver sees in `timestep` of
+    /// `scenario_index`.
+    ///
+    /// If any recorder returns true the values are captured once the components of the network
+    /// have been computed, before the solve, and are available to [`Recorder::save`] from
+    /// [`State::pre_solve_trace`].
+    fn traces(&self, _timestep: &Timestep, _scenario_index: &ScenarioIndex) -> bool {
+        false
+    }
+
     fn save(
         &self,
         _timestep: &Timestep,
@@ -94,9 +181,54 @@ pub trait Recorder: Send + Sync {
         Ok(())
     }
 
+    /// The paths of any files written by the recorder.
+    ///
+    /// These files are added to the [`FileManifest`] of the run after the recorder is finalised.
+    fn output_paths(&self) -> Vec<&Path> {
+        Vec::new()
+    }
+
+    /// Describe the values that will be written by the recorder.
+    ///
+    /// Returns `None` if the recorder does not write any values (e.g. an assertion recorder).
+    fn describe(&self, _domain: &ModelDomain, _network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        Ok(None)
+    }
+
     fn aggregated_value(&self, _internal_state: &Option<Box<dyn Any>>) -> Result<f64, PywrError> {
         Err(PywrError::RecorderDoesNotSupportAggregation)
     }
+
+    /// Aggregate over a single scenario group, returning a value for each combination of the
+    /// remaining scenario groups.
+    fn aggregated_values_by_scenario_group(
+        &self,
+        _internal_state: &Option<Box<dyn Any>>,
+    ) -> Result<ScenarioGroupValues, PywrError> {
+        Err(PywrError::RecorderDoesNotSupportAggregation)
+    }
+
+    /// The values saved by the recorder as a [`DataFrame`].
+    ///
+    /// Returns `None` if the recorder does not keep its values in memory.
+    fn to_dataframe(
+        &self,
+        _network: &Network,
+        _internal_state: &Option<Box<dyn Any>>,
+    ) -> Result<Option<DataFrame>, PywrError> {
+        Ok(None)
+    }
+
+    /// The values saved by the recorder as a [`ResultsFrame`].
+    ///
+    /// Returns `None` if the recorder does not keep its values in memory.
+    fn to_results_frame(
+        &self,
+        _network: &Network,
+        _internal_state: &Option<Box<dyn Any>>,
+    ) -> Result<Option<ResultsFrame>, PywrError> {
+        Ok(None)
+    }
 }
 
 pub struct Array2Recorder {
@@ -134,13 +266,7 @@ impl Recorder for Array2Recorder {
         internal_state: &mut Option<Box<dyn Any>>,
     ) -> Result<(), PywrError> {
         // Downcast the internal state to the correct type
-        let array = match internal_state {
-            Some(internal) => match internal.downcast_mut::<Array2<f64>>() {
-                Some(pa) => pa,
-                None => panic!("Internal state did not downcast to the correct type! :("),
-            },
-            None => panic!("No internal state defined when one was expected! :("),
-        };
+        let array = downcast_internal_state_mut::<Array2<f64>>(self.name(), internal_state)?;
 
         // This panics if out-of-bounds
         for scenario_index in scenario_indices {
@@ -0,0 +1,393 @@
+use crate::recorders::OutputMetric;
+use crate::PywrError;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::NaiveDateTime;
+use ndarray::{s, Array3, ArrayView1};
+use polars::prelude::{Column, DataFrame};
+use std::sync::Arc;
+
+/// A metric of a [`ResultsFrame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultsMetric {
+    /// The name of the metric; usually the name of the component it is a metric of (e.g. a node).
+    pub name: String,
+    pub attribute: String,
+    /// The type of the component (e.g. `Storage`).
+    pub ty: String,
+    pub sub_type: Option<String>,
+}
+
+impl From<&OutputMetric> for ResultsMetric {
+    fn from(metric: &OutputMetric) -> Self {
+        Self {
+            name: metric.name().to_string(),
+            attribute: metric.attribute().to_string(),
+            ty: metric.ty().to_string(),
+            sub_type: metric.sub_type().map(|s| s.to_string()),
+        }
+    }
+}
+
+/// A single value of a [`ResultsFrame`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResultsValue<'a> {
+    pub metric: &'a ResultsMetric,
+    pub scenario_index: usize,
+    pub time_index: usize,
+    pub time_start: NaiveDateTime,
+    pub time_end: NaiveDateTime,
+    pub value: f64,
+}
+
+/// The values of the metrics of a metric set saved by a recorder, for each scenario and period.
+///
+/// This is a typed alternative to the files written by the other recorders. Values can be
+/// accessed by metric, scenario and time index, or iterated over in long format. The time
+/// index of a value is its position in the saved periods, which are all the periods of the run
+/// unless the recorder filters them. The results of a recorder that keeps its values in memory
+/// (e.g. [`MemoryRecorder`](crate::recorders::MemoryRecorder)) are returned from a run by
+/// [`Network::get_recorder_results`](crate::network::Network::get_recorder_results).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultsFrame {
+    metric_set: String,
+    metrics: Vec<ResultsMetric>,
+    periods: Vec<(NaiveDateTime, NaiveDateTime)>,
+    scenario_groups: Vec<String>,
+    /// The index in each scenario group of each scenario.
+    scenario_indices: Vec<Vec<usize>>,
+    /// The values indexed by scenario, period and metric.
+    values: Array3<f64>,
+}
+
+impl ResultsFrame {
+    pub(crate) fn new(
+        metric_set: &str,
+        metrics: Vec<ResultsMetric>,
+        periods: Vec<(NaiveDateTime, NaiveDateTime)>,
+        scenario_groups: Vec<String>,
+        scenario_indices: Vec<Vec<usize>>,
+        values: Array3<f64>,
+    ) -> Self {
+        Self {
+            metric_set: metric_set.to_string(),
+            metrics,
+            periods,
+            scenario_groups,
+            scenario_indices,
+            values,
+        }
+    }
+
+    /// The name of the metric set of the values.
+    pub fn metric_set(&self) -> &str {
+        &self.metric_set
+    }
+
+    pub fn metrics(&self) -> &[ResultsMetric] {
+        &self.metrics
+    }
+
+    /// The start and end of each saved period.
+    pub fn periods(&self) -> &[(NaiveDateTime, NaiveDateTime)] {
+        &self.periods
+    }
+
+    pub fn num_scenarios(&self) -> usize {
+        self.scenario_indices.len()
+    }
+
+    /// The names of the scenario groups.
+    pub fn scenario_groups(&self) -> &[String] {
+        &self.scenario_groups
+    }
+
+    /// The index in each scenario group of the scenario with index `scenario_index`.
+    pub fn scenario_group_indices(&self, scenario_index: usize) -> Option<&[usize]> {
+        self.scenario_indices.get(scenario_index).map(|i| i.as_slice())
+    }
+
+    /// The index of the scenario with the given index in each scenario group.
+    pub fn scenario_index(&self, group_indices: &[usize]) -> Option<usize> {
+        self.scenario_indices.iter().position(|i| i == group_indices)
+    }
+
+    /// The index of the metric with `name` and, if given, `attribute`.
+    ///
+    /// If `attribute` is `None` the first metric with `name` is returned.
+    pub fn metric_index(&self, name: &str, attribute: Option<&str>) -> Option<usize> {
+        self.metrics
+            .iter()
+            .position(|m| m.name == name && attribute.map_or(true, |a| m.attribute == a))
+    }
+
+    /// The indices of the metrics of the component (e.g. node) with `name`.
+    pub fn metrics_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = usize> + 'a {
+        self.metrics
+            .iter()
+            .enumerate()
+            .filter(move |(_, m)| m.name == name)
+            .map(|(idx, _)| idx)
+    }
+
+    /// The value of a metric in a scenario and period.
+    pub fn value(&self, metric_index: usize, scenario_index: usize, time_index: usize) -> Option<f64> {
+        self.values.get((scenario_index, time_index, metric_index)).copied()
+    }
+
+    /// The values of a metric in each period of a scenario.
+    pub fn series(&self, metric_index: usize, scenario_index: usize) -> Option<ArrayView1<f64>> {
+        if metric_index >= self.metrics.len() || scenario_index >= self.num_scenarios() {
+            return None;
+        }
+        Some(self.values.slice(s![scenario_index, .., metric_index]))
+    }
+
+    /// The values of each metric in a scenario and period.
+    pub fn period(&self, scenario_index: usize, time_index: usize) -> Option<ArrayView1<f64>> {
+        if scenario_index >= self.num_scenarios() || time_index >= self.periods.len() {
+            return None;
+        }
+        Some(self.values.slice(s![scenario_index, time_index, ..]))
+    }
+
+    /// Iterate over the time series of each metric and scenario.
+    pub fn iter_series(&self) -> impl Iterator<Item = (&ResultsMetric, usize, ArrayView1<f64>)> + '_ {
+        (0..self.num_scenarios()).flat_map(move |scenario_index| {
+            self.metrics.iter().enumerate().map(move |(metric_index, metric)| {
+                let series = self.values.slice(s![scenario_index, .., metric_index]);
+                (metric, scenario_index, series)
+            })
+        })
+    }
+
+    /// Iterate over every value, ordered by scenario, period and then metric.
+    pub fn iter(&self) -> impl Iterator<Item = ResultsValue<'_>> + '_ {
+        self.values
+            .indexed_iter()
+            .map(move |((scenario_index, time_index, metric_index), value)| {
+                let (time_start, time_end) = self.periods[time_index];
+                ResultsValue {
+                    metric: &self.metrics[metric_index],
+                    scenario_index,
+                    time_index,
+                    time_start,
+                    time_end,
+                    value: *value,
+                }
+            })
+    }
+
+    /// The values in long format, with a row for each scenario, period and metric.
+    ///
+    /// The columns are `time_start`, `time_end`, `scenario_index`, the index of the scenario in
+    /// each scenario group (named after the group), `metric_set`, `name`, `attribute` and `value`.
+    pub fn to_dataframe(&self) -> Result<DataFrame, PywrError> {
+        let num_rows = self.values.len();
+
+        let mut time_start = Vec::with_capacity(num_rows);
+        let mut time_end = Vec::with_capacity(num_rows);
+        let mut scenario_index = Vec::with_capacity(num_rows);
+        let mut group_columns = vec![Vec::with_capacity(num_rows); self.scenario_groups.len()];
+        let mut names = Vec::with_capacity(num_rows);
+        let mut attributes = Vec::with_capacity(num_rows);
+        let mut values = Vec::with_capacity(num_rows);
+
+        for v in self.iter() {
+            time_start.push(v.time_start);
+            time_end.push(v.time_end);
+            scenario_index.push(v.scenario_index as u64);
+            for (column, group_idx) in group_columns.iter_mut().zip(&self.scenario_indices[v.scenario_index]) {
+                column.push(*group_idx as u64);
+            }
+            names.push(v.metric.name.as_str());
+            attributes.push(v.metric.attribute.as_str());
+            values.push(v.value);
+        }
+
+        let mut columns = vec![
+            Column::new("time_start".into(), time_start),
+            Column::new("time_end".into(), time_end),
+            Column::new("scenario_index".into(), scenario_index),
+        ];
+        for (name, column) in self.scenario_groups.iter().zip(group_columns) {
+            columns.push(Column::new(name.as_str().into(), column));
+        }
+        columns.push(Column::new(
+            "metric_set".into(),
+            vec![self.metric_set.as_str(); values.len()],
+        ));
+        columns.push(Column::new("name".into(), names));
+        columns.push(Column::new("attribute".into(), attributes));
+        columns.push(Column::new("value".into(), values));
+
+        DataFrame::new(columns).map_err(|e| PywrError::DataFrameError(e.to_string()))
+    }
+
+    /// The values in long format (see [`ResultsFrame::to_dataframe`]) as Arrow record batches.
+    ///
+    /// There is one batch for each scenario, and every batch has the same schema. The times are
+    /// timestamps in milliseconds without a time zone.
+    pub fn to_record_batches(&self) -> Result<Vec<RecordBatch>, PywrError> {
+        let timestamp = DataType::Timestamp(TimeUnit::Millisecond, None);
+        let mut fields = vec![
+            Field::new("time_start", timestamp.clone(), false),
+            Field::new("time_end", timestamp, false),
+            Field::new("scenario_index", DataType::UInt64, false),
+        ];
+        for name in &self.scenario_groups {
+            fields.push(Field::new(name, DataType::UInt64, false));
+        }
+        fields.push(Field::new("metric_set", DataType::Utf8, false));
+        fields.push(Field::new("name", DataType::Utf8, false));
+        fields.push(Field::new("attribute", DataType::Utf8, false));
+        fields.push(Field::new("value", DataType::Float64, false));
+        let schema = Arc::new(Schema::new(fields));
+
+        let num_metrics = self.metrics.len();
+        let num_rows = self.periods.len() * num_metrics;
+
+        // The time and metric columns are the same in every scenario
+        let time_start: ArrayRef = Arc::new(TimestampMillisecondArray::from_iter_values(
+            self.periods
+                .iter()
+                .flat_map(|(start, _)| std::iter::repeat(start.and_utc().timestamp_millis()).take(num_metrics)),
+        ));
+        let time_end: ArrayRef = Arc::new(TimestampMillisecondArray::from_iter_values(
+            self.periods
+                .iter()
+                .flat_map(|(_, end)| std::iter::repeat(end.and_utc().timestamp_millis()).take(num_metrics)),
+        ));
+        let metric_set: ArrayRef = Arc::new(StringArray::from_iter_values(
+            std::iter::repeat(self.metric_set.as_str()).take(num_rows),
+        ));
+        let names: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..self.periods.len()).flat_map(|_| self.metrics.iter().map(|m| m.name.as_str())),
+        ));
+        let attributes: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..self.periods.len()).flat_map(|_| self.metrics.iter().map(|m| m.attribute.as_str())),
+        ));
+
+        (0..self.num_scenarios())
+            .map(|scenario_index| {
+                let mut columns = vec![
+                    time_start.clone(),
+                    time_end.clone(),
+                    Arc::new(UInt64Array::from_value(scenario_index as u64, num_rows)) as ArrayRef,
+                ];
+                for group_idx in &self.scenario_indices[scenario_index] {
+                    columns.push(Arc::new(UInt64Array::from_value(*group_idx as u64, num_rows)));
+                }
+                columns.push(metric_set.clone());
+                columns.push(names.clone());
+                columns.push(attributes.clone());
+                columns.push(Arc::new(Float64Array::from_iter_values(
+                    self.values.slice(s![scenario_index, .., ..]).iter().copied(),
+                )));
+
+                RecordBatch::try_new(schema.clone(), columns).map_err(|e| PywrError::ArrowError(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResultsFrame, ResultsMetric};
+    use arrow_array::{Array, Float64Array, StringArray, TimestampMillisecondArray};
+    use chrono::{NaiveDate, TimeDelta};
+    use ndarray::Array3;
+
+    fn results_frame() -> ResultsFrame {
+        let metric = |name: &str, attribute: &str| ResultsMetric {
+            name: name.to_string(),
+            attribute: attribute.to_string(),
+            ty: "Storage".to_string(),
+            sub_type: None,
+        };
+
+        let start = NaiveDate::from_ymd_opt(2020, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let periods = (0..3)
+            .map(|i| {
+                let period_start = start + TimeDelta::days(i);
+                (period_start, period_start + TimeDelta::days(1))
+            })
+            .collect();
+
+        // The value encodes its scenario, period and metric
+        let values = Array3::from_shape_fn((2, 3, 2), |(s, t, m)| (s * 100 + t * 10 + m) as f64);
+
+        ResultsFrame::new(
+            "nodes",
+            vec![metric("reservoir", "volume"), metric("reservoir", "inflow")],
+            periods,
+            vec!["climate".to_string()],
+            vec![vec![0], vec![1]],
+            values,
+        )
+    }
+
+    #[test]
+    fn test_results_frame_accessors() {
+        let frame = results_frame();
+
+        let volume = frame.metric_index("reservoir", Some("volume")).unwrap();
+        let inflow = frame.metric_index("reservoir", Some("inflow")).unwrap();
+        assert_eq!(frame.metric_index("reservoir", None), Some(volume));
+        assert_eq!(frame.metric_index("reservoir", Some("outflow")), None);
+        assert_eq!(frame.metrics_of("reservoir").collect::<Vec<_>>(), vec![volume, inflow]);
+
+        assert_eq!(frame.value(inflow, 1, 2), Some(121.0));
+        assert_eq!(frame.value(inflow, 2, 0), None);
+        assert_eq!(frame.series(volume, 1).unwrap().to_vec(), vec![100.0, 110.0, 120.0]);
+        assert_eq!(frame.period(0, 1).unwrap().to_vec(), vec![10.0, 11.0]);
+        assert_eq!(frame.scenario_index(&[1]), Some(1));
+
+        assert_eq!(frame.iter().count(), 12);
+        assert_eq!(frame.iter_series().count(), 4);
+        let last = frame.iter().last().unwrap();
+        assert_eq!((last.scenario_index, last.time_index, last.value), (1, 2, 121.0));
+    }
+
+    #[test]
+    fn test_results_frame_to_record_batches() {
+        let frame = results_frame();
+
+        let df = frame.to_dataframe().unwrap();
+        assert_eq!(df.height(), 12);
+        assert!(df.column("climate").is_ok());
+
+        let batches = frame.to_record_batches().unwrap();
+        assert_eq!(batches.len(), 2);
+        let num_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(num_rows, 12);
+
+        // The rows of each batch are ordered by period and then metric, as in the data frame
+        let batch = &batches[1];
+        assert_eq!(batch.schema().field(3).name(), "climate");
+        let values = batch
+            .column_by_name("value")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(values.values().to_vec(), vec![100.0, 101.0, 110.0, 111.0, 120.0, 121.0]);
+        let attributes = batch
+            .column_by_name("attribute")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(attributes.value(1), "inflow");
+        let time_start = batch
+            .column_by_name("time_start")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(time_start.value(2), frame.periods()[1].0.and_utc().timestamp_millis());
+    }
+}
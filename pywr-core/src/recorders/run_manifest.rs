@@ -0,0 +1,290 @@
+use crate::checksum::Checksum;
+use crate::models::ModelDomain;
+use crate::network::Network;
+use crate::recorders::{downcast_internal_state_mut, MetricSetState, OutputDescription, Recorder, RecorderMeta};
+use crate::PywrError;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// The details of a run that are only known once the model is run (e.g. the solver).
+///
+/// These are passed to each recorder with [`Recorder::start_run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunInfo {
+    solver: String,
+    solver_settings: Option<String>,
+}
+
+impl RunInfo {
+    pub fn new(solver: &str) -> Self {
+        Self {
+            solver: solver.to_string(),
+            solver_settings: None,
+        }
+    }
+
+    /// Record the settings of the solver.
+    pub fn with_solver_settings(mut self, settings: &dyn Debug) -> Self {
+        self.solver_settings = Some(format!("{settings:?}"));
+        self
+    }
+
+    pub fn solver(&self) -> &str {
+        &self.solver
+    }
+
+    pub fn solver_settings(&self) -> Option<&str> {
+        self.solver_settings.as_deref()
+    }
+}
+
+/// A scenario group of the run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunManifestScenarioGroup {
+    pub name: String,
+    pub size: usize,
+    /// The members of the group that were run, if only a subset of the group was run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subset: Option<Vec<usize>>,
+}
+
+/// An output written by the run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunManifestOutput {
+    pub name: String,
+    pub format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// The wall-clock time of the run.
+///
+/// These are the only values of a [`RunManifest`] that differ between identical runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifestTimings {
+    pub started: DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    pub elapsed_seconds: f64,
+}
+
+/// The provenance of a model run.
+///
+/// This records what was run (the model, network and scenarios), how it was run (the version of
+/// Pywr and the solver) and when. Other than the `timings`, two runs of the same model with the
+/// same solver and settings produce identical manifests, so the manifests of two sets of outputs
+/// can be compared to check whether they were produced in the same way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub pywr_version: String,
+    /// The checksum of the model definition, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_checksum: Option<Checksum>,
+    /// The digest of the structure of the network (see [`Network::structure_digest`]).
+    pub network_digest: Checksum,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solver: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solver_settings: Option<String>,
+    /// The start of the first time-step.
+    pub first_timestep: Option<NaiveDateTime>,
+    /// The start of the last time-step.
+    pub last_timestep: Option<NaiveDateTime>,
+    pub num_timesteps: usize,
+    pub scenarios: Vec<RunManifestScenarioGroup>,
+    /// The other outputs of the run.
+    pub outputs: Vec<RunManifestOutput>,
+    pub timings: RunManifestTimings,
+}
+
+impl RunManifest {
+    /// Read a manifest previously written by a [`RunManifestRecorder`].
+    pub fn from_path(path: &Path) -> Result<Self, PywrError> {
+        let map_err = |e: String| PywrError::RunManifestError(path.to_path_buf(), e);
+
+        let data = std::fs::read_to_string(path).map_err(|e| map_err(e.to_string()))?;
+        serde_json::from_str(&data).map_err(|e| map_err(e.to_string()))
+    }
+}
+
+struct InternalState {
+    manifest: RunManifest,
+    started: Instant,
+}
+
+/// A recorder that writes a [`RunManifest`] of the run to a JSON file.
+///
+/// The manifest is written when the run is finalised. It is independent of the format of the
+/// other outputs, so that every run records the same provenance however its results are saved.
+pub struct RunManifestRecorder {
+    meta: RecorderMeta,
+    filename: PathBuf,
+    model_checksum: Option<Checksum>,
+}
+
+impl RunManifestRecorder {
+    pub fn new<P: Into<PathBuf>>(name: &str, filename: P) -> Self {
+        Self {
+            meta: RecorderMeta::new(name),
+            filename: filename.into(),
+            model_checksum: None,
+        }
+    }
+
+    /// Record the checksum of the model definition (e.g. of the model file) in the manifest.
+    pub fn with_model_checksum(mut self, checksum: Checksum) -> Self {
+        self.model_checksum = Some(checksum);
+        self
+    }
+}
+
+impl Recorder for RunManifestRecorder {
+    fn meta(&self) -> &RecorderMeta {
+        &self.meta
+    }
+
+    fn setup(&self, domain: &ModelDomain, network: &Network) -> Result<Option<Box<dyn Any>>, PywrError> {
+        let timesteps = domain.time().timesteps();
+
+        let scenarios = domain
+            .scenarios()
+            .groups()
+            .iter()
+            .map(|group| RunManifestScenarioGroup {
+                name: group.name().to_string(),
+                size: group.size(),
+                subset: group.subset().map(|s| s.to_vec()),
+            })
+            .collect();
+
+        let outputs = network
+            .describe_outputs(domain)?
+            .into_iter()
+            .filter(|d| d.name != self.name())
+            .map(|d| RunManifestOutput {
+                name: d.name,
+                format: d.format,
+                path: d.path,
+            })
+            .collect();
+
+        let now = Utc::now();
+        let manifest = RunManifest {
+            pywr_version: env!("CARGO_PKG_VERSION").to_string(),
+            model_checksum: self.model_checksum.clone(),
+            network_digest: network.structure_digest(),
+            solver: None,
+            solver_settings: None,
+            first_timestep: timesteps.first().map(|t| t.date),
+            last_timestep: timesteps.last().map(|t| t.date),
+            num_timesteps: timesteps.len(),
+            scenarios,
+            outputs,
+            timings: RunManifestTimings {
+                started: now,
+                finished: now,
+                elapsed_seconds: 0.0,
+            },
+        };
+
+        Ok(Some(Box::new(InternalState {
+            manifest,
+            started: Instant::now(),
+        })))
+    }
+
+    fn start_run(&self, run: &RunInfo, internal_state: &mut Option<Box<dyn Any>>) -> Result<(), PywrError> {
+        let internal_state = downcast_internal_state_mut::<InternalState>(self.name(), internal_state)?;
+
+        internal_state.manifest.solver = Some(run.solver().to_string());
+        internal_state.manifest.solver_settings = run.solver_settings().map(|s| s.to_string());
+        internal_state.manifest.timings.started = Utc::now();
+        internal_state.started = Instant::now();
+
+        Ok(())
+    }
+
+    fn finalise(
+        &self,
+        _network: &Network,
+        _metric_set_states: &[Vec<MetricSetState>],
+        internal_state: &mut Option<Box<dyn Any>>,
+    ) -> Result<(), PywrError> {
+        let internal_state = downcast_internal_state_mut::<InternalState>(self.name(), internal_state)?;
+
+        let timings = &mut internal_state.manifest.timings;
+        timings.finished = Utc::now();
+        timings.elapsed_seconds = internal_state.started.elapsed().as_secs_f64();
+
+        let map_err = |e: String| PywrError::RunManifestError(self.filename.clone(), e);
+        let data = serde_json::to_string_pretty(&internal_state.manifest).map_err(|e| map_err(e.to_string()))?;
+        std::fs::write(&self.filename, data).map_err(|e| map_err(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn output_paths(&self) -> Vec<&Path> {
+        vec![self.filename.as_path()]
+    }
+
+    fn describe(&self, _domain: &ModelDomain, _network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        Ok(Some(OutputDescription::new(
+            self.name(),
+            "manifest",
+            Some(self.filename.clone()),
+            Vec::new(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RunManifest, RunManifestRecorder};
+    use crate::metric::MetricF64;
+    use crate::recorders::{CsvWideFmtOutput, MetricSet, OutputMetric};
+    use crate::solvers::{ClpSolver, ClpSolverSettings, Solver};
+    use crate::test_utils::simple_model;
+
+    #[test]
+    fn test_run_manifest_recorder() {
+        let mut model = simple_model(2, None);
+
+        let node_idx = model.network().get_node_index_by_name("output", None).unwrap();
+        let metric = OutputMetric::new("output", "inflow", "Output", None, MetricF64::NodeInFlow(node_idx));
+        let metric_set = MetricSet::new("nodes", None, vec![metric]);
+        let metric_set_idx = model.network_mut().add_metric_set(metric_set).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let csv_path = dir.path().join("outputs.csv");
+        let recorder = CsvWideFmtOutput::new("outputs", &csv_path, metric_set_idx);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        let path = dir.path().join("manifest.json");
+        let recorder = RunManifestRecorder::new("manifest", &path);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+        assert_eq!(result.manifest().len(), 2);
+
+        let manifest = RunManifest::from_path(&path).unwrap();
+        assert_eq!(manifest.pywr_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(manifest.network_digest, model.network().structure_digest());
+        assert_eq!(manifest.solver.as_deref(), Some(ClpSolver::name()));
+        assert!(manifest.solver_settings.is_some());
+        assert_eq!(manifest.num_timesteps, model.domain().time().timesteps().len());
+        assert_eq!(manifest.scenarios.len(), 1);
+        assert_eq!(manifest.scenarios[0].size, 2);
+        assert_eq!(manifest.outputs.len(), 1);
+        assert_eq!(manifest.outputs[0].name, "outputs");
+        assert!(manifest.timings.finished >= manifest.timings.started);
+
+        // A second run produces the same manifest except for the timings.
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+        let mut second = RunManifest::from_path(&path).unwrap();
+        second.timings = manifest.timings.clone();
+        assert_eq!(second, manifest);
+    }
+}
@@ -0,0 +1,358 @@
+use crate::metric::{MetricF64, MetricU64};
+use crate::models::ModelDomain;
+use crate::network::Network;
+use crate::node::{FlowBounds, NodeCost};
+use crate::recorders::{
+    downcast_internal_state_mut, MetricSetState, OutputDescription, Recorder, RecorderMeta, TimeFilter,
+};
+use crate::scenario::ScenarioIndex;
+use crate::state::State;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::path::{Path, PathBuf};
+
+/// The values of a state that the solver sees, captured before it is solved.
+///
+/// This is captured by the network when a recorder requests it (see [`Recorder::traces`]),
+/// because the bounds of the storage nodes, and anything else that depends on the flows, have
+/// changed by the time the recorders are saved.
+#[derive(Debug, Clone)]
+pub struct PreSolveTrace {
+    parameters: Vec<SolveTraceParameter>,
+    index_parameters: Vec<SolveTraceIndexParameter>,
+    node_bounds: Vec<(FlowBounds, NodeCost)>,
+}
+
+impl PreSolveTrace {
+    pub(crate) fn capture(network: &Network, timestep: &Timestep, state: &State) -> Result<Self, PywrError> {
+        let parameters = network
+            .parameters()
+            .iter_f64_indices()
+            .map(|idx| {
+                Ok(SolveTraceParameter {
+                    name: network.get_parameter(idx)?.name().to_string(),
+                    value: MetricF64::from(idx).get_value(network, state)?,
+                })
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        let index_parameters = network
+            .parameters()
+            .iter_u64_indices()
+            .map(|idx| {
+                Ok(SolveTraceIndexParameter {
+                    name: network.get_index_parameter(idx)?.name().to_string(),
+                    value: MetricU64::from(idx).get_value(network, state)?,
+                })
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        let node_bounds = network
+            .nodes()
+            .iter()
+            .map(|node| {
+                let bounds = network.get_node_flow_bounds(&node.index(), timestep, state)?;
+                let cost = network.get_node_cost(&node.index(), state)?;
+                Ok((bounds, cost))
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        Ok(Self {
+            parameters,
+            index_parameters,
+            node_bounds,
+        })
+    }
+}
+
+/// The value of a parameter in a [`SolveTraceEntry`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolveTraceParameter {
+    pub name: String,
+    pub value: f64,
+}
+
+/// The value of an index parameter in a [`SolveTraceEntry`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolveTraceIndexParameter {
+    pub name: String,
+    pub value: u64,
+}
+
+/// A node in a [`SolveTraceEntry`], with its bounds and costs as the solver saw them and its
+/// flows in the solution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolveTraceNode {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub_name: Option<String>,
+    /// The bounds of the flow of the node. For a storage node these are the bounds of the net
+    /// flow in to (positive) or out of (negative) the node.
+    pub min_flow: f64,
+    pub max_flow: f64,
+    pub incoming_cost: f64,
+    pub outgoing_cost: f64,
+    pub in_flow: f64,
+    pub out_flow: f64,
+    /// The volume at the end of the time-step of a storage node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+}
+
+/// An edge in a [`SolveTraceEntry`] with its flow in the solution.
+///
+/// The nodes are given by their position in the nodes of the entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolveTraceEdge {
+    pub from_node: usize,
+    pub to_node: usize,
+    pub flow: f64,
+}
+
+/// The trace of the solve of a single time-step of a single scenario.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolveTraceEntry {
+    pub timestep_index: usize,
+    pub date: NaiveDateTime,
+    /// The index of the scenario, and the index of its member of each scenario group.
+    pub scenario_index: usize,
+    pub scenario_indices: Vec<usize>,
+    pub parameters: Vec<SolveTraceParameter>,
+    pub index_parameters: Vec<SolveTraceIndexParameter>,
+    pub nodes: Vec<SolveTraceNode>,
+    pub edges: Vec<SolveTraceEdge>,
+}
+
+impl SolveTraceEntry {
+    fn new(
+        network: &Network,
+        timestep: &Timestep,
+        scenario_index: &ScenarioIndex,
+        trace: &PreSolveTrace,
+        state: &State,
+    ) -> Result<Self, PywrError> {
+        let network_state = state.get_network_state();
+
+        let nodes = network
+            .nodes()
+            .iter()
+            .zip(trace.node_bounds.iter())
+            .map(|(node, (bounds, cost))| {
+                let (name, sub_name) = node.full_name();
+                Ok(SolveTraceNode {
+                    name: name.to_string(),
+                    sub_name: sub_name.map(|s| s.to_string()),
+                    min_flow: bounds.min_flow,
+                    max_flow: bounds.max_flow,
+                    incoming_cost: cost.incoming,
+                    outgoing_cost: cost.outgoing,
+                    in_flow: network_state.get_node_in_flow(&node.index())?,
+                    out_flow: network_state.get_node_out_flow(&node.index())?,
+                    volume: network_state.get_node_volume(&node.index()).ok(),
+                })
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        let edges = network
+            .edges()
+            .iter()
+            .map(|edge| {
+                Ok(SolveTraceEdge {
+                    from_node: *edge.from_node_index(),
+                    to_node: *edge.to_node_index(),
+                    flow: network_state.get_edge_flow(&edge.index())?,
+                })
+            })
+            .collect::<Result<Vec<_>, PywrError>>()?;
+
+        Ok(Self {
+            timestep_index: timestep.index,
+            date: timestep.date,
+            scenario_index: scenario_index.index,
+            scenario_indices: scenario_index.indices.clone(),
+            parameters: trace.parameters.clone(),
+            index_parameters: trace.index_parameters.clone(),
+            nodes,
+            edges,
+        })
+    }
+}
+
+/// The traces of the solves written by a [`TraceRecorder`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SolveTrace {
+    pub entries: Vec<SolveTraceEntry>,
+}
+
+impl SolveTrace {
+    /// Read a trace previously written by a [`TraceRecorder`].
+    pub fn from_path(path: &Path) -> Result<Self, PywrError> {
+        let map_err = |e: String| PywrError::SolveTraceError(path.to_path_buf(), e);
+
+        let data = std::fs::read_to_string(path).map_err(|e| map_err(e.to_string()))?;
+        serde_json::from_str(&data).map_err(|e| map_err(e.to_string()))
+    }
+}
+
+/// A recorder that writes a [`SolveTrace`] of selected time-steps and scenarios to a JSON file.
+///
+/// For each selected time-step of each selected scenario the trace contains the value of every
+/// parameter, the bounds and costs of every node as the solver saw them, and the flows of the
+/// solution. This is intended to help understand why the model made a decision, so the
+/// time-steps and scenarios should be restricted to those of interest; the trace of every
+/// time-step of a large model is very large. Multi-valued parameters are not included.
+pub struct TraceRecorder {
+    meta: RecorderMeta,
+    filename: PathBuf,
+    time_filter: TimeFilter,
+    scenarios: Option<Vec<usize>>,
+}
+
+impl TraceRecorder {
+    /// Create a recorder that traces every time-step of every scenario.
+    pub fn new<P: Into<PathBuf>>(name: &str, filename: P) -> Self {
+        Self {
+            meta: RecorderMeta::new(name),
+            filename: filename.into(),
+            time_filter: TimeFilter::default(),
+            scenarios: None,
+        }
+    }
+
+    /// Only trace the time-steps that start within `filter`.
+    pub fn with_time_filter(mut self, filter: TimeFilter) -> Self {
+        self.time_filter = filter;
+        self
+    }
+
+    /// Only trace the scenarios with the given indices.
+    pub fn with_scenarios(mut self, scenarios: Vec<usize>) -> Self {
+        self.scenarios = Some(scenarios);
+        self
+    }
+}
+
+impl Recorder for TraceRecorder {
+    fn meta(&self) -> &RecorderMeta {
+        &self.meta
+    }
+
+    fn setup(&self, _domain: &ModelDomain, _network: &Network) -> Result<Option<Box<dyn Any>>, PywrError> {
+        Ok(Some(Box::new(SolveTrace::default())))
+    }
+
+    fn traces(&self, timestep: &Timestep, scenario_index: &ScenarioIndex) -> bool {
+        self.time_filter.is_match(&timestep.date)
+            && self
+                .scenarios
+                .as_ref()
+                .map_or(true, |s| s.contains(&scenario_index.index))
+    }
+
+    fn save(
+        &self,
+        timestep: &Timestep,
+        scenario_indices: &[ScenarioIndex],
+        network: &Network,
+        state: &[State],
+        _metric_set_states: &[Vec<MetricSetState>],
+        internal_state: &mut Option<Box<dyn Any>>,
+    ) -> Result<(), PywrError> {
+        let trace = downcast_internal_state_mut::<SolveTrace>(self.name(), internal_state)?;
+
+        for (scenario_index, state) in scenario_indices.iter().zip(state) {
+            if !self.traces(timestep, scenario_index) {
+                continue;
+            }
+
+            let pre_solve = state.pre_solve_trace().ok_or_else(|| {
+                PywrError::SolveTraceError(
+                    self.filename.clone(),
+                    format!(
+                        "the values of time-step {} of scenario {} were not captured before the solve",
+                        timestep.index, scenario_index.index
+                    ),
+                )
+            })?;
+
+            let entry = SolveTraceEntry::new(network, timestep, scenario_index, pre_solve, state)?;
+            trace.entries.push(entry);
+        }
+
+        Ok(())
+    }
+
+    fn finalise(
+        &self,
+        _network: &Network,
+        _metric_set_states: &[Vec<MetricSetState>],
+        internal_state: &mut Option<Box<dyn Any>>,
+    ) -> Result<(), PywrError> {
+        let trace = downcast_internal_state_mut::<SolveTrace>(self.name(), internal_state)?;
+
+        let map_err = |e: String| PywrError::SolveTraceError(self.filename.clone(), e);
+        let data = serde_json::to_string_pretty(trace).map_err(|e| map_err(e.to_string()))?;
+        std::fs::write(&self.filename, data).map_err(|e| map_err(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn output_paths(&self) -> Vec<&Path> {
+        vec![self.filename.as_path()]
+    }
+
+    fn describe(&self, _domain: &ModelDomain, _network: &Network) -> Result<Option<OutputDescription>, PywrError> {
+        Ok(Some(OutputDescription::new(
+            self.name(),
+            "trace",
+            Some(self.filename.clone()),
+            Vec::new(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SolveTrace, TraceRecorder};
+    use crate::recorders::TimeFilter;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_trace_recorder() {
+        let mut model = simple_model(2, None);
+
+        let date = NaiveDate::from_ymd_opt(2020, 1, 3)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("trace.json");
+        let recorder = TraceRecorder::new("trace", &path)
+            .with_time_filter(TimeFilter::new(Some(date), Some(date)))
+            .with_scenarios(vec![1]);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let trace = SolveTrace::from_path(&path).unwrap();
+        assert_eq!(trace.entries.len(), 1);
+
+        let entry = &trace.entries[0];
+        assert_eq!(entry.date, date);
+        assert_eq!(entry.scenario_index, 1);
+        assert_eq!(entry.nodes.len(), model.network().nodes().len());
+        assert_eq!(entry.edges.len(), model.network().edges().len());
+        assert!(!entry.parameters.is_empty());
+
+        // The flow of each node is within the bounds the solver saw
+        for node in entry.nodes.iter() {
+            assert!(node.in_flow <= node.max_flow + 1e-6);
+            assert!(node.in_flow >= node.min_flow - 1e-6);
+        }
+    }
+}
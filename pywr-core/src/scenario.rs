@@ -1,9 +1,14 @@
 use crate::PywrError;
+use std::collections::BTreeMap;
+
+/// The name of the scenario group added by [`ScenarioGroupCollection::add_repeats`].
+pub const REPEAT_GROUP: &str = "repeat";
 
 #[derive(Clone, Debug)]
 pub struct ScenarioGroup {
     name: String,
     size: usize,
+    subset: Option<Vec<usize>>,
     // TODO labels
     // labels: Option<Vec<String>>
 }
@@ -13,6 +18,7 @@ impl ScenarioGroup {
         Self {
             name: name.to_string(),
             size,
+            subset: None,
         }
     }
 
@@ -20,9 +26,70 @@ impl ScenarioGroup {
         &self.name
     }
 
+    /// The total number of members in the group, including those not in its subset.
     pub fn size(&self) -> usize {
         self.size
     }
+
+    /// The indices of the members of the group that are run, if only a subset of them are.
+    pub fn subset(&self) -> Option<&[usize]> {
+        self.subset.as_deref()
+    }
+
+    /// The number of members of the group that are run.
+    pub fn len(&self) -> usize {
+        self.subset.as_ref().map_or(self.size, |s| s.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The index of the `i`th member of the group that is run.
+    fn member(&self, i: usize) -> usize {
+        self.subset.as_ref().map_or(i, |s| s[i])
+    }
+
+    /// The indices of the members of the group that are run.
+    pub fn members(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).map(|i| self.member(i))
+    }
+}
+
+/// A selection of the members of a [`ScenarioGroup`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScenarioSubset {
+    /// The members from `start` (inclusive) to `end` (exclusive).
+    Slice { start: usize, end: usize },
+    /// The members with the given indices.
+    Indices(Vec<usize>),
+}
+
+impl ScenarioSubset {
+    /// Return the sorted and unique indices of the members of a group of `size` members.
+    fn indices(&self, group: &str, size: usize) -> Result<Vec<usize>, PywrError> {
+        let mut indices = match self {
+            Self::Slice { start, end } => (*start..*end).collect::<Vec<_>>(),
+            Self::Indices(indices) => indices.clone(),
+        };
+        indices.sort_unstable();
+        indices.dedup();
+
+        if indices.is_empty() {
+            return Err(PywrError::InvalidScenarioSubset {
+                group: group.to_string(),
+                reason: "no scenarios are selected".to_string(),
+            });
+        }
+        if let Some(idx) = indices.iter().find(|&&idx| idx >= size) {
+            return Err(PywrError::InvalidScenarioSubset {
+                group: group.to_string(),
+                reason: format!("index {idx} is out of range for a group of size {size}"),
+            });
+        }
+
+        Ok(indices)
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -77,21 +144,59 @@ impl ScenarioGroupCollection {
         self.groups.push(ScenarioGroup::new(name, size));
     }
 
+    /// Repeat every combination of the other groups `repeats` times.
+    ///
+    /// This adds a scenario group named [`REPEAT_GROUP`] before the other groups, so that the
+    /// whole scenario domain is run once for each repeat in a single model. The repeat is then a
+    /// dimension of the outputs like any other scenario group. Stochastic parameters (see
+    /// [`crate::parameters::RandomParameter`]) draw different random numbers in each repeat.
+    pub fn add_repeats(&mut self, repeats: usize) -> Result<(), PywrError> {
+        if self.groups.iter().any(|g| g.name == REPEAT_GROUP) {
+            return Err(PywrError::ScenarioGroupAlreadyExists(REPEAT_GROUP.to_string()));
+        }
+
+        self.groups.insert(0, ScenarioGroup::new(REPEAT_GROUP, repeats));
+        Ok(())
+    }
+
+    /// Restrict the scenario group with the given name to a subset of its members.
+    ///
+    /// Only the combinations of the members in the subset are run, and the model only
+    /// allocates state for those scenarios. The scenario indices retain the index of each
+    /// member in the full group so that data (e.g. the columns of a table) are still
+    /// selected by the member's original index.
+    pub fn subset(&mut self, name: &str, subset: &ScenarioSubset) -> Result<(), PywrError> {
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|g| g.name == name)
+            .ok_or_else(|| PywrError::ScenarioNotFound(name.to_string()))?;
+
+        group.subset = Some(subset.indices(name, group.size)?);
+        Ok(())
+    }
+
     /// Return a vector of `ScenarioIndex`s for all combinations of the groups.
     fn scenario_indices(&self) -> Vec<ScenarioIndex> {
-        let num: usize = self.groups.iter().map(|grp| grp.size).product();
+        let num: usize = self.groups.iter().map(|grp| grp.len()).product();
         let mut scenario_indices: Vec<ScenarioIndex> = Vec::with_capacity(num);
 
         for scenario_id in 0..num {
             let mut remaining = scenario_id;
             let mut indices: Vec<usize> = Vec::with_capacity(self.groups.len());
             for grp in self.groups.iter().rev() {
-                let idx = remaining % grp.size;
-                remaining /= grp.size;
-                indices.push(idx);
+                let idx = remaining % grp.len();
+                remaining /= grp.len();
+                indices.push(grp.member(idx));
             }
             indices.reverse();
-            scenario_indices.push(ScenarioIndex::new(scenario_id, indices));
+            // The index of this scenario had every member of every group been run
+            let global_id = self
+                .groups
+                .iter()
+                .zip(&indices)
+                .fold(0, |global_id, (grp, idx)| global_id * grp.size + idx);
+            scenario_indices.push(ScenarioIndex::new_with_global_index(scenario_id, global_id, indices));
         }
         scenario_indices
     }
@@ -99,13 +204,33 @@ impl ScenarioGroupCollection {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ScenarioIndex {
+    /// The index of the scenario among those that are run.
     pub(crate) index: usize,
+    /// The index of the scenario in the full scenario domain, ignoring any group subsets.
+    pub(crate) global_index: usize,
+    /// The index of the member of each group, in the full group.
     pub(crate) indices: Vec<usize>,
 }
 
 impl ScenarioIndex {
     pub(crate) fn new(index: usize, indices: Vec<usize>) -> Self {
-        Self { index, indices }
+        Self::new_with_global_index(index, index, indices)
+    }
+
+    pub(crate) fn new_with_global_index(index: usize, global_index: usize, indices: Vec<usize>) -> Self {
+        Self {
+            index,
+            global_index,
+            indices,
+        }
+    }
+
+    /// The index of the scenario in the full scenario domain, ignoring any group subsets.
+    ///
+    /// This is the index exposed to user-defined (e.g. Python and Rhai) parameters, so
+    /// that it does not depend on which scenarios are run.
+    pub fn global_index(&self) -> usize {
+        self.global_index
     }
 }
 
@@ -137,6 +262,28 @@ impl ScenarioDomain {
     pub fn groups(&self) -> &[ScenarioGroup] {
         &self.scenario_groups
     }
+
+    /// Partition the scenarios by their members of every group except the group at `group_idx`.
+    ///
+    /// Each partition contains the members of the remaining groups, and the position in
+    /// [`ScenarioDomain::indices`] of each scenario with those members. The partitions are sorted
+    /// by the members of the remaining groups.
+    pub fn partition_by_group(&self, group_idx: usize) -> Vec<(Vec<usize>, Vec<usize>)> {
+        let mut partitions: BTreeMap<Vec<usize>, Vec<usize>> = BTreeMap::new();
+
+        for (position, scenario_index) in self.scenario_indices.iter().enumerate() {
+            let remaining = scenario_index
+                .indices
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != group_idx)
+                .map(|(_, idx)| *idx)
+                .collect();
+            partitions.entry(remaining).or_default().push(position);
+        }
+
+        partitions.into_iter().collect()
+    }
 }
 
 impl From<ScenarioGroupCollection> for ScenarioDomain {
@@ -155,3 +302,99 @@ impl From<ScenarioGroupCollection> for ScenarioDomain {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ScenarioDomain, ScenarioGroupCollection, ScenarioSubset, REPEAT_GROUP};
+
+    #[test]
+    fn test_scenario_indices() {
+        let mut collection = ScenarioGroupCollection::default();
+        collection.add_group("a", 2);
+        collection.add_group("b", 3);
+
+        let domain: ScenarioDomain = collection.into();
+        assert_eq!(domain.len(), 6);
+        let indices: Vec<_> = domain.indices().iter().map(|si| si.indices.clone()).collect();
+        assert_eq!(
+            indices,
+            vec![vec![0, 0], vec![0, 1], vec![0, 2], vec![1, 0], vec![1, 1], vec![1, 2]]
+        );
+    }
+
+    #[test]
+    fn test_repeats() {
+        let mut collection = ScenarioGroupCollection::default();
+        collection.add_group("a", 2);
+        collection.add_repeats(3).unwrap();
+        assert!(collection.add_repeats(2).is_err());
+
+        let domain: ScenarioDomain = collection.into();
+        assert_eq!(domain.len(), 6);
+        assert_eq!(domain.group_index(REPEAT_GROUP), Some(0));
+
+        // Each repeat contains every scenario of the other groups
+        let indices: Vec<_> = domain.indices().iter().map(|si| si.indices.clone()).collect();
+        assert_eq!(
+            indices,
+            vec![vec![0, 0], vec![0, 1], vec![1, 0], vec![1, 1], vec![2, 0], vec![2, 1]]
+        );
+    }
+
+    #[test]
+    fn test_scenario_subset() {
+        let mut collection = ScenarioGroupCollection::default();
+        collection.add_group("a", 2);
+        collection.add_group("b", 200);
+
+        collection
+            .subset("b", &ScenarioSubset::Slice { start: 100, end: 103 })
+            .unwrap();
+        collection.subset("a", &ScenarioSubset::Indices(vec![1])).unwrap();
+
+        let domain: ScenarioDomain = collection.into();
+        assert_eq!(domain.len(), 3);
+        assert_eq!(domain.groups()[1].size(), 200);
+        assert_eq!(domain.groups()[1].len(), 3);
+
+        // The position of each scenario is within the subset, but the indices are of the full groups
+        let positions: Vec<_> = domain.indices().iter().map(|si| si.index).collect();
+        assert_eq!(positions, vec![0, 1, 2]);
+        let indices: Vec<_> = domain.indices().iter().map(|si| si.indices.clone()).collect();
+        assert_eq!(indices, vec![vec![1, 100], vec![1, 101], vec![1, 102]]);
+        // The global index is the position of each scenario in the full domain
+        let global: Vec<_> = domain.indices().iter().map(|si| si.global_index()).collect();
+        assert_eq!(global, vec![300, 301, 302]);
+    }
+
+    #[test]
+    fn test_partition_by_group() {
+        let mut collection = ScenarioGroupCollection::default();
+        collection.add_group("climate", 2);
+        collection.add_group("hydrology", 3);
+        let domain: ScenarioDomain = collection.into();
+
+        // Partition over the hydrology members; a partition for each climate scenario
+        assert_eq!(
+            domain.partition_by_group(1),
+            vec![(vec![0], vec![0, 1, 2]), (vec![1], vec![3, 4, 5])]
+        );
+        // Partition over the climate members; a partition for each hydrology member
+        assert_eq!(
+            domain.partition_by_group(0),
+            vec![(vec![0], vec![0, 3]), (vec![1], vec![1, 4]), (vec![2], vec![2, 5])]
+        );
+    }
+
+    #[test]
+    fn test_invalid_scenario_subset() {
+        let mut collection = ScenarioGroupCollection::default();
+        collection.add_group("a", 10);
+
+        assert!(collection
+            .subset("a", &ScenarioSubset::Slice { start: 5, end: 11 })
+            .is_err());
+        assert!(collection.subset("a", &ScenarioSubset::Indices(vec![])).is_err());
+        assert!(collection.subset("b", &ScenarioSubset::Indices(vec![0])).is_err());
+    }
+}
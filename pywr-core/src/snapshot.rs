@@ -0,0 +1,287 @@
+use crate::state::State;
+use crate::timestep::TimestepIndex;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+use thiserror::Error;
+
+/// The magic bytes at the start of every snapshot.
+const MAGIC: &[u8; 8] = b"PYWRSNAP";
+/// The length of the header; the magic bytes, the major and minor version and the payload length.
+const HEADER_LEN: usize = MAGIC.len() + 2 + 2 + 8;
+
+/// The version of the snapshot format.
+///
+/// The major version is incremented whenever the layout of the payload changes. A snapshot
+/// can only be read by a release that writes the same major version. The minor version is
+/// incremented for changes that do not alter the layout (e.g. a change in how a value is
+/// interpreted). Snapshots with an older, or equal, minor version can be read, but those
+/// written by a newer release with a higher minor version are rejected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SnapshotVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl SnapshotVersion {
+    /// The version written by this release.
+    pub const CURRENT: Self = Self { major: 3, minor: 0 };
+
+    /// Returns `true` if a snapshot of this version can be read by this release.
+    pub fn is_supported(&self) -> bool {
+        self.major == Self::CURRENT.major && self.minor <= Self::CURRENT.minor
+    }
+}
+
+impl Display for SnapshotVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("data is not a Pywr state snapshot")]
+    InvalidMagic,
+    #[error("snapshot is truncated; expected {expected} bytes but found {found}")]
+    Truncated { expected: usize, found: usize },
+    #[error("snapshot version {found} is not supported; this release supports version {supported}")]
+    UnsupportedVersion {
+        found: SnapshotVersion,
+        supported: SnapshotVersion,
+    },
+    #[error("snapshot contains {found} scenario states but the model has {expected}")]
+    ScenarioCountMismatch { expected: usize, found: usize },
+    #[error("snapshot state for scenario {0} does not match the model's network")]
+    IncompatibleState(usize),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A snapshot of the [`State`] of every scenario at a given time-step.
+///
+/// Snapshots are stored in a compact binary format consisting of a fixed size header followed
+/// by the payload (see [`crate::encoding`]). The header contains magic bytes, the
+/// [`SnapshotVersion`] and the length of the payload (all little-endian). The version is checked when a snapshot
+/// is read so that incompatible snapshots are rejected with an error rather than decoded into
+/// an invalid state.
+///
+/// Only the [`State`] is included in the snapshot. The internal states of parameters and
+/// recorders are not included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    timestep_index: TimestepIndex,
+    states: Vec<State>,
+}
+
+impl StateSnapshot {
+    pub fn new(timestep_index: TimestepIndex, states: Vec<State>) -> Self {
+        Self { timestep_index, states }
+    }
+
+    /// The index of the next time-step to be simulated from this snapshot.
+    pub fn timestep_index(&self) -> TimestepIndex {
+        self.timestep_index
+    }
+
+    /// The state of each scenario.
+    pub fn states(&self) -> &[State] {
+        &self.states
+    }
+
+    pub fn into_states(self) -> Vec<State> {
+        self.states
+    }
+
+    /// Encode the snapshot, including its header, in to bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let payload = crate::encoding::serialize(self)?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&SnapshotVersion::CURRENT.major.to_le_bytes());
+        bytes.extend_from_slice(&SnapshotVersion::CURRENT.minor.to_le_bytes());
+        bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+
+        Ok(bytes)
+    }
+
+    /// Read the version of a snapshot from its header without decoding the payload.
+    pub fn read_version(bytes: &[u8]) -> Result<SnapshotVersion, SnapshotError> {
+        if bytes.len() < HEADER_LEN {
+            if !MAGIC.starts_with(&bytes[..bytes.len().min(MAGIC.len())]) {
+                return Err(SnapshotError::InvalidMagic);
+            }
+            return Err(SnapshotError::Truncated {
+                expected: HEADER_LEN,
+                found: bytes.len(),
+            });
+        }
+
+        if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+
+        let major = u16::from_le_bytes([bytes[8], bytes[9]]);
+        let minor = u16::from_le_bytes([bytes[10], bytes[11]]);
+
+        Ok(SnapshotVersion { major, minor })
+    }
+
+    /// Decode a snapshot from bytes.
+    ///
+    /// An error is returned if the bytes are not a snapshot, the snapshot is truncated or the
+    /// snapshot's version is not supported by this release.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let version = Self::read_version(bytes)?;
+        if !version.is_supported() {
+            return Err(SnapshotError::UnsupportedVersion {
+                found: version,
+                supported: SnapshotVersion::CURRENT,
+            });
+        }
+
+        let mut len = [0u8; 8];
+        len.copy_from_slice(&bytes[12..HEADER_LEN]);
+        let payload_len = u64::from_le_bytes(len) as usize;
+
+        let expected = HEADER_LEN.saturating_add(payload_len);
+        if bytes.len() < expected {
+            return Err(SnapshotError::Truncated {
+                expected,
+                found: bytes.len(),
+            });
+        }
+
+        let snapshot = crate::encoding::deserialize(&bytes[HEADER_LEN..expected])?;
+        Ok(snapshot)
+    }
+
+    /// Write the snapshot to a file.
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Read a snapshot from a file.
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Check that this snapshot can replace the given states.
+    ///
+    /// The snapshot must contain a state for each scenario and each of those must have been
+    /// created for the same network (i.e. have the same number of nodes, parameters, etc.).
+    pub fn check_compatible(&self, states: &[State]) -> Result<(), SnapshotError> {
+        if self.states.len() != states.len() {
+            return Err(SnapshotError::ScenarioCountMismatch {
+                expected: states.len(),
+                found: self.states.len(),
+            });
+        }
+
+        for (idx, (snapshot_state, state)) in self.states.iter().zip(states).enumerate() {
+            if !snapshot_state.has_same_shape(state) {
+                return Err(SnapshotError::IncompatibleState(idx));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SnapshotError, SnapshotVersion, StateSnapshot, HEADER_LEN};
+    use crate::models::ModelState;
+    use crate::network::Network;
+    use crate::node::NodeIndex;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_storage_model;
+
+    fn reservoir_volume<S>(state: &ModelState<S>, network: &Network) -> f64 {
+        let idx: NodeIndex = network.get_node_index_by_name("reservoir", None).unwrap();
+        state.network_state().states()[0]
+            .get_network_state()
+            .get_node_volume(&idx)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let model = simple_storage_model();
+        let settings = ClpSolverSettings::default();
+        let mut state = model.setup::<ClpSolver>(&settings).unwrap();
+
+        let mut timings = Default::default();
+        for _ in 0..3 {
+            model.step::<ClpSolver>(&mut state, None, &mut timings).unwrap();
+        }
+        let volume = reservoir_volume(&state, model.network());
+        assert_eq!(volume, 70.0);
+
+        let bytes = state.snapshot().to_bytes().unwrap();
+        assert_eq!(StateSnapshot::read_version(&bytes).unwrap(), SnapshotVersion::CURRENT);
+
+        // Restore the snapshot in to a freshly setup model
+        let snapshot = StateSnapshot::from_bytes(&bytes).unwrap();
+        let mut new_state = model.setup::<ClpSolver>(&settings).unwrap();
+        new_state.restore(snapshot).unwrap();
+        assert_eq!(reservoir_volume(&new_state, model.network()), volume);
+
+        // The restored model continues from the snapshot's time-step
+        model.step::<ClpSolver>(&mut new_state, None, &mut timings).unwrap();
+        assert_eq!(reservoir_volume(&new_state, model.network()), 60.0);
+    }
+
+    #[test]
+    fn test_snapshot_version_checks() {
+        let model = simple_storage_model();
+        let state = model.setup::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+        let bytes = state.snapshot().to_bytes().unwrap();
+
+        // Older minor versions are accepted; newer minor and other major versions are not
+        let mut newer_minor = bytes.clone();
+        newer_minor[10..12].copy_from_slice(&(SnapshotVersion::CURRENT.minor + 1).to_le_bytes());
+        assert!(matches!(
+            StateSnapshot::from_bytes(&newer_minor),
+            Err(SnapshotError::UnsupportedVersion { .. })
+        ));
+
+        let mut other_major = bytes.clone();
+        other_major[8..10].copy_from_slice(&(SnapshotVersion::CURRENT.major + 1).to_le_bytes());
+        assert!(matches!(
+            StateSnapshot::from_bytes(&other_major),
+            Err(SnapshotError::UnsupportedVersion { .. })
+        ));
+
+        assert!(matches!(
+            StateSnapshot::from_bytes(b"not a snapshot at all"),
+            Err(SnapshotError::InvalidMagic)
+        ));
+
+        assert!(matches!(
+            StateSnapshot::from_bytes(&bytes[..HEADER_LEN + 1]),
+            Err(SnapshotError::Truncated { .. })
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_incompatible_network() {
+        let model = simple_storage_model();
+        let other_model = crate::test_utils::simple_model(1, None);
+        let settings = ClpSolverSettings::default();
+
+        let state = model.setup::<ClpSolver>(&settings).unwrap();
+        let mut other_state = other_model.setup::<ClpSolver>(&settings).unwrap();
+
+        assert!(matches!(
+            other_state.restore(state.snapshot()),
+            Err(crate::PywrError::Snapshot(SnapshotError::IncompatibleState(0)))
+        ));
+    }
+}
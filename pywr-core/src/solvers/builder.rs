@@ -1,11 +1,12 @@
 use crate::aggregated_node::AggregatedNodeIndex;
 use crate::edge::EdgeIndex;
 use crate::network::Network;
-use crate::node::{Node, NodeBounds, NodeIndex, NodeType};
+use crate::node::{Node, NodeBounds, NodeIndex, NodeType, SwitchStatus};
 use crate::solvers::col_edge_map::{ColumnEdgeMap, ColumnEdgeMapBuilder};
-use crate::solvers::SolverTimings;
-use crate::state::{ConstParameterValues, State};
+use crate::solvers::{LpStatistics, SolverTimings};
+use crate::state::{ConstParameterValues, DualValues, State};
 use crate::timestep::Timestep;
+use crate::virtual_storage::VirtualStorageIndex;
 use crate::PywrError;
 use num::Zero;
 use std::collections::{BTreeMap, HashMap, HashSet};
@@ -46,6 +47,8 @@ struct Lp<I> {
     row_starts: Vec<I>,
     columns: Vec<I>,
     elements: Vec<f64>,
+    /// The constraints represented by each row; a row may be shared by more than one constraint.
+    row_sources: Vec<Vec<RowSource>>,
 
     coefficients_to_update: Vec<(I, I, f64)>,
 }
@@ -137,6 +140,10 @@ struct LpBuilder<I> {
     col_type: Vec<ColType>,
     rows: Vec<RowBuilder<I>>,
     fixed_rows: Vec<RowBuilder<I>>,
+    /// The constraints represented by each variable row.
+    row_sources: Vec<Vec<RowSource>>,
+    /// The constraint represented by each fixed row.
+    fixed_row_sources: Vec<RowSource>,
 }
 
 impl<I> Default for LpBuilder<I>
@@ -151,6 +158,8 @@ where
             col_type: Vec::new(),
             rows: Vec::new(),
             fixed_rows: Vec::new(),
+            row_sources: Vec::new(),
+            fixed_row_sources: Vec::new(),
         }
     }
 }
@@ -177,20 +186,28 @@ where
 
     /// Add a fixed row to the LP.
     ///
-    /// This row is always added to the end of the LP, and does not return its row number
-    /// because it should not be changed again.
-    fn add_fixed_row(&mut self, row: RowBuilder<I>) {
+    /// This row is always added to the end of the LP. Its row number is not known until the LP
+    /// is built, so its position among the fixed rows is returned instead.
+    fn add_fixed_row(&mut self, row: RowBuilder<I>, source: RowSource) -> usize {
         self.fixed_rows.push(row);
+        self.fixed_row_sources.push(source);
+        self.fixed_rows.len() - 1
     }
 
     /// Add a row to the LP or return an existing row number if the same row already exists.
-    fn add_variable_row(&mut self, row: RowBuilder<I>) -> I {
+    ///
+    /// `source` is the constraint of the network that the row represents.
+    fn add_variable_row(&mut self, row: RowBuilder<I>, source: RowSource) -> I {
         match self.rows.iter().position(|r| r == &row) {
-            Some(row_id) => I::from(row_id).unwrap(),
+            Some(row_id) => {
+                self.row_sources[row_id].push(source);
+                I::from(row_id).unwrap()
+            }
             None => {
                 // No row found, add a new one
                 let row_id = self.num_variable_rows();
                 self.rows.push(row);
+                self.row_sources.push(vec![source]);
                 row_id
             }
         }
@@ -230,6 +247,9 @@ where
             }
         }
 
+        let mut row_sources = self.row_sources;
+        row_sources.extend(self.fixed_row_sources.into_iter().map(|source| vec![source]));
+
         Lp {
             col_lower: self.col_lower,
             col_upper: self.col_upper,
@@ -241,6 +261,7 @@ where
             row_starts,
             columns,
             elements,
+            row_sources,
             coefficients_to_update: Vec::new(),
         }
     }
@@ -281,12 +302,14 @@ where
 
     /// Add an element to the row
     ///
-    /// If the column already exists `value` will be added to the existing coefficient.
-    pub fn add_element(&mut self, column: I, value: f64) {
+    /// If the column already exists `value` will be added to the existing coefficient. An error
+    /// is returned if `value` is not finite.
+    pub fn add_element(&mut self, column: I, value: f64) -> Result<(), PywrError> {
         if !value.is_finite() {
-            panic!("Row factor is non-finite.");
+            return Err(PywrError::NonFiniteRowFactor);
         }
         *self.columns.entry(column).or_insert(0.0) += value;
+        Ok(())
     }
 }
 
@@ -299,6 +322,14 @@ struct NodeRowId<I> {
     row_type: NodeRowType<I>,
 }
 
+/// The position of a row in the LP before it is built.
+#[derive(Copy, Clone)]
+enum RowPosition<I> {
+    Variable(I),
+    /// The position of the row among the fixed rows.
+    Fixed(usize),
+}
+
 /// The row id types associated with a node's constraints.
 #[derive(Copy, Clone)]
 enum NodeRowType<I> {
@@ -308,6 +339,25 @@ enum NodeRowType<I> {
     Binary { bin_col_id: I },
 }
 
+/// The constraint of the network that a row of the LP represents.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RowSource {
+    /// The mass balance of a link node.
+    MassBalance(NodeIndex),
+    /// The flow bounds of a node.
+    NodeFlow(NodeIndex),
+    /// The flow bounds of an aggregated node.
+    AggregatedNodeFlow(AggregatedNodeIndex),
+    /// The flow factors of an aggregated node.
+    AggregatedNodeFactors(AggregatedNodeIndex),
+    /// The volume bounds of a virtual storage node.
+    VirtualStorage(VirtualStorageIndex),
+    /// The minimum flow when on, or the status, of a node with switching constraints.
+    Switching(NodeIndex),
+    /// The number of active nodes of an aggregated node with mutual exclusivity.
+    MutualExclusivity(AggregatedNodeIndex),
+}
+
 struct AggNodeFactorRow<I> {
     agg_node_idx: AggregatedNodeIndex,
     // Row index for each node-pair. If `None` the row is fixed and does not need updating.
@@ -321,6 +371,14 @@ pub struct BuiltSolver<I> {
     agg_node_constraint_row_ids: Vec<usize>,
     agg_node_factor_constraint_row_ids: Vec<AggNodeFactorRow<I>>,
     virtual_storage_constraint_row_ids: Vec<usize>,
+    /// The row of each node's (non-binary) flow constraint used to save its dual value.
+    node_dual_row_ids: Vec<(NodeIndex, usize)>,
+    /// The row bounding the binary status variable of each node with switching constraints.
+    switching_constraint_row_ids: Vec<(NodeIndex, usize)>,
+    /// The perturbation of each edge's cost used to break ties (see [`SolverBuilder::with_tie_breaking`]).
+    ///
+    /// Empty if tie-breaking is disabled.
+    edge_cost_perturbations: Vec<f64>,
 }
 
 impl<I> BuiltSolver<I>
@@ -331,7 +389,6 @@ where
         I::from(self.builder.col_upper.len()).unwrap()
     }
 
-    #[allow(dead_code)]
     pub fn num_rows(&self) -> I {
         I::from(self.builder.row_upper.len()).unwrap()
     }
@@ -353,7 +410,6 @@ where
         &self.builder.col_obj_coef
     }
 
-    #[allow(dead_code)]
     pub fn col_type(&self) -> &[ColType] {
         &self.builder.col_type
     }
@@ -391,6 +447,58 @@ where
         &self.builder.coefficients_to_update
     }
 
+    /// The constraints of the network represented by `row`.
+    pub fn row_sources(&self, row: usize) -> &[RowSource] {
+        &self.builder.row_sources[row]
+    }
+
+    /// The row of the constraint of each storage node.
+    pub fn storage_node_row_ids(&self, network: &Network) -> Result<Vec<usize>, PywrError> {
+        let mut row_ids = Vec::new();
+        for row in self.node_constraints_row_ids.iter() {
+            let node = network.get_node(&row.node_idx)?;
+            if matches!(row.row_type, NodeRowType::Continuous) && matches!(node.node_type(), NodeType::Storage) {
+                row_ids.push(row.row_id.to_usize().unwrap());
+            }
+        }
+        Ok(row_ids)
+    }
+
+    /// Map the dual value of each row of the LP to the network's constraints.
+    pub fn dual_values(&self, network: &Network, row_duals: &[f64]) -> DualValues {
+        let mut nodes = vec![None; network.nodes().len()];
+        for (node_idx, row_id) in self.node_dual_row_ids.iter() {
+            nodes[*node_idx.deref()] = Some(row_duals[*row_id]);
+        }
+
+        let aggregated_nodes = self
+            .agg_node_constraint_row_ids
+            .iter()
+            .map(|row_id| row_duals[*row_id])
+            .collect();
+
+        let virtual_storage = self
+            .virtual_storage_constraint_row_ids
+            .iter()
+            .map(|row_id| row_duals[*row_id])
+            .collect();
+
+        DualValues::new(nodes, aggregated_nodes, virtual_storage)
+    }
+
+    /// Calculate statistics of the constraint matrix.
+    pub fn statistics(&self) -> LpStatistics {
+        let row_starts: Vec<usize> = self.row_starts().iter().map(|s| s.to_usize().unwrap()).collect();
+        let columns: Vec<usize> = self.columns().iter().map(|c| c.to_usize().unwrap()).collect();
+
+        LpStatistics::from_sparse_rows(
+            self.num_cols().to_usize().unwrap(),
+            &row_starts,
+            &columns,
+            self.elements(),
+        )
+    }
+
     pub fn update(
         &mut self,
         network: &Network,
@@ -411,6 +519,7 @@ where
         self.update_aggregated_node_factor_constraints(network, state)?;
         self.update_aggregated_node_constraint_bounds(network, state)?;
         self.update_virtual_storage_node_constraint_bounds(network, timestep, state)?;
+        self.update_switching_constraint_bounds(network, state)?;
         timings.update_constraints += start_constraint_update.elapsed();
 
         Ok(())
@@ -420,7 +529,10 @@ where
     fn update_edge_objectives(&mut self, network: &Network, state: &State) -> Result<(), PywrError> {
         self.builder.zero_obj_coefficients();
         for edge in network.edges().deref() {
-            let obj_coef: f64 = edge.cost(network.nodes(), network, state)?;
+            let mut obj_coef: f64 = edge.cost(network.nodes(), network, state)?;
+            if let Some(perturbation) = self.edge_cost_perturbations.get(*edge.index()) {
+                obj_coef += perturbation;
+            }
             let col = self.col_for_edge(&edge.index());
 
             self.builder.add_obj_coefficient(col.to_usize().unwrap(), obj_coef);
@@ -435,14 +547,10 @@ where
         timestep: &Timestep,
         state: &State,
     ) -> Result<(), PywrError> {
-        let dt = timestep.days();
-
         for row in self.node_constraints_row_ids.iter() {
             let node = network.get_node(&row.node_idx)?;
-            let (lb, ub): (f64, f64) = match node.get_bounds(network, state)? {
-                NodeBounds::Flow(bounds) => (bounds.min_flow, bounds.max_flow),
-                NodeBounds::Volume(bounds) => (-bounds.available / dt, bounds.missing / dt),
-            };
+            let bounds = network.get_node_flow_bounds(&row.node_idx, timestep, state)?;
+            let (lb, ub) = (bounds.min_flow, bounds.max_flow);
 
             match row.row_type {
                 NodeRowType::Continuous => {
@@ -451,7 +559,7 @@ where
                 }
                 NodeRowType::Binary { bin_col_id } => {
                     if !lb.is_zero() {
-                        panic!("Binary node constraint with non-zero lower bounds not implemented!");
+                        return Err(PywrError::BinaryNodeNonZeroLowerBound(node.name().to_string()));
                     }
                     // Update the coefficients for the binary column to be the upper bound
                     self.builder.coefficients_to_update.push((row.row_id, bin_col_id, -ub));
@@ -468,7 +576,7 @@ where
         for agg_node_row in self.agg_node_factor_constraint_row_ids.iter() {
             let agg_node = network.get_aggregated_node(&agg_node_row.agg_node_idx)?;
             // Only create row for nodes that have factors
-            if let Some(node_pairs) = agg_node.get_norm_factor_pairs(network, state) {
+            if let Some(node_pairs) = agg_node.get_norm_factor_pairs(network, state)? {
                 assert_eq!(
                     agg_node_row.row_indices.len(),
                     node_pairs.len(),
@@ -479,16 +587,15 @@ where
                     // Only update pairs with a row index (i.e. not fixed)
                     if let Some(row_idx) = row_idx {
                         // Modify the constraint matrix coefficients for the nodes
-                        // TODO error handling?
                         let nodes = network.nodes();
                         for node0_idx in node_pair.node0.indices {
-                            let node0 = nodes.get(node0_idx).expect("Node index not found!");
+                            let node0 = nodes.get(node0_idx)?;
                             self.builder
                                 .update_row_coefficients(*row_idx, node0, 1.0, &self.col_edge_map);
                         }
 
                         for node1_idx in node_pair.node1.indices {
-                            let node1 = nodes.get(node1_idx).expect("Node index not found!");
+                            let node1 = nodes.get(node1_idx)?;
                             self.builder.update_row_coefficients(
                                 *row_idx,
                                 node1,
@@ -501,7 +608,7 @@ where
                     }
                 }
             } else {
-                panic!("No factor pairs found for an aggregated node that was setup with factors?!");
+                return Err(PywrError::AggregatedNodeFactorsNotDefined(agg_node.name().to_string()));
             }
         }
 
@@ -535,7 +642,7 @@ where
             .iter()
             .zip(network.virtual_storage_nodes().deref())
         {
-            let (avail, missing) = node.get_available_volume_bounds(state)?;
+            let (avail, missing) = node.get_available_volume_bounds(timestep, state)?;
 
             let (lb, ub) = (-avail / dt, missing / dt);
             self.builder.apply_row_bounds(*row_id, lb, ub);
@@ -543,6 +650,28 @@ where
 
         Ok(())
     }
+
+    /// Fix the status of nodes that have not been on, or off, for their minimum up, or down, time.
+    fn update_switching_constraint_bounds(&mut self, network: &Network, state: &State) -> Result<(), PywrError> {
+        for (node_idx, row_id) in self.switching_constraint_row_ids.iter() {
+            let node = network.get_node(node_idx)?;
+            let switch_state = state.get_network_state().get_node_switch_state(node_idx)?;
+
+            let status = match node.get_switching_constraints() {
+                Some(switching) => switching.status(switch_state),
+                None => SwitchStatus::Free,
+            };
+
+            let (lb, ub) = match status {
+                SwitchStatus::Free => (0.0, 1.0),
+                SwitchStatus::MustBeOn => (1.0, 1.0),
+                SwitchStatus::MustBeOff => (0.0, 0.0),
+            };
+            self.builder.apply_row_bounds(*row_id, lb, ub);
+        }
+
+        Ok(())
+    }
 }
 
 pub struct SolverBuilder<I> {
@@ -550,6 +679,32 @@ pub struct SolverBuilder<I> {
     col_edge_map: ColumnEdgeMapBuilder<I>,
     node_bin_col_map: HashMap<NodeIndex, Vec<I>>,
     node_set_bin_col_map: HashMap<Vec<NodeIndex>, I>,
+    node_dual_rows: Vec<(NodeIndex, RowPosition<I>)>,
+    /// The binary status column of each node with switching constraints.
+    switching_cols: Vec<(NodeIndex, I)>,
+    tie_breaking: Option<f64>,
+}
+
+/// The perturbation of each edge's cost, indexed by edge, used to break ties between routes.
+///
+/// See [`SolverBuilder::with_tie_breaking`].
+fn edge_cost_perturbations(network: &Network, epsilon: f64) -> Result<Vec<f64>, PywrError> {
+    let mut perturbations = vec![0.0; network.edges().len()];
+    for node in network.nodes().iter() {
+        if let NodeType::Output = node.node_type() {
+            continue;
+        }
+        let outgoing_edges = node.get_outgoing_edges()?;
+        if outgoing_edges.len() < 2 {
+            continue;
+        }
+        let mid = (outgoing_edges.len() - 1) as f64 / 2.0;
+        let step = epsilon / (outgoing_edges.len() - 1) as f64;
+        for (rank, edge_index) in outgoing_edges.iter().enumerate() {
+            perturbations[**edge_index] = step * (rank as f64 - mid);
+        }
+    }
+    Ok(perturbations)
 }
 
 impl<I> Default for SolverBuilder<I>
@@ -562,6 +717,9 @@ where
             col_edge_map: ColumnEdgeMapBuilder::default(),
             node_bin_col_map: HashMap::new(),
             node_set_bin_col_map: HashMap::new(),
+            node_dual_rows: Vec::new(),
+            switching_cols: Vec::new(),
+            tie_breaking: None,
         }
     }
 }
@@ -574,22 +732,51 @@ where
         self.col_edge_map.col_for_edge(edge_index)
     }
 
+    /// Break ties between routes of equal cost by perturbing the cost of each edge.
+    ///
+    /// Only the edges leaving a node with more than one outgoing edge are perturbed. These are
+    /// ordered by the order in which they were added to the network, and perturbed by values
+    /// spread evenly between `-epsilon / 2` and `epsilon / 2`, so that the edges added first are
+    /// preferred. The perturbations at each branch sum to zero, and a route is only perturbed
+    /// where it branches, so the length of a route does not change its perturbation. This makes
+    /// the allocation between routes of equal cost independent of the solver.
+    pub fn with_tie_breaking(mut self, epsilon: Option<f64>) -> Self {
+        self.tie_breaking = epsilon;
+        self
+    }
+
     pub fn create(mut self, network: &Network, values: &ConstParameterValues) -> Result<BuiltSolver<I>, PywrError> {
         // Create the columns
         self.create_columns(network)?;
 
         // Create edge mass balance constraints
-        self.create_mass_balance_constraints(network);
+        self.create_mass_balance_constraints(network)?;
         // Create the nodal constraints
         let node_constraints_row_ids = self.create_node_constraints(network, values)?;
         // Create the aggregated node constraints
-        let agg_node_constraint_row_ids = self.create_aggregated_node_constraints(network);
+        let agg_node_constraint_row_ids = self.create_aggregated_node_constraints(network)?;
         // Create the aggregated node factor constraints
-        let agg_node_factor_constraint_row_ids = self.create_aggregated_node_factor_constraints(network, values);
+        let agg_node_factor_constraint_row_ids = self.create_aggregated_node_factor_constraints(network, values)?;
         // Create virtual storage constraints
-        let virtual_storage_constraint_row_ids = self.create_virtual_storage_constraints(network);
+        let virtual_storage_constraint_row_ids = self.create_virtual_storage_constraints(network)?;
         // Create mutual exclusivity constraints
-        self.create_mutual_exclusivity_constraints(network);
+        self.create_mutual_exclusivity_constraints(network)?;
+        // Create switching constraints
+        let switching_constraint_row_ids = self.create_switching_constraints(network)?;
+
+        // Fixed rows are added after all of the variable rows
+        let num_variable_rows = self.builder.num_variable_rows().to_usize().unwrap();
+        let node_dual_row_ids = self
+            .node_dual_rows
+            .iter()
+            .map(|(node_idx, position)| {
+                let row_id = match position {
+                    RowPosition::Variable(row_id) => row_id.to_usize().unwrap(),
+                    RowPosition::Fixed(fixed_idx) => num_variable_rows + fixed_idx,
+                };
+                (*node_idx, row_id)
+            })
+            .collect();
 
         Ok(BuiltSolver {
             builder: self.builder.build(),
@@ -598,6 +785,12 @@ where
             agg_node_factor_constraint_row_ids,
             agg_node_constraint_row_ids,
             virtual_storage_constraint_row_ids,
+            node_dual_row_ids,
+            switching_constraint_row_ids,
+            edge_cost_perturbations: match self.tie_breaking {
+                Some(epsilon) => edge_cost_perturbations(network, epsilon)?,
+                None => Vec::new(),
+            },
         })
     }
 
@@ -623,7 +816,9 @@ where
                 let incoming_edges = from_node.get_incoming_edges()?;
                 // NB `edge` should be one of these outgoing edges
                 let outgoing_edges = from_node.get_outgoing_edges()?;
-                assert!(outgoing_edges.contains(&edge_index));
+                if !outgoing_edges.contains(&edge_index) {
+                    return Err(PywrError::EdgeNotOutgoingFromNode(from_node.name().to_string()));
+                }
                 if (incoming_edges.len() == 1) && (outgoing_edges.len() == 1) {
                     // Because of the mass-balance constraint these two edges must be equal to
                     // one another.
@@ -666,82 +861,94 @@ where
             self.node_set_bin_col_map.insert(node_set.to_vec(), col_id);
         }
 
+        // Add a binary column for the status of each node with switching constraints. The node's
+        // flow is bounded by this column in the same way as a node in a mutual exclusivity.
+        for node in network.nodes().deref() {
+            if node.get_switching_constraints().is_some() {
+                let col_id = self.builder.add_column(0.0, Bounds::Double(0.0, 1.0), ColType::Integer);
+                self.node_bin_col_map.entry(node.index()).or_default().push(col_id);
+                self.switching_cols.push((node.index(), col_id));
+            }
+        }
+
         Ok(())
     }
 
     /// Create mass balance constraints for each edge
-    fn create_mass_balance_constraints(&mut self, network: &Network) {
+    fn create_mass_balance_constraints(&mut self, network: &Network) -> Result<(), PywrError> {
         for node in network.nodes().deref() {
             // Only link nodes create mass-balance constraints
 
             if let NodeType::Link = node.node_type() {
                 let mut row: RowBuilder<I> = RowBuilder::default();
 
-                let incoming_edges = node.get_incoming_edges().unwrap();
-                let outgoing_edges = node.get_outgoing_edges().unwrap();
+                let incoming_edges = node.get_incoming_edges()?;
+                let outgoing_edges = node.get_outgoing_edges()?;
 
-                // TODO use Display for the error message
                 if incoming_edges.is_empty() {
-                    panic!("Node {:?} contains no incoming edges 💥", node.full_name())
+                    return Err(PywrError::NodeHasNoIncomingEdges(node.name().to_string()));
                 }
                 if outgoing_edges.is_empty() {
-                    panic!("Node {:?} contains no outgoing edges 💥", node.full_name())
+                    return Err(PywrError::NodeHasNoOutgoingEdges(node.name().to_string()));
                 }
 
                 for edge in incoming_edges {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, 1.0);
+                    row.add_element(column, 1.0)?;
                 }
                 for edge in outgoing_edges {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, -1.0);
+                    row.add_element(column, -1.0)?;
                 }
 
                 if row.columns.is_empty() {
-                    panic!("Row contains no columns!")
+                    return Err(PywrError::MassBalanceRowHasNoColumns(node.name().to_string()));
                 } else if row.columns.len() == 1 {
                     // Skip this row because the edges must be mapped to the same column
                 } else {
                     row.set_upper(0.0);
                     row.set_lower(0.0);
 
-                    self.builder.add_fixed_row(row);
+                    self.builder.add_fixed_row(row, RowSource::MassBalance(node.index()));
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn add_node(&self, node: &Node, factor: f64, row: &mut RowBuilder<I>) {
+    fn add_node(&self, node: &Node, factor: f64, row: &mut RowBuilder<I>) -> Result<(), PywrError> {
         match node.node_type() {
             NodeType::Link => {
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Input => {
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Output => {
-                for edge in node.get_incoming_edges().unwrap() {
+                for edge in node.get_incoming_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Storage => {
-                for edge in node.get_incoming_edges().unwrap() {
+                for edge in node.get_incoming_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, -factor);
+                    row.add_element(column, -factor)?;
                 }
             }
         }
+        Ok(())
     }
 
     /// Create node constraints
@@ -768,16 +975,16 @@ where
             if let Some(cols) = self.node_bin_col_map.get(&node.index()) {
                 for col in cols {
                     let mut row: RowBuilder<I> = RowBuilder::default();
-                    self.add_node(node, 1.0, &mut row);
+                    self.add_node(node, 1.0, &mut row)?;
                     let mut is_fixed = false;
 
                     match bounds {
                         Some(bounds) => {
                             if bounds.min_flow != 0.0 {
-                                panic!("Binary node constraint with non-zero lower bounds not implemented!");
+                                return Err(PywrError::BinaryNodeNonZeroLowerBound(node.name().to_string()));
                             }
                             // If the bounds are constant then the binary variable is used to control the upper bound
-                            row.add_element(*col, -bounds.max_flow.min(1e8));
+                            row.add_element(*col, -bounds.max_flow.min(1e8))?;
                             row.set_lower(FMIN);
                             row.set_upper(0.0);
                             is_fixed = true;
@@ -785,14 +992,14 @@ where
                         None => {
                             // If the bounds are not constant then the binary variable coefficient is updated later
                             // Use a placeholder of -1.0 for now
-                            row.add_element(*col, -1.0);
+                            row.add_element(*col, -1.0)?;
                         }
                     }
 
                     if is_fixed {
-                        self.builder.add_fixed_row(row);
+                        self.builder.add_fixed_row(row, RowSource::NodeFlow(node.index()));
                     } else {
-                        let row_id = self.builder.add_variable_row(row);
+                        let row_id = self.builder.add_variable_row(row, RowSource::NodeFlow(node.index()));
                         let row_type = NodeRowType::Binary { bin_col_id: *col };
 
                         row_ids.push(NodeRowId {
@@ -804,7 +1011,7 @@ where
                 }
             } else {
                 let mut row: RowBuilder<I> = RowBuilder::default();
-                self.add_node(node, 1.0, &mut row);
+                self.add_node(node, 1.0, &mut row)?;
                 let mut is_fixed = false;
 
                 // Apply the bounds if they are constant; otherwise the bounds are updated later
@@ -815,9 +1022,11 @@ where
                 }
 
                 if is_fixed {
-                    self.builder.add_fixed_row(row);
+                    let fixed_idx = self.builder.add_fixed_row(row, RowSource::NodeFlow(node.index()));
+                    self.node_dual_rows.push((node.index(), RowPosition::Fixed(fixed_idx)));
                 } else {
-                    let row_id = self.builder.add_variable_row(row);
+                    let row_id = self.builder.add_variable_row(row, RowSource::NodeFlow(node.index()));
+                    self.node_dual_rows.push((node.index(), RowPosition::Variable(row_id)));
 
                     row_ids.push(NodeRowId {
                         row_id,
@@ -837,12 +1046,12 @@ where
         &mut self,
         network: &Network,
         values: &ConstParameterValues,
-    ) -> Vec<AggNodeFactorRow<I>> {
+    ) -> Result<Vec<AggNodeFactorRow<I>>, PywrError> {
         let mut row_ids = Vec::new();
 
         for agg_node in network.aggregated_nodes().deref() {
             // Only create row for nodes that have factors
-            if let Some(node_pairs) = agg_node.get_const_norm_factor_pairs(values) {
+            if let Some(node_pairs) = agg_node.get_const_norm_factor_pairs(values)? {
                 let mut row_indices_for_agg_node = Vec::with_capacity(node_pairs.len());
 
                 for node_pair in node_pairs {
@@ -850,18 +1059,17 @@ where
 
                     let mut row = RowBuilder::default();
 
-                    // TODO error handling?
                     let nodes = network.nodes();
                     for node0_idx in node_pair.node0.indices {
-                        let node0 = nodes.get(node0_idx).expect("Node index not found!");
-                        self.add_node(node0, 1.0, &mut row);
+                        let node0 = nodes.get(node0_idx)?;
+                        self.add_node(node0, 1.0, &mut row)?;
                     }
 
                     let ratio = node_pair.ratio();
 
                     for node1_idx in node_pair.node1.indices {
-                        let node1 = nodes.get(node1_idx).expect("Node index not found!");
-                        self.add_node(node1, -ratio.unwrap_or(1.0), &mut row);
+                        let node1 = nodes.get(node1_idx)?;
+                        self.add_node(node1, -ratio.unwrap_or(1.0), &mut row)?;
                     }
                     // Make the row fixed at zero RHS
                     row.set_lower(0.0);
@@ -869,11 +1077,14 @@ where
 
                     // Row is fixed if we can compute the ratio now
                     if ratio.is_some() {
-                        self.builder.add_fixed_row(row);
+                        self.builder
+                            .add_fixed_row(row, RowSource::AggregatedNodeFactors(agg_node.index()));
                         row_indices_for_agg_node.push(None)
                     } else {
                         // These rows will be updated with the correct ratio later
-                        let row_idx = self.builder.add_variable_row(row);
+                        let row_idx = self
+                            .builder
+                            .add_variable_row(row, RowSource::AggregatedNodeFactors(agg_node.index()));
                         row_indices_for_agg_node.push(Some(row_idx));
                     }
                 }
@@ -885,15 +1096,14 @@ where
             }
         }
 
-        row_ids
+        Ok(row_ids)
     }
 
     /// Create aggregated node constraints
     ///
     /// One constraint is created per node to enforce any constraints (flow or storage)
     /// that it may define. Returns the row ids associated with each aggregated node constraint.
-    /// Panics if the model contains aggregated nodes with broken references to nodes.
-    fn create_aggregated_node_constraints(&mut self, network: &Network) -> Vec<usize> {
+    fn create_aggregated_node_constraints(&mut self, network: &Network) -> Result<Vec<usize>, PywrError> {
         let mut row_ids = Vec::with_capacity(network.aggregated_nodes().len());
 
         for agg_node in network.aggregated_nodes().deref() {
@@ -901,22 +1111,23 @@ where
             let mut row: RowBuilder<I> = RowBuilder::default();
 
             for node_indices in agg_node.iter_nodes() {
-                // TODO error handling?
                 for node_idx in node_indices {
-                    let node = network.nodes().get(node_idx).expect("Node index not found!");
-                    self.add_node(node, 1.0, &mut row);
+                    let node = network.nodes().get(node_idx)?;
+                    self.add_node(node, 1.0, &mut row)?;
                 }
             }
 
-            let row_id = self.builder.add_variable_row(row);
+            let row_id = self
+                .builder
+                .add_variable_row(row, RowSource::AggregatedNodeFlow(agg_node.index()));
             row_ids.push(row_id.to_usize().unwrap())
         }
-        row_ids
+        Ok(row_ids)
     }
 
     /// Create virtual storage node constraints
     ///
-    fn create_virtual_storage_constraints(&mut self, network: &Network) -> Vec<usize> {
+    fn create_virtual_storage_constraints(&mut self, network: &Network) -> Result<Vec<usize>, PywrError> {
         let mut row_ids = Vec::with_capacity(network.virtual_storage_nodes().len());
 
         for virtual_storage in network.virtual_storage_nodes().deref() {
@@ -925,22 +1136,57 @@ where
             let mut row: RowBuilder<I> = RowBuilder::default();
             for (node_index, factor) in virtual_storage.iter_nodes_with_factors() {
                 if !factor.is_finite() {
-                    panic!(
-                        "Virtual storage node {:?} contains a non-finite factor.",
-                        virtual_storage.full_name()
-                    );
+                    return Err(PywrError::VirtualStorageNonFiniteFactor(
+                        virtual_storage.name().to_string(),
+                    ));
                 }
-                let node = network.nodes().get(node_index).expect("Node index not found!");
-                self.add_node(node, -factor, &mut row);
+                let node = network.nodes().get(node_index)?;
+                self.add_node(node, -factor, &mut row)?;
             }
-            let row_id = self.builder.add_variable_row(row);
+            let row_id = self
+                .builder
+                .add_variable_row(row, RowSource::VirtualStorage(virtual_storage.index()));
             row_ids.push(row_id.to_usize().unwrap());
         }
-        row_ids
+        Ok(row_ids)
+    }
+
+    /// Create switching constraints
+    ///
+    /// For each node with switching constraints a fixed row ensures its flow is at least its
+    /// minimum on flow when its binary status column is one. A second row bounds the status column
+    /// so that it can be fixed when the node is not free to switch. Returns the row ids of the
+    /// latter.
+    fn create_switching_constraints(&mut self, network: &Network) -> Result<Vec<(NodeIndex, usize)>, PywrError> {
+        let mut row_ids = Vec::with_capacity(self.switching_cols.len());
+
+        for (node_idx, col) in self.switching_cols.clone() {
+            let node = network.get_node(&node_idx)?;
+            let min_on_flow = node
+                .get_switching_constraints()
+                .map(|s| s.min_on_flow)
+                .unwrap_or_default();
+
+            if min_on_flow > 0.0 {
+                let mut row: RowBuilder<I> = RowBuilder::default();
+                self.add_node(node, 1.0, &mut row)?;
+                row.add_element(col, -min_on_flow)?;
+                row.set_lower(0.0);
+                row.set_upper(FMAX);
+                self.builder.add_fixed_row(row, RowSource::Switching(node_idx));
+            }
+
+            let mut row: RowBuilder<I> = RowBuilder::default();
+            row.add_element(col, 1.0)?;
+            let row_id = self.builder.add_variable_row(row, RowSource::Switching(node_idx));
+            row_ids.push((node_idx, row_id.to_usize().unwrap()));
+        }
+
+        Ok(row_ids)
     }
 
     /// Create mutual exclusivity constraints
-    fn create_mutual_exclusivity_constraints(&mut self, network: &Network) {
+    fn create_mutual_exclusivity_constraints(&mut self, network: &Network) -> Result<(), PywrError> {
         for agg_node in network.aggregated_nodes().iter() {
             if let Some(exclusivity) = agg_node.get_exclusivity() {
                 let mut row = RowBuilder::default();
@@ -950,20 +1196,40 @@ where
                         .get(node_index)
                         .expect("Binary column not found for Node in mutual exclusivity constraint!");
 
-                    row.add_element(*bin_col, 1.0);
+                    row.add_element(*bin_col, 1.0)?;
                 }
                 row.set_upper(exclusivity.max_active() as f64);
                 row.set_lower(exclusivity.min_active() as f64);
 
-                self.builder.add_fixed_row(row);
+                self.builder
+                    .add_fixed_row(row, RowSource::MutualExclusivity(agg_node.index()));
             }
         }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metric::MetricF64;
+    use crate::models::Model;
+    use crate::recorders::AssertionFnRecorder;
+    use crate::scenario::ScenarioIndex;
+    #[cfg(feature = "cbc")]
+    use crate::solvers::{CbcSolver, CbcSolverSettingsBuilder};
+    use crate::solvers::{ClpSolver, ClpSolverSettingsBuilder};
+    #[cfg(feature = "gurobi")]
+    use crate::solvers::{GurobiSolver, GurobiSolverSettingsBuilder};
+    #[cfg(feature = "highs")]
+    use crate::solvers::{HighsSolver, HighsSolverSettingsBuilder};
+    use crate::test_utils::default_model;
+
+    /// A row source for rows that are not created from a network.
+    fn row_source() -> RowSource {
+        let mut network = Network::default();
+        RowSource::NodeFlow(network.add_input_node("input", None).unwrap())
+    }
 
     #[test]
     fn model_builder_new() {
@@ -974,11 +1240,11 @@ mod tests {
     fn builder_add_rows() {
         let mut builder: LpBuilder<i32> = LpBuilder::default();
         let mut row = RowBuilder::default();
-        row.add_element(0, 1.0);
-        row.add_element(1, 1.0);
+        row.add_element(0, 1.0).unwrap();
+        row.add_element(1, 1.0).unwrap();
         row.set_lower(0.0);
         row.set_upper(2.0);
-        builder.add_variable_row(row);
+        builder.add_variable_row(row, row_source());
     }
 
     #[test]
@@ -991,21 +1257,21 @@ mod tests {
 
         // Row1
         let mut row = RowBuilder::default();
-        row.add_element(0, 3.0);
-        row.add_element(1, 2.0);
-        row.add_element(2, 1.0);
+        row.add_element(0, 3.0).unwrap();
+        row.add_element(1, 2.0).unwrap();
+        row.add_element(2, 1.0).unwrap();
         row.set_lower(f64::MIN);
         row.set_upper(10.0);
-        builder.add_variable_row(row);
+        builder.add_variable_row(row, row_source());
 
         // Row2
         let mut row = RowBuilder::default();
-        row.add_element(0, 2.0);
-        row.add_element(1, 5.0);
-        row.add_element(2, 3.0);
+        row.add_element(0, 2.0).unwrap();
+        row.add_element(1, 5.0).unwrap();
+        row.add_element(2, 3.0).unwrap();
         row.set_lower(f64::MIN);
         row.set_upper(15.0);
-        builder.add_variable_row(row);
+        builder.add_variable_row(row, row_source());
 
         let lp = builder.build();
 
@@ -1017,5 +1283,77 @@ mod tests {
         assert_eq!(lp.row_starts, vec![0, 3, 6]);
         assert_eq!(lp.columns, vec![0, 1, 2, 0, 1, 2]);
         assert_eq!(lp.elements, vec![3.0, 2.0, 1.0, 2.0, 5.0, 3.0]);
+        assert_eq!(lp.row_sources, vec![vec![row_source()], vec![row_source()]]);
+    }
+
+    /// A model with two routes of equal cost between an input and an output.
+    ///
+    /// The first route out of the input (via `link1` and `link3`) is longer than the second
+    /// (via `link2`), and its downstream edges are added to the network last.
+    fn tie_breaking_model() -> Model {
+        let mut model = default_model();
+        let network = model.network_mut();
+
+        let input = network.add_input_node("input", None).unwrap();
+        let link1 = network.add_link_node("link1", None).unwrap();
+        let link2 = network.add_link_node("link2", None).unwrap();
+        let link3 = network.add_link_node("link3", None).unwrap();
+        let output = network.add_output_node("output", None).unwrap();
+
+        network.connect_nodes(input, link1).unwrap();
+        network.connect_nodes(input, link2).unwrap();
+        network.connect_nodes(link2, output).unwrap();
+        network.connect_nodes(link1, link3).unwrap();
+        network.connect_nodes(link3, output).unwrap();
+
+        let node = network.get_mut_node_by_name("input", None).unwrap();
+        node.set_max_flow_constraint(Some(5.0.into())).unwrap();
+        let node = network.get_mut_node_by_name("output", None).unwrap();
+        node.set_cost(Some((-10.0).into()));
+
+        let expected = |_ts: &Timestep, _si: &ScenarioIndex| 5.0;
+        let recorder = AssertionFnRecorder::new("link1-flow", MetricF64::NodeInFlow(link1), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+        let expected = |_ts: &Timestep, _si: &ScenarioIndex| 0.0;
+        let recorder = AssertionFnRecorder::new("link2-flow", MetricF64::NodeInFlow(link2), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        model
+    }
+
+    #[test]
+    fn tie_breaking_perturbations_are_cost_neutral() {
+        let model = tie_breaking_model();
+        let perturbations = edge_cost_perturbations(model.network(), 1e-6).unwrap();
+
+        // Only the two edges out of the input are perturbed, by equal and opposite values.
+        assert_eq!(perturbations, vec![-0.5e-6, 0.5e-6, 0.0, 0.0, 0.0]);
+    }
+
+    /// OSQP is not tested because its tolerance is coarser than the perturbations.
+    #[test]
+    fn tie_breaking_prefers_first_route() {
+        let model = tie_breaking_model();
+
+        let settings = ClpSolverSettingsBuilder::default().tie_breaking(1e-6).build();
+        model.run::<ClpSolver>(&settings).unwrap();
+
+        #[cfg(feature = "cbc")]
+        {
+            let settings = CbcSolverSettingsBuilder::default().tie_breaking(1e-6).build();
+            model.run::<CbcSolver>(&settings).unwrap();
+        }
+
+        #[cfg(feature = "highs")]
+        {
+            let settings = HighsSolverSettingsBuilder::default().tie_breaking(1e-6).build();
+            model.run::<HighsSolver>(&settings).unwrap();
+        }
+
+        #[cfg(feature = "gurobi")]
+        {
+            let settings = GurobiSolverSettingsBuilder::default().tie_breaking(1e-6).build();
+            model.run::<GurobiSolver>(&settings).unwrap();
+        }
     }
 }
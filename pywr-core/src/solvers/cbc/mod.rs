@@ -3,7 +3,8 @@ mod settings;
 use super::builder::{ColType, SolverBuilder};
 use crate::network::Network;
 use crate::solvers::builder::BuiltSolver;
-use crate::solvers::{Solver, SolverFeatures, SolverTimings};
+use crate::solvers::infeasibility::diagnose_infeasibility;
+use crate::solvers::{Solver, SolverFeatures, SolverSettings, SolverTimings};
 use crate::state::{ConstParameterValues, State};
 use crate::timestep::Timestep;
 use crate::PywrError;
@@ -153,6 +154,11 @@ impl Cbc {
         }
     }
 
+    /// Whether the last solve proved that the problem is infeasible.
+    fn is_proven_infeasible(&self) -> bool {
+        unsafe { Cbc_isProvenInfeasible(self.ptr) != 0 }
+    }
+
     fn primal_column_solution(&mut self, number: usize) -> Vec<c_double> {
         let solution: Vec<c_double>;
         unsafe {
@@ -162,6 +168,17 @@ impl Cbc {
         solution
     }
 
+    /// The dual value of each row. For a model with integer columns these are the dual values of
+    /// the final linear relaxation.
+    fn row_price(&mut self, number: usize) -> Vec<c_double> {
+        let solution: Vec<c_double>;
+        unsafe {
+            let data_ptr = Cbc_getRowPrice(self.ptr);
+            solution = slice::from_raw_parts(data_ptr, number).to_vec()
+        }
+        solution
+    }
+
     #[allow(dead_code)]
     fn get_objective_coefficients(&mut self, number: usize) -> Vec<c_double> {
         let coef: Vec<c_double>;
@@ -191,10 +208,14 @@ impl Cbc {
 pub struct CbcSolver {
     builder: BuiltSolver<c_int>,
     cbc: Cbc,
+    /// Whether the dual values are saved to the state after each solve.
+    duals: bool,
+    /// Whether an infeasible time-step is diagnosed (see [`crate::solvers::InfeasibilityReport`]).
+    diagnose_infeasibility: bool,
 }
 
 impl CbcSolver {
-    fn from_builder(builder: BuiltSolver<c_int>) -> Self {
+    fn from_builder(builder: BuiltSolver<c_int>, duals: bool, diagnose_infeasibility: bool) -> Self {
         let mut cbc = Cbc::default();
 
         cbc.add_cols(
@@ -212,7 +233,12 @@ impl CbcSolver {
             builder.elements(),
         );
 
-        CbcSolver { builder, cbc }
+        CbcSolver {
+            builder,
+            cbc,
+            duals,
+            diagnose_infeasibility,
+        }
     }
 
     fn solve(&mut self) -> Vec<c_double> {
@@ -237,18 +263,19 @@ impl Solver for CbcSolver {
             SolverFeatures::VirtualStorage,
             SolverFeatures::AggregatedNodeFactors,
             SolverFeatures::MutualExclusivity,
+            SolverFeatures::SwitchingConstraints,
         ]
     }
 
     fn setup(
         model: &Network,
         values: &ConstParameterValues,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
     ) -> Result<Box<Self>, PywrError> {
-        let builder = SolverBuilder::default();
+        let builder = SolverBuilder::default().with_tie_breaking(settings.tie_breaking());
         let built = builder.create(model, values)?;
 
-        let solver = CbcSolver::from_builder(built);
+        let solver = CbcSolver::from_builder(built, settings.duals(), settings.diagnose_infeasibility());
         Ok(Box::new(solver))
     }
 
@@ -276,6 +303,10 @@ impl Solver for CbcSolver {
         let solution = self.solve();
         timings.solve = now.elapsed();
 
+        if self.diagnose_infeasibility && self.cbc.is_proven_infeasible() {
+            return Err(diagnose_infeasibility(model, timestep, &self.builder));
+        }
+
         // Create the updated network state from the results
         let network_state = state.get_mut_network_state();
         network_state.reset();
@@ -288,6 +319,12 @@ impl Solver for CbcSolver {
             let flow = if flow.abs() < 1e-10 { 0.0 } else { flow };
             network_state.add_flow(edge, timestep, flow)?;
         }
+
+        if self.duals {
+            let row_duals = self.cbc.row_price(self.builder.num_rows() as usize);
+            network_state.set_duals(self.builder.dual_values(model, &row_duals));
+        }
+
         state.complete(model, timestep)?;
         timings.save_solution += start_save_solution.elapsed();
 
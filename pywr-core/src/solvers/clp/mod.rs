@@ -3,7 +3,9 @@ mod settings;
 use super::builder::SolverBuilder;
 use crate::network::Network;
 use crate::solvers::builder::BuiltSolver;
-use crate::solvers::{Solver, SolverFeatures, SolverTimings};
+use crate::solvers::horizon::HorizonLp;
+use crate::solvers::infeasibility::diagnose_infeasibility;
+use crate::solvers::{Solver, SolverFeatures, SolverSettings, SolverTimings, SolverWarmState, WarmStartError};
 use crate::state::{ConstParameterValues, State};
 use crate::timestep::Timestep;
 use crate::PywrError;
@@ -12,7 +14,7 @@ use libc::{c_double, c_int};
 pub use settings::{ClpSolverSettings, ClpSolverSettingsBuilder};
 use std::ffi::CString;
 use std::slice;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -25,6 +27,13 @@ pub enum ClpError {
 
 pub type CoinBigIndex = c_int;
 
+/// The status of a solve that was stopped on reaching its iteration or time limit.
+const CLP_STATUS_STOPPED: c_int = 3;
+/// The status of a basic variable in Clp's status array.
+const CLP_STATUS_BASIC: u8 = 1;
+/// The status of a non-basic variable at its lower bound in Clp's status array.
+const CLP_STATUS_AT_LOWER_BOUND: u8 = 3;
+
 struct ClpSimplex {
     ptr: *mut Clp_Simplex,
 }
@@ -119,7 +128,6 @@ impl ClpSimplex {
         }
     }
 
-    #[allow(dead_code)]
     fn initial_solve(&mut self) {
         unsafe {
             Clp_initialSolve(self.ptr);
@@ -152,6 +160,16 @@ impl ClpSimplex {
         }
     }
 
+    #[allow(dead_code)]
+    fn dual_row_solution(&mut self, number: usize) -> Vec<c_double> {
+        let solution: Vec<c_double>;
+        unsafe {
+            let data_ptr = Clp_dualRowSolution(self.ptr);
+            solution = slice::from_raw_parts(data_ptr, number).to_vec()
+        }
+        solution
+    }
+
     fn primal_column_solution(&mut self, number: usize) -> Vec<c_double> {
         let solution: Vec<c_double>;
         unsafe {
@@ -161,6 +179,22 @@ impl ClpSimplex {
         solution
     }
 
+    /// Copy the dual value of each row in to `solution`.
+    fn copy_dual_row_solution(&self, solution: &mut [c_double]) {
+        unsafe {
+            let data_ptr = Clp_dualRowSolution(self.ptr);
+            solution.copy_from_slice(slice::from_raw_parts(data_ptr, solution.len()));
+        }
+    }
+
+    /// Copy the primal value of each column in to `solution`.
+    fn copy_primal_column_solution(&self, solution: &mut [c_double]) {
+        unsafe {
+            let data_ptr = Clp_primalColumnSolution(self.ptr);
+            solution.copy_from_slice(slice::from_raw_parts(data_ptr, solution.len()));
+        }
+    }
+
     #[allow(dead_code)]
     fn get_objective_coefficients(&mut self, number: usize) -> Vec<c_double> {
         let coef: Vec<c_double>;
@@ -181,19 +215,125 @@ impl ClpSimplex {
         ub
     }
 
+    /// The status of each column followed by each row.
+    fn status_array(&self, number: usize) -> Vec<u8> {
+        let status: Vec<u8>;
+        unsafe {
+            let data_ptr = Clp_statusArray(self.ptr);
+            status = slice::from_raw_parts(data_ptr, number).to_vec()
+        }
+        status
+    }
+
+    /// Copy the status of each column followed by each row in to `status`.
+    fn copy_status_array(&self, status: &mut [u8]) {
+        unsafe {
+            let data_ptr = Clp_statusArray(self.ptr);
+            status.copy_from_slice(slice::from_raw_parts(data_ptr, status.len()));
+        }
+    }
+
+    /// Copy in the status of each column followed by each row.
+    fn copy_in_status(&mut self, status: &[u8]) {
+        unsafe {
+            Clp_copyinStatus(self.ptr, status.as_ptr());
+        }
+    }
+
+    /// The status of the last solve; zero if it was optimal.
+    fn status(&self) -> c_int {
+        unsafe { Clp_status(self.ptr) }
+    }
+
+    /// Limit the time of each subsequent solve to `seconds`.
+    fn set_maximum_seconds(&mut self, seconds: c_double) {
+        unsafe { Clp_setMaximumSeconds(self.ptr, seconds) }
+    }
+
+    /// Whether the last solve proved that the LP is infeasible.
+    fn is_proven_primal_infeasible(&self) -> bool {
+        unsafe { Clp_isProvenPrimalInfeasible(self.ptr) != 0 }
+    }
+
     #[allow(dead_code)]
     fn objective_value(&self) -> c_double {
         unsafe { Clp_objectiveValue(self.ptr) }
     }
 }
 
+/// Solve a linear program of a network over all the time-steps of a run with Clp.
+///
+/// Returns the value of each column of the optimal solution.
+pub(crate) fn solve_horizon_lp(lp: &HorizonLp<c_int>) -> Result<Vec<f64>, PywrError> {
+    solve_sparse_lp(
+        &lp.col_lower,
+        &lp.col_upper,
+        &lp.col_obj_coef,
+        &lp.row_lower,
+        &lp.row_upper,
+        &lp.row_starts,
+        &lp.columns,
+        &lp.elements,
+    )
+    .map_err(PywrError::WholeHorizonSolveFailed)
+}
+
+/// Solve a linear program given in sparse form with Clp.
+///
+/// Returns the value of each column of the optimal solution, or the status of Clp if the
+/// solve was not optimal.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn solve_sparse_lp(
+    col_lower: &[f64],
+    col_upper: &[f64],
+    col_obj_coef: &[f64],
+    row_lower: &[f64],
+    row_upper: &[f64],
+    row_starts: &[c_int],
+    columns: &[c_int],
+    elements: &[f64],
+) -> Result<Vec<f64>, c_int> {
+    let mut clp_simplex = ClpSimplex::default();
+
+    clp_simplex.resize(0, col_lower.len() as c_int);
+    clp_simplex.change_column_lower(col_lower);
+    clp_simplex.change_column_upper(col_upper);
+    clp_simplex.change_objective_coefficients(col_obj_coef);
+    clp_simplex.add_rows(row_lower, row_upper, row_starts, columns, elements);
+
+    clp_simplex.initial_solve();
+
+    match clp_simplex.status() {
+        0 => Ok(clp_simplex.primal_column_solution(col_lower.len())),
+        status => Err(status),
+    }
+}
+
 pub struct ClpSolver {
     builder: BuiltSolver<c_int>,
     clp_simplex: ClpSimplex,
+    /// Whether the basis of the previous solve is used to start the next.
+    warm_start: bool,
+    /// The optimal basis of the previous solve, if it was optimal.
+    basis: Option<Vec<u8>>,
+    /// Whether the next solve starts from an imported basis, even if `warm_start` is false.
+    imported_basis: bool,
+    /// Whether the dual values are saved to the state after each solve.
+    duals: bool,
+    /// Whether an infeasible time-step is diagnosed (see [`crate::solvers::InfeasibilityReport`]).
+    diagnose_infeasibility: bool,
+    /// Whether the time of each solve is limited (see [`Solver::set_time_limit`]).
+    time_limited: bool,
+    /// A basis with all the rows basic and all the columns at their lower bound.
+    slack_basis: Vec<u8>,
+    /// The value of each column of the last solve; reused between time-steps.
+    solution: Vec<c_double>,
+    /// The dual value of each row of the last solve; reused between time-steps.
+    row_duals: Vec<c_double>,
 }
 
 impl ClpSolver {
-    fn from_builder(builder: BuiltSolver<c_int>) -> Self {
+    fn from_builder(builder: BuiltSolver<c_int>, warm_start: bool, duals: bool, diagnose_infeasibility: bool) -> Self {
         let mut clp_simplex = ClpSimplex::default();
 
         let num_cols = builder.num_cols();
@@ -214,15 +354,77 @@ impl ClpSolver {
 
         clp_simplex.initial_dual_solve();
 
-        ClpSolver { builder, clp_simplex }
+        let slack_basis = Self::slack_basis(&builder);
+        let solution = vec![0.0; num_cols as usize];
+        let row_duals = if duals {
+            vec![0.0; builder.num_rows() as usize]
+        } else {
+            Vec::new()
+        };
+
+        let mut solver = ClpSolver {
+            builder,
+            clp_simplex,
+            warm_start,
+            basis: None,
+            imported_basis: false,
+            duals,
+            diagnose_infeasibility,
+            time_limited: false,
+            slack_basis,
+            solution,
+            row_duals,
+        };
+        solver.save_basis();
+        solver
     }
 
-    fn solve(&mut self) -> Vec<c_double> {
-        self.clp_simplex.dual_solve();
+    /// The number of entries in the status array; one for each column and row.
+    fn status_len(&self) -> usize {
+        (self.builder.num_cols() + self.builder.num_rows()) as usize
+    }
 
-        let num_cols = self.builder.num_cols() as usize;
+    /// Store the current basis if the last solve was optimal.
+    ///
+    /// A basis from an infeasible or otherwise failed solve is discarded so that it is not used
+    /// as the starting point for the next solve.
+    ///
+    /// The buffer of the previous basis is reused if there is one.
+    fn save_basis(&mut self) {
+        if self.clp_simplex.status() != 0 {
+            self.basis = None;
+            return;
+        }
 
-        self.clp_simplex.primal_column_solution(num_cols)
+        let status_len = self.status_len();
+        let basis = self.basis.get_or_insert_with(|| vec![0; status_len]);
+        self.clp_simplex.copy_status_array(basis);
+    }
+
+    /// A basis with all the rows basic and all the columns at their lower bound.
+    fn slack_basis(builder: &BuiltSolver<c_int>) -> Vec<u8> {
+        let num_cols = builder.num_cols() as usize;
+        let num_rows = builder.num_rows() as usize;
+
+        let mut basis = vec![CLP_STATUS_AT_LOWER_BOUND; num_cols];
+        basis.extend(std::iter::repeat(CLP_STATUS_BASIC).take(num_rows));
+        basis
+    }
+
+    /// Solve the LP and copy the value of each column in to `self.solution`.
+    fn solve(&mut self) {
+        // Start from the previous optimal basis, if available, or otherwise from a slack basis.
+        // An imported basis is always used for the first solve after it is imported.
+        match (self.warm_start || self.imported_basis, &self.basis) {
+            (true, Some(basis)) => self.clp_simplex.copy_in_status(basis),
+            _ => self.clp_simplex.copy_in_status(&self.slack_basis),
+        }
+        self.imported_basis = false;
+
+        self.clp_simplex.dual_solve();
+        self.save_basis();
+
+        self.clp_simplex.copy_primal_column_solution(&mut self.solution);
     }
 }
 
@@ -239,18 +441,24 @@ impl Solver for ClpSolver {
             SolverFeatures::AggregatedNodeFactors,
             SolverFeatures::AggregatedNodeDynamicFactors,
             SolverFeatures::VirtualStorage,
+            SolverFeatures::SwitchingConstraints,
         ]
     }
 
     fn setup(
         model: &Network,
         values: &ConstParameterValues,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
     ) -> Result<Box<Self>, PywrError> {
-        let builder = SolverBuilder::default();
+        let builder = SolverBuilder::default().with_tie_breaking(settings.tie_breaking());
         let built = builder.create(model, values)?;
 
-        let solver = ClpSolver::from_builder(built);
+        let solver = ClpSolver::from_builder(
+            built,
+            settings.warm_start(),
+            settings.duals(),
+            settings.diagnose_infeasibility(),
+        );
         Ok(Box::new(solver))
     }
 
@@ -274,9 +482,17 @@ impl Solver for ClpSolver {
         timings.update_constraints += now.elapsed();
 
         let now = Instant::now();
-        let solution = self.solve();
+        self.solve();
         timings.solve = now.elapsed();
 
+        if self.time_limited && self.clp_simplex.status() == CLP_STATUS_STOPPED {
+            return Err(PywrError::SolveTimeLimitReached);
+        }
+
+        if self.diagnose_infeasibility && self.clp_simplex.is_proven_primal_infeasible() {
+            return Err(diagnose_infeasibility(model, timestep, &self.builder));
+        }
+
         // Create the updated network state from the results
         let network_state = state.get_mut_network_state();
         network_state.reset();
@@ -284,19 +500,62 @@ impl Solver for ClpSolver {
         let start_save_solution = Instant::now();
         for edge in model.edges().iter() {
             let col = self.builder.col_for_edge(&edge.index()) as usize;
-            let flow = solution[col];
+            let flow = self.solution[col];
             network_state.add_flow(edge, timestep, flow)?;
         }
+
+        if self.duals {
+            self.clp_simplex.copy_dual_row_solution(&mut self.row_duals);
+            network_state.set_duals(self.builder.dual_values(model, &self.row_duals));
+        }
+
         state.complete(model, timestep)?;
         timings.save_solution += start_save_solution.elapsed();
 
         Ok(timings)
     }
+
+    fn export_warm_state(&self) -> Option<SolverWarmState> {
+        Some(SolverWarmState::Basis(self.clp_simplex.status_array(self.status_len())))
+    }
+
+    fn set_time_limit(&mut self, time_limit: Duration) {
+        self.clp_simplex.set_maximum_seconds(time_limit.as_secs_f64());
+        self.time_limited = true;
+    }
+
+    fn import_warm_state(&mut self, state: &SolverWarmState) -> Result<(), PywrError> {
+        match state {
+            SolverWarmState::Basis(status) => {
+                let expected = self.status_len();
+                if status.len() != expected {
+                    return Err(WarmStartError::BasisSizeMismatch {
+                        expected,
+                        found: status.len(),
+                    }
+                    .into());
+                }
+                self.clp_simplex.copy_in_status(status);
+                match &mut self.basis {
+                    Some(basis) => basis.copy_from_slice(status),
+                    None => self.basis = Some(status.clone()),
+                }
+                self.imported_basis = true;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metric::MetricF64;
+    use crate::network::RunTimings;
+    use crate::recorders::AssertionFnRecorder;
+    use crate::scenario::ScenarioIndex;
+    use crate::test_utils::{default_model, simple_model, simple_storage_model};
     use float_cmp::approx_eq;
 
     #[test]
@@ -367,4 +626,52 @@ mod tests {
         assert!(approx_eq!(f64, lp.objective_value(), -40.0));
         assert_eq!(lp.primal_column_solution(3), vec![0.0, 0.0, 10.0]);
     }
+
+    #[test]
+    fn warm_and_cold_start_solutions_match() {
+        let mut model = simple_storage_model();
+        let network = model.network_mut();
+
+        let idx = network.get_node_index_by_name("reservoir", None).unwrap();
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| (90.0 - 10.0 * ts.index as f64).max(0.0);
+        let recorder = AssertionFnRecorder::new("reservoir-volume", MetricF64::NodeVolume(idx), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        for warm_start in [true, false] {
+            let settings = ClpSolverSettingsBuilder::default().warm_start(warm_start).build();
+            model.run::<ClpSolver>(&settings).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_imported_basis_without_warm_start() {
+        let model = simple_model(1, None);
+        let network = model.network();
+        let timesteps = model.domain().time().timesteps();
+        let scenario_indices = model.domain().scenarios().indices();
+        let settings = ClpSolverSettingsBuilder::default().warm_start(false).build();
+
+        let mut state = network.setup_network(timesteps, scenario_indices, 0).unwrap();
+        let mut solvers = network
+            .setup_solver::<ClpSolver>(scenario_indices, &state, &settings)
+            .unwrap();
+        let mut timings = RunTimings::default();
+        network
+            .step(&timesteps[0], scenario_indices, &mut solvers, &mut state, &mut timings)
+            .unwrap();
+        let warm_state = solvers[0].export_warm_state().unwrap();
+
+        // The imported basis is kept, and used for the next solve only
+        let mut solvers = network
+            .setup_solver::<ClpSolver>(scenario_indices, &state, &settings)
+            .unwrap();
+        solvers[0].import_warm_state(&warm_state).unwrap();
+        assert!(solvers[0].imported_basis);
+        assert!(solvers[0].basis.is_some());
+
+        network
+            .step(&timesteps[1], scenario_indices, &mut solvers, &mut state, &mut timings)
+            .unwrap();
+        assert!(!solvers[0].imported_basis);
+    }
 }
@@ -1,12 +1,17 @@
-use crate::solvers::SolverSettings;
+use crate::solvers::{LpExport, SolverSettings};
 
 /// Settings for the OpenCL IPM solvers.
 ///
 /// Create new settings using [`ClpSolverSettingsBuilder`] or use the default implementation;
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct ClpSolverSettings {
     parallel: bool,
     threads: usize,
+    warm_start: bool,
+    duals: bool,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    diagnose_infeasibility: bool,
 }
 
 // Default implementation is a convenience that defers to the builder.
@@ -24,6 +29,26 @@ impl SolverSettings for ClpSolverSettings {
     fn threads(&self) -> usize {
         self.threads
     }
+
+    fn warm_start(&self) -> bool {
+        self.warm_start
+    }
+
+    fn duals(&self) -> bool {
+        self.duals
+    }
+
+    fn tie_breaking(&self) -> Option<f64> {
+        self.tie_breaking
+    }
+
+    fn lp_export(&self) -> Option<&LpExport> {
+        self.lp_export.as_ref()
+    }
+
+    fn diagnose_infeasibility(&self) -> bool {
+        self.diagnose_infeasibility
+    }
 }
 
 impl ClpSolverSettings {
@@ -48,11 +73,31 @@ impl ClpSolverSettings {
 /// builder = builder.parallel();
 /// let settings = builder.build();
 ///
+/// // Settings that solve every time-step from a cold (slack) basis.
+/// let settings = ClpSolverSettingsBuilder::default().warm_start(false).build();
 /// ```
-#[derive(Default)]
 pub struct ClpSolverSettingsBuilder {
     parallel: bool,
     threads: usize,
+    warm_start: bool,
+    duals: bool,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    diagnose_infeasibility: bool,
+}
+
+impl Default for ClpSolverSettingsBuilder {
+    fn default() -> Self {
+        Self {
+            parallel: false,
+            threads: 0,
+            warm_start: true,
+            duals: false,
+            tie_breaking: None,
+            lp_export: None,
+            diagnose_infeasibility: false,
+        }
+    }
 }
 
 impl ClpSolverSettingsBuilder {
@@ -66,11 +111,53 @@ impl ClpSolverSettingsBuilder {
         self
     }
 
+    /// Reuse the optimal basis of the previous time-step as the starting point of the next
+    /// (enabled by default). If disabled each time-step is solved from a slack basis.
+    pub fn warm_start(mut self, warm_start: bool) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
+    /// Save the dual values of the constraints to the state after each solve.
+    pub fn duals(mut self) -> Self {
+        self.duals = true;
+        self
+    }
+
+    /// Break ties between routes of equal cost deterministically.
+    ///
+    /// The costs of the edges out of each node with more than one outgoing edge are perturbed by
+    /// values between `-epsilon / 2` and `epsilon / 2`, so that edges added earlier are preferred
+    /// and the perturbations at each node sum to zero. `epsilon` should be much smaller than any
+    /// meaningful difference between the costs of the routes.
+    pub fn tie_breaking(mut self, epsilon: f64) -> Self {
+        self.tie_breaking = Some(epsilon);
+        self
+    }
+
+    /// Write the LP of a time-step of a scenario to a file (see [`LpExport`]).
+    pub fn export_lp(mut self, export: LpExport) -> Self {
+        self.lp_export = Some(export);
+        self
+    }
+
+    /// Diagnose an infeasible time-step before the run fails (see
+    /// [`InfeasibilityReport`](crate::solvers::InfeasibilityReport)).
+    pub fn diagnose_infeasibility(mut self) -> Self {
+        self.diagnose_infeasibility = true;
+        self
+    }
+
     /// Construct a [`ClpSolverSettings`] from the builder.
     pub fn build(self) -> ClpSolverSettings {
         ClpSolverSettings {
             parallel: self.parallel,
             threads: self.threads,
+            warm_start: self.warm_start,
+            duals: self.duals,
+            tie_breaking: self.tie_breaking,
+            lp_export: self.lp_export,
+            diagnose_infeasibility: self.diagnose_infeasibility,
         }
     }
 }
@@ -84,6 +171,11 @@ mod tests {
         let _settings = ClpSolverSettings {
             parallel: true,
             threads: 0,
+            warm_start: true,
+            duals: false,
+            tie_breaking: None,
+            lp_export: None,
+            diagnose_infeasibility: false,
         };
         let settings_from_builder = ClpSolverSettingsBuilder::default().parallel().build();
 
@@ -0,0 +1,572 @@
+mod settings;
+
+use crate::network::Network;
+use crate::solvers::builder::{BuiltSolver, ColType, SolverBuilder};
+use crate::solvers::infeasibility::diagnose_infeasibility;
+use crate::solvers::{Solver, SolverFeatures, SolverSettings, SolverTimings};
+use crate::state::{ConstParameterValues, State};
+use crate::timestep::Timestep;
+use crate::PywrError;
+use grb_sys::{
+    GRBaddconstrs, GRBchgcoeffs, GRBemptyenv, GRBenv, GRBfreeenv, GRBfreemodel, GRBgetdblattrarray, GRBgetenv,
+    GRBgeterrormsg, GRBgetintattr, GRBmodel, GRBnewmodel, GRBoptimize, GRBsetdblattrarray, GRBsetdblparam,
+    GRBsetintparam, GRBstartenv,
+};
+use libc::{c_char, c_double, c_int};
+pub use settings::{GurobiMethod, GurobiSolverSettings, GurobiSolverSettingsBuilder};
+use std::ffi::{CStr, CString};
+use std::ops::Deref;
+use std::ptr::{null, null_mut};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Instant;
+use thiserror::Error;
+
+/// Gurobi treats bounds at or beyond this value as infinite.
+const GRB_INFINITY: f64 = 1e100;
+const GRB_CONTINUOUS: c_char = b'C' as c_char;
+const GRB_INTEGER: c_char = b'I' as c_char;
+const GRB_LESS_EQUAL: c_char = b'<' as c_char;
+const GRB_GREATER_EQUAL: c_char = b'>' as c_char;
+const GRB_OPTIMAL: c_int = 2;
+const GRB_INFEASIBLE: c_int = 3;
+const GRB_INF_OR_UNBD: c_int = 4;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GurobiError {
+    #[error("`{call}` failed with error code {code} in Gurobi: {message}")]
+    Call {
+        call: &'static str,
+        code: c_int,
+        message: String,
+    },
+    #[error("Gurobi did not find an optimal solution (status {0})")]
+    NotOptimal(c_int),
+}
+
+/// The message of the last error of a Gurobi environment.
+fn error_message(env: *mut GRBenv) -> String {
+    unsafe {
+        let ptr = GRBgeterrormsg(env);
+        if ptr.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(ptr).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// A started Gurobi environment that is shared by the models of all the solvers.
+///
+/// Starting an environment checks out a Gurobi licence, so the environment is created by the
+/// first solver and shared by any others (e.g. of the other scenarios) that exist at the same
+/// time. Each model has its own copy of the environment's parameters, which are set per model.
+struct GurobiEnv {
+    env: *mut GRBenv,
+}
+
+// The environment is only used to create models, which is serialised by `SHARED_ENV`.
+unsafe impl Send for GurobiEnv {}
+unsafe impl Sync for GurobiEnv {}
+
+/// The shared environment, if any solver is using it.
+static SHARED_ENV: Mutex<Weak<GurobiEnv>> = Mutex::new(Weak::new());
+
+impl Drop for GurobiEnv {
+    fn drop(&mut self) {
+        unsafe { GRBfreeenv(self.env) };
+    }
+}
+
+impl GurobiEnv {
+    /// Create and start a new environment with its output disabled.
+    fn new() -> Result<Self, GurobiError> {
+        let mut env: *mut GRBenv = null_mut();
+        let ret = unsafe { GRBemptyenv(&mut env) };
+        if ret != 0 {
+            // The environment is not valid, so there is no error message to get from it.
+            return Err(GurobiError::Call {
+                call: "GRBemptyenv",
+                code: ret,
+                message: String::new(),
+            });
+        }
+
+        // The environment is freed if it fails to start
+        let env = Self { env };
+
+        let name = CString::new("OutputFlag").unwrap();
+        let ret = unsafe { GRBsetintparam(env.env, name.as_ptr(), 0) };
+        if ret != 0 {
+            return Err(GurobiError::Call {
+                call: "GRBsetintparam",
+                code: ret,
+                message: error_message(env.env),
+            });
+        }
+
+        let ret = unsafe { GRBstartenv(env.env) };
+        if ret != 0 {
+            return Err(GurobiError::Call {
+                call: "GRBstartenv",
+                code: ret,
+                message: error_message(env.env),
+            });
+        }
+
+        Ok(env)
+    }
+}
+
+/// A Gurobi model of the LP.
+///
+/// Each row of the LP is added to the model as two constraints; one for its lower bound followed
+/// by one for its upper bound. Gurobi only supports range constraints by adding a column for
+/// each range, and this keeps the bounds of the rows simple to update.
+struct Gurobi {
+    /// The shared environment; this must outlive the model.
+    shared_env: Arc<GurobiEnv>,
+    /// The environment of the model once it is created, or otherwise the shared environment.
+    env: *mut GRBenv,
+    model: *mut GRBmodel,
+    num_rows: usize,
+    settings: GurobiSolverSettings,
+}
+
+unsafe impl Send for Gurobi {}
+
+impl Drop for Gurobi {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.model.is_null() {
+                GRBfreemodel(self.model);
+            }
+        }
+    }
+}
+
+impl Gurobi {
+    /// Create a new model using the shared environment, starting the environment if no other
+    /// model is using it. The settings are applied to the model when its columns are added.
+    fn new(settings: &GurobiSolverSettings) -> Result<Self, GurobiError> {
+        let shared_env = {
+            let mut shared = SHARED_ENV.lock().unwrap_or_else(|e| e.into_inner());
+            match shared.upgrade() {
+                Some(env) => env,
+                None => {
+                    let env = Arc::new(GurobiEnv::new()?);
+                    *shared = Arc::downgrade(&env);
+                    env
+                }
+            }
+        };
+
+        Ok(Self {
+            env: shared_env.env,
+            shared_env,
+            model: null_mut(),
+            num_rows: 0,
+            settings: settings.clone(),
+        })
+    }
+
+    /// Apply the settings to the environment of the model.
+    fn apply_settings(&mut self) -> Result<(), GurobiError> {
+        // The scenarios of a parallel run are already solved on separate threads, so by default
+        // each LP is solved on a single thread rather than on all of the cores of the machine.
+        let solver_threads = match self.settings.solver_threads() {
+            Some(threads) => Some(threads),
+            None if self.settings.parallel() => Some(1),
+            None => None,
+        };
+        if let Some(threads) = solver_threads {
+            self.set_int_param("Threads", threads as c_int)?;
+        }
+        self.set_int_param("Method", self.settings.method().value())?;
+        if let Some(tolerance) = self.settings.feasibility_tolerance() {
+            self.set_dbl_param("FeasibilityTol", tolerance)?;
+        }
+        if let Some(tolerance) = self.settings.optimality_tolerance() {
+            self.set_dbl_param("OptimalityTol", tolerance)?;
+        }
+        if let Some(gap) = self.settings.mip_gap() {
+            self.set_dbl_param("MIPGap", gap)?;
+        }
+
+        Ok(())
+    }
+
+    /// Convert the code returned by a Gurobi call into an error if it is not zero.
+    fn check_error(&self, call: &'static str, code: c_int) -> Result<(), GurobiError> {
+        if code == 0 {
+            return Ok(());
+        }
+
+        Err(GurobiError::Call {
+            call,
+            code,
+            message: error_message(self.env),
+        })
+    }
+
+    /// Set a parameter of the environment of the model.
+    fn set_int_param(&mut self, name: &str, value: c_int) -> Result<(), GurobiError> {
+        let name = CString::new(name).unwrap();
+        let ret = unsafe { GRBsetintparam(self.env, name.as_ptr(), value) };
+        self.check_error("GRBsetintparam", ret)
+    }
+
+    fn set_dbl_param(&mut self, name: &str, value: f64) -> Result<(), GurobiError> {
+        let name = CString::new(name).unwrap();
+        let ret = unsafe { GRBsetdblparam(self.env, name.as_ptr(), value) };
+        self.check_error("GRBsetdblparam", ret)
+    }
+
+    fn set_dbl_attr_array(&mut self, name: &str, first: usize, values: &[f64]) -> Result<(), GurobiError> {
+        let name = CString::new(name).unwrap();
+        let ret = unsafe {
+            GRBsetdblattrarray(
+                self.model,
+                name.as_ptr(),
+                first as c_int,
+                values.len() as c_int,
+                values.as_ptr(),
+            )
+        };
+        self.check_error("GRBsetdblattrarray", ret)
+    }
+
+    fn dbl_attr_array(&self, name: &str, len: usize) -> Result<Vec<f64>, GurobiError> {
+        let name = CString::new(name).unwrap();
+        let mut values = vec![0.0; len];
+        let ret = unsafe { GRBgetdblattrarray(self.model, name.as_ptr(), 0, len as c_int, values.as_mut_ptr()) };
+        self.check_error("GRBgetdblattrarray", ret)?;
+        Ok(values)
+    }
+
+    /// Create the model with the given columns.
+    pub fn add_cols(
+        &mut self,
+        col_lower: &[f64],
+        col_upper: &[f64],
+        col_obj_coef: &[f64],
+        col_type: &[ColType],
+    ) -> Result<(), GurobiError> {
+        let col_lower: Vec<c_double> = col_lower.iter().map(|v| v.max(-GRB_INFINITY)).collect();
+        let col_upper: Vec<c_double> = col_upper.iter().map(|v| v.min(GRB_INFINITY)).collect();
+        let vtype: Vec<c_char> = col_type
+            .iter()
+            .map(|ctype| match ctype {
+                ColType::Continuous => GRB_CONTINUOUS,
+                ColType::Integer => GRB_INTEGER,
+            })
+            .collect();
+
+        let name = CString::new("pywr").unwrap();
+        let ret = {
+            // Models are created from the shared environment one at a time
+            let _shared = SHARED_ENV.lock().unwrap_or_else(|e| e.into_inner());
+            unsafe {
+                GRBnewmodel(
+                    self.shared_env.env,
+                    &mut self.model,
+                    name.as_ptr(),
+                    col_obj_coef.len() as c_int,
+                    col_obj_coef.as_ptr(),
+                    col_lower.as_ptr(),
+                    col_upper.as_ptr(),
+                    vtype.as_ptr(),
+                    null(),
+                )
+            }
+        };
+        self.check_error("GRBnewmodel", ret)?;
+
+        // The model has its own copy of the environment, so its parameters do not affect the
+        // models of the other solvers.
+        self.env = unsafe { GRBgetenv(self.model) };
+        self.apply_settings()
+    }
+
+    /// Add the rows to the model as a constraint for the lower bounds followed by a constraint
+    /// for the upper bounds.
+    pub fn add_rows(
+        &mut self,
+        row_lower: &[f64],
+        row_upper: &[f64],
+        row_starts: &[c_int],
+        columns: &[c_int],
+        elements: &[f64],
+    ) -> Result<(), GurobiError> {
+        let num_rows = row_upper.len();
+        let row_starts = &row_starts[..num_rows];
+
+        for (sense, rhs) in [(GRB_GREATER_EQUAL, row_lower), (GRB_LESS_EQUAL, row_upper)] {
+            let senses = vec![sense; num_rows];
+            let rhs: Vec<c_double> = rhs.iter().map(|v| v.clamp(-GRB_INFINITY, GRB_INFINITY)).collect();
+            let ret = unsafe {
+                GRBaddconstrs(
+                    self.model,
+                    num_rows as c_int,
+                    elements.len() as c_int,
+                    row_starts.as_ptr(),
+                    columns.as_ptr(),
+                    elements.as_ptr(),
+                    senses.as_ptr(),
+                    rhs.as_ptr(),
+                    null(),
+                )
+            };
+            self.check_error("GRBaddconstrs", ret)?;
+        }
+
+        self.num_rows = num_rows;
+        Ok(())
+    }
+
+    pub fn change_objective_coefficients(&mut self, obj_coefficients: &[f64]) -> Result<(), GurobiError> {
+        self.set_dbl_attr_array("Obj", 0, obj_coefficients)
+    }
+
+    pub fn change_row_bounds(&mut self, lower: &[f64], upper: &[f64]) -> Result<(), GurobiError> {
+        let lower: Vec<c_double> = lower.iter().map(|v| v.clamp(-GRB_INFINITY, GRB_INFINITY)).collect();
+        let upper: Vec<c_double> = upper.iter().map(|v| v.clamp(-GRB_INFINITY, GRB_INFINITY)).collect();
+        self.set_dbl_attr_array("RHS", 0, &lower)?;
+        self.set_dbl_attr_array("RHS", self.num_rows, &upper)
+    }
+
+    /// Change coefficients of the rows; each is changed in both of the constraints of its row.
+    pub fn change_coefficients(&mut self, coefficients: &[(c_int, c_int, f64)]) -> Result<(), GurobiError> {
+        if coefficients.is_empty() {
+            return Ok(());
+        }
+
+        let mut rows = Vec::with_capacity(2 * coefficients.len());
+        let mut cols = Vec::with_capacity(2 * coefficients.len());
+        let mut values = Vec::with_capacity(2 * coefficients.len());
+        for offset in [0, self.num_rows as c_int] {
+            for (row, col, value) in coefficients {
+                rows.push(row + offset);
+                cols.push(*col);
+                values.push(*value);
+            }
+        }
+
+        let ret = unsafe {
+            GRBchgcoeffs(
+                self.model,
+                values.len() as c_int,
+                rows.as_ptr(),
+                cols.as_ptr(),
+                values.as_ptr(),
+            )
+        };
+        self.check_error("GRBchgcoeffs", ret)
+    }
+
+    pub fn optimize(&mut self) -> Result<(), GurobiError> {
+        let ret = unsafe { GRBoptimize(self.model) };
+        self.check_error("GRBoptimize", ret)
+    }
+
+    /// The status of the model after the last optimisation.
+    pub fn status(&self) -> Result<c_int, GurobiError> {
+        let name = CString::new("Status").unwrap();
+        let mut status: c_int = 0;
+        let ret = unsafe { GRBgetintattr(self.model, name.as_ptr(), &mut status) };
+        self.check_error("GRBgetintattr", ret)?;
+        Ok(status)
+    }
+
+    /// Whether the last optimisation found that the model is infeasible.
+    pub fn is_infeasible(&self) -> Result<bool, GurobiError> {
+        Ok(matches!(self.status()?, GRB_INFEASIBLE | GRB_INF_OR_UNBD))
+    }
+
+    #[allow(dead_code)]
+    pub fn objective_value(&self) -> Result<f64, GurobiError> {
+        Ok(self.dbl_attr_array("ObjVal", 1)?[0])
+    }
+
+    pub fn primal_column_solution(&self, num_cols: usize) -> Result<Vec<f64>, GurobiError> {
+        self.dbl_attr_array("X", num_cols)
+    }
+
+    /// The dual value of each row; the sum of the dual values of its two constraints.
+    pub fn dual_row_solution(&self) -> Result<Vec<f64>, GurobiError> {
+        let pi = self.dbl_attr_array("Pi", 2 * self.num_rows)?;
+        let (lower, upper) = pi.split_at(self.num_rows);
+        Ok(lower.iter().zip(upper).map(|(l, u)| l + u).collect())
+    }
+}
+
+pub struct GurobiSolver {
+    builder: BuiltSolver<c_int>,
+    gurobi: Gurobi,
+    /// Whether the dual values are saved to the state after each solve.
+    duals: bool,
+    /// Whether an infeasible time-step is diagnosed (see [`crate::solvers::InfeasibilityReport`]).
+    diagnose_infeasibility: bool,
+}
+
+impl Solver for GurobiSolver {
+    type Settings = GurobiSolverSettings;
+
+    fn name() -> &'static str {
+        "gurobi"
+    }
+
+    fn features() -> &'static [SolverFeatures] {
+        &[
+            SolverFeatures::AggregatedNode,
+            SolverFeatures::AggregatedNodeFactors,
+            SolverFeatures::AggregatedNodeDynamicFactors,
+            SolverFeatures::VirtualStorage,
+            SolverFeatures::MutualExclusivity,
+            SolverFeatures::SwitchingConstraints,
+        ]
+    }
+
+    fn setup(
+        network: &Network,
+        values: &ConstParameterValues,
+        settings: &Self::Settings,
+    ) -> Result<Box<Self>, PywrError> {
+        let builder: SolverBuilder<c_int> = SolverBuilder::default().with_tie_breaking(settings.tie_breaking());
+        let built = builder.create(network, values)?;
+
+        let mut gurobi = Gurobi::new(settings)?;
+        gurobi.add_cols(
+            built.col_lower(),
+            built.col_upper(),
+            built.col_obj_coef(),
+            built.col_type(),
+        )?;
+        gurobi.add_rows(
+            built.row_lower(),
+            built.row_upper(),
+            built.row_starts(),
+            built.columns(),
+            built.elements(),
+        )?;
+
+        Ok(Box::new(Self {
+            builder: built,
+            gurobi,
+            duals: settings.duals(),
+            diagnose_infeasibility: settings.diagnose_infeasibility(),
+        }))
+    }
+
+    fn solve(&mut self, network: &Network, timestep: &Timestep, state: &mut State) -> Result<SolverTimings, PywrError> {
+        let mut timings = SolverTimings::default();
+        self.builder.update(network, timestep, state, &mut timings)?;
+
+        let num_cols = self.builder.num_cols() as usize;
+
+        let now = Instant::now();
+        self.gurobi.change_objective_coefficients(self.builder.col_obj_coef())?;
+        timings.update_objective += now.elapsed();
+
+        let now = Instant::now();
+        self.gurobi
+            .change_row_bounds(self.builder.row_lower(), self.builder.row_upper())?;
+        self.gurobi.change_coefficients(self.builder.coefficients_to_update())?;
+        timings.update_constraints += now.elapsed();
+
+        let now = Instant::now();
+        self.gurobi.optimize()?;
+        if self.diagnose_infeasibility && self.gurobi.is_infeasible()? {
+            return Err(diagnose_infeasibility(network, timestep, &self.builder));
+        }
+        let status = self.gurobi.status()?;
+        if status != GRB_OPTIMAL {
+            return Err(GurobiError::NotOptimal(status).into());
+        }
+        let solution = self.gurobi.primal_column_solution(num_cols)?;
+        timings.solve = now.elapsed();
+
+        // Reset the network state from the results
+        let network_state = state.get_mut_network_state();
+        network_state.reset();
+        let start_save_solution = Instant::now();
+
+        for edge in network.edges().deref() {
+            let col = self.builder.col_for_edge(&edge.index()) as usize;
+            let flow = solution[col];
+            network_state.add_flow(edge, timestep, flow)?;
+        }
+
+        if self.duals {
+            let row_duals = self.gurobi.dual_row_solution()?;
+            network_state.set_duals(self.builder.dual_values(network, &row_duals));
+        }
+
+        state.complete(network, timestep)?;
+        timings.save_solution += start_save_solution.elapsed();
+
+        Ok(timings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_cmp::approx_eq;
+
+    /// Create a Gurobi model of the example LP with the given upper bounds of its rows.
+    fn example_lp(row_upper: &[f64]) -> Gurobi {
+        let row_lower = vec![0.0, 0.0];
+        let col_lower = vec![0.0, 0.0, 0.0];
+        let col_upper = vec![f64::MAX, f64::MAX, f64::MAX];
+        let col_obj_coef = vec![-2.0, -3.0, -4.0];
+        let col_type = vec![ColType::Continuous, ColType::Continuous, ColType::Continuous];
+        let row_starts = vec![0, 3, 6];
+        let columns = vec![0, 1, 2, 0, 1, 2];
+        let elements = vec![3.0, 2.0, 1.0, 2.0, 5.0, 3.0];
+
+        let mut lp = Gurobi::new(&GurobiSolverSettings::default()).unwrap();
+        lp.add_cols(&col_lower, &col_upper, &col_obj_coef, &col_type).unwrap();
+        lp.add_rows(&row_lower, row_upper, &row_starts, &columns, &elements)
+            .unwrap();
+        lp
+    }
+
+    #[test]
+    fn simple_solve() {
+        let mut lp = example_lp(&[10.0, 15.0]);
+        lp.optimize().unwrap();
+
+        assert_eq!(lp.status().unwrap(), GRB_OPTIMAL);
+        assert!(approx_eq!(f64, lp.objective_value().unwrap(), -20.0));
+        assert_eq!(lp.primal_column_solution(3).unwrap(), vec![0.0, 0.0, 5.0]);
+    }
+
+    #[test]
+    fn solve_with_inf_row_bound() {
+        let mut lp = example_lp(&[10.0, f64::MAX]);
+        lp.optimize().unwrap();
+
+        assert!(approx_eq!(f64, lp.objective_value().unwrap(), -40.0));
+        assert_eq!(lp.primal_column_solution(3).unwrap(), vec![0.0, 0.0, 10.0]);
+    }
+
+    #[test]
+    fn change_row_bounds() {
+        let mut lp = example_lp(&[10.0, 15.0]);
+        lp.change_row_bounds(&[0.0, 0.0], &[10.0, 30.0]).unwrap();
+        lp.optimize().unwrap();
+
+        assert!(approx_eq!(f64, lp.objective_value().unwrap(), -40.0));
+        assert_eq!(lp.dual_row_solution().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn shared_environment() {
+        let lp1 = example_lp(&[10.0, 15.0]);
+        let lp2 = example_lp(&[10.0, 15.0]);
+
+        // The models are created from the same environment but have their own parameters
+        assert!(Arc::ptr_eq(&lp1.shared_env, &lp2.shared_env));
+        assert_ne!(lp1.env, lp2.env);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::solvers::{LpExport, SolverSettings};
+
+/// The algorithm used by Gurobi to solve the LP of each time-step.
+///
+/// See the documentation of the `Method` parameter of Gurobi for details of each algorithm.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum GurobiMethod {
+    /// Gurobi chooses the algorithm.
+    #[default]
+    Automatic,
+    PrimalSimplex,
+    DualSimplex,
+    Barrier,
+    Concurrent,
+    DeterministicConcurrent,
+}
+
+impl GurobiMethod {
+    /// The value of the `Method` parameter of Gurobi.
+    pub(crate) fn value(&self) -> i32 {
+        match self {
+            Self::Automatic => -1,
+            Self::PrimalSimplex => 0,
+            Self::DualSimplex => 1,
+            Self::Barrier => 2,
+            Self::Concurrent => 3,
+            Self::DeterministicConcurrent => 4,
+        }
+    }
+}
+
+/// Settings for the Gurobi solver.
+///
+/// Create new settings using [`GurobiSolverSettingsBuilder`] or use the default implementation;
+#[derive(PartialEq, Debug, Clone)]
+pub struct GurobiSolverSettings {
+    parallel: bool,
+    threads: usize,
+    duals: bool,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    diagnose_infeasibility: bool,
+    solver_threads: Option<usize>,
+    method: GurobiMethod,
+    feasibility_tolerance: Option<f64>,
+    optimality_tolerance: Option<f64>,
+    mip_gap: Option<f64>,
+}
+
+// Default implementation is a convenience that defers to the builder.
+impl Default for GurobiSolverSettings {
+    fn default() -> Self {
+        GurobiSolverSettingsBuilder::default().build()
+    }
+}
+
+impl SolverSettings for GurobiSolverSettings {
+    fn parallel(&self) -> bool {
+        self.parallel
+    }
+
+    fn threads(&self) -> usize {
+        self.threads
+    }
+
+    fn duals(&self) -> bool {
+        self.duals
+    }
+
+    fn tie_breaking(&self) -> Option<f64> {
+        self.tie_breaking
+    }
+
+    fn lp_export(&self) -> Option<&LpExport> {
+        self.lp_export.as_ref()
+    }
+
+    fn diagnose_infeasibility(&self) -> bool {
+        self.diagnose_infeasibility
+    }
+}
+
+impl GurobiSolverSettings {
+    /// Create a new builder for the settings
+    pub fn builder() -> GurobiSolverSettingsBuilder {
+        GurobiSolverSettingsBuilder::default()
+    }
+
+    /// The number of threads used by Gurobi to solve each LP, or `None` for Gurobi's default.
+    pub fn solver_threads(&self) -> Option<usize> {
+        self.solver_threads
+    }
+
+    pub fn method(&self) -> GurobiMethod {
+        self.method
+    }
+
+    pub fn feasibility_tolerance(&self) -> Option<f64> {
+        self.feasibility_tolerance
+    }
+
+    pub fn optimality_tolerance(&self) -> Option<f64> {
+        self.optimality_tolerance
+    }
+
+    pub fn mip_gap(&self) -> Option<f64> {
+        self.mip_gap
+    }
+}
+
+/// Builder for [`GurobiSolverSettings`].
+///
+/// # Examples
+///
+/// ```
+/// use pywr_core::solvers::{GurobiMethod, GurobiSolverSettingsBuilder};
+/// // Settings with parallel enabled and 4 threads.
+/// let settings = GurobiSolverSettingsBuilder::default().parallel().threads(4).build();
+///
+/// // Settings that solve each LP with the dual simplex on a single thread.
+/// let settings = GurobiSolverSettingsBuilder::default()
+///     .solver_threads(1)
+///     .method(GurobiMethod::DualSimplex)
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct GurobiSolverSettingsBuilder {
+    parallel: bool,
+    threads: usize,
+    duals: bool,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    diagnose_infeasibility: bool,
+    solver_threads: Option<usize>,
+    method: GurobiMethod,
+    feasibility_tolerance: Option<f64>,
+    optimality_tolerance: Option<f64>,
+    mip_gap: Option<f64>,
+}
+
+impl GurobiSolverSettingsBuilder {
+    pub fn parallel(mut self) -> Self {
+        self.parallel = true;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Save the dual values of the constraints to the state after each solve.
+    ///
+    /// Gurobi only provides dual values if the LP has no integer columns.
+    pub fn duals(mut self) -> Self {
+        self.duals = true;
+        self
+    }
+
+    /// Break ties between routes of equal cost deterministically.
+    ///
+    /// The costs of the edges out of each node with more than one outgoing edge are perturbed by
+    /// values between `-epsilon / 2` and `epsilon / 2`, so that edges added earlier are preferred
+    /// and the perturbations at each node sum to zero. `epsilon` should be much smaller than any
+    /// meaningful difference between the costs of the routes.
+    pub fn tie_breaking(mut self, epsilon: f64) -> Self {
+        self.tie_breaking = Some(epsilon);
+        self
+    }
+
+    /// Write the LP of a time-step of a scenario to a file (see [`LpExport`]).
+    pub fn export_lp(mut self, export: LpExport) -> Self {
+        self.lp_export = Some(export);
+        self
+    }
+
+    /// Diagnose an infeasible time-step before the run fails (see
+    /// [`InfeasibilityReport`](crate::solvers::InfeasibilityReport)).
+    pub fn diagnose_infeasibility(mut self) -> Self {
+        self.diagnose_infeasibility = true;
+        self
+    }
+
+    /// The number of threads used by Gurobi to solve the LP of each scenario (the `Threads`
+    /// parameter).
+    ///
+    /// This is separate from [`Self::threads`], which is the number of scenarios solved in
+    /// parallel. By default Gurobi uses all of the cores of the machine for each LP.
+    pub fn solver_threads(mut self, threads: usize) -> Self {
+        self.solver_threads = Some(threads);
+        self
+    }
+
+    /// The algorithm used to solve the LP (the `Method` parameter).
+    pub fn method(mut self, method: GurobiMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// The primal feasibility tolerance (the `FeasibilityTol` parameter).
+    pub fn feasibility_tolerance(mut self, tolerance: f64) -> Self {
+        self.feasibility_tolerance = Some(tolerance);
+        self
+    }
+
+    /// The dual feasibility tolerance (the `OptimalityTol` parameter).
+    pub fn optimality_tolerance(mut self, tolerance: f64) -> Self {
+        self.optimality_tolerance = Some(tolerance);
+        self
+    }
+
+    /// The relative gap at which the solve of an LP with integer columns stops (the `MIPGap`
+    /// parameter).
+    pub fn mip_gap(mut self, gap: f64) -> Self {
+        self.mip_gap = Some(gap);
+        self
+    }
+
+    /// Construct a [`GurobiSolverSettings`] from the builder.
+    pub fn build(self) -> GurobiSolverSettings {
+        GurobiSolverSettings {
+            parallel: self.parallel,
+            threads: self.threads,
+            duals: self.duals,
+            tie_breaking: self.tie_breaking,
+            lp_export: self.lp_export,
+            diagnose_infeasibility: self.diagnose_infeasibility,
+            solver_threads: self.solver_threads,
+            method: self.method,
+            feasibility_tolerance: self.feasibility_tolerance,
+            optimality_tolerance: self.optimality_tolerance,
+            mip_gap: self.mip_gap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GurobiMethod, GurobiSolverSettings, GurobiSolverSettingsBuilder};
+
+    #[test]
+    fn builder_test() {
+        let settings = GurobiSolverSettings {
+            parallel: true,
+            threads: 0,
+            duals: false,
+            tie_breaking: None,
+            lp_export: None,
+            diagnose_infeasibility: false,
+            solver_threads: Some(1),
+            method: GurobiMethod::DualSimplex,
+            feasibility_tolerance: None,
+            optimality_tolerance: None,
+            mip_gap: None,
+        };
+        let settings_from_builder = GurobiSolverSettingsBuilder::default()
+            .parallel()
+            .solver_threads(1)
+            .method(GurobiMethod::DualSimplex)
+            .build();
+
+        assert_eq!(settings_from_builder, settings);
+    }
+}
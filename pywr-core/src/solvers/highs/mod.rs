@@ -2,15 +2,16 @@ mod settings;
 
 use crate::network::Network;
 use crate::solvers::builder::{BuiltSolver, ColType, SolverBuilder};
-use crate::solvers::{Solver, SolverFeatures, SolverTimings};
+use crate::solvers::infeasibility::diagnose_infeasibility;
+use crate::solvers::{Solver, SolverFeatures, SolverSettings, SolverTimings};
 use crate::state::{ConstParameterValues, State};
 use crate::timestep::Timestep;
 use crate::PywrError;
 use highs_sys::{
-    kHighsVarTypeContinuous, kHighsVarTypeInteger, HighsInt, Highs_addCols, Highs_addRows, Highs_changeCoeff,
-    Highs_changeColIntegrality, Highs_changeColsCostByRange, Highs_changeObjectiveSense, Highs_changeRowsBoundsByMask,
-    Highs_create, Highs_getDoubleInfoValue, Highs_getSolution, Highs_run, Highs_setBoolOptionValue,
-    Highs_setStringOptionValue, OBJECTIVE_SENSE_MINIMIZE, STATUS_OK,
+    kHighsModelStatusInfeasible, kHighsVarTypeContinuous, kHighsVarTypeInteger, HighsInt, Highs_addCols, Highs_addRows,
+    Highs_changeCoeff, Highs_changeColIntegrality, Highs_changeColsCostByRange, Highs_changeObjectiveSense,
+    Highs_changeRowsBoundsByMask, Highs_create, Highs_getDoubleInfoValue, Highs_getModelStatus, Highs_getSolution,
+    Highs_run, Highs_setBoolOptionValue, Highs_setStringOptionValue, OBJECTIVE_SENSE_MINIMIZE, STATUS_OK,
 };
 use libc::c_void;
 pub use settings::{HighsSolverSettings, HighsSolverSettingsBuilder};
@@ -18,6 +19,22 @@ use std::ffi::CString;
 use std::ops::Deref;
 use std::ptr::null;
 use std::time::Instant;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HighsError {
+    #[error("`{call}` returned an error status ({status}) in HiGHS")]
+    Status { call: &'static str, status: HighsInt },
+}
+
+/// Convert the status returned by a HiGHS call into an error if it is not OK.
+fn check_status(call: &'static str, status: HighsInt) -> Result<(), HighsError> {
+    if status == STATUS_OK {
+        Ok(())
+    } else {
+        Err(HighsError::Status { call, status })
+    }
+}
 
 struct Highs {
     ptr: *mut c_void,
@@ -49,17 +66,13 @@ impl Default for Highs {
     }
 }
 
-// TODO add error handling for all Highs calls
-
 impl Highs {
     #[allow(dead_code)]
-    fn presolve(&mut self, value: &str) {
+    fn presolve(&mut self, value: &str) -> Result<(), HighsError> {
         let option_name = CString::new("presolve").unwrap();
         let option_value = CString::new(value).unwrap();
-        unsafe {
-            let ret = Highs_setStringOptionValue(self.ptr, option_name.as_ptr(), option_value.as_ptr());
-            assert_eq!(ret, STATUS_OK);
-        }
+        let ret = unsafe { Highs_setStringOptionValue(self.ptr, option_name.as_ptr(), option_value.as_ptr()) };
+        check_status("Highs_setStringOptionValue", ret)
     }
 
     pub fn add_cols(
@@ -69,10 +82,10 @@ impl Highs {
         col_obj_coef: &[f64],
         col_type: &[ColType],
         ncols: HighsInt,
-    ) {
+    ) -> Result<(), HighsError> {
         // Add all of the columns
-        unsafe {
-            let ret = Highs_addCols(
+        let ret = unsafe {
+            Highs_addCols(
                 self.ptr,
                 ncols,
                 col_obj_coef.as_ptr(),
@@ -82,9 +95,9 @@ impl Highs {
                 null(),
                 null(),
                 null(),
-            );
-            assert_eq!(ret, STATUS_OK);
-        }
+            )
+        };
+        check_status("Highs_addCols", ret)?;
 
         // Now change the column types
         for (i, &ctype) in col_type.iter().enumerate() {
@@ -93,11 +106,11 @@ impl Highs {
                 ColType::Integer => kHighsVarTypeInteger,
             };
 
-            unsafe {
-                let ret = Highs_changeColIntegrality(self.ptr, i as HighsInt, ctype_int);
-                assert_eq!(ret, STATUS_OK);
-            }
+            let ret = unsafe { Highs_changeColIntegrality(self.ptr, i as HighsInt, ctype_int) };
+            check_status("Highs_changeColIntegrality", ret)?;
         }
+
+        Ok(())
     }
 
     pub fn add_rows(
@@ -108,9 +121,9 @@ impl Highs {
         row_starts: &[HighsInt],
         columns: &[HighsInt],
         elements: &[f64],
-    ) {
-        unsafe {
-            let ret = Highs_addRows(
+    ) -> Result<(), HighsError> {
+        let ret = unsafe {
+            Highs_addRows(
                 self.ptr,
                 row_upper.len() as HighsInt,
                 row_lower.as_ptr(),
@@ -119,37 +132,38 @@ impl Highs {
                 row_starts.as_ptr(),
                 columns.as_ptr(),
                 elements.as_ptr(),
-            );
-            assert_eq!(ret, STATUS_OK);
-        }
+            )
+        };
+        check_status("Highs_addRows", ret)
     }
 
-    pub fn change_objective_coefficients(&mut self, obj_coefficients: &[f64], numcols: HighsInt) {
-        unsafe {
-            let ret = Highs_changeColsCostByRange(self.ptr, 0, numcols - 1, obj_coefficients.as_ptr());
-            assert_eq!(ret, STATUS_OK);
-        }
+    pub fn change_objective_coefficients(
+        &mut self,
+        obj_coefficients: &[f64],
+        numcols: HighsInt,
+    ) -> Result<(), HighsError> {
+        let ret = unsafe { Highs_changeColsCostByRange(self.ptr, 0, numcols - 1, obj_coefficients.as_ptr()) };
+        check_status("Highs_changeColsCostByRange", ret)
     }
 
-    pub fn change_row_bounds(&mut self, mask: &[HighsInt], lower: &[f64], upper: &[f64]) {
-        unsafe {
-            let ret = Highs_changeRowsBoundsByMask(self.ptr, mask.as_ptr(), lower.as_ptr(), upper.as_ptr());
-            assert_eq!(ret, STATUS_OK);
-        }
+    pub fn change_row_bounds(&mut self, mask: &[HighsInt], lower: &[f64], upper: &[f64]) -> Result<(), HighsError> {
+        let ret = unsafe { Highs_changeRowsBoundsByMask(self.ptr, mask.as_ptr(), lower.as_ptr(), upper.as_ptr()) };
+        check_status("Highs_changeRowsBoundsByMask", ret)
     }
 
-    pub fn change_coefficient(&mut self, row: HighsInt, col: HighsInt, value: f64) {
-        unsafe {
-            let ret = Highs_changeCoeff(self.ptr, row, col, value);
-            assert_eq!(ret, STATUS_OK);
-        }
+    pub fn change_coefficient(&mut self, row: HighsInt, col: HighsInt, value: f64) -> Result<(), HighsError> {
+        let ret = unsafe { Highs_changeCoeff(self.ptr, row, col, value) };
+        check_status("Highs_changeCoeff", ret)
     }
 
-    pub fn run(&mut self) {
-        unsafe {
-            let status = Highs_run(self.ptr);
-            assert_eq!(status, STATUS_OK);
-        }
+    pub fn run(&mut self) -> Result<(), HighsError> {
+        let status = unsafe { Highs_run(self.ptr) };
+        check_status("Highs_run", status)
+    }
+
+    /// Whether the last run found that the model is infeasible.
+    pub fn is_infeasible(&self) -> bool {
+        unsafe { Highs_getModelStatus(self.ptr) == kHighsModelStatusInfeasible }
     }
 
     #[allow(dead_code)]
@@ -166,30 +180,41 @@ impl Highs {
         objective_function_value
     }
 
-    pub fn primal_column_solution(&mut self, numcol: usize, numrow: usize) -> Vec<f64> {
+    #[allow(dead_code)]
+    pub fn primal_column_solution(&mut self, numcol: usize, numrow: usize) -> Result<Vec<f64>, HighsError> {
+        Ok(self.solution(numcol, numrow)?.0)
+    }
+
+    /// The primal column solution and the dual row solution.
+    pub fn solution(&mut self, numcol: usize, numrow: usize) -> Result<(Vec<f64>, Vec<f64>), HighsError> {
         let colvalue: &mut [f64] = &mut vec![0.; numcol];
         let coldual: &mut [f64] = &mut vec![0.; numcol];
         let rowvalue: &mut [f64] = &mut vec![0.; numrow];
         let rowdual: &mut [f64] = &mut vec![0.; numrow];
 
-        unsafe {
-            // Get the primal and dual solution
-            let ret = Highs_getSolution(
+        // Get the primal and dual solution
+        let ret = unsafe {
+            Highs_getSolution(
                 self.ptr,
                 colvalue.as_mut_ptr(),
                 coldual.as_mut_ptr(),
                 rowvalue.as_mut_ptr(),
                 rowdual.as_mut_ptr(),
-            );
-            assert_eq!(ret, STATUS_OK);
-        }
-        colvalue.to_vec()
+            )
+        };
+        check_status("Highs_getSolution", ret)?;
+
+        Ok((colvalue.to_vec(), rowdual.to_vec()))
     }
 }
 
 pub struct HighsSolver {
     builder: BuiltSolver<HighsInt>,
     highs: Highs,
+    /// Whether the dual values are saved to the state after each solve.
+    duals: bool,
+    /// Whether an infeasible time-step is diagnosed (see [`crate::solvers::InfeasibilityReport`]).
+    diagnose_infeasibility: bool,
 }
 
 impl Solver for HighsSolver {
@@ -207,15 +232,16 @@ impl Solver for HighsSolver {
             SolverFeatures::AggregatedNodeFactors,
             SolverFeatures::AggregatedNodeDynamicFactors,
             SolverFeatures::VirtualStorage,
+            SolverFeatures::SwitchingConstraints,
         ]
     }
 
     fn setup(
         network: &Network,
         values: &ConstParameterValues,
-        _settings: &Self::Settings,
+        settings: &Self::Settings,
     ) -> Result<Box<Self>, PywrError> {
-        let builder: SolverBuilder<HighsInt> = SolverBuilder::default();
+        let builder: SolverBuilder<HighsInt> = SolverBuilder::default().with_tie_breaking(settings.tie_breaking());
         let built = builder.create(network, values)?;
 
         let num_cols = built.num_cols();
@@ -229,7 +255,7 @@ impl Solver for HighsSolver {
             built.col_obj_coef(),
             built.col_type(),
             num_cols,
-        );
+        )?;
 
         highs_lp.add_rows(
             built.row_lower(),
@@ -238,11 +264,13 @@ impl Solver for HighsSolver {
             built.row_starts(),
             built.columns(),
             built.elements(),
-        );
+        )?;
 
         Ok(Box::new(Self {
             builder: built,
             highs: highs_lp,
+            duals: settings.duals(),
+            diagnose_infeasibility: settings.diagnose_infeasibility(),
         }))
     }
     fn solve(&mut self, network: &Network, timestep: &Timestep, state: &mut State) -> Result<SolverTimings, PywrError> {
@@ -254,7 +282,7 @@ impl Solver for HighsSolver {
 
         let now = Instant::now();
         self.highs
-            .change_objective_coefficients(self.builder.col_obj_coef(), num_cols);
+            .change_objective_coefficients(self.builder.col_obj_coef(), num_cols)?;
         timings.update_objective += now.elapsed();
 
         let now = Instant::now();
@@ -263,19 +291,22 @@ impl Solver for HighsSolver {
             self.builder.row_mask(),
             self.builder.row_lower(),
             self.builder.row_upper(),
-        );
+        )?;
 
         for (row, column, coefficient) in self.builder.coefficients_to_update() {
             // Highs only accepts coefficients in the range -1e10 to 1e10
             self.highs
-                .change_coefficient(*row, *column, coefficient.clamp(-1e10, 1e10));
+                .change_coefficient(*row, *column, coefficient.clamp(-1e10, 1e10))?;
         }
 
         timings.update_constraints += now.elapsed();
 
         let now = Instant::now();
-        self.highs.run();
-        let solution = self.highs.primal_column_solution(num_cols as usize, num_rows as usize);
+        self.highs.run()?;
+        if self.diagnose_infeasibility && self.highs.is_infeasible() {
+            return Err(diagnose_infeasibility(network, timestep, &self.builder));
+        }
+        let (solution, row_duals) = self.highs.solution(num_cols as usize, num_rows as usize)?;
         timings.solve = now.elapsed();
 
         // Reset the network state from the results
@@ -288,6 +319,11 @@ impl Solver for HighsSolver {
             let flow = solution[col];
             network_state.add_flow(edge, timestep, flow)?;
         }
+
+        if self.duals {
+            network_state.set_duals(self.builder.dual_values(network, &row_duals));
+        }
+
         state.complete(network, timestep)?;
         timings.save_solution += start_save_solution.elapsed();
 
@@ -314,7 +350,8 @@ mod tests {
         let col_obj_coef: Vec<f64> = vec![1.0, 1.0];
         let col_type = vec![ColType::Continuous, ColType::Continuous];
 
-        lp.add_cols(&col_lower, &col_upper, &col_obj_coef, &col_type, 2);
+        lp.add_cols(&col_lower, &col_upper, &col_obj_coef, &col_type, 2)
+            .unwrap();
 
         let row_lower: Vec<f64> = vec![0.0];
         let row_upper: Vec<f64> = vec![2.0];
@@ -322,7 +359,8 @@ mod tests {
         let columns: Vec<HighsInt> = vec![0, 1];
         let elements: Vec<f64> = vec![1.0, 1.0];
 
-        lp.add_rows(&row_lower, &row_upper, 2, &row_starts, &columns, &elements);
+        lp.add_rows(&row_lower, &row_upper, 2, &row_starts, &columns, &elements)
+            .unwrap();
     }
 
     #[test]
@@ -342,14 +380,16 @@ mod tests {
         let nrows = row_upper.len() as HighsInt;
         let nnz = elements.len() as HighsInt;
 
-        lp.add_cols(&col_lower, &col_upper, &col_obj_coef, &col_type, ncols);
+        lp.add_cols(&col_lower, &col_upper, &col_obj_coef, &col_type, ncols)
+            .unwrap();
 
-        lp.add_rows(&row_lower, &row_upper, nnz, &row_starts, &columns, &elements);
-        lp.run();
+        lp.add_rows(&row_lower, &row_upper, nnz, &row_starts, &columns, &elements)
+            .unwrap();
+        lp.run().unwrap();
 
         assert!(approx_eq!(f64, lp.objective_value(), -20.0));
         assert_eq!(
-            lp.primal_column_solution(ncols as usize, nrows as usize),
+            lp.primal_column_solution(ncols as usize, nrows as usize).unwrap(),
             vec![0.0, 0.0, 5.0]
         );
     }
@@ -371,14 +411,16 @@ mod tests {
         let nrows = row_upper.len() as HighsInt;
         let nnz = elements.len() as HighsInt;
 
-        lp.add_cols(&col_lower, &col_upper, &col_obj_coef, &col_type, ncols);
+        lp.add_cols(&col_lower, &col_upper, &col_obj_coef, &col_type, ncols)
+            .unwrap();
 
-        lp.add_rows(&row_lower, &row_upper, nnz, &row_starts, &columns, &elements);
-        lp.run();
+        lp.add_rows(&row_lower, &row_upper, nnz, &row_starts, &columns, &elements)
+            .unwrap();
+        lp.run().unwrap();
 
         assert!(approx_eq!(f64, lp.objective_value(), -40.0));
         assert_eq!(
-            lp.primal_column_solution(ncols as usize, nrows as usize),
+            lp.primal_column_solution(ncols as usize, nrows as usize).unwrap(),
             vec![0.0, 0.0, 10.0]
         );
     }
@@ -1,12 +1,16 @@
-use crate::solvers::SolverSettings;
+use crate::solvers::{LpExport, SolverSettings};
 
 /// Settings for the OpenCL IPM solvers.
 ///
 /// Create new settings using [`HighsSolverSettingsBuilder`] or use the default implementation;
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct HighsSolverSettings {
     parallel: bool,
     threads: usize,
+    duals: bool,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    diagnose_infeasibility: bool,
 }
 
 // Default implementation is a convenience that defers to the builder.
@@ -24,6 +28,22 @@ impl SolverSettings for HighsSolverSettings {
     fn threads(&self) -> usize {
         self.threads
     }
+
+    fn duals(&self) -> bool {
+        self.duals
+    }
+
+    fn tie_breaking(&self) -> Option<f64> {
+        self.tie_breaking
+    }
+
+    fn lp_export(&self) -> Option<&LpExport> {
+        self.lp_export.as_ref()
+    }
+
+    fn diagnose_infeasibility(&self) -> bool {
+        self.diagnose_infeasibility
+    }
 }
 
 impl HighsSolverSettings {
@@ -52,6 +72,10 @@ impl HighsSolverSettings {
 pub struct HighsSolverSettingsBuilder {
     parallel: bool,
     threads: usize,
+    duals: bool,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    diagnose_infeasibility: bool,
 }
 
 impl HighsSolverSettingsBuilder {
@@ -65,11 +89,45 @@ impl HighsSolverSettingsBuilder {
         self
     }
 
+    /// Save the dual values of the constraints to the state after each solve.
+    pub fn duals(mut self) -> Self {
+        self.duals = true;
+        self
+    }
+
+    /// Break ties between routes of equal cost deterministically.
+    ///
+    /// The costs of the edges out of each node with more than one outgoing edge are perturbed by
+    /// values between `-epsilon / 2` and `epsilon / 2`, so that edges added earlier are preferred
+    /// and the perturbations at each node sum to zero. `epsilon` should be much smaller than any
+    /// meaningful difference between the costs of the routes.
+    pub fn tie_breaking(mut self, epsilon: f64) -> Self {
+        self.tie_breaking = Some(epsilon);
+        self
+    }
+
+    /// Write the LP of a time-step of a scenario to a file (see [`LpExport`]).
+    pub fn export_lp(mut self, export: LpExport) -> Self {
+        self.lp_export = Some(export);
+        self
+    }
+
+    /// Diagnose an infeasible time-step before the run fails (see
+    /// [`InfeasibilityReport`](crate::solvers::InfeasibilityReport)).
+    pub fn diagnose_infeasibility(mut self) -> Self {
+        self.diagnose_infeasibility = true;
+        self
+    }
+
     /// Construct a [`HighsSolverSettings`] from the builder.
     pub fn build(self) -> HighsSolverSettings {
         HighsSolverSettings {
             parallel: self.parallel,
             threads: self.threads,
+            duals: self.duals,
+            tie_breaking: self.tie_breaking,
+            lp_export: self.lp_export,
+            diagnose_infeasibility: self.diagnose_infeasibility,
         }
     }
 }
@@ -83,6 +141,10 @@ mod tests {
         let settings = HighsSolverSettings {
             parallel: true,
             threads: 0,
+            duals: false,
+            tie_breaking: None,
+            lp_export: None,
+            diagnose_infeasibility: false,
         };
         let settings_from_builder = HighsSolverSettingsBuilder::default().parallel().build();
 
@@ -0,0 +1,246 @@
+use crate::edge::EdgeIndex;
+use crate::network::Network;
+use crate::solvers::builder::{BuiltSolver, ColType, SolverBuilder};
+use crate::solvers::SolverTimings;
+use crate::state::{ConstParameterValues, State};
+use crate::timestep::Timestep;
+use crate::PywrError;
+use std::fmt::Debug;
+
+const FMAX: f64 = f64::MAX;
+const FMIN: f64 = f64::MIN;
+
+/// Sparse form of a linear program of a network over all the time-steps of a run.
+///
+/// The LP of each time-step is the same as that solved by the sequential solvers, except that
+/// the bounds of the storage nodes are replaced by a column for the change in volume of each
+/// storage node since the start of the run. These columns link the time-steps together by
+/// storage continuity.
+pub(crate) struct HorizonLp<I> {
+    pub col_lower: Vec<f64>,
+    pub col_upper: Vec<f64>,
+    pub col_obj_coef: Vec<f64>,
+    pub row_lower: Vec<f64>,
+    pub row_upper: Vec<f64>,
+    pub row_starts: Vec<I>,
+    pub columns: Vec<I>,
+    pub elements: Vec<f64>,
+    /// The first column of each time-step.
+    step_col_offsets: Vec<usize>,
+}
+
+impl<I> HorizonLp<I> {
+    pub fn num_cols(&self) -> usize {
+        self.col_lower.len()
+    }
+
+    fn add_row(&mut self, lower: f64, upper: f64, elements: impl IntoIterator<Item = (I, f64)>)
+    where
+        I: num::PrimInt,
+    {
+        self.row_lower.push(lower);
+        self.row_upper.push(upper);
+        for (column, value) in elements {
+            self.columns.push(column);
+            self.elements.push(value);
+        }
+        self.row_starts.push(I::from(self.columns.len()).unwrap());
+    }
+}
+
+/// Helper for constructing a [`HorizonLp`] one time-step at a time.
+pub(crate) struct HorizonLpBuilder<I> {
+    built: BuiltSolver<I>,
+    lp: HorizonLp<I>,
+    /// The row of each storage node in the LP of a single time-step.
+    storage_rows: Vec<usize>,
+    /// The column of the change in volume of each storage node in the previous time-step.
+    previous_volume_cols: Option<Vec<I>>,
+}
+
+impl<I> HorizonLpBuilder<I>
+where
+    I: num::PrimInt + Default + Debug + Copy,
+{
+    pub fn new(network: &Network, values: &ConstParameterValues) -> Result<Self, PywrError> {
+        if !network.virtual_storage_nodes().is_empty() {
+            return Err(PywrError::WholeHorizonNotSupported("virtual storage nodes".to_string()));
+        }
+
+        let built = SolverBuilder::default().create(network, values)?;
+        if built.col_type().iter().any(|t| matches!(t, ColType::Integer)) {
+            return Err(PywrError::WholeHorizonNotSupported(
+                "binary variables (e.g. mutual exclusivity or switching constraints)".to_string(),
+            ));
+        }
+
+        let storage_rows = built.storage_node_row_ids(network)?;
+
+        Ok(Self {
+            built,
+            lp: HorizonLp {
+                col_lower: Vec::new(),
+                col_upper: Vec::new(),
+                col_obj_coef: Vec::new(),
+                row_lower: Vec::new(),
+                row_upper: Vec::new(),
+                row_starts: vec![I::zero()],
+                columns: Vec::new(),
+                elements: Vec::new(),
+                step_col_offsets: Vec::new(),
+            },
+            storage_rows,
+            previous_volume_cols: None,
+        })
+    }
+
+    /// Add the LP of `timestep` using the values of the parameters in `state`.
+    pub fn add_timestep(&mut self, network: &Network, timestep: &Timestep, state: &State) -> Result<(), PywrError> {
+        let mut timings = SolverTimings::default();
+        self.built.update(network, timestep, state, &mut timings)?;
+
+        if !self.built.coefficients_to_update().is_empty() {
+            return Err(PywrError::WholeHorizonNotSupported(
+                "constraint coefficients that change during the run (e.g. dynamic aggregated node factors)".to_string(),
+            ));
+        }
+
+        let built = &self.built;
+        let lp = &mut self.lp;
+        let offset = lp.num_cols();
+        let col = |c: &I| I::from(offset + c.to_usize().unwrap()).unwrap();
+
+        lp.step_col_offsets.push(offset);
+        lp.col_lower.extend_from_slice(built.col_lower());
+        lp.col_upper.extend_from_slice(built.col_upper());
+        lp.col_obj_coef.extend_from_slice(built.col_obj_coef());
+
+        let row_starts = built.row_starts();
+        let row_elements = |row: usize| {
+            let (start, end) = (
+                row_starts[row].to_usize().unwrap(),
+                row_starts[row + 1].to_usize().unwrap(),
+            );
+            built.columns()[start..end].iter().zip(&built.elements()[start..end])
+        };
+
+        // The rows of the time-step; the storage rows are unbounded because the volume of the
+        // storage nodes is constrained by the continuity rows below.
+        for row in 0..built.num_rows().to_usize().unwrap() {
+            let (lower, upper) = if self.storage_rows.contains(&row) {
+                (FMIN, FMAX)
+            } else {
+                (built.row_lower()[row], built.row_upper()[row])
+            };
+            lp.add_row(lower, upper, row_elements(row).map(|(c, v)| (col(c), *v)));
+        }
+
+        // The change in volume of each storage node is bounded by the volume bounds of the node
+        // in this time-step. The flow bounds of the storage row are relative to the initial
+        // volume because the state is not updated with the flows of the previous time-steps.
+        let dt = timestep.days();
+        let mut volume_cols = Vec::with_capacity(self.storage_rows.len());
+        for (i, row) in self.storage_rows.iter().enumerate() {
+            let volume_col = I::from(lp.num_cols()).unwrap();
+            lp.col_lower.push(built.row_lower()[*row] * dt);
+            lp.col_upper.push(built.row_upper()[*row] * dt);
+            lp.col_obj_coef.push(0.0);
+
+            let mut elements: Vec<(I, f64)> = vec![(volume_col, 1.0)];
+            if let Some(previous) = &self.previous_volume_cols {
+                elements.push((previous[i], -1.0));
+            }
+            elements.extend(row_elements(*row).map(|(c, v)| (col(c), -v * dt)));
+            lp.add_row(0.0, 0.0, elements);
+
+            volume_cols.push(volume_col);
+        }
+        self.previous_volume_cols = Some(volume_cols);
+
+        Ok(())
+    }
+
+    pub fn build(self) -> (HorizonLp<I>, HorizonSolution<I>) {
+        let solution = HorizonSolution {
+            built: self.built,
+            step_col_offsets: self.lp.step_col_offsets.clone(),
+        };
+        (self.lp, solution)
+    }
+}
+
+/// Maps the solution of a [`HorizonLp`] to the flows of the edges in each time-step.
+pub(crate) struct HorizonSolution<I> {
+    built: BuiltSolver<I>,
+    step_col_offsets: Vec<usize>,
+}
+
+impl<I> HorizonSolution<I>
+where
+    I: num::PrimInt + Default + Debug + Copy,
+{
+    /// The flow of an edge in the time-step `step` of the `solution`.
+    pub fn edge_flow(&self, solution: &[f64], step: usize, edge_index: &EdgeIndex) -> f64 {
+        let col = self.built.col_for_edge(edge_index).to_usize().unwrap();
+        solution[self.step_col_offsets[step] + col]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metric::MetricF64;
+    use crate::models::Model;
+    use crate::network::Network;
+    use crate::node::StorageInitialVolume;
+    use crate::parameters::{AggFunc, AggregatedParameter, Array1Parameter};
+    use crate::recorders::AssertionRecorder;
+    use crate::test_utils::default_time_domain;
+    use ndarray::{Array1, Array2};
+
+    /// Test that a whole-horizon run saves the water in a reservoir for the most valuable days.
+    #[test]
+    fn test_whole_horizon() {
+        let mut network = Network::default();
+        let reservoir = network
+            .add_storage_node(
+                "reservoir",
+                None,
+                StorageInitialVolume::Absolute(100.0),
+                None,
+                Some(100.0.into()),
+            )
+            .unwrap();
+        let output = network.add_output_node("output", None).unwrap();
+        network.connect_nodes(reservoir, output).unwrap();
+
+        // The benefit of supplying the demand increases each day
+        let costs = Array1::from_iter((0..15).map(|i| -1.0 - i as f64));
+        let cost = Array1Parameter::new("demand-cost".into(), costs, None);
+        let cost = network.add_simple_parameter(Box::new(cost)).unwrap();
+
+        let node = network.get_mut_node_by_name("output", None).unwrap();
+        node.set_max_flow_constraint(Some(10.0.into())).unwrap();
+        node.set_cost(Some(cost.into()));
+
+        // A sequential run would supply the demand for the first ten days; with perfect
+        // foresight the water is kept for the last ten days.
+        let expected = Array2::from_shape_fn((15, 1), |(i, _)| if i < 5 { 0.0 } else { 10.0 });
+        let recorder = AssertionRecorder::new("output-flow", MetricF64::NodeInFlow(output), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let expected = Array2::from_shape_fn((15, 1), |(i, _)| 100.0 - 10.0 * (i.max(4) - 4) as f64);
+        let recorder = AssertionRecorder::new("volume", MetricF64::NodeVolume(reservoir), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        // A parameter that depends on the state of the network keeps the value computed from the
+        // initial state, as used in the linear program, rather than following the solution.
+        let volume = AggregatedParameter::new("volume".into(), &[MetricF64::NodeVolume(reservoir)], AggFunc::Sum);
+        let volume = network.add_parameter(Box::new(volume)).unwrap();
+        let expected = Array2::from_elem((15, 1), 100.0);
+        let recorder = AssertionRecorder::new("volume-parameter", volume.into(), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(default_time_domain().into(), network);
+        model.run_whole_horizon().unwrap();
+    }
+}
@@ -0,0 +1,362 @@
+use crate::network::Network;
+use crate::scenario::ScenarioIndex;
+use crate::solvers::builder::{BuiltSolver, RowSource};
+use crate::solvers::clp::solve_sparse_lp;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use chrono::NaiveDateTime;
+use libc::c_int;
+use std::fmt::{Debug, Display, Formatter};
+
+/// Violations of a constraint smaller than this are not reported.
+const VIOLATION_TOLERANCE: f64 = 1e-6;
+
+/// The kind of a constraint of the network that was violated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// The minimum and maximum flow of a node.
+    NodeFlow,
+    /// The minimum and maximum flow of an aggregated node.
+    AggregatedNodeFlow,
+    /// The flow factors of an aggregated node.
+    AggregatedNodeFactors,
+    /// The available and missing volume of a virtual storage node.
+    VirtualStorageVolume,
+    /// The minimum flow when on, or the status, of a node with switching constraints.
+    Switching,
+    /// The number of active nodes of an aggregated node with mutual exclusivity.
+    MutualExclusivity,
+}
+
+impl Display for ConstraintKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::NodeFlow => "flow of node",
+            Self::AggregatedNodeFlow => "flow of aggregated node",
+            Self::AggregatedNodeFactors => "flow factors of aggregated node",
+            Self::VirtualStorageVolume => "volume of virtual storage node",
+            Self::Switching => "switching constraints of node",
+            Self::MutualExclusivity => "mutual exclusivity of aggregated node",
+        };
+        write!(f, "{description}")
+    }
+}
+
+/// A constraint of the network that had to be violated to find a solution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintViolation {
+    pub kind: ConstraintKind,
+    pub name: String,
+    pub sub_name: Option<String>,
+    /// The lower bound of the constraint; negative infinity if it has no lower bound.
+    pub lower_bound: f64,
+    /// The upper bound of the constraint; infinity if it has no upper bound.
+    pub upper_bound: f64,
+    /// The value of the constrained quantity (e.g. the flow of a node) in the relaxed solution.
+    pub value: f64,
+}
+
+impl ConstraintViolation {
+    /// The amount by which the value is outside of the bounds.
+    pub fn amount(&self) -> f64 {
+        (self.lower_bound - self.value)
+            .max(self.value - self.upper_bound)
+            .max(0.0)
+    }
+}
+
+impl Display for ConstraintViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} \"{}\"", self.kind, self.name)?;
+        if let Some(sub_name) = &self.sub_name {
+            write!(f, " ({sub_name})")?;
+        }
+        write!(
+            f,
+            " violated by {}: the value {} is outside of the bounds [{}, {}]",
+            self.amount(),
+            self.value,
+            self.lower_bound,
+            self.upper_bound
+        )
+    }
+}
+
+/// The diagnosis of an infeasible time-step.
+///
+/// The LP of the time-step is solved again with elastic slack variables on each of the
+/// constraints of the network, minimising the total amount by which they are violated. The
+/// constraints that had to be violated are the likely cause of the infeasibility. The mass balance
+/// of the link nodes is never relaxed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InfeasibilityReport {
+    pub timestep_index: usize,
+    pub date: NaiveDateTime,
+    /// The index of the scenario, if it is known.
+    pub scenario_index: Option<usize>,
+    /// The violated constraints, with the largest violation first.
+    pub violations: Vec<ConstraintViolation>,
+}
+
+impl Display for InfeasibilityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "time-step {} ({})", self.timestep_index, self.date)?;
+        if let Some(scenario_index) = self.scenario_index {
+            write!(f, " of scenario {scenario_index}")?;
+        }
+
+        if self.violations.is_empty() {
+            write!(f, "; no constraint of the network had to be violated")
+        } else {
+            write!(f, "; the following constraints had to be violated:")?;
+            for violation in &self.violations {
+                write!(f, "\n  - {violation}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Add the index of the scenario to the report of an infeasible time-step.
+pub(crate) fn with_scenario_index(error: PywrError, scenario_index: &ScenarioIndex) -> PywrError {
+    match error {
+        PywrError::Infeasible(mut report) => {
+            report.scenario_index = Some(scenario_index.index);
+            PywrError::Infeasible(report)
+        }
+        error => error,
+    }
+}
+
+/// The builder uses the largest finite values for unbounded rows.
+fn bound_or_infinity(value: f64) -> f64 {
+    if value.abs() == f64::MAX {
+        value.signum() * f64::INFINITY
+    } else {
+        value
+    }
+}
+
+/// Diagnose an infeasible time-step whose LP has been updated in `built`.
+///
+/// Returns [`PywrError::Infeasible`] with the report of the diagnosis, or the error that
+/// prevented the diagnosis.
+pub(crate) fn diagnose_infeasibility<I>(network: &Network, timestep: &Timestep, built: &BuiltSolver<I>) -> PywrError
+where
+    I: num::PrimInt + Default + Debug + Copy,
+{
+    match find_violations(network, built) {
+        Ok(violations) => PywrError::Infeasible(Box::new(InfeasibilityReport {
+            timestep_index: timestep.index,
+            date: timestep.date,
+            scenario_index: None,
+            violations,
+        })),
+        Err(error) => error,
+    }
+}
+
+fn find_violations<I>(network: &Network, built: &BuiltSolver<I>) -> Result<Vec<ConstraintViolation>, PywrError>
+where
+    I: num::PrimInt + Default + Debug + Copy,
+{
+    let num_cols = built.num_cols().to_usize().unwrap();
+
+    // The coefficients that depend on the state are only updated in the solvers
+    let mut rows: Vec<Vec<(usize, f64)>> = built
+        .row_starts()
+        .windows(2)
+        .map(|w| {
+            let (start, end) = (w[0].to_usize().unwrap(), w[1].to_usize().unwrap());
+            built.columns()[start..end]
+                .iter()
+                .map(|c| c.to_usize().unwrap())
+                .zip(built.elements()[start..end].iter().copied())
+                .collect()
+        })
+        .collect();
+    for (row, column, value) in built.coefficients_to_update() {
+        let (row, column) = (row.to_usize().unwrap(), column.to_usize().unwrap());
+        if let Some(element) = rows[row].iter_mut().find(|(c, _)| *c == column) {
+            element.1 = *value;
+        }
+    }
+
+    // The original columns have no cost; the integer columns are relaxed
+    let mut col_lower = built.col_lower().to_vec();
+    let mut col_upper = built.col_upper().to_vec();
+    let mut col_obj_coef = vec![0.0; num_cols];
+
+    // Add a slack column below and above each elastic row
+    let mut elastic_rows = Vec::new();
+    for (row, elements) in rows.iter_mut().enumerate() {
+        let sources = built.row_sources(row);
+        if sources.iter().all(|s| matches!(s, RowSource::MassBalance(_))) {
+            continue;
+        }
+
+        for factor in [1.0, -1.0] {
+            elements.push((col_lower.len(), factor));
+            col_lower.push(0.0);
+            col_upper.push(f64::MAX);
+            col_obj_coef.push(1.0);
+        }
+        elastic_rows.push(row);
+    }
+
+    let mut row_starts: Vec<c_int> = vec![0];
+    let mut columns: Vec<c_int> = Vec::new();
+    let mut elements = Vec::new();
+    for row in rows.iter() {
+        for (column, value) in row {
+            columns.push(*column as c_int);
+            elements.push(*value);
+        }
+        row_starts.push(columns.len() as c_int);
+    }
+
+    let solution = solve_sparse_lp(
+        &col_lower,
+        &col_upper,
+        &col_obj_coef,
+        built.row_lower(),
+        built.row_upper(),
+        &row_starts,
+        &columns,
+        &elements,
+    )
+    .map_err(PywrError::InfeasibilityDiagnosisFailed)?;
+
+    let mut violations = Vec::new();
+    for row in elastic_rows {
+        // The value of the row excluding its slack columns
+        let value: f64 = rows[row]
+            .iter()
+            .filter(|(column, _)| *column < num_cols)
+            .map(|(column, factor)| solution[*column] * factor)
+            .sum();
+
+        let lower_bound = bound_or_infinity(built.row_lower()[row]);
+        let upper_bound = bound_or_infinity(built.row_upper()[row]);
+        if value >= lower_bound - VIOLATION_TOLERANCE && value <= upper_bound + VIOLATION_TOLERANCE {
+            continue;
+        }
+
+        for source in built.row_sources(row) {
+            let (kind, (name, sub_name)) = match source {
+                RowSource::MassBalance(_) => continue,
+                RowSource::NodeFlow(idx) => (ConstraintKind::NodeFlow, network.get_node(idx)?.full_name()),
+                RowSource::AggregatedNodeFlow(idx) => (
+                    ConstraintKind::AggregatedNodeFlow,
+                    network.get_aggregated_node(idx)?.full_name(),
+                ),
+                RowSource::AggregatedNodeFactors(idx) => (
+                    ConstraintKind::AggregatedNodeFactors,
+                    network.get_aggregated_node(idx)?.full_name(),
+                ),
+                RowSource::VirtualStorage(idx) => (
+                    ConstraintKind::VirtualStorageVolume,
+                    network.get_virtual_storage_node(idx)?.full_name(),
+                ),
+                RowSource::Switching(idx) => (ConstraintKind::Switching, network.get_node(idx)?.full_name()),
+                RowSource::MutualExclusivity(idx) => (
+                    ConstraintKind::MutualExclusivity,
+                    network.get_aggregated_node(idx)?.full_name(),
+                ),
+            };
+
+            violations.push(ConstraintViolation {
+                kind,
+                name: name.to_string(),
+                sub_name: sub_name.map(|s| s.to_string()),
+                lower_bound,
+                upper_bound,
+                value,
+            });
+        }
+    }
+
+    violations.sort_by(|a, b| b.amount().total_cmp(&a.amount()));
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConstraintKind, InfeasibilityReport};
+    use crate::models::Model;
+    use crate::network::Network;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::default_time_domain;
+    use crate::PywrError;
+
+    /// A demand with a minimum flow that the supply can not meet.
+    fn infeasible_model() -> Model {
+        let mut network = Network::default();
+        let supply = network.add_input_node("supply", None).unwrap();
+        let demand = network.add_output_node("demand", None).unwrap();
+        network.connect_nodes(supply, demand).unwrap();
+
+        network.set_node_max_flow("supply", None, Some(5.0.into())).unwrap();
+        network.set_node_min_flow("demand", None, Some(8.0.into())).unwrap();
+        network.set_node_max_flow("demand", None, Some(10.0.into())).unwrap();
+
+        Model::new(default_time_domain().into(), network)
+    }
+
+    /// Check the report of [`infeasible_model`] from a run with diagnosis enabled.
+    fn assert_report<T>(result: Result<T, PywrError>) {
+        let report: Box<InfeasibilityReport> = match result {
+            Err(PywrError::Infeasible(report)) => report,
+            other => panic!("Expected an infeasible time-step, found {:?}", other.err()),
+        };
+
+        assert_eq!(report.timestep_index, 0);
+        assert_eq!(report.scenario_index, Some(0));
+        assert_eq!(report.violations.len(), 1);
+
+        let violation = &report.violations[0];
+        assert!(matches!(violation.kind, ConstraintKind::NodeFlow));
+        assert!(violation.name == "supply" || violation.name == "demand");
+        assert!((violation.amount() - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_diagnose_infeasibility() {
+        let model = infeasible_model();
+        let settings = ClpSolverSettings::builder().diagnose_infeasibility().build();
+        assert_report(model.run::<ClpSolver>(&settings));
+    }
+
+    /// The report is returned, rather than a panic, when the scenarios are solved in parallel.
+    #[test]
+    fn test_diagnose_infeasibility_parallel() {
+        let model = infeasible_model();
+        let settings = ClpSolverSettings::builder()
+            .parallel()
+            .threads(2)
+            .diagnose_infeasibility()
+            .build();
+        assert_report(model.run::<ClpSolver>(&settings));
+    }
+
+    #[cfg(feature = "cbc")]
+    #[test]
+    fn test_diagnose_infeasibility_cbc() {
+        use crate::solvers::{CbcSolver, CbcSolverSettings};
+
+        let model = infeasible_model();
+        let settings = CbcSolverSettings::builder().diagnose_infeasibility().build();
+        assert_report(model.run::<CbcSolver>(&settings));
+    }
+
+    #[cfg(feature = "highs")]
+    #[test]
+    fn test_diagnose_infeasibility_highs() {
+        use crate::solvers::{HighsSolver, HighsSolverSettings};
+
+        let model = infeasible_model();
+        let settings = HighsSolverSettings::builder().diagnose_infeasibility().build();
+        assert_report(model.run::<HighsSolver>(&settings));
+    }
+}
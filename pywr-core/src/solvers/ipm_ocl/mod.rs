@@ -4,15 +4,16 @@ use crate::edge::EdgeIndex;
 use crate::network::Network;
 use crate::node::{Node, NodeBounds, NodeType};
 use crate::solvers::col_edge_map::{ColumnEdgeMap, ColumnEdgeMapBuilder};
-use crate::solvers::{MultiStateSolver, SolverFeatures, SolverTimings};
+use crate::solvers::{ClpSolver, ClpSolverSettings, MultiStateSolver, Solver, SolverFeatures, SolverTimings};
 use crate::state::State;
 use crate::timestep::Timestep;
 use crate::PywrError;
-use ipm_ocl::{GetClProgram, PathFollowingDirectClSolver};
+use ipm_ocl::{GetClProgram, LpReduction, PathFollowingDirectClSolver};
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::ParallelSliceMut;
-pub use settings::{ClIpmSolverSettings, ClIpmSolverSettingsBuilder};
+pub use settings::{ClIpmFallback, ClIpmSolverSettings, ClIpmSolverSettingsBuilder};
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::f64;
 use std::num::NonZeroUsize;
@@ -250,15 +251,52 @@ impl RowBuilder {
 
     /// Add an element to the row
     ///
-    /// If the column already exists `value` will be added to the existing coefficient.
-    fn add_element(&mut self, column: usize, value: f64) {
+    /// If the column already exists `value` will be added to the existing coefficient. An error
+    /// is returned if `value` is not finite.
+    fn add_element(&mut self, column: usize, value: f64) -> Result<(), PywrError> {
         if !value.is_finite() {
-            panic!("Row factor is non-finite.");
+            return Err(PywrError::NonFiniteRowFactor);
         }
         *self.columns.entry(column).or_insert(0.0) += value;
+        Ok(())
     }
 }
 
+/// Buffers that are reused to avoid allocating when the LPs are updated.
+///
+/// Each worker thread has its own buffers (see [`with_work_buffers`]), which are shared by
+/// every chunk of LPs that the thread updates, in every time-step.
+#[derive(Default)]
+struct WorkBuffers {
+    /// A value for each LP of the chunk.
+    values: Vec<f64>,
+    /// A second value for each LP of the chunk.
+    other_values: Vec<f64>,
+    row_upper_f32: Vec<f32>,
+    col_obj_coef_f32: Vec<f32>,
+}
+
+impl WorkBuffers {
+    /// The row upper bounds and objective coefficients of `lp` converted to `f32`.
+    fn f32_data(&mut self, lp: &Lp) -> (&[f32], &[f32]) {
+        self.row_upper_f32.clear();
+        self.row_upper_f32.extend(lp.row_upper.iter().map(|&v| v as f32));
+        self.col_obj_coef_f32.clear();
+        self.col_obj_coef_f32.extend(lp.col_obj_coef.iter().map(|&v| v as f32));
+
+        (&self.row_upper_f32, &self.col_obj_coef_f32)
+    }
+}
+
+thread_local! {
+    static WORK_BUFFERS: RefCell<WorkBuffers> = RefCell::new(WorkBuffers::default());
+}
+
+/// Call `f` with the work buffers of the current thread.
+fn with_work_buffers<R>(f: impl FnOnce(&mut WorkBuffers) -> R) -> R {
+    WORK_BUFFERS.with(|buffers| f(&mut buffers.borrow_mut()))
+}
+
 struct BuiltSolver {
     lp: Lp,
     col_edge_map: ColumnEdgeMap<usize>,
@@ -285,35 +323,40 @@ impl BuiltSolver {
         states: &[State],
         timings: &mut SolverTimings,
     ) -> Result<(), PywrError> {
-        let start_objective_update = Instant::now();
-        self.update_edge_objectives(network, states)?;
-        timings.update_objective += start_objective_update.elapsed();
+        with_work_buffers(|buffers| -> Result<(), PywrError> {
+            let start_objective_update = Instant::now();
+            self.update_edge_objectives(network, states, &mut buffers.values)?;
+            timings.update_objective += start_objective_update.elapsed();
 
-        let start_constraint_update = Instant::now();
+            let start_constraint_update = Instant::now();
 
-        self.lp.reset_row_bounds();
-        self.update_node_constraint_bounds(network, timestep, states)?;
-        // self.update_aggregated_node_constraint_bounds(network, state)?;
-        timings.update_constraints += start_constraint_update.elapsed();
+            self.lp.reset_row_bounds();
+            self.update_node_constraint_bounds(network, timestep, states, buffers)?;
+            // self.update_aggregated_node_constraint_bounds(network, state)?;
+            timings.update_constraints += start_constraint_update.elapsed();
 
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Update edge objective coefficients
-    fn update_edge_objectives(&mut self, network: &Network, states: &[State]) -> Result<(), PywrError> {
+    fn update_edge_objectives(
+        &mut self,
+        network: &Network,
+        states: &[State],
+        cost: &mut Vec<f64>,
+    ) -> Result<(), PywrError> {
         self.lp.zero_obj_coefficients();
         for edge in network.edges().deref() {
             // Collect all of the costs for all states together
-            let cost = states
-                .iter()
-                .map(|s| {
-                    edge.cost(&network.nodes(), network, s)
-                        .map(|c| if c != 0.0 { -c } else { 0.0 })
-                })
-                .collect::<Result<Vec<f64>, _>>()?;
+            cost.clear();
+            for s in states {
+                let c = edge.cost(&network.nodes(), network, s)?;
+                cost.push(if c != 0.0 { -c } else { 0.0 });
+            }
 
-            let col = self.col_for_edge(&edge.index());
-            self.lp.add_obj_coefficient(col, &cost);
+            let col = self.col_edge_map.col_for_edge(&edge.index());
+            self.lp.add_obj_coefficient(col, cost);
         }
         Ok(())
     }
@@ -324,41 +367,50 @@ impl BuiltSolver {
         network: &Network,
         timestep: &Timestep,
         states: &[State],
+        buffers: &mut WorkBuffers,
     ) -> Result<(), PywrError> {
         let mut row_ids = self.node_constraints_row_ids.iter();
+        let WorkBuffers {
+            values, other_values, ..
+        } = buffers;
 
         let dt = timestep.days();
 
         for node in network.nodes().deref() {
             match node.node_type() {
                 NodeType::Input | NodeType::Output | NodeType::Link => {
-                    if !node.is_max_flow_unconstrained().unwrap() {
+                    if !node.is_max_flow_unconstrained()? {
+                        // The row was scaled by the node's weight; the bounds must be too.
+                        let weight = node.get_solver_weight();
                         // Flow nodes will only respect the upper bounds
-                        let ub: Vec<f64> = states
-                            .iter()
-                            .map(|state| {
-                                // TODO check for non-zero lower bounds and error?
-                                match node.get_bounds(network, state).expect("Failed to get node bounds.") {
-                                    NodeBounds::Flow(bounds) => bounds.max_flow.min(B_MAX),
-                                    _ => panic!("Flow bounds expected for Input, Output and Link nodes."),
-                                }
-                            })
-                            .collect();
+                        let ub = &mut *values;
+                        ub.clear();
+                        for state in states {
+                            // TODO check for non-zero lower bounds and error?
+                            match node.get_bounds(network, state)? {
+                                NodeBounds::Flow(bounds) => ub.push((weight * bounds.max_flow).min(B_MAX)),
+                                _ => return Err(PywrError::FlowConstraintsUndefined),
+                            }
+                        }
                         // Apply the bounds to LP
                         self.lp.apply_row_bounds(*row_ids.next().unwrap(), ub.as_slice());
                     }
                 }
                 NodeType::Storage => {
+                    let weight = node.get_solver_weight();
                     // Storage nodes instead have two constraints for available and missing volume.
-                    let (avail, missing): (Vec<_>, Vec<_>) = states
-                        .iter()
-                        .map(
-                            |state| match node.get_bounds(network, state).expect("Failed to get node bounds.") {
-                                NodeBounds::Volume(bounds) => (bounds.available / dt, bounds.missing / dt),
-                                _ => panic!("Volume bounds expected for Storage nodes."),
-                            },
-                        )
-                        .unzip();
+                    let (avail, missing) = (&mut *values, &mut *other_values);
+                    avail.clear();
+                    missing.clear();
+                    for state in states {
+                        match node.get_bounds(network, state)? {
+                            NodeBounds::Volume(bounds) => {
+                                avail.push(weight * bounds.available / dt);
+                                missing.push(weight * bounds.missing / dt);
+                            }
+                            _ => return Err(PywrError::StorageConstraintsUndefined),
+                        }
+                    }
                     // Storage nodes add two rows the LP. First is the bounds on increase
                     // in volume. The second is the bounds on decrease in volume.
                     self.lp.apply_row_bounds(*row_ids.next().unwrap(), missing.as_slice());
@@ -398,9 +450,9 @@ impl SolverBuilder {
         self.create_columns(network)?;
 
         // Create edge mass balance constraints
-        self.create_mass_balance_constraints(network);
+        self.create_mass_balance_constraints(network)?;
         // Create the nodal constraints
-        let node_constraints_row_ids = self.create_node_constraints(network);
+        let node_constraints_row_ids = self.create_node_constraints(network)?;
         // // Create the aggregated node constraints
         // builder.create_aggregated_node_constraints(network);
         // // Create the aggregated node factor constraints
@@ -437,7 +489,9 @@ impl SolverBuilder {
                 let incoming_edges = from_node.get_incoming_edges()?;
                 // NB `edge` should be one of these outgoing edges
                 let outgoing_edges = from_node.get_outgoing_edges()?;
-                assert!(outgoing_edges.contains(&edge_index));
+                if !outgoing_edges.contains(&edge_index) {
+                    return Err(PywrError::EdgeNotOutgoingFromNode(from_node.name().to_string()));
+                }
                 if (incoming_edges.len() == 1) && (outgoing_edges.len() == 1) {
                     // Because of the mass-balance constraint these two edges must be equal to
                     // one another.
@@ -461,28 +515,28 @@ impl SolverBuilder {
     }
 
     /// Create mass balance constraints for each edge
-    fn create_mass_balance_constraints(&mut self, network: &Network) {
+    fn create_mass_balance_constraints(&mut self, network: &Network) -> Result<(), PywrError> {
         for node in network.nodes().deref() {
             // Only link nodes create mass-balance constraints
 
             if let NodeType::Link = node.node_type() {
                 let mut row = RowBuilder::fixed();
-                let incoming_edges = node.get_incoming_edges().unwrap();
-                let outgoing_edges = node.get_outgoing_edges().unwrap();
+                let incoming_edges = node.get_incoming_edges()?;
+                let outgoing_edges = node.get_outgoing_edges()?;
 
                 // TODO check for length >= 1
 
                 for edge in incoming_edges {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, 1.0);
+                    row.add_element(column, 1.0)?;
                 }
                 for edge in outgoing_edges {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, -1.0);
+                    row.add_element(column, -1.0)?;
                 }
 
                 if row.columns.is_empty() {
-                    panic!("Row contains no columns!")
+                    return Err(PywrError::MassBalanceRowHasNoColumns(node.name().to_string()));
                 } else if row.columns.len() == 1 {
                     // Skip this row because the edges must be mapped to the same column
                 } else {
@@ -490,56 +544,61 @@ impl SolverBuilder {
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn add_node(&mut self, node: &Node, factor: f64, row: &mut RowBuilder) {
+    fn add_node(&mut self, node: &Node, factor: f64, row: &mut RowBuilder) -> Result<(), PywrError> {
         match node.node_type() {
             NodeType::Link => {
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Input => {
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Output => {
-                for edge in node.get_incoming_edges().unwrap() {
+                for edge in node.get_incoming_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Storage => {
-                for edge in node.get_incoming_edges().unwrap() {
+                for edge in node.get_incoming_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, -factor);
+                    row.add_element(column, -factor)?;
                 }
             }
         }
+        Ok(())
     }
 
     /// Create node constraints
     ///
     /// One constraint is created per node to enforce any constraints (flow or storage)
-    /// that it may define.
-    fn create_node_constraints(&mut self, network: &Network) -> Vec<usize> {
+    /// that it may define. Each row is scaled by the node's solver weight so that its
+    /// residual counts proportionally more (or less) towards convergence.
+    fn create_node_constraints(&mut self, network: &Network) -> Result<Vec<usize>, PywrError> {
         let mut row_ids = Vec::with_capacity(network.nodes().len());
 
         for node in network.nodes().deref() {
+            let weight = node.get_solver_weight();
             match node.node_type() {
                 NodeType::Input | NodeType::Output | NodeType::Link => {
                     // Only create node constraints for nodes that could become constrained
-                    if !node.is_max_flow_unconstrained().unwrap() {
+                    if !node.is_max_flow_unconstrained()? {
                         // Create empty arrays to store the matrix data
                         let mut row = RowBuilder::upper();
-                        self.add_node(node, 1.0, &mut row);
+                        self.add_node(node, weight, &mut row)?;
 
                         let row_id = self.builder.add_row(row.clone()).unwrap();
                         row_ids.push(row_id);
@@ -548,7 +607,7 @@ impl SolverBuilder {
                 NodeType::Storage => {
                     // Storage nodes have a different type of constraint
                     let mut row = RowBuilder::upper();
-                    self.add_node(node, 1.0, &mut row);
+                    self.add_node(node, weight, &mut row)?;
                     let row_id = self.builder.add_row(row.clone()).unwrap();
                     row_ids.push(row_id);
 
@@ -559,16 +618,130 @@ impl SolverBuilder {
             }
         }
 
-        row_ids
+        Ok(row_ids)
+    }
+}
+
+/// Handle the LPs of a chunk of states that the IPM failed to converge.
+///
+/// Depending on `fallback` either an error is returned or the LPs are solved again with
+/// a [`ClpSolver`]. The Clp solver is created the first time that it is needed.
+fn solve_non_converged(
+    fallback: ClIpmFallback,
+    fallback_solver: &mut Option<Box<ClpSolver>>,
+    network: &Network,
+    timestep: &Timestep,
+    chunk_states: &mut [State],
+    non_converged: &[usize],
+) -> Result<(), PywrError> {
+    if non_converged.is_empty() {
+        return Ok(());
+    }
+
+    match fallback {
+        ClIpmFallback::Error => Err(PywrError::IpmNotConverged {
+            count: non_converged.len(),
+            total: chunk_states.len(),
+        }),
+        ClIpmFallback::Clp => {
+            for &i in non_converged {
+                let state = &mut chunk_states[i];
+                let solver = match fallback_solver {
+                    Some(solver) => solver,
+                    None => fallback_solver.insert(ClpSolver::setup(
+                        network,
+                        &state.get_const_parameter_values(),
+                        &ClpSolverSettings::default(),
+                    )?),
+                };
+                solver.solve(network, timestep, state)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The sum, minimum and maximum flow of each edge across all the scenarios of the last
+/// time-step, which are calculated on the device when the solver is configured with
+/// [`ClIpmSolverSettingsBuilder::reduce_on_device`].
+#[derive(Clone, Debug, Default)]
+pub struct EdgeFlowReductions {
+    num_scenarios: usize,
+    sum: Vec<f64>,
+    min: Vec<f64>,
+    max: Vec<f64>,
+}
+
+impl EdgeFlowReductions {
+    fn new(num_edges: usize) -> Self {
+        Self {
+            num_scenarios: 0,
+            sum: vec![0.0; num_edges],
+            min: vec![f64::INFINITY; num_edges],
+            max: vec![f64::NEG_INFINITY; num_edges],
+        }
+    }
+
+    /// Combine the reductions of a chunk of `num_scenarios` scenarios.
+    fn add_chunk<T>(&mut self, network: &Network, built: &BuiltSolver, num_scenarios: usize, reduction: &LpReduction<T>)
+    where
+        T: Copy + Into<f64>,
+    {
+        self.num_scenarios += num_scenarios;
+        for edge in network.edges().deref() {
+            let idx = *edge.index();
+            let col = built.col_for_edge(&edge.index());
+            self.sum[idx] += reduction.sum(col).into();
+            self.min[idx] = self.min[idx].min(reduction.min(col).into());
+            self.max[idx] = self.max[idx].max(reduction.max(col).into());
+        }
+    }
+
+    pub fn sum(&self, edge_index: EdgeIndex) -> f64 {
+        self.sum[*edge_index]
+    }
+
+    pub fn mean(&self, edge_index: EdgeIndex) -> f64 {
+        self.sum[*edge_index] / self.num_scenarios as f64
+    }
+
+    pub fn min(&self, edge_index: EdgeIndex) -> f64 {
+        self.min[*edge_index]
+    }
+
+    pub fn max(&self, edge_index: EdgeIndex) -> f64 {
+        self.max[*edge_index]
     }
 }
 
+/// Check that the edge flows of `network` can be reduced on the device.
+///
+/// The flows of the individual scenarios are not saved when they are reduced on the device, so
+/// any component that reads the flows or volumes of a scenario (e.g. a storage node, a
+/// parameter that depends on the state of the network or a recorder) would silently read zero.
+/// Only [`EdgeFlowReductions`] are available from a run with the reduction on the device.
+fn check_device_reduction(network: &Network) -> Result<(), PywrError> {
+    match network.flow_dependent_component() {
+        Some(component) => Err(PywrError::DeviceReductionUnsupported(format!(
+            "the network contains {component}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn ocl_error(e: ocl::Error) -> PywrError {
+    PywrError::OpenCl(e.to_string())
+}
+
 pub struct ClIpmF32Solver {
     built: Vec<BuiltSolver>,
     ipm: Vec<PathFollowingDirectClSolver<f32>>,
     chunk_size: NonZeroUsize,
     max_iterations: NonZeroUsize,
     queue: ocl::Queue,
+    fallback: ClIpmFallback,
+    fallback_solvers: Vec<Option<Box<ClpSolver>>>,
+    reductions: Option<EdgeFlowReductions>,
 }
 
 impl MultiStateSolver for ClIpmF32Solver {
@@ -583,17 +756,25 @@ impl MultiStateSolver for ClIpmF32Solver {
     }
 
     fn setup(network: &Network, num_scenarios: usize, settings: &Self::Settings) -> Result<Box<Self>, PywrError> {
-        let platform = ocl::Platform::default();
-        let device = ocl::Device::first(platform).expect("Failed to get OpenCL device.");
+        let reductions = if settings.reduce_on_device() {
+            check_device_reduction(network)?;
+            Some(EdgeFlowReductions::new(network.edges().len()))
+        } else {
+            None
+        };
+
+        let (platform, device) = settings
+            .device()
+            .select()
+            .map_err(|e| PywrError::OpenClDeviceNotFound(e.to_string()))?;
         let context = ocl::Context::builder()
             .platform(platform)
             .devices(device)
             .build()
-            .expect("Failed to create OpenCL context.");
+            .map_err(ocl_error)?;
 
-        let program =
-            f32::get_cl_program(&context, &device, &settings.tolerances()).expect("Failed to create OpenCL program.");
-        let queue = ocl::Queue::new(&context, device, None).expect("Failed to create OpenCL queue.");
+        let program = f32::get_cl_program(&context, &device, &settings.tolerances()).map_err(ocl_error)?;
+        let queue = ocl::Queue::new(&context, device, None).map_err(ocl_error)?;
 
         let mut built_solvers = Vec::new();
         let mut ipms = Vec::new();
@@ -609,7 +790,6 @@ impl MultiStateSolver for ClIpmF32Solver {
             let num_rows = matrix.row_starts.len() - 1;
             let num_cols = built.lp.num_cols;
 
-            // TODO handle the error better
             let ipm = PathFollowingDirectClSolver::from_data(
                 &queue,
                 &program,
@@ -621,18 +801,23 @@ impl MultiStateSolver for ClIpmF32Solver {
                 built.lp.inequality.nrows() as u32,
                 chunk_scenarios.len() as u32,
             )
-            .expect("Failed to create the OpenCL IPM solver from the given LP data.");
+            .map_err(ocl_error)?;
 
             built_solvers.push(built);
             ipms.push(ipm)
         }
 
+        let fallback_solvers = built_solvers.iter().map(|_| None).collect();
+
         Ok(Box::new(Self {
             built: built_solvers,
             ipm: ipms,
             chunk_size,
             max_iterations: settings.max_iterations(),
             queue,
+            fallback: settings.fallback(),
+            fallback_solvers,
+            reductions,
         }))
     }
 
@@ -642,6 +827,10 @@ impl MultiStateSolver for ClIpmF32Solver {
         timestep: &Timestep,
         states: &mut [State],
     ) -> Result<SolverTimings, PywrError> {
+        if self.reductions.is_some() {
+            return self.solve_reduced(network, timestep, states);
+        }
+
         // TODO complete the timings
         let timings = SolverTimings::default();
 
@@ -649,34 +838,110 @@ impl MultiStateSolver for ClIpmF32Solver {
             .par_chunks_mut(self.chunk_size.get())
             .zip(&mut self.built)
             .zip(&mut self.ipm)
-            .for_each(|((chunk_states, built), ipm)| {
+            .zip(&mut self.fallback_solvers)
+            .try_for_each(|(((chunk_states, built), ipm), fallback_solver)| {
                 let mut timings = SolverTimings::default();
 
-                built.update(network, timestep, chunk_states, &mut timings).unwrap();
+                built.update(network, timestep, chunk_states, &mut timings)?;
+
+                let non_converged = with_work_buffers(|buffers| -> Result<Vec<usize>, PywrError> {
+                    let now = Instant::now();
+                    let (row_upper, col_obj_coef) = buffers.f32_data(&built.lp);
+
+                    let solution = ipm
+                        .solve(&self.queue, row_upper, col_obj_coef, self.max_iterations)
+                        .map_err(ocl_error)?;
+                    timings.solve = now.elapsed();
+                    let non_converged = ipm.non_converged();
+
+                    let start_save_solution = Instant::now();
+                    let num_states = chunk_states.len();
+                    for (i, state) in chunk_states.iter_mut().enumerate() {
+                        let network_state = state.get_mut_network_state();
+                        network_state.reset();
+
+                        // The flows of the states that did not converge are handled below
+                        if non_converged.contains(&i) {
+                            continue;
+                        }
+
+                        for edge in network.edges().deref() {
+                            let col = built.col_for_edge(&edge.index());
+                            let flow = solution[col * num_states + i];
+                            network_state.add_flow(edge, timestep, flow as f64)?;
+                        }
+                    }
+                    timings.save_solution += start_save_solution.elapsed();
 
-                let now = Instant::now();
-                let row_upper: Vec<_> = built.row_upper().iter().map(|&v| v as f32).collect();
-                let col_obj_coef: Vec<_> = built.col_obj_coef().iter().map(|&v| v as f32).collect();
+                    Ok(non_converged)
+                })?;
 
-                let solution = ipm
-                    .solve(&self.queue, &row_upper, &col_obj_coef, self.max_iterations)
-                    .expect("Solve failed with the OpenCL IPM solver.");
-                timings.solve = now.elapsed();
+                solve_non_converged(
+                    self.fallback,
+                    fallback_solver,
+                    network,
+                    timestep,
+                    chunk_states,
+                    &non_converged,
+                )
+            })?;
 
-                let start_save_solution = Instant::now();
-                let num_states = chunk_states.len();
-                for (i, state) in chunk_states.iter_mut().enumerate() {
-                    let network_state = state.get_mut_network_state();
-                    network_state.reset();
+        Ok(timings)
+    }
+}
 
-                    for edge in network.edges().deref() {
-                        let col = built.col_for_edge(&edge.index());
-                        let flow = solution[col * num_states + i];
-                        network_state.add_flow(edge, timestep, flow as f64).unwrap();
-                    }
-                }
-                timings.save_solution += start_save_solution.elapsed();
-            });
+impl ClIpmF32Solver {
+    /// The sum, minimum and maximum flow of each edge across all the scenarios of the last
+    /// time-step. This is only available if the solver was configured to reduce the edge flows
+    /// on the device.
+    pub fn edge_flow_reductions(&self) -> Option<&EdgeFlowReductions> {
+        self.reductions.as_ref()
+    }
+
+    /// Solve the LPs and only copy the reduced edge flows back from the device.
+    fn solve_reduced(
+        &mut self,
+        network: &Network,
+        timestep: &Timestep,
+        states: &mut [State],
+    ) -> Result<SolverTimings, PywrError> {
+        let mut timings = SolverTimings::default();
+        let mut reductions = EdgeFlowReductions::new(network.edges().len());
+
+        for ((chunk_states, built), ipm) in states
+            .chunks_mut(self.chunk_size.get())
+            .zip(&mut self.built)
+            .zip(&mut self.ipm)
+        {
+            built.update(network, timestep, chunk_states, &mut timings)?;
+
+            with_work_buffers(|buffers| -> Result<(), PywrError> {
+                let now = Instant::now();
+                let (row_upper, col_obj_coef) = buffers.f32_data(&built.lp);
+
+                let reduction = ipm
+                    .solve_reduced(&self.queue, row_upper, col_obj_coef, self.max_iterations)
+                    .map_err(ocl_error)?;
+                reductions.add_chunk(network, built, chunk_states.len(), reduction);
+                timings.solve += now.elapsed();
+
+                Ok(())
+            })?;
+
+            let non_converged = ipm.non_converged();
+            if !non_converged.is_empty() {
+                return Err(PywrError::IpmNotConverged {
+                    count: non_converged.len(),
+                    total: chunk_states.len(),
+                });
+            }
+
+            for state in chunk_states.iter_mut() {
+                state.get_mut_network_state().reset();
+            }
+        }
+
+        self.reductions = Some(reductions);
 
         Ok(timings)
     }
@@ -688,6 +953,9 @@ pub struct ClIpmF64Solver {
     chunk_size: NonZeroUsize,
     max_iterations: NonZeroUsize,
     queues: Vec<ocl::Queue>,
+    fallback: ClIpmFallback,
+    fallback_solvers: Vec<Option<Box<ClpSolver>>>,
+    reductions: Option<EdgeFlowReductions>,
 }
 
 impl MultiStateSolver for ClIpmF64Solver {
@@ -702,16 +970,24 @@ impl MultiStateSolver for ClIpmF64Solver {
     }
 
     fn setup(network: &Network, num_scenarios: usize, settings: &Self::Settings) -> Result<Box<Self>, PywrError> {
-        let platform = ocl::Platform::default();
-        let device = ocl::Device::first(platform).expect("Failed to get OpenCL device.");
+        let reductions = if settings.reduce_on_device() {
+            check_device_reduction(network)?;
+            Some(EdgeFlowReductions::new(network.edges().len()))
+        } else {
+            None
+        };
+
+        let (platform, device) = settings
+            .device()
+            .select()
+            .map_err(|e| PywrError::OpenClDeviceNotFound(e.to_string()))?;
         let context = ocl::Context::builder()
             .platform(platform)
             .devices(device)
             .build()
-            .expect("Failed to create OpenCL context.");
+            .map_err(ocl_error)?;
 
-        let program =
-            f64::get_cl_program(&context, &device, &settings.tolerances()).expect("Failed to create OpenCL program.");
+        let program = f64::get_cl_program(&context, &device, &settings.tolerances()).map_err(ocl_error)?;
 
         let mut built_solvers = Vec::new();
         let mut ipms = Vec::new();
@@ -722,7 +998,7 @@ impl MultiStateSolver for ClIpmF64Solver {
 
         for chunk_scenarios in (0..num_scenarios).collect::<Vec<_>>().chunks(chunk_size.get()) {
             // Create a queue per chunk.
-            let queue = ocl::Queue::new(&context, device, None).expect("Failed to create OpenCL queue.");
+            let queue = ocl::Queue::new(&context, device, None).map_err(ocl_error)?;
 
             let builder = SolverBuilder::new(chunk_scenarios.len());
             let built = builder.create(network)?;
@@ -731,7 +1007,6 @@ impl MultiStateSolver for ClIpmF64Solver {
             let num_rows = matrix.row_starts.len() - 1;
             let num_cols = built.lp.num_cols;
 
-            // TODO handle the error better
             let ipm = PathFollowingDirectClSolver::from_data(
                 &queue,
                 &program,
@@ -743,19 +1018,24 @@ impl MultiStateSolver for ClIpmF64Solver {
                 built.lp.inequality.nrows() as u32,
                 chunk_scenarios.len() as u32,
             )
-            .expect("Failed to create the OpenCL IPM solver from the given LP data.");
+            .map_err(ocl_error)?;
 
             built_solvers.push(built);
             ipms.push(ipm);
             queues.push(queue);
         }
 
+        let fallback_solvers = built_solvers.iter().map(|_| None).collect();
+
         Ok(Box::new(Self {
             built: built_solvers,
             ipm: ipms,
             chunk_size,
             max_iterations: settings.max_iterations(),
             queues,
+            fallback: settings.fallback(),
+            fallback_solvers,
+            reductions,
         }))
     }
 
@@ -765,6 +1045,10 @@ impl MultiStateSolver for ClIpmF64Solver {
         timestep: &Timestep,
         states: &mut [State],
     ) -> Result<SolverTimings, PywrError> {
+        if self.reductions.is_some() {
+            return self.solve_reduced(network, timestep, states);
+        }
+
         // TODO complete the timings
         let timings = SolverTimings::default();
 
@@ -773,33 +1057,187 @@ impl MultiStateSolver for ClIpmF64Solver {
             .zip(&mut self.built)
             .zip(&mut self.ipm)
             .zip(&self.queues)
-            .for_each(|(((chunk_states, built), ipm), queue)| {
+            .zip(&mut self.fallback_solvers)
+            .try_for_each(|((((chunk_states, built), ipm), queue), fallback_solver)| {
                 let mut timings = SolverTimings::default();
 
-                built.update(network, timestep, chunk_states, &mut timings).unwrap();
+                built.update(network, timestep, chunk_states, &mut timings)?;
 
                 let now = Instant::now();
 
                 let solution = ipm
                     .solve(queue, built.row_upper(), built.col_obj_coef(), self.max_iterations)
-                    .expect("Solve failed with the OpenCL IPM solver.");
+                    .map_err(ocl_error)?;
                 timings.solve = now.elapsed();
 
                 let start_save_solution = Instant::now();
+                let non_converged = ipm.non_converged();
                 let num_states = chunk_states.len();
                 for (i, state) in chunk_states.iter_mut().enumerate() {
                     let network_state = state.get_mut_network_state();
                     network_state.reset();
 
+                    // The flows of the states that did not converge are handled below
+                    if non_converged.contains(&i) {
+                        continue;
+                    }
+
                     for edge in network.edges().deref() {
                         let col = built.col_for_edge(&edge.index());
                         let flow = solution[col * num_states + i];
-                        network_state.add_flow(edge, timestep, flow).unwrap();
+                        network_state.add_flow(edge, timestep, flow)?;
                     }
                 }
                 timings.save_solution += start_save_solution.elapsed();
-            });
+
+                solve_non_converged(
+                    self.fallback,
+                    fallback_solver,
+                    network,
+                    timestep,
+                    chunk_states,
+                    &non_converged,
+                )
+            })?;
+
+        Ok(timings)
+    }
+}
+
+impl ClIpmF64Solver {
+    /// The sum, minimum and maximum flow of each edge across all the scenarios of the last
+    /// time-step. This is only available if the solver was configured to reduce the edge flows
+    /// on the device.
+    pub fn edge_flow_reductions(&self) -> Option<&EdgeFlowReductions> {
+        self.reductions.as_ref()
+    }
+
+    /// Solve the LPs and only copy the reduced edge flows back from the device.
+    fn solve_reduced(
+        &mut self,
+        network: &Network,
+        timestep: &Timestep,
+        states: &mut [State],
+    ) -> Result<SolverTimings, PywrError> {
+        let mut timings = SolverTimings::default();
+        let mut reductions = EdgeFlowReductions::new(network.edges().len());
+
+        for (((chunk_states, built), ipm), queue) in states
+            .chunks_mut(self.chunk_size.get())
+            .zip(&mut self.built)
+            .zip(&mut self.ipm)
+            .zip(&self.queues)
+        {
+            built.update(network, timestep, chunk_states, &mut timings)?;
+
+            let now = Instant::now();
+            let reduction = ipm
+                .solve_reduced(queue, built.row_upper(), built.col_obj_coef(), self.max_iterations)
+                .map_err(ocl_error)?;
+            reductions.add_chunk(network, built, chunk_states.len(), reduction);
+            timings.solve += now.elapsed();
+
+            let non_converged = ipm.non_converged();
+            if !non_converged.is_empty() {
+                return Err(PywrError::IpmNotConverged {
+                    count: non_converged.len(),
+                    total: chunk_states.len(),
+                });
+            }
+
+            for state in chunk_states.iter_mut() {
+                state.get_mut_network_state().reset();
+            }
+        }
+
+        self.reductions = Some(reductions);
 
         Ok(timings)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{check_device_reduction, ClIpmF32Solver, ClIpmF64Solver, ClIpmFallback, ClIpmSolverSettingsBuilder};
+    use crate::metric::MetricF64;
+    use crate::network::Network;
+    use crate::node::StorageInitialVolume;
+    use crate::recorders::{AssertionRecorder, MetricSet, OutputMetric};
+    use crate::test_utils::simple_storage_model;
+    use crate::PywrError;
+    use ndarray::Array2;
+    use std::num::NonZeroUsize;
+
+    fn link_network() -> Network {
+        let mut network = Network::default();
+        let input = network.add_input_node("input", None).unwrap();
+        let link = network.add_link_node("link", None).unwrap();
+        let output = network.add_output_node("output", None).unwrap();
+        network.connect_nodes(input, link).unwrap();
+        network.connect_nodes(link, output).unwrap();
+        network
+    }
+
+    #[test]
+    fn test_check_device_reduction() {
+        let network = link_network();
+        assert!(check_device_reduction(&network).is_ok());
+    }
+
+    /// The flows of each scenario are not saved by the reduction, so components that would read
+    /// them are rejected rather than silently reading zero.
+    #[test]
+    fn test_check_device_reduction_flow_dependent() {
+        let mut network = link_network();
+        let link = network.get_node_index_by_name("link", None).unwrap();
+        let metric = OutputMetric::new("link", "inflow", "Link", None, MetricF64::NodeInFlow(link));
+        network
+            .add_metric_set(MetricSet::new("flows", None, vec![metric]))
+            .unwrap();
+
+        let result = check_device_reduction(&network);
+        assert!(matches!(result, Err(PywrError::DeviceReductionUnsupported(_))));
+    }
+
+    #[test]
+    fn test_check_device_reduction_storage() {
+        let mut network = link_network();
+        network
+            .add_storage_node(
+                "reservoir",
+                None,
+                StorageInitialVolume::Absolute(0.0),
+                None,
+                Some(100.0.into()),
+            )
+            .unwrap();
+
+        let result = check_device_reduction(&network);
+        assert!(matches!(result, Err(PywrError::DeviceReductionUnsupported(_))));
+    }
+
+    /// The storage volumes of the states that are solved by the Clp fallback must only be
+    /// changed by the Clp solution, and not also by the IPM's flows that did not converge.
+    #[test]
+    fn test_fallback_storage_volume() {
+        let mut model = simple_storage_model();
+        let idx = model.network().get_node_by_name("reservoir", None).unwrap().index();
+        // The demand of 10.0 is taken from the initial volume of 100.0 until it is empty
+        let expected = Array2::from_shape_fn((15, 1), |(i, _)| (100.0 - 10.0 * (i + 1) as f64).max(0.0));
+        let recorder = AssertionRecorder::new("reservoir-volume", MetricF64::NodeVolume(idx), expected, None, None);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        // A single iteration is not enough for any of the LPs to converge
+        let settings = ClIpmSolverSettingsBuilder::default()
+            .max_iterations(NonZeroUsize::new(1).unwrap())
+            .fallback(ClIpmFallback::Clp)
+            .build();
+
+        model
+            .run_multi_scenario::<ClIpmF32Solver>(&settings)
+            .expect("Failed to run model with the Clp fallback!");
+        model
+            .run_multi_scenario::<ClIpmF64Solver>(&settings)
+            .expect("Failed to run model with the Clp fallback!");
+    }
+}
@@ -1,7 +1,17 @@
 use crate::solvers::SolverSettings;
-use ipm_ocl::Tolerances;
+use ipm_ocl::{DeviceSelector, DeviceType, Tolerances};
 use std::num::NonZeroUsize;
 
+/// What to do with the LPs that the OpenCL IPM solver fails to converge.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ClIpmFallback {
+    /// Return an error and stop the run.
+    #[default]
+    Error,
+    /// Solve the LPs again using the CPU based Clp solver.
+    Clp,
+}
+
 /// Settings for the OpenCL IPM solvers.
 ///
 /// Create new settings using [`ClIpmSolverSettingsBuilder`] or use the default implementation;
@@ -12,6 +22,9 @@ pub struct ClIpmSolverSettings {
     num_chunks: NonZeroUsize,
     tolerances: Tolerances,
     max_iterations: NonZeroUsize,
+    device: DeviceSelector,
+    fallback: ClIpmFallback,
+    reduce_on_device: bool,
 }
 
 // Default implementation is a convenience that defers to the builder.
@@ -48,6 +61,18 @@ impl ClIpmSolverSettings {
     pub fn max_iterations(&self) -> NonZeroUsize {
         self.max_iterations
     }
+
+    pub fn device(&self) -> &DeviceSelector {
+        &self.device
+    }
+
+    pub fn fallback(&self) -> ClIpmFallback {
+        self.fallback
+    }
+
+    pub fn reduce_on_device(&self) -> bool {
+        self.reduce_on_device
+    }
 }
 
 /// Builder for [`ClIpmSolverSettings`].
@@ -74,6 +99,9 @@ pub struct ClIpmSolverSettingsBuilder {
     num_chunks: NonZeroUsize,
     tolerances: Tolerances,
     max_iterations: NonZeroUsize,
+    device: DeviceSelector,
+    fallback: ClIpmFallback,
+    reduce_on_device: bool,
 }
 
 impl Default for ClIpmSolverSettingsBuilder {
@@ -85,6 +113,9 @@ impl Default for ClIpmSolverSettingsBuilder {
             num_chunks: NonZeroUsize::new(4).unwrap(),
             tolerances: Tolerances::default(),
             max_iterations: NonZeroUsize::new(200).unwrap(),
+            device: DeviceSelector::default(),
+            fallback: ClIpmFallback::default(),
+            reduce_on_device: false,
         }
     }
 }
@@ -125,6 +156,46 @@ impl ClIpmSolverSettingsBuilder {
         self
     }
 
+    /// Select the OpenCL platform by its index in the list of platforms (see [`crate::solvers::list_cl_devices`]).
+    pub fn platform(mut self, platform: usize) -> Self {
+        self.device.platform = Some(platform);
+        self
+    }
+
+    /// Select the OpenCL device by its index in the list of the platform's devices.
+    ///
+    /// If a device type is also given this is the index within the devices of that type.
+    pub fn device(mut self, device: usize) -> Self {
+        self.device.device = Some(device);
+        self
+    }
+
+    /// Only consider OpenCL devices of the given type.
+    pub fn device_type(mut self, device_type: DeviceType) -> Self {
+        self.device.device_type = Some(device_type);
+        self
+    }
+
+    /// Set what to do with the LPs that fail to converge.
+    pub fn fallback(mut self, fallback: ClIpmFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Aggregate the edge flows across the scenarios on the device.
+    ///
+    /// Only the sum, minimum and maximum flow of each edge across all the scenarios is copied
+    /// back from the device (see [`crate::solvers::ClIpmF64Solver::edge_flow_reductions`]). The
+    /// flows of the individual scenarios are not saved to their states, so the setup of the
+    /// solver fails if the network has any component that reads them: storage or virtual storage
+    /// nodes, parameters that depend on the state of the network, derived metrics, metric sets,
+    /// recorders or state overrides. LPs that fail to converge always return an error in this
+    /// mode.
+    pub fn reduce_on_device(mut self) -> Self {
+        self.reduce_on_device = true;
+        self
+    }
+
     /// Construct a [`ClIpmSolverSettings`] from the builder.
     pub fn build(self) -> ClIpmSolverSettings {
         ClIpmSolverSettings {
@@ -133,14 +204,17 @@ impl ClIpmSolverSettingsBuilder {
             num_chunks: self.num_chunks,
             tolerances: self.tolerances,
             max_iterations: self.max_iterations,
+            device: self.device,
+            fallback: self.fallback,
+            reduce_on_device: self.reduce_on_device,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ClIpmSolverSettings, ClIpmSolverSettingsBuilder};
-    use ipm_ocl::Tolerances;
+    use super::{ClIpmFallback, ClIpmSolverSettings, ClIpmSolverSettingsBuilder};
+    use ipm_ocl::{DeviceSelector, DeviceType, Tolerances};
     use std::num::NonZeroUsize;
 
     #[test]
@@ -151,9 +225,34 @@ mod tests {
             num_chunks: NonZeroUsize::new(4).unwrap(),
             max_iterations: NonZeroUsize::new(200).unwrap(),
             tolerances: Tolerances::default(),
+            device: DeviceSelector::default(),
+            fallback: ClIpmFallback::Error,
+            reduce_on_device: false,
         };
         let settings_from_builder = ClIpmSolverSettingsBuilder::default().parallel().build();
 
         assert_eq!(settings, settings_from_builder);
     }
+
+    #[test]
+    fn builder_device_test() {
+        let settings = ClIpmSolverSettingsBuilder::default()
+            .platform(1)
+            .device(2)
+            .device_type(DeviceType::Gpu)
+            .fallback(ClIpmFallback::Clp)
+            .reduce_on_device()
+            .build();
+
+        assert_eq!(
+            settings.device(),
+            &DeviceSelector {
+                platform: Some(1),
+                device: Some(2),
+                device_type: Some(DeviceType::Gpu),
+            }
+        );
+        assert_eq!(settings.fallback(), ClIpmFallback::Clp);
+        assert!(settings.reduce_on_device());
+    }
 }
@@ -4,7 +4,7 @@ use crate::edge::EdgeIndex;
 use crate::network::Network;
 use crate::node::{Node, NodeBounds, NodeType};
 use crate::solvers::col_edge_map::{ColumnEdgeMap, ColumnEdgeMapBuilder};
-use crate::solvers::{MultiStateSolver, SolverFeatures, SolverTimings};
+use crate::solvers::{ClpSolver, ClpSolverSettings, MultiStateSolver, Solver, SolverFeatures, SolverTimings};
 use crate::state::State;
 use crate::timestep::Timestep;
 use crate::PywrError;
@@ -12,7 +12,8 @@ use ipm_simd::{PathFollowingDirectSimdSolver, Tolerances};
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::ParallelSliceMut;
-pub use settings::{SimdIpmSolverSettings, SimdIpmSolverSettingsBuilder};
+pub use settings::{SimdIpmFallback, SimdIpmSolverSettings, SimdIpmSolverSettingsBuilder};
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::num::NonZeroUsize;
 use std::ops::Deref;
@@ -83,9 +84,9 @@ where
             Simd::<f64, N>::from_slice(obj_coef)
         } else {
             // Pad the last entry to ensure it is the full width
-            let pad: Vec<_> = (0..N - obj_coef.len()).map(|_| *obj_coef.last().unwrap()).collect();
-            let values = [obj_coef, &pad].concat();
-            Simd::<f64, N>::from_slice(values.as_slice())
+            let mut values = [*obj_coef.last().unwrap(); N];
+            values[..obj_coef.len()].copy_from_slice(obj_coef);
+            Simd::<f64, N>::from_array(values)
         };
 
         self.col_obj_coef[col] += value;
@@ -107,9 +108,9 @@ where
             Simd::<f64, N>::from_slice(ub)
         } else {
             // Pad the last entry to ensure it is the full width
-            let pad: Vec<_> = (0..N - ub.len()).map(|_| *ub.last().unwrap()).collect();
-            let values = [ub, &pad].concat();
-            Simd::<f64, N>::from_slice(values.as_slice())
+            let mut values = [*ub.last().unwrap(); N];
+            values[..ub.len()].copy_from_slice(ub);
+            Simd::<f64, N>::from_array(values)
         };
 
         self.row_upper[row] = self.row_upper[row].simd_min(value);
@@ -273,15 +274,38 @@ impl RowBuilder {
 
     /// Add an element to the row
     ///
-    /// If the column already exists `value` will be added to the existing coefficient.
-    fn add_element(&mut self, column: usize, value: f64) {
+    /// If the column already exists `value` will be added to the existing coefficient. An error
+    /// is returned if `value` is not finite.
+    fn add_element(&mut self, column: usize, value: f64) -> Result<(), PywrError> {
         if !value.is_finite() {
-            panic!("Row factor is non-finite.");
+            return Err(PywrError::NonFiniteRowFactor);
         }
         *self.columns.entry(column).or_insert(0.0) += value;
+        Ok(())
     }
 }
 
+/// Buffers that are reused to avoid allocating when the LPs are updated.
+///
+/// Each worker thread has its own buffers (see [`with_work_buffers`]), which are shared by
+/// every chunk of LPs that the thread updates, in every time-step.
+#[derive(Default)]
+struct WorkBuffers {
+    /// A value for each LP of the chunk.
+    values: Vec<f64>,
+    /// A second value for each LP of the chunk.
+    other_values: Vec<f64>,
+}
+
+thread_local! {
+    static WORK_BUFFERS: RefCell<WorkBuffers> = RefCell::new(WorkBuffers::default());
+}
+
+/// Call `f` with the work buffers of the current thread.
+fn with_work_buffers<R>(f: impl FnOnce(&mut WorkBuffers) -> R) -> R {
+    WORK_BUFFERS.with(|buffers| f(&mut buffers.borrow_mut()))
+}
+
 struct BuiltSolver<const N: usize>
 where
     LaneCount<N>: SupportedLaneCount,
@@ -314,35 +338,40 @@ where
         states: &[State],
         timings: &mut SolverTimings,
     ) -> Result<(), PywrError> {
-        let start_objective_update = Instant::now();
-        self.update_edge_objectives(network, states)?;
-        timings.update_objective += start_objective_update.elapsed();
+        with_work_buffers(|buffers| -> Result<(), PywrError> {
+            let start_objective_update = Instant::now();
+            self.update_edge_objectives(network, states, &mut buffers.values)?;
+            timings.update_objective += start_objective_update.elapsed();
 
-        let start_constraint_update = Instant::now();
+            let start_constraint_update = Instant::now();
 
-        self.lp.reset_row_bounds();
-        self.update_node_constraint_bounds(network, timestep, states)?;
-        // self.update_aggregated_node_constraint_bounds(network, state)?;
-        timings.update_constraints += start_constraint_update.elapsed();
+            self.lp.reset_row_bounds();
+            self.update_node_constraint_bounds(network, timestep, states, buffers)?;
+            // self.update_aggregated_node_constraint_bounds(network, state)?;
+            timings.update_constraints += start_constraint_update.elapsed();
 
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Update edge objective coefficients
-    fn update_edge_objectives(&mut self, network: &Network, states: &[State]) -> Result<(), PywrError> {
+    fn update_edge_objectives(
+        &mut self,
+        network: &Network,
+        states: &[State],
+        cost: &mut Vec<f64>,
+    ) -> Result<(), PywrError> {
         self.lp.zero_obj_coefficients();
         for edge in network.edges().deref() {
             // Collect all of the costs for all states together
-            let cost = states
-                .iter()
-                .map(|s| {
-                    edge.cost(&network.nodes(), network, s)
-                        .map(|c| if c != 0.0 { -c } else { 0.0 })
-                })
-                .collect::<Result<Vec<f64>, _>>()?;
-
-            let col = self.col_for_edge(&edge.index());
-            self.lp.add_obj_coefficient(col, &cost);
+            cost.clear();
+            for s in states {
+                let c = edge.cost(&network.nodes(), network, s)?;
+                cost.push(if c != 0.0 { -c } else { 0.0 });
+            }
+
+            let col = self.col_edge_map.col_for_edge(&edge.index());
+            self.lp.add_obj_coefficient(col, cost);
         }
         Ok(())
     }
@@ -353,41 +382,48 @@ where
         network: &Network,
         timestep: &Timestep,
         states: &[State],
+        buffers: &mut WorkBuffers,
     ) -> Result<(), PywrError> {
         let mut row_ids = self.node_constraints_row_ids.iter();
+        let WorkBuffers { values, other_values } = buffers;
 
         let dt = timestep.days();
 
         for node in network.nodes().deref() {
             match node.node_type() {
                 NodeType::Input | NodeType::Output | NodeType::Link => {
-                    if !node.is_max_flow_unconstrained().unwrap() {
+                    if !node.is_max_flow_unconstrained()? {
+                        // The row was scaled by the node's weight; the bounds must be too.
+                        let weight = node.get_solver_weight();
                         // Flow nodes will only respect the upper bounds
-                        let ub: Vec<f64> = states
-                            .iter()
-                            .map(|state| {
-                                // TODO check for non-zero lower bounds and error?
-                                match node.get_bounds(network, state).expect("Failed to get node bounds.") {
-                                    NodeBounds::Flow(bounds) => bounds.max_flow.min(B_MAX),
-                                    _ => panic!("Flow bounds expected for Input, Output and Link nodes."),
-                                }
-                            })
-                            .collect();
+                        let ub = &mut *values;
+                        ub.clear();
+                        for state in states {
+                            // TODO check for non-zero lower bounds and error?
+                            match node.get_bounds(network, state)? {
+                                NodeBounds::Flow(bounds) => ub.push((weight * bounds.max_flow).min(B_MAX)),
+                                _ => return Err(PywrError::FlowConstraintsUndefined),
+                            }
+                        }
                         // Apply the bounds to LP
                         self.lp.apply_row_bounds(*row_ids.next().unwrap(), ub.as_slice());
                     }
                 }
                 NodeType::Storage => {
+                    let weight = node.get_solver_weight();
                     // Storage nodes instead have two constraints for available and missing volume.
-                    let (avail, missing): (Vec<_>, Vec<_>) = states
-                        .iter()
-                        .map(
-                            |state| match node.get_bounds(network, state).expect("Failed to get node bounds.") {
-                                NodeBounds::Volume(bounds) => (bounds.available / dt, bounds.missing / dt),
-                                _ => panic!("Volume bounds expected for Storage nodes."),
-                            },
-                        )
-                        .unzip();
+                    let (avail, missing) = (&mut *values, &mut *other_values);
+                    avail.clear();
+                    missing.clear();
+                    for state in states {
+                        match node.get_bounds(network, state)? {
+                            NodeBounds::Volume(bounds) => {
+                                avail.push(weight * bounds.available / dt);
+                                missing.push(weight * bounds.missing / dt);
+                            }
+                            _ => return Err(PywrError::StorageConstraintsUndefined),
+                        }
+                    }
                     // Storage nodes add two rows the LP. First is the bounds on increase
                     // in volume. The second is the bounds on decrease in volume.
                     self.lp.apply_row_bounds(*row_ids.next().unwrap(), missing.as_slice());
@@ -430,9 +466,9 @@ impl SolverBuilder {
         self.create_columns(network)?;
 
         // Create edge mass balance constraints
-        self.create_mass_balance_constraints(network);
+        self.create_mass_balance_constraints(network)?;
         // Create the nodal constraints
-        let node_constraints_row_ids = self.create_node_constraints(network);
+        let node_constraints_row_ids = self.create_node_constraints(network)?;
         // // Create the aggregated node constraints
         // builder.create_aggregated_node_constraints(network);
         // // Create the aggregated node factor constraints
@@ -469,7 +505,9 @@ impl SolverBuilder {
                 let incoming_edges = from_node.get_incoming_edges()?;
                 // NB `edge` should be one of these outgoing edges
                 let outgoing_edges = from_node.get_outgoing_edges()?;
-                assert!(outgoing_edges.contains(&edge_index));
+                if !outgoing_edges.contains(&edge_index) {
+                    return Err(PywrError::EdgeNotOutgoingFromNode(from_node.name().to_string()));
+                }
                 if (incoming_edges.len() == 1) && (outgoing_edges.len() == 1) {
                     // Because of the mass-balance constraint these two edges must be equal to
                     // one another.
@@ -493,28 +531,28 @@ impl SolverBuilder {
     }
 
     /// Create mass balance constraints for each edge
-    fn create_mass_balance_constraints(&mut self, network: &Network) {
+    fn create_mass_balance_constraints(&mut self, network: &Network) -> Result<(), PywrError> {
         for node in network.nodes().deref() {
             // Only link nodes create mass-balance constraints
 
             if let NodeType::Link = node.node_type() {
                 let mut row = RowBuilder::fixed();
-                let incoming_edges = node.get_incoming_edges().unwrap();
-                let outgoing_edges = node.get_outgoing_edges().unwrap();
+                let incoming_edges = node.get_incoming_edges()?;
+                let outgoing_edges = node.get_outgoing_edges()?;
 
                 // TODO check for length >= 1
 
                 for edge in incoming_edges {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, 1.0);
+                    row.add_element(column, 1.0)?;
                 }
                 for edge in outgoing_edges {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, -1.0);
+                    row.add_element(column, -1.0)?;
                 }
 
                 if row.columns.is_empty() {
-                    panic!("Row contains no columns!")
+                    return Err(PywrError::MassBalanceRowHasNoColumns(node.name().to_string()));
                 } else if row.columns.len() == 1 {
                     // Skip this row because the edges must be mapped to the same column
                 } else {
@@ -522,56 +560,61 @@ impl SolverBuilder {
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn add_node(&mut self, node: &Node, factor: f64, row: &mut RowBuilder) {
+    fn add_node(&mut self, node: &Node, factor: f64, row: &mut RowBuilder) -> Result<(), PywrError> {
         match node.node_type() {
             NodeType::Link => {
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Input => {
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Output => {
-                for edge in node.get_incoming_edges().unwrap() {
+                for edge in node.get_incoming_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
             }
             NodeType::Storage => {
-                for edge in node.get_incoming_edges().unwrap() {
+                for edge in node.get_incoming_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, factor);
+                    row.add_element(column, factor)?;
                 }
-                for edge in node.get_outgoing_edges().unwrap() {
+                for edge in node.get_outgoing_edges()? {
                     let column = self.col_for_edge(edge);
-                    row.add_element(column, -factor);
+                    row.add_element(column, -factor)?;
                 }
             }
         }
+        Ok(())
     }
 
     /// Create node constraints
     ///
     /// One constraint is created per node to enforce any constraints (flow or storage)
-    /// that it may define.
-    fn create_node_constraints(&mut self, network: &Network) -> Vec<usize> {
+    /// that it may define. Each row is scaled by the node's solver weight so that its
+    /// residual counts proportionally more (or less) towards convergence.
+    fn create_node_constraints(&mut self, network: &Network) -> Result<Vec<usize>, PywrError> {
         let mut row_ids = Vec::with_capacity(network.nodes().len());
 
         for node in network.nodes().deref() {
+            let weight = node.get_solver_weight();
             match node.node_type() {
                 NodeType::Input | NodeType::Output | NodeType::Link => {
                     // Only create node constraints for nodes that could become constrained
-                    if !node.is_max_flow_unconstrained().unwrap() {
+                    if !node.is_max_flow_unconstrained()? {
                         // Create empty arrays to store the matrix data
                         let mut row = RowBuilder::upper();
-                        self.add_node(node, 1.0, &mut row);
+                        self.add_node(node, weight, &mut row)?;
 
                         let row_id = self.builder.add_row(row.clone()).unwrap();
                         row_ids.push(row_id);
@@ -580,7 +623,7 @@ impl SolverBuilder {
                 NodeType::Storage => {
                     // Storage nodes have a different type of constraint
                     let mut row = RowBuilder::upper();
-                    self.add_node(node, 1.0, &mut row);
+                    self.add_node(node, weight, &mut row)?;
                     let row_id = self.builder.add_row(row.clone()).unwrap();
                     row_ids.push(row_id);
 
@@ -591,7 +634,46 @@ impl SolverBuilder {
             }
         }
 
-        row_ids
+        Ok(row_ids)
+    }
+}
+
+/// Handle the LPs of a chunk of states that the IPM failed to converge.
+///
+/// Depending on `fallback` either an error is returned or the LPs are solved again with
+/// a [`ClpSolver`]. The Clp solver is created the first time that it is needed.
+fn solve_failed_lanes(
+    fallback: SimdIpmFallback,
+    fallback_solver: &mut Option<Box<ClpSolver>>,
+    network: &Network,
+    timestep: &Timestep,
+    chunk_states: &mut [State],
+    failed: &[usize],
+) -> Result<(), PywrError> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    match fallback {
+        SimdIpmFallback::Error => Err(PywrError::IpmNotConverged {
+            count: failed.len(),
+            total: chunk_states.len(),
+        }),
+        SimdIpmFallback::Clp => {
+            for &i in failed {
+                let state = &mut chunk_states[i];
+                let solver = match fallback_solver {
+                    Some(solver) => solver,
+                    None => fallback_solver.insert(ClpSolver::setup(
+                        network,
+                        &state.get_const_parameter_values(),
+                        &ClpSolverSettings::default(),
+                    )?),
+                };
+                solver.solve(network, timestep, state)?;
+            }
+            Ok(())
+        }
     }
 }
 
@@ -603,6 +685,9 @@ where
     ipm: Vec<PathFollowingDirectSimdSolver<f64, N>>,
     tolerances: Tolerances<f64, N>,
     max_iterations: NonZeroUsize,
+    max_restarts: usize,
+    fallback: SimdIpmFallback,
+    fallback_solvers: Vec<Option<Box<ClpSolver>>>,
 }
 
 impl<const N: usize> MultiStateSolver for SimdIpmF64Solver<N>
@@ -638,17 +723,23 @@ where
                 matrix.columns,
                 matrix.elements,
                 built.lp.inequality.nrows(),
-            );
+            )
+            .map_err(|err| PywrError::IpmSetup(err.to_string()))?;
 
             built_solvers.push(built);
             ipms.push(ipm)
         }
 
+        let fallback_solvers = built_solvers.iter().map(|_| None).collect();
+
         Ok(Box::new(Self {
             built: built_solvers,
             ipm: ipms,
             tolerances: settings.tolerances(),
             max_iterations: settings.max_iterations(),
+            max_restarts: settings.max_restarts(),
+            fallback: settings.fallback(),
+            fallback_solvers,
         }))
     }
 
@@ -666,10 +757,11 @@ where
             .par_chunks_mut(N)
             .zip(&mut self.built)
             .zip(&mut self.ipm)
-            .for_each(|((chunk_states, built), ipm)| {
+            .zip(&mut self.fallback_solvers)
+            .try_for_each(|(((chunk_states, built), ipm), fallback_solver)| {
                 let mut timings = SolverTimings::default();
 
-                built.update(network, timestep, chunk_states, &mut timings).unwrap();
+                built.update(network, timestep, chunk_states, &mut timings)?;
 
                 let now = Instant::now();
 
@@ -678,6 +770,7 @@ where
                     built.col_obj_coef(),
                     &self.tolerances,
                     self.max_iterations,
+                    self.max_restarts,
                 );
 
                 timings.solve = now.elapsed();
@@ -691,22 +784,67 @@ where
 
                 for edge in network.edges().deref() {
                     let col = built.col_for_edge(&edge.index());
-                    let flows = solution[col];
-
-                    for (state, flow) in chunk_states.iter_mut().zip(flows.as_array()) {
-                        if !flow.is_finite() {
-                            panic!(
-                                "Non-finite flow encountered from solver. Edge: {:#?}, value: {}",
-                                edge, flow
-                            )
+                    let flows = solution.x()[col];
+
+                    for ((state, flow), converged) in
+                        chunk_states.iter_mut().zip(flows.as_array()).zip(solution.converged())
+                    {
+                        // The flows of the lanes that did not converge, including any with
+                        // non-finite flows, are handled below
+                        if !converged {
+                            continue;
                         }
-                        state.get_mut_network_state().add_flow(edge, timestep, *flow).unwrap();
+                        state.get_mut_network_state().add_flow(edge, timestep, *flow)?;
                     }
                 }
 
                 timings.save_solution += start_save_solution.elapsed();
-            });
+
+                let failed: Vec<usize> = solution
+                    .failed_lanes()
+                    .into_iter()
+                    .filter(|&i| i < chunk_states.len())
+                    .collect();
+
+                solve_failed_lanes(self.fallback, fallback_solver, network, timestep, chunk_states, &failed)
+            })?;
 
         Ok(timings)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{SimdIpmF64Solver, SimdIpmFallback, SimdIpmSolverSettingsBuilder};
+    use crate::metric::MetricF64;
+    use crate::recorders::AssertionRecorder;
+    use crate::test_utils::simple_model;
+    use crate::PywrError;
+    use ndarray::Array;
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn test_fallback() {
+        let mut model = simple_model(4, None);
+        let idx = model.network().get_node_by_name("output", None).unwrap().index();
+        let expected = Array::from_shape_fn((366, 4), |(i, j)| (1.0 + i as f64 + j as f64).min(12.0));
+        let recorder = AssertionRecorder::new("output-flow", MetricF64::NodeInFlow(idx), expected, None, None);
+        model.network_mut().add_recorder(Box::new(recorder)).unwrap();
+
+        // A single iteration is not enough for any of the LPs to converge ...
+        let settings = SimdIpmSolverSettingsBuilder::default()
+            .max_iterations(NonZeroUsize::new(1).unwrap())
+            .build();
+        let result = model.run_multi_scenario::<SimdIpmF64Solver<4>>(&settings);
+        assert!(matches!(result, Err(PywrError::IpmNotConverged { count: 4, total: 4 })));
+
+        // ... but they are solved by Clp instead
+        let settings = SimdIpmSolverSettingsBuilder::default()
+            .max_iterations(NonZeroUsize::new(1).unwrap())
+            .fallback(SimdIpmFallback::Clp)
+            .build();
+        model
+            .run_multi_scenario::<SimdIpmF64Solver<4>>(&settings)
+            .expect("Failed to run model with the Clp fallback!");
+    }
+}
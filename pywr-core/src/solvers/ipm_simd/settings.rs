@@ -3,6 +3,16 @@ use ipm_simd::Tolerances;
 use std::num::NonZeroUsize;
 use std::simd::{LaneCount, Simd, SimdElement, SupportedLaneCount};
 
+/// What to do with the LPs that the SIMD IPM solver fails to converge.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SimdIpmFallback {
+    /// Return an error and stop the run.
+    #[default]
+    Error,
+    /// Solve the LPs again using the Clp solver.
+    Clp,
+}
+
 /// Settings for the OpenCL IPM solvers.
 ///
 /// Create new settings using [`SimdIpmSolverSettingsBuilder`] or use the default implementation;
@@ -16,6 +26,8 @@ where
     threads: usize,
     tolerances: Tolerances<T, N>,
     max_iterations: NonZeroUsize,
+    max_restarts: usize,
+    fallback: SimdIpmFallback,
 }
 
 // Default implementation is a convenience that defers to the builder.
@@ -60,6 +72,14 @@ where
     pub fn max_iterations(&self) -> NonZeroUsize {
         self.max_iterations
     }
+
+    pub fn max_restarts(&self) -> usize {
+        self.max_restarts
+    }
+
+    pub fn fallback(&self) -> SimdIpmFallback {
+        self.fallback
+    }
 }
 
 /// Builder for [`SimdIpmSolverSettings`].
@@ -91,6 +111,8 @@ where
     threads: usize,
     tolerances: Tolerances<T, N>,
     max_iterations: NonZeroUsize,
+    max_restarts: usize,
+    fallback: SimdIpmFallback,
 }
 
 impl<T, const N: usize> Default for SimdIpmSolverSettingsBuilder<T, N>
@@ -105,6 +127,8 @@ where
             tolerances: Tolerances::default(),
             // Unwrap is safe as the value is non-zero!
             max_iterations: NonZeroUsize::new(200).unwrap(),
+            max_restarts: 3,
+            fallback: SimdIpmFallback::default(),
         }
     }
 }
@@ -143,6 +167,19 @@ where
         self.max_iterations = max_iterations;
         self
     }
+
+    /// Set the number of times that a diverged LP is restarted with a smaller step.
+    pub fn max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    /// Set what to do with the LPs that fail to converge.
+    pub fn fallback(mut self, fallback: SimdIpmFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
     /// Construct a [`SimdIpmSolverSettings`] from the builder.
     pub fn build(self) -> SimdIpmSolverSettings<T, N> {
         SimdIpmSolverSettings {
@@ -150,13 +187,15 @@ where
             threads: self.threads,
             tolerances: self.tolerances,
             max_iterations: self.max_iterations,
+            max_restarts: self.max_restarts,
+            fallback: self.fallback,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{SimdIpmSolverSettings, SimdIpmSolverSettingsBuilder};
+    use super::{SimdIpmFallback, SimdIpmSolverSettings, SimdIpmSolverSettingsBuilder};
     use ipm_simd::Tolerances;
     use std::num::NonZeroUsize;
 
@@ -167,6 +206,8 @@ mod tests {
             threads: 0,
             tolerances: Tolerances::default(),
             max_iterations: NonZeroUsize::new(200).unwrap(),
+            max_restarts: 3,
+            fallback: SimdIpmFallback::Error,
         };
         let settings_from_builder = SimdIpmSolverSettingsBuilder::<f64, 4>::default().parallel().build();
 
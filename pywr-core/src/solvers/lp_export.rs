@@ -0,0 +1,616 @@
+use crate::network::Network;
+use crate::node::Node;
+use crate::scenario::ScenarioIndex;
+use crate::solvers::builder::{BuiltSolver, ColType, SolverBuilder};
+use crate::solvers::SolverTimings;
+use crate::state::State;
+use crate::timestep::Timestep;
+use crate::PywrError;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The number of terms written on each line of an expression in the LP format.
+const LP_TERMS_PER_LINE: usize = 8;
+
+/// The file format of an exported LP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LpFileFormat {
+    /// The free MPS format.
+    Mps,
+    /// The CPLEX LP format.
+    Lp,
+}
+
+impl LpFileFormat {
+    /// The format implied by the extension of `path` (`.mps` or `.lp`), if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "mps" => Some(Self::Mps),
+            "lp" => Some(Self::Lp),
+            _ => None,
+        }
+    }
+}
+
+/// A request to write the LP of a single time-step of a single scenario to a file.
+///
+/// The LP is written as it is given to the solver, after the parameters have been computed for
+/// the time-step and before it is solved. It is built with the LP builder shared by the solvers,
+/// so the file is the same whichever solver runs the model, and can be used to inspect the
+/// problem (e.g. to find the cause of an infeasibility) with external tools. The columns are
+/// named `C0`, `C1`, ... and the rows `R0`, `R1`, ...; the edges of each column are listed in
+/// the comments at the start of the file.
+///
+/// In a multi-network model the LP of each network is written to its own file, named by
+/// appending the name of the network to the file name of `path` (e.g. `model-upstream.mps`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LpExport {
+    path: PathBuf,
+    format: LpFileFormat,
+    timestep: usize,
+    scenario: usize,
+}
+
+impl LpExport {
+    /// Write the LP of the time-step with index `timestep` of the first scenario to `path`.
+    pub fn new<P: Into<PathBuf>>(path: P, format: LpFileFormat, timestep: usize) -> Self {
+        Self {
+            path: path.into(),
+            format,
+            timestep,
+            scenario: 0,
+        }
+    }
+
+    /// Write the LP of the scenario with index `scenario` in the run.
+    pub fn with_scenario(mut self, scenario: usize) -> Self {
+        self.scenario = scenario;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> LpFileFormat {
+        self.format
+    }
+
+    pub fn timestep(&self) -> usize {
+        self.timestep
+    }
+
+    pub fn scenario(&self) -> usize {
+        self.scenario
+    }
+
+    /// The export of the LP of the network with `name` in a multi-network model.
+    pub(crate) fn for_network(&self, name: &str) -> Self {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let file_name = match self.path.extension() {
+            Some(extension) => format!("{stem}-{name}.{}", extension.to_string_lossy()),
+            None => format!("{stem}-{name}"),
+        };
+
+        Self {
+            path: self.path.with_file_name(file_name),
+            ..self.clone()
+        }
+    }
+
+    /// Check that the time-step and scenario are in a run of the given size.
+    pub(crate) fn check(&self, num_timesteps: usize, num_scenarios: usize) -> Result<(), PywrError> {
+        let invalid = |reason: String| PywrError::LpExportError(self.path.clone(), reason);
+
+        if self.timestep >= num_timesteps {
+            return Err(invalid(format!(
+                "time-step {} is not in the run of {num_timesteps} time-steps",
+                self.timestep
+            )));
+        }
+        if self.scenario >= num_scenarios {
+            return Err(invalid(format!(
+                "scenario {} is not in the run of {num_scenarios} scenarios",
+                self.scenario
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_requested(&self, timestep: &Timestep, scenario_index: &ScenarioIndex) -> bool {
+        self.timestep == timestep.index && self.scenario == scenario_index.index
+    }
+}
+
+/// Build the LP of `state` in `timestep` and write it to the file of `export`.
+pub(crate) fn export_lp(
+    network: &Network,
+    timestep: &Timestep,
+    state: &State,
+    export: &LpExport,
+    tie_breaking: Option<f64>,
+) -> Result<(), PywrError> {
+    let values = state.get_const_parameter_values();
+    let mut built = SolverBuilder::<usize>::default()
+        .with_tie_breaking(tie_breaking)
+        .create(network, &values)?;
+    built.update(network, timestep, state, &mut SolverTimings::default())?;
+
+    let lp = LpFile::new(network, &built)?;
+
+    let map_err = |e: std::io::Error| PywrError::LpExportError(export.path.clone(), e.to_string());
+    let mut writer = BufWriter::new(File::create(&export.path).map_err(map_err)?);
+    match export.format {
+        LpFileFormat::Mps => lp.write_mps(&mut writer),
+        LpFileFormat::Lp => lp.write_lp(&mut writer),
+    }
+    .map_err(map_err)?;
+    writer.flush().map_err(map_err)
+}
+
+/// The sense of a row of the LP.
+enum RowSense {
+    Free,
+    Equal(f64),
+    Greater(f64),
+    Less(f64),
+    Range(f64, f64),
+}
+
+impl RowSense {
+    fn new(lower: f64, upper: f64) -> Self {
+        match (is_infinite(lower), is_infinite(upper)) {
+            (true, true) => Self::Free,
+            (true, false) => Self::Less(upper),
+            (false, true) => Self::Greater(lower),
+            (false, false) if lower == upper => Self::Equal(lower),
+            (false, false) => Self::Range(lower, upper),
+        }
+    }
+}
+
+/// The builder uses the largest finite values for unbounded rows and columns.
+fn is_infinite(value: f64) -> bool {
+    value.is_infinite() || value.abs() == f64::MAX
+}
+
+/// The LP of a time-step in a form that can be written to a file.
+struct LpFile {
+    col_lower: Vec<f64>,
+    col_upper: Vec<f64>,
+    col_obj_coef: Vec<f64>,
+    col_type: Vec<ColType>,
+    row_lower: Vec<f64>,
+    row_upper: Vec<f64>,
+    /// The non-zero elements of each row.
+    rows: Vec<Vec<(usize, f64)>>,
+    /// The edges of each column.
+    col_edges: Vec<Vec<String>>,
+}
+
+impl LpFile {
+    fn new(network: &Network, built: &BuiltSolver<usize>) -> Result<Self, PywrError> {
+        let mut rows: Vec<Vec<(usize, f64)>> = built
+            .row_starts()
+            .windows(2)
+            .map(|w| {
+                built.columns()[w[0]..w[1]]
+                    .iter()
+                    .copied()
+                    .zip(built.elements()[w[0]..w[1]].iter().copied())
+                    .collect()
+            })
+            .collect();
+
+        // The coefficients that depend on the state are only updated in the solvers
+        for (row, column, value) in built.coefficients_to_update() {
+            if let Some(element) = rows[*row].iter_mut().find(|(c, _)| c == column) {
+                element.1 = *value;
+            }
+        }
+
+        let node_label = |node: &Node| match node.full_name() {
+            (name, Some(sub_name)) => format!("{name} ({sub_name})"),
+            (name, None) => name.to_string(),
+        };
+
+        let mut col_edges = vec![Vec::new(); built.num_cols()];
+        for edge in network.edges().iter() {
+            let from_node = network.get_node(&edge.from_node_index())?;
+            let to_node = network.get_node(&edge.to_node_index())?;
+            col_edges[built.col_for_edge(&edge.index())].push(format!(
+                "{} -> {}",
+                node_label(from_node),
+                node_label(to_node)
+            ));
+        }
+
+        Ok(Self {
+            col_lower: built.col_lower().to_vec(),
+            col_upper: built.col_upper().to_vec(),
+            col_obj_coef: built.col_obj_coef().to_vec(),
+            col_type: built.col_type().to_vec(),
+            row_lower: built.row_lower().to_vec(),
+            row_upper: built.row_upper().to_vec(),
+            rows,
+            col_edges,
+        })
+    }
+
+    fn write_header<W: Write>(&self, writer: &mut W, comment: &str) -> std::io::Result<()> {
+        writeln!(writer, "{comment} LP exported by Pywr {}", env!("CARGO_PKG_VERSION"))?;
+        for (col, edges) in self.col_edges.iter().enumerate() {
+            if !edges.is_empty() {
+                writeln!(writer, "{comment} C{col}: {}", edges.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the LP in the free MPS format.
+    fn write_mps<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.write_header(writer, "*")?;
+        writeln!(writer, "NAME PYWR")?;
+
+        writeln!(writer, "ROWS")?;
+        writeln!(writer, " N OBJ")?;
+        for (row, (lower, upper)) in self.row_lower.iter().zip(&self.row_upper).enumerate() {
+            let sense = match RowSense::new(*lower, *upper) {
+                RowSense::Free => "N",
+                RowSense::Equal(_) => "E",
+                RowSense::Greater(_) | RowSense::Range(_, _) => "G",
+                RowSense::Less(_) => "L",
+            };
+            writeln!(writer, " {sense} R{row}")?;
+        }
+
+        // The elements are written by column
+        let mut cols = vec![Vec::new(); self.col_lower.len()];
+        for (row, elements) in self.rows.iter().enumerate() {
+            for (col, value) in elements {
+                cols[*col].push((row, *value));
+            }
+        }
+
+        writeln!(writer, "COLUMNS")?;
+        let mut integer = false;
+        for (col, elements) in cols.iter().enumerate() {
+            let is_integer = matches!(self.col_type[col], ColType::Integer);
+            if is_integer != integer {
+                let marker = if is_integer { "INTORG" } else { "INTEND" };
+                writeln!(writer, "    MARKER 'MARKER' '{marker}'")?;
+                integer = is_integer;
+            }
+
+            // Every column is written with its objective coefficient, even if it is zero
+            writeln!(writer, "    C{col} OBJ {}", self.col_obj_coef[col])?;
+            for (row, value) in elements {
+                writeln!(writer, "    C{col} R{row} {value}")?;
+            }
+        }
+        if integer {
+            writeln!(writer, "    MARKER 'MARKER' 'INTEND'")?;
+        }
+
+        writeln!(writer, "RHS")?;
+        let mut ranges = Vec::new();
+        for (row, (lower, upper)) in self.row_lower.iter().zip(&self.row_upper).enumerate() {
+            let rhs = match RowSense::new(*lower, *upper) {
+                RowSense::Free => continue,
+                RowSense::Equal(rhs) | RowSense::Greater(rhs) | RowSense::Less(rhs) => rhs,
+                RowSense::Range(lower, upper) => {
+                    ranges.push((row, upper - lower));
+                    lower
+                }
+            };
+            if rhs != 0.0 {
+                writeln!(writer, "    RHS R{row} {rhs}")?;
+            }
+        }
+
+        if !ranges.is_empty() {
+            writeln!(writer, "RANGES")?;
+            for (row, range) in ranges {
+                writeln!(writer, "    RNG R{row} {range}")?;
+            }
+        }
+
+        writeln!(writer, "BOUNDS")?;
+        for (col, (lower, upper)) in self.col_lower.iter().zip(&self.col_upper).enumerate() {
+            let (lower, upper) = (*lower, *upper);
+            match (is_infinite(lower), is_infinite(upper)) {
+                (true, true) => writeln!(writer, " FR BND C{col}")?,
+                (false, false) if lower == upper => writeln!(writer, " FX BND C{col} {lower}")?,
+                (lower_infinite, upper_infinite) => {
+                    if lower_infinite {
+                        writeln!(writer, " MI BND C{col}")?;
+                    } else if lower != 0.0 {
+                        writeln!(writer, " LO BND C{col} {lower}")?;
+                    }
+
+                    if !upper_infinite {
+                        writeln!(writer, " UP BND C{col} {upper}")?;
+                    } else if matches!(self.col_type[col], ColType::Integer) {
+                        // Some readers give integer columns an upper bound of one by default
+                        writeln!(writer, " PL BND C{col}")?;
+                    }
+                }
+            }
+        }
+
+        writeln!(writer, "ENDATA")
+    }
+
+    /// Write the LP in the CPLEX LP format.
+    fn write_lp<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.write_header(writer, "\\")?;
+
+        writeln!(writer, "Minimize")?;
+        write!(writer, " obj:")?;
+        let objective = self
+            .col_obj_coef
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value != 0.0)
+            .map(|(col, value)| (col, *value));
+        write_lp_expression(writer, objective)?;
+        writeln!(writer)?;
+
+        writeln!(writer, "Subject To")?;
+        for (row, elements) in self.rows.iter().enumerate() {
+            // A ranged row is written as two rows
+            let constraints = match RowSense::new(self.row_lower[row], self.row_upper[row]) {
+                RowSense::Free => continue,
+                RowSense::Equal(rhs) => vec![(format!("R{row}"), "=", rhs)],
+                RowSense::Greater(rhs) => vec![(format!("R{row}"), ">=", rhs)],
+                RowSense::Less(rhs) => vec![(format!("R{row}"), "<=", rhs)],
+                RowSense::Range(lower, upper) => {
+                    vec![(format!("R{row}_lo"), ">=", lower), (format!("R{row}_up"), "<=", upper)]
+                }
+            };
+
+            for (name, sense, rhs) in constraints {
+                write!(writer, " {name}:")?;
+                write_lp_expression(writer, elements.iter().copied())?;
+                writeln!(writer, " {sense} {rhs}")?;
+            }
+        }
+
+        writeln!(writer, "Bounds")?;
+        for (col, (lower, upper)) in self.col_lower.iter().zip(&self.col_upper).enumerate() {
+            let (lower, upper) = (*lower, *upper);
+            match (is_infinite(lower), is_infinite(upper)) {
+                (true, true) => writeln!(writer, " C{col} free")?,
+                (true, false) => writeln!(writer, " -inf <= C{col} <= {upper}")?,
+                (false, true) if lower != 0.0 => writeln!(writer, " C{col} >= {lower}")?,
+                (false, true) => {}
+                (false, false) if lower == upper => writeln!(writer, " C{col} = {lower}")?,
+                (false, false) => writeln!(writer, " {lower} <= C{col} <= {upper}")?,
+            }
+        }
+
+        let integers: Vec<usize> = self
+            .col_type
+            .iter()
+            .enumerate()
+            .filter(|(_, ty)| matches!(ty, ColType::Integer))
+            .map(|(col, _)| col)
+            .collect();
+        if !integers.is_empty() {
+            writeln!(writer, "General")?;
+            for col in integers {
+                writeln!(writer, " C{col}")?;
+            }
+        }
+
+        writeln!(writer, "End")
+    }
+}
+
+/// Write the terms of a linear expression in the LP format.
+///
+/// An empty expression is written as a zero coefficient of the first column.
+fn write_lp_expression<W: Write>(writer: &mut W, terms: impl Iterator<Item = (usize, f64)>) -> std::io::Result<()> {
+    let mut count = 0;
+    for (col, value) in terms {
+        if count > 0 && count % LP_TERMS_PER_LINE == 0 {
+            write!(writer, "\n   ")?;
+        }
+
+        let sign = if value < 0.0 { "-" } else { "+" };
+        if count == 0 && sign == "+" {
+            write!(writer, " {value} C{col}")?;
+        } else {
+            write!(writer, " {sign} {} C{col}", value.abs())?;
+        }
+        count += 1;
+    }
+
+    if count == 0 {
+        write!(writer, " 0 C0")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LpExport, LpFile, LpFileFormat};
+    use crate::solvers::builder::ColType;
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::simple_model;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// An LP with each type of row, bound and column.
+    fn lp_file() -> LpFile {
+        LpFile {
+            col_lower: vec![0.0, 0.0, -f64::MAX, 0.0],
+            col_upper: vec![f64::MAX, 10.0, f64::MAX, 1.0],
+            col_obj_coef: vec![1.0, -2.0, 0.0, 0.0],
+            col_type: vec![
+                ColType::Continuous,
+                ColType::Continuous,
+                ColType::Continuous,
+                ColType::Integer,
+            ],
+            row_lower: vec![5.0, 2.0, -f64::MAX, 1.0],
+            row_upper: vec![5.0, f64::MAX, 0.0, 4.0],
+            rows: vec![
+                vec![(0, 1.0), (1, 1.0)],
+                vec![(1, 1.0), (2, -1.0)],
+                vec![(0, 1.0), (3, -10.0)],
+                vec![(2, 2.5)],
+            ],
+            col_edges: vec![
+                vec!["a -> b".to_string()],
+                vec!["b -> c".to_string(), "c -> d".to_string()],
+                vec![],
+                vec![],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_write_mps() {
+        let mut data = Vec::new();
+        lp_file().write_mps(&mut data).unwrap();
+
+        let expected = format!(
+            "* LP exported by Pywr {}
+* C0: a -> b
+* C1: b -> c, c -> d
+NAME PYWR
+ROWS
+ N OBJ
+ E R0
+ G R1
+ L R2
+ G R3
+COLUMNS
+    C0 OBJ 1
+    C0 R0 1
+    C0 R2 1
+    C1 OBJ -2
+    C1 R0 1
+    C1 R1 1
+    C2 OBJ 0
+    C2 R1 -1
+    C2 R3 2.5
+    MARKER 'MARKER' 'INTORG'
+    C3 OBJ 0
+    C3 R2 -10
+    MARKER 'MARKER' 'INTEND'
+RHS
+    RHS R0 5
+    RHS R1 2
+    RHS R3 1
+RANGES
+    RNG R3 3
+BOUNDS
+ UP BND C1 10
+ FR BND C2
+ UP BND C3 1
+ENDATA
+",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(String::from_utf8(data).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_lp() {
+        let mut data = Vec::new();
+        lp_file().write_lp(&mut data).unwrap();
+
+        let expected = format!(
+            "\\ LP exported by Pywr {}
+\\ C0: a -> b
+\\ C1: b -> c, c -> d
+Minimize
+ obj: 1 C0 - 2 C1
+Subject To
+ R0: 1 C0 + 1 C1 = 5
+ R1: 1 C1 - 1 C2 >= 2
+ R2: 1 C0 - 10 C3 <= 0
+ R3_lo: 2.5 C2 >= 1
+ R3_up: 2.5 C2 <= 4
+Bounds
+ 0 <= C1 <= 10
+ C2 free
+ 0 <= C3 <= 1
+General
+ C3
+End
+",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(String::from_utf8(data).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_export_lp_for_network() {
+        let export = LpExport::new("out/model.mps", LpFileFormat::Mps, 0);
+        assert_eq!(
+            export.for_network("upstream").path(),
+            Path::new("out/model-upstream.mps")
+        );
+
+        let export = LpExport::new("model", LpFileFormat::Lp, 0);
+        assert_eq!(export.for_network("upstream").path(), Path::new("model-upstream"));
+    }
+
+    #[test]
+    fn test_lp_file_format() {
+        assert_eq!(LpFileFormat::from_path(Path::new("model.mps")), Some(LpFileFormat::Mps));
+        assert_eq!(LpFileFormat::from_path(Path::new("model.LP")), Some(LpFileFormat::Lp));
+        assert_eq!(LpFileFormat::from_path(Path::new("model.txt")), None);
+    }
+
+    #[test]
+    fn test_export_lp() {
+        let model = simple_model(2, None);
+        let dir = TempDir::new().unwrap();
+
+        for (extension, format) in [("mps", LpFileFormat::Mps), ("lp", LpFileFormat::Lp)] {
+            let path = dir.path().join(format!("model.{extension}"));
+            let export = LpExport::new(&path, format, 3).with_scenario(1);
+            let settings = ClpSolverSettings::builder().export_lp(export).build();
+            model.run::<ClpSolver>(&settings).unwrap();
+
+            // The edges of the link are collapsed into a single column; the row of the input and
+            // output is bounded by the inflow of the time-step and scenario (1 + 3 + 1).
+            let data = std::fs::read_to_string(&path).unwrap();
+            assert!(data.contains("C0: input -> link, link -> output\n"));
+            match format {
+                LpFileFormat::Mps => {
+                    assert!(data.contains("\n G R0\n"));
+                    assert!(data.contains("\n    C0 R0 1\n"));
+                    assert!(data.contains("\n    RNG R0 5\n"));
+                    assert!(data.trim_end().ends_with("ENDATA"));
+                }
+                LpFileFormat::Lp => {
+                    assert!(data.contains("\n R0_lo: 1 C0 >= 0\n R0_up: 1 C0 <= 5\n"));
+                    assert!(data.trim_end().ends_with("End"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_export_lp_invalid_scenario() {
+        let model = simple_model(2, None);
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("model.mps");
+
+        let export = LpExport::new(&path, LpFileFormat::Mps, 0).with_scenario(2);
+        let settings = ClpSolverSettings::builder().export_lp(export).build();
+        assert!(model.run::<ClpSolver>(&settings).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -1,7 +1,9 @@
 use crate::network::Network;
+use crate::solvers::builder::SolverBuilder;
 use crate::state::{ConstParameterValues, State};
 use crate::timestep::Timestep;
 use crate::PywrError;
+use std::fmt::Debug;
 use std::ops::{Add, AddAssign};
 use std::time::Duration;
 
@@ -11,22 +13,49 @@ mod builder;
 mod cbc;
 mod clp;
 mod col_edge_map;
+#[cfg(feature = "gurobi")]
+mod gurobi;
 #[cfg(feature = "highs")]
 mod highs;
+mod horizon;
+mod infeasibility;
 #[cfg(feature = "ipm-ocl")]
 mod ipm_ocl;
 #[cfg(feature = "ipm-simd")]
 mod ipm_simd;
+mod lp_export;
+#[cfg(feature = "osqp")]
+mod osqp;
+mod statistics;
+mod warm_start;
 
 #[cfg(feature = "ipm-ocl")]
-pub use self::ipm_ocl::{ClIpmF32Solver, ClIpmF64Solver, ClIpmSolverSettings, ClIpmSolverSettingsBuilder};
+pub use self::ipm_ocl::{
+    ClIpmF32Solver, ClIpmF64Solver, ClIpmFallback, ClIpmSolverSettings, ClIpmSolverSettingsBuilder, EdgeFlowReductions,
+};
 #[cfg(feature = "ipm-simd")]
-pub use self::ipm_simd::{SimdIpmF64Solver, SimdIpmSolverSettings, SimdIpmSolverSettingsBuilder};
+pub use self::ipm_simd::{SimdIpmF64Solver, SimdIpmFallback, SimdIpmSolverSettings, SimdIpmSolverSettingsBuilder};
+#[cfg(feature = "osqp")]
+pub use self::osqp::{OsqpError, OsqpFallback, OsqpSolver, OsqpSolverSettings, OsqpSolverSettingsBuilder};
+#[cfg(feature = "ipm-ocl")]
+pub use ::ipm_ocl::{
+    list_devices as list_cl_devices, DeviceDescription as ClDeviceDescription, DeviceType as ClDeviceType,
+};
 #[cfg(feature = "cbc")]
 pub use cbc::{CbcError, CbcSolver, CbcSolverSettings, CbcSolverSettingsBuilder};
+pub(crate) use clp::solve_horizon_lp;
 pub use clp::{ClpError, ClpSolver, ClpSolverSettings, ClpSolverSettingsBuilder};
+#[cfg(feature = "gurobi")]
+pub use gurobi::{GurobiError, GurobiMethod, GurobiSolver, GurobiSolverSettings, GurobiSolverSettingsBuilder};
 #[cfg(feature = "highs")]
-pub use highs::{HighsSolver, HighsSolverSettings, HighsSolverSettingsBuilder};
+pub use highs::{HighsError, HighsSolver, HighsSolverSettings, HighsSolverSettingsBuilder};
+pub(crate) use horizon::HorizonLpBuilder;
+pub(crate) use infeasibility::with_scenario_index;
+pub use infeasibility::{ConstraintKind, ConstraintViolation, InfeasibilityReport};
+pub(crate) use lp_export::export_lp;
+pub use lp_export::{LpExport, LpFileFormat};
+pub use statistics::{LpConditioningWarning, LpStatistics};
+pub use warm_start::{SolverWarmState, WarmStart, WarmStartError};
 
 #[derive(Default, Debug)]
 pub struct SolverTimings {
@@ -75,16 +104,50 @@ pub enum SolverFeatures {
     AggregatedNodeDynamicFactors,
     VirtualStorage,
     MutualExclusivity,
+    SwitchingConstraints,
 }
 
 /// Solver settings that are common to all solvers.
 pub trait SolverSettings {
     fn parallel(&self) -> bool;
     fn threads(&self) -> usize;
+    /// Whether the solution of the previous time-step is used to warm-start the solver.
+    ///
+    /// Solvers that do not support warm-starting ignore this setting.
+    fn warm_start(&self) -> bool {
+        false
+    }
+    /// Whether the dual values of the constraints are saved to the state after each solve.
+    ///
+    /// See [`crate::state::DualValues`]. Solvers that do not provide dual values ignore this setting.
+    fn duals(&self) -> bool {
+        false
+    }
+    /// The range of the perturbations added to the costs of the edges to break ties between
+    /// routes of equal cost, or `None` if ties are not broken.
+    ///
+    /// Solvers that do not support tie-breaking ignore this setting.
+    fn tie_breaking(&self) -> Option<f64> {
+        None
+    }
+    /// The time-step and scenario whose LP is written to a file, if any (see [`LpExport`]).
+    ///
+    /// The LP is built by the LP builder shared by the solvers, so it is the same for every
+    /// solver.
+    fn lp_export(&self) -> Option<&LpExport> {
+        None
+    }
+    /// Whether an infeasible time-step is diagnosed before the run fails (see
+    /// [`InfeasibilityReport`]).
+    ///
+    /// Solvers that do not detect infeasibility ignore this setting.
+    fn diagnose_infeasibility(&self) -> bool {
+        false
+    }
 }
 
 pub trait Solver: Send {
-    type Settings;
+    type Settings: Debug;
 
     fn name() -> &'static str;
     /// An array of features that this solver provides.
@@ -92,10 +155,28 @@ pub trait Solver: Send {
     fn setup(model: &Network, values: &ConstParameterValues, settings: &Self::Settings)
         -> Result<Box<Self>, PywrError>;
     fn solve(&mut self, model: &Network, timestep: &Timestep, state: &mut State) -> Result<SolverTimings, PywrError>;
+    /// Export the current state of the solver so that it can be used to warm-start another run.
+    ///
+    /// Returns `None` if the solver does not support warm-starting.
+    fn export_warm_state(&self) -> Option<SolverWarmState> {
+        None
+    }
+    /// Import a state previously exported by [`Solver::export_warm_state`].
+    ///
+    /// Solvers that do not support warm-starting ignore the state.
+    fn import_warm_state(&mut self, _state: &SolverWarmState) -> Result<(), PywrError> {
+        Ok(())
+    }
+    /// Limit the wall-clock time of the next solve to `time_limit`.
+    ///
+    /// A solve that reaches the limit returns [`PywrError::SolveTimeLimitReached`]. Solvers that
+    /// can not be stopped during a solve ignore the limit; the time limit of a run is then only
+    /// checked at the end of each time-step.
+    fn set_time_limit(&mut self, _time_limit: Duration) {}
 }
 
 pub trait MultiStateSolver: Send {
-    type Settings;
+    type Settings: Debug;
 
     fn name() -> &'static str;
     /// An array of features that this solver provides.
@@ -104,3 +185,9 @@ pub trait MultiStateSolver: Send {
     fn solve(&mut self, model: &Network, timestep: &Timestep, states: &mut [State])
         -> Result<SolverTimings, PywrError>;
 }
+
+/// Build the linear program for a network and calculate statistics of its constraint matrix.
+pub(crate) fn lp_statistics(network: &Network, values: &ConstParameterValues) -> Result<LpStatistics, PywrError> {
+    let built = SolverBuilder::<usize>::default().create(network, values)?;
+    Ok(built.statistics())
+}
@@ -0,0 +1,382 @@
+//! A first-order solver for approximate, fast, solves of the LP of each time-step.
+//!
+//! [OSQP](https://osqp.org) solves the LP with the alternating direction method of multipliers
+//! (ADMM). Each iteration is cheap and is warm-started from the solution of the previous
+//! time-step, so a run can be much faster than with a simplex solver. However, a solve stops as
+//! soon as the residuals are within the tolerances of the settings, and the solution is only as
+//! accurate as those tolerances:
+//!
+//! - The flows may break the bounds of the nodes, and the mass balance of the network, by a small
+//!   amount. These errors accumulate in the volume of storage nodes over a run.
+//! - Routes whose costs are similar may share the flow between them, rather than the cheapest
+//!   route taking all of it.
+//!
+//! The solver is intended for screening runs, such as the first pass over many scenarios or
+//! options, whose results are confirmed with a simplex solver. Tighter tolerances, or polishing
+//! the solutions (see [`OsqpSolverSettingsBuilder::polish`]), improve the accuracy at the cost of
+//! speed. Integer columns are not supported, so models with mutual exclusivity or switching
+//! constraints can not be solved.
+mod settings;
+
+use crate::network::Network;
+use crate::solvers::builder::{BuiltSolver, SolverBuilder};
+use crate::solvers::{ClpSolver, ClpSolverSettingsBuilder, Solver, SolverFeatures, SolverSettings, SolverTimings};
+use crate::state::{ConstParameterValues, State};
+use crate::timestep::Timestep;
+use crate::PywrError;
+use ::osqp::{CscMatrix, Problem, Settings, Status};
+pub use settings::{OsqpFallback, OsqpSolverSettings, OsqpSolverSettingsBuilder};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::time::Instant;
+use thiserror::Error;
+use tracing::warn;
+
+/// OSQP treats bounds at or beyond this value as infinite.
+const OSQP_INFINITY: f64 = 1e30;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OsqpError {
+    #[error("failed to setup the OSQP problem: {0}")]
+    Setup(String),
+    #[error("OSQP did not solve the LP: {0}")]
+    NotSolved(&'static str),
+}
+
+/// The constraint matrix of OSQP in compressed sparse column format.
+///
+/// The rows of the LP are followed by a row for the bounds of each column, as OSQP has no
+/// bounds on the columns.
+struct ConstraintMatrix {
+    num_rows: usize,
+    num_cols: usize,
+    col_starts: Vec<usize>,
+    rows: Vec<usize>,
+    elements: Vec<f64>,
+    /// The position in `elements` of each (row, column) of the LP.
+    positions: HashMap<(usize, usize), usize>,
+}
+
+impl ConstraintMatrix {
+    fn new(built: &BuiltSolver<usize>) -> Self {
+        let num_lp_rows = built.num_rows();
+        let num_cols = built.num_cols();
+
+        // Collect the elements of each column, in order of their rows
+        let mut columns: Vec<Vec<(usize, f64)>> = vec![Vec::new(); num_cols];
+        for (row, w) in built.row_starts().windows(2).enumerate() {
+            for (column, value) in built.columns()[w[0]..w[1]].iter().zip(&built.elements()[w[0]..w[1]]) {
+                columns[*column].push((row, *value));
+            }
+        }
+        for (column, elements) in columns.iter_mut().enumerate() {
+            elements.push((num_lp_rows + column, 1.0));
+        }
+
+        let mut col_starts = vec![0];
+        let mut rows = Vec::new();
+        let mut elements = Vec::new();
+        let mut positions = HashMap::new();
+        for (column, column_elements) in columns.into_iter().enumerate() {
+            for (row, value) in column_elements {
+                positions.insert((row, column), elements.len());
+                rows.push(row);
+                elements.push(value);
+            }
+            col_starts.push(elements.len());
+        }
+
+        Self {
+            num_rows: num_lp_rows + num_cols,
+            num_cols,
+            col_starts,
+            rows,
+            elements,
+            positions,
+        }
+    }
+
+    /// Update the coefficients of the LP; returns `true` if any were changed.
+    fn update(&mut self, coefficients: &[(usize, usize, f64)]) -> bool {
+        let mut changed = false;
+        for (row, column, value) in coefficients {
+            if let Some(&position) = self.positions.get(&(*row, *column)) {
+                changed |= self.elements[position] != *value;
+                self.elements[position] = *value;
+            }
+        }
+        changed
+    }
+
+    fn as_csc(&self) -> CscMatrix {
+        CscMatrix {
+            nrows: self.num_rows,
+            ncols: self.num_cols,
+            indptr: Cow::Borrowed(&self.col_starts),
+            indices: Cow::Borrowed(&self.rows),
+            data: Cow::Borrowed(&self.elements),
+        }
+    }
+}
+
+/// The bounds of the rows of the LP followed by the bounds of the columns.
+fn bounds(built: &BuiltSolver<usize>) -> (Vec<f64>, Vec<f64>) {
+    let lower = built
+        .row_lower()
+        .iter()
+        .chain(built.col_lower())
+        .map(|v| v.max(-OSQP_INFINITY))
+        .collect();
+    let upper = built
+        .row_upper()
+        .iter()
+        .chain(built.col_upper())
+        .map(|v| v.min(OSQP_INFINITY))
+        .collect();
+    (lower, upper)
+}
+
+/// Returns true if the lower bound of any row or column is above its upper bound, or either is
+/// not a number.
+///
+/// OSQP panics if it is given such bounds.
+fn has_invalid_bounds(lower: &[f64], upper: &[f64]) -> bool {
+    lower.iter().zip(upper).any(|(l, u)| l.is_nan() || u.is_nan() || l > u)
+}
+
+/// A description of a status of OSQP without a solution.
+fn status_description(status: &Status) -> &'static str {
+    match status {
+        Status::Solved(_) | Status::SolvedInaccurate(_) => "solved",
+        Status::MaxIterationsReached(_) => "the maximum number of iterations was reached",
+        Status::TimeLimitReached(_) => "the time limit was reached",
+        Status::PrimalInfeasible(_) | Status::PrimalInfeasibleInaccurate(_) => "the LP is infeasible",
+        Status::DualInfeasible(_) | Status::DualInfeasibleInaccurate(_) => "the LP is unbounded",
+        Status::NonConvex(_) => "the problem is not convex",
+        _ => "unknown status",
+    }
+}
+
+pub struct OsqpSolver {
+    builder: BuiltSolver<usize>,
+    matrix: ConstraintMatrix,
+    problem: Problem,
+    fallback: OsqpFallback,
+    fallback_solver: Option<Box<ClpSolver>>,
+    /// The tie-breaking of the LP, which is also used by the fallback solver.
+    tie_breaking: Option<f64>,
+}
+
+impl OsqpSolver {
+    /// Update the bounds of the problem and solve it.
+    ///
+    /// Returns the value of each column, or a description of why the LP was not solved.
+    fn solve_problem(&mut self, timestep: &Timestep) -> Result<Vec<f64>, &'static str> {
+        let (lower, upper) = bounds(&self.builder);
+        if has_invalid_bounds(&lower, &upper) {
+            return Err("the lower bound of a constraint is above its upper bound");
+        }
+        self.problem.update_bounds(&lower, &upper);
+        if self.matrix.update(self.builder.coefficients_to_update()) {
+            self.problem.update_A(self.matrix.as_csc());
+        }
+
+        match self.problem.solve() {
+            Status::Solved(solution) => Ok(solution.x().to_vec()),
+            Status::SolvedInaccurate(solution) => {
+                warn!(
+                    "OSQP solved the LP of time-step {} inaccurately; the flows are clamped to their bounds.",
+                    timestep.date
+                );
+                let solution = solution
+                    .x()
+                    .iter()
+                    .zip(self.builder.col_lower().iter().zip(self.builder.col_upper()))
+                    .map(|(x, (lower, upper))| x.max(*lower).min(*upper))
+                    .collect();
+                Ok(solution)
+            }
+            status => Err(status_description(&status)),
+        }
+    }
+}
+
+impl Solver for OsqpSolver {
+    type Settings = OsqpSolverSettings;
+
+    fn name() -> &'static str {
+        "osqp"
+    }
+
+    fn features() -> &'static [SolverFeatures] {
+        &[
+            SolverFeatures::AggregatedNode,
+            SolverFeatures::AggregatedNodeFactors,
+            SolverFeatures::AggregatedNodeDynamicFactors,
+            SolverFeatures::VirtualStorage,
+        ]
+    }
+
+    fn setup(
+        network: &Network,
+        values: &ConstParameterValues,
+        settings: &Self::Settings,
+    ) -> Result<Box<Self>, PywrError> {
+        let builder: SolverBuilder<usize> = SolverBuilder::default().with_tie_breaking(settings.tie_breaking());
+        let built = builder.create(network, values)?;
+
+        let matrix = ConstraintMatrix::new(&built);
+        let (lower, upper) = bounds(&built);
+
+        // The LP has no quadratic terms
+        let num_cols = built.num_cols();
+        let p = CscMatrix {
+            nrows: num_cols,
+            ncols: num_cols,
+            indptr: Cow::Owned(vec![0; num_cols + 1]),
+            indices: Cow::Owned(Vec::new()),
+            data: Cow::Owned(Vec::new()),
+        };
+
+        let osqp_settings = Settings::default()
+            .verbose(false)
+            .eps_abs(settings.absolute_tolerance())
+            .eps_rel(settings.relative_tolerance())
+            .max_iter(settings.max_iterations())
+            .polish(settings.polish());
+
+        let problem = Problem::new(p, built.col_obj_coef(), matrix.as_csc(), &lower, &upper, &osqp_settings)
+            .map_err(|e| OsqpError::Setup(e.to_string()))?;
+
+        Ok(Box::new(Self {
+            builder: built,
+            matrix,
+            problem,
+            fallback: settings.fallback(),
+            fallback_solver: None,
+            tie_breaking: settings.tie_breaking(),
+        }))
+    }
+
+    fn solve(&mut self, network: &Network, timestep: &Timestep, state: &mut State) -> Result<SolverTimings, PywrError> {
+        let mut timings = SolverTimings::default();
+        self.builder.update(network, timestep, state, &mut timings)?;
+
+        let now = Instant::now();
+        self.problem.update_lin_cost(self.builder.col_obj_coef());
+        timings.update_objective += now.elapsed();
+
+        let now = Instant::now();
+        let solution = match self.solve_problem(timestep) {
+            Ok(solution) => solution,
+            Err(description) => {
+                return match self.fallback {
+                    OsqpFallback::Error => Err(OsqpError::NotSolved(description).into()),
+                    OsqpFallback::Clp => {
+                        let solver = match &mut self.fallback_solver {
+                            Some(solver) => solver,
+                            None => {
+                                let mut settings = ClpSolverSettingsBuilder::default();
+                                if let Some(epsilon) = self.tie_breaking {
+                                    settings = settings.tie_breaking(epsilon);
+                                }
+                                self.fallback_solver.insert(ClpSolver::setup(
+                                    network,
+                                    &state.get_const_parameter_values(),
+                                    &settings.build(),
+                                )?)
+                            }
+                        };
+                        solver.solve(network, timestep, state)
+                    }
+                };
+            }
+        };
+        timings.solve = now.elapsed();
+
+        // Reset the network state from the results
+        let network_state = state.get_mut_network_state();
+        network_state.reset();
+        let start_save_solution = Instant::now();
+
+        for edge in network.edges().deref() {
+            let col = self.builder.col_for_edge(&edge.index());
+            let flow = solution[col];
+            network_state.add_flow(edge, timestep, flow)?;
+        }
+
+        state.complete(network, timestep)?;
+        timings.save_solution += start_save_solution.elapsed();
+
+        Ok(timings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_invalid_bounds, OsqpError, OsqpFallback, OsqpSolver, OsqpSolverSettings};
+    use crate::metric::MetricF64;
+    use crate::models::Model;
+    use crate::network::Network;
+    use crate::recorders::AssertionRecorder;
+    use crate::test_utils::{default_time_domain, simple_model};
+    use crate::PywrError;
+    use ndarray::Array2;
+
+    #[test]
+    fn test_simple_model() {
+        let model = simple_model(2, None);
+
+        let settings = OsqpSolverSettings::builder().tolerance(1e-6).polish().build();
+        model.run::<OsqpSolver>(&settings).unwrap();
+    }
+
+    /// A supply whose flow is limited by a demand; the flow is accurate to the tolerance.
+    #[test]
+    fn test_accuracy() {
+        let mut network = Network::default();
+        let supply = network.add_input_node("supply", None).unwrap();
+        let demand = network.add_output_node("demand", None).unwrap();
+        network.connect_nodes(supply, demand).unwrap();
+
+        network.set_node_max_flow("supply", None, Some(15.0.into())).unwrap();
+        network.set_node_max_flow("demand", None, Some(10.0.into())).unwrap();
+        network.set_node_cost("demand", None, Some((-10.0).into())).unwrap();
+
+        let expected = Array2::from_elem((366, 10), 10.0);
+        let recorder = AssertionRecorder::new("demand-flow", MetricF64::NodeInFlow(demand), expected, None, Some(1e-3));
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let model = Model::new(default_time_domain().into(), network);
+        // Any time-step that OSQP fails to solve is an error, rather than solved by Clp
+        let settings = OsqpSolverSettings::builder()
+            .tolerance(1e-5)
+            .fallback(OsqpFallback::Error)
+            .build();
+        model.run::<OsqpSolver>(&settings).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_bounds() {
+        assert!(!has_invalid_bounds(&[0.0, -1.0], &[0.0, 1.0]));
+        assert!(has_invalid_bounds(&[0.0, 2.0], &[1.0, 1.0]));
+        assert!(has_invalid_bounds(&[f64::NAN], &[1.0]));
+    }
+
+    /// A time-step whose bounds are inverted is an error rather than a panic in OSQP.
+    #[test]
+    fn test_inverted_bounds() {
+        let mut network = Network::default();
+        let supply = network.add_input_node("supply", None).unwrap();
+        let demand = network.add_output_node("demand", None).unwrap();
+        network.connect_nodes(supply, demand).unwrap();
+
+        network.set_node_min_flow("demand", None, Some(10.0.into())).unwrap();
+        network.set_node_max_flow("demand", None, Some(5.0.into())).unwrap();
+
+        let model = Model::new(default_time_domain().into(), network);
+        let result = model.run::<OsqpSolver>(&OsqpSolverSettings::default());
+        assert!(matches!(result, Err(PywrError::OsqpError(OsqpError::NotSolved(_)))));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::solvers::{LpExport, SolverSettings};
+
+/// What to do with the time-steps that OSQP fails to solve.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum OsqpFallback {
+    /// Return an error and stop the run.
+    #[default]
+    Error,
+    /// Solve the time-step again using the Clp solver.
+    Clp,
+}
+
+/// Settings for the OSQP solver.
+///
+/// Create new settings using [`OsqpSolverSettingsBuilder`] or use the default implementation;
+#[derive(PartialEq, Debug, Clone)]
+pub struct OsqpSolverSettings {
+    parallel: bool,
+    threads: usize,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    absolute_tolerance: f64,
+    relative_tolerance: f64,
+    max_iterations: u32,
+    polish: bool,
+    fallback: OsqpFallback,
+}
+
+// Default implementation is a convenience that defers to the builder.
+impl Default for OsqpSolverSettings {
+    fn default() -> Self {
+        OsqpSolverSettingsBuilder::default().build()
+    }
+}
+
+impl SolverSettings for OsqpSolverSettings {
+    fn parallel(&self) -> bool {
+        self.parallel
+    }
+
+    fn threads(&self) -> usize {
+        self.threads
+    }
+
+    fn tie_breaking(&self) -> Option<f64> {
+        self.tie_breaking
+    }
+
+    fn lp_export(&self) -> Option<&LpExport> {
+        self.lp_export.as_ref()
+    }
+}
+
+impl OsqpSolverSettings {
+    /// Create a new builder for the settings
+    pub fn builder() -> OsqpSolverSettingsBuilder {
+        OsqpSolverSettingsBuilder::default()
+    }
+
+    pub fn absolute_tolerance(&self) -> f64 {
+        self.absolute_tolerance
+    }
+
+    pub fn relative_tolerance(&self) -> f64 {
+        self.relative_tolerance
+    }
+
+    pub fn max_iterations(&self) -> u32 {
+        self.max_iterations
+    }
+
+    pub fn polish(&self) -> bool {
+        self.polish
+    }
+
+    pub fn fallback(&self) -> OsqpFallback {
+        self.fallback
+    }
+}
+
+/// Builder for [`OsqpSolverSettings`].
+///
+/// # Examples
+///
+/// ```
+/// use pywr_core::solvers::{OsqpFallback, OsqpSolverSettingsBuilder};
+/// // Settings with parallel enabled and 4 threads.
+/// let settings = OsqpSolverSettingsBuilder::default().parallel().threads(4).build();
+///
+/// // Settings for a quick screening run, which fall back to Clp if a time-step is not solved.
+/// let settings = OsqpSolverSettingsBuilder::default()
+///     .tolerance(1e-2)
+///     .fallback(OsqpFallback::Clp)
+///     .build();
+/// ```
+pub struct OsqpSolverSettingsBuilder {
+    parallel: bool,
+    threads: usize,
+    tie_breaking: Option<f64>,
+    lp_export: Option<LpExport>,
+    absolute_tolerance: f64,
+    relative_tolerance: f64,
+    max_iterations: u32,
+    polish: bool,
+    fallback: OsqpFallback,
+}
+
+impl Default for OsqpSolverSettingsBuilder {
+    fn default() -> Self {
+        // The defaults of OSQP
+        Self {
+            parallel: false,
+            threads: 0,
+            tie_breaking: None,
+            lp_export: None,
+            absolute_tolerance: 1e-3,
+            relative_tolerance: 1e-3,
+            max_iterations: 4000,
+            polish: false,
+            fallback: OsqpFallback::default(),
+        }
+    }
+}
+
+impl OsqpSolverSettingsBuilder {
+    pub fn parallel(mut self) -> Self {
+        self.parallel = true;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Break ties between routes of equal cost deterministically.
+    ///
+    /// The costs of the edges out of each node with more than one outgoing edge are perturbed by
+    /// values between `-epsilon / 2` and `epsilon / 2`, so that edges added earlier are preferred
+    /// and the perturbations at each node sum to zero. `epsilon` should be much smaller than any
+    /// meaningful difference between the costs of the routes.
+    pub fn tie_breaking(mut self, epsilon: f64) -> Self {
+        self.tie_breaking = Some(epsilon);
+        self
+    }
+
+    /// Write the LP of a time-step of a scenario to a file (see [`LpExport`]).
+    pub fn export_lp(mut self, export: LpExport) -> Self {
+        self.lp_export = Some(export);
+        self
+    }
+
+    /// Set both the absolute and the relative tolerance.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.absolute_tolerance = tolerance;
+        self.relative_tolerance = tolerance;
+        self
+    }
+
+    /// The absolute tolerance of the residuals at which a solve stops (default `1e-3`).
+    pub fn absolute_tolerance(mut self, tolerance: f64) -> Self {
+        self.absolute_tolerance = tolerance;
+        self
+    }
+
+    /// The tolerance of the residuals, relative to the size of the flows and costs, at which a
+    /// solve stops (default `1e-3`).
+    pub fn relative_tolerance(mut self, tolerance: f64) -> Self {
+        self.relative_tolerance = tolerance;
+        self
+    }
+
+    /// The maximum number of iterations of each solve (default `4000`).
+    pub fn max_iterations(mut self, max_iterations: u32) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Polish each solution by solving the equations of its active constraints.
+    ///
+    /// A polished solution is usually accurate to the precision of the flows, at the cost of a
+    /// slower solve.
+    pub fn polish(mut self) -> Self {
+        self.polish = true;
+        self
+    }
+
+    /// Set what to do with the time-steps that are not solved.
+    pub fn fallback(mut self, fallback: OsqpFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Construct a [`OsqpSolverSettings`] from the builder.
+    pub fn build(self) -> OsqpSolverSettings {
+        OsqpSolverSettings {
+            parallel: self.parallel,
+            threads: self.threads,
+            tie_breaking: self.tie_breaking,
+            lp_export: self.lp_export,
+            absolute_tolerance: self.absolute_tolerance,
+            relative_tolerance: self.relative_tolerance,
+            max_iterations: self.max_iterations,
+            polish: self.polish,
+            fallback: self.fallback,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OsqpFallback, OsqpSolverSettings, OsqpSolverSettingsBuilder};
+
+    #[test]
+    fn builder_test() {
+        let settings = OsqpSolverSettings {
+            parallel: true,
+            threads: 0,
+            tie_breaking: None,
+            lp_export: None,
+            absolute_tolerance: 1e-2,
+            relative_tolerance: 1e-2,
+            max_iterations: 4000,
+            polish: false,
+            fallback: OsqpFallback::Clp,
+        };
+        let settings_from_builder = OsqpSolverSettingsBuilder::default()
+            .parallel()
+            .tolerance(1e-2)
+            .fallback(OsqpFallback::Clp)
+            .build();
+
+        assert_eq!(settings_from_builder, settings);
+    }
+}
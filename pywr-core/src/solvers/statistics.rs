@@ -0,0 +1,345 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Coefficients with an absolute value above this threshold are considered large.
+const LARGE_COEFFICIENT: f64 = 1e6;
+/// Coefficients with an absolute value below this threshold are considered small.
+const SMALL_COEFFICIENT: f64 = 1e-6;
+/// Ratios of the largest to smallest absolute coefficient above this threshold are considered
+/// likely to cause numerical problems.
+const LARGE_COEFFICIENT_RATIO: f64 = 1e8;
+
+/// Statistics describing the constraint matrix of the linear program built from a network.
+///
+/// These are calculated once the LP has been built (i.e. before the first time-step is solved),
+/// and are intended to help diagnose solver failures on large or automatically generated models.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LpStatistics {
+    /// Number of rows (constraints).
+    pub num_rows: usize,
+    /// Number of columns (variables).
+    pub num_cols: usize,
+    /// Number of non-zero coefficients in the constraint matrix.
+    pub num_non_zero: usize,
+    /// Estimate of the rank of the constraint matrix.
+    ///
+    /// This is the structural rank (i.e. the size of a maximum matching between rows and columns
+    /// of the sparsity pattern). It is an upper bound on the numerical rank.
+    pub rank_estimate: usize,
+    /// Number of rows without any coefficients.
+    pub num_empty_rows: usize,
+    /// Number of columns without any coefficients.
+    pub num_empty_cols: usize,
+    /// Largest absolute non-zero coefficient. `None` if the matrix has no non-zero coefficients.
+    pub max_abs_coefficient: Option<f64>,
+    /// Smallest absolute non-zero coefficient. `None` if the matrix has no non-zero coefficients.
+    pub min_abs_coefficient: Option<f64>,
+}
+
+/// Warnings about the likely numerical conditioning of the constraint matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LpConditioningWarning {
+    /// The structural rank is less than the smaller of the number of rows and columns.
+    RankDeficient { rank_estimate: usize, max_rank: usize },
+    /// The ratio of the largest to smallest absolute coefficient is large.
+    LargeCoefficientRange { ratio: f64 },
+    /// There is at least one very large coefficient.
+    LargeCoefficient { value: f64 },
+    /// There is at least one very small (but non-zero) coefficient.
+    SmallCoefficient { value: f64 },
+    /// There are rows without any coefficients.
+    EmptyRows { count: usize },
+    /// There are columns without any coefficients.
+    EmptyColumns { count: usize },
+}
+
+impl Display for LpConditioningWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RankDeficient { rank_estimate, max_rank } => write!(
+                f,
+                "The constraint matrix may be rank deficient (rank estimate: {rank_estimate}, full rank: {max_rank}). This can indicate redundant or disconnected components."
+            ),
+            Self::LargeCoefficientRange { ratio } => write!(
+                f,
+                "The ratio of the largest to smallest coefficient is {ratio:e}. Consider rescaling the model's factors or units."
+            ),
+            Self::LargeCoefficient { value } => write!(
+                f,
+                "The constraint matrix contains a large coefficient ({value:e})."
+            ),
+            Self::SmallCoefficient { value } => write!(
+                f,
+                "The constraint matrix contains a small coefficient ({value:e})."
+            ),
+            Self::EmptyRows { count } => write!(f, "The constraint matrix contains {count} empty row(s)."),
+            Self::EmptyColumns { count } => write!(f, "The constraint matrix contains {count} empty column(s)."),
+        }
+    }
+}
+
+impl LpStatistics {
+    /// Calculate the statistics from a matrix in compressed sparse row format.
+    pub(crate) fn from_sparse_rows(num_cols: usize, row_starts: &[usize], columns: &[usize], elements: &[f64]) -> Self {
+        let num_rows = row_starts.len().saturating_sub(1);
+
+        let mut max_abs_coefficient: Option<f64> = None;
+        let mut min_abs_coefficient: Option<f64> = None;
+        let mut num_non_zero = 0;
+        let mut col_counts = vec![0usize; num_cols];
+
+        for (col, value) in columns.iter().zip(elements) {
+            if *value == 0.0 {
+                continue;
+            }
+            num_non_zero += 1;
+            col_counts[*col] += 1;
+
+            let abs = value.abs();
+            max_abs_coefficient = Some(max_abs_coefficient.map_or(abs, |m| m.max(abs)));
+            min_abs_coefficient = Some(min_abs_coefficient.map_or(abs, |m| m.min(abs)));
+        }
+
+        let num_empty_rows = row_starts
+            .windows(2)
+            .filter(|w| elements[w[0]..w[1]].iter().all(|v| *v == 0.0))
+            .count();
+        let num_empty_cols = col_counts.iter().filter(|c| **c == 0).count();
+
+        let rank_estimate = structural_rank(num_cols, row_starts, columns, elements);
+
+        Self {
+            num_rows,
+            num_cols,
+            num_non_zero,
+            rank_estimate,
+            num_empty_rows,
+            num_empty_cols,
+            max_abs_coefficient,
+            min_abs_coefficient,
+        }
+    }
+
+    /// The proportion of the matrix's entries that are non-zero.
+    pub fn density(&self) -> f64 {
+        let size = self.num_rows * self.num_cols;
+        if size == 0 {
+            0.0
+        } else {
+            self.num_non_zero as f64 / size as f64
+        }
+    }
+
+    /// The ratio of the largest to smallest absolute coefficient.
+    pub fn coefficient_ratio(&self) -> Option<f64> {
+        match (self.max_abs_coefficient, self.min_abs_coefficient) {
+            (Some(max), Some(min)) => Some(max / min),
+            _ => None,
+        }
+    }
+
+    /// Return any warnings about likely conditioning problems with the constraint matrix.
+    pub fn warnings(&self) -> Vec<LpConditioningWarning> {
+        let mut warnings = Vec::new();
+
+        let max_rank = self.num_rows.min(self.num_cols);
+        if self.rank_estimate < max_rank {
+            warnings.push(LpConditioningWarning::RankDeficient {
+                rank_estimate: self.rank_estimate,
+                max_rank,
+            });
+        }
+
+        if let Some(ratio) = self.coefficient_ratio() {
+            if ratio > LARGE_COEFFICIENT_RATIO {
+                warnings.push(LpConditioningWarning::LargeCoefficientRange { ratio });
+            }
+        }
+
+        if let Some(value) = self.max_abs_coefficient {
+            if value > LARGE_COEFFICIENT {
+                warnings.push(LpConditioningWarning::LargeCoefficient { value });
+            }
+        }
+
+        if let Some(value) = self.min_abs_coefficient {
+            if value < SMALL_COEFFICIENT {
+                warnings.push(LpConditioningWarning::SmallCoefficient { value });
+            }
+        }
+
+        if self.num_empty_rows > 0 {
+            warnings.push(LpConditioningWarning::EmptyRows {
+                count: self.num_empty_rows,
+            });
+        }
+
+        if self.num_empty_cols > 0 {
+            warnings.push(LpConditioningWarning::EmptyColumns {
+                count: self.num_empty_cols,
+            });
+        }
+
+        warnings
+    }
+}
+
+impl Display for LpStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{: <24} | {: <10}", "Statistic", "Value")?;
+        writeln!(f, "{: <24} | {: <10}", "Rows", self.num_rows)?;
+        writeln!(f, "{: <24} | {: <10}", "Columns", self.num_cols)?;
+        writeln!(f, "{: <24} | {: <10}", "Non-zeros", self.num_non_zero)?;
+        writeln!(f, "{: <24} | {: <10.6}", "Density", self.density())?;
+        writeln!(f, "{: <24} | {: <10}", "Rank estimate", self.rank_estimate)?;
+        writeln!(f, "{: <24} | {: <10}", "Empty rows", self.num_empty_rows)?;
+        writeln!(f, "{: <24} | {: <10}", "Empty columns", self.num_empty_cols)?;
+        match self.max_abs_coefficient {
+            Some(v) => writeln!(f, "{: <24} | {: <10e}", "Largest coefficient", v)?,
+            None => writeln!(f, "{: <24} | {: <10}", "Largest coefficient", "-")?,
+        }
+        match self.min_abs_coefficient {
+            Some(v) => write!(f, "{: <24} | {: <10e}", "Smallest coefficient", v),
+            None => write!(f, "{: <24} | {: <10}", "Smallest coefficient", "-"),
+        }
+    }
+}
+
+/// Compute the structural rank of a sparse matrix in compressed sparse row format.
+///
+/// This is the size of a maximum matching in the bipartite graph of rows and columns, and is
+/// found using augmenting paths. Explicit zeros are ignored.
+fn structural_rank(num_cols: usize, row_starts: &[usize], columns: &[usize], elements: &[f64]) -> usize {
+    let num_rows = row_starts.len().saturating_sub(1);
+    // The row matched to each column, if any.
+    let mut col_match: Vec<Option<usize>> = vec![None; num_cols];
+    let mut rank = 0;
+
+    // Greedy initial matching to reduce the number of augmenting path searches.
+    let mut row_matched = vec![false; num_rows];
+    for (row, matched) in row_matched.iter_mut().enumerate() {
+        for idx in row_starts[row]..row_starts[row + 1] {
+            let col = columns[idx];
+            if elements[idx] != 0.0 && col_match[col].is_none() {
+                col_match[col] = Some(row);
+                *matched = true;
+                rank += 1;
+                break;
+            }
+        }
+    }
+
+    let mut visited = vec![usize::MAX; num_cols];
+    for row in (0..num_rows).filter(|r| !row_matched[*r]) {
+        if augment(row, row, row_starts, columns, elements, &mut col_match, &mut visited) {
+            rank += 1;
+        }
+    }
+
+    rank
+}
+
+/// Search for an augmenting path from `start_row` using an explicit stack.
+///
+/// `visited` records the search (identified by `search`) that last visited each column.
+fn augment(
+    start_row: usize,
+    search: usize,
+    row_starts: &[usize],
+    columns: &[usize],
+    elements: &[f64],
+    col_match: &mut [Option<usize>],
+    visited: &mut [usize],
+) -> bool {
+    // Each stack entry is a row and the next position in its list of columns to try.
+    let mut stack: Vec<(usize, usize)> = vec![(start_row, row_starts[start_row])];
+    // The column used to reach each row on the stack (after the first).
+    let mut path: Vec<usize> = Vec::new();
+
+    while let Some((row, pos)) = stack.last_mut() {
+        let row = *row;
+        if *pos >= row_starts[row + 1] {
+            // No further columns to try from this row; backtrack.
+            stack.pop();
+            path.pop();
+            continue;
+        }
+
+        let idx = *pos;
+        *pos += 1;
+
+        let col = columns[idx];
+        if elements[idx] == 0.0 || visited[col] == search {
+            continue;
+        }
+        visited[col] = search;
+
+        match col_match[col] {
+            None => {
+                // Found a free column; flip the matching along the path.
+                col_match[col] = Some(row);
+                for (i, c) in path.iter().enumerate().rev() {
+                    col_match[*c] = Some(stack[i].0);
+                }
+                return true;
+            }
+            Some(next_row) => {
+                path.push(col);
+                stack.push((next_row, row_starts[next_row]));
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LpConditioningWarning, LpStatistics};
+
+    #[test]
+    fn test_full_rank() {
+        // [[1, 1, 0],
+        //  [0, 1, 1]]
+        let stats = LpStatistics::from_sparse_rows(3, &[0, 2, 4], &[0, 1, 1, 2], &[1.0, 1.0, 1.0, 1.0]);
+
+        assert_eq!(stats.num_rows, 2);
+        assert_eq!(stats.num_cols, 3);
+        assert_eq!(stats.num_non_zero, 4);
+        assert_eq!(stats.rank_estimate, 2);
+        assert_eq!(stats.max_abs_coefficient, Some(1.0));
+        assert_eq!(stats.min_abs_coefficient, Some(1.0));
+        assert!(stats.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_augmenting_path() {
+        // The greedy matching pairs row 0 with column 0, leaving row 1 to be matched via an
+        // augmenting path that moves row 0 to column 1.
+        // [[1, 1],
+        //  [1, 0]]
+        let stats = LpStatistics::from_sparse_rows(2, &[0, 2, 3], &[0, 1, 0], &[1.0, 1.0, 1.0]);
+        assert_eq!(stats.rank_estimate, 2);
+    }
+
+    #[test]
+    fn test_rank_deficient() {
+        // Three rows that only reference two of the three columns.
+        let stats = LpStatistics::from_sparse_rows(3, &[0, 1, 2, 4], &[0, 0, 0, 1], &[1.0, 2.0, 1e-9, 1e3]);
+
+        assert_eq!(stats.rank_estimate, 2);
+        assert_eq!(stats.min_abs_coefficient, Some(1e-9));
+        assert_eq!(stats.max_abs_coefficient, Some(1e3));
+
+        let warnings = stats.warnings();
+        assert!(warnings.contains(&LpConditioningWarning::RankDeficient {
+            rank_estimate: 2,
+            max_rank: 3
+        }));
+        assert!(warnings.contains(&LpConditioningWarning::EmptyColumns { count: 1 }));
+        assert!(warnings.contains(&LpConditioningWarning::SmallCoefficient { value: 1e-9 }));
+        assert!(warnings
+            .iter()
+            .any(|w| matches!(w, LpConditioningWarning::LargeCoefficientRange { .. })));
+    }
+}
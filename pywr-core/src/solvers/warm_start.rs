@@ -0,0 +1,154 @@
+use crate::checksum::Checksum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum WarmStartError {
+    #[error("warm start was exported from solver `{found}` but the model is using `{expected}`")]
+    SolverMismatch { expected: String, found: String },
+    #[error("warm start was exported from a network with a different structure")]
+    NetworkMismatch,
+    #[error("warm start contains {found} scenarios but the model has {expected}")]
+    ScenarioCountMismatch { expected: usize, found: usize },
+    #[error("warm start basis has {found} entries but the LP requires {expected}")]
+    BasisSizeMismatch { expected: usize, found: usize },
+    #[error("serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The state of a solver that can be used to warm-start a subsequent run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SolverWarmState {
+    /// The status (basic, at lower bound, etc.) of each column followed by each row of a
+    /// simplex solver's final basis.
+    Basis(Vec<u8>),
+}
+
+/// The final states of the solvers of a run, for each scenario, that can be imported in to a
+/// subsequent run of the same model to accelerate it.
+///
+/// This is intended for iterative studies where the same model is run repeatedly with slightly
+/// different parameter values. The name of the solver and a digest of the network's structure
+/// (see [`crate::network::Network::structure_digest`]) are recorded so that a warm start is only
+/// imported in to a compatible model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WarmStart {
+    solver: String,
+    network_digest: Checksum,
+    states: Vec<SolverWarmState>,
+}
+
+impl WarmStart {
+    pub fn new(solver: &str, network_digest: Checksum, states: Vec<SolverWarmState>) -> Self {
+        Self {
+            solver: solver.to_string(),
+            network_digest,
+            states,
+        }
+    }
+
+    /// The name of the solver the warm start was exported from.
+    pub fn solver(&self) -> &str {
+        &self.solver
+    }
+
+    pub fn network_digest(&self) -> &Checksum {
+        &self.network_digest
+    }
+
+    /// The state of the solver for each scenario.
+    pub fn states(&self) -> &[SolverWarmState] {
+        &self.states
+    }
+
+    /// Check the warm start can be imported in to a model using `solver` with a network
+    /// with the given digest and number of scenarios.
+    pub fn check_compatible(
+        &self,
+        solver: &str,
+        network_digest: &Checksum,
+        num_scenarios: usize,
+    ) -> Result<(), WarmStartError> {
+        if self.solver != solver {
+            return Err(WarmStartError::SolverMismatch {
+                expected: solver.to_string(),
+                found: self.solver.clone(),
+            });
+        }
+
+        if &self.network_digest != network_digest {
+            return Err(WarmStartError::NetworkMismatch);
+        }
+
+        if self.states.len() != num_scenarios {
+            return Err(WarmStartError::ScenarioCountMismatch {
+                expected: num_scenarios,
+                found: self.states.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Write the warm start to a file.
+    pub fn save(&self, path: &Path) -> Result<(), WarmStartError> {
+        std::fs::write(path, crate::encoding::serialize(self)?)?;
+        Ok(())
+    }
+
+    /// Read a warm start from a file.
+    pub fn load(path: &Path) -> Result<Self, WarmStartError> {
+        let bytes = std::fs::read(path)?;
+        Ok(crate::encoding::deserialize(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WarmStart, WarmStartError};
+    use crate::solvers::{ClpSolver, ClpSolverSettings};
+    use crate::test_utils::{simple_model, simple_storage_model};
+    use crate::PywrError;
+
+    #[test]
+    fn test_warm_start_round_trip() {
+        let model = simple_model(2, None);
+        let settings = ClpSolverSettings::default();
+
+        let mut state = model.setup::<ClpSolver>(&settings).unwrap();
+        model.run_with_state::<ClpSolver>(&mut state, &settings).unwrap();
+        let warm_start = model.export_warm_start(&state).unwrap();
+
+        assert_eq!(warm_start.solver(), "clp");
+        assert_eq!(warm_start.states().len(), 2);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("warm-start.bin");
+        warm_start.save(&path).unwrap();
+        let loaded = WarmStart::load(&path).unwrap();
+        assert_eq!(loaded, warm_start);
+
+        let mut state = model.setup::<ClpSolver>(&settings).unwrap();
+        model.import_warm_start(&mut state, &loaded).unwrap();
+        model.run_with_state::<ClpSolver>(&mut state, &settings).unwrap();
+    }
+
+    #[test]
+    fn test_warm_start_incompatible_network() {
+        let model = simple_model(1, None);
+        let other_model = simple_storage_model();
+        let settings = ClpSolverSettings::default();
+
+        let state = model.setup::<ClpSolver>(&settings).unwrap();
+        let warm_start = model.export_warm_start(&state).unwrap();
+
+        let mut other_state = other_model.setup::<ClpSolver>(&settings).unwrap();
+        assert!(matches!(
+            other_model.import_warm_start(&mut other_state, &warm_start),
+            Err(PywrError::WarmStart(WarmStartError::NetworkMismatch))
+        ));
+    }
+}
@@ -1,20 +1,24 @@
-use crate::derived_metric::DerivedMetricIndex;
+use crate::aggregated_node::AggregatedNodeIndex;
+use crate::derived_metric::{DerivedMetricHistory, DerivedMetricIndex};
 use crate::edge::{Edge, EdgeIndex};
-use crate::models::MultiNetworkTransferIndex;
+use crate::models::{ExternalInputIndex, MultiNetworkTransferIndex};
 use crate::network::Network;
 use crate::node::{Node, NodeIndex};
 use crate::parameters::{
     ConstParameterIndex, GeneralParameterIndex, ParameterCollection, ParameterCollectionSize, SimpleParameterIndex,
 };
+use crate::recorders::PreSolveTrace;
+use crate::state_override::StateOverrideIndex;
 use crate::timestep::Timestep;
 use crate::virtual_storage::VirtualStorageIndex;
 use crate::PywrError;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::num::NonZeroUsize;
 use std::ops::Deref;
 use thiserror::Error;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum NodeState {
     Flow(FlowState),
     Storage(StorageState),
@@ -64,17 +68,56 @@ impl NodeState {
         };
     }
 
-    fn clamp_volume(&mut self, min_volume: f64, max_volume: f64) {
-        if let Self::Storage(s) = self {
-            s.clamp(min_volume, max_volume);
+    fn clamp_volume(&mut self, name: &str, min_volume: f64, max_volume: f64) -> Result<(), PywrError> {
+        match self {
+            Self::Storage(s) => s.clamp(name, min_volume, max_volume),
+            Self::Flow(_) => Ok(()),
+        }
+    }
+}
+
+/// The flow below which a node with switching constraints is considered to be off.
+const SWITCH_OFF_TOLERANCE: f64 = 1e-6;
+
+/// The on/off status of a node in the previous time-steps.
+///
+/// See [`crate::node::SwitchingConstraints`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwitchState {
+    /// Whether the node was on in the previous time-step.
+    pub is_on: bool,
+    /// The number of consecutive time-steps the node has had its current status.
+    pub duration: usize,
+}
+
+impl Default for SwitchState {
+    /// The initial state is off for long enough that the node is free to switch on.
+    fn default() -> Self {
+        Self {
+            is_on: false,
+            duration: usize::MAX,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+impl SwitchState {
+    fn update(&mut self, flow: f64) {
+        let is_on = flow > SWITCH_OFF_TOLERANCE;
+        if is_on == self.is_on {
+            self.duration = self.duration.saturating_add(1);
+        } else {
+            self.is_on = is_on;
+            self.duration = 1;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct FlowState {
     pub in_flow: f64,
     pub out_flow: f64,
+    /// The on/off status of the node; only updated if the node has switching constraints.
+    pub switch: SwitchState,
 }
 
 impl FlowState {
@@ -82,6 +125,7 @@ impl FlowState {
         Self {
             in_flow: 0.0,
             out_flow: 0.0,
+            switch: SwitchState::default(),
         }
     }
 
@@ -98,7 +142,7 @@ impl FlowState {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct StorageState {
     pub volume: f64,
     pub flows: FlowState,
@@ -132,27 +176,30 @@ impl StorageState {
     }
 
     /// Ensure the volume is within the min and max volume range (inclusive). If the volume
-    /// is more than 1E6 outside the min or max volume then this function will panic,
-    /// reporting a mass-balance message.
-    fn clamp(&mut self, min_volume: f64, max_volume: f64) {
+    /// is more than 1E6 outside the min or max volume then a mass-balance error naming the
+    /// node (`name`) is returned.
+    fn clamp(&mut self, name: &str, min_volume: f64, max_volume: f64) -> Result<(), PywrError> {
         if (self.volume - min_volume) < -1e-6 {
-            panic!(
-                "Mass-balance error detected. Volume ({}) is smaller than minimum volume ({}).",
-                self.volume, min_volume
-            );
+            return Err(PywrError::VolumeBelowMinimum {
+                name: name.to_string(),
+                volume: self.volume,
+                min_volume,
+            });
         }
         if (self.volume - max_volume) > 1e-6 {
-            panic!(
-                "Mass-balance error detected. Volume ({}) is greater than maximum volume ({}).",
-                self.volume, max_volume,
-            );
+            return Err(PywrError::VolumeAboveMaximum {
+                name: name.to_string(),
+                volume: self.volume,
+                max_volume,
+            });
         }
         self.volume = self.volume.clamp(min_volume, max_volume);
+        Ok(())
     }
 }
 
 /// Stores the history of virtual storage flows.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct VirtualStorageHistory {
     /// The flows are stored in a queue. The oldest flow is popped from the front of the queue
     flows: VecDeque<f64>,
@@ -191,7 +238,7 @@ impl VirtualStorageHistory {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct VirtualStorageState {
     last_reset: Option<Timestep>,
     storage: StorageState,
@@ -241,12 +288,12 @@ impl VirtualStorageState {
         self.storage.proportional_volume(max_volume)
     }
 
-    fn clamp_volume(&mut self, min_volume: f64, max_volume: f64) {
-        self.storage.clamp(min_volume, max_volume);
+    fn clamp_volume(&mut self, name: &str, min_volume: f64, max_volume: f64) -> Result<(), PywrError> {
+        self.storage.clamp(name, min_volume, max_volume)
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct EdgeState {
     flow: f64,
 }
@@ -260,7 +307,7 @@ impl EdgeState {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MultiValue {
     values: HashMap<String, f64>,
     indices: HashMap<String, u64>,
@@ -289,7 +336,7 @@ pub enum ParameterValuesError {
 }
 
 // State of the parameters
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ParameterValues {
     values: Vec<f64>,
     indices: Vec<u64>,
@@ -305,6 +352,13 @@ impl ParameterValues {
         }
     }
 
+    /// Returns `true` if `other` holds the same number of values, indices and multi-values.
+    fn has_same_shape(&self, other: &Self) -> bool {
+        self.values.len() == other.values.len()
+            && self.indices.len() == other.indices.len()
+            && self.multi_values.len() == other.multi_values.len()
+    }
+
     fn get_value(&self, idx: usize) -> Result<f64, ParameterValuesError> {
         self.values
             .get(idx)
@@ -374,7 +428,7 @@ impl ParameterValues {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParameterValuesCollection {
     constant: ParameterValues,
     simple: ParameterValues,
@@ -382,6 +436,12 @@ pub struct ParameterValuesCollection {
 }
 
 impl ParameterValuesCollection {
+    fn has_same_shape(&self, other: &Self) -> bool {
+        self.constant.has_same_shape(&other.constant)
+            && self.simple.has_same_shape(&other.simple)
+            && self.general.has_same_shape(&other.general)
+    }
+
     fn get_simple_parameter_values(&self) -> SimpleParameterValues {
         SimpleParameterValues {
             constant: ConstParameterValues {
@@ -523,12 +583,62 @@ impl ConstParameterValues<'_> {
     }
 }
 
+/// The dual values (shadow prices) of a network's constraints from the last solve.
+///
+/// The dual value of a constraint is the change in the objective function for a unit increase
+/// in the bound of that constraint. For example, the dual value of a demand node's flow constraint
+/// is the marginal value of an additional unit of flow to that node.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DualValues {
+    /// The dual value of each node's flow constraint. Nodes whose flow constraint is controlled
+    /// by a binary variable do not have a dual value.
+    nodes: Vec<Option<f64>>,
+    aggregated_nodes: Vec<f64>,
+    virtual_storage: Vec<f64>,
+}
+
+impl DualValues {
+    pub(crate) fn new(nodes: Vec<Option<f64>>, aggregated_nodes: Vec<f64>, virtual_storage: Vec<f64>) -> Self {
+        Self {
+            nodes,
+            aggregated_nodes,
+            virtual_storage,
+        }
+    }
+
+    pub fn get_node_dual(&self, idx: &NodeIndex) -> Result<f64, PywrError> {
+        match self.nodes.get(*idx.deref()) {
+            Some(Some(dual)) => Ok(*dual),
+            Some(None) => Err(PywrError::DualValueNotAvailable),
+            None => Err(PywrError::NodeIndexNotFound),
+        }
+    }
+
+    pub fn get_aggregated_node_dual(&self, idx: &AggregatedNodeIndex) -> Result<f64, PywrError> {
+        self.aggregated_nodes
+            .get(*idx.deref())
+            .copied()
+            .ok_or(PywrError::NodeIndexNotFound)
+    }
+
+    pub fn get_virtual_storage_dual(&self, idx: &VirtualStorageIndex) -> Result<f64, PywrError> {
+        self.virtual_storage
+            .get(*idx.deref())
+            .copied()
+            .ok_or(PywrError::VirtualStorageIndexNotFound(*idx))
+    }
+}
+
 // State of the nodes and edges
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NetworkState {
     node_states: Vec<NodeState>,
     edge_states: Vec<EdgeState>,
     virtual_storage_states: Vec<VirtualStorageState>,
+    /// The dual values of the last solve, if the solver was configured to save them. These are
+    /// not included in snapshots because they are replaced by the next solve.
+    #[serde(skip)]
+    duals: Option<DualValues>,
 }
 
 impl NetworkState {
@@ -541,9 +651,17 @@ impl NetworkState {
             node_states: initial_node_states,
             edge_states: (0..num_edges).map(|_| EdgeState::default()).collect(),
             virtual_storage_states: initial_virtual_storage_states,
+            duals: None,
         }
     }
 
+    /// Returns `true` if `other` holds the same number of node, edge and virtual storage states.
+    fn has_same_shape(&self, other: &Self) -> bool {
+        self.node_states.len() == other.node_states.len()
+            && self.edge_states.len() == other.edge_states.len()
+            && self.virtual_storage_states.len() == other.virtual_storage_states.len()
+    }
+
     /// Reset the current flow information
     ///
     /// This method should be called between each time-step to set all the flow states to zero.
@@ -588,12 +706,12 @@ impl NetworkState {
     /// once all the flows have been updated.
     fn update_derived_states(&mut self, model: &Network, timestep: &Timestep) -> Result<(), PywrError> {
         // Update virtual storage node states
-        for (state, node) in self
+        for (state, vs_node) in self
             .virtual_storage_states
             .iter_mut()
             .zip(model.virtual_storage_nodes().iter())
         {
-            let flow = node
+            let flow = vs_node
                 .iter_nodes_with_factors()
                 .map(|(idx, factor)| match self.node_states.get(*idx.deref()) {
                     None => Err(PywrError::NodeIndexNotFound),
@@ -603,7 +721,10 @@ impl NetworkState {
                             Node::Input(_) => Ok(factor * s.get_out_flow()),
                             Node::Output(_) => Ok(factor * s.get_in_flow()),
                             Node::Link(_) => Ok(factor * s.get_in_flow()),
-                            Node::Storage(_) => panic!("Storage node not supported on virtual storage."),
+                            Node::Storage(_) => Err(PywrError::StorageNodeOnVirtualStorage {
+                                virtual_storage: vs_node.name().to_string(),
+                                node: node.name().to_string(),
+                            }),
                         }
                     }
                 })
@@ -612,16 +733,38 @@ impl NetworkState {
             state.add_out_flow(flow, timestep);
         }
 
+        // Update the on/off status of nodes with switching constraints
+        for node in model.nodes().iter() {
+            if node.get_switching_constraints().is_none() {
+                continue;
+            }
+
+            match self.node_states.get_mut(*node.index()) {
+                Some(NodeState::Flow(s)) => {
+                    let flow = match node {
+                        Node::Input(_) => s.out_flow,
+                        _ => s.in_flow,
+                    };
+                    s.switch.update(flow);
+                }
+                Some(NodeState::Storage(_)) => return Err(PywrError::FlowConstraintsUndefined),
+                None => return Err(PywrError::NodeIndexNotFound),
+            }
+        }
+
         Ok(())
     }
 
     /// Clamp the volume of `node_index` to be within the bounds provided.
-    fn clamp_node_volume(&mut self, node_index: &NodeIndex, min_volume: f64, max_volume: f64) -> Result<(), PywrError> {
+    fn clamp_node_volume(
+        &mut self,
+        node_index: &NodeIndex,
+        name: &str,
+        min_volume: f64,
+        max_volume: f64,
+    ) -> Result<(), PywrError> {
         match self.node_states.get_mut(*node_index.deref()) {
-            Some(s) => {
-                s.clamp_volume(min_volume, max_volume);
-                Ok(())
-            }
+            Some(s) => s.clamp_volume(name, min_volume, max_volume),
             None => Err(PywrError::NodeIndexNotFound),
         }
     }
@@ -630,14 +773,12 @@ impl NetworkState {
     fn clamp_virtual_storage_node_volume(
         &mut self,
         node_index: &VirtualStorageIndex,
+        name: &str,
         min_volume: f64,
         max_volume: f64,
     ) -> Result<(), PywrError> {
         match self.virtual_storage_states.get_mut(*node_index.deref()) {
-            Some(s) => {
-                s.clamp_volume(min_volume, max_volume);
-                Ok(())
-            }
+            Some(s) => s.clamp_volume(name, min_volume, max_volume),
             None => Err(PywrError::VirtualStorageIndexNotFound(*node_index)),
         }
     }
@@ -655,6 +796,15 @@ impl NetworkState {
         }
     }
 
+    /// Get the on/off status of a node with switching constraints.
+    pub fn get_node_switch_state(&self, node_index: &NodeIndex) -> Result<&SwitchState, PywrError> {
+        match self.node_states.get(*node_index.deref()) {
+            Some(NodeState::Flow(s)) => Ok(&s.switch),
+            Some(NodeState::Storage(_)) => Err(PywrError::FlowConstraintsUndefined),
+            None => Err(PywrError::NodeIndexNotFound),
+        }
+    }
+
     pub fn get_node_volume(&self, node_index: &NodeIndex) -> Result<f64, PywrError> {
         match self.node_states.get(*node_index.deref()) {
             Some(s) => match s {
@@ -675,6 +825,37 @@ impl NetworkState {
         }
     }
 
+    /// Set the dual values of the last solve.
+    pub(crate) fn set_duals(&mut self, duals: DualValues) {
+        self.duals = Some(duals);
+    }
+
+    /// The dual values of the last solve, if they were saved by the solver.
+    pub fn duals(&self) -> Option<&DualValues> {
+        self.duals.as_ref()
+    }
+
+    pub fn get_node_dual(&self, node_index: &NodeIndex) -> Result<f64, PywrError> {
+        self.duals
+            .as_ref()
+            .ok_or(PywrError::DualValueNotAvailable)?
+            .get_node_dual(node_index)
+    }
+
+    pub fn get_aggregated_node_dual(&self, idx: &AggregatedNodeIndex) -> Result<f64, PywrError> {
+        self.duals
+            .as_ref()
+            .ok_or(PywrError::DualValueNotAvailable)?
+            .get_aggregated_node_dual(idx)
+    }
+
+    pub fn get_virtual_storage_dual(&self, idx: &VirtualStorageIndex) -> Result<f64, PywrError> {
+        self.duals
+            .as_ref()
+            .ok_or(PywrError::DualValueNotAvailable)?
+            .get_virtual_storage_dual(idx)
+    }
+
     pub fn get_edge_flow(&self, edge_index: &EdgeIndex) -> Result<f64, PywrError> {
         match self.edge_states.get(*edge_index.deref()) {
             Some(s) => Ok(s.flow),
@@ -683,17 +864,39 @@ impl NetworkState {
     }
 
     pub fn set_volume(&mut self, idx: NodeIndex, volume: f64) -> Result<(), PywrError> {
-        // TODO handle these errors properly
-        if let Some(node_state) = self.node_states.get_mut(*idx.deref()) {
-            match node_state {
-                NodeState::Flow(_) => panic!("Cannot set volume for a non-storage state :("),
-                NodeState::Storage(s) => s.volume = volume,
+        match self.node_states.get_mut(*idx.deref()) {
+            Some(NodeState::Storage(s)) => {
+                s.volume = volume;
+                Ok(())
             }
-        } else {
-            panic!("Node state not found.")
+            Some(NodeState::Flow(_)) => Err(PywrError::StorageConstraintsUndefined),
+            None => Err(PywrError::NodeIndexNotFound),
         }
+    }
 
-        Ok(())
+    /// Set both the inflow and outflow of a flow node; the flows of its edges are not changed.
+    pub fn set_node_flow(&mut self, idx: NodeIndex, flow: f64) -> Result<(), PywrError> {
+        match self.node_states.get_mut(*idx.deref()) {
+            Some(NodeState::Flow(s)) => {
+                s.in_flow = flow;
+                s.out_flow = flow;
+                Ok(())
+            }
+            Some(NodeState::Storage(_)) => Err(PywrError::FlowConstraintsUndefined),
+            None => Err(PywrError::NodeIndexNotFound),
+        }
+    }
+
+    /// Set the volume of a virtual storage node without resetting it (see
+    /// [`Self::reset_virtual_storage_volume`]).
+    pub fn set_virtual_storage_volume(&mut self, idx: VirtualStorageIndex, volume: f64) -> Result<(), PywrError> {
+        match self.virtual_storage_states.get_mut(*idx.deref()) {
+            Some(s) => {
+                s.storage.volume = volume;
+                Ok(())
+            }
+            None => Err(PywrError::VirtualStorageIndexNotFound(idx)),
+        }
     }
 
     pub fn reset_virtual_storage_volume(
@@ -769,20 +972,49 @@ impl NetworkState {
 ///
 /// This struct contains the state of the model simulation at a given point in time. The state
 /// contains the current state of the network, the values of the parameters, the values of the
-/// derived metrics, and the values of the inter-network transfers.
+/// derived metrics, the values of the inter-network transfers and the values supplied by any
+/// external process.
 ///
 /// This struct can be constructed using the [`StateBuilder`] and then updated using the various
-/// methods to set the values of the parameters, derived metrics, and inter-network transfers.
+/// methods to set the values of the parameters, derived metrics, inter-network transfers and
+/// external inputs.
 ///
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct State {
     network: NetworkState,
     parameters: ParameterValuesCollection,
     derived_metrics: Vec<f64>,
+    /// The history of the values of the rolling and season-to-date derived metrics.
+    derived_metric_histories: Vec<DerivedMetricHistory>,
     inter_network_values: Vec<f64>,
+    /// The values sent by the lagged inter-network transfers that are yet to be received.
+    #[serde(default)]
+    inter_network_delays: Vec<VecDeque<f64>>,
+    external_input_values: Vec<f64>,
+    /// The mass-balance residuals of the state overrides in the last time-step.
+    #[serde(default)]
+    state_override_residuals: Vec<f64>,
+    /// The values captured before the last solve, if a recorder requested a trace of it.
+    #[serde(skip)]
+    pre_solve_trace: Option<Box<PreSolveTrace>>,
 }
 
 impl State {
+    /// Returns `true` if `other` was created for a network with the same number of nodes, edges,
+    /// virtual storages, parameters, derived metrics, inter-network transfers and external inputs.
+    ///
+    /// This is used to check that a state (e.g. from a snapshot) can replace this state.
+    pub fn has_same_shape(&self, other: &State) -> bool {
+        self.network.has_same_shape(&other.network)
+            && self.parameters.has_same_shape(&other.parameters)
+            && self.derived_metrics.len() == other.derived_metrics.len()
+            && self.derived_metric_histories.len() == other.derived_metric_histories.len()
+            && self.inter_network_values.len() == other.inter_network_values.len()
+            && self.inter_network_delays.len() == other.inter_network_delays.len()
+            && self.external_input_values.len() == other.external_input_values.len()
+            && self.state_override_residuals.len() == other.state_override_residuals.len()
+    }
+
     pub fn get_network_state(&self) -> &NetworkState {
         &self.network
     }
@@ -791,6 +1023,17 @@ impl State {
         &mut self.network
     }
 
+    /// The values of all the parameters.
+    pub(crate) fn parameter_values(&self) -> &ParameterValuesCollection {
+        &self.parameters
+    }
+
+    /// Replace the values of all the parameters with `values`, which must be from a state of the
+    /// same network.
+    pub(crate) fn set_parameter_values(&mut self, values: ParameterValuesCollection) {
+        self.parameters = values;
+    }
+
     pub fn get_parameter_value(&self, idx: GeneralParameterIndex<f64>) -> Result<f64, PywrError> {
         self.parameters.general.get_value(*idx).map_err(|e| match e {
             ParameterValuesError::IndexNotFound(_) => PywrError::GeneralParameterIndexNotFound(idx),
@@ -964,6 +1207,15 @@ impl State {
         }
     }
 
+    pub fn get_mut_derived_metric_history(
+        &mut self,
+        idx: DerivedMetricIndex,
+    ) -> Result<&mut DerivedMetricHistory, PywrError> {
+        self.derived_metric_histories
+            .get_mut(*idx.deref())
+            .ok_or(PywrError::DerivedMetricIndexNotFound(idx))
+    }
+
     pub fn get_inter_network_transfer_value(&self, idx: MultiNetworkTransferIndex) -> Result<f64, PywrError> {
         match self.inter_network_values.get(*idx.deref()) {
             Some(s) => Ok(*s),
@@ -985,6 +1237,73 @@ impl State {
         }
     }
 
+    /// Delay a value sent by an inter-network transfer by `lag` time-steps.
+    ///
+    /// This should be called once per time-step. It returns the value that was sent `lag`
+    /// time-steps ago, or `None` during the first `lag` time-steps.
+    pub fn delay_inter_network_transfer_value(
+        &mut self,
+        idx: MultiNetworkTransferIndex,
+        value: f64,
+        lag: usize,
+    ) -> Result<Option<f64>, PywrError> {
+        match self.inter_network_delays.get_mut(*idx.deref()) {
+            Some(delay) => {
+                delay.push_back(value);
+                if delay.len() > lag {
+                    Ok(delay.pop_front())
+                } else {
+                    Ok(None)
+                }
+            }
+            None => Err(PywrError::MultiNetworkTransferIndexNotFound(idx)),
+        }
+    }
+
+    pub fn get_external_input_value(&self, idx: ExternalInputIndex) -> Result<f64, PywrError> {
+        match self.external_input_values.get(*idx.deref()) {
+            Some(s) => Ok(*s),
+            None => Err(PywrError::ExternalInputIndexNotFound(idx)),
+        }
+    }
+
+    pub fn set_external_input_value(&mut self, idx: ExternalInputIndex, value: f64) -> Result<(), PywrError> {
+        match self.external_input_values.get_mut(*idx.deref()) {
+            Some(s) => {
+                *s = value;
+                Ok(())
+            }
+            None => Err(PywrError::ExternalInputIndexNotFound(idx)),
+        }
+    }
+
+    pub fn get_state_override_residual(&self, idx: StateOverrideIndex) -> Result<f64, PywrError> {
+        match self.state_override_residuals.get(*idx.deref()) {
+            Some(s) => Ok(*s),
+            None => Err(PywrError::StateOverrideIndexNotFound(idx)),
+        }
+    }
+
+    pub fn set_state_override_residual(&mut self, idx: StateOverrideIndex, value: f64) -> Result<(), PywrError> {
+        match self.state_override_residuals.get_mut(*idx.deref()) {
+            Some(s) => {
+                *s = value;
+                Ok(())
+            }
+            None => Err(PywrError::StateOverrideIndexNotFound(idx)),
+        }
+    }
+
+    /// The values captured before the last solve of this state, if they were requested by a
+    /// recorder (see [`Recorder::traces`](crate::recorders::Recorder::traces)).
+    pub fn pre_solve_trace(&self) -> Option<&PreSolveTrace> {
+        self.pre_solve_trace.as_deref()
+    }
+
+    pub(crate) fn set_pre_solve_trace(&mut self, trace: Option<PreSolveTrace>) {
+        self.pre_solve_trace = trace.map(Box::new);
+    }
+
     /// Complete a timestep after all the flow has been added.
     ///
     /// This final step ensures, once all the flows have been updated, that:
@@ -996,7 +1315,8 @@ impl State {
                 let node_index = node.index();
                 let min_volume = node.get_min_volume(self)?;
                 let max_volume = node.get_max_volume(self)?;
-                self.network.clamp_node_volume(&node_index, min_volume, max_volume)?;
+                self.network
+                    .clamp_node_volume(&node_index, node.name(), min_volume, max_volume)?;
             }
         }
 
@@ -1005,7 +1325,7 @@ impl State {
             let min_volume = node.get_min_volume(self)?;
             let max_volume = node.get_max_volume(self)?;
             self.network
-                .clamp_virtual_storage_node_volume(&node_index, min_volume, max_volume)?;
+                .clamp_virtual_storage_node_volume(&node_index, node.name(), min_volume, max_volume)?;
         }
 
         self.network.update_derived_states(model, timestep)
@@ -1024,6 +1344,8 @@ pub struct StateBuilder {
     num_parameters: Option<ParameterCollectionSize>,
     num_derived_metrics: Option<usize>,
     num_inter_network_values: Option<usize>,
+    num_external_inputs: Option<usize>,
+    num_state_overrides: Option<usize>,
 }
 
 impl StateBuilder {
@@ -1041,6 +1363,8 @@ impl StateBuilder {
             num_parameters: None,
             num_derived_metrics: None,
             num_inter_network_values: None,
+            num_external_inputs: None,
+            num_state_overrides: None,
         }
     }
 
@@ -1068,6 +1392,18 @@ impl StateBuilder {
         self
     }
 
+    /// Add the number of external input values to the builder.
+    pub fn with_external_inputs(mut self, num_external_inputs: usize) -> Self {
+        self.num_external_inputs = Some(num_external_inputs);
+        self
+    }
+
+    /// Add the number of state overrides to the builder.
+    pub fn with_state_overrides(mut self, num_state_overrides: usize) -> Self {
+        self.num_state_overrides = Some(num_state_overrides);
+        self
+    }
+
     /// Build the [`State`] from the builder.
     pub fn build(self) -> State {
         let constant = ParameterValues::new(
@@ -1101,7 +1437,12 @@ impl StateBuilder {
             ),
             parameters,
             derived_metrics: vec![0.0; self.num_derived_metrics.unwrap_or(0)],
+            derived_metric_histories: vec![DerivedMetricHistory::default(); self.num_derived_metrics.unwrap_or(0)],
             inter_network_values: vec![0.0; self.num_inter_network_values.unwrap_or(0)],
+            inter_network_delays: vec![VecDeque::new(); self.num_inter_network_values.unwrap_or(0)],
+            external_input_values: vec![0.0; self.num_external_inputs.unwrap_or(0)],
+            state_override_residuals: vec![0.0; self.num_state_overrides.unwrap_or(0)],
+            pre_solve_trace: None,
         }
     }
 }
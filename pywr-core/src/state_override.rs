@@ -0,0 +1,137 @@
+use crate::metric::MetricF64;
+use crate::network::Network;
+use crate::node::NodeIndex;
+use crate::state::State;
+use crate::virtual_storage::VirtualStorageIndex;
+use crate::PywrError;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub struct StateOverrideIndex(usize);
+
+impl Deref for StateOverrideIndex {
+    type Target = usize;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl StateOverrideIndex {
+    pub fn new(idx: usize) -> Self {
+        Self(idx)
+    }
+}
+
+impl Display for StateOverrideIndex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The part of the network state that is replaced by a [`StateOverride`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StateOverrideTarget {
+    /// The volume of a storage node.
+    NodeVolume(NodeIndex),
+    /// The flow through a link node (e.g. an observed transfer).
+    ///
+    /// Only the flow of the node itself is replaced; the flows of its edges, and therefore of
+    /// the nodes either side of it, are as solved.
+    NodeFlow(NodeIndex),
+    /// The volume of a virtual storage node.
+    VirtualStorageVolume(VirtualStorageIndex),
+}
+
+/// A user-specified adjustment to the network state at the end of each time-step.
+///
+/// Overrides are applied after the solve and before any parameters, derived metrics or
+/// recorders see the state of the time-step. This allows a simulation to replay what actually
+/// happened (e.g. an observed reservoir volume or transfer flow) rather than what the model would have done.
+///
+/// The override replaces the solved value of `target` with `value` in every time-step in which
+/// `active` is non-zero (or in every time-step if `active` is `None`). The difference between
+/// the override and the solved value is the mass-balance residual of the override; it is zero
+/// in time-steps in which the override is not active, and is available via
+/// [`MetricF64::StateOverrideResidual`].
+#[derive(Clone, Debug)]
+pub struct StateOverride {
+    name: String,
+    target: StateOverrideTarget,
+    value: MetricF64,
+    active: Option<MetricF64>,
+}
+
+impl StateOverride {
+    pub fn new(name: &str, target: StateOverrideTarget, value: MetricF64, active: Option<MetricF64>) -> Self {
+        Self {
+            name: name.to_string(),
+            target,
+            value,
+            active,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn target(&self) -> &StateOverrideTarget {
+        &self.target
+    }
+
+    /// Apply the override to `state` returning the mass-balance residual.
+    pub(crate) fn apply(&self, network: &Network, state: &mut State) -> Result<f64, PywrError> {
+        if let Some(active) = &self.active {
+            if active.get_value(network, state)? == 0.0 {
+                return Ok(0.0);
+            }
+        }
+
+        let value = self.value.get_value(network, state)?;
+
+        let current = match &self.target {
+            StateOverrideTarget::NodeVolume(idx) => {
+                let node = network.get_node(idx)?;
+                self.check_volume(value, node.get_min_volume(state)?, node.get_max_volume(state)?)?;
+                let current = state.get_network_state().get_node_volume(idx)?;
+                state.set_node_volume(*idx, value)?;
+                current
+            }
+            StateOverrideTarget::VirtualStorageVolume(idx) => {
+                let node = network.get_virtual_storage_node(idx)?;
+                self.check_volume(value, node.get_min_volume(state)?, node.get_max_volume(state)?)?;
+                let current = state.get_network_state().get_virtual_storage_volume(idx)?;
+                state.get_mut_network_state().set_virtual_storage_volume(*idx, value)?;
+                current
+            }
+            StateOverrideTarget::NodeFlow(idx) => {
+                if value < 0.0 {
+                    return Err(PywrError::StateOverrideNegativeFlow {
+                        name: self.name.clone(),
+                        value,
+                    });
+                }
+                let current = state.get_network_state().get_node_in_flow(idx)?;
+                state.get_mut_network_state().set_node_flow(*idx, value)?;
+                current
+            }
+        };
+
+        Ok(value - current)
+    }
+
+    fn check_volume(&self, value: f64, min_volume: f64, max_volume: f64) -> Result<(), PywrError> {
+        if !(min_volume..=max_volume).contains(&value) {
+            return Err(PywrError::StateOverrideOutOfBounds {
+                name: self.name.clone(),
+                value,
+                min_volume,
+                max_volume,
+            });
+        }
+        Ok(())
+    }
+}
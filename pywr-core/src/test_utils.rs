@@ -1,5 +1,5 @@
 use crate::metric::MetricF64;
-use crate::models::{Model, ModelDomain};
+use crate::models::{Model, ModelDomain, RandomModelBuilder};
 /// Utilities for unit tests.
 /// TODO move this to its own local crate ("test-utilities") as part of a workspace.
 use crate::network::Network;
@@ -11,6 +11,8 @@ use crate::scenario::ScenarioGroupCollection;
 use crate::solvers::CbcSolver;
 #[cfg(feature = "ipm-ocl")]
 use crate::solvers::ClIpmF64Solver;
+#[cfg(feature = "gurobi")]
+use crate::solvers::GurobiSolver;
 #[cfg(feature = "highs")]
 use crate::solvers::HighsSolver;
 #[cfg(any(feature = "ipm-simd", feature = "ipm-ocl"))]
@@ -24,7 +26,6 @@ use chrono::{Days, NaiveDate};
 use float_cmp::{approx_eq, F64Margin};
 use ndarray::{Array, Array2};
 use rand::Rng;
-use rand_distr::{Distribution, Normal};
 use std::path::PathBuf;
 
 pub fn default_timestepper() -> Timestepper {
@@ -226,6 +227,17 @@ pub fn run_all_solvers(
         }
     }
 
+    #[cfg(feature = "gurobi")]
+    {
+        if !solvers_to_skip.contains(&"gurobi") {
+            check_features_and_run::<GurobiSolver>(
+                model,
+                !solvers_without_features.contains(&"gurobi"),
+                expected_outputs,
+            );
+        }
+    }
+
     #[cfg(feature = "ipm-simd")]
     {
         if !solvers_to_skip.contains(&"ipm-simd") {
@@ -297,142 +309,18 @@ where
     }
 }
 
-/// Make a simple system with random inputs.
-fn make_simple_system<R: Rng>(
-    network: &mut Network,
-    suffix: &str,
-    num_timesteps: usize,
-    num_inflow_scenarios: usize,
-    inflow_scenario_group_index: usize,
-    rng: &mut R,
-) -> Result<(), PywrError> {
-    let input_idx = network.add_input_node("input", Some(suffix))?;
-    let link_idx = network.add_link_node("link", Some(suffix))?;
-    let output_idx = network.add_output_node("output", Some(suffix))?;
-
-    network.connect_nodes(input_idx, link_idx)?;
-    network.connect_nodes(link_idx, output_idx)?;
-
-    let inflow_distr: Normal<f64> = Normal::new(9.0, 1.0).unwrap();
-
-    let mut inflow = ndarray::Array2::zeros((num_timesteps, num_inflow_scenarios));
-
-    for x in inflow.iter_mut() {
-        *x = inflow_distr.sample(rng).max(0.0);
-    }
-    let inflow = Array2Parameter::new(
-        format!("inflow-{suffix}").as_str().into(),
-        inflow,
-        inflow_scenario_group_index,
-        None,
-    );
-    let idx = network.add_simple_parameter(Box::new(inflow))?;
-
-    network.set_node_max_flow("input", Some(suffix), Some(idx.into()))?;
-
-    let input_cost = rng.gen_range(-20.0..-5.00);
-    network.set_node_cost("input", Some(suffix), Some(input_cost.into()))?;
-
-    let outflow_distr = Normal::new(8.0, 3.0).unwrap();
-    let mut outflow: f64 = outflow_distr.sample(rng);
-    outflow = outflow.max(0.0);
-
-    network.set_node_max_flow("output", Some(suffix), Some(outflow.into()))?;
-
-    network.set_node_cost("output", Some(suffix), Some((-500.0).into()))?;
-
-    Ok(())
-}
-
-/// Make a simple connections between random systems
+/// Make a random model using a seed drawn from `rng`.
 ///
-///
-fn make_simple_connections<R: Rng>(
-    model: &mut Network,
-    num_systems: usize,
-    density: usize,
-    rng: &mut R,
-) -> Result<(), PywrError> {
-    let num_connections = (num_systems.pow(2) * density / 100 / 2).max(1);
-
-    let mut connections_added: usize = 0;
-
-    while connections_added < num_connections {
-        let i = rng.gen_range(0..num_systems);
-        let j = rng.gen_range(0..num_systems);
-
-        if i == j {
-            continue;
-        }
-
-        let name = format!("{i:04}->{j:04}");
-
-        if let Ok(idx) = model.add_link_node("transfer", Some(&name)) {
-            let transfer_cost = rng.gen_range(0.0..1.0);
-            model.set_node_cost("transfer", Some(&name), Some(transfer_cost.into()))?;
-
-            let from_suffix = format!("sys-{i:04}");
-            let from_idx = model.get_node_index_by_name("link", Some(&from_suffix))?;
-            let to_suffix = format!("sys-{j:04}");
-            let to_idx = model.get_node_index_by_name("link", Some(&to_suffix))?;
-
-            model.connect_nodes(from_idx, idx)?;
-            model.connect_nodes(idx, to_idx)?;
-
-            connections_added += 1;
-        }
-    }
-
-    Ok(())
-}
-
+/// See [`RandomModelBuilder`] for more control over the generated model.
 pub fn make_random_model<R: Rng>(
     num_systems: usize,
     density: usize,
     num_scenarios: usize,
     rng: &mut R,
 ) -> Result<Model, PywrError> {
-    let start = NaiveDate::from_ymd_opt(2020, 1, 1)
-        .unwrap()
-        .and_hms_opt(0, 0, 0)
-        .unwrap();
-    let end = NaiveDate::from_ymd_opt(2020, 4, 9)
-        .unwrap()
-        .and_hms_opt(0, 0, 0)
-        .unwrap();
-    let duration = TimestepDuration::Days(1);
-    let timestepper = Timestepper::new(start, end, duration);
-
-    let mut scenario_collection = ScenarioGroupCollection::default();
-    scenario_collection.add_group("test-scenario", num_scenarios);
-
-    let domain = ModelDomain::from(timestepper, scenario_collection).unwrap();
-
-    let inflow_scenario_group_index = domain
-        .scenarios()
-        .group_index("test-scenario")
-        .expect("Could not find scenario group.");
-
-    let (num_timesteps, num_inflow_scenarios) = domain.shape();
-
-    let mut network = Network::default();
-    for i in 0..num_systems {
-        let suffix = format!("sys-{i:04}");
-        make_simple_system(
-            &mut network,
-            &suffix,
-            num_timesteps,
-            num_inflow_scenarios,
-            inflow_scenario_group_index,
-            rng,
-        )?;
-    }
-
-    make_simple_connections(&mut network, num_systems, density, rng)?;
-
-    let model = Model::new(domain, network);
-
-    Ok(model)
+    RandomModelBuilder::new(num_systems, density, num_scenarios)
+        .seed(rng.gen())
+        .build()
 }
 
 #[cfg(all(test, feature = "ipm-simd"))]
@@ -1,10 +1,11 @@
 use crate::PywrError;
 use chrono::Datelike;
-use chrono::{Months, NaiveDateTime, TimeDelta};
+use chrono::{Months, NaiveDateTime, TimeDelta, Weekday};
 use polars::datatypes::TimeUnit;
 use polars::time::ClosedWindow;
 #[cfg(feature = "pyo3")]
 use pyo3::pyclass;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::ops::Add;
 
 const SECS_IN_DAY: i64 = 60 * 60 * 24;
@@ -102,10 +103,30 @@ impl PywrDuration {
     }
 }
 
+/// The duration is serialized as a whole number of milliseconds.
+impl Serialize for PywrDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(self.milliseconds())
+    }
+}
+
+impl<'de> Deserialize<'de> for PywrDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let milliseconds = i64::deserialize(deserializer)?;
+        Ok(Self(TimeDelta::milliseconds(milliseconds)))
+    }
+}
+
 pub type TimestepIndex = usize;
 
 #[cfg_attr(feature = "pyo3", pyclass)]
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Timestep {
     pub date: NaiveDateTime,
     pub index: TimestepIndex,
@@ -154,6 +175,60 @@ impl Add<PywrDuration> for Timestep {
 pub enum TimestepDuration {
     Days(i64),
     Frequency(String),
+    /// Weekly time-steps that start on the given day of the week.
+    ///
+    /// If the start date is a different day of the week, the first time-step is shorter and
+    /// ends on the first `weekday` after the start date.
+    Weekly(Weekday),
+    /// Time-steps that start on each of the given dates between the start and end dates. Each
+    /// time-step ends at the next date, so the last date is the end of the final time-step.
+    Dates(Vec<NaiveDateTime>),
+}
+
+/// A period of time that is not simulated.
+///
+/// Any time-step that starts between `start` and `end` (inclusive) is excluded from the time
+/// domain.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ExcludedPeriod {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl ExcludedPeriod {
+    pub fn new(start: NaiveDateTime, end: NaiveDateTime) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns true if `date` is within the period.
+    pub fn contains(&self, date: NaiveDateTime) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+/// A window of days within each year, from the `first` to the `last` day (inclusive).
+///
+/// The days are given as `(month, day)`. The window wraps around the end of the year if its last
+/// day is before its first (e.g. from the 1st November to the 28th February).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DayOfYearWindow {
+    first: (u32, u32),
+    last: (u32, u32),
+}
+
+impl DayOfYearWindow {
+    pub fn new(first: (u32, u32), last: (u32, u32)) -> Self {
+        Self { first, last }
+    }
+
+    pub(crate) fn contains(&self, date: &NaiveDateTime) -> bool {
+        let day = (date.month(), date.day());
+        if self.first <= self.last {
+            self.first <= day && day <= self.last
+        } else {
+            day >= self.first || day <= self.last
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -161,19 +236,71 @@ pub struct Timestepper {
     start: NaiveDateTime,
     end: NaiveDateTime,
     timestep: TimestepDuration,
+    excluded_periods: Vec<ExcludedPeriod>,
 }
 
 impl Timestepper {
     pub fn new(start: NaiveDateTime, end: NaiveDateTime, timestep: TimestepDuration) -> Self {
-        Self { start, end, timestep }
+        Self {
+            start,
+            end,
+            timestep,
+            excluded_periods: Vec::new(),
+        }
+    }
+
+    /// Exclude the time-steps that start within any of the given periods from the simulation.
+    ///
+    /// The excluded time-steps are removed from the time domain entirely; the remaining
+    /// time-steps keep their duration and are re-indexed contiguously. The state of the model
+    /// (e.g. the volume of storage nodes) is carried over unchanged from the last time-step before
+    /// a gap to the first time-step after it, and recorders only see the simulated time-steps.
+    pub fn with_excluded_periods(mut self, excluded_periods: Vec<ExcludedPeriod>) -> Self {
+        self.excluded_periods = excluded_periods;
+        self
     }
 
     /// Create a vector of `Timestep`s between the start and end dates at the given duration.
     fn timesteps(&self) -> Result<Vec<Timestep>, PywrError> {
-        match &self.timestep {
-            TimestepDuration::Days(days) => Ok(self.generate_timesteps_from_days(*days)),
-            TimestepDuration::Frequency(frequency) => self.generate_timesteps_from_frequency(frequency.as_str()),
+        self.exclude_periods(self.all_timesteps()?)
+    }
+
+    /// Create a vector of all the `Timestep`s between the start and end dates, including those
+    /// within any excluded periods.
+    fn all_timesteps(&self) -> Result<Vec<Timestep>, PywrError> {
+        let timesteps = match &self.timestep {
+            TimestepDuration::Days(days) => self.generate_timesteps_from_days(*days),
+            TimestepDuration::Frequency(frequency) => self.generate_timesteps_from_frequency(frequency.as_str())?,
+            TimestepDuration::Weekly(weekday) => self.generate_weekly_timesteps(*weekday),
+            TimestepDuration::Dates(dates) => self.generate_timesteps_from_dates(dates)?,
+        };
+
+        Ok(timesteps)
+    }
+
+    /// Returns true if a time-step starting at `date` is excluded from the simulation.
+    fn is_excluded(&self, date: NaiveDateTime) -> bool {
+        self.excluded_periods.iter().any(|p| p.contains(date))
+    }
+
+    /// Remove the time-steps within the excluded periods and re-index those that remain.
+    fn exclude_periods(&self, timesteps: Vec<Timestep>) -> Result<Vec<Timestep>, PywrError> {
+        if let Some(period) = self.excluded_periods.iter().find(|p| p.end < p.start) {
+            return Err(PywrError::InvalidExcludedPeriod(period.start, period.end));
         }
+
+        let timesteps: Vec<Timestep> = timesteps
+            .into_iter()
+            .filter(|t| !self.is_excluded(t.date))
+            .enumerate()
+            .map(|(index, t)| Timestep::new(t.date, index, t.duration))
+            .collect();
+
+        if timesteps.is_empty() {
+            return Err(PywrError::NoTimesteps);
+        }
+
+        Ok(timesteps)
     }
 
     /// Creates a vector of `Timestep`s between the start and end dates at the given duration of days.
@@ -227,29 +354,70 @@ impl Timestepper {
         .map(|x| x.ok_or(PywrError::TimestepGenerationError(frequency.to_string())))
         .collect::<Result<Vec<NaiveDateTime>, PywrError>>()?;
 
-        let timesteps = dates
-            .windows(2)
-            .enumerate()
-            .map(|(i, dates)| {
-                let duration = dates[1] - dates[0];
-                Timestep::new(dates[0], i, duration.into())
-            })
-            .collect::<Vec<Timestep>>();
+        Ok(timesteps_between(&dates))
+    }
 
-        Ok(timesteps)
+    /// Creates a vector of weekly `Timestep`s that start on `weekday`.
+    fn generate_weekly_timesteps(&self, weekday: Weekday) -> Vec<Timestep> {
+        let days_to_weekday = (weekday.num_days_from_monday() + 7 - self.start.weekday().num_days_from_monday()) % 7;
+        let mut next = match days_to_weekday {
+            0 => self.start + TimeDelta::weeks(1),
+            d => self.start + TimeDelta::days(d as i64),
+        };
+
+        let mut dates = vec![self.start];
+        while next <= self.end {
+            dates.push(next);
+            next += TimeDelta::weeks(1);
+        }
+        // The end of the last time-step
+        dates.push(next);
+
+        timesteps_between(&dates)
     }
+
+    /// Creates a vector of `Timestep`s that start on the given dates between the start and end dates.
+    fn generate_timesteps_from_dates(&self, dates: &[NaiveDateTime]) -> Result<Vec<Timestep>, PywrError> {
+        if dates.len() < 2 || dates.windows(2).any(|d| d[0] >= d[1]) {
+            return Err(PywrError::InvalidTimestepDates);
+        }
+
+        let first = dates.partition_point(|d| *d < self.start);
+        let last = dates.partition_point(|d| *d <= self.end).min(dates.len() - 1);
+        if first >= last {
+            return Err(PywrError::NoTimesteps);
+        }
+
+        Ok(timesteps_between(&dates[first..=last]))
+    }
+}
+
+/// Create a `Timestep` between each consecutive pair of dates.
+fn timesteps_between(dates: &[NaiveDateTime]) -> Vec<Timestep> {
+    dates
+        .windows(2)
+        .enumerate()
+        .map(|(i, dates)| {
+            let duration = dates[1] - dates[0];
+            Timestep::new(dates[0], i, duration.into())
+        })
+        .collect()
 }
 
 /// The time domain that a model will be simulated over.
 #[derive(Debug)]
 pub struct TimeDomain {
     timesteps: Vec<Timestep>,
-    duration: PywrDuration,
+    duration: Option<PywrDuration>,
+    excluded_periods: Vec<ExcludedPeriod>,
+    /// The index of each time-step among all the time-steps, including the excluded ones.
+    full_indices: Vec<usize>,
 }
 
 impl TimeDomain {
-    /// Return the duration of each time-step.
-    pub fn step_duration(&self) -> PywrDuration {
+    /// Return the duration of each time-step, or `None` if the time-steps have different
+    /// durations (e.g. monthly time-steps).
+    pub fn step_duration(&self) -> Option<PywrDuration> {
         self.duration
     }
 
@@ -257,6 +425,28 @@ impl TimeDomain {
         &self.timesteps
     }
 
+    /// The periods that are excluded from the simulation.
+    pub fn excluded_periods(&self) -> &[ExcludedPeriod] {
+        &self.excluded_periods
+    }
+
+    /// The index of each time-step among all the time-steps between the start and end dates,
+    /// including those within the excluded periods.
+    ///
+    /// Data with a row for every time-step between the start and end dates (e.g. an array in a
+    /// table) should be indexed with these rather than [`Timestep::index`].
+    pub fn full_indices(&self) -> &[usize] {
+        &self.full_indices
+    }
+
+    /// Returns true if there is an excluded period between the given time-step and the one before it.
+    pub fn follows_gap(&self, timestep: &Timestep) -> bool {
+        match timestep.index.checked_sub(1).and_then(|i| self.timesteps.get(i)) {
+            Some(previous) => previous.date + previous.duration.0 < timestep.date,
+            None => false,
+        }
+    }
+
     /// The total number of time-steps in the domain.
     pub fn len(&self) -> usize {
         self.timesteps.len()
@@ -279,22 +469,34 @@ impl TryFrom<Timestepper> for TimeDomain {
     type Error = PywrError;
 
     fn try_from(value: Timestepper) -> Result<Self, Self::Error> {
-        let timesteps = value.timesteps()?;
-        let duration = timesteps.first().expect("No time-steps defined.").duration;
-        match timesteps.iter().all(|t| t.duration == duration) {
-            true => Ok(Self { timesteps, duration }),
-            false => Err(PywrError::TimestepDurationMismatch),
-        }
+        let all_timesteps = value.all_timesteps()?;
+        let full_indices = all_timesteps
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| !value.is_excluded(t.date))
+            .map(|(i, _)| i)
+            .collect();
+
+        let timesteps = value.exclude_periods(all_timesteps)?;
+        let first = timesteps.first().expect("No time-steps defined.").duration;
+        let duration = timesteps.iter().all(|t| t.duration == first).then_some(first);
+
+        Ok(Self {
+            timesteps,
+            duration,
+            excluded_periods: value.excluded_periods,
+            full_indices,
+        })
     }
 }
 
 #[cfg(test)]
 mod test {
-    use chrono::{NaiveDateTime, TimeDelta};
+    use chrono::{Datelike, NaiveDateTime, TimeDelta, Weekday};
 
     use crate::timestep::{is_leap_year, PywrDuration, SECS_IN_DAY};
 
-    use super::{TimestepDuration, Timestepper};
+    use super::{ExcludedPeriod, TimeDomain, TimestepDuration, Timestepper};
 
     #[test]
     fn test_days() {
@@ -344,6 +546,57 @@ mod test {
         assert_eq!(timesteps[1].duration, TimeDelta::days(28));
         assert_eq!(timesteps[2].duration, TimeDelta::days(31));
         assert_eq!(timesteps[3].duration, TimeDelta::days(30));
+
+        // The time domain has time-steps of different durations
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Frequency(String::from("1mo")));
+        let domain = TimeDomain::try_from(timestepper).unwrap();
+        assert_eq!(domain.len(), 4);
+        assert!(domain.step_duration().is_none());
+    }
+
+    #[test]
+    fn test_weekly() {
+        // 2021-01-01 is a Friday
+        let start = NaiveDateTime::parse_from_str("2021-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let end = NaiveDateTime::parse_from_str("2021-01-20 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Weekly(Weekday::Mon));
+        let timesteps = timestepper.timesteps().unwrap();
+        assert_eq!(timesteps.len(), 4);
+        assert_eq!(timesteps[0].duration, TimeDelta::days(3));
+        assert!(timesteps[1..].iter().all(|t| t.date.weekday() == Weekday::Mon));
+        assert!(timesteps[1..].iter().all(|t| t.duration == TimeDelta::days(7)));
+
+        // Starting on the weekday gives whole weeks
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Weekly(Weekday::Fri));
+        let timesteps = timestepper.timesteps().unwrap();
+        assert_eq!(timesteps.len(), 3);
+        assert!(timesteps.iter().all(|t| t.duration == TimeDelta::days(7)));
+    }
+
+    #[test]
+    fn test_dates() {
+        let dates: Vec<NaiveDateTime> = ["2020-12-01", "2021-01-01", "2021-01-11", "2021-02-01", "2021-03-01"]
+            .iter()
+            .map(|d| NaiveDateTime::parse_from_str(&format!("{d} 00:00:00"), "%Y-%m-%d %H:%M:%S").unwrap())
+            .collect();
+        let start = dates[1];
+        let end = NaiveDateTime::parse_from_str("2021-02-15 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Dates(dates.clone()));
+        let timesteps = timestepper.timesteps().unwrap();
+        assert_eq!(timesteps.len(), 3);
+        assert_eq!(timesteps[0].date, start);
+        assert_eq!(timesteps[0].duration, TimeDelta::days(10));
+        assert_eq!(timesteps[1].duration, TimeDelta::days(21));
+        assert_eq!(timesteps[2].duration, TimeDelta::days(28));
+        assert!(timesteps.iter().enumerate().all(|(i, t)| t.index == i));
+
+        // The dates must be increasing
+        let mut unordered = dates.clone();
+        unordered.swap(1, 2);
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Dates(unordered));
+        assert!(timestepper.timesteps().is_err());
     }
 
     #[test]
@@ -359,6 +612,44 @@ mod test {
         assert_eq!(timesteps.last().unwrap().duration, TimeDelta::hours(1));
     }
 
+    #[test]
+    fn test_excluded_periods() {
+        let start = NaiveDateTime::parse_from_str("2020-02-25 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let end = NaiveDateTime::parse_from_str("2020-03-10 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let leap_day = NaiveDateTime::parse_from_str("2020-02-29 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let gap_start = NaiveDateTime::parse_from_str("2020-03-05 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let gap_end = NaiveDateTime::parse_from_str("2020-03-07 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Days(1)).with_excluded_periods(vec![
+            ExcludedPeriod::new(leap_day, leap_day),
+            ExcludedPeriod::new(gap_start, gap_end),
+        ]);
+        let domain = TimeDomain::try_from(timestepper).unwrap();
+        let timesteps = domain.timesteps();
+
+        assert_eq!(timesteps.len(), 11);
+        assert!(timesteps.iter().all(|t| t.duration == TimeDelta::days(1)));
+        assert!(timesteps.iter().enumerate().all(|(i, t)| t.index == i));
+        assert!(timesteps.iter().all(|t| t.date != leap_day));
+        // The time-step after the leap day is 1st March
+        assert_eq!(timesteps[4].date.format("%m-%d").to_string(), "03-01");
+        assert!(domain.follows_gap(&timesteps[4]));
+        assert!(!domain.follows_gap(&timesteps[3]));
+        assert_eq!(timesteps[8].date.format("%m-%d").to_string(), "03-08");
+        assert!(domain.follows_gap(&timesteps[8]));
+        assert_eq!(domain.full_indices(), &[0, 1, 2, 3, 5, 6, 7, 8, 12, 13, 14]);
+
+        // A period that ends before it starts is invalid
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Days(1))
+            .with_excluded_periods(vec![ExcludedPeriod::new(gap_end, gap_start)]);
+        assert!(TimeDomain::try_from(timestepper).is_err());
+
+        // Excluding every time-step is an error
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Days(1))
+            .with_excluded_periods(vec![ExcludedPeriod::new(start, end)]);
+        assert!(TimeDomain::try_from(timestepper).is_err());
+    }
+
     #[test]
     fn test_pywr_duration() {
         let duration = PywrDuration::days(5);
@@ -74,10 +74,12 @@ pub struct VirtualStorageBuilder {
     nodes: Vec<NodeIndex>,
     factors: Option<Vec<f64>>,
     initial_volume: StorageInitialVolume,
+    reset_volume: Option<StorageInitialVolume>,
     min_volume: Option<SimpleMetricF64>,
     max_volume: Option<SimpleMetricF64>,
     reset: VirtualStorageReset,
     rolling_window: Option<NonZeroUsize>,
+    licence_profile: Option<LicenceProfile>,
     cost: Option<MetricF64>,
 }
 
@@ -89,10 +91,12 @@ impl VirtualStorageBuilder {
             nodes: nodes.to_vec(),
             factors: None,
             initial_volume: StorageInitialVolume::Absolute(0.0),
+            reset_volume: None,
             min_volume: None,
             max_volume: None,
             reset: VirtualStorageReset::Never,
             rolling_window: None,
+            licence_profile: None,
             cost: None,
         }
     }
@@ -112,6 +116,14 @@ impl VirtualStorageBuilder {
         self
     }
 
+    /// The volume the virtual storage is set to when it is reset after the first time-step.
+    ///
+    /// If not given the initial volume is also used at each reset.
+    pub fn reset_volume(mut self, reset_volume: StorageInitialVolume) -> Self {
+        self.reset_volume = Some(reset_volume);
+        self
+    }
+
     pub fn min_volume(mut self, min_volume: Option<SimpleMetricF64>) -> Self {
         self.min_volume = min_volume;
         self
@@ -132,6 +144,11 @@ impl VirtualStorageBuilder {
         self
     }
 
+    pub fn licence_profile(mut self, licence_profile: LicenceProfile) -> Self {
+        self.licence_profile = Some(licence_profile);
+        self
+    }
+
     pub fn cost(mut self, cost: Option<MetricF64>) -> Self {
         self.cost = cost;
         self
@@ -146,9 +163,11 @@ impl VirtualStorageBuilder {
             nodes: self.nodes,
             factors,
             initial_volume: self.initial_volume,
+            reset_volume: self.reset_volume,
             storage_constraints: StorageConstraints::new(self.min_volume, self.max_volume),
             reset: self.reset,
             rolling_window: self.rolling_window,
+            licence_profile: self.licence_profile,
             cost: self.cost,
         }
     }
@@ -160,6 +179,41 @@ pub enum VirtualStorageReset {
     NumberOfMonths { months: i32 },
 }
 
+/// A profile of the maximum cumulative use of a [`VirtualStorage`] since it was last reset.
+///
+/// Each value is the proportion of the maximum volume that may have been used by the end of
+/// the corresponding period, counted from the date of the last reset. The values should
+/// therefore be non-decreasing and between zero and one. If the model runs beyond the end of
+/// the profile the last value continues to apply.
+///
+/// This reflects how many abstraction licences are written; for example, an annual licence
+/// may only permit 10% of its volume to be taken in the first month, 20% by the end of the
+/// second month, and so on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LicenceProfile {
+    /// A value for each week (i.e. seven days) since the last reset.
+    Weekly(Vec<f64>),
+    /// A value for each month since the last reset.
+    Monthly([f64; 12]),
+}
+
+impl LicenceProfile {
+    /// The proportion of the maximum volume that may have been used by `current` since the
+    /// virtual storage was last reset on `last_reset`.
+    fn cumulative_proportion(&self, current: &NaiveDateTime, last_reset: &NaiveDateTime) -> f64 {
+        match self {
+            Self::Weekly(values) => {
+                let week = ((*current - *last_reset).num_days() / 7).max(0) as usize;
+                values.get(week).or(values.last()).copied().unwrap_or(1.0)
+            }
+            Self::Monthly(values) => {
+                let month = months_since_last_reset(current, last_reset).clamp(0, 11) as usize;
+                values[month]
+            }
+        }
+    }
+}
+
 /// A component that represents a virtual storage constraint.
 ///
 /// Virtual storage are not part of the main network but can have their volume "used" by
@@ -171,14 +225,19 @@ pub enum VirtualStorageReset {
 /// for the choices. In addition, a rolling window can be provided as a number of time-steps.
 /// Volume is recovered into the virtual storage after this number of time-steps once per time-step
 /// with the oldest value added back to the volume.
+///
+/// Optionally, a [`LicenceProfile`] can limit the cumulative use of the volume through the
+/// period following each reset. This is applied in addition to the minimum volume.
 pub struct VirtualStorage {
     meta: NodeMeta<VirtualStorageIndex>,
     nodes: Vec<NodeIndex>,
     factors: Vec<f64>,
     initial_volume: StorageInitialVolume,
+    reset_volume: Option<StorageInitialVolume>,
     storage_constraints: StorageConstraints,
     reset: VirtualStorageReset,
     rolling_window: Option<NonZeroUsize>,
+    licence_profile: Option<LicenceProfile>,
     cost: Option<MetricF64>,
 }
 
@@ -240,11 +299,7 @@ impl VirtualStorage {
 
         if do_reset {
             let max_volume = self.get_max_volume(state)?;
-            // Determine the initial volume
-            let volume = match &self.initial_volume {
-                StorageInitialVolume::Absolute(iv) => *iv,
-                StorageInitialVolume::Proportional(ipc) => max_volume * ipc,
-            };
+            let volume = self.get_reset_volume(timestep, max_volume);
 
             // Reset the volume
             state.reset_virtual_storage_node_volume(*self.meta.index(), volume, timestep)?;
@@ -281,9 +336,51 @@ impl VirtualStorage {
             .get_max_volume(&state.get_simple_parameter_values())
     }
 
-    pub fn get_available_volume_bounds(&self, state: &State) -> Result<(f64, f64), PywrError> {
-        let min_vol = self.get_min_volume(state)?;
+    /// The volume the virtual storage is set to when it is reset at `timestep`.
+    ///
+    /// The initial volume is used on the first time-step, and the reset volume (if defined)
+    /// thereafter.
+    fn get_reset_volume(&self, timestep: &Timestep, max_volume: f64) -> f64 {
+        let volume = match &self.reset_volume {
+            Some(reset_volume) if !timestep.is_first() => reset_volume,
+            _ => &self.initial_volume,
+        };
+
+        match volume {
+            StorageInitialVolume::Absolute(iv) => *iv,
+            StorageInitialVolume::Proportional(ipc) => max_volume * ipc,
+        }
+    }
+
+    /// The minimum volume at `timestep` implied by the licence profile, if one is defined.
+    ///
+    /// This is the volume at the last reset less the cumulative use permitted by the profile.
+    fn get_licence_profile_min_volume(
+        &self,
+        timestep: &Timestep,
+        max_volume: f64,
+        state: &State,
+    ) -> Result<Option<f64>, PywrError> {
+        let profile = match &self.licence_profile {
+            Some(profile) => profile,
+            None => return Ok(None),
+        };
+
+        let last_reset = match state.get_network_state().get_virtual_storage_last_reset(self.index())? {
+            Some(last_reset) => *last_reset,
+            None => *timestep,
+        };
+
+        let permitted_use = max_volume * profile.cumulative_proportion(&timestep.date, &last_reset.date);
+        Ok(Some(self.get_reset_volume(&last_reset, max_volume) - permitted_use))
+    }
+
+    pub fn get_available_volume_bounds(&self, timestep: &Timestep, state: &State) -> Result<(f64, f64), PywrError> {
         let max_vol = self.get_max_volume(state)?;
+        let min_vol = match self.get_licence_profile_min_volume(timestep, max_vol, state)? {
+            Some(profile_min_vol) => self.get_min_volume(state)?.max(profile_min_vol),
+            None => self.get_min_volume(state)?,
+        };
 
         let current_volume = state.get_network_state().get_virtual_storage_volume(&self.index())?;
 
@@ -310,7 +407,7 @@ mod tests {
     use crate::scenario::ScenarioIndex;
     use crate::test_utils::{default_timestepper, run_all_solvers, simple_model};
     use crate::timestep::{Timestep, TimestepDuration, Timestepper};
-    use crate::virtual_storage::{months_since_last_reset, VirtualStorageBuilder, VirtualStorageReset};
+    use crate::virtual_storage::{months_since_last_reset, LicenceProfile, VirtualStorageBuilder, VirtualStorageReset};
     use chrono::{Datelike, NaiveDate};
     use ndarray::Array;
     use std::num::NonZeroUsize;
@@ -570,4 +667,114 @@ mod tests {
         // Test all solvers
         run_all_solvers(&model, &["ipm-ocl", "ipm-simd"], &[], &[]);
     }
+
+    #[test]
+    /// Test virtual storage licence profile limits the cumulative use since the last reset
+    fn test_virtual_storage_node_licence_profile() {
+        let mut network = Network::default();
+
+        let input_node = network.add_input_node("input", None).unwrap();
+        let link_node = network.add_link_node("link", None).unwrap();
+        let output_node = network.add_output_node("output", None).unwrap();
+
+        network.connect_nodes(input_node, link_node).unwrap();
+        network.connect_nodes(link_node, output_node).unwrap();
+
+        // Only 5% of the licence can be used in the first week, 10% by the end of the second
+        // week and 15% by the end of the third week.
+        let vs_builder = VirtualStorageBuilder::new("licence", &[link_node])
+            .initial_volume(StorageInitialVolume::Proportional(1.0))
+            .min_volume(Some(0.0.into()))
+            .max_volume(Some(1000.0.into()))
+            .reset(VirtualStorageReset::Never)
+            .licence_profile(LicenceProfile::Weekly(vec![0.05, 0.10, 0.15]));
+
+        let vs_idx = network.add_virtual_storage_node(vs_builder).unwrap();
+
+        let output_node = network.get_mut_node_by_name("output", None).unwrap();
+        output_node.set_max_flow_constraint(Some(10.0.into())).unwrap();
+        output_node.set_cost(Some((-10.0).into()));
+
+        // The demand of 10 is met until the weekly cumulative limit of 50 is reached
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| {
+            if ts.index % 7 < 5 {
+                10.0
+            } else {
+                0.0
+            }
+        };
+        let recorder = AssertionFnRecorder::new("link-flow", MetricF64::NodeOutFlow(link_node), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let expected_vol = |ts: &Timestep, _si: &ScenarioIndex| {
+            let weeks = (ts.index / 7) as f64;
+            let days = (ts.index % 7 + 1).min(5) as f64;
+            1000.0 - weeks * 50.0 - days * 10.0
+        };
+        let recorder = AssertionFnRecorder::new(
+            "vs-volume",
+            MetricF64::VirtualStorageVolume(vs_idx),
+            expected_vol,
+            None,
+            None,
+        );
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let domain = default_timestepper().try_into().unwrap();
+        let model = Model::new(domain, network);
+        // Test all solvers
+        run_all_solvers(&model, &["ipm-ocl", "ipm-simd"], &[], &[]);
+    }
+
+    #[test]
+    /// Test virtual storage is reset to the reset volume rather than its initial volume
+    fn test_virtual_storage_node_reset_volume() {
+        let mut network = Network::default();
+
+        let input_node = network.add_input_node("input", None).unwrap();
+        let link_node = network.add_link_node("link", None).unwrap();
+        let output_node = network.add_output_node("output", None).unwrap();
+
+        network.connect_nodes(input_node, link_node).unwrap();
+        network.connect_nodes(link_node, output_node).unwrap();
+
+        // The virtual storage starts part used, but is reset to full at the start of each month.
+        let vs_builder = VirtualStorageBuilder::new("licence", &[link_node])
+            .initial_volume(StorageInitialVolume::Absolute(30.0))
+            .reset_volume(StorageInitialVolume::Proportional(1.0))
+            .min_volume(Some(0.0.into()))
+            .max_volume(Some(100.0.into()))
+            .reset(VirtualStorageReset::NumberOfMonths { months: 1 });
+
+        network.add_virtual_storage_node(vs_builder).unwrap();
+
+        let output_node = network.get_mut_node_by_name("output", None).unwrap();
+        output_node.set_max_flow_constraint(Some(10.0.into())).unwrap();
+        output_node.set_cost(Some((-10.0).into()));
+
+        // The initial 30 is used in the first three days of January; the full 100 is then
+        // available from the 1st February.
+        let expected = |ts: &Timestep, _si: &ScenarioIndex| {
+            if ts.date.month() == 1 && ts.index >= 3 {
+                0.0
+            } else {
+                10.0
+            }
+        };
+        let recorder = AssertionFnRecorder::new("link-flow", MetricF64::NodeOutFlow(link_node), expected, None, None);
+        network.add_recorder(Box::new(recorder)).unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2020, 1, 25)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2020, 2, 10)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let timestepper = Timestepper::new(start, end, TimestepDuration::Days(1));
+        let model = Model::new(timestepper.try_into().unwrap(), network);
+        // Test all solvers
+        run_all_solvers(&model, &["ipm-ocl", "ipm-simd"], &[], &[]);
+    }
 }
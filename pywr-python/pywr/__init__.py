@@ -9,11 +9,13 @@ def run_from_path(
     data_path: Optional[Path] = None,
     output_path: Optional[Path] = None,
     solver: str = "clp",
+    solver_kwargs: Optional[dict] = None,
 ):
     """Load and run a Pywr model from a file path.
 
     If the `data_path` and `output_path` are not specified, they will be set to the
-    directory containing the model file.
+    directory containing the model file. The `solver_kwargs` are passed to the solver's
+    settings (e.g. `{"tie_breaking": 1e-6}`).
     """
 
     if data_path is None:
@@ -23,4 +25,4 @@ def run_from_path(
 
     schema = Schema.from_path(filename)
     model = schema.build(data_path=data_path, output_path=output_path)
-    model.run(solver)
+    model.run(solver, solver_kwargs)
@@ -25,14 +25,26 @@ def cli():
 
 @cli.command()
 @click.argument("path", type=click.Path(exists=True, file_okay=True))
-@click.option("-s", "--solver", type=click.Choice(["clp", "highs"]), default="clp")
+@click.option("-s", "--solver", type=click.Choice(["clp", "cbc", "highs", "osqp", "gurobi"]), default="clp")
 @click.option(
     "-d", "--data-path", type=click.Path(exists=True, dir_okay=True), default=None
 )
 @click.option(
     "-o", "--output-path", type=click.Path(exists=True, dir_okay=True), default=None
 )
-def run(path: str, solver: str, data_path: Optional[str], output_path: Optional[str]):
+@click.option(
+    "--tie-breaking",
+    type=float,
+    default=None,
+    help="Break ties between routes of equal cost by perturbing the edge costs by up to this value.",
+)
+def run(
+    path: str,
+    solver: str,
+    data_path: Optional[str],
+    output_path: Optional[str],
+    tie_breaking: Optional[float],
+):
 
     data_path = Path(data_path) if data_path is not None else None
     output_path = Path(output_path) if output_path is not None else None
@@ -42,6 +54,7 @@ def run(path: str, solver: str, data_path: Optional[str], output_path: Optional[
         solver=solver,
         data_path=data_path,
         output_path=output_path,
+        solver_kwargs={"tie_breaking": tie_breaking} if tie_breaking is not None else None,
     )
 
 
@@ -1,8 +1,18 @@
-use chrono::NaiveDateTime;
-use pyo3::exceptions::PyRuntimeError;
+use chrono::{NaiveDateTime, TimeDelta};
+use polars::frame::DataFrame;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyTuple, PyType};
+use pyo3::types::{PyBytes, PyDict, PyTuple, PyType};
+use pyo3_log::{Caching, Logger, ResetHandle};
+use pyo3_polars::PyDataFrame;
 
+use pywr_core::metric::MetricF64;
+use pywr_core::models::{Progress, ProgressCallback, ProgressControl, ProgressFrequency, RunOptions};
+use pywr_core::network::{Network, NetworkState};
+use pywr_core::recorders::FileManifest;
+use pywr_core::snapshot::StateSnapshot;
+#[cfg(feature = "ipm-ocl")]
+use pywr_core::solvers::MultiStateSolver;
 /// Python API
 ///
 /// The following structures provide a Python API to access the core model structures.
@@ -10,16 +20,43 @@ use pyo3::types::{PyDict, PyTuple, PyType};
 ///
 ///
 
+#[cfg(feature = "cbc")]
+use pywr_core::solvers::{CbcSolver, CbcSolverSettings, CbcSolverSettingsBuilder};
 #[cfg(feature = "ipm-ocl")]
 use pywr_core::solvers::{ClIpmF32Solver, ClIpmF64Solver, ClIpmSolverSettings};
-use pywr_core::solvers::{ClpSolver, ClpSolverSettings, ClpSolverSettingsBuilder};
+use pywr_core::solvers::{ClpSolver, ClpSolverSettings, ClpSolverSettingsBuilder, Solver, SolverSettings};
+#[cfg(feature = "gurobi")]
+use pywr_core::solvers::{GurobiMethod, GurobiSolver, GurobiSolverSettings, GurobiSolverSettingsBuilder};
 #[cfg(feature = "highs")]
 use pywr_core::solvers::{HighsSolver, HighsSolverSettings, HighsSolverSettingsBuilder};
+#[cfg(feature = "osqp")]
+use pywr_core::solvers::{OsqpFallback, OsqpSolver, OsqpSolverSettings, OsqpSolverSettingsBuilder};
+use pywr_core::timestep::Timestep;
 use pywr_schema::model::DateType;
-use pywr_schema::{ComponentConversionError, ConversionData, ConversionError, TryIntoV2};
+use pywr_schema::{
+    ComponentConversionError, ConversionData, ConversionError, ModelUpdate, TryIntoV2, UpdateFromSchema,
+};
+use std::any::Any;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// The names of the Python loggers that receive the log events of the Rust crates.
+///
+/// Log events are forwarded to a logger named after the module they originate from (e.g.
+/// `pywr_core.network`), so these are the parents of all the loggers used by Pywr.
+const LOGGER_NAMES: [&str; 2] = ["pywr_core", "pywr_schema"];
+
+/// Handle used to clear the cached Python loggers and levels after they are reconfigured.
+static LOG_RESET_HANDLE: OnceLock<ResetHandle> = OnceLock::new();
+
+fn reset_log_cache() {
+    if let Some(handle) = LOG_RESET_HANDLE.get() {
+        handle.reset();
+    }
+}
 
 #[derive(Debug)]
 struct PySchemaError {
@@ -75,18 +112,45 @@ impl Schema {
     }
 
     /// Create a new schema object from a file path.
+    ///
+    /// The values of any of the model's constants can be overridden with `constants`, and the
+    /// variables referenced by the model (e.g. `"${DATA_DIR}"`) can be defined with `definitions`.
+    /// Variables that are not defined are read from the environment.
     #[classmethod]
-    fn from_path(_cls: &Bound<'_, PyType>, path: PathBuf) -> PyResult<Self> {
+    #[pyo3(signature = (path, constants=None, definitions=None))]
+    fn from_path(
+        _cls: &Bound<'_, PyType>,
+        path: PathBuf,
+        constants: Option<BTreeMap<String, f64>>,
+        definitions: Option<BTreeMap<String, String>>,
+    ) -> PyResult<Self> {
         Ok(Self {
-            schema: pywr_schema::PywrModel::from_path(path)?,
+            schema: pywr_schema::PywrModel::from_path_with_definitions(
+                path,
+                &constants.unwrap_or_default(),
+                &definitions.unwrap_or_default(),
+            )?,
         })
     }
 
     ///  Create a new schema object from a JSON string.
+    ///
+    /// The values of any of the model's constants can be overridden with `constants`, and the
+    /// variables referenced by the model can be defined with `definitions` (see `from_path`).
     #[classmethod]
-    fn from_json_string(_cls: &Bound<'_, PyType>, data: &str) -> PyResult<Self> {
+    #[pyo3(signature = (data, constants=None, definitions=None))]
+    fn from_json_string(
+        _cls: &Bound<'_, PyType>,
+        data: &str,
+        constants: Option<BTreeMap<String, f64>>,
+        definitions: Option<BTreeMap<String, String>>,
+    ) -> PyResult<Self> {
         Ok(Self {
-            schema: pywr_schema::PywrModel::from_str(data)?,
+            schema: pywr_schema::PywrModel::from_str_with_definitions(
+                data,
+                &constants.unwrap_or_default(),
+                &definitions.unwrap_or_default(),
+            )?,
         })
     }
 
@@ -100,7 +164,55 @@ impl Schema {
     #[pyo3(signature = (data_path=None, output_path=None))]
     fn build(&mut self, data_path: Option<PathBuf>, output_path: Option<PathBuf>) -> PyResult<Model> {
         let model = self.schema.build_model(data_path.as_deref(), output_path.as_deref())?;
-        Ok(Model { model })
+        Ok(Model {
+            model,
+            schema: Some((self.schema.clone(), data_path)),
+            snapshot: None,
+            final_state: None,
+            logging: LoggingConfig::default(),
+            logging_guard: None,
+        })
+    }
+
+    /// Optimise the model's variable parameters using the schema's `optimisation` definition.
+    ///
+    /// Returns a list containing a dictionary for each non-dominated solution with the values
+    /// of its `variables` and `objectives`.
+    #[pyo3(signature = (solver_name, data_path=None, solver_kwargs=None))]
+    fn optimise(
+        &self,
+        py: Python,
+        solver_name: &str,
+        data_path: Option<PathBuf>,
+        solver_kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Vec<Py<PyDict>>> {
+        let solutions = match solver_name {
+            "clp" => {
+                let settings = build_clp_settings(solver_kwargs)?;
+                self.schema.optimise::<ClpSolver>(data_path.as_deref(), &settings)?
+            }
+            #[cfg(feature = "highs")]
+            "highs" => {
+                let settings = build_highs_settings(solver_kwargs)?;
+                self.schema.optimise::<HighsSolver>(data_path.as_deref(), &settings)?
+            }
+            #[cfg(feature = "gurobi")]
+            "gurobi" => {
+                let settings = build_gurobi_settings(solver_kwargs)?;
+                self.schema.optimise::<GurobiSolver>(data_path.as_deref(), &settings)?
+            }
+            _ => return Err(PyRuntimeError::new_err(format!("Unknown solver: {}", solver_name))),
+        };
+
+        solutions
+            .into_iter()
+            .map(|solution| {
+                let dict = PyDict::new_bound(py);
+                dict.set_item("variables", solution.variables)?;
+                dict.set_item("objectives", solution.objectives)?;
+                Ok(dict.unbind())
+            })
+            .collect()
     }
 }
 
@@ -147,38 +259,680 @@ fn convert_metric_from_v1_json_string(_py: Python, data: &str) -> PyResult<Metri
     Ok(py_metric)
 }
 
+/// Configuration of the Python loggers applied while a [`Model`] is used as a context manager.
+#[derive(Default)]
+struct LoggingConfig {
+    /// The level to set on the loggers; any value accepted by `logging.Logger.setLevel`.
+    level: Option<PyObject>,
+    /// A file to which the log records are also written.
+    filename: Option<PathBuf>,
+}
+
+impl LoggingConfig {
+    /// Apply the configuration to the Python loggers, returning a guard that can restore
+    /// their previous configuration.
+    fn apply(&self, py: Python) -> PyResult<LoggingGuard> {
+        let logging = py.import_bound("logging")?;
+
+        let handler = match &self.filename {
+            Some(filename) => {
+                let handler = logging.call_method1("FileHandler", (filename,))?;
+                let formatter =
+                    logging.call_method1("Formatter", ("%(asctime)s %(levelname)s %(name)s: %(message)s",))?;
+                handler.call_method1("setFormatter", (formatter,))?;
+                Some(handler)
+            }
+            None => None,
+        };
+
+        let mut levels = Vec::with_capacity(LOGGER_NAMES.len());
+        for name in LOGGER_NAMES {
+            let logger = logging.call_method1("getLogger", (name,))?;
+            levels.push((logger.clone().unbind(), logger.getattr("level")?.unbind()));
+
+            if let Some(level) = &self.level {
+                logger.call_method1("setLevel", (level,))?;
+            }
+            if let Some(handler) = &handler {
+                logger.call_method1("addHandler", (handler,))?;
+            }
+        }
+
+        // The levels of the loggers are cached on the Rust side and must be refreshed.
+        reset_log_cache();
+
+        Ok(LoggingGuard {
+            levels,
+            handler: handler.map(|h| h.unbind()),
+        })
+    }
+}
+
+/// The configuration of the Python loggers before a [`LoggingConfig`] was applied.
+struct LoggingGuard {
+    /// Each logger and its previous level.
+    levels: Vec<(PyObject, PyObject)>,
+    /// The file handler added to the loggers, if any.
+    handler: Option<PyObject>,
+}
+
+impl LoggingGuard {
+    /// Restore the previous configuration of the loggers and close the file handler.
+    fn restore(self, py: Python) -> PyResult<()> {
+        for (logger, level) in &self.levels {
+            let logger = logger.bind(py);
+            logger.call_method1("setLevel", (level,))?;
+            if let Some(handler) = &self.handler {
+                logger.call_method1("removeHandler", (handler,))?;
+            }
+        }
+
+        if let Some(handler) = &self.handler {
+            handler.call_method0(py, "close")?;
+        }
+
+        reset_log_cache();
+        Ok(())
+    }
+}
+
+/// Forwards the progress of a run to a Python callable.
+///
+/// The callable is called with the number of completed time-steps, the total number of
+/// time-steps and the date of the last completed time-step. The run is cancelled if the callable
+/// returns `False` or raises an exception; the exception is re-raised once the run has stopped.
+struct PyProgressCallback {
+    callback: PyObject,
+    error: Option<PyErr>,
+}
+
+impl PyProgressCallback {
+    fn new(callback: PyObject) -> Self {
+        Self { callback, error: None }
+    }
+
+    /// Convert the result of a run in to a Python result, re-raising any exception from the callable.
+    fn into_result<T>(self, result: Result<T, pywr_core::PywrError>) -> PyResult<T> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(result?),
+        }
+    }
+}
+
+impl ProgressCallback for PyProgressCallback {
+    fn on_progress(&mut self, progress: &Progress) -> ProgressControl {
+        Python::with_gil(|py| {
+            let result = self
+                .callback
+                .call1(
+                    py,
+                    (progress.completed_timesteps, progress.num_timesteps, progress.date),
+                )
+                .map(|r| matches!(r.extract::<bool>(py), Ok(false)));
+
+            match result {
+                Ok(true) => ProgressControl::Cancel,
+                Ok(false) => ProgressControl::Continue,
+                Err(e) => {
+                    self.error = Some(e);
+                    ProgressControl::Cancel
+                }
+            }
+        })
+    }
+}
+
+/// The results of a model run.
+#[pyclass]
+pub struct ModelResult {
+    manifest: FileManifest,
+    /// The name and values of each output that keeps its values in memory.
+    dataframes: Vec<(String, DataFrame)>,
+}
+
+impl ModelResult {
+    fn new(network: &Network, recorder_states: &[Option<Box<dyn Any>>], manifest: FileManifest) -> PyResult<Self> {
+        Ok(Self {
+            manifest,
+            dataframes: network.get_recorder_dataframes(recorder_states)?,
+        })
+    }
+}
+
+#[pymethods]
+impl ModelResult {
+    /// A list containing a dictionary for each file written by the recorders with the name of
+    /// the recorder, the path of the file, its size in bytes and its SHA-256 checksum.
+    #[getter]
+    fn manifest(&self, py: Python) -> PyResult<Vec<Py<PyDict>>> {
+        self.manifest
+            .files()
+            .iter()
+            .map(|entry| {
+                let dict = PyDict::new_bound(py);
+                dict.set_item("recorder", &entry.recorder)?;
+                dict.set_item("path", &entry.path)?;
+                dict.set_item("size", entry.size)?;
+                dict.set_item(entry.checksum.algorithm(), entry.checksum.value())?;
+                Ok(dict.unbind())
+            })
+            .collect()
+    }
+
+    /// The names of the outputs whose values are kept in memory (e.g. `Memory` outputs).
+    #[getter]
+    fn output_names(&self) -> Vec<String> {
+        self.dataframes.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// The values of the in-memory output `name` as a Polars `DataFrame`.
+    ///
+    /// The data frame is in long format with a row for each scenario, time period and metric.
+    fn to_dataframe(&self, name: &str) -> PyResult<PyDataFrame> {
+        self.dataframes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, df)| PyDataFrame(df.clone()))
+            .ok_or_else(|| PyValueError::new_err(format!("No in-memory output named: {}", name)))
+    }
+
+    /// The values of the in-memory output `name` as a pandas `DataFrame`.
+    ///
+    /// This requires pandas and pyarrow to be installed.
+    fn to_pandas(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        self.to_dataframe(name)?.into_py(py).call_method0(py, "to_pandas")
+    }
+}
+
+/// A token used to cancel a model run from another thread.
+///
+/// Pass the token to `Model.run` and call `cancel` to stop the run at the end of the current
+/// time-step. The outputs are finalised with the completed time-steps and the run raises a
+/// `RuntimeError`.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    token: pywr_core::models::CancellationToken,
+}
+
+#[pymethods]
+impl CancellationToken {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request that any run using this token is stopped.
+    fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Whether the token has been cancelled.
+    #[getter]
+    fn cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
 #[pyclass]
 pub struct Model {
     model: pywr_core::models::Model,
+    /// The schema the model was built from and its data path, if it was built from a schema.
+    schema: Option<(pywr_schema::PywrModel, Option<PathBuf>)>,
+    /// A snapshot of the state from which the model is run.
+    snapshot: Option<StateSnapshot>,
+    /// A snapshot of the state at the end of the latest run of the model.
+    final_state: Option<StateSnapshot>,
+    /// The logging configuration applied while the model is used as a context manager.
+    logging: LoggingConfig,
+    /// The previous logging configuration, restored when the context manager exits.
+    logging_guard: Option<LoggingGuard>,
+}
+
+impl Model {
+    /// Run the model with a [`Solver`].
+    ///
+    /// The GIL is released during the run so that other Python threads, e.g. one that cancels
+    /// the run, are not blocked. The progress callback and any Python parameters acquire it
+    /// when they are called.
+    fn run_solver<S>(
+        &mut self,
+        py: Python,
+        settings: &S::Settings,
+        progress: Option<(PyProgressCallback, ProgressFrequency)>,
+        cancellation_token: Option<pywr_core::models::CancellationToken>,
+    ) -> PyResult<ModelResult>
+    where
+        S: Solver,
+        <S as Solver>::Settings: SolverSettings + Sync,
+    {
+        let (model, snapshot) = (&self.model, self.snapshot.as_ref());
+
+        let (result, final_state) = py.allow_threads(move || {
+            let mut state = model.setup::<S>(settings)?;
+            if let Some(snapshot) = snapshot {
+                state.restore(snapshot.clone())?;
+            }
+            let mut options = RunOptions::default();
+            if let Some(token) = cancellation_token {
+                options = options.cancellation_token(token);
+            }
+            let manifest = match progress {
+                Some((mut callback, frequency)) => {
+                    let options = options.progress(&mut callback, frequency);
+                    let result = model.run_with_state_and_options::<S>(&mut state, settings, options);
+                    callback.into_result(result)?
+                }
+                None => model.run_with_state_and_options::<S>(&mut state, settings, options)?,
+            };
+
+            let result = ModelResult::new(model.network(), state.recorder_state(), manifest)?;
+            Ok::<_, PyErr>((result, state.snapshot()))
+        })?;
+
+        self.final_state = Some(final_state);
+        Ok(result)
+    }
+
+    /// Run the model with a [`MultiStateSolver`].
+    ///
+    /// See [`Model::run_solver`].
+    #[cfg(feature = "ipm-ocl")]
+    fn run_multi_scenario_solver<S>(
+        &mut self,
+        py: Python,
+        settings: &S::Settings,
+        progress: Option<(PyProgressCallback, ProgressFrequency)>,
+        cancellation_token: Option<pywr_core::models::CancellationToken>,
+    ) -> PyResult<ModelResult>
+    where
+        S: MultiStateSolver,
+        <S as MultiStateSolver>::Settings: SolverSettings + Sync,
+    {
+        let (model, snapshot) = (&self.model, self.snapshot.as_ref());
+
+        let (result, final_state) = py.allow_threads(move || {
+            let mut state = model.setup_multi_scenario::<S>(settings)?;
+            if let Some(snapshot) = snapshot {
+                state.restore(snapshot.clone())?;
+            }
+            let mut options = RunOptions::default();
+            if let Some(token) = cancellation_token {
+                options = options.cancellation_token(token);
+            }
+            let manifest = match progress {
+                Some((mut callback, frequency)) => {
+                    let options = options.progress(&mut callback, frequency);
+                    let result = model.run_multi_scenario_with_state_and_options::<S>(&mut state, settings, options);
+                    callback.into_result(result)?
+                }
+                None => model.run_multi_scenario_with_state_and_options::<S>(&mut state, settings, options)?,
+            };
+
+            let result = ModelResult::new(model.network(), state.recorder_state(), manifest)?;
+            Ok::<_, PyErr>((result, state.snapshot()))
+        })?;
+
+        self.final_state = Some(final_state);
+        Ok(result)
+    }
+
+    /// Compute the parameters for the time-step with index `timestep_index` from the model's
+    /// state (see `set_state`) without running the model.
+    fn compute_parameters(&self, timestep_index: usize) -> PyResult<(Timestep, NetworkState)> {
+        if let Some(snapshot) = &self.snapshot {
+            if snapshot.timestep_index() > timestep_index {
+                return Err(PyValueError::new_err(format!(
+                    "the model's state is already after time-step {timestep_index}"
+                )));
+            }
+        }
+
+        Ok(self.model.compute_parameters(timestep_index, self.snapshot.as_ref())?)
+    }
 }
 
 #[pymethods]
 impl Model {
-    #[pyo3(signature = (solver_name, solver_kwargs=None))]
-    fn run(&self, solver_name: &str, solver_kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
-        match solver_name {
+    /// Update the model in place so that it matches `schema`, a new version of the schema the
+    /// model was built from.
+    ///
+    /// Only the definitions of global parameters that can be replaced without rebuilding the
+    /// network (e.g. constants and profiles) may differ. Returns the names of the replaced
+    /// parameters, or `None` if the schema is unchanged. A `RuntimeError` is raised, and the
+    /// model is not modified, if the schema differs in any other way.
+    fn update_from_schema(&mut self, schema: &Schema) -> PyResult<Option<Vec<String>>> {
+        let (current, data_path) = self
+            .schema
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("The model was not built from a schema."))?;
+
+        let update = self
+            .model
+            .update_from_schema(current, &schema.schema, data_path.as_deref())?;
+
+        let data_path = data_path.clone();
+        self.schema = Some((schema.schema.clone(), data_path));
+
+        Ok(match update {
+            ModelUpdate::Unchanged => None,
+            ModelUpdate::Parameters(names) => Some(names),
+        })
+    }
+
+    /// Run the model with the given solver.
+    ///
+    /// Returns a `ModelResult` containing the manifest of the files written by the recorders
+    /// and the values of any in-memory outputs.
+    ///
+    /// If `progress` is given it is called with the number of completed time-steps, the total
+    /// number of time-steps and the date of the last completed time-step. It is called after
+    /// every time-step, or every `progress_percent` percent of the time-steps if given. The run
+    /// is cancelled if `progress` returns `False` or raises an exception.
+    ///
+    /// The run is also cancelled once `cancellation_token` (a `CancellationToken`) is cancelled,
+    /// e.g. from another thread. The GIL is released while the model runs.
+    ///
+    /// The model is run from the state set with `set_state`, if any, and the state at the end
+    /// of the run is then returned by `get_state`.
+    #[pyo3(
+        signature = (solver_name, solver_kwargs=None, progress=None, progress_percent=None, cancellation_token=None)
+    )]
+    fn run(
+        &mut self,
+        py: Python,
+        solver_name: &str,
+        solver_kwargs: Option<&Bound<'_, PyDict>>,
+        progress: Option<PyObject>,
+        progress_percent: Option<usize>,
+        cancellation_token: Option<PyRef<'_, CancellationToken>>,
+    ) -> PyResult<ModelResult> {
+        let frequency = match progress_percent {
+            Some(percent) => ProgressFrequency::Percent(
+                NonZeroUsize::new(percent)
+                    .ok_or_else(|| PyValueError::new_err("`progress_percent` must be non-zero"))?,
+            ),
+            None => ProgressFrequency::Timestep,
+        };
+        let progress = progress.map(|callback| (PyProgressCallback::new(callback), frequency));
+        let token = cancellation_token.map(|t| t.token.clone());
+
+        let result = match solver_name {
             "clp" => {
                 let settings = build_clp_settings(solver_kwargs)?;
-                self.model.run::<ClpSolver>(&settings)?;
+                self.run_solver::<ClpSolver>(py, &settings, progress, token)?
+            }
+            #[cfg(feature = "cbc")]
+            "cbc" => {
+                let settings = build_cbc_settings(solver_kwargs)?;
+                self.run_solver::<CbcSolver>(py, &settings, progress, token)?
             }
             #[cfg(feature = "highs")]
             "highs" => {
                 let settings = build_highs_settings(solver_kwargs)?;
-                self.model.run::<HighsSolver>(&settings)?;
+                self.run_solver::<HighsSolver>(py, &settings, progress, token)?
+            }
+            #[cfg(feature = "osqp")]
+            "osqp" => {
+                let settings = build_osqp_settings(solver_kwargs)?;
+                self.run_solver::<OsqpSolver>(py, &settings, progress, token)?
+            }
+            #[cfg(feature = "gurobi")]
+            "gurobi" => {
+                let settings = build_gurobi_settings(solver_kwargs)?;
+                self.run_solver::<GurobiSolver>(py, &settings, progress, token)?
             }
             #[cfg(feature = "ipm-ocl")]
-            "clipm-f32" => self
-                .model
-                .run_multi_scenario::<ClIpmF32Solver>(&ClIpmSolverSettings::default()),
+            "clipm-f32" => {
+                self.run_multi_scenario_solver::<ClIpmF32Solver>(py, &ClIpmSolverSettings::default(), progress, token)?
+            }
             #[cfg(feature = "ipm-ocl")]
-            "clipm-f64" => self
-                .model
-                .run_multi_scenario::<ClIpmF64Solver>(&ClIpmSolverSettings::default()),
+            "clipm-f64" => {
+                self.run_multi_scenario_solver::<ClIpmF64Solver>(py, &ClIpmSolverSettings::default(), progress, token)?
+            }
             _ => return Err(PyRuntimeError::new_err(format!("Unknown solver: {}", solver_name))),
+        };
+
+        Ok(result)
+    }
+
+    /// Describe the values that will be written by each of the model's outputs.
+    ///
+    /// Returns a list containing a dictionary for each output with its `name`, `format`, `path`
+    /// (or `None`) and `metric_sets`. Each metric set has a `name`, `units` (or `None`), a list
+    /// of `metrics` and a list of `dimensions`. Each dimension has a `name`, a `size` and a list
+    /// of `labels` (or `None`). The model is not run.
+    fn describe_outputs(&self, py: Python) -> PyResult<Vec<Py<PyDict>>> {
+        let descriptions = self.model.describe_outputs()?;
+
+        descriptions
+            .iter()
+            .map(|output| {
+                let metric_sets = output
+                    .metric_sets
+                    .iter()
+                    .map(|metric_set| {
+                        let metrics = metric_set
+                            .metrics
+                            .iter()
+                            .map(|metric| {
+                                let dict = PyDict::new_bound(py);
+                                dict.set_item("name", &metric.name)?;
+                                dict.set_item("attribute", &metric.attribute)?;
+                                dict.set_item("type", &metric.ty)?;
+                                dict.set_item("sub_type", &metric.sub_type)?;
+                                Ok(dict)
+                            })
+                            .collect::<PyResult<Vec<_>>>()?;
+
+                        let dimensions = metric_set
+                            .dimensions
+                            .iter()
+                            .map(|dimension| {
+                                let dict = PyDict::new_bound(py);
+                                dict.set_item("name", &dimension.name)?;
+                                dict.set_item("size", dimension.size)?;
+                                dict.set_item("labels", &dimension.labels)?;
+                                Ok(dict)
+                            })
+                            .collect::<PyResult<Vec<_>>>()?;
+
+                        let dict = PyDict::new_bound(py);
+                        dict.set_item("name", &metric_set.name)?;
+                        dict.set_item("units", &metric_set.units)?;
+                        dict.set_item("metrics", metrics)?;
+                        dict.set_item("dimensions", dimensions)?;
+                        Ok(dict)
+                    })
+                    .collect::<PyResult<Vec<_>>>()?;
+
+                let dict = PyDict::new_bound(py);
+                dict.set_item("name", &output.name)?;
+                dict.set_item("format", &output.format)?;
+                dict.set_item("path", &output.path)?;
+                dict.set_item("metric_sets", metric_sets)?;
+                Ok(dict.unbind())
+            })
+            .collect()
+    }
+
+    /// Return the effective flow bounds and costs of a node at a time-step, as the solver sees them.
+    ///
+    /// The parameters are computed for the time-step with index `timestep_index` from the model's
+    /// state (see `set_state`); neither the preceding time-steps nor the time-step itself are
+    /// solved, and no outputs are written. Returns a list with a dictionary for each scenario containing the node's `min_flow`,
+    /// `max_flow`, `incoming_cost` and `outgoing_cost`. The bounds of storage nodes are given as
+    /// the flows into (positive) or out of (negative) the node over the time-step.
+    #[pyo3(signature = (name, timestep_index, sub_name=None))]
+    fn node_bounds(
+        &self,
+        py: Python,
+        name: &str,
+        timestep_index: usize,
+        sub_name: Option<&str>,
+    ) -> PyResult<Vec<Py<PyDict>>> {
+        let network = self.model.network();
+        let idx = network.get_node_index_by_name(name, sub_name)?;
+
+        let (timestep, state) = self.compute_parameters(timestep_index)?;
+
+        state
+            .states()
+            .iter()
+            .map(|scenario_state| {
+                let bounds = network.get_node_flow_bounds(&idx, &timestep, scenario_state)?;
+                let cost = network.get_node_cost(&idx, scenario_state)?;
+
+                let dict = PyDict::new_bound(py);
+                dict.set_item("min_flow", bounds.min_flow)?;
+                dict.set_item("max_flow", bounds.max_flow)?;
+                dict.set_item("incoming_cost", cost.incoming)?;
+                dict.set_item("outgoing_cost", cost.outgoing)?;
+                Ok(dict.unbind())
+            })
+            .collect()
+    }
+
+    /// Evaluate the value of a parameter on a date without running the whole model.
+    ///
+    /// The parameters are computed for the time-step that contains `date` from the model's state
+    /// (see `set_state`), in the same way as `node_bounds`. Returns the value for the scenario with index
+    /// `scenario`, or a list with the value for each scenario if no scenario is given. Index
+    /// parameters are returned as floats.
+    #[pyo3(signature = (name, date, scenario=None))]
+    fn evaluate_parameter(
+        &self,
+        py: Python,
+        name: &str,
+        date: NaiveDateTime,
+        scenario: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let network = self.model.network();
+        let parameter_name = name.into();
+        let metric: MetricF64 = match network.get_parameter_index_by_name(&parameter_name) {
+            Ok(idx) => idx.into(),
+            Err(_) => network.get_index_parameter_index_by_name(&parameter_name)?.into(),
+        };
+
+        let timesteps = self.model.domain().time().timesteps();
+        let timestep_index = timesteps
+            .iter()
+            .rposition(|t| t.date <= date)
+            .filter(|i| {
+                let t = &timesteps[*i];
+                date < t.date + TimeDelta::milliseconds(t.duration.milliseconds())
+            })
+            .ok_or_else(|| PyValueError::new_err(format!("the date {date} is not within the model's time-steps")))?;
+
+        let num_scenarios = self.model.domain().scenarios().len();
+        if let Some(scenario) = scenario {
+            if scenario >= num_scenarios {
+                return Err(PyValueError::new_err(format!(
+                    "scenario index {scenario} is out of range; the model has {num_scenarios} scenario(s)"
+                )));
+            }
+        }
+
+        let (_, state) = self.compute_parameters(timestep_index)?;
+
+        let values = state
+            .states()
+            .iter()
+            .map(|scenario_state| metric.get_value(network, scenario_state))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match scenario {
+            Some(scenario) => Ok(values[scenario].into_py(py)),
+            None => Ok(values.into_py(py)),
         }
+    }
+
+    /// Return the state of the model as a binary snapshot.
+    ///
+    /// After the model has been run this is the state at the end of the latest run. Otherwise
+    /// it is the state set with `set_state`, or the initial state of the model if no state has
+    /// been set.
+    fn get_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let snapshot = match (&self.final_state, &self.snapshot) {
+            (Some(snapshot), _) | (None, Some(snapshot)) => snapshot.clone(),
+            (None, None) => self.model.setup::<ClpSolver>(&ClpSolverSettings::default())?.snapshot(),
+        };
+
+        let data = snapshot
+            .to_bytes()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(PyBytes::new_bound(py, &data))
+    }
+
+    /// Set the state from which the model is run from a binary snapshot.
+    ///
+    /// The snapshot must have been created from a model with the same network and number of
+    /// scenarios. Passing `None` clears the state so that the model is run from its initial state.
+    #[pyo3(signature = (data=None))]
+    fn set_state(&mut self, data: Option<&[u8]>) -> PyResult<()> {
+        self.snapshot = match data {
+            Some(data) => {
+                let snapshot = StateSnapshot::from_bytes(data).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                // Check the snapshot is compatible with this model before accepting it.
+                let initial = self.model.setup::<ClpSolver>(&ClpSolverSettings::default())?;
+                snapshot
+                    .check_compatible(initial.network_state().states())
+                    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+                Some(snapshot)
+            }
+            None => None,
+        };
+        self.final_state = None;
 
         Ok(())
     }
+
+    /// Configure the logging applied while the model is used as a context manager.
+    ///
+    /// Log events from Pywr are forwarded to the Python `logging` module using a logger
+    /// named after the Rust module that emitted them (e.g. `pywr_core.network`). Inside the
+    /// `with` block the `pywr_core` and `pywr_schema` loggers are set to `level` and, if
+    /// `filename` is given, their records are also written to that file. The previous
+    /// configuration is restored when the block exits.
+    ///
+    /// Returns the model so that it can be used directly in a `with` statement.
+    #[pyo3(signature = (level=None, filename=None))]
+    fn configure_logging(
+        mut slf: PyRefMut<'_, Self>,
+        level: Option<PyObject>,
+        filename: Option<PathBuf>,
+    ) -> PyRefMut<'_, Self> {
+        slf.logging = LoggingConfig { level, filename };
+        slf
+    }
+
+    fn __enter__(mut slf: PyRefMut<'_, Self>) -> PyResult<PyRefMut<'_, Self>> {
+        if slf.logging_guard.is_some() {
+            return Err(PyRuntimeError::new_err(
+                "Model is already being used as a context manager.",
+            ));
+        }
+
+        let guard = slf.logging.apply(slf.py())?;
+        slf.logging_guard = Some(guard);
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        py: Python,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        if let Some(guard) = self.logging_guard.take() {
+            guard.restore(py)?;
+        }
+        // Do not suppress any exception raised in the `with` block.
+        Ok(false)
+    }
 }
 
 fn build_clp_settings(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<ClpSolverSettings> {
@@ -201,6 +955,77 @@ fn build_clp_settings(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<ClpSolverS
             kwargs.del_item("parallel")?;
         }
 
+        if let Ok(value) = kwargs.get_item("warm_start") {
+            if let Some(warm_start) = value {
+                builder = builder.warm_start(warm_start.extract::<bool>()?);
+            }
+            kwargs.del_item("warm_start")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("duals") {
+            if let Some(duals) = value {
+                if duals.extract::<bool>()? {
+                    builder = builder.duals();
+                }
+            }
+            kwargs.del_item("duals")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("tie_breaking") {
+            if let Some(epsilon) = value {
+                builder = builder.tie_breaking(epsilon.extract::<f64>()?);
+            }
+            kwargs.del_item("tie_breaking")?;
+        }
+
+        if !kwargs.is_empty() {
+            return Err(PyRuntimeError::new_err(format!(
+                "Unknown keyword arguments: {:?}",
+                kwargs
+            )));
+        }
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(feature = "cbc")]
+fn build_cbc_settings(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<CbcSolverSettings> {
+    let mut builder = CbcSolverSettingsBuilder::default();
+
+    if let Some(kwargs) = kwargs {
+        if let Ok(value) = kwargs.get_item("threads") {
+            if let Some(threads) = value {
+                builder = builder.threads(threads.extract::<usize>()?);
+            }
+            kwargs.del_item("threads")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("parallel") {
+            if let Some(parallel) = value {
+                if parallel.extract::<bool>()? {
+                    builder = builder.parallel();
+                }
+            }
+            kwargs.del_item("parallel")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("duals") {
+            if let Some(duals) = value {
+                if duals.extract::<bool>()? {
+                    builder = builder.duals();
+                }
+            }
+            kwargs.del_item("duals")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("tie_breaking") {
+            if let Some(epsilon) = value {
+                builder = builder.tie_breaking(epsilon.extract::<f64>()?);
+            }
+            kwargs.del_item("tie_breaking")?;
+        }
+
         if !kwargs.is_empty() {
             return Err(PyRuntimeError::new_err(format!(
                 "Unknown keyword arguments: {:?}",
@@ -233,6 +1058,211 @@ fn build_highs_settings(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<HighsSol
             kwargs.del_item("parallel")?;
         }
 
+        if let Ok(value) = kwargs.get_item("duals") {
+            if let Some(duals) = value {
+                if duals.extract::<bool>()? {
+                    builder = builder.duals();
+                }
+            }
+            kwargs.del_item("duals")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("tie_breaking") {
+            if let Some(epsilon) = value {
+                builder = builder.tie_breaking(epsilon.extract::<f64>()?);
+            }
+            kwargs.del_item("tie_breaking")?;
+        }
+
+        if !kwargs.is_empty() {
+            return Err(PyRuntimeError::new_err(format!(
+                "Unknown keyword arguments: {:?}",
+                kwargs
+            )));
+        }
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(feature = "osqp")]
+fn build_osqp_settings(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<OsqpSolverSettings> {
+    let mut builder = OsqpSolverSettingsBuilder::default();
+
+    if let Some(kwargs) = kwargs {
+        if let Ok(value) = kwargs.get_item("threads") {
+            if let Some(threads) = value {
+                builder = builder.threads(threads.extract::<usize>()?);
+            }
+            kwargs.del_item("threads")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("parallel") {
+            if let Some(parallel) = value {
+                if parallel.extract::<bool>()? {
+                    builder = builder.parallel();
+                }
+            }
+            kwargs.del_item("parallel")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("tie_breaking") {
+            if let Some(epsilon) = value {
+                builder = builder.tie_breaking(epsilon.extract::<f64>()?);
+            }
+            kwargs.del_item("tie_breaking")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("tolerance") {
+            if let Some(tolerance) = value {
+                builder = builder.tolerance(tolerance.extract::<f64>()?);
+            }
+            kwargs.del_item("tolerance")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("absolute_tolerance") {
+            if let Some(tolerance) = value {
+                builder = builder.absolute_tolerance(tolerance.extract::<f64>()?);
+            }
+            kwargs.del_item("absolute_tolerance")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("relative_tolerance") {
+            if let Some(tolerance) = value {
+                builder = builder.relative_tolerance(tolerance.extract::<f64>()?);
+            }
+            kwargs.del_item("relative_tolerance")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("max_iterations") {
+            if let Some(max_iterations) = value {
+                builder = builder.max_iterations(max_iterations.extract::<u32>()?);
+            }
+            kwargs.del_item("max_iterations")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("polish") {
+            if let Some(polish) = value {
+                if polish.extract::<bool>()? {
+                    builder = builder.polish();
+                }
+            }
+            kwargs.del_item("polish")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("fallback") {
+            if let Some(fallback) = value {
+                let fallback = match fallback.extract::<String>()?.as_str() {
+                    "error" => OsqpFallback::Error,
+                    "clp" => OsqpFallback::Clp,
+                    other => {
+                        return Err(PyValueError::new_err(format!(
+                            "Unknown OSQP fallback `{other}`; expected `error` or `clp`"
+                        )))
+                    }
+                };
+                builder = builder.fallback(fallback);
+            }
+            kwargs.del_item("fallback")?;
+        }
+
+        if !kwargs.is_empty() {
+            return Err(PyRuntimeError::new_err(format!(
+                "Unknown keyword arguments: {:?}",
+                kwargs
+            )));
+        }
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(feature = "gurobi")]
+fn build_gurobi_settings(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<GurobiSolverSettings> {
+    let mut builder = GurobiSolverSettingsBuilder::default();
+
+    if let Some(kwargs) = kwargs {
+        if let Ok(value) = kwargs.get_item("threads") {
+            if let Some(threads) = value {
+                builder = builder.threads(threads.extract::<usize>()?);
+            }
+            kwargs.del_item("threads")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("parallel") {
+            if let Some(parallel) = value {
+                if parallel.extract::<bool>()? {
+                    builder = builder.parallel();
+                }
+            }
+            kwargs.del_item("parallel")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("duals") {
+            if let Some(duals) = value {
+                if duals.extract::<bool>()? {
+                    builder = builder.duals();
+                }
+            }
+            kwargs.del_item("duals")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("tie_breaking") {
+            if let Some(epsilon) = value {
+                builder = builder.tie_breaking(epsilon.extract::<f64>()?);
+            }
+            kwargs.del_item("tie_breaking")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("method") {
+            if let Some(method) = value {
+                let method = match method.extract::<String>()?.as_str() {
+                    "automatic" => GurobiMethod::Automatic,
+                    "primal-simplex" => GurobiMethod::PrimalSimplex,
+                    "dual-simplex" => GurobiMethod::DualSimplex,
+                    "barrier" => GurobiMethod::Barrier,
+                    "concurrent" => GurobiMethod::Concurrent,
+                    "deterministic-concurrent" => GurobiMethod::DeterministicConcurrent,
+                    other => {
+                        return Err(PyValueError::new_err(format!(
+                            "Unknown Gurobi method `{other}`; expected `automatic`, `primal-simplex`, \
+                            `dual-simplex`, `barrier`, `concurrent` or `deterministic-concurrent`"
+                        )))
+                    }
+                };
+                builder = builder.method(method);
+            }
+            kwargs.del_item("method")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("feasibility_tolerance") {
+            if let Some(tolerance) = value {
+                builder = builder.feasibility_tolerance(tolerance.extract::<f64>()?);
+            }
+            kwargs.del_item("feasibility_tolerance")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("optimality_tolerance") {
+            if let Some(tolerance) = value {
+                builder = builder.optimality_tolerance(tolerance.extract::<f64>()?);
+            }
+            kwargs.del_item("optimality_tolerance")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("mip_gap") {
+            if let Some(gap) = value {
+                builder = builder.mip_gap(gap.extract::<f64>()?);
+            }
+            kwargs.del_item("mip_gap")?;
+        }
+
+        if let Ok(value) = kwargs.get_item("solver_threads") {
+            if let Some(threads) = value {
+                builder = builder.solver_threads(threads.extract::<usize>()?);
+            }
+            kwargs.del_item("solver_threads")?;
+        }
+
         if !kwargs.is_empty() {
             return Err(PyRuntimeError::new_err(format!(
                 "Unknown keyword arguments: {:?}",
@@ -246,13 +1276,19 @@ fn build_highs_settings(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<HighsSol
 
 /// A Python module implemented in Rust.
 #[pymodule]
-fn pywr(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
-    pyo3_log::init();
+fn pywr(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // Forward all log events to Python; the levels of the Python loggers determine which are emitted.
+    let logger = Logger::new(py, Caching::LoggersAndLevels)?.filter(log::LevelFilter::Trace);
+    if let Ok(handle) = logger.install() {
+        let _ = LOG_RESET_HANDLE.set(handle);
+    }
 
     m.add_function(wrap_pyfunction!(convert_model_from_v1_json_string, m)?)?;
     m.add_function(wrap_pyfunction!(convert_metric_from_v1_json_string, m)?)?;
     m.add_class::<Schema>()?;
     m.add_class::<Model>()?;
+    m.add_class::<ModelResult>()?;
+    m.add_class::<CancellationToken>()?;
     m.add_class::<Metric>()?;
 
     // Error classes
@@ -1,6 +1,10 @@
+from datetime import datetime
+import hashlib
+import json
+import logging
 import numpy as np
 import pandas
-from pywr import Schema, Model
+from pywr import Schema, Model, CancellationToken
 from pathlib import Path
 import h5py
 import pytest
@@ -25,10 +29,17 @@ def test_simple_timeseries(model_dir: Path, tmpdir: Path):
 
     schema = Schema.from_path(filename)
     model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
-    model.run("clp")
+    result = model.run("clp")
 
     assert output_fn.exists()
 
+    # The manifest records the output file's size and checksum
+    manifest = result.manifest
+    assert len(manifest) == 1
+    assert Path(manifest[0]["path"]) == output_fn
+    assert manifest[0]["size"] == output_fn.size()
+    assert manifest[0]["sha256"] == hashlib.sha256(output_fn.read_binary()).hexdigest()
+
     expected_data = pandas.read_csv(
         model_dir / "simple-timeseries" / "expected.csv", index_col=0, header=[0, 1]
     )
@@ -39,6 +50,258 @@ def test_simple_timeseries(model_dir: Path, tmpdir: Path):
             np.testing.assert_allclose(simulated, df)
 
 
+def test_memory_output_dataframe(model_dir: Path):
+    """Test the values of an in-memory output can be retrieved as a data frame."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+    data = json.loads(filename.read_text("utf-8"))
+    # Replace the HDF5 output so that nothing is written to disk
+    data["network"]["outputs"] = [
+        {"name": "flows", "type": "Memory", "metric_set": "nodes"}
+    ]
+
+    schema = Schema.from_json_string(json.dumps(data))
+    model = schema.build(data_path=model_dir / "simple-timeseries")
+    result = model.run("clp")
+
+    assert result.manifest == []
+    assert result.output_names == ["flows"]
+
+    df = result.to_pandas("flows")
+    # A row for each of the 365 time-steps and 3 metrics of the single scenario
+    assert len(df) == 365 * 3
+
+    expected_data = pandas.read_csv(
+        model_dir / "simple-timeseries" / "expected.csv", index_col=0, header=[0, 1]
+    )
+    for (node, attr), expected in expected_data.items():
+        simulated = df[(df["name"] == node) & (df["attribute"] == attr)]["value"]
+        np.testing.assert_allclose(simulated, expected)
+
+    with pytest.raises(ValueError):
+        result.to_dataframe("missing")
+
+
+def test_definitions(model_dir: Path):
+    """Test the variables referenced by a model can be defined when it is loaded."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+    data = json.loads(filename.read_text("utf-8"))
+    data["network"]["timeseries"][0]["provider"]["url"] = "${INFLOW_DIR}/inflow.csv"
+    data["network"]["outputs"] = [
+        {"name": "flows", "type": "Memory", "metric_set": "nodes"}
+    ]
+
+    with pytest.raises(RuntimeError):
+        Schema.from_json_string(json.dumps(data))
+
+    schema = Schema.from_json_string(
+        json.dumps(data),
+        definitions={"INFLOW_DIR": str(model_dir / "simple-timeseries")},
+    )
+    # The data path is not used because the path of the timeseries is absolute
+    model = schema.build(data_path=model_dir)
+    result = model.run("clp")
+
+    df = result.to_pandas("flows")
+    assert len(df) == 365 * 3
+
+
+def test_logging_context_manager(model_dir: Path, tmpdir: Path):
+    """Test the model configures the Python loggers for the duration of a run."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+    log_fn = tmpdir / "pywr.log"
+
+    schema = Schema.from_path(filename)
+    model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
+
+    logger = logging.getLogger("pywr_core")
+    previous_level = logger.level
+
+    with model.configure_logging(level="DEBUG", filename=log_fn) as m:
+        assert logger.level == logging.DEBUG
+        m.run("clp")
+
+    # The previous configuration is restored
+    assert logger.level == previous_level
+    assert not any(isinstance(h, logging.FileHandler) for h in logger.handlers)
+
+    # Events are logged with a logger named after the Rust module
+    log = log_fn.read_text("utf-8")
+    assert "pywr_core.network" in log
+    assert "pywr_core.models.simple" in log
+
+
+def test_progress_callback(model_dir: Path, tmpdir: Path):
+    """Test the progress of a run is reported and the run can be cancelled."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+
+    schema = Schema.from_path(filename)
+    model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
+
+    reports = []
+
+    def progress(completed, total, date):
+        reports.append((completed, total))
+
+    model.run("clp", progress=progress, progress_percent=50)
+    assert len(reports) == 2
+    assert reports[-1][0] == reports[-1][1]
+
+    def cancel(completed, total, date):
+        return completed < 2
+
+    with pytest.raises(RuntimeError, match="cancelled"):
+        model.run("clp", progress=cancel)
+
+
+def test_cancellation_token(model_dir: Path, tmpdir: Path):
+    """Test a run can be cancelled from another thread with a cancellation token."""
+    import threading
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+
+    schema = Schema.from_path(filename)
+    model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
+
+    token = CancellationToken()
+    assert not token.cancelled
+
+    # Cancel the token from another thread after the first time-step, and wait for it so
+    # that the run is stopped at the end of that time-step.
+    def progress(completed, total, date):
+        if completed == 1:
+            threading.Thread(target=token.cancel).start()
+            while not token.cancelled:
+                pass
+
+    with pytest.raises(RuntimeError, match="cancelled"):
+        model.run("clp", progress=progress, cancellation_token=token)
+    assert token.cancelled
+
+
+def test_describe_outputs(model_dir: Path, tmpdir: Path):
+    """Test the outputs of a model can be described before it is run."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+
+    schema = Schema.from_path(filename)
+    model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
+
+    descriptions = model.describe_outputs()
+    assert len(descriptions) == 1
+
+    output = descriptions[0]
+    assert output["name"] == "outputs"
+    assert output["format"] == "hdf5"
+    assert Path(output["path"]) == Path(tmpdir / "outputs.h5")
+
+    metric_set = output["metric_sets"][0]
+    assert metric_set["name"] == "nodes"
+    assert [m["name"] for m in metric_set["metrics"]] == ["input1", "link1", "output1"]
+    assert [(d["name"], d["size"]) for d in metric_set["dimensions"]] == [
+        ("time", 365),
+        ("metric", 3),
+    ]
+    # The model has not been run
+    assert not (tmpdir / "outputs.h5").exists()
+
+
+def test_node_bounds(model_dir: Path, tmpdir: Path):
+    """Test the bounds and costs of a node can be inspected at a time-step."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+
+    schema = Schema.from_path(filename)
+    model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
+
+    bounds = model.node_bounds("input1", 1)
+    assert len(bounds) == 1
+    assert bounds[0]["min_flow"] == 0.0
+    assert bounds[0]["max_flow"] == 2.0
+
+    bounds = model.node_bounds("output1", 1)
+    assert bounds[0]["max_flow"] == 10.0
+    assert bounds[0]["incoming_cost"] == -10.0
+
+    with pytest.raises(RuntimeError):
+        model.node_bounds("missing", 1)
+
+    # No outputs are written
+    assert not (tmpdir / "outputs.h5").exists()
+
+
+def test_evaluate_parameter(model_dir: Path, tmpdir: Path):
+    """Test a parameter can be evaluated on a date without running the model."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+
+    schema = Schema.from_path(filename)
+    model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
+
+    assert model.evaluate_parameter("demand", datetime(2021, 3, 1)) == [10.0]
+    assert model.evaluate_parameter("demand", datetime(2021, 3, 1), scenario=0) == 10.0
+
+    with pytest.raises(ValueError):
+        model.evaluate_parameter("demand", datetime(2022, 1, 1))
+
+    with pytest.raises(ValueError):
+        model.evaluate_parameter("demand", datetime(2021, 3, 1), scenario=1)
+
+    with pytest.raises(RuntimeError):
+        model.evaluate_parameter("missing", datetime(2021, 3, 1))
+
+
+def test_get_state(model_dir: Path, tmpdir: Path):
+    """Test the state at the end of a run is returned by `get_state`."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+
+    schema = Schema.from_path(filename)
+    model = schema.build(data_path=model_dir / "simple-timeseries", output_path=tmpdir)
+
+    initial_state = model.get_state()
+    model.run("clp")
+    final_state = model.get_state()
+    assert final_state != initial_state
+
+    # Setting a state replaces the state of the previous run
+    model.set_state(initial_state)
+    assert model.get_state() == initial_state
+
+    # The model can be run again from the state that was set
+    model.run("clp")
+    assert model.get_state() == final_state
+
+
+def test_update_from_schema(model_dir: Path):
+    """Test a model's global parameters can be updated without rebuilding it."""
+
+    filename = model_dir / "simple-timeseries" / "model.json"
+    data = json.loads(filename.read_text("utf-8"))
+    data["network"]["outputs"] = [
+        {"name": "flows", "type": "Memory", "metric_set": "nodes"}
+    ]
+
+    schema = Schema.from_json_string(json.dumps(data))
+    model = schema.build(data_path=model_dir / "simple-timeseries")
+    assert model.update_from_schema(schema) is None
+
+    data["network"]["parameters"][0]["value"] = 5.0
+    assert model.update_from_schema(Schema.from_json_string(json.dumps(data))) == [
+        "demand"
+    ]
+    assert model.evaluate_parameter("demand", datetime(2021, 3, 1), scenario=0) == 5.0
+
+    # Changes other than to the global parameters are rejected
+    data["network"]["edges"].pop()
+    with pytest.raises(RuntimeError):
+        model.update_from_schema(Schema.from_json_string(json.dumps(data)))
+    assert model.evaluate_parameter("demand", datetime(2021, 3, 1), scenario=0) == 5.0
+
+
 # TODO these tests could be auto-discovered.
 @pytest.mark.parametrize(
     "model_name",
@@ -0,0 +1,182 @@
+//! Model-level constants that can be referenced from any numeric field of a model.
+//!
+//! A model may define a top-level `constants` section of named scalar values. Any value in the
+//! model of the form `"${name}"` is replaced with the value of the constant `name` before the
+//! model is deserialised. The values in the section may be overridden when the model is loaded,
+//! allowing a single model file to be used as a simple template.
+//!
+//! ```json
+//! {
+//!   "constants": {
+//!     "peak_demand": 15.0
+//!   },
+//!   "network": {
+//!     "parameters": [
+//!       {
+//!         "meta": {"name": "demand"},
+//!         "type": "Constant",
+//!         "value": "${peak_demand}"
+//!       }
+//!     ]
+//!   }
+//! }
+//! ```
+use crate::SchemaError;
+use serde_json::{Number, Value};
+use std::collections::BTreeMap;
+
+/// The name of the section of a model that defines the constants.
+pub(crate) const CONSTANTS_KEY: &str = "constants";
+
+/// Returns `true` if `data` may contain a reference to a constant.
+pub(crate) fn contains_reference(data: &str) -> bool {
+    data.contains("${")
+}
+
+/// Parse a reference to a constant (e.g. `"${peak_demand}"`), returning the constant's name.
+fn parse_reference(s: &str) -> Option<&str> {
+    s.strip_prefix("${")?.strip_suffix('}')
+}
+
+/// Convert a constant's value to a JSON number.
+///
+/// Whole numbers are converted to integers so that constants can also be used in integer
+/// fields (e.g. the size of a scenario group).
+fn to_json_number(name: &str, value: f64) -> Result<Number, SchemaError> {
+    if !value.is_finite() {
+        return Err(SchemaError::InvalidConstantValue {
+            name: name.to_string(),
+            value,
+        });
+    }
+
+    if value.fract() == 0.0 && value.abs() <= i64::MAX as f64 {
+        Ok(Number::from(value as i64))
+    } else {
+        Number::from_f64(value).ok_or_else(|| SchemaError::InvalidConstantValue {
+            name: name.to_string(),
+            value,
+        })
+    }
+}
+
+/// Replace all references to constants in `value`.
+fn substitute(value: &mut Value, constants: &BTreeMap<String, f64>) -> Result<(), SchemaError> {
+    match value {
+        Value::String(s) => {
+            if let Some(name) = parse_reference(s).map(str::to_string) {
+                let constant = constants
+                    .get(&name)
+                    .ok_or_else(|| SchemaError::ConstantNotFound(name.clone()))?;
+                *value = Value::Number(to_json_number(&name, *constant)?);
+            }
+        }
+        Value::Array(values) => {
+            for v in values {
+                substitute(v, constants)?;
+            }
+        }
+        Value::Object(map) => {
+            for v in map.values_mut() {
+                substitute(v, constants)?;
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+
+    Ok(())
+}
+
+/// Substitute references to the constants of a model document.
+///
+/// The constants are read from the document's `constants` section and updated with `overrides`.
+/// Only constants defined in the document may be overridden. The section is updated with the
+/// overridden values so that the document records the values that were used.
+pub(crate) fn substitute_constants(document: &mut Value, overrides: &BTreeMap<String, f64>) -> Result<(), SchemaError> {
+    let Some(map) = document.as_object_mut() else {
+        // Not a valid model; leave it for deserialisation to report the error.
+        return Ok(());
+    };
+
+    let mut constants: BTreeMap<String, f64> = match map.get(CONSTANTS_KEY) {
+        Some(Value::Null) | None => BTreeMap::new(),
+        Some(section) => serde_json::from_value(section.clone())?,
+    };
+
+    for (name, value) in overrides {
+        match constants.get_mut(name) {
+            Some(constant) => *constant = *value,
+            None => return Err(SchemaError::ConstantNotFound(name.clone())),
+        }
+    }
+
+    if !overrides.is_empty() {
+        let section = constants
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), Value::Number(to_json_number(name, *value)?))))
+            .collect::<Result<_, SchemaError>>()?;
+        map.insert(CONSTANTS_KEY.to_string(), Value::Object(section));
+    }
+
+    for (key, value) in map.iter_mut() {
+        if key != CONSTANTS_KEY {
+            substitute(value, &constants)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::substitute_constants;
+    use crate::SchemaError;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_substitute_constants() {
+        let mut document = json!({
+            "constants": {"demand": 10.5, "size": 3.0},
+            "scenarios": [{"name": "a", "size": "${size}"}],
+            "network": {"value": "${demand}", "name": "demand"}
+        });
+
+        substitute_constants(&mut document, &BTreeMap::new()).unwrap();
+        assert_eq!(document["scenarios"][0]["size"], json!(3));
+        assert_eq!(document["network"]["value"], json!(10.5));
+        assert_eq!(document["network"]["name"], json!("demand"));
+    }
+
+    #[test]
+    fn test_override_constants() {
+        let mut document = json!({
+            "constants": {"demand": 10.5},
+            "network": {"value": "${demand}"}
+        });
+
+        let overrides = BTreeMap::from([("demand".to_string(), 20.0)]);
+        substitute_constants(&mut document, &overrides).unwrap();
+        assert_eq!(document["constants"]["demand"], json!(20));
+        assert_eq!(document["network"]["value"], json!(20));
+
+        // Only constants defined in the model may be overridden
+        let overrides = BTreeMap::from([("supply".to_string(), 20.0)]);
+        assert!(matches!(
+            substitute_constants(&mut document, &overrides),
+            Err(SchemaError::ConstantNotFound(n)) if n == "supply"
+        ));
+    }
+
+    #[test]
+    fn test_undefined_constant() {
+        let mut document = json!({
+            "network": {"value": "${demand}"}
+        });
+
+        assert!(matches!(
+            substitute_constants(&mut document, &BTreeMap::new()),
+            Err(SchemaError::ConstantNotFound(n)) if n == "demand"
+        ));
+    }
+}
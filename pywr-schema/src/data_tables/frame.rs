@@ -0,0 +1,200 @@
+//! Loading of tables from columnar files (e.g. Parquet or Arrow IPC).
+//!
+//! The files are scanned lazily so that only the required columns are read. The index columns
+//! are converted to strings from their own type (e.g. an integer index of `1` is the key `"1"`,
+//! not `"1.0"`) and the value columns are converted to `f64`. The tables are built directly from
+//! the typed columns, and are interpreted in the same way as a CSV table with the same lookup.
+use crate::data_tables::scalar::{LoadedScalarTable, ScalarTableOne, ScalarTableR1C1, ScalarTableR2};
+use crate::data_tables::vec::LoadedVecTable;
+use crate::data_tables::{CsvDataTableLookup, DataTableType, LoadedTable, TableError};
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The number of columns that index the rows of a table with `lookup`.
+///
+/// The column names are the only header of a columnar file, so lookups that require more
+/// than one header row are not supported.
+fn num_index_columns(lookup: &CsvDataTableLookup) -> Result<usize, TableError> {
+    match *lookup {
+        CsvDataTableLookup::Row(n) => Ok(n),
+        CsvDataTableLookup::Col(1) => Ok(0),
+        CsvDataTableLookup::Both(n, 1) => Ok(n),
+        _ => Err(TableError::FormatNotSupported(
+            "Columnar tables with more than one row of column headers are not supported.".to_string(),
+        )),
+    }
+}
+
+/// Load a table of `f64` values from a lazily scanned file.
+///
+/// Only the `columns` given, if any, are read; these must include the index columns.
+pub(crate) fn load_frame_table(
+    frame: LazyFrame,
+    columns: Option<&[String]>,
+    ty: &DataTableType,
+    lookup: &CsvDataTableLookup,
+    path: &Path,
+) -> Result<LoadedTable, TableError> {
+    let num_index = num_index_columns(lookup)?;
+
+    let frame = match columns {
+        Some(columns) => frame.select(columns.iter().map(|c| col(c.as_str())).collect::<Vec<_>>()),
+        None => frame,
+    };
+    let df = frame.collect().map_err(|e| TableError::Polars(e.to_string()))?;
+    let table = FrameColumns::new(&df, num_index)?;
+
+    match ty {
+        DataTableType::Scalar => match *lookup {
+            CsvDataTableLookup::Row(1) => Ok(LoadedTable::FloatScalar(table.row_scalar_table_one(path)?)),
+            CsvDataTableLookup::Row(2) => Ok(LoadedTable::FloatScalar(table.row2_scalar_table(path)?)),
+            CsvDataTableLookup::Both(1, 1) => Ok(LoadedTable::FloatScalar(table.row_col_scalar_table())),
+            _ => Err(TableError::FormatNotSupported(
+                "Columnar scalar table with this lookup is not supported.".to_string(),
+            )),
+        },
+        DataTableType::Array => match *lookup {
+            CsvDataTableLookup::Row(1) => Ok(LoadedTable::FloatVec(table.row_vec_table_one()?)),
+            CsvDataTableLookup::Row(2) => Ok(LoadedTable::FloatVec(table.row2_vec_table()?)),
+            CsvDataTableLookup::Col(1) => Ok(LoadedTable::FloatVec(table.col_vec_table_one()?)),
+            _ => Err(TableError::FormatNotSupported(
+                "Columnar array table with this lookup is not supported.".to_string(),
+            )),
+        },
+    }
+}
+
+/// The columns of a data frame, with the index columns as strings and the value columns as `f64`.
+struct FrameColumns {
+    index: Vec<StringChunked>,
+    value_names: Vec<String>,
+    values: Vec<Float64Chunked>,
+    height: usize,
+}
+
+impl FrameColumns {
+    /// Split the columns of `df` into the first `num_index` columns and the value columns.
+    fn new(df: &DataFrame, num_index: usize) -> Result<Self, TableError> {
+        let columns: Vec<&Series> = df.get_columns().iter().map(|c| c.as_materialized_series()).collect();
+        if columns.len() <= num_index {
+            return Err(TableError::InvalidFormat(format!(
+                "The table has {} columns, but {} index columns and at least one value column are required.",
+                columns.len(),
+                num_index
+            )));
+        }
+
+        let index = columns[..num_index]
+            .iter()
+            .map(|s| s.cast(&DataType::String).and_then(|s| s.str().cloned()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TableError::Polars(e.to_string()))?;
+
+        let values = columns[num_index..]
+            .iter()
+            .map(|s| {
+                s.strict_cast(&DataType::Float64)
+                    .and_then(|s| s.f64().cloned())
+                    .map_err(|_| {
+                        TableError::InvalidFormat(format!("Column `{}` does not contain numeric values.", s.name()))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            index,
+            value_names: columns[num_index..].iter().map(|s| s.name().to_string()).collect(),
+            values,
+            height: df.height(),
+        })
+    }
+
+    /// The key of a row in an index column; a missing key is an empty string, as in a CSV file.
+    fn key(&self, column: usize, row: usize) -> String {
+        self.index[column].get(row).unwrap_or_default().to_string()
+    }
+
+    fn value(&self, column: usize, row: usize) -> Result<f64, TableError> {
+        self.values[column].get(row).ok_or_else(|| {
+            TableError::InvalidFormat(format!(
+                "Column `{}` is missing a value in row {row}.",
+                self.value_names[column]
+            ))
+        })
+    }
+
+    fn row_values(&self, row: usize) -> Result<Vec<f64>, TableError> {
+        (0..self.values.len()).map(|column| self.value(column, row)).collect()
+    }
+
+    /// A scalar table has a single value column.
+    fn check_single_value(&self, path: &Path) -> Result<(), TableError> {
+        if self.values.len() > 1 {
+            return Err(TableError::TooManyValues(path.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    fn row_scalar_table_one(&self, path: &Path) -> Result<LoadedScalarTable<f64>, TableError> {
+        self.check_single_value(path)?;
+        let keys = (0..self.height).map(|row| self.key(0, row)).collect();
+        let values = (0..self.height)
+            .map(|row| self.value(0, row))
+            .collect::<Result<_, _>>()?;
+
+        Ok(LoadedScalarTable::One(ScalarTableOne::new(keys, values)))
+    }
+
+    fn row2_scalar_table(&self, path: &Path) -> Result<LoadedScalarTable<f64>, TableError> {
+        self.check_single_value(path)?;
+        let values: HashMap<(String, String), f64> = (0..self.height)
+            .map(|row| Ok(((self.key(0, row), self.key(1, row)), self.value(0, row)?)))
+            .collect::<Result<_, TableError>>()?;
+
+        Ok(LoadedScalarTable::Row2(ScalarTableR2::new(values)))
+    }
+
+    /// Missing values are not an error in a table indexed by row and column, but can not be
+    /// looked up.
+    fn row_col_scalar_table(&self) -> LoadedScalarTable<f64> {
+        let row_keys = (0..self.height).map(|row| self.key(0, row)).collect();
+        let values = (0..self.height)
+            .map(|row| self.values.iter().map(|column| column.get(row)).collect())
+            .collect();
+
+        LoadedScalarTable::Row1Col1(ScalarTableR1C1::new((row_keys, self.value_names.clone()), values))
+    }
+
+    fn row_vec_table_one(&self) -> Result<LoadedVecTable<f64>, TableError> {
+        let tbl = (0..self.height)
+            .map(|row| Ok((self.key(0, row), self.row_values(row)?)))
+            .collect::<Result<_, TableError>>()?;
+
+        Ok(LoadedVecTable::One(tbl))
+    }
+
+    fn row2_vec_table(&self) -> Result<LoadedVecTable<f64>, TableError> {
+        let tbl = (0..self.height)
+            .map(|row| Ok(((self.key(0, row), self.key(1, row)), self.row_values(row)?)))
+            .collect::<Result<_, TableError>>()?;
+
+        Ok(LoadedVecTable::Two(tbl))
+    }
+
+    fn col_vec_table_one(&self) -> Result<LoadedVecTable<f64>, TableError> {
+        let tbl = self
+            .value_names
+            .iter()
+            .enumerate()
+            .map(|(column, name)| {
+                let values = (0..self.height)
+                    .map(|row| self.value(column, row))
+                    .collect::<Result<_, _>>()?;
+                Ok((name.clone(), values))
+            })
+            .collect::<Result<_, TableError>>()?;
+
+        Ok(LoadedVecTable::One(tbl))
+    }
+}
@@ -1,12 +1,20 @@
 #[cfg(feature = "core")]
+mod frame;
+#[cfg(feature = "core")]
 mod scalar;
 #[cfg(feature = "core")]
 mod vec;
 
+#[cfg(feature = "core")]
+use crate::excel::{range_to_csv, read_range};
 use crate::parameters::TableIndex;
 use crate::ConversionError;
 #[cfg(feature = "core")]
 use crate::SchemaError;
+#[cfg(feature = "core")]
+use frame::load_frame_table;
+#[cfg(feature = "core")]
+use polars::prelude::{LazyFrame, ScanArgsIpc, ScanArgsParquet};
 use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::parameters::TableDataRef as TableDataRefV1;
 #[cfg(feature = "core")]
@@ -16,6 +24,10 @@ use scalar::{
 use schemars::JsonSchema;
 #[cfg(feature = "core")]
 use std::collections::HashMap;
+#[cfg(feature = "core")]
+use std::fs::File;
+#[cfg(feature = "core")]
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 #[cfg(feature = "core")]
@@ -36,18 +48,27 @@ pub enum DataTableType {
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, strum_macros::Display)]
 pub enum DataTableFormat {
     CSV,
+    Excel,
+    Parquet,
+    Ipc,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, strum_macros::Display)]
 #[serde(tag = "format", rename_all = "lowercase")]
 pub enum DataTable {
     CSV(CsvDataTable),
+    Excel(ExcelDataTable),
+    Parquet(ParquetDataTable),
+    Ipc(IpcDataTable),
 }
 
 impl DataTable {
     pub fn name(&self) -> &str {
         match self {
             DataTable::CSV(tbl) => &tbl.name,
+            DataTable::Excel(tbl) => &tbl.name,
+            DataTable::Parquet(tbl) => &tbl.name,
+            DataTable::Ipc(tbl) => &tbl.name,
         }
     }
 
@@ -55,6 +76,9 @@ impl DataTable {
     pub fn load(&self, data_path: Option<&Path>) -> Result<LoadedTable, TableError> {
         match self {
             DataTable::CSV(tbl) => tbl.load_f64(data_path),
+            DataTable::Excel(tbl) => tbl.load_f64(data_path),
+            DataTable::Parquet(tbl) => tbl.load_f64(data_path),
+            DataTable::Ipc(tbl) => tbl.load_f64(data_path),
         }
     }
 }
@@ -80,53 +104,142 @@ pub struct CsvDataTable {
 #[cfg(feature = "core")]
 impl CsvDataTable {
     fn load_f64(&self, data_path: Option<&Path>) -> Result<LoadedTable, TableError> {
-        match &self.ty {
-            DataTableType::Scalar => match self.lookup {
-                CsvDataTableLookup::Row(i) => match i {
-                    1 => Ok(LoadedTable::FloatScalar(load_csv_row_scalar_table_one(
-                        &self.url, data_path,
-                    )?)),
-                    2 => Ok(LoadedTable::FloatScalar(load_csv_row2_scalar_table_one(
-                        &self.url, data_path,
-                    )?)),
-                    _ => Err(TableError::FormatNotSupported(
-                        "CSV row scalar table with more than two index columns is not supported.".to_string(),
-                    )),
-                },
-                CsvDataTableLookup::Col(_) => todo!(),
-                CsvDataTableLookup::Both(nrows, ncols) => match (nrows, ncols) {
-                    (1, 1) => Ok(LoadedTable::FloatScalar(load_csv_row_col_scalar_table_one(
-                        &self.url, data_path,
-                    )?)),
-                    _ => Err(TableError::FormatNotSupported(
-                        "CSV row & col scalar table with more than one index is not supported.".to_string(),
-                    )),
-                },
+        let path = make_path(&self.url, data_path);
+        let file = File::open(&path).map_err(|e| TableError::IO(e.to_string()))?;
+        let rdr = csv::Reader::from_reader(BufReader::new(file));
+
+        load_f64_table(&self.ty, &self.lookup, rdr, &path)
+    }
+}
+
+/// An external table of data that is read from an Excel workbook.
+///
+/// The cells are read from the sheet named `sheet`, or the first sheet of the workbook if it
+/// is not given. The cells can be restricted with `range` to either a range of the sheet (e.g.
+/// `B2:F20`) or the name of a named range defined in the workbook. The cells are interpreted
+/// in the same way as the rows and columns of a CSV table with the same `lookup`; the first row
+/// of the cells is the header row.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+pub struct ExcelDataTable {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: DataTableType,
+    pub lookup: CsvDataTableLookup,
+    pub url: PathBuf,
+    pub sheet: Option<String>,
+    pub range: Option<String>,
+}
+
+#[cfg(feature = "core")]
+impl ExcelDataTable {
+    fn load_f64(&self, data_path: Option<&Path>) -> Result<LoadedTable, TableError> {
+        let path = make_path(&self.url, data_path);
+        let cells = read_range(&path, self.sheet.as_deref(), self.range.as_deref())
+            .map_err(|e| TableError::Excel(e.to_string()))?;
+        let data = range_to_csv(&cells).map_err(|e| TableError::Excel(e.to_string()))?;
+        let rdr = csv::Reader::from_reader(data.as_slice());
+
+        load_f64_table(&self.ty, &self.lookup, rdr, &path)
+    }
+}
+
+/// An external table of data that is read from a Parquet file.
+///
+/// The file is scanned lazily, and only the `columns` given are read if any; these must include
+/// the index columns. This allows a few columns of a large table to be loaded quickly. The rows
+/// and columns are interpreted in the same way as those of a CSV table with the same `lookup`;
+/// the column names are the header row, so only a single header row is supported.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+pub struct ParquetDataTable {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: DataTableType,
+    pub lookup: CsvDataTableLookup,
+    pub url: PathBuf,
+    pub columns: Option<Vec<String>>,
+}
+
+#[cfg(feature = "core")]
+impl ParquetDataTable {
+    fn load_f64(&self, data_path: Option<&Path>) -> Result<LoadedTable, TableError> {
+        let path = make_path(&self.url, data_path);
+        let frame = LazyFrame::scan_parquet(&path, ScanArgsParquet::default())
+            .map_err(|e| TableError::Polars(e.to_string()))?;
+
+        load_frame_table(frame, self.columns.as_deref(), &self.ty, &self.lookup, &path)
+    }
+}
+
+/// An external table of data that is read from an Arrow IPC (Feather) file.
+///
+/// This is read in the same way as a [`ParquetDataTable`].
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+pub struct IpcDataTable {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: DataTableType,
+    pub lookup: CsvDataTableLookup,
+    pub url: PathBuf,
+    pub columns: Option<Vec<String>>,
+}
+
+#[cfg(feature = "core")]
+impl IpcDataTable {
+    fn load_f64(&self, data_path: Option<&Path>) -> Result<LoadedTable, TableError> {
+        let path = make_path(&self.url, data_path);
+        let frame =
+            LazyFrame::scan_ipc(&path, ScanArgsIpc::default()).map_err(|e| TableError::Polars(e.to_string()))?;
+
+        load_frame_table(frame, self.columns.as_deref(), &self.ty, &self.lookup, &path)
+    }
+}
+
+/// Load a table of `f64` values from data in the CSV format.
+#[cfg(feature = "core")]
+fn load_f64_table<R: Read>(
+    ty: &DataTableType,
+    lookup: &CsvDataTableLookup,
+    rdr: csv::Reader<R>,
+    path: &Path,
+) -> Result<LoadedTable, TableError> {
+    match ty {
+        DataTableType::Scalar => match *lookup {
+            CsvDataTableLookup::Row(i) => match i {
+                1 => Ok(LoadedTable::FloatScalar(load_csv_row_scalar_table_one(rdr, path)?)),
+                2 => Ok(LoadedTable::FloatScalar(load_csv_row2_scalar_table_one(rdr, path)?)),
+                _ => Err(TableError::FormatNotSupported(
+                    "CSV row scalar table with more than two index columns is not supported.".to_string(),
+                )),
             },
-            DataTableType::Array => match self.lookup {
-                CsvDataTableLookup::Row(i) => match i {
-                    1 => Ok(LoadedTable::FloatVec(load_csv_row_vec_table_one(&self.url, data_path)?)),
-                    2 => Ok(LoadedTable::FloatVec(load_csv_row2_vec_table_one(
-                        &self.url, data_path,
-                    )?)),
-                    _ => Err(TableError::FormatNotSupported(
-                        "CSV row array table with more than two index columns is not supported.".to_string(),
-                    )),
-                },
-                CsvDataTableLookup::Col(i) => match i {
-                    1 => Ok(LoadedTable::FloatVec(load_csv_col1_vec_table_one(
-                        &self.url, data_path,
-                    )?)),
-                    2 => Ok(LoadedTable::FloatVec(load_csv_col2_vec_table_two(
-                        &self.url, data_path,
-                    )?)),
-                    _ => Err(TableError::FormatNotSupported(
-                        "CSV column array table with more than two index columns is not supported.".to_string(),
-                    )),
-                },
-                CsvDataTableLookup::Both(_, _) => todo!(),
+            CsvDataTableLookup::Col(_) => Err(TableError::FormatNotSupported(
+                "CSV column scalar table is not supported.".to_string(),
+            )),
+            CsvDataTableLookup::Both(nrows, ncols) => match (nrows, ncols) {
+                (1, 1) => Ok(LoadedTable::FloatScalar(load_csv_row_col_scalar_table_one(rdr)?)),
+                _ => Err(TableError::FormatNotSupported(
+                    "CSV row & col scalar table with more than one index is not supported.".to_string(),
+                )),
             },
-        }
+        },
+        DataTableType::Array => match *lookup {
+            CsvDataTableLookup::Row(i) => match i {
+                1 => Ok(LoadedTable::FloatVec(load_csv_row_vec_table_one(rdr)?)),
+                2 => Ok(LoadedTable::FloatVec(load_csv_row2_vec_table_one(rdr)?)),
+                _ => Err(TableError::FormatNotSupported(
+                    "CSV row array table with more than two index columns is not supported.".to_string(),
+                )),
+            },
+            CsvDataTableLookup::Col(i) => match i {
+                1 => Ok(LoadedTable::FloatVec(load_csv_col1_vec_table_one(rdr)?)),
+                2 => Ok(LoadedTable::FloatVec(load_csv_col2_vec_table_two(rdr)?)),
+                _ => Err(TableError::FormatNotSupported(
+                    "CSV column array table with more than two index columns is not supported.".to_string(),
+                )),
+            },
+            CsvDataTableLookup::Both(_, _) => Err(TableError::FormatNotSupported(
+                "CSV row & col array table is not supported.".to_string(),
+            )),
+        },
     }
 }
 
@@ -161,6 +274,10 @@ pub enum TableError {
     IO(String),
     #[error("CSV error: {0}")]
     Csv(String),
+    #[error("Excel error: {0}")]
+    Excel(String),
+    #[error("Polars error: {0}")]
+    Polars(String),
     #[error("Format not supported: {0}")]
     FormatNotSupported(String),
     #[error("Failed to parse: {0}")]
@@ -314,6 +431,8 @@ impl TryFrom<TableDataRefV1> for TableDataRef {
 #[cfg(feature = "core")]
 mod tests {
     use super::*;
+    use polars::df;
+    use polars::prelude::{IpcWriter, ParquetWriter, SerWriter};
     use std::fs::File;
     use std::io::Write;
     use tempfile::tempdir;
@@ -361,4 +480,141 @@ my-reservoir,0.2,0.2,0.2,0.2,0.2,0.2,0.2,0.2,0.2,0.2,0.2,0.2";
             &vec![0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2]
         );
     }
+
+    #[test]
+    fn test_columnar_tables() {
+        let dir = tempdir().unwrap();
+
+        let mut df = df!(
+            "reservoir" => ["a-reservoir", "my-reservoir"],
+            "zone" => [1i64, 2],
+            "control-curve" => [0.5, 0.8],
+            "comment" => ["first", "second"],
+        )
+        .unwrap();
+
+        let parquet_path = dir.path().join("my-data.parquet");
+        ParquetWriter::new(File::create(&parquet_path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+        let ipc_path = dir.path().join("my-data.arrow");
+        IpcWriter::new(File::create(&ipc_path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+
+        for (format, path) in [("parquet", parquet_path), ("ipc", ipc_path)] {
+            let table_def = serde_json::json!({
+                "name": "my-scalars",
+                "type": "scalar",
+                "format": format,
+                "lookup": {"row": 2},
+                "url": path,
+                "columns": ["reservoir", "zone", "control-curve"]
+            });
+            let tbl: DataTable = serde_json::from_value(table_def.clone()).unwrap();
+            let tbl = tbl.load(None).unwrap();
+
+            // The integer index is used as the key without converting it to a float
+            assert_eq!(tbl.get_scalar_f64(&["my-reservoir", "2"]).unwrap(), 0.8);
+
+            // Without the projection the text column is loaded as a value column
+            let mut table_def = table_def;
+            table_def.as_object_mut().unwrap().remove("columns");
+            let tbl: DataTable = serde_json::from_value(table_def).unwrap();
+            assert!(matches!(tbl.load(None), Err(TableError::InvalidFormat(_))));
+        }
+    }
+
+    #[test]
+    fn test_columnar_table_lookups() {
+        let dir = tempdir().unwrap();
+
+        let mut df = df!(
+            "reservoir" => ["a-reservoir", "my-reservoir"],
+            "jan" => [Some(0.1), Some(0.2)],
+            "feb" => [Some(0.3), None],
+        )
+        .unwrap();
+
+        let path = dir.path().join("my-data.parquet");
+        ParquetWriter::new(File::create(&path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+
+        let load = |ty: &str, lookup: serde_json::Value| {
+            let table_def = serde_json::json!({
+                "name": "my-table",
+                "type": ty,
+                "format": "parquet",
+                "lookup": lookup,
+                "url": path,
+            });
+            serde_json::from_value::<DataTable>(table_def).unwrap().load(None)
+        };
+
+        // A missing value can not be looked up in a table indexed by row and column
+        let tbl = load("scalar", serde_json::json!({"both": [1, 1]})).unwrap();
+        assert_eq!(tbl.get_scalar_f64(&["a-reservoir", "feb"]).unwrap(), 0.3);
+        assert_eq!(tbl.get_scalar_f64(&["my-reservoir", "jan"]).unwrap(), 0.2);
+        assert_eq!(
+            tbl.get_scalar_f64(&["my-reservoir", "feb"]),
+            Err(TableError::EntryNotFound)
+        );
+
+        // ... but is an error in an array table
+        assert!(matches!(
+            load("array", serde_json::json!({"row": 1})),
+            Err(TableError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            load("scalar", serde_json::json!({"row": 1})),
+            Err(TableError::TooManyValues(_))
+        ));
+
+        let mut df = df!(
+            "reservoir" => ["a-reservoir", "my-reservoir"],
+            "jan" => [0.1, 0.2],
+            "feb" => [0.3, 0.4],
+        )
+        .unwrap();
+        ParquetWriter::new(File::create(&path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+
+        let tbl = load("array", serde_json::json!({"row": 1})).unwrap();
+        assert_eq!(tbl.get_vec_f64(&["my-reservoir"]).unwrap(), &vec![0.2, 0.4]);
+
+        // The index column is a value column when the table is indexed by the column names only
+        assert!(matches!(
+            load("array", serde_json::json!({"col": 1})),
+            Err(TableError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_csv_lookups() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("my-data.csv");
+        std::fs::write(&path, "reservoir,jan,feb\nmy-reservoir,0.1,0.2\n").unwrap();
+
+        let load = |ty: &str, lookup: serde_json::Value| {
+            let table_def = serde_json::json!({
+                "name": "my-table",
+                "type": ty,
+                "format": "csv",
+                "lookup": lookup,
+                "url": path,
+            });
+            serde_json::from_value::<DataTable>(table_def).unwrap().load(None)
+        };
+
+        assert!(matches!(
+            load("scalar", serde_json::json!({"col": 1})),
+            Err(TableError::FormatNotSupported(_))
+        ));
+        assert!(matches!(
+            load("array", serde_json::json!({"both": [1, 1]})),
+            Err(TableError::FormatNotSupported(_))
+        ));
+    }
 }
@@ -1,7 +1,6 @@
-use crate::data_tables::{make_path, TableError};
+use crate::data_tables::TableError;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::Read;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -19,6 +18,10 @@ impl<T> ScalarTableOne<T>
 where
     T: Copy,
 {
+    pub(crate) fn new(keys: Vec<String>, values: Vec<T>) -> Self {
+        Self { keys, values }
+    }
+
     fn get_scalar(&self, key: &str) -> Result<T, TableError> {
         let index = table_key_to_position(key, &self.keys)?;
         self.values
@@ -39,6 +42,10 @@ impl<T> ScalarTableR1C1<T>
 where
     T: Copy,
 {
+    pub(crate) fn new(index: (Vec<String>, Vec<String>), values: Vec<Vec<Option<T>>>) -> Self {
+        Self { index, values }
+    }
+
     fn get_scalar(&self, index: &[&str]) -> Result<T, TableError> {
         if index.len() == 2 {
             let idx0 = table_key_to_position(index[0], &self.index.0)?;
@@ -68,6 +75,10 @@ impl<T> ScalarTableR2<T>
 where
     T: Copy,
 {
+    pub(crate) fn new(values: HashMap<(String, String), T>) -> Self {
+        Self { values }
+    }
+
     fn get_scalar(&self, index: &[&str]) -> Result<T, TableError> {
         if index.len() == 2 {
             // I think this copies the strings and is not very efficient.
@@ -129,20 +140,12 @@ where
 }
 
 /// Load a CSV file with looks for each rows & columns
-pub fn load_csv_row_col_scalar_table_one<T>(
-    table_path: &Path,
-    data_path: Option<&Path>,
-) -> Result<LoadedScalarTable<T>, TableError>
+pub fn load_csv_row_col_scalar_table_one<T, R>(mut rdr: csv::Reader<R>) -> Result<LoadedScalarTable<T>, TableError>
 where
     T: FromStr + Copy,
     TableError: From<T::Err>,
+    R: Read,
 {
-    let path = make_path(table_path, data_path);
-
-    let file = File::open(path).map_err(|e| TableError::IO(e.to_string()))?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(buf_reader);
-
     let col_headers: Vec<String> = rdr
         .headers()
         .map_err(|e| TableError::Csv(e.to_string()))?
@@ -175,20 +178,15 @@ where
     }))
 }
 
-pub fn load_csv_row_scalar_table_one<T>(
-    table_path: &Path,
-    data_path: Option<&Path>,
+pub fn load_csv_row_scalar_table_one<T, R>(
+    mut rdr: csv::Reader<R>,
+    path: &Path,
 ) -> Result<LoadedScalarTable<T>, TableError>
 where
     T: FromStr + Copy,
     TableError: From<T::Err>,
+    R: Read,
 {
-    let path = make_path(table_path, data_path);
-
-    let file = File::open(path.clone()).map_err(|e| TableError::IO(e.to_string()))?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(buf_reader);
-
     let (keys, values): (Vec<String>, Vec<T>) = rdr
         .records()
         .map(|result| {
@@ -201,7 +199,7 @@ where
             let values: Vec<T> = record.iter().skip(1).map(|v| v.parse()).collect::<Result<_, _>>()?;
 
             if values.len() > 1 {
-                return Err(TableError::TooManyValues(path.clone()));
+                return Err(TableError::TooManyValues(path.to_path_buf()));
             }
 
             Ok((key, values[0]))
@@ -213,20 +211,15 @@ where
     Ok(LoadedScalarTable::One(ScalarTableOne { keys, values }))
 }
 
-pub fn load_csv_row2_scalar_table_one<T>(
-    table_path: &Path,
-    data_path: Option<&Path>,
+pub fn load_csv_row2_scalar_table_one<T, R>(
+    mut rdr: csv::Reader<R>,
+    path: &Path,
 ) -> Result<LoadedScalarTable<T>, TableError>
 where
     T: FromStr + Copy,
     TableError: From<T::Err>,
+    R: Read,
 {
-    let path = make_path(table_path, data_path);
-
-    let file = File::open(path.clone()).map_err(|e| TableError::IO(e.to_string()))?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(buf_reader);
-
     let values: HashMap<(String, String), T> = rdr
         .records()
         .map(|result| {
@@ -242,7 +235,7 @@ where
             let values: Vec<T> = record.iter().skip(2).map(|v| v.parse()).collect::<Result<_, _>>()?;
 
             if values.len() > 1 {
-                return Err(TableError::TooManyValues(path.clone()));
+                return Err(TableError::TooManyValues(path.to_path_buf()));
             }
 
             Ok((key, values[0]))
@@ -1,8 +1,6 @@
-use crate::data_tables::{make_path, TableError};
+use crate::data_tables::TableError;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
+use std::io::Read;
 use std::str::FromStr;
 
 pub enum LoadedVecTable<T> {
@@ -46,20 +44,12 @@ where
     }
 }
 
-pub fn load_csv_row_vec_table_one<T>(
-    table_path: &Path,
-    data_path: Option<&Path>,
-) -> Result<LoadedVecTable<T>, TableError>
+pub fn load_csv_row_vec_table_one<T, R>(mut rdr: csv::Reader<R>) -> Result<LoadedVecTable<T>, TableError>
 where
     T: FromStr,
     TableError: From<T::Err>,
+    R: Read,
 {
-    let path = make_path(table_path, data_path);
-
-    let file = File::open(path).map_err(|e| TableError::IO(e.to_string()))?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(buf_reader);
-
     let tbl: HashMap<String, Vec<T>> = rdr
         .records()
         .map(|result| {
@@ -78,20 +68,12 @@ where
     Ok(LoadedVecTable::One(tbl))
 }
 
-pub fn load_csv_row2_vec_table_one<T>(
-    table_path: &Path,
-    data_path: Option<&Path>,
-) -> Result<LoadedVecTable<T>, TableError>
+pub fn load_csv_row2_vec_table_one<T, R>(mut rdr: csv::Reader<R>) -> Result<LoadedVecTable<T>, TableError>
 where
     T: FromStr,
     TableError: From<T::Err>,
+    R: Read,
 {
-    let path = make_path(table_path, data_path);
-
-    let file = File::open(path).map_err(|e| TableError::IO(e.to_string()))?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(buf_reader);
-
     let tbl: HashMap<(String, String), Vec<T>> = rdr
         .records()
         .map(|result| {
@@ -113,20 +95,12 @@ where
     Ok(LoadedVecTable::Two(tbl))
 }
 
-pub fn load_csv_col1_vec_table_one<T>(
-    table_path: &Path,
-    data_path: Option<&Path>,
-) -> Result<LoadedVecTable<T>, TableError>
+pub fn load_csv_col1_vec_table_one<T, R>(mut rdr: csv::Reader<R>) -> Result<LoadedVecTable<T>, TableError>
 where
     T: FromStr,
     TableError: From<T::Err>,
+    R: Read,
 {
-    let path = make_path(table_path, data_path);
-
-    let file = File::open(path).map_err(|e| TableError::IO(e.to_string()))?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(buf_reader);
-
     let mut tbl: HashMap<String, Vec<T>> = HashMap::new();
 
     // Read the headers
@@ -162,20 +136,12 @@ where
     Ok(LoadedVecTable::One(tbl))
 }
 
-pub fn load_csv_col2_vec_table_two<T>(
-    table_path: &Path,
-    data_path: Option<&Path>,
-) -> Result<LoadedVecTable<T>, TableError>
+pub fn load_csv_col2_vec_table_two<T, R>(mut rdr: csv::Reader<R>) -> Result<LoadedVecTable<T>, TableError>
 where
     T: FromStr,
     TableError: From<T::Err>,
+    R: Read,
 {
-    let path = make_path(table_path, data_path);
-
-    let file = File::open(path).map_err(|e| TableError::IO(e.to_string()))?;
-    let buf_reader = BufReader::new(file);
-    let mut rdr = csv::Reader::from_reader(buf_reader);
-
     let mut tbl: HashMap<(String, String), Vec<T>> = HashMap::new();
 
     // Read the headers
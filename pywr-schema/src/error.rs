@@ -12,6 +12,24 @@ pub enum SchemaError {
     IO { path: PathBuf, error: std::io::Error },
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("JSON error at `{path}`{}: {error}", .component.as_ref().map(|c| format!(" (in `{c}`)")).unwrap_or_default())]
+    JsonPath {
+        /// The path to the value that failed to deserialise (e.g. `network.nodes[12].max_volume`).
+        path: String,
+        /// The name of the innermost component containing the value, if any.
+        component: Option<String>,
+        error: serde_json::Error,
+    },
+    #[error("failed to include `{path}`: {reason}")]
+    Include { path: PathBuf, reason: String },
+    #[error("circular include of files: {0:?}")]
+    CircularInclude(Vec<PathBuf>),
+    #[error("variable `{0}` is not defined; give a definition or set an environment variable")]
+    VariableNotDefined(String),
+    #[error("constant `{0}` is not defined in the model")]
+    ConstantNotFound(String),
+    #[error("invalid value for constant `{name}`: {value}")]
+    InvalidConstantValue { name: String, value: f64 },
     #[error("node with name {0} not found")]
     NodeNotFound(String),
     #[error("node ({ty}) with name {name} does not support attribute {attr}")]
@@ -28,11 +46,18 @@ pub enum SchemaError {
     LocalParameterReferenceRequiresParent(String),
     #[error("network {0} not found")]
     NetworkNotFound(String),
+    #[error("scenario group {0} not found")]
+    ScenarioGroupNotFound(String),
     #[error("missing initial volume for node: {0}")]
     MissingInitialVolume(String),
     #[error("Pywr core error: {0}")]
     #[cfg(feature = "core")]
     PywrCore(#[from] pywr_core::PywrError),
+    #[error("Optimisation error: {0}")]
+    #[cfg(feature = "core")]
+    Optimisation(#[from] pywr_core::optimise::OptimisationError),
+    #[error("The model does not define an optimisation")]
+    OptimisationNotDefined,
     #[error("Error loading data from table `{0}` (column: `{1:?}`, index: `{2:?}`) error: {error}", table_ref.table, table_ref.column, table_ref.index)]
     TableRefLoad { table_ref: TableDataRef, error: TableError },
     #[error("Error loading table `{table_def:?}` error: {error}")]
@@ -49,6 +74,12 @@ pub enum SchemaError {
     HDF5Error(String),
     #[error("Missing metric set: {0}")]
     MissingMetricSet(String),
+    #[error("Output `{0}` does not support temporal aggregation in its format")]
+    UnsupportedOutputAggregation(String),
+    #[error("Invalid aggregation frequency: {0}")]
+    InvalidAggregationFrequency(String),
+    #[error("Invalid aggregation function: {0}")]
+    InvalidAggregationFunction(String),
     #[error("mismatch in the length of data provided. expected: {expected}, found: {found}")]
     DataLengthMismatch { expected: usize, found: usize },
     #[error("Failed to estimate epsilon for use in the radial basis function.")]
@@ -66,12 +97,52 @@ pub enum SchemaError {
     #[error("The output of literal constant values is not supported. This is because they do not have a unique identifier such as a name. If you would like to output a constant value please use a `Constant` parameter."
     )]
     LiteralConstantOutputNotSupported,
+    #[error("A {ty} profile requires {expected} values but {found} were given")]
+    InvalidProfileLength { ty: String, expected: usize, found: usize },
     #[error("Chrono out of range error: {0}")]
     OutOfRange(#[from] chrono::OutOfRange),
     #[error("The metric set with name '{0}' contains no metrics")]
     EmptyMetricSet(String),
+    #[error("Invalid filter of metric set `{name}`: {reason}")]
+    InvalidMetricSetFilter { name: String, reason: String },
+    #[error("Invalid derived metric `{name}` of metric set `{metric_set}`: {reason}")]
+    InvalidDerivedMetric {
+        metric_set: String,
+        name: String,
+        reason: String,
+    },
+    #[error("Node `{0}` does not contain any storage")]
+    NodeHasNoStorage(String),
+    #[error("Invalid storage zones for node `{name}`: {reason}")]
+    InvalidStorageZones { name: String, reason: String },
+    #[error("Invalid bathymetry for node `{name}`: {reason}")]
+    InvalidBathymetry { name: String, reason: String },
+    #[error("Invalid licence profile for node `{name}`: {reason}")]
+    InvalidLicenceProfile { name: String, reason: String },
+    #[error("Invalid licence `{name}`: {reason}")]
+    InvalidLicence { name: String, reason: String },
+    #[error("Invalid turbine `{name}`: {reason}")]
+    InvalidTurbine { name: String, reason: String },
+    #[error("Invalid hydropower cascade `{name}`: {reason}")]
+    InvalidHydropowerCascade { name: String, reason: String },
+    #[error("Invalid time filter on output `{name}`: {reason}")]
+    InvalidTimeFilter { name: String, reason: String },
+    #[error("Invalid date range of parameter `{name}`: {reason}")]
+    InvalidDateRange { name: String, reason: String },
+    #[error("Invalid HDF5 output `{name}`: {reason}")]
+    InvalidHdf5Output { name: String, reason: String },
+    #[error("State override `{name}` can not be applied to node `{node}` of type {ty}")]
+    StateOverrideNodeNotSupported { name: String, node: String, ty: String },
+    #[error("Bounds are only supported on parameters that return a floating point value: {0}")]
+    ParameterBoundsNotSupported(String),
+    #[error("Parameter '{0}' can not be replaced in a network that has already been built.")]
+    ParameterNotReplaceable(String),
+    #[error("The model can not be updated from the new schema: {0}")]
+    ModelUpdateNotSupported(String),
     #[error("The feature '{0}' must be enabled to use this functionality.")]
     FeatureNotEnabled(String),
+    #[error("The network contains components of an unknown type that can not be built: {0:?}")]
+    UnknownComponents(Vec<String>),
 }
 
 #[cfg(all(feature = "core", feature = "pyo3"))]
@@ -0,0 +1,207 @@
+//! Reading of cells from Excel workbooks.
+//!
+//! The cells of a workbook are read using [`calamine`], which also supports the `xls`, `xlsb`
+//! and `ods` formats.
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExcelError {
+    #[error("failed to open workbook `{path}`: {error}")]
+    Open { path: PathBuf, error: calamine::Error },
+    #[error("the workbook `{0}` has no sheets")]
+    NoSheets(PathBuf),
+    #[error("sheet `{sheet}` not found in workbook `{path}`")]
+    SheetNotFound { sheet: String, path: PathBuf },
+    #[error("failed to read sheet `{sheet}`: {error}")]
+    Read { sheet: String, error: calamine::Error },
+    #[error("invalid range of cells `{0}`")]
+    InvalidRange(String),
+    #[error("CSV error: {0}")]
+    Csv(String),
+}
+
+/// Read a range of cells from a sheet of the workbook at `path`.
+///
+/// If `range` is the name of a named range defined in the workbook then the cells of that
+/// range are returned. Otherwise `range` is a range of cells (e.g. `B2:F20`, or a single cell)
+/// of `sheet`. If `range` is not given all the used cells of `sheet` are returned. If `sheet`
+/// is not given the first sheet of the workbook is used.
+pub(crate) fn read_range(path: &Path, sheet: Option<&str>, range: Option<&str>) -> Result<Range<Data>, ExcelError> {
+    let mut workbook = open_workbook_auto(path).map_err(|error| ExcelError::Open {
+        path: path.to_path_buf(),
+        error,
+    })?;
+
+    let named_range = range.and_then(|name| {
+        workbook
+            .defined_names()
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, formula)| formula.clone())
+    });
+
+    let (sheet, cells) = match named_range {
+        Some(formula) => {
+            let (sheet, cells) = split_reference(&formula)?;
+            (sheet, Some(cells))
+        }
+        None => {
+            let sheet = match sheet {
+                Some(sheet) => sheet.to_string(),
+                None => workbook
+                    .sheet_names()
+                    .first()
+                    .cloned()
+                    .ok_or_else(|| ExcelError::NoSheets(path.to_path_buf()))?,
+            };
+            (sheet, range.map(|r| r.to_string()))
+        }
+    };
+
+    if !workbook.sheet_names().contains(&sheet) {
+        return Err(ExcelError::SheetNotFound {
+            sheet,
+            path: path.to_path_buf(),
+        });
+    }
+
+    let sheet_range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|error| ExcelError::Read { sheet, error })?;
+
+    match cells {
+        Some(cells) => {
+            let (start, end) = parse_range(&cells)?;
+            Ok(sheet_range.range(start, end))
+        }
+        None => Ok(sheet_range),
+    }
+}
+
+/// Write the rows of cells in the CSV format so that they can be read in the same way as a
+/// CSV file. Rows in which all the cells are empty are skipped.
+pub(crate) fn range_to_csv(range: &Range<Data>) -> Result<Vec<u8>, ExcelError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    for row in range.rows() {
+        if row.iter().all(|c| matches!(c, Data::Empty)) {
+            continue;
+        }
+        writer
+            .write_record(row.iter().map(|c| c.to_string()))
+            .map_err(|e| ExcelError::Csv(e.to_string()))?;
+    }
+
+    writer.into_inner().map_err(|e| ExcelError::Csv(e.to_string()))
+}
+
+/// Split a reference to a range of cells (e.g. `'Sheet 1'!$A$1:$C$10`) in to the name of the
+/// sheet and the range of cells.
+fn split_reference(reference: &str) -> Result<(String, String), ExcelError> {
+    let reference = reference.trim_start_matches('=');
+    let (sheet, cells) = reference
+        .rsplit_once('!')
+        .ok_or_else(|| ExcelError::InvalidRange(reference.to_string()))?;
+
+    let sheet = sheet
+        .strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .map(|s| s.replace("''", "'"))
+        .unwrap_or_else(|| sheet.to_string());
+
+    Ok((sheet, cells.to_string()))
+}
+
+/// Parse a range of cells (e.g. `B2:F20`) in to the zero-based (row, column) positions of its
+/// first and last cells. A single cell (e.g. `B2`) is a range of one cell.
+fn parse_range(range: &str) -> Result<((u32, u32), (u32, u32)), ExcelError> {
+    let invalid = || ExcelError::InvalidRange(range.to_string());
+
+    let (start, end) = range.split_once(':').unwrap_or((range, range));
+    let start = parse_cell(start).ok_or_else(invalid)?;
+    let end = parse_cell(end).ok_or_else(invalid)?;
+
+    if start.0 > end.0 || start.1 > end.1 {
+        return Err(invalid());
+    }
+
+    Ok((start, end))
+}
+
+/// Parse a reference to a cell (e.g. `B2` or `$B$2`) in to its zero-based (row, column) position.
+fn parse_cell(cell: &str) -> Option<(u32, u32)> {
+    let cell = cell.trim().replace('$', "");
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let col = letters.to_ascii_uppercase().chars().try_fold(0u32, |acc, c| {
+        acc.checked_mul(26)?.checked_add(c as u32 - 'A' as u32 + 1)
+    })?;
+    let row: u32 = digits.parse().ok()?;
+
+    Some((row.checked_sub(1)?, col - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cell, parse_range, read_range, split_reference};
+    use std::path::PathBuf;
+
+    fn workbook_path() -> PathBuf {
+        PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/excel1.xlsx"))
+    }
+
+    #[test]
+    fn test_parse_cell() {
+        assert_eq!(parse_cell("A1"), Some((0, 0)));
+        assert_eq!(parse_cell("$C$10"), Some((9, 2)));
+        assert_eq!(parse_cell("AA3"), Some((2, 26)));
+        assert_eq!(parse_cell("A0"), None);
+        assert_eq!(parse_cell("12"), None);
+        assert_eq!(parse_cell("B"), None);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("B2:D5").unwrap(), ((1, 1), (4, 3)));
+        assert_eq!(parse_range("B2").unwrap(), ((1, 1), (1, 1)));
+        assert!(parse_range("D5:B2").is_err());
+    }
+
+    #[test]
+    fn test_split_reference() {
+        assert_eq!(
+            split_reference("'My sheet'!$A$1:$B$3").unwrap(),
+            ("My sheet".to_string(), "$A$1:$B$3".to_string())
+        );
+        assert_eq!(
+            split_reference("tables!A1").unwrap(),
+            ("tables".to_string(), "A1".to_string())
+        );
+        assert!(split_reference("A1:B3").is_err());
+    }
+
+    #[test]
+    fn test_read_range() {
+        let path = workbook_path();
+
+        // The first sheet is used by default
+        let cells = read_range(&path, None, None).unwrap();
+        assert_eq!(cells.get_size(), (366, 2));
+
+        let cells = read_range(&path, Some("tables"), Some("A1:B3")).unwrap();
+        assert_eq!(cells.get_size(), (3, 2));
+
+        // A named range defined in the workbook
+        let cells = read_range(&path, None, Some("demands")).unwrap();
+        assert_eq!(cells.get_size(), (3, 2));
+
+        assert!(read_range(&path, Some("missing"), None).is_err());
+    }
+}
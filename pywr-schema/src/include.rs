@@ -0,0 +1,298 @@
+//! Inclusion of the components of a network from other files.
+//!
+//! Any section of a network (e.g. `nodes` or `parameters`) may be replaced by an include
+//! directive, or may contain include directives amongst its components. An include directive is
+//! an object with a single `include` key giving the path of a file, or a glob pattern matching
+//! several files, relative to the file containing the directive. Each file must contain either a
+//! single component or a list of components, which replace the directive in the order of the
+//! files' paths. The included files may themselves contain include directives.
+//!
+//! ```json
+//! {
+//!   "network": {
+//!     "nodes": {"include": "nodes/*.json"},
+//!     "edges": [
+//!       {"include": "edges/catchment.json"},
+//!       {"from_node": "reservoir", "to_node": "demand"}
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! The includes are resolved when a model or network is loaded from a file, and the paths of the
+//! included files are recorded in [`crate::model::PywrNetwork::includes`]. A model that is saved
+//! after it is loaded contains the included components in place of the directives.
+use crate::error::SchemaError;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The key of an include directive.
+const INCLUDE_KEY: &str = "include";
+
+/// Returns `true` if `data` may contain an include directive.
+pub(crate) fn contains_include(data: &str) -> bool {
+    data.contains("\"include\"")
+}
+
+/// The path or pattern of `value` if it is an include directive.
+fn include_pattern(value: &Value) -> Option<&str> {
+    let map = value.as_object()?;
+    if map.len() != 1 {
+        return None;
+    }
+    map.get(INCLUDE_KEY)?.as_str()
+}
+
+/// Parse a document read from `path`, resolving the include directives in its network.
+///
+/// `network` is the key of the network in the document, or `None` if the document is a network.
+/// Returns the document and the paths of the included files relative to the directory of `path`.
+pub(crate) fn parse_with_includes(
+    data: &str,
+    path: &Path,
+    network: Option<&str>,
+) -> Result<(Value, Vec<PathBuf>), SchemaError> {
+    let mut document: Value = serde_json::from_str(data)?;
+
+    let sections = match network {
+        Some(key) => document.get_mut(key),
+        None => Some(&mut document),
+    };
+
+    let includes = match sections {
+        Some(sections) => resolve_includes(sections, path)?,
+        None => Vec::new(),
+    };
+
+    Ok((document, includes))
+}
+
+/// Resolve the include directives in the sections of a network read from `path`.
+///
+/// Returns the paths of the included files relative to the directory of `path`.
+pub(crate) fn resolve_includes(network: &mut Value, path: &Path) -> Result<Vec<PathBuf>, SchemaError> {
+    let Some(sections) = network.as_object_mut() else {
+        return Ok(Vec::new());
+    };
+
+    let root = path.parent().unwrap_or(Path::new(""));
+    let mut resolver = IncludeResolver {
+        root,
+        stack: vec![canonical(path)?],
+        included: Vec::new(),
+    };
+
+    for section in sections.values_mut() {
+        resolver.resolve(section, root)?;
+    }
+    Ok(resolver.included)
+}
+
+fn canonical(path: &Path) -> Result<PathBuf, SchemaError> {
+    std::fs::canonicalize(path).map_err(|error| SchemaError::IO {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+struct IncludeResolver<'a> {
+    /// The directory of the model; the included paths are recorded relative to this.
+    root: &'a Path,
+    /// The files currently being included, starting with the model, to detect circular includes.
+    stack: Vec<PathBuf>,
+    included: Vec<PathBuf>,
+}
+
+impl IncludeResolver<'_> {
+    /// Resolve the include directives of a section, or of the contents of an included file, that
+    /// was read from a file in `dir`.
+    fn resolve(&mut self, section: &mut Value, dir: &Path) -> Result<(), SchemaError> {
+        if let Some(pattern) = include_pattern(section) {
+            let pattern = pattern.to_string();
+            *section = Value::Array(self.include(&pattern, dir)?);
+        } else if let Value::Array(components) = section {
+            if components.iter().any(|c| include_pattern(c).is_some()) {
+                let mut resolved = Vec::with_capacity(components.len());
+                for component in components.drain(..) {
+                    match include_pattern(&component) {
+                        Some(pattern) => resolved.extend(self.include(pattern, dir)?),
+                        None => resolved.push(component),
+                    }
+                }
+                *components = resolved;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The components of the files matching `pattern` relative to `dir`.
+    fn include(&mut self, pattern: &str, dir: &Path) -> Result<Vec<Value>, SchemaError> {
+        // The directory is escaped so that only the pattern itself is treated as a glob
+        let full_pattern = Path::new(&glob::Pattern::escape(&dir.to_string_lossy())).join(pattern);
+        let include_error = |path: &Path, reason: String| SchemaError::Include {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let paths = glob::glob(&full_pattern.to_string_lossy())
+            .map_err(|e| include_error(&dir.join(pattern), e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| include_error(e.path(), e.error().to_string()))?;
+
+        if paths.is_empty() {
+            return Err(include_error(&dir.join(pattern), "no files match".to_string()));
+        }
+
+        let mut components = Vec::new();
+        for path in paths {
+            let canonical_path = canonical(&path)?;
+            if self.stack.contains(&canonical_path) {
+                let mut files = self.stack.clone();
+                files.push(canonical_path);
+                return Err(SchemaError::CircularInclude(files));
+            }
+
+            let data = std::fs::read_to_string(&path).map_err(|error| SchemaError::IO {
+                path: path.clone(),
+                error,
+            })?;
+            let mut value: Value = serde_json::from_str(&data).map_err(|e| include_error(&path, e.to_string()))?;
+
+            self.stack.push(canonical_path);
+            self.resolve(&mut value, path.parent().unwrap_or(Path::new("")))?;
+            self.stack.pop();
+
+            self.included.push(
+                path.strip_prefix(self.root)
+                    .map_or_else(|_| path.clone(), Path::to_path_buf),
+            );
+
+            match value {
+                Value::Array(values) => components.extend(values),
+                value => components.push(value),
+            }
+        }
+
+        Ok(components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{PywrMultiNetworkModel, PywrNetworkRef};
+    use crate::{PywrModel, SchemaError};
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, contents: &str) {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    const MODEL: &str = r#"
+    {
+        "metadata": {"title": "Includes"},
+        "timestepper": {"start": "2015-01-01", "end": "2015-12-31", "timestep": 1},
+        "network": {
+            "nodes": {"include": "nodes/*.json"},
+            "edges": [
+                {"include": "edges.json"},
+                {"from_node": "link1", "to_node": "demand1"}
+            ]
+        }
+    }
+    "#;
+
+    #[test]
+    fn test_includes() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "model.json", MODEL);
+        write(
+            &dir,
+            "nodes/a.json",
+            r#"[{"meta": {"name": "supply1"}, "type": "Input"}, {"meta": {"name": "link1"}, "type": "Link"}]"#,
+        );
+        write(
+            &dir,
+            "nodes/b.json",
+            r#"{"meta": {"name": "demand1"}, "type": "Output"}"#,
+        );
+        write(&dir, "edges.json", r#"{"from_node": "supply1", "to_node": "link1"}"#);
+
+        let model = PywrModel::from_path(dir.path().join("model.json")).unwrap();
+
+        let names: Vec<_> = model.network.nodes.iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["supply1", "link1", "demand1"]);
+        assert_eq!(model.network.edges.len(), 2);
+        assert_eq!(
+            model.network.includes,
+            vec![
+                PathBuf::from("nodes/a.json"),
+                PathBuf::from("nodes/b.json"),
+                PathBuf::from("edges.json")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multi_network_includes() {
+        let dir = TempDir::new().unwrap();
+        write(
+            &dir,
+            "model.json",
+            r#"
+            {
+                "metadata": {"title": "Includes"},
+                "timestepper": {"start": "2015-01-01", "end": "2015-12-31", "timestep": 1},
+                "networks": [
+                    {
+                        "name": "network1",
+                        "network": {
+                            "nodes": {"include": "nodes/*.json"},
+                            "edges": [{"from_node": "supply1", "to_node": "demand1"}]
+                        },
+                        "transfers": []
+                    },
+                    {"name": "network2", "network": "${NETWORK2}", "transfers": []}
+                ]
+            }
+            "#,
+        );
+        write(
+            &dir,
+            "nodes/a.json",
+            r#"[{"meta": {"name": "supply1"}, "type": "Input"}, {"meta": {"name": "demand1"}, "type": "Output"}]"#,
+        );
+
+        let definitions = BTreeMap::from([("NETWORK2".to_string(), "network2.json".to_string())]);
+        let model =
+            PywrMultiNetworkModel::from_path_with_definitions(dir.path().join("model.json"), &definitions).unwrap();
+
+        let PywrNetworkRef::Inline(network) = &model.networks[0].network else {
+            panic!("Expected an inline network");
+        };
+        let names: Vec<_> = network.nodes.iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["supply1", "demand1"]);
+        assert_eq!(network.includes, vec![PathBuf::from("nodes/a.json")]);
+
+        let PywrNetworkRef::Path(path) = &model.networks[1].network else {
+            panic!("Expected the path of a network");
+        };
+        assert_eq!(path, &PathBuf::from("network2.json"));
+    }
+
+    #[test]
+    fn test_circular_includes() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "model.json", MODEL);
+        write(&dir, "nodes/a.json", r#"{"include": "../nodes/b.json"}"#);
+        write(&dir, "nodes/b.json", r#"{"include": "a.json"}"#);
+
+        let result = PywrModel::from_path(dir.path().join("model.json"));
+        assert!(matches!(result, Err(SchemaError::CircularInclude(_))));
+    }
+}
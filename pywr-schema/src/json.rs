@@ -0,0 +1,196 @@
+//! Deserialisation of JSON documents with the location of any error.
+//!
+//! The errors of `serde_json` only give the line and column of the error, which is of little
+//! help in a large model. These functions track the path to the value that failed to deserialise
+//! (e.g. `network.nodes[12].max_volume`) and the name of the component containing it, which are
+//! reported in [`SchemaError::JsonPath`].
+//!
+//! Internally tagged enums, such as [`crate::nodes::Node`] and [`crate::parameters::Parameter`],
+//! buffer their contents before deserialising the variant given by their `type`, and the path
+//! is lost inside the buffered contents. An error in a node or parameter therefore stops at the
+//! component (e.g. `network.nodes[12]`), so the component is deserialised again as its variant
+//! to find the rest of the path. The path stops at any other internally tagged value within the
+//! component (e.g. a metric).
+use crate::error::SchemaError;
+use crate::nodes::NodeType;
+use crate::parameters::ParameterType;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use std::str::FromStr;
+
+/// Deserialise a JSON string, reporting the location of any error.
+pub(crate) fn from_json_str<T: DeserializeOwned>(data: &str) -> Result<T, SchemaError> {
+    let mut deserializer = serde_json::Deserializer::from_str(data);
+
+    let value: T = serde_path_to_error::deserialize(&mut deserializer).map_err(|error| {
+        // The name of the component is only available if the document is valid JSON
+        let document = serde_json::from_str(data).ok();
+        path_error(error, document.as_ref())
+    })?;
+    deserializer.end()?;
+
+    Ok(value)
+}
+
+/// Deserialise a JSON value, reporting the location of any error.
+pub(crate) fn from_json_value<T: DeserializeOwned>(document: &Value) -> Result<T, SchemaError> {
+    serde_path_to_error::deserialize(document).map_err(|error| path_error(error, Some(document)))
+}
+
+fn path_error(error: serde_path_to_error::Error<serde_json::Error>, document: Option<&Value>) -> SchemaError {
+    let path = error.path().clone();
+    let error = error.into_inner();
+
+    // Syntax errors are not specific to a value, and are reported by their line and column
+    if error.is_syntax() || error.is_eof() || error.is_io() {
+        return SchemaError::Json(error);
+    }
+
+    let component = document.and_then(|document| component_name(document, &path));
+
+    match document.and_then(|document| component_error(document, &path)) {
+        Some((inner_path, error)) => SchemaError::JsonPath {
+            path: join_paths(&path, &inner_path),
+            component,
+            error,
+        },
+        None => SchemaError::JsonPath {
+            path: path.to_string(),
+            component,
+            error,
+        },
+    }
+}
+
+/// The path and error within the node or parameter at `path`, if there is one.
+fn component_error(
+    document: &Value,
+    path: &serde_path_to_error::Path,
+) -> Option<(serde_path_to_error::Path, serde_json::Error)> {
+    let segments: Vec<&Segment> = path.iter().collect();
+    let [.., Segment::Map { key }, Segment::Seq { .. }] = segments.as_slice() else {
+        return None;
+    };
+
+    let value = values_along(document, path).last()?;
+    let ty = value.get("type")?.as_str()?;
+
+    let (inner_path, error) = match key.as_str() {
+        "nodes" => NodeType::from_str(ty).ok()?.variant_error(value)?,
+        "parameters" => ParameterType::from_str(ty).ok()?.variant_error(value)?,
+        _ => return None,
+    };
+
+    // An error in the component itself (e.g. a missing field) is already at the right path
+    (inner_path.iter().next().is_some()).then_some((inner_path, error))
+}
+
+/// The path and error of deserialising `value`, the contents of a variant of an internally tagged
+/// enum, as `T`.
+pub(crate) fn variant_error<T: DeserializeOwned>(
+    value: &Value,
+) -> Option<(serde_path_to_error::Path, serde_json::Error)> {
+    let mut value = value.clone();
+    value.as_object_mut()?.remove("type");
+
+    serde_path_to_error::deserialize::<_, T>(&value)
+        .err()
+        .map(|error| (error.path().clone(), error.into_inner()))
+}
+
+fn join_paths(outer: &serde_path_to_error::Path, inner: &serde_path_to_error::Path) -> String {
+    let inner = inner.to_string();
+    if inner.starts_with('[') {
+        format!("{outer}{inner}")
+    } else {
+        format!("{outer}.{inner}")
+    }
+}
+
+/// The values in `document` along `path`.
+fn values_along<'a>(document: &'a Value, path: &serde_path_to_error::Path) -> Vec<&'a Value> {
+    let mut value = document;
+    let mut values = Vec::new();
+
+    for segment in path.iter() {
+        let next = match segment {
+            Segment::Seq { index } => value.get(*index),
+            Segment::Map { key } => value.get(key),
+            // Enum variants and unknown segments do not correspond to a value in the document
+            Segment::Enum { .. } | Segment::Unknown => continue,
+        };
+
+        let Some(next) = next else {
+            break;
+        };
+        value = next;
+        values.push(value);
+    }
+
+    values
+}
+
+/// The name of the innermost component (i.e. an object with a `meta.name`) along `path`.
+fn component_name(document: &Value, path: &serde_path_to_error::Path) -> Option<String> {
+    values_along(document, path)
+        .into_iter()
+        .rev()
+        .find_map(|value| value.get("meta")?.get("name")?.as_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::PywrNetwork;
+    use crate::SchemaError;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_path_error() {
+        let data = r#"
+        {
+            "nodes": [
+                {"meta": {"name": "supply1"}, "type": "Input"},
+                {"meta": {"name": "demand1"}, "type": "Output", "max_flow": {"type": "Constant", "value": "ten"}}
+            ],
+            "edges": []
+        }
+        "#;
+
+        match PywrNetwork::from_str(data) {
+            Err(SchemaError::JsonPath { path, component, .. }) => {
+                assert_eq!(path, "nodes[1].max_flow");
+                assert_eq!(component.as_deref(), Some("demand1"));
+            }
+            Err(e) => panic!("Unexpected error: {e}"),
+            Ok(_) => panic!("Expected an error"),
+        }
+
+        let data = r#"
+        {
+            "nodes": [{"meta": {"name": "supply1"}, "type": "Input"}],
+            "edges": [],
+            "parameters": [
+                {"meta": {"name": "p1"}, "type": "Constant", "value": 1.0},
+                {"meta": {"name": "p2"}, "type": "MonthlyProfile", "values": [1.0, "two"]}
+            ]
+        }
+        "#;
+
+        match PywrNetwork::from_str(data) {
+            Err(SchemaError::JsonPath { path, component, .. }) => {
+                assert_eq!(path, "parameters[1].values");
+                assert_eq!(component.as_deref(), Some("p2"));
+            }
+            Err(e) => panic!("Unexpected error: {e}"),
+            Ok(_) => panic!("Expected an error"),
+        }
+
+        // Syntax errors are still reported by their line and column
+        assert!(matches!(
+            PywrNetwork::from_str("{\"nodes\": ["),
+            Err(SchemaError::Json(_))
+        ));
+    }
+}
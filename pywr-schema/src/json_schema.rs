@@ -0,0 +1,146 @@
+//! JSON Schema documents for the files used to define Pywr models.
+//!
+//! These documents can be used by editors and other tools to validate, and provide
+//! autocompletion for, model files. Each document is given a stable `$id` so that it can be
+//! referenced from a model file (e.g. using the `$schema` keyword) or from editor settings.
+//!
+use crate::data_tables::DataTable;
+use crate::edge::Edge;
+use crate::metric_sets::MetricSet;
+use crate::model::{PywrModel, PywrMultiNetworkModel, PywrNetwork};
+use crate::nodes::Node;
+use crate::outputs::Output;
+use crate::parameters::Parameter;
+use crate::timeseries::Timeseries;
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The prefix of the `$id` given to each exported schema document.
+pub const SCHEMA_ID_PREFIX: &str = "urn:pywr:schema:v2:";
+
+/// A JSON Schema document for one of the Pywr file types or components.
+pub struct SchemaDocument {
+    name: &'static str,
+    schema: RootSchema,
+}
+
+impl SchemaDocument {
+    fn new(name: &'static str, mut schema: RootSchema) -> Self {
+        schema.schema.metadata().id = Some(format!("{SCHEMA_ID_PREFIX}{name}"));
+        Self { name, schema }
+    }
+
+    /// The name of the document (e.g. `model` or `node`).
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// The `$id` of the document.
+    pub fn id(&self) -> String {
+        format!("{SCHEMA_ID_PREFIX}{}", self.name)
+    }
+
+    /// A file name suitable for saving the document.
+    pub fn file_name(&self) -> String {
+        format!("{}.schema.json", self.name)
+    }
+
+    pub fn schema(&self) -> &RootSchema {
+        &self.schema
+    }
+
+    /// The descriptions of the variants of a component with a `type` tag (e.g. the nodes).
+    ///
+    /// The description of each variant is taken from the documentation of its type, and only
+    /// the first paragraph is returned. Variants without documentation are omitted.
+    pub fn variant_descriptions(&self) -> BTreeMap<String, String> {
+        let Ok(value) = serde_json::to_value(&self.schema) else {
+            return BTreeMap::new();
+        };
+
+        let description = |schema: &Value| -> Option<String> {
+            let description = schema["description"].as_str().or_else(|| {
+                schema["allOf"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|s| s["$ref"].as_str()?.strip_prefix("#/definitions/"))
+                    .find_map(|name| value["definitions"][name]["description"].as_str())
+            })?;
+            description.split("\n\n").next().map(|d| d.trim().to_string())
+        };
+
+        value["oneOf"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|variant| {
+                let ty = variant["properties"]["type"]["enum"][0].as_str()?;
+                Some((ty.to_string(), description(variant)?))
+            })
+            .collect()
+    }
+}
+
+/// The schema for a single network model file (i.e. [`PywrModel`]).
+pub fn model_schema() -> SchemaDocument {
+    SchemaDocument::new("model", schema_for!(PywrModel))
+}
+
+/// The schema for a single node (i.e. [`Node`]).
+pub fn node_schema() -> SchemaDocument {
+    SchemaDocument::new("node", schema_for!(Node))
+}
+
+/// The schema for a single parameter (i.e. [`Parameter`]).
+pub fn parameter_schema() -> SchemaDocument {
+    SchemaDocument::new("parameter", schema_for!(Parameter))
+}
+
+/// Return the schema documents for all model file types and their components.
+///
+/// This includes the single and multi-network model files, the network file that can be
+/// referenced from a multi-network model, and each of the components that make up a network.
+pub fn all_schemas() -> Vec<SchemaDocument> {
+    vec![
+        model_schema(),
+        SchemaDocument::new("multi-network-model", schema_for!(PywrMultiNetworkModel)),
+        SchemaDocument::new("network", schema_for!(PywrNetwork)),
+        node_schema(),
+        SchemaDocument::new("edge", schema_for!(Edge)),
+        parameter_schema(),
+        SchemaDocument::new("table", schema_for!(DataTable)),
+        SchemaDocument::new("timeseries", schema_for!(Timeseries)),
+        SchemaDocument::new("metric-set", schema_for!(MetricSet)),
+        SchemaDocument::new("output", schema_for!(Output)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all_schemas, node_schema, SCHEMA_ID_PREFIX};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_schema_ids() {
+        let schemas = all_schemas();
+
+        let ids: HashSet<_> = schemas.iter().map(|s| s.id()).collect();
+        assert_eq!(ids.len(), schemas.len(), "Schema ids must be unique.");
+
+        for doc in &schemas {
+            let value = serde_json::to_value(doc.schema()).unwrap();
+            assert_eq!(value["$id"], doc.id());
+            assert!(doc.id().starts_with(SCHEMA_ID_PREFIX));
+        }
+    }
+
+    #[test]
+    fn test_variant_descriptions() {
+        let descriptions = node_schema().variant_descriptions();
+
+        let turbine = descriptions.get("Turbine").expect("Turbine node has no description.");
+        assert!(turbine.starts_with("This turbine node can be used"));
+    }
+}
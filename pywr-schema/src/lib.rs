@@ -4,20 +4,37 @@
 //!
 //! Serializing and deserializing is accomplished using [`serde`].
 //!
+mod constants;
 pub mod data_tables;
 pub mod edge;
 mod error;
+#[cfg(feature = "core")]
+mod excel;
+mod include;
+mod json;
+pub mod json_schema;
 pub mod metric;
 pub mod metric_sets;
 pub mod model;
 pub mod nodes;
+pub mod optimisation;
 pub mod outputs;
 pub mod parameters;
+mod prune;
+pub mod state_overrides;
 pub mod timeseries;
+mod unknown;
 mod v1;
+mod validate;
+mod variables;
 mod visit;
 
 pub use error::{ComponentConversionError, ConversionError, SchemaError};
 pub use model::PywrModel;
+#[cfg(feature = "core")]
+pub use model::{ModelUpdate, UpdateFromSchema};
+pub use prune::PruneReport;
+pub use unknown::{UnknownComponent, UnknownComponentKind};
 pub use v1::{ConversionData, TryFromV1, TryIntoV2};
+pub use validate::{ValidationIssue, ValidationSeverity};
 pub use visit::{VisitMetrics, VisitPaths};
@@ -18,6 +18,7 @@ use crate::v1::{ConversionData, TryFromV1, TryIntoV2};
 use crate::ConversionError;
 #[cfg(feature = "core")]
 use pywr_core::{
+    derived_metric::{DerivedMetric, RollingWindow, SeasonToDate},
     metric::{MetricF64, MetricU64},
     models::MultiNetworkTransferIndex,
     parameters::ParameterName,
@@ -27,6 +28,7 @@ use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::parameters::ParameterValue as ParameterValueV1;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::num::NonZeroUsize;
 use strum_macros::{Display, EnumDiscriminants, EnumString, IntoStaticStr, VariantNames};
 
 /// A floating point value representing different model metrics.
@@ -45,12 +47,25 @@ use strum_macros::{Display, EnumDiscriminants, EnumString, IntoStaticStr, Varian
 pub enum Metric {
     /// A constant floating point value.
     Constant { value: f64 },
+    /// A value that varies by the month of the year; 12 values starting in January.
+    ///
+    /// This is a shorthand for a `MonthlyProfile` parameter that is created when the model is
+    /// built. It is useful for values, such as the cost of a node following a seasonal tariff,
+    /// that would otherwise require a separate parameter to be declared.
+    MonthlyProfile { values: Vec<f64> },
+    /// A value that varies by the day of the year; 366 values starting on the 1st January.
+    ///
+    /// This is a shorthand for a `DailyProfile` parameter that is created when the model is
+    /// built (see `MonthlyProfile`).
+    DailyProfile { values: Vec<f64> },
     /// A reference to a constant value in a table.
     Table(TableDataRef),
     /// An attribute of a node.
     Node(NodeReference),
     /// An attribute of an edge.
     Edge(EdgeReference),
+    /// An attribute of a virtual storage (e.g. the remaining volume of a licence).
+    VirtualStorage(VirtualStorageReference),
     /// A reference to a value from a timeseries.
     Timeseries(TimeseriesReference),
     /// A reference to a global parameter.
@@ -59,6 +74,59 @@ pub enum Metric {
     LocalParameter(ParameterReference),
     /// A reference to an inter-network transfer by name.
     InterNetworkTransfer { name: String },
+    /// A value supplied by an external process each time-step when running a coupled simulation.
+    ///
+    /// The input is created the first time it is referenced. Any other references using the same
+    /// name share the same value.
+    ExternalInput { name: String },
+    /// The mass-balance residual of a state override in the current time-step.
+    StateOverrideResidual { name: String },
+    /// A statistic of another metric over the last `window` time-steps, including the current
+    /// time-step.
+    ///
+    /// The value is updated at the end of each time-step, so a parameter referencing it uses
+    /// the statistic up to the end of the previous time-step. It is zero in the first time-step.
+    Rolling {
+        name: String,
+        metric: Box<Metric>,
+        window: NonZeroUsize,
+        statistic: WindowStatistic,
+    },
+    /// A statistic of another metric since the start of the current season.
+    ///
+    /// The season starts each year on the given `month` and `day` (e.g. the 1st April for a water
+    /// year). The value is updated in the same way as a `Rolling` metric.
+    SeasonToDate {
+        name: String,
+        metric: Box<Metric>,
+        month: u32,
+        day: u32,
+        statistic: WindowStatistic,
+    },
+}
+
+/// A statistic of the values of a metric over a period of time.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq, Display)]
+pub enum WindowStatistic {
+    /// The sum of the values multiplied by the duration of their time-step in days (e.g. the
+    /// volume from a flow).
+    Total,
+    /// The mean of the values weighted by the duration of their time-step.
+    Mean,
+    Min,
+    Max,
+}
+
+#[cfg(feature = "core")]
+impl From<WindowStatistic> for pywr_core::derived_metric::WindowStatistic {
+    fn from(value: WindowStatistic) -> Self {
+        match value {
+            WindowStatistic::Total => Self::Total,
+            WindowStatistic::Mean => Self::Mean,
+            WindowStatistic::Min => Self::Min,
+            WindowStatistic::Max => Self::Max,
+        }
+    }
 }
 
 impl Default for Metric {
@@ -96,6 +164,18 @@ impl Metric {
                 parameter_ref.load_f64(network, parent)
             }
             Self::Constant { value } => Ok((*value).into()),
+            Self::MonthlyProfile { values } => {
+                let values = profile_values(values, "monthly")?;
+                let name = inline_parameter_name(network, "monthly-profile", parent);
+                let p = pywr_core::parameters::MonthlyProfileParameter::new(name, values, None);
+                Ok(network.add_simple_parameter(Box::new(p))?.into())
+            }
+            Self::DailyProfile { values } => {
+                let values = profile_values(values, "daily")?;
+                let name = inline_parameter_name(network, "daily-profile", parent);
+                let p = pywr_core::parameters::DailyProfileParameter::new(name, values);
+                Ok(network.add_simple_parameter(Box::new(p))?.into())
+            }
             Self::Table(table_ref) => {
                 let value = args
                     .tables
@@ -128,6 +208,39 @@ impl Metric {
                 }
             }
             Self::Edge(edge_ref) => edge_ref.load(network, args),
+            Self::VirtualStorage(vs_ref) => vs_ref.load(network),
+            Self::ExternalInput { name } => {
+                let idx = match network.get_external_input_index_by_name(name) {
+                    Ok(idx) => idx,
+                    Err(_) => network.add_external_input(name)?,
+                };
+                Ok(MetricF64::ExternalInput(idx))
+            }
+            Self::StateOverrideResidual { name } => {
+                let idx = network.get_state_override_index_by_name(name)?;
+                Ok(MetricF64::StateOverrideResidual(idx))
+            }
+            Self::Rolling {
+                name,
+                metric,
+                window,
+                statistic,
+            } => {
+                let metric = metric.load(network, args, parent)?;
+                let rolling = RollingWindow::new(name, metric, *window, (*statistic).into());
+                Ok(network.add_derived_metric(DerivedMetric::Rolling(rolling)).into())
+            }
+            Self::SeasonToDate {
+                name,
+                metric,
+                month,
+                day,
+                statistic,
+            } => {
+                let metric = metric.load(network, args, parent)?;
+                let season = SeasonToDate::new(name, metric, *month, *day, (*statistic).into())?;
+                Ok(network.add_derived_metric(DerivedMetric::SeasonToDate(season)).into())
+            }
         }
     }
 
@@ -136,11 +249,17 @@ impl Metric {
             Self::Node(node_ref) => Ok(node_ref.name.to_string()),
             Self::Parameter(parameter_ref) => Ok(parameter_ref.name.clone()),
             Self::LocalParameter(parameter_ref) => Ok(parameter_ref.name.clone()),
-            Self::Constant { .. } => Err(SchemaError::LiteralConstantOutputNotSupported),
+            Self::Constant { .. } | Self::MonthlyProfile { .. } | Self::DailyProfile { .. } => {
+                Err(SchemaError::LiteralConstantOutputNotSupported)
+            }
             Self::Table(table_ref) => Ok(table_ref.table.clone()),
             Self::Timeseries(ts_ref) => Ok(ts_ref.name.clone()),
             Self::InterNetworkTransfer { name } => Ok(name.clone()),
             Self::Edge(edge_ref) => Ok(edge_ref.edge.to_string()),
+            Self::VirtualStorage(vs_ref) => Ok(vs_ref.name.clone()),
+            Self::ExternalInput { name } => Ok(name.clone()),
+            Self::StateOverrideResidual { name } => Ok(name.clone()),
+            Self::Rolling { name, .. } | Self::SeasonToDate { name, .. } => Ok(name.clone()),
         }
     }
 
@@ -150,10 +269,19 @@ impl Metric {
             Self::Parameter(_) => "value".to_string(),
             Self::LocalParameter(_) => "value".to_string(),
             Self::Constant { .. } => "value".to_string(),
+            Self::MonthlyProfile { .. } => "value".to_string(),
+            Self::DailyProfile { .. } => "value".to_string(),
             Self::Table(_) => "value".to_string(),
             Self::Timeseries(_) => "value".to_string(),
             Self::InterNetworkTransfer { .. } => "value".to_string(),
             Self::Edge { .. } => "Flow".to_string(),
+            Self::VirtualStorage(vs_ref) => vs_ref.attribute.unwrap_or_default().to_string(),
+            Self::ExternalInput { .. } => "value".to_string(),
+            Self::StateOverrideResidual { .. } => "residual".to_string(),
+            Self::Rolling { statistic, .. } => format!("rolling_{}", statistic.to_string().to_lowercase()),
+            Self::SeasonToDate { statistic, .. } => {
+                format!("season_to_date_{}", statistic.to_string().to_lowercase())
+            }
         };
 
         Ok(attribute)
@@ -168,10 +296,17 @@ impl Metric {
             Self::Parameter(parameter_ref) => Some(parameter_ref.parameter_type(args)?.to_string()),
             Self::LocalParameter(parameter_ref) => Some(parameter_ref.parameter_type(args)?.to_string()),
             Self::Constant { .. } => None,
+            Self::MonthlyProfile { .. } => None,
+            Self::DailyProfile { .. } => None,
             Self::Table(_) => None,
             Self::Timeseries(_) => None,
             Self::InterNetworkTransfer { .. } => None,
             Self::Edge { .. } => None,
+            Self::VirtualStorage(vs_ref) => vs_ref.sub_name.clone(),
+            Self::ExternalInput { .. } => None,
+            Self::StateOverrideResidual { .. } => None,
+            Self::Rolling { .. } => None,
+            Self::SeasonToDate { .. } => None,
         };
 
         Ok(sub_type)
@@ -198,6 +333,26 @@ impl Metric {
     }
 }
 
+/// Convert the values of an inline profile to an array of the required length.
+#[cfg(feature = "core")]
+fn profile_values<const N: usize>(values: &[f64], ty: &str) -> Result<[f64; N], SchemaError> {
+    values.try_into().map_err(|_| SchemaError::InvalidProfileLength {
+        ty: ty.to_string(),
+        expected: N,
+        found: values.len(),
+    })
+}
+
+/// A name, not used by any other parameter of `parent`, for a parameter created from an inline
+/// metric.
+#[cfg(feature = "core")]
+fn inline_parameter_name(network: &pywr_core::network::Network, prefix: &str, parent: Option<&str>) -> ParameterName {
+    (0..)
+        .map(|i| ParameterName::new(&format!("{prefix}-{i}"), parent))
+        .find(|name| network.get_parameter_index_by_name(name).is_err())
+        .expect("Failed to find a unique name for an inline parameter.")
+}
+
 impl TryFromV1<ParameterValueV1> for Metric {
     type Error = ConversionError;
 
@@ -277,16 +432,16 @@ impl NodeReference {
     #[cfg(feature = "core")]
     pub fn load_u64(
         &self,
-        _network: &mut pywr_core::network::Network,
+        network: &mut pywr_core::network::Network,
         args: &LoadArgs,
     ) -> Result<MetricU64, SchemaError> {
         // This is the associated node in the schema
-        let _node = args
+        let node = args
             .schema
             .get_node_by_name(&self.name)
             .ok_or_else(|| SchemaError::NodeNotFound(self.name.clone()))?;
 
-        todo!("Support usize attributes on nodes.")
+        node.create_index_metric(network, self.attribute, args)
     }
 
     /// Return the attribute of the node. If the attribute is not specified then the default
@@ -467,6 +622,48 @@ impl EdgeReference {
     }
 }
 
+/// An attribute of a virtual storage that can be used as a metric.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default, JsonSchema, Display, PartialEq, Eq)]
+pub enum VirtualStorageAttribute {
+    #[default]
+    Volume,
+    ProportionalVolume,
+}
+
+/// A reference to a virtual storage with an optional attribute.
+///
+/// Virtual storages are created by the virtual storage nodes (e.g. `VirtualStorage`,
+/// `AnnualVirtualStorage` or `Licence`) and are referred to by the name of the node. Nodes that
+/// create more than one virtual storage distinguish them by a sub-name; for example, each limit
+/// of a `Licence` node is named `limit-00`, `limit-01`, etc.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct VirtualStorageReference {
+    /// The name of the node that created the virtual storage.
+    pub name: String,
+    /// The sub-name of the virtual storage, if the node created more than one.
+    pub sub_name: Option<String>,
+    /// The attribute of the virtual storage. If this is `None` then the volume is used.
+    pub attribute: Option<VirtualStorageAttribute>,
+}
+
+#[cfg(feature = "core")]
+impl VirtualStorageReference {
+    pub fn load(&self, network: &mut pywr_core::network::Network) -> Result<MetricF64, SchemaError> {
+        let idx = network.get_virtual_storage_node_index_by_name(&self.name, self.sub_name.as_deref())?;
+
+        let metric = match self.attribute.unwrap_or_default() {
+            VirtualStorageAttribute::Volume => MetricF64::VirtualStorageVolume(idx),
+            VirtualStorageAttribute::ProportionalVolume => {
+                let dm = DerivedMetric::VirtualStorageProportionalVolume(idx);
+                MetricF64::DerivedMetric(network.add_derived_metric(dm))
+            }
+        };
+
+        Ok(metric)
+    }
+}
+
 /// An unsigned integer value representing different model metrics.
 ///
 /// This struct is the integer equivalent of [`Metric`] and is used in places where an integer
@@ -3,8 +3,12 @@ use crate::error::SchemaError;
 use crate::metric::Metric;
 #[cfg(feature = "core")]
 use crate::model::LoadArgs;
+use crate::nodes::NodeAttribute;
 #[cfg(feature = "core")]
 use crate::parameters::{Parameter, PythonReturnType};
+use chrono::NaiveDate;
+#[cfg(feature = "core")]
+use chrono::NaiveTime;
 use pywr_schema_macros::PywrVisitPaths;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -21,37 +25,106 @@ pub enum MetricAggFunc {
     Min,
     Mean,
     CountNonZero,
+    /// The quantile (between 0 and 1) of the values (e.g. `0.9` for the 90th percentile).
+    Quantile {
+        quantile: f64,
+    },
+    /// The proportion of the values that are greater than the threshold.
+    ExceedanceFrequency {
+        threshold: f64,
+    },
 }
 
 #[cfg(feature = "core")]
-impl From<MetricAggFunc> for pywr_core::recorders::AggregationFunction {
-    fn from(value: MetricAggFunc) -> Self {
-        match value {
+impl TryFrom<MetricAggFunc> for pywr_core::recorders::AggregationFunction {
+    type Error = SchemaError;
+
+    fn try_from(value: MetricAggFunc) -> Result<Self, Self::Error> {
+        let func = match value {
             MetricAggFunc::Sum => pywr_core::recorders::AggregationFunction::Sum,
             MetricAggFunc::Max => pywr_core::recorders::AggregationFunction::Max,
             MetricAggFunc::Min => pywr_core::recorders::AggregationFunction::Min,
             MetricAggFunc::Mean => pywr_core::recorders::AggregationFunction::Mean,
             MetricAggFunc::CountNonZero => pywr_core::recorders::AggregationFunction::CountNonZero,
-        }
+            MetricAggFunc::Quantile { quantile } => {
+                if !(0.0..=1.0).contains(&quantile) {
+                    return Err(SchemaError::InvalidAggregationFunction(format!(
+                        "the quantile must be between 0 and 1; found {}",
+                        quantile
+                    )));
+                }
+                pywr_core::recorders::AggregationFunction::Quantile(quantile)
+            }
+            MetricAggFunc::ExceedanceFrequency { threshold } => {
+                pywr_core::recorders::AggregationFunction::ExceedanceFrequency { threshold }
+            }
+        };
+
+        Ok(func)
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Copy, Clone, JsonSchema, strum_macros::Display)]
+/// The frequency of the periods over which metric values are aggregated.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, strum_macros::Display)]
 #[serde(tag = "type")]
 pub enum MetricAggFrequency {
     Monthly,
     Annual,
-    Days { days: NonZeroUsize },
+    /// Annual periods starting on the first day of `start_month` (1-12). For example, water
+    /// years that start on the 1st of October.
+    WaterYear {
+        start_month: u32,
+    },
+    Days {
+        days: NonZeroUsize,
+    },
+    /// Periods bounded by the given dates, which must be in ascending order. Each date is the
+    /// start of a new period. Any values before the first date, or after the last date, are
+    /// aggregated in a single period.
+    Periods {
+        boundaries: Vec<NaiveDate>,
+    },
 }
 
 #[cfg(feature = "core")]
-impl From<MetricAggFrequency> for pywr_core::recorders::AggregationFrequency {
-    fn from(value: MetricAggFrequency) -> Self {
-        match value {
+impl TryFrom<MetricAggFrequency> for pywr_core::recorders::AggregationFrequency {
+    type Error = SchemaError;
+
+    fn try_from(value: MetricAggFrequency) -> Result<Self, Self::Error> {
+        let freq = match value {
             MetricAggFrequency::Monthly => pywr_core::recorders::AggregationFrequency::Monthly,
             MetricAggFrequency::Annual => pywr_core::recorders::AggregationFrequency::Annual,
+            MetricAggFrequency::WaterYear { start_month } => {
+                let start_month = u8::try_from(start_month)
+                    .ok()
+                    .and_then(|m| chrono::Month::try_from(m).ok())
+                    .ok_or_else(|| {
+                        SchemaError::InvalidAggregationFrequency(format!(
+                            "the start month of a water year must be between 1 and 12; found {}",
+                            start_month
+                        ))
+                    })?;
+                pywr_core::recorders::AggregationFrequency::WaterYear { start_month }
+            }
             MetricAggFrequency::Days { days } => pywr_core::recorders::AggregationFrequency::Days(days),
-        }
+            MetricAggFrequency::Periods { boundaries } => {
+                if boundaries.is_empty() {
+                    return Err(SchemaError::InvalidAggregationFrequency(
+                        "at least one period boundary must be given".to_string(),
+                    ));
+                }
+                if boundaries.windows(2).any(|w| w[0] >= w[1]) {
+                    return Err(SchemaError::InvalidAggregationFrequency(
+                        "the period boundaries must be in ascending order".to_string(),
+                    ));
+                }
+                pywr_core::recorders::AggregationFrequency::Periods(
+                    boundaries.into_iter().map(|d| d.and_time(NaiveTime::MIN)).collect(),
+                )
+            }
+        };
+
+        Ok(freq)
     }
 }
 
@@ -64,7 +137,7 @@ impl From<MetricAggFrequency> for pywr_core::recorders::AggregationFrequency {
 ///
 /// If the metric set has a child aggregator then the aggregation will be performed over the
 /// aggregated values of the child aggregator.
-#[derive(Deserialize, Serialize, Clone, JsonSchema)]
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
 pub struct MetricAggregator {
     /// Optional aggregation frequency.
     pub freq: Option<MetricAggFrequency>,
@@ -75,62 +148,316 @@ pub struct MetricAggregator {
 }
 
 #[cfg(feature = "core")]
-impl From<MetricAggregator> for pywr_core::recorders::Aggregator {
-    fn from(value: MetricAggregator) -> Self {
-        pywr_core::recorders::Aggregator::new(
-            value.freq.map(|p| p.into()),
-            value.func.into(),
-            value.child.map(|a| (*a).into()),
-        )
+impl TryFrom<MetricAggregator> for pywr_core::recorders::Aggregator {
+    type Error = SchemaError;
+
+    fn try_from(value: MetricAggregator) -> Result<Self, Self::Error> {
+        Ok(pywr_core::recorders::Aggregator::new(
+            value.freq.map(|p| p.try_into()).transpose()?,
+            value.func.try_into()?,
+            value.child.map(|a| (*a).try_into()).transpose()?,
+        ))
+    }
+}
+
+/// A statistic of the values of each metric computed across all of the scenarios.
+///
+/// For example, a statistic with the function `{"type": "Quantile", "quantile": 0.9}` gives the
+/// 90th percentile of the values of the scenario ensemble, and a statistic with the function
+/// `{"type": "ExceedanceFrequency", "threshold": 0.0}` applied to a metric of the deficit of a
+/// demand gives the probability of failure.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MetricScenarioStatistic {
+    /// The label of the statistic, which is used in place of the scenario in the outputs.
+    pub label: String,
+    /// The function to apply over the values of the scenarios.
+    pub func: MetricAggFunc,
+}
+
+#[cfg(feature = "core")]
+impl TryFrom<MetricScenarioStatistic> for pywr_core::recorders::ScenarioStatistic {
+    type Error = SchemaError;
+
+    fn try_from(value: MetricScenarioStatistic) -> Result<Self, Self::Error> {
+        Ok(pywr_core::recorders::ScenarioStatistic::new(
+            &value.label,
+            value.func.try_into()?,
+        ))
+    }
+}
+
+/// The calculation of a [`MetricSetDerivedMetric`].
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, JsonSchema, strum_macros::Display)]
+pub enum DerivedMetricOperation {
+    /// The value of `lhs` divided by the value of `rhs`.
+    Ratio,
+    /// The value of `lhs` minus the value of `rhs`.
+    Difference,
+    /// The sum of the values of `lhs` and `rhs`.
+    Sum,
+}
+
+#[cfg(feature = "core")]
+impl From<DerivedMetricOperation> for pywr_core::recorders::DerivedOutputOperation {
+    fn from(value: DerivedMetricOperation) -> Self {
+        match value {
+            DerivedMetricOperation::Ratio => pywr_core::recorders::DerivedOutputOperation::Ratio,
+            DerivedMetricOperation::Difference => pywr_core::recorders::DerivedOutputOperation::Difference,
+            DerivedMetricOperation::Sum => pywr_core::recorders::DerivedOutputOperation::Sum,
+        }
     }
 }
 
+/// A metric calculated from the values of two other metrics of the same metric set.
+///
+/// The operands, `lhs` and `rhs`, are the names of the other metrics as they appear in the
+/// outputs. If the set has more than one metric with the same name the attribute must also be
+/// given, separated by a `/` (e.g. `reservoir1/volume`). A derived metric can also refer to a
+/// derived metric that is defined before it.
+///
+/// For example, the ratio of the supply to the demand of a node:
+///
+/// ```json
+/// {"name": "demand1-supply-ratio", "operation": "Ratio", "lhs": "demand1", "rhs": "demand-1"}
+/// ```
+///
+/// The value is calculated every time-step, before any aggregation by the metric set. A ratio
+/// with a denominator of zero is infinite, or NaN if the numerator is also zero.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MetricSetDerivedMetric {
+    /// The name of the metric in the outputs.
+    pub name: String,
+    /// The attribute of the metric in the outputs. Defaults to the name of the operation (e.g.
+    /// `ratio`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<String>,
+    pub operation: DerivedMetricOperation,
+    pub lhs: String,
+    pub rhs: String,
+}
+
+#[cfg(feature = "core")]
+impl MetricSetDerivedMetric {
+    fn load(
+        &self,
+        metric_set: &str,
+        metrics: &[pywr_core::recorders::OutputMetric],
+    ) -> Result<pywr_core::recorders::OutputMetric, SchemaError> {
+        let operation: pywr_core::recorders::DerivedOutputOperation = self.operation.into();
+        let attribute = self.attribute.as_deref().unwrap_or(operation.name());
+
+        let lhs = self.find_operand(metric_set, metrics, &self.lhs)?;
+        let rhs = self.find_operand(metric_set, metrics, &self.rhs)?;
+
+        Ok(pywr_core::recorders::OutputMetric::derived(
+            &self.name, attribute, operation, lhs, rhs,
+        ))
+    }
+
+    fn find_operand(
+        &self,
+        metric_set: &str,
+        metrics: &[pywr_core::recorders::OutputMetric],
+        operand: &str,
+    ) -> Result<pywr_core::recorders::OutputMetric, SchemaError> {
+        let invalid = |reason: String| SchemaError::InvalidDerivedMetric {
+            metric_set: metric_set.to_string(),
+            name: self.name.clone(),
+            reason,
+        };
+
+        let (name, attribute) = match operand.split_once('/') {
+            Some((name, attribute)) => (name, Some(attribute)),
+            None => (operand, None),
+        };
+
+        let mut found = metrics
+            .iter()
+            .filter(|m| m.name() == name && attribute.map_or(true, |a| m.attribute() == a));
+
+        match (found.next(), found.next()) {
+            (Some(metric), None) => Ok(metric.clone()),
+            (None, _) => Err(invalid(format!("the metric set has no metric `{operand}`"))),
+            (Some(_), Some(_)) => Err(invalid(format!(
+                "the metric set has more than one metric `{operand}`; the attribute must be given (e.g. `{name}/<attribute>`)"
+            ))),
+        }
+    }
+}
+
+/// A selection of the nodes of a model by their names and types.
+///
+/// For example, `{"types": ["Storage"], "attribute": "Volume"}` selects the volume of every
+/// storage node, and `{"name": "demand-*"}` the default metric of every node whose name starts
+/// with `demand-`.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MetricSetNodeSelection {
+    /// A glob pattern (e.g. `reservoir-*`) that the names of the nodes must match. Nodes with any
+    /// name are selected if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The types of the nodes (e.g. `Storage`). Nodes of any type are selected if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub types: Option<Vec<String>>,
+    /// The attribute of the nodes to add. The default attribute of each node is added if not
+    /// given. Every selected node must support the attribute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<NodeAttribute>,
+}
+
+/// A selection of the parameters of a model by their names and types.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MetricSetParameterSelection {
+    /// A glob pattern (e.g. `*-demand`) that the names of the parameters must match. Parameters
+    /// with any name are selected if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The types of the parameters (e.g. `MonthlyProfile`). Parameters of any type are selected
+    /// if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub types: Option<Vec<String>>,
+}
+
 /// Filters that allow multiple metrics to be added to a metric set.
 ///
 /// The filters allow the default metrics for all nodes and/or parameters in a model
-/// to be added to a metric set.
+/// to be added to a metric set, or only those of the nodes and parameters that match any of
+/// the `nodes` and `parameters` selections. The selections are resolved when the model is
+/// built, so that large models can record subsets of their components without listing each
+/// metric.
 #[derive(Deserialize, Serialize, Clone, JsonSchema, Default)]
 pub struct MetricSetFilters {
     #[serde(default)]
     all_nodes: bool,
     #[serde(default)]
     all_parameters: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nodes: Vec<MetricSetNodeSelection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    parameters: Vec<MetricSetParameterSelection>,
+}
+
+/// A compiled name pattern and set of types of a selection.
+#[cfg(feature = "core")]
+struct Selector<'a> {
+    pattern: Option<glob::Pattern>,
+    types: Option<&'a [String]>,
+}
+
+#[cfg(feature = "core")]
+impl<'a> Selector<'a> {
+    fn new(
+        metric_set: &str,
+        name: Option<&str>,
+        types: Option<&'a [String]>,
+        valid_types: &[&str],
+    ) -> Result<Self, SchemaError> {
+        let invalid = |reason: String| SchemaError::InvalidMetricSetFilter {
+            name: metric_set.to_string(),
+            reason,
+        };
+
+        let pattern = name
+            .map(glob::Pattern::new)
+            .transpose()
+            .map_err(|e| invalid(format!("invalid name pattern: {e}")))?;
+
+        for ty in types.unwrap_or_default() {
+            if !valid_types.contains(&ty.as_str()) {
+                return Err(invalid(format!(
+                    "unknown type `{ty}`; expected one of: {}",
+                    valid_types.join(", ")
+                )));
+            }
+        }
+
+        Ok(Self { pattern, types })
+    }
+
+    fn matches(&self, name: &str, ty: &str) -> bool {
+        self.pattern.as_ref().map_or(true, |p| p.matches(name))
+            && self.types.map_or(true, |types| types.iter().any(|t| t == ty))
+    }
 }
 
 #[cfg(feature = "core")]
 impl MetricSetFilters {
-    fn create_metrics(&self, args: &LoadArgs) -> Option<Vec<Metric>> {
+    fn create_metrics(&self, metric_set: &str, args: &LoadArgs) -> Result<Option<Vec<Metric>>, SchemaError> {
         use crate::metric::{NodeReference, ParameterReference};
+        use crate::nodes::NodeType;
+        use crate::parameters::ParameterType;
+        use strum::VariantNames;
 
-        if !self.all_nodes && !self.all_parameters {
-            return None;
+        if !self.all_nodes && !self.all_parameters && self.nodes.is_empty() && self.parameters.is_empty() {
+            return Ok(None);
         }
 
         let mut metrics = vec![];
+        let mut push = |metric: Metric| {
+            if !metrics.contains(&metric) {
+                metrics.push(metric);
+            }
+        };
 
         if self.all_nodes {
             for node in args.schema.nodes.iter() {
-                metrics.push(Metric::Node(NodeReference::new(node.name().to_string(), None)));
+                push(Metric::Node(NodeReference::new(node.name().to_string(), None)));
             }
         }
 
-        if self.all_parameters {
-            if let Some(parameters) = args.schema.parameters.as_ref() {
-                for parameter in parameters.iter() {
-                    // Skip Python parameters that return multiple values as the type or keys of these values is not
-                    // known at this point.
-                    if let Parameter::Python(param) = parameter {
-                        if matches!(param.return_type, PythonReturnType::Dict) {
-                            continue;
-                        }
+        for selection in &self.nodes {
+            let selector = Selector::new(
+                metric_set,
+                selection.name.as_deref(),
+                selection.types.as_deref(),
+                NodeType::VARIANTS,
+            )?;
+
+            for node in args.schema.nodes.iter() {
+                if selector.matches(node.name(), node.node_type().into()) {
+                    push(Metric::Node(NodeReference::new(
+                        node.name().to_string(),
+                        selection.attribute,
+                    )));
+                }
+            }
+        }
+
+        let selectors = self
+            .parameters
+            .iter()
+            .map(|selection| {
+                Selector::new(
+                    metric_set,
+                    selection.name.as_deref(),
+                    selection.types.as_deref(),
+                    ParameterType::VARIANTS,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(parameters) = args.schema.parameters.as_ref() {
+            for parameter in parameters.iter() {
+                // Skip Python parameters that return multiple values as the type or keys of these values is not
+                // known at this point.
+                if let Parameter::Python(param) = parameter {
+                    if matches!(param.return_type, PythonReturnType::Dict) {
+                        continue;
                     }
+                }
 
-                    metrics.push(Metric::Parameter(ParameterReference::new(parameter.name(), None)));
+                let ty: &str = parameter.parameter_type().into();
+                if self.all_parameters || selectors.iter().any(|s| s.matches(parameter.name(), ty)) {
+                    push(Metric::Parameter(ParameterReference::new(parameter.name(), None)));
                 }
             }
         }
 
-        Some(metrics)
+        Ok(Some(metrics))
     }
 }
 
@@ -142,6 +469,15 @@ impl MetricSetFilters {
 ///
 /// Metrics added by the filters will be appended to any metrics specified for the metric attribute,
 /// if they are not a duplication.
+///
+/// If `scenario_statistics` are given then the CSV outputs of the metric set contain the value of
+/// each statistic, computed across all of the scenarios, instead of the values of each scenario.
+/// The statistics are computed after any aggregation over time by the `aggregator`. If a
+/// `scenario_group` is also given the statistics are computed across the members of that group
+/// only, with a value for each combination of the members of the remaining groups.
+///
+/// Any `derived` metrics, calculated from the other metrics of the set, are added after the
+/// metrics and the metrics added by the filters.
 #[derive(Deserialize, Serialize, Clone, JsonSchema)]
 pub struct MetricSet {
     pub name: String,
@@ -149,6 +485,23 @@ pub struct MetricSet {
     pub aggregator: Option<MetricAggregator>,
     #[serde(default)]
     pub filters: MetricSetFilters,
+    /// The units of the metrics in the set (e.g. `Ml/d`).
+    ///
+    /// The units are not used by the model; they are included in the description of any
+    /// outputs of the set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    /// Statistics of the values of each metric to compute across all of the scenarios.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenario_statistics: Option<Vec<MetricScenarioStatistic>>,
+    /// The name of the scenario group over which the `scenario_statistics` are computed.
+    ///
+    /// By default the statistics are computed across all of the scenarios.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenario_group: Option<String>,
+    /// Metrics calculated from the other metrics of the set (e.g. the ratio of two flows).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived: Option<Vec<MetricSetDerivedMetric>>,
 }
 
 impl MetricSet {
@@ -156,40 +509,207 @@ impl MetricSet {
     pub fn add_to_model(&self, network: &mut pywr_core::network::Network, args: &LoadArgs) -> Result<(), SchemaError> {
         use pywr_core::recorders::OutputMetric;
 
-        let output_metrics = match self.metrics {
-            Some(ref metrics) => {
-                let mut output_metrics: Vec<OutputMetric> = metrics
-                    .iter()
-                    .map(|m| m.load_as_output(network, args, None))
-                    .collect::<Result<_, _>>()?;
-
-                if let Some(additional_metrics) = self.filters.create_metrics(args) {
-                    for m in additional_metrics.iter() {
-                        let output_metric = m.load_as_output(network, args, None)?;
-                        if !output_metrics.contains(&output_metric) {
-                            output_metrics.push(output_metric);
-                        }
-                    }
-                }
-                output_metrics
+        let mut output_metrics: Vec<OutputMetric> = self
+            .metrics
+            .iter()
+            .flatten()
+            .map(|m| m.load_as_output(network, args, None))
+            .collect::<Result<_, _>>()?;
+
+        match self.filters.create_metrics(&self.name, args)? {
+            // Filters that select nothing would otherwise build an empty set
+            Some(additional_metrics) if additional_metrics.is_empty() && output_metrics.is_empty() => {
+                return Err(SchemaError::EmptyMetricSet(self.name.clone()))
             }
-            None => {
-                if let Some(metrics) = self.filters.create_metrics(args) {
-                    metrics
-                        .iter()
-                        .map(|m| m.load_as_output(network, args, None))
-                        .collect::<Result<_, _>>()?
-                } else {
-                    return Err(SchemaError::EmptyMetricSet(self.name.clone()));
+            Some(additional_metrics) => {
+                for m in additional_metrics.iter() {
+                    let output_metric = m.load_as_output(network, args, None)?;
+                    if !output_metrics.contains(&output_metric) {
+                        output_metrics.push(output_metric);
+                    }
                 }
             }
-        };
+            None if self.metrics.is_none() => return Err(SchemaError::EmptyMetricSet(self.name.clone())),
+            None => {}
+        }
+
+        for derived in self.derived.iter().flatten() {
+            let output_metric = derived.load(&self.name, &output_metrics)?;
+            output_metrics.push(output_metric);
+        }
 
-        let aggregator = self.aggregator.clone().map(|a| a.into());
+        let aggregator = self.aggregator.clone().map(|a| a.try_into()).transpose()?;
 
-        let metric_set = pywr_core::recorders::MetricSet::new(&self.name, aggregator, output_metrics);
+        let mut metric_set = pywr_core::recorders::MetricSet::new(&self.name, aggregator, output_metrics);
+        if let Some(units) = &self.units {
+            metric_set = metric_set.with_units(units);
+        }
+        if let Some(statistics) = &self.scenario_statistics {
+            let statistics = statistics
+                .iter()
+                .cloned()
+                .map(|s| s.try_into())
+                .collect::<Result<Vec<_>, _>>()?;
+            metric_set = metric_set.with_scenario_statistics(statistics);
+        }
+        if let Some(scenario_group) = &self.scenario_group {
+            metric_set = metric_set.with_scenario_group(scenario_group);
+        }
         let _ = network.add_metric_set(metric_set)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "core")]
+mod tests {
+    use super::{MetricAggFunc, MetricScenarioStatistic};
+    use crate::model::PywrModel;
+    use crate::SchemaError;
+    use std::str::FromStr;
+
+    fn model_str(filters: &str, derived: &str) -> String {
+        format!(
+            r#"
+            {{
+                "metadata": {{"title": "Metric set filters", "minimum_version": "0.1"}},
+                "timestepper": {{"start": "2015-01-01", "end": "2015-01-10", "timestep": 1}},
+                "network": {{
+                    "nodes": [
+                        {{"meta": {{"name": "supply1"}}, "type": "Input", "max_flow": {{"type": "Constant", "value": 15.0}}}},
+                        {{
+                            "meta": {{"name": "reservoir1"}},
+                            "type": "Storage",
+                            "initial_volume": {{"Proportional": 1.0}},
+                            "max_volume": {{"type": "Constant", "value": 100.0}}
+                        }},
+                        {{
+                            "meta": {{"name": "reservoir2"}},
+                            "type": "Storage",
+                            "initial_volume": {{"Proportional": 1.0}},
+                            "max_volume": {{"type": "Constant", "value": 50.0}}
+                        }},
+                        {{
+                            "meta": {{"name": "demand1"}},
+                            "type": "Output",
+                            "max_flow": {{"type": "Parameter", "name": "demand-1"}},
+                            "cost": {{"type": "Constant", "value": -10.0}}
+                        }}
+                    ],
+                    "edges": [
+                        {{"from_node": "supply1", "to_node": "reservoir1"}},
+                        {{"from_node": "reservoir1", "to_node": "reservoir2"}},
+                        {{"from_node": "reservoir2", "to_node": "demand1"}}
+                    ],
+                    "parameters": [
+                        {{"meta": {{"name": "demand-1"}}, "type": "Constant", "value": 10.0}},
+                        {{"meta": {{"name": "demand-2"}}, "type": "Constant", "value": 5.0}},
+                        {{"meta": {{"name": "other"}}, "type": "Constant", "value": 1.0}}
+                    ],
+                    "metric_sets": [{{"name": "selected", "filters": {filters}, "derived": {derived}}}]
+                }}
+            }}
+            "#
+        )
+    }
+
+    #[test]
+    fn test_metric_set_selections() {
+        let filters = r#"{
+            "nodes": [
+                {"types": ["Storage"], "attribute": "Volume"},
+                {"name": "reservoir*", "types": ["Storage"], "attribute": "Volume"},
+                {"name": "demand?"}
+            ],
+            "parameters": [{"name": "demand-*", "types": ["Constant"]}]
+        }"#;
+
+        let schema = PywrModel::from_str(&model_str(filters, "[]")).unwrap();
+        let model = schema.build_model(None, None).unwrap();
+
+        let network = model.network();
+        let idx = network.get_metric_set_index_by_name("selected").unwrap();
+        let metrics: Vec<_> = network
+            .get_metric_set(idx)
+            .unwrap()
+            .iter_metrics()
+            .map(|m| (m.name().to_string(), m.attribute().to_string()))
+            .collect();
+
+        // The duplicate selection of the storage nodes is only added once
+        assert_eq!(metrics.len(), 5);
+        assert_eq!(metrics[0].0, "reservoir1");
+        assert_eq!(metrics[1].0, "reservoir2");
+        assert_eq!(metrics[0].1, metrics[1].1);
+        assert_eq!(metrics[2].0, "demand1");
+        assert_eq!(metrics[3].0, "demand-1");
+        assert_eq!(metrics[4].0, "demand-2");
+    }
+
+    #[test]
+    fn test_metric_set_selection_unknown_type() {
+        let filters = r#"{"nodes": [{"types": ["NotANodeType"]}]}"#;
+
+        let schema = PywrModel::from_str(&model_str(filters, "[]")).unwrap();
+        let result = schema.build_model(None, None);
+        assert!(matches!(result, Err(SchemaError::InvalidMetricSetFilter { .. })));
+    }
+
+    #[test]
+    fn test_metric_set_selection_no_matches() {
+        let filters = r#"{"nodes": [{"name": "river*"}]}"#;
+
+        let schema = PywrModel::from_str(&model_str(filters, "[]")).unwrap();
+        let result = schema.build_model(None, None);
+        assert!(matches!(result, Err(SchemaError::EmptyMetricSet(name)) if name == "selected"));
+    }
+
+    #[test]
+    fn test_metric_set_derived_metrics() {
+        let filters = r#"{"nodes": [{"types": ["Storage"], "attribute": "Volume"}, {"name": "demand1"}]}"#;
+        let derived = r#"[
+            {"name": "total-volume", "operation": "Sum", "lhs": "reservoir1", "rhs": "reservoir2"},
+            {"name": "headroom", "attribute": "volume", "operation": "Difference", "lhs": "total-volume/sum", "rhs": "demand1"}
+        ]"#;
+
+        let schema = PywrModel::from_str(&model_str(filters, derived)).unwrap();
+        let model = schema.build_model(None, None).unwrap();
+
+        let network = model.network();
+        let idx = network.get_metric_set_index_by_name("selected").unwrap();
+        let metrics: Vec<_> = network.get_metric_set(idx).unwrap().iter_metrics().collect();
+
+        assert_eq!(metrics.len(), 5);
+        assert_eq!(metrics[3].name(), "total-volume");
+        assert_eq!(metrics[3].attribute(), "sum");
+        assert_eq!(metrics[3].ty(), "Derived");
+        assert_eq!(metrics[4].name(), "headroom");
+        assert_eq!(metrics[4].attribute(), "volume");
+        assert_eq!(metrics[4].sub_type(), Some("difference"));
+    }
+
+    #[test]
+    fn test_metric_set_derived_metric_unknown_operand() {
+        let filters = r#"{"nodes": [{"name": "demand1"}]}"#;
+        let derived = r#"[{"name": "ratio", "operation": "Ratio", "lhs": "demand1", "rhs": "demand2"}]"#;
+
+        let schema = PywrModel::from_str(&model_str(filters, derived)).unwrap();
+        let result = schema.build_model(None, None);
+        assert!(matches!(result, Err(SchemaError::InvalidDerivedMetric { .. })));
+    }
+
+    #[test]
+    fn test_metric_set_invalid_quantile() {
+        let filters = r#"{"nodes": [{"name": "demand1"}]}"#;
+
+        let mut schema = PywrModel::from_str(&model_str(filters, "[]")).unwrap();
+        schema.network.metric_sets.as_mut().unwrap()[0].scenario_statistics = Some(vec![MetricScenarioStatistic {
+            label: "p150".to_string(),
+            func: MetricAggFunc::Quantile { quantile: 1.5 },
+        }]);
+
+        let result = schema.build_model(None, None);
+        assert!(matches!(result, Err(SchemaError::InvalidAggregationFunction(_))));
+    }
+}
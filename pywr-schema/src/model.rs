@@ -1,17 +1,26 @@
 use super::edge::Edge;
 use super::nodes::Node;
 use super::parameters::{Parameter, ParameterOrTimeseriesRef};
+use crate::constants::{contains_reference, substitute_constants};
 use crate::data_tables::DataTable;
 #[cfg(feature = "core")]
 use crate::data_tables::LoadedTableCollection;
 use crate::error::{ComponentConversionError, SchemaError};
+use crate::include::{contains_include, parse_with_includes, resolve_includes};
+use crate::json::{from_json_str, from_json_value};
 use crate::metric::Metric;
 use crate::metric_sets::MetricSet;
+use crate::optimisation::Optimisation;
 use crate::outputs::Output;
+use crate::state_overrides::StateOverride;
 #[cfg(feature = "core")]
 use crate::timeseries::LoadedTimeseriesCollection;
 use crate::timeseries::Timeseries;
+use crate::unknown::{
+    extract_unknown_components, merge_unknown_components, ComponentRef, UnknownComponent, UnknownComponentKind,
+};
 use crate::v1::{ConversionData, TryIntoV2};
+use crate::variables::substitute_variables;
 use crate::visit::{VisitMetrics, VisitPaths};
 #[cfg(feature = "core")]
 use chrono::NaiveTime;
@@ -19,8 +28,11 @@ use chrono::{NaiveDate, NaiveDateTime};
 #[cfg(feature = "pyo3")]
 use pyo3::pyclass;
 #[cfg(feature = "core")]
-use pywr_core::{models::ModelDomain, timestep::TimestepDuration, PywrError};
+use pywr_core::{models::ModelDomain, optimise::Solution, timestep::TimestepDuration, PywrError};
+use pywr_schema_macros::PywrVisitAll;
 use schemars::JsonSchema;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -53,11 +65,47 @@ impl From<pywr_v1_schema::model::Metadata> for Metadata {
     }
 }
 
+/// A day of the week.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, JsonSchema, strum_macros::Display)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+#[cfg(feature = "core")]
+impl From<Weekday> for chrono::Weekday {
+    fn from(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Monday => Self::Mon,
+            Weekday::Tuesday => Self::Tue,
+            Weekday::Wednesday => Self::Wed,
+            Weekday::Thursday => Self::Thu,
+            Weekday::Friday => Self::Fri,
+            Weekday::Saturday => Self::Sat,
+            Weekday::Sunday => Self::Sun,
+        }
+    }
+}
+
+/// The time-steps of the model.
+///
+/// The time-steps can be a fixed number of days, a frequency string (e.g. `"1mo"` for calendar
+/// months or `"1h"` for hourly time-steps starting at the time of the start date), weekly
+/// time-steps that start on a given day of the week, or a list of the dates that the time-steps
+/// start on. The last of these dates is the end of the final time-step. Time-steps do not need to
+/// have the same duration; flows are converted to volumes using the duration of each time-step.
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, strum_macros::Display)]
 #[serde(untagged)]
 pub enum Timestep {
     Days(i64),
     Frequency(String),
+    Weekly { weekday: Weekday },
+    Dates(Vec<DateType>),
 }
 
 impl From<pywr_v1_schema::model::Timestep> for Timestep {
@@ -85,11 +133,77 @@ impl From<pywr_v1_schema::model::DateType> for DateType {
     }
 }
 
+#[cfg(feature = "core")]
+impl From<DateType> for NaiveDateTime {
+    fn from(date: DateType) -> Self {
+        match date {
+            DateType::Date(date) => NaiveDateTime::new(date, NaiveTime::default()),
+            DateType::DateTime(date_time) => date_time,
+        }
+    }
+}
+
+/// A period that is not simulated.
+///
+/// Time-steps that start between `start` and `end` (inclusive) are removed from the simulation.
+/// For example, a period from "2020-02-29" to "2020-02-29" skips the leap day of a daily model.
+/// The state of the model (e.g. storage volumes) is carried over unchanged across the period,
+/// timeseries data within it are ignored, and outputs only contain the simulated time-steps.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ExcludedPeriod {
+    pub start: DateType,
+    pub end: DateType,
+}
+
+/// A day of the year, e.g. the 1st June.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct DayOfYear {
+    pub month: u32,
+    pub day: u32,
+}
+
+/// A window of days within each year, from `start` to `end` (inclusive).
+///
+/// The window wraps around the end of the year if `end` is before `start` (e.g. from the
+/// 1st November to the 28th February).
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct DayOfYearWindow {
+    pub start: DayOfYear,
+    pub end: DayOfYear,
+}
+
+#[cfg(feature = "core")]
+impl DayOfYearWindow {
+    /// Create the core window, checking that both days exist in a leap year (so the 29th
+    /// February is allowed).
+    pub(crate) fn load(&self) -> Result<pywr_core::timestep::DayOfYearWindow, String> {
+        for day in [self.start, self.end] {
+            if NaiveDate::from_ymd_opt(2000, day.month, day.day).is_none() {
+                return Err(format!(
+                    "the day {} of month {} is not a valid day of the year",
+                    day.day, day.month
+                ));
+            }
+        }
+
+        Ok(pywr_core::timestep::DayOfYearWindow::new(
+            (self.start.month, self.start.day),
+            (self.end.month, self.end.day),
+        ))
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema)]
 pub struct Timestepper {
     pub start: DateType,
     pub end: DateType,
     pub timestep: Timestep,
+    /// Optional periods to exclude from the simulation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excluded_periods: Option<Vec<ExcludedPeriod>>,
 }
 
 impl Default for Timestepper {
@@ -98,6 +212,7 @@ impl Default for Timestepper {
             start: DateType::Date(NaiveDate::from_ymd_opt(2000, 1, 1).expect("Invalid date")),
             end: DateType::Date(NaiveDate::from_ymd_opt(2000, 12, 31).expect("Invalid date")),
             timestep: Timestep::Days(1),
+            excluded_periods: None,
         }
     }
 }
@@ -108,6 +223,7 @@ impl From<pywr_v1_schema::model::Timestepper> for Timestepper {
             start: v1.start.into(),
             end: v1.end.into(),
             timestep: v1.timestep.into(),
+            excluded_periods: None,
         }
     }
 }
@@ -118,19 +234,45 @@ impl From<Timestepper> for pywr_core::timestep::Timestepper {
         let timestep = match ts.timestep {
             Timestep::Days(d) => TimestepDuration::Days(d),
             Timestep::Frequency(f) => TimestepDuration::Frequency(f),
+            Timestep::Weekly { weekday } => TimestepDuration::Weekly(weekday.into()),
+            Timestep::Dates(dates) => TimestepDuration::Dates(dates.into_iter().map(|d| d.into()).collect()),
         };
 
-        let start = match ts.start {
-            DateType::Date(date) => NaiveDateTime::new(date, NaiveTime::default()),
-            DateType::DateTime(date_time) => date_time,
-        };
+        let excluded_periods = ts
+            .excluded_periods
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| pywr_core::timestep::ExcludedPeriod::new(p.start.into(), p.end.into()))
+            .collect();
 
-        let end = match ts.end {
-            DateType::Date(date) => NaiveDateTime::new(date, NaiveTime::default()),
-            DateType::DateTime(date_time) => date_time,
-        };
+        Self::new(ts.start.into(), ts.end.into(), timestep).with_excluded_periods(excluded_periods)
+    }
+}
 
-        Self::new(start, end, timestep)
+/// A selection of the members of a scenario group to run.
+///
+/// The model only allocates state for, and records the results of, the selected members.
+/// Data (e.g. the columns of a table) are still selected by the index of the member in the
+/// full group.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "type")]
+pub enum ScenarioSubset {
+    /// The members from `start` (inclusive) to `end` (exclusive).
+    Slice { start: usize, end: usize },
+    /// The members with the given indices.
+    Indices { indices: Vec<usize> },
+}
+
+#[cfg(feature = "core")]
+impl From<&ScenarioSubset> for pywr_core::scenario::ScenarioSubset {
+    fn from(value: &ScenarioSubset) -> Self {
+        match value {
+            ScenarioSubset::Slice { start, end } => Self::Slice {
+                start: *start,
+                end: *end,
+            },
+            ScenarioSubset::Indices { indices } => Self::Indices(indices.clone()),
+        }
     }
 }
 
@@ -139,6 +281,46 @@ pub struct Scenario {
     pub name: String,
     pub size: usize,
     pub ensemble_names: Option<Vec<String>>,
+    /// Optionally run only a subset of the members of the group.
+    pub subset: Option<ScenarioSubset>,
+}
+
+/// Create the collection of scenario groups to run.
+#[cfg(feature = "core")]
+fn build_scenario_collection(
+    scenarios: Option<&[Scenario]>,
+    repeats: Option<NonZeroUsize>,
+) -> Result<pywr_core::scenario::ScenarioGroupCollection, SchemaError> {
+    let mut scenario_collection = pywr_core::scenario::ScenarioGroupCollection::default();
+
+    for scenario in scenarios.unwrap_or_default() {
+        scenario_collection.add_group(&scenario.name, scenario.size);
+        if let Some(subset) = &scenario.subset {
+            scenario_collection.subset(&scenario.name, &subset.into())?;
+        }
+    }
+
+    if let Some(repeats) = repeats {
+        scenario_collection.add_repeats(repeats.get())?;
+    }
+
+    Ok(scenario_collection)
+}
+
+/// Set the subset of the scenario group named `name`.
+fn set_scenario_subset(
+    scenarios: Option<&mut [Scenario]>,
+    name: &str,
+    subset: ScenarioSubset,
+) -> Result<(), SchemaError> {
+    let scenario = scenarios
+        .unwrap_or_default()
+        .iter_mut()
+        .find(|s| s.name == name)
+        .ok_or_else(|| SchemaError::ScenarioGroupNotFound(name.to_string()))?;
+
+    scenario.subset = Some(subset);
+    Ok(())
 }
 
 #[cfg(feature = "core")]
@@ -152,7 +334,7 @@ pub struct LoadArgs<'a> {
     pub inter_network_transfers: &'a [PywrMultiNetworkTransfer],
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone, Default, JsonSchema)]
+#[derive(serde::Deserialize, Clone, Default, JsonSchema)]
 #[cfg_attr(feature = "pyo3", pyclass)]
 pub struct PywrNetwork {
     pub nodes: Vec<Node>,
@@ -162,18 +344,95 @@ pub struct PywrNetwork {
     pub timeseries: Option<Vec<Timeseries>>,
     pub metric_sets: Option<Vec<MetricSet>>,
     pub outputs: Option<Vec<Output>>,
+    /// Adjustments applied to the state of the network at the end of each time-step.
+    pub state_overrides: Option<Vec<StateOverride>>,
+    /// Nodes and parameters of an unknown type that are preserved when the network is saved.
+    ///
+    /// See [`PywrModel::from_str_preserving_unknown`].
+    #[serde(skip)]
+    pub unknown_components: Vec<UnknownComponent>,
+    /// The files that components of the network were included from, relative to the directory
+    /// of the file the network was loaded from.
+    ///
+    /// The included components are part of the network's sections, and are saved in place of the
+    /// include directives. See [`PywrModel::from_path`].
+    #[serde(skip)]
+    pub includes: Vec<PathBuf>,
+}
+
+/// The sections of a [`PywrNetwork`] as they are written when it is saved.
+#[derive(serde::Serialize)]
+struct PywrNetworkSections<'a> {
+    nodes: Vec<ComponentRef<'a, Node>>,
+    edges: &'a Vec<Edge>,
+    parameters: Option<Vec<ComponentRef<'a, Parameter>>>,
+    tables: &'a Option<Vec<DataTable>>,
+    timeseries: &'a Option<Vec<Timeseries>>,
+    metric_sets: &'a Option<Vec<MetricSet>>,
+    outputs: &'a Option<Vec<Output>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_overrides: &'a Option<Vec<StateOverride>>,
+}
+
+impl serde::Serialize for PywrNetwork {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Every field is destructured so that a new field can not be left out of the saved network
+        let Self {
+            nodes,
+            edges,
+            parameters,
+            tables,
+            timeseries,
+            metric_sets,
+            outputs,
+            state_overrides,
+            unknown_components,
+            // The included components are saved in place of the include directives
+            includes: _,
+        } = self;
+        let unknown = unknown_components.as_slice();
+
+        let has_unknown_parameters = unknown.iter().any(|c| c.kind == UnknownComponentKind::Parameter);
+        let parameters = match parameters {
+            Some(parameters) => Some(merge_unknown_components(
+                parameters,
+                unknown,
+                UnknownComponentKind::Parameter,
+            )),
+            None if has_unknown_parameters => {
+                Some(merge_unknown_components(&[], unknown, UnknownComponentKind::Parameter))
+            }
+            None => None,
+        };
+
+        PywrNetworkSections {
+            nodes: merge_unknown_components(nodes, unknown, UnknownComponentKind::Node),
+            edges,
+            parameters,
+            tables,
+            timeseries,
+            metric_sets,
+            outputs,
+            state_overrides,
+        }
+        .serialize(serializer)
+    }
 }
 
 impl FromStr for PywrNetwork {
     type Err = SchemaError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(serde_json::from_str(s)?)
+        from_json_str(s)
     }
 }
 
 impl VisitPaths for PywrNetwork {
     fn visit_paths<F: FnMut(&Path)>(&self, visitor: &mut F) {
+        for include in &self.includes {
+            visitor(include);
+        }
+
         for node in &self.nodes {
             node.visit_paths(visitor);
         }
@@ -191,6 +450,10 @@ impl VisitPaths for PywrNetwork {
         }
     }
     fn visit_paths_mut<F: FnMut(&mut PathBuf)>(&mut self, visitor: &mut F) {
+        for include in self.includes.iter_mut() {
+            visitor(include);
+        }
+
         for node in self.nodes.iter_mut() {
             node.visit_paths_mut(visitor);
         }
@@ -228,6 +491,14 @@ impl VisitMetrics for PywrNetwork {
                 }
             }
         }
+
+        for output in self.outputs.as_deref().into_iter().flatten() {
+            output.visit_metrics(visitor);
+        }
+
+        for state_override in self.state_overrides.as_deref().into_iter().flatten() {
+            state_override.visit_metrics(visitor);
+        }
     }
 
     fn visit_metrics_mut<F: FnMut(&mut Metric)>(&mut self, visitor: &mut F) {
@@ -248,16 +519,53 @@ impl VisitMetrics for PywrNetwork {
                 }
             }
         }
+
+        for output in self.outputs.as_deref_mut().into_iter().flatten() {
+            output.visit_metrics_mut(visitor);
+        }
+
+        for state_override in self.state_overrides.as_deref_mut().into_iter().flatten() {
+            state_override.visit_metrics_mut(visitor);
+        }
     }
 }
 
 impl PywrNetwork {
+    /// Load a network from a file, including the components of any other files it includes.
+    ///
+    /// See [`PywrModel::from_path`] for more information.
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, SchemaError> {
         let data = std::fs::read_to_string(&path).map_err(|error| SchemaError::IO {
             path: path.as_ref().to_path_buf(),
             error,
         })?;
-        Ok(serde_json::from_str(data.as_str())?)
+
+        if !contains_include(&data) {
+            return from_json_str(data.as_str());
+        }
+
+        let (document, includes) = parse_with_includes(&data, path.as_ref(), None)?;
+        let mut network: Self = from_json_value(&document)?;
+        network.includes = includes;
+        Ok(network)
+    }
+
+    /// Load a network from a file, preserving any nodes or parameters of an unknown type.
+    ///
+    /// See [`PywrModel::from_str_preserving_unknown`] for more information.
+    pub fn from_path_preserving_unknown<P: AsRef<Path>>(path: P) -> Result<Self, SchemaError> {
+        let data = std::fs::read_to_string(&path).map_err(|error| SchemaError::IO {
+            path: path.as_ref().to_path_buf(),
+            error,
+        })?;
+
+        let (mut document, includes) = parse_with_includes(&data, path.as_ref(), None)?;
+        let unknown_components = extract_unknown_components(&mut document);
+
+        let mut network: Self = from_json_value(&document)?;
+        network.unknown_components = unknown_components;
+        network.includes = includes;
+        Ok(network)
     }
 
     /// Convert a v1 network to a v2 network.
@@ -350,6 +658,7 @@ impl PywrNetwork {
                 timeseries,
                 metric_sets,
                 outputs,
+                state_overrides: None,
             },
             errors,
         )
@@ -405,6 +714,15 @@ impl PywrNetwork {
         timeseries: &LoadedTimeseriesCollection,
         inter_network_transfers: &[PywrMultiNetworkTransfer],
     ) -> Result<pywr_core::network::Network, SchemaError> {
+        if !self.unknown_components.is_empty() {
+            let names = self
+                .unknown_components
+                .iter()
+                .map(|c| c.name().unwrap_or("<unnamed>").to_string())
+                .collect();
+            return Err(SchemaError::UnknownComponents(names));
+        }
+
         let mut network = pywr_core::network::Network::default();
 
         let args = LoadArgs {
@@ -498,6 +816,13 @@ impl PywrNetwork {
             node.set_constraints(&mut network, &args)?;
         }
 
+        // Create the state overrides
+        if let Some(state_overrides) = &self.state_overrides {
+            for state_override in state_overrides {
+                state_override.add_to_model(&mut network, &args)?;
+            }
+        }
+
         // Create all of the metric sets
         if let Some(metric_sets) = &self.metric_sets {
             for metric_set in metric_sets {
@@ -508,7 +833,7 @@ impl PywrNetwork {
         // Create all of the outputs
         if let Some(outputs) = &self.outputs {
             for output in outputs {
-                output.add_to_model(&mut network, output_path)?;
+                output.add_to_model(&mut network, &args, output_path)?;
             }
         }
 
@@ -516,13 +841,95 @@ impl PywrNetwork {
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone, strum_macros::Display)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, JsonSchema, strum_macros::Display)]
 #[serde(untagged)]
 pub enum PywrNetworkRef {
     Path(PathBuf),
     Inline(PywrNetwork),
 }
 
+/// The outcome of updating a model from a new schema (see [`UpdateFromSchema::update_from_schema`]).
+#[cfg(feature = "core")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelUpdate {
+    /// The new schema is the same as the current schema.
+    Unchanged,
+    /// The named global parameters were replaced in the model's existing network.
+    Parameters(Vec<String>),
+}
+
+/// Update a [`pywr_core::models::Model`] in place from a new version of the schema it was built from.
+#[cfg(feature = "core")]
+pub trait UpdateFromSchema {
+    /// Update the model, which was built from the schema `current`, so that it matches the
+    /// schema `new`.
+    ///
+    /// The global parameters that differ between the two schemas are replaced in the model's
+    /// existing network. This avoids reloading the timeseries and rebuilding the nodes, edges
+    /// and solver structures of the network, and keeps the model's state, which makes repeatedly
+    /// updating a model (e.g. during calibration) much faster than rebuilding it.
+    ///
+    /// Only global parameters that can be replaced (see [`Parameter::is_replaceable`]) may
+    /// differ. Any other change returns [`SchemaError::ModelUpdateNotSupported`] before the
+    /// model is modified; such a model must be rebuilt with [`PywrModel::build_model`].
+    fn update_from_schema(
+        &mut self,
+        current: &PywrModel,
+        new: &PywrModel,
+        data_path: Option<&Path>,
+    ) -> Result<ModelUpdate, SchemaError>;
+}
+
+#[cfg(feature = "core")]
+impl UpdateFromSchema for pywr_core::models::Model {
+    fn update_from_schema(
+        &mut self,
+        current: &PywrModel,
+        new: &PywrModel,
+        data_path: Option<&Path>,
+    ) -> Result<ModelUpdate, SchemaError> {
+        let changed = current.changed_parameters(new)?;
+        if changed.is_empty() {
+            return Ok(ModelUpdate::Unchanged);
+        }
+
+        let tables = new.network.load_tables(data_path)?;
+        // The replaceable parameters do not reference any timeseries
+        let timeseries = LoadedTimeseriesCollection::default();
+
+        let args = LoadArgs {
+            schema: &new.network,
+            domain: self.domain(),
+            tables: &tables,
+            timeseries: &timeseries,
+            data_path,
+            inter_network_transfers: &[],
+        };
+
+        for parameter in &changed {
+            parameter.replace_in_model(self.network_mut(), &args, None)?;
+        }
+
+        Ok(ModelUpdate::Parameters(
+            changed.iter().map(|p| p.name().to_string()).collect(),
+        ))
+    }
+}
+
+/// The keys of two JSON objects whose values differ, prefixed by `prefix`.
+#[cfg(feature = "core")]
+fn changed_keys(current: &serde_json::Value, new: &serde_json::Value, prefix: &str) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let current = current.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+
+    let keys: std::collections::BTreeSet<&String> = current.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter(|k| current.get(*k) != new.get(*k))
+        .map(|k| format!("{prefix}{k}"))
+        .collect()
+}
+
 /// The top-level schema for a Pywr model.
 ///
 /// A Pywr model is defined by this top-level schema which is mostly conveniently loaded from a
@@ -549,14 +956,23 @@ pub struct PywrModel {
     pub metadata: Metadata,
     pub timestepper: Timestepper,
     pub scenarios: Option<Vec<Scenario>>,
+    /// Optionally repeat the model this many times. The repeats are run as an additional
+    /// scenario group named `repeat`, and each repeat uses a different stream of random numbers
+    /// in the random parameters of the model (see [`crate::parameters::RandomParameter`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeats: Option<NonZeroUsize>,
+    /// Named scalar values that can be referenced from any numeric field as `"${name}"`.
+    pub constants: Option<BTreeMap<String, f64>>,
     pub network: PywrNetwork,
+    /// Optional definition of the multi-objective optimisation of the model.
+    pub optimisation: Option<Optimisation>,
 }
 
 impl FromStr for PywrModel {
     type Err = SchemaError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(serde_json::from_str(s)?)
+        Self::from_str_with_constants(s, &BTreeMap::new())
     }
 }
 
@@ -591,18 +1007,153 @@ impl PywrModel {
                 start: *start,
                 end: *end,
                 timestep: Timestep::Days(1),
+                excluded_periods: None,
             },
             scenarios: None,
+            repeats: None,
+            constants: None,
+            optimisation: None,
             network: PywrNetwork::default(),
         }
     }
 
+    /// Load a model from a file.
+    ///
+    /// The sections of the model's network may include components from other files with include
+    /// directives (e.g. `"nodes": {"include": "nodes/*.json"}`). The paths of the included files
+    /// are relative to the file containing the directive, and are recorded in
+    /// [`PywrNetwork::includes`].
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, SchemaError> {
+        Self::from_path_with_constants(path, &BTreeMap::new())
+    }
+
+    /// Load a model from a file overriding the values of some of its constants.
+    ///
+    /// See [`PywrModel::from_str_with_constants`] for more information.
+    pub fn from_path_with_constants<P: AsRef<Path>>(
+        path: P,
+        constants: &BTreeMap<String, f64>,
+    ) -> Result<Self, SchemaError> {
+        Self::from_path_with_definitions(path, constants, &BTreeMap::new())
+    }
+
+    /// Load a model from a file overriding the values of some of its constants and defining
+    /// some variables.
+    ///
+    /// See [`PywrModel::from_str_with_definitions`] for more information.
+    pub fn from_path_with_definitions<P: AsRef<Path>>(
+        path: P,
+        constants: &BTreeMap<String, f64>,
+        definitions: &BTreeMap<String, String>,
+    ) -> Result<Self, SchemaError> {
+        let data = std::fs::read_to_string(&path).map_err(|error| SchemaError::IO {
+            path: path.as_ref().to_path_buf(),
+            error,
+        })?;
+
+        if !contains_include(&data) {
+            return Self::from_str_with_definitions(data.as_str(), constants, definitions);
+        }
+
+        let (mut document, includes) = parse_with_includes(&data, path.as_ref(), Some("network"))?;
+        substitute_variables(&mut document, definitions)?;
+        substitute_constants(&mut document, constants)?;
+        let mut model: Self = from_json_value(&document)?;
+        model.network.includes = includes;
+        Ok(model)
+    }
+
+    /// Load a model from a JSON string overriding the values of some of its constants.
+    ///
+    /// References to the model's constants (e.g. `"${peak_demand}"`) are replaced with their
+    /// values before the model is deserialised. Only constants defined in the model's
+    /// `constants` section can be overridden.
+    pub fn from_str_with_constants(data: &str, constants: &BTreeMap<String, f64>) -> Result<Self, SchemaError> {
+        Self::from_str_with_definitions(data, constants, &BTreeMap::new())
+    }
+
+    /// Load a model from a JSON string overriding the values of some of its constants and
+    /// defining some variables.
+    ///
+    /// References to variables in any string of the model (e.g. `"${DATA_DIR}/inflows.csv"`)
+    /// are replaced with their values from `definitions`, or otherwise from the environment
+    /// variable of the same name. The constants are then substituted as described in
+    /// [`PywrModel::from_str_with_constants`]; a reference to a constant is not replaced by a
+    /// variable of the same name.
+    pub fn from_str_with_definitions(
+        data: &str,
+        constants: &BTreeMap<String, f64>,
+        definitions: &BTreeMap<String, String>,
+    ) -> Result<Self, SchemaError> {
+        if constants.is_empty() && !contains_reference(data) {
+            return from_json_str(data);
+        }
+
+        let mut document: serde_json::Value = serde_json::from_str(data)?;
+        substitute_variables(&mut document, definitions)?;
+        substitute_constants(&mut document, constants)?;
+        from_json_value(&document)
+    }
+
+    /// Load a model from a JSON string, preserving any nodes or parameters of an unknown type.
+    ///
+    /// Components of a type that is not known to this version of Pywr (e.g. from a newer version
+    /// or a third-party plugin) are kept as opaque JSON values in
+    /// [`PywrNetwork::unknown_components`] and are written back to their original positions when
+    /// the model is saved. This allows tooling that only reads or edits part of a model to work
+    /// with it without destroying the unrecognised sections. The model can not be built while it
+    /// contains any unknown components.
+    pub fn from_str_preserving_unknown(data: &str) -> Result<Self, SchemaError> {
+        let mut document: serde_json::Value = serde_json::from_str(data)?;
+
+        let unknown_components = match document.get_mut("network") {
+            Some(network) => extract_unknown_components(network),
+            None => Vec::new(),
+        };
+
+        if contains_reference(data) {
+            substitute_variables(&mut document, &BTreeMap::new())?;
+            substitute_constants(&mut document, &BTreeMap::new())?;
+        }
+
+        let mut model: Self = from_json_value(&document)?;
+        model.network.unknown_components = unknown_components;
+        Ok(model)
+    }
+
+    /// Load a model from a file, preserving any nodes or parameters of an unknown type.
+    ///
+    /// See [`PywrModel::from_str_preserving_unknown`] for more information.
+    pub fn from_path_preserving_unknown<P: AsRef<Path>>(path: P) -> Result<Self, SchemaError> {
         let data = std::fs::read_to_string(&path).map_err(|error| SchemaError::IO {
             path: path.as_ref().to_path_buf(),
             error,
         })?;
-        Ok(serde_json::from_str(data.as_str())?)
+
+        if !contains_include(&data) {
+            return Self::from_str_preserving_unknown(data.as_str());
+        }
+
+        let (mut document, includes) = parse_with_includes(&data, path.as_ref(), Some("network"))?;
+
+        let unknown_components = match document.get_mut("network") {
+            Some(network) => extract_unknown_components(network),
+            None => Vec::new(),
+        };
+        substitute_variables(&mut document, &BTreeMap::new())?;
+        substitute_constants(&mut document, &BTreeMap::new())?;
+
+        let mut model: Self = from_json_value(&document)?;
+        model.network.unknown_components = unknown_components;
+        model.network.includes = includes;
+        Ok(model)
+    }
+
+    /// Run only a subset of the members of the scenario group named `name`.
+    ///
+    /// This replaces any subset defined for the group in the model.
+    pub fn set_scenario_subset(&mut self, name: &str, subset: ScenarioSubset) -> Result<(), SchemaError> {
+        set_scenario_subset(self.scenarios.as_deref_mut(), name, subset)
     }
 
     #[cfg(feature = "core")]
@@ -613,14 +1164,7 @@ impl PywrModel {
     ) -> Result<pywr_core::models::Model, SchemaError> {
         let timestepper = self.timestepper.clone().into();
 
-        let mut scenario_collection = pywr_core::scenario::ScenarioGroupCollection::default();
-
-        if let Some(scenarios) = &self.scenarios {
-            for scenario in scenarios {
-                scenario_collection.add_group(&scenario.name, scenario.size);
-            }
-        }
-
+        let scenario_collection = build_scenario_collection(self.scenarios.as_deref(), self.repeats)?;
         let domain = ModelDomain::from(timestepper, scenario_collection)?;
 
         let tables = self.network.load_tables(data_path)?;
@@ -635,6 +1179,92 @@ impl PywrModel {
         Ok(model)
     }
 
+    /// Find the global parameters of `new` that differ from those of this schema.
+    ///
+    /// An error is returned if the schemas differ in any other way, or if a parameter that
+    /// differs can not be replaced (see [`Parameter::is_replaceable`]).
+    #[cfg(feature = "core")]
+    fn changed_parameters<'a>(&self, new: &'a PywrModel) -> Result<Vec<&'a Parameter>, SchemaError> {
+        let not_supported = |reason: String| Err(SchemaError::ModelUpdateNotSupported(reason));
+
+        let mut current_value = serde_json::to_value(self)?;
+        let mut new_value = serde_json::to_value(new)?;
+        // Compare everything except the global parameters
+        for value in [&mut current_value, &mut new_value] {
+            if let Some(network) = value.get_mut("network").and_then(|n| n.as_object_mut()) {
+                network.remove("parameters");
+            }
+        }
+        if current_value != new_value {
+            let mut changed = changed_keys(&current_value, &new_value, "");
+            if let (Some(current), Some(new)) = (current_value.get("network"), new_value.get("network")) {
+                changed.retain(|k| k != "network");
+                changed.extend(changed_keys(current, new, "network."));
+            }
+            return not_supported(format!(
+                "only the global parameters can change, but {} changed",
+                changed.join(", ")
+            ));
+        }
+
+        let current_parameters = self.network.parameters.as_deref().unwrap_or_default();
+        let new_parameters = new.network.parameters.as_deref().unwrap_or_default();
+        if current_parameters.len() != new_parameters.len() {
+            return not_supported("global parameters were added or removed".to_string());
+        }
+
+        let mut changed = Vec::new();
+        for (current, new) in current_parameters.iter().zip(new_parameters) {
+            if current.name() != new.name() {
+                return not_supported(format!(
+                    "global parameter `{}` was renamed or reordered to `{}`",
+                    current.name(),
+                    new.name()
+                ));
+            }
+            if std::mem::discriminant(current) != std::mem::discriminant(new) {
+                return not_supported(format!(
+                    "the type of global parameter `{}` changed from {} to {}",
+                    new.name(),
+                    current.parameter_type(),
+                    new.parameter_type()
+                ));
+            }
+
+            if serde_json::to_value(current)? != serde_json::to_value(new)? {
+                if !new.is_replaceable() {
+                    return not_supported(format!(
+                        "global parameter `{}` of type {} can not be replaced",
+                        new.name(),
+                        new.parameter_type()
+                    ));
+                }
+                changed.push(new);
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Optimise the model's variable parameters using the model's `optimisation` definition.
+    ///
+    /// Each evaluation of the optimisation is a run of the model with the solver [`S`]. The
+    /// model is built without an output path, so any outputs that write files should be removed
+    /// before optimising. The non-dominated solutions of the final generation are returned.
+    #[cfg(feature = "core")]
+    pub fn optimise<S>(&self, data_path: Option<&Path>, settings: &S::Settings) -> Result<Vec<Solution>, SchemaError>
+    where
+        S: pywr_core::solvers::Solver,
+        <S as pywr_core::solvers::Solver>::Settings: pywr_core::solvers::SolverSettings,
+    {
+        let optimisation = self.optimisation.as_ref().ok_or(SchemaError::OptimisationNotDefined)?;
+
+        let model = self.build_model(data_path, None)?;
+        let problem = optimisation.build_problem(&self.network, &model)?;
+
+        Ok(problem.optimise::<S>(settings, &optimisation.nsga2_settings())?)
+    }
+
     /// Convert a v1 model to a v2 model.
     ///
     /// This function is used to convert a v1 model to a v2 model. The conversion is not always
@@ -656,6 +1286,9 @@ impl PywrModel {
                 metadata,
                 timestepper,
                 scenarios: None,
+                repeats: None,
+                constants: None,
+                optimisation: None,
                 network,
             },
             errors,
@@ -672,19 +1305,63 @@ impl PywrModel {
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, JsonSchema)]
 pub struct PywrMultiNetworkTransfer {
     pub from_network: String,
     pub metric: Metric,
     pub name: String,
     pub initial_value: Option<f64>,
+    /// The number of time-steps between a value being sent and received (default zero).
+    ///
+    /// The `initial_value` (or zero) is received until the first value sent arrives.
+    pub lag: Option<usize>,
+    /// The fraction of the value that is lost in transfer (default zero).
+    pub loss: Option<f64>,
+}
+
+/// Settings for iterating the inter-network transfers to convergence within each time-step.
+///
+/// Without these settings each network is solved once per time-step, and can only use the
+/// values of the current time-step from the networks defined before it. With these settings
+/// the networks are solved repeatedly within each time-step until the values of all the
+/// transfers change by less than `tolerance`, or `max_iterations` is reached.
+///
+/// Transfers that oscillate between iterations can be damped by giving a `damping` greater than
+/// zero and less than one. Each transfer then only moves by that fraction of its change from the
+/// previous iteration (default one, no damping).
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PywrMultiNetworkIteration {
+    pub tolerance: f64,
+    pub max_iterations: NonZeroUsize,
+    pub damping: Option<f64>,
+}
+
+#[cfg(feature = "core")]
+impl TryFrom<PywrMultiNetworkIteration> for pywr_core::models::MultiNetworkIteration {
+    type Error = pywr_core::PywrError;
+
+    fn try_from(value: PywrMultiNetworkIteration) -> Result<Self, Self::Error> {
+        let iteration = Self::new(value.tolerance, value.max_iterations);
+        match value.damping {
+            Some(damping) => iteration.with_damping(damping),
+            None => Ok(iteration),
+        }
+    }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, JsonSchema)]
 pub struct PywrMultiNetworkEntry {
     pub name: String,
     pub network: PywrNetworkRef,
     pub transfers: Vec<PywrMultiNetworkTransfer>,
+    /// Optionally step this network with longer time-steps than the model (e.g. weekly in a
+    /// daily model). Each of the network's time-steps must start at the start of one of the
+    /// model's time-steps, and the last must end with the model's last time-step. Transfers sent
+    /// from a network with shorter time-steps are received as the mean of the values sent since
+    /// the receiving network's previous time-step. The network's time-steps can not be used with
+    /// `iteration`.
+    pub timestepper: Option<Timestepper>,
 }
 
 /// A Pywr model containing multiple link networks.
@@ -715,6 +1392,11 @@ pub struct PywrMultiNetworkEntry {
 /// }
 /// ```
 ///
+/// If the networks depend on each other's values in the same time-step, the transfers can be
+/// iterated to convergence by giving the optional `iteration` settings (see
+/// [`PywrMultiNetworkIteration`]). The networks are then solved repeatedly within each
+/// time-step, starting from the same state each time, until the transfers converge.
+///
 /// # When to use
 ///
 /// A [`PywrMultiNetworkModel`] should be used in cases where there is a strong separation between
@@ -748,29 +1430,111 @@ pub struct PywrMultiNetworkEntry {
 ///
 ///
 ///
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, JsonSchema)]
 pub struct PywrMultiNetworkModel {
     pub metadata: Metadata,
     pub timestepper: Timestepper,
     pub scenarios: Option<Vec<Scenario>>,
+    /// Optionally repeat the model this many times. The repeats are run as an additional
+    /// scenario group named `repeat`, and each repeat uses a different stream of random numbers
+    /// in the random parameters of the model (see [`crate::parameters::RandomParameter`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeats: Option<NonZeroUsize>,
     pub networks: Vec<PywrMultiNetworkEntry>,
+    /// Optional settings to iterate the inter-network transfers to convergence.
+    pub iteration: Option<PywrMultiNetworkIteration>,
 }
 
 impl FromStr for PywrMultiNetworkModel {
     type Err = SchemaError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(serde_json::from_str(s)?)
+        from_json_str(s)
     }
 }
 
 impl PywrMultiNetworkModel {
+    /// Load a multi-network model from a file.
+    ///
+    /// The networks defined inline in the model may include components from other files in the
+    /// same way as the network of a [`PywrModel`] (see [`PywrModel::from_path`]).
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, SchemaError> {
-        let data = std::fs::read_to_string(&path).map_err(|error| SchemaError::IO {
-            path: path.as_ref().to_path_buf(),
+        Self::from_path_with_definitions(path, &BTreeMap::new())
+    }
+
+    /// Load a multi-network model from a file defining some variables.
+    ///
+    /// References to variables in any string of the model are replaced as described in
+    /// [`PywrModel::from_str_with_definitions`].
+    pub fn from_path_with_definitions<P: AsRef<Path>>(
+        path: P,
+        definitions: &BTreeMap<String, String>,
+    ) -> Result<Self, SchemaError> {
+        Self::load_path(path.as_ref(), definitions, false)
+    }
+
+    /// Load a multi-network model from a file, preserving any nodes or parameters of an unknown
+    /// type in the networks defined inline in the model.
+    ///
+    /// See [`PywrModel::from_str_preserving_unknown`] for more information. The networks given
+    /// by a path can be loaded with [`PywrNetwork::from_path_preserving_unknown`].
+    pub fn from_path_preserving_unknown<P: AsRef<Path>>(path: P) -> Result<Self, SchemaError> {
+        Self::load_path(path.as_ref(), &BTreeMap::new(), true)
+    }
+
+    fn load_path(
+        path: &Path,
+        definitions: &BTreeMap<String, String>,
+        preserve_unknown: bool,
+    ) -> Result<Self, SchemaError> {
+        let data = std::fs::read_to_string(path).map_err(|error| SchemaError::IO {
+            path: path.to_path_buf(),
             error,
         })?;
-        Ok(serde_json::from_str(data.as_str())?)
+
+        if !preserve_unknown && !contains_include(&data) && !contains_reference(&data) {
+            return from_json_str(data.as_str());
+        }
+
+        let mut document: serde_json::Value = serde_json::from_str(&data)?;
+
+        // The included files and unknown components of each of the inline networks
+        let mut inline_networks = Vec::new();
+        if let Some(networks) = document.get_mut("networks").and_then(|n| n.as_array_mut()) {
+            for entry in networks {
+                inline_networks.push(match entry.get_mut("network") {
+                    Some(network) => {
+                        let includes = resolve_includes(network, path)?;
+                        let unknown_components = match preserve_unknown && network.is_object() {
+                            true => extract_unknown_components(network),
+                            false => Vec::new(),
+                        };
+                        (includes, unknown_components)
+                    }
+                    None => (Vec::new(), Vec::new()),
+                });
+            }
+        }
+
+        substitute_variables(&mut document, definitions)?;
+        substitute_constants(&mut document, &BTreeMap::new())?;
+        let mut model: Self = from_json_value(&document)?;
+
+        for (entry, (includes, unknown_components)) in model.networks.iter_mut().zip(inline_networks) {
+            if let PywrNetworkRef::Inline(network) = &mut entry.network {
+                network.includes = includes;
+                network.unknown_components = unknown_components;
+            }
+        }
+
+        Ok(model)
+    }
+
+    /// Run only a subset of the members of the scenario group named `name`.
+    ///
+    /// This replaces any subset defined for the group in the model.
+    pub fn set_scenario_subset(&mut self, name: &str, subset: ScenarioSubset) -> Result<(), SchemaError> {
+        set_scenario_subset(self.scenarios.as_deref_mut(), name, subset)
     }
 
     #[cfg(feature = "core")]
@@ -781,24 +1545,28 @@ impl PywrMultiNetworkModel {
     ) -> Result<pywr_core::models::MultiNetworkModel, SchemaError> {
         let timestepper = self.timestepper.clone().into();
 
-        let mut scenario_collection = pywr_core::scenario::ScenarioGroupCollection::default();
-
-        if let Some(scenarios) = &self.scenarios {
-            for scenario in scenarios {
-                scenario_collection.add_group(&scenario.name, scenario.size);
-            }
-        }
-
+        let scenario_collection = build_scenario_collection(self.scenarios.as_deref(), self.repeats)?;
         let domain = ModelDomain::from(timestepper, scenario_collection)?;
         let mut networks = Vec::with_capacity(self.networks.len());
         let mut inter_network_transfers = Vec::new();
         let mut schemas: Vec<(PywrNetwork, LoadedTableCollection, LoadedTimeseriesCollection)> =
             Vec::with_capacity(self.networks.len());
+        // The domain of each network that has its own time-steps
+        let mut network_domains = Vec::with_capacity(self.networks.len());
 
         // First load all the networks
         // These will contain any parameters that are referenced by the inter-model transfers
         // Because of potential circular references, we need to load all the networks first.
         for network_entry in &self.networks {
+            let network_domain = match &network_entry.timestepper {
+                Some(timestepper) => Some(ModelDomain::from(
+                    timestepper.clone().into(),
+                    build_scenario_collection(self.scenarios.as_deref(), self.repeats)?,
+                )?),
+                None => None,
+            };
+            let network_domain_ref = network_domain.as_ref().unwrap_or(&domain);
+
             // Load the network itself
             let (network, schema, tables, timeseries) = match &network_entry.network {
                 PywrNetworkRef::Path(path) => {
@@ -814,9 +1582,9 @@ impl PywrMultiNetworkModel {
 
                     let network_schema = PywrNetwork::from_path(pth)?;
                     let tables = network_schema.load_tables(data_path)?;
-                    let timeseries = network_schema.load_timeseries(&domain, data_path)?;
+                    let timeseries = network_schema.load_timeseries(network_domain_ref, data_path)?;
                     let net = network_schema.build_network(
-                        &domain,
+                        network_domain_ref,
                         data_path,
                         output_path,
                         &tables,
@@ -828,9 +1596,9 @@ impl PywrMultiNetworkModel {
                 }
                 PywrNetworkRef::Inline(network_schema) => {
                     let tables = network_schema.load_tables(data_path)?;
-                    let timeseries = network_schema.load_timeseries(&domain, data_path)?;
+                    let timeseries = network_schema.load_timeseries(network_domain_ref, data_path)?;
                     let net = network_schema.build_network(
-                        &domain,
+                        network_domain_ref,
                         data_path,
                         output_path,
                         &tables,
@@ -844,6 +1612,7 @@ impl PywrMultiNetworkModel {
 
             schemas.push((schema, tables, timeseries));
             networks.push((network_entry.name.clone(), network));
+            network_domains.push(network_domain);
         }
 
         // Now load the inter-model transfers
@@ -868,7 +1637,7 @@ impl PywrMultiNetworkModel {
 
                 let args = LoadArgs {
                     schema: from_schema,
-                    domain: &domain,
+                    domain: network_domains[from_network_idx].as_ref().unwrap_or(&domain),
                     tables: from_tables,
                     timeseries: from_timeseries,
                     data_path,
@@ -877,30 +1646,43 @@ impl PywrMultiNetworkModel {
 
                 let from_metric = transfer.metric.load(from_network, &args, None)?;
 
-                inter_network_transfers.push((from_network_idx, from_metric, to_network_idx, transfer.initial_value));
+                inter_network_transfers.push((from_network_idx, from_metric, to_network_idx, transfer));
             }
         }
 
         // Now construct the model from the loaded components
         let mut model = pywr_core::models::MultiNetworkModel::new(domain);
 
-        for (name, network) in networks {
-            model.add_network(&name, network)?;
+        for ((name, network), network_domain) in networks.into_iter().zip(network_domains) {
+            match network_domain {
+                Some(network_domain) => model.add_network_with_domain(&name, network, network_domain)?,
+                None => model.add_network(&name, network)?,
+            };
         }
 
-        for (from_network_idx, from_metric, to_network_idx, initial_value) in inter_network_transfers {
-            model.add_inter_network_transfer(from_network_idx, from_metric, to_network_idx, initial_value);
+        for (from_network_idx, from_metric, to_network_idx, transfer) in inter_network_transfers {
+            model.add_inter_network_transfer(
+                from_network_idx,
+                from_metric,
+                to_network_idx,
+                transfer.initial_value,
+                transfer.lag.unwrap_or_default(),
+                transfer.loss.unwrap_or_default(),
+            )?;
         }
 
+        model.set_iteration(self.iteration.map(|i| i.try_into()).transpose()?);
+
         Ok(model)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::PywrModel;
+    use super::{PywrModel, PywrMultiNetworkModel, PywrNetwork, PywrNetworkRef};
+    use crate::metric::Metric;
     use crate::model::Timestepper;
-    use crate::visit::VisitPaths;
+    use crate::visit::{VisitMetrics, VisitPaths};
     use std::fs::read_to_string;
     use std::path::PathBuf;
 
@@ -1012,17 +1794,166 @@ mod tests {
             panic!("Expected an error due to missing file: {str}");
         }
     }
+
+    /// Test that components of an unknown type are preserved when a model is saved.
+    #[test]
+    fn test_preserving_unknown() {
+        let mut document: serde_json::Value = serde_json::from_str(&model_str()).unwrap();
+        let unknown_node = serde_json::json!({"meta": {"name": "turbine1"}, "type": "FancyTurbine", "head": 10.0});
+        document["network"]["nodes"]
+            .as_array_mut()
+            .unwrap()
+            .insert(1, unknown_node.clone());
+        let data = serde_json::to_string(&document).unwrap();
+
+        assert!(serde_json::from_str::<PywrModel>(&data).is_err());
+
+        let schema = PywrModel::from_str_preserving_unknown(&data).unwrap();
+        assert_eq!(schema.network.nodes.len(), 3);
+        assert_eq!(schema.network.unknown_components.len(), 1);
+        assert_eq!(schema.network.unknown_components[0].name(), Some("turbine1"));
+
+        // The unknown node is written back to its original position
+        let saved = serde_json::to_value(&schema).unwrap();
+        assert_eq!(saved["network"]["nodes"][1], unknown_node);
+        assert_eq!(saved["network"]["nodes"].as_array().unwrap().len(), 4);
+
+        #[cfg(feature = "core")]
+        assert!(matches!(
+            schema.build_model(None, None),
+            Err(crate::SchemaError::UnknownComponents(names)) if names == ["turbine1"]
+        ));
+    }
+
+    /// Test that unknown components are preserved in the inline networks of a multi-network model.
+    #[test]
+    fn test_multi_network_preserving_unknown() {
+        let unknown_parameter = serde_json::json!({"meta": {"name": "p1"}, "type": "FancyParameter"});
+        let document = serde_json::json!({
+            "metadata": {"title": "Unknown"},
+            "timestepper": {"start": "2015-01-01", "end": "2015-12-31", "timestep": 1},
+            "networks": [
+                {
+                    "name": "network1",
+                    "network": {
+                        "nodes": [{"meta": {"name": "supply1"}, "type": "Input"}],
+                        "edges": [],
+                        "parameters": [unknown_parameter.clone()]
+                    },
+                    "transfers": []
+                }
+            ]
+        });
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("model.json");
+        std::fs::write(&path, serde_json::to_string(&document).unwrap()).unwrap();
+
+        assert!(PywrMultiNetworkModel::from_path(&path).is_err());
+
+        let schema = PywrMultiNetworkModel::from_path_preserving_unknown(&path).unwrap();
+        let PywrNetworkRef::Inline(network) = &schema.networks[0].network else {
+            panic!("Expected an inline network");
+        };
+        assert_eq!(network.unknown_components.len(), 1);
+
+        let saved = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            saved["networks"][0]["network"]["parameters"],
+            serde_json::json!([unknown_parameter])
+        );
+    }
+
+    /// Test that every section of a network is saved.
+    #[test]
+    fn test_network_round_trip() {
+        let document = serde_json::json!({
+            "nodes": [
+                {"meta": {"name": "supply1"}, "type": "Input"},
+                {"meta": {"name": "reservoir1"}, "type": "Storage", "max_volume": {"type": "Constant", "value": 1000.0}, "initial_volume": {"Absolute": 500.0}},
+                {"meta": {"name": "demand1"}, "type": "Output"}
+            ],
+            "edges": [
+                {"from_node": "supply1", "to_node": "reservoir1"},
+                {"from_node": "reservoir1", "to_node": "demand1"}
+            ],
+            "parameters": [{"meta": {"name": "demand"}, "type": "Constant", "value": 10.0}],
+            "tables": [{"name": "demands", "format": "excel", "type": "scalar", "lookup": {"row": 1}, "url": "excel1.xlsx", "range": "demands"}],
+            "timeseries": [{"meta": {"name": "inflow"}, "provider": {"type": "Excel", "time_col": "date", "url": "excel1.xlsx", "sheet": "flows"}}],
+            "metric_sets": [{"name": "nodes", "metrics": [{"type": "Node", "name": "demand1"}]}],
+            "outputs": [{"type": "Memory", "name": "flows", "metric_set": "nodes"}],
+            "state_overrides": [{"name": "observed-volume", "node": {"name": "reservoir1"}, "value": {"type": "Constant", "value": 400.0}}]
+        });
+
+        let network: PywrNetwork = serde_json::from_value(document.clone()).unwrap();
+        let saved = serde_json::to_value(&network).unwrap();
+
+        let sections = saved.as_object().unwrap();
+        for (key, value) in document.as_object().unwrap() {
+            let saved_value = &sections[key];
+            assert_eq!(
+                saved_value.as_array().map(Vec::len),
+                value.as_array().map(Vec::len),
+                "section `{key}` was not saved"
+            );
+        }
+        assert_eq!(sections.len(), document.as_object().unwrap().len());
+
+        // Saving the network again gives the same document
+        let reloaded: PywrNetwork = serde_json::from_value(saved.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&reloaded).unwrap(), saved);
+    }
+
+    /// Test that the metrics of the outputs are visited.
+    #[test]
+    fn test_network_visit_output_metrics() {
+        let document = serde_json::json!({
+            "nodes": [
+                {"meta": {"name": "supply1"}, "type": "Input"},
+                {"meta": {"name": "demand1"}, "type": "Output"}
+            ],
+            "edges": [{"from_node": "supply1", "to_node": "demand1"}],
+            "outputs": [{
+                "type": "Events",
+                "name": "low-flows",
+                "metric": {"type": "Node", "name": "demand1"},
+                "threshold": {"type": "Constant", "value": 10.0},
+                "predicate": "<"
+            }]
+        });
+
+        let mut network: PywrNetwork = serde_json::from_value(document).unwrap();
+
+        let mut metrics = Vec::new();
+        network.visit_metrics(&mut |metric| metrics.push(metric.clone()));
+        assert_eq!(metrics.len(), 2);
+        assert!(matches!(metrics[0], Metric::Node(_)));
+        assert!(matches!(metrics[1], Metric::Constant { value } if value == 10.0));
+
+        network.visit_metrics_mut(&mut |metric| {
+            if let Metric::Constant { value } = metric {
+                *value = 5.0;
+            }
+        });
+        let saved = serde_json::to_value(&network).unwrap();
+        assert_eq!(saved["outputs"][0]["threshold"]["value"], 5.0);
+    }
 }
 
 #[cfg(test)]
 #[cfg(feature = "core")]
 mod core_tests {
-    use super::{PywrModel, PywrMultiNetworkModel};
+    use super::{ModelUpdate, PywrModel, PywrMultiNetworkModel, Scenario, ScenarioSubset, UpdateFromSchema};
+    use crate::error::SchemaError;
     use crate::metric::{Metric, ParameterReference};
-    use crate::parameters::{AggFunc, AggregatedParameter, ConstantParameter, ConstantValue, Parameter, ParameterMeta};
+    use crate::parameters::{
+        AggFunc, AggregatedParameter, ConstantFloatVec, ConstantParameter, ConstantValue, MonthlyProfileParameter,
+        Parameter, ParameterMeta,
+    };
     use ndarray::{Array1, Array2, Axis};
     use pywr_core::{metric::MetricF64, recorders::AssertionRecorder, solvers::ClpSolver, test_utils::run_all_solvers};
     use std::fs::read_to_string;
+    use std::num::NonZeroUsize;
     use std::path::PathBuf;
 
     fn model_str() -> String {
@@ -1057,6 +1988,124 @@ mod core_tests {
         run_all_solvers(&model, &[], &[], &[]);
     }
 
+    #[test]
+    fn test_scenario_subset() {
+        let data = model_str();
+        let mut schema: PywrModel = serde_json::from_str(&data).unwrap();
+        schema.scenarios = Some(vec![Scenario {
+            name: "ensemble".to_string(),
+            size: 10,
+            ensemble_names: None,
+            subset: Some(ScenarioSubset::Slice { start: 2, end: 5 }),
+        }]);
+
+        let model = schema.build_model(None, None).unwrap();
+        let scenarios = model.domain().scenarios();
+        assert_eq!(scenarios.len(), 3);
+        assert_eq!(scenarios.groups()[0].size(), 10);
+
+        // Overriding the subset replaces the one in the model
+        schema
+            .set_scenario_subset("ensemble", ScenarioSubset::Indices { indices: vec![0, 9] })
+            .unwrap();
+        let model = schema.build_model(None, None).unwrap();
+        assert_eq!(model.domain().scenarios().len(), 2);
+        model.run::<ClpSolver>(&Default::default()).unwrap();
+
+        assert!(schema
+            .set_scenario_subset("missing", ScenarioSubset::Indices { indices: vec![0] })
+            .is_err());
+        schema
+            .set_scenario_subset("ensemble", ScenarioSubset::Slice { start: 5, end: 11 })
+            .unwrap();
+        assert!(schema.build_model(None, None).is_err());
+    }
+
+    #[test]
+    fn test_repeats() {
+        let data = model_str();
+        let mut schema: PywrModel = serde_json::from_str(&data).unwrap();
+        schema.scenarios = Some(vec![Scenario {
+            name: "ensemble".to_string(),
+            size: 2,
+            ensemble_names: None,
+            subset: None,
+        }]);
+        schema.repeats = NonZeroUsize::new(3);
+
+        let model = schema.build_model(None, None).unwrap();
+        let scenarios = model.domain().scenarios();
+        assert_eq!(scenarios.len(), 6);
+        assert_eq!(scenarios.groups()[0].name(), "repeat");
+        assert_eq!(scenarios.groups()[0].size(), 3);
+        model.run::<ClpSolver>(&Default::default()).unwrap();
+    }
+
+    #[test]
+    fn test_update_from_schema() {
+        let data = model_str();
+        let schema: PywrModel = serde_json::from_str(&data).unwrap();
+        let mut model = schema.build_model(None, None).unwrap();
+
+        assert_eq!(
+            model.update_from_schema(&schema, &schema, None).unwrap(),
+            ModelUpdate::Unchanged
+        );
+
+        // Changing the value of a constant parameter only replaces that parameter
+        let mut new_schema = schema.clone();
+        if let Some(Parameter::Constant(p)) = new_schema.network.parameters.as_mut().and_then(|p| p.first_mut()) {
+            p.value = ConstantValue::Literal(5.0);
+        }
+        assert_eq!(
+            model.update_from_schema(&schema, &new_schema, None).unwrap(),
+            ModelUpdate::Parameters(vec!["demand".to_string()])
+        );
+
+        let network = model.network_mut();
+        let demand1_idx = network.get_node_index_by_name("demand1", None).unwrap();
+        let expected_values: Array1<f64> = [5.0; 365].to_vec().into();
+        let rec = AssertionRecorder::new(
+            "assert-demand1",
+            MetricF64::NodeInFlow(demand1_idx),
+            expected_values.insert_axis(Axis(1)),
+            None,
+            None,
+        );
+        network.add_recorder(Box::new(rec)).unwrap();
+        model.run::<ClpSolver>(&Default::default()).unwrap();
+
+        // Changing the network's topology is not supported
+        let mut edges_schema = new_schema.clone();
+        edges_schema.network.edges.pop();
+        let err = model.update_from_schema(&new_schema, &edges_schema, None).unwrap_err();
+        assert!(
+            matches!(&err, SchemaError::ModelUpdateNotSupported(reason) if reason.contains("network.edges")),
+            "unexpected error: {err}"
+        );
+
+        // Nor is changing the type of a parameter
+        let mut type_schema = new_schema.clone();
+        if let Some(p) = type_schema.network.parameters.as_mut().and_then(|p| p.first_mut()) {
+            *p = Parameter::MonthlyProfile(MonthlyProfileParameter {
+                meta: ParameterMeta {
+                    name: "demand".to_string(),
+                    comment: None,
+                    min: None,
+                    max: None,
+                },
+                values: ConstantFloatVec::Literal(vec![5.0; 12]),
+                interp_day: None,
+                leap_day_policy: None,
+            });
+        }
+        let err = model.update_from_schema(&new_schema, &type_schema, None).unwrap_err();
+        assert!(
+            matches!(&err, SchemaError::ModelUpdateNotSupported(reason) if reason.contains("type of global parameter `demand`")),
+            "unexpected error: {err}"
+        );
+    }
+
     /// Test that a cycle in parameter dependencies does not load.
     #[test]
     fn test_cycle_error() {
@@ -1070,6 +2119,8 @@ mod core_tests {
                     meta: ParameterMeta {
                         name: "agg1".to_string(),
                         comment: None,
+                        min: None,
+                        max: None,
                     },
                     agg_func: AggFunc::Sum,
                     metrics: vec![
@@ -1087,6 +2138,8 @@ mod core_tests {
                     meta: ParameterMeta {
                         name: "p1".to_string(),
                         comment: None,
+                        min: None,
+                        max: None,
                     },
                     value: ConstantValue::Literal(10.0),
                     variable: None,
@@ -1095,6 +2148,8 @@ mod core_tests {
                     meta: ParameterMeta {
                         name: "agg2".to_string(),
                         comment: None,
+                        min: None,
+                        max: None,
                     },
                     agg_func: AggFunc::Sum,
                     metrics: vec![
@@ -1127,6 +2182,8 @@ mod core_tests {
                     meta: ParameterMeta {
                         name: "agg1".to_string(),
                         comment: None,
+                        min: None,
+                        max: None,
                     },
                     agg_func: AggFunc::Sum,
                     metrics: vec![
@@ -1144,6 +2201,8 @@ mod core_tests {
                     meta: ParameterMeta {
                         name: "p1".to_string(),
                         comment: None,
+                        min: None,
+                        max: None,
                     },
                     value: ConstantValue::Literal(10.0),
                     variable: None,
@@ -1152,6 +2211,8 @@ mod core_tests {
                     meta: ParameterMeta {
                         name: "p2".to_string(),
                         comment: None,
+                        min: None,
+                        max: None,
                     },
                     value: ConstantValue::Literal(10.0),
                     variable: None,
@@ -1262,4 +2323,37 @@ mod core_tests {
 
         model.run::<ClpSolver>(&Default::default()).unwrap();
     }
+
+    /// Test the multi3 model
+    #[test]
+    fn test_multi3_model() {
+        let mut model_fn = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        model_fn.push("tests/multi3/model.json");
+
+        let schema = PywrMultiNetworkModel::from_path(model_fn.as_path()).unwrap();
+        let mut model = schema.build_model(model_fn.parent(), None).unwrap();
+        assert!(model.iteration().is_some());
+
+        // Inflow to demand1 should be 5.0 in every time-step, including the first, because the
+        // transfer from network2 is iterated within the time-step.
+        let network_1_idx = model
+            .get_network_index_by_name("network1")
+            .expect("network 1 not found");
+        let network_1 = model.network_mut(network_1_idx).expect("network 1 not found");
+        let demand1_idx = network_1.get_node_index_by_name("demand1", None).unwrap();
+
+        let expected_values: Array1<f64> = [5.0; 365].to_vec().into();
+        let expected_values: Array2<f64> = expected_values.insert_axis(Axis(1));
+
+        let rec = AssertionRecorder::new(
+            "assert-demand1",
+            MetricF64::NodeInFlow(demand1_idx),
+            expected_values,
+            None,
+            None,
+        );
+        network_1.add_recorder(Box::new(rec)).unwrap();
+
+        model.run::<ClpSolver>(&Default::default()).unwrap();
+    }
 }
@@ -12,7 +12,7 @@ use crate::v1::{try_convert_initial_storage, try_convert_node_attr, ConversionDa
 use pywr_core::{
     derived_metric::DerivedMetric,
     metric::MetricF64,
-    virtual_storage::{VirtualStorageBuilder, VirtualStorageReset},
+    virtual_storage::{LicenceProfile as LicenceProfileCore, VirtualStorageBuilder, VirtualStorageReset},
 };
 use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::nodes::AnnualVirtualStorageNode as AnnualVirtualStorageNodeV1;
@@ -36,6 +36,54 @@ impl Default for AnnualReset {
     }
 }
 
+/// The maximum cumulative use of the annual volume through the year.
+///
+/// Each value is the proportion of the maximum volume that may have been used by the end of
+/// the corresponding week or month, counted from the reset date. The values must be
+/// non-decreasing and between zero and one.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll, strum_macros::Display)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum LicenceProfile {
+    Weekly { values: Vec<f64> },
+    Monthly { values: [f64; 12] },
+}
+
+#[cfg(feature = "core")]
+impl LicenceProfile {
+    fn values(&self) -> &[f64] {
+        match self {
+            Self::Weekly { values } => values,
+            Self::Monthly { values } => values,
+        }
+    }
+
+    /// Validate the profile and convert it to the core representation.
+    fn load(&self, name: &str) -> Result<LicenceProfileCore, SchemaError> {
+        let invalid = |reason: &str| SchemaError::InvalidLicenceProfile {
+            name: name.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let values = self.values();
+        if values.is_empty() {
+            return Err(invalid("at least one value must be given"));
+        }
+        if values.iter().any(|v| !(0.0..=1.0).contains(v)) {
+            return Err(invalid("values must be between zero and one"));
+        }
+        if values.windows(2).any(|w| w[1] < w[0]) {
+            return Err(invalid("values must be non-decreasing"));
+        }
+
+        let profile = match self {
+            Self::Weekly { values } => LicenceProfileCore::Weekly(values.clone()),
+            Self::Monthly { values } => LicenceProfileCore::Monthly(*values),
+        };
+
+        Ok(profile)
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
 #[serde(deny_unknown_fields)]
 pub struct AnnualVirtualStorageNode {
@@ -49,6 +97,8 @@ pub struct AnnualVirtualStorageNode {
     pub cost: Option<Metric>,
     pub initial_volume: StorageInitialVolume,
     pub reset: AnnualReset,
+    /// Optional profile limiting the cumulative use of the volume since the last reset.
+    pub licence_profile: Option<LicenceProfile>,
 }
 
 impl AnnualVirtualStorageNode {
@@ -125,6 +175,10 @@ impl AnnualVirtualStorageNode {
             builder = builder.factors(factors);
         }
 
+        if let Some(licence_profile) = &self.licence_profile {
+            builder = builder.licence_profile(licence_profile.load(&self.meta.name)?);
+        }
+
         network.add_virtual_storage_node(builder)?;
         Ok(())
     }
@@ -192,6 +246,7 @@ impl TryFromV1<AnnualVirtualStorageNodeV1> for AnnualVirtualStorageNode {
                 month: v1.reset_month as u8,
                 use_initial_volume: v1.reset_to_initial_volume,
             },
+            licence_profile: None,
         };
         Ok(n)
     }
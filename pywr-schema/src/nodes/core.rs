@@ -30,6 +30,8 @@ pub struct InputNode {
     pub max_flow: Option<Metric>,
     pub min_flow: Option<Metric>,
     pub cost: Option<Metric>,
+    /// Optional minimum up and down times for an asset that cannot be switched on and off freely.
+    pub switching: Option<SwitchingConstraints>,
 }
 
 impl InputNode {
@@ -81,12 +83,16 @@ impl InputNode {
             network.set_node_min_flow(self.meta.name.as_str(), None, value.into())?;
         }
 
+        if let Some(switching) = &self.switching {
+            network.set_node_switching_constraints(self.meta.name.as_str(), None, Some(switching.into()))?;
+        }
+
         Ok(())
     }
 
     pub fn create_metric(
         &self,
-        network: &pywr_core::network::Network,
+        network: &mut pywr_core::network::Network,
         attribute: Option<NodeAttribute>,
     ) -> Result<MetricF64, SchemaError> {
         // Use the default attribute if none is specified
@@ -96,6 +102,16 @@ impl InputNode {
 
         let metric = match attr {
             NodeAttribute::Outflow => MetricF64::NodeOutFlow(idx),
+            NodeAttribute::Utilisation => {
+                let dm = DerivedMetric::NodeUtilisation(idx);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
+            NodeAttribute::Dual => {
+                let dm = DerivedMetric::NodeDual(idx);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
             _ => {
                 return Err(SchemaError::NodeAttributeNotSupported {
                     ty: "InputNode".to_string(),
@@ -129,11 +145,47 @@ impl TryFromV1<InputNodeV1> for InputNode {
             max_flow,
             min_flow,
             cost,
+            switching: None,
         };
         Ok(n)
     }
 }
 
+/// Minimum up and down times for a node representing an asset, such as a desalination plant,
+/// that cannot be switched on and off freely.
+///
+/// The node is on in a time-step if its flow is greater than zero. Once switched on it must remain
+/// on, with a flow of at least `min_on_flow`, for `min_up_time` time-steps. Once switched off it
+/// must remain off for `min_down_time` time-steps. The node must not have a non-zero `min_flow`.
+///
+/// Solvers with integer variables (CBC and HiGHS) also ensure the flow is either zero or at least
+/// `min_on_flow` when the node is free to switch. CLP only enforces the minimum up and down
+/// times; a flow less than `min_on_flow` may be allocated when the node is free to switch.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct SwitchingConstraints {
+    /// The minimum number of time-steps the node remains on once switched on.
+    #[serde(default)]
+    pub min_up_time: u64,
+    /// The minimum number of time-steps the node remains off once switched off.
+    #[serde(default)]
+    pub min_down_time: u64,
+    /// The minimum flow through the node while it is on.
+    #[serde(default)]
+    pub min_on_flow: f64,
+}
+
+#[cfg(feature = "core")]
+impl From<&SwitchingConstraints> for pywr_core::node::SwitchingConstraints {
+    fn from(value: &SwitchingConstraints) -> Self {
+        Self::new(
+            value.min_up_time as usize,
+            value.min_down_time as usize,
+            value.min_on_flow,
+        )
+    }
+}
+
 /// Cost and flow metric for soft node's constraints
 #[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
 pub struct SoftConstraint {
@@ -505,7 +557,7 @@ impl LinkNode {
 
     pub fn create_metric(
         &self,
-        network: &pywr_core::network::Network,
+        network: &mut pywr_core::network::Network,
         attribute: Option<NodeAttribute>,
     ) -> Result<MetricF64, SchemaError> {
         // Use the default attribute if none is specified
@@ -540,6 +592,17 @@ impl LinkNode {
                 indices,
                 name: self.meta.name.to_string(),
             },
+            // The max flow is only applied to the link node itself when there are no soft constraints
+            NodeAttribute::Utilisation if self.soft_min.is_none() && self.soft_max.is_none() => {
+                let dm = DerivedMetric::NodeUtilisation(link_node);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
+            NodeAttribute::Dual if self.soft_min.is_none() && self.soft_max.is_none() => {
+                let dm = DerivedMetric::NodeDual(link_node);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
             _ => {
                 return Err(SchemaError::NodeAttributeNotSupported {
                     ty: "LinkNode".to_string(),
@@ -636,6 +699,16 @@ impl OutputNode {
                 let dm_idx = network.add_derived_metric(dm);
                 MetricF64::DerivedMetric(dm_idx)
             }
+            NodeAttribute::Utilisation => {
+                let dm = DerivedMetric::NodeUtilisation(idx);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
+            NodeAttribute::Dual => {
+                let dm = DerivedMetric::NodeDual(idx);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
             _ => {
                 return Err(SchemaError::NodeAttributeNotSupported {
                     ty: "OutputNode".to_string(),
@@ -963,6 +1036,11 @@ impl CatchmentNode {
 
         let metric = match attr {
             NodeAttribute::Outflow => MetricF64::NodeOutFlow(idx),
+            NodeAttribute::Utilisation => {
+                let dm = DerivedMetric::NodeUtilisation(idx);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
             _ => {
                 return Err(SchemaError::NodeAttributeNotSupported {
                     ty: "CatchmentNode".to_string(),
@@ -1002,12 +1080,17 @@ impl TryFromV1<CatchmentNodeV1> for CatchmentNode {
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll, strum_macros::Display)]
 #[serde(tag = "type", deny_unknown_fields)]
 pub enum Relationship {
-    Proportion {
-        factors: Vec<Metric>,
-    },
-    Ratio {
-        factors: Vec<Metric>,
-    },
+    /// The flow of each node is a proportion of the total flow through the aggregated node.
+    ///
+    /// One factor is given for each node except the first, whose proportion is the remainder.
+    /// The factors can be any metric and are re-evaluated every time-step, so the proportions
+    /// can vary over time (e.g. following a profile or control curve).
+    Proportion { factors: Vec<Metric> },
+    /// The flows of the nodes are in the ratio of the factors.
+    ///
+    /// One factor is given for each node. The factors can be any metric and are re-evaluated
+    /// every time-step, so the ratio can vary over time (e.g. a blend that follows a profile).
+    Ratio { factors: Vec<Metric> },
     Exclusive {
         min_active: Option<u64>,
         max_active: Option<u64>,
@@ -1202,6 +1285,14 @@ impl TryFromV1<AggregatedNodeV1> for AggregatedNode {
     }
 }
 
+/// A node that aggregates the volume of one or more storage nodes.
+///
+/// The aggregated node is not connectable, but its total volume (`Volume`) and proportional
+/// volume (`ProportionalVolume`) can be used as metrics anywhere a storage node's can. For
+/// example, a control curve parameter may reference the aggregated node to create a trigger
+/// based on the combined storage of several reservoirs. The referenced nodes may be any node
+/// that contains storage (e.g. `Storage`, `PiecewiseStorage` or `ZonedStorage`); all of the
+/// storage of a compound node is included in the aggregation.
 #[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
 #[serde(deny_unknown_fields)]
 pub struct AggregatedStorageNode {
@@ -1252,12 +1343,30 @@ impl AggregatedStorageNode {
             .collect();
         Ok(indices)
     }
-    pub fn add_to_model(&self, network: &mut pywr_core::network::Network) -> Result<(), SchemaError> {
-        let nodes = self
-            .storage_nodes
-            .iter()
-            .map(|node_ref| network.get_node_index_by_name(&node_ref.name, None))
-            .collect::<Result<_, _>>()?;
+    pub fn add_to_model(&self, network: &mut pywr_core::network::Network, args: &LoadArgs) -> Result<(), SchemaError> {
+        let mut nodes = Vec::new();
+
+        for node_ref in &self.storage_nodes {
+            let node = args
+                .schema
+                .get_node_by_name(&node_ref.name)
+                .ok_or_else(|| SchemaError::NodeNotFound(node_ref.name.to_string()))?;
+
+            // Only the core storage nodes of the referenced node are aggregated. Compound nodes
+            // may contain more than one storage node (and other non-storage nodes).
+            let mut storage_indices = Vec::new();
+            for idx in node.node_indices_for_constraints(network, args)? {
+                if let pywr_core::node::NodeType::Storage = network.get_node(&idx)?.node_type() {
+                    storage_indices.push(idx);
+                }
+            }
+
+            if storage_indices.is_empty() {
+                return Err(SchemaError::NodeHasNoStorage(node_ref.name.clone()));
+            }
+
+            nodes.extend(storage_indices);
+        }
 
         network.add_aggregated_storage_node(self.meta.name.as_str(), None, nodes)?;
         Ok(())
@@ -1307,9 +1416,10 @@ impl From<AggregatedStorageNodeV1> for AggregatedStorageNode {
 
 #[cfg(test)]
 mod tests {
-    use crate::nodes::core::StorageInitialVolume;
+    use crate::nodes::core::{AggregatedStorageNode, StorageInitialVolume};
     use crate::nodes::InputNode;
     use crate::nodes::StorageNode;
+    use pywr_v1_schema::nodes::AggregatedStorageNode as AggregatedStorageNodeV1;
 
     #[test]
     fn test_input() {
@@ -1330,6 +1440,23 @@ mod tests {
         assert_eq!(node.meta.name, "supply1");
     }
 
+    #[test]
+    fn test_aggregated_storage_from_v1() {
+        let data = r#"
+            {
+                "name": "total",
+                "storage_nodes": ["reservoir1", "reservoir2"]
+            }
+            "#;
+
+        let v1: AggregatedStorageNodeV1 = serde_json::from_str(data).unwrap();
+        let node: AggregatedStorageNode = v1.into();
+
+        assert_eq!(node.meta.name, "total");
+        let names: Vec<_> = node.storage_nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["reservoir1", "reservoir2"]);
+    }
+
     #[test]
     fn test_storage_initial_volume_absolute() {
         let data = r#"
@@ -0,0 +1,229 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+use crate::metric::{Metric, SimpleNodeReference};
+#[cfg(feature = "core")]
+use crate::model::{LoadArgs, PywrNetwork};
+#[cfg(feature = "core")]
+use crate::nodes::{Node, TurbineNode};
+use crate::nodes::{NodeAttribute, NodeMeta};
+use crate::parameters::Parameter;
+#[cfg(feature = "core")]
+use pywr_core::{derived_metric::DerivedMetric, metric::MetricF64};
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// A chain of hydropower turbines in series on the same river.
+///
+/// The `turbines` must be given in order from the most upstream to the most downstream, and
+/// each must be a `Turbine` node. This node coordinates the turbines of the cascade:
+///
+///   1. The tailwater of each turbine (except the last) is the water entering the next turbine
+///      downstream. A turbine without a `tailwater_elevation` uses the water elevation of the
+///      next turbine instead.
+///   2. The optional `max_flow` limits the flow through each of the turbines, for example when
+///      they share a design capacity. As the turbines are in series the same water passes
+///      through each of them, so the limit applies to each turbine rather than to their sum.
+///   3. The `Power` attribute of this node is the total power produced by the turbines. The
+///      `Inflow` attribute is the flow into the first turbine and the `Outflow` attribute the
+///      flow out of the last turbine.
+///
+/// The turbines must still be connected by the edges of the network.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct HydropowerCascadeNode {
+    pub meta: NodeMeta,
+    /// Optional local parameters.
+    pub parameters: Option<Vec<Parameter>>,
+    /// The turbines of the cascade, from upstream to downstream.
+    pub turbines: Vec<SimpleNodeReference>,
+    /// The maximum flow through each of the turbines.
+    pub max_flow: Option<Metric>,
+}
+
+impl HydropowerCascadeNode {
+    const DEFAULT_ATTRIBUTE: NodeAttribute = NodeAttribute::Power;
+
+    pub fn input_connectors(&self) -> Vec<(&str, Option<String>)> {
+        vec![]
+    }
+
+    pub fn output_connectors(&self) -> Vec<(&str, Option<String>)> {
+        vec![]
+    }
+
+    pub fn default_metric(&self) -> NodeAttribute {
+        Self::DEFAULT_ATTRIBUTE
+    }
+}
+
+#[cfg(feature = "core")]
+impl HydropowerCascadeNode {
+    /// The turbine nodes of the cascade, from upstream to downstream.
+    fn turbine_nodes<'a>(&self, schema: &'a PywrNetwork) -> Result<Vec<&'a TurbineNode>, SchemaError> {
+        if self.turbines.len() < 2 {
+            return Err(SchemaError::InvalidHydropowerCascade {
+                name: self.meta.name.clone(),
+                reason: "at least two turbines must be defined".to_string(),
+            });
+        }
+
+        self.turbines
+            .iter()
+            .map(|turbine_ref| {
+                match schema
+                    .get_node_by_name(&turbine_ref.name)
+                    .ok_or_else(|| SchemaError::NodeNotFound(turbine_ref.name.to_string()))?
+                {
+                    Node::Turbine(turbine) => Ok(turbine),
+                    node => Err(SchemaError::InvalidHydropowerCascade {
+                        name: self.meta.name.clone(),
+                        reason: format!("node `{}` is a {} node, not a Turbine", node.name(), node.node_type()),
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    pub fn node_indices_for_constraints(
+        &self,
+        network: &pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<Vec<pywr_core::node::NodeIndex>, SchemaError> {
+        let indices = self
+            .turbine_nodes(args.schema)?
+            .into_iter()
+            .map(|turbine| turbine.node_indices_for_constraints(network))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        Ok(indices)
+    }
+
+    pub fn add_to_model(&self, network: &mut pywr_core::network::Network, args: &LoadArgs) -> Result<(), SchemaError> {
+        if self.max_flow.is_none() {
+            return Ok(());
+        }
+
+        // The flow of each turbine is constrained by its own aggregated node, so that the limit
+        // is in addition to any limit of the turbine itself.
+        for turbine in self.turbine_nodes(args.schema)? {
+            let nodes = turbine.node_indices_for_constraints(network)?;
+            network.add_aggregated_node(
+                self.meta.name.as_str(),
+                Some(turbine.meta.name.as_str()),
+                &[nodes],
+                None,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn set_constraints(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<(), SchemaError> {
+        if let Some(max_flow) = &self.max_flow {
+            let value = max_flow.load(network, args, Some(&self.meta.name))?;
+            for turbine in self.turbine_nodes(args.schema)? {
+                network.set_aggregated_node_max_flow(
+                    self.meta.name.as_str(),
+                    Some(turbine.meta.name.as_str()),
+                    Some(value.clone()),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn create_metric(
+        &self,
+        network: &mut pywr_core::network::Network,
+        attribute: Option<NodeAttribute>,
+        args: &LoadArgs,
+    ) -> Result<MetricF64, SchemaError> {
+        // Use the default attribute if none is specified
+        let attr = attribute.unwrap_or(Self::DEFAULT_ATTRIBUTE);
+
+        let metric = match attr {
+            NodeAttribute::Power => {
+                let turbines = self
+                    .turbine_nodes(args.schema)?
+                    .into_iter()
+                    .map(|turbine| {
+                        let idx = network.get_node_index_by_name(turbine.meta.name.as_str(), None)?;
+                        Ok((idx, turbine.turbine_data(network, args)?))
+                    })
+                    .collect::<Result<Vec<_>, SchemaError>>()?;
+
+                let dm_idx = network.add_derived_metric(DerivedMetric::PowerFromNodeFlows(turbines));
+                MetricF64::DerivedMetric(dm_idx)
+            }
+            NodeAttribute::Inflow => {
+                let turbines = self.turbine_nodes(args.schema)?;
+                let first = turbines.first().ok_or_else(|| SchemaError::InvalidHydropowerCascade {
+                    name: self.meta.name.clone(),
+                    reason: "at least two turbines must be defined".to_string(),
+                })?;
+                MetricF64::NodeInFlow(network.get_node_index_by_name(first.meta.name.as_str(), None)?)
+            }
+            NodeAttribute::Outflow => {
+                let turbines = self.turbine_nodes(args.schema)?;
+                let last = turbines.last().ok_or_else(|| SchemaError::InvalidHydropowerCascade {
+                    name: self.meta.name.clone(),
+                    reason: "at least two turbines must be defined".to_string(),
+                })?;
+                MetricF64::NodeOutFlow(network.get_node_index_by_name(last.meta.name.as_str(), None)?)
+            }
+            _ => {
+                return Err(SchemaError::NodeAttributeNotSupported {
+                    ty: "HydropowerCascadeNode".to_string(),
+                    name: self.meta.name.clone(),
+                    attr,
+                })
+            }
+        };
+
+        Ok(metric)
+    }
+}
+
+/// The turbine immediately downstream of `name` in any cascade of the network.
+#[cfg(feature = "core")]
+pub(super) fn downstream_turbine<'a>(schema: &'a PywrNetwork, name: &str) -> Option<&'a TurbineNode> {
+    schema.nodes.iter().find_map(|node| {
+        let Node::HydropowerCascade(cascade) = node else {
+            return None;
+        };
+
+        let position = cascade.turbines.iter().position(|t| t.name == name)?;
+        match schema.get_node_by_name(&cascade.turbines.get(position + 1)?.name)? {
+            Node::Turbine(turbine) => Some(turbine),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::HydropowerCascadeNode;
+
+    #[test]
+    fn test_hydropower_cascade_schema() {
+        let data = r#"
+            {
+                "meta": {
+                    "name": "cascade"
+                },
+                "turbines": [{"name": "turbine1"}, {"name": "turbine2"}],
+                "max_flow": {"type": "Constant", "value": 50.0}
+            }
+            "#;
+
+        let node: HydropowerCascadeNode = serde_json::from_str(data).unwrap();
+        assert_eq!(node.turbines.len(), 2);
+        assert!(node.max_flow.is_some());
+    }
+}
@@ -0,0 +1,248 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+use crate::metric::{Metric, SimpleNodeReference};
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::nodes::{AnnualReset, LicenceProfile, NodeAttribute, NodeMeta, RollingWindow, StorageInitialVolume};
+use crate::parameters::Parameter;
+#[cfg(feature = "core")]
+use pywr_core::{
+    derived_metric::DerivedMetric,
+    metric::MetricF64,
+    virtual_storage::{VirtualStorageBuilder, VirtualStorageIndex, VirtualStorageReset},
+};
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// The period over which the volume of a [`LicenceLimit`] applies.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll, strum_macros::Display)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum LicencePeriod {
+    /// The volume is reset every year on the date given by `reset` (defaults to the 1st January).
+    Annual {
+        reset: Option<AnnualReset>,
+        /// Optional profile limiting the cumulative use of the volume through the year.
+        licence_profile: Option<LicenceProfile>,
+    },
+    /// The volume is reset at the start of every month.
+    Monthly,
+    /// The volume applies to the use over the last `window` days or time-steps.
+    Rolling { window: RollingWindow },
+}
+
+/// A limit on the total abstraction over a period.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct LicenceLimit {
+    pub period: LicencePeriod,
+    /// The volume that may be abstracted in each period.
+    pub volume: Metric,
+    /// The volume remaining at the start of the simulation. Defaults to the full volume.
+    pub initial_volume: Option<StorageInitialVolume>,
+    /// The volume remaining at the start of each subsequent period. Defaults to the full volume.
+    pub reset_volume: Option<StorageInitialVolume>,
+}
+
+/// An abstraction licence with one or more limits.
+///
+/// A licence often limits the abstraction from one or more sources over several periods at
+/// once; for example, a daily limit, a limit of 1000 Ml each month and 9000 Ml each year. This
+/// node constrains the flow through `nodes` (scaled by the optional `factors`) so that all of
+/// its `limits` are satisfied. Daily limits should be applied to the nodes' `max_flow`.
+///
+/// Each limit is represented by a separate virtual storage node. The annual and monthly limits
+/// are reset at the start of each period, and rolling limits apply to the use over the most
+/// recent days or time-steps. The `Volume` and `ProportionalVolume` attributes of this node
+/// give the smallest remaining volume of the limits (i.e. of the limit closest to being used).
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct LicenceNode {
+    pub meta: NodeMeta,
+    /// Optional local parameters.
+    pub parameters: Option<Vec<Parameter>>,
+    pub nodes: Vec<SimpleNodeReference>,
+    pub factors: Option<Vec<f64>>,
+    pub limits: Vec<LicenceLimit>,
+    /// Optional cost applied to the use of the licence. This is applied once regardless of
+    /// the number of limits.
+    pub cost: Option<Metric>,
+}
+
+impl LicenceNode {
+    const DEFAULT_ATTRIBUTE: NodeAttribute = NodeAttribute::Volume;
+
+    pub fn input_connectors(&self) -> Vec<(&str, Option<String>)> {
+        vec![]
+    }
+
+    pub fn output_connectors(&self) -> Vec<(&str, Option<String>)> {
+        vec![]
+    }
+
+    pub fn default_metric(&self) -> NodeAttribute {
+        Self::DEFAULT_ATTRIBUTE
+    }
+}
+
+#[cfg(feature = "core")]
+impl LicenceNode {
+    fn limit_sub_name(i: usize) -> String {
+        format!("limit-{i:02}")
+    }
+
+    pub fn node_indices_for_constraints(
+        &self,
+        network: &pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<Vec<pywr_core::node::NodeIndex>, SchemaError> {
+        let indices = self
+            .nodes
+            .iter()
+            .map(|node_ref| {
+                args.schema
+                    .get_node_by_name(&node_ref.name)
+                    .ok_or_else(|| SchemaError::NodeNotFound(node_ref.name.to_string()))?
+                    .node_indices_for_constraints(network, args)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        Ok(indices)
+    }
+
+    pub fn add_to_model(&self, network: &mut pywr_core::network::Network, args: &LoadArgs) -> Result<(), SchemaError> {
+        if self.limits.is_empty() {
+            return Err(SchemaError::InvalidLicence {
+                name: self.meta.name.clone(),
+                reason: "at least one limit must be defined".to_string(),
+            });
+        }
+
+        let node_idxs = self.node_indices_for_constraints(network, args)?;
+
+        for (i, limit) in self.limits.iter().enumerate() {
+            // The cost is only applied to the first limit; otherwise it would be incurred
+            // once for every limit.
+            let cost = match &self.cost {
+                Some(v) if i == 0 => v.load(network, args, Some(&self.meta.name))?.into(),
+                _ => None,
+            };
+            let max_volume = Some(limit.volume.load(network, args, Some(&self.meta.name))?.try_into()?);
+            let initial_volume = limit.initial_volume.unwrap_or(StorageInitialVolume::Proportional(1.0));
+            let reset_volume = limit.reset_volume.unwrap_or(StorageInitialVolume::Proportional(1.0));
+
+            let mut builder = VirtualStorageBuilder::new(self.meta.name.as_str(), &node_idxs)
+                .sub_name(&Self::limit_sub_name(i))
+                .initial_volume(initial_volume.into())
+                .reset_volume(reset_volume.into())
+                .max_volume(max_volume)
+                .cost(cost);
+
+            builder = match &limit.period {
+                LicencePeriod::Annual { reset, licence_profile } => {
+                    let reset = reset.clone().unwrap_or_default();
+                    let builder = builder.reset(VirtualStorageReset::DayOfYear {
+                        day: reset.day as u32,
+                        month: reset.month.try_into()?,
+                    });
+                    match licence_profile {
+                        Some(licence_profile) => builder.licence_profile(licence_profile.load(&self.meta.name)?),
+                        None => builder,
+                    }
+                }
+                LicencePeriod::Monthly => builder.reset(VirtualStorageReset::NumberOfMonths { months: 1 }),
+                LicencePeriod::Rolling { window } => {
+                    let timesteps =
+                        window
+                            .as_timesteps(args.domain.time())
+                            .ok_or_else(|| SchemaError::InvalidRollingWindow {
+                                name: self.meta.name.clone(),
+                            })?;
+                    builder.reset(VirtualStorageReset::Never).rolling_window(timesteps)
+                }
+            };
+
+            if let Some(factors) = &self.factors {
+                builder = builder.factors(factors);
+            }
+
+            network.add_virtual_storage_node(builder)?;
+        }
+
+        Ok(())
+    }
+
+    fn limit_indices(&self, network: &pywr_core::network::Network) -> Result<Vec<VirtualStorageIndex>, SchemaError> {
+        let indices = (0..self.limits.len())
+            .map(|i| {
+                network.get_virtual_storage_node_index_by_name(
+                    self.meta.name.as_str(),
+                    Some(Self::limit_sub_name(i).as_str()),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(indices)
+    }
+
+    pub fn create_metric(
+        &self,
+        network: &mut pywr_core::network::Network,
+        attribute: Option<NodeAttribute>,
+    ) -> Result<MetricF64, SchemaError> {
+        // Use the default attribute if none is specified
+        let attr = attribute.unwrap_or(Self::DEFAULT_ATTRIBUTE);
+
+        let indices = self.limit_indices(network)?;
+
+        let dm = match attr {
+            NodeAttribute::Volume => DerivedMetric::VirtualStorageMinVolume(indices),
+            NodeAttribute::ProportionalVolume => DerivedMetric::VirtualStorageMinProportionalVolume(indices),
+            _ => {
+                return Err(SchemaError::NodeAttributeNotSupported {
+                    ty: "LicenceNode".to_string(),
+                    name: self.meta.name.clone(),
+                    attr,
+                })
+            }
+        };
+
+        let derived_metric_idx = network.add_derived_metric(dm);
+        Ok(MetricF64::DerivedMetric(derived_metric_idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::{LicenceNode, LicencePeriod, StorageInitialVolume};
+
+    #[test]
+    fn test_licence_schema() {
+        let data = r#"
+            {
+                "meta": {
+                    "name": "licence"
+                },
+                "nodes": [{"name": "abstraction"}],
+                "limits": [
+                    {"period": {"type": "Annual"}, "volume": {"type": "Constant", "value": 9000.0}},
+                    {
+                        "period": {"type": "Monthly"},
+                        "volume": {"type": "Constant", "value": 1000.0},
+                        "initial_volume": {"Absolute": 400.0}
+                    },
+                    {"period": {"type": "Rolling", "window": {"Days": 7}}, "volume": {"type": "Constant", "value": 300.0}}
+                ]
+            }
+            "#;
+
+        let node: LicenceNode = serde_json::from_str(data).unwrap();
+        assert_eq!(node.limits.len(), 3);
+        assert!(matches!(node.limits[1].period, LicencePeriod::Monthly));
+        assert_eq!(
+            node.limits[1].initial_volume,
+            Some(StorageInitialVolume::Absolute(400.0))
+        );
+        assert_eq!(node.limits[1].reset_volume, None);
+    }
+}
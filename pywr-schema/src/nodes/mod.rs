@@ -1,10 +1,13 @@
 mod annual_virtual_storage;
 mod core;
 mod delay;
+mod hydropower_cascade;
+mod licence;
 mod loss_link;
 mod monthly_virtual_storage;
 mod piecewise_link;
 mod piecewise_storage;
+mod reservoir;
 mod river;
 mod river_gauge;
 mod river_split_with_gauge;
@@ -12,10 +15,12 @@ mod rolling_virtual_storage;
 mod turbine;
 mod virtual_storage;
 mod water_treatment_works;
+mod zoned_storage;
 
 #[cfg(feature = "core")]
 use crate::error::SchemaError;
 use crate::error::{ComponentConversionError, ConversionError};
+use crate::json::variant_error;
 use crate::metric::Metric;
 #[cfg(feature = "core")]
 use crate::model::LoadArgs;
@@ -23,22 +28,25 @@ use crate::model::PywrNetwork;
 use crate::parameters::Parameter;
 use crate::v1::{ConversionData, TryFromV1, TryIntoV2};
 use crate::visit::{VisitMetrics, VisitPaths};
-pub use annual_virtual_storage::{AnnualReset, AnnualVirtualStorageNode};
+pub use annual_virtual_storage::{AnnualReset, AnnualVirtualStorageNode, LicenceProfile};
 pub use core::{
     AggregatedNode, AggregatedStorageNode, CatchmentNode, InputNode, LinkNode, OutputNode, Relationship,
-    SoftConstraint, StorageInitialVolume, StorageNode,
+    SoftConstraint, StorageInitialVolume, StorageNode, SwitchingConstraints,
 };
 pub use delay::DelayNode;
+pub use hydropower_cascade::HydropowerCascadeNode;
+pub use licence::{LicenceLimit, LicenceNode, LicencePeriod};
 pub use loss_link::{LossFactor, LossLinkNode};
 pub use monthly_virtual_storage::{MonthlyVirtualStorageNode, NumberOfMonthsReset};
 pub use piecewise_link::{PiecewiseLinkNode, PiecewiseLinkStep};
 pub use piecewise_storage::{PiecewiseStorageNode, PiecewiseStore};
 #[cfg(feature = "core")]
-use pywr_core::metric::MetricF64;
+use pywr_core::metric::{MetricF64, MetricU64};
 use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::nodes::{
     CoreNode as CoreNodeV1, Node as NodeV1, NodeMeta as NodeMetaV1, NodePosition as NodePositionV1,
 };
+pub use reservoir::{Bathymetry, ReservoirNode, ReservoirSpill};
 pub use river::RiverNode;
 pub use river_gauge::RiverGaugeNode;
 pub use river_split_with_gauge::{RiverSplit, RiverSplitWithGaugeNode};
@@ -46,9 +54,10 @@ pub use rolling_virtual_storage::{RollingVirtualStorageNode, RollingWindow};
 use schemars::JsonSchema;
 use std::path::{Path, PathBuf};
 use strum_macros::{Display, EnumDiscriminants, EnumString, IntoStaticStr, VariantNames};
-pub use turbine::{TargetType, TurbineNode};
+pub use turbine::{LevelVolume, TargetType, TurbineNode};
 pub use virtual_storage::VirtualStorageNode;
 pub use water_treatment_works::WaterTreatmentWorks;
+pub use zoned_storage::{StorageZone, StorageZoneType, ZonedStorageNode};
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitAll)]
 pub struct NodePosition {
@@ -74,6 +83,13 @@ pub struct NodeMeta {
     pub comment: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub position: Option<NodePosition>,
+    /// An optional weight applied to this node's constraints by solvers that support it.
+    ///
+    /// Currently only the IPM solvers use this hint. A weight greater than one tightens the
+    /// effective convergence tolerance of the node's constraints (e.g. for small compensation
+    /// flows) without tightening the global tolerances. Other solvers ignore it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solver_weight: Option<f64>,
 }
 
 impl From<NodeMetaV1> for NodeMeta {
@@ -82,6 +98,7 @@ impl From<NodeMetaV1> for NodeMeta {
             name: v1.name,
             comment: v1.comment,
             position: v1.position.map(|p| p.into()),
+            solver_weight: None,
         }
     }
 }
@@ -97,7 +114,22 @@ pub enum NodeAttribute {
     ProportionalVolume,
     Loss,
     Deficit,
+    /// The flow through a node divided by its maximum flow.
+    ///
+    /// This is supported by the nodes that have a maximum flow (e.g. the `flow` of a catchment
+    /// or the `mrf` of a river gauge). Nodes without one, such as a river, are never constrained
+    /// and so do not support it.
+    Utilisation,
+    /// The dual value (shadow price) of a node's flow constraint. This requires the solver to be
+    /// configured to save dual values.
+    Dual,
     Power,
+    /// The index of the zone containing the current volume of a zoned storage node.
+    ZoneIndex,
+    /// The surface area of a reservoir interpolated from its current volume.
+    Area,
+    /// The level of a reservoir interpolated from its current volume.
+    Level,
 }
 
 pub struct NodeBuilder {
@@ -234,6 +266,22 @@ impl NodeBuilder {
                 meta,
                 ..Default::default()
             }),
+            NodeType::ZonedStorage => Node::ZonedStorage(ZonedStorageNode {
+                meta,
+                ..Default::default()
+            }),
+            NodeType::Reservoir => Node::Reservoir(ReservoirNode {
+                meta,
+                ..Default::default()
+            }),
+            NodeType::Licence => Node::Licence(LicenceNode {
+                meta,
+                ..Default::default()
+            }),
+            NodeType::HydropowerCascade => Node::HydropowerCascade(HydropowerCascadeNode {
+                meta,
+                ..Default::default()
+            }),
         }
     }
 }
@@ -264,6 +312,47 @@ pub enum Node {
     MonthlyVirtualStorage(MonthlyVirtualStorageNode),
     RollingVirtualStorage(RollingVirtualStorageNode),
     Turbine(TurbineNode),
+    ZonedStorage(ZonedStorageNode),
+    Reservoir(ReservoirNode),
+    Licence(LicenceNode),
+    HydropowerCascade(HydropowerCascadeNode),
+}
+
+impl NodeType {
+    /// The path and error of deserialising `value` as a node of this type.
+    ///
+    /// See [`crate::json`] for why this is needed.
+    pub(crate) fn variant_error(
+        &self,
+        value: &serde_json::Value,
+    ) -> Option<(serde_path_to_error::Path, serde_json::Error)> {
+        match self {
+            Self::Input => variant_error::<InputNode>(value),
+            Self::Link => variant_error::<LinkNode>(value),
+            Self::Output => variant_error::<OutputNode>(value),
+            Self::Storage => variant_error::<StorageNode>(value),
+            Self::Catchment => variant_error::<CatchmentNode>(value),
+            Self::RiverGauge => variant_error::<RiverGaugeNode>(value),
+            Self::LossLink => variant_error::<LossLinkNode>(value),
+            Self::Delay => variant_error::<DelayNode>(value),
+            Self::PiecewiseLink => variant_error::<PiecewiseLinkNode>(value),
+            Self::PiecewiseStorage => variant_error::<PiecewiseStorageNode>(value),
+            Self::River => variant_error::<RiverNode>(value),
+            Self::RiverSplitWithGauge => variant_error::<RiverSplitWithGaugeNode>(value),
+            Self::WaterTreatmentWorks => variant_error::<WaterTreatmentWorks>(value),
+            Self::Aggregated => variant_error::<AggregatedNode>(value),
+            Self::AggregatedStorage => variant_error::<AggregatedStorageNode>(value),
+            Self::VirtualStorage => variant_error::<VirtualStorageNode>(value),
+            Self::AnnualVirtualStorage => variant_error::<AnnualVirtualStorageNode>(value),
+            Self::MonthlyVirtualStorage => variant_error::<MonthlyVirtualStorageNode>(value),
+            Self::RollingVirtualStorage => variant_error::<RollingVirtualStorageNode>(value),
+            Self::Turbine => variant_error::<TurbineNode>(value),
+            Self::ZonedStorage => variant_error::<ZonedStorageNode>(value),
+            Self::Reservoir => variant_error::<ReservoirNode>(value),
+            Self::Licence => variant_error::<LicenceNode>(value),
+            Self::HydropowerCascade => variant_error::<HydropowerCascadeNode>(value),
+        }
+    }
 }
 
 impl Node {
@@ -302,6 +391,10 @@ impl Node {
             Node::MonthlyVirtualStorage(n) => &n.meta,
             Node::RollingVirtualStorage(n) => &n.meta,
             Node::Turbine(n) => &n.meta,
+            Node::ZonedStorage(n) => &n.meta,
+            Node::Reservoir(n) => &n.meta,
+            Node::Licence(n) => &n.meta,
+            Node::HydropowerCascade(n) => &n.meta,
         }
     }
 
@@ -328,6 +421,10 @@ impl Node {
             Node::Delay(n) => n.input_connectors(),
             Node::RollingVirtualStorage(n) => n.input_connectors(),
             Node::Turbine(n) => n.input_connectors(),
+            Node::ZonedStorage(n) => n.input_connectors(),
+            Node::Reservoir(n) => n.input_connectors(),
+            Node::Licence(n) => n.input_connectors(),
+            Node::HydropowerCascade(n) => n.input_connectors(),
         }
     }
 
@@ -354,6 +451,10 @@ impl Node {
             Node::Delay(n) => n.output_connectors(),
             Node::RollingVirtualStorage(n) => n.output_connectors(),
             Node::Turbine(n) => n.output_connectors(),
+            Node::ZonedStorage(n) => n.output_connectors(),
+            Node::Reservoir(n) => n.output_connectors(slot),
+            Node::Licence(n) => n.output_connectors(),
+            Node::HydropowerCascade(n) => n.output_connectors(),
         }
     }
     pub fn default_metric(&self) -> NodeAttribute {
@@ -378,6 +479,10 @@ impl Node {
             Node::Delay(n) => n.default_metric(),
             Node::RollingVirtualStorage(n) => n.default_metric(),
             Node::Turbine(n) => n.default_metric(),
+            Node::ZonedStorage(n) => n.default_metric(),
+            Node::Reservoir(n) => n.default_metric(),
+            Node::Licence(n) => n.default_metric(),
+            Node::HydropowerCascade(n) => n.default_metric(),
         }
     }
 
@@ -407,6 +512,10 @@ impl Node {
             Node::Delay(n) => n.parameters.as_deref(),
             Node::RollingVirtualStorage(n) => n.parameters.as_deref(),
             Node::Turbine(n) => n.parameters.as_deref(),
+            Node::ZonedStorage(n) => n.parameters.as_deref(),
+            Node::Reservoir(n) => n.parameters.as_deref(),
+            Node::Licence(n) => n.parameters.as_deref(),
+            Node::HydropowerCascade(n) => n.parameters.as_deref(),
         }
     }
 }
@@ -414,6 +523,30 @@ impl Node {
 #[cfg(feature = "core")]
 impl Node {
     pub fn add_to_model(&self, network: &mut pywr_core::network::Network, args: &LoadArgs) -> Result<(), SchemaError> {
+        self.add_nodes_to_model(network, args)?;
+
+        // Apply the solver weight to every core node created for this node.
+        if let Some(weight) = self.meta().solver_weight {
+            let sub_names: Vec<Option<String>> = network
+                .nodes()
+                .iter()
+                .filter(|n| n.name() == self.name())
+                .map(|n| n.sub_name().map(|s| s.to_string()))
+                .collect();
+
+            for sub_name in sub_names {
+                network.set_node_solver_weight(self.name(), sub_name.as_deref(), Some(weight))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_nodes_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<(), SchemaError> {
         match self {
             Node::Input(n) => n.add_to_model(network),
             Node::Link(n) => n.add_to_model(network),
@@ -426,13 +559,17 @@ impl Node {
             Node::RiverSplitWithGauge(n) => n.add_to_model(network),
             Node::WaterTreatmentWorks(n) => n.add_to_model(network),
             Node::Aggregated(n) => n.add_to_model(network, args),
-            Node::AggregatedStorage(n) => n.add_to_model(network),
+            Node::AggregatedStorage(n) => n.add_to_model(network, args),
             Node::VirtualStorage(n) => n.add_to_model(network, args),
             Node::AnnualVirtualStorage(n) => n.add_to_model(network, args),
             Node::PiecewiseLink(n) => n.add_to_model(network),
             Node::PiecewiseStorage(n) => n.add_to_model(network),
             Node::Delay(n) => n.add_to_model(network),
             Node::Turbine(n) => n.add_to_model(network, args),
+            Node::ZonedStorage(n) => n.add_to_model(network),
+            Node::Reservoir(n) => n.add_to_model(network),
+            Node::Licence(n) => n.add_to_model(network, args),
+            Node::HydropowerCascade(n) => n.add_to_model(network, args),
             Node::MonthlyVirtualStorage(n) => n.add_to_model(network, args),
             Node::RollingVirtualStorage(n) => n.add_to_model(network, args),
         }
@@ -463,6 +600,10 @@ impl Node {
             Node::PiecewiseStorage(n) => n.node_indices_for_constraints(network),
             Node::Delay(n) => n.node_indices_for_constraints(network),
             Node::Turbine(n) => n.node_indices_for_constraints(network),
+            Node::ZonedStorage(n) => n.node_indices_for_constraints(network),
+            Node::Reservoir(n) => n.node_indices_for_constraints(network),
+            Node::Licence(n) => n.node_indices_for_constraints(network, args),
+            Node::HydropowerCascade(n) => n.node_indices_for_constraints(network, args),
             Node::MonthlyVirtualStorage(n) => n.node_indices_for_constraints(network, args),
             Node::RollingVirtualStorage(n) => n.node_indices_for_constraints(network, args),
         }
@@ -492,6 +633,10 @@ impl Node {
             Node::PiecewiseStorage(n) => n.set_constraints(network, args),
             Node::Delay(n) => n.set_constraints(network, args),
             Node::Turbine(n) => n.set_constraints(network, args),
+            Node::ZonedStorage(n) => n.set_constraints(network, args),
+            Node::Reservoir(n) => n.set_constraints(network, args),
+            Node::Licence(_) => Ok(()), // The limits are defined when the node is added
+            Node::HydropowerCascade(n) => n.set_constraints(network, args),
             Node::MonthlyVirtualStorage(_) => Ok(()), // TODO
             Node::RollingVirtualStorage(_) => Ok(()), // TODO
         }
@@ -525,6 +670,29 @@ impl Node {
             Node::Delay(n) => n.create_metric(network, attribute),
             Node::RollingVirtualStorage(n) => n.create_metric(network, attribute),
             Node::Turbine(n) => n.create_metric(network, attribute, args),
+            Node::ZonedStorage(n) => n.create_metric(network, attribute, args),
+            Node::Reservoir(n) => n.create_metric(network, attribute),
+            Node::Licence(n) => n.create_metric(network, attribute),
+            Node::HydropowerCascade(n) => n.create_metric(network, attribute, args),
+        }
+    }
+
+    /// Create an index metric for the given attribute of this node.
+    ///
+    /// Only some node types provide attributes with integer values.
+    pub fn create_index_metric(
+        &self,
+        network: &mut pywr_core::network::Network,
+        attribute: Option<NodeAttribute>,
+        args: &LoadArgs,
+    ) -> Result<MetricU64, SchemaError> {
+        match self {
+            Node::ZonedStorage(n) => n.create_index_metric(network, attribute, args),
+            _ => Err(SchemaError::NodeAttributeNotSupported {
+                ty: self.node_type().to_string(),
+                name: self.name().to_string(),
+                attr: attribute.unwrap_or_else(|| self.default_metric()),
+            }),
         }
     }
 }
@@ -619,6 +787,10 @@ impl VisitMetrics for Node {
             Node::MonthlyVirtualStorage(n) => n.visit_metrics(visitor),
             Node::RollingVirtualStorage(n) => n.visit_metrics(visitor),
             Node::Turbine(n) => n.visit_metrics(visitor),
+            Node::ZonedStorage(n) => n.visit_metrics(visitor),
+            Node::Reservoir(n) => n.visit_metrics(visitor),
+            Node::Licence(n) => n.visit_metrics(visitor),
+            Node::HydropowerCascade(n) => n.visit_metrics(visitor),
         }
     }
 
@@ -644,6 +816,10 @@ impl VisitMetrics for Node {
             Node::MonthlyVirtualStorage(n) => n.visit_metrics_mut(visitor),
             Node::RollingVirtualStorage(n) => n.visit_metrics_mut(visitor),
             Node::Turbine(n) => n.visit_metrics_mut(visitor),
+            Node::ZonedStorage(n) => n.visit_metrics_mut(visitor),
+            Node::Reservoir(n) => n.visit_metrics_mut(visitor),
+            Node::Licence(n) => n.visit_metrics_mut(visitor),
+            Node::HydropowerCascade(n) => n.visit_metrics_mut(visitor),
         }
     }
 }
@@ -671,6 +847,10 @@ impl VisitPaths for Node {
             Node::MonthlyVirtualStorage(n) => n.visit_paths(visitor),
             Node::RollingVirtualStorage(n) => n.visit_paths(visitor),
             Node::Turbine(n) => n.visit_paths(visitor),
+            Node::ZonedStorage(n) => n.visit_paths(visitor),
+            Node::Reservoir(n) => n.visit_paths(visitor),
+            Node::Licence(n) => n.visit_paths(visitor),
+            Node::HydropowerCascade(n) => n.visit_paths(visitor),
         }
     }
 
@@ -696,6 +876,10 @@ impl VisitPaths for Node {
             Node::MonthlyVirtualStorage(n) => n.visit_paths_mut(visitor),
             Node::RollingVirtualStorage(n) => n.visit_paths_mut(visitor),
             Node::Turbine(n) => n.visit_paths_mut(visitor),
+            Node::ZonedStorage(n) => n.visit_paths_mut(visitor),
+            Node::Reservoir(n) => n.visit_paths_mut(visitor),
+            Node::Licence(n) => n.visit_paths_mut(visitor),
+            Node::HydropowerCascade(n) => n.visit_paths_mut(visitor),
         }
     }
 }
@@ -1,6 +1,6 @@
-use crate::error::ComponentConversionError;
 #[cfg(feature = "core")]
 use crate::error::SchemaError;
+use crate::error::{ComponentConversionError, ConversionError};
 use crate::metric::Metric;
 #[cfg(feature = "core")]
 use crate::model::LoadArgs;
@@ -173,20 +173,34 @@ impl TryFromV1<PiecewiseLinkNodeV1> for PiecewiseLinkNode {
     ) -> Result<Self, Self::Error> {
         let meta: NodeMeta = v1.meta.into();
 
+        // Each of the given attributes must have a value for every step
+        let check_len = |attr: &str, found: usize| {
+            if found != v1.nsteps {
+                Err(ComponentConversionError::Node {
+                    attr: attr.to_string(),
+                    name: meta.name.clone(),
+                    error: ConversionError::IncorrectNumberOfValues {
+                        expected: v1.nsteps,
+                        found,
+                    },
+                })
+            } else {
+                Ok(())
+            }
+        };
+
+        if let Some(costs) = &v1.costs {
+            check_len("costs", costs.len())?;
+        }
+        if let Some(max_flows) = &v1.max_flows {
+            check_len("max_flows", max_flows.len())?;
+        }
+
         let costs = match v1.costs {
             None => vec![None; v1.nsteps],
             Some(v1_costs) => v1_costs
                 .into_iter()
-                .map(|v| {
-                    try_convert_node_attr(
-                        &meta.name,
-                        "costs",
-                        v,
-                        parent_node.or(Some(&meta.name)),
-                        conversion_data,
-                    )
-                    .map(Some)
-                })
+                .map(|v| try_convert_node_attr(&meta.name, "costs", v, parent_node, conversion_data).map(Some))
                 .collect::<Result<Vec<_>, _>>()?,
         };
 
@@ -221,3 +235,35 @@ impl TryFromV1<PiecewiseLinkNodeV1> for PiecewiseLinkNode {
         Ok(n)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PiecewiseLinkNode;
+    use crate::error::{ComponentConversionError, ConversionError};
+    use crate::v1::{ConversionData, TryFromV1};
+    use pywr_v1_schema::nodes::PiecewiseLinkNode as PiecewiseLinkNodeV1;
+
+    #[test]
+    fn test_from_v1_incorrect_number_of_costs() {
+        let data = r#"
+            {
+                "name": "licence",
+                "nsteps": 2,
+                "max_flows": [10.0, null],
+                "costs": [-5.0]
+            }
+            "#;
+
+        let v1: PiecewiseLinkNodeV1 = serde_json::from_str(data).unwrap();
+        let result = PiecewiseLinkNode::try_from_v1(v1, None, &mut ConversionData::default());
+
+        assert!(matches!(
+            result,
+            Err(ComponentConversionError::Node {
+                attr,
+                error: ConversionError::IncorrectNumberOfValues { expected: 2, found: 1 },
+                ..
+            }) if attr == "costs"
+        ));
+    }
+}
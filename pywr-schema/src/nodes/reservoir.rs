@@ -0,0 +1,402 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+use crate::metric::Metric;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::nodes::{NodeAttribute, NodeMeta, StorageInitialVolume};
+use crate::parameters::Parameter;
+#[cfg(feature = "core")]
+use pywr_core::{
+    derived_metric::DerivedMetric,
+    metric::MetricF64,
+    parameters::{AggFunc, AggregatedParameter, InterpolatedParameter, ParameterIndex, ParameterName},
+};
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// The relationship between the volume, surface area and level of a reservoir.
+///
+/// The values are given as a table with one entry per point. The `volumes` must be in
+/// ascending order, and values between the points are linearly interpolated. Volumes
+/// outside the table use the first or last point.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct Bathymetry {
+    pub volumes: Vec<f64>,
+    /// The surface area at each volume. This is required to calculate rainfall and evaporation.
+    pub areas: Option<Vec<f64>>,
+    /// The level (e.g. elevation of the water surface) at each volume.
+    pub levels: Option<Vec<f64>>,
+}
+
+/// The spill from a [`ReservoirNode`].
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct ReservoirSpill {
+    /// The cost of spilling. A positive cost prevents water being spilled before the reservoir is full.
+    pub cost: Option<Metric>,
+}
+
+#[doc = svgbobdoc::transform!(
+/// A storage node with rainfall, evaporation and an optional spill.
+///
+/// This node standardises the common pattern of a reservoir whose rainfall and evaporation
+/// depend on its surface area. The surface area (and level) is interpolated from the
+/// [`Bathymetry`] table using the volume at the start of the time-step. The rainfall and
+/// evaporation are given as depths per time-step and are multiplied by the surface area and
+/// `unit_conversion` to give the flows in to and out of the storage.
+///
+/// The rainfall is added to the storage with a large negative cost so that it is always taken
+/// unless the reservoir is full. Any rainfall in excess of the space in a full reservoir is
+/// spilled, if `spill` is defined, or is otherwise lost. The evaporation is removed with a large
+/// negative cost (`evaporation_cost`) so that it is prioritised over other abstractions.
+/// If `spill` is defined an additional link is created from the storage; this is connected
+/// to other nodes using the `spill` slot of an edge.
+///
+/// ```svgbob
+///
+///    <node>.rainfall
+///        U
+///        *
+///        |
+///        v   <node>       <node>.spill
+///  -*--->S ------------->*---------->*-
+///        |
+///        v
+///        *
+///        D
+///   <node>.evaporation
+/// ```
+///
+)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct ReservoirNode {
+    pub meta: NodeMeta,
+    /// Optional local parameters.
+    pub parameters: Option<Vec<Parameter>>,
+    pub max_volume: Option<Metric>,
+    pub min_volume: Option<Metric>,
+    pub cost: Option<Metric>,
+    pub initial_volume: StorageInitialVolume,
+    pub bathymetry: Option<Bathymetry>,
+    /// The depth of rainfall per time-step.
+    pub rainfall: Option<Metric>,
+    /// The depth of evaporation per time-step.
+    pub evaporation: Option<Metric>,
+    /// The cost of evaporation. Defaults to `-999.0`.
+    pub evaporation_cost: Option<Metric>,
+    /// A factor to convert the product of area and depth in to the units of volume. Defaults to `1.0`.
+    pub unit_conversion: Option<f64>,
+    pub spill: Option<ReservoirSpill>,
+}
+
+impl ReservoirNode {
+    const DEFAULT_ATTRIBUTE: NodeAttribute = NodeAttribute::Volume;
+
+    fn spill_sub_name() -> Option<&'static str> {
+        Some("spill")
+    }
+
+    pub fn input_connectors(&self) -> Vec<(&str, Option<String>)> {
+        vec![(self.meta.name.as_str(), None)]
+    }
+
+    pub fn output_connectors(&self, slot: Option<&str>) -> Vec<(&str, Option<String>)> {
+        match slot {
+            Some("spill") if self.spill.is_some() => {
+                vec![(self.meta.name.as_str(), Self::spill_sub_name().map(|s| s.to_string()))]
+            }
+            Some(slot) => panic!("Invalid slot name `{slot}` for reservoir `{}`!", self.meta.name),
+            None => vec![(self.meta.name.as_str(), None)],
+        }
+    }
+
+    pub fn default_metric(&self) -> NodeAttribute {
+        Self::DEFAULT_ATTRIBUTE
+    }
+}
+
+#[cfg(feature = "core")]
+impl ReservoirNode {
+    const DEFAULT_EVAPORATION_COST: f64 = -999.0;
+    const RAINFALL_COST: f64 = -999.0;
+
+    fn rainfall_sub_name() -> Option<&'static str> {
+        Some("rainfall")
+    }
+
+    fn evaporation_sub_name() -> Option<&'static str> {
+        Some("evaporation")
+    }
+
+    /// Check the bathymetry is correctly defined.
+    fn validate(&self) -> Result<(), SchemaError> {
+        let invalid = |reason: &str| SchemaError::InvalidBathymetry {
+            name: self.meta.name.clone(),
+            reason: reason.to_string(),
+        };
+
+        match &self.bathymetry {
+            Some(bathymetry) => {
+                if bathymetry.volumes.len() < 2 {
+                    return Err(invalid("at least two volumes are required"));
+                }
+                if bathymetry.volumes.windows(2).any(|w| w[0] >= w[1]) {
+                    return Err(invalid("the volumes must be in ascending order"));
+                }
+                for values in [&bathymetry.areas, &bathymetry.levels].into_iter().flatten() {
+                    if values.len() != bathymetry.volumes.len() {
+                        return Err(invalid("the areas and levels must have the same length as the volumes"));
+                    }
+                }
+                if bathymetry.areas.is_none() && (self.rainfall.is_some() || self.evaporation.is_some()) {
+                    return Err(invalid("areas are required to calculate rainfall and evaporation"));
+                }
+            }
+            None => {
+                if self.rainfall.is_some() || self.evaporation.is_some() {
+                    return Err(invalid(
+                        "a bathymetry is required to calculate rainfall and evaporation",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn node_indices_for_constraints(
+        &self,
+        network: &pywr_core::network::Network,
+    ) -> Result<Vec<pywr_core::node::NodeIndex>, SchemaError> {
+        let idx = network.get_node_index_by_name(self.meta.name.as_str(), None)?;
+        Ok(vec![idx])
+    }
+
+    pub fn add_to_model(&self, network: &mut pywr_core::network::Network) -> Result<(), SchemaError> {
+        self.validate()?;
+
+        let idx = network.add_storage_node(self.meta.name.as_str(), None, self.initial_volume.into(), None, None)?;
+
+        if self.rainfall.is_some() {
+            let rainfall = network.add_input_node(self.meta.name.as_str(), Self::rainfall_sub_name())?;
+            network.connect_nodes(rainfall, idx)?;
+        }
+
+        if self.evaporation.is_some() {
+            let evaporation = network.add_output_node(self.meta.name.as_str(), Self::evaporation_sub_name())?;
+            network.connect_nodes(idx, evaporation)?;
+        }
+
+        if self.spill.is_some() {
+            let spill = network.add_link_node(self.meta.name.as_str(), Self::spill_sub_name())?;
+            network.connect_nodes(idx, spill)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_constraints(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<(), SchemaError> {
+        if let Some(cost) = &self.cost {
+            let value = cost.load(network, args, Some(&self.meta.name))?;
+            network.set_node_cost(self.meta.name.as_str(), None, value.into())?;
+        }
+
+        if let Some(min_volume) = &self.min_volume {
+            let value = min_volume.load(network, args, Some(&self.meta.name))?;
+            network.set_node_min_volume(self.meta.name.as_str(), None, Some(value.try_into()?))?;
+        }
+
+        if let Some(max_volume) = &self.max_volume {
+            let value = max_volume.load(network, args, Some(&self.meta.name))?;
+            network.set_node_max_volume(self.meta.name.as_str(), None, Some(value.try_into()?))?;
+        }
+
+        if let Some(rainfall) = &self.rainfall {
+            let depth = rainfall.load(network, args, Some(&self.meta.name))?;
+            let flow = self.volume_from_depth(network, "rainfall-flow", depth)?;
+
+            // The rainfall is not a minimum flow because it can not enter a full reservoir
+            // without a spill; instead the cost ensures it is taken whenever possible.
+            network.set_node_max_flow(self.meta.name.as_str(), Self::rainfall_sub_name(), Some(flow))?;
+            network.set_node_cost(
+                self.meta.name.as_str(),
+                Self::rainfall_sub_name(),
+                Some(Self::RAINFALL_COST.into()),
+            )?;
+        }
+
+        if let Some(evaporation) = &self.evaporation {
+            let depth = evaporation.load(network, args, Some(&self.meta.name))?;
+            let flow = self.volume_from_depth(network, "evaporation-flow", depth)?;
+            network.set_node_max_flow(self.meta.name.as_str(), Self::evaporation_sub_name(), Some(flow))?;
+
+            let cost = match &self.evaporation_cost {
+                Some(cost) => cost.load(network, args, Some(&self.meta.name))?,
+                None => Self::DEFAULT_EVAPORATION_COST.into(),
+            };
+            network.set_node_cost(self.meta.name.as_str(), Self::evaporation_sub_name(), cost.into())?;
+        }
+
+        if let Some(cost) = self.spill.as_ref().and_then(|s| s.cost.as_ref()) {
+            let value = cost.load(network, args, Some(&self.meta.name))?;
+            network.set_node_cost(self.meta.name.as_str(), Self::spill_sub_name(), value.into())?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a parameter for the volume of `depth` over the current surface area.
+    fn volume_from_depth(
+        &self,
+        network: &mut pywr_core::network::Network,
+        name: &str,
+        depth: MetricF64,
+    ) -> Result<MetricF64, SchemaError> {
+        let area: MetricF64 = self
+            .bathymetry_parameter(network, NodeAttribute::Area)?
+            .ok_or_else(|| SchemaError::InvalidBathymetry {
+                name: self.meta.name.clone(),
+                reason: "areas are required to calculate rainfall and evaporation".to_string(),
+            })?
+            .into();
+        let conversion: MetricF64 = self.unit_conversion.unwrap_or(1.0).into();
+
+        let parameter = AggregatedParameter::new(
+            ParameterName::new(name, Some(&self.meta.name)),
+            &[depth, area, conversion],
+            AggFunc::Product,
+        );
+        Ok(network.add_parameter(Box::new(parameter))?.into())
+    }
+
+    /// Return the parameter interpolating the area or level from the current volume.
+    ///
+    /// The parameter is created the first time it is requested. `None` is returned if the
+    /// bathymetry does not define the values of the attribute.
+    fn bathymetry_parameter(
+        &self,
+        network: &mut pywr_core::network::Network,
+        attribute: NodeAttribute,
+    ) -> Result<Option<ParameterIndex<f64>>, SchemaError> {
+        let Some(bathymetry) = &self.bathymetry else {
+            return Ok(None);
+        };
+
+        let (values, name) = match attribute {
+            NodeAttribute::Area => (&bathymetry.areas, "area"),
+            NodeAttribute::Level => (&bathymetry.levels, "level"),
+            _ => return Ok(None),
+        };
+        let Some(values) = values else {
+            return Ok(None);
+        };
+
+        let name = ParameterName::new(name, Some(&self.meta.name));
+        if let Ok(idx) = network.get_parameter_index_by_name(&name) {
+            return Ok(Some(idx));
+        }
+
+        let idx = network.get_node_index_by_name(self.meta.name.as_str(), None)?;
+        let points = bathymetry
+            .volumes
+            .iter()
+            .zip(values)
+            .map(|(&v, &y)| (v.into(), y.into()))
+            .collect();
+
+        let parameter = InterpolatedParameter::new(name, MetricF64::NodeVolume(idx), points, false);
+        Ok(Some(network.add_parameter(Box::new(parameter))?))
+    }
+
+    pub fn create_metric(
+        &self,
+        network: &mut pywr_core::network::Network,
+        attribute: Option<NodeAttribute>,
+    ) -> Result<MetricF64, SchemaError> {
+        // Use the default attribute if none is specified
+        let attr = attribute.unwrap_or(Self::DEFAULT_ATTRIBUTE);
+
+        let idx = network.get_node_index_by_name(self.meta.name.as_str(), None)?;
+
+        let not_supported = || SchemaError::NodeAttributeNotSupported {
+            ty: "ReservoirNode".to_string(),
+            name: self.meta.name.clone(),
+            attr,
+        };
+
+        let metric = match attr {
+            NodeAttribute::Volume => MetricF64::NodeVolume(idx),
+            NodeAttribute::ProportionalVolume => {
+                let dm = DerivedMetric::NodeProportionalVolume(idx);
+                let derived_metric_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(derived_metric_idx)
+            }
+            NodeAttribute::Area | NodeAttribute::Level => self
+                .bathymetry_parameter(network, attr)?
+                .ok_or_else(not_supported)?
+                .into(),
+            _ => return Err(not_supported()),
+        };
+
+        Ok(metric)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::ReservoirNode;
+
+    #[test]
+    fn test_reservoir_schema() {
+        let data = r#"
+            {
+                "meta": {
+                    "name": "reservoir"
+                },
+                "max_volume": {"type": "Constant", "value": 1000.0},
+                "initial_volume": {"Proportional": 1.0},
+                "bathymetry": {
+                    "volumes": [0.0, 500.0, 1000.0],
+                    "areas": [0.0, 80.0, 100.0],
+                    "levels": [10.0, 15.0, 18.0]
+                },
+                "rainfall": {"type": "Constant", "value": 0.005},
+                "evaporation": {"type": "Constant", "value": 0.01},
+                "spill": {"cost": {"type": "Constant", "value": 1.0}}
+            }
+            "#;
+
+        let node: ReservoirNode = serde_json::from_str(data).unwrap();
+        assert_eq!(node.bathymetry.unwrap().volumes.len(), 3);
+        assert!(node.spill.is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "core")]
+    fn test_reservoir_validation() {
+        use crate::nodes::reservoir::Bathymetry;
+
+        let mut node = ReservoirNode {
+            evaporation: Some(0.01.into()),
+            ..Default::default()
+        };
+        // Evaporation requires the surface area
+        assert!(node.validate().is_err());
+
+        node.bathymetry = Some(Bathymetry {
+            volumes: vec![0.0, 100.0],
+            areas: Some(vec![0.0]),
+            levels: None,
+        });
+        assert!(node.validate().is_err());
+
+        node.bathymetry.as_mut().unwrap().areas = Some(vec![0.0, 10.0]);
+        assert!(node.validate().is_ok());
+    }
+}
@@ -8,7 +8,7 @@ use crate::nodes::{NodeAttribute, NodeMeta};
 use crate::parameters::Parameter;
 use crate::v1::{try_convert_node_attr, ConversionData, TryFromV1};
 #[cfg(feature = "core")]
-use pywr_core::metric::MetricF64;
+use pywr_core::{derived_metric::DerivedMetric, metric::MetricF64};
 use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::nodes::RiverGaugeNode as RiverGaugeNodeV1;
 use schemars::JsonSchema;
@@ -16,6 +16,12 @@ use schemars::JsonSchema;
 #[doc = svgbobdoc::transform!(
 /// This is used to represent a minimum residual flow (MRF) at a gauging station.
 ///
+/// Flow up to the MRF is routed through the `mrf` link, and any remaining flow through the
+/// `bypass` link. The MRF is not a hard constraint; a negative `mrf_cost` should be used to
+/// encourage the MRF to be met. The shortfall against the MRF (i.e. the MRF minus the flow
+/// through the `mrf` link) is available as the node's `Deficit` attribute, which can be used
+/// in metric sets and recorders to assess how often and by how much the MRF is not met. The
+/// proportion of the MRF that is met is available as the `Utilisation` attribute.
 ///
 /// ```svgbob
 ///            <node>.mrf
@@ -112,7 +118,7 @@ impl RiverGaugeNode {
     }
     pub fn create_metric(
         &self,
-        network: &pywr_core::network::Network,
+        network: &mut pywr_core::network::Network,
         attribute: Option<NodeAttribute>,
     ) -> Result<MetricF64, SchemaError> {
         // Use the default attribute if none is specified
@@ -132,6 +138,18 @@ impl RiverGaugeNode {
                 indices,
                 name: self.meta.name.to_string(),
             },
+            NodeAttribute::Deficit if self.mrf.is_some() => {
+                // The shortfall is the flow through the MRF link below its maximum flow.
+                let dm = DerivedMetric::NodeInFlowDeficit(indices[0]);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
+            NodeAttribute::Utilisation if self.mrf.is_some() => {
+                // The proportion of the MRF that is met by the flow through the MRF link.
+                let dm = DerivedMetric::NodeUtilisation(indices[0]);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
             _ => {
                 return Err(SchemaError::NodeAttributeNotSupported {
                     ty: "RiverGaugeNode".to_string(),
@@ -8,6 +8,8 @@ use crate::nodes::{NodeAttribute, NodeMeta, StorageInitialVolume};
 use crate::parameters::Parameter;
 use crate::v1::{try_convert_initial_storage, try_convert_node_attr, ConversionData, TryFromV1};
 #[cfg(feature = "core")]
+use chrono::TimeDelta;
+#[cfg(feature = "core")]
 use pywr_core::{
     derived_metric::DerivedMetric,
     metric::MetricF64,
@@ -42,13 +44,20 @@ impl RollingWindow {
     pub fn as_timesteps(&self, time: &TimeDomain) -> Option<NonZeroUsize> {
         match self {
             Self::Days(days) => {
-                let ts_days = match time.step_duration().whole_days() {
-                    Some(d) => d as usize,
-                    // If the timestep duration is not a whole number of days then the rolling window cannot be specified in days.
-                    None => return None,
-                };
+                // If the timestep duration varies then the rolling window cannot be specified in days.
+                let ts_duration = time.step_duration()?;
 
-                let timesteps = days.get() / ts_days;
+                let timesteps = match ts_duration.whole_days() {
+                    Some(ts_days) => days.get() / ts_days as usize,
+                    // Sub-daily time-steps must divide the window exactly
+                    None => {
+                        let window = TimeDelta::days(days.get() as i64).num_milliseconds();
+                        if window % ts_duration.milliseconds() != 0 {
+                            return None;
+                        }
+                        (window / ts_duration.milliseconds()) as usize
+                    }
+                };
 
                 NonZeroUsize::new(timesteps)
             }
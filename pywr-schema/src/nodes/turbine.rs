@@ -1,6 +1,10 @@
-use crate::metric::Metric;
+#[cfg(feature = "core")]
+use crate::metric::NodeReference;
+use crate::metric::{Metric, SimpleNodeReference};
 #[cfg(feature = "core")]
 use crate::model::LoadArgs;
+#[cfg(feature = "core")]
+use crate::nodes::hydropower_cascade::downstream_turbine;
 use crate::nodes::{NodeAttribute, NodeMeta};
 use crate::parameters::Parameter;
 #[cfg(feature = "core")]
@@ -9,7 +13,7 @@ use crate::SchemaError;
 use pywr_core::{
     derived_metric::{DerivedMetric, TurbineData},
     metric::MetricF64,
-    parameters::{HydropowerTargetData, ParameterName},
+    parameters::{HydropowerTargetData, InterpolatedParameter, ParameterName},
 };
 use pywr_schema_macros::PywrVisitAll;
 use schemars::JsonSchema;
@@ -24,8 +28,29 @@ pub enum TargetType {
     Both,
 }
 
-/// This turbine node can be used to set a flow constraint based on a hydropower production target.
-/// The turbine elevation, minimum head and efficiency can also be configured.
+/// The relationship between the volume of a storage node and its water level.
+///
+/// The `volumes` must be in ascending order, and the level between the points is linearly
+/// interpolated. Volumes outside the table use the first or last point.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct LevelVolume {
+    /// The storage node whose volume determines the level.
+    pub storage_node: SimpleNodeReference,
+    pub volumes: Vec<f64>,
+    pub levels: Vec<f64>,
+}
+
+/// A node that converts the flow through it and the head into power.
+///
+/// The working head of the turbine is the difference between the elevation of the water
+/// entering the turbine and the higher of the `turbine_elevation` and `tailwater_elevation`.
+/// The water elevation is either given directly by `water_elevation` or interpolated from the
+/// volume of a storage node using `level_volume`. The power produced is available as the
+/// `Power` attribute of the node, which is calculated from the flow and head at the end of
+/// each time-step; no power is produced if the head is less than or equal to `min_head`.
+///
+/// This node can also be used to set a flow constraint based on a hydropower production target.
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll)]
 #[serde(deny_unknown_fields)]
 pub struct TurbineNode {
@@ -45,6 +70,13 @@ pub struct TurbineNode {
     /// and can be a constant, a value from a table, a parameter name or an inline parameter
     /// (see [`DynamicFloatValue`]).
     pub water_elevation: Option<Metric>,
+    /// The relationship between the volume of a storage node and the elevation of the water
+    /// entering the turbine. This is an alternative to `water_elevation`.
+    pub level_volume: Option<LevelVolume>,
+    /// The elevation of the water downstream of the turbine. If this is higher than the
+    /// `turbine_elevation` then it is used to calculate the working head instead. If omitted for
+    /// a turbine in a hydropower cascade, the water elevation of the next turbine is used.
+    pub tailwater_elevation: Option<Metric>,
     /// The elevation of the turbine. The difference between the `water_elevation` and this value
     /// gives the working head of the turbine. Default to `0.0`.
     pub turbine_elevation: f64,
@@ -72,6 +104,8 @@ impl Default for TurbineNode {
             target: None,
             target_type: TargetType::MaxFlow,
             water_elevation: None,
+            level_volume: None,
+            tailwater_elevation: None,
             turbine_elevation: 0.0,
             min_head: 0.0,
             efficiency: 1.0,
@@ -99,10 +133,6 @@ impl TurbineNode {
 
 #[cfg(feature = "core")]
 impl TurbineNode {
-    fn sub_name() -> Option<&'static str> {
-        Some("turbine")
-    }
-
     pub fn node_indices_for_constraints(
         &self,
         network: &pywr_core::network::Network,
@@ -129,14 +159,12 @@ impl TurbineNode {
             let name = ParameterName::new("power", Some(self.meta.name.as_str()));
             let target_value = target.load(network, args, Some(&self.meta.name))?;
 
-            let water_elevation = self
-                .water_elevation
-                .as_ref()
-                .map(|t| t.load(network, args, Some(&self.meta.name)))
-                .transpose()?;
+            let water_elevation = self.water_elevation(network, args)?;
+            let tailwater_elevation = self.tailwater_elevation(network, args)?;
             let turbine_data = HydropowerTargetData {
                 target: target_value,
                 water_elevation,
+                tailwater_elevation,
                 elevation: Some(self.turbine_elevation),
                 min_head: Some(self.min_head),
                 max_flow: None,
@@ -152,14 +180,14 @@ impl TurbineNode {
 
             match self.target_type {
                 TargetType::MaxFlow => {
-                    network.set_node_max_flow(self.meta.name.as_str(), Self::sub_name(), metric.clone().into())?;
+                    network.set_node_max_flow(self.meta.name.as_str(), None, metric.clone().into())?;
                 }
                 TargetType::MinFlow => {
-                    network.set_node_min_flow(self.meta.name.as_str(), Self::sub_name(), metric.clone().into())?;
+                    network.set_node_min_flow(self.meta.name.as_str(), None, metric.clone().into())?;
                 }
                 TargetType::Both => {
-                    network.set_node_max_flow(self.meta.name.as_str(), Self::sub_name(), metric.clone().into())?;
-                    network.set_node_min_flow(self.meta.name.as_str(), Self::sub_name(), metric.clone().into())?
+                    network.set_node_max_flow(self.meta.name.as_str(), None, metric.clone().into())?;
+                    network.set_node_min_flow(self.meta.name.as_str(), None, metric.clone().into())?
                 }
             }
         }
@@ -167,6 +195,96 @@ impl TurbineNode {
         Ok(())
     }
 
+    /// The elevation of the water entering the turbine, if it is defined.
+    ///
+    /// If the elevation is given by the `level_volume` relationship, the parameter that
+    /// interpolates the level is created the first time this is called.
+    pub(super) fn water_elevation(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<Option<MetricF64>, SchemaError> {
+        let level_volume = match (&self.water_elevation, &self.level_volume) {
+            (Some(_), Some(_)) => {
+                return Err(SchemaError::InvalidTurbine {
+                    name: self.meta.name.clone(),
+                    reason: "only one of `water_elevation` and `level_volume` can be given".to_string(),
+                })
+            }
+            (Some(water_elevation), None) => {
+                return Ok(Some(water_elevation.load(network, args, Some(&self.meta.name))?));
+            }
+            (None, Some(level_volume)) => level_volume,
+            (None, None) => return Ok(None),
+        };
+
+        let name = ParameterName::new("water_elevation", Some(&self.meta.name));
+        if let Ok(idx) = network.get_parameter_index_by_name(&name) {
+            return Ok(Some(idx.into()));
+        }
+
+        if level_volume.volumes.len() < 2 || level_volume.volumes.len() != level_volume.levels.len() {
+            return Err(SchemaError::InvalidTurbine {
+                name: self.meta.name.clone(),
+                reason: "`level_volume` requires the same number (at least two) of volumes and levels".to_string(),
+            });
+        }
+        if level_volume.volumes.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(SchemaError::InvalidTurbine {
+                name: self.meta.name.clone(),
+                reason: "the volumes of `level_volume` must be in ascending order".to_string(),
+            });
+        }
+
+        let volume = NodeReference::new(level_volume.storage_node.name.clone(), Some(NodeAttribute::Volume))
+            .load_f64(network, args)?;
+        let points = level_volume
+            .volumes
+            .iter()
+            .zip(&level_volume.levels)
+            .map(|(&v, &l)| (v.into(), l.into()))
+            .collect();
+
+        let parameter = InterpolatedParameter::new(name, volume, points, false);
+        Ok(Some(network.add_parameter(Box::new(parameter))?.into()))
+    }
+
+    /// The elevation of the water downstream of the turbine, if it is defined.
+    ///
+    /// If no `tailwater_elevation` is given and the turbine is part of a hydropower cascade,
+    /// the water elevation of the next turbine downstream in the cascade is used.
+    fn tailwater_elevation(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<Option<MetricF64>, SchemaError> {
+        match &self.tailwater_elevation {
+            Some(t) => Ok(Some(t.load(network, args, Some(&self.meta.name))?)),
+            None => match downstream_turbine(args.schema, &self.meta.name) {
+                Some(turbine) => turbine.water_elevation(network, args),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// The data used to calculate the power produced by the turbine.
+    pub(super) fn turbine_data(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<TurbineData, SchemaError> {
+        Ok(TurbineData {
+            elevation: self.turbine_elevation,
+            efficiency: self.efficiency,
+            water_elevation: self.water_elevation(network, args)?,
+            tailwater_elevation: self.tailwater_elevation(network, args)?,
+            min_head: self.min_head,
+            water_density: self.water_density,
+            flow_unit_conversion: self.flow_unit_conversion,
+            energy_unit_conversion: self.energy_unit_conversion,
+        })
+    }
+
     pub fn create_metric(
         &self,
         network: &mut pywr_core::network::Network,
@@ -182,20 +300,7 @@ impl TurbineNode {
             NodeAttribute::Outflow => MetricF64::NodeOutFlow(idx),
             NodeAttribute::Inflow => MetricF64::NodeInFlow(idx),
             NodeAttribute::Power => {
-                let water_elevation = self
-                    .water_elevation
-                    .as_ref()
-                    .map(|t| t.load(network, args, Some(&self.meta.name)))
-                    .transpose()?;
-
-                let turbine_data = TurbineData {
-                    elevation: self.turbine_elevation,
-                    efficiency: self.efficiency,
-                    water_elevation,
-                    water_density: self.water_density,
-                    flow_unit_conversion: self.flow_unit_conversion,
-                    energy_unit_conversion: self.energy_unit_conversion,
-                };
+                let turbine_data = self.turbine_data(network, args)?;
                 let dm = DerivedMetric::PowerFromNodeFlow(idx, turbine_data);
                 let dm_idx = network.add_derived_metric(dm);
                 MetricF64::DerivedMetric(dm_idx)
@@ -7,7 +7,7 @@ use crate::nodes::loss_link::LossFactor;
 use crate::nodes::{NodeAttribute, NodeMeta};
 use crate::parameters::Parameter;
 #[cfg(feature = "core")]
-use pywr_core::metric::MetricF64;
+use pywr_core::{derived_metric::DerivedMetric, metric::MetricF64};
 use pywr_schema_macros::PywrVisitAll;
 use schemars::JsonSchema;
 
@@ -231,6 +231,12 @@ impl WaterTreatmentWorks {
                     Err(_) => 0.0.into(),
                 }
             }
+            NodeAttribute::Utilisation => {
+                let idx = network.get_node_index_by_name(self.meta.name.as_str(), Self::net_sub_name())?;
+                let dm = DerivedMetric::NodeUtilisation(idx);
+                let dm_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(dm_idx)
+            }
             _ => {
                 return Err(SchemaError::NodeAttributeNotSupported {
                     ty: "WaterTreatmentWorksNode".to_string(),
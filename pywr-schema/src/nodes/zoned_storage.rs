@@ -0,0 +1,368 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+use crate::metric::Metric;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::nodes::{NodeAttribute, NodeMeta};
+use crate::parameters::Parameter;
+#[cfg(feature = "core")]
+use pywr_core::{
+    derived_metric::DerivedMetric,
+    metric::{MetricF64, MetricU64, SimpleMetricF64},
+    node::StorageInitialVolume,
+    parameters::{ControlCurveIndexParameter, ParameterIndex, ParameterName, VolumeBetweenControlCurvesParameter},
+};
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// The type of storage zone.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, JsonSchema, PywrVisitAll)]
+pub enum StorageZoneType {
+    /// Storage that can not be released (e.g. below the lowest outlet). This zone can be filled,
+    /// but once water enters this zone it is not available for use. It must be the lowest zone.
+    Dead,
+    /// Storage that is reserved for use during emergencies.
+    Emergency,
+    /// Storage that is used for normal operation.
+    Operational,
+    /// Storage that is reserved for flood attenuation. This zone is empty at the start of a simulation.
+    Flood,
+}
+
+/// A zone in a [`ZonedStorageNode`].
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct StorageZone {
+    pub zone: StorageZoneType,
+    /// The proportion of the node's maximum volume at the bottom of this zone. This must be given
+    /// for every zone except the lowest zone, which extends to empty.
+    pub lower: Option<Metric>,
+    /// The cost of storing water in this zone. Negative costs penalise releases from the zone,
+    /// and positive costs (e.g. for a flood zone) encourage releases from the zone.
+    pub cost: Option<Metric>,
+}
+
+#[doc = svgbobdoc::transform!(
+/// A storage node with named zones that have separate costs.
+///
+/// This node standardises the common pattern of dividing a reservoir into zones (e.g. flood,
+/// operational, emergency and dead storage) using control curves, and applying a different
+/// cost to each zone. Each zone is represented by a separate storage node in the allocation
+/// problem with its own cost. The zones are given from the top of the reservoir to the bottom,
+/// and the `lower` boundary of each zone is given as a proportion of the node's maximum volume.
+///
+/// The zones are connected with bi-directional transfers, except for dead storage which can only
+/// be filled from the zone above. Each zone starts full, except for flood zones which start empty.
+///
+/// The index of the zone (in the order given) that contains the current volume is available
+/// using the `ZoneIndex` attribute. This can be used with index based parameters in place of a
+/// separate control curve index parameter.
+///
+/// ```svgbob
+///
+///            <node>.zone-00       D
+///     -*---------->S ----------->*-
+///      U           ^
+///                  |
+///                  v
+///    <node>.zone-01 S
+///                  ^
+///                  :
+///                  v
+///    <node>.zone-n  S
+/// ```
+///
+)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct ZonedStorageNode {
+    pub meta: NodeMeta,
+    /// Optional local parameters.
+    pub parameters: Option<Vec<Parameter>>,
+    pub max_volume: Metric,
+    /// The zones from the top of the storage to the bottom.
+    pub zones: Vec<StorageZone>,
+}
+
+impl ZonedStorageNode {
+    const DEFAULT_ATTRIBUTE: NodeAttribute = NodeAttribute::Volume;
+
+    fn zone_sub_name(i: usize) -> Option<String> {
+        Some(format!("zone-{i:02}"))
+    }
+
+    pub fn input_connectors(&self) -> Vec<(&str, Option<String>)> {
+        vec![(self.meta.name.as_str(), Self::zone_sub_name(0))]
+    }
+    pub fn output_connectors(&self) -> Vec<(&str, Option<String>)> {
+        vec![(self.meta.name.as_str(), Self::zone_sub_name(0))]
+    }
+
+    pub fn default_metric(&self) -> NodeAttribute {
+        Self::DEFAULT_ATTRIBUTE
+    }
+}
+
+#[cfg(feature = "core")]
+impl ZonedStorageNode {
+    fn agg_sub_name() -> Option<&'static str> {
+        Some("agg-store")
+    }
+
+    fn zone_index_name(&self) -> ParameterName {
+        ParameterName::new("zone-index", Some(&self.meta.name))
+    }
+
+    /// Check the zones are correctly defined.
+    fn validate(&self) -> Result<(), SchemaError> {
+        let invalid = |reason: &str| SchemaError::InvalidStorageZones {
+            name: self.meta.name.clone(),
+            reason: reason.to_string(),
+        };
+
+        let last = self
+            .zones
+            .len()
+            .checked_sub(1)
+            .ok_or_else(|| invalid("no zones defined"))?;
+
+        for (i, zone) in self.zones.iter().enumerate() {
+            if i < last && zone.lower.is_none() {
+                return Err(invalid("only the lowest zone may omit its `lower` boundary"));
+            }
+            if i == last && zone.lower.is_some() {
+                return Err(invalid("the lowest zone must not define a `lower` boundary"));
+            }
+            if i < last && zone.zone == StorageZoneType::Dead {
+                return Err(invalid("dead storage must be the lowest zone"));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn node_indices_for_constraints(
+        &self,
+        network: &pywr_core::network::Network,
+    ) -> Result<Vec<pywr_core::node::NodeIndex>, SchemaError> {
+        let indices = self
+            .zones
+            .iter()
+            .enumerate()
+            .map(|(i, _)| network.get_node_index_by_name(self.meta.name.as_str(), Self::zone_sub_name(i).as_deref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(indices)
+    }
+
+    pub fn add_to_model(&self, network: &mut pywr_core::network::Network) -> Result<(), SchemaError> {
+        self.validate()?;
+
+        let mut zone_node_indices = Vec::with_capacity(self.zones.len());
+
+        // Create a storage node for each zone; from the top to the bottom.
+        for (i, zone) in self.zones.iter().enumerate() {
+            let initial_volume = match zone.zone {
+                StorageZoneType::Flood => StorageInitialVolume::Proportional(0.0),
+                _ => StorageInitialVolume::Proportional(1.0),
+            };
+
+            let idx = network.add_storage_node(
+                self.meta.name.as_str(),
+                Self::zone_sub_name(i).as_deref(),
+                initial_volume,
+                None,
+                None,
+            )?;
+
+            if let Some(above_idx) = zone_node_indices.last() {
+                // Dead storage can be filled, but not released to the zone above.
+                network.connect_nodes(*above_idx, idx)?;
+                if zone.zone != StorageZoneType::Dead {
+                    network.connect_nodes(idx, *above_idx)?;
+                }
+            }
+
+            zone_node_indices.push(idx);
+        }
+
+        // Finally, add an aggregate storage node covering all the zones
+        network.add_aggregated_storage_node(self.meta.name.as_str(), Self::agg_sub_name(), zone_node_indices)?;
+
+        Ok(())
+    }
+
+    pub fn set_constraints(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<(), SchemaError> {
+        let total_volume: SimpleMetricF64 = self.max_volume.load(network, args, Some(&self.meta.name))?.try_into()?;
+
+        let mut upper: Option<SimpleMetricF64> = None;
+
+        for (i, zone) in self.zones.iter().enumerate() {
+            let sub_name = Self::zone_sub_name(i);
+
+            // The volume of this zone is the proportion between the lower boundary of the zone
+            // above (or full if the top zone) and this zone's lower boundary (or empty if the lowest).
+            let lower: Option<SimpleMetricF64> = match &zone.lower {
+                Some(lower) => Some(lower.load(network, args, Some(&self.meta.name))?.try_into()?),
+                None => None,
+            };
+
+            let max_volume_parameter = VolumeBetweenControlCurvesParameter::new(
+                ParameterName::new(
+                    format!("{}-max-volume", sub_name.as_deref().unwrap()).as_str(),
+                    Some(&self.meta.name),
+                ),
+                total_volume.clone(),
+                upper.clone(),
+                lower.clone(),
+            );
+            let max_volume_parameter_idx = network.add_simple_parameter(Box::new(max_volume_parameter))?;
+            let max_volume = Some(max_volume_parameter_idx.try_into()?);
+            network.set_node_max_volume(self.meta.name.as_str(), sub_name.as_deref(), max_volume)?;
+
+            if let Some(cost) = &zone.cost {
+                let value = cost.load(network, args, Some(&self.meta.name))?;
+                network.set_node_cost(self.meta.name.as_str(), sub_name.as_deref(), value.into())?;
+            }
+
+            upper = lower;
+        }
+
+        Ok(())
+    }
+
+    /// Return the index parameter for the zone occupied by the current volume.
+    ///
+    /// The parameter is created the first time it is requested.
+    fn zone_index_parameter(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<u64>, SchemaError> {
+        let name = self.zone_index_name();
+
+        if let Ok(idx) = network.get_index_parameter_index_by_name(&name) {
+            return Ok(idx);
+        }
+
+        let agg_idx =
+            network.get_aggregated_storage_node_index_by_name(self.meta.name.as_str(), Self::agg_sub_name())?;
+        let dm = DerivedMetric::AggregatedNodeProportionalVolume(agg_idx);
+        let proportional_volume = MetricF64::DerivedMetric(network.add_derived_metric(dm));
+
+        let control_curves = self
+            .zones
+            .iter()
+            .filter_map(|z| z.lower.as_ref())
+            .map(|lower| lower.load(network, args, Some(&self.meta.name)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let parameter = ControlCurveIndexParameter::new(name, proportional_volume, control_curves);
+        Ok(network.add_index_parameter(Box::new(parameter))?)
+    }
+
+    pub fn create_metric(
+        &self,
+        network: &mut pywr_core::network::Network,
+        attribute: Option<NodeAttribute>,
+        args: &LoadArgs,
+    ) -> Result<MetricF64, SchemaError> {
+        // Use the default attribute if none is specified
+        let attr = attribute.unwrap_or(Self::DEFAULT_ATTRIBUTE);
+
+        let idx = network.get_aggregated_storage_node_index_by_name(self.meta.name.as_str(), Self::agg_sub_name())?;
+
+        let metric = match attr {
+            NodeAttribute::Volume => MetricF64::AggregatedNodeVolume(idx),
+            NodeAttribute::ProportionalVolume => {
+                let dm = DerivedMetric::AggregatedNodeProportionalVolume(idx);
+                let derived_metric_idx = network.add_derived_metric(dm);
+                MetricF64::DerivedMetric(derived_metric_idx)
+            }
+            NodeAttribute::ZoneIndex => self.zone_index_parameter(network, args)?.into(),
+            _ => {
+                return Err(SchemaError::NodeAttributeNotSupported {
+                    ty: "ZonedStorageNode".to_string(),
+                    name: self.meta.name.clone(),
+                    attr,
+                })
+            }
+        };
+
+        Ok(metric)
+    }
+
+    pub fn create_index_metric(
+        &self,
+        network: &mut pywr_core::network::Network,
+        attribute: Option<NodeAttribute>,
+        args: &LoadArgs,
+    ) -> Result<MetricU64, SchemaError> {
+        match attribute {
+            Some(NodeAttribute::ZoneIndex) | None => Ok(self.zone_index_parameter(network, args)?.into()),
+            Some(attr) => Err(SchemaError::NodeAttributeNotSupported {
+                ty: "ZonedStorageNode".to_string(),
+                name: self.meta.name.clone(),
+                attr,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::ZonedStorageNode;
+
+    #[test]
+    fn test_zoned_storage_schema() {
+        let data = r#"
+            {
+                "meta": {
+                    "name": "reservoir"
+                },
+                "max_volume": {"type": "Constant", "value": 100.0},
+                "zones": [
+                    {"zone": "Flood", "lower": {"type": "Constant", "value": 0.9}, "cost": {"type": "Constant", "value": 10.0}},
+                    {"zone": "Operational", "lower": {"type": "Constant", "value": 0.3}, "cost": {"type": "Constant", "value": -10.0}},
+                    {"zone": "Emergency", "lower": {"type": "Constant", "value": 0.1}, "cost": {"type": "Constant", "value": -100.0}},
+                    {"zone": "Dead"}
+                ]
+            }
+            "#;
+
+        let node: ZonedStorageNode = serde_json::from_str(data).unwrap();
+        assert_eq!(node.zones.len(), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "core")]
+    fn test_zoned_storage_validation() {
+        use crate::nodes::{StorageZone, StorageZoneType};
+
+        let mut node = ZonedStorageNode {
+            zones: vec![
+                StorageZone {
+                    zone: StorageZoneType::Dead,
+                    lower: Some(0.1.into()),
+                    cost: None,
+                },
+                StorageZone {
+                    zone: StorageZoneType::Operational,
+                    lower: None,
+                    cost: None,
+                },
+            ],
+            ..Default::default()
+        };
+        // Dead storage must be the lowest zone
+        assert!(node.validate().is_err());
+
+        node.zones.reverse();
+        node.zones[0].lower = Some(0.1.into());
+        node.zones[1].lower = None;
+        assert!(node.validate().is_ok());
+    }
+}
@@ -0,0 +1,173 @@
+//! Definition of the multi-objective optimisation of a model.
+//!
+//! The variables of the optimisation are the model's parameters that have active variable
+//! settings (e.g. a [`crate::parameters::ConstantParameter`] with `variable.is_active`) and all
+//! [`crate::parameters::CapacityParameter`]s. The objectives are the aggregated values of the
+//! model's [`crate::outputs::MemoryOutput`]s. The model is optimised with the NSGA-II algorithm.
+//!
+//! ```json
+//! {
+//!   "optimisation": {
+//!     "objectives": [
+//!       {"output": "total-deficit", "sense": "Minimise"},
+//!       {"output": "total-capacity-cost", "sense": "Minimise"}
+//!     ],
+//!     "population_size": 40,
+//!     "generations": 50
+//!   }
+//! }
+//! ```
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+#[cfg(feature = "core")]
+use crate::model::PywrNetwork;
+#[cfg(feature = "core")]
+use crate::parameters::Parameter;
+#[cfg(feature = "core")]
+use pywr_core::optimise::{Nsga2Settings, Objective, Problem};
+#[cfg(feature = "core")]
+use pywr_core::parameters::ParameterName;
+use schemars::JsonSchema;
+
+/// Whether an objective is minimised or maximised.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Copy, Clone, JsonSchema, strum_macros::Display)]
+pub enum ObjectiveSense {
+    Minimise,
+    Maximise,
+}
+
+#[cfg(feature = "core")]
+impl From<ObjectiveSense> for pywr_core::optimise::ObjectiveSense {
+    fn from(value: ObjectiveSense) -> Self {
+        match value {
+            ObjectiveSense::Minimise => pywr_core::optimise::ObjectiveSense::Minimise,
+            ObjectiveSense::Maximise => pywr_core::optimise::ObjectiveSense::Maximise,
+        }
+    }
+}
+
+/// An objective of the optimisation.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OptimisationObjective {
+    /// The name of the memory output whose aggregated value is the objective.
+    pub output: String,
+    pub sense: ObjectiveSense,
+}
+
+/// The multi-objective optimisation of a model using the NSGA-II algorithm.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Optimisation {
+    pub objectives: Vec<OptimisationObjective>,
+    /// The number of solutions in each generation (defaults to 40).
+    pub population_size: Option<usize>,
+    /// The number of generations to evolve (defaults to 25).
+    pub generations: Option<usize>,
+    /// The seed of the random number generator (defaults to 0).
+    pub seed: Option<u64>,
+}
+
+#[cfg(feature = "core")]
+impl Optimisation {
+    /// Create the optimisation [`Problem`] of a model built from `network`.
+    pub fn build_problem<'a>(
+        &self,
+        network: &PywrNetwork,
+        model: &'a pywr_core::models::Model,
+    ) -> Result<Problem<'a>, SchemaError> {
+        let mut problem = Problem::new(model);
+
+        let mut parameters: Vec<(Option<&str>, &Parameter)> = Vec::new();
+        for node in &network.nodes {
+            if let Some(local_parameters) = node.local_parameters() {
+                parameters.extend(local_parameters.iter().map(|p| (Some(node.name()), p)));
+            }
+        }
+        if let Some(global_parameters) = network.parameters.as_deref() {
+            parameters.extend(global_parameters.iter().map(|p| (None, p)));
+        }
+
+        for (parent, parameter) in parameters {
+            let config: Box<dyn pywr_core::parameters::VariableConfig> = match parameter {
+                Parameter::Constant(p) => match &p.variable {
+                    Some(variable) if variable.is_active => {
+                        Box::new(pywr_core::parameters::ActivationFunction::from(variable.activation))
+                    }
+                    _ => continue,
+                },
+                Parameter::Capacity(_) => Box::new(()),
+                _ => continue,
+            };
+
+            let name = ParameterName::new(parameter.name(), parent);
+            let idx = model.network().get_parameter_index_by_name(&name)?;
+            problem.add_variable(idx, config)?;
+        }
+
+        for objective in &self.objectives {
+            problem.add_objective(Objective::new(&objective.output, objective.sense.into()))?;
+        }
+
+        Ok(problem)
+    }
+
+    pub fn nsga2_settings(&self) -> Nsga2Settings {
+        let mut settings = Nsga2Settings::default();
+        if let Some(population_size) = self.population_size {
+            settings = settings.with_population_size(population_size);
+        }
+        if let Some(generations) = self.generations {
+            settings = settings.with_generations(generations);
+        }
+        if let Some(seed) = self.seed {
+            settings = settings.with_seed(seed);
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PywrModel;
+    #[cfg(feature = "core")]
+    use float_cmp::assert_approx_eq;
+    #[cfg(feature = "core")]
+    use pywr_core::solvers::{ClpSolver, ClpSolverSettings};
+    use std::fs::read_to_string;
+    use std::str::FromStr;
+
+    fn optimisation1_str() -> String {
+        read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/optimisation1.json"))
+            .expect("Failed to read optimisation1.json")
+    }
+
+    #[test]
+    fn test_schema() {
+        let data = optimisation1_str();
+        let schema = PywrModel::from_str(&data).unwrap();
+
+        assert!(schema.optimisation.is_some_and(|o| o.objectives.len() == 2));
+    }
+
+    #[test]
+    #[cfg(feature = "core")]
+    fn test_optimise() {
+        let data = optimisation1_str();
+        let schema = PywrModel::from_str(&data).unwrap();
+
+        let solutions = schema
+            .optimise::<ClpSolver>(None, &ClpSolverSettings::default())
+            .unwrap();
+        assert!(!solutions.is_empty());
+
+        for solution in solutions {
+            // The only variable is the capacity of the supply, which is the second objective
+            let capacity = solution.variables[0];
+            assert_approx_eq!(f64, solution.objectives[1], capacity, epsilon = 1e-6);
+            // The deficit of the demand over the five days
+            let deficit = 5.0 * (10.0 - capacity.min(10.0));
+            assert_approx_eq!(f64, solution.objectives[0], deficit, epsilon = 1e-6);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 #[cfg(feature = "core")]
 use crate::error::SchemaError;
+use crate::metric_sets::MetricAggregator;
+use crate::outputs::{OutputMetricFilter, OutputMetricSets, OutputTimeFilter};
 #[cfg(feature = "core")]
-use pywr_core::recorders::{CsvLongFmtOutput, CsvWideFmtOutput, Recorder};
+use pywr_core::recorders::{CsvLongFmtOutput, CsvWideFmtOutput, MetricFilter, Recorder};
 use pywr_schema_macros::PywrVisitPaths;
 use schemars::JsonSchema;
 #[cfg(feature = "core")]
@@ -20,11 +22,21 @@ pub enum CsvFormat {
     Long,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths, strum_macros::Display)]
-#[serde(untagged)]
-pub enum CsvMetricSet {
-    Single(String),
-    Multiple(Vec<String>),
+/// Compression of a CSV output file.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitPaths)]
+#[serde(tag = "type")]
+pub enum CsvCompression {
+    /// Compress the file with zstd, optionally at the given level (1-22).
+    Zstd { level: Option<i32> },
+}
+
+#[cfg(feature = "core")]
+impl From<CsvCompression> for pywr_core::recorders::CsvCompression {
+    fn from(value: CsvCompression) -> Self {
+        match value {
+            CsvCompression::Zstd { level } => Self::Zstd(level.unwrap_or_default()),
+        }
+    }
 }
 
 /// Output data to a CSV file.
@@ -35,16 +47,37 @@ pub enum CsvMetricSet {
 /// numbers of metrics or scenarios, while the long format is useful for large numbers of metrics
 /// or scenarios. For more details see the [`CsvLongFmtOutput`] and [`CsvWideFmtOutput`] types.
 ///
-/// The long format supports either a single metric set or a list of metric sets. However,
-/// the wide format only supports a single metric set.
+/// The long format supports either a single metric set or a list of metric sets. Each row
+/// includes the name of the metric set that produced the value. However, the wide format only
+/// supports a single metric set. In both formats an optional `filter` can be used to include
+/// or exclude metrics by name.
+///
+/// The long format also supports an optional `aggregation` that aggregates the values of each
+/// metric over time (e.g. daily values to monthly or annual values) before they are written.
+/// This allows the same metric sets to be written at different temporal resolutions.
+///
+/// Either format can optionally be compressed with the given `compression`, which is
+/// recommended for large outputs. The file is otherwise written as plain text.
+///
+/// An optional `time_filter` limits the rows that are written to those of the periods within
+/// the given dates (see [`OutputTimeFilter`]).
 ///
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths)]
 pub struct CsvOutput {
     pub name: String,
     pub filename: PathBuf,
     pub format: CsvFormat,
-    pub metric_set: CsvMetricSet,
+    pub metric_set: OutputMetricSets,
     pub decimal_places: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<OutputMetricFilter>,
+    /// Optional temporal aggregation of the values; only supported by the long format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregation: Option<MetricAggregator>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CsvCompression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_filter: Option<OutputTimeFilter>,
 }
 
 #[cfg(feature = "core")]
@@ -59,33 +92,53 @@ impl CsvOutput {
             _ => self.filename.to_path_buf(),
         };
 
+        let filter = self.filter.as_ref().map(MetricFilter::from).unwrap_or_default();
+        let compression = self.compression.map(Into::into).unwrap_or_default();
+        let time_filter = self
+            .time_filter
+            .as_ref()
+            .map(|f| f.load(&self.name))
+            .transpose()?
+            .unwrap_or_default();
+
         let recorder: Box<dyn Recorder> = match self.format {
             CsvFormat::Wide => match &self.metric_set {
-                CsvMetricSet::Single(metric_set) => {
+                OutputMetricSets::Single(_) if self.aggregation.is_some() => {
+                    return Err(SchemaError::UnsupportedOutputAggregation(self.name.clone()))
+                }
+                OutputMetricSets::Single(metric_set) => {
                     let metric_set_idx = network.get_metric_set_index_by_name(metric_set)?;
-                    Box::new(CsvWideFmtOutput::new(&self.name, filename, metric_set_idx))
+                    Box::new(
+                        CsvWideFmtOutput::new(&self.name, filename, metric_set_idx)
+                            .with_metric_filter(filter)
+                            .with_time_filter(time_filter)
+                            .with_compression(compression),
+                    )
                 }
-                CsvMetricSet::Multiple(_) => {
+                OutputMetricSets::Multiple(_) => {
                     return Err(SchemaError::MissingMetricSet(
                         "Wide format CSV output requires a single `metric_set`".to_string(),
                     ))
                 }
             },
             CsvFormat::Long => {
-                let metric_set_indices = match &self.metric_set {
-                    CsvMetricSet::Single(metric_set) => vec![network.get_metric_set_index_by_name(metric_set)?],
-                    CsvMetricSet::Multiple(metric_sets) => metric_sets
-                        .iter()
-                        .map(|ms| network.get_metric_set_index_by_name(ms))
-                        .collect::<Result<Vec<_>, _>>()?,
-                };
+                let metric_set_indices = self.metric_set.load(network)?;
 
-                Box::new(CsvLongFmtOutput::new(
+                let mut recorder = CsvLongFmtOutput::new(
                     &self.name,
                     filename,
                     &metric_set_indices,
                     self.decimal_places.and_then(NonZeroU32::new),
-                ))
+                )
+                .with_metric_filter(filter)
+                .with_time_filter(time_filter)
+                .with_compression(compression);
+
+                if let Some(aggregation) = &self.aggregation {
+                    recorder = recorder.with_aggregator(aggregation.clone().try_into()?);
+                }
+
+                Box::new(recorder)
             }
         };
 
@@ -0,0 +1,88 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+use crate::metric::Metric;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::parameters::Predicate;
+#[cfg(feature = "core")]
+use pywr_core::recorders::{EventFileFormat, EventRecorder};
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+#[cfg(feature = "core")]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default, JsonSchema, PywrVisitAll, strum_macros::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum EventOutputFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+#[cfg(feature = "core")]
+impl From<EventOutputFormat> for EventFileFormat {
+    fn from(value: EventOutputFormat) -> Self {
+        match value {
+            EventOutputFormat::Csv => EventFileFormat::Csv,
+            EventOutputFormat::Parquet => EventFileFormat::Parquet,
+        }
+    }
+}
+
+/// Output the events in which a metric crosses a threshold.
+///
+/// An event starts in the first time-step in which the `metric` compared against the `threshold`
+/// using the `predicate` is true, and ends when the comparison is next false. A table with the
+/// scenario, start, end, duration (in time-steps) and peak magnitude (the largest absolute
+/// difference between the metric and the threshold) of each event is written to `filename`.
+///
+/// For example, the events in which a reservoir is below its emergency storage can be used to
+/// calculate drought performance metrics such as reliability, resilience and vulnerability. For
+/// more details see [`EventRecorder`].
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct EventOutput {
+    pub name: String,
+    /// The metric to compare against the threshold.
+    pub metric: Metric,
+    /// The threshold to compare against.
+    pub threshold: Metric,
+    /// The comparison predicate. Should be one of `LT`, `GT`, `EQ`, `LE`, or `GE` or their equivalents `<`, `>`, `==`,
+    /// `<=` or `>=`.
+    pub predicate: Predicate,
+    /// The file to write the events to. If not given the events are only available in memory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<PathBuf>,
+    #[serde(default)]
+    pub format: EventOutputFormat,
+}
+
+#[cfg(feature = "core")]
+impl EventOutput {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+        output_path: Option<&Path>,
+    ) -> Result<(), SchemaError> {
+        let metric = self.metric.load(network, args, None)?;
+        let threshold = self.threshold.load(network, args, None)?;
+
+        let mut recorder = EventRecorder::new(&self.name, metric, threshold, self.predicate.into());
+
+        if let Some(filename) = &self.filename {
+            let filename = match (output_path, filename.is_relative()) {
+                (Some(odir), true) => odir.join(filename),
+                _ => filename.to_path_buf(),
+            };
+            recorder = recorder.with_file(filename, self.format.into());
+        }
+
+        network.add_recorder(Box::new(recorder))?;
+
+        Ok(())
+    }
+}
@@ -1,19 +1,145 @@
 #[cfg(feature = "core")]
 use crate::error::SchemaError;
+use crate::outputs::{OutputMetricFilter, OutputMetricSets, OutputTimeFilter};
 #[cfg(feature = "core")]
-use pywr_core::recorders::HDF5Recorder;
+use pywr_core::recorders::{Baseline, DifferenceKind, HDF5Recorder, Hdf5Compression, Hdf5Precision, MetricFilter};
 use pywr_schema_macros::PywrVisitPaths;
 use schemars::JsonSchema;
 #[cfg(feature = "core")]
 use std::path::Path;
 use std::path::PathBuf;
 
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default, JsonSchema, PywrVisitPaths, strum_macros::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputDifference {
+    /// The value minus the baseline value.
+    #[default]
+    Absolute,
+    /// The difference as a percentage of the baseline value.
+    Percentage,
+}
+
+#[cfg(feature = "core")]
+impl From<OutputDifference> for DifferenceKind {
+    fn from(value: OutputDifference) -> Self {
+        match value {
+            OutputDifference::Absolute => DifferenceKind::Absolute,
+            OutputDifference::Percentage => DifferenceKind::Percentage,
+        }
+    }
+}
+
+/// The results of a previous run to compare against.
+///
+/// The `filename` must be an HDF5 file written by a run with the same metric sets, time-steps
+/// and scenarios. Relative paths are resolved in the same way as the output's `filename`.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths)]
+pub struct OutputBaseline {
+    pub filename: PathBuf,
+    #[serde(default)]
+    pub difference: OutputDifference,
+}
+
+/// Compression of the datasets of an HDF5 output.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitPaths)]
+#[serde(tag = "type")]
+pub enum Hdf5OutputCompression {
+    /// Compress the datasets with gzip, optionally at the given level (0-9; defaults to 4).
+    Gzip { level: Option<u8> },
+    /// Compress the datasets with LZF, which is faster than gzip but compresses less.
+    Lzf,
+}
+
+#[cfg(feature = "core")]
+impl Hdf5OutputCompression {
+    const DEFAULT_GZIP_LEVEL: u8 = 4;
+
+    fn load(&self, name: &str) -> Result<Hdf5Compression, SchemaError> {
+        match self {
+            Self::Gzip { level } => {
+                let level = level.unwrap_or(Self::DEFAULT_GZIP_LEVEL);
+                if level > 9 {
+                    return Err(SchemaError::InvalidHdf5Output {
+                        name: name.to_string(),
+                        reason: format!("the gzip level must be between 0 and 9; found {level}"),
+                    });
+                }
+                Ok(Hdf5Compression::Gzip(level))
+            }
+            Self::Lzf => Ok(Hdf5Compression::Lzf),
+        }
+    }
+}
+
+/// The shape of the chunks of the datasets of an HDF5 output.
+///
+/// Each dataset has a row for each time-step and a column for each scenario. The chunks are
+/// limited to the size of the datasets.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitPaths)]
+#[serde(deny_unknown_fields)]
+pub struct Hdf5ChunkShape {
+    pub time_steps: usize,
+    pub scenarios: usize,
+}
+
+/// The precision of the values of the datasets of an HDF5 output.
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default, JsonSchema, PywrVisitPaths, strum_macros::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Hdf5OutputPrecision {
+    /// Store the values as 64-bit floats.
+    #[default]
+    Double,
+    /// Store the values as 32-bit floats, which halves the size of the file.
+    Single,
+}
+
+#[cfg(feature = "core")]
+impl From<Hdf5OutputPrecision> for Hdf5Precision {
+    fn from(value: Hdf5OutputPrecision) -> Self {
+        match value {
+            Hdf5OutputPrecision::Double => Hdf5Precision::Double,
+            Hdf5OutputPrecision::Single => Hdf5Precision::Single,
+        }
+    }
+}
+
+/// Output data to an HDF5 file.
+///
+/// If more than one metric set is given the metrics of each set are saved in a group with the
+/// name of the metric set. An optional `filter` can be used to include or exclude metrics by name.
+///
+/// If a `baseline` is given the differences (absolute or percentage) between each value and the
+/// corresponding value of the baseline are saved instead of the values themselves. This allows
+/// the outputs of, for example, option appraisal runs to be used directly.
+///
+/// An optional `time_filter` limits the saved time-steps to those within the given dates (see
+/// [`OutputTimeFilter`]). A `baseline` must then have been saved with the same filter.
+///
+/// The datasets are uncompressed and store 64-bit values by default. For large ensembles the
+/// size of the file can be reduced with a `compression`, a `chunk_shape` that matches how the
+/// file will be read (e.g. all the time-steps of a few scenarios), and a `single` `precision`.
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths)]
 pub struct Hdf5Output {
     pub name: String,
     pub filename: PathBuf,
-    /// The metric set to save
-    pub metric_set: String,
+    /// The metric set(s) to save
+    pub metric_set: OutputMetricSets,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<OutputMetricFilter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<OutputBaseline>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_filter: Option<OutputTimeFilter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Hdf5OutputCompression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_shape: Option<Hdf5ChunkShape>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<Hdf5OutputPrecision>,
 }
 
 #[cfg(feature = "core")]
@@ -23,14 +149,43 @@ impl Hdf5Output {
         network: &mut pywr_core::network::Network,
         output_path: Option<&Path>,
     ) -> Result<(), SchemaError> {
-        let filename = match (output_path, self.filename.is_relative()) {
-            (Some(odir), true) => odir.join(&self.filename),
-            _ => self.filename.to_path_buf(),
+        let resolve = |filename: &PathBuf| match (output_path, filename.is_relative()) {
+            (Some(odir), true) => odir.join(filename),
+            _ => filename.to_path_buf(),
         };
 
-        let metric_set_idx = network.get_metric_set_index_by_name(&self.metric_set)?;
+        let filename = resolve(&self.filename);
+
+        let metric_set_indices = self.metric_set.load(network)?;
+        let filter = self.filter.as_ref().map(MetricFilter::from).unwrap_or_default();
+
+        let mut recorder = HDF5Recorder::new(&self.name, filename, &metric_set_indices).with_metric_filter(filter);
+
+        if let Some(time_filter) = &self.time_filter {
+            recorder = recorder.with_time_filter(time_filter.load(&self.name)?);
+        }
+
+        if let Some(baseline) = &self.baseline {
+            recorder = recorder.with_baseline(Baseline::new(resolve(&baseline.filename), baseline.difference.into()));
+        }
+
+        if let Some(compression) = &self.compression {
+            recorder = recorder.with_compression(compression.load(&self.name)?);
+        }
 
-        let recorder = HDF5Recorder::new(&self.name, filename, metric_set_idx);
+        if let Some(chunk_shape) = &self.chunk_shape {
+            if chunk_shape.time_steps == 0 || chunk_shape.scenarios == 0 {
+                return Err(SchemaError::InvalidHdf5Output {
+                    name: self.name.clone(),
+                    reason: "the chunk shape must not have a zero size".to_string(),
+                });
+            }
+            recorder = recorder.with_chunk_shape(chunk_shape.time_steps, chunk_shape.scenarios);
+        }
+
+        if let Some(precision) = self.precision {
+            recorder = recorder.with_precision(precision.into());
+        }
 
         network.add_recorder(Box::new(recorder))?;
 
@@ -89,4 +244,60 @@ mod tests {
         let expected_path = temp_dir.path().join("outputs.h5");
         assert!(expected_path.exists());
     }
+
+    #[test]
+    #[cfg(feature = "core")]
+    fn test_run_with_baseline() {
+        let data = model_str();
+        let schema = PywrModel::from_str(&data).unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+
+        // Run once to create the baseline
+        let model = schema.build_model(None, Some(temp_dir.path())).unwrap();
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        // Run again with a larger demand comparing against the baseline
+        let data = data.replace(r#""value": 10.0"#, r#""value": 12.0"#).replace(
+            r#""filename": "outputs.h5","#,
+            r#""filename": "outputs-diff.h5", "baseline": {"filename": "outputs.h5", "difference": "percentage"},"#,
+        );
+        let schema = PywrModel::from_str(&data).unwrap();
+
+        let model = schema.build_model(None, Some(temp_dir.path())).unwrap();
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let file = hdf5_metno::File::open(temp_dir.path().join("outputs-diff.h5")).unwrap();
+        let values = file.dataset("demand1/Inflow").unwrap().read_2d::<f64>().unwrap();
+        assert!(values.iter().all(|v| (v - 20.0).abs() < 1e-6));
+    }
+
+    #[test]
+    #[cfg(feature = "core")]
+    fn test_run_with_storage_options() {
+        let data = model_str().replace(
+            r#""filename": "outputs.h5","#,
+            r#""filename": "outputs.h5",
+               "compression": {"type": "Gzip", "level": 6},
+               "chunk_shape": {"time_steps": 30, "scenarios": 1},
+               "precision": "single","#,
+        );
+        let schema = PywrModel::from_str(&data).unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let model = schema.build_model(None, Some(temp_dir.path())).unwrap();
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let file = hdf5_metno::File::open(temp_dir.path().join("outputs.h5")).unwrap();
+        let ds = file.dataset("demand1/Inflow").unwrap();
+        assert_eq!(ds.dtype().unwrap().size(), 4);
+        assert_eq!(ds.chunk(), Some(vec![30, 1]));
+        let values = ds.read_2d::<f64>().unwrap();
+        assert!(values.iter().all(|v| (v - 10.0).abs() < 1e-6));
+
+        // An invalid gzip level is an error
+        let data = data.replace(r#""level": 6"#, r#""level": 10"#);
+        let schema = PywrModel::from_str(&data).unwrap();
+        assert!(schema.build_model(None, Some(temp_dir.path())).is_err());
+    }
 }
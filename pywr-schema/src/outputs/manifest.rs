@@ -0,0 +1,114 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+#[cfg(feature = "core")]
+use pywr_core::checksum::Checksum;
+#[cfg(feature = "core")]
+use pywr_core::recorders::RunManifestRecorder;
+use pywr_schema_macros::PywrVisitPaths;
+use schemars::JsonSchema;
+#[cfg(feature = "core")]
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Output a manifest of the provenance of the run.
+///
+/// A JSON file is written to `filename` at the end of the run with the checksum of the network
+/// definition, the digest of the network's structure, the version of Pywr, the solver and its
+/// settings, the time-steps and scenarios of the run, the other outputs and the wall-clock time
+/// of the run. Apart from the times, the manifest is the same for every run of the same model
+/// with the same solver. For more details see [`RunManifestRecorder`].
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestOutput {
+    pub name: String,
+    pub filename: PathBuf,
+}
+
+#[cfg(feature = "core")]
+impl ManifestOutput {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+        output_path: Option<&Path>,
+    ) -> Result<(), SchemaError> {
+        let filename = match (output_path, self.filename.is_relative()) {
+            (Some(odir), true) => odir.join(&self.filename),
+            _ => self.filename.to_path_buf(),
+        };
+
+        // The network is converted to a value first so that the keys of any maps are sorted and
+        // the checksum does not depend on the order in which they are serialised.
+        let network_value = serde_json::to_value(args.schema)?;
+        let checksum = Checksum::sha256(&serde_json::to_vec(&network_value)?);
+        let recorder = RunManifestRecorder::new(&self.name, filename).with_model_checksum(checksum);
+
+        network.add_recorder(Box::new(recorder))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "core")]
+mod tests {
+    use crate::model::PywrModel;
+    use pywr_core::recorders::RunManifest;
+    use pywr_core::solvers::{ClpSolver, ClpSolverSettings};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn model_str() -> &'static str {
+        r#"
+        {
+            "metadata": {"title": "Manifest", "minimum_version": "0.1"},
+            "timestepper": {"start": "2015-01-01", "end": "2015-01-10", "timestep": 1},
+            "scenarios": [{"name": "demand", "size": 2}],
+            "network": {
+                "nodes": [
+                    {"meta": {"name": "supply1"}, "type": "Input", "max_flow": {"type": "Constant", "value": 15.0}},
+                    {
+                        "meta": {"name": "demand1"},
+                        "type": "Output",
+                        "max_flow": {"type": "Constant", "value": 10.0},
+                        "cost": {"type": "Constant", "value": -10.0}
+                    }
+                ],
+                "edges": [{"from_node": "supply1", "to_node": "demand1"}],
+                "metric_sets": [{"name": "nodes", "metrics": [{"type": "Node", "name": "demand1"}]}],
+                "outputs": [
+                    {"name": "flows", "type": "CSV", "format": "long", "filename": "flows.csv", "metric_set": "nodes"},
+                    {"name": "manifest", "type": "Manifest", "filename": "manifest.json"}
+                ]
+            }
+        }
+        "#
+    }
+
+    #[test]
+    fn test_run_with_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let schema = PywrModel::from_str(model_str()).unwrap();
+        let model = schema.build_model(None, Some(temp_dir.path())).unwrap();
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let manifest = RunManifest::from_path(&temp_dir.path().join("manifest.json")).unwrap();
+        assert!(manifest.model_checksum.is_some());
+        assert_eq!(manifest.solver.as_deref(), Some("clp"));
+        assert_eq!(manifest.num_timesteps, 10);
+        assert_eq!(manifest.scenarios.len(), 1);
+        assert_eq!(manifest.scenarios[0].name, "demand");
+        assert_eq!(manifest.scenarios[0].size, 2);
+        assert_eq!(manifest.outputs.len(), 1);
+        assert_eq!(manifest.outputs[0].path, Some(temp_dir.path().join("flows.csv")));
+
+        // The same model gives the same checksum
+        let model = schema.build_model(None, Some(temp_dir.path())).unwrap();
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+        let second = RunManifest::from_path(&temp_dir.path().join("manifest.json")).unwrap();
+        assert_eq!(second.model_checksum, manifest.model_checksum);
+    }
+}
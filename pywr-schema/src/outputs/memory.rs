@@ -1,26 +1,34 @@
 use crate::metric_sets::MetricAggFunc;
+use crate::outputs::OutputTimeFilter;
 #[cfg(feature = "core")]
 use crate::SchemaError;
 #[cfg(feature = "core")]
-use pywr_core::recorders::MemoryRecorder;
+use pywr_core::recorders::{BufferSettings, MemoryRecorder};
 use pywr_schema_macros::PywrVisitPaths;
 use schemars::JsonSchema;
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default, JsonSchema, PywrVisitPaths)]
 pub struct MemoryAggregation {
     pub time: Option<MetricAggFunc>,
     pub scenario: Option<MetricAggFunc>,
     pub metric: Option<MetricAggFunc>,
+    /// The name of a scenario group to aggregate over, keeping the other scenario groups
+    /// separate (e.g. the mean over the hydrology members for each climate scenario).
+    ///
+    /// The flat aggregation over all of the scenarios is still available.
+    pub scenario_group: Option<String>,
 }
 
 #[cfg(feature = "core")]
-impl From<MemoryAggregation> for pywr_core::recorders::Aggregation {
-    fn from(value: MemoryAggregation) -> Self {
-        pywr_core::recorders::Aggregation::new(
-            value.time.map(|f| f.into()),
-            value.scenario.map(|f| f.into()),
-            value.metric.map(|f| f.into()),
-        )
+impl TryFrom<MemoryAggregation> for pywr_core::recorders::Aggregation {
+    type Error = SchemaError;
+
+    fn try_from(value: MemoryAggregation) -> Result<Self, Self::Error> {
+        Ok(pywr_core::recorders::Aggregation::new(
+            value.time.map(|f| f.try_into()).transpose()?,
+            value.scenario.map(|f| f.try_into()).transpose()?,
+            value.metric.map(|f| f.try_into()).transpose()?,
+        ))
     }
 }
 
@@ -42,25 +50,58 @@ impl From<MemoryAggregationOrder> for pywr_core::recorders::AggregationOrder {
     }
 }
 
+/// An output that keeps the values of a metric set in memory.
+///
+/// No files are written. The values can be aggregated (e.g. to provide the objectives of an
+/// optimisation) or retrieved as a data frame after the run. The `aggregation` is only required
+/// if the aggregated values are used.
+///
+/// An optional `time_filter` limits the saved, and therefore aggregated, values to those of the
+/// periods within the given dates (see [`OutputTimeFilter`]).
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths)]
 pub struct MemoryOutput {
     pub name: String,
     pub metric_set: String,
+    #[serde(default)]
     pub aggregation: MemoryAggregation,
     pub order: Option<MemoryAggregationOrder>,
+    /// The number of time-steps of each scenario that are compressed together.
+    pub block_len: Option<usize>,
+    /// The maximum number of bytes of compressed data to hold in memory. Any data in excess of
+    /// this is written to a temporary file.
+    pub memory_budget: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_filter: Option<OutputTimeFilter>,
 }
 
 #[cfg(feature = "core")]
 impl MemoryOutput {
     pub fn add_to_model(&self, network: &mut pywr_core::network::Network) -> Result<(), SchemaError> {
         let metric_set_idx = network.get_metric_set_index_by_name(&self.metric_set)?;
-        let recorder = MemoryRecorder::new(
+        let mut recorder = MemoryRecorder::new(
             &self.name,
             metric_set_idx,
-            self.aggregation.clone().into(),
+            self.aggregation.clone().try_into()?,
             self.order.map(|o| o.into()).unwrap_or_default(),
         );
 
+        if let Some(scenario_group) = &self.aggregation.scenario_group {
+            recorder = recorder.with_scenario_group(scenario_group);
+        }
+
+        let mut buffer_settings = BufferSettings::default();
+        if let Some(block_len) = self.block_len {
+            buffer_settings = buffer_settings.with_block_len(block_len);
+        }
+        if let Some(memory_budget) = self.memory_budget {
+            buffer_settings = buffer_settings.with_memory_budget(memory_budget);
+        }
+        recorder = recorder.with_buffer_settings(buffer_settings);
+
+        if let Some(time_filter) = &self.time_filter {
+            recorder = recorder.with_time_filter(time_filter.load(&self.name)?);
+        }
+
         network.add_recorder(Box::new(recorder))?;
 
         Ok(())
@@ -103,11 +144,11 @@ mod tests {
 
         let model = schema.build_model(None, Some(temp_dir.path())).unwrap();
 
-        let recorder_states = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+        let result = model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
 
         let result = model
             .network()
-            .get_aggregated_value("outputs", &recorder_states)
+            .get_aggregated_value("outputs", result.recorder_states())
             .expect("No results found");
 
         assert_approx_eq!(f64, result, 91.0);
@@ -1,16 +1,121 @@
 mod csv;
+mod events;
 mod hdf;
+mod manifest;
 mod memory;
+mod trace;
 
-pub use self::csv::CsvOutput;
+pub use self::csv::{CsvCompression, CsvOutput};
 #[cfg(feature = "core")]
 use crate::error::SchemaError;
-pub use hdf::Hdf5Output;
+use crate::metric::Metric;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::model::{DateType, DayOfYearWindow};
+use crate::visit::VisitMetrics;
+#[cfg(feature = "core")]
+use chrono::NaiveDateTime;
+pub use events::{EventOutput, EventOutputFormat};
+pub use hdf::{
+    Hdf5ChunkShape, Hdf5Output, Hdf5OutputCompression, Hdf5OutputPrecision, OutputBaseline, OutputDifference,
+};
+pub use manifest::ManifestOutput;
 pub use memory::MemoryOutput;
+#[cfg(feature = "core")]
+use pywr_core::recorders::{MetricFilter, MetricSetIndex, TimeFilter};
 use pywr_schema_macros::PywrVisitPaths;
 use schemars::JsonSchema;
 #[cfg(feature = "core")]
 use std::path::Path;
+pub use trace::TraceOutput;
+
+/// One or more metric sets to write to an output.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths, strum_macros::Display)]
+#[serde(untagged)]
+pub enum OutputMetricSets {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[cfg(feature = "core")]
+impl OutputMetricSets {
+    /// Return the indices of the metric sets in the network.
+    pub fn load(&self, network: &pywr_core::network::Network) -> Result<Vec<MetricSetIndex>, SchemaError> {
+        let indices = match self {
+            Self::Single(metric_set) => vec![network.get_metric_set_index_by_name(metric_set)?],
+            Self::Multiple(metric_sets) => metric_sets
+                .iter()
+                .map(|ms| network.get_metric_set_index_by_name(ms))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        Ok(indices)
+    }
+}
+
+/// A filter on the names of the metrics written to an output.
+///
+/// If `include` is given only the metrics with those names are written. Any metrics with a
+/// name in `exclude` are then removed. This allows an output to reuse existing metric sets
+/// without the need to define additional sets for each output.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default, JsonSchema, PywrVisitPaths)]
+pub struct OutputMetricFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+#[cfg(feature = "core")]
+impl From<&OutputMetricFilter> for MetricFilter {
+    fn from(filter: &OutputMetricFilter) -> Self {
+        MetricFilter::new(filter.include.clone(), filter.exclude.clone())
+    }
+}
+
+/// A filter on the periods written to an output.
+///
+/// Only the periods that start between `start` and `end` (inclusive) are written. An optional
+/// `window` further restricts these to the periods that start within the same days of each year
+/// (e.g. the summer months). This allows, for example, a long warm-up period to be excluded from
+/// the outputs of a run without changing the simulated period.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default, JsonSchema, PywrVisitPaths)]
+#[serde(deny_unknown_fields)]
+pub struct OutputTimeFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<DayOfYearWindow>,
+}
+
+#[cfg(feature = "core")]
+impl OutputTimeFilter {
+    /// Create the core time filter of the output named `name`.
+    pub fn load(&self, name: &str) -> Result<TimeFilter, SchemaError> {
+        let invalid = |reason: String| SchemaError::InvalidTimeFilter {
+            name: name.to_string(),
+            reason,
+        };
+
+        let start = self.start.map(NaiveDateTime::from);
+        let end = self.end.map(NaiveDateTime::from);
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Err(invalid(format!("the start ({start}) is after the end ({end})")));
+            }
+        }
+
+        let mut filter = TimeFilter::new(start, end);
+
+        if let Some(window) = &self.window {
+            filter = filter.with_window(window.load().map_err(invalid)?);
+        }
+
+        Ok(filter)
+    }
+}
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths, strum_macros::Display)]
 #[serde(tag = "type")]
@@ -18,6 +123,24 @@ pub enum Output {
     CSV(CsvOutput),
     HDF5(Hdf5Output),
     Memory(MemoryOutput),
+    Events(EventOutput),
+    Manifest(ManifestOutput),
+    Trace(TraceOutput),
+}
+
+/// Only the event outputs refer to metrics directly; the other outputs refer to metric sets.
+impl VisitMetrics for Output {
+    fn visit_metrics<F: FnMut(&Metric)>(&self, visitor: &mut F) {
+        if let Self::Events(output) = self {
+            output.visit_metrics(visitor);
+        }
+    }
+
+    fn visit_metrics_mut<F: FnMut(&mut Metric)>(&mut self, visitor: &mut F) {
+        if let Self::Events(output) = self {
+            output.visit_metrics_mut(visitor);
+        }
+    }
 }
 
 #[cfg(feature = "core")]
@@ -25,12 +148,16 @@ impl Output {
     pub fn add_to_model(
         &self,
         network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
         output_path: Option<&Path>,
     ) -> Result<(), SchemaError> {
         match self {
             Self::CSV(o) => o.add_to_model(network, output_path),
             Self::HDF5(o) => o.add_to_model(network, output_path),
             Self::Memory(o) => o.add_to_model(network),
+            Self::Events(o) => o.add_to_model(network, args, output_path),
+            Self::Manifest(o) => o.add_to_model(network, args, output_path),
+            Self::Trace(o) => o.add_to_model(network, output_path),
         }
     }
 }
@@ -0,0 +1,134 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+use crate::outputs::OutputTimeFilter;
+#[cfg(feature = "core")]
+use pywr_core::recorders::TraceRecorder;
+use pywr_schema_macros::PywrVisitPaths;
+use schemars::JsonSchema;
+#[cfg(feature = "core")]
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Output a trace of the values seen by the solver.
+///
+/// A JSON file is written to `filename` at the end of the run. For each traced time-step of each
+/// traced scenario it contains the value of every parameter, the flow bounds and costs of every
+/// node as they were given to the solver, and the flows of the solution. This is intended for
+/// understanding why the model made a particular decision, so the traced periods should be
+/// restricted with a `time_filter` (see [`OutputTimeFilter`]) and the traced scenarios with
+/// `scenarios` (the indices of the scenarios); by default every time-step of every scenario is
+/// traced. For more details see [`TraceRecorder`].
+///
+/// ```json
+/// {
+///     "name": "trace",
+///     "type": "Trace",
+///     "filename": "trace.json",
+///     "time_filter": {"start": "2021-08-01", "end": "2021-08-07"},
+///     "scenarios": [0]
+/// }
+/// ```
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitPaths)]
+#[serde(deny_unknown_fields)]
+pub struct TraceOutput {
+    pub name: String,
+    pub filename: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_filter: Option<OutputTimeFilter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenarios: Option<Vec<usize>>,
+}
+
+#[cfg(feature = "core")]
+impl TraceOutput {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        output_path: Option<&Path>,
+    ) -> Result<(), SchemaError> {
+        let filename = match (output_path, self.filename.is_relative()) {
+            (Some(odir), true) => odir.join(&self.filename),
+            _ => self.filename.to_path_buf(),
+        };
+
+        let mut recorder = TraceRecorder::new(&self.name, filename);
+        if let Some(time_filter) = &self.time_filter {
+            recorder = recorder.with_time_filter(time_filter.load(&self.name)?);
+        }
+        if let Some(scenarios) = &self.scenarios {
+            recorder = recorder.with_scenarios(scenarios.clone());
+        }
+
+        network.add_recorder(Box::new(recorder))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "core")]
+mod tests {
+    use crate::model::PywrModel;
+    use pywr_core::recorders::SolveTrace;
+    use pywr_core::solvers::{ClpSolver, ClpSolverSettings};
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn model_str() -> &'static str {
+        r#"
+        {
+            "metadata": {"title": "Trace", "minimum_version": "0.1"},
+            "timestepper": {"start": "2015-01-01", "end": "2015-01-10", "timestep": 1},
+            "scenarios": [{"name": "demand", "size": 2}],
+            "network": {
+                "nodes": [
+                    {"meta": {"name": "supply1"}, "type": "Input", "max_flow": {"type": "Constant", "value": 15.0}},
+                    {
+                        "meta": {"name": "demand1"},
+                        "type": "Output",
+                        "max_flow": {"type": "Parameter", "name": "demand"},
+                        "cost": {"type": "Constant", "value": -10.0}
+                    }
+                ],
+                "edges": [{"from_node": "supply1", "to_node": "demand1"}],
+                "parameters": [{"meta": {"name": "demand"}, "type": "Constant", "value": 10.0}],
+                "outputs": [
+                    {
+                        "name": "trace",
+                        "type": "Trace",
+                        "filename": "trace.json",
+                        "time_filter": {"start": "2015-01-03", "end": "2015-01-04"},
+                        "scenarios": [1]
+                    }
+                ]
+            }
+        }
+        "#
+    }
+
+    #[test]
+    fn test_run_with_trace() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let schema = PywrModel::from_str(model_str()).unwrap();
+        let model = schema.build_model(None, Some(temp_dir.path())).unwrap();
+        model.run::<ClpSolver>(&ClpSolverSettings::default()).unwrap();
+
+        let trace = SolveTrace::from_path(&temp_dir.path().join("trace.json")).unwrap();
+        assert_eq!(trace.entries.len(), 2);
+        assert!(trace.entries.iter().all(|e| e.scenario_index == 1));
+
+        let entry = &trace.entries[0];
+        assert_eq!(entry.timestep_index, 2);
+        assert_eq!(entry.parameters.len(), 1);
+        assert_eq!(entry.parameters[0].name, "demand");
+        assert_eq!(entry.parameters[0].value, 10.0);
+
+        let demand = entry.nodes.iter().find(|n| n.name == "demand1").unwrap();
+        assert_eq!(demand.max_flow, 10.0);
+        assert_eq!(demand.incoming_cost, -10.0);
+        assert_eq!(demand.in_flow, 10.0);
+        assert_eq!(entry.edges.len(), 1);
+        assert_eq!(entry.edges[0].flow, 10.0);
+    }
+}
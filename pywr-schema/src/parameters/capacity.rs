@@ -0,0 +1,59 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::parameters::{ConstantValue, ParameterMeta};
+#[cfg(feature = "core")]
+use pywr_core::parameters::{ParameterIndex, ParameterName};
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// A parameter representing the capacity of an asset for use in planning studies.
+///
+/// This parameter can be used as a node's `max_flow` or a storage node's `max_volume` to
+/// treat the size of that infrastructure as a decision variable. The capacity is exposed to
+/// external algorithms as a variable bounded by `min` and `max`. Each unit of capacity has an
+/// associated `unit_cost`, which allows the cost of the infrastructure to be included in the
+/// objectives of a capacity expansion study.
+///
+/// # JSON Examples
+///
+/// A capacity of 1000.0 that can be varied between 500.0 and 2500.0 at a cost of 12.5 per unit:
+/// ```json
+#[doc = include_str!("doc_examples/capacity.json")]
+/// ```
+///
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct CapacityParameter {
+    /// Meta-data.
+    pub meta: ParameterMeta,
+    /// The initial capacity. This is used until a value is set by an external algorithm.
+    pub value: ConstantValue<f64>,
+    /// The minimum capacity.
+    pub min: f64,
+    /// The maximum capacity.
+    pub max: f64,
+    /// The cost per unit of capacity (defaults to zero).
+    pub unit_cost: Option<f64>,
+}
+
+#[cfg(feature = "core")]
+impl CapacityParameter {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+        parent: Option<&str>,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let name = ParameterName::new(&self.meta.name, parent);
+        let p = pywr_core::parameters::CapacityParameter::new(
+            name,
+            self.value.load(args.tables)?,
+            self.min,
+            self.max,
+            self.unit_cost.unwrap_or(0.0),
+        )?;
+        Ok(network.add_const_parameter(Box::new(p))?)
+    }
+}
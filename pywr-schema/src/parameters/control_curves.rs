@@ -77,11 +77,11 @@ impl TryFromV1<ControlCurveInterpolatedParameterV1> for ControlCurveInterpolated
         )?;
 
         // Handle the case where neither or both "values" and "parameters" are defined.
-        let values = match (v1.values, v1.parameters) {
+        let (values, values_attr) = match (v1.values, v1.parameters) {
             (None, None) => {
                 return Err(ComponentConversionError::Parameter {
                     name: meta.name,
-                    attr: "control_curves".to_string(),
+                    attr: "values".to_string(),
                     error: ConversionError::MissingAttribute {
                         attrs: vec!["values".to_string(), "parameters".to_string()],
                     },
@@ -90,19 +90,35 @@ impl TryFromV1<ControlCurveInterpolatedParameterV1> for ControlCurveInterpolated
             (Some(_), Some(_)) => {
                 return Err(ComponentConversionError::Parameter {
                     name: meta.name,
-                    attr: "control_curves".to_string(),
-                    error: ConversionError::UnexpectedAttribute {
+                    attr: "values".to_string(),
+                    error: ConversionError::AmbiguousAttributes {
                         attrs: vec!["values".to_string(), "parameters".to_string()],
                     },
                 });
             }
-            (Some(values), None) => values.into_iter().map(Metric::from).collect(),
-            (None, Some(parameters)) => parameters
-                .into_iter()
-                .map(|p| try_convert_parameter_attr(&meta.name, "parameters", p, parent_node, conversion_data))
-                .collect::<Result<Vec<_>, _>>()?,
+            (Some(values), None) => (values.into_iter().map(Metric::from).collect::<Vec<_>>(), "values"),
+            (None, Some(parameters)) => (
+                parameters
+                    .into_iter()
+                    .map(|p| try_convert_parameter_attr(&meta.name, "parameters", p, parent_node, conversion_data))
+                    .collect::<Result<Vec<_>, _>>()?,
+                "parameters",
+            ),
         };
 
+        // There must be a value for the top (full) and bottom (empty) of the storage in addition
+        // to a value for each control curve.
+        if values.len() != control_curves.len() + 2 {
+            return Err(ComponentConversionError::Parameter {
+                name: meta.name,
+                attr: values_attr.to_string(),
+                error: ConversionError::IncorrectNumberOfValues {
+                    expected: control_curves.len() + 2,
+                    found: values.len(),
+                },
+            });
+        }
+
         // v1 uses proportional volume for control curves
         let storage_node = NodeReference {
             name: v1.storage_node,
@@ -399,7 +415,33 @@ impl TryFromV1<ControlCurvePiecewiseInterpolatedParameterV1> for ControlCurvePie
 
 #[cfg(test)]
 mod tests {
-    use crate::parameters::control_curves::ControlCurvePiecewiseInterpolatedParameter;
+    use crate::error::{ComponentConversionError, ConversionError};
+    use crate::metric::{Metric, NodeReference, ParameterReference};
+    use crate::nodes::NodeAttribute;
+    use crate::parameters::control_curves::{
+        ControlCurveInterpolatedParameter, ControlCurvePiecewiseInterpolatedParameter,
+    };
+    use crate::v1::{ConversionData, TryIntoV2};
+    use pywr_v1_schema::parameters::{CoreParameter, Parameter as ParameterV1};
+
+    /// Convert a v1 `ControlCurveInterpolatedParameter` from a JSON snippet.
+    fn convert_v1_interpolated(
+        data: &str,
+        conversion_data: &mut ConversionData,
+    ) -> Result<ControlCurveInterpolatedParameter, ComponentConversionError> {
+        let v1: ParameterV1 = serde_json::from_str(data).unwrap();
+        let v1 = match v1 {
+            ParameterV1::Core(CoreParameter::ControlCurveInterpolated(p)) => p,
+            _ => panic!("Expected a v1 ControlCurveInterpolatedParameter"),
+        };
+        v1.try_into_v2(None, conversion_data)
+    }
+
+    /// Serialise a converted parameter and deserialize it again.
+    fn round_trip(param: &ControlCurveInterpolatedParameter) -> ControlCurveInterpolatedParameter {
+        let data = serde_json::to_string_pretty(param).unwrap();
+        serde_json::from_str(&data).unwrap()
+    }
 
     #[test]
     fn test_control_curve_piecewise_interpolated() {
@@ -430,4 +472,177 @@ mod tests {
 
         assert_eq!(param.storage_node.name, "Reservoir");
     }
+
+    #[test]
+    fn test_v1_control_curve_interpolated_values() {
+        let data = r#"
+            {
+                "name": "reservoir1_cost",
+                "type": "controlcurveinterpolated",
+                "storage_node": "reservoir1",
+                "control_curves": ["reservoir1_cc", 0.5],
+                "values": [-10.0, -5.0, -2.0, 0.0]
+            }
+            "#;
+
+        let mut conversion_data = ConversionData::default();
+        let param = convert_v1_interpolated(data, &mut conversion_data).unwrap();
+
+        assert_eq!(param.meta.name, "reservoir1_cost");
+        // Storage nodes are referenced by name using their proportional volume
+        assert_eq!(
+            param.storage_node,
+            NodeReference::new("reservoir1".to_string(), Some(NodeAttribute::ProportionalVolume))
+        );
+        assert_eq!(
+            param.control_curves,
+            vec![
+                Metric::Parameter(ParameterReference::new("reservoir1_cc", None)),
+                Metric::Constant { value: 0.5 },
+            ]
+        );
+        assert_eq!(
+            param.values,
+            vec![
+                Metric::Constant { value: -10.0 },
+                Metric::Constant { value: -5.0 },
+                Metric::Constant { value: -2.0 },
+                Metric::Constant { value: 0.0 },
+            ]
+        );
+        assert!(conversion_data.parameters.is_empty());
+
+        let param2 = round_trip(&param);
+        assert_eq!(param2.storage_node, param.storage_node);
+        assert_eq!(param2.control_curves, param.control_curves);
+        assert_eq!(param2.values, param.values);
+    }
+
+    #[test]
+    fn test_v1_control_curve_interpolated_parameters() {
+        let data = r#"
+            {
+                "name": "reservoir1_release",
+                "type": "controlcurveinterpolated",
+                "storage_node": "reservoir1",
+                "control_curve": "reservoir1_cc",
+                "parameters": [
+                    "max_release",
+                    {
+                        "type": "constant",
+                        "value": 5.0
+                    },
+                    0.0
+                ]
+            }
+            "#;
+
+        let mut conversion_data = ConversionData::default();
+        let param = convert_v1_interpolated(data, &mut conversion_data).unwrap();
+
+        assert_eq!(
+            param.storage_node,
+            NodeReference::new("reservoir1".to_string(), Some(NodeAttribute::ProportionalVolume))
+        );
+        assert_eq!(
+            param.control_curves,
+            vec![Metric::Parameter(ParameterReference::new("reservoir1_cc", None))]
+        );
+
+        // The inline parameter is extracted and replaced with a reference
+        assert_eq!(conversion_data.parameters.len(), 1);
+        let inline_name = conversion_data.parameters[0].name();
+        assert_eq!(
+            param.values,
+            vec![
+                Metric::Parameter(ParameterReference::new("max_release", None)),
+                Metric::Parameter(ParameterReference::new(inline_name, None)),
+                Metric::Constant { value: 0.0 },
+            ]
+        );
+
+        let param2 = round_trip(&param);
+        assert_eq!(param2.storage_node, param.storage_node);
+        assert_eq!(param2.control_curves, param.control_curves);
+        assert_eq!(param2.values, param.values);
+    }
+
+    #[test]
+    fn test_v1_control_curve_interpolated_missing_values() {
+        let data = r#"
+            {
+                "name": "reservoir1_cost",
+                "type": "controlcurveinterpolated",
+                "storage_node": "reservoir1",
+                "control_curves": ["reservoir1_cc"]
+            }
+            "#;
+
+        let mut conversion_data = ConversionData::default();
+        let err = convert_v1_interpolated(data, &mut conversion_data).unwrap_err();
+
+        assert_eq!(
+            err,
+            ComponentConversionError::Parameter {
+                attr: "values".to_string(),
+                name: "reservoir1_cost".to_string(),
+                error: ConversionError::MissingAttribute {
+                    attrs: vec!["values".to_string(), "parameters".to_string()]
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_v1_control_curve_interpolated_values_and_parameters() {
+        let data = r#"
+            {
+                "name": "reservoir1_cost",
+                "type": "controlcurveinterpolated",
+                "storage_node": "reservoir1",
+                "control_curves": ["reservoir1_cc"],
+                "values": [-10.0, -5.0, 0.0],
+                "parameters": ["a", "b", "c"]
+            }
+            "#;
+
+        let mut conversion_data = ConversionData::default();
+        let err = convert_v1_interpolated(data, &mut conversion_data).unwrap_err();
+
+        assert_eq!(
+            err,
+            ComponentConversionError::Parameter {
+                attr: "values".to_string(),
+                name: "reservoir1_cost".to_string(),
+                error: ConversionError::AmbiguousAttributes {
+                    attrs: vec!["values".to_string(), "parameters".to_string()]
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_v1_control_curve_interpolated_incorrect_number_of_values() {
+        let data = r#"
+            {
+                "name": "reservoir1_cost",
+                "type": "controlcurveinterpolated",
+                "storage_node": "reservoir1",
+                "control_curves": ["reservoir1_cc", 0.5],
+                "values": [-10.0, 0.0]
+            }
+            "#;
+
+        let mut conversion_data = ConversionData::default();
+        let err = convert_v1_interpolated(data, &mut conversion_data).unwrap_err();
+
+        assert_eq!(
+            err,
+            ComponentConversionError::Parameter {
+                attr: "values".to_string(),
+                name: "reservoir1_cost".to_string(),
+                error: ConversionError::IncorrectNumberOfValues { expected: 4, found: 2 },
+            }
+        );
+    }
 }
@@ -7,7 +7,7 @@ use crate::model::LoadArgs;
 use crate::parameters::{ConstantValue, ConversionData, ParameterMeta};
 use crate::v1::{try_convert_parameter_attr, IntoV2, TryFromV1};
 #[cfg(feature = "core")]
-use pywr_core::parameters::{ParameterIndex, ParameterName};
+use pywr_core::parameters::{Parameter as _, ParameterIndex, ParameterName};
 use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::parameters::{
     ConstantParameter as ConstantParameterV1, DivisionParameter as DivisionParameterV1, MaxParameter as MaxParameterV1,
@@ -170,10 +170,34 @@ impl ConstantParameter {
         args: &LoadArgs,
         parent: Option<&str>,
     ) -> Result<ParameterIndex<f64>, SchemaError> {
-        let name = ParameterName::new(&self.meta.name, parent);
-        let p = pywr_core::parameters::ConstantParameter::new(name, self.value.load(args.tables)?);
+        let p = self.create(args, parent)?;
         Ok(network.add_const_parameter(Box::new(p))?)
     }
+
+    /// Replace the parameter of the same name in a network that has already been built.
+    pub fn replace_in_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+        parent: Option<&str>,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args, parent)?;
+        let idx = network.get_parameter_index_by_name(p.name())?;
+        network.replace_const_parameter(idx, Box::new(p))?;
+        Ok(idx)
+    }
+
+    fn create(
+        &self,
+        args: &LoadArgs,
+        parent: Option<&str>,
+    ) -> Result<pywr_core::parameters::ConstantParameter, SchemaError> {
+        let name = ParameterName::new(&self.meta.name, parent);
+        Ok(pywr_core::parameters::ConstantParameter::new(
+            name,
+            self.value.load(args.tables)?,
+        ))
+    }
 }
 
 impl TryFromV1<ConstantParameterV1> for ConstantParameter {
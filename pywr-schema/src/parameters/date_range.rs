@@ -0,0 +1,119 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+#[cfg(feature = "core")]
+use crate::model::DayOfYearWindow;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::model::{DateType, DayOfYear};
+use crate::parameters::ParameterMeta;
+#[cfg(feature = "core")]
+use chrono::NaiveDateTime;
+#[cfg(feature = "core")]
+use pywr_core::parameters::ParameterIndex;
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// A window of dates in which a [`DateRangeIndexParameter`] is active.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitAll)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum DateRangeWindow {
+    /// From `start` to `end` (inclusive).
+    Dates { start: DateType, end: DateType },
+    /// Within the same days of every year, from `start` to `end` (inclusive). The window wraps
+    /// around the end of the year if `end` is before `start` (e.g. from the 1st November to the
+    /// 28th February).
+    Annual { start: DayOfYear, end: DayOfYear },
+}
+
+#[cfg(feature = "core")]
+impl DateRangeWindow {
+    fn load(&self) -> Result<pywr_core::parameters::DateWindow, String> {
+        match self {
+            Self::Dates { start, end } => {
+                let start = NaiveDateTime::from(*start);
+                let end = NaiveDateTime::from(*end);
+                if start > end {
+                    return Err(format!("the start ({start}) is after the end ({end})"));
+                }
+                Ok(pywr_core::parameters::DateWindow::Dates { start, end })
+            }
+            Self::Annual { start, end } => {
+                let window = DayOfYearWindow {
+                    start: *start,
+                    end: *end,
+                };
+                Ok(pywr_core::parameters::DateWindow::Annual(window.load()?))
+            }
+        }
+    }
+}
+
+/// An index parameter that is 1 if the time-step starts within any of its `windows`, and 0
+/// otherwise.
+///
+/// This can be used to switch other parameters or constraints on and off for parts of a run
+/// (with `Dates` windows) or for seasons of every year (with `Annual` windows), for example
+/// with an `IndexedArray` parameter.
+///
+/// ```json
+/// {
+///     "meta": {"name": "summer-restrictions"},
+///     "type": "DateRange",
+///     "windows": [
+///         {"type": "Annual", "start": {"month": 6, "day": 1}, "end": {"month": 8, "day": 31}},
+///         {"type": "Dates", "start": "2021-03-01", "end": "2021-03-31"}
+///     ]
+/// }
+/// ```
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct DateRangeIndexParameter {
+    pub meta: ParameterMeta,
+    pub windows: Vec<DateRangeWindow>,
+}
+
+#[cfg(feature = "core")]
+impl DateRangeIndexParameter {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        _args: &LoadArgs,
+    ) -> Result<ParameterIndex<u64>, SchemaError> {
+        let windows = self
+            .windows
+            .iter()
+            .map(|w| w.load())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|reason| SchemaError::InvalidDateRange {
+                name: self.meta.name.clone(),
+                reason,
+            })?;
+
+        let p = pywr_core::parameters::DateRangeIndexParameter::new(self.meta.name.as_str().into(), windows);
+
+        Ok(network.add_simple_index_parameter(Box::new(p))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DateRangeIndexParameter, DateRangeWindow};
+
+    #[test]
+    fn test_date_range_schema() {
+        let data = r#"
+            {
+                "meta": {"name": "summer"},
+                "windows": [
+                    {"type": "Annual", "start": {"month": 6, "day": 1}, "end": {"month": 8, "day": 31}},
+                    {"type": "Dates", "start": "2021-03-01", "end": "2021-03-31T12:00:00"}
+                ]
+            }
+            "#;
+
+        let p: DateRangeIndexParameter = serde_json::from_str(data).unwrap();
+        assert_eq!(p.windows.len(), 2);
+        assert!(matches!(p.windows[0], DateRangeWindow::Annual { .. }));
+        assert!(matches!(p.windows[1], DateRangeWindow::Dates { .. }));
+    }
+}
@@ -104,6 +104,7 @@ impl HydropowerTargetParameter {
         let turbine_data = HydropowerTargetData {
             target,
             water_elevation,
+            tailwater_elevation: None,
             elevation: self.turbine_elevation,
             min_head: self.min_head,
             max_flow,
@@ -9,8 +9,10 @@
 //! Serializing and deserializing is accomplished using [`serde`].
 mod aggregated;
 mod asymmetric_switch;
+mod capacity;
 mod control_curves;
 mod core;
+mod date_range;
 mod delay;
 mod discount_factor;
 mod hydropower;
@@ -20,6 +22,7 @@ mod offset;
 mod polynomial;
 mod profiles;
 mod python;
+mod random;
 mod tables;
 mod thresholds;
 
@@ -29,6 +32,7 @@ pub use super::data_tables::TableDataRef;
 #[cfg(feature = "core")]
 use crate::error::SchemaError;
 use crate::error::{ComponentConversionError, ConversionError};
+use crate::json::variant_error;
 use crate::metric::Metric;
 #[cfg(feature = "core")]
 use crate::model::LoadArgs;
@@ -37,6 +41,7 @@ use crate::v1::{ConversionData, IntoV2, TryFromV1, TryIntoV2};
 use crate::visit::{VisitMetrics, VisitPaths};
 pub use aggregated::{AggFunc, AggregatedIndexParameter, AggregatedParameter, IndexAggFunc};
 pub use asymmetric_switch::AsymmetricSwitchIndexParameter;
+pub use capacity::CapacityParameter;
 pub use control_curves::{
     ControlCurveIndexParameter, ControlCurveInterpolatedParameter, ControlCurveParameter,
     ControlCurvePiecewiseInterpolatedParameter,
@@ -45,6 +50,7 @@ pub use core::{
     ActivationFunction, ConstantParameter, DivisionParameter, MaxParameter, MinParameter, NegativeMaxParameter,
     NegativeMinParameter, NegativeParameter, VariableSettings,
 };
+pub use date_range::{DateRangeIndexParameter, DateRangeWindow};
 pub use delay::DelayParameter;
 pub use discount_factor::DiscountFactorParameter;
 pub use hydropower::HydropowerTargetParameter;
@@ -53,8 +59,9 @@ pub use interpolated::InterpolatedParameter;
 pub use offset::OffsetParameter;
 pub use polynomial::Polynomial1DParameter;
 pub use profiles::{
-    DailyProfileParameter, MonthlyInterpDay, MonthlyProfileParameter, RadialBasisFunction, RbfProfileParameter,
-    RbfProfileVariableSettings, UniformDrawdownProfileParameter, WeeklyProfileParameter,
+    DailyProfileParameter, DiurnalProfileParameter, LeapDayPolicy, MonthlyInterpDay, MonthlyProfileParameter,
+    RadialBasisFunction, RbfProfileParameter, RbfProfileVariableSettings, UniformDrawdownProfileParameter,
+    WeeklyProfileParameter,
 };
 #[cfg(all(feature = "core", feature = "pyo3"))]
 pub use python::try_json_value_into_py;
@@ -64,17 +71,26 @@ use pywr_v1_schema::parameters::{
     CoreParameter, DataFrameParameter as DataFrameParameterV1, Parameter as ParameterV1,
     ParameterValue as ParameterValueV1, TableIndex as TableIndexV1, TableIndexEntry as TableIndexEntryV1,
 };
+pub use random::{RandomDistribution, RandomParameter};
 use schemars::JsonSchema;
 use std::path::{Path, PathBuf};
 use strum_macros::{Display, EnumDiscriminants, EnumString, IntoStaticStr, VariantNames};
 pub use tables::TablesArrayParameter;
-pub use thresholds::ThresholdParameter;
+pub use thresholds::{ConsecutiveCountParameter, Predicate, ThresholdParameter};
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
 pub struct ParameterMeta {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    /// Optional lower bound on the parameter's value. Computed values below this bound are
+    /// clamped, and the number of times this occurs is reported at the end of the run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Optional upper bound on the parameter's value. Computed values above this bound are
+    /// clamped, and the number of times this occurs is reported at the end of the run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, EnumDiscriminants, Clone, JsonSchema, Display)]
@@ -92,6 +108,7 @@ pub enum Parameter {
     ControlCurveIndex(ControlCurveIndexParameter),
     ControlCurve(ControlCurveParameter),
     DailyProfile(DailyProfileParameter),
+    DiurnalProfile(DiurnalProfileParameter),
     IndexedArray(IndexedArrayParameter),
     MonthlyProfile(MonthlyProfileParameter),
     WeeklyProfile(WeeklyProfileParameter),
@@ -104,6 +121,7 @@ pub enum Parameter {
     HydropowerTarget(HydropowerTargetParameter),
     Polynomial1D(Polynomial1DParameter),
     Threshold(ThresholdParameter),
+    ConsecutiveCount(ConsecutiveCountParameter),
     TablesArray(TablesArrayParameter),
     Python(PythonParameter),
     Delay(DelayParameter),
@@ -112,43 +130,104 @@ pub enum Parameter {
     DiscountFactor(DiscountFactorParameter),
     Interpolated(InterpolatedParameter),
     RbfProfile(RbfProfileParameter),
+    Capacity(CapacityParameter),
+    Random(RandomParameter),
+    DateRange(DateRangeIndexParameter),
+}
+
+impl ParameterType {
+    /// The path and error of deserialising `value` as a parameter of this type.
+    ///
+    /// See [`crate::json`] for why this is needed.
+    pub(crate) fn variant_error(
+        &self,
+        value: &serde_json::Value,
+    ) -> Option<(serde_path_to_error::Path, serde_json::Error)> {
+        match self {
+            Self::Aggregated => variant_error::<AggregatedParameter>(value),
+            Self::AggregatedIndex => variant_error::<AggregatedIndexParameter>(value),
+            Self::AsymmetricSwitchIndex => variant_error::<AsymmetricSwitchIndexParameter>(value),
+            Self::Constant => variant_error::<ConstantParameter>(value),
+            Self::ControlCurvePiecewiseInterpolated => {
+                variant_error::<ControlCurvePiecewiseInterpolatedParameter>(value)
+            }
+            Self::ControlCurveInterpolated => variant_error::<ControlCurveInterpolatedParameter>(value),
+            Self::ControlCurveIndex => variant_error::<ControlCurveIndexParameter>(value),
+            Self::ControlCurve => variant_error::<ControlCurveParameter>(value),
+            Self::DailyProfile => variant_error::<DailyProfileParameter>(value),
+            Self::DiurnalProfile => variant_error::<DiurnalProfileParameter>(value),
+            Self::IndexedArray => variant_error::<IndexedArrayParameter>(value),
+            Self::MonthlyProfile => variant_error::<MonthlyProfileParameter>(value),
+            Self::WeeklyProfile => variant_error::<WeeklyProfileParameter>(value),
+            Self::UniformDrawdownProfile => variant_error::<UniformDrawdownProfileParameter>(value),
+            Self::Max => variant_error::<MaxParameter>(value),
+            Self::Min => variant_error::<MinParameter>(value),
+            Self::Negative => variant_error::<NegativeParameter>(value),
+            Self::NegativeMax => variant_error::<NegativeMaxParameter>(value),
+            Self::NegativeMin => variant_error::<NegativeMinParameter>(value),
+            Self::HydropowerTarget => variant_error::<HydropowerTargetParameter>(value),
+            Self::Polynomial1D => variant_error::<Polynomial1DParameter>(value),
+            Self::Threshold => variant_error::<ThresholdParameter>(value),
+            Self::ConsecutiveCount => variant_error::<ConsecutiveCountParameter>(value),
+            Self::TablesArray => variant_error::<TablesArrayParameter>(value),
+            Self::Python => variant_error::<PythonParameter>(value),
+            Self::Delay => variant_error::<DelayParameter>(value),
+            Self::Division => variant_error::<DivisionParameter>(value),
+            Self::Offset => variant_error::<OffsetParameter>(value),
+            Self::DiscountFactor => variant_error::<DiscountFactorParameter>(value),
+            Self::Interpolated => variant_error::<InterpolatedParameter>(value),
+            Self::RbfProfile => variant_error::<RbfProfileParameter>(value),
+            Self::Capacity => variant_error::<CapacityParameter>(value),
+            Self::Random => variant_error::<RandomParameter>(value),
+            Self::DateRange => variant_error::<DateRangeIndexParameter>(value),
+        }
+    }
 }
 
 impl Parameter {
-    pub fn name(&self) -> &str {
+    pub fn meta(&self) -> &ParameterMeta {
         match self {
-            Self::Constant(p) => p.meta.name.as_str(),
-            Self::ControlCurveInterpolated(p) => p.meta.name.as_str(),
-            Self::Aggregated(p) => p.meta.name.as_str(),
-            Self::AggregatedIndex(p) => p.meta.name.as_str(),
-            Self::AsymmetricSwitchIndex(p) => p.meta.name.as_str(),
-            Self::ControlCurvePiecewiseInterpolated(p) => p.meta.name.as_str(),
-            Self::ControlCurveIndex(p) => p.meta.name.as_str(),
-            Self::ControlCurve(p) => p.meta.name.as_str(),
-            Self::DailyProfile(p) => p.meta.name.as_str(),
-            Self::IndexedArray(p) => p.meta.name.as_str(),
-            Self::MonthlyProfile(p) => p.meta.name.as_str(),
-            Self::WeeklyProfile(p) => p.meta.name.as_str(),
-            Self::UniformDrawdownProfile(p) => p.meta.name.as_str(),
-            Self::Max(p) => p.meta.name.as_str(),
-            Self::Min(p) => p.meta.name.as_str(),
-            Self::Negative(p) => p.meta.name.as_str(),
-            Self::Polynomial1D(p) => p.meta.name.as_str(),
-            Self::Threshold(p) => p.meta.name.as_str(),
-            Self::TablesArray(p) => p.meta.name.as_str(),
-            Self::Python(p) => p.meta.name.as_str(),
-            Self::Division(p) => p.meta.name.as_str(),
-            Self::Delay(p) => p.meta.name.as_str(),
-            Self::Offset(p) => p.meta.name.as_str(),
-            Self::DiscountFactor(p) => p.meta.name.as_str(),
-            Self::Interpolated(p) => p.meta.name.as_str(),
-            Self::HydropowerTarget(p) => p.meta.name.as_str(),
-            Self::RbfProfile(p) => p.meta.name.as_str(),
-            Self::NegativeMax(p) => p.meta.name.as_str(),
-            Self::NegativeMin(p) => p.meta.name.as_str(),
+            Self::Constant(p) => &p.meta,
+            Self::ControlCurveInterpolated(p) => &p.meta,
+            Self::Aggregated(p) => &p.meta,
+            Self::AggregatedIndex(p) => &p.meta,
+            Self::AsymmetricSwitchIndex(p) => &p.meta,
+            Self::ControlCurvePiecewiseInterpolated(p) => &p.meta,
+            Self::ControlCurveIndex(p) => &p.meta,
+            Self::ControlCurve(p) => &p.meta,
+            Self::DailyProfile(p) => &p.meta,
+            Self::DiurnalProfile(p) => &p.meta,
+            Self::IndexedArray(p) => &p.meta,
+            Self::MonthlyProfile(p) => &p.meta,
+            Self::WeeklyProfile(p) => &p.meta,
+            Self::UniformDrawdownProfile(p) => &p.meta,
+            Self::Max(p) => &p.meta,
+            Self::Min(p) => &p.meta,
+            Self::Negative(p) => &p.meta,
+            Self::Polynomial1D(p) => &p.meta,
+            Self::Threshold(p) => &p.meta,
+            Self::ConsecutiveCount(p) => &p.meta,
+            Self::TablesArray(p) => &p.meta,
+            Self::Python(p) => &p.meta,
+            Self::Division(p) => &p.meta,
+            Self::Delay(p) => &p.meta,
+            Self::Offset(p) => &p.meta,
+            Self::DiscountFactor(p) => &p.meta,
+            Self::Interpolated(p) => &p.meta,
+            Self::HydropowerTarget(p) => &p.meta,
+            Self::RbfProfile(p) => &p.meta,
+            Self::Capacity(p) => &p.meta,
+            Self::NegativeMax(p) => &p.meta,
+            Self::NegativeMin(p) => &p.meta,
+            Self::Random(p) => &p.meta,
+            Self::DateRange(p) => &p.meta,
         }
     }
 
+    pub fn name(&self) -> &str {
+        self.meta().name.as_str()
+    }
+
     pub fn parameter_type(&self) -> ParameterType {
         // Implementation provided by the `EnumDiscriminants` derive macro.
         self.into()
@@ -181,6 +260,7 @@ impl Parameter {
             Self::ControlCurveIndex(p) => pywr_core::parameters::ParameterType::Index(p.add_to_model(network, args)?),
             Self::ControlCurve(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::DailyProfile(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
+            Self::DiurnalProfile(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::IndexedArray(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::MonthlyProfile(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::WeeklyProfile(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
@@ -192,6 +272,7 @@ impl Parameter {
             Self::Negative(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::Polynomial1D(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::Threshold(p) => p.add_to_model(network, args)?,
+            Self::ConsecutiveCount(p) => pywr_core::parameters::ParameterType::Index(p.add_to_model(network, args)?),
             Self::TablesArray(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::Python(p) => p.add_to_model(network, args)?,
             Self::Delay(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
@@ -200,15 +281,83 @@ impl Parameter {
             Self::DiscountFactor(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::Interpolated(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::RbfProfile(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network)?),
+            Self::Capacity(p) => {
+                pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args, parent)?)
+            }
             Self::NegativeMax(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::NegativeMin(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
             Self::HydropowerTarget(p) => {
                 pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?)
             }
+            Self::Random(p) => pywr_core::parameters::ParameterType::Parameter(p.add_to_model(network, args)?),
+            Self::DateRange(p) => pywr_core::parameters::ParameterType::Index(p.add_to_model(network, args)?),
+        };
+
+        self.set_bounds(network, &ty)?;
+
+        Ok(ty)
+    }
+
+    /// Whether the parameter can be replaced in a network that has already been built
+    /// (see [`Parameter::replace_in_model`]).
+    ///
+    /// Only parameters that do not reference other components of the network can be replaced.
+    pub fn is_replaceable(&self) -> bool {
+        matches!(
+            self,
+            Self::Constant(_)
+                | Self::DailyProfile(_)
+                | Self::DiurnalProfile(_)
+                | Self::MonthlyProfile(_)
+                | Self::UniformDrawdownProfile(_)
+                | Self::WeeklyProfile(_)
+        )
+    }
+
+    /// Replace the parameter of the same name in a network that has already been built.
+    ///
+    /// This allows the definition of a parameter to be changed without rebuilding the network.
+    /// An error is returned if the parameter can not be replaced (see [`Parameter::is_replaceable`]).
+    pub fn replace_in_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+        parent: Option<&str>,
+    ) -> Result<pywr_core::parameters::ParameterType, SchemaError> {
+        let idx = match self {
+            Self::Constant(p) => p.replace_in_model(network, args, parent)?,
+            Self::DailyProfile(p) => p.replace_in_model(network, args)?,
+            Self::DiurnalProfile(p) => p.replace_in_model(network, args)?,
+            Self::MonthlyProfile(p) => p.replace_in_model(network, args)?,
+            Self::UniformDrawdownProfile(p) => p.replace_in_model(network, args)?,
+            Self::WeeklyProfile(p) => p.replace_in_model(network, args)?,
+            _ => return Err(SchemaError::ParameterNotReplaceable(self.name().to_string())),
         };
 
+        let ty = pywr_core::parameters::ParameterType::Parameter(idx);
+        self.set_bounds(network, &ty)?;
+
         Ok(ty)
     }
+
+    /// Apply the optional bounds of the parameter's meta-data to the parameter in the network.
+    fn set_bounds(
+        &self,
+        network: &mut pywr_core::network::Network,
+        ty: &pywr_core::parameters::ParameterType,
+    ) -> Result<(), SchemaError> {
+        let meta = self.meta();
+        if meta.min.is_some() || meta.max.is_some() {
+            match ty {
+                pywr_core::parameters::ParameterType::Parameter(idx) => {
+                    let bounds = pywr_core::parameters::ParameterBounds::new(meta.min, meta.max)?;
+                    network.set_parameter_bounds(*idx, bounds)?;
+                }
+                _ => return Err(SchemaError::ParameterBoundsNotSupported(meta.name.clone())),
+            }
+        }
+        Ok(())
+    }
 }
 
 impl VisitMetrics for Parameter {
@@ -223,6 +372,7 @@ impl VisitMetrics for Parameter {
             Self::ControlCurveIndex(p) => p.visit_metrics(visitor),
             Self::ControlCurve(p) => p.visit_metrics(visitor),
             Self::DailyProfile(p) => p.visit_metrics(visitor),
+            Self::DiurnalProfile(p) => p.visit_metrics(visitor),
             Self::IndexedArray(p) => p.visit_metrics(visitor),
             Self::MonthlyProfile(p) => p.visit_metrics(visitor),
             Self::WeeklyProfile(p) => p.visit_metrics(visitor),
@@ -232,6 +382,7 @@ impl VisitMetrics for Parameter {
             Self::Negative(p) => p.visit_metrics(visitor),
             Self::Polynomial1D(p) => p.visit_metrics(visitor),
             Self::Threshold(p) => p.visit_metrics(visitor),
+            Self::ConsecutiveCount(p) => p.visit_metrics(visitor),
             Self::TablesArray(p) => p.visit_metrics(visitor),
             Self::Python(p) => p.visit_metrics(visitor),
             Self::Delay(p) => p.visit_metrics(visitor),
@@ -240,9 +391,12 @@ impl VisitMetrics for Parameter {
             Self::DiscountFactor(p) => p.visit_metrics(visitor),
             Self::Interpolated(p) => p.visit_metrics(visitor),
             Self::RbfProfile(p) => p.visit_metrics(visitor),
+            Self::Capacity(p) => p.visit_metrics(visitor),
             Self::NegativeMax(p) => p.visit_metrics(visitor),
             Self::NegativeMin(p) => p.visit_metrics(visitor),
             Self::HydropowerTarget(p) => p.visit_metrics(visitor),
+            Self::Random(p) => p.visit_metrics(visitor),
+            Self::DateRange(p) => p.visit_metrics(visitor),
         }
     }
 
@@ -257,6 +411,7 @@ impl VisitMetrics for Parameter {
             Self::ControlCurveIndex(p) => p.visit_metrics_mut(visitor),
             Self::ControlCurve(p) => p.visit_metrics_mut(visitor),
             Self::DailyProfile(p) => p.visit_metrics_mut(visitor),
+            Self::DiurnalProfile(p) => p.visit_metrics_mut(visitor),
             Self::IndexedArray(p) => p.visit_metrics_mut(visitor),
             Self::MonthlyProfile(p) => p.visit_metrics_mut(visitor),
             Self::WeeklyProfile(p) => p.visit_metrics_mut(visitor),
@@ -266,6 +421,7 @@ impl VisitMetrics for Parameter {
             Self::Negative(p) => p.visit_metrics_mut(visitor),
             Self::Polynomial1D(p) => p.visit_metrics_mut(visitor),
             Self::Threshold(p) => p.visit_metrics_mut(visitor),
+            Self::ConsecutiveCount(p) => p.visit_metrics_mut(visitor),
             Self::TablesArray(p) => p.visit_metrics_mut(visitor),
             Self::Python(p) => p.visit_metrics_mut(visitor),
             Self::Delay(p) => p.visit_metrics_mut(visitor),
@@ -274,9 +430,12 @@ impl VisitMetrics for Parameter {
             Self::DiscountFactor(p) => p.visit_metrics_mut(visitor),
             Self::Interpolated(p) => p.visit_metrics_mut(visitor),
             Self::RbfProfile(p) => p.visit_metrics_mut(visitor),
+            Self::Capacity(p) => p.visit_metrics_mut(visitor),
             Self::NegativeMax(p) => p.visit_metrics_mut(visitor),
             Self::NegativeMin(p) => p.visit_metrics_mut(visitor),
             Self::HydropowerTarget(p) => p.visit_metrics_mut(visitor),
+            Self::Random(p) => p.visit_metrics_mut(visitor),
+            Self::DateRange(p) => p.visit_metrics_mut(visitor),
         }
     }
 }
@@ -293,6 +452,7 @@ impl VisitPaths for Parameter {
             Self::ControlCurveIndex(p) => p.visit_paths(visitor),
             Self::ControlCurve(p) => p.visit_paths(visitor),
             Self::DailyProfile(p) => p.visit_paths(visitor),
+            Self::DiurnalProfile(p) => p.visit_paths(visitor),
             Self::IndexedArray(p) => p.visit_paths(visitor),
             Self::MonthlyProfile(p) => p.visit_paths(visitor),
             Self::WeeklyProfile(p) => p.visit_paths(visitor),
@@ -302,6 +462,7 @@ impl VisitPaths for Parameter {
             Self::Negative(p) => p.visit_paths(visitor),
             Self::Polynomial1D(p) => p.visit_paths(visitor),
             Self::Threshold(p) => p.visit_paths(visitor),
+            Self::ConsecutiveCount(p) => p.visit_paths(visitor),
             Self::TablesArray(p) => p.visit_paths(visitor),
             Self::Python(p) => p.visit_paths(visitor),
             Self::Delay(p) => p.visit_paths(visitor),
@@ -310,9 +471,12 @@ impl VisitPaths for Parameter {
             Self::DiscountFactor(p) => p.visit_paths(visitor),
             Self::Interpolated(p) => p.visit_paths(visitor),
             Self::RbfProfile(p) => p.visit_paths(visitor),
+            Self::Capacity(p) => p.visit_paths(visitor),
             Self::NegativeMax(p) => p.visit_paths(visitor),
             Self::NegativeMin(p) => p.visit_paths(visitor),
             Self::HydropowerTarget(p) => p.visit_paths(visitor),
+            Self::Random(p) => p.visit_paths(visitor),
+            Self::DateRange(p) => p.visit_paths(visitor),
         }
     }
 
@@ -327,6 +491,7 @@ impl VisitPaths for Parameter {
             Self::ControlCurveIndex(p) => p.visit_paths_mut(visitor),
             Self::ControlCurve(p) => p.visit_paths_mut(visitor),
             Self::DailyProfile(p) => p.visit_paths_mut(visitor),
+            Self::DiurnalProfile(p) => p.visit_paths_mut(visitor),
             Self::IndexedArray(p) => p.visit_paths_mut(visitor),
             Self::MonthlyProfile(p) => p.visit_paths_mut(visitor),
             Self::WeeklyProfile(p) => p.visit_paths_mut(visitor),
@@ -336,6 +501,7 @@ impl VisitPaths for Parameter {
             Self::Negative(p) => p.visit_paths_mut(visitor),
             Self::Polynomial1D(p) => p.visit_paths_mut(visitor),
             Self::Threshold(p) => p.visit_paths_mut(visitor),
+            Self::ConsecutiveCount(p) => p.visit_paths_mut(visitor),
             Self::TablesArray(p) => p.visit_paths_mut(visitor),
             Self::Python(p) => p.visit_paths_mut(visitor),
             Self::Delay(p) => p.visit_paths_mut(visitor),
@@ -344,9 +510,12 @@ impl VisitPaths for Parameter {
             Self::DiscountFactor(p) => p.visit_paths_mut(visitor),
             Self::Interpolated(p) => p.visit_paths_mut(visitor),
             Self::RbfProfile(p) => p.visit_paths_mut(visitor),
+            Self::Capacity(p) => p.visit_paths_mut(visitor),
             Self::NegativeMax(p) => p.visit_paths_mut(visitor),
             Self::NegativeMin(p) => p.visit_paths_mut(visitor),
             Self::HydropowerTarget(p) => p.visit_paths_mut(visitor),
+            Self::Random(p) => p.visit_paths_mut(visitor),
+            Self::DateRange(p) => p.visit_paths_mut(visitor),
         }
     }
 }
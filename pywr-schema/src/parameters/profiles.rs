@@ -6,7 +6,7 @@ use crate::model::LoadArgs;
 use crate::parameters::{ConstantFloatVec, ConstantValue, ConversionData, ParameterMeta};
 use crate::v1::{try_convert_values, FromV1, IntoV2, TryFromV1};
 #[cfg(feature = "core")]
-use pywr_core::parameters::{ParameterIndex, WeeklyProfileError, WeeklyProfileValues};
+use pywr_core::parameters::{Parameter as _, ParameterIndex, WeeklyProfileError, WeeklyProfileValues};
 use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::parameters::{
     DailyProfileParameter as DailyProfileParameterV1, MonthInterpDay as MonthInterpDayV1,
@@ -16,11 +16,44 @@ use pywr_v1_schema::parameters::{
 };
 use schemars::JsonSchema;
 
+/// How a profile treats the 29th February.
+///
+/// For a daily profile this also gives the number of values. The weekly, monthly and uniform
+/// drawdown profiles use it to place the 29th February in a year of 366 (`Profile366`) or 365 days.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Copy, Clone, strum_macros::Display, JsonSchema, PywrVisitAll)]
+pub enum LeapDayPolicy {
+    /// The profile has 366 values, including one for the 29th February. In other years the value
+    /// of the 29th February is skipped.
+    Profile366,
+    /// The profile has 365 values and the value of the 28th February is repeated on the 29th.
+    RepeatFeb28,
+    /// The profile has 365 values and the value of the 29th February is the mean of the values of
+    /// the 28th February and the 1st March.
+    Interpolate,
+}
+
+#[cfg(feature = "core")]
+impl From<LeapDayPolicy> for pywr_core::parameters::LeapDayPolicy {
+    fn from(value: LeapDayPolicy) -> Self {
+        match value {
+            LeapDayPolicy::Profile366 => Self::Profile366,
+            LeapDayPolicy::RepeatFeb28 => Self::RepeatFeb28,
+            LeapDayPolicy::Interpolate => Self::Interpolate,
+        }
+    }
+}
+
+/// A profile of a value for each day of the year.
+///
+/// By default the profile has 366 `values`, one for each day of a leap year; any further values
+/// are ignored. The number of values and how the 29th February is handled can be changed with
+/// `leap_day_policy` (see [`LeapDayPolicy`]).
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
 #[serde(deny_unknown_fields)]
 pub struct DailyProfileParameter {
     pub meta: ParameterMeta,
     pub values: ConstantFloatVec,
+    pub leap_day_policy: Option<LeapDayPolicy>,
 }
 
 #[cfg(feature = "core")]
@@ -30,13 +63,43 @@ impl DailyProfileParameter {
         network: &mut pywr_core::network::Network,
         args: &LoadArgs,
     ) -> Result<ParameterIndex<f64>, SchemaError> {
-        let values = &self.values.load(args.tables)?[..366];
-        let p = pywr_core::parameters::DailyProfileParameter::new(
-            self.meta.name.as_str().into(),
-            values.try_into().expect(""),
-        );
+        let p = self.create(args)?;
         Ok(network.add_simple_parameter(Box::new(p))?)
     }
+
+    /// Replace the parameter of the same name in a network that has already been built.
+    pub fn replace_in_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        let idx = network.get_parameter_index_by_name(p.name())?;
+        network.replace_simple_parameter(idx, Box::new(p))?;
+        Ok(idx)
+    }
+
+    fn create(&self, args: &LoadArgs) -> Result<pywr_core::parameters::DailyProfileParameter, SchemaError> {
+        let mut values = self.values.load(args.tables)?;
+        let leap_day_policy = match self.leap_day_policy {
+            Some(p) => p.into(),
+            None => {
+                // Without a policy any values after the first 366 are ignored
+                values.truncate(366);
+                pywr_core::parameters::LeapDayPolicy::Profile366
+            }
+        };
+
+        pywr_core::parameters::DailyProfileParameter::from_values(
+            self.meta.name.as_str().into(),
+            &values,
+            leap_day_policy,
+        )
+        .map_err(|err| SchemaError::LoadParameter {
+            name: self.meta.name.to_string(),
+            error: err.to_string(),
+        })
+    }
 }
 
 impl TryFromV1<DailyProfileParameterV1> for DailyProfileParameter {
@@ -51,11 +114,59 @@ impl TryFromV1<DailyProfileParameterV1> for DailyProfileParameter {
 
         let values = try_convert_values(&meta.name, v1.values, v1.external, v1.table_ref)?;
 
-        let p = Self { meta, values };
+        let p = Self {
+            meta,
+            values,
+            leap_day_policy: None,
+        };
         Ok(p)
     }
 }
 
+/// A profile of values over the course of a day (e.g. an hourly demand pattern).
+///
+/// The day is divided into intervals of equal length, one for each of the `values` (e.g. 24
+/// values give an hourly profile). The model's time-steps must not be longer than the intervals or
+/// cross their boundaries; this is checked before the model is run.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct DiurnalProfileParameter {
+    pub meta: ParameterMeta,
+    pub values: ConstantFloatVec,
+}
+
+#[cfg(feature = "core")]
+impl DiurnalProfileParameter {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        Ok(network.add_simple_parameter(Box::new(p))?)
+    }
+
+    /// Replace the parameter of the same name in a network that has already been built.
+    pub fn replace_in_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        let idx = network.get_parameter_index_by_name(p.name())?;
+        network.replace_simple_parameter(idx, Box::new(p))?;
+        Ok(idx)
+    }
+
+    fn create(&self, args: &LoadArgs) -> Result<pywr_core::parameters::DiurnalProfileParameter, SchemaError> {
+        let values = self.values.load(args.tables)?;
+        Ok(pywr_core::parameters::DiurnalProfileParameter::new(
+            self.meta.name.as_str().into(),
+            values,
+        )?)
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Copy, Clone, strum_macros::Display, JsonSchema, PywrVisitAll)]
 pub enum MonthlyInterpDay {
     First,
@@ -78,6 +189,8 @@ pub struct MonthlyProfileParameter {
     pub meta: ParameterMeta,
     pub values: ConstantFloatVec,
     pub interp_day: Option<MonthlyInterpDay>,
+    /// The number of days in February when interpolating. By default this is 29 in leap years.
+    pub leap_day_policy: Option<LeapDayPolicy>,
 }
 
 #[cfg(feature = "core")]
@@ -87,13 +200,33 @@ impl MonthlyProfileParameter {
         network: &mut pywr_core::network::Network,
         args: &LoadArgs,
     ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        Ok(network.add_simple_parameter(Box::new(p))?)
+    }
+
+    /// Replace the parameter of the same name in a network that has already been built.
+    pub fn replace_in_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        let idx = network.get_parameter_index_by_name(p.name())?;
+        network.replace_simple_parameter(idx, Box::new(p))?;
+        Ok(idx)
+    }
+
+    fn create(&self, args: &LoadArgs) -> Result<pywr_core::parameters::MonthlyProfileParameter, SchemaError> {
         let values = &self.values.load(args.tables)?[..12];
-        let p = pywr_core::parameters::MonthlyProfileParameter::new(
+        let mut p = pywr_core::parameters::MonthlyProfileParameter::new(
             self.meta.name.as_str().into(),
             values.try_into().expect(""),
             self.interp_day.map(|id| id.into()),
         );
-        Ok(network.add_simple_parameter(Box::new(p))?)
+        if let Some(leap_day_policy) = self.leap_day_policy {
+            p = p.with_leap_day_policy(leap_day_policy.into());
+        }
+        Ok(p)
     }
 }
 
@@ -123,6 +256,7 @@ impl TryFromV1<MonthlyProfileParameterV1> for MonthlyProfileParameter {
             meta,
             values,
             interp_day,
+            leap_day_policy: None,
         };
         Ok(p)
     }
@@ -135,6 +269,9 @@ pub struct UniformDrawdownProfileParameter {
     pub reset_day: Option<ConstantValue<u64>>,
     pub reset_month: Option<ConstantValue<u64>>,
     pub residual_days: Option<ConstantValue<u64>>,
+    /// Whether the 29th February is counted in the period. By default periods that contain the
+    /// 29th February have 366 days.
+    pub leap_day_policy: Option<LeapDayPolicy>,
 }
 
 #[cfg(feature = "core")]
@@ -144,6 +281,23 @@ impl UniformDrawdownProfileParameter {
         network: &mut pywr_core::network::Network,
         args: &LoadArgs,
     ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        Ok(network.add_simple_parameter(Box::new(p))?)
+    }
+
+    /// Replace the parameter of the same name in a network that has already been built.
+    pub fn replace_in_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        let idx = network.get_parameter_index_by_name(p.name())?;
+        network.replace_simple_parameter(idx, Box::new(p))?;
+        Ok(idx)
+    }
+
+    fn create(&self, args: &LoadArgs) -> Result<pywr_core::parameters::UniformDrawdownProfileParameter, SchemaError> {
         let reset_day = match &self.reset_day {
             Some(v) => v.load(args.tables)? as u32,
             None => 1,
@@ -157,13 +311,16 @@ impl UniformDrawdownProfileParameter {
             None => 0,
         };
 
-        let p = pywr_core::parameters::UniformDrawdownProfileParameter::new(
+        let mut p = pywr_core::parameters::UniformDrawdownProfileParameter::new(
             self.meta.name.as_str().into(),
             reset_day,
             reset_month,
             residual_days,
         );
-        Ok(network.add_simple_parameter(Box::new(p))?)
+        if let Some(leap_day_policy) = self.leap_day_policy {
+            p = p.with_leap_day_policy(leap_day_policy.into());
+        }
+        Ok(p)
     }
 }
 
@@ -180,6 +337,7 @@ impl FromV1<UniformDrawdownProfileParameterV1> for UniformDrawdownProfileParamet
             reset_day: v1.reset_day.map(|v| ConstantValue::Literal(v as u64)),
             reset_month: v1.reset_day.map(|v| ConstantValue::Literal(v as u64)),
             residual_days: v1.reset_day.map(|v| ConstantValue::Literal(v as u64)),
+            leap_day_policy: None,
         }
     }
 }
@@ -325,6 +483,10 @@ pub struct RbfProfileParameter {
     /// Optional settings for configuring how the value of this parameter can be varied. This
     /// is used by, for example, external algorithms to optimise the value of the parameter.
     pub variable: Option<RbfProfileVariableSettings>,
+    /// How the value of the 29th February is found. The default is
+    /// [`LeapDayPolicy::RepeatFeb28`]; with [`LeapDayPolicy::Profile366`] the radial basis
+    /// function is evaluated half way between the 28th February and the 1st March.
+    pub leap_day_policy: Option<LeapDayPolicy>,
 }
 
 #[cfg(feature = "core")]
@@ -337,6 +499,10 @@ impl RbfProfileParameter {
             self.points.clone(),
             function,
         );
+        let p = match self.leap_day_policy {
+            Some(leap_day_policy) => p.with_leap_day_policy(leap_day_policy.into()),
+            None => p,
+        };
         Ok(network.add_simple_parameter(Box::new(p))?)
     }
 }
@@ -431,6 +597,7 @@ impl TryFromV1<RbfProfileParameterV1> for RbfProfileParameter {
             points,
             function,
             variable: None, // TODO convert variable settings
+            leap_day_policy: None,
         };
 
         Ok(p)
@@ -520,6 +687,8 @@ pub struct WeeklyProfileParameter {
     pub meta: ParameterMeta,
     pub values: ConstantFloatVec,
     pub interp_day: Option<WeeklyInterpDay>,
+    /// How the 29th February is placed in the weeks. By default the weeks follow the calendar.
+    pub leap_day_policy: Option<LeapDayPolicy>,
 }
 
 #[cfg(feature = "core")]
@@ -529,7 +698,24 @@ impl WeeklyProfileParameter {
         network: &mut pywr_core::network::Network,
         args: &LoadArgs,
     ) -> Result<ParameterIndex<f64>, SchemaError> {
-        let p = pywr_core::parameters::WeeklyProfileParameter::new(
+        let p = self.create(args)?;
+        Ok(network.add_simple_parameter(Box::new(p))?)
+    }
+
+    /// Replace the parameter of the same name in a network that has already been built.
+    pub fn replace_in_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let p = self.create(args)?;
+        let idx = network.get_parameter_index_by_name(p.name())?;
+        network.replace_simple_parameter(idx, Box::new(p))?;
+        Ok(idx)
+    }
+
+    fn create(&self, args: &LoadArgs) -> Result<pywr_core::parameters::WeeklyProfileParameter, SchemaError> {
+        let mut p = pywr_core::parameters::WeeklyProfileParameter::new(
             self.meta.name.as_str().into(),
             WeeklyProfileValues::try_from(self.values.load(args.tables)?.as_slice()).map_err(
                 |err: WeeklyProfileError| SchemaError::LoadParameter {
@@ -539,7 +725,10 @@ impl WeeklyProfileParameter {
             )?,
             self.interp_day.map(|id| id.into()),
         );
-        Ok(network.add_simple_parameter(Box::new(p))?)
+        if let Some(leap_day_policy) = self.leap_day_policy {
+            p = p.with_leap_day_policy(leap_day_policy.into());
+        }
+        Ok(p)
     }
 }
 
@@ -560,6 +749,7 @@ impl TryFromV1<WeeklyProfileParameterV1> for WeeklyProfileParameter {
             meta,
             values,
             interp_day: None,
+            leap_day_policy: None,
         };
         Ok(p)
     }
@@ -0,0 +1,65 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+use crate::parameters::ParameterMeta;
+#[cfg(feature = "core")]
+use pywr_core::parameters::ParameterIndex;
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// The distribution that a [`RandomParameter`] samples from.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, JsonSchema, PywrVisitAll)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum RandomDistribution {
+    /// A uniform distribution between `lower` (inclusive) and `upper` (exclusive).
+    Uniform { lower: f64, upper: f64 },
+    /// A normal distribution with the given mean and standard deviation.
+    Normal { mean: f64, std_dev: f64 },
+}
+
+#[cfg(feature = "core")]
+impl From<RandomDistribution> for pywr_core::parameters::RandomDistribution {
+    fn from(d: RandomDistribution) -> Self {
+        match d {
+            RandomDistribution::Uniform { lower, upper } => Self::Uniform { lower, upper },
+            RandomDistribution::Normal { mean, std_dev } => Self::Normal { mean, std_dev },
+        }
+    }
+}
+
+/// A parameter that samples a new value from a random distribution each time-step.
+///
+/// The random numbers are determined by the `seed`. When the model defines `repeats`, each
+/// repeat uses a different stream of random numbers, while the other scenarios of the same
+/// repeat share the same values.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct RandomParameter {
+    pub meta: ParameterMeta,
+    pub distribution: RandomDistribution,
+    /// The seed of the random numbers. Defaults to zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+#[cfg(feature = "core")]
+impl RandomParameter {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<f64>, SchemaError> {
+        let mut p = pywr_core::parameters::RandomParameter::new(
+            self.meta.name.as_str().into(),
+            self.distribution.into(),
+            self.seed.unwrap_or_default(),
+        )?;
+
+        if let Some(group) = args.domain.scenarios().group_index(pywr_core::scenario::REPEAT_GROUP) {
+            p = p.with_repeat_group(group);
+        }
+
+        Ok(network.add_parameter(Box::new(p))?)
+    }
+}
@@ -5,7 +5,7 @@ use crate::model::LoadArgs;
 use crate::parameters::{ConversionData, ParameterMeta};
 use crate::v1::{FromV1, IntoV2};
 #[cfg(feature = "core")]
-use ndarray::s;
+use ndarray::{s, Axis};
 #[cfg(feature = "core")]
 use pywr_core::parameters::ParameterIndex;
 use pywr_schema_macros::PywrVisitAll;
@@ -56,10 +56,37 @@ impl TablesArrayParameter {
             .dataset(&self.node)
             .map_err(|e| SchemaError::HDF5Error(e.to_string()))?; // find the dataset
 
-        let array = ds.read_2d::<f64>().map_err(|e| SchemaError::HDF5Error(e.to_string()))?;
+        let mut array = ds.read_2d::<f64>().map_err(|e| SchemaError::HDF5Error(e.to_string()))?;
         // 2. TODO Validate the shape of the data array. I.e. check number of columns matches scenario
         //    and number of rows matches time-steps.
 
+        // The array has a row for every time-step, including those in any excluded periods. Select
+        // the rows of the simulated time-steps (applying the offset to the full time-steps) so
+        // that they are aligned with the re-indexed time-steps.
+        let mut timestep_offset = self.timestep_offset;
+        let time = args.domain.time();
+        if !time.excluded_periods().is_empty() {
+            let num_rows = array.len_of(Axis(0));
+            let rows: Vec<usize> = time
+                .full_indices()
+                .iter()
+                .map(|&i| match timestep_offset {
+                    None => i,
+                    Some(offset) => (i as i32 + offset).clamp(0, (num_rows as i32 - 1).max(0)) as usize,
+                })
+                .collect();
+
+            if rows.iter().any(|&i| i >= num_rows) {
+                return Err(SchemaError::DataLengthMismatch {
+                    expected: time.full_indices().last().map_or(0, |i| i + 1),
+                    found: num_rows,
+                });
+            }
+
+            array = array.select(Axis(0), &rows);
+            timestep_offset = None;
+        }
+
         // 3. Create an ArrayParameter using the loaded array.
         if let Some(scenario) = &self.scenario {
             let scenario_group_index = args
@@ -72,16 +99,12 @@ impl TablesArrayParameter {
                 self.meta.name.as_str().into(),
                 array,
                 scenario_group_index,
-                self.timestep_offset,
+                timestep_offset,
             );
             Ok(network.add_simple_parameter(Box::new(p))?)
         } else {
             let array = array.slice_move(s![.., 0]);
-            let p = pywr_core::parameters::Array1Parameter::new(
-                self.meta.name.as_str().into(),
-                array,
-                self.timestep_offset,
-            );
+            let p = pywr_core::parameters::Array1Parameter::new(self.meta.name.as_str().into(), array, timestep_offset);
             Ok(network.add_simple_parameter(Box::new(p))?)
         }
     }
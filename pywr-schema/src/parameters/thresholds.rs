@@ -8,7 +8,7 @@ use crate::parameters::{ConversionData, ParameterMeta};
 use crate::v1::{try_convert_parameter_attr, IntoV2, TryFromV1};
 use crate::ConversionError;
 #[cfg(feature = "core")]
-use pywr_core::parameters::{ParameterName, ParameterType};
+use pywr_core::parameters::{ParameterIndex, ParameterName, ParameterType};
 use pywr_schema_macros::PywrVisitAll;
 use pywr_v1_schema::parameters::{
     NodeThresholdParameter as NodeThresholdParameterV1, ParameterThresholdParameter as ParameterThresholdParameterV1,
@@ -143,6 +143,46 @@ impl ThresholdParameter {
     }
 }
 
+/// A parameter that counts the number of consecutive time-steps a predicate has been true.
+///
+/// Each time-step the `metric` is compared against the `threshold` using the `predicate`. If the
+/// comparison evaluates to true the count is incremented, otherwise it is reset to zero. The
+/// current count is returned as an index. This can be used to define rules such as "trigger after
+/// 10 consecutive days with a reservoir volume below 50%".
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct ConsecutiveCountParameter {
+    pub meta: ParameterMeta,
+    /// The metric to compare against the threshold.
+    pub metric: Metric,
+    /// The threshold to compare against.
+    pub threshold: Metric,
+    /// The comparison predicate. Should be one of `LT`, `GT`, `EQ`, `LE`, or `GE` or their equivalents `<`, `>`, `==`,
+    /// `<=` or `>=`.
+    pub predicate: Predicate,
+}
+
+#[cfg(feature = "core")]
+impl ConsecutiveCountParameter {
+    pub fn add_to_model(
+        &self,
+        network: &mut pywr_core::network::Network,
+        args: &LoadArgs,
+    ) -> Result<ParameterIndex<u64>, SchemaError> {
+        let metric = self.metric.load(network, args, None)?;
+        let threshold = self.threshold.load(network, args, None)?;
+
+        let p = pywr_core::parameters::ConsecutiveCountParameter::new(
+            self.meta.name.as_str().into(),
+            metric,
+            threshold,
+            self.predicate.into(),
+        );
+
+        Ok(network.add_index_parameter(Box::new(p))?)
+    }
+}
+
 impl TryFromV1<ParameterThresholdParameterV1> for ThresholdParameter {
     type Error = ComponentConversionError;
 
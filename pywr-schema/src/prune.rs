@@ -0,0 +1,351 @@
+//! An optional pass that simplifies a network before it is built.
+//!
+//! Networks generated automatically (e.g. from a national database of assets) often contain
+//! components that can not affect the results of a model, but which still add rows and columns
+//! to its linear program. [`PywrNetwork::prune`] removes:
+//!
+//!   1. Edges to or from an `Input`, `Link` or `Output` node with a constant maximum flow of zero,
+//!      and any such node that is left without edges.
+//!   2. `Link` nodes without any constraints or cost that have a single edge in and a single edge
+//!      out, which are replaced by an edge from their upstream to their downstream node.
+//!   3. Parameters that are not referenced by any other component.
+//!
+//! A component is only removed if its name is not used anywhere else in the network, so that
+//! components referenced by a metric, an output or another node (e.g. a virtual storage node)
+//! are always kept.
+use crate::edge::Edge;
+use crate::error::SchemaError;
+use crate::metric::Metric;
+use crate::model::PywrNetwork;
+use crate::nodes::{LinkNode, Node};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// The components removed from a network by [`PywrNetwork::prune`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PruneReport {
+    /// The edges removed because they connect to a node that can not have any flow.
+    pub zero_capacity_edges: Vec<String>,
+    /// The nodes removed because all of their edges were removed.
+    pub disconnected_nodes: Vec<String>,
+    /// The link nodes replaced by an edge from their upstream to their downstream node.
+    pub collapsed_links: Vec<String>,
+    /// The parameters removed because they are not referenced by any other component.
+    pub unreferenced_parameters: Vec<String>,
+}
+
+impl PruneReport {
+    /// Returns `true` if nothing was removed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of components removed.
+    pub fn len(&self) -> usize {
+        self.zero_capacity_edges.len()
+            + self.disconnected_nodes.len()
+            + self.collapsed_links.len()
+            + self.unreferenced_parameters.len()
+    }
+}
+
+impl Display for PruneReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "Nothing was pruned.");
+        }
+
+        let sections = [
+            ("Zero capacity edges", &self.zero_capacity_edges),
+            ("Disconnected nodes", &self.disconnected_nodes),
+            ("Collapsed link nodes", &self.collapsed_links),
+            ("Unreferenced parameters", &self.unreferenced_parameters),
+        ];
+
+        let lines: Vec<String> = sections
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(title, names)| format!("{title} ({}): {}", names.len(), names.join(", ")))
+            .collect();
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl PywrNetwork {
+    /// Remove the components of the network that can not affect the results of the model.
+    ///
+    /// This removes edges to or from simple nodes with a constant maximum flow of zero (and the
+    /// nodes if they are left without edges), replaces unconstrained `Link` nodes with a single
+    /// edge in and out by an edge from their upstream to their downstream node, and removes
+    /// parameters that are not referenced. Components whose names are used anywhere else in the
+    /// network are never removed. The passes are repeated until nothing else can be removed,
+    /// because removing a component may leave others unused.
+    ///
+    /// This is opt-in because the pruned components are no longer available to be recorded or
+    /// referenced after the model is built.
+    pub fn prune(&mut self) -> Result<PruneReport, SchemaError> {
+        let mut report = PruneReport::default();
+
+        loop {
+            let pruned = report.len();
+
+            self.prune_zero_capacity_edges(&mut report)?;
+            self.collapse_links(&mut report)?;
+            self.prune_unreferenced_parameters(&mut report)?;
+
+            if report.len() == pruned {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn prune_zero_capacity_edges(&mut self, report: &mut PruneReport) -> Result<(), SchemaError> {
+        let zero_capacity: HashSet<&str> = self
+            .nodes
+            .iter()
+            .filter(|n| is_zero_capacity(n))
+            .map(|n| n.name())
+            .collect();
+
+        if zero_capacity.is_empty() {
+            return Ok(());
+        }
+
+        let mut removed_from = HashSet::new();
+        self.edges.retain(|edge| {
+            let keep =
+                !zero_capacity.contains(edge.from_node.as_str()) && !zero_capacity.contains(edge.to_node.as_str());
+            if !keep {
+                report.zero_capacity_edges.push(edge.to_string());
+                removed_from.insert(edge.from_node.clone());
+                removed_from.insert(edge.to_node.clone());
+            }
+            keep
+        });
+
+        // Only the simple nodes are removed; other nodes may still be used without any edges.
+        let referenced = self.referenced_names()?;
+        let connected: HashSet<&str> = self
+            .edges
+            .iter()
+            .flat_map(|e| [e.from_node.as_str(), e.to_node.as_str()])
+            .collect();
+
+        self.nodes.retain(|node| {
+            let remove = matches!(node, Node::Input(_) | Node::Link(_) | Node::Output(_))
+                && removed_from.contains(node.name())
+                && !connected.contains(node.name())
+                && !referenced.contains(node.name());
+            if remove {
+                report.disconnected_nodes.push(node.name().to_string());
+            }
+            !remove
+        });
+
+        Ok(())
+    }
+
+    fn collapse_links(&mut self, report: &mut PruneReport) -> Result<(), SchemaError> {
+        let referenced = self.referenced_names()?;
+
+        let candidates: Vec<String> = self
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::Link(link) if is_pass_through(link) && !referenced.contains(link.meta.name.as_str()) => {
+                    Some(link.meta.name.clone())
+                }
+                _ => None,
+            })
+            .collect();
+
+        for name in candidates {
+            let incoming: Vec<usize> = (0..self.edges.len())
+                .filter(|i| self.edges[*i].to_node == name)
+                .collect();
+            let outgoing: Vec<usize> = (0..self.edges.len())
+                .filter(|i| self.edges[*i].from_node == name)
+                .collect();
+
+            let ([incoming], [outgoing]) = (incoming.as_slice(), outgoing.as_slice()) else {
+                continue;
+            };
+            let (incoming, outgoing) = (&self.edges[*incoming], &self.edges[*outgoing]);
+
+            // Link nodes do not have slots; an edge with a slot on the link is left for the
+            // network to report as an error.
+            if incoming.to_slot.is_some() || outgoing.from_slot.is_some() {
+                continue;
+            }
+
+            let edge = Edge {
+                from_node: incoming.from_node.clone(),
+                to_node: outgoing.to_node.clone(),
+                from_slot: incoming.from_slot.clone(),
+                to_slot: outgoing.to_slot.clone(),
+            };
+
+            // The link can not be collapsed if it would create a loop or duplicate an edge.
+            if edge.from_node == edge.to_node || self.edges.contains(&edge) {
+                continue;
+            }
+
+            self.edges.retain(|e| e.from_node != name && e.to_node != name);
+            self.edges.push(edge);
+            self.nodes.retain(|n| n.name() != name);
+            report.collapsed_links.push(name);
+        }
+
+        Ok(())
+    }
+
+    fn prune_unreferenced_parameters(&mut self, report: &mut PruneReport) -> Result<(), SchemaError> {
+        let referenced = self.referenced_names()?;
+
+        if let Some(parameters) = self.parameters.as_mut() {
+            parameters.retain(|parameter| {
+                let keep = referenced.contains(parameter.name());
+                if !keep {
+                    report.unreferenced_parameters.push(parameter.name().to_string());
+                }
+                keep
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The strings used in the network, other than the names of its top-level components and
+    /// the nodes of its edges.
+    ///
+    /// Any component whose name is in this set may be referenced by another component.
+    fn referenced_names(&self) -> Result<HashSet<String>, SchemaError> {
+        let document = serde_json::to_value(self)?;
+        let mut names = HashSet::new();
+
+        let Value::Object(sections) = &document else {
+            return Ok(names);
+        };
+
+        for (key, section) in sections {
+            if key == "edges" {
+                continue;
+            }
+
+            match section {
+                Value::Array(components) => {
+                    for component in components {
+                        match component {
+                            // The component's own name is not a reference to it
+                            Value::Object(fields) => fields
+                                .iter()
+                                .filter(|(key, _)| key.as_str() != "meta")
+                                .for_each(|(_, value)| collect_strings(value, &mut names)),
+                            value => collect_strings(value, &mut names),
+                        }
+                    }
+                }
+                value => collect_strings(value, &mut names),
+            }
+        }
+
+        Ok(names)
+    }
+}
+
+/// Returns `true` if `node` is a simple node with a constant maximum flow of zero.
+fn is_zero_capacity(node: &Node) -> bool {
+    let (max_flow, min_flow) = match node {
+        Node::Input(n) => (&n.max_flow, &n.min_flow),
+        Node::Link(n) => (&n.max_flow, &n.min_flow),
+        Node::Output(n) => (&n.max_flow, &n.min_flow),
+        _ => return false,
+    };
+
+    let is_zero = |metric: &Metric| matches!(metric, Metric::Constant { value } if *value == 0.0);
+    max_flow.as_ref().is_some_and(is_zero) && min_flow.as_ref().map_or(true, is_zero)
+}
+
+/// Returns `true` if `link` has no constraints, cost or local parameters.
+fn is_pass_through(link: &LinkNode) -> bool {
+    link.parameters.is_none()
+        && link.max_flow.is_none()
+        && link.min_flow.is_none()
+        && link.cost.is_none()
+        && link.soft_min.is_none()
+        && link.soft_max.is_none()
+}
+
+fn collect_strings(value: &Value, strings: &mut HashSet<String>) {
+    match value {
+        Value::String(s) => {
+            strings.insert(s.clone());
+        }
+        Value::Array(values) => values.iter().for_each(|v| collect_strings(v, strings)),
+        Value::Object(map) => map.values().for_each(|v| collect_strings(v, strings)),
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::PywrNetwork;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_prune() {
+        let data = r#"
+        {
+            "nodes": [
+                {"meta": {"name": "supply1"}, "type": "Input", "max_flow": {"type": "Constant", "value": 10.0}},
+                {"meta": {"name": "link1"}, "type": "Link"},
+                {"meta": {"name": "link2"}, "type": "Link"},
+                {"meta": {"name": "supply2"}, "type": "Input", "max_flow": {"type": "Constant", "value": 0.0}},
+                {"meta": {"name": "link3"}, "type": "Link"},
+                {
+                    "meta": {"name": "demand1"},
+                    "type": "Output",
+                    "max_flow": {"type": "Parameter", "name": "demand"},
+                    "cost": {"type": "Constant", "value": -10.0}
+                }
+            ],
+            "edges": [
+                {"from_node": "supply1", "to_node": "link1"},
+                {"from_node": "link1", "to_node": "link2"},
+                {"from_node": "link2", "to_node": "demand1"},
+                {"from_node": "supply2", "to_node": "demand1"}
+            ],
+            "parameters": [
+                {"meta": {"name": "demand"}, "type": "Constant", "value": 5.0},
+                {"meta": {"name": "unused"}, "type": "Max", "parameter": {"type": "Parameter", "name": "inner"}},
+                {"meta": {"name": "inner"}, "type": "Constant", "value": 1.0},
+                {"meta": {"name": "link2-flow"}, "type": "Max", "parameter": {"type": "Node", "name": "link2"}}
+            ],
+            "metric_sets": [
+                {"name": "flows", "metrics": [{"type": "Parameter", "name": "link2-flow"}]}
+            ]
+        }
+        "#;
+
+        let mut network = PywrNetwork::from_str(data).unwrap();
+        let report = network.prune().unwrap();
+
+        assert_eq!(report.zero_capacity_edges, vec!["supply2->demand1"]);
+        assert_eq!(report.disconnected_nodes, vec!["supply2"]);
+        // The isolated `link3` is not removed, and `link2` is referenced by a parameter
+        assert_eq!(report.collapsed_links, vec!["link1"]);
+        assert_eq!(report.unreferenced_parameters, vec!["unused", "inner"]);
+
+        let names: Vec<_> = network.nodes.iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["supply1", "link2", "link3", "demand1"]);
+        let edges: Vec<_> = network.edges.iter().map(|e| e.to_string()).collect();
+        assert_eq!(edges, vec!["link2->demand1", "supply1->link2"]);
+
+        // A second pass has nothing to remove
+        assert!(network.prune().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(feature = "core")]
+use crate::error::SchemaError;
+use crate::metric::{Metric, SimpleNodeReference};
+#[cfg(feature = "core")]
+use crate::model::LoadArgs;
+#[cfg(feature = "core")]
+use crate::nodes::Node;
+#[cfg(feature = "core")]
+use pywr_core::state_override::StateOverrideTarget;
+use pywr_schema_macros::PywrVisitAll;
+use schemars::JsonSchema;
+
+/// An adjustment to the volume of a storage node, or the flow of a link, at the end of each
+/// time-step.
+///
+/// State overrides allow a simulation to replay what actually happened; for example, by
+/// replacing the simulated volume of a reservoir with its observed volume, or the simulated flow
+/// of a transfer with its observed flow. After each solve the volume (or flow) of `node` is
+/// replaced by `value` in every time-step in which `active` is non-zero (or in every time-step
+/// if `active` is not given). This happens before any parameters or outputs see the state of
+/// the time-step. Overriding a flow does not change the flows of the nodes either side of the
+/// link.
+///
+/// The difference between the override and the simulated volume is the mass-balance residual
+/// of the override. It can be used elsewhere in the model, or recorded, using a
+/// `StateOverrideResidual` metric with the name of the override.
+///
+/// The node must be a `Storage`, `Reservoir`, `Link` or one of the virtual storage nodes.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, JsonSchema, PywrVisitAll)]
+#[serde(deny_unknown_fields)]
+pub struct StateOverride {
+    pub name: String,
+    pub node: SimpleNodeReference,
+    /// The volume, or for a `Link` the flow, of the node at the end of the time-step.
+    pub value: Metric,
+    /// Optional metric that determines whether the override is applied in each time-step.
+    pub active: Option<Metric>,
+}
+
+#[cfg(feature = "core")]
+impl StateOverride {
+    pub fn add_to_model(&self, network: &mut pywr_core::network::Network, args: &LoadArgs) -> Result<(), SchemaError> {
+        let node = args
+            .schema
+            .get_node_by_name(&self.node.name)
+            .ok_or_else(|| SchemaError::NodeNotFound(self.node.name.clone()))?;
+
+        let target = match node {
+            Node::Storage(_) | Node::Reservoir(_) => {
+                StateOverrideTarget::NodeVolume(network.get_node_index_by_name(&self.node.name, None)?)
+            }
+            Node::Link(_) => StateOverrideTarget::NodeFlow(network.get_node_index_by_name(&self.node.name, None)?),
+            Node::VirtualStorage(_)
+            | Node::AnnualVirtualStorage(_)
+            | Node::MonthlyVirtualStorage(_)
+            | Node::RollingVirtualStorage(_) => StateOverrideTarget::VirtualStorageVolume(
+                network.get_virtual_storage_node_index_by_name(&self.node.name, None)?,
+            ),
+            _ => {
+                return Err(SchemaError::StateOverrideNodeNotSupported {
+                    name: self.name.clone(),
+                    node: self.node.name.clone(),
+                    ty: node.node_type().to_string(),
+                })
+            }
+        };
+
+        let value = self.value.load(network, args, None)?;
+        let active = match &self.active {
+            Some(active) => Some(active.load(network, args, None)?),
+            None => None,
+        };
+
+        let state_override = pywr_core::state_override::StateOverride::new(&self.name, target, value, active);
+        network.add_state_override(state_override)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateOverride;
+
+    #[test]
+    fn test_state_override_schema() {
+        let data = r#"
+            {
+                "name": "observed-volume",
+                "node": {"name": "reservoir"},
+                "value": {"type": "Timeseries", "name": "observations", "columns": {"type": "Column", "name": "volume"}},
+                "active": {"type": "Timeseries", "name": "observations", "columns": {"type": "Column", "name": "observed"}}
+            }
+            "#;
+
+        let state_override: StateOverride = serde_json::from_str(data).unwrap();
+        assert_eq!(state_override.node.name, "reservoir");
+        assert!(state_override.active.is_some());
+    }
+}
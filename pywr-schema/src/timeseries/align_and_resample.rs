@@ -1,5 +1,6 @@
 use polars::{prelude::*, series::ops::NullBehavior};
 use pywr_core::models::ModelDomain;
+use pywr_core::timestep::{ExcludedPeriod, PywrDuration};
 use std::{cmp::Ordering, ops::Deref};
 
 use crate::timeseries::TimeseriesError;
@@ -23,14 +24,44 @@ pub fn align_and_resample(
         .collect()?
         .sort([time_col], sort_options)?;
 
+    let mut df = match domain.time().step_duration() {
+        Some(model_duration) => resample_to_duration(name, df, time_col, domain, model_duration)?,
+        None => resample_to_timesteps(name, df, time_col, domain)?,
+    };
+
+    if df.height() != domain.time().timesteps().len() {
+        return Err(TimeseriesError::DataFrameTimestepMismatch(name.to_string()));
+    }
+
+    if drop_time_col {
+        let _ = df.drop_in_place(time_col)?;
+    }
+
+    Ok(df)
+}
+
+/// Resample the data to the fixed duration of the model's time-steps.
+fn resample_to_duration(
+    name: &str,
+    df: DataFrame,
+    time_col: &str,
+    domain: &ModelDomain,
+    model_duration: PywrDuration,
+) -> Result<DataFrame, TimeseriesError> {
     // Ensure that df start aligns with models start for any resampling
     let df = slice_start(df, time_col, domain)?;
+    let df = remove_excluded_periods(df, time_col, domain)?;
 
-    // Get the durations of the time column
+    // Get the durations of the time column, ignoring the gaps left by any excluded periods
     let durations = df
         .clone()
         .lazy()
-        .select([col(time_col).diff(1, NullBehavior::Drop).unique().alias("duration")])
+        .select([
+            col(time_col).diff(1, NullBehavior::Ignore).alias("duration"),
+            spans_excluded_period(time_col, domain).alias("gap"),
+        ])
+        .filter(col("gap").not())
+        .select([col("duration").drop_nulls().unique()])
         .collect()?;
     let durations = durations.column("duration")?.duration()?.deref();
 
@@ -43,7 +74,6 @@ pub fn align_and_resample(
         None => return Err(TimeseriesError::TimeseriesDurationNotFound(name.to_string())),
     };
 
-    let model_duration = domain.time().step_duration();
     let model_duration_string = model_duration.duration_string();
 
     let df = match model_duration.milliseconds().cmp(&timeseries_duration) {
@@ -76,16 +106,75 @@ pub fn align_and_resample(
         Ordering::Equal => df,
     };
 
-    let mut df = slice_end(df, time_col, domain)?;
+    let df = slice_end(df, time_col, domain)?;
+    remove_excluded_periods(df, time_col, domain)
+}
 
-    if df.height() != domain.time().timesteps().len() {
-        return Err(TimeseriesError::DataFrameTimestepMismatch(name.to_string()));
+/// Resample the data to model time-steps of different durations (e.g. monthly time-steps).
+///
+/// The values of each time-step are the mean of the rows within it. An error is returned if any
+/// time-step does not contain a row (e.g. when the data are coarser than the time-steps or do not
+/// cover the whole model period).
+fn resample_to_timesteps(
+    name: &str,
+    df: DataFrame,
+    time_col: &str,
+    domain: &ModelDomain,
+) -> Result<DataFrame, TimeseriesError> {
+    let timesteps = domain.time().timesteps();
+
+    // The index of the time-step that contains each row, if any
+    let timestep_index: Vec<Option<u32>> = df
+        .column(time_col)?
+        .datetime()?
+        .as_datetime_iter()
+        .map(|date| {
+            let date = date?;
+            let i = timesteps.partition_point(|t| t.date <= date).checked_sub(1)?;
+            let timestep = &timesteps[i];
+            (date < timestep.duration + timestep.date).then_some(i as u32)
+        })
+        .collect();
+
+    let mut has_rows = vec![false; timesteps.len()];
+    for i in timestep_index.iter().flatten() {
+        has_rows[*i as usize] = true;
     }
-
-    if drop_time_col {
-        let _ = df.drop_in_place(time_col)?;
+    if let Some(i) = has_rows.iter().position(|has_row| !has_row) {
+        return Err(TimeseriesError::TimestepWithoutData {
+            name: name.to_string(),
+            date: timesteps[i].date,
+        });
     }
 
+    let mut df = df;
+    df.with_column(Column::new("timestep".into(), timestep_index))?;
+
+    let means = df
+        .lazy()
+        .filter(col("timestep").is_not_null())
+        .group_by([col("timestep")])
+        .agg([col("*").exclude([time_col, "timestep"]).mean()])
+        .collect()?;
+
+    let all_timesteps = df!(
+        "timestep" => (0..timesteps.len() as u32).collect::<Vec<_>>(),
+        time_col => timesteps.iter().map(|t| t.date).collect::<Vec<_>>()
+    )?;
+
+    let mut df = all_timesteps
+        .lazy()
+        .with_columns([col(time_col).cast(DataType::Datetime(TimeUnit::Milliseconds, None))])
+        .join(
+            means.lazy(),
+            [col("timestep")],
+            [col("timestep")],
+            JoinArgs::new(JoinType::Left),
+        )
+        .sort(["timestep"], SortMultipleOptions::default())
+        .collect()?;
+    let _ = df.drop_in_place("timestep")?;
+
     Ok(df)
 }
 
@@ -95,6 +184,37 @@ fn slice_start(df: DataFrame, time_col: &str, domain: &ModelDomain) -> Result<Da
     Ok(df)
 }
 
+/// Remove the rows within the periods that are excluded from the model's time domain.
+fn remove_excluded_periods(df: DataFrame, time_col: &str, domain: &ModelDomain) -> Result<DataFrame, TimeseriesError> {
+    let Some(excluded) = within_excluded_periods(col(time_col), domain.time().excluded_periods()) else {
+        return Ok(df);
+    };
+
+    let df = df.lazy().filter(excluded.not()).collect()?;
+    Ok(df)
+}
+
+/// An expression that is true for the times within any of the excluded periods, or `None` if
+/// there are no excluded periods.
+pub(crate) fn within_excluded_periods(time: Expr, excluded_periods: &[ExcludedPeriod]) -> Option<Expr> {
+    excluded_periods
+        .iter()
+        .map(|p| time.clone().gt_eq(lit(p.start)).and(time.clone().lt_eq(lit(p.end))))
+        .reduce(|a, b| a.or(b))
+}
+
+/// An expression that is true for the rows that follow a gap left by an excluded period.
+fn spans_excluded_period(time_col: &str, domain: &ModelDomain) -> Expr {
+    let previous = col(time_col).shift(lit(1));
+    domain
+        .time()
+        .excluded_periods()
+        .iter()
+        .map(|p| previous.clone().lt(lit(p.start)).and(col(time_col).gt(lit(p.end))))
+        .reduce(|a, b| a.or(b))
+        .unwrap_or(lit(false))
+}
+
 fn slice_end(df: DataFrame, time_col: &str, domain: &ModelDomain) -> Result<DataFrame, TimeseriesError> {
     let end = domain.time().last_timestep().date;
     let df = df.clone().lazy().filter(col(time_col).lt_eq(lit(end))).collect()?;
@@ -112,6 +232,7 @@ mod tests {
     };
 
     use crate::timeseries::align_and_resample::align_and_resample;
+    use crate::timeseries::TimeseriesError;
 
     #[test]
     fn test_downsample_and_slice() {
@@ -207,6 +328,83 @@ mod tests {
         assert!(resampled_values.equals(&expected_values));
     }
 
+    #[test]
+    fn test_resample_to_months() {
+        let start = NaiveDateTime::parse_from_str("2021-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let end = NaiveDateTime::parse_from_str("2021-03-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let timestep = TimestepDuration::Frequency("1mo".to_string());
+        let timestepper = Timestepper::new(start, end, timestep);
+        let time_domain = TimeDomain::try_from(timestepper).unwrap();
+
+        let scenario_domain: ScenarioDomain = ScenarioGroupCollection::new(vec![]).into();
+        let domain = ModelDomain::new(time_domain, scenario_domain);
+
+        let time = date_range(
+            "time".into(),
+            NaiveDate::from_ymd_opt(2021, 1, 1).unwrap().into(),
+            NaiveDate::from_ymd_opt(2021, 4, 30).unwrap().into(),
+            Duration::parse("1d"),
+            ClosedWindow::Both,
+            TimeUnit::Milliseconds,
+            None,
+        )
+        .unwrap();
+
+        let values: Vec<f64> = (1..121).map(|x| x as f64).collect();
+        let mut df = df!(
+            "time" => time,
+            "values" => values
+        )
+        .unwrap();
+
+        df = align_and_resample("test", df, "time", &domain, false).unwrap();
+
+        let expected_values = Column::new(
+            "values".into(),
+            vec![
+                16.0, // mean of 1 to 31
+                45.5, // mean of 32 to 59
+                75.0, // mean of 60 to 90
+            ],
+        );
+        let resampled_values = df.column("values").unwrap();
+        assert!(resampled_values.equals(&expected_values));
+    }
+
+    #[test]
+    fn test_resample_to_months_without_data() {
+        let start = NaiveDateTime::parse_from_str("2021-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let end = NaiveDateTime::parse_from_str("2021-03-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let timestep = TimestepDuration::Frequency("1mo".to_string());
+        let timestepper = Timestepper::new(start, end, timestep);
+        let time_domain = TimeDomain::try_from(timestepper).unwrap();
+
+        let scenario_domain: ScenarioDomain = ScenarioGroupCollection::new(vec![]).into();
+        let domain = ModelDomain::new(time_domain, scenario_domain);
+
+        // Data every 45 days leave March without any rows
+        let time = date_range(
+            "time".into(),
+            NaiveDate::from_ymd_opt(2021, 1, 1).unwrap().into(),
+            NaiveDate::from_ymd_opt(2021, 4, 30).unwrap().into(),
+            Duration::parse("45d"),
+            ClosedWindow::Both,
+            TimeUnit::Milliseconds,
+            None,
+        )
+        .unwrap();
+
+        let values: Vec<f64> = (0..time.len()).map(|x| x as f64).collect();
+        let df = df!(
+            "time" => time,
+            "values" => values
+        )
+        .unwrap();
+
+        let result = align_and_resample("test", df, "time", &domain, false);
+        assert!(matches!(result, Err(TimeseriesError::TimestepWithoutData { .. })));
+    }
+
     #[test]
     #[cfg(feature = "core")]
     fn test_no_resample_slice() {
@@ -0,0 +1,138 @@
+use crate::visit::VisitPaths;
+use schemars::JsonSchema;
+use std::path::{Path, PathBuf};
+
+/// A dataset that is read from a sheet of an Excel workbook.
+///
+/// The cells are selected with `sheet` and `range` in the same way as an
+/// [`crate::data_tables::ExcelDataTable`]. The first row of the cells is the header row and
+/// the remaining rows contain the data. The time column can contain either Excel dates or
+/// text; text is parsed using `date_format` if it is given, otherwise it must be an ISO 8601
+/// date (e.g. `2021-01-31`) or date and time.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+pub struct ExcelDataset {
+    pub time_col: Option<String>,
+    pub url: PathBuf,
+    /// The name of the sheet to read. Defaults to the first sheet of the workbook.
+    pub sheet: Option<String>,
+    /// A range of cells of the sheet (e.g. `A1:D366`) or the name of a named range defined in
+    /// the workbook.
+    pub range: Option<String>,
+    /// The format of dates given as text in the time column (e.g. `%d/%m/%Y`).
+    pub date_format: Option<String>,
+}
+
+impl VisitPaths for ExcelDataset {
+    fn visit_paths<F: FnMut(&Path)>(&self, visitor: &mut F) {
+        visitor(&self.url);
+    }
+
+    fn visit_paths_mut<F: FnMut(&mut PathBuf)>(&mut self, visitor: &mut F) {
+        visitor(&mut self.url);
+    }
+}
+
+#[cfg(feature = "core")]
+mod core {
+    use super::ExcelDataset;
+    use crate::data_tables::make_path;
+    use crate::excel::read_range;
+    use crate::timeseries::align_and_resample::align_and_resample;
+    use crate::timeseries::TimeseriesError;
+    use calamine::{Data, DataType};
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use polars::prelude::*;
+    use pywr_core::models::ModelDomain;
+    use std::path::Path;
+
+    impl ExcelDataset {
+        pub fn load(
+            &self,
+            name: &str,
+            data_path: Option<&Path>,
+            domain: &ModelDomain,
+        ) -> Result<DataFrame, TimeseriesError> {
+            let path = make_path(&self.url, data_path);
+            let cells = read_range(&path, self.sheet.as_deref(), self.range.as_deref())?;
+
+            let mut rows = cells.rows().filter(|row| !row.iter().all(|c| matches!(c, Data::Empty)));
+
+            let header: Vec<String> = rows
+                .next()
+                .ok_or_else(|| TimeseriesError::TimeseriesDataframeHasNoColumns(name.to_string()))?
+                .iter()
+                .map(|c| c.to_string())
+                .collect();
+
+            // If a time col has not been provided assume it is the first column
+            let time_col = match self.time_col {
+                Some(ref col) => col.clone(),
+                None => header[0].clone(),
+            };
+            let time_idx =
+                header
+                    .iter()
+                    .position(|c| *c == time_col)
+                    .ok_or_else(|| TimeseriesError::ColumnNotFound {
+                        col: time_col.clone(),
+                        name: name.to_string(),
+                    })?;
+
+            let mut dates: Vec<NaiveDateTime> = Vec::new();
+            let mut values: Vec<Vec<f64>> = vec![Vec::new(); header.len()];
+
+            for row in rows {
+                for (col_idx, cell) in row.iter().enumerate() {
+                    if col_idx == time_idx {
+                        let date = self.parse_date(cell).ok_or_else(|| TimeseriesError::InvalidCellValue {
+                            name: name.to_string(),
+                            col: header[col_idx].clone(),
+                            value: cell.to_string(),
+                        })?;
+                        dates.push(date);
+                    } else {
+                        let value = cell.as_f64().ok_or_else(|| TimeseriesError::InvalidCellValue {
+                            name: name.to_string(),
+                            col: header[col_idx].clone(),
+                            value: cell.to_string(),
+                        })?;
+                        values[col_idx].push(value);
+                    }
+                }
+            }
+
+            let columns: Vec<Column> = header
+                .iter()
+                .zip(values)
+                .enumerate()
+                .map(|(col_idx, (col, values))| {
+                    if col_idx == time_idx {
+                        Column::new(col.as_str().into(), dates.clone())
+                    } else {
+                        Column::new(col.as_str().into(), values)
+                    }
+                })
+                .collect();
+
+            let df = DataFrame::new(columns)?;
+
+            align_and_resample(name, df, &time_col, domain, true)
+        }
+
+        /// Parse the date of a cell of the time column.
+        fn parse_date(&self, cell: &Data) -> Option<NaiveDateTime> {
+            match (cell.get_string(), &self.date_format) {
+                (Some(text), Some(format)) => NaiveDateTime::parse_from_str(text, format).ok().or_else(|| {
+                    NaiveDate::parse_from_str(text, format)
+                        .ok()
+                        .map(|d| d.and_time(NaiveTime::default()))
+                }),
+                (Some(text), None) => text
+                    .parse::<NaiveDateTime>()
+                    .ok()
+                    .or_else(|| text.parse::<NaiveDate>().ok().map(|d| d.and_time(NaiveTime::default()))),
+                (None, _) => cell.as_datetime(),
+            }
+        }
+    }
+}
@@ -1,15 +1,23 @@
 #[cfg(feature = "core")]
 mod align_and_resample;
+mod database_dataset;
+mod excel_dataset;
+mod netcdf_dataset;
 mod pandas;
 mod polars_dataset;
 
 use crate::error::ComponentConversionError;
+#[cfg(feature = "core")]
+use crate::excel::ExcelError;
 use crate::parameters::ParameterMeta;
 use crate::v1::{ConversionData, IntoV2, TryFromV1};
 use crate::visit::VisitPaths;
 use crate::ConversionError;
+pub use database_dataset::DatabaseDataset;
+pub use excel_dataset::ExcelDataset;
 #[cfg(feature = "core")]
 use ndarray::Array2;
+pub use netcdf_dataset::NetCdfDataset;
 pub use pandas::PandasDataset;
 #[cfg(feature = "core")]
 use polars::error::PolarsError;
@@ -19,11 +27,13 @@ use polars::prelude::{
     DataType::{Float64, UInt64},
     Float64Type, IndexOrder, UInt64Type,
 };
+#[cfg(feature = "core")]
+use polars_dataset::ChunkedTimeseries;
 pub use polars_dataset::PolarsDataset;
 #[cfg(feature = "core")]
 use pywr_core::{
     models::ModelDomain,
-    parameters::{Array1Parameter, Array2Parameter, ParameterIndex, ParameterName},
+    parameters::{Array1Parameter, Array2Parameter, ParameterIndex, ParameterName, SimpleParameter},
     PywrError,
 };
 use pywr_v1_schema::parameters::DataFrameParameter as DataFrameParameterV1;
@@ -49,6 +59,8 @@ pub enum TimeseriesError {
     ScenarioGroupNotFound(String),
     #[error("The length of the resampled timeseries dataframe '{0}' does not match the number of model timesteps.")]
     DataFrameTimestepMismatch(String),
+    #[error("The timeseries '{name}' has no data in the time-step starting {date}.")]
+    TimestepWithoutData { name: String, date: chrono::NaiveDateTime },
     #[error("A timeseries dataframe with the name '{0}' already exists.")]
     TimeseriesDataframeAlreadyExists(String),
     #[error("The timeseries dataset '{0}' has more than one column of data so a column or scenario name must be provided for any reference"
@@ -56,15 +68,40 @@ pub enum TimeseriesError {
     TimeseriesColumnOrScenarioRequired(String),
     #[error("The timeseries dataset '{0}' has no columns")]
     TimeseriesDataframeHasNoColumns(String),
+    #[error("The separator '{separator}' of timeseries '{name}' must be an ASCII character")]
+    InvalidCsvSeparator { name: String, separator: char },
+    #[error("The timeseries dataset '{0}' is in the long format so must have a single column of values")]
+    LongFormatValueColumn(String),
+    #[error("The timeseries dataset '{0}' is in the long format which can not be read in chunks")]
+    ChunkedLongFormatNotSupported(String),
+    #[error("The value '{value}' in column '{col}' of timeseries '{name}' is not valid")]
+    InvalidCellValue { name: String, col: String, value: String },
+    #[error("The NetCDF file of timeseries '{name}' can not be read: {reason}")]
+    InvalidNetCdf { name: String, reason: String },
     #[error("Polars error: {0}")]
     #[cfg(feature = "core")]
     PolarsError(#[from] PolarsError),
+    #[error("Excel error: {0}")]
+    #[cfg(feature = "core")]
+    Excel(#[from] ExcelError),
     #[cfg(feature = "core")]
     #[error("Pywr core error: {0}")]
     PywrCore(#[from] PywrError),
     #[cfg(feature = "core")]
     #[error("Python not enabled.")]
     PythonNotEnabled,
+    #[cfg(feature = "core")]
+    #[error("Database timeseries are not enabled; Pywr must be built with the `database` feature.")]
+    DatabaseNotEnabled,
+    #[cfg(feature = "database")]
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[cfg(feature = "core")]
+    #[error("NetCDF timeseries are not enabled; Pywr must be built with the `netcdf` feature.")]
+    NetCdfNotEnabled,
+    #[cfg(feature = "netcdf")]
+    #[error("NetCDF error: {0}")]
+    NetCdf(#[from] netcdf::Error),
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
@@ -72,6 +109,9 @@ pub enum TimeseriesError {
 pub enum TimeseriesProvider {
     Pandas(PandasDataset),
     Polars(PolarsDataset),
+    Excel(ExcelDataset),
+    Database(DatabaseDataset),
+    NetCdf(NetCdfDataset),
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
@@ -87,6 +127,9 @@ impl Timeseries {
         match &self.provider {
             TimeseriesProvider::Polars(dataset) => dataset.load(self.meta.name.as_str(), data_path, domain),
             TimeseriesProvider::Pandas(dataset) => dataset.load(self.meta.name.as_str(), data_path, domain),
+            TimeseriesProvider::Excel(dataset) => dataset.load(self.meta.name.as_str(), data_path, domain),
+            TimeseriesProvider::Database(dataset) => dataset.load(self.meta.name.as_str(), data_path, domain),
+            TimeseriesProvider::NetCdf(dataset) => dataset.load(self.meta.name.as_str(), data_path, domain),
         }
     }
 
@@ -100,6 +143,9 @@ impl VisitPaths for Timeseries {
         match &self.provider {
             TimeseriesProvider::Polars(dataset) => dataset.visit_paths(visitor),
             TimeseriesProvider::Pandas(dataset) => dataset.visit_paths(visitor),
+            TimeseriesProvider::Excel(dataset) => dataset.visit_paths(visitor),
+            TimeseriesProvider::Database(dataset) => dataset.visit_paths(visitor),
+            TimeseriesProvider::NetCdf(dataset) => dataset.visit_paths(visitor),
         }
     }
 
@@ -107,6 +153,70 @@ impl VisitPaths for Timeseries {
         match &mut self.provider {
             TimeseriesProvider::Polars(dataset) => dataset.visit_paths_mut(visitor),
             TimeseriesProvider::Pandas(dataset) => dataset.visit_paths_mut(visitor),
+            TimeseriesProvider::Excel(dataset) => dataset.visit_paths_mut(visitor),
+            TimeseriesProvider::Database(dataset) => dataset.visit_paths_mut(visitor),
+            TimeseriesProvider::NetCdf(dataset) => dataset.visit_paths_mut(visitor),
+        }
+    }
+}
+
+/// A timeseries dataset that has been loaded ready to create parameters from.
+#[cfg(feature = "core")]
+enum LoadedTimeseries {
+    /// The whole dataset loaded in to memory.
+    DataFrame(DataFrame),
+    /// A dataset that is read in chunks during the simulation.
+    Chunked(ChunkedTimeseries),
+}
+
+#[cfg(feature = "core")]
+impl LoadedTimeseries {
+    /// Return the name of the only column of data in the dataset.
+    fn single_column(&self, name: &str) -> Result<String, TimeseriesError> {
+        let cols: Vec<String> = match self {
+            Self::DataFrame(df) => df.get_column_names().into_iter().map(|c| c.to_string()).collect(),
+            Self::Chunked(ts) => ts.columns().to_vec(),
+        };
+
+        if cols.len() > 1 {
+            return Err(TimeseriesError::TimeseriesColumnOrScenarioRequired(name.to_string()));
+        };
+
+        cols.into_iter().next().ok_or(TimeseriesError::ColumnNotFound {
+            col: "".to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// Create a parameter for a single column of the dataset.
+    fn column_parameter_f64(
+        &self,
+        name: ParameterName,
+        col: &str,
+    ) -> Result<Box<dyn SimpleParameter<f64>>, TimeseriesError> {
+        match self {
+            Self::DataFrame(df) => {
+                let series = df.column(col)?;
+                let array = series.cast(&Float64)?.f64()?.to_ndarray()?.to_owned();
+                Ok(Box::new(Array1Parameter::new(name, array, None)))
+            }
+            Self::Chunked(ts) => Ok(Box::new(ts.parameter::<f64>(name, &[col.to_string()], None)?)),
+        }
+    }
+
+    /// Create an index parameter for a single column of the dataset.
+    fn column_parameter_usize(
+        &self,
+        name: ParameterName,
+        col: &str,
+    ) -> Result<Box<dyn SimpleParameter<u64>>, TimeseriesError> {
+        match self {
+            Self::DataFrame(df) => {
+                let series = df.column(col)?;
+                let array = series.cast(&UInt64)?.u64()?.to_ndarray()?.to_owned();
+                Ok(Box::new(Array1Parameter::new(name, array, None)))
+            }
+            Self::Chunked(ts) => Ok(Box::new(ts.parameter::<u64>(name, &[col.to_string()], None)?)),
         }
     }
 }
@@ -114,7 +224,7 @@ impl VisitPaths for Timeseries {
 #[derive(Default)]
 #[cfg(feature = "core")]
 pub struct LoadedTimeseriesCollection {
-    timeseries: HashMap<String, DataFrame>,
+    timeseries: HashMap<String, LoadedTimeseries>,
 }
 
 #[cfg(feature = "core")]
@@ -127,37 +237,47 @@ impl LoadedTimeseriesCollection {
         let mut timeseries = HashMap::new();
         if let Some(timeseries_defs) = timeseries_defs {
             for ts in timeseries_defs {
-                let df = ts.load(domain, data_path)?;
+                let loaded = match &ts.provider {
+                    TimeseriesProvider::Polars(dataset) => match dataset.chunk_size {
+                        Some(chunk_size) => {
+                            LoadedTimeseries::Chunked(dataset.load_chunked(ts.name(), data_path, domain, chunk_size)?)
+                        }
+                        None => LoadedTimeseries::DataFrame(ts.load(domain, data_path)?),
+                    },
+                    TimeseriesProvider::Pandas(_) | TimeseriesProvider::Excel(_) => {
+                        LoadedTimeseries::DataFrame(ts.load(domain, data_path)?)
+                    }
+                };
                 if timeseries.contains_key(&ts.meta.name) {
                     return Err(TimeseriesError::TimeseriesDataframeAlreadyExists(ts.meta.name.clone()));
                 }
-                timeseries.insert(ts.meta.name.clone(), df);
+                timeseries.insert(ts.meta.name.clone(), loaded);
             }
         }
         Ok(Self { timeseries })
     }
 
+    fn get(&self, name: &str) -> Result<&LoadedTimeseries, TimeseriesError> {
+        self.timeseries
+            .get(name)
+            .ok_or(TimeseriesError::TimeseriesNotFound(name.to_string()))
+    }
+
     pub fn load_column_f64(
         &self,
         network: &mut pywr_core::network::Network,
         name: &str,
         col: &str,
     ) -> Result<ParameterIndex<f64>, TimeseriesError> {
-        let df = self
-            .timeseries
-            .get(name)
-            .ok_or(TimeseriesError::TimeseriesNotFound(name.to_string()))?;
-        let series = df.column(col)?;
-
-        let array = series.cast(&Float64)?.f64()?.to_ndarray()?.to_owned();
+        let ts = self.get(name)?;
         let name = ParameterName::new(col, Some(name));
 
         match network.get_parameter_index_by_name(&name) {
             Ok(idx) => Ok(idx),
             Err(e) => match e {
                 PywrError::ParameterNotFound(_) => {
-                    let p = Array1Parameter::new(name, array, None);
-                    Ok(network.add_simple_parameter(Box::new(p))?)
+                    let p = ts.column_parameter_f64(name, col)?;
+                    Ok(network.add_simple_parameter(p)?)
                 }
                 _ => Err(TimeseriesError::PywrCore(e)),
             },
@@ -170,21 +290,15 @@ impl LoadedTimeseriesCollection {
         name: &str,
         col: &str,
     ) -> Result<ParameterIndex<u64>, TimeseriesError> {
-        let df = self
-            .timeseries
-            .get(name)
-            .ok_or(TimeseriesError::TimeseriesNotFound(name.to_string()))?;
-        let series = df.column(col)?;
-
-        let array = series.cast(&UInt64)?.u64()?.to_ndarray()?.to_owned();
+        let ts = self.get(name)?;
         let name = ParameterName::new(col, Some(name));
 
         match network.get_index_parameter_index_by_name(&name) {
             Ok(idx) => Ok(idx),
             Err(e) => match e {
                 PywrError::ParameterNotFound(_) => {
-                    let p = Array1Parameter::new(name, array, None);
-                    Ok(network.add_simple_index_parameter(Box::new(p))?)
+                    let p = ts.column_parameter_usize(name, col)?;
+                    Ok(network.add_simple_index_parameter(p)?)
                 }
                 _ => Err(TimeseriesError::PywrCore(e)),
             },
@@ -196,33 +310,16 @@ impl LoadedTimeseriesCollection {
         network: &mut pywr_core::network::Network,
         name: &str,
     ) -> Result<ParameterIndex<f64>, TimeseriesError> {
-        let df = self
-            .timeseries
-            .get(name)
-            .ok_or(TimeseriesError::TimeseriesNotFound(name.to_string()))?;
-
-        let cols = df.get_column_names();
-
-        if cols.len() > 1 {
-            return Err(TimeseriesError::TimeseriesColumnOrScenarioRequired(name.to_string()));
-        };
-
-        let col = cols.first().ok_or(TimeseriesError::ColumnNotFound {
-            col: "".to_string(),
-            name: name.to_string(),
-        })?;
-
-        let series = df.column(col)?;
-
-        let array = series.cast(&Float64)?.f64()?.to_ndarray()?.to_owned();
-        let name = ParameterName::new(col, Some(name));
+        let ts = self.get(name)?;
+        let col = ts.single_column(name)?;
+        let name = ParameterName::new(&col, Some(name));
 
         match network.get_parameter_index_by_name(&name) {
             Ok(idx) => Ok(idx),
             Err(e) => match e {
                 PywrError::ParameterNotFound(_) => {
-                    let p = Array1Parameter::new(name, array, None);
-                    Ok(network.add_simple_parameter(Box::new(p))?)
+                    let p = ts.column_parameter_f64(name, &col)?;
+                    Ok(network.add_simple_parameter(p)?)
                 }
                 _ => Err(TimeseriesError::PywrCore(e)),
             },
@@ -234,33 +331,16 @@ impl LoadedTimeseriesCollection {
         network: &mut pywr_core::network::Network,
         name: &str,
     ) -> Result<ParameterIndex<u64>, TimeseriesError> {
-        let df = self
-            .timeseries
-            .get(name)
-            .ok_or(TimeseriesError::TimeseriesNotFound(name.to_string()))?;
-
-        let cols = df.get_column_names();
-
-        if cols.len() > 1 {
-            return Err(TimeseriesError::TimeseriesColumnOrScenarioRequired(name.to_string()));
-        };
-
-        let col = cols.first().ok_or(TimeseriesError::ColumnNotFound {
-            col: "".to_string(),
-            name: name.to_string(),
-        })?;
-
-        let series = df.column(col)?;
-
-        let array = series.cast(&UInt64)?.u64()?.to_ndarray()?.to_owned();
-        let name = ParameterName::new(col, Some(name));
+        let ts = self.get(name)?;
+        let col = ts.single_column(name)?;
+        let name = ParameterName::new(&col, Some(name));
 
         match network.get_index_parameter_index_by_name(&name) {
             Ok(idx) => Ok(idx),
             Err(e) => match e {
                 PywrError::ParameterNotFound(_) => {
-                    let p = Array1Parameter::new(name, array, None);
-                    Ok(network.add_simple_index_parameter(Box::new(p))?)
+                    let p = ts.column_parameter_usize(name, &col)?;
+                    Ok(network.add_simple_index_parameter(p)?)
                 }
                 _ => Err(TimeseriesError::PywrCore(e)),
             },
@@ -280,20 +360,23 @@ impl LoadedTimeseriesCollection {
             .group_index(scenario)
             .ok_or(TimeseriesError::ScenarioGroupNotFound(scenario.to_string()))?;
 
-        let df = self
-            .timeseries
-            .get(name)
-            .ok_or(TimeseriesError::TimeseriesNotFound(name.to_string()))?;
-
-        let array: Array2<f64> = df.to_ndarray::<Float64Type>(IndexOrder::default()).unwrap();
+        let ts = self.get(name)?;
         let name = ParameterName::new(scenario, Some(name));
 
         match network.get_parameter_index_by_name(&name) {
             Ok(idx) => Ok(idx),
             Err(e) => match e {
                 PywrError::ParameterNotFound(_) => {
-                    let p = Array2Parameter::new(name, array, scenario_group_index, None);
-                    Ok(network.add_simple_parameter(Box::new(p))?)
+                    let p: Box<dyn SimpleParameter<f64>> = match ts {
+                        LoadedTimeseries::DataFrame(df) => {
+                            let array: Array2<f64> = df.to_ndarray::<Float64Type>(IndexOrder::default())?;
+                            Box::new(Array2Parameter::new(name, array, scenario_group_index, None))
+                        }
+                        LoadedTimeseries::Chunked(ts) => {
+                            Box::new(ts.parameter::<f64>(name, ts.columns(), Some(scenario_group_index))?)
+                        }
+                    };
+                    Ok(network.add_simple_parameter(p)?)
                 }
                 _ => Err(TimeseriesError::PywrCore(e)),
             },
@@ -313,20 +396,23 @@ impl LoadedTimeseriesCollection {
             .group_index(scenario)
             .ok_or(TimeseriesError::ScenarioGroupNotFound(scenario.to_string()))?;
 
-        let df = self
-            .timeseries
-            .get(name)
-            .ok_or(TimeseriesError::TimeseriesNotFound(name.to_string()))?;
-
-        let array: Array2<u64> = df.to_ndarray::<UInt64Type>(IndexOrder::default()).unwrap();
+        let ts = self.get(name)?;
         let name = ParameterName::new(scenario, Some(name));
 
         match network.get_index_parameter_index_by_name(&name) {
             Ok(idx) => Ok(idx),
             Err(e) => match e {
                 PywrError::ParameterNotFound(_) => {
-                    let p = Array2Parameter::new(name, array, scenario_group_index, None);
-                    Ok(network.add_simple_index_parameter(Box::new(p))?)
+                    let p: Box<dyn SimpleParameter<u64>> = match ts {
+                        LoadedTimeseries::DataFrame(df) => {
+                            let array: Array2<u64> = df.to_ndarray::<UInt64Type>(IndexOrder::default())?;
+                            Box::new(Array2Parameter::new(name, array, scenario_group_index, None))
+                        }
+                        LoadedTimeseries::Chunked(ts) => {
+                            Box::new(ts.parameter::<u64>(name, ts.columns(), Some(scenario_group_index))?)
+                        }
+                    };
+                    Ok(network.add_simple_index_parameter(p)?)
                 }
                 _ => Err(TimeseriesError::PywrCore(e)),
             },
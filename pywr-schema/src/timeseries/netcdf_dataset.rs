@@ -0,0 +1,357 @@
+use crate::visit::VisitPaths;
+use schemars::JsonSchema;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A dataset that is read from a variable of a NetCDF file.
+///
+/// This is intended for the output of climate models, which is usually distributed as NetCDF
+/// files following the CF conventions. The `variable` must have a time dimension, and may have
+/// an ensemble dimension whose members become the columns of the dataset in the order of the
+/// file. A timeseries with an ensemble can then be used with a scenario group of the same size,
+/// with the first member used for the first scenario of the group and so on. The columns are
+/// named after the values of the ensemble's coordinate variable, if it has one, or otherwise
+/// the position of the member (e.g. `0`).
+///
+/// Any other dimensions of the variable (e.g. the latitude and longitude of gridded data) must
+/// be given the position to read in `indices`, unless they are of length one. The dates are
+/// decoded from the `units` (e.g. `days since 1970-01-01`) and `calendar` of the time's
+/// coordinate variable; the `standard`, `gregorian`, `proleptic_gregorian`, `noleap` and
+/// `365_day` calendars are supported. The `scale_factor` and `add_offset` of packed data are
+/// applied to the values.
+///
+/// This requires Pywr to be built with the `netcdf` feature.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NetCdfDataset {
+    pub url: PathBuf,
+    /// The name of the variable to read.
+    pub variable: String,
+    /// The name of the time dimension. Defaults to `time`.
+    pub time_dim: Option<String>,
+    /// The name of the dimension of the ensemble members, if any.
+    pub ensemble_dim: Option<String>,
+    /// The position to read along each of the other dimensions of the variable.
+    pub indices: Option<BTreeMap<String, usize>>,
+}
+
+impl VisitPaths for NetCdfDataset {
+    fn visit_paths<F: FnMut(&Path)>(&self, visitor: &mut F) {
+        visitor(&self.url);
+    }
+
+    fn visit_paths_mut<F: FnMut(&mut PathBuf)>(&mut self, visitor: &mut F) {
+        visitor(&mut self.url);
+    }
+}
+
+#[cfg(all(feature = "core", not(feature = "netcdf")))]
+mod core {
+    use super::NetCdfDataset;
+    use crate::timeseries::TimeseriesError;
+    use polars::frame::DataFrame;
+    use pywr_core::models::ModelDomain;
+    use std::path::Path;
+
+    impl NetCdfDataset {
+        pub fn load(
+            &self,
+            _name: &str,
+            _data_path: Option<&Path>,
+            _domain: &ModelDomain,
+        ) -> Result<DataFrame, TimeseriesError> {
+            Err(TimeseriesError::NetCdfNotEnabled)
+        }
+    }
+}
+
+#[cfg(all(feature = "core", feature = "netcdf"))]
+mod core {
+    use super::NetCdfDataset;
+    use crate::data_tables::make_path;
+    use crate::timeseries::align_and_resample::align_and_resample;
+    use crate::timeseries::TimeseriesError;
+    use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+    use netcdf::{AttributeValue, Extent, Variable};
+    use polars::prelude::*;
+    use pywr_core::models::ModelDomain;
+    use std::path::Path;
+
+    const DEFAULT_TIME_DIM: &str = "time";
+    const SECONDS_PER_DAY: f64 = 86400.0;
+    /// The number of days in a year before the start of each month, ignoring leap days.
+    const DAYS_BEFORE_MONTH: [i64; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+    impl NetCdfDataset {
+        pub fn load(
+            &self,
+            name: &str,
+            data_path: Option<&Path>,
+            domain: &ModelDomain,
+        ) -> Result<DataFrame, TimeseriesError> {
+            let invalid = |reason: String| TimeseriesError::InvalidNetCdf {
+                name: name.to_string(),
+                reason,
+            };
+
+            let path = make_path(&self.url, data_path);
+            let file = netcdf::open(&path)?;
+
+            let variable = file
+                .variable(&self.variable)
+                .ok_or_else(|| invalid(format!("variable `{}` not found", self.variable)))?;
+            let time_dim = self.time_dim.as_deref().unwrap_or(DEFAULT_TIME_DIM);
+
+            let dims: Vec<(String, usize)> = variable.dimensions().iter().map(|d| (d.name(), d.len())).collect();
+            let position = |dim: &str| {
+                dims.iter()
+                    .position(|(n, _)| n == dim)
+                    .ok_or_else(|| invalid(format!("variable `{}` has no dimension `{dim}`", self.variable)))
+            };
+            let time_pos = position(time_dim)?;
+            let ensemble_pos = self.ensemble_dim.as_deref().map(position).transpose()?;
+
+            if let Some(indices) = &self.indices {
+                if let Some(dim) = indices.keys().find(|d| !dims.iter().any(|(n, _)| n == *d)) {
+                    return Err(invalid(format!(
+                        "variable `{}` has no dimension `{dim}`",
+                        self.variable
+                    )));
+                }
+            }
+
+            // Read the whole of the time and ensemble dimensions, and a single position of the others
+            let extents = dims
+                .iter()
+                .enumerate()
+                .map(|(i, (dim, len))| {
+                    if i == time_pos || Some(i) == ensemble_pos {
+                        return Ok(Extent::from(..));
+                    }
+                    match self.indices.as_ref().and_then(|indices| indices.get(dim)) {
+                        Some(&index) if index < *len => Ok(Extent::from(index)),
+                        Some(index) => Err(invalid(format!(
+                            "index {index} is out of range for dimension `{dim}` of length {len}"
+                        ))),
+                        None if *len == 1 => Ok(Extent::from(0)),
+                        None => Err(invalid(format!("an index must be given for dimension `{dim}`"))),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let values = unpack(&variable, variable.get_values::<f64, _>(extents)?)?;
+            if values.iter().any(|v| v.is_nan()) {
+                return Err(invalid(format!("variable `{}` contains missing values", self.variable)));
+            }
+
+            let times = self.times(name, &file, time_dim)?;
+            let n_times = dims[time_pos].1;
+            let n_members = ensemble_pos.map_or(1, |pos| dims[pos].1);
+
+            // The values are in the order of the variable's dimensions
+            let value = |t: usize, m: usize| match ensemble_pos {
+                Some(pos) if pos < time_pos => values[m * n_times + t],
+                _ => values[t * n_members + m],
+            };
+
+            let mut columns = vec![Column::new(time_dim.into(), times)];
+            for (m, member) in self.member_names(&file, n_members).into_iter().enumerate() {
+                let member_values: Vec<f64> = (0..n_times).map(|t| value(t, m)).collect();
+                columns.push(Column::new(member.into(), member_values));
+            }
+
+            let df = DataFrame::new(columns)?;
+            align_and_resample(name, df, time_dim, domain, true)
+        }
+
+        /// The dates of the time dimension, decoded from its coordinate variable.
+        fn times(
+            &self,
+            name: &str,
+            file: &netcdf::File,
+            time_dim: &str,
+        ) -> Result<Vec<NaiveDateTime>, TimeseriesError> {
+            let invalid = |reason: String| TimeseriesError::InvalidNetCdf {
+                name: name.to_string(),
+                reason,
+            };
+
+            let variable = file
+                .variable(time_dim)
+                .ok_or_else(|| invalid(format!("the time dimension `{time_dim}` has no coordinate variable")))?;
+
+            let units = string_attribute(&variable, "units")?
+                .ok_or_else(|| invalid(format!("the time variable `{time_dim}` has no units")))?;
+            let calendar = string_attribute(&variable, "calendar")?.unwrap_or_else(|| "standard".to_string());
+
+            let (seconds_per_unit, reference) = parse_time_units(&units)
+                .ok_or_else(|| invalid(format!("the time units `{units}` are not supported")))?;
+
+            let noleap = match calendar.to_lowercase().as_str() {
+                "standard" | "gregorian" | "proleptic_gregorian" => false,
+                "noleap" | "365_day" => true,
+                _ => return Err(invalid(format!("the calendar `{calendar}` is not supported"))),
+            };
+
+            variable
+                .get_values::<f64, _>(..)?
+                .into_iter()
+                .map(|offset| {
+                    let seconds = (offset * seconds_per_unit).round() as i64;
+                    let time = if noleap {
+                        add_noleap_seconds(reference, seconds)
+                    } else {
+                        TimeDelta::try_seconds(seconds).and_then(|d| reference.checked_add_signed(d))
+                    };
+                    time.ok_or_else(|| invalid(format!("the time {offset} {units} is out of range")))
+                })
+                .collect()
+        }
+
+        /// The names of the ensemble's members, from its coordinate variable if it has one.
+        fn member_names(&self, file: &netcdf::File, n_members: usize) -> Vec<String> {
+            let Some(ensemble_dim) = &self.ensemble_dim else {
+                return vec![self.variable.clone()];
+            };
+
+            match file.variable(ensemble_dim).map(|v| v.get_values::<f64, _>(..)) {
+                Some(Ok(values)) if values.len() == n_members => values.into_iter().map(|v| v.to_string()).collect(),
+                _ => (0..n_members).map(|m| m.to_string()).collect(),
+            }
+        }
+    }
+
+    fn string_attribute(variable: &Variable, name: &str) -> Result<Option<String>, TimeseriesError> {
+        match variable.attribute_value(name).transpose()? {
+            Some(AttributeValue::Str(value)) => Ok(Some(value)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Apply the `scale_factor` and `add_offset` of packed data, and replace any fill values
+    /// with NaN.
+    fn unpack(variable: &Variable, mut values: Vec<f64>) -> Result<Vec<f64>, TimeseriesError> {
+        let attribute = |name: &str| -> Result<Option<f64>, TimeseriesError> {
+            match variable.attribute_value(name).transpose()? {
+                Some(value) => Ok(Some(value.try_into()?)),
+                None => Ok(None),
+            }
+        };
+
+        for fill_value in [attribute("_FillValue")?, attribute("missing_value")?]
+            .into_iter()
+            .flatten()
+        {
+            values
+                .iter_mut()
+                .filter(|v| **v == fill_value)
+                .for_each(|v| *v = f64::NAN);
+        }
+
+        let scale_factor = attribute("scale_factor")?.unwrap_or(1.0);
+        let add_offset = attribute("add_offset")?.unwrap_or(0.0);
+        values.iter_mut().for_each(|v| *v = *v * scale_factor + add_offset);
+
+        Ok(values)
+    }
+
+    /// Parse CF time units (e.g. `days since 2000-01-01 00:00:00`) in to the number of seconds
+    /// per unit and the reference date.
+    fn parse_time_units(units: &str) -> Option<(f64, NaiveDateTime)> {
+        let (unit, reference) = units.split_once(" since ")?;
+
+        let seconds_per_unit = match unit.trim().to_lowercase().as_str() {
+            "seconds" | "second" | "secs" | "sec" | "s" => 1.0,
+            "minutes" | "minute" | "mins" | "min" => 60.0,
+            "hours" | "hour" | "hrs" | "hr" | "h" => 3600.0,
+            "days" | "day" | "d" => SECONDS_PER_DAY,
+            _ => return None,
+        };
+
+        let reference = reference.trim().trim_end_matches('Z').trim_end_matches(" UTC");
+        let reference = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(reference, format).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(reference, "%Y-%m-%d")
+                    .ok()
+                    .map(|d| d.and_time(NaiveTime::default()))
+            })?;
+
+        Some((seconds_per_unit, reference))
+    }
+
+    /// Add a number of seconds to a date in a calendar without leap days.
+    fn add_noleap_seconds(reference: NaiveDateTime, seconds: i64) -> Option<NaiveDateTime> {
+        let day =
+            reference.year() as i64 * 365 + DAYS_BEFORE_MONTH[reference.month0() as usize] + reference.day0() as i64;
+        let total = day * SECONDS_PER_DAY as i64 + reference.num_seconds_from_midnight() as i64 + seconds;
+
+        let (day, seconds) = (
+            total.div_euclid(SECONDS_PER_DAY as i64),
+            total.rem_euclid(SECONDS_PER_DAY as i64),
+        );
+        let (year, day_of_year) = (day.div_euclid(365), day.rem_euclid(365));
+        let month0 = DAYS_BEFORE_MONTH.iter().rposition(|d| *d <= day_of_year)?;
+
+        let date = NaiveDate::from_ymd_opt(
+            year.try_into().ok()?,
+            month0 as u32 + 1,
+            (day_of_year - DAYS_BEFORE_MONTH[month0]) as u32 + 1,
+        )?;
+        Some(date.and_time(NaiveTime::from_num_seconds_from_midnight_opt(seconds as u32, 0)?))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{add_noleap_seconds, parse_time_units};
+        use chrono::NaiveDate;
+
+        #[test]
+        fn test_time_units() {
+            let (seconds, reference) = parse_time_units("days since 2000-01-01 00:00:00").unwrap();
+            assert_eq!(seconds, 86400.0);
+            assert_eq!(
+                reference,
+                NaiveDate::from_ymd_opt(2000, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+            );
+
+            let (seconds, reference) = parse_time_units("hours since 1970-01-01T12:00:00Z").unwrap();
+            assert_eq!(seconds, 3600.0);
+            assert_eq!(
+                reference,
+                NaiveDate::from_ymd_opt(1970, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(12, 0, 0)
+                    .unwrap()
+            );
+
+            assert!(parse_time_units("months since 2000-01-01").is_none());
+
+            // The 28th February is followed by the 1st March in a calendar without leap days
+            let reference = NaiveDate::from_ymd_opt(2000, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            let date = add_noleap_seconds(reference, 59 * 86400).unwrap();
+            assert_eq!(
+                date,
+                NaiveDate::from_ymd_opt(2000, 3, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+            );
+            let date = add_noleap_seconds(reference, 365 * 86400).unwrap();
+            assert_eq!(
+                date,
+                NaiveDate::from_ymd_opt(2001, 1, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+            );
+        }
+    }
+}
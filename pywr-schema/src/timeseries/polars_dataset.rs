@@ -2,11 +2,48 @@ use crate::visit::VisitPaths;
 use schemars::JsonSchema;
 use std::path::{Path, PathBuf};
 
+/// A dataset that is loaded using Polars.
+///
+/// CSV files are read in either a wide format, with a column for each scenario, or a long
+/// format, with a column that identifies the scenario of each row and a single column of
+/// values. Data in the long format is pivoted in to the wide format with the scenarios in the
+/// order in which they first appear. The long format is used if `scenario_col` is given, or if
+/// the data has a column named `scenario`.
+///
+/// The options `separator`, `decimal_comma` and `date_format` allow data using other locales'
+/// conventions to be read without preprocessing. For example, data with rows of the form
+/// `31.12.2021;1,5` can be read with a separator of `;`, `decimal_comma` set to `true` and
+/// a date format of `%d.%m.%Y`.
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, JsonSchema)]
 pub struct PolarsDataset {
     pub time_col: Option<String>,
     pub url: PathBuf,
     pub infer_schema_length: Option<usize>,
+    /// The character that separates the fields of a CSV file. Defaults to `,`.
+    pub separator: Option<char>,
+    /// If `true` a comma is used as the decimal separator of numbers in a CSV file.
+    pub decimal_comma: Option<bool>,
+    /// The format of the dates in the time column (e.g. `%d/%m/%Y %H:%M`).
+    ///
+    /// If not given the format is inferred from the data; this is ambiguous for dates where
+    /// the day and month could be either way round.
+    pub date_format: Option<String>,
+    /// The time zone (e.g. `Europe/London`) to convert the dates of the time column to.
+    ///
+    /// This requires that the dates include their offset from UTC (e.g. a date format with
+    /// `%z`). The converted dates are used as the model's (local) dates.
+    pub time_zone: Option<String>,
+    /// The name of the column that identifies the scenario of each row of data in the long
+    /// format.
+    pub scenario_col: Option<String>,
+    /// If given the data is read during the simulation in chunks of this number of time-steps,
+    /// rather than loaded in to memory before the simulation starts.
+    ///
+    /// This is intended for datasets that are too large to be loaded in to memory (e.g. with
+    /// a large number of scenarios). The next chunk is read on a background thread while the
+    /// current chunk is being used. Only CSV files are supported, and the data is not resampled;
+    /// it must contain a row for each of the model's time-steps.
+    pub chunk_size: Option<usize>,
 }
 
 impl VisitPaths for PolarsDataset {
@@ -19,36 +56,54 @@ impl VisitPaths for PolarsDataset {
     }
 }
 
+#[cfg(feature = "core")]
+pub(crate) use self::core::{long_to_wide, ChunkedTimeseries};
+
 #[cfg(feature = "core")]
 mod core {
     use super::PolarsDataset;
-    use crate::timeseries::align_and_resample::align_and_resample;
+    use crate::timeseries::align_and_resample::{align_and_resample, within_excluded_periods};
     use crate::timeseries::TimeseriesError;
+    use chrono::NaiveDateTime;
+    use ndarray::Array2;
     use polars::{frame::DataFrame, prelude::*};
     use pywr_core::models::ModelDomain;
-    use std::path::Path;
+    use pywr_core::parameters::{ChunkSource, ChunkedArray2Parameter, ParameterName};
+    use pywr_core::timestep::{ExcludedPeriod, TimestepIndex};
+    use pywr_core::PywrError;
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    /// The name of the column that is assumed to identify the scenarios of data in the long
+    /// format if a `scenario_col` is not given.
+    const DEFAULT_SCENARIO_COL: &str = "scenario";
 
     impl PolarsDataset {
+        fn full_path(&self, data_path: Option<&Path>) -> PathBuf {
+            if self.url.is_absolute() {
+                self.url.clone()
+            } else if let Some(data_path) = data_path {
+                data_path.join(self.url.as_path())
+            } else {
+                self.url.clone()
+            }
+        }
+
         pub fn load(
             &self,
             name: &str,
             data_path: Option<&Path>,
             domain: &ModelDomain,
         ) -> Result<DataFrame, TimeseriesError> {
-            let fp = if self.url.is_absolute() {
-                self.url.clone()
-            } else if let Some(data_path) = data_path {
-                data_path.join(self.url.as_path())
-            } else {
-                self.url.clone()
-            };
+            let fp = self.full_path(data_path);
 
             let mut df = match fp.extension() {
                 Some(ext) => {
                     let ext = ext.to_str().map(|s| s.to_lowercase());
                     match ext.as_deref() {
                         Some("csv") => {
-                            let parse_options = CsvParseOptions::default().with_try_parse_dates(true);
+                            let parse_options = self.csv_parse_options(name)?;
 
                             let mut read_options = CsvReadOptions::default()
                                 .with_schema(None)
@@ -86,16 +141,354 @@ mod core {
                 }
             };
 
-            df = match self.time_col {
-                Some(ref col) => align_and_resample(name, df, col, domain, true)?,
+            let time_col = match self.time_col {
+                Some(ref col) => col.clone(),
+                // If a time col has not been provided assume it is the first column
+                None => df.get_column_names()[0].to_string(),
+            };
+
+            df = df.lazy().with_column(self.time_expr(&time_col)).collect()?;
+            let columns: Vec<String> = df.get_column_names().iter().map(|c| c.to_string()).collect();
+            if let Some(scenario_col) = self.long_format_scenario_col(&columns) {
+                df = long_to_wide(name, df, &time_col, &scenario_col)?;
+            }
+            df = align_and_resample(name, df, &time_col, domain, true)?;
+
+            Ok(df)
+        }
+
+        /// The options for parsing a CSV file.
+        fn csv_parse_options(&self, name: &str) -> Result<CsvParseOptions, TimeseriesError> {
+            let mut options = CsvParseOptions::default()
+                // Dates are parsed explicitly if a format is given
+                .with_try_parse_dates(self.date_format.is_none())
+                .with_decimal_comma(self.decimal_comma.unwrap_or(false));
+
+            if let Some(separator) = self.separator {
+                if !separator.is_ascii() {
+                    return Err(TimeseriesError::InvalidCsvSeparator {
+                        name: name.to_string(),
+                        separator,
+                    });
+                }
+                options = options.with_separator(separator as u8);
+            }
+
+            Ok(options)
+        }
+
+        /// An expression that parses the time column and converts it to the time zone.
+        fn time_expr(&self, time_col: &str) -> Expr {
+            let mut time = col(time_col);
+
+            if let Some(format) = &self.date_format {
+                let options = StrptimeOptions {
+                    format: Some(format.as_str().into()),
+                    ..Default::default()
+                };
+                time = time
+                    .str()
+                    .to_datetime(Some(TimeUnit::Milliseconds), None, options, lit("raise"));
+            }
+
+            if let Some(time_zone) = &self.time_zone {
+                time = time
+                    .dt()
+                    .convert_time_zone(time_zone.as_str().into())
+                    .dt()
+                    .replace_time_zone(None, lit("raise"), NonExistent::Raise);
+            }
+
+            time.alias(time_col)
+        }
+
+        /// The name of the scenario column if the data is in the long format.
+        fn long_format_scenario_col(&self, columns: &[String]) -> Option<String> {
+            match &self.scenario_col {
+                Some(scenario_col) => Some(scenario_col.clone()),
+                None => columns
+                    .iter()
+                    .any(|c| c == DEFAULT_SCENARIO_COL)
+                    .then(|| DEFAULT_SCENARIO_COL.to_string()),
+            }
+        }
+
+        /// Prepare the dataset to be read in chunks during the simulation.
+        ///
+        /// Only the schema of the file is read; see [`ChunkedTimeseries`].
+        pub(crate) fn load_chunked(
+            &self,
+            name: &str,
+            data_path: Option<&Path>,
+            domain: &ModelDomain,
+            chunk_size: usize,
+        ) -> Result<ChunkedTimeseries, TimeseriesError> {
+            let path = self.full_path(data_path);
+
+            match path.extension().and_then(|ext| ext.to_str()).map(|s| s.to_lowercase()) {
+                Some(ext) if ext == "csv" => {}
+                Some(other_ext) => {
+                    return Err(TimeseriesError::TimeseriesUnsupportedFileFormat {
+                        provider: "polars".to_string(),
+                        fmt: other_ext,
+                    })
+                }
                 None => {
-                    // If a time col has not been provided assume it is the first column
-                    let first_col = df.get_column_names()[0].to_string();
-                    align_and_resample(name, df, first_col.as_str(), domain, true)?
+                    return Err(TimeseriesError::TimeseriesUnparsableFileFormat {
+                        provider: "polars".to_string(),
+                        path: self.url.to_string_lossy().to_string(),
+                    })
                 }
+            }
+
+            let source = CsvChunkSource {
+                name: name.to_string(),
+                path,
+                time_col: String::new(),
+                dataset: self.clone(),
+                parse_options: self.csv_parse_options(name)?,
+                columns: Vec::new(),
+                dates: Arc::new(domain.time().timesteps().iter().map(|t| t.date).collect()),
+                excluded_periods: Arc::new(domain.time().excluded_periods().to_vec()),
+                row_offsets: Arc::default(),
             };
 
-            Ok(df)
+            let schema = source.scan(0)?.collect_schema()?;
+            let mut names = schema
+                .iter_names()
+                .map(|n| n.to_string())
+                .filter(|n| n != ROW_INDEX_COL);
+
+            // If a time col has not been provided assume it is the first column
+            let time_col = match self.time_col {
+                Some(ref col) => col.clone(),
+                None => names
+                    .next()
+                    .ok_or(TimeseriesError::TimeseriesDataframeHasNoColumns(name.to_string()))?,
+            };
+            let columns: Vec<String> = names.filter(|n| *n != time_col).collect();
+
+            if self.long_format_scenario_col(&columns).is_some() {
+                return Err(TimeseriesError::ChunkedLongFormatNotSupported(name.to_string()));
+            }
+
+            if !schema.contains(&time_col) {
+                return Err(TimeseriesError::ColumnNotFound {
+                    col: time_col,
+                    name: name.to_string(),
+                });
+            }
+
+            if columns.is_empty() {
+                return Err(TimeseriesError::TimeseriesDataframeHasNoColumns(name.to_string()));
+            }
+
+            Ok(ChunkedTimeseries {
+                source: CsvChunkSource { time_col, ..source },
+                columns,
+                chunk_size,
+            })
+        }
+    }
+
+    /// Pivot data in the long format in to the wide format with a column for each scenario.
+    ///
+    /// The scenarios are in the order in which they first appear. The data must have a single
+    /// column of values in addition to the time and scenario columns.
+    pub(crate) fn long_to_wide(
+        name: &str,
+        df: DataFrame,
+        time_col: &str,
+        scenario_col: &str,
+    ) -> Result<DataFrame, TimeseriesError> {
+        let columns: Vec<String> = df.get_column_names().iter().map(|c| c.to_string()).collect();
+
+        if !columns.iter().any(|c| c == scenario_col) {
+            return Err(TimeseriesError::ColumnNotFound {
+                col: scenario_col.to_string(),
+                name: name.to_string(),
+            });
+        }
+
+        let value_cols: Vec<&String> = columns
+            .iter()
+            .filter(|c| c.as_str() != time_col && c.as_str() != scenario_col)
+            .collect();
+
+        let [value_col] = value_cols.as_slice() else {
+            return Err(TimeseriesError::LongFormatValueColumn(name.to_string()));
+        };
+
+        Ok(pivot::pivot_stable(
+            &df,
+            [scenario_col],
+            Some([time_col]),
+            Some([value_col.as_str()]),
+            false,
+            None,
+            None,
+        )?)
+    }
+
+    /// A timeseries dataset that is read in chunks of time-steps during the simulation.
+    pub(crate) struct ChunkedTimeseries {
+        source: CsvChunkSource,
+        columns: Vec<String>,
+        chunk_size: usize,
+    }
+
+    impl ChunkedTimeseries {
+        /// The names of the data columns (i.e. excluding the time column).
+        pub fn columns(&self) -> &[String] {
+            &self.columns
+        }
+
+        /// Create a parameter that reads the given columns of the dataset in chunks.
+        pub fn parameter<T>(
+            &self,
+            name: ParameterName,
+            columns: &[String],
+            scenario_group_index: Option<usize>,
+        ) -> Result<ChunkedArray2Parameter<T>, TimeseriesError>
+        where
+            T: Copy + Send + Sync + 'static,
+            CsvChunkSource: ChunkSource<T>,
+        {
+            if let Some(col) = columns.iter().find(|c| !self.columns.contains(c)) {
+                return Err(TimeseriesError::ColumnNotFound {
+                    col: col.clone(),
+                    name: self.source.name.clone(),
+                });
+            }
+
+            let source = CsvChunkSource {
+                columns: columns.to_vec(),
+                row_offsets: Arc::default(),
+                ..self.source.clone()
+            };
+
+            Ok(ChunkedArray2Parameter::new(
+                name,
+                Arc::new(source),
+                self.source.dates.len(),
+                self.chunk_size,
+                scenario_group_index,
+            )?)
+        }
+    }
+
+    /// The name of the column of the row numbers of the CSV file that are read.
+    const ROW_INDEX_COL: &str = "__pywr_row_index";
+
+    /// Reads chunks of a CSV file by scanning it for the rows of the chunk's time-steps.
+    ///
+    /// The row of the file at which each chunk ends is kept so that the rows of the earlier
+    /// time-steps are skipped, rather than parsed again, when the following chunk is read.
+    #[derive(Clone)]
+    pub(crate) struct CsvChunkSource {
+        name: String,
+        path: PathBuf,
+        time_col: String,
+        dataset: PolarsDataset,
+        parse_options: CsvParseOptions,
+        columns: Vec<String>,
+        /// The date of each of the model's time-steps.
+        dates: Arc<Vec<NaiveDateTime>>,
+        /// The periods excluded from the model's time domain.
+        excluded_periods: Arc<Vec<ExcludedPeriod>>,
+        /// The row of the file from which the rows of the time-steps from each time-step onwards
+        /// are found.
+        row_offsets: Arc<Mutex<BTreeMap<TimestepIndex, usize>>>,
+    }
+
+    impl CsvChunkSource {
+        /// Scan the CSV file skipping the first `offset` rows after the header.
+        ///
+        /// A column of the number of each row in the file is added (see [`ROW_INDEX_COL`]).
+        fn scan(&self, offset: usize) -> Result<LazyFrame, TimeseriesError> {
+            let mut reader = LazyCsvReader::new(&self.path)
+                .with_has_header(true)
+                .with_skip_rows_after_header(offset)
+                .with_row_index(Some(RowIndex {
+                    name: ROW_INDEX_COL.into(),
+                    offset: offset as IdxSize,
+                }))
+                .with_separator(self.parse_options.separator)
+                .with_decimal_comma(self.parse_options.decimal_comma)
+                .with_try_parse_dates(self.parse_options.try_parse_dates);
+
+            if self.dataset.infer_schema_length.is_some() {
+                reader = reader.with_infer_schema_length(self.dataset.infer_schema_length);
+            }
+
+            Ok(reader.finish()?)
+        }
+
+        fn read_df(&self, start: TimestepIndex, len: usize) -> Result<DataFrame, TimeseriesError> {
+            let (first, last) = match (self.dates.get(start), self.dates.get(start + len - 1)) {
+                (Some(first), Some(last)) => (*first, *last),
+                _ => return Err(TimeseriesError::DataFrameTimestepMismatch(self.name.clone())),
+            };
+
+            let time = self
+                .dataset
+                .time_expr(&self.time_col)
+                .cast(DataType::Datetime(TimeUnit::Milliseconds, None));
+            let mut in_chunk = time.clone().gt_eq(lit(first)).and(time.clone().lt_eq(lit(last)));
+            // Skip the rows of any excluded periods that the chunk spans
+            if let Some(excluded) = within_excluded_periods(time, &self.excluded_periods) {
+                in_chunk = in_chunk.and(excluded.not());
+            }
+
+            // Start from the end of the latest chunk that was read before this one
+            let offset = self
+                .row_offsets
+                .lock()
+                .unwrap()
+                .range(..=start)
+                .next_back()
+                .map_or(0, |(_, row)| *row);
+
+            let df = self
+                .scan(offset)?
+                .filter(in_chunk)
+                .select(
+                    self.columns
+                        .iter()
+                        .map(|c| col(c.as_str()))
+                        .chain([col(ROW_INDEX_COL)])
+                        .collect::<Vec<_>>(),
+                )
+                .collect()?;
+
+            if df.height() != len {
+                return Err(TimeseriesError::DataFrameTimestepMismatch(self.name.clone()));
+            }
+
+            if let Some(last_row) = df.column(ROW_INDEX_COL)?.as_materialized_series().idx()?.last() {
+                self.row_offsets
+                    .lock()
+                    .unwrap()
+                    .insert(start + len, last_row as usize + 1);
+            }
+
+            Ok(df.drop(ROW_INDEX_COL)?)
+        }
+    }
+
+    impl ChunkSource<f64> for CsvChunkSource {
+        fn read(&self, start: TimestepIndex, len: usize) -> Result<Array2<f64>, PywrError> {
+            self.read_df(start, len)
+                .and_then(|df| Ok(df.to_ndarray::<Float64Type>(IndexOrder::default())?))
+                .map_err(|e| PywrError::TimeseriesChunkError(e.to_string()))
+        }
+    }
+
+    impl ChunkSource<u64> for CsvChunkSource {
+        fn read(&self, start: TimestepIndex, len: usize) -> Result<Array2<u64>, PywrError> {
+            self.read_df(start, len)
+                .and_then(|df| Ok(df.to_ndarray::<UInt64Type>(IndexOrder::default())?))
+                .map_err(|e| PywrError::TimeseriesChunkError(e.to_string()))
         }
     }
 }
@@ -0,0 +1,119 @@
+//! Preservation of components of a type that is not known to this version of Pywr.
+//!
+//! A model may contain nodes or parameters from a newer version of Pywr or from a third-party
+//! plugin. Such a model can not be deserialised normally, which prevents tooling that only reads
+//! or edits part of the model from working with it. When a model is loaded with
+//! [`crate::PywrModel::from_str_preserving_unknown`], or the equivalent functions of a network
+//! or multi-network model, these components are removed from the document before it is
+//! deserialised and kept as opaque JSON values. They are written back to their original
+//! positions when the model is saved, but the model can not be built while any remain.
+use crate::nodes::NodeType;
+use crate::parameters::ParameterType;
+use serde_json::Value;
+use strum::VariantNames;
+
+/// The section of a network that an [`UnknownComponent`] was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownComponentKind {
+    Node,
+    Parameter,
+}
+
+impl UnknownComponentKind {
+    /// The key of the section of the network containing this kind of component.
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Node => "nodes",
+            Self::Parameter => "parameters",
+        }
+    }
+
+    fn is_known_type(&self, ty: &str) -> bool {
+        match self {
+            Self::Node => NodeType::VARIANTS.contains(&ty),
+            Self::Parameter => ParameterType::VARIANTS.contains(&ty),
+        }
+    }
+}
+
+/// A node or parameter of a type that is not known to this version of Pywr.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownComponent {
+    pub kind: UnknownComponentKind,
+    /// The position of the component in its section of the original document.
+    pub position: usize,
+    /// The component exactly as it appeared in the document.
+    pub value: Value,
+}
+
+impl UnknownComponent {
+    /// The name of the component, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.value.get("meta")?.get("name")?.as_str()
+    }
+
+    /// The type of the component, if it has one.
+    pub fn ty(&self) -> Option<&str> {
+        self.value.get("type")?.as_str()
+    }
+}
+
+/// Remove the nodes and parameters of an unknown type from a network document.
+///
+/// The removed components are returned in the order they appeared in each section.
+pub(crate) fn extract_unknown_components(network: &mut Value) -> Vec<UnknownComponent> {
+    let mut unknown = Vec::new();
+
+    for kind in [UnknownComponentKind::Node, UnknownComponentKind::Parameter] {
+        let Some(Value::Array(components)) = network.get_mut(kind.key()) else {
+            continue;
+        };
+
+        let mut position = 0;
+        components.retain(|component| {
+            let is_known = component
+                .get("type")
+                .and_then(|ty| ty.as_str())
+                .map_or(true, |ty| kind.is_known_type(ty));
+
+            if !is_known {
+                unknown.push(UnknownComponent {
+                    kind,
+                    position,
+                    value: component.clone(),
+                });
+            }
+            position += 1;
+            is_known
+        });
+    }
+
+    unknown
+}
+
+/// A component of a network section that is written when the network is saved.
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub(crate) enum ComponentRef<'a, T> {
+    Known(&'a T),
+    Unknown(&'a Value),
+}
+
+/// Merge the known components of a section with the unknown components of `kind`, restoring the
+/// unknown components to their original positions.
+pub(crate) fn merge_unknown_components<'a, T>(
+    known: &'a [T],
+    unknown: &'a [UnknownComponent],
+    kind: UnknownComponentKind,
+) -> Vec<ComponentRef<'a, T>> {
+    let mut merged: Vec<ComponentRef<T>> = known.iter().map(ComponentRef::Known).collect();
+
+    // The components are inserted in the order they appeared so that the positions of the
+    // earlier ones are correct when the later ones are inserted.
+    for component in unknown.iter().filter(|c| c.kind == kind) {
+        let position = component.position.min(merged.len());
+        merged.insert(position, ComponentRef::Unknown(&component.value));
+    }
+
+    merged
+}
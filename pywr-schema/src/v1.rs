@@ -109,6 +109,8 @@ impl FromV1<ParameterMetaV1> for ParameterMeta {
                 pname
             }),
             comment: v1.comment,
+            min: None,
+            max: None,
         }
     }
 }
@@ -121,6 +123,8 @@ impl FromV1<Option<ParameterMetaV1>> for ParameterMeta {
                 let meta = Self {
                     name: format!("unnamed-{}", conversion_data.unnamed_count),
                     comment: None,
+                    min: None,
+                    max: None,
                 };
                 conversion_data.unnamed_count += 1;
                 meta
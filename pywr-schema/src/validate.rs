@@ -0,0 +1,700 @@
+//! Validation of the references between the components of a model without building it.
+//!
+//! Building a model stops at the first component that can not be resolved, and requires the
+//! data and solver of the model to be available. [`PywrModel::validate`] instead checks the
+//! references of every component of the document and reports all the problems it finds, each
+//! with the JSON path of the part of the document that caused it.
+use crate::edge::Edge;
+use crate::metric::{IndexMetric, Metric};
+use crate::model::{PywrModel, PywrMultiNetworkModel, PywrNetwork, PywrNetworkRef};
+use crate::UnknownComponentKind;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// The severity of a [`ValidationIssue`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValidationSeverity {
+    /// The model can not be built.
+    Error,
+    /// The model can be built, but may not behave as intended.
+    Warning,
+}
+
+impl Display for ValidationSeverity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found when validating a model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: ValidationSeverity,
+    /// The JSON path of the part of the document with the problem (e.g. `$.network.edges[3].to_node`).
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(path: String, message: String) -> Self {
+        Self {
+            severity: ValidationSeverity::Error,
+            path,
+            message,
+        }
+    }
+
+    fn warning(path: String, message: String) -> Self {
+        Self {
+            severity: ValidationSeverity::Warning,
+            path,
+            message,
+        }
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+impl PywrModel {
+    /// Check the references between the components of the model.
+    ///
+    /// See [`PywrNetwork::validate`] for the checks that are performed.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        self.network.validate_at("$.network", &HashSet::new(), &[])
+    }
+}
+
+impl PywrMultiNetworkModel {
+    /// Check the references between the components of each of the model's networks, and
+    /// between the networks.
+    ///
+    /// Each network is checked as described in [`PywrNetwork::validate`]. In addition, networks
+    /// with the same name, transfers from networks that do not exist, transfer metrics referring
+    /// to components that do not exist in the network the transfer is from, and metrics of
+    /// inter-network transfers that the network does not receive are reported as errors. A
+    /// parameter that is only used by a transfer is not reported as unused.
+    ///
+    /// Networks given by a path are loaded relative to `data_path`, and the paths of their
+    /// issues are prefixed by the file (e.g. `network1.json:$.edges[0].to_node`). A network that
+    /// can not be loaded is reported as an error.
+    pub fn validate(&self, data_path: Option<&Path>) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        let mut names = HashSet::new();
+        for (i, entry) in self.networks.iter().enumerate() {
+            if !names.insert(entry.name.as_str()) {
+                let path = format!("$.networks[{i}].name");
+                let message = format!("network `{}` is defined more than once", entry.name);
+                issues.push(ValidationIssue::error(path, message));
+            }
+        }
+
+        // The metrics of the transfers from each network
+        let mut transfer_metrics: Vec<Vec<(String, Value)>> = vec![Vec::new(); self.networks.len()];
+        for (i, entry) in self.networks.iter().enumerate() {
+            for (j, transfer) in entry.transfers.iter().enumerate() {
+                let path = format!("$.networks[{i}].transfers[{j}]");
+                let Some(from) = self.networks.iter().position(|e| e.name == transfer.from_network) else {
+                    let message = format!("network `{}` does not exist", transfer.from_network);
+                    issues.push(ValidationIssue::error(format!("{path}.from_network"), message));
+                    continue;
+                };
+
+                match serde_json::to_value(&transfer.metric) {
+                    Ok(metric) => transfer_metrics[from].push((format!("{path}.metric"), metric)),
+                    Err(e) => {
+                        let message = format!("the metric could not be serialised: {e}");
+                        issues.push(ValidationIssue::error(format!("{path}.metric"), message));
+                    }
+                }
+            }
+        }
+
+        for (i, (entry, transfer_metrics)) in self.networks.iter().zip(&transfer_metrics).enumerate() {
+            let (network, root) = match &entry.network {
+                PywrNetworkRef::Inline(network) => (Cow::Borrowed(network), format!("$.networks[{i}].network")),
+                PywrNetworkRef::Path(path) => {
+                    let full_path = match data_path {
+                        Some(data_path) if path.is_relative() => data_path.join(path),
+                        _ => path.clone(),
+                    };
+
+                    match PywrNetwork::from_path_preserving_unknown(&full_path) {
+                        Ok(network) => (Cow::Owned(network), format!("{}:$", path.display())),
+                        Err(e) => {
+                            let path = format!("$.networks[{i}].network");
+                            let message = format!("the network could not be loaded: {e}");
+                            issues.push(ValidationIssue::error(path, message));
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let transfers = entry.transfers.iter().map(|t| t.name.as_str()).collect();
+            issues.extend(network.validate_at(&root, &transfers, transfer_metrics));
+        }
+
+        issues.sort_by_key(|i| i.severity);
+        issues
+    }
+}
+
+impl PywrNetwork {
+    /// Check the references between the components of the network.
+    ///
+    /// The following are reported as errors: duplicate node or parameter names; edges between
+    /// nodes that do not exist; metrics referring to nodes, edges, parameters, tables or
+    /// timeseries that do not exist; outputs of metric sets that do not exist; and parameters
+    /// that depend on themselves through their metrics. Parameters that are never referenced,
+    /// and components of an unknown type (whose references can not be checked), are reported
+    /// as warnings.
+    ///
+    /// No data is loaded and no solver is required. The errors are reported before the warnings.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        self.validate_at("$", &HashSet::new(), &[])
+    }
+
+    /// Validate the network at the path `root` of a document.
+    ///
+    /// `transfers` are the names of the inter-network transfers received by the network, and
+    /// `external_metrics` are the path and value of metrics elsewhere in the document (i.e.
+    /// of transfers to other networks) that refer to the components of this network.
+    fn validate_at(
+        &self,
+        root: &str,
+        transfers: &HashSet<&str>,
+        external_metrics: &[(String, Value)],
+    ) -> Vec<ValidationIssue> {
+        // The document is validated as it is written so that the paths, including the positions
+        // of any unknown components, are those of the original file.
+        let document = match serde_json::to_value(self) {
+            Ok(document) => document,
+            Err(e) => {
+                return vec![ValidationIssue::error(
+                    root.to_string(),
+                    format!("the network could not be serialised: {e}"),
+                )]
+            }
+        };
+
+        let mut validator = Validator::new(root, &document, transfers);
+        for (path, metric) in external_metrics {
+            find_references(metric, path, None, &mut validator.references);
+        }
+        validator.check_unknown_components(self);
+        validator.check_edges();
+        validator.check_references();
+        validator.check_outputs();
+        validator.check_parameter_cycles();
+        validator.check_unused_parameters();
+
+        let mut issues = validator.issues;
+        issues.sort_by_key(|i| i.severity);
+        issues
+    }
+}
+
+/// A reference from a metric to another component of the network.
+enum Reference {
+    Node(String),
+    Edge(Edge),
+    Parameter(String),
+    Table(String),
+    Timeseries(String),
+    InterNetworkTransfer(String),
+}
+
+impl Reference {
+    fn from_value(value: &Value) -> Option<Self> {
+        if let Ok(metric) = serde_json::from_value::<Metric>(value.clone()) {
+            return match metric {
+                Metric::Node(r) => Some(Self::Node(r.name)),
+                Metric::Edge(r) => Some(Self::Edge(r.edge)),
+                Metric::VirtualStorage(r) => Some(Self::Node(r.name)),
+                Metric::Parameter(r) => Some(Self::Parameter(r.name)),
+                Metric::Table(r) => Some(Self::Table(r.table)),
+                Metric::Timeseries(r) => Some(Self::Timeseries(r.name)),
+                Metric::InterNetworkTransfer { name } => Some(Self::InterNetworkTransfer(name)),
+                _ => None,
+            };
+        }
+
+        match serde_json::from_value::<IndexMetric>(value.clone()).ok()? {
+            IndexMetric::Node(r) => Some(Self::Node(r.name)),
+            IndexMetric::Parameter(r) => Some(Self::Parameter(r.name)),
+            IndexMetric::Table(r) => Some(Self::Table(r.table)),
+            IndexMetric::Timeseries(r) => Some(Self::Timeseries(r.name)),
+            IndexMetric::InterNetworkTransfer { name } => Some(Self::InterNetworkTransfer(name)),
+            _ => None,
+        }
+    }
+}
+
+/// A reference found in the document.
+struct FoundReference {
+    path: String,
+    reference: Reference,
+    /// The index of the parameter containing the reference, if any.
+    parameter: Option<usize>,
+}
+
+struct Validator<'a> {
+    root: &'a str,
+    document: &'a Value,
+    node_names: HashSet<&'a str>,
+    /// The index of each parameter by name.
+    parameter_names: HashMap<&'a str, usize>,
+    /// The names of the inter-network transfers received by the network.
+    transfers: &'a HashSet<&'a str>,
+    references: Vec<FoundReference>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(root: &'a str, document: &'a Value, transfers: &'a HashSet<&'a str>) -> Self {
+        let mut validator = Self {
+            root,
+            document,
+            node_names: HashSet::new(),
+            parameter_names: HashMap::new(),
+            transfers,
+            references: Vec::new(),
+            issues: Vec::new(),
+        };
+
+        for (i, name) in validator.names("nodes") {
+            if !validator.node_names.insert(name) {
+                let path = format!("{root}.nodes[{i}].meta.name");
+                let message = format!("node `{name}` is defined more than once");
+                validator.issues.push(ValidationIssue::error(path, message));
+            }
+        }
+
+        for (i, name) in validator.names("parameters") {
+            if validator.parameter_names.insert(name, i).is_some() {
+                let path = format!("{root}.parameters[{i}].meta.name");
+                let message = format!("parameter `{name}` is defined more than once");
+                validator.issues.push(ValidationIssue::error(path, message));
+            }
+        }
+
+        validator.collect_references();
+        validator
+    }
+
+    /// The components of a section of the network.
+    fn section(&self, key: &str) -> &'a [Value] {
+        self.document
+            .get(key)
+            .and_then(Value::as_array)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The names of the components of a section of the network.
+    fn names(&self, key: &str) -> Vec<(usize, &'a str)> {
+        self.section(key)
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| Some((i, c.get("meta")?.get("name")?.as_str()?)))
+            .collect()
+    }
+
+    fn has_table(&self, name: &str) -> bool {
+        self.section("tables")
+            .iter()
+            .any(|t| t.get("name").and_then(Value::as_str) == Some(name))
+    }
+
+    fn has_timeseries(&self, name: &str) -> bool {
+        self.section("timeseries")
+            .iter()
+            .any(|t| t.get("meta").and_then(|m| m.get("name")).and_then(Value::as_str) == Some(name))
+    }
+
+    fn has_edge(&self, edge: &Edge) -> bool {
+        self.section("edges").iter().any(|e| {
+            e.get("from_node").and_then(Value::as_str) == Some(edge.from_node.as_str())
+                && e.get("to_node").and_then(Value::as_str) == Some(edge.to_node.as_str())
+        })
+    }
+
+    /// Find the metrics of every component that refer to another component.
+    ///
+    /// The nodes, parameters and outputs themselves are not metrics, so only their contents are
+    /// searched.
+    fn collect_references(&mut self) {
+        let mut references = Vec::new();
+
+        for (key, is_component) in [
+            ("nodes", true),
+            ("parameters", true),
+            ("metric_sets", false),
+            ("outputs", true),
+            ("state_overrides", false),
+        ] {
+            for (i, value) in self.section(key).iter().enumerate() {
+                let path = format!("{}.{key}[{i}]", self.root);
+                let parameter = (key == "parameters").then_some(i);
+
+                if is_component {
+                    visit_children(value, &path, &mut |path, value| {
+                        find_references(value, path, parameter, &mut references)
+                    });
+                } else {
+                    find_references(value, &path, parameter, &mut references);
+                }
+            }
+        }
+
+        self.references = references;
+    }
+
+    fn check_unknown_components(&mut self, network: &PywrNetwork) {
+        for component in &network.unknown_components {
+            let section = match component.kind {
+                UnknownComponentKind::Node => "nodes",
+                UnknownComponentKind::Parameter => "parameters",
+            };
+            let path = format!("{}.{section}[{}]", self.root, component.position);
+            let message = format!(
+                "the type `{}` is not known; its references can not be checked",
+                component.ty().unwrap_or_default()
+            );
+            self.issues.push(ValidationIssue::warning(path, message));
+        }
+    }
+
+    fn check_edges(&mut self) {
+        for (i, edge) in self.section("edges").iter().enumerate() {
+            for key in ["from_node", "to_node"] {
+                let Some(name) = edge.get(key).and_then(Value::as_str) else {
+                    continue;
+                };
+
+                if !self.node_names.contains(name) {
+                    let path = format!("{}.edges[{i}].{key}", self.root);
+                    let message = format!("node `{name}` does not exist");
+                    self.issues.push(ValidationIssue::error(path, message));
+                }
+            }
+        }
+    }
+
+    fn check_references(&mut self) {
+        for found in &self.references {
+            let message = match &found.reference {
+                Reference::Node(name) if !self.node_names.contains(name.as_str()) => {
+                    format!("node `{name}` does not exist")
+                }
+                Reference::Edge(edge) if !self.has_edge(edge) => {
+                    format!("edge from `{}` to `{}` does not exist", edge.from_node, edge.to_node)
+                }
+                Reference::Parameter(name) if !self.parameter_names.contains_key(name.as_str()) => {
+                    format!("parameter `{name}` does not exist")
+                }
+                Reference::Table(name) if !self.has_table(name) => format!("table `{name}` does not exist"),
+                Reference::Timeseries(name) if !self.has_timeseries(name) => {
+                    format!("timeseries `{name}` does not exist")
+                }
+                Reference::InterNetworkTransfer(name) if !self.transfers.contains(name.as_str()) => {
+                    format!("inter-network transfer `{name}` is not received by the network")
+                }
+                _ => continue,
+            };
+
+            self.issues.push(ValidationIssue::error(found.path.clone(), message));
+        }
+    }
+
+    /// Check the metric sets of the outputs exist.
+    fn check_outputs(&mut self) {
+        let metric_sets: HashSet<&str> = self
+            .section("metric_sets")
+            .iter()
+            .filter_map(|m| m.get("name").and_then(Value::as_str))
+            .collect();
+
+        for (i, output) in self.section("outputs").iter().enumerate() {
+            let names: Vec<(String, &str)> = match output.get("metric_set") {
+                Some(Value::String(name)) => vec![(".metric_set".to_string(), name.as_str())],
+                Some(Value::Array(names)) => names
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(j, n)| Some((format!(".metric_set[{j}]"), n.as_str()?)))
+                    .collect(),
+                _ => continue,
+            };
+
+            for (key, name) in names {
+                if !metric_sets.contains(name) {
+                    let path = format!("{}.outputs[{i}]{key}", self.root);
+                    let message = format!("metric set `{name}` does not exist");
+                    self.issues.push(ValidationIssue::error(path, message));
+                }
+            }
+        }
+    }
+
+    /// Report each cycle of parameters that depend on each other through their metrics.
+    fn check_parameter_cycles(&mut self) {
+        let num_parameters = self.section("parameters").len();
+        let mut dependencies: Vec<Vec<(usize, &str)>> = vec![Vec::new(); num_parameters];
+        for found in &self.references {
+            if let (Some(parameter), Reference::Parameter(name)) = (found.parameter, &found.reference) {
+                if let Some(&dependency) = self.parameter_names.get(name.as_str()) {
+                    dependencies[parameter].push((dependency, found.path.as_str()));
+                }
+            }
+        }
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        let mut marks = vec![Mark::Unvisited; num_parameters];
+        let mut cycles = Vec::new();
+
+        for start in 0..num_parameters {
+            if marks[start] != Mark::Unvisited {
+                continue;
+            }
+
+            // Depth-first search with an explicit stack of (parameter, next dependency)
+            let mut stack = vec![(start, 0)];
+            marks[start] = Mark::InProgress;
+
+            while let Some((parameter, next)) = stack.last_mut() {
+                let Some(&(dependency, path)) = dependencies[*parameter].get(*next) else {
+                    marks[*parameter] = Mark::Done;
+                    stack.pop();
+                    continue;
+                };
+                *next += 1;
+
+                match marks[dependency] {
+                    Mark::Unvisited => {
+                        marks[dependency] = Mark::InProgress;
+                        stack.push((dependency, 0));
+                    }
+                    Mark::InProgress => {
+                        let position = stack.iter().position(|(p, _)| *p == dependency).unwrap();
+                        let cycle: Vec<usize> = stack[position..].iter().map(|(p, _)| *p).collect();
+                        cycles.push((path.to_string(), cycle));
+                    }
+                    Mark::Done => {}
+                }
+            }
+        }
+
+        let names = self.names("parameters");
+        let name = |idx: usize| names.iter().find(|(i, _)| *i == idx).map_or("", |(_, n)| n);
+
+        for (path, cycle) in cycles {
+            let mut description: Vec<String> = cycle.iter().map(|p| format!("`{}`", name(*p))).collect();
+            description.push(format!("`{}`", name(cycle[0])));
+
+            let message = format!("parameters depend on themselves: {}", description.join(" -> "));
+            self.issues.push(ValidationIssue::error(path, message));
+        }
+    }
+
+    fn check_unused_parameters(&mut self) {
+        let used: HashSet<&str> = self
+            .references
+            .iter()
+            .filter_map(|found| match &found.reference {
+                Reference::Parameter(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        for (i, name) in self.names("parameters") {
+            if !used.contains(name) {
+                let path = format!("{}.parameters[{i}]", self.root);
+                let message = format!("parameter `{name}` is not used by any component");
+                self.issues.push(ValidationIssue::warning(path, message));
+            }
+        }
+    }
+}
+
+/// Call `visitor` with the path and value of each child of an object or array.
+fn visit_children<F: FnMut(&str, &Value)>(value: &Value, path: &str, visitor: &mut F) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                visitor(&child_path(path, key), child);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                visitor(&format!("{path}[{i}]"), child);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The path of the member `key` of the object at `path`.
+fn child_path(path: &str, key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}[{key:?}]")
+    }
+}
+
+/// Recursively find the references in `value`, including those of metrics derived from other
+/// metrics.
+fn find_references(value: &Value, path: &str, parameter: Option<usize>, references: &mut Vec<FoundReference>) {
+    if value.get("type").is_some() {
+        if let Some(reference) = Reference::from_value(value) {
+            references.push(FoundReference {
+                path: path.to_string(),
+                reference,
+                parameter,
+            });
+        }
+    }
+
+    visit_children(value, path, &mut |path, child| {
+        find_references(child, path, parameter, references)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidationSeverity;
+    use crate::model::{PywrModel, PywrMultiNetworkModel};
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        let data = json!({
+            "metadata": {"title": "Validation"},
+            "timestepper": {"start": "2015-01-01", "end": "2015-12-31", "timestep": 1},
+            "network": {
+                "nodes": [
+                    {"meta": {"name": "supply"}, "type": "Input", "max_flow": {"type": "Parameter", "name": "a"}},
+                    {"meta": {"name": "demand"}, "type": "Output", "cost": {"type": "Parameter", "name": "missing"}},
+                    {"meta": {"name": "demand"}, "type": "Output"}
+                ],
+                "edges": [
+                    {"from_node": "supply", "to_node": "demand"},
+                    {"from_node": "supply", "to_node": "nowhere"}
+                ],
+                "parameters": [
+                    {"meta": {"name": "a"}, "type": "Max", "parameter": {"type": "Parameter", "name": "b"}},
+                    {"meta": {"name": "b"}, "type": "Max", "parameter": {"type": "Parameter", "name": "a"}},
+                    {"meta": {"name": "unused"}, "type": "Constant", "value": 1.0},
+                    {"meta": {"name": "threshold"}, "type": "Constant", "value": 1.0}
+                ],
+                "outputs": [
+                    {"type": "CSV", "name": "outputs", "filename": "outputs.csv", "format": "long", "metric_set": "nodes"},
+                    {
+                        "type": "Events",
+                        "name": "events",
+                        "metric": {"type": "Node", "name": "nowhere"},
+                        "threshold": {"type": "Parameter", "name": "threshold"},
+                        "predicate": "LT"
+                    }
+                ]
+            }
+        });
+        let model: PywrModel = serde_json::from_value(data).unwrap();
+
+        let issues: Vec<_> = model.validate().into_iter().map(|i| (i.severity, i.path)).collect();
+
+        assert_eq!(
+            issues,
+            vec![
+                (ValidationSeverity::Error, "$.network.nodes[2].meta.name".to_string()),
+                (ValidationSeverity::Error, "$.network.edges[1].to_node".to_string()),
+                (ValidationSeverity::Error, "$.network.nodes[1].cost".to_string()),
+                (ValidationSeverity::Error, "$.network.outputs[1].metric".to_string()),
+                (ValidationSeverity::Error, "$.network.outputs[0].metric_set".to_string()),
+                (
+                    ValidationSeverity::Error,
+                    "$.network.parameters[1].parameter".to_string()
+                ),
+                (ValidationSeverity::Warning, "$.network.parameters[2]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_multi_network() {
+        let network = |transfer_metric: &str| {
+            json!({
+                "nodes": [
+                    {"meta": {"name": "supply"}, "type": "Input", "max_flow": {"type": "InterNetworkTransfer", "name": transfer_metric}},
+                    {"meta": {"name": "demand"}, "type": "Output"}
+                ],
+                "edges": [{"from_node": "supply", "to_node": "demand"}],
+                "parameters": [{"meta": {"name": "sent"}, "type": "Constant", "value": 1.0}]
+            })
+        };
+
+        let data = json!({
+            "metadata": {"title": "Validation"},
+            "timestepper": {"start": "2015-01-01", "end": "2015-12-31", "timestep": 1},
+            "networks": [
+                {
+                    "name": "network1",
+                    "network": network("missing"),
+                    "transfers": [
+                        {"from_network": "network2", "metric": {"type": "Parameter", "name": "sent"}, "name": "received"},
+                        {"from_network": "nowhere", "metric": {"type": "Parameter", "name": "sent"}, "name": "other"}
+                    ]
+                },
+                {
+                    "name": "network2",
+                    "network": network("received"),
+                    "transfers": [
+                        {"from_network": "network1", "metric": {"type": "Node", "name": "missing"}, "name": "received"}
+                    ]
+                }
+            ]
+        });
+        let model: PywrMultiNetworkModel = serde_json::from_value(data).unwrap();
+
+        let issues: Vec<_> = model.validate(None).into_iter().map(|i| (i.severity, i.path)).collect();
+
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    ValidationSeverity::Error,
+                    "$.networks[0].transfers[1].from_network".to_string()
+                ),
+                (
+                    ValidationSeverity::Error,
+                    "$.networks[0].network.nodes[0].max_flow".to_string()
+                ),
+                (
+                    ValidationSeverity::Error,
+                    "$.networks[1].transfers[0].metric".to_string()
+                ),
+                // The parameter of network 2 is sent to network 1, but that of network 1 is not used
+                (
+                    ValidationSeverity::Warning,
+                    "$.networks[0].network.parameters[0]".to_string()
+                ),
+            ]
+        );
+    }
+}
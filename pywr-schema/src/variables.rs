@@ -0,0 +1,172 @@
+//! Substitution of variables in the strings of a model.
+//!
+//! Any string in a model may contain references to variables of the form `${NAME}`, which are
+//! replaced when the model is loaded. This allows a single model file to be used with, for
+//! example, different data directories or sizes of scenario groups. The value of a variable is
+//! taken from the definitions given when the model is loaded (e.g. `--define` in the CLI), or
+//! otherwise from the environment variable of the same name.
+//!
+//! ```json
+//! {
+//!   "scenarios": [{"name": "ensemble", "size": "${ENSEMBLE_SIZE}"}],
+//!   "network": {
+//!     "timeseries": [
+//!       {
+//!         "meta": {"name": "inflows"},
+//!         "provider": {"type": "Polars", "url": "${DATA_DIR}/inflows.csv"}
+//!       }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! A string that is only a reference to a variable is replaced with the variable's value as a
+//! number or boolean if it is one (e.g. `"${ENSEMBLE_SIZE}"` above). A string that is only a
+//! reference to one of the model's constants is left for the constant to be substituted; the
+//! constants take precedence over the variables.
+use crate::constants::CONSTANTS_KEY;
+use crate::SchemaError;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// The variables used to substitute the references in a model.
+struct Variables<'a> {
+    definitions: &'a BTreeMap<String, String>,
+    constants: HashSet<String>,
+}
+
+impl Variables<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        self.definitions.get(name).cloned().or_else(|| std::env::var(name).ok())
+    }
+
+    /// Replace all references to variables in `value`.
+    fn substitute(&self, value: &mut Value) -> Result<(), SchemaError> {
+        match value {
+            Value::String(s) => {
+                if let Some(name) = whole_reference(s) {
+                    if self.constants.contains(name) {
+                        return Ok(());
+                    }
+                    // An undefined reference is left to be reported as a missing constant
+                    if let Some(v) = self.get(name) {
+                        *value = match serde_json::from_str(&v) {
+                            Ok(parsed @ (Value::Number(_) | Value::Bool(_))) => parsed,
+                            _ => Value::String(v),
+                        };
+                    }
+                } else if s.contains("${") {
+                    *s = self.substitute_str(s)?;
+                }
+            }
+            Value::Array(values) => {
+                for v in values {
+                    self.substitute(v)?;
+                }
+            }
+            Value::Object(map) => {
+                for v in map.values_mut() {
+                    self.substitute(v)?;
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Replace the references to variables within a string.
+    fn substitute_str(&self, s: &str) -> Result<String, SchemaError> {
+        let mut substituted = String::with_capacity(s.len());
+        let mut rest = s;
+
+        while let Some(start) = rest.find("${") {
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 2..start + end];
+            let value = self
+                .get(name)
+                .ok_or_else(|| SchemaError::VariableNotDefined(name.to_string()))?;
+
+            substituted.push_str(&rest[..start]);
+            substituted.push_str(&value);
+            rest = &rest[start + end + 1..];
+        }
+
+        substituted.push_str(rest);
+        Ok(substituted)
+    }
+}
+
+/// The name of the variable if `s` is only a reference to it (e.g. `"${DATA_DIR}"`).
+fn whole_reference(s: &str) -> Option<&str> {
+    let name = s.strip_prefix("${")?.strip_suffix('}')?;
+    (!name.contains('}')).then_some(name)
+}
+
+/// Substitute the references to variables in a model document.
+///
+/// The values of the variables are taken from `definitions`, or otherwise from the environment.
+pub(crate) fn substitute_variables(
+    document: &mut Value,
+    definitions: &BTreeMap<String, String>,
+) -> Result<(), SchemaError> {
+    let Some(map) = document.as_object_mut() else {
+        // Not a valid model; leave it for deserialisation to report the error.
+        return Ok(());
+    };
+
+    let constants = match map.get(CONSTANTS_KEY) {
+        Some(Value::Object(constants)) => constants.keys().cloned().collect(),
+        _ => HashSet::new(),
+    };
+    let variables = Variables { definitions, constants };
+
+    for (key, value) in map.iter_mut() {
+        if key != CONSTANTS_KEY {
+            variables.substitute(value)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::substitute_variables;
+    use crate::SchemaError;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_substitute_variables() {
+        let mut document = json!({
+            "constants": {"demand": 10.5},
+            "scenarios": [{"name": "ensemble", "size": "${ENSEMBLE_SIZE}"}],
+            "network": {
+                "url": "${DATA_DIR}/inflow.csv",
+                "value": "${demand}",
+                "name": "${NAME}"
+            }
+        });
+
+        let definitions = BTreeMap::from([
+            ("ENSEMBLE_SIZE".to_string(), "10".to_string()),
+            ("DATA_DIR".to_string(), "/data".to_string()),
+            ("NAME".to_string(), "supply".to_string()),
+            // The constant takes precedence
+            ("demand".to_string(), "1.0".to_string()),
+        ]);
+        substitute_variables(&mut document, &definitions).unwrap();
+
+        assert_eq!(document["scenarios"][0]["size"], json!(10));
+        assert_eq!(document["network"]["url"], json!("/data/inflow.csv"));
+        assert_eq!(document["network"]["value"], json!("${demand}"));
+        assert_eq!(document["network"]["name"], json!("supply"));
+
+        let mut document = json!({"network": {"url": "${PYWR_TEST_UNDEFINED_VARIABLE}/inflow.csv"}});
+        let result = substitute_variables(&mut document, &BTreeMap::new());
+        assert!(matches!(result, Err(SchemaError::VariableNotDefined(_))));
+    }
+}
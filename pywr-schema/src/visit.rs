@@ -1,4 +1,6 @@
 use crate::metric::{IndexMetric, Metric};
+use crate::metric_sets::MetricAggregator;
+use crate::model::DateType;
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
@@ -19,10 +21,19 @@ pub trait VisitMetrics {
 impl VisitMetrics for Metric {
     fn visit_metrics<F: FnMut(&Metric)>(&self, visitor: &mut F) {
         visitor(self);
+
+        // Also visit any metric that this metric is derived from
+        if let Metric::Rolling { metric, .. } | Metric::SeasonToDate { metric, .. } = self {
+            metric.visit_metrics(visitor);
+        }
     }
 
     fn visit_metrics_mut<F: FnMut(&mut Metric)>(&mut self, visitor: &mut F) {
         visitor(self);
+
+        if let Metric::Rolling { metric, .. } | Metric::SeasonToDate { metric, .. } = self {
+            metric.visit_metrics_mut(visitor);
+        }
     }
 }
 
@@ -108,6 +119,7 @@ impl VisitMetrics for u16 {}
 impl VisitMetrics for u32 {}
 impl VisitMetrics for i32 {}
 impl VisitMetrics for chrono::Month {}
+impl VisitMetrics for DateType {}
 impl VisitMetrics for f32 {}
 impl VisitMetrics for f64 {}
 impl<const N: usize> VisitMetrics for [f64; N] {}
@@ -133,6 +145,7 @@ pub trait VisitPaths {
 
 impl VisitPaths for Metric {}
 impl VisitPaths for IndexMetric {}
+impl VisitPaths for MetricAggregator {}
 
 impl<T> VisitPaths for Option<T>
 where
@@ -211,6 +224,7 @@ impl VisitPaths for u16 {}
 impl VisitPaths for u32 {}
 impl VisitPaths for i32 {}
 impl VisitPaths for chrono::Month {}
+impl VisitPaths for DateType {}
 impl VisitPaths for f32 {}
 impl VisitPaths for f64 {}
 impl<const N: usize> VisitPaths for [f64; N] {}
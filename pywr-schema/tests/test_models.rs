@@ -1,10 +1,12 @@
 #[cfg(feature = "core")]
 use pywr_core::test_utils::{run_all_solvers, ExpectedOutputs};
 use pywr_schema::PywrModel;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 #[cfg(feature = "core")]
 use std::path::PathBuf;
+use std::str::FromStr;
 #[cfg(feature = "core")]
 use tempfile::TempDir;
 
@@ -41,16 +43,34 @@ model_tests! {
     test_csv1: ("csv1.json", vec!["csv1-outputs-long.csv", "csv1-outputs-wide.csv"], vec![], vec![]),
     test_csv2: ("csv2.json", vec!["csv2-outputs-long.csv", "csv2-outputs-wide.csv"], vec![], vec![]),
     test_csv3: ("csv3.json", vec!["csv3-outputs-long.csv"], vec![], vec![]),
+    test_csv4: ("csv4.json", vec!["csv4-outputs-long.csv"], vec![], vec![]),
+    test_csv5: ("csv5.json", vec!["csv5-outputs-long.csv"], vec![], vec![]),
+    test_csv6: ("csv6.json", vec!["csv6-outputs-long.csv"], vec![], vec![]),
+    test_scenario_statistics1: ("scenario_statistics1.json", vec!["scenario_statistics1-expected.csv"], vec![], vec![]),
+    test_events1: ("events1.json", vec!["events1-expected.csv"], vec![], vec![]),
+    test_excluded_periods1: ("excluded_periods1.json", vec!["excluded_periods1-expected.csv"], vec![], vec![]),
+    test_excluded_periods2: ("excluded_periods2.json", vec!["excluded_periods2-expected.csv"], vec![], vec![]),
+    test_monthly_timesteps1: ("monthly_timesteps1.json", vec!["monthly_timesteps1-expected.csv"], vec![], vec![]),
+    test_hourly_timesteps1: ("hourly_timesteps1.json", vec!["hourly_timesteps1-expected.csv"], vec![], vec![]),
     test_hdf1: ("hdf1.json", vec![], vec![], vec![]), // TODO asserting h5 results not possible with this framework
     test_memory1: ("memory1.json", vec![], vec![], vec![]),  // TODO asserting memory results not possible with this framework
     test_timeseries: ("timeseries.json", vec!["timeseries-expected.csv"], vec![], vec![]),
+    test_timeseries_chunked: ("timeseries_chunked.json", vec!["timeseries-expected.csv"], vec![], vec![]),
+    test_timeseries_eu: ("timeseries_eu.json", vec!["timeseries-expected.csv"], vec![], vec![]),
+    test_timeseries_excel: ("timeseries_excel.json", vec!["timeseries-expected.csv"], vec![], vec![]),
     test_storage_max_volumes: ("storage_max_volumes.json", vec![], vec![], vec![]),
+    test_aggregated_node_factors1: ("aggregated_node_factors1.json", vec!["aggregated_node_factors1-expected.csv"], vec!["cbc", "ipm-simd", "ipm-ocl"], vec![]),
+    test_aggregated_node_max_flow1: ("aggregated_node_max_flow1.json", vec!["aggregated_node_max_flow1-expected.csv"], vec!["ipm-simd", "ipm-ocl"], vec![]),
     test_mutual_exclusivity1: ("mutual-exclusivity1.json", vec!["mutual-exclusivity1.csv"], vec!["clp", "ipm-simd", "ipm-ocl"], vec![]),
     test_mutual_exclusivity2: ("mutual-exclusivity2.json", vec!["mutual-exclusivity2.csv"], vec!["clp", "ipm-simd", "ipm-ocl"], vec![]),
     test_mutual_exclusivity3: ("mutual-exclusivity3.json", vec!["mutual-exclusivity3.csv"], vec!["clp", "ipm-simd", "ipm-ocl"], vec![]),
     test_link_with_soft_min: ("link_with_soft_min.json", vec![], vec!["ipm-simd", "ipm-ocl"], vec![]),
     test_link_with_soft_max: ("link_with_soft_max.json", vec![], vec!["ipm-simd", "ipm-ocl"], vec![]),
     test_delay1: ("delay1.json", vec!["delay1-expected.csv"], vec![], vec![]),
+    test_consecutive_count1: ("consecutive_count1.json", vec!["consecutive_count1-expected.csv"], vec![], vec![]),
+    test_date_range1: ("date_range1.json", vec!["date_range1-expected.csv"], vec![], vec![]),
+    test_daily_profile1: ("daily_profile1.json", vec!["daily_profile1-expected.csv"], vec![], vec![]),
+    test_derived_metrics1: ("derived_metrics1.json", vec!["derived_metrics1-expected.csv"], vec![], vec![]),
     test_loss_link1: ("loss_link1.json", vec!["loss_link1-expected.csv"], vec!["ipm-simd", "ipm-ocl"], vec![]),
     test_loss_link2: ("loss_link2.json", vec!["loss_link2-expected.csv"], vec!["ipm-simd", "ipm-ocl"], vec![]),
     // TODO this asserted internal flows in the previous test
@@ -59,14 +79,31 @@ model_tests! {
     test_piecewise_storage1: ("piecewise_storage1.json", vec!["piecewise_storage1-expected.csv"], vec![], vec!["ipm-simd", "ipm-ocl"]),
     // TODO not sure why this is failing in IPM solvers (https://github.com/pywr/pywr-next/issues/293)
     test_piecewise_storage2: ("piecewise_storage2.json", vec!["piecewise_storage2-expected.csv"], vec![], vec!["ipm-simd", "ipm-ocl"]),
+    // Each zone is a separate storage node, as in the piecewise storage tests above, which fail in the IPM solvers (https://github.com/pywr/pywr-next/issues/293)
+    test_zoned_storage1: ("zoned_storage1.json", vec!["zoned_storage1-expected.csv"], vec![], vec!["ipm-simd", "ipm-ocl"]),
+    test_reservoir1: ("reservoir1.json", vec!["reservoir1-expected.csv"], vec![], vec!["ipm-simd", "ipm-ocl"]),
+    test_reservoir2: ("reservoir2.json", vec!["reservoir2-expected.csv"], vec![], vec!["ipm-simd", "ipm-ocl"]),
     test_river_loss1: ("river_loss1.json", vec!["river_loss1-expected.csv"], vec!["ipm-simd", "ipm-ocl"], vec![]),
     // TODO not sure why this is failing in IPM solvers (https://github.com/pywr/pywr-next/issues/293)
     test_river_gauge1: ("river_gauge1.json", vec![], vec![], vec!["ipm-simd", "ipm-ocl"]),
+    // Uses a river gauge node, as in `test_river_gauge1`, which fails in the IPM solvers (https://github.com/pywr/pywr-next/issues/293)
+    test_river_gauge2: ("river_gauge2.json", vec!["river_gauge2-expected.csv"], vec![], vec!["ipm-simd", "ipm-ocl"]),
     test_river_split_with_gauge1: ("river_split_with_gauge1.json", vec![], vec![], vec![]),
     test_thirty_day_licence: ("30-day-licence.json", vec![], vec!["ipm-simd", "ipm-ocl"], vec![]),
+    test_annual_licence_profile1: ("annual_licence_profile1.json", vec![], vec!["ipm-simd", "ipm-ocl"], vec![]),
+    test_licence1: ("licence1.json", vec!["licence1-expected.csv"], vec![], vec!["ipm-simd", "ipm-ocl"]),
+    test_state_override1: ("state_override1.json", vec!["state_override1-expected.csv"], vec![], vec![]),
+    test_cost_profile1: ("cost_profile1.json", vec!["cost_profile1-expected.csv"], vec![], vec![]),
+    test_turbine1: ("turbine1.json", vec!["turbine1-expected.csv"], vec![], vec![]),
+    test_hydropower_cascade1: ("hydropower_cascade1.json", vec!["hydropower_cascade1-expected.csv"], vec![], vec![]),
     test_wtw1: ("wtw1.json", vec!["wtw1-expected.csv"], vec!["ipm-simd", "ipm-ocl"], vec![]),
     test_wtw2: ("wtw2.json", vec!["wtw2-expected.csv"], vec!["ipm-simd", "ipm-ocl"], vec![]),
     test_local_parameter1: ("local-parameter1.json", vec!["local-parameter1-expected.csv"], vec![], vec![]),
+    test_parameter_bounds1: ("parameter-bounds1.json", vec!["parameter-bounds1-expected.csv"], vec![], vec![]),
+    test_constants1: ("constants1.json", vec!["constants1-expected.csv"], vec![], vec![]),
+    test_solver_weight1: ("solver-weight1.json", vec!["solver-weight1-expected.csv"], vec![], vec![]),
+    test_aggregated_storage1: ("aggregated_storage1.json", vec!["aggregated_storage1-demand.csv", "aggregated_storage1-storage.csv"], vec![], vec![]),
+    test_switching1: ("switching1.json", vec!["switching1-expected.csv"], vec!["ipm-simd", "ipm-ocl"], vec![]),
 }
 
 /// Test Pandas backend for reading timeseries data.
@@ -85,9 +122,53 @@ fn test_timeseries_pandas() {
     run_test_model(&schema, &expected_paths, &[], &[]);
 }
 
+/// Test reading timeseries data from a SQLite database.
+#[test]
+#[cfg(feature = "database")]
+fn test_timeseries_database() {
+    let input_pth = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("timeseries_database.json");
+    let expected_paths = vec![Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("timeseries-expected.csv")];
+    let schema = deserialise_test_model(&input_pth);
+    run_test_model(&schema, &expected_paths, &[], &[]);
+}
+
+/// Test reading an ensemble of timeseries from a NetCDF file.
+#[test]
+#[cfg(feature = "netcdf")]
+fn test_netcdf1() {
+    let input_pth = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("netcdf1.json");
+    let expected_paths = vec![Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("netcdf1-expected.csv")];
+    let schema = deserialise_test_model(&input_pth);
+    run_test_model(&schema, &expected_paths, &[], &[]);
+}
+
 fn deserialise_test_model(model_path: &Path) -> PywrModel {
     let data = fs::read_to_string(model_path).expect("Unable to read file");
-    serde_json::from_str(&data).expect("Failed to deserialize model")
+    PywrModel::from_str(&data).expect("Failed to deserialize model")
+}
+
+/// Test overriding the value of a model constant when the model is loaded.
+#[test]
+fn test_constants1_override() {
+    let input_pth = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("constants1.json");
+    let overrides = BTreeMap::from([("peak_demand".to_string(), 5.0)]);
+    let schema = PywrModel::from_path_with_constants(&input_pth, &overrides).unwrap();
+
+    let constants = schema.constants.unwrap();
+    assert_eq!(constants["peak_demand"], 5.0);
+    assert_eq!(constants["supply"], 15.0);
+
+    // Constants that are not defined in the model can not be overridden
+    let overrides = BTreeMap::from([("missing".to_string(), 5.0)]);
+    assert!(PywrModel::from_path_with_constants(&input_pth, &overrides).is_err());
 }
 
 #[cfg(feature = "core")]
@@ -134,6 +215,8 @@ convert_tests! {
     test_convert_timeseries: ("v1/timeseries.json", "v1/timeseries-converted.json"),
     test_convert_inline_parameter: ("v1/inline-parameter.json", "v1/inline-parameter-converted.json"),
     test_convert_river_split_with_gauge1: ("v1/river_split_with_gauge1.json", "v1/river_split_with_gauge1-converted.json"),
+    test_convert_piecewise_link1: ("v1/piecewise_link1.json", "v1/piecewise_link1-converted.json"),
+    test_convert_aggregated_node_max_flow1: ("v1/aggregated_node_max_flow1.json", "v1/aggregated_node_max_flow1-converted.json"),
 }
 
 fn convert_model(v1_path: &Path, v2_path: &Path) {